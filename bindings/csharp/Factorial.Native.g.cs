@@ -0,0 +1,1568 @@
+// <auto-generated>
+//     Generated by factorial-bindgen from crates/factorial-ffi. Do not edit;
+//     run `cargo run -p factorial-bindgen` after changing the C API.
+// </auto-generated>
+
+using System.Runtime.InteropServices;
+
+using FfiNodeId = System.UInt64;
+using FfiEdgeId = System.UInt64;
+using FfiPendingNodeId = System.UInt64;
+using FfiPendingEdgeId = System.UInt64;
+using FfiCraftingQueueId = System.UInt64;
+using FfiEdgeGroupId = System.UInt64;
+using FfiEventCursorId = System.UInt64;
+using FfiWireNetworkId = System.UInt32;
+
+namespace Factorial.Native
+{
+    /// <summary>Status codes returned by all FFI functions.</summary>
+    public enum FactorialResult : int
+    {
+        /// <summary>Success.</summary>
+        Ok = 0,
+        /// <summary>A required pointer argument was null.</summary>
+        NullPointer = 1,
+        /// <summary>The engine handle is invalid (null or dangling).</summary>
+        InvalidHandle = 2,
+        /// <summary>Serialization failed.</summary>
+        SerializeError = 3,
+        /// <summary>Deserialization failed.</summary>
+        DeserializeError = 4,
+        /// <summary>The requested node was not found.</summary>
+        NodeNotFound = 5,
+        /// <summary>The requested edge was not found.</summary>
+        EdgeNotFound = 6,
+        /// <summary>An internal panic was caught at the FFI boundary.</summary>
+        InternalError = 7,
+        /// <summary>The engine is poisoned (a previous panic left it in an inconsistent state).</summary>
+        Poisoned = 8,
+        /// <summary>The pending id's mutation has not been applied yet.</summary>
+        PendingQueued = 9,
+        /// <summary>The pending id's batch is older than the resolution history.</summary>
+        PendingExpired = 10,
+        /// <summary>The pending id was never issued by this engine.</summary>
+        PendingUnknown = 11,
+        /// <summary>The requested crafting queue was not found.</summary>
+        CraftingQueueNotFound = 12,
+        /// <summary>A crafting request was rejected: missing ingredients, unknown recipe, invalid speed, or job index out of range.</summary>
+        CraftingRejected = 13,
+        /// <summary>The caller's output buffer is too small; the size needed was written.</summary>
+        BufferTooSmall = 14,
+        /// <summary>An enum-valued argument was out of range.</summary>
+        InvalidArgument = 15,
+        /// <summary>The node's processor or the edge's transport is a different kind from the one the getter reads.</summary>
+        KindMismatch = 16,
+        /// <summary>A read-only call overlapped a mutating call on the same handle and read nothing. Only detected in debug builds.</summary>
+        ConcurrentMutation = 17,
+        /// <summary>The requested edge group was not found.</summary>
+        EdgeGroupNotFound = 18,
+        /// <summary>The step ran, but exceeded the step work limit and skipped its remaining simulation phases.</summary>
+        WorkLimitReached = 19,
+        /// <summary>A sliced tick is open: the call would change the engine mid-step. Finish the tick with <c>factorial_continue_tick</c> first.</summary>
+        TickInProgress = 20,
+        /// <summary>The requested event cursor was not found.</summary>
+        EventCursorNotFound = 21,
+    }
+
+    /// <summary>C-compatible processor state tag.</summary>
+    public enum FfiProcessorState : int
+    {
+        Idle = 0,
+        Working = 1,
+        StalledMissingInputs = 2,
+        StalledOutputFull = 3,
+        StalledNoPower = 4,
+        StalledDepleted = 5,
+        Storing = 6,
+    }
+
+    /// <summary>C-compatible event tag.</summary>
+    public enum FfiEventKind : int
+    {
+        ItemProduced = 0,
+        ItemConsumed = 1,
+        /// <summary>For a flexible processor <c>item_type</c> holds the selected recipe id and <c>quantity</c> is 1; otherwise both are 0.</summary>
+        RecipeStarted = 2,
+        RecipeCompleted = 3,
+        BuildingStalled = 4,
+        BuildingResumed = 5,
+        ItemDelivered = 6,
+        TransportFull = 7,
+        NodeAdded = 8,
+        NodeRemoved = 9,
+        EdgeAdded = 10,
+        EdgeRemoved = 11,
+        RecipeSwitched = 12,
+        /// <summary><c>item_type</c> holds the recipe id.</summary>
+        CraftStarted = 13,
+        /// <summary><c>item_type</c> holds the recipe id and <c>quantity</c> the crafts left in the job.</summary>
+        CraftCompleted = 14,
+        /// <summary><c>item_type</c> and <c>quantity</c> describe the items that spoiled. The product, if any, is the item type's configured decay product.</summary>
+        ItemSpoiled = 15,
+        /// <summary><c>item_type</c> is the item the slot holds, <c>quantity</c> the slot index, and <c>building_type</c> 0 for the input inventory or 1 for the output inventory.</summary>
+        InventoryFull = 16,
+        /// <summary>Fields as for <c>InventoryFull</c>; <c>item_type</c> is the item the slot held when it filled.</summary>
+        InventorySpaceAvailable = 17,
+        /// <summary><c>quantity</c> is the work done, saturated to <c>u32::MAX</c>, and <c>building_type</c> the phase after which the step stopped: 0 pre-tick, 1 transport, 2 process, 3 component.</summary>
+        StepTruncated = 18,
+    }
+
+    /// <summary>Where a sliced tick stands after <c>factorial_continue_tick</c>.</summary>
+    public enum FfiTickSliceStatus : int
+    {
+        /// <summary>The tick has work left; call <c>factorial_continue_tick</c> again.</summary>
+        TickSliceInProgress = 0,
+        /// <summary>The tick is complete, or no tick was open.</summary>
+        TickSliceComplete = 1,
+    }
+
+    /// <summary>C-compatible simulation strategy kind for <c>factorial_set_strategy</c>.</summary>
+    public enum FfiSimulationStrategy : int
+    {
+        /// <summary>One step per <c>factorial_step</c> or <c>factorial_advance</c> call.</summary>
+        Tick = 0,
+        /// <summary><c>factorial_advance</c> accumulates <c>dt</c> and runs fixed steps.</summary>
+        Delta = 1,
+    }
+
+    /// <summary>C-compatible vehicle phase for <c>FfiTransportProgress</c>. See <c>VehiclePhase</c> in factorial-core.</summary>
+    public enum FfiVehiclePhase : int
+    {
+        Loading = 0,
+        Traveling = 1,
+        Unloading = 2,
+        Returning = 3,
+    }
+
+    /// <summary>C-compatible transport kind for <c>FfiTransportConfig</c>.</summary>
+    public enum FfiTransportKind : int
+    {
+        Flow = 0,
+        Item = 1,
+        Batch = 2,
+        Vehicle = 3,
+    }
+
+    /// <summary>C-compatible source depletion kind.</summary>
+    public enum FfiDepletionKind : int
+    {
+        /// <summary>Never runs out (<c>Depletion::Infinite</c>; renamed so the C name does not collide with the Windows <c>INFINITE</c> macro).</summary>
+        Unlimited = 0,
+        /// <summary>Stops once <c>remaining</c> reaches zero.</summary>
+        Finite = 1,
+        /// <summary>Rate halves every <c>half_life</c> ticks.</summary>
+        Decaying = 2,
+    }
+
+    /// <summary>C-compatible processor kind, from <c>factorial_get_processor_config</c>.</summary>
+    public enum FfiProcessorKind : int
+    {
+        ProcessorKindSource = 0,
+        ProcessorKindFixed = 1,
+        ProcessorKindProperty = 2,
+        ProcessorKindDemand = 3,
+        ProcessorKindPassthrough = 4,
+        ProcessorKindMultiRecipe = 5,
+        ProcessorKindWeightedRecipe = 6,
+        ProcessorKindFlexible = 7,
+        ProcessorKindStorage = 8,
+        ProcessorKindMultiSource = 9,
+    }
+
+    /// <summary>C-compatible node/edge simulation status.</summary>
+    public enum FfiNodeStatus : int
+    {
+        /// <summary>Planned; stays in the graph but does not simulate.</summary>
+        Ghost = 0,
+        /// <summary>Built and simulating normally.</summary>
+        Active = 1,
+    }
+
+    /// <summary>What an <c>FfiOperationRecord</c> describes.</summary>
+    public enum FfiOperationKind : int
+    {
+        /// <summary><c>item</c> is the building type.</summary>
+        OpNodeAdded = 0,
+        OpNodeRemoved = 1,
+        /// <summary><c>node</c> is the edge's source.</summary>
+        OpEdgeAdded = 2,
+        OpEdgeRemoved = 3,
+        /// <summary>A processor at <c>node</c> produced <c>quantity</c> of <c>item</c>.</summary>
+        OpProduced = 4,
+        /// <summary><c>edge</c> delivered <c>quantity</c> of <c>item</c> to <c>node</c>.</summary>
+        OpDelivered = 5,
+        /// <summary>Step <c>tick</c> finished.</summary>
+        OpStepCompleted = 6,
+    }
+
+    /// <summary>C-compatible wire color.</summary>
+    public enum FfiWireColor : int
+    {
+        Red = 0,
+        Green = 1,
+    }
+
+    /// <summary>C-compatible signal selector kind.</summary>
+    public enum FfiSelectorKind : int
+    {
+        Signal = 0,
+        Constant = 1,
+        Each = 2,
+    }
+
+    /// <summary>C-compatible arithmetic operation.</summary>
+    public enum FfiArithmeticOp : int
+    {
+        Add = 0,
+        Sub = 1,
+        Mul = 2,
+        Div = 3,
+        Mod = 4,
+    }
+
+    /// <summary>C-compatible comparison operation.</summary>
+    public enum FfiComparisonOp : int
+    {
+        Gt = 0,
+        Lt = 1,
+        Eq = 2,
+        Gte = 3,
+        Lte = 4,
+        Ne = 5,
+    }
+
+    /// <summary>C-compatible decider output kind.</summary>
+    public enum FfiDeciderOutputKind : int
+    {
+        One = 0,
+        InputCount = 1,
+        Everything = 2,
+    }
+
+    /// <summary>C-compatible processor state with progress.</summary>
+    [StructLayout(LayoutKind.Sequential)]
+    public struct FfiProcessorInfo
+    {
+        public FfiProcessorState state;
+        /// <summary>For <c>Working</c> state, the current progress tick. 0 otherwise.</summary>
+        public uint progress;
+    }
+
+    /// <summary>C-compatible per-subsystem state hashes. The wrapping sum of all fields equals <c>factorial_get_state_hash</c>. See <c>HashBreakdown</c> in factorial-core for field meanings.</summary>
+    [StructLayout(LayoutKind.Sequential)]
+    public struct FfiHashBreakdown
+    {
+        public ulong graph;
+        public ulong inventories;
+        public ulong processor_states;
+        public ulong transports;
+        public ulong sim_state;
+        public ulong fluid;
+        public ulong crafting;
+        public ulong modules;
+    }
+
+    /// <summary>C-compatible factory-wide count of one item type. See <c>ItemCensus</c> in factorial-core for field meanings.</summary>
+    [StructLayout(LayoutKind.Sequential)]
+    public struct FfiItemCensus
+    {
+        public ulong in_input_inventories;
+        public ulong in_output_inventories;
+        public ulong in_transit;
+        public ulong total;
+    }
+
+    /// <summary>C-compatible stall summary for one item type. See <c>ItemBottleneck</c> in factorial-core for field meanings.</summary>
+    [StructLayout(LayoutKind.Sequential)]
+    public struct FfiItemBottleneck
+    {
+        public uint item_type;
+        public uint starved_nodes;
+        public uint blocked_nodes;
+        /// <summary>The starved node missing the most units. 0 if none.</summary>
+        public FfiNodeId worst_starved;
+        /// <summary>The blocked node holding the most units in its output. 0 if none.</summary>
+        public FfiNodeId worst_blocked;
+    }
+
+    /// <summary>Engine-owned array returned by <c>factorial_get_bottleneck_digest</c>.</summary>
+    [StructLayout(LayoutKind.Sequential)]
+    public unsafe struct FfiBottleneckBuffer
+    {
+        public FfiItemBottleneck* items;
+        public uint count;
+    }
+
+    /// <summary>C-compatible save summary. See <c>SaveSummary</c> in factorial-core for field meanings. Per-item totals are reduced to their count and sum.</summary>
+    [StructLayout(LayoutKind.Sequential)]
+    public struct FfiSaveSummary
+    {
+        public uint version;
+        public ulong tick;
+        public uint node_count;
+        public uint edge_count;
+        public uint extensions;
+        public ulong state_hash;
+        /// <summary>Whether the save recorded item totals. The next two fields are zero otherwise.</summary>
+        [MarshalAs(UnmanagedType.U1)]
+        public bool has_item_totals;
+        /// <summary>Number of item types with at least one unit.</summary>
+        public uint item_type_count;
+        /// <summary>Units of all item types together.</summary>
+        public ulong total_items;
+        /// <summary>Whether the save had no summary section and was fully decoded.</summary>
+        [MarshalAs(UnmanagedType.U1)]
+        public bool full_decode;
+    }
+
+    /// <summary>C-compatible per-phase operation counts from the most recent step. See <c>StepProfile</c> in factorial-core for field meanings.</summary>
+    [StructLayout(LayoutKind.Sequential)]
+    public struct FfiStepProfile
+    {
+        /// <summary>Engine tick after the step. 0 means no profile is available.</summary>
+        public ulong tick;
+        public uint mutations_applied;
+        public uint transports_advanced;
+        public uint items_delivered;
+        public uint processors_ticked;
+        public uint junctions_processed;
+        public uint modules_run;
+        public uint events_delivered;
+        public uint module_work;
+        public uint topology_rebuilds;
+    }
+
+    /// <summary>C-compatible outgoing edge from <c>factorial_get_topology</c>. <c>kind</c> is only meaningful when <c>has_transport</c> is true.</summary>
+    [StructLayout(LayoutKind.Sequential)]
+    public struct FfiTopologyEdge
+    {
+        public FfiEdgeId edge;
+        /// <summary>Index of the edge's target in the node array.</summary>
+        public uint target;
+        public FfiTransportKind kind;
+        [MarshalAs(UnmanagedType.U1)]
+        public bool has_transport;
+    }
+
+    /// <summary>C-compatible event data. Union fields are determined by <c>kind</c>. We use a flat struct with all possible fields to keep it simple and fully <c>repr(C)</c> without actual C unions.</summary>
+    [StructLayout(LayoutKind.Sequential)]
+    public struct FfiEvent
+    {
+        public FfiEventKind kind;
+        public ulong tick;
+        /// <summary>Node ID (used by most events). 0 if not applicable.</summary>
+        public FfiNodeId node;
+        /// <summary>Edge ID (used by transport events). 0 if not applicable.</summary>
+        public FfiEdgeId edge;
+        /// <summary>Item type ID (used by item events). 0 if not applicable.</summary>
+        public uint item_type;
+        /// <summary>Quantity (used by item events). 0 if not applicable.</summary>
+        public uint quantity;
+        /// <summary>Building type ID (used by NodeAdded). 0 if not applicable.</summary>
+        public uint building_type;
+        /// <summary>Source node (used by EdgeAdded). 0 if not applicable.</summary>
+        public FfiNodeId from_node;
+        /// <summary>Dest node (used by EdgeAdded). 0 if not applicable.</summary>
+        public FfiNodeId to_node;
+    }
+
+    /// <summary>Result of polling events: a pointer to engine-owned event buffer and count.</summary>
+    [StructLayout(LayoutKind.Sequential)]
+    public unsafe struct FfiEventBuffer
+    {
+        /// <summary>Pointer to an array of <c>FfiEvent</c>. Valid until next step/destroy.</summary>
+        public FfiEvent* events;
+        /// <summary>Number of events in the buffer.</summary>
+        public uint count;
+    }
+
+    /// <summary>An engine-allocated byte buffer returned from serialization.</summary>
+    [StructLayout(LayoutKind.Sequential)]
+    public unsafe struct FfiByteBuffer
+    {
+        /// <summary>Pointer to the data. Null on error.</summary>
+        public byte* data;
+        /// <summary>Length in bytes.</summary>
+        public nuint len;
+    }
+
+    /// <summary>Result of applying mutations. Maps pending IDs to real IDs.</summary>
+    [StructLayout(LayoutKind.Sequential)]
+    public unsafe struct FfiMutationResult
+    {
+        /// <summary>Array of (pending_node_id, real_node_id) pairs.</summary>
+        public FfiIdPair* added_nodes;
+        public uint added_node_count;
+        /// <summary>Array of (pending_edge_id, real_edge_id) pairs.</summary>
+        public FfiIdPair* added_edges;
+        public uint added_edge_count;
+        /// <summary>Array of node additions refused in strict mode.</summary>
+        public FfiRejectedMutation* rejected;
+        public uint rejected_count;
+    }
+
+    /// <summary>A node addition refused at apply time. Listed in the order the mutations were applied, like <c>FfiIdPair</c>.</summary>
+    [StructLayout(LayoutKind.Sequential)]
+    public struct FfiRejectedMutation
+    {
+        public ulong pending_id;
+        /// <summary>Reason code: <c>1</c> = building locked behind incomplete research.</summary>
+        public uint reason;
+    }
+
+    /// <summary>A pair of (pending_id, real_id) for mutation results. Pairs are in the order the mutations were applied: by ascending lane, then in call order.</summary>
+    [StructLayout(LayoutKind.Sequential)]
+    public struct FfiIdPair
+    {
+        public ulong pending_id;
+        public ulong real_id;
+        /// <summary>The mutation lane the addition was queued in.</summary>
+        public byte lane;
+    }
+
+    /// <summary>C-compatible view of one item riding on a belt (item transport) edge.</summary>
+    [StructLayout(LayoutKind.Sequential)]
+    public struct FfiTransportItem
+    {
+        /// <summary>Stable per-engine instance id; unchanged while the item is on the belt.</summary>
+        public ulong instance_id;
+        public uint item_type;
+        public uint lane;
+        /// <summary>Position along the edge as raw Fixed64 bits: 0 at the source end, <c>1 &lt;&lt; 32</c> at the destination end.</summary>
+        public long position;
+    }
+
+    /// <summary>Engine-owned array returned by <c>factorial_get_transport_items</c>.</summary>
+    [StructLayout(LayoutKind.Sequential)]
+    public unsafe struct FfiTransportItemBuffer
+    {
+        public FfiTransportItem* items;
+        public uint count;
+    }
+
+    /// <summary>C-compatible transport progress. Only the fields for <c>kind</c> are set; the others are zero:</summary>
+    [StructLayout(LayoutKind.Sequential)]
+    public struct FfiTransportProgress
+    {
+        public FfiTransportKind kind;
+        public long buffered;
+        public long capacity;
+        public uint items_in_transit;
+        public long head_position;
+        public uint ticks_until_delivery;
+        public uint accumulated;
+        public uint batch_size;
+        public uint ticks_until_forced_dispatch;
+        public FfiVehiclePhase phase;
+        public uint ticks_remaining_in_phase;
+        public uint cargo_count;
+    }
+
+    /// <summary>Filter for <c>factorial_query_event_history</c>. Zeroed fields (and <c>max_tick = UINT64_MAX</c>) mean "no restriction".</summary>
+    [StructLayout(LayoutKind.Sequential)]
+    public struct FfiEventFilter
+    {
+        /// <summary>Bit <c>1 &lt;&lt; kind</c> for each <c>FfiEventKind</c> to keep; 0 keeps every kind.</summary>
+        public uint kind_mask;
+        /// <summary>Keep only events about this node (0 = any node).</summary>
+        public FfiNodeId node;
+        /// <summary>Keep only events about this edge (0 = any edge).</summary>
+        public FfiEdgeId edge;
+        /// <summary>Keep only events stamped at or after this tick.</summary>
+        public ulong min_tick;
+        /// <summary>Keep only events stamped at or before this tick.</summary>
+        public ulong max_tick;
+    }
+
+    /// <summary>C-compatible item stack (item type + quantity).</summary>
+    [StructLayout(LayoutKind.Sequential)]
+    public struct FfiItemStack
+    {
+        public uint item_type;
+        public uint quantity;
+    }
+
+    /// <summary>C-compatible recipe for FixedRecipe processor.</summary>
+    [StructLayout(LayoutKind.Sequential)]
+    public unsafe struct FfiRecipe
+    {
+        public uint input_count;
+        public FfiItemStack* inputs;
+        public uint output_count;
+        public FfiItemStack* outputs;
+        public uint duration;
+    }
+
+    /// <summary>C-compatible fluid ingredient or product: fluid type plus raw Fixed64 bits (Q32.32) per craft.</summary>
+    [StructLayout(LayoutKind.Sequential)]
+    public struct FfiFluidAmount
+    {
+        public uint fluid;
+        public long amount;
+    }
+
+    /// <summary>Version 2 of <c>FfiRecipe</c>: adds fluid ingredient and product arrays.</summary>
+    [StructLayout(LayoutKind.Sequential)]
+    public unsafe struct FfiRecipeV2
+    {
+        public uint input_count;
+        public FfiItemStack* inputs;
+        public uint output_count;
+        public FfiItemStack* outputs;
+        public uint duration;
+        public uint fluid_input_count;
+        public FfiFluidAmount* fluid_inputs;
+        public uint fluid_output_count;
+        public FfiFluidAmount* fluid_outputs;
+    }
+
+    /// <summary>Version 3 of <c>FfiRecipe</c>: adds a chance for each item output.</summary>
+    [StructLayout(LayoutKind.Sequential)]
+    public unsafe struct FfiRecipeV3
+    {
+        public uint input_count;
+        public FfiItemStack* inputs;
+        public uint output_count;
+        public FfiItemStack* outputs;
+        public uint duration;
+        public uint fluid_input_count;
+        public FfiFluidAmount* fluid_inputs;
+        public uint fluid_output_count;
+        public FfiFluidAmount* fluid_outputs;
+        public long* output_probabilities;
+    }
+
+    /// <summary>Engine-owned array of item stacks returned by the inventory clear calls.</summary>
+    [StructLayout(LayoutKind.Sequential)]
+    public unsafe struct FfiItemBuffer
+    {
+        public FfiItemStack* items;
+        public uint count;
+    }
+
+    /// <summary>C-compatible transport configuration. Only the fields used by <c>kind</c> are read:</summary>
+    [StructLayout(LayoutKind.Sequential)]
+    public struct FfiTransportConfig
+    {
+        public FfiTransportKind kind;
+        public long rate;
+        public uint slot_count;
+        public byte lanes;
+        public uint batch_size;
+        public uint cycle_time;
+        public uint capacity;
+        public uint travel_time;
+    }
+
+    /// <summary>C-compatible source depletion. Only the field matching <c>kind</c> is meaningful; the other is zero.</summary>
+    [StructLayout(LayoutKind.Sequential)]
+    public struct FfiDepletion
+    {
+        public FfiDepletionKind kind;
+        /// <summary>Raw Fixed64 bits of the amount left (<c>Finite</c>).</summary>
+        public long remaining;
+        /// <summary>Half-life in ticks (<c>Decaying</c>).</summary>
+        public ulong half_life;
+    }
+
+    /// <summary>C-compatible description of a node's processor, filled by <c>factorial_get_processor_config</c>.</summary>
+    [StructLayout(LayoutKind.Sequential)]
+    public unsafe struct FfiProcessorConfig
+    {
+        public FfiProcessorKind kind;
+        /// <summary>Item produced (<c>Source</c>, <c>Property</c>) or consumed (<c>Demand</c>).</summary>
+        public uint item;
+        /// <summary>Item consumed by a <c>Property</c> processor.</summary>
+        public uint input_item;
+        /// <summary>Raw Fixed64 bits of the base rate (<c>Source</c>, <c>Demand</c>).</summary>
+        public long rate;
+        /// <summary>Deposit depletion (<c>Source</c>, <c>MultiSource</c>).</summary>
+        public FfiDepletion depletion;
+        /// <summary>Recipes held: 1 for <c>Fixed</c>, the list length for <c>MultiRecipe</c>, <c>WeightedRecipe</c> and <c>Flexible</c>.</summary>
+        public uint recipe_count;
+        /// <summary>Index of the recipe described below (<c>MultiRecipe</c>).</summary>
+        public uint active_recipe;
+        /// <summary>Cycle length in ticks of the described recipe.</summary>
+        public uint duration;
+        /// <summary>Caller buffer for the described recipe's item inputs.</summary>
+        public FfiItemStack* inputs;
+        public uint input_capacity;
+        /// <summary>Number of item inputs, written even if the buffer is too small.</summary>
+        public uint input_count;
+        /// <summary>Caller buffer for the described recipe's item outputs.</summary>
+        public FfiItemStack* outputs;
+        public uint output_capacity;
+        /// <summary>Number of item outputs, written even if the buffer is too small.</summary>
+        public uint output_count;
+    }
+
+    /// <summary>C-compatible output of a multi-output source: item type plus raw Fixed64 bits (Q32.32) of items per tick.</summary>
+    [StructLayout(LayoutKind.Sequential)]
+    public struct FfiSourceOutput
+    {
+        public uint item_type;
+        public long rate;
+    }
+
+    /// <summary>C-compatible richness curve. Every <c>Fixed64</c> field is raw Q32.32 bits.</summary>
+    [StructLayout(LayoutKind.Sequential)]
+    public struct FfiRichnessCurve
+    {
+        /// <summary>Width of a noise cell in map units (<c>0</c> behaves as <c>1</c>).</summary>
+        public uint cell_size;
+        public long min_rate;
+        public long max_rate;
+        public long min_amount;
+        /// <summary>Deposit size at richness 1; zero or negative makes deposits infinite.</summary>
+        public long max_amount;
+    }
+
+    /// <summary>C-compatible record of where a procedural source sits.</summary>
+    [StructLayout(LayoutKind.Sequential)]
+    public struct FfiSourceSite
+    {
+        public int x;
+        public int y;
+        /// <summary>Richness in <c>[0, 1)</c>, as raw Fixed64 bits.</summary>
+        public long richness;
+    }
+
+    /// <summary>One entry of <c>factorial_set_processors_bulk</c>: a node and the FixedRecipe to give it.</summary>
+    [StructLayout(LayoutKind.Sequential)]
+    public struct FfiProcessorAssignment
+    {
+        public FfiNodeId node;
+        public FfiRecipe recipe;
+    }
+
+    /// <summary>One inventory to fill with <c>factorial_fill_inventories_bulk</c>: <c>count</c> items starting at <c>offset</c> in the shared item array go into the node's input (<c>which</c> 0) or output (<c>which</c> 1) inventory.</summary>
+    [StructLayout(LayoutKind.Sequential)]
+    public struct FfiInventoryFill
+    {
+        public FfiNodeId node;
+        public uint which;
+        public uint offset;
+        public uint count;
+    }
+
+    /// <summary>C-compatible crafting queue progress.</summary>
+    [StructLayout(LayoutKind.Sequential)]
+    public struct FfiCraftProgress
+    {
+        /// <summary>Recipe id of the front job. Only meaningful when <c>jobs &gt; 0</c>.</summary>
+        public uint recipe;
+        /// <summary>Completion of the front craft as raw Fixed64 bits (0..1).</summary>
+        public long progress;
+        /// <summary>Crafts left across all jobs.</summary>
+        public uint remaining;
+        /// <summary>Number of jobs in the queue.</summary>
+        public uint jobs;
+    }
+
+    /// <summary>One logged engine operation. Ids that do not apply are 0, which is never a valid id; other unused fields are 0 too.</summary>
+    [StructLayout(LayoutKind.Sequential)]
+    public struct FfiOperationRecord
+    {
+        public FfiOperationKind kind;
+        public ulong tick;
+        public FfiNodeId node;
+        public FfiEdgeId edge;
+        public uint item;
+        public uint quantity;
+    }
+
+    /// <summary>One entry of the per-tick changed-signal report. <c>old</c> and <c>new</c> are raw Fixed64 bits.</summary>
+    [StructLayout(LayoutKind.Sequential)]
+    public struct FfiSignalChange
+    {
+        public FfiNodeId node;
+        public FfiWireColor wire;
+        public uint signal;
+        public FfiWireNetworkId network;
+        public long old;
+        public long @new;
+    }
+
+    /// <summary>Engine-owned array returned by <c>factorial_logic_get_changed_signals</c>.</summary>
+    [StructLayout(LayoutKind.Sequential)]
+    public unsafe struct FfiSignalChangeBuffer
+    {
+        public FfiSignalChange* changes;
+        public uint count;
+    }
+
+    /// <summary>Called with each <c>FfiOperationRecord</c> and the user data passed to <c>factorial_set_operation_logger</c>. The record is valid for the call only.</summary>
+    [UnmanagedFunctionPointer(CallingConvention.Cdecl)]
+    public unsafe delegate void FactorialOperationLogger(FfiOperationRecord* record, void* user_data);
+
+    /// <summary>Opaque engine handle. Callers receive <c>*mut FactorialEngine</c> from <c>factorial_create</c> and pass it to all subsequent calls. Opaque; only handled through pointers.</summary>
+    public struct FactorialEngine
+    {
+    }
+
+    /// <summary>Opaque; only handled through pointers.</summary>
+    public struct Engine
+    {
+    }
+
+    /// <summary>Opaque; only handled through pointers.</summary>
+    public struct FactorialEnginePool
+    {
+    }
+
+    /// <summary>Opaque; only handled through pointers.</summary>
+    public struct FactorialEngineInspector
+    {
+    }
+
+    /// <summary>Entry points and constants of the <c>factorial_ffi</c> library.</summary>
+    public static unsafe class FactorialNative
+    {
+        /// <summary>Name of the native library.</summary>
+        public const string LibraryName = "factorial_ffi";
+
+        /// <summary>Default maximum number of events held in the poll cache per step.</summary>
+        public const uint DEFAULT_EVENT_CACHE_LIMIT = 262144;
+
+        /// <summary>Flag for <c>factorial_add_node_ex</c>: the node emits no events.</summary>
+        public const uint NODE_FLAG_SILENT = 1;
+
+        /// <summary>Flag for <c>factorial_add_node_ex</c>: statistics trackers ignore the node.</summary>
+        public const uint NODE_FLAG_NO_STATS = 2;
+
+        /// <summary>Written to <c>ticks_until_delivery</c> of <c>FfiTransportProgress</c> for a belt with no item on its way.</summary>
+        public const uint NO_DELIVERY = 4294967295;
+
+        /// <summary>Bundle component: the tech tree registered on the engine.</summary>
+        public const uint BUNDLE_TECH_TREE = 1;
+
+        /// <summary>Bundle component: a fluid module.</summary>
+        public const uint BUNDLE_FLUID = 2;
+
+        /// <summary>Bundle component: a statistics configuration.</summary>
+        public const uint BUNDLE_STATS_CONFIG = 4;
+
+        /// <summary>Written by <c>factorial_get_active_recipe</c> when no recipe is in progress.</summary>
+        public const uint NO_RECIPE = 4294967295;
+
+        /// <summary>Pass to <c>factorial_set_storage_limit</c> to make every slot usable.</summary>
+        public const uint NO_STORAGE_LIMIT = 4294967295;
+
+        /// <summary>Create a new engine with <c>Tick</c> simulation strategy. Returns a heap-allocated engine pointer. The caller must eventually call <c>factorial_destroy</c> to free the memory.</summary>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialEngine* factorial_create();
+
+        /// <summary>Create a new engine with <c>Delta</c> simulation strategy. <c>fixed_timestep</c> is the number of ticks per fixed simulation step.</summary>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialEngine* factorial_create_delta(ulong fixed_timestep);
+
+        /// <summary>Destroy an engine and free its memory.</summary>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_destroy(FactorialEngine* engine);
+
+        /// <summary>Wrap an <c>Engine</c> owned by Rust code in an FFI handle without taking ownership, so an embedder can hand its existing engine to C plugins. The handle registers the same event listeners and caches as one from <c>factorial_create</c>.</summary>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialEngine* factorial_wrap_borrowed(Engine* engine);
+
+        /// <summary>Release a handle from <c>factorial_wrap_borrowed</c>, returning control of the engine to its owner. The engine is not freed. Buffers the handle handed out are invalid afterwards.</summary>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_unwrap(FactorialEngine* engine);
+
+        /// <summary>Advance the simulation by one tick (tick mode) or by <c>dt</c> ticks (delta mode).</summary>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_step(FactorialEngine* engine);
+
+        /// <summary>Advance the simulation by <c>dt</c> ticks. In tick mode <c>dt</c> is ignored and exactly one step runs. In delta mode, <c>dt</c> is accumulated and one step runs per whole fixed timestep.</summary>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_advance(FactorialEngine* engine, ulong dt);
+
+        /// <summary>Open a sliced tick: one step run in pieces by <c>factorial_continue_tick</c>, ending in the same state and events as <c>factorial_step</c>. Clears the event buffer, which collects the tick's events when it completes.</summary>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_begin_tick(FactorialEngine* engine);
+
+        /// <summary>Run up to <c>budget</c> units of the open sliced tick: the pre-tick phase, one edge or one node each, the component phase, and the end of the step each count as one. A budget of 0 counts as 1. Writes whether the tick completed to <c>out_status</c>.</summary>
+        /// <param name="out_status">Out: written by the call.</param>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_continue_tick(FactorialEngine* engine, uint budget, FfiTickSliceStatus* out_status);
+
+        /// <summary>Limit the work a single step may do; 0 removes the limit.</summary>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_set_step_work_limit(FactorialEngine* engine, ulong max_work_units);
+
+        /// <summary>Switch the simulation strategy between steps, without recreating the engine. <c>fixed_timestep</c> is the ticks per step for <c>Delta</c> and ignored for <c>Tick</c>. Factory state and the tick counter are untouched.</summary>
+        /// <param name="out_discarded">Out: written by the call.</param>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_set_strategy(FactorialEngine* engine, FfiSimulationStrategy strategy, ulong fixed_timestep, ulong* out_discarded);
+
+        /// <summary>Read the simulation strategy. <c>out_fixed_timestep</c> is 0 in tick mode.</summary>
+        /// <param name="out_strategy">Out: written by the call.</param>
+        /// <param name="out_fixed_timestep">Out: written by the call.</param>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_get_strategy(FactorialEngine* engine, FfiSimulationStrategy* out_strategy, ulong* out_fixed_timestep);
+
+        /// <summary>Create an empty engine pool. The caller must eventually call <c>factorial_pool_destroy</c>.</summary>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialEnginePool* factorial_pool_create();
+
+        /// <summary>Destroy a pool. The engines in it are not destroyed.</summary>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_pool_destroy(FactorialEnginePool* pool);
+
+        /// <summary>Add an engine to a pool. Returns <c>InvalidHandle</c> if the engine is already in the pool.</summary>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_pool_add(FactorialEnginePool* pool, FactorialEngine* engine);
+
+        /// <summary>Remove an engine from a pool without destroying it. Returns <c>InvalidHandle</c> if the engine is not in the pool.</summary>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_pool_remove(FactorialEnginePool* pool, FactorialEngine* engine);
+
+        /// <summary>Get the number of engines in a pool.</summary>
+        /// <param name="out_count">Out: written by the call.</param>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_pool_len(FactorialEnginePool* pool, uint* out_count);
+
+        /// <summary>Step every engine in the pool once, exactly as <c>factorial_step</c> would.</summary>
+        /// <param name="out_results">Buffer with room for one element per engine in the pool, written by the call.</param>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_pool_step_all(FactorialEnginePool* pool, uint thread_count, FactorialResult* out_results);
+
+        /// <summary>Queue a node to be added to the graph. Returns a pending node ID via <c>out_pending</c>. The real node ID is assigned after <c>factorial_apply_mutations</c>.</summary>
+        /// <param name="out_pending">Out: written by the call.</param>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_add_node(FactorialEngine* engine, uint building_type, FfiPendingNodeId* out_pending);
+
+        /// <summary>Queue a node to be added to the graph with behaviour flags, a bitwise OR of <c>NODE_FLAG_*</c> values. Unknown bits are ignored. Otherwise behaves like <c>factorial_add_node</c>.</summary>
+        /// <param name="out_pending">Out: written by the call.</param>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_add_node_ex(FactorialEngine* engine, uint building_type, uint flags, FfiPendingNodeId* out_pending);
+
+        /// <summary>Queue a ghost node: a planned building that is visible to queries and logistics but does not simulate, costs nothing per tick and is left out of the state hash. Turn it into a real node with <c>factorial_materialize_ghost</c>; it keeps its id and configuration. Otherwise behaves like <c>factorial_add_node</c>.</summary>
+        /// <param name="out_pending">Out: written by the call.</param>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_add_ghost(FactorialEngine* engine, uint building_type, FfiPendingNodeId* out_pending);
+
+        /// <summary>Queue a node for removal.</summary>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_remove_node(FactorialEngine* engine, FfiNodeId node_id);
+
+        /// <summary>Queue an edge connecting two nodes. Returns a pending edge ID via <c>out_pending</c>.</summary>
+        /// <param name="out_pending">Out: written by the call.</param>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_connect(FactorialEngine* engine, FfiNodeId from_node, FfiNodeId to_node, FfiPendingEdgeId* out_pending);
+
+        /// <summary>Queue a ghost edge between two nodes, either of which may be a ghost. <c>factorial_connect</c> refuses ghost endpoints; planned edges use this instead. Returns a pending edge ID via <c>out_pending</c>.</summary>
+        /// <param name="out_pending">Out: written by the call.</param>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_connect_ghost(FactorialEngine* engine, FfiNodeId from_node, FfiNodeId to_node, FfiPendingEdgeId* out_pending);
+
+        /// <summary>Queue an edge for removal (disconnect).</summary>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_disconnect(FactorialEngine* engine, FfiEdgeId edge_id);
+
+        /// <summary>Queue a node addition in mutation lane <c>lane</c>. <c>factorial_apply_mutations</c> applies lanes in ascending order and calls within a lane in call order, so the relative order of, say, player actions in lane 0 and automation in lane 1 does not depend on which was queued first. The other queue calls use lane 0. Otherwise behaves like <c>factorial_add_node</c>.</summary>
+        /// <param name="out_pending">Out: written by the call.</param>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_add_node_in_lane(FactorialEngine* engine, byte lane, uint building_type, FfiPendingNodeId* out_pending);
+
+        /// <summary>Queue a node removal in mutation lane <c>lane</c>; see <c>factorial_add_node_in_lane</c>.</summary>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_remove_node_in_lane(FactorialEngine* engine, byte lane, FfiNodeId node_id);
+
+        /// <summary>Queue an edge in mutation lane <c>lane</c>; see <c>factorial_add_node_in_lane</c>. Returns a pending edge ID via <c>out_pending</c>.</summary>
+        /// <param name="out_pending">Out: written by the call.</param>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_connect_in_lane(FactorialEngine* engine, byte lane, FfiNodeId from_node, FfiNodeId to_node, FfiPendingEdgeId* out_pending);
+
+        /// <summary>Queue an edge removal in mutation lane <c>lane</c>; see <c>factorial_add_node_in_lane</c>.</summary>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_disconnect_in_lane(FactorialEngine* engine, byte lane, FfiEdgeId edge_id);
+
+        /// <summary>Apply all queued graph mutations atomically. Results (pending-&gt;real ID mappings) are written to <c>out_result</c>.</summary>
+        /// <param name="out_result">Out: written by the call.</param>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_apply_mutations(FactorialEngine* engine, FfiMutationResult* out_result);
+
+        /// <summary>Look up the real node id a pending node resolved to.</summary>
+        /// <param name="out_real">Out: written by the call.</param>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_resolve_pending_node(FactorialEngine* engine, FfiPendingNodeId pending, FfiNodeId* out_real);
+
+        /// <summary>Look up the real edge id a pending edge resolved to. Result codes match <c>factorial_resolve_pending_node</c>.</summary>
+        /// <param name="out_real">Out: written by the call.</param>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_resolve_pending_edge(FactorialEngine* engine, FfiPendingEdgeId pending, FfiEdgeId* out_real);
+
+        /// <summary>Set how many applied mutation batches the engine remembers for <c>factorial_resolve_pending_node</c> / <c>factorial_resolve_pending_edge</c>. Only batches that add nodes or edges count. <c>0</c> disables the history.</summary>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_set_resolution_history(FactorialEngine* engine, uint batches);
+
+        /// <summary>Get the number of nodes in the graph.</summary>
+        /// <param name="out_count">Out: written by the call.</param>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_node_count(FactorialEngine* engine, uint* out_count);
+
+        /// <summary>Get the number of edges in the graph.</summary>
+        /// <param name="out_count">Out: written by the call.</param>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_edge_count(FactorialEngine* engine, uint* out_count);
+
+        /// <summary>Get the topology generation: a counter that changes whenever applied mutations add or remove nodes or edges. Hosts holding a copy of <c>factorial_get_topology</c> refetch it when this changes, and after changing an edge's transport to another kind.</summary>
+        /// <param name="out_generation">Out: written by the call.</param>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_topology_generation(FactorialEngine* engine, ulong* out_generation);
+
+        /// <summary>Copy the graph's adjacency as compressed sparse rows: every node id in ascending order into <c>out_nodes</c>, <c>node_count + 1</c> offsets into <c>out_offsets</c>, and the outgoing edges into <c>out_edges</c>. The edges of node <c>i</c> are <c>out_edges[out_offsets[i]..out_offsets[i + 1]]</c>, in ascending edge id order.</summary>
+        /// <param name="out_nodes">Out: written by the call.</param>
+        /// <param name="out_offsets">Out: written by the call.</param>
+        /// <param name="out_edges">Out: written by the call.</param>
+        /// <param name="out_node_count">Out: written by the call.</param>
+        /// <param name="out_edge_count">Out: written by the call.</param>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_get_topology(FactorialEngine* engine, FfiNodeId* out_nodes, uint nodes_len, uint* out_offsets, uint offsets_len, FfiTopologyEdge* out_edges, uint edges_len, uint* out_node_count, uint* out_edge_count);
+
+        /// <summary>Get the current tick counter.</summary>
+        /// <param name="out_tick">Out: written by the call.</param>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_get_tick(FactorialEngine* engine, ulong* out_tick);
+
+        /// <summary>Get the state hash of the engine (for desync detection).</summary>
+        /// <param name="out_hash">Out: written by the call.</param>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_get_state_hash(FactorialEngine* engine, ulong* out_hash);
+
+        /// <summary>Get the most recent state hash split by subsystem, so two desynced peers can tell which subsystem diverged.</summary>
+        /// <param name="out_breakdown">Out: written by the call.</param>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_get_state_hash_breakdown(FactorialEngine* engine, FfiHashBreakdown* out_breakdown);
+
+        /// <summary>Get the enabled extensions as a bit mask: bit <c>n</c> is set when the extension with id <c>n</c> is enabled (0 logic, 1 fluid, 2 stats, 3 tech tree, 4 power, 5 spatial, 6 crafting, 7 spoilage).</summary>
+        /// <param name="out_mask">Out: written by the call.</param>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_enabled_extensions(FactorialEngine* engine, uint* out_mask);
+
+        /// <summary>Enable an extension by id (see <c>factorial_enabled_extensions</c>), for hosts that run an extension outside the engine and want saves and state hashes to record it.</summary>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_enable_extension(FactorialEngine* engine, uint extension);
+
+        /// <summary>Count every unit of <c>item_type</c> in node inventories and transports.</summary>
+        /// <param name="out_census">Out: written by the call.</param>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_item_census(FactorialEngine* engine, uint item_type, FfiItemCensus* out_census);
+
+        /// <summary>Summarise every item type currently involved in a stall, one record per item type in ascending item-type order. See <c>Engine::bottleneck_digest</c>.</summary>
+        /// <param name="out_buffer">Out: engine-owned; valid until the next call on the same engine.</param>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_get_bottleneck_digest(FactorialEngine* engine, FfiBottleneckBuffer* out_buffer);
+
+        /// <summary>Get the processor state for a node.</summary>
+        /// <param name="out_info">Out: written by the call.</param>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_get_processor_state(FactorialEngine* engine, FfiNodeId node_id, FfiProcessorInfo* out_info);
+
+        /// <summary>Get the total item count in a node's input inventory.</summary>
+        /// <param name="out_count">Out: written by the call.</param>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_get_input_inventory_count(FactorialEngine* engine, FfiNodeId node_id, uint* out_count);
+
+        /// <summary>Get the total item count in a node's output inventory.</summary>
+        /// <param name="out_count">Out: written by the call.</param>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_get_output_inventory_count(FactorialEngine* engine, FfiNodeId node_id, uint* out_count);
+
+        /// <summary>List the items currently on a belt edge, with stable instance ids for animation. Non-belt transports report zero items.</summary>
+        /// <param name="out_buffer">Out: engine-owned; valid until the next call on the same engine.</param>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_get_transport_items(FactorialEngine* engine, FfiEdgeId edge_id, FfiTransportItemBuffer* out_buffer);
+
+        /// <summary>Write the delivery accumulator of a flow edge to <c>out_value</c> as raw Fixed64 bits: the fraction of an item drained from the buffer but not yet delivered. Always in <c>[0, 1)</c>.</summary>
+        /// <param name="out_value">Out: written by the call.</param>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_get_flow_accumulator(FactorialEngine* engine, FfiEdgeId edge_id, long* out_value);
+
+        /// <summary>Write the number of items currently moving along an edge to <c>out_count</c>: belt items, whole items in a flow buffer, a batch's pending items, or a vehicle's cargo.</summary>
+        /// <param name="out_count">Out: written by the call.</param>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_get_transport_in_transit(FactorialEngine* engine, FfiEdgeId edge_id, uint* out_count);
+
+        /// <summary>Write where an edge's transport is in its delivery cycle to <c>out</c>: buffer fill, belt head and next arrival, batch fill and dispatch countdown, or vehicle phase. Tick counts assume the destination has room; a count of <c>n</c> means the delivery or phase change happens during the <c>n</c>th <c>factorial_step</c> from now.</summary>
+        /// <param name="out">Out: written by the call.</param>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_get_transport_progress(FactorialEngine* engine, FfiEdgeId edge_id, FfiTransportProgress* @out);
+
+        /// <summary>Create a read-only inspector for <c>engine</c>. The caller must eventually call <c>factorial_destroy_inspector</c>.</summary>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialEngineInspector* factorial_create_inspector(FactorialEngine* engine);
+
+        /// <summary>Free an inspector. Its engine is not affected.</summary>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_destroy_inspector(FactorialEngineInspector* inspector);
+
+        /// <summary>Get the number of nodes in the graph. Inspector version of <c>factorial_node_count</c>.</summary>
+        /// <param name="out_count">Out: written by the call.</param>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_inspector_node_count(FactorialEngineInspector* inspector, uint* out_count);
+
+        /// <summary>Get the number of edges in the graph. Inspector version of <c>factorial_edge_count</c>.</summary>
+        /// <param name="out_count">Out: written by the call.</param>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_inspector_edge_count(FactorialEngineInspector* inspector, uint* out_count);
+
+        /// <summary>Get the current tick. Inspector version of <c>factorial_get_tick</c>.</summary>
+        /// <param name="out_tick">Out: written by the call.</param>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_inspector_get_tick(FactorialEngineInspector* inspector, ulong* out_tick);
+
+        /// <summary>Get the state hash of the most recent step. Inspector version of <c>factorial_get_state_hash</c>.</summary>
+        /// <param name="out_hash">Out: written by the call.</param>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_inspector_get_state_hash(FactorialEngineInspector* inspector, ulong* out_hash);
+
+        /// <summary>Get the most recent state hash split by subsystem. Inspector version of <c>factorial_get_state_hash_breakdown</c>.</summary>
+        /// <param name="out_breakdown">Out: written by the call.</param>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_inspector_get_state_hash_breakdown(FactorialEngineInspector* inspector, FfiHashBreakdown* out_breakdown);
+
+        /// <summary>Count every unit of an item type in the factory. Inspector version of <c>factorial_item_census</c>.</summary>
+        /// <param name="out_census">Out: written by the call.</param>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_inspector_item_census(FactorialEngineInspector* inspector, uint item_type, FfiItemCensus* out_census);
+
+        /// <summary>Get the processor state of a node. Inspector version of <c>factorial_get_processor_state</c>.</summary>
+        /// <param name="out_info">Out: written by the call.</param>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_inspector_get_processor_state(FactorialEngineInspector* inspector, FfiNodeId node_id, FfiProcessorInfo* out_info);
+
+        /// <summary>Get the total item count in a node's input inventory. Inspector version of <c>factorial_get_input_inventory_count</c>.</summary>
+        /// <param name="out_count">Out: written by the call.</param>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_inspector_get_input_inventory_count(FactorialEngineInspector* inspector, FfiNodeId node_id, uint* out_count);
+
+        /// <summary>Get the total item count in a node's output inventory. Inspector version of <c>factorial_get_output_inventory_count</c>.</summary>
+        /// <param name="out_count">Out: written by the call.</param>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_inspector_get_output_inventory_count(FactorialEngineInspector* inspector, FfiNodeId node_id, uint* out_count);
+
+        /// <summary>Get the simulation status of a node. Inspector version of <c>factorial_get_node_status</c>.</summary>
+        /// <param name="out_status">Out: written by the call.</param>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_inspector_get_node_status(FactorialEngineInspector* inspector, FfiNodeId node_id, FfiNodeStatus* out_status);
+
+        /// <summary>Get the simulation status of an edge. Inspector version of <c>factorial_get_edge_status</c>.</summary>
+        /// <param name="out_status">Out: written by the call.</param>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_inspector_get_edge_status(FactorialEngineInspector* inspector, FfiEdgeId edge_id, FfiNodeStatus* out_status);
+
+        /// <summary>Poll all buffered events since the last step. Returns a pointer to an engine-owned buffer of <c>FfiEvent</c> structs. The buffer is valid until the next <c>factorial_step</c>, <c>factorial_advance</c>, or <c>factorial_destroy</c>.</summary>
+        /// <param name="out_buffer">Out: engine-owned; valid until the next call on the same engine.</param>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_poll_events(FactorialEngine* engine, FfiEventBuffer* out_buffer);
+
+        /// <summary>Encode the events <c>factorial_poll_events</c> would return in the compact, versioned network format (readable with <c>Engine::decode_events_compact</c>). Each event is a tag plus only the fields it carries, so the buffer is far smaller than the flat <c>FfiEvent</c> array.</summary>
+        /// <param name="out_buffer">Out: caller-owned; release it with <c>factorial_free_buffer</c>.</param>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_encode_events_compact(FactorialEngine* engine, FfiByteBuffer* out_buffer);
+
+        /// <summary>Set the maximum number of events cached for <c>factorial_poll_events</c> per step. Once the cache holds <c>max_events</c>, further events are counted by <c>factorial_dropped_event_count</c> instead of being cached. The cap only affects observation; the simulation itself is unchanged. Defaults to <c>DEFAULT_EVENT_CACHE_LIMIT</c>.</summary>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_set_event_cache_limit(FactorialEngine* engine, uint max_events);
+
+        /// <summary>Get the number of events dropped by the event cache limit since the last step. A non-zero count means the buffer from <c>factorial_poll_events</c> is incomplete.</summary>
+        /// <param name="out_count">Out: written by the call.</param>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_dropped_event_count(FactorialEngine* engine, ulong* out_count);
+
+        /// <summary>Retain the last <c>capacity</c> events across steps for <c>factorial_poll_event_history</c>. Once full, the oldest events are dropped. A capacity of 0 disables the history.</summary>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_set_event_history_capacity(FactorialEngine* engine, uint capacity);
+
+        /// <summary>Copy retained history events stamped at or after <c>since_tick</c> into the caller-provided <c>out_events</c> array, oldest first, writing at most <c>max</c> events. The number written is stored in <c>*out_written</c>. Unlike <c>factorial_poll_events</c>, the history is not cleared by stepping.</summary>
+        /// <param name="out_events">Buffer with room for <c>max</c> elements, written by the call.</param>
+        /// <param name="max">Element count of <c>out_events</c>.</param>
+        /// <param name="out_written">Out: written by the call.</param>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_poll_event_history(FactorialEngine* engine, ulong since_tick, FfiEvent* out_events, uint max, uint* out_written);
+
+        /// <summary>Copy retained history events matching <c>filter</c> into <c>out_events</c>, oldest first, writing at most <c>max</c> events. The number written is stored in <c>*out_count</c>. A null <c>filter</c> matches every event.</summary>
+        /// <param name="out_events">Buffer with room for <c>max</c> elements, written by the call.</param>
+        /// <param name="max">Element count of <c>out_events</c>.</param>
+        /// <param name="out_count">Out: written by the call.</param>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_query_event_history(FactorialEngine* engine, FfiEventFilter* filter, FfiEvent* out_events, uint max, uint* out_count);
+
+        /// <summary>Create a cursor over the event history that reads events matching <c>filter</c> (null matches every event), starting from the oldest retained event. Each cursor keeps its own position and does not affect <c>factorial_poll_events</c>. Cursors are not serialized. The cursor id is written to <c>out_cursor</c>.</summary>
+        /// <param name="out_cursor">Out: written by the call.</param>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_create_event_cursor(FactorialEngine* engine, FfiEventFilter* filter, FfiEventCursorId* out_cursor);
+
+        /// <summary>Destroy an event cursor.</summary>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_destroy_event_cursor(FactorialEngine* engine, FfiEventCursorId cursor);
+
+        /// <summary>Copy up to <c>max</c> history events matching the cursor's filter that were recorded after its position into <c>out_events</c>, oldest first, and advance the cursor past them. The number written is stored in <c>*out_count</c>. <c>*out_lagged</c> is set if the history overwrote events the cursor had not read; the cursor then resumed from the oldest retained event.</summary>
+        /// <param name="out_events">Buffer with room for <c>max</c> elements, written by the call.</param>
+        /// <param name="max">Element count of <c>out_events</c>.</param>
+        /// <param name="out_count">Out: written by the call.</param>
+        /// <param name="out_lagged">Out: written by the call.</param>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_read_event_cursor(FactorialEngine* engine, FfiEventCursorId cursor, FfiEvent* out_events, uint max, uint* out_count, bool* out_lagged);
+
+        /// <summary>Include the event history in <c>factorial_serialize</c> output. Off by default; a snapshot that carries a history restores it on load.</summary>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_set_serialize_event_history(FactorialEngine* engine, [MarshalAs(UnmanagedType.U1)] bool enabled);
+
+        /// <summary>Start recording an event log. Every tick's events are kept until taken with <c>factorial_take_event_log</c>, independent of the poll buffer. No-op if already enabled.</summary>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_enable_event_log(FactorialEngine* engine);
+
+        /// <summary>Stop recording the event log and discard anything not yet taken.</summary>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_disable_event_log(FactorialEngine* engine);
+
+        /// <summary>Take the event log recorded since the last take, encoded in the versioned <c>EventLog</c> byte format (readable with <c>EventLog::from_bytes</c>). Recording stays enabled. If the log is not enabled, an empty log is returned.</summary>
+        /// <param name="out_buffer">Out: caller-owned; release it with <c>factorial_free_buffer</c>.</param>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_take_event_log(FactorialEngine* engine, FfiByteBuffer* out_buffer);
+
+        /// <summary>Serialize the engine state to a binary buffer. The returned <c>FfiByteBuffer</c> contains a pointer and length. The caller must free the buffer with <c>factorial_free_buffer</c> when done.</summary>
+        /// <param name="out_buffer">Out: caller-owned; release it with <c>factorial_free_buffer</c>.</param>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_serialize(FactorialEngine* engine, FfiByteBuffer* out_buffer);
+
+        /// <summary>Deserialize an engine from a binary buffer. Returns a new engine pointer via <c>out_engine</c>. The caller takes ownership.</summary>
+        /// <param name="data">Buffer of <c>len</c> elements read by the call.</param>
+        /// <param name="len">Element count of <c>data</c>.</param>
+        /// <param name="out_engine">Out: written by the call.</param>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_deserialize(byte* data, nuint len, FactorialEngine** out_engine);
+
+        /// <summary>Capture the engine as a scenario bundle. <c>flags</c> is a bitwise OR of the <c>BUNDLE_*</c> components to include alongside it; only <c>BUNDLE_TECH_TREE</c> applies to an FFI engine, and only if a tech tree is registered on it. The caller must free the buffer with <c>factorial_free_buffer</c>.</summary>
+        /// <param name="out_buffer">Out: caller-owned; release it with <c>factorial_free_buffer</c>.</param>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_bundle_capture(FactorialEngine* engine, uint flags, FfiByteBuffer* out_buffer);
+
+        /// <summary>Restore a scenario bundle into a new engine, returned via <c>out_engine</c>. The caller takes ownership. A tech tree that was registered on the captured engine is registered on the new one. <c>out_components</c> receives the <c>BUNDLE_*</c> components the bundle holds; standalone tech trees, fluid modules and statistics configurations belong to Rust hosts and are not restored here. Unknown sections are skipped.</summary>
+        /// <param name="data">Buffer of <c>len</c> elements read by the call.</param>
+        /// <param name="len">Element count of <c>data</c>.</param>
+        /// <param name="out_engine">Out: written by the call.</param>
+        /// <param name="out_components">Buffer with room for <c>len</c> elements, written by the call.</param>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_bundle_restore(byte* data, nuint len, FactorialEngine** out_engine, uint* out_components);
+
+        /// <summary>Read the summary of a buffer produced by <c>factorial_serialize</c> without deserializing the engine. Saves without a summary section are fully decoded. Only the summary section is validated: a corrupt body is not noticed, a checksum mismatch returns <c>FACTORIAL_RESULT_DESERIALIZE_ERROR</c>.</summary>
+        /// <param name="data">Buffer of <c>len</c> elements read by the call.</param>
+        /// <param name="len">Element count of <c>data</c>.</param>
+        /// <param name="out_summary">Out: written by the call.</param>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_inspect(byte* data, nuint len, FfiSaveSummary* out_summary);
+
+        /// <summary>Record per-item totals in the summary section of every <c>factorial_serialize</c> output, for <c>factorial_inspect</c>. Off by default.</summary>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_set_summary_item_totals(FactorialEngine* engine, [MarshalAs(UnmanagedType.U1)] bool enabled);
+
+        /// <summary>Free a byte buffer returned by <c>factorial_serialize</c> or <c>factorial_take_event_log</c>.</summary>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_free_buffer(FfiByteBuffer buffer);
+
+        /// <summary>Set a node's processor to Source.</summary>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_set_source(FactorialEngine* engine, FfiNodeId node_id, uint item_type, long rate);
+
+        /// <summary>Read back a Source processor's configuration. <c>out_rate</c> receives raw Fixed64 bits.</summary>
+        /// <param name="out_item">Out: written by the call.</param>
+        /// <param name="out_rate">Out: written by the call.</param>
+        /// <param name="out_depletion">Out: written by the call.</param>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_get_source(FactorialEngine* engine, FfiNodeId node_id, uint* out_item, long* out_rate, FfiDepletion* out_depletion);
+
+        /// <summary>Read back a node's processor: its kind and parameters. The recipe of a <c>Fixed</c> processor, or the active recipe of a <c>MultiRecipe</c> one, is described by <c>duration</c> and the item stacks copied into the caller's <c>inputs</c> and <c>outputs</c> buffers, in recipe order. Fluid ingredients and output chances are not reported.</summary>
+        /// <param name="out_config">Out: written by the call.</param>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_get_processor_config(FactorialEngine* engine, FfiNodeId node_id, FfiProcessorConfig* out_config);
+
+        /// <summary>Set a node's processor to MultiSource: one deposit yielding every item in <c>outputs</c>, each at its own rate, with every extracted item counting against the shared <c>depletion</c>.</summary>
+        /// <param name="outputs">Buffer of <c>output_count</c> elements read by the call.</param>
+        /// <param name="output_count">Element count of <c>outputs</c>.</param>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_set_multi_source(FactorialEngine* engine, FfiNodeId node_id, FfiSourceOutput* outputs, uint output_count, FfiDepletion depletion);
+
+        /// <summary>Install the seeded noise field used by <c>factorial_add_source_at</c>. Sources already added keep their configuration.</summary>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_set_richness_provider(FactorialEngine* engine, ulong seed, FfiRichnessCurve* curve);
+
+        /// <summary>Make a node a Source whose rate and deposit are read from the richness provider at <c>(x, y)</c>. <c>out_site</c> may be null; otherwise it receives the recorded site.</summary>
+        /// <param name="out_site">Out: written by the call.</param>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_add_source_at(FactorialEngine* engine, FfiNodeId node_id, uint item_type, int x, int y, FfiSourceSite* out_site);
+
+        /// <summary>Read the coordinates and richness of a source added with <c>factorial_add_source_at</c>.</summary>
+        /// <param name="out_site">Out: written by the call.</param>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_get_source_site(FactorialEngine* engine, FfiNodeId node_id, FfiSourceSite* out_site);
+
+        /// <summary>Set a node's processor to FixedRecipe.</summary>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_set_fixed_processor(FactorialEngine* engine, FfiNodeId node_id, FfiRecipe* recipe);
+
+        /// <summary>Give each of <c>count</c> nodes a FixedRecipe processor in one call, as <c>factorial_set_fixed_processor</c> does for one.</summary>
+        /// <param name="assignments">Buffer of <c>count</c> elements read by the call.</param>
+        /// <param name="count">Element count of <c>assignments</c>.</param>
+        /// <param name="out_results">Buffer with room for <c>count</c> elements, written by the call.</param>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_set_processors_bulk(FactorialEngine* engine, FfiProcessorAssignment* assignments, uint count, FactorialResult* out_results);
+
+        /// <summary>Set a node's processor to a FixedRecipe with fluid ingredients.</summary>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_set_fixed_processor_v2(FactorialEngine* engine, FfiNodeId node_id, FfiRecipeV2* recipe);
+
+        /// <summary>Set a node's processor to a FixedRecipe with fluid ingredients and chance-based outputs.</summary>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_set_fixed_processor_v3(FactorialEngine* engine, FfiNodeId node_id, FfiRecipeV3* recipe);
+
+        /// <summary>Set a node's processor to a flexible processor that picks one of <c>recipe_count</c> registry recipes each time a cycle starts, from the ingredients in its input inventory.</summary>
+        /// <param name="recipes">Buffer of <c>recipe_count</c> elements read by the call.</param>
+        /// <param name="recipe_count">Element count of <c>recipes</c>.</param>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_set_flexible_processor(FactorialEngine* engine, FfiNodeId node_id, uint* recipes, uint recipe_count, uint selection);
+
+        /// <summary>Write the registry recipe a flexible node is crafting to <c>out_recipe</c>, or <c>NO_RECIPE</c> if it is not mid-cycle.</summary>
+        /// <param name="out_recipe">Out: written by the call.</param>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_get_active_recipe(FactorialEngine* engine, FfiNodeId node_id, uint* out_recipe);
+
+        /// <summary>Turn a node into a storage node (chest, warehouse) with <c>slot_count</c> slots of <c>slot_capacity</c> items each, all usable.</summary>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_set_storage_processor(FactorialEngine* engine, FfiNodeId node_id, uint slot_count, uint slot_capacity, [MarshalAs(UnmanagedType.U1)] bool read_signal);
+
+        /// <summary>Limit how many of a storage node's slots accept deliveries, counted from the first, or pass <c>NO_STORAGE_LIMIT</c> to lift the limit. Items already in slots past the limit stay there and can still be taken out.</summary>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_set_storage_limit(FactorialEngine* engine, FfiNodeId node_id, uint usable_slots);
+
+        /// <summary>Replace the definition of a recipe in a running engine, for hot-reloading recipe data. Flexible nodes use the new definition the next time they select the recipe.</summary>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_update_recipe(FactorialEngine* engine, uint recipe_id, FfiRecipe* recipe, uint policy);
+
+        /// <summary>Deliver fluid to a node's recipe fluid buffer.</summary>
+        /// <param name="out_accepted">Out: written by the call.</param>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_add_fluid_input(FactorialEngine* engine, FfiNodeId node_id, uint fluid, long amount, long* out_accepted);
+
+        /// <summary>Remove up to <c>max</c> (raw Fixed64 bits) of a fluid from a node's recipe output buffer. The amount removed (raw bits) is written to <c>out_taken</c>.</summary>
+        /// <param name="out_taken">Out: written by the call.</param>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_take_fluid_output(FactorialEngine* engine, FfiNodeId node_id, uint fluid, long max, long* out_taken);
+
+        /// <summary>Set an edge's transport to FlowTransport with default buffer/latency.</summary>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_set_flow_transport(FactorialEngine* engine, FfiEdgeId edge_id, long rate);
+
+        /// <summary>Set an edge's transport to ItemTransport.</summary>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_set_item_transport(FactorialEngine* engine, FfiEdgeId edge_id, long speed, uint slot_count, byte lanes);
+
+        /// <summary>Set an edge's transport to BatchTransport.</summary>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_set_batch_transport(FactorialEngine* engine, FfiEdgeId edge_id, uint batch_size, uint cycle_time);
+
+        /// <summary>Set an edge's transport to VehicleTransport.</summary>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_set_vehicle_transport(FactorialEngine* engine, FfiEdgeId edge_id, uint capacity, uint travel_time);
+
+        /// <summary>Read back a FlowTransport's configuration. <c>out_rate</c> and <c>out_capacity</c> (the buffer capacity) receive raw Fixed64 bits.</summary>
+        /// <param name="out_rate">Out: written by the call.</param>
+        /// <param name="out_capacity">Out: written by the call.</param>
+        /// <param name="out_latency">Out: written by the call.</param>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_get_flow_transport(FactorialEngine* engine, FfiEdgeId edge_id, long* out_rate, long* out_capacity, uint* out_latency);
+
+        /// <summary>Read back an ItemTransport's configuration. <c>out_speed</c> receives raw Fixed64 bits.</summary>
+        /// <param name="out_speed">Out: written by the call.</param>
+        /// <param name="out_slot_count">Out: written by the call.</param>
+        /// <param name="out_lanes">Out: written by the call.</param>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_get_item_transport(FactorialEngine* engine, FfiEdgeId edge_id, long* out_speed, uint* out_slot_count, byte* out_lanes);
+
+        /// <summary>Read back a BatchTransport's configuration.</summary>
+        /// <param name="out_batch_size">Out: written by the call.</param>
+        /// <param name="out_cycle_time">Out: written by the call.</param>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_get_batch_transport(FactorialEngine* engine, FfiEdgeId edge_id, uint* out_batch_size, uint* out_cycle_time);
+
+        /// <summary>Read back a VehicleTransport's configuration.</summary>
+        /// <param name="out_capacity">Out: written by the call.</param>
+        /// <param name="out_travel_time">Out: written by the call.</param>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_get_vehicle_transport(FactorialEngine* engine, FfiEdgeId edge_id, uint* out_capacity, uint* out_travel_time);
+
+        /// <summary>Apply one transport configuration to <c>count</c> edges in a single call.</summary>
+        /// <param name="edges">Buffer of <c>count</c> elements read by the call.</param>
+        /// <param name="count">Element count of <c>edges</c>.</param>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_set_transport_bulk(FactorialEngine* engine, FfiEdgeId* edges, uint count, FfiTransportConfig* config);
+
+        /// <summary>Group <c>count</c> edges into one logical transport line and write its id to <c>*out_group</c>. Edges not in the graph and repeated edges are skipped; an edge already in another group moves to the new one.</summary>
+        /// <param name="edges">Buffer of <c>count</c> elements read by the call.</param>
+        /// <param name="count">Element count of <c>edges</c>.</param>
+        /// <param name="out_group">Out: written by the call.</param>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_create_edge_group(FactorialEngine* engine, FfiEdgeId* edges, uint count, FfiEdgeGroupId* out_group);
+
+        /// <summary>Dissolve an edge group. Its edges are untouched.</summary>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_remove_edge_group(FactorialEngine* engine, FfiEdgeGroupId group);
+
+        /// <summary>Copy the edges of a group, in the order they were grouped, into <c>out_edges</c>.</summary>
+        /// <param name="out_edges">Buffer with room for <c>len</c> elements, written by the call.</param>
+        /// <param name="len">Element count of <c>out_edges</c>.</param>
+        /// <param name="out_written">Out: written by the call.</param>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_get_group_edges(FactorialEngine* engine, FfiEdgeGroupId group, FfiEdgeId* out_edges, uint len, uint* out_written);
+
+        /// <summary>Apply one transport configuration to every edge of a group. Each edge keeps the items in transit when its state fits the new transport (same kind and, for belts, the same slot layout) and starts empty otherwise.</summary>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_set_group_transport(FactorialEngine* engine, FfiEdgeGroupId group, FfiTransportConfig* config);
+
+        /// <summary>Write whether any edge of a group is at capacity to <c>*out_congested</c>.</summary>
+        /// <param name="out_congested">Out: written by the call.</param>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_group_is_congested(FactorialEngine* engine, FfiEdgeGroupId group, bool* out_congested);
+
+        /// <summary>Serialize the internal state of one edge's transport (flow buffer, belt slots, batch progress or vehicle cargo) into a byte buffer. The caller must free the buffer with <c>factorial_free_buffer</c>.</summary>
+        /// <param name="out_buffer">Out: caller-owned; release it with <c>factorial_free_buffer</c>.</param>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_get_transport_state(FactorialEngine* engine, FfiEdgeId edge_id, FfiByteBuffer* out_buffer);
+
+        /// <summary>Restore one edge's transport state from a buffer produced by <c>factorial_get_transport_state</c>.</summary>
+        /// <param name="data">Buffer of <c>len</c> elements read by the call.</param>
+        /// <param name="len">Element count of <c>data</c>.</param>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_set_transport_state(FactorialEngine* engine, FfiEdgeId edge_id, byte* data, nuint len);
+
+        /// <summary>Set the input inventory for a node with the given capacity.</summary>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_set_input_capacity(FactorialEngine* engine, FfiNodeId node_id, uint capacity);
+
+        /// <summary>Set the output inventory for a node with the given capacity.</summary>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_set_output_capacity(FactorialEngine* engine, FfiNodeId node_id, uint capacity);
+
+        /// <summary>Read back the shape of one of a node's inventories. <c>which</c> is 0 for the input inventory and 1 for the output inventory. <c>out_capacity</c> is the capacity of the first slot, which every slot shares when the inventory was set through <c>factorial_set_input_capacity</c> or <c>factorial_set_output_capacity</c>; it is 0 if the inventory has no slots.</summary>
+        /// <param name="out_slots_in">Out: written by the call.</param>
+        /// <param name="out_slots_out">Out: written by the call.</param>
+        /// <param name="out_capacity">Out: written by the call.</param>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_get_inventory_config(FactorialEngine* engine, FfiNodeId node_id, uint which, uint* out_slots_in, uint* out_slots_out, uint* out_capacity);
+
+        /// <summary>Set a node's simulation status. A ghost node keeps its configuration and stays visible to queries, but its processor does not run and no transport moves items into or out of it.</summary>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_set_node_status(FactorialEngine* engine, FfiNodeId node_id, FfiNodeStatus status);
+
+        /// <summary>Write a node's simulation status to <c>out_status</c>.</summary>
+        /// <param name="out_status">Out: written by the call.</param>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_get_node_status(FactorialEngine* engine, FfiNodeId node_id, FfiNodeStatus* out_status);
+
+        /// <summary>Attach an opaque host value to a node, replacing any previous value. The engine never interprets it; it is saved with the node and dropped when the node is removed.</summary>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_set_node_user_data(FactorialEngine* engine, FfiNodeId node_id, ulong data);
+
+        /// <summary>Write a node's host value to <c>out_data</c>.</summary>
+        /// <param name="out_data">Out: written by the call.</param>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_get_node_user_data(FactorialEngine* engine, FfiNodeId node_id, ulong* out_data);
+
+        /// <summary>Remove a node's host value, if any.</summary>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_clear_node_user_data(FactorialEngine* engine, FfiNodeId node_id);
+
+        /// <summary>Set an edge's simulation status. A ghost edge keeps its transport and state but does not advance.</summary>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_set_edge_status(FactorialEngine* engine, FfiEdgeId edge_id, FfiNodeStatus status);
+
+        /// <summary>Write an edge's own simulation status to <c>out_status</c>.</summary>
+        /// <param name="out_status">Out: written by the call.</param>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_get_edge_status(FactorialEngine* engine, FfiEdgeId edge_id, FfiNodeStatus* out_status);
+
+        /// <summary>Turn a ghost node into a real one. It keeps its id, processor, inventories and edges, and starts simulating on the next step.</summary>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_materialize_ghost(FactorialEngine* engine, FfiNodeId node_id);
+
+        /// <summary>Write the number of ghost nodes to <c>out_count</c>.</summary>
+        /// <param name="out_count">Out: written by the call.</param>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_ghost_count(FactorialEngine* engine, uint* out_count);
+
+        /// <summary>Choose whether ghost nodes and edges are folded into the state hash. Off by default, so planned construction does not change the hash; turn it on for desync checks that should cover plans too.</summary>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_set_hash_ghosts(FactorialEngine* engine, [MarshalAs(UnmanagedType.U1)] bool enabled);
+
+        /// <summary>Remove a node's processor, turning it into a passive node that consumes and produces nothing. The node keeps its inventories and edges.</summary>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_clear_processor(FactorialEngine* engine, FfiNodeId node_id);
+
+        /// <summary>Remove a node's input inventory. The items it held are written to <c>out_items</c> so the host can drop or refund them.</summary>
+        /// <param name="out_items">Out: engine-owned; valid until the next call on the same engine.</param>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_clear_input_inventory(FactorialEngine* engine, FfiNodeId node_id, FfiItemBuffer* out_items);
+
+        /// <summary>Remove a node's output inventory. The items it held are written to <c>out_items</c> so the host can drop or refund them.</summary>
+        /// <param name="out_items">Out: engine-owned; valid until the next call on the same engine.</param>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_clear_output_inventory(FactorialEngine* engine, FfiNodeId node_id, FfiItemBuffer* out_items);
+
+        /// <summary>Replace the contents of one of a node's inventories with <c>items</c>. <c>which</c> is 0 for the input inventory and 1 for the output inventory.</summary>
+        /// <param name="items">Buffer of <c>item_count</c> elements read by the call.</param>
+        /// <param name="item_count">Element count of <c>items</c>.</param>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_fill_inventory(FactorialEngine* engine, FfiNodeId node_id, uint which, FfiItemStack* items, uint item_count);
+
+        /// <summary>Fill many inventories in one call. Each entry names an inventory as <c>factorial_fill_inventory</c> does and a range of the shared <c>items</c> array.</summary>
+        /// <param name="entries">Buffer of <c>entry_count</c> elements read by the call.</param>
+        /// <param name="entry_count">Element count of <c>entries</c>.</param>
+        /// <param name="items">Buffer of <c>item_count</c> elements read by the call.</param>
+        /// <param name="item_count">Element count of <c>items</c>.</param>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_fill_inventories_bulk(FactorialEngine* engine, FfiInventoryFill* entries, uint entry_count, FfiItemStack* items, uint item_count);
+
+        /// <summary>Create a hand-crafting queue that draws ingredients from and deposits products into the input inventory of <c>node_id</c>. <c>speed</c> is raw Fixed64 bits and must be positive. The new queue id is written to <c>out_queue</c>.</summary>
+        /// <param name="out_queue">Out: written by the call.</param>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_create_crafting_queue(FactorialEngine* engine, FfiNodeId node_id, long speed, FfiCraftingQueueId* out_queue);
+
+        /// <summary>Remove a crafting queue, refunding the ingredients of every craft that has not completed.</summary>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_remove_crafting_queue(FactorialEngine* engine, FfiCraftingQueueId queue);
+
+        /// <summary>Enable or disable recursive crafting of missing intermediates. Recursive crafting looks up intermediate recipes in the engine's registry.</summary>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_set_crafting_recursive(FactorialEngine* engine, FfiCraftingQueueId queue, [MarshalAs(UnmanagedType.U1)] bool recursive);
+
+        /// <summary>Queue <c>count</c> crafts, taking all of their ingredients from the queue's inventory now. When <c>recipe</c> is non-null it defines the recipe and <c>recipe_id</c> only tags the job and its events; when null, <c>recipe_id</c> is looked up in the engine's registry.</summary>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_queue_craft(FactorialEngine* engine, FfiCraftingQueueId queue, uint recipe_id, FfiRecipe* recipe, uint count);
+
+        /// <summary>Cancel the job at <c>index</c> and refund ingredients for its crafts that have not started; a craft in progress keeps running. Intermediates queued by recursive crafting are cancelled with the job they feed. The number of crafts removed is written to <c>out_cancelled</c> when it is non-null.</summary>
+        /// <param name="out_cancelled">Out: written by the call.</param>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_cancel_craft(FactorialEngine* engine, FfiCraftingQueueId queue, uint index, uint* out_cancelled);
+
+        /// <summary>Write a crafting queue's progress to <c>out_progress</c>.</summary>
+        /// <param name="out_progress">Out: written by the call.</param>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_get_crafting_progress(FactorialEngine* engine, FfiCraftingQueueId queue, FfiCraftProgress* out_progress);
+
+        /// <summary>Enable or disable per-step operation counting. Disabling clears the last profile.</summary>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_set_profiling(FactorialEngine* engine, [MarshalAs(UnmanagedType.U1)] bool enabled);
+
+        /// <summary>Get the operation counts from the most recent step. Writes an all-zero profile (<c>tick == 0</c>) if profiling is disabled or no step has run since it was enabled.</summary>
+        /// <param name="out_profile">Out: written by the call.</param>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_get_step_profile(FactorialEngine* engine, FfiStepProfile* out_profile);
+
+        /// <summary>Enable or disable transit latency tracking for one edge.</summary>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_set_latency_tracking(FactorialEngine* engine, FfiEdgeId edge_id, [MarshalAs(UnmanagedType.U1)] bool enabled);
+
+        /// <summary>Set the latency histogram bucket edges, in ticks, and reset every histogram. A histogram has <c>count + 1</c> buckets.</summary>
+        /// <param name="edges">Buffer of <c>count</c> elements read by the call.</param>
+        /// <param name="count">Element count of <c>edges</c>.</param>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_set_latency_buckets(FactorialEngine* engine, ulong* edges, uint count);
+
+        /// <summary>Copy an edge's latency histogram into <c>out_counts</c>.</summary>
+        /// <param name="out_counts">Buffer with room for <c>len</c> elements, written by the call.</param>
+        /// <param name="len">Element count of <c>out_counts</c>.</param>
+        /// <param name="out_written">Out: written by the call.</param>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_get_edge_latency_histogram(FactorialEngine* engine, FfiEdgeId edge_id, ulong* out_counts, uint len, uint* out_written);
+
+        /// <summary>Zero every latency histogram.</summary>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_reset_latency_histograms(FactorialEngine* engine);
+
+        /// <summary>Call <c>logger</c> synchronously, with <c>user_data</c>, for every operation the engine performs from now on: graph mutations applied, processor output, transport deliveries and completed steps, in a deterministic order. This is a tooling trace, separate from the event queue and unaffected by event suppression. Pass a null <c>logger</c> to remove it.</summary>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_set_operation_logger(FactorialEngine* engine, FactorialOperationLogger logger, void* user_data);
+
+        /// <summary>Format raw Fixed64 <c>bits</c> as a decimal string with exactly <c>decimals</c> places (at most 19), so every host shows the same digits for the same value. <c>mode</c> is 0 = half up, 1 = half even, 2 = floor, 3 = ceil.</summary>
+        /// <param name="out_buf">Buffer with room for <c>len</c> elements, written by the call.</param>
+        /// <param name="len">Element count of <c>out_buf</c>.</param>
+        /// <param name="out_written">Out: written by the call.</param>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_format_fixed(long bits, uint decimals, uint mode, byte* out_buf, uint len, uint* out_written);
+
+        /// <summary>Check whether the engine is poisoned (a previous panic left it in an inconsistent state). Returns <c>false</c> if the engine pointer is null.</summary>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        [return: MarshalAs(UnmanagedType.U1)]
+        public static extern bool factorial_is_poisoned(FactorialEngine* engine);
+
+        /// <summary>Clear the poisoned flag on an engine, allowing it to be used again.</summary>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_clear_poison(FactorialEngine* engine);
+
+        /// <summary>Register the logic module with the engine.</summary>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_logic_register(FactorialEngine* engine);
+
+        /// <summary>Create a wire network with the given color. The new network ID is written to <c>out_id</c>.</summary>
+        /// <param name="out_id">Out: written by the call.</param>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_logic_create_network(FactorialEngine* engine, FfiWireColor color, FfiWireNetworkId* out_id);
+
+        /// <summary>Remove a wire network.</summary>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_logic_remove_network(FactorialEngine* engine, FfiWireNetworkId network_id);
+
+        /// <summary>Add a node to a wire network.</summary>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_logic_add_to_network(FactorialEngine* engine, FfiWireNetworkId network_id, FfiNodeId node_id);
+
+        /// <summary>Remove a node from a wire network.</summary>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_logic_remove_from_network(FactorialEngine* engine, FfiWireNetworkId network_id, FfiNodeId node_id);
+
+        /// <summary>Set a constant combinator on a node.</summary>
+        /// <param name="item_ids_ptr">Buffer of <c>count</c> elements read by the call.</param>
+        /// <param name="values_ptr">Buffer of <c>count</c> elements read by the call.</param>
+        /// <param name="count">Element count of <c>item_ids_ptr</c>.</param>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_logic_set_constant(FactorialEngine* engine, FfiNodeId node_id, uint* item_ids_ptr, long* values_ptr, uint count, byte enabled);
+
+        /// <summary>Set an arithmetic combinator on a node.</summary>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_logic_set_arithmetic(FactorialEngine* engine, FfiNodeId node_id, FfiSelectorKind left_kind, ulong left_value, FfiArithmeticOp op, FfiSelectorKind right_kind, ulong right_value, uint output_item);
+
+        /// <summary>Set a decider combinator on a node.</summary>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_logic_set_decider(FactorialEngine* engine, FfiNodeId node_id, FfiSelectorKind left_kind, ulong left_value, FfiComparisonOp cmp_op, FfiSelectorKind right_kind, ulong right_value, FfiDeciderOutputKind output_kind, uint output_item);
+
+        /// <summary>Set circuit control on a node.</summary>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_logic_set_circuit_control(FactorialEngine* engine, FfiNodeId node_id, FfiSelectorKind left_kind, ulong left_value, FfiComparisonOp cmp_op, FfiSelectorKind right_kind, ulong right_value, FfiWireColor wire_color);
+
+        /// <summary>Query whether a node's circuit control condition is active.</summary>
+        /// <param name="out_active">Out: written by the call.</param>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_logic_is_active(FactorialEngine* engine, FfiNodeId node_id, byte* out_active);
+
+        /// <summary>Query a signal value from a wire network.</summary>
+        /// <param name="out_value">Out: written by the call.</param>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_logic_get_network_signal(FactorialEngine* engine, FfiWireNetworkId network_id, uint item_id, long* out_value);
+
+        /// <summary>List the signals whose value on some node's wire changed during the most recent step, ordered by (node, wire, signal, network).</summary>
+        /// <param name="out_buffer">Out: engine-owned; valid until the next call on the same engine.</param>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_logic_get_changed_signals(FactorialEngine* engine, FfiSignalChangeBuffer* out_buffer);
+
+        /// <summary>Remove all logic state for a node (constant, combinators, circuit control, and network memberships).</summary>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_logic_remove_node(FactorialEngine* engine, FfiNodeId node_id);
+
+        /// <summary>Set an inventory reader on a node. The reader watches <c>target_node_id</c> and emits signals based on its inventory contents.</summary>
+        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        public static extern FactorialResult factorial_logic_set_inventory_reader(FactorialEngine* engine, FfiNodeId node_id, FfiNodeId target_node_id, byte source);
+    }
+}
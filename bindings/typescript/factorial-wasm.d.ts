@@ -0,0 +1,281 @@
+// Generated by factorial-bindgen from crates/factorial-wasm. Do not edit;
+// run `cargo run -p factorial-bindgen` after changing the WASM API.
+
+/** Size in bytes of one entry written by `factorial_logic_get_changed_signals`. */
+export declare const SIGNAL_CHANGE_SIZE: 36;
+/** Size in bytes of one packed record written by `factorial_get_inventory_items_page`. */
+export declare const INVENTORY_ITEM_RECORD_SIZE: 8;
+/** Size in bytes of one packed record written by `factorial_get_transport_items`. */
+export declare const TRANSPORT_ITEM_RECORD_SIZE: 24;
+/** Success. */
+export declare const RESULT_OK: 0;
+/** The engine handle is invalid. */
+export declare const RESULT_INVALID_HANDLE: 1;
+/** Serialization failed. */
+export declare const RESULT_SERIALIZE_ERROR: 2;
+/** Deserialization failed. */
+export declare const RESULT_DESERIALIZE_ERROR: 3;
+/** The requested node was not found. */
+export declare const RESULT_NODE_NOT_FOUND: 4;
+/** The requested edge was not found. */
+export declare const RESULT_EDGE_NOT_FOUND: 5;
+/** An internal error occurred. */
+export declare const RESULT_INTERNAL_ERROR: 6;
+/** Memory allocation failed. */
+export declare const RESULT_ALLOC_ERROR: 7;
+/** An index passed to an accessor is past the end of its list. */
+export declare const RESULT_INDEX_OUT_OF_RANGE: 8;
+/** An argument is outside the values the export accepts. */
+export declare const RESULT_INVALID_ARGUMENT: 9;
+/** Maximum number of simultaneous engine instances. */
+export declare const MAX_ENGINES: 16;
+/** Default maximum number of events held in the poll cache per step. */
+export declare const DEFAULT_EVENT_CACHE_LIMIT: 262144;
+
+/** Byte size, alignment and field offsets of a `repr(C)` struct in linear memory. */
+export interface StructLayout<Field extends string> {
+  readonly size: number;
+  readonly align: number;
+  readonly offsets: { readonly [F in Field]: number };
+}
+
+/** Flat, `repr(C)` event representation for WASM consumers. */
+export declare const FLAT_EVENT_LAYOUT: StructLayout<"kind" | "tick" | "node" | "edge" | "item_type" | "quantity" | "building_type" | "from_node" | "to_node">;
+
+/** The functions exported by the WASM module. */
+export interface FactorialWasmExports {
+  readonly memory: WebAssembly.Memory;
+  /** Create a new engine with `Tick` simulation strategy. */
+  factorial_create(): number;
+  /** Create a new engine with `Delta` simulation strategy. */
+  factorial_create_delta(fixed_timestep: bigint): number;
+  /** Destroy the engine at `handle` and free its slot. */
+  factorial_destroy(handle: number): number;
+  /** Run a single simulation step on the engine at `handle`. */
+  factorial_step(handle: number): number;
+  /** Advance the simulation by `dt` ticks. */
+  factorial_advance(handle: number, dt: bigint): number;
+  /**
+   * Poll events captured during the most recent `factorial_step` or `factorial_advance` call. Copies `FlatEvent` structs into the caller-provided buffer at `out_ptr` (capacity `out_len` bytes).
+   * @param out_ptr Buffer with room for `out_len` elements, written by the call.
+   * @param out_len Element count of `out_ptr`.
+   * @param out_count_ptr Out: written by the call.
+   */
+  factorial_poll_events(handle: number, out_ptr: number, out_len: number, out_count_ptr: number): number;
+  /** Set the maximum number of events cached for `factorial_poll_events` per step. Events past the limit are counted by `factorial_dropped_event_count` instead of being cached. The cap only affects observation, never the simulation. */
+  factorial_set_event_cache_limit(handle: number, max_events: number): number;
+  /**
+   * Write the number of events dropped by the event cache limit since the last step to `*out_count_ptr`. A non-zero count means the events from `factorial_poll_events` are incomplete.
+   * @param out_count_ptr Out: written by the call.
+   */
+  factorial_dropped_event_count(handle: number, out_count_ptr: number): number;
+  /**
+   * Queue a new node with the given building type. Writes the pending ID to `*out_pending_ptr`. The node is not yet live -- call `factorial_apply_mutations` to materialise it.
+   * @param out_pending_ptr Out: written by the call.
+   */
+  factorial_add_node(handle: number, building_type: number, out_pending_ptr: number): number;
+  /** Queue a node for removal. */
+  factorial_remove_node(handle: number, node_id: bigint): number;
+  /**
+   * Queue an edge connecting `from_node` to `to_node`. Writes the pending edge ID to `*out_pending_ptr`.
+   * @param out_pending_ptr Out: written by the call.
+   */
+  factorial_connect(handle: number, from_node: bigint, to_node: bigint, out_pending_ptr: number): number;
+  /** Queue an edge for removal. */
+  factorial_disconnect(handle: number, edge_id: bigint): number;
+  /**
+   * Apply all queued graph mutations and write the results into a flat byte buffer at `out_ptr` (capacity `out_len` bytes).
+   * @param out_ptr Buffer with room for `out_len` elements, written by the call.
+   * @param out_len Element count of `out_ptr`.
+   * @param out_written_ptr Out: written by the call.
+   */
+  factorial_apply_mutations(handle: number, out_ptr: number, out_len: number, out_written_ptr: number): number;
+  /** Apply all queued graph mutations and keep the results in the engine slot for the `factorial_mutation_*` accessors, which stay valid until the next apply or `crate::engine::factorial_destroy`. */
+  factorial_apply_mutations_indexed(handle: number): number;
+  /** Number of nodes added by the last applied batch, or `-1` if the handle is invalid. */
+  factorial_mutation_added_node_count(handle: number): number;
+  /** Number of edges added by the last applied batch, or `-1` if the handle is invalid. */
+  factorial_mutation_added_edge_count(handle: number): number;
+  /**
+   * Write the pending id of the `index`th added node as low and high 32-bit halves. Returns `RESULT_INDEX_OUT_OF_RANGE` past the end of the list.
+   * @param out_lo Out: written by the call.
+   * @param out_hi Out: written by the call.
+   */
+  factorial_mutation_added_node_pending(handle: number, index: number, out_lo: number, out_hi: number): number;
+  /**
+   * Write the real id of the `index`th added node as low and high 32-bit halves. Returns `RESULT_INDEX_OUT_OF_RANGE` past the end of the list.
+   * @param out_lo Out: written by the call.
+   * @param out_hi Out: written by the call.
+   */
+  factorial_mutation_added_node_real(handle: number, index: number, out_lo: number, out_hi: number): number;
+  /**
+   * Write the real id of the `index`th added node as a decimal string (no terminator). `*out_written_ptr` receives the length needed; if `out_len` is too small nothing else is written and `crate::RESULT_INTERNAL_ERROR` is returned.
+   * @param out_ptr Buffer with room for `out_len` elements, written by the call.
+   * @param out_len Element count of `out_ptr`.
+   * @param out_written_ptr Out: written by the call.
+   */
+  factorial_mutation_added_node_real_str(handle: number, index: number, out_ptr: number, out_len: number, out_written_ptr: number): number;
+  /**
+   * Write the pending id of the `index`th added edge as low and high 32-bit halves. Returns `RESULT_INDEX_OUT_OF_RANGE` past the end of the list.
+   * @param out_lo Out: written by the call.
+   * @param out_hi Out: written by the call.
+   */
+  factorial_mutation_added_edge_pending(handle: number, index: number, out_lo: number, out_hi: number): number;
+  /**
+   * Write the real id of the `index`th added edge as low and high 32-bit halves. Returns `RESULT_INDEX_OUT_OF_RANGE` past the end of the list.
+   * @param out_lo Out: written by the call.
+   * @param out_hi Out: written by the call.
+   */
+  factorial_mutation_added_edge_real(handle: number, index: number, out_lo: number, out_hi: number): number;
+  /**
+   * Write the real id of the `index`th added edge as a decimal string; see `factorial_mutation_added_node_real_str`.
+   * @param out_ptr Buffer with room for `out_len` elements, written by the call.
+   * @param out_len Element count of `out_ptr`.
+   * @param out_written_ptr Out: written by the call.
+   */
+  factorial_mutation_added_edge_real_str(handle: number, index: number, out_ptr: number, out_len: number, out_written_ptr: number): number;
+  /** Register the logic module on the engine at `handle`. */
+  factorial_logic_register(handle: number): number;
+  /**
+   * Create a new wire network of the given color.
+   * @param out_id_ptr Out: written by the call.
+   */
+  factorial_logic_create_network(handle: number, color: number, out_id_ptr: number): number;
+  /** Remove a wire network by ID. */
+  factorial_logic_remove_network(handle: number, network_id: number): number;
+  /** Add a node to a wire network. */
+  factorial_logic_add_to_network(handle: number, network_id: number, node_id: bigint): number;
+  /** Remove a node from a wire network. */
+  factorial_logic_remove_from_network(handle: number, network_id: number, node_id: bigint): number;
+  /**
+   * Configure a constant combinator on a node.
+   * @param item_ids_ptr Buffer of `count` elements read by the call.
+   * @param values_ptr Buffer of `count` elements read by the call.
+   * @param count Element count of `item_ids_ptr`.
+   */
+  factorial_logic_set_constant(handle: number, node_id: bigint, item_ids_ptr: number, values_ptr: number, count: number, enabled: number): number;
+  /** Configure an arithmetic combinator on a node. */
+  factorial_logic_set_arithmetic(handle: number, node_id: bigint, left_kind: number, left_value: bigint, op: number, right_kind: number, right_value: bigint, output_item: number): number;
+  /** Configure a decider combinator on a node. */
+  factorial_logic_set_decider(handle: number, node_id: bigint, left_kind: number, left_value: bigint, cmp_op: number, right_kind: number, right_value: bigint, output_kind: number, output_item: number): number;
+  /** Set a circuit control condition on a node. */
+  factorial_logic_set_circuit_control(handle: number, node_id: bigint, left_kind: number, left_value: bigint, cmp_op: number, right_kind: number, right_value: bigint, wire_color: number): number;
+  /**
+   * Query whether a node's circuit control is active.
+   * @param out_active_ptr Out: written by the call.
+   */
+  factorial_logic_is_active(handle: number, node_id: bigint, out_active_ptr: number): number;
+  /**
+   * Get the signal value for a specific item on a wire network.
+   * @param out_value_ptr Out: written by the call.
+   */
+  factorial_logic_get_network_signal(handle: number, network_id: number, item_id: number, out_value_ptr: number): number;
+  /**
+   * Write the signals whose value on some node's wire changed during the most recent step into a flat byte buffer at `out_ptr` (capacity `out_len` bytes), ordered by (node, wire, signal, network).
+   * @param out_ptr Buffer with room for `out_len` elements, written by the call.
+   * @param out_len Element count of `out_ptr`.
+   * @param out_count_ptr Out: written by the call.
+   */
+  factorial_logic_get_changed_signals(handle: number, out_ptr: number, out_len: number, out_count_ptr: number): number;
+  /** Remove all logic state associated with a node. */
+  factorial_logic_remove_node(handle: number, node_id: bigint): number;
+  /** Configure an inventory reader on a node. */
+  factorial_logic_set_inventory_reader(handle: number, node_id: bigint, target_node_id: bigint, source: number): number;
+  /** Configure a node as a source processor (mine, extractor, well). */
+  factorial_set_source(handle: number, node_id: bigint, item_type: number, rate: bigint): number;
+  /**
+   * Configure a node with a fixed recipe processor (assembler, smelter).
+   * @param recipe_ptr Buffer of `recipe_len` elements read by the call.
+   * @param recipe_len Element count of `recipe_ptr`.
+   */
+  factorial_set_fixed_processor(handle: number, node_id: bigint, recipe_ptr: number, recipe_len: number): number;
+  /**
+   * Write the current node count to `*out_count`.
+   * @param out_count Out: written by the call.
+   */
+  factorial_node_count(handle: number, out_count: number): number;
+  /**
+   * Write the current edge count to `*out_count`.
+   * @param out_count Out: written by the call.
+   */
+  factorial_edge_count(handle: number, out_count: number): number;
+  /**
+   * Write the current tick number to `*out_tick`.
+   * @param out_tick Out: written by the call.
+   */
+  factorial_get_tick(handle: number, out_tick: number): number;
+  /**
+   * Write the most recent state hash to `*out_hash`.
+   * @param out_hash Out: written by the call.
+   */
+  factorial_get_state_hash(handle: number, out_hash: number): number;
+  /**
+   * Query the processor state for `node_id`.
+   * @param out_state Out: written by the call.
+   * @param out_progress Out: written by the call.
+   */
+  factorial_get_processor_state(handle: number, node_id: bigint, out_state: number, out_progress: number): number;
+  /**
+   * Write the total input inventory item count for `node_id` to `*out_count`.
+   * @param out_count Out: written by the call.
+   */
+  factorial_get_input_inventory_count(handle: number, node_id: bigint, out_count: number): number;
+  /**
+   * Write the total output inventory item count for `node_id` to `*out_count`.
+   * @param out_count Out: written by the call.
+   */
+  factorial_get_output_inventory_count(handle: number, node_id: bigint, out_count: number): number;
+  /** Number of distinct item types held in one side of a node's inventory (`which`: 0 = input, 1 = output). */
+  factorial_get_inventory_item_count(handle: number, node_id: bigint, which: number): number;
+  /**
+   * Write up to `max` per-item records from one side of a node's inventory into `out_ptr`, starting at the `start_index`-th distinct item type.
+   * @param out_ptr Buffer with room for `max` elements, written by the call.
+   * @param max Element count of `out_ptr`.
+   */
+  factorial_get_inventory_items_page(handle: number, node_id: bigint, which: number, start_index: number, out_ptr: number, max: number): number;
+  /**
+   * Write the items currently on a belt edge into `out_ptr` as a packed little-endian buffer, for animating items with stable identities.
+   * @param out_ptr Buffer with room for `out_len` elements, written by the call.
+   * @param out_len Element count of `out_ptr`.
+   * @param out_written_ptr Out: written by the call.
+   */
+  factorial_get_transport_items(handle: number, edge_id: bigint, out_ptr: number, out_len: number, out_written_ptr: number): number;
+  /**
+   * Serialize engine state into the caller-provided buffer at `out_ptr` (capacity `out_len` bytes). Writes the actual byte count to `*out_written_ptr`.
+   * @param out_ptr Buffer with room for `out_len` elements, written by the call.
+   * @param out_len Element count of `out_ptr`.
+   * @param out_written_ptr Out: written by the call.
+   */
+  factorial_serialize(handle: number, out_ptr: number, out_len: number, out_written_ptr: number): number;
+  /**
+   * Deserialize engine state from the byte buffer at `data_ptr` (length `data_len` bytes).
+   * @param data_ptr Buffer of `data_len` elements read by the call.
+   * @param data_len Element count of `data_ptr`.
+   */
+  factorial_deserialize(data_ptr: number, data_len: number): number;
+  /** Set the transport on `edge_id` to a continuous flow transport. */
+  factorial_set_flow_transport(handle: number, edge_id: bigint, rate: bigint): number;
+  /** Set the transport on `edge_id` to a discrete item (belt) transport. */
+  factorial_set_item_transport(handle: number, edge_id: bigint, speed: bigint, slot_count: number, lanes: number): number;
+  /** Set the transport on `edge_id` to a batch transport. */
+  factorial_set_batch_transport(handle: number, edge_id: bigint, batch_size: number, cycle_time: number): number;
+  /** Set the transport on `edge_id` to a vehicle transport. */
+  factorial_set_vehicle_transport(handle: number, edge_id: bigint, capacity: number, travel_time: number): number;
+  /** Set the input inventory capacity for `node_id`. */
+  factorial_set_input_capacity(handle: number, node_id: bigint, capacity: number): number;
+  /** Set the output inventory capacity for `node_id`. */
+  factorial_set_output_capacity(handle: number, node_id: bigint, capacity: number): number;
+  /** Allocate `size` bytes aligned to `align` in the WASM linear memory. Returns a pointer to the allocated region, or null on failure. */
+  factorial_alloc(size: number, align: number): number;
+  /** Free a region previously allocated by `factorial_alloc`. */
+  factorial_free(ptr: number, size: number, align: number): void;
+}
+
+/**
+ * Instantiate the WASM module from its bytes or a `fetch` response and
+ * check that it exports every function declared above.
+ */
+export declare function loadFactorial(
+  source: BufferSource | Response | PromiseLike<Response>,
+  imports?: WebAssembly.Imports,
+): Promise<FactorialWasmExports>;
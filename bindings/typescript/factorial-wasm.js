@@ -0,0 +1,109 @@
+// Generated by factorial-bindgen from crates/factorial-wasm. Do not edit;
+// run `cargo run -p factorial-bindgen` after changing the WASM API.
+
+export const SIGNAL_CHANGE_SIZE = 36;
+export const INVENTORY_ITEM_RECORD_SIZE = 8;
+export const TRANSPORT_ITEM_RECORD_SIZE = 24;
+export const RESULT_OK = 0;
+export const RESULT_INVALID_HANDLE = 1;
+export const RESULT_SERIALIZE_ERROR = 2;
+export const RESULT_DESERIALIZE_ERROR = 3;
+export const RESULT_NODE_NOT_FOUND = 4;
+export const RESULT_EDGE_NOT_FOUND = 5;
+export const RESULT_INTERNAL_ERROR = 6;
+export const RESULT_ALLOC_ERROR = 7;
+export const RESULT_INDEX_OUT_OF_RANGE = 8;
+export const RESULT_INVALID_ARGUMENT = 9;
+export const MAX_ENGINES = 16;
+export const DEFAULT_EVENT_CACHE_LIMIT = 262144;
+
+export const FLAT_EVENT_LAYOUT = Object.freeze({
+  size: 64,
+  align: 8,
+  offsets: Object.freeze({
+    kind: 0,
+    tick: 8,
+    node: 16,
+    edge: 24,
+    item_type: 32,
+    quantity: 36,
+    building_type: 40,
+    from_node: 48,
+    to_node: 56,
+  }),
+});
+
+const FUNCTIONS = [
+  "factorial_create",
+  "factorial_create_delta",
+  "factorial_destroy",
+  "factorial_step",
+  "factorial_advance",
+  "factorial_poll_events",
+  "factorial_set_event_cache_limit",
+  "factorial_dropped_event_count",
+  "factorial_add_node",
+  "factorial_remove_node",
+  "factorial_connect",
+  "factorial_disconnect",
+  "factorial_apply_mutations",
+  "factorial_apply_mutations_indexed",
+  "factorial_mutation_added_node_count",
+  "factorial_mutation_added_edge_count",
+  "factorial_mutation_added_node_pending",
+  "factorial_mutation_added_node_real",
+  "factorial_mutation_added_node_real_str",
+  "factorial_mutation_added_edge_pending",
+  "factorial_mutation_added_edge_real",
+  "factorial_mutation_added_edge_real_str",
+  "factorial_logic_register",
+  "factorial_logic_create_network",
+  "factorial_logic_remove_network",
+  "factorial_logic_add_to_network",
+  "factorial_logic_remove_from_network",
+  "factorial_logic_set_constant",
+  "factorial_logic_set_arithmetic",
+  "factorial_logic_set_decider",
+  "factorial_logic_set_circuit_control",
+  "factorial_logic_is_active",
+  "factorial_logic_get_network_signal",
+  "factorial_logic_get_changed_signals",
+  "factorial_logic_remove_node",
+  "factorial_logic_set_inventory_reader",
+  "factorial_set_source",
+  "factorial_set_fixed_processor",
+  "factorial_node_count",
+  "factorial_edge_count",
+  "factorial_get_tick",
+  "factorial_get_state_hash",
+  "factorial_get_processor_state",
+  "factorial_get_input_inventory_count",
+  "factorial_get_output_inventory_count",
+  "factorial_get_inventory_item_count",
+  "factorial_get_inventory_items_page",
+  "factorial_get_transport_items",
+  "factorial_serialize",
+  "factorial_deserialize",
+  "factorial_set_flow_transport",
+  "factorial_set_item_transport",
+  "factorial_set_batch_transport",
+  "factorial_set_vehicle_transport",
+  "factorial_set_input_capacity",
+  "factorial_set_output_capacity",
+  "factorial_alloc",
+  "factorial_free",
+];
+
+export async function loadFactorial(source, imports = {}) {
+  const input = await source;
+  const result =
+    typeof Response !== "undefined" && input instanceof Response
+      ? await WebAssembly.instantiateStreaming(input, imports)
+      : await WebAssembly.instantiate(input, imports);
+  const exports = result.instance.exports;
+  const missing = FUNCTIONS.filter((name) => typeof exports[name] !== "function");
+  if (missing.length > 0) {
+    throw new Error(`the WASM module is missing exports: ${missing.join(", ")}`);
+  }
+  return exports;
+}
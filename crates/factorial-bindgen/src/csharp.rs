@@ -0,0 +1,391 @@
+//! C# bindings for the C API: enums, struct layouts and `DllImport`
+//! declarations in one file.
+//!
+//! Signatures keep the C shapes -- pointers stay pointers -- so the file is
+//! `unsafe` code that a host wraps as it sees fit. Type aliases such as
+//! `FfiNodeId` become file-level `using` aliases, `bool` is marshalled as one
+//! byte, callbacks become `UnmanagedFunctionPointer` delegates, and each
+//! pointer parameter's [`Role`](crate::model::Role) is
+//! written into its `<param>` doc.
+
+use std::fmt::Write;
+
+use crate::model::{Api, Param, Prim, Ty};
+
+/// Namespace of the generated types.
+pub const NAMESPACE: &str = "Factorial.Native";
+/// Class holding the `DllImport` declarations and constants.
+pub const CLASS: &str = "FactorialNative";
+
+/// Render the C# file for the API of `library`.
+pub fn generate(api: &Api, library: &str, source: &str) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "// <auto-generated>");
+    let _ = writeln!(
+        out,
+        "//     Generated by factorial-bindgen from {source}. Do not edit;"
+    );
+    let _ = writeln!(
+        out,
+        "//     run `cargo run -p factorial-bindgen` after changing the C API."
+    );
+    let _ = writeln!(out, "// </auto-generated>");
+    out.push('\n');
+    out.push_str("using System.Runtime.InteropServices;\n\n");
+    for alias in &api.aliases {
+        let _ = writeln!(out, "using {} = {};", alias.name, system_type(alias.target));
+    }
+    if !api.aliases.is_empty() {
+        out.push('\n');
+    }
+    let _ = writeln!(out, "namespace {NAMESPACE}\n{{");
+
+    let mut items = Vec::new();
+    for item in &api.enums {
+        let mut text = String::new();
+        doc(&mut text, 1, &item.doc);
+        let _ = writeln!(text, "    public enum {} : int\n    {{", item.name);
+        for variant in &item.variants {
+            doc(&mut text, 2, &variant.doc);
+            let _ = writeln!(text, "        {} = {},", variant.name, variant.value);
+        }
+        text.push_str("    }\n");
+        items.push(text);
+    }
+    for item in &api.structs {
+        let mut text = String::new();
+        doc(&mut text, 1, &item.doc);
+        let has_pointer = item.fields.iter().any(|f| matches!(f.ty, Ty::Ptr { .. }));
+        let _ = writeln!(text, "    [StructLayout(LayoutKind.Sequential)]");
+        let _ = writeln!(
+            text,
+            "    public {}struct {}\n    {{",
+            if has_pointer { "unsafe " } else { "" },
+            item.name
+        );
+        for field in &item.fields {
+            doc(&mut text, 2, &field.doc);
+            if field.ty == Ty::Prim(Prim::Bool) {
+                text.push_str("        [MarshalAs(UnmanagedType.U1)]\n");
+            }
+            let _ = writeln!(
+                text,
+                "        public {} {};",
+                type_name(&field.ty),
+                ident(&field.name)
+            );
+        }
+        text.push_str("    }\n");
+        items.push(text);
+    }
+    for item in &api.callbacks {
+        let mut text = String::new();
+        doc(&mut text, 1, &item.doc);
+        let _ = writeln!(
+            text,
+            "    [UnmanagedFunctionPointer(CallingConvention.Cdecl)]"
+        );
+        let ret = match &item.ret {
+            None => "void".to_string(),
+            Some(ty) => type_name(ty),
+        };
+        let _ = writeln!(
+            text,
+            "    public unsafe delegate {ret} {}({});",
+            item.name,
+            params(&item.params)
+        );
+        items.push(text);
+    }
+    for item in &api.opaque {
+        let mut text = String::new();
+        let summary = if item.doc.is_empty() {
+            "Opaque; only handled through pointers.".to_string()
+        } else {
+            format!("{} Opaque; only handled through pointers.", item.doc)
+        };
+        doc(&mut text, 1, &summary);
+        let _ = writeln!(text, "    public struct {}\n    {{\n    }}", item.name);
+        items.push(text);
+    }
+    items.push(native_class(api, library));
+    out.push_str(&items.join("\n"));
+    out.push_str("}\n");
+    out
+}
+
+fn native_class(api: &Api, library: &str) -> String {
+    let mut text = String::new();
+    doc(
+        &mut text,
+        1,
+        &format!("Entry points and constants of the `{library}` library."),
+    );
+    let _ = writeln!(text, "    public static unsafe class {CLASS}\n    {{");
+    doc(&mut text, 2, "Name of the native library.");
+    let _ = writeln!(
+        text,
+        "        public const string LibraryName = \"{library}\";"
+    );
+    for constant in &api.constants {
+        text.push('\n');
+        doc(&mut text, 2, &constant.doc);
+        let _ = writeln!(
+            text,
+            "        public const {} {} = {};",
+            type_name(&Ty::Prim(constant.ty)),
+            constant.name,
+            constant.value
+        );
+    }
+    for function in &api.functions {
+        text.push('\n');
+        doc(&mut text, 2, &function.doc);
+        for param in &function.params {
+            if let Some(role) = param.role.describe() {
+                let _ = writeln!(
+                    text,
+                    "        /// <param name=\"{}\">{}</param>",
+                    param.name,
+                    xml(&role)
+                );
+            }
+        }
+        let _ = writeln!(
+            text,
+            "        [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]"
+        );
+        let ret = match &function.ret {
+            None => "void".to_string(),
+            Some(Ty::Prim(Prim::Bool)) => {
+                text.push_str("        [return: MarshalAs(UnmanagedType.U1)]\n");
+                "bool".to_string()
+            }
+            Some(ty) => type_name(ty),
+        };
+        let _ = writeln!(
+            text,
+            "        public static extern {ret} {}({});",
+            function.name,
+            params(&function.params)
+        );
+    }
+    text.push_str("    }\n");
+    text
+}
+
+fn params(params: &[Param]) -> String {
+    params
+        .iter()
+        .map(|param| {
+            let marshal = if param.ty == Ty::Prim(Prim::Bool) {
+                "[MarshalAs(UnmanagedType.U1)] "
+            } else {
+                ""
+            };
+            format!("{marshal}{} {}", type_name(&param.ty), ident(&param.name))
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn type_name(ty: &Ty) -> String {
+    match ty {
+        Ty::Prim(prim) => match prim {
+            Prim::Bool => "bool",
+            Prim::U8 => "byte",
+            Prim::I8 => "sbyte",
+            Prim::U16 => "ushort",
+            Prim::I16 => "short",
+            Prim::U32 => "uint",
+            Prim::I32 => "int",
+            Prim::U64 => "ulong",
+            Prim::I64 => "long",
+            Prim::Usize => "nuint",
+            Prim::Isize => "nint",
+            Prim::F32 => "float",
+            Prim::F64 => "double",
+        }
+        .to_string(),
+        Ty::Named(name) => name.clone(),
+        Ty::Void => "void".to_string(),
+        Ty::Ptr { pointee, .. } => format!("{}*", type_name(pointee)),
+    }
+}
+
+/// The `System` type an alias target names, as `using` aliases require.
+fn system_type(prim: Prim) -> &'static str {
+    match prim {
+        Prim::Bool => "System.Boolean",
+        Prim::U8 => "System.Byte",
+        Prim::I8 => "System.SByte",
+        Prim::U16 => "System.UInt16",
+        Prim::I16 => "System.Int16",
+        Prim::U32 => "System.UInt32",
+        Prim::I32 => "System.Int32",
+        Prim::U64 => "System.UInt64",
+        Prim::I64 => "System.Int64",
+        Prim::Usize => "System.UIntPtr",
+        Prim::Isize => "System.IntPtr",
+        Prim::F32 => "System.Single",
+        Prim::F64 => "System.Double",
+    }
+}
+
+const KEYWORDS: &[&str] = &[
+    "abstract",
+    "as",
+    "base",
+    "bool",
+    "break",
+    "byte",
+    "case",
+    "catch",
+    "char",
+    "checked",
+    "class",
+    "const",
+    "continue",
+    "decimal",
+    "default",
+    "delegate",
+    "do",
+    "double",
+    "else",
+    "enum",
+    "event",
+    "explicit",
+    "extern",
+    "false",
+    "finally",
+    "fixed",
+    "float",
+    "for",
+    "foreach",
+    "goto",
+    "if",
+    "implicit",
+    "in",
+    "int",
+    "interface",
+    "internal",
+    "is",
+    "lock",
+    "long",
+    "namespace",
+    "new",
+    "null",
+    "object",
+    "operator",
+    "out",
+    "override",
+    "params",
+    "private",
+    "protected",
+    "public",
+    "readonly",
+    "ref",
+    "return",
+    "sbyte",
+    "sealed",
+    "short",
+    "sizeof",
+    "stackalloc",
+    "static",
+    "string",
+    "struct",
+    "switch",
+    "this",
+    "throw",
+    "true",
+    "try",
+    "typeof",
+    "uint",
+    "ulong",
+    "unchecked",
+    "unsafe",
+    "ushort",
+    "using",
+    "virtual",
+    "void",
+    "volatile",
+    "while",
+];
+
+fn ident(name: &str) -> String {
+    if KEYWORDS.contains(&name) {
+        format!("@{name}")
+    } else {
+        name.to_string()
+    }
+}
+
+fn doc(out: &mut String, depth: usize, text: &str) {
+    if text.is_empty() {
+        return;
+    }
+    let indent = "    ".repeat(depth);
+    let _ = writeln!(out, "{indent}/// <summary>{}</summary>", xml(text));
+}
+
+/// Escape `text` for an XML doc comment and turn `code` into `<c>code</c>`.
+fn xml(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut in_code = false;
+    for c in text.chars() {
+        match c {
+            '`' => {
+                out.push_str(if in_code { "</c>" } else { "<c>" });
+                in_code = !in_code;
+            }
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            _ => out.push(c),
+        }
+    }
+    if in_code {
+        out.push_str("</c>");
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{Function, Role};
+
+    #[test]
+    fn declarations_keep_the_c_shapes() {
+        let mut api = Api::default();
+        api.functions.push(Function {
+            name: "factorial_get_thing".to_string(),
+            doc: "Read a thing; `out` must be non-null & aligned.".to_string(),
+            params: vec![
+                Param {
+                    name: "flag".to_string(),
+                    ty: Ty::Prim(Prim::Bool),
+                    role: Role::In,
+                },
+                Param {
+                    name: "out".to_string(),
+                    ty: Ty::Ptr {
+                        mutable: true,
+                        pointee: Box::new(Ty::Named("FfiNodeId".to_string())),
+                    },
+                    role: Role::Out { ownership: None },
+                },
+            ],
+            ret: Some(Ty::Named("FactorialResult".to_string())),
+        });
+        let text = generate(&api, "factorial_ffi", "test");
+        assert!(text.contains(
+            "/// <summary>Read a thing; <c>out</c> must be non-null &amp; aligned.</summary>"
+        ));
+        assert!(text.contains("/// <param name=\"out\">Out: written by the call.</param>"));
+        assert!(text.contains(
+            "public static extern FactorialResult factorial_get_thing(\
+             [MarshalAs(UnmanagedType.U1)] bool flag, FfiNodeId* @out);"
+        ));
+    }
+}
@@ -0,0 +1,97 @@
+//! Typed host bindings for the Factorial C and WASM APIs.
+//!
+//! Hosts that hand-write P/Invoke signatures or JavaScript glue for the
+//! exported functions drift from the library as it grows. This crate reads
+//! the `factorial-ffi` and `factorial-wasm` sources with `syn` -- the
+//! `extern "C"` functions, `#[repr(C)]` types, aliases and constants they
+//! already declare -- and generates:
+//!
+//! - [`CSHARP_PATH`]: C# enums, struct layouts and `DllImport` declarations
+//!   for the C API.
+//! - [`TS_DECLARATIONS_PATH`] and [`TS_LOADER_PATH`]: a TypeScript `.d.ts`
+//!   and an ES module loader for the WASM exports, with `wasm32` struct
+//!   layouts.
+//!
+//! Pointer parameters are classified as out-parameters, buffers with their
+//! lengths, or plain inputs, and out-parameters carry who owns what they
+//! receive; see [`meta`] for the rules. The generated files are committed.
+//! Run `cargo run -p factorial-bindgen` after changing either API; the
+//! crate's tests fail while the committed files are out of date.
+
+pub mod csharp;
+pub mod meta;
+pub mod model;
+pub mod parse;
+pub mod typescript;
+
+use std::path::{Path, PathBuf};
+
+/// The C# bindings, relative to the workspace root.
+pub const CSHARP_PATH: &str = "bindings/csharp/Factorial.Native.g.cs";
+/// The TypeScript declarations, relative to the workspace root.
+pub const TS_DECLARATIONS_PATH: &str = "bindings/typescript/factorial-wasm.d.ts";
+/// The JavaScript loader, relative to the workspace root.
+pub const TS_LOADER_PATH: &str = "bindings/typescript/factorial-wasm.js";
+
+/// Errors from reading the API sources.
+#[derive(Debug, thiserror::Error)]
+pub enum BindgenError {
+    #[error("failed to read {}: {source}", path.display())]
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("failed to parse {}: {source}", path.display())]
+    Parse { path: PathBuf, source: syn::Error },
+    #[error("`{item}` uses {what}, which has no C ABI binding")]
+    Unsupported { item: String, what: String },
+}
+
+/// One generated file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GeneratedFile {
+    /// Path relative to the workspace root.
+    pub path: &'static str,
+    pub contents: String,
+}
+
+/// The workspace this crate was built in.
+pub fn workspace_root() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("../..")
+}
+
+/// Generate every binding file from the sources under `workspace`.
+pub fn generate(workspace: &Path) -> Result<Vec<GeneratedFile>, BindgenError> {
+    let mut ffi = parse::parse_crate(&parse::lib_rs(workspace, "factorial-ffi"))?;
+    meta::assign_roles(&mut ffi);
+    let mut wasm = parse::parse_crate(&parse::lib_rs(workspace, "factorial-wasm"))?;
+    meta::assign_roles(&mut wasm);
+
+    Ok(vec![
+        GeneratedFile {
+            path: CSHARP_PATH,
+            contents: csharp::generate(&ffi, "factorial_ffi", "crates/factorial-ffi"),
+        },
+        GeneratedFile {
+            path: TS_DECLARATIONS_PATH,
+            contents: typescript::declarations(&wasm, "crates/factorial-wasm"),
+        },
+        GeneratedFile {
+            path: TS_LOADER_PATH,
+            contents: typescript::loader(&wasm, "crates/factorial-wasm"),
+        },
+    ])
+}
+
+/// The generated files whose committed copy under `workspace` is missing or
+/// differs from what [`generate`] produces.
+pub fn stale_files(workspace: &Path) -> Result<Vec<&'static str>, BindgenError> {
+    Ok(generate(workspace)?
+        .into_iter()
+        .filter(|file| {
+            std::fs::read_to_string(workspace.join(file.path))
+                .map_or(true, |committed| committed != file.contents)
+        })
+        .map(|file| file.path)
+        .collect())
+}
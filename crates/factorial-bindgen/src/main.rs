@@ -0,0 +1,48 @@
+//! Regenerate the committed host bindings.
+//!
+//! `cargo run -p factorial-bindgen` rewrites the files; with `--check` it
+//! only lists the stale ones and exits with status 1 if there are any.
+
+use std::process::ExitCode;
+
+fn main() -> ExitCode {
+    let check = std::env::args().skip(1).any(|arg| arg == "--check");
+    let workspace = factorial_bindgen::workspace_root();
+
+    if check {
+        return match factorial_bindgen::stale_files(&workspace) {
+            Ok(stale) if stale.is_empty() => ExitCode::SUCCESS,
+            Ok(stale) => {
+                for path in stale {
+                    eprintln!("{path} is out of date");
+                }
+                ExitCode::FAILURE
+            }
+            Err(err) => {
+                eprintln!("factorial-bindgen: {err}");
+                ExitCode::FAILURE
+            }
+        };
+    }
+
+    let files = match factorial_bindgen::generate(&workspace) {
+        Ok(files) => files,
+        Err(err) => {
+            eprintln!("factorial-bindgen: {err}");
+            return ExitCode::FAILURE;
+        }
+    };
+    for file in files {
+        let path = workspace.join(file.path);
+        let written = path
+            .parent()
+            .map_or(Ok(()), std::fs::create_dir_all)
+            .and_then(|()| std::fs::write(&path, &file.contents));
+        if let Err(err) = written {
+            eprintln!("factorial-bindgen: failed to write {}: {err}", file.path);
+            return ExitCode::FAILURE;
+        }
+        println!("wrote {}", file.path);
+    }
+    ExitCode::SUCCESS
+}
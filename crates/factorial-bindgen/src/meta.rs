@@ -0,0 +1,225 @@
+//! Parameter roles and result ownership.
+//!
+//! A C signature does not say whether a pointer is read or written, how many
+//! elements it covers, or who frees what it points at. The Factorial C APIs
+//! follow naming conventions that do, and this module reads them so every
+//! generated binding documents the same facts:
+//!
+//! - A pointer paired with an integer length is a buffer. The length is
+//!   named after the pointer -- `items`/`item_count`, `entries`/`entry_count`,
+//!   `recipe_ptr`/`recipe_len` -- or, when the pointer's name is plural,
+//!   `data` or `buf`, or ends in `_ptr`, it is the signature's `count`, `len`
+//!   or `max`.
+//! - Any other pointer whose name starts with `out` is written by the call.
+//! - Buffers sized by something other than a parameter are listed in
+//!   [`IMPLIED_LENGTHS`].
+//! - What an out-parameter receives is caller-owned when the crate exports
+//!   a `*free*` function taking that type by value, and engine-owned when
+//!   the type's documentation says it is.
+
+use crate::model::{Api, Length, Ownership, Param, Role, Ty};
+
+/// Buffers no parameter sizes: function, parameter, and what sizes it.
+pub const IMPLIED_LENGTHS: &[(&str, &str, &str)] = &[(
+    "factorial_pool_step_all",
+    "out_results",
+    "one element per engine in the pool",
+)];
+
+/// Fill in the [`Role`] of every parameter of every function in `api`.
+pub fn assign_roles(api: &mut Api) {
+    let ownership: Vec<(String, Ownership)> = api
+        .structs
+        .iter()
+        .filter_map(|s| Some((s.name.clone(), ownership_of(api, &s.name)?)))
+        .collect();
+
+    for function in &mut api.functions {
+        let params = &function.params;
+        let mut roles: Vec<Role> = vec![Role::In; params.len()];
+        for (i, param) in params.iter().enumerate() {
+            let Ty::Ptr { mutable, .. } = param.ty else {
+                continue;
+            };
+            let implied = IMPLIED_LENGTHS
+                .iter()
+                .find(|(f, p, _)| *f == function.name && *p == param.name);
+            if let Some(&(_, _, text)) = implied {
+                roles[i] = if mutable {
+                    Role::OutBuffer {
+                        len: Length::Implied(text),
+                    }
+                } else {
+                    Role::InBuffer {
+                        len: Length::Implied(text),
+                    }
+                };
+            } else if let Some(len) = length_for(params, i) {
+                roles[i] = if mutable {
+                    Role::OutBuffer {
+                        len: Length::Param(len.clone()),
+                    }
+                } else {
+                    Role::InBuffer {
+                        len: Length::Param(len.clone()),
+                    }
+                };
+                // A length shared by several buffers is recorded against the first.
+                let len_index = params.iter().position(|p| p.name == len);
+                if let Some(j) = len_index
+                    && roles[j] == Role::In
+                {
+                    roles[j] = Role::Len {
+                        of: param.name.clone(),
+                    };
+                }
+            } else if mutable && param.name.starts_with("out") {
+                let owner = match param.ty.pointee() {
+                    Some(Ty::Named(name)) => ownership
+                        .iter()
+                        .find(|(n, _)| n == name)
+                        .map(|(_, o)| o.clone()),
+                    _ => None,
+                };
+                roles[i] = Role::Out { ownership: owner };
+            }
+        }
+        for (param, role) in function.params.iter_mut().zip(roles) {
+            param.role = role;
+        }
+    }
+}
+
+fn ownership_of(api: &Api, name: &str) -> Option<Ownership> {
+    let free = api.functions.iter().find(|f| {
+        f.name.contains("free")
+            && f.params
+                .iter()
+                .any(|p| matches!(&p.ty, Ty::Named(n) if n == name))
+    });
+    if let Some(free) = free {
+        return Some(Ownership::Caller {
+            free: free.name.clone(),
+        });
+    }
+    let item = api.find_struct(name)?;
+    item.doc
+        .to_lowercase()
+        .contains("engine-owned")
+        .then_some(Ownership::Engine)
+}
+
+/// The integer parameter holding the element count of pointer `params[i]`.
+fn length_for(params: &[Param], i: usize) -> Option<String> {
+    let name = params[i].name.as_str();
+    let base = name.strip_suffix("_ptr").unwrap_or(name);
+    let is_length = |p: &&Param| matches!(p.ty, Ty::Prim(prim) if prim.is_integer());
+
+    let named = [base.to_string(), singular(base)]
+        .into_iter()
+        .flat_map(|stem| [format!("{stem}_count"), format!("{stem}_len")]);
+    for candidate in named {
+        if let Some(p) = params
+            .iter()
+            .filter(is_length)
+            .find(|p| p.name == candidate)
+        {
+            return Some(p.name.clone());
+        }
+    }
+
+    let last_word = base.rsplit('_').next().unwrap_or(base);
+    let plural = last_word.ends_with('s') || matches!(last_word, "data" | "buf");
+    if plural || name.ends_with("_ptr") {
+        return params
+            .iter()
+            .filter(is_length)
+            .find(|p| matches!(p.name.as_str(), "count" | "len" | "max"))
+            .map(|p| p.name.clone());
+    }
+    None
+}
+
+fn singular(word: &str) -> String {
+    if let Some(stem) = word.strip_suffix("ies") {
+        format!("{stem}y")
+    } else if let Some(stem) = word.strip_suffix('s') {
+        stem.to_string()
+    } else {
+        word.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{Function, Prim};
+
+    fn param(name: &str, ty: Ty) -> Param {
+        Param {
+            name: name.to_string(),
+            ty,
+            role: Role::In,
+        }
+    }
+
+    fn ptr(mutable: bool, prim: Prim) -> Ty {
+        Ty::Ptr {
+            mutable,
+            pointee: Box::new(Ty::Prim(prim)),
+        }
+    }
+
+    fn roles(params: Vec<Param>) -> Vec<Role> {
+        let mut api = Api::default();
+        api.functions.push(Function {
+            name: "f".to_string(),
+            doc: String::new(),
+            params,
+            ret: None,
+        });
+        assign_roles(&mut api);
+        api.functions[0]
+            .params
+            .iter()
+            .map(|p| p.role.clone())
+            .collect()
+    }
+
+    #[test]
+    fn buffers_pair_with_their_lengths() {
+        let got = roles(vec![
+            param("entries", ptr(false, Prim::U32)),
+            param("entry_count", Ty::Prim(Prim::U32)),
+            param("out_results", ptr(true, Prim::U32)),
+            param("count", Ty::Prim(Prim::U32)),
+        ]);
+        assert_eq!(
+            got,
+            vec![
+                Role::InBuffer {
+                    len: Length::Param("entry_count".to_string())
+                },
+                Role::Len {
+                    of: "entries".to_string()
+                },
+                Role::OutBuffer {
+                    len: Length::Param("count".to_string())
+                },
+                Role::Len {
+                    of: "out_results".to_string()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn singular_pointers_are_not_buffers() {
+        let got = roles(vec![
+            param("recipe", ptr(false, Prim::U32)),
+            param("count", Ty::Prim(Prim::U32)),
+            param("out_value", ptr(true, Prim::U64)),
+        ]);
+        assert_eq!(got, vec![Role::In, Role::In, Role::Out { ownership: None }]);
+    }
+}
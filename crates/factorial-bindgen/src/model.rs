@@ -0,0 +1,257 @@
+//! The language-neutral description of an exported API.
+
+/// A primitive Rust type as it crosses the C ABI.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Prim {
+    Bool,
+    U8,
+    I8,
+    U16,
+    I16,
+    U32,
+    I32,
+    U64,
+    I64,
+    Usize,
+    Isize,
+    F32,
+    F64,
+}
+
+impl Prim {
+    pub fn from_ident(ident: &str) -> Option<Self> {
+        Some(match ident {
+            "bool" => Prim::Bool,
+            "u8" => Prim::U8,
+            "i8" => Prim::I8,
+            "u16" => Prim::U16,
+            "i16" => Prim::I16,
+            "u32" => Prim::U32,
+            "i32" => Prim::I32,
+            "u64" => Prim::U64,
+            "i64" => Prim::I64,
+            "usize" => Prim::Usize,
+            "isize" => Prim::Isize,
+            "f32" => Prim::F32,
+            "f64" => Prim::F64,
+            _ => return None,
+        })
+    }
+
+    pub fn is_integer(self) -> bool {
+        !matches!(self, Prim::Bool | Prim::F32 | Prim::F64)
+    }
+
+    /// The smallest and largest value of an integer type.
+    pub fn bounds(self) -> Option<(i128, i128)> {
+        Some(match self {
+            Prim::U8 => (0, u8::MAX.into()),
+            Prim::I8 => (i8::MIN.into(), i8::MAX.into()),
+            Prim::U16 => (0, u16::MAX.into()),
+            Prim::I16 => (i16::MIN.into(), i16::MAX.into()),
+            Prim::U32 => (0, u32::MAX.into()),
+            Prim::I32 => (i32::MIN.into(), i32::MAX.into()),
+            Prim::U64 | Prim::Usize => (0, u64::MAX.into()),
+            Prim::I64 | Prim::Isize => (i64::MIN.into(), i64::MAX.into()),
+            Prim::Bool | Prim::F32 | Prim::F64 => return None,
+        })
+    }
+}
+
+/// A parameter, return or field type.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Ty {
+    Prim(Prim),
+    /// A struct, enum, type alias, callback or opaque type, by name.
+    Named(String),
+    /// `c_void`; only valid behind a pointer.
+    Void,
+    Ptr {
+        mutable: bool,
+        pointee: Box<Ty>,
+    },
+}
+
+impl Ty {
+    pub fn pointee(&self) -> Option<&Ty> {
+        match self {
+            Ty::Ptr { pointee, .. } => Some(pointee),
+            _ => None,
+        }
+    }
+}
+
+/// Who frees what an out-parameter points at once the call returns.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Ownership {
+    /// The caller owns the result and releases it with the named function.
+    Caller { free: String },
+    /// The engine owns the result; it stays valid until the next call on
+    /// the same engine.
+    Engine,
+}
+
+/// How many elements a buffer parameter covers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Length {
+    /// The value of another parameter.
+    Param(String),
+    /// A count the signature does not carry, described in words.
+    Implied(&'static str),
+}
+
+impl Length {
+    fn describe(&self) -> String {
+        match self {
+            Length::Param(name) => format!("`{name}` elements"),
+            Length::Implied(text) => (*text).to_string(),
+        }
+    }
+}
+
+/// What a parameter is for, beyond its type.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Role {
+    /// Passed by value, or a pointer to a single input.
+    In,
+    /// A pointer the call writes one result through.
+    Out { ownership: Option<Ownership> },
+    /// A pointer to the first of `len` elements the call reads.
+    InBuffer { len: Length },
+    /// A pointer to room for `len` elements the call writes.
+    OutBuffer { len: Length },
+    /// The element count of another parameter.
+    Len { of: String },
+}
+
+impl Role {
+    /// A one-line description for generated docs, with code in backticks.
+    pub fn describe(&self) -> Option<String> {
+        Some(match self {
+            Role::In => return None,
+            Role::Out { ownership: None } => "Out: written by the call.".to_string(),
+            Role::Out {
+                ownership: Some(Ownership::Caller { free }),
+            } => format!("Out: caller-owned; release it with `{free}`."),
+            Role::Out {
+                ownership: Some(Ownership::Engine),
+            } => "Out: engine-owned; valid until the next call on the same engine.".to_string(),
+            Role::InBuffer { len } => format!("Buffer of {} read by the call.", len.describe()),
+            Role::OutBuffer { len } => format!(
+                "Buffer with room for {}, written by the call.",
+                len.describe()
+            ),
+            Role::Len { of } => format!("Element count of `{of}`."),
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Param {
+    pub name: String,
+    pub ty: Ty,
+    pub role: Role,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Function {
+    pub name: String,
+    /// The first paragraph of the doc comment.
+    pub doc: String,
+    pub params: Vec<Param>,
+    pub ret: Option<Ty>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Field {
+    pub name: String,
+    pub doc: String,
+    pub ty: Ty,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Struct {
+    pub name: String,
+    pub doc: String,
+    pub fields: Vec<Field>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Variant {
+    pub name: String,
+    pub doc: String,
+    pub value: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Enum {
+    pub name: String,
+    pub doc: String,
+    pub variants: Vec<Variant>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Alias {
+    pub name: String,
+    pub doc: String,
+    pub target: Prim,
+}
+
+/// A C function pointer type the host implements, such as a logger. Used
+/// as `Option<Callback>` it may be null.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Callback {
+    pub name: String,
+    pub doc: String,
+    pub params: Vec<Param>,
+    pub ret: Option<Ty>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Constant {
+    pub name: String,
+    pub doc: String,
+    pub ty: Prim,
+    pub value: i128,
+}
+
+/// A type only ever handled behind a pointer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Opaque {
+    pub name: String,
+    pub doc: String,
+}
+
+/// Everything one crate exports over the C ABI, in source order.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Api {
+    pub functions: Vec<Function>,
+    pub structs: Vec<Struct>,
+    pub enums: Vec<Enum>,
+    pub aliases: Vec<Alias>,
+    pub callbacks: Vec<Callback>,
+    pub constants: Vec<Constant>,
+    pub opaque: Vec<Opaque>,
+}
+
+impl Api {
+    pub fn find_struct(&self, name: &str) -> Option<&Struct> {
+        self.structs.iter().find(|s| s.name == name)
+    }
+
+    pub fn find_enum(&self, name: &str) -> Option<&Enum> {
+        self.enums.iter().find(|e| e.name == name)
+    }
+
+    pub fn find_alias(&self, name: &str) -> Option<&Alias> {
+        self.aliases.iter().find(|a| a.name == name)
+    }
+
+    pub fn find_callback(&self, name: &str) -> Option<&Callback> {
+        self.callbacks.iter().find(|c| c.name == name)
+    }
+
+    pub fn is_opaque(&self, name: &str) -> bool {
+        self.opaque.iter().any(|o| o.name == name)
+    }
+}
@@ -0,0 +1,511 @@
+//! Reading a crate's C ABI surface from its source with `syn`.
+//!
+//! Starting at `src/lib.rs`, every file module that is not `#[cfg(test)]`
+//! is read in declaration order. The API is made of:
+//!
+//! - `pub extern "C"` functions marked `#[no_mangle]`,
+//! - `#[repr(C)]` structs and enums (structs with private fields are
+//!   opaque),
+//! - `pub type` aliases of primitive types,
+//! - `pub type` aliases of `extern "C"` function pointers (callbacks), taken
+//!   as `Option<Callback>` where null is allowed,
+//! - `pub const` integers with a literal or `MIN`/`MAX` value.
+//!
+//! Named types that only appear behind pointers and are not declared in the
+//! crate (such as the core `Engine`) are opaque.
+
+use std::path::{Path, PathBuf};
+
+use syn::{
+    Attribute, Expr, Fields, FnArg, GenericArgument, Item, Lit, Pat, PathArguments, ReturnType,
+    Type, UnOp, Visibility,
+};
+
+use crate::BindgenError;
+use crate::model::{
+    Alias, Api, Callback, Constant, Enum, Field, Function, Opaque, Param, Prim, Role, Struct, Ty,
+    Variant,
+};
+
+/// Parse the crate whose `lib.rs` is at `lib_rs`.
+pub fn parse_crate(lib_rs: &Path) -> Result<Api, BindgenError> {
+    let mut api = Api::default();
+    parse_file(lib_rs, lib_rs.parent().unwrap_or(Path::new(".")), &mut api)?;
+    add_undeclared_opaque(&mut api)?;
+    Ok(api)
+}
+
+fn parse_file(path: &Path, mod_dir: &Path, api: &mut Api) -> Result<(), BindgenError> {
+    let source = std::fs::read_to_string(path).map_err(|source| BindgenError::Io {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    let file = syn::parse_file(&source).map_err(|source| BindgenError::Parse {
+        path: path.to_path_buf(),
+        source,
+    })?;
+
+    for item in &file.items {
+        match item {
+            Item::Mod(module) if module.content.is_none() && !is_cfg_test(&module.attrs) => {
+                let name = module.ident.to_string();
+                let file = mod_dir.join(format!("{name}.rs"));
+                let (file, dir) = if file.exists() {
+                    (file, mod_dir.join(&name))
+                } else {
+                    (mod_dir.join(&name).join("mod.rs"), mod_dir.join(&name))
+                };
+                parse_file(&file, &dir, api)?;
+            }
+            Item::Fn(function) if is_exported_fn(function) => {
+                api.functions.push(parse_fn(function)?);
+            }
+            Item::Struct(item) if is_pub(&item.vis) && has_repr_c(&item.attrs) => {
+                let name = item.ident.to_string();
+                let doc = doc_summary(&item.attrs);
+                let public = match &item.fields {
+                    Fields::Named(fields) => fields.named.iter().all(|f| is_pub(&f.vis)),
+                    _ => false,
+                };
+                if !public {
+                    api.opaque.push(Opaque { name, doc });
+                    continue;
+                }
+                let mut fields = Vec::new();
+                for field in &item.fields {
+                    let field_name = field.ident.as_ref().map(|i| i.to_string());
+                    fields.push(Field {
+                        name: field_name.unwrap_or_default(),
+                        doc: doc_summary(&field.attrs),
+                        ty: convert_type(&field.ty, &name)?,
+                    });
+                }
+                api.structs.push(Struct { name, doc, fields });
+            }
+            Item::Enum(item) if is_pub(&item.vis) && has_repr_c(&item.attrs) => {
+                let name = item.ident.to_string();
+                let mut next = 0i64;
+                let mut variants = Vec::new();
+                for variant in &item.variants {
+                    if let Some((_, expr)) = &variant.discriminant {
+                        next = eval_int(expr)
+                            .and_then(|v| i64::try_from(v).ok())
+                            .ok_or_else(|| BindgenError::Unsupported {
+                                item: format!("{name}::{}", variant.ident),
+                                what: "a non-literal discriminant".to_string(),
+                            })?;
+                    }
+                    variants.push(Variant {
+                        name: variant.ident.to_string(),
+                        doc: doc_summary(&variant.attrs),
+                        value: next,
+                    });
+                    next += 1;
+                }
+                api.enums.push(Enum {
+                    name,
+                    doc: doc_summary(&item.attrs),
+                    variants,
+                });
+            }
+            Item::Type(item) if is_pub(&item.vis) => {
+                if let Type::BareFn(function) = &*item.ty {
+                    api.callbacks.push(parse_callback(
+                        function,
+                        item.ident.to_string(),
+                        &item.attrs,
+                    )?);
+                } else if let Ok(Ty::Prim(target)) = convert_type(&item.ty, &item.ident.to_string())
+                {
+                    api.aliases.push(Alias {
+                        name: item.ident.to_string(),
+                        doc: doc_summary(&item.attrs),
+                        target,
+                    });
+                }
+            }
+            Item::Const(item) if is_pub(&item.vis) => {
+                let Ok(Ty::Prim(ty)) = convert_type(&item.ty, &item.ident.to_string()) else {
+                    continue;
+                };
+                if let Some(value) = eval_int(&item.expr) {
+                    api.constants.push(Constant {
+                        name: item.ident.to_string(),
+                        doc: doc_summary(&item.attrs),
+                        ty,
+                        value,
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+fn parse_fn(function: &syn::ItemFn) -> Result<Function, BindgenError> {
+    let name = function.sig.ident.to_string();
+    let mut params = Vec::new();
+    for input in &function.sig.inputs {
+        let FnArg::Typed(arg) = input else {
+            continue;
+        };
+        let Pat::Ident(ident) = &*arg.pat else {
+            return Err(BindgenError::Unsupported {
+                item: name,
+                what: "a destructuring parameter".to_string(),
+            });
+        };
+        params.push(Param {
+            name: ident.ident.to_string(),
+            ty: convert_type(&arg.ty, &name)?,
+            role: Role::In,
+        });
+    }
+    let ret = match &function.sig.output {
+        ReturnType::Default => None,
+        ReturnType::Type(_, ty) => Some(convert_type(ty, &name)?),
+    };
+    Ok(Function {
+        doc: doc_summary(&function.attrs),
+        name,
+        params,
+        ret,
+    })
+}
+
+fn parse_callback(
+    function: &syn::TypeBareFn,
+    name: String,
+    attrs: &[Attribute],
+) -> Result<Callback, BindgenError> {
+    let extern_c = function
+        .abi
+        .as_ref()
+        .is_some_and(|abi| abi.name.as_ref().is_some_and(|name| name.value() == "C"));
+    if !extern_c {
+        return Err(BindgenError::Unsupported {
+            item: name,
+            what: "a function pointer without the C ABI".to_string(),
+        });
+    }
+    let mut params = Vec::new();
+    for (i, input) in function.inputs.iter().enumerate() {
+        params.push(Param {
+            name: input
+                .name
+                .as_ref()
+                .map_or_else(|| format!("arg{i}"), |(ident, _)| ident.to_string()),
+            ty: convert_type(&input.ty, &name)?,
+            role: Role::In,
+        });
+    }
+    let ret = match &function.output {
+        ReturnType::Default => None,
+        ReturnType::Type(_, ty) => Some(convert_type(ty, &name)?),
+    };
+    Ok(Callback {
+        doc: doc_summary(attrs),
+        name,
+        params,
+        ret,
+    })
+}
+
+fn convert_type(ty: &Type, item: &str) -> Result<Ty, BindgenError> {
+    let unsupported = |what: &str| BindgenError::Unsupported {
+        item: item.to_string(),
+        what: what.to_string(),
+    };
+    match ty {
+        Type::Path(path) if path.qself.is_none() => {
+            let segment = path
+                .path
+                .segments
+                .last()
+                .ok_or_else(|| unsupported("an empty path"))?;
+            if segment.ident == "Option" {
+                // How a nullable callback is spelled.
+                if let PathArguments::AngleBracketed(args) = &segment.arguments
+                    && let [GenericArgument::Type(inner)] =
+                        args.args.iter().collect::<Vec<_>>().as_slice()
+                    && let Ty::Named(name) = convert_type(inner, item)?
+                {
+                    return Ok(Ty::Named(name));
+                }
+                return Err(unsupported("an `Option` of anything but a callback"));
+            }
+            if !segment.arguments.is_none() {
+                return Err(unsupported(&format!(
+                    "the generic type `{}`",
+                    segment.ident
+                )));
+            }
+            let ident = segment.ident.to_string();
+            if ident == "c_void" {
+                return Ok(Ty::Void);
+            }
+            Ok(Prim::from_ident(&ident).map_or(Ty::Named(ident), Ty::Prim))
+        }
+        Type::Ptr(ptr) => Ok(Ty::Ptr {
+            mutable: ptr.mutability.is_some(),
+            pointee: Box::new(convert_type(&ptr.elem, item)?),
+        }),
+        Type::Paren(inner) => convert_type(&inner.elem, item),
+        Type::Reference(_) => Err(unsupported("a reference")),
+        Type::Array(_) => Err(unsupported("an array")),
+        Type::BareFn(_) => Err(unsupported("a function pointer")),
+        Type::Tuple(_) => Err(unsupported("a tuple")),
+        _ => Err(unsupported("this kind of type")),
+    }
+}
+
+/// Declare the named types used only behind pointers as opaque, and reject
+/// unknown named types used by value.
+fn add_undeclared_opaque(api: &mut Api) -> Result<(), BindgenError> {
+    fn visit(
+        ty: &Ty,
+        behind_pointer: bool,
+        item: &str,
+        api: &Api,
+        found: &mut Vec<String>,
+    ) -> Result<(), BindgenError> {
+        match ty {
+            Ty::Prim(_) => Ok(()),
+            Ty::Void if behind_pointer => Ok(()),
+            Ty::Void => Err(BindgenError::Unsupported {
+                item: item.to_string(),
+                what: "`c_void` by value".to_string(),
+            }),
+            Ty::Ptr { pointee, .. } => visit(pointee, true, item, api, found),
+            Ty::Named(name) => {
+                let declared = api.find_struct(name).is_some()
+                    || api.find_enum(name).is_some()
+                    || api.find_alias(name).is_some()
+                    || api.find_callback(name).is_some()
+                    || api.is_opaque(name);
+                if declared {
+                    Ok(())
+                } else if behind_pointer {
+                    if !found.contains(name) {
+                        found.push(name.clone());
+                    }
+                    Ok(())
+                } else {
+                    Err(BindgenError::Unsupported {
+                        item: item.to_string(),
+                        what: format!("the undeclared type `{name}`"),
+                    })
+                }
+            }
+        }
+    }
+
+    let mut found = Vec::new();
+    for function in &api.functions {
+        for ty in function.params.iter().map(|p| &p.ty).chain(&function.ret) {
+            visit(ty, false, &function.name, api, &mut found)?;
+        }
+    }
+    for item in &api.structs {
+        for field in &item.fields {
+            visit(&field.ty, false, &item.name, api, &mut found)?;
+        }
+    }
+    for callback in &api.callbacks {
+        for ty in callback.params.iter().map(|p| &p.ty).chain(&callback.ret) {
+            visit(ty, false, &callback.name, api, &mut found)?;
+        }
+    }
+    api.opaque.extend(found.into_iter().map(|name| Opaque {
+        name,
+        doc: String::new(),
+    }));
+    Ok(())
+}
+
+/// Evaluate an integer literal, a negated literal, or `T::MIN`/`T::MAX`.
+fn eval_int(expr: &Expr) -> Option<i128> {
+    match expr {
+        Expr::Lit(lit) => match &lit.lit {
+            Lit::Int(int) => int.base10_parse::<i128>().ok(),
+            _ => None,
+        },
+        Expr::Unary(unary) if matches!(unary.op, UnOp::Neg(_)) => eval_int(&unary.expr).map(|v| -v),
+        Expr::Group(group) => eval_int(&group.expr),
+        Expr::Paren(paren) => eval_int(&paren.expr),
+        Expr::Path(path) => {
+            let mut segments = path.path.segments.iter().map(|s| s.ident.to_string());
+            let (Some(owner), Some(bound), None) =
+                (segments.next(), segments.next(), segments.next())
+            else {
+                return None;
+            };
+            let (min, max) = Prim::from_ident(&owner)?.bounds()?;
+            match bound.as_str() {
+                "MIN" => Some(min),
+                "MAX" => Some(max),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+fn is_pub(vis: &Visibility) -> bool {
+    matches!(vis, Visibility::Public(_))
+}
+
+fn is_exported_fn(function: &syn::ItemFn) -> bool {
+    let extern_c = function
+        .sig
+        .abi
+        .as_ref()
+        .is_some_and(|abi| abi.name.as_ref().is_some_and(|name| name.value() == "C"));
+    let no_mangle = function.attrs.iter().any(|attr| {
+        attr.path().is_ident("no_mangle")
+            || (attr.path().is_ident("unsafe")
+                && attr
+                    .parse_args::<syn::Path>()
+                    .is_ok_and(|p| p.is_ident("no_mangle")))
+    });
+    is_pub(&function.vis) && extern_c && no_mangle
+}
+
+fn has_repr_c(attrs: &[Attribute]) -> bool {
+    attrs.iter().any(|attr| {
+        attr.path().is_ident("repr")
+            && attr
+                .parse_args::<syn::Ident>()
+                .is_ok_and(|ident| ident == "C")
+    })
+}
+
+fn is_cfg_test(attrs: &[Attribute]) -> bool {
+    attrs.iter().any(|attr| {
+        attr.path().is_ident("cfg")
+            && attr
+                .parse_args::<syn::Ident>()
+                .is_ok_and(|ident| ident == "test")
+    })
+}
+
+/// The first paragraph of an item's doc comment on one line, with rustdoc
+/// link brackets and targets removed.
+fn doc_summary(attrs: &[Attribute]) -> String {
+    let mut lines = Vec::new();
+    for attr in attrs {
+        let syn::Meta::NameValue(meta) = &attr.meta else {
+            continue;
+        };
+        if !meta.path.is_ident("doc") {
+            continue;
+        }
+        if let Expr::Lit(syn::ExprLit {
+            lit: Lit::Str(text),
+            ..
+        }) = &meta.value
+        {
+            lines.push(text.value());
+        }
+    }
+    let summary: Vec<&str> = lines
+        .iter()
+        .map(|line| line.trim())
+        .skip_while(|line| line.is_empty())
+        .take_while(|line| !line.is_empty())
+        .collect();
+    strip_links(&summary.join(" "))
+}
+
+/// Turn "[`a`](b)" and "[`a`]" into "`a`".
+fn strip_links(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '[' if chars.peek() == Some(&'`') => {}
+            ']' if out.ends_with('`') => {
+                if chars.peek() == Some(&'(') {
+                    for c in chars.by_ref() {
+                        if c == ')' {
+                            break;
+                        }
+                    }
+                }
+            }
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// Where a crate's `lib.rs` lives, relative to the workspace root.
+pub fn lib_rs(workspace: &Path, crate_name: &str) -> PathBuf {
+    workspace
+        .join("crates")
+        .join(crate_name)
+        .join("src")
+        .join("lib.rs")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn doc_summaries_keep_the_first_paragraph_without_links() {
+        let item: syn::ItemFn = syn::parse_quote! {
+            /// Step the engine; see [`factorial_advance`] and
+            /// [`Engine::step`](factorial_core::engine::Engine::step).
+            ///
+            /// # Safety
+            fn f() {}
+        };
+        assert_eq!(
+            doc_summary(&item.attrs),
+            "Step the engine; see `factorial_advance` and `Engine::step`."
+        );
+    }
+
+    #[test]
+    fn constants_evaluate_literals_and_bounds() {
+        let max: Expr = syn::parse_quote!(u32::MAX);
+        let negative: Expr = syn::parse_quote!(-1);
+        let literal: Expr = syn::parse_quote!(262_144);
+        let call: Expr = syn::parse_quote!(f());
+        assert_eq!(eval_int(&max), Some(u32::MAX.into()));
+        assert_eq!(eval_int(&negative), Some(-1));
+        assert_eq!(eval_int(&literal), Some(262_144));
+        assert_eq!(eval_int(&call), None);
+    }
+
+    #[test]
+    fn callbacks_parse_with_nullable_uses_and_void_pointers() {
+        let item: syn::ItemType = syn::parse_quote! {
+            /// Called per record.
+            pub type Logger = unsafe extern "C" fn(record: *const u32, user_data: *mut c_void);
+        };
+        let Type::BareFn(function) = &*item.ty else {
+            panic!("not a function pointer");
+        };
+        let callback = parse_callback(function, "Logger".to_string(), &item.attrs).unwrap();
+        assert_eq!(callback.doc, "Called per record.");
+        assert_eq!(callback.params[1].name, "user_data");
+        assert_eq!(
+            callback.params[1].ty,
+            Ty::Ptr {
+                mutable: true,
+                pointee: Box::new(Ty::Void),
+            }
+        );
+
+        let nullable: Type = syn::parse_quote!(Option<Logger>);
+        assert_eq!(
+            convert_type(&nullable, "f").unwrap(),
+            Ty::Named("Logger".to_string())
+        );
+        let pointer: Type = syn::parse_quote!(Option<*mut u8>);
+        assert!(convert_type(&pointer, "f").is_err());
+    }
+}
@@ -0,0 +1,288 @@
+//! TypeScript bindings for the WASM exports: a `.d.ts` with the export
+//! signatures, constants and struct layouts, and a small ES module loader
+//! that instantiates the module and checks it exports every function.
+//!
+//! WASM passes 64-bit integers as `bigint` and everything else -- including
+//! pointers into linear memory -- as `number`. Struct layouts are computed
+//! for `wasm32` so hosts can read records such as `FlatEvent` out of
+//! `memory.buffer` at the right offsets.
+
+use std::fmt::Write;
+
+use crate::model::{Api, Prim, Struct, Ty};
+
+/// Name of the generated `.d.ts` interface describing the exports.
+pub const EXPORTS_INTERFACE: &str = "FactorialWasmExports";
+
+/// Byte size and alignment of a `repr(C)` struct on `wasm32`, with the
+/// offset of each field.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Layout {
+    pub size: usize,
+    pub align: usize,
+    pub offsets: Vec<(String, usize)>,
+}
+
+/// Compute the `wasm32` layout of `item`.
+pub fn layout(api: &Api, item: &Struct) -> Layout {
+    let mut offset: usize = 0;
+    let mut align: usize = 1;
+    let mut offsets = Vec::new();
+    for field in &item.fields {
+        let (size, field_align) = size_align(api, &field.ty);
+        offset = offset.next_multiple_of(field_align);
+        offsets.push((field.name.clone(), offset));
+        offset += size;
+        align = align.max(field_align);
+    }
+    Layout {
+        size: offset.next_multiple_of(align),
+        align,
+        offsets,
+    }
+}
+
+fn size_align(api: &Api, ty: &Ty) -> (usize, usize) {
+    match ty {
+        Ty::Prim(prim) => {
+            let size = match prim {
+                Prim::Bool | Prim::U8 | Prim::I8 => 1,
+                Prim::U16 | Prim::I16 => 2,
+                Prim::U32 | Prim::I32 | Prim::F32 | Prim::Usize | Prim::Isize => 4,
+                Prim::U64 | Prim::I64 | Prim::F64 => 8,
+            };
+            (size, size)
+        }
+        Ty::Ptr { .. } => (4, 4),
+        // Only ever behind a pointer.
+        Ty::Void => (0, 1),
+        Ty::Named(name) => {
+            if let Some(alias) = api.find_alias(name) {
+                size_align(api, &Ty::Prim(alias.target))
+            } else if let Some(item) = api.find_struct(name) {
+                let layout = layout(api, item);
+                (layout.size, layout.align)
+            } else {
+                // C enums are `int`-sized.
+                (4, 4)
+            }
+        }
+    }
+}
+
+/// Render the `.d.ts` declarations for `api`.
+pub fn declarations(api: &Api, source: &str) -> String {
+    let mut out = header(source);
+    for constant in &api.constants {
+        jsdoc(&mut out, "", &constant.doc, &[]);
+        let _ = writeln!(
+            out,
+            "export declare const {}: {};",
+            constant.name,
+            literal(constant.ty, constant.value)
+        );
+    }
+    out.push('\n');
+    out.push_str(
+        "/** Byte size, alignment and field offsets of a `repr(C)` struct in linear memory. */\n\
+         export interface StructLayout<Field extends string> {\n\
+         \x20 readonly size: number;\n\
+         \x20 readonly align: number;\n\
+         \x20 readonly offsets: { readonly [F in Field]: number };\n\
+         }\n",
+    );
+    for item in &api.structs {
+        out.push('\n');
+        jsdoc(&mut out, "", &item.doc, &[]);
+        let fields: Vec<String> = item
+            .fields
+            .iter()
+            .map(|f| format!("\"{}\"", f.name))
+            .collect();
+        let _ = writeln!(
+            out,
+            "export declare const {}: StructLayout<{}>;",
+            layout_name(&item.name),
+            fields.join(" | ")
+        );
+    }
+    out.push('\n');
+    let _ = writeln!(out, "/** The functions exported by the WASM module. */");
+    let _ = writeln!(out, "export interface {EXPORTS_INTERFACE} {{");
+    let _ = writeln!(out, "  readonly memory: WebAssembly.Memory;");
+    for function in &api.functions {
+        let params: Vec<(String, String)> = function
+            .params
+            .iter()
+            .filter_map(|p| Some((p.name.clone(), p.role.describe()?)))
+            .collect();
+        jsdoc(&mut out, "  ", &function.doc, &params);
+        let args: Vec<String> = function
+            .params
+            .iter()
+            .map(|p| format!("{}: {}", p.name, ts_type(api, &p.ty)))
+            .collect();
+        let ret = function
+            .ret
+            .as_ref()
+            .map_or("void".to_string(), |ty| ts_type(api, ty));
+        let _ = writeln!(out, "  {}({}): {ret};", function.name, args.join(", "));
+    }
+    out.push_str("}\n\n");
+    out.push_str(
+        "/**\n\
+         \x20* Instantiate the WASM module from its bytes or a `fetch` response and\n\
+         \x20* check that it exports every function declared above.\n\
+         \x20*/\n\
+         export declare function loadFactorial(\n\
+         \x20 source: BufferSource | Response | PromiseLike<Response>,\n\
+         \x20 imports?: WebAssembly.Imports,\n\
+         ): Promise<FactorialWasmExports>;\n",
+    );
+    out
+}
+
+/// Render the ES module implementing the declarations.
+pub fn loader(api: &Api, source: &str) -> String {
+    let mut out = header(source);
+    for constant in &api.constants {
+        let _ = writeln!(
+            out,
+            "export const {} = {};",
+            constant.name,
+            literal(constant.ty, constant.value)
+        );
+    }
+    for item in &api.structs {
+        let layout = layout(api, item);
+        out.push('\n');
+        let _ = writeln!(
+            out,
+            "export const {} = Object.freeze({{",
+            layout_name(&item.name)
+        );
+        let _ = writeln!(out, "  size: {},", layout.size);
+        let _ = writeln!(out, "  align: {},", layout.align);
+        let _ = writeln!(out, "  offsets: Object.freeze({{");
+        for (field, offset) in &layout.offsets {
+            let _ = writeln!(out, "    {field}: {offset},");
+        }
+        out.push_str("  }),\n});\n");
+    }
+    out.push_str("\nconst FUNCTIONS = [\n");
+    for function in &api.functions {
+        let _ = writeln!(out, "  \"{}\",", function.name);
+    }
+    out.push_str("];\n\n");
+    out.push_str(
+        "export async function loadFactorial(source, imports = {}) {\n\
+         \x20 const input = await source;\n\
+         \x20 const result =\n\
+         \x20   typeof Response !== \"undefined\" && input instanceof Response\n\
+         \x20     ? await WebAssembly.instantiateStreaming(input, imports)\n\
+         \x20     : await WebAssembly.instantiate(input, imports);\n\
+         \x20 const exports = result.instance.exports;\n\
+         \x20 const missing = FUNCTIONS.filter((name) => typeof exports[name] !== \"function\");\n\
+         \x20 if (missing.length > 0) {\n\
+         \x20   throw new Error(`the WASM module is missing exports: ${missing.join(\", \")}`);\n\
+         \x20 }\n\
+         \x20 return exports;\n\
+         }\n",
+    );
+    out
+}
+
+fn header(source: &str) -> String {
+    format!(
+        "// Generated by factorial-bindgen from {source}. Do not edit;\n\
+         // run `cargo run -p factorial-bindgen` after changing the WASM API.\n\n"
+    )
+}
+
+fn ts_type(api: &Api, ty: &Ty) -> String {
+    let prim = match ty {
+        Ty::Prim(prim) => Some(*prim),
+        Ty::Named(name) => api.find_alias(name).map(|a| a.target),
+        Ty::Ptr { .. } | Ty::Void => None,
+    };
+    match prim {
+        Some(Prim::U64 | Prim::I64) => "bigint",
+        _ => "number",
+    }
+    .to_string()
+}
+
+fn literal(ty: Prim, value: i128) -> String {
+    match ty {
+        Prim::U64 | Prim::I64 => format!("{value}n"),
+        _ => value.to_string(),
+    }
+}
+
+/// `FlatEvent` -> `FLAT_EVENT_LAYOUT`.
+fn layout_name(name: &str) -> String {
+    let mut out = String::new();
+    for (i, c) in name.chars().enumerate() {
+        if c.is_uppercase() && i > 0 {
+            out.push('_');
+        }
+        out.push(c.to_ascii_uppercase());
+    }
+    out.push_str("_LAYOUT");
+    out
+}
+
+fn jsdoc(out: &mut String, indent: &str, text: &str, params: &[(String, String)]) {
+    match (text.is_empty(), params.is_empty()) {
+        (true, true) => {}
+        (false, true) => {
+            let _ = writeln!(out, "{indent}/** {text} */");
+        }
+        _ => {
+            let _ = writeln!(out, "{indent}/**");
+            if !text.is_empty() {
+                let _ = writeln!(out, "{indent} * {text}");
+            }
+            for (name, description) in params {
+                let _ = writeln!(out, "{indent} * @param {name} {description}");
+            }
+            let _ = writeln!(out, "{indent} */");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::Field;
+
+    #[test]
+    fn layouts_follow_wasm32_alignment() {
+        let field = |name: &str, prim| Field {
+            name: name.to_string(),
+            doc: String::new(),
+            ty: Ty::Prim(prim),
+        };
+        let item = Struct {
+            name: "FlatEvent".to_string(),
+            doc: String::new(),
+            fields: vec![
+                field("kind", Prim::U32),
+                field("tick", Prim::U64),
+                field("flag", Prim::Bool),
+            ],
+        };
+        let layout = layout(&Api::default(), &item);
+        assert_eq!(layout.size, 24);
+        assert_eq!(layout.align, 8);
+        assert_eq!(
+            layout.offsets,
+            vec![
+                ("kind".to_string(), 0),
+                ("tick".to_string(), 8),
+                ("flag".to_string(), 16)
+            ]
+        );
+        assert_eq!(layout_name(&item.name), "FLAT_EVENT_LAYOUT");
+    }
+}
@@ -0,0 +1,31 @@
+//! The committed bindings must match the current FFI and WASM sources, so an
+//! API change cannot land without regenerating them.
+
+use factorial_bindgen::{generate, stale_files, workspace_root};
+
+#[test]
+fn committed_bindings_are_up_to_date() {
+    let stale = stale_files(&workspace_root()).expect("failed to read the API sources");
+    assert!(
+        stale.is_empty(),
+        "out of date: {stale:?}; run `cargo run -p factorial-bindgen` and commit the result"
+    );
+}
+
+#[test]
+fn every_export_is_bound() {
+    let files = generate(&workspace_root()).unwrap();
+    let csharp = &files[0].contents;
+    let loader = &files[2].contents;
+    // Spot-check both sides of the API surface.
+    assert!(
+        csharp.contains(
+            "public static extern FactorialResult factorial_step(FactorialEngine* engine);"
+        )
+    );
+    assert!(csharp.contains("public enum FfiEventKind : int"));
+    assert!(csharp.contains("Out: caller-owned; release it with <c>factorial_free_buffer</c>."));
+    assert!(csharp.contains("Buffer with room for one element per engine in the pool"));
+    assert!(loader.contains("\"factorial_step\","));
+    assert!(loader.contains("export const FLAT_EVENT_LAYOUT"));
+}
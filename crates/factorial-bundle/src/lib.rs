@@ -0,0 +1,545 @@
+//! Self-contained scenario bundles for the Factorial engine.
+//!
+//! Reproducing a scenario takes more than the engine save: the host's tech
+//! tree, fluid module and statistics configuration drive the simulation
+//! too. [`Bundle::capture`] packs whichever of them a host uses into one
+//! buffer, and [`Bundle::restore`] unpacks it into a setup that steps
+//! identically.
+//!
+//! # Format
+//!
+//! All integers are little-endian. A bundle is a header followed by any
+//! number of sections:
+//!
+//! | Field | Size |
+//! |---|---|
+//! | [`BUNDLE_MAGIC`] | 4 bytes |
+//! | [`BUNDLE_VERSION`] | 4 bytes |
+//! | section tag ([`Section`]) | 4 bytes |
+//! | payload length | 8 bytes |
+//! | payload | length bytes |
+//! | ... more sections | |
+//!
+//! The engine section holds [`Engine::serialize`] output; the others hold
+//! the component's serde representation encoded with bitcode. Sections may
+//! come in any order. Absent sections restore as `None`, and tags this build
+//! does not know are skipped and reported in
+//! [`BundleContents::unknown_sections`], so a bundle written by a newer
+//! build still loads everything the two builds share.
+//!
+//! # What is restored
+//!
+//! The engine section is required. A tech tree captured while registered
+//! as a module on the engine is registered on the restored engine again, so
+//! its state keeps contributing to the state hash; a standalone tree comes
+//! back in [`BundleContents::tech_tree`]. The fluid module keeps its
+//! inventory deliveries; the host resumes calling `tick` and `deliver` on
+//! it. Statistics history is not bundled, only the configuration:
+//! [`BundleContents::production_stats`] builds a tracker for the restored
+//! engine. As with engine saves, listeners, tick hooks and other modules are
+//! session state the host registers again.
+
+use factorial_core::engine::Engine;
+use factorial_core::serialize::{DeserializeError, SerializeError};
+use factorial_fluid::FluidModule;
+use factorial_stats::{ProductionStats, StatsConfig};
+use factorial_tech_tree::TechTree;
+
+/// Magic number at the start of every bundle.
+pub const BUNDLE_MAGIC: u32 = 0xFAC7_00B1;
+
+/// Current bundle container version.
+pub const BUNDLE_VERSION: u32 = 1;
+
+/// Bytes before the first section: magic and version.
+const HEADER_LEN: usize = 8;
+
+/// Bytes before each section's payload: tag and length.
+const SECTION_HEADER_LEN: usize = 12;
+
+// ---------------------------------------------------------------------------
+// Error types
+// ---------------------------------------------------------------------------
+
+/// Errors from capturing or restoring a bundle.
+#[derive(Debug, thiserror::Error)]
+pub enum BundleError {
+    #[error("engine serialization failed: {0}")]
+    Serialize(#[from] SerializeError),
+    #[error("{0:?} section encoding failed: {1}")]
+    Encode(Section, String),
+    #[error("data too short for bundle header")]
+    TooShort,
+    #[error("invalid magic number: expected 0x{:08X}, got 0x{:08X}", BUNDLE_MAGIC, .0)]
+    InvalidMagic(u32),
+    #[error("bundle from future version {0} (this build supports up to {BUNDLE_VERSION})")]
+    FutureVersion(u32),
+    #[error("section with tag {0} runs past the end of the data")]
+    Truncated(u32),
+    #[error("{0:?} section appears more than once")]
+    DuplicateSection(Section),
+    #[error("bundle has no engine section")]
+    MissingEngine,
+    #[error("engine section: {0}")]
+    Engine(#[from] DeserializeError),
+    #[error("{0:?} section decoding failed: {1}")]
+    Decode(Section, String),
+}
+
+// ---------------------------------------------------------------------------
+// Sections
+// ---------------------------------------------------------------------------
+
+/// The kinds of section a bundle can hold. The discriminant is the tag
+/// written to the container.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[repr(u32)]
+pub enum Section {
+    /// The engine, as [`Engine::serialize`] writes it.
+    Engine = 1,
+    /// A tech tree the host drives alongside the engine.
+    TechTree = 2,
+    /// A tech tree registered as a module on the engine.
+    TechTreeModule = 3,
+    /// The fluid module.
+    Fluid = 4,
+    /// The statistics configuration.
+    StatsConfig = 5,
+}
+
+impl Section {
+    /// The section with `tag`, if this build knows it.
+    pub fn from_tag(tag: u32) -> Option<Self> {
+        match tag {
+            1 => Some(Self::Engine),
+            2 => Some(Self::TechTree),
+            3 => Some(Self::TechTreeModule),
+            4 => Some(Self::Fluid),
+            5 => Some(Self::StatsConfig),
+            _ => None,
+        }
+    }
+
+    /// The tag written to the container.
+    pub fn tag(self) -> u32 {
+        self as u32
+    }
+}
+
+/// Everything [`Bundle::restore`] read back.
+pub struct BundleContents {
+    /// The engine, with its tech tree module registered again if the
+    /// bundle has one.
+    pub engine: Engine,
+    /// The standalone tech tree, if one was captured.
+    pub tech_tree: Option<TechTree>,
+    /// The fluid module, if one was captured.
+    pub fluid: Option<FluidModule>,
+    /// The statistics configuration, if one was captured.
+    pub stats_config: Option<StatsConfig>,
+    /// The known sections present, in bundle order.
+    pub sections: Vec<Section>,
+    /// Tags of sections this build does not know, in bundle order.
+    pub unknown_sections: Vec<u32>,
+}
+
+impl BundleContents {
+    /// A statistics tracker with the bundled configuration, initialized
+    /// for the restored engine. `None` if the bundle has no configuration.
+    pub fn production_stats(&self) -> Option<ProductionStats> {
+        let config = self.stats_config.clone()?;
+        let mut stats = ProductionStats::new(config);
+        stats.initialize(&self.engine);
+        Some(stats)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Capture and restore
+// ---------------------------------------------------------------------------
+
+/// Captures and restores scenario bundles.
+pub struct Bundle;
+
+impl Bundle {
+    /// Pack `engine` and whichever components are given into one buffer.
+    ///
+    /// Pass the tree registered on the engine
+    /// (`engine.find_module::<TechTree>()`) to have it registered again on
+    /// restore; any other tree is bundled as a standalone one. Fails if the
+    /// engine cannot be serialized, for instance mid sliced tick.
+    pub fn capture(
+        engine: &Engine,
+        tech_tree: Option<&TechTree>,
+        fluid: Option<&FluidModule>,
+        stats_config: Option<&StatsConfig>,
+    ) -> Result<Vec<u8>, BundleError> {
+        let mut out = Vec::new();
+        out.extend_from_slice(&BUNDLE_MAGIC.to_le_bytes());
+        out.extend_from_slice(&BUNDLE_VERSION.to_le_bytes());
+        write_section(&mut out, Section::Engine, &engine.serialize()?);
+        if let Some(tree) = tech_tree {
+            let registered = engine
+                .find_module::<TechTree>()
+                .is_some_and(|module| std::ptr::eq(module, tree));
+            let section = if registered {
+                Section::TechTreeModule
+            } else {
+                Section::TechTree
+            };
+            write_section(&mut out, section, &encode(section, tree)?);
+        }
+        if let Some(fluid) = fluid {
+            write_section(&mut out, Section::Fluid, &encode(Section::Fluid, fluid)?);
+        }
+        if let Some(config) = stats_config {
+            let payload = encode(Section::StatsConfig, config)?;
+            write_section(&mut out, Section::StatsConfig, &payload);
+        }
+        Ok(out)
+    }
+
+    /// Unpack a bundle written by [`capture`](Self::capture).
+    pub fn restore(data: &[u8]) -> Result<BundleContents, BundleError> {
+        if data.len() < HEADER_LEN {
+            return Err(BundleError::TooShort);
+        }
+        let magic = read_u32(data, 0);
+        if magic != BUNDLE_MAGIC {
+            return Err(BundleError::InvalidMagic(magic));
+        }
+        let version = read_u32(data, 4);
+        if version > BUNDLE_VERSION {
+            return Err(BundleError::FutureVersion(version));
+        }
+
+        let mut engine = None;
+        let mut tech_tree = None;
+        let mut tech_tree_module = None;
+        let mut fluid = None;
+        let mut stats_config = None;
+        let mut sections = Vec::new();
+        let mut unknown_sections = Vec::new();
+        let mut offset = HEADER_LEN;
+        while offset < data.len() {
+            let (tag, payload) = read_section(data, offset)?;
+            offset += SECTION_HEADER_LEN + payload.len();
+            let Some(section) = Section::from_tag(tag) else {
+                unknown_sections.push(tag);
+                continue;
+            };
+            if sections.contains(&section) {
+                return Err(BundleError::DuplicateSection(section));
+            }
+            sections.push(section);
+            match section {
+                Section::Engine => engine = Some(Engine::deserialize(payload)?),
+                Section::TechTree => tech_tree = Some(decode(section, payload)?),
+                Section::TechTreeModule => {
+                    tech_tree_module = Some(decode::<TechTree>(section, payload)?)
+                }
+                Section::Fluid => fluid = Some(decode(section, payload)?),
+                Section::StatsConfig => stats_config = Some(decode(section, payload)?),
+            }
+        }
+
+        let mut engine = engine.ok_or(BundleError::MissingEngine)?;
+        if let Some(tree) = tech_tree_module {
+            engine.register_module(Box::new(tree));
+        }
+        Ok(BundleContents {
+            engine,
+            tech_tree,
+            fluid,
+            stats_config,
+            sections,
+            unknown_sections,
+        })
+    }
+}
+
+fn encode<T: serde::Serialize + ?Sized>(
+    section: Section,
+    value: &T,
+) -> Result<Vec<u8>, BundleError> {
+    bitcode::serialize(value).map_err(|e| BundleError::Encode(section, e.to_string()))
+}
+
+fn decode<T: serde::de::DeserializeOwned>(
+    section: Section,
+    payload: &[u8],
+) -> Result<T, BundleError> {
+    bitcode::deserialize(payload).map_err(|e| BundleError::Decode(section, e.to_string()))
+}
+
+fn write_section(out: &mut Vec<u8>, section: Section, payload: &[u8]) {
+    out.extend_from_slice(&section.tag().to_le_bytes());
+    out.extend_from_slice(&(payload.len() as u64).to_le_bytes());
+    out.extend_from_slice(payload);
+}
+
+/// The tag and payload of the section starting at `offset`.
+fn read_section(data: &[u8], offset: usize) -> Result<(u32, &[u8]), BundleError> {
+    let Some(header) = data.get(offset..offset + SECTION_HEADER_LEN) else {
+        let tag = data
+            .get(offset..offset + 4)
+            .map_or(0, |_| read_u32(data, offset));
+        return Err(BundleError::Truncated(tag));
+    };
+    let tag = read_u32(header, 0);
+    let len = u64::from_le_bytes(header[4..12].try_into().expect("8 bytes"));
+    let start = offset + SECTION_HEADER_LEN;
+    let payload = usize::try_from(len)
+        .ok()
+        .and_then(|len| data.get(start..start.checked_add(len)?))
+        .ok_or(BundleError::Truncated(tag))?;
+    Ok((tag, payload))
+}
+
+fn read_u32(data: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(data[offset..offset + 4].try_into().expect("4 bytes"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use factorial_core::fixed::{Fixed64, Ticks};
+    use factorial_core::id::ItemTypeId;
+    use factorial_core::module::Module;
+    use factorial_core::processor::Processor;
+    use factorial_core::sim::SimulationStrategy;
+    use factorial_core::test_utils::*;
+    use factorial_fluid::{FluidConsumer, FluidNetworkId, FluidProducer, FluidStorage};
+    use factorial_tech_tree::{ResearchCost, TechId, Technology};
+
+    fn water() -> ItemTypeId {
+        ItemTypeId(50)
+    }
+
+    fn fixed(v: f64) -> Fixed64 {
+        Fixed64::from_num(v)
+    }
+
+    /// A host's whole setup: the engine with a registered tech tree, and
+    /// the fluid module it drives alongside.
+    struct Scenario {
+        engine: Engine,
+        fluid: FluidModule,
+        network: FluidNetworkId,
+        tick: Ticks,
+    }
+
+    impl Scenario {
+        /// Iron mine -> gear assembler, and a pump feeding water through a
+        /// tank into a plant that turns it into gears. Research runs at a
+        /// fixed rate.
+        fn new() -> Self {
+            let mut engine = Engine::new(SimulationStrategy::Tick);
+            let mine = add_node(&mut engine, make_source(iron(), 2.0), 0, 20);
+            let assembler = add_node(
+                &mut engine,
+                make_recipe(vec![(iron(), 2)], vec![(gear(), 1)], 3),
+                10,
+                50,
+            );
+            connect(&mut engine, mine, assembler, make_item_transport(4));
+            let plant = add_node(
+                &mut engine,
+                make_recipe(vec![(water(), 6)], vec![(gear(), 1)], 2),
+                30,
+                50,
+            );
+            let pump = add_node(&mut engine, Processor::Passthrough, 0, 0);
+            let tank = add_node(&mut engine, Processor::Passthrough, 0, 0);
+
+            let mut fluid = FluidModule::new();
+            let network = fluid.create_network(water());
+            fluid.add_producer(network, pump, FluidProducer { rate: fixed(5.0) });
+            fluid.add_consumer(network, plant, FluidConsumer { rate: fixed(4.0) });
+            fluid.add_storage(
+                network,
+                tank,
+                FluidStorage {
+                    capacity: fixed(40.0),
+                    current: fixed(0.0),
+                    fill_rate: fixed(2.5),
+                },
+            );
+            assert!(fluid.deliver_to_inventory(network, plant, plant));
+
+            let mut tree = TechTree::new();
+            tree.register(Technology {
+                id: TechId(0),
+                name: "automation".to_string(),
+                prerequisites: vec![],
+                cost: ResearchCost::Rate {
+                    points_per_tick: fixed(0.75),
+                    total: fixed(150.0),
+                },
+                unlocks: vec![],
+                repeatable: false,
+                cost_scaling: None,
+            })
+            .unwrap();
+            tree.start_research(TechId(0), 0).unwrap();
+            engine.register_module(Box::new(tree));
+
+            Self {
+                engine,
+                fluid,
+                network,
+                tick: 0,
+            }
+        }
+
+        fn step(&mut self) {
+            self.fluid.tick(self.tick);
+            self.fluid.deliver(&mut self.engine);
+            let tree = self.engine.find_module_mut::<TechTree>().unwrap();
+            let _ = tree.tick_rate(TechId(0), self.tick);
+            self.engine.step();
+            self.tick += 1;
+        }
+
+        fn tree(&self) -> &TechTree {
+            self.engine.find_module::<TechTree>().unwrap()
+        }
+    }
+
+    fn stats_config() -> StatsConfig {
+        StatsConfig {
+            window_size: 30,
+            history_capacity: 64,
+        }
+    }
+
+    fn capture(scenario: &Scenario) -> Vec<u8> {
+        Bundle::capture(
+            &scenario.engine,
+            Some(scenario.tree()),
+            Some(&scenario.fluid),
+            Some(&stats_config()),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn restored_scenario_steps_identically() {
+        let mut original = Scenario::new();
+        for _ in 0..50 {
+            original.step();
+        }
+        let contents = Bundle::restore(&capture(&original)).unwrap();
+        assert_eq!(
+            contents.sections,
+            vec![
+                Section::Engine,
+                Section::TechTreeModule,
+                Section::Fluid,
+                Section::StatsConfig
+            ]
+        );
+        assert!(contents.tech_tree.is_none());
+        assert!(contents.unknown_sections.is_empty());
+        let stats = contents.production_stats().unwrap();
+        assert_eq!(stats.config(), &stats_config());
+        let mut restored = Scenario {
+            engine: contents.engine,
+            fluid: contents.fluid.unwrap(),
+            network: original.network,
+            tick: original.tick,
+        };
+
+        for _ in 0..200 {
+            original.step();
+            restored.step();
+            assert_eq!(original.engine.state_hash(), restored.engine.state_hash());
+        }
+        // Research completed after the capture, in both.
+        assert!(original.tree().is_completed(TechId(0)));
+        assert!(restored.tree().is_completed(TechId(0)));
+        assert_eq!(original.tree().state_hash(), restored.tree().state_hash());
+        let network = original.network;
+        assert!(original.fluid.pressure(network).unwrap() > Fixed64::ZERO);
+        assert_eq!(
+            original.fluid.network_balance(network),
+            restored.fluid.network_balance(network)
+        );
+        assert_eq!(
+            bitcode::serialize(&original.fluid).unwrap(),
+            bitcode::serialize(&restored.fluid).unwrap()
+        );
+    }
+
+    #[test]
+    fn absent_and_unknown_sections_are_tolerated() {
+        let mut scenario = Scenario::new();
+        scenario.step();
+        let standalone = scenario.tree().clone();
+        let mut data = Bundle::capture(&scenario.engine, Some(&standalone), None, None).unwrap();
+        // A section from a newer build, between the engine and the tree.
+        let (_, engine_payload) = read_section(&data, HEADER_LEN).unwrap();
+        let engine_end = HEADER_LEN + SECTION_HEADER_LEN + engine_payload.len();
+        let mut unknown = Vec::new();
+        unknown.extend_from_slice(&99u32.to_le_bytes());
+        unknown.extend_from_slice(&3u64.to_le_bytes());
+        unknown.extend_from_slice(&[1, 2, 3]);
+        data.splice(engine_end..engine_end, unknown);
+
+        let contents = Bundle::restore(&data).unwrap();
+        assert_eq!(contents.sections, vec![Section::Engine, Section::TechTree]);
+        assert_eq!(contents.unknown_sections, vec![99]);
+        assert!(contents.fluid.is_none());
+        assert!(contents.stats_config.is_none());
+        assert!(contents.production_stats().is_none());
+        // A standalone tree stays standalone.
+        assert!(contents.engine.find_module::<TechTree>().is_none());
+        let tree = contents.tech_tree.unwrap();
+        assert_eq!(tree.state_hash(), standalone.state_hash());
+        assert_eq!(tree.get_state(TechId(0)), standalone.get_state(TechId(0)));
+    }
+
+    #[test]
+    fn malformed_bundles_are_rejected() {
+        let scenario = Scenario::new();
+        let data = capture(&scenario);
+
+        assert!(matches!(
+            Bundle::restore(&data[..4]),
+            Err(BundleError::TooShort)
+        ));
+        let mut bad_magic = data.clone();
+        bad_magic[0] ^= 0xFF;
+        assert!(matches!(
+            Bundle::restore(&bad_magic),
+            Err(BundleError::InvalidMagic(_))
+        ));
+        let mut future = data.clone();
+        future[4..8].copy_from_slice(&(BUNDLE_VERSION + 1).to_le_bytes());
+        assert!(matches!(
+            Bundle::restore(&future),
+            Err(BundleError::FutureVersion(v)) if v == BUNDLE_VERSION + 1
+        ));
+        assert!(matches!(
+            Bundle::restore(&data[..data.len() - 1]),
+            Err(BundleError::Truncated(5))
+        ));
+
+        let mut twice = data.clone();
+        write_section(&mut twice, Section::StatsConfig, &[]);
+        assert!(matches!(
+            Bundle::restore(&twice),
+            Err(BundleError::DuplicateSection(Section::StatsConfig))
+        ));
+        let stats_only = {
+            let mut out = data[..HEADER_LEN].to_vec();
+            let payload = encode(Section::StatsConfig, &stats_config()).unwrap();
+            write_section(&mut out, Section::StatsConfig, &payload);
+            out
+        };
+        assert!(matches!(
+            Bundle::restore(&stats_only),
+            Err(BundleError::MissingEngine)
+        ));
+    }
+}
@@ -57,8 +57,12 @@ fn main() {
                 item_type: ItemTypeId(1),
                 quantity: 1,
                 bonus: None,
+                probability: None,
             }],
             duration: 3,
+            fluid_inputs: Vec::new(),
+            fluid_outputs: Vec::new(),
+            duration_per_unit: None,
         }),
     );
 
@@ -68,8 +68,12 @@ fn main() {
                 item_type: ItemTypeId(1), // iron gear
                 quantity: 1,
                 bonus: None,
+                probability: None,
             }],
             duration: 5,
+            fluid_inputs: Vec::new(),
+            fluid_outputs: Vec::new(),
+            duration_per_unit: None,
         }),
     );
 
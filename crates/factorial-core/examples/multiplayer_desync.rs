@@ -49,8 +49,12 @@ fn setup_factory(engine: &mut Engine) -> (NodeId, NodeId) {
                 item_type: ItemTypeId(1),
                 quantity: 1,
                 bonus: None,
+                probability: None,
             }],
             duration: 4,
+            fluid_inputs: Vec::new(),
+            fluid_outputs: Vec::new(),
+            duration_per_unit: None,
         }),
     );
 
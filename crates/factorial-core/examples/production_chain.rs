@@ -61,8 +61,12 @@ fn main() {
                 item_type: ItemTypeId(1), // iron plate
                 quantity: 1,
                 bonus: None,
+                probability: None,
             }],
             duration: 3,
+            fluid_inputs: Vec::new(),
+            fluid_outputs: Vec::new(),
+            duration_per_unit: None,
         }),
     );
 
@@ -79,8 +83,12 @@ fn main() {
                 item_type: ItemTypeId(2), // iron gear
                 quantity: 1,
                 bonus: None,
+                probability: None,
             }],
             duration: 5,
+            fluid_inputs: Vec::new(),
+            fluid_outputs: Vec::new(),
+            duration_per_unit: None,
         }),
     );
 
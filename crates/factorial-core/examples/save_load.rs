@@ -50,8 +50,12 @@ fn build_factory() -> Engine {
                 item_type: ItemTypeId(1),
                 quantity: 1,
                 bonus: None,
+                probability: None,
             }],
             duration: 3,
+            fluid_inputs: Vec::new(),
+            fluid_outputs: Vec::new(),
+            duration_per_unit: None,
         }),
     );
 
@@ -113,6 +113,7 @@ fn main() {
             speed: Fixed64::from_num(1),
             slot_count: 5,
             lanes: 1,
+            min_spacing: Fixed64::ONE,
         }),
     );
 
@@ -0,0 +1,320 @@
+//! Factory-wide bottleneck digest.
+//!
+//! [`Engine::bottleneck_digest`] answers "why did production stop?" from the
+//! current processor states and inventory levels. Every node stalled for
+//! missing inputs is *starved* of the recipe inputs it lacks; every node
+//! stalled because its output is full is *blocked* on the outputs that no
+//! longer fit. The digest groups both by item type, so an item starved
+//! somewhere and blocked nowhere points at missing supply, while an item
+//! blocked at its producer points at missing demand downstream.
+//!
+//! The digest is computed on every call in one pass over the nodes; it does
+//! not look at history. Ghost nodes are skipped.
+//!
+//! Which items a stalled node is charged with:
+//!
+//! - **Starved**: recipe inputs (of the active recipe, for multi-recipe,
+//!   weighted and flexible processors) with fewer units in the input
+//!   inventory than one cycle needs; the input of a property processor; the
+//!   accepted types of a demand processor. Fluid shortfalls are not items
+//!   and are not reported.
+//! - **Blocked**: recipe outputs with less free output space than one cycle
+//!   produces, or every output when none is short on its own (the stall
+//!   came from total capacity); the output of a source, multi-output source
+//!   or property processor.
+//!
+//! Each item names its worst offender: the starved node with the largest
+//! shortfall and the blocked node holding the most of the item in its
+//! output, ties broken by the lowest node id.
+
+use std::collections::BTreeMap;
+
+use crate::engine::Engine;
+use crate::id::{ItemTypeId, NodeId};
+use crate::processor::{FixedRecipe, Processor, ProcessorState, StallReason};
+
+/// Stalls involving one item type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ItemBottleneck {
+    /// The item type.
+    pub item_type: ItemTypeId,
+    /// Nodes stalled for want of this item.
+    pub starved_nodes: u32,
+    /// Nodes stalled because this item no longer fits in their output.
+    pub blocked_nodes: u32,
+    /// The starved node missing the most units, if any.
+    pub worst_starved: Option<NodeId>,
+    /// The blocked node holding the most units in its output, if any.
+    pub worst_blocked: Option<NodeId>,
+}
+
+/// Every item type involved in a stall, from [`Engine::bottleneck_digest`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BottleneckDigest {
+    /// One entry per item type, in ascending item-type order.
+    pub items: Vec<ItemBottleneck>,
+}
+
+impl BottleneckDigest {
+    /// The entry for `item_type`, if it is involved in any stall.
+    pub fn get(&self, item_type: ItemTypeId) -> Option<&ItemBottleneck> {
+        self.items
+            .binary_search_by_key(&item_type, |item| item.item_type)
+            .ok()
+            .map(|i| &self.items[i])
+    }
+
+    /// Whether no node is stalled on an item.
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+}
+
+/// Running totals for one item: count and `(severity, node)` of the worst
+/// offender on each side.
+#[derive(Default)]
+struct Tally {
+    starved: u32,
+    blocked: u32,
+    worst_starved: Option<(u32, NodeId)>,
+    worst_blocked: Option<(u32, NodeId)>,
+}
+
+/// Keep the higher severity, or the lower id on a tie.
+fn offer(worst: &mut Option<(u32, NodeId)>, severity: u32, node: NodeId) {
+    let better = match *worst {
+        None => true,
+        Some((s, n)) => severity > s || (severity == s && node < n),
+    };
+    if better {
+        *worst = Some((severity, node));
+    }
+}
+
+/// The recipe a recipe-running processor is working towards.
+fn active_recipe(processor: &Processor) -> Option<&FixedRecipe> {
+    match processor {
+        Processor::Fixed(recipe) => Some(recipe),
+        Processor::MultiRecipe(multi) => multi.recipes.get(multi.active_recipe),
+        Processor::WeightedRecipe(weighted) => weighted
+            .recipes
+            .get(weighted.selected.unwrap_or(0))
+            .map(|(recipe, _)| recipe),
+        Processor::Flexible(flexible) => flexible.active.as_ref().map(|(_, recipe)| recipe),
+        _ => None,
+    }
+}
+
+impl Engine {
+    /// Summarise every item type currently involved in a stall: how many
+    /// nodes are starved of it or blocked producing it, and the worst node
+    /// on each side. Runs in O(nodes).
+    pub fn bottleneck_digest(&self) -> BottleneckDigest {
+        let mut tallies: BTreeMap<ItemTypeId, Tally> = BTreeMap::new();
+        for (node, _) in self.graph.nodes() {
+            if self.ghost_nodes.contains_key(node) {
+                continue;
+            }
+            let (Some(processor), Some(ProcessorState::Stalled { reason })) =
+                (self.processors.get(node), self.processor_states.get(node))
+            else {
+                continue;
+            };
+            match reason {
+                StallReason::MissingInputs => {
+                    for (item, shortfall) in self.starved_items(node, processor) {
+                        let tally = tallies.entry(item).or_default();
+                        tally.starved += 1;
+                        offer(&mut tally.worst_starved, shortfall, node);
+                    }
+                }
+                StallReason::OutputFull => {
+                    for (item, held) in self.blocked_items(node, processor) {
+                        let tally = tallies.entry(item).or_default();
+                        tally.blocked += 1;
+                        offer(&mut tally.worst_blocked, held, node);
+                    }
+                }
+                StallReason::NoPower | StallReason::Depleted => {}
+            }
+        }
+        BottleneckDigest {
+            items: tallies
+                .into_iter()
+                .map(|(item_type, tally)| ItemBottleneck {
+                    item_type,
+                    starved_nodes: tally.starved,
+                    blocked_nodes: tally.blocked,
+                    worst_starved: tally.worst_starved.map(|(_, node)| node),
+                    worst_blocked: tally.worst_blocked.map(|(_, node)| node),
+                })
+                .collect(),
+        }
+    }
+
+    /// Items a starved node lacks, with the units missing for one cycle.
+    fn starved_items(&self, node: NodeId, processor: &Processor) -> Vec<(ItemTypeId, u32)> {
+        let have = |item: ItemTypeId| self.inputs.get(node).map_or(0, |inv| inv.count_of(item));
+        let needs: Vec<(ItemTypeId, u32)> = match processor {
+            Processor::Property(prop) => vec![(prop.input_type, 1)],
+            Processor::Demand(demand) => match &demand.accepted_types {
+                Some(types) => types.iter().map(|&item| (item, 1)).collect(),
+                None => vec![(demand.input_type, 1)],
+            },
+            _ => active_recipe(processor)
+                .map(|recipe| {
+                    recipe
+                        .inputs
+                        .iter()
+                        .map(|input| (input.item_type, input.quantity))
+                        .collect()
+                })
+                .unwrap_or_default(),
+        };
+        needs
+            .into_iter()
+            .filter_map(|(item, need)| {
+                let have = have(item);
+                (have < need).then(|| (item, need - have))
+            })
+            .collect()
+    }
+
+    /// Outputs a blocked node cannot fit, with the units of each it holds.
+    fn blocked_items(&self, node: NodeId, processor: &Processor) -> Vec<(ItemTypeId, u32)> {
+        let output = self.outputs.get(node);
+        let held = |item: ItemTypeId| output.map_or(0, |inv| inv.count_of(item));
+        let items: Vec<ItemTypeId> = match processor {
+            Processor::Source(source) => vec![source.output_type],
+            Processor::MultiSource(multi) => multi.outputs.iter().map(|o| o.item_type).collect(),
+            Processor::Property(prop) => vec![prop.output_type],
+            _ => match active_recipe(processor) {
+                Some(recipe) => {
+                    let short: Vec<ItemTypeId> = recipe
+                        .outputs
+                        .iter()
+                        .filter(|o| {
+                            output.is_none_or(|inv| {
+                                Self::output_space_for(inv, o.item_type) < o.quantity
+                            })
+                        })
+                        .map(|o| o.item_type)
+                        .collect();
+                    if short.is_empty() {
+                        recipe.outputs.iter().map(|o| o.item_type).collect()
+                    } else {
+                        short
+                    }
+                }
+                None => Vec::new(),
+            },
+        };
+        items.into_iter().map(|item| (item, held(item))).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sim::SimulationStrategy;
+    use crate::test_utils::*;
+
+    fn science() -> ItemTypeId {
+        ItemTypeId(90)
+    }
+
+    /// Iron mine -> gear assembler -> science assembler -> lab, with no
+    /// copper for the science packs.
+    #[test]
+    fn starved_science_points_back_at_the_blocked_gears() {
+        let mut engine = Engine::new(SimulationStrategy::Tick);
+        let mine = add_node(&mut engine, make_source(iron(), 2.0), 0, 20);
+        let gears = add_node(
+            &mut engine,
+            make_recipe(vec![(iron(), 2)], vec![(gear(), 1)], 1),
+            10,
+            5,
+        );
+        let packs = add_node(
+            &mut engine,
+            make_recipe(vec![(gear(), 1), (copper(), 1)], vec![(science(), 1)], 5),
+            5,
+            5,
+        );
+        let lab = add_node(
+            &mut engine,
+            make_recipe(vec![(science(), 1)], vec![], 10),
+            5,
+            0,
+        );
+        connect(&mut engine, mine, gears, make_item_transport(4));
+        connect(&mut engine, gears, packs, make_item_transport(4));
+        connect(&mut engine, packs, lab, make_item_transport(4));
+        for _ in 0..100 {
+            engine.step();
+        }
+
+        let digest = engine.bottleneck_digest();
+        let science_entry = digest.get(science()).unwrap();
+        assert_eq!(science_entry.starved_nodes, 1);
+        assert_eq!(science_entry.worst_starved, Some(lab));
+        assert_eq!(science_entry.blocked_nodes, 0);
+        assert_eq!(science_entry.worst_blocked, None);
+
+        let gear_entry = digest.get(gear()).unwrap();
+        assert_eq!(gear_entry.blocked_nodes, 1);
+        assert_eq!(gear_entry.worst_blocked, Some(gears));
+        assert_eq!(gear_entry.starved_nodes, 0);
+
+        let copper_entry = digest.get(copper()).unwrap();
+        assert_eq!(copper_entry.worst_starved, Some(packs));
+    }
+
+    #[test]
+    fn worst_offender_breaks_ties_by_lowest_id() {
+        let mut engine = Engine::new(SimulationStrategy::Tick);
+        let needs = |n| make_recipe(vec![(iron(), n)], vec![(gear(), 1)], 1);
+        let a = add_node(&mut engine, needs(2), 10, 10);
+        let b = add_node(&mut engine, needs(5), 10, 10);
+        let c = add_node(&mut engine, needs(5), 10, 10);
+        engine.step();
+
+        let digest = engine.bottleneck_digest();
+        let iron_entry = digest.get(iron()).unwrap();
+        assert_eq!(iron_entry.starved_nodes, 3);
+        assert_eq!(iron_entry.blocked_nodes, 0);
+        assert_eq!(iron_entry.worst_starved, Some(b.min(c)));
+        assert_ne!(iron_entry.worst_starved, Some(a));
+        assert_eq!(iron_entry.worst_blocked, None);
+        assert!(digest.get(gear()).is_none());
+    }
+
+    #[test]
+    fn full_sources_are_blocked_on_their_output() {
+        let mut engine = Engine::new(SimulationStrategy::Tick);
+        let mine = add_node(&mut engine, make_source(copper(), 1.0), 0, 2);
+        for _ in 0..5 {
+            engine.step();
+        }
+
+        let digest = engine.bottleneck_digest();
+        assert_eq!(
+            digest.items,
+            vec![ItemBottleneck {
+                item_type: copper(),
+                starved_nodes: 0,
+                blocked_nodes: 1,
+                worst_starved: None,
+                worst_blocked: Some(mine),
+            }]
+        );
+
+        // Emptying the output clears the stall, and the digest with it.
+        let _ = engine
+            .get_output_inventory_mut(mine)
+            .unwrap()
+            .take(copper(), 2);
+        engine.step();
+        assert!(engine.bottleneck_digest().is_empty());
+    }
+}
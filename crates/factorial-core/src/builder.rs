@@ -0,0 +1,581 @@
+//! Fluent construction of production graphs.
+//!
+//! [`FactoryBuilder`] declares named nodes and the transports between them,
+//! then materializes everything into a fresh [`Engine`] in one deterministic
+//! pass. Nodes are created in declaration order and edges after all nodes, so
+//! the same declarations always produce the same [`NodeId`]s and [`EdgeId`]s.
+//!
+//! ```rust
+//! use factorial_core::builder::{recipe_processor, FactoryBuilder};
+//! use factorial_core::id::ItemTypeId;
+//!
+//! let ore = ItemTypeId(0);
+//! let plate = ItemTypeId(1);
+//!
+//! let mut builder = FactoryBuilder::new();
+//! builder.node("miner").source(ore, 3.0).output_cap(100);
+//! builder
+//!     .node("smelter")
+//!     .processor(recipe_processor(vec![(ore, 1)], vec![(plate, 1)], 5));
+//! builder.belt("miner", "smelter", 1.0);
+//!
+//! let mut factory = builder.build().unwrap();
+//! let smelter = factory.node("smelter").unwrap();
+//! factory.engine.step();
+//! assert!(factory.engine.get_processor_state(smelter).is_some());
+//! ```
+
+use std::collections::BTreeMap;
+
+use crate::engine::Engine;
+use crate::fixed::Fixed64;
+use crate::id::*;
+use crate::item::Inventory;
+use crate::processor::*;
+use crate::sim::SimulationStrategy;
+use crate::transport::*;
+
+/// Default input/output inventory capacity for builder nodes.
+pub const DEFAULT_INVENTORY_CAPACITY: u32 = 100;
+
+/// Default slot count for belts created with [`FactoryBuilder::belt`].
+pub const DEFAULT_BELT_SLOTS: u32 = 10;
+
+// ===========================================================================
+// Errors
+// ===========================================================================
+
+/// Errors produced while materializing a [`FactoryBuilder`].
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum BuilderError {
+    /// Two nodes were declared with the same name.
+    #[error("duplicate node name: {0}")]
+    DuplicateNode(String),
+    /// A connection referenced a node name that was never declared.
+    #[error("unknown node name: {0}")]
+    UnknownNode(String),
+    /// Initial inventory contents exceed the node's inventory capacity.
+    #[error("initial contents of node {node} exceed inventory capacity")]
+    InventoryOverflow { node: String },
+}
+
+// ===========================================================================
+// Processor and transport constructors
+// ===========================================================================
+
+/// Infinite source producing `rate` items of `item` per tick.
+pub fn source_processor(item: ItemTypeId, rate: f64) -> Processor {
+    Processor::Source(SourceProcessor {
+        output_type: item,
+        base_rate: Fixed64::from_num(rate),
+        depletion: Depletion::Infinite,
+        accumulated: Fixed64::ZERO,
+        initial_properties: None,
+    })
+}
+
+/// Fixed recipe consuming `inputs` and producing `outputs` every `duration` ticks.
+pub fn recipe_processor(
+    inputs: Vec<(ItemTypeId, u32)>,
+    outputs: Vec<(ItemTypeId, u32)>,
+    duration: u32,
+) -> Processor {
+    Processor::Fixed(FixedRecipe {
+        inputs: inputs
+            .into_iter()
+            .map(|(item_type, quantity)| RecipeInput {
+                item_type,
+                quantity,
+                consumed: true,
+            })
+            .collect(),
+        outputs: outputs
+            .into_iter()
+            .map(|(item_type, quantity)| RecipeOutput {
+                item_type,
+                quantity,
+                bonus: None,
+                probability: None,
+            })
+            .collect(),
+        duration,
+        fluid_inputs: Vec::new(),
+        fluid_outputs: Vec::new(),
+        duration_per_unit: None,
+    })
+}
+
+/// Flow transport moving `rate` items per tick with a 1000-item buffer and no latency.
+pub fn flow_transport(rate: f64) -> Transport {
+    Transport::Flow(FlowTransport {
+        rate: Fixed64::from_num(rate),
+        buffer_capacity: Fixed64::from_num(1000),
+        latency: 0,
+    })
+}
+
+/// Single-lane belt with the given speed and slot count.
+pub fn belt_transport(speed: f64, slot_count: u32) -> Transport {
+    Transport::Item(ItemTransport {
+        speed: Fixed64::from_num(speed),
+        slot_count,
+        lanes: 1,
+        min_spacing: Fixed64::ONE,
+    })
+}
+
+/// Batch transport delivering `batch_size` items every `cycle_time` ticks.
+pub fn batch_transport(batch_size: u32, cycle_time: u32) -> Transport {
+    Transport::Batch(BatchTransport {
+        batch_size,
+        cycle_time,
+    })
+}
+
+/// Vehicle transport carrying `capacity` items with a one-way `travel_time`.
+pub fn vehicle_transport(capacity: u32, travel_time: u32) -> Transport {
+    Transport::Vehicle(VehicleTransport {
+        capacity,
+        travel_time,
+    })
+}
+
+// ===========================================================================
+// Declarations
+// ===========================================================================
+
+#[derive(Debug, Clone)]
+struct NodeSpec {
+    name: String,
+    building: BuildingTypeId,
+    processor: Option<Processor>,
+    input_capacity: u32,
+    output_capacity: u32,
+    initial_inputs: Vec<(ItemTypeId, u32)>,
+    initial_outputs: Vec<(ItemTypeId, u32)>,
+}
+
+#[derive(Debug, Clone)]
+struct LinkSpec {
+    from: String,
+    to: String,
+    transport: Transport,
+    item_filter: Option<ItemTypeId>,
+}
+
+/// Declarative builder for a production graph. See the [module docs](self).
+#[derive(Debug, Clone)]
+pub struct FactoryBuilder {
+    strategy: SimulationStrategy,
+    seed: Option<u64>,
+    nodes: Vec<NodeSpec>,
+    links: Vec<LinkSpec>,
+}
+
+impl Default for FactoryBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FactoryBuilder {
+    /// Builder for a tick-strategy engine with the default seed.
+    pub fn new() -> Self {
+        Self {
+            strategy: SimulationStrategy::Tick,
+            seed: None,
+            nodes: Vec::new(),
+            links: Vec::new(),
+        }
+    }
+
+    /// Set the simulation strategy of the built engine.
+    pub fn strategy(&mut self, strategy: SimulationStrategy) -> &mut Self {
+        self.strategy = strategy;
+        self
+    }
+
+    /// Seed the built engine's RNG.
+    pub fn seed(&mut self, seed: u64) -> &mut Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Declare a node. The returned [`NodeBuilder`] configures it in place.
+    ///
+    /// Declaring the same name twice makes [`build`](Self::build) fail with
+    /// [`BuilderError::DuplicateNode`].
+    pub fn node(&mut self, name: impl Into<String>) -> NodeBuilder<'_> {
+        self.nodes.push(NodeSpec {
+            name: name.into(),
+            building: BuildingTypeId(0),
+            processor: None,
+            input_capacity: DEFAULT_INVENTORY_CAPACITY,
+            output_capacity: DEFAULT_INVENTORY_CAPACITY,
+            initial_inputs: Vec::new(),
+            initial_outputs: Vec::new(),
+        });
+        let index = self.nodes.len() - 1;
+        NodeBuilder {
+            spec: &mut self.nodes[index],
+        }
+    }
+
+    /// Connect two named nodes with the given transport.
+    pub fn connect(
+        &mut self,
+        from: impl Into<String>,
+        to: impl Into<String>,
+        transport: Transport,
+    ) -> &mut Self {
+        self.links.push(LinkSpec {
+            from: from.into(),
+            to: to.into(),
+            transport,
+            item_filter: None,
+        });
+        self
+    }
+
+    /// Connect two named nodes with a transport that only carries `item`.
+    pub fn connect_filtered(
+        &mut self,
+        from: impl Into<String>,
+        to: impl Into<String>,
+        transport: Transport,
+        item: ItemTypeId,
+    ) -> &mut Self {
+        self.links.push(LinkSpec {
+            from: from.into(),
+            to: to.into(),
+            transport,
+            item_filter: Some(item),
+        });
+        self
+    }
+
+    /// Connect two named nodes with a single-lane belt of
+    /// [`DEFAULT_BELT_SLOTS`] slots moving at `speed` slots per tick.
+    pub fn belt(
+        &mut self,
+        from: impl Into<String>,
+        to: impl Into<String>,
+        speed: f64,
+    ) -> &mut Self {
+        self.connect(from, to, belt_transport(speed, DEFAULT_BELT_SLOTS))
+    }
+
+    /// Connect two named nodes with a flow transport of `rate` items per tick.
+    pub fn flow(&mut self, from: impl Into<String>, to: impl Into<String>, rate: f64) -> &mut Self {
+        self.connect(from, to, flow_transport(rate))
+    }
+
+    /// Create the engine and materialize every declaration.
+    ///
+    /// All names are validated before the engine is touched. Nodes and edges
+    /// are added through [`Engine::apply_mutations`], so the usual
+    /// `NodeAdded`/`EdgeAdded` events are delivered on the first step.
+    pub fn build(&self) -> Result<Factory, BuilderError> {
+        let mut names: BTreeMap<&str, usize> = BTreeMap::new();
+        for (index, spec) in self.nodes.iter().enumerate() {
+            if names.insert(spec.name.as_str(), index).is_some() {
+                return Err(BuilderError::DuplicateNode(spec.name.clone()));
+            }
+        }
+        for link in &self.links {
+            for name in [&link.from, &link.to] {
+                if !names.contains_key(name.as_str()) {
+                    return Err(BuilderError::UnknownNode(name.clone()));
+                }
+            }
+        }
+
+        let mut engine = match self.seed {
+            Some(seed) => Engine::new_with_seed(self.strategy.clone(), seed),
+            None => Engine::new(self.strategy.clone()),
+        };
+
+        let pending: Vec<_> = self
+            .nodes
+            .iter()
+            .map(|spec| engine.graph.queue_add_node(spec.building))
+            .collect();
+        let result = engine.apply_mutations();
+
+        let mut node_ids = Vec::with_capacity(self.nodes.len());
+        let mut nodes = BTreeMap::new();
+        for (spec, pending) in self.nodes.iter().zip(pending) {
+            let Some(node) = result.resolve_node(pending) else {
+                continue;
+            };
+            if let Some(processor) = &spec.processor {
+                engine.set_processor(node, processor.clone());
+            }
+            let mut input = Inventory::new(1, 1, spec.input_capacity);
+            for &(item, quantity) in &spec.initial_inputs {
+                if input.input_slots[0].add(item, quantity) > 0 {
+                    return Err(BuilderError::InventoryOverflow {
+                        node: spec.name.clone(),
+                    });
+                }
+            }
+            let mut output = Inventory::new(1, 1, spec.output_capacity);
+            for &(item, quantity) in &spec.initial_outputs {
+                if output.output_slots[0].add(item, quantity) > 0 {
+                    return Err(BuilderError::InventoryOverflow {
+                        node: spec.name.clone(),
+                    });
+                }
+            }
+            engine.set_input_inventory(node, input);
+            engine.set_output_inventory(node, output);
+            node_ids.push(node);
+            nodes.insert(spec.name.clone(), node);
+        }
+
+        let pending: Vec<_> = self
+            .links
+            .iter()
+            .map(|link| {
+                let from = node_ids[names[link.from.as_str()]];
+                let to = node_ids[names[link.to.as_str()]];
+                engine
+                    .graph
+                    .queue_connect_filtered(from, to, link.item_filter)
+            })
+            .collect();
+        let result = engine.apply_mutations();
+
+        let mut edges = Vec::with_capacity(self.links.len());
+        for (link, pending) in self.links.iter().zip(pending) {
+            if let Some(edge) = result.resolve_edge(pending) {
+                engine.set_transport(edge, link.transport.clone());
+                edges.push(edge);
+            }
+        }
+
+        Ok(Factory {
+            engine,
+            nodes,
+            edges,
+        })
+    }
+}
+
+/// Configures a single node declared with [`FactoryBuilder::node`].
+#[derive(Debug)]
+pub struct NodeBuilder<'a> {
+    spec: &'a mut NodeSpec,
+}
+
+impl NodeBuilder<'_> {
+    /// Building type recorded on the graph node (defaults to `BuildingTypeId(0)`).
+    pub fn building(self, building: BuildingTypeId) -> Self {
+        self.spec.building = building;
+        self
+    }
+
+    /// Attach an arbitrary processor.
+    pub fn processor(self, processor: Processor) -> Self {
+        self.spec.processor = Some(processor);
+        self
+    }
+
+    /// Attach an infinite source of `item` at `rate` items per tick.
+    pub fn source(self, item: ItemTypeId, rate: f64) -> Self {
+        self.processor(source_processor(item, rate))
+    }
+
+    /// Attach a fixed recipe.
+    pub fn recipe(
+        self,
+        inputs: Vec<(ItemTypeId, u32)>,
+        outputs: Vec<(ItemTypeId, u32)>,
+        duration: u32,
+    ) -> Self {
+        self.processor(recipe_processor(inputs, outputs, duration))
+    }
+
+    /// Attach a passthrough processor (junction-style node).
+    pub fn passthrough(self) -> Self {
+        self.processor(Processor::Passthrough)
+    }
+
+    /// Input inventory capacity (defaults to [`DEFAULT_INVENTORY_CAPACITY`]).
+    pub fn input_cap(self, capacity: u32) -> Self {
+        self.spec.input_capacity = capacity;
+        self
+    }
+
+    /// Output inventory capacity (defaults to [`DEFAULT_INVENTORY_CAPACITY`]).
+    pub fn output_cap(self, capacity: u32) -> Self {
+        self.spec.output_capacity = capacity;
+        self
+    }
+
+    /// Pre-fill the input inventory.
+    pub fn with_input(self, item: ItemTypeId, quantity: u32) -> Self {
+        self.spec.initial_inputs.push((item, quantity));
+        self
+    }
+
+    /// Pre-fill the output inventory.
+    pub fn with_output(self, item: ItemTypeId, quantity: u32) -> Self {
+        self.spec.initial_outputs.push((item, quantity));
+        self
+    }
+}
+
+// ===========================================================================
+// Result
+// ===========================================================================
+
+/// An engine produced by [`FactoryBuilder::build`] plus its name lookup.
+#[derive(Debug)]
+pub struct Factory {
+    pub engine: Engine,
+    /// Declared node names mapped to their ids.
+    pub nodes: BTreeMap<String, NodeId>,
+    /// Edge ids in connection declaration order.
+    pub edges: Vec<EdgeId>,
+}
+
+impl Factory {
+    /// Look up a node id by its declared name.
+    pub fn node(&self, name: &str) -> Option<NodeId> {
+        self.nodes.get(name).copied()
+    }
+
+    /// Consume the factory, returning the engine and the name map.
+    pub fn into_parts(self) -> (Engine, BTreeMap<String, NodeId>) {
+        (self.engine, self.nodes)
+    }
+}
+
+// ===========================================================================
+// Tests
+// ===========================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event::EventKind;
+    use crate::test_utils::{gear, input_quantity, iron, output_quantity};
+
+    fn chain() -> FactoryBuilder {
+        let mut builder = FactoryBuilder::new();
+        builder.node("miner").source(iron(), 3.0).output_cap(100);
+        builder
+            .node("smelter")
+            .recipe(vec![(iron(), 1)], vec![(gear(), 1)], 2);
+        builder.belt("miner", "smelter", 1.0);
+        builder
+    }
+
+    #[test]
+    fn builds_named_nodes_and_edges() {
+        let factory = chain().build().unwrap();
+        assert_eq!(factory.nodes.len(), 2);
+        assert_eq!(factory.edges.len(), 1);
+
+        let miner = factory.node("miner").unwrap();
+        let smelter = factory.node("smelter").unwrap();
+        let edge = factory.engine.graph.get_edge(factory.edges[0]).unwrap();
+        assert_eq!(edge.from, miner);
+        assert_eq!(edge.to, smelter);
+        assert!(factory.engine.get_transport(factory.edges[0]).is_some());
+        assert!(factory.node("missing").is_none());
+    }
+
+    #[test]
+    fn built_factory_produces() {
+        let mut factory = chain().build().unwrap();
+        let smelter = factory.node("smelter").unwrap();
+        for _ in 0..50 {
+            factory.engine.step();
+        }
+        assert!(output_quantity(&factory.engine, smelter, gear()) > 0);
+    }
+
+    #[test]
+    fn same_declarations_are_deterministic() {
+        let mut a = chain().build().unwrap();
+        let mut b = chain().build().unwrap();
+        assert_eq!(a.nodes, b.nodes);
+        assert_eq!(a.edges, b.edges);
+        for _ in 0..30 {
+            a.engine.step();
+            b.engine.step();
+        }
+        assert_eq!(a.engine.state_hash(), b.engine.state_hash());
+    }
+
+    #[test]
+    fn duplicate_node_name_is_rejected() {
+        let mut builder = chain();
+        builder.node("miner");
+        assert_eq!(
+            builder.build().unwrap_err(),
+            BuilderError::DuplicateNode("miner".into())
+        );
+    }
+
+    #[test]
+    fn unknown_node_reference_is_rejected() {
+        let mut builder = chain();
+        builder.flow("smelter", "storage", 1.0);
+        assert_eq!(
+            builder.build().unwrap_err(),
+            BuilderError::UnknownNode("storage".into())
+        );
+    }
+
+    #[test]
+    fn initial_contents_respect_capacity() {
+        let mut builder = FactoryBuilder::new();
+        builder
+            .node("chest")
+            .input_cap(10)
+            .with_input(iron(), 4)
+            .with_output(gear(), 2);
+        let factory = builder.build().unwrap();
+        let chest = factory.node("chest").unwrap();
+        assert_eq!(input_quantity(&factory.engine, chest, iron()), 4);
+        assert_eq!(output_quantity(&factory.engine, chest, gear()), 2);
+
+        let mut builder = FactoryBuilder::new();
+        builder.node("chest").input_cap(3).with_input(iron(), 4);
+        assert_eq!(
+            builder.build().unwrap_err(),
+            BuilderError::InventoryOverflow {
+                node: "chest".into()
+            }
+        );
+    }
+
+    #[test]
+    fn filtered_connection_records_filter() {
+        let mut builder = chain();
+        builder.node("sink");
+        builder.connect_filtered("smelter", "sink", flow_transport(2.0), gear());
+        let factory = builder.build().unwrap();
+        let edge = factory.engine.graph.get_edge(factory.edges[1]).unwrap();
+        assert_eq!(edge.item_filter, Some(gear()));
+    }
+
+    #[test]
+    fn graph_events_delivered_on_first_step() {
+        use std::cell::RefCell;
+        use std::rc::Rc;
+
+        let mut factory = chain().build().unwrap();
+        let added = Rc::new(RefCell::new(0u32));
+        let counter = added.clone();
+        factory.engine.on_passive(
+            EventKind::NodeAdded,
+            Box::new(move |_| *counter.borrow_mut() += 1),
+        );
+        factory.engine.step();
+        assert_eq!(*added.borrow(), 2);
+    }
+}
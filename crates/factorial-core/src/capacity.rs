@@ -0,0 +1,253 @@
+//! Inventory capacity transitions.
+//!
+//! Once per tick, during the component phase, the engine compares every
+//! inventory slot against its capacity and reports changes:
+//! [`Event::InventoryFull`] when a slot reaches its capacity and
+//! [`Event::InventorySpaceAvailable`] when a full slot drops below it. Like
+//! the fluid module's pressure events, these fire on transitions only, never
+//! for a slot that stays full.
+//!
+//! The set of full slots is session state. After deserialization it is
+//! rebuilt from the restored inventories without emitting events.
+
+use serde::{Deserialize, Serialize};
+
+use crate::engine::Engine;
+use crate::event::Event;
+use crate::id::{ItemTypeId, NodeId};
+use crate::item::{Inventory, InventorySlot};
+
+/// Which of a node's inventories a slot belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum InventorySide {
+    /// The input inventory's slots.
+    Input,
+    /// The output inventory's slots.
+    Output,
+}
+
+/// A slot that was at capacity at the end of the last tick.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct FullSlot {
+    side: InventorySide,
+    slot: u32,
+    /// The item the slot held when it filled, reported again when it drains.
+    item_type: ItemTypeId,
+}
+
+impl Engine {
+    /// Whether the given slot of `node` is at capacity, as of the end of the
+    /// last tick.
+    pub fn is_slot_full(&self, node: NodeId, side: InventorySide, slot: u32) -> bool {
+        self.full_slots
+            .get(node)
+            .is_some_and(|full| full.iter().any(|f| f.side == side && f.slot == slot))
+    }
+
+    /// Compare every slot with its state at the end of the last tick and emit
+    /// `InventoryFull` / `InventorySpaceAvailable` for those that changed.
+    pub(crate) fn detect_capacity_transitions(&mut self) {
+        let tick = self.sim_state.tick;
+        let nodes: Vec<NodeId> = self.graph.nodes().map(|(node, _)| node).collect();
+        for node in nodes {
+            let now = full_slots_of(self.inputs.get(node), self.outputs.get(node));
+            let before = self
+                .full_slots
+                .get(node)
+                .map(Vec::as_slice)
+                .unwrap_or_default();
+            if now.as_slice() == before {
+                continue;
+            }
+            for f in before {
+                if !now.iter().any(|n| n.side == f.side && n.slot == f.slot) {
+                    self.event_bus.emit(Event::InventorySpaceAvailable {
+                        node,
+                        side: f.side,
+                        slot: f.slot,
+                        item_type: f.item_type,
+                        tick,
+                    });
+                }
+            }
+            for n in &now {
+                if !before.iter().any(|f| f.side == n.side && f.slot == n.slot) {
+                    self.event_bus.emit(Event::InventoryFull {
+                        node,
+                        side: n.side,
+                        slot: n.slot,
+                        item_type: n.item_type,
+                        tick,
+                    });
+                }
+            }
+            if now.is_empty() {
+                self.full_slots.remove(node);
+            } else {
+                self.full_slots.insert(node, now);
+            }
+        }
+    }
+
+    /// Rebuild the full-slot set from the current inventories without
+    /// emitting events. Called after deserialization.
+    pub(crate) fn rebuild_full_slots(&mut self) {
+        self.full_slots.clear();
+        let nodes: Vec<NodeId> = self.graph.nodes().map(|(node, _)| node).collect();
+        for node in nodes {
+            let full = full_slots_of(self.inputs.get(node), self.outputs.get(node));
+            if !full.is_empty() {
+                self.full_slots.insert(node, full);
+            }
+        }
+    }
+}
+
+/// The full slots of a node's inventories, input side first, in slot order.
+fn full_slots_of(input: Option<&Inventory>, output: Option<&Inventory>) -> Vec<FullSlot> {
+    let input = input
+        .into_iter()
+        .flat_map(|inv| &inv.input_slots)
+        .map(|slot| (InventorySide::Input, slot));
+    let output = output
+        .into_iter()
+        .flat_map(|inv| &inv.output_slots)
+        .map(|slot| (InventorySide::Output, slot));
+    let mut full = Vec::new();
+    let mut index = [0u32; 2];
+    for (side, slot) in input.chain(output) {
+        let i = &mut index[side as usize];
+        if let Some(item_type) = full_item(slot) {
+            full.push(FullSlot {
+                side,
+                slot: *i,
+                item_type,
+            });
+        }
+        *i += 1;
+    }
+    full
+}
+
+/// The item a slot at capacity holds, or `None` if the slot has room.
+fn full_item(slot: &InventorySlot) -> Option<ItemTypeId> {
+    if slot.capacity == 0 || slot.total() < slot.capacity {
+        return None;
+    }
+    slot.stacks
+        .iter()
+        .find(|stack| stack.quantity > 0)
+        .map(|stack| stack.item_type)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::event::EventKind;
+    use crate::sim::SimulationStrategy;
+    use crate::test_utils::*;
+
+    /// A storage node: inventories but no processor.
+    fn chest(engine: &mut Engine) -> NodeId {
+        let pending = engine.graph.queue_add_node(building());
+        let node = engine.apply_mutations().resolve_node(pending).unwrap();
+        engine.set_input_inventory(node, simple_inventory(5));
+        engine.set_output_inventory(node, simple_inventory(5));
+        node
+    }
+
+    fn record(engine: &mut Engine) -> Rc<RefCell<Vec<Event>>> {
+        let seen = Rc::new(RefCell::new(Vec::new()));
+        for kind in [EventKind::InventoryFull, EventKind::InventorySpaceAvailable] {
+            let seen = seen.clone();
+            engine.on_passive(
+                kind,
+                Box::new(move |event| seen.borrow_mut().push(event.clone())),
+            );
+        }
+        seen
+    }
+
+    #[test]
+    fn filling_a_slot_emits_full_once() {
+        let mut engine = Engine::new(SimulationStrategy::Tick);
+        let chest = chest(&mut engine);
+        let seen = record(&mut engine);
+
+        let _ = engine
+            .get_input_inventory_mut(chest)
+            .unwrap()
+            .insert(iron(), 4);
+        engine.step();
+        assert!(seen.borrow().is_empty());
+
+        let _ = engine
+            .get_input_inventory_mut(chest)
+            .unwrap()
+            .insert(iron(), 1);
+        for _ in 0..3 {
+            engine.step();
+        }
+        assert_eq!(
+            *seen.borrow(),
+            vec![Event::InventoryFull {
+                node: chest,
+                side: InventorySide::Input,
+                slot: 0,
+                item_type: iron(),
+                tick: 1,
+            }]
+        );
+        assert!(engine.is_slot_full(chest, InventorySide::Input, 0));
+    }
+
+    #[test]
+    fn removing_an_item_emits_space_available_once() {
+        let mut engine = Engine::new(SimulationStrategy::Tick);
+        let chest = chest(&mut engine);
+        for slot in &mut engine.get_output_inventory_mut(chest).unwrap().output_slots {
+            let _ = slot.add(gear(), 5);
+        }
+        engine.step();
+        let seen = record(&mut engine);
+
+        for slot in &mut engine.get_output_inventory_mut(chest).unwrap().output_slots {
+            let _ = slot.remove(gear(), 1);
+        }
+        for _ in 0..3 {
+            engine.step();
+        }
+        assert_eq!(
+            *seen.borrow(),
+            vec![Event::InventorySpaceAvailable {
+                node: chest,
+                side: InventorySide::Output,
+                slot: 0,
+                item_type: gear(),
+                tick: 1,
+            }]
+        );
+        assert!(!engine.is_slot_full(chest, InventorySide::Output, 0));
+    }
+
+    #[test]
+    fn full_slots_survive_a_round_trip_without_events() {
+        let mut engine = Engine::new(SimulationStrategy::Tick);
+        let chest = chest(&mut engine);
+        let _ = engine
+            .get_input_inventory_mut(chest)
+            .unwrap()
+            .insert(iron(), 5);
+        engine.step();
+
+        let data = engine.serialize().unwrap();
+        let mut restored = Engine::deserialize(&data).unwrap();
+        let seen = record(&mut restored);
+        restored.step();
+        assert!(seen.borrow().is_empty());
+        assert!(restored.is_slot_full(chest, InventorySide::Input, 0));
+    }
+}
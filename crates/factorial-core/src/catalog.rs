@@ -0,0 +1,324 @@
+//! Runtime updates to the recipe catalog.
+//!
+//! [`Engine::update_recipe`] replaces a recipe definition in a running
+//! engine, so recipes tweaked in data files can be hot-reloaded without
+//! restarting a save. The new definition takes precedence over the
+//! registry's and is used the next time a node selects the recipe by id.
+//!
+//! Nodes that select recipes by id are [`Flexible`](Processor::Flexible)
+//! processors. A flexible node already crafting the recipe carries its own
+//! copy of the old definition, so the update decides what happens to that
+//! craft with an [`InFlightPolicy`]: it either finishes as it started, or is
+//! cancelled with its ingredients refunded. Processors configured with a
+//! recipe directly, and hand-crafting queues, copy their recipes when they
+//! are set up and are not affected.
+//!
+//! Updated definitions are part of snapshots and of the state hash. An
+//! engine that never updates a recipe hashes as it did before updates
+//! existed.
+
+use crate::dirty::DirtyTracker;
+use crate::engine::Engine;
+use crate::id::{ItemTypeId, NodeId, RecipeId};
+use crate::processor::{FixedRecipe, Processor, ProcessorState};
+
+/// What [`Engine::update_recipe`] does with crafts already in progress.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum InFlightPolicy {
+    /// Crafts in progress finish under the definition they started with.
+    #[default]
+    Finish,
+    /// Crafts in progress are cancelled and their consumed ingredients are
+    /// returned to the node's input inventory.
+    Cancel,
+}
+
+/// How one node was migrated by [`Engine::update_recipe`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RecipeMigration {
+    /// The node was not crafting the recipe. Its next craft of it uses the
+    /// new definition.
+    NextCraft,
+    /// The craft in progress finishes under the old definition; later
+    /// crafts use the new one.
+    FinishCurrent,
+    /// The craft in progress was cancelled.
+    Cancelled {
+        /// Ingredients placed back into the input inventory.
+        refunded: Vec<(ItemTypeId, u32)>,
+        /// Ingredients that no longer fit and were dropped.
+        overflow: Vec<(ItemTypeId, u32)>,
+    },
+}
+
+/// The result of [`Engine::update_recipe`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UpdateReport {
+    /// The recipe that was replaced.
+    pub recipe: RecipeId,
+    /// Every node that may select the recipe, in graph order, with what
+    /// happened to it.
+    pub nodes: Vec<(NodeId, RecipeMigration)>,
+}
+
+impl Engine {
+    /// Replace the definition of `id` with `recipe`.
+    ///
+    /// Every node that lists `id` among its candidate recipes is reported.
+    /// Nodes mid-craft on `id` are handled by `policy`; the rest pick up the
+    /// new definition the next time they select the recipe. Refunds under
+    /// [`InFlightPolicy::Cancel`] use the node's current efficiency, and
+    /// fluid ingredients are not refunded.
+    ///
+    /// `id` does not need to be in the registry: an unknown id becomes a
+    /// recipe that flexible nodes listing it can select.
+    pub fn update_recipe(
+        &mut self,
+        id: RecipeId,
+        recipe: FixedRecipe,
+        policy: InFlightPolicy,
+    ) -> UpdateReport {
+        let nodes: Vec<NodeId> = self
+            .graph
+            .nodes()
+            .filter(|&(node, _)| {
+                matches!(
+                    self.processors.get(node),
+                    Some(Processor::Flexible(flexible)) if flexible.recipes.contains(&id)
+                )
+            })
+            .map(|(node, _)| node)
+            .collect();
+
+        let mut report = UpdateReport {
+            recipe: id,
+            nodes: Vec::with_capacity(nodes.len()),
+        };
+        for node in nodes {
+            let crafting = matches!(
+                self.processor_states.get(node),
+                Some(ProcessorState::Working { .. })
+            ) && self.processors.get(node).and_then(Processor::active_recipe)
+                == Some(id);
+            let migration = match (crafting, policy) {
+                (false, _) => RecipeMigration::NextCraft,
+                (true, InFlightPolicy::Finish) => RecipeMigration::FinishCurrent,
+                (true, InFlightPolicy::Cancel) => self.cancel_flexible_craft(node),
+            };
+            report.nodes.push((node, migration));
+        }
+
+        self.recipe_overrides.insert(id, recipe);
+        self.hash_cache_cold = true;
+        self.dirty
+            .mark_partition(DirtyTracker::PARTITION_PROCESSORS);
+        report
+    }
+
+    /// The current definition of `id`: its latest
+    /// [`update_recipe`](Self::update_recipe), or else the registry's.
+    pub fn recipe(&self, id: RecipeId) -> Option<FixedRecipe> {
+        if let Some(recipe) = self.recipe_overrides.get(&id) {
+            return Some(recipe.clone());
+        }
+        self.registry
+            .as_ref()?
+            .get_recipe(id)
+            .map(|def| def.to_fixed_recipe())
+    }
+
+    /// Cancel the craft in progress at a flexible node and refund its
+    /// consumed ingredients.
+    fn cancel_flexible_craft(&mut self, node: NodeId) -> RecipeMigration {
+        let (Some(Processor::Flexible(flexible)), Some(state)) = (
+            self.processors.get_mut(node),
+            self.processor_states.get_mut(node),
+        ) else {
+            return RecipeMigration::NextCraft;
+        };
+        let modifiers = self.modifiers.get(node).map_or(&[][..], Vec::as_slice);
+        let consumed = flexible.cancel_cycle(state, modifiers);
+
+        let mut refunded = Vec::new();
+        let mut overflow = Vec::new();
+        for (item, quantity) in consumed {
+            let left = match self.inputs.get_mut(node) {
+                Some(inventory) => inventory.insert(item, quantity),
+                None => quantity,
+            };
+            if quantity > left {
+                refunded.push((item, quantity - left));
+            }
+            if left > 0 {
+                overflow.push((item, left));
+            }
+        }
+
+        self.hash_dirty_nodes.push(node);
+        self.dirty.mark_node(node);
+        self.dirty
+            .mark_partition(DirtyTracker::PARTITION_INVENTORIES);
+        RecipeMigration::Cancelled { refunded, overflow }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event::{Event, EventKind};
+    use crate::fixed::Ticks;
+    use crate::processor::{FlexibleProcessor, RecipeSelection};
+    use crate::registry::{RecipeEntry, RegistryBuilder};
+    use crate::sim::SimulationStrategy;
+    use crate::test_utils;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    const ORE: ItemTypeId = ItemTypeId(0);
+    const PLATE: ItemTypeId = ItemTypeId(1);
+
+    /// A flexible furnace running the 6-tick registry recipe "2 ore -> 1
+    /// plate" with `ore` ore on hand, and a log of the ticks it produced on.
+    fn smelting(ore: u32) -> (Engine, NodeId, Rc<RefCell<Vec<Ticks>>>) {
+        let mut builder = RegistryBuilder::new();
+        builder.register_item("ore", vec![]);
+        builder.register_item("plate", vec![]);
+        let entry = |item, quantity| RecipeEntry {
+            item,
+            quantity,
+            consumed: true,
+        };
+        builder.register_recipe("plate", vec![entry(ORE, 2)], vec![entry(PLATE, 1)], 6);
+        let mut engine =
+            Engine::new_with_registry(SimulationStrategy::Tick, builder.build().unwrap());
+        let furnace = test_utils::add_node(
+            &mut engine,
+            Processor::Flexible(FlexibleProcessor::new(
+                vec![RecipeId(0)],
+                RecipeSelection::FirstMatch,
+            )),
+            50,
+            50,
+        );
+        let _ = engine
+            .get_input_inventory_mut(furnace)
+            .unwrap()
+            .insert(ORE, ore);
+
+        let produced = Rc::new(RefCell::new(Vec::new()));
+        let log = Rc::clone(&produced);
+        engine.on_passive(
+            EventKind::ItemProduced,
+            Box::new(move |event| {
+                if let Event::ItemProduced { tick, .. } = event {
+                    log.borrow_mut().push(*tick);
+                }
+            }),
+        );
+        (engine, furnace, produced)
+    }
+
+    fn two_tick_plate() -> FixedRecipe {
+        match test_utils::make_recipe(vec![(ORE, 2)], vec![(PLATE, 1)], 2) {
+            Processor::Fixed(recipe) => recipe,
+            _ => unreachable!(),
+        }
+    }
+
+    /// Ore on hand plus ore already turned into plates.
+    fn ore_accounted(engine: &Engine, furnace: NodeId) -> u32 {
+        test_utils::input_quantity(engine, furnace, ORE)
+            + 2 * test_utils::output_quantity(engine, furnace, PLATE)
+    }
+
+    #[test]
+    fn finish_policy_completes_the_old_craft_first() {
+        let (mut engine, furnace, produced) = smelting(4);
+        engine.step();
+        engine.step();
+
+        let report = engine.update_recipe(RecipeId(0), two_tick_plate(), InFlightPolicy::Finish);
+        assert_eq!(
+            report.nodes,
+            vec![(furnace, RecipeMigration::FinishCurrent)]
+        );
+        for _ in 0..8 {
+            engine.step();
+        }
+        // The 6-tick craft started on tick 0 ends on tick 5; the next one
+        // takes the new 2 ticks.
+        assert_eq!(*produced.borrow(), vec![5, 7]);
+        assert_eq!(test_utils::output_quantity(&engine, furnace, PLATE), 2);
+        assert_eq!(test_utils::input_quantity(&engine, furnace, ORE), 0);
+    }
+
+    #[test]
+    fn cancel_policy_refunds_and_restarts_under_the_new_recipe() {
+        let (mut engine, furnace, produced) = smelting(4);
+        engine.step();
+        engine.step();
+        assert_eq!(test_utils::input_quantity(&engine, furnace, ORE), 2);
+
+        let report = engine.update_recipe(RecipeId(0), two_tick_plate(), InFlightPolicy::Cancel);
+        assert_eq!(
+            report.nodes,
+            vec![(
+                furnace,
+                RecipeMigration::Cancelled {
+                    refunded: vec![(ORE, 2)],
+                    overflow: vec![],
+                }
+            )]
+        );
+        assert_eq!(test_utils::input_quantity(&engine, furnace, ORE), 4);
+        assert_eq!(
+            engine.get_processor_state(furnace),
+            Some(&ProcessorState::Idle)
+        );
+
+        for _ in 0..8 {
+            engine.step();
+            assert_eq!(ore_accounted(&engine, furnace) % 2, 0);
+            assert!(ore_accounted(&engine, furnace) <= 4);
+        }
+        assert_eq!(*produced.borrow(), vec![3, 5]);
+        assert_eq!(test_utils::output_quantity(&engine, furnace, PLATE), 2);
+        assert_eq!(ore_accounted(&engine, furnace), 4);
+    }
+
+    #[test]
+    fn idle_nodes_use_the_new_recipe_on_their_next_craft() {
+        let (mut engine, furnace, produced) = smelting(0);
+        engine.step();
+        let report = engine.update_recipe(RecipeId(0), two_tick_plate(), InFlightPolicy::Cancel);
+        assert_eq!(report.nodes, vec![(furnace, RecipeMigration::NextCraft)]);
+        assert_eq!(engine.recipe(RecipeId(0)).map(|r| r.duration), Some(2));
+
+        let _ = engine
+            .get_input_inventory_mut(furnace)
+            .unwrap()
+            .insert(ORE, 2);
+        engine.step();
+        engine.step();
+        assert_eq!(*produced.borrow(), vec![2]);
+    }
+
+    #[test]
+    fn updated_recipes_are_hashed_and_saved() {
+        let (mut engine, _, _) = smelting(4);
+        let (mut updated, furnace, _) = smelting(4);
+        let _ = updated.update_recipe(RecipeId(0), two_tick_plate(), InFlightPolicy::Finish);
+        engine.step();
+        updated.step();
+        assert_ne!(engine.state_hash(), updated.state_hash());
+
+        // The restored engine has no registry but keeps the update.
+        let mut restored = Engine::deserialize(&updated.serialize().unwrap()).unwrap();
+        assert_eq!(restored.state_hash(), updated.state_hash());
+        assert_eq!(restored.recipe(RecipeId(0)).map(|r| r.duration), Some(2));
+        for _ in 0..3 {
+            restored.step();
+        }
+        assert_eq!(test_utils::output_quantity(&restored, furnace, PLATE), 2);
+    }
+}
@@ -0,0 +1,370 @@
+//! Item-type categories.
+//!
+//! A category groups item types -- every ore, every fluid, every science
+//! pack -- so hosts can filter and display them together. Each item type
+//! belongs to at most one category; [`Engine::items_in_category`] lists the
+//! members in ascending item-id order.
+//!
+//! Categories stand in for item lists in two places:
+//!
+//! - **Edge filters.** [`Engine::set_edge_category_filter`] lets an edge
+//!   carry any member of a category. A transport still carries one item type
+//!   at a time, so the edge picks the lowest-id member its source has on
+//!   hand and keeps carrying it while the source has more. When the source
+//!   runs out, the edge waits until its transport has emptied before it
+//!   switches to the next member. An edge's own item filter takes
+//!   precedence over its category.
+//! - **Demand processors.** [`Engine::set_demand_category`] keeps a demand
+//!   node's accepted types equal to the category's members, updating them as
+//!   membership changes.
+//!
+//! Categories, category filters and each edge's current member are part of
+//! snapshots and of the state hash. An engine that uses no categories hashes
+//! as it did before they existed.
+
+use crate::dirty::DirtyTracker;
+use crate::engine::Engine;
+use crate::id::{EdgeId, ItemCategoryId, ItemTypeId, NodeId};
+use crate::processor::Processor;
+use crate::transport::TransportState;
+
+impl Engine {
+    /// Put `item_type` in `category`, moving it out of any category it was
+    /// in.
+    pub fn set_item_category(&mut self, item_type: ItemTypeId, category: ItemCategoryId) {
+        let previous = self.item_categories.insert(item_type, category);
+        if previous == Some(category) {
+            return;
+        }
+        self.categories_changed(previous.into_iter().chain([category]));
+    }
+
+    /// Remove `item_type` from its category. Returns the category it was in.
+    pub fn clear_item_category(&mut self, item_type: ItemTypeId) -> Option<ItemCategoryId> {
+        let previous = self.item_categories.remove(&item_type)?;
+        self.categories_changed([previous]);
+        Some(previous)
+    }
+
+    /// The category `item_type` belongs to, if any.
+    pub fn item_category(&self, item_type: ItemTypeId) -> Option<ItemCategoryId> {
+        self.item_categories.get(&item_type).copied()
+    }
+
+    /// Every item type in `category`, in ascending id order.
+    pub fn items_in_category(&self, category: ItemCategoryId) -> Vec<ItemTypeId> {
+        self.item_categories
+            .iter()
+            .filter(|&(_, &c)| c == category)
+            .map(|(&item, _)| item)
+            .collect()
+    }
+
+    /// Let `edge` carry any member of `category`, or remove its category
+    /// filter with `None`. Items already in transit when the filter is
+    /// removed are delivered as the edge's unfiltered item type. Returns
+    /// `false` if the edge does not exist.
+    pub fn set_edge_category_filter(
+        &mut self,
+        edge: EdgeId,
+        category: Option<ItemCategoryId>,
+    ) -> bool {
+        if self.graph.get_edge(edge).is_none() {
+            return false;
+        }
+        match category {
+            Some(category) => {
+                self.edge_category_filters.insert(edge, category);
+            }
+            None => {
+                self.edge_category_filters.remove(edge);
+                self.category_picks.remove(edge);
+            }
+        }
+        self.hash_cache_cold = true;
+        self.dirty
+            .mark_partition(DirtyTracker::PARTITION_TRANSPORTS);
+        true
+    }
+
+    /// The category `edge` is filtered to, if any.
+    pub fn edge_category_filter(&self, edge: EdgeId) -> Option<ItemCategoryId> {
+        self.edge_category_filters.get(edge).copied()
+    }
+
+    /// The category member `edge` is currently carrying. `None` until a
+    /// member first reaches the edge.
+    pub fn edge_category_item(&self, edge: EdgeId) -> Option<ItemTypeId> {
+        self.category_picks.get(edge).copied()
+    }
+
+    /// Make the demand processor at `node` accept every member of
+    /// `category`, or go back to its own input type with `None`. Returns
+    /// `false` if `node` has no demand processor.
+    pub fn set_demand_category(&mut self, node: NodeId, category: Option<ItemCategoryId>) -> bool {
+        if !matches!(self.processors.get(node), Some(Processor::Demand(_))) {
+            return false;
+        }
+        match category {
+            Some(category) => {
+                self.demand_categories.insert(node, category);
+            }
+            None => {
+                self.demand_categories.remove(node);
+            }
+        }
+        let accepted = category.map(|c| self.items_in_category(c));
+        if let Some(Processor::Demand(demand)) = self.processors.get_mut(node) {
+            demand.accepted_types = accepted;
+        }
+        self.hash_cache_cold = true;
+        self.dirty.mark_node(node);
+        self.dirty
+            .mark_partition(DirtyTracker::PARTITION_PROCESSORS);
+        true
+    }
+
+    /// The category the demand processor at `node` follows, if any.
+    pub fn demand_category(&self, node: NodeId) -> Option<ItemCategoryId> {
+        self.demand_categories.get(node).copied()
+    }
+
+    /// Membership of `changed` categories moved: refresh the demand nodes
+    /// that follow them.
+    fn categories_changed(&mut self, changed: impl IntoIterator<Item = ItemCategoryId>) {
+        self.hash_cache_cold = true;
+        self.dirty.mark_partition(DirtyTracker::PARTITION_GRAPH);
+        for category in changed {
+            let nodes: Vec<NodeId> = self
+                .demand_categories
+                .iter()
+                .filter(|&(_, &c)| c == category)
+                .map(|(node, _)| node)
+                .collect();
+            if nodes.is_empty() {
+                continue;
+            }
+            let members = self.items_in_category(category);
+            for node in nodes {
+                if let Some(Processor::Demand(demand)) = self.processors.get_mut(node) {
+                    demand.accepted_types = Some(members.clone());
+                    self.dirty.mark_node(node);
+                }
+            }
+            self.dirty
+                .mark_partition(DirtyTracker::PARTITION_PROCESSORS);
+        }
+    }
+
+    /// Choose the item type a category-filtered edge moves this tick, and
+    /// whether it may load more of it. `None` while no member has reached
+    /// the edge.
+    pub(crate) fn pick_category_item(
+        &mut self,
+        edge: EdgeId,
+        source: NodeId,
+    ) -> Option<(ItemTypeId, bool)> {
+        let category = *self.edge_category_filters.get(edge)?;
+        let current = self.category_picks.get(edge).copied();
+        if let Some(item) = current {
+            let member = self.item_categories.get(&item) == Some(&category);
+            if member && self.output_quantity_of(source, item) > 0 {
+                return Some((item, true));
+            }
+            // Drain what is in transit before switching.
+            let empty = self
+                .transport_states
+                .get(edge)
+                .is_none_or(TransportState::is_empty);
+            if !empty {
+                return Some((item, false));
+            }
+        }
+        let next = self
+            .item_categories
+            .iter()
+            .filter(|&(_, &c)| c == category)
+            .map(|(&item, _)| item)
+            .find(|&item| self.output_quantity_of(source, item) > 0);
+        match next {
+            Some(item) => {
+                if current != Some(item) {
+                    self.category_picks.insert(edge, item);
+                }
+                Some((item, true))
+            }
+            None => current.map(|item| (item, false)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixed::Fixed64;
+    use crate::processor::{DemandProcessor, SourceProcessor};
+    use crate::sim::SimulationStrategy;
+    use crate::test_utils::{self, copper, gear, iron};
+
+    const ORE: ItemCategoryId = ItemCategoryId(1);
+
+    /// A store holding iron, copper and gear, feeding a sink over a belt
+    /// filtered to the ore category.
+    fn ore_line() -> (Engine, NodeId, NodeId, EdgeId) {
+        let mut engine = Engine::new(SimulationStrategy::Tick);
+        engine.set_item_category(iron(), ORE);
+        engine.set_item_category(copper(), ORE);
+        let store = test_utils::add_node(&mut engine, Processor::Passthrough, 50, 50);
+        // Waits for water that never comes, so deliveries stay in its input.
+        let sink = test_utils::add_node(
+            &mut engine,
+            test_utils::make_recipe(vec![(test_utils::water(), 1)], vec![(gear(), 1)], 1),
+            50,
+            50,
+        );
+        let outputs = engine.get_output_inventory_mut(store).unwrap();
+        let slot = &mut outputs.output_slots[0];
+        let _ = slot.add(gear(), 3);
+        let _ = slot.add(copper(), 2);
+        let _ = slot.add(iron(), 2);
+        let edge =
+            test_utils::connect(&mut engine, store, sink, test_utils::make_item_transport(1));
+        assert!(engine.set_edge_category_filter(edge, Some(ORE)));
+        (engine, store, sink, edge)
+    }
+
+    #[test]
+    fn category_filter_moves_every_member_and_nothing_else() {
+        let (mut engine, store, sink, edge) = ore_line();
+        for _ in 0..20 {
+            engine.step();
+        }
+        assert_eq!(test_utils::input_quantity(&engine, sink, iron()), 2);
+        assert_eq!(test_utils::input_quantity(&engine, sink, copper()), 2);
+        assert_eq!(test_utils::input_quantity(&engine, sink, gear()), 0);
+        let outputs = engine.get_output_inventory(store).unwrap();
+        assert_eq!(outputs.count_of(gear()), 3);
+        // Iron has the lower id, so it went first.
+        assert_eq!(engine.edge_category_item(edge), Some(copper()));
+    }
+
+    #[test]
+    fn category_queries_are_deterministic() {
+        let mut engine = Engine::new(SimulationStrategy::Tick);
+        for id in [7, 3, 9, 1] {
+            engine.set_item_category(ItemTypeId(id), ORE);
+        }
+        engine.set_item_category(ItemTypeId(5), ItemCategoryId(2));
+        let members = [ItemTypeId(1), ItemTypeId(3), ItemTypeId(7), ItemTypeId(9)];
+        assert_eq!(engine.items_in_category(ORE), members);
+
+        // Moving an item changes both categories.
+        engine.set_item_category(ItemTypeId(3), ItemCategoryId(2));
+        assert_eq!(
+            engine.items_in_category(ItemCategoryId(2)),
+            [ItemTypeId(3), ItemTypeId(5)]
+        );
+        assert_eq!(
+            engine.clear_item_category(ItemTypeId(3)),
+            Some(ItemCategoryId(2))
+        );
+        assert_eq!(engine.item_category(ItemTypeId(3)), None);
+        assert!(engine.items_in_category(ItemCategoryId(9)).is_empty());
+    }
+
+    #[test]
+    fn demand_category_follows_membership() {
+        let mut engine = Engine::new(SimulationStrategy::Tick);
+        engine.set_item_category(iron(), ORE);
+        let node = test_utils::add_node(
+            &mut engine,
+            Processor::Demand(DemandProcessor {
+                input_type: gear(),
+                base_rate: Fixed64::from_num(1),
+                accumulated: Fixed64::ZERO,
+                consumed_total: 0,
+                accepted_types: None,
+            }),
+            10,
+            10,
+        );
+        assert!(engine.set_demand_category(node, Some(ORE)));
+        engine.set_item_category(copper(), ORE);
+        let accepted = |engine: &Engine| match engine.processors.get(node) {
+            Some(Processor::Demand(d)) => d.accepted_types.clone(),
+            _ => unreachable!(),
+        };
+        assert_eq!(accepted(&engine), Some(vec![iron(), copper()]));
+
+        let _ = engine.get_input_inventory_mut(node).unwrap().input_slots[0].add(copper(), 3);
+        for _ in 0..3 {
+            engine.step();
+        }
+        assert_eq!(test_utils::input_quantity(&engine, node, copper()), 0);
+
+        assert!(engine.set_demand_category(node, None));
+        assert_eq!(accepted(&engine), None);
+        let source = test_utils::add_node(&mut engine, test_utils::make_source(iron(), 1.0), 5, 5);
+        assert!(!engine.set_demand_category(source, Some(ORE)));
+    }
+
+    #[test]
+    fn categories_survive_serialization() {
+        let (mut engine, _, _, edge) = ore_line();
+        for _ in 0..3 {
+            engine.step();
+        }
+        let restored = Engine::deserialize(&engine.serialize().unwrap()).unwrap();
+        assert_eq!(restored.items_in_category(ORE), [iron(), copper()]);
+        assert_eq!(restored.edge_category_filter(edge), Some(ORE));
+        assert_eq!(
+            restored.edge_category_item(edge),
+            engine.edge_category_item(edge)
+        );
+        assert_eq!(restored.state_hash(), engine.state_hash());
+
+        let snapshot = engine.serialize_partitioned().unwrap();
+        let mut restored = Engine::deserialize_partitioned(&snapshot).unwrap();
+        assert_eq!(
+            restored.edge_category_item(edge),
+            engine.edge_category_item(edge)
+        );
+        for _ in 0..10 {
+            engine.step();
+            restored.step();
+        }
+        assert_eq!(restored.state_hash(), engine.state_hash());
+    }
+
+    #[test]
+    fn categories_change_the_hash() {
+        let factory = || {
+            let mut engine = Engine::new(SimulationStrategy::Tick);
+            let src = test_utils::add_node(
+                &mut engine,
+                Processor::Source(SourceProcessor {
+                    output_type: iron(),
+                    base_rate: Fixed64::from_num(1),
+                    depletion: crate::processor::Depletion::Infinite,
+                    accumulated: Fixed64::ZERO,
+                    initial_properties: None,
+                }),
+                10,
+                10,
+            );
+            let sink = test_utils::add_node(&mut engine, Processor::Passthrough, 10, 10);
+            test_utils::connect(&mut engine, src, sink, test_utils::make_item_transport(2));
+            engine
+        };
+        let mut plain = factory();
+        let mut categorized = factory();
+        categorized.set_item_category(iron(), ORE);
+        plain.step();
+        categorized.step();
+        assert_ne!(plain.state_hash(), categorized.state_hash());
+        assert_eq!(
+            plain.state_hash_breakdown().transports,
+            categorized.state_hash_breakdown().transports
+        );
+    }
+}
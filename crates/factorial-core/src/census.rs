@@ -0,0 +1,220 @@
+//! Factory-wide item counts.
+//!
+//! [`Engine::item_census`] answers "how much of this item exists right now?"
+//! by walking every node inventory and every transport in one deterministic
+//! pass. [`Engine::census`] does the same for all item types at once.
+//!
+//! The census is recomputed on every call rather than kept as running
+//! counters: inventories are reachable mutably through
+//! [`Engine::get_input_inventory_mut`], module contexts and tick hooks, so
+//! counters maintained by the engine's own transfer paths could drift from
+//! the stored state. Comparing two censuses taken a step apart against the
+//! step's production and consumption events is the conservation check.
+//!
+//! Items in transit are attributed as follows: belt slots carry their own
+//! item type; flow buffers, batch accumulators and vehicle cargo are counted
+//! as the edge's filter type, or as the item type the edge currently carries
+//! when it has no filter. Flow buffers count the whole items taken from the
+//! source and not yet delivered.
+
+use std::collections::BTreeMap;
+
+use crate::engine::Engine;
+use crate::id::ItemTypeId;
+use crate::item::Inventory;
+use crate::transport::TransportState;
+
+/// Where the units of one item type are.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ItemCensus {
+    /// Units in node input inventories.
+    pub in_input_inventories: u64,
+    /// Units in node output inventories.
+    pub in_output_inventories: u64,
+    /// Units held by transports between their source and destination.
+    pub in_transit: u64,
+    /// Sum of the other three fields.
+    pub total: u64,
+}
+
+impl ItemCensus {
+    fn add(&mut self, location: Location, quantity: u64) {
+        match location {
+            Location::Input => self.in_input_inventories += quantity,
+            Location::Output => self.in_output_inventories += quantity,
+            Location::Transit => self.in_transit += quantity,
+        }
+        self.total += quantity;
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Location {
+    Input,
+    Output,
+    Transit,
+}
+
+impl Engine {
+    /// Count every unit of `item_type` in the factory: node inventories and
+    /// transports. Runs in O(nodes + edges).
+    pub fn item_census(&self, item_type: ItemTypeId) -> ItemCensus {
+        let mut census = ItemCensus::default();
+        self.visit_items(|item, location, quantity| {
+            if item == item_type {
+                census.add(location, quantity);
+            }
+        });
+        census
+    }
+
+    /// Count every unit of every item type in one pass. Item types with no
+    /// units are omitted.
+    pub fn census(&self) -> BTreeMap<ItemTypeId, ItemCensus> {
+        let mut census: BTreeMap<ItemTypeId, ItemCensus> = BTreeMap::new();
+        self.visit_items(|item, location, quantity| {
+            census.entry(item).or_default().add(location, quantity);
+        });
+        census
+    }
+
+    /// Call `f` for every non-empty stack and transport load, in graph order.
+    fn visit_items(&self, mut f: impl FnMut(ItemTypeId, Location, u64)) {
+        let mut visit_inventory = |inventory: Option<&Inventory>, location: Location| {
+            let Some(inventory) = inventory else {
+                return;
+            };
+            for slot in inventory.input_slots.iter().chain(&inventory.output_slots) {
+                for stack in slot.stacks.iter().filter(|s| s.quantity > 0) {
+                    f(stack.item_type, location, u64::from(stack.quantity));
+                }
+            }
+        };
+        for (node, _) in self.graph.nodes() {
+            visit_inventory(self.inputs.get(node), Location::Input);
+            visit_inventory(self.outputs.get(node), Location::Output);
+        }
+
+        for (edge, data) in self.graph.edges() {
+            let Some(state) = self.transport_states.get(edge) else {
+                continue;
+            };
+            let edge_item = || {
+                data.item_filter
+                    .or_else(|| self.category_picks.get(edge).copied())
+                    .unwrap_or_else(|| self.determine_item_type_for_edge(data.from))
+            };
+            match state {
+                TransportState::Item(belt) => {
+                    for (_, item) in belt.items() {
+                        f(item.item_type, Location::Transit, 1);
+                    }
+                }
+                TransportState::Flow(flow) => {
+                    let held = u64::from(flow.held());
+                    if held > 0 {
+                        f(edge_item(), Location::Transit, held);
+                    }
+                }
+                TransportState::Batch(batch) => {
+                    if batch.pending > 0 {
+                        f(edge_item(), Location::Transit, u64::from(batch.pending));
+                    }
+                }
+                TransportState::Vehicle(vehicle) => {
+                    let held: u64 = vehicle.cargo.iter().map(|s| u64::from(s.quantity)).sum();
+                    if held > 0 {
+                        f(edge_item(), Location::Transit, held);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::processor::Processor;
+    use crate::rng::SimRng;
+    use crate::sim::SimulationStrategy;
+    use crate::test_utils::{self, iron};
+
+    /// Four passthrough nodes in a ring, one edge of each transport kind,
+    /// with `seed` iron placed in the first node.
+    fn closed_loop(seed: u32) -> (Engine, Vec<crate::id::NodeId>) {
+        let mut engine = Engine::new(SimulationStrategy::Tick);
+        let nodes: Vec<_> = (0..4)
+            .map(|_| test_utils::add_node(&mut engine, Processor::Passthrough, 50, 50))
+            .collect();
+        let transports = [
+            test_utils::make_flow_transport(1.5),
+            test_utils::make_item_transport(4),
+            test_utils::make_batch_transport(5, 3),
+            test_utils::make_vehicle_transport(7, 2),
+        ];
+        for (i, transport) in transports.into_iter().enumerate() {
+            test_utils::connect_filtered(
+                &mut engine,
+                nodes[i],
+                nodes[(i + 1) % 4],
+                transport,
+                Some(iron()),
+            );
+        }
+        let _ = engine
+            .get_input_inventory_mut(nodes[0])
+            .unwrap()
+            .insert(iron(), seed);
+        (engine, nodes)
+    }
+
+    #[test]
+    fn closed_loop_conserves_its_items() {
+        let (mut engine, _) = closed_loop(40);
+        let mut saw_transit = false;
+        for _ in 0..1000 {
+            engine.step();
+            let census = engine.item_census(iron());
+            assert_eq!(census.total, 40);
+            assert_eq!(
+                census.total,
+                census.in_input_inventories + census.in_output_inventories + census.in_transit
+            );
+            saw_transit |= census.in_transit > 0;
+        }
+        assert!(saw_transit);
+    }
+
+    #[test]
+    fn census_tracks_host_edits_between_steps() {
+        let (mut engine, nodes) = closed_loop(20);
+        let mut rng = SimRng::new(7);
+        let mut expected = 20u64;
+        for _ in 0..300 {
+            let node = nodes[rng.next_u64() as usize % nodes.len()];
+            let amount = (rng.next_u64() % 5) as u32;
+            if rng.next_u64().is_multiple_of(2) {
+                let inv = engine.get_input_inventory_mut(node).unwrap();
+                expected += u64::from(amount - inv.insert(iron(), amount));
+            } else {
+                let inv = engine.get_output_inventory_mut(node).unwrap();
+                expected -= u64::from(inv.take(iron(), amount));
+            }
+            engine.step();
+
+            let all = engine.census();
+            let census = engine.item_census(iron());
+            assert_eq!(census.total, expected);
+            assert_eq!(all.get(&iron()).copied().unwrap_or_default(), census);
+            assert_eq!(all.len(), usize::from(expected > 0));
+        }
+    }
+
+    #[test]
+    fn empty_engine_has_an_empty_census() {
+        let engine = Engine::new(SimulationStrategy::Tick);
+        assert!(engine.census().is_empty());
+        assert_eq!(engine.item_census(iron()), ItemCensus::default());
+    }
+}
@@ -20,13 +20,18 @@ use crate::transport::Transport;
 #[derive(Debug, Clone)]
 pub enum Command {
     /// Add a new node with the given building type.
-    AddNode { building_type: BuildingTypeId },
+    AddNode {
+        building_type: BuildingTypeId,
+        /// Mutation lane; see
+        /// [`ProductionGraph::queue_add_node_in_lane`](crate::graph::ProductionGraph::queue_add_node_in_lane).
+        lane: u8,
+    },
     /// Remove an existing node and all its edges.
-    RemoveNode { node: NodeId },
+    RemoveNode { node: NodeId, lane: u8 },
     /// Connect two nodes with a new edge.
-    Connect { from: NodeId, to: NodeId },
+    Connect { from: NodeId, to: NodeId, lane: u8 },
     /// Disconnect an existing edge.
-    Disconnect { edge: EdgeId },
+    Disconnect { edge: EdgeId, lane: u8 },
     /// Set the processor for a node.
     SetProcessor { node: NodeId, processor: Processor },
     /// Set the transport for an edge.
@@ -159,12 +164,14 @@ mod tests {
     fn make_add_node_cmd() -> Command {
         Command::AddNode {
             building_type: BuildingTypeId(0),
+            lane: 0,
         }
     }
 
     fn make_remove_node_cmd() -> Command {
         Command::RemoveNode {
             node: make_node_id(),
+            lane: 0,
         }
     }
 
@@ -172,6 +179,7 @@ mod tests {
         Command::Connect {
             from: make_node_id(),
             to: make_node_id(),
+            lane: 0,
         }
     }
 
@@ -210,6 +218,7 @@ mod tests {
             make_connect_cmd(),
             Command::Disconnect {
                 edge: make_edge_id(),
+                lane: 0,
             },
         ];
         queue.push_batch(commands);
@@ -239,13 +248,16 @@ mod tests {
         let mut queue = CommandQueue::new();
         queue.push(Command::AddNode {
             building_type: BuildingTypeId(1),
+            lane: 0,
         });
         queue.push(Command::RemoveNode {
             node: make_node_id(),
+            lane: 0,
         });
         queue.push(Command::Connect {
             from: make_node_id(),
             to: make_node_id(),
+            lane: 0,
         });
 
         let drained = queue.drain(0);
@@ -0,0 +1,848 @@
+//! Hand-crafting queues for player-driven production.
+//!
+//! A crafting queue belongs to an inventory node (typically a player's
+//! inventory) and works through registry recipes one craft at a time.
+//! Ingredients for every queued craft are taken from the node's input
+//! inventory when the craft is queued, and products are deposited back into
+//! the same inventory as each craft completes. Cancelling a job refunds the
+//! ingredients of the crafts that have not started yet.
+//!
+//! With recursive crafting enabled, missing ingredients are crafted first:
+//! the queue inserts intermediate jobs ahead of the requested one and
+//! withholds their products for it. Queues advance during the component
+//! phase and are part of the serialized engine state.
+
+use std::collections::{BTreeMap, VecDeque};
+
+use serde::{Deserialize, Serialize};
+
+use crate::engine::Engine;
+use crate::event::Event;
+use crate::fixed::{Fixed64, Ticks};
+use crate::id::{CraftingQueueId, ItemTypeId, NodeId, RecipeId};
+use crate::registry::{RecipeDef, Registry};
+use crate::sim::StateHash;
+use slotmap::Key;
+
+// ---------------------------------------------------------------------------
+// Queue state
+// ---------------------------------------------------------------------------
+
+/// One recipe request in a crafting queue, covering `remaining` crafts.
+///
+/// The recipe's consumed inputs, outputs and duration are copied in when
+/// the job is queued, so the queue keeps running without a registry (for
+/// example right after deserialization).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CraftJob {
+    /// Identifies the job within its queue. Never reused.
+    pub id: u64,
+    /// The recipe being crafted.
+    pub recipe: RecipeId,
+    /// Crafts not yet completed, including the one in progress.
+    pub remaining: u32,
+    /// Consumed ingredients per craft.
+    pub inputs: Vec<(ItemTypeId, u32)>,
+    /// Products per craft.
+    pub outputs: Vec<(ItemTypeId, u32)>,
+    /// Ticks per craft at speed 1.
+    pub duration: Ticks,
+    /// Products still owed to the job in `feeds`. They are withheld from
+    /// the inventory as crafts complete instead of being deposited.
+    pub withheld: Vec<(ItemTypeId, u32)>,
+    /// The job this intermediate was queued for by recursive crafting.
+    pub feeds: Option<u64>,
+}
+
+/// A hand-crafting queue attached to an inventory node.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CraftingQueue {
+    /// Node whose input inventory supplies ingredients and receives products.
+    pub inventory: NodeId,
+    /// Crafting speed multiplier. Each tick adds `speed` ticks of work.
+    pub speed: Fixed64,
+    /// Whether missing ingredients are crafted from other recipes.
+    pub recursive: bool,
+    /// Jobs in crafting order. The front job is the one being worked on.
+    pub jobs: VecDeque<CraftJob>,
+    /// Work done on the front craft, in ticks.
+    pub progress: Fixed64,
+    /// Whether the front craft has started.
+    pub started: bool,
+    next_job_id: u64,
+}
+
+impl CraftingQueue {
+    fn new(inventory: NodeId, speed: Fixed64) -> Self {
+        Self {
+            inventory,
+            speed,
+            recursive: false,
+            jobs: VecDeque::new(),
+            progress: Fixed64::ZERO,
+            started: false,
+            next_job_id: 0,
+        }
+    }
+
+    /// Total crafts left across all jobs.
+    pub fn remaining_crafts(&self) -> u32 {
+        self.jobs
+            .iter()
+            .fold(0u32, |acc, job| acc.saturating_add(job.remaining))
+    }
+}
+
+/// Progress summary for a crafting queue.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CraftProgress {
+    /// Recipe of the front job, or `None` when the queue is empty.
+    pub recipe: Option<RecipeId>,
+    /// Completion of the front craft as a 0..1 fraction.
+    pub progress: Fixed64,
+    /// Crafts left across all jobs.
+    pub remaining: u32,
+    /// Number of jobs in the queue.
+    pub jobs: u32,
+}
+
+/// Items returned to the inventory by [`Engine::cancel_craft`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CraftRefund {
+    /// Crafts removed from the queue.
+    pub crafts: u32,
+    /// Ingredients placed back into the inventory.
+    pub items: Vec<(ItemTypeId, u32)>,
+    /// Ingredients that no longer fit and were dropped.
+    pub overflow: Vec<(ItemTypeId, u32)>,
+}
+
+/// Error type for crafting queue operations.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum CraftingError {
+    #[error("unknown crafting queue")]
+    UnknownQueue,
+    #[error("node {0:?} has no input inventory")]
+    NoInventory(NodeId),
+    #[error("crafting speed must be positive")]
+    InvalidSpeed,
+    #[error("recipe {0:?} is not in the engine's registry")]
+    UnknownRecipe(RecipeId),
+    #[error("missing {missing} of item {item:?}")]
+    MissingIngredients { item: ItemTypeId, missing: u32 },
+    #[error("job index {0} out of bounds")]
+    IndexOutOfBounds(usize),
+}
+
+// ---------------------------------------------------------------------------
+// Planning
+// ---------------------------------------------------------------------------
+
+/// Jobs and inventory withdrawals for one `queue_craft` call.
+struct CraftPlan {
+    jobs: Vec<CraftJob>,
+    take: BTreeMap<ItemTypeId, u32>,
+    available: BTreeMap<ItemTypeId, u32>,
+    next_job_id: u64,
+    recursive: bool,
+    /// Recipes on the current recursion path, to break cycles.
+    path: Vec<RecipeId>,
+}
+
+impl CraftPlan {
+    /// Plan `crafts` crafts of `recipe`. Intermediates are pushed before
+    /// the job they feed, so the plan is already in crafting order.
+    fn add(
+        &mut self,
+        registry: Option<&Registry>,
+        recipe_id: RecipeId,
+        recipe: &RecipeDef,
+        crafts: u32,
+        feeds: Option<(u64, ItemTypeId, u32)>,
+    ) -> Result<(), CraftingError> {
+        let id = self.next_job_id;
+        self.next_job_id += 1;
+        self.path.push(recipe_id);
+
+        for entry in &recipe.inputs {
+            let have = self.available.get(&entry.item).copied().unwrap_or(0);
+            if !entry.consumed {
+                // Catalysts must be present but are never crafted or taken.
+                if have < entry.quantity {
+                    return Err(CraftingError::MissingIngredients {
+                        item: entry.item,
+                        missing: entry.quantity - have,
+                    });
+                }
+                continue;
+            }
+            let needed = entry.quantity.saturating_mul(crafts);
+            let used = needed.min(have);
+            if used > 0 {
+                self.available.insert(entry.item, have - used);
+                *self.take.entry(entry.item).or_default() += used;
+            }
+            let shortfall = needed - used;
+            if shortfall == 0 {
+                continue;
+            }
+            let producer = registry
+                .filter(|_| self.recursive)
+                .and_then(|registry| find_producer(registry, entry.item, &self.path));
+            let Some((sub_id, sub_recipe, per_craft)) = producer else {
+                return Err(CraftingError::MissingIngredients {
+                    item: entry.item,
+                    missing: shortfall,
+                });
+            };
+            self.add(
+                registry,
+                sub_id,
+                sub_recipe,
+                shortfall.div_ceil(per_craft),
+                Some((id, entry.item, shortfall)),
+            )?;
+        }
+
+        self.path.pop();
+        self.jobs.push(CraftJob {
+            id,
+            recipe: recipe_id,
+            remaining: crafts,
+            inputs: recipe
+                .inputs
+                .iter()
+                .filter(|e| e.consumed)
+                .map(|e| (e.item, e.quantity))
+                .collect(),
+            outputs: recipe
+                .outputs
+                .iter()
+                .map(|e| (e.item, e.quantity))
+                .collect(),
+            duration: recipe.duration,
+            withheld: feeds
+                .map(|(_, item, qty)| vec![(item, qty)])
+                .unwrap_or_default(),
+            feeds: feeds.map(|(parent, _, _)| parent),
+        });
+        Ok(())
+    }
+}
+
+/// The lowest-id recipe that produces `item` and is not already on the
+/// recursion path, with its output quantity per craft.
+fn find_producer<'a>(
+    registry: &'a Registry,
+    item: ItemTypeId,
+    path: &[RecipeId],
+) -> Option<(RecipeId, &'a RecipeDef, u32)> {
+    (0..registry.recipe_count() as u32)
+        .map(RecipeId)
+        .filter(|id| !path.contains(id))
+        .find_map(|id| {
+            let recipe = registry.get_recipe(id)?;
+            let per_craft: u32 = recipe
+                .outputs
+                .iter()
+                .filter(|e| e.item == item)
+                .map(|e| e.quantity)
+                .sum();
+            (per_craft > 0).then_some((id, recipe, per_craft))
+        })
+}
+
+/// Add `qty` of `item` to a running item tally.
+fn tally(items: &mut BTreeMap<ItemTypeId, u32>, item: ItemTypeId, qty: u32) {
+    if qty > 0 {
+        let entry = items.entry(item).or_default();
+        *entry = entry.saturating_add(qty);
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Engine API
+// ---------------------------------------------------------------------------
+
+impl Engine {
+    /// Create a crafting queue drawing from and depositing into the input
+    /// inventory of `inventory_node`. `speed` scales recipe durations: at
+    /// speed 2 a 10-tick recipe takes 5 ticks.
+    pub fn create_crafting_queue(
+        &mut self,
+        inventory_node: NodeId,
+        speed: Fixed64,
+    ) -> Result<CraftingQueueId, CraftingError> {
+        if speed <= Fixed64::ZERO {
+            return Err(CraftingError::InvalidSpeed);
+        }
+        if !self.inputs.contains_key(inventory_node) {
+            return Err(CraftingError::NoInventory(inventory_node));
+        }
+        self.enable_extension(crate::extension::ExtensionId::Crafting);
+        self.dirty
+            .mark_partition(crate::dirty::DirtyTracker::PARTITION_INVENTORIES);
+        Ok(self
+            .crafting_queues
+            .insert(CraftingQueue::new(inventory_node, speed)))
+    }
+
+    /// Remove a crafting queue, refunding the ingredients of every craft
+    /// that has not completed, including one in progress.
+    pub fn remove_crafting_queue(&mut self, queue: CraftingQueueId) -> Option<CraftRefund> {
+        self.crafting_queues.get_mut(queue)?.started = false;
+        let mut refund = CraftRefund::default();
+        while !self.crafting_queues.get(queue)?.jobs.is_empty() {
+            let part = self.cancel_craft(queue, 0).ok()?;
+            refund.crafts += part.crafts;
+            refund.items.extend(part.items);
+            refund.overflow.extend(part.overflow);
+        }
+        self.crafting_queues.remove(queue)?;
+        Some(refund)
+    }
+
+    /// Look up a crafting queue.
+    pub fn crafting_queue(&self, queue: CraftingQueueId) -> Option<&CraftingQueue> {
+        self.crafting_queues.get(queue)
+    }
+
+    /// Iterate over all crafting queues.
+    pub fn crafting_queues(&self) -> impl Iterator<Item = (CraftingQueueId, &CraftingQueue)> {
+        self.crafting_queues.iter()
+    }
+
+    /// Enable or disable recursive crafting of missing ingredients.
+    /// Returns `false` if the queue does not exist.
+    pub fn set_crafting_recursive(&mut self, queue: CraftingQueueId, recursive: bool) -> bool {
+        let Some(queue) = self.crafting_queues.get_mut(queue) else {
+            return false;
+        };
+        queue.recursive = recursive;
+        self.dirty
+            .mark_partition(crate::dirty::DirtyTracker::PARTITION_INVENTORIES);
+        true
+    }
+
+    /// Queue `count` crafts of a registry recipe, taking all of their
+    /// ingredients from the inventory now.
+    ///
+    /// If ingredients are missing and the queue is recursive, intermediate
+    /// jobs are queued first for each missing item using the lowest-id
+    /// registry recipe that produces it. Otherwise, or if no recipe can make
+    /// the item, nothing is queued and `MissingIngredients` is returned.
+    pub fn queue_craft(
+        &mut self,
+        queue: CraftingQueueId,
+        recipe: RecipeId,
+        count: u32,
+    ) -> Result<(), CraftingError> {
+        let def = self
+            .registry
+            .as_ref()
+            .and_then(|registry| registry.get_recipe(recipe))
+            .cloned()
+            .ok_or(CraftingError::UnknownRecipe(recipe))?;
+        self.queue_craft_def(queue, recipe, &def, count)
+    }
+
+    /// Like [`queue_craft`](Self::queue_craft), but with the recipe given
+    /// directly instead of looked up in the registry. `recipe` is only used
+    /// to tag the job and its events. Recursive crafting still draws
+    /// intermediates from the registry, if one is attached.
+    pub fn queue_craft_def(
+        &mut self,
+        queue: CraftingQueueId,
+        recipe: RecipeId,
+        def: &RecipeDef,
+        count: u32,
+    ) -> Result<(), CraftingError> {
+        let queue_state = self
+            .crafting_queues
+            .get_mut(queue)
+            .ok_or(CraftingError::UnknownQueue)?;
+        let node = queue_state.inventory;
+        let inventory = self
+            .inputs
+            .get_mut(node)
+            .ok_or(CraftingError::NoInventory(node))?;
+        if count == 0 {
+            return Ok(());
+        }
+
+        let mut plan = CraftPlan {
+            jobs: Vec::new(),
+            take: BTreeMap::new(),
+            available: inventory.iter_items().collect(),
+            next_job_id: queue_state.next_job_id,
+            recursive: queue_state.recursive,
+            path: Vec::new(),
+        };
+        plan.add(self.registry.as_ref(), recipe, def, count, None)?;
+
+        for (&item, &qty) in &plan.take {
+            let mut left = qty;
+            for slot in &mut inventory.input_slots {
+                if left == 0 {
+                    break;
+                }
+                left -= slot.remove(item, left);
+            }
+        }
+        queue_state.next_job_id = plan.next_job_id;
+        queue_state.jobs.extend(plan.jobs);
+
+        self.hash_dirty_nodes.push(node);
+        self.dirty.mark_node(node);
+        self.dirty
+            .mark_partition(crate::dirty::DirtyTracker::PARTITION_INVENTORIES);
+        Ok(())
+    }
+
+    /// Cancel the job at `index` and refund ingredients for its crafts that
+    /// have not started. A craft already in progress keeps running.
+    ///
+    /// Jobs queued by recursive crafting are cancelled together with the
+    /// job they feed, so cancelling either one cancels the whole group.
+    /// Refunds that no longer fit in the inventory are dropped and reported
+    /// in [`CraftRefund::overflow`].
+    pub fn cancel_craft(
+        &mut self,
+        queue: CraftingQueueId,
+        index: usize,
+    ) -> Result<CraftRefund, CraftingError> {
+        let queue_state = self
+            .crafting_queues
+            .get_mut(queue)
+            .ok_or(CraftingError::UnknownQueue)?;
+        let target = queue_state
+            .jobs
+            .get(index)
+            .ok_or(CraftingError::IndexOutOfBounds(index))?;
+
+        // Walk up to the job the target was ultimately queued for, then
+        // gather it and every intermediate feeding it. Intermediates sit
+        // ahead of the job they feed, so one reverse pass finds them all.
+        let mut root = target;
+        while let Some(parent) = root
+            .feeds
+            .and_then(|id| queue_state.jobs.iter().find(|job| job.id == id))
+        {
+            root = parent;
+        }
+        let mut group = vec![root.id];
+        for job in queue_state.jobs.iter().rev() {
+            if job.feeds.is_some_and(|id| group.contains(&id)) && !group.contains(&job.id) {
+                group.push(job.id);
+            }
+        }
+        let root_id = group[0];
+
+        let mut crafts = 0u32;
+        let mut owed: BTreeMap<ItemTypeId, u32> = BTreeMap::new();
+        let mut unmade: BTreeMap<ItemTypeId, u32> = BTreeMap::new();
+        let started = queue_state.started;
+        for (pos, job) in queue_state.jobs.iter_mut().enumerate() {
+            if !group.contains(&job.id) {
+                continue;
+            }
+            let keep = u32::from(pos == 0 && started);
+            let cancelled = job.remaining - keep;
+            crafts += cancelled;
+            for &(item, qty) in &job.inputs {
+                tally(&mut owed, item, qty.saturating_mul(cancelled));
+            }
+            // Withheld products were paid for by the job they feed but were
+            // never made, so they must not be refunded through it.
+            if job.id != root_id {
+                for &(item, qty) in &job.withheld {
+                    tally(&mut unmade, item, qty);
+                }
+            }
+            job.remaining = keep;
+            job.withheld.clear();
+            job.feeds = None;
+        }
+        queue_state.jobs.retain(|job| job.remaining > 0);
+        if queue_state.jobs.is_empty() {
+            queue_state.progress = Fixed64::ZERO;
+            queue_state.started = false;
+        }
+
+        let node = queue_state.inventory;
+        let mut refund = CraftRefund {
+            crafts,
+            ..CraftRefund::default()
+        };
+        if let Some(inventory) = self.inputs.get_mut(node) {
+            for (item, qty) in owed {
+                let qty = qty.saturating_sub(unmade.get(&item).copied().unwrap_or(0));
+                if qty == 0 {
+                    continue;
+                }
+                let overflow = inventory.insert(item, qty);
+                if qty > overflow {
+                    refund.items.push((item, qty - overflow));
+                }
+                if overflow > 0 {
+                    refund.overflow.push((item, overflow));
+                }
+            }
+        }
+
+        self.hash_dirty_nodes.push(node);
+        self.dirty.mark_node(node);
+        self.dirty
+            .mark_partition(crate::dirty::DirtyTracker::PARTITION_INVENTORIES);
+        Ok(refund)
+    }
+
+    /// Progress of a crafting queue, or `None` if it does not exist.
+    pub fn crafting_progress(&self, queue: CraftingQueueId) -> Option<CraftProgress> {
+        let queue = self.crafting_queues.get(queue)?;
+        let front = queue.jobs.front();
+        let progress = match front {
+            Some(job) if job.duration > 0 => {
+                (queue.progress / Fixed64::saturating_from_num(job.duration)).min(Fixed64::ONE)
+            }
+            _ => Fixed64::ZERO,
+        };
+        Some(CraftProgress {
+            recipe: front.map(|job| job.recipe),
+            progress,
+            remaining: queue.remaining_crafts(),
+            jobs: queue.jobs.len() as u32,
+        })
+    }
+
+    /// Advance every crafting queue by one tick. Called from the component
+    /// phase.
+    pub(crate) fn advance_crafting_queues(&mut self) {
+        if self.crafting_queues.is_empty() {
+            return;
+        }
+        let tick = self.sim_state.tick;
+        for (_, queue) in &mut self.crafting_queues {
+            let node = queue.inventory;
+            if queue.jobs.is_empty() || self.ghost_nodes.contains_key(node) {
+                continue;
+            }
+            let Some(inventory) = self.inputs.get_mut(node) else {
+                continue;
+            };
+            queue.progress = queue.progress.saturating_add(queue.speed);
+
+            while let Some(job) = queue.jobs.front_mut() {
+                if !queue.started {
+                    queue.started = true;
+                    self.event_bus.emit(Event::CraftStarted {
+                        node,
+                        recipe: job.recipe,
+                        tick,
+                    });
+                }
+                let duration = Fixed64::saturating_from_num(job.duration);
+                if queue.progress < duration {
+                    break;
+                }
+
+                // Products owed to a later job stay out of the inventory.
+                let mut deposit: BTreeMap<ItemTypeId, u32> = BTreeMap::new();
+                for &(item, qty) in &job.outputs {
+                    tally(&mut deposit, item, qty);
+                }
+                let mut withheld = job.withheld.clone();
+                for (item, owed) in &mut withheld {
+                    if let Some(qty) = deposit.get_mut(item) {
+                        let held = (*qty).min(*owed);
+                        *qty -= held;
+                        *owed -= held;
+                    }
+                }
+                if deposit
+                    .iter()
+                    .any(|(&item, &qty)| inventory.free_space_for(item) < qty)
+                {
+                    // Inventory full: hold the finished craft until it fits.
+                    queue.progress = duration;
+                    break;
+                }
+                for (item, qty) in deposit {
+                    let overflow = inventory.insert(item, qty);
+                    debug_assert_eq!(overflow, 0, "free space was checked before depositing");
+                }
+                withheld.retain(|&(_, owed)| owed > 0);
+                job.withheld = withheld;
+                job.remaining -= 1;
+                queue.progress -= duration;
+                queue.started = false;
+                self.event_bus.emit(Event::CraftCompleted {
+                    node,
+                    recipe: job.recipe,
+                    remaining: job.remaining,
+                    tick,
+                });
+                if job.remaining == 0 {
+                    queue.jobs.pop_front();
+                }
+            }
+            if queue.jobs.is_empty() {
+                queue.progress = Fixed64::ZERO;
+            }
+            self.hash_dirty_nodes.push(node);
+        }
+        self.dirty
+            .mark_partition(crate::dirty::DirtyTracker::PARTITION_INVENTORIES);
+    }
+
+    /// Hash all crafting queues. Only called when at least one exists.
+    pub(crate) fn crafting_queue_hash(&self) -> u64 {
+        let mut h = StateHash::new();
+        for (id, queue) in &self.crafting_queues {
+            h.write_u64(id.data().as_ffi());
+            h.write_u64(queue.inventory.data().as_ffi());
+            h.write_fixed64(queue.speed);
+            h.write_fixed64(queue.progress);
+            h.write_u32(u32::from(queue.started) | (u32::from(queue.recursive) << 1));
+            h.write_u32(queue.jobs.len() as u32);
+            for job in &queue.jobs {
+                h.write_u64(job.id);
+                h.write_u32(job.recipe.0);
+                h.write_u32(job.remaining);
+                for &(item, qty) in &job.withheld {
+                    h.write_u32(item.0);
+                    h.write_u32(qty);
+                }
+            }
+        }
+        h.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event::{EventKind, HistoryFilter};
+    use crate::id::BuildingTypeId;
+    use crate::item::Inventory;
+    use crate::registry::{RecipeEntry, RegistryBuilder};
+    use crate::sim::SimulationStrategy;
+
+    const PLATE: ItemTypeId = ItemTypeId(0);
+    const GEAR: ItemTypeId = ItemTypeId(1);
+    const ORE: ItemTypeId = ItemTypeId(2);
+    const GEAR_RECIPE: RecipeId = RecipeId(0);
+
+    fn entry(item: ItemTypeId, quantity: u32) -> RecipeEntry {
+        RecipeEntry {
+            item,
+            quantity,
+            consumed: true,
+        }
+    }
+
+    /// Gears take 2 plates and 10 ticks; plates take 1 ore and 5 ticks.
+    fn setup(plates: u32, ore: u32) -> (Engine, NodeId, CraftingQueueId) {
+        let mut builder = RegistryBuilder::new();
+        builder.register_item("iron_plate", vec![]);
+        builder.register_item("gear", vec![]);
+        builder.register_item("iron_ore", vec![]);
+        builder.register_recipe("gear", vec![entry(PLATE, 2)], vec![entry(GEAR, 1)], 10);
+        builder.register_recipe("plate", vec![entry(ORE, 1)], vec![entry(PLATE, 1)], 5);
+        let mut engine =
+            Engine::new_with_registry(SimulationStrategy::Tick, builder.build().unwrap());
+
+        let pending = engine.graph.queue_add_node(BuildingTypeId(0));
+        let node = engine.apply_mutations().resolve_node(pending).unwrap();
+        let mut inventory = Inventory::new(1, 0, 100);
+        let _ = inventory.insert(PLATE, plates);
+        let _ = inventory.insert(ORE, ore);
+        engine.set_input_inventory(node, inventory);
+
+        let queue = engine
+            .create_crafting_queue(node, Fixed64::from_num(1))
+            .unwrap();
+        engine.set_event_history_capacity(256);
+        (engine, node, queue)
+    }
+
+    fn count(engine: &Engine, node: NodeId, item: ItemTypeId) -> u32 {
+        engine.get_input_inventory(node).unwrap().count_of(item)
+    }
+
+    fn completion_ticks(engine: &Engine) -> Vec<Ticks> {
+        engine
+            .event_history(HistoryFilter {
+                kinds: vec![EventKind::CraftCompleted],
+                ..HistoryFilter::default()
+            })
+            .map(Event::tick)
+            .collect()
+    }
+
+    #[test]
+    fn cancel_refunds_unstarted_crafts_exactly() {
+        let (mut engine, node, queue) = setup(20, 0);
+        engine.queue_craft(queue, GEAR_RECIPE, 3).unwrap();
+        engine.queue_craft(queue, GEAR_RECIPE, 2).unwrap();
+        // All five gears' ingredients are taken up front.
+        assert_eq!(count(&engine, node, PLATE), 10);
+        assert_eq!(engine.crafting_progress(queue).unwrap().remaining, 5);
+
+        for _ in 0..15 {
+            engine.step();
+        }
+        let progress = engine.crafting_progress(queue).unwrap();
+        assert_eq!(progress.remaining, 4);
+        assert_eq!(progress.progress, Fixed64::from_num(0.5));
+        assert_eq!(count(&engine, node, GEAR), 1);
+
+        // Cancel the second job (2 gears, not started).
+        let refund = engine.cancel_craft(queue, 1).unwrap();
+        assert_eq!(refund.crafts, 2);
+        assert_eq!(refund.items, vec![(PLATE, 4)]);
+        assert!(refund.overflow.is_empty());
+        assert_eq!(count(&engine, node, PLATE), 14);
+
+        for _ in 0..20 {
+            engine.step();
+        }
+        assert_eq!(count(&engine, node, GEAR), 3);
+        assert_eq!(count(&engine, node, PLATE), 14);
+        assert_eq!(engine.crafting_progress(queue).unwrap().jobs, 0);
+        assert_eq!(completion_ticks(&engine), vec![9, 19, 29]);
+    }
+
+    #[test]
+    fn cancelling_the_front_job_keeps_the_craft_in_progress() {
+        let (mut engine, node, queue) = setup(10, 0);
+        engine.queue_craft(queue, GEAR_RECIPE, 5).unwrap();
+        for _ in 0..3 {
+            engine.step();
+        }
+        let refund = engine.cancel_craft(queue, 0).unwrap();
+        assert_eq!(refund.crafts, 4);
+        assert_eq!(refund.items, vec![(PLATE, 8)]);
+
+        for _ in 0..7 {
+            engine.step();
+        }
+        assert_eq!(count(&engine, node, GEAR), 1);
+        assert_eq!(count(&engine, node, PLATE), 8);
+        assert_eq!(completion_ticks(&engine), vec![9]);
+    }
+
+    #[test]
+    fn speed_scales_craft_time() {
+        let (mut engine, node, _) = setup(4, 0);
+        let fast = engine
+            .create_crafting_queue(node, Fixed64::from_num(2))
+            .unwrap();
+        engine.queue_craft(fast, GEAR_RECIPE, 2).unwrap();
+        for _ in 0..10 {
+            engine.step();
+        }
+        assert_eq!(count(&engine, node, GEAR), 2);
+        assert_eq!(completion_ticks(&engine), vec![4, 9]);
+    }
+
+    #[test]
+    fn missing_ingredients_leave_inventory_untouched() {
+        let (mut engine, node, queue) = setup(3, 5);
+        assert_eq!(
+            engine.queue_craft(queue, GEAR_RECIPE, 2),
+            Err(CraftingError::MissingIngredients {
+                item: PLATE,
+                missing: 1
+            })
+        );
+        assert_eq!(count(&engine, node, PLATE), 3);
+        assert_eq!(engine.crafting_progress(queue).unwrap().jobs, 0);
+
+        assert_eq!(
+            engine.queue_craft(queue, RecipeId(9), 1),
+            Err(CraftingError::UnknownRecipe(RecipeId(9)))
+        );
+        assert_eq!(
+            engine.create_crafting_queue(node, Fixed64::ZERO),
+            Err(CraftingError::InvalidSpeed)
+        );
+    }
+
+    #[test]
+    fn recursive_crafting_queues_intermediates() {
+        let (mut engine, node, queue) = setup(0, 3);
+        assert!(engine.set_crafting_recursive(queue, true));
+        engine.queue_craft(queue, GEAR_RECIPE, 1).unwrap();
+
+        let jobs = &engine.crafting_queue(queue).unwrap().jobs;
+        assert_eq!(jobs.len(), 2);
+        assert_eq!(jobs[0].recipe, RecipeId(1));
+        assert_eq!(jobs[0].remaining, 2);
+        assert_eq!(count(&engine, node, ORE), 1);
+
+        for _ in 0..20 {
+            engine.step();
+        }
+        // Intermediate plates went straight into the gear.
+        assert_eq!(count(&engine, node, GEAR), 1);
+        assert_eq!(count(&engine, node, PLATE), 0);
+        assert_eq!(count(&engine, node, ORE), 1);
+        assert_eq!(completion_ticks(&engine), vec![4, 9, 19]);
+    }
+
+    #[test]
+    fn cancelling_a_recursive_job_refunds_only_what_was_made() {
+        let (mut engine, node, queue) = setup(0, 3);
+        engine.set_crafting_recursive(queue, true);
+        engine.queue_craft(queue, GEAR_RECIPE, 1).unwrap();
+        // First plate done, second in progress.
+        for _ in 0..7 {
+            engine.step();
+        }
+        let refund = engine.cancel_craft(queue, 1).unwrap();
+        assert_eq!(refund.crafts, 1);
+        // One plate already made is returned; the one in progress finishes.
+        assert_eq!(refund.items, vec![(PLATE, 1)]);
+
+        for _ in 0..3 {
+            engine.step();
+        }
+        assert_eq!(count(&engine, node, PLATE), 2);
+        assert_eq!(count(&engine, node, ORE), 1);
+        assert_eq!(count(&engine, node, GEAR), 0);
+        assert_eq!(engine.crafting_progress(queue).unwrap().jobs, 0);
+    }
+
+    #[test]
+    fn queues_survive_serialization() {
+        let (mut engine, node, queue) = setup(10, 0);
+        engine.queue_craft(queue, GEAR_RECIPE, 4).unwrap();
+        for _ in 0..13 {
+            engine.step();
+        }
+        let mut restored = Engine::deserialize(&engine.serialize().unwrap()).unwrap();
+        assert_eq!(restored.crafting_queue(queue), engine.crafting_queue(queue));
+        assert_eq!(restored.state_hash(), engine.state_hash());
+
+        for _ in 0..30 {
+            engine.step();
+            restored.step();
+        }
+        assert_eq!(count(&restored, node, GEAR), 4);
+        assert_eq!(restored.state_hash(), engine.state_hash());
+    }
+
+    #[test]
+    fn removing_a_queue_refunds_everything() {
+        let (mut engine, node, queue) = setup(10, 0);
+        engine.queue_craft(queue, GEAR_RECIPE, 5).unwrap();
+        for _ in 0..4 {
+            engine.step();
+        }
+        let refund = engine.remove_crafting_queue(queue).unwrap();
+        assert_eq!(refund.crafts, 5);
+        assert_eq!(count(&engine, node, PLATE), 10);
+        assert!(engine.crafting_queue(queue).is_none());
+    }
+}
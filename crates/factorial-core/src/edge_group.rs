@@ -0,0 +1,251 @@
+//! Edge groups: one logical transport line spanning several edges.
+//!
+//! A belt the player drew as one line is usually many engine edges, one per
+//! segment between buildings. An edge group lets the host treat those edges
+//! as a unit: reconfigure every segment with
+//! [`Engine::set_group_transport`] or ask whether any segment is backed up
+//! with [`Engine::group_is_congested`]. The line's throughput comes from the
+//! statistics module, which tracks deliveries per edge.
+//!
+//! Groups are bookkeeping only. They never change how the simulation runs
+//! and are not part of the state hash, but they are saved with the engine.
+//! An edge belongs to at most one group; removing an edge from the graph
+//! removes it from its group.
+
+use crate::dirty::DirtyTracker;
+use crate::engine::Engine;
+use crate::fixed::Fixed64;
+use crate::id::{EdgeGroupId, EdgeId};
+use crate::transport::{Transport, TransportState};
+
+impl Engine {
+    /// Group `edges` into one line, in the given order. Edges that are not
+    /// in the graph and repeated edges are skipped; an edge already in
+    /// another group moves to the new one.
+    pub fn create_edge_group(&mut self, edges: &[EdgeId]) -> EdgeGroupId {
+        let mut members = Vec::with_capacity(edges.len());
+        for &edge in edges {
+            if self.graph.contains_edge(edge) && !members.contains(&edge) {
+                self.ungroup_edge(edge);
+                members.push(edge);
+            }
+        }
+        let group = self.edge_groups.insert(members);
+        for &edge in &self.edge_groups[group] {
+            self.edge_group_of.insert(edge, group);
+        }
+        self.dirty.mark_partition(DirtyTracker::PARTITION_GRAPH);
+        group
+    }
+
+    /// Dissolve `group`. Its edges are untouched. Returns `false` if the
+    /// group does not exist.
+    pub fn remove_edge_group(&mut self, group: EdgeGroupId) -> bool {
+        let Some(members) = self.edge_groups.remove(group) else {
+            return false;
+        };
+        for edge in members {
+            self.edge_group_of.remove(edge);
+        }
+        self.dirty.mark_partition(DirtyTracker::PARTITION_GRAPH);
+        true
+    }
+
+    /// The edges of `group`, in the order they were grouped. Empty if the
+    /// group does not exist.
+    pub fn group_edges(&self, group: EdgeGroupId) -> &[EdgeId] {
+        self.edge_groups.get(group).map_or(&[], Vec::as_slice)
+    }
+
+    /// The group `edge` belongs to, if any.
+    pub fn edge_group(&self, edge: EdgeId) -> Option<EdgeGroupId> {
+        self.edge_group_of.get(edge).copied()
+    }
+
+    /// Whether `group` exists.
+    pub fn contains_edge_group(&self, group: EdgeGroupId) -> bool {
+        self.edge_groups.contains_key(group)
+    }
+
+    /// Set `transport` on every edge of `group`. Like
+    /// [`for_each_transport_mut`](Self::for_each_transport_mut), each edge
+    /// keeps its state -- and the items in transit -- when the state fits the
+    /// new transport, and starts fresh otherwise. Returns the number of edges
+    /// configured.
+    pub fn set_group_transport(&mut self, group: EdgeGroupId, transport: Transport) -> usize {
+        let Some(members) = self.edge_groups.get(group) else {
+            return 0;
+        };
+        let members = members.clone();
+        for &edge in &members {
+            if !self
+                .transport_states
+                .get(edge)
+                .is_some_and(|state| state.fits(&transport))
+            {
+                self.transport_states
+                    .insert(edge, TransportState::new_for(&transport));
+                self.latency.clear_in_flight(edge);
+            }
+            self.replace_transport(edge, Some(transport.clone()));
+            self.dirty.mark_edge(edge);
+        }
+        self.dirty
+            .mark_partition(DirtyTracker::PARTITION_TRANSPORTS);
+        members.len()
+    }
+
+    /// Whether any edge of `group` is at capacity (utilization of 1).
+    pub fn group_is_congested(&self, group: EdgeGroupId) -> bool {
+        self.group_edges(group)
+            .iter()
+            .any(|&edge| self.get_edge_utilization(edge) >= Some(Fixed64::ONE))
+    }
+
+    /// Take `edge` out of its group, if it has one.
+    pub(crate) fn ungroup_edge(&mut self, edge: EdgeId) {
+        let Some(group) = self.edge_group_of.remove(edge) else {
+            return;
+        };
+        if let Some(members) = self.edge_groups.get_mut(group) {
+            members.retain(|&member| member != edge);
+        }
+        self.dirty.mark_partition(DirtyTracker::PARTITION_GRAPH);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::id::NodeId;
+    use crate::processor::Processor;
+    use crate::sim::SimulationStrategy;
+    use crate::test_utils::{self, iron};
+    use crate::transport::ItemTransport;
+
+    /// A source feeding a sink through `segments` belt segments joined by
+    /// passthrough nodes.
+    fn belt_line(segments: usize) -> (Engine, Vec<NodeId>, Vec<EdgeId>) {
+        let mut engine = Engine::new(SimulationStrategy::Tick);
+        let mut nodes = vec![test_utils::add_node(
+            &mut engine,
+            test_utils::make_source(iron(), 1.0),
+            20,
+            20,
+        )];
+        let mut edges = Vec::new();
+        for _ in 0..segments {
+            let next = test_utils::add_node(&mut engine, Processor::Passthrough, 20, 20);
+            let edge = test_utils::connect(
+                &mut engine,
+                *nodes.last().unwrap(),
+                next,
+                test_utils::make_item_transport(4),
+            );
+            nodes.push(next);
+            edges.push(edge);
+        }
+        (engine, nodes, edges)
+    }
+
+    fn faster_belt() -> Transport {
+        Transport::Item(ItemTransport {
+            speed: Fixed64::from_num(2),
+            slot_count: 4,
+            lanes: 1,
+            min_spacing: Fixed64::ONE,
+        })
+    }
+
+    #[test]
+    fn group_transport_upgrades_every_segment_and_keeps_items() {
+        let (mut engine, _, edges) = belt_line(5);
+        let group = engine.create_edge_group(&edges);
+        for _ in 0..6 {
+            engine.step();
+        }
+        let in_transit = |engine: &Engine| -> Vec<usize> {
+            edges
+                .iter()
+                .map(|&edge| match engine.get_transport_state(edge) {
+                    Some(TransportState::Item(belt)) => belt.occupied_count(),
+                    _ => unreachable!(),
+                })
+                .collect()
+        };
+        let before = in_transit(&engine);
+        assert!(before.iter().sum::<usize>() > 0);
+
+        assert_eq!(engine.set_group_transport(group, faster_belt()), 5);
+        for &edge in &edges {
+            assert!(matches!(
+                engine.get_transport(edge),
+                Some(Transport::Item(belt)) if belt.speed == Fixed64::from_num(2)
+            ));
+        }
+        assert_eq!(in_transit(&engine), before);
+
+        // A different slot layout cannot keep the belt contents.
+        let longer = test_utils::make_item_transport(8);
+        engine.set_group_transport(group, longer);
+        assert_eq!(in_transit(&engine), vec![0; 5]);
+    }
+
+    #[test]
+    fn membership_survives_save_load_and_edge_removal() {
+        let (mut engine, _, edges) = belt_line(5);
+        let group = engine.create_edge_group(&edges);
+        engine.step();
+
+        let restored = Engine::deserialize(&engine.serialize().unwrap()).unwrap();
+        assert_eq!(restored.group_edges(group), edges);
+        assert_eq!(restored.edge_group(edges[2]), Some(group));
+        assert_eq!(restored.state_hash(), engine.state_hash());
+        let snapshot = engine.serialize_partitioned().unwrap();
+        let restored = Engine::deserialize_partitioned(&snapshot).unwrap();
+        assert_eq!(restored.group_edges(group), edges);
+
+        engine.graph.queue_disconnect(edges[2]);
+        engine.step();
+        assert_eq!(engine.edge_group(edges[2]), None);
+        let remaining = [edges[0], edges[1], edges[3], edges[4]];
+        assert_eq!(engine.group_edges(group), remaining);
+    }
+
+    #[test]
+    fn grouping_moves_edges_and_leaves_the_hash_alone() {
+        let (mut plain, _, _) = belt_line(3);
+        let (mut grouped, _, edges) = belt_line(3);
+        let first = grouped.create_edge_group(&edges);
+        let second = grouped.create_edge_group(&[edges[2], edges[2]]);
+        assert_eq!(grouped.group_edges(first), &edges[..2]);
+        assert_eq!(grouped.group_edges(second), [edges[2]]);
+
+        for _ in 0..10 {
+            plain.step();
+            grouped.step();
+        }
+        assert_eq!(plain.state_hash(), grouped.state_hash());
+
+        assert!(grouped.remove_edge_group(first));
+        assert!(!grouped.contains_edge_group(first));
+        assert!(grouped.group_edges(first).is_empty());
+        assert_eq!(grouped.edge_group(edges[0]), None);
+        assert!(!grouped.remove_edge_group(first));
+    }
+
+    #[test]
+    fn congestion_reports_any_full_segment() {
+        let (mut engine, nodes, edges) = belt_line(2);
+        let group = engine.create_edge_group(&edges);
+        assert!(!engine.group_is_congested(group));
+
+        // Block the last node so the final segment backs up.
+        engine.set_input_inventory(*nodes.last().unwrap(), crate::item::Inventory::new(1, 1, 0));
+        for _ in 0..30 {
+            engine.step();
+        }
+        assert_eq!(engine.get_edge_utilization(edges[1]), Some(Fixed64::ONE));
+        assert!(engine.group_is_congested(group));
+    }
+}
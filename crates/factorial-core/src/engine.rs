@@ -23,17 +23,32 @@
 
 use crate::event::{Event, EventBus, EventKind, EventMutation};
 use crate::fixed::{Fixed64, Ticks};
-use crate::graph::ProductionGraph;
-use crate::id::{EdgeId, ItemTypeId, NodeId, PropertyId};
+use crate::graph::{NodeStatus, ProductionGraph};
+use crate::hook::TickPhase;
+use crate::id::{EdgeId, ItemTypeId, NodeId, PropertyId, RecipeId};
 use crate::item::{Inventory, ItemStack};
-use crate::junction::{Junction, JunctionState};
-use crate::processor::{FixedRecipe, Modifier, Processor, ProcessorResult, ProcessorState};
-use crate::query::{NodeSnapshot, TransportSnapshot};
-use crate::sim::{AdvanceResult, SimState, SimulationStrategy, StateHash};
-use crate::transport::{Transport, TransportResult, TransportState};
+use crate::junction::{Junction, JunctionState, MergePriority, MergeTurn};
+use crate::oplog::{OperationKind, OperationRecord};
+use crate::processor::{
+    FixedRecipe, InputMode, Modifier, Processor, ProcessorResult, ProcessorState,
+};
+use crate::query::{
+    NodeSnapshot, TransportItemView, TransportProgress, TransportSnapshot, VehiclePhase,
+};
+use crate::sim::{AdvanceResult, SimState, SimulationStrategy, StateHash, StepPhase};
+use crate::transport::{PropertyGroup, Transport, TransportResult, TransportState};
 use slotmap::Key;
 use slotmap::SecondaryMap;
 
+/// One unit of process-phase work: a node, or with the `parallel` feature a
+/// topological level whose nodes tick in parallel.
+#[cfg(not(feature = "parallel"))]
+pub(crate) type ProcessUnit = NodeId;
+/// One unit of process-phase work: a node, or with the `parallel` feature a
+/// topological level whose nodes tick in parallel.
+#[cfg(feature = "parallel")]
+pub(crate) type ProcessUnit = Vec<NodeId>;
+
 // ---------------------------------------------------------------------------
 // Per-node hashing (free function for parallelism)
 // ---------------------------------------------------------------------------
@@ -45,15 +60,18 @@ fn hash_node_state(
     inputs: &SecondaryMap<NodeId, Inventory>,
     outputs: &SecondaryMap<NodeId, Inventory>,
     processor_states: &SecondaryMap<NodeId, ProcessorState>,
-) -> u64 {
-    let mut hasher = StateHash::new();
+    fluid_ports: &SecondaryMap<NodeId, crate::processor::FluidPorts>,
+    warmups: &SecondaryMap<NodeId, crate::warmup::Warmup>,
+) -> NodeHash {
+    let mut inventories = StateHash::new();
 
     // Hash input inventory.
     if let Some(inv) = inputs.get(node_id) {
         for slot in &inv.input_slots {
             for stack in &slot.stacks {
-                hasher.write_u32(stack.item_type.0);
-                hasher.write_u32(stack.quantity);
+                inventories.write_u32(stack.item_type.0);
+                inventories.write_u32(stack.quantity);
+                crate::spoilage::hash_item_ages(&mut inventories, &stack.ages);
             }
         }
     }
@@ -62,28 +80,126 @@ fn hash_node_state(
     if let Some(inv) = outputs.get(node_id) {
         for slot in &inv.output_slots {
             for stack in &slot.stacks {
-                hasher.write_u32(stack.item_type.0);
-                hasher.write_u32(stack.quantity);
+                inventories.write_u32(stack.item_type.0);
+                inventories.write_u32(stack.quantity);
+                crate::spoilage::hash_item_ages(&mut inventories, &stack.ages);
             }
         }
     }
 
     // Hash processor state.
+    let mut processor_state = StateHash::new();
     if let Some(ps) = processor_states.get(node_id) {
         match ps {
-            ProcessorState::Idle => hasher.write_u32(0),
+            ProcessorState::Idle => processor_state.write_u32(0),
             ProcessorState::Working { progress } => {
-                hasher.write_u32(1);
-                hasher.write_u32(*progress);
+                processor_state.write_u32(1);
+                processor_state.write_u32(*progress);
             }
             ProcessorState::Stalled { reason } => {
-                hasher.write_u32(2);
-                hasher.write_u32(*reason as u32);
+                processor_state.write_u32(2);
+                processor_state.write_u32(*reason as u32);
+            }
+            ProcessorState::Storing => processor_state.write_u32(3),
+        }
+    }
+    if let Some(warmup) = warmups.get(node_id) {
+        crate::warmup::hash_warmup(&mut processor_state, warmup);
+    }
+
+    // Hash buffered recipe fluids. Nodes without fluid ingredients
+    // contribute nothing.
+    let fluid = match fluid_ports.get(node_id).filter(|p| !p.is_empty()) {
+        Some(ports) => {
+            let mut h = StateHash::new();
+            h.write_u32(ports.input.len() as u32);
+            for (fluid, amount) in ports.input.iter().chain(ports.output.iter()) {
+                h.write_u32(fluid.0);
+                h.write_fixed64(*amount);
             }
+            h.finish()
+        }
+        None => 0,
+    };
+
+    NodeHash {
+        inventories: inventories.finish(),
+        processor_state: processor_state.finish(),
+        fluid,
+    }
+}
+
+/// One node's contribution to each per-node field of [`HashBreakdown`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct NodeHash {
+    inventories: u64,
+    processor_state: u64,
+    fluid: u64,
+}
+
+impl NodeHash {
+    fn wrapping_add(self, other: Self) -> Self {
+        Self {
+            inventories: self.inventories.wrapping_add(other.inventories),
+            processor_state: self.processor_state.wrapping_add(other.processor_state),
+            fluid: self.fluid.wrapping_add(other.fluid),
+        }
+    }
+
+    fn wrapping_sub(self, other: Self) -> Self {
+        Self {
+            inventories: self.inventories.wrapping_sub(other.inventories),
+            processor_state: self.processor_state.wrapping_sub(other.processor_state),
+            fluid: self.fluid.wrapping_sub(other.fluid),
         }
     }
+}
+
+/// The state hash split by subsystem, for localizing desyncs.
+///
+/// Each field hashes one part of the simulation state, and
+/// [`combined`](HashBreakdown::combined) -- their wrapping sum -- is
+/// [`Engine::state_hash`]. When two peers' state hashes disagree, comparing
+/// their breakdowns field by field shows which subsystem diverged.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct HashBreakdown {
+    /// Nodes, building types, edges, item filters, and ghost status.
+    pub graph: u64,
+    /// Input and output inventory contents, including item ages.
+    pub inventories: u64,
+    /// Processor runtime states: idle, working progress, or stall reason.
+    pub processor_states: u64,
+    /// Transport runtime states.
+    pub transports: u64,
+    /// The tick counter.
+    pub sim_state: u64,
+    /// Fluids buffered in recipe fluid ports.
+    pub fluid: u64,
+    /// Hand-crafting queues.
+    pub crafting: u64,
+    /// State reported by registered modules through
+    /// [`Module::state_hash`](crate::module::Module::state_hash), such as
+    /// tech tree research progress, plus the set of enabled
+    /// [extensions](crate::extension).
+    pub modules: u64,
+}
 
-    hasher.finish()
+impl HashBreakdown {
+    /// The overall state hash: the wrapping sum of every field.
+    pub fn combined(&self) -> u64 {
+        [
+            self.graph,
+            self.inventories,
+            self.processor_states,
+            self.transports,
+            self.sim_state,
+            self.fluid,
+            self.crafting,
+            self.modules,
+        ]
+        .into_iter()
+        .fold(0, u64::wrapping_add)
+    }
 }
 
 // ---------------------------------------------------------------------------
@@ -106,6 +222,15 @@ pub struct Engine {
     /// Whether the simulation is paused.
     pub(crate) paused: bool,
 
+    /// Work units a step may perform before its remaining simulation phases
+    /// are skipped. See [`set_step_work_limit`](Self::set_step_work_limit).
+    pub(crate) step_work_limit: Option<u64>,
+
+    /// Most fixed steps one delta-mode `advance` call may run, and what
+    /// happens to the rest. See
+    /// [`set_max_catchup_steps`](Self::set_max_catchup_steps).
+    pub(crate) max_catchup: Option<(u32, crate::sim::CatchupPolicy)>,
+
     // -- Per-node state (SoA, keyed by NodeId) --
     /// Processor configuration for each node.
     pub(crate) processors: SecondaryMap<NodeId, Processor>,
@@ -122,6 +247,10 @@ pub struct Engine {
     /// Modifiers applied to each node's processor.
     pub(crate) modifiers: SecondaryMap<NodeId, Vec<Modifier>>,
 
+    /// Input acquisition mode for each node. Missing entries are
+    /// [`InputMode::Push`].
+    pub(crate) input_modes: SecondaryMap<NodeId, InputMode>,
+
     // -- Per-edge state (SoA, keyed by EdgeId) --
     /// Transport configuration for each edge.
     pub(crate) transports: SecondaryMap<EdgeId, Transport>,
@@ -132,12 +261,19 @@ pub struct Engine {
     /// The most recently computed state hash.
     pub(crate) last_state_hash: u64,
 
+    /// Per-subsystem parts of `last_state_hash` (session state, rebuilt on
+    /// load).
+    pub(crate) last_hash_breakdown: HashBreakdown,
+
     /// Typed event bus for simulation events.
     pub event_bus: EventBus,
 
     /// Registered simulation modules.
     pub(crate) modules: Vec<Box<dyn crate::module::Module>>,
 
+    /// Registered tick hooks (session state, not serialized).
+    pub(crate) tick_hooks: crate::hook::TickHooks,
+
     /// Dirty state tracker.
     pub(crate) dirty: crate::dirty::DirtyTracker,
 
@@ -151,6 +287,16 @@ pub struct Engine {
     /// processing (splitter budget computation) and consumed by transport.
     pub(crate) edge_budgets: SecondaryMap<EdgeId, u32>,
 
+    /// Merge priority per node, deciding which incoming edge is served first.
+    pub(crate) merge_priorities: SecondaryMap<NodeId, MergePriority>,
+
+    /// Balanced-merge cursor per node: index of the incoming edge served first.
+    pub(crate) merge_cursors: SecondaryMap<NodeId, u32>,
+
+    /// Per-edge places in this tick's balanced merges. Rebuilt at the start
+    /// of transport and consumed as the edges advance.
+    pub(crate) merge_turns: SecondaryMap<EdgeId, MergeTurn>,
+
     /// Reusable buffer for iterating transport edge IDs (avoids per-tick alloc).
     pub(crate) transport_edge_buf: Vec<EdgeId>,
 
@@ -164,10 +310,10 @@ pub struct Engine {
     pub(crate) registry: Option<crate::registry::Registry>,
 
     /// Per-node hash cache for incremental state hashing.
-    pub(crate) node_hash_cache: SecondaryMap<NodeId, u64>,
+    pub(crate) node_hash_cache: SecondaryMap<NodeId, NodeHash>,
 
     /// Running sum of all per-node hashes (order-independent via wrapping_add).
-    pub(crate) combined_node_hash: u64,
+    pub(crate) combined_node_hash: NodeHash,
 
     /// Nodes whose hash needs recomputation this tick.
     pub(crate) hash_dirty_nodes: Vec<NodeId>,
@@ -182,9 +328,128 @@ pub struct Engine {
     /// Per-node PRNG for bonus output rolls (derived from `rng_seed`).
     pub(crate) node_rngs: SecondaryMap<NodeId, crate::rng::SimRng>,
 
+    /// Next instance id handed to an item entering a belt. Starts at 1;
+    /// `0` is reserved for untracked items.
+    pub(crate) next_item_instance_id: u64,
+
+    /// Per-node fluid buffers for recipes with fluid ingredients. Filled and
+    /// drained by the host (typically through a fluid bridge).
+    pub(crate) fluid_ports: SecondaryMap<NodeId, crate::processor::FluidPorts>,
+
+    /// Whether node additions are checked against
+    /// [`Module::allows_building`](crate::module::Module::allows_building).
+    /// Session configuration; not serialized.
+    pub(crate) strict_mode: bool,
+
+    /// Nodes in [`NodeStatus::Ghost`] status. Absent means active.
+    pub(crate) ghost_nodes: SecondaryMap<NodeId, ()>,
+
+    /// Edges in [`NodeStatus::Ghost`] status. Absent means active.
+    pub(crate) ghost_edges: SecondaryMap<EdgeId, ()>,
+
+    /// Whether ghosts count towards the state hash. See
+    /// [`set_hash_ghosts`](Self::set_hash_ghosts). Session configuration;
+    /// not serialized.
+    pub(crate) hash_ghosts: bool,
+
+    /// Hand-crafting queues, advanced during the component phase.
+    pub(crate) crafting_queues:
+        slotmap::SlotMap<crate::id::CraftingQueueId, crate::crafting::CraftingQueue>,
+
+    /// Per-item-type decay settings for perishable items.
+    pub(crate) item_decay: std::collections::BTreeMap<ItemTypeId, crate::spoilage::ItemDecay>,
+
+    /// Edge groups: logical transport lines spanning several edges.
+    pub(crate) edge_groups: slotmap::SlotMap<crate::id::EdgeGroupId, Vec<EdgeId>>,
+
+    /// The group each grouped edge belongs to.
+    pub(crate) edge_group_of: SecondaryMap<EdgeId, crate::id::EdgeGroupId>,
+
+    /// Category of each item type that has one.
+    pub(crate) item_categories: std::collections::BTreeMap<ItemTypeId, crate::id::ItemCategoryId>,
+
+    /// Edges that carry any member of a category instead of one item type.
+    pub(crate) edge_category_filters: SecondaryMap<EdgeId, crate::id::ItemCategoryId>,
+
+    /// The category member each category-filtered edge is carrying.
+    pub(crate) category_picks: SecondaryMap<EdgeId, ItemTypeId>,
+
+    /// Demand processors whose accepted types follow a category.
+    pub(crate) demand_categories: SecondaryMap<NodeId, crate::id::ItemCategoryId>,
+
+    /// Opaque host values set with
+    /// [`set_node_user_data`](Engine::set_node_user_data). Saved, not hashed.
+    pub(crate) node_user_data: SecondaryMap<NodeId, u64>,
+
+    /// Recipe definitions replaced at runtime by
+    /// [`update_recipe`](Engine::update_recipe). They take precedence over
+    /// the registry and, unlike it, are saved with the engine.
+    pub(crate) recipe_overrides:
+        std::collections::BTreeMap<RecipeId, crate::processor::FixedRecipe>,
+
+    /// Noise field for [`add_source_at`](Engine::add_source_at).
+    pub(crate) richness_provider: Option<crate::richness::RichnessProvider>,
+
+    /// Coordinates and richness of sources added by
+    /// [`add_source_at`](Engine::add_source_at).
+    pub(crate) source_sites: SecondaryMap<NodeId, crate::richness::SourceSite>,
+
+    /// Warmup and cooldown settings and timers per node.
+    pub(crate) warmups: SecondaryMap<NodeId, crate::warmup::Warmup>,
+
+    /// Inventory slots at capacity as of the end of the last tick, for
+    /// capacity transition events. Session-only: rebuilt on deserialize.
+    pub(crate) full_slots: SecondaryMap<NodeId, Vec<crate::capacity::FullSlot>>,
+
+    /// Event log being recorded, if enabled. Session-only: not serialized.
+    pub(crate) event_log: Option<crate::event::EventLog>,
+
+    /// The most recent events across steps, if enabled via
+    /// [`set_event_history_capacity`](Self::set_event_history_capacity).
+    /// Session-only: not serialized.
+    pub(crate) event_history: Option<crate::event::EventBuffer>,
+
+    /// Whether `serialize` includes `event_history`.
+    pub(crate) serialize_event_history: bool,
+
+    /// Subscriber cursors over `event_history`. Session-only: not
+    /// serialized.
+    pub(crate) event_cursors: slotmap::SlotMap<crate::id::EventCursorId, crate::event::EventCursor>,
+
+    /// Whether [`serialize`](Self::serialize) records item totals in the
+    /// save summary. Session-only: not serialized.
+    pub(crate) summary_item_totals: bool,
+
+    /// Per-edge transit latency histograms. Session-only: not serialized.
+    pub(crate) latency: crate::latency::LatencyTracker,
+
+    /// Operation logger. Session-only: not serialized.
+    pub(crate) operation_log: crate::oplog::OperationLog,
+
+    /// Enabled extensions, as a mask of [`ExtensionId::bit`](crate::extension::ExtensionId::bit)s.
+    pub(crate) extensions: u32,
+
+    /// Tick-zero initialization state. Session-only: not serialized.
+    pub(crate) init: crate::init::InitState,
+
+    /// Operation counters for the step in progress. `Some` while profiling
+    /// is enabled via [`set_profiling`](Self::set_profiling). Not serialized.
+    pub(crate) step_counters: Option<crate::profiling::StepProfile>,
+
+    /// Operation counts from the most recent completed step.
+    pub(crate) last_step_profile: Option<crate::profiling::StepProfile>,
+
     /// Timing profile for the most recent tick (profiling feature only).
     #[cfg(feature = "profiling")]
     pub(crate) last_profile: Option<crate::profiling::TickProfile>,
+
+    /// The sliced tick in progress, if any. See
+    /// [`begin_tick`](Self::begin_tick). Not serialized.
+    pub(crate) tick_slice: Option<crate::slice::TickSlice>,
+
+    /// Cached adjacency snapshot. See
+    /// [`topology_snapshot`](Self::topology_snapshot). Not serialized.
+    pub(crate) topology: crate::topology::TopologyCache,
 }
 
 impl Engine {
@@ -205,32 +470,73 @@ impl Engine {
             strategy,
             sim_state: SimState::new(),
             paused: false,
+            step_work_limit: None,
+            max_catchup: None,
             processors: SecondaryMap::new(),
             processor_states: SecondaryMap::new(),
             inputs: SecondaryMap::new(),
             outputs: SecondaryMap::new(),
             modifiers: SecondaryMap::new(),
+            input_modes: SecondaryMap::new(),
             transports: SecondaryMap::new(),
             transport_states: SecondaryMap::new(),
             last_state_hash: 0,
+            last_hash_breakdown: HashBreakdown::default(),
             event_bus: EventBus::default(),
             modules: Vec::new(),
+            tick_hooks: Default::default(),
             dirty: crate::dirty::DirtyTracker::new(),
             junctions: SecondaryMap::new(),
             junction_states: SecondaryMap::new(),
             edge_budgets: SecondaryMap::new(),
+            merge_priorities: SecondaryMap::new(),
+            merge_cursors: SecondaryMap::new(),
+            merge_turns: SecondaryMap::new(),
             transport_edge_buf: Vec::new(),
             input_buf: Vec::new(),
             node_item_type_cache: SecondaryMap::new(),
             registry: None,
             node_hash_cache: SecondaryMap::new(),
-            combined_node_hash: 0,
+            combined_node_hash: NodeHash::default(),
             hash_dirty_nodes: Vec::new(),
             hash_cache_cold: true,
             rng_seed: 0,
             node_rngs: SecondaryMap::new(),
+            next_item_instance_id: 1,
+            fluid_ports: SecondaryMap::new(),
+            strict_mode: false,
+            ghost_nodes: SecondaryMap::new(),
+            ghost_edges: SecondaryMap::new(),
+            hash_ghosts: false,
+            crafting_queues: slotmap::SlotMap::with_key(),
+            item_decay: std::collections::BTreeMap::new(),
+            edge_groups: slotmap::SlotMap::with_key(),
+            edge_group_of: SecondaryMap::new(),
+            item_categories: std::collections::BTreeMap::new(),
+            edge_category_filters: SecondaryMap::new(),
+            category_picks: SecondaryMap::new(),
+            demand_categories: SecondaryMap::new(),
+            node_user_data: SecondaryMap::new(),
+            recipe_overrides: std::collections::BTreeMap::new(),
+            richness_provider: None,
+            source_sites: SecondaryMap::new(),
+            warmups: SecondaryMap::new(),
+            full_slots: SecondaryMap::new(),
+            event_log: None,
+            event_history: None,
+            serialize_event_history: false,
+            event_cursors: slotmap::SlotMap::with_key(),
+            summary_item_totals: false,
+            latency: Default::default(),
+            operation_log: Default::default(),
+            extensions: 0,
+            init: Default::default(),
+            step_counters: None,
+            last_step_profile: None,
             #[cfg(feature = "profiling")]
             last_profile: None,
+            tick_slice: None,
+            topology: Default::default(),
         }
     }
 
@@ -278,17 +584,186 @@ impl Engine {
         self.registry.as_ref()
     }
 
+    // -----------------------------------------------------------------------
+    // Graph mutations
+    // -----------------------------------------------------------------------
+
+    /// Apply all queued graph mutations now and emit the matching graph
+    /// events (`NodeAdded`, `NodeRemoved`, `EdgeAdded`, `EdgeRemoved`).
+    ///
+    /// Hosts that mutate the graph between steps should call this instead of
+    /// `graph.apply_mutations()` so the engine can observe the change.
+    ///
+    /// # Event contract
+    ///
+    /// Graph events are stamped with the *upcoming* tick -- the value of
+    /// `sim_state.tick`, which is the tick the next `step()` will run -- and
+    /// are buffered on the event bus rather than dispatched immediately. They
+    /// are delivered together with the next step's events during its
+    /// post-tick phase, exactly like mutations applied by that step's own
+    /// pre-tick phase. Calling this several times between two steps is
+    /// equivalent to queueing everything and applying it once.
+    ///
+    /// # Strict mode
+    ///
+    /// With [`set_strict_mode`](Self::set_strict_mode) enabled, each queued
+    /// node addition is offered to every registered module's
+    /// [`allows_building`](crate::module::Module::allows_building). Refused
+    /// additions are skipped and listed in
+    /// [`MutationResult::rejected`](crate::graph::MutationResult::rejected);
+    /// the rest of the batch applies normally.
+    ///
+    /// # Ghosts
+    ///
+    /// Nodes and edges queued as ghosts are added in
+    /// [`NodeStatus::Ghost`] status. Real connections queued to or from any
+    /// ghost node are refused with
+    /// [`MutationRejectReason::GhostEndpoint`](crate::graph::MutationRejectReason::GhostEndpoint).
+    pub fn apply_mutations(&mut self) -> crate::graph::MutationResult {
+        let strict = self.strict_mode;
+        let modules = &self.modules;
+        let ghost_nodes = &self.ghost_nodes;
+        let result = self.graph.apply_mutations_checked(
+            |building_type| {
+                (strict && !modules.iter().all(|m| m.allows_building(building_type)))
+                    .then_some(crate::graph::MutationRejectReason::BuildingLocked)
+            },
+            |node| ghost_nodes.contains_key(node),
+        );
+        for &node in &result.ghost_nodes {
+            self.ghost_nodes.insert(node, ());
+        }
+        for &edge in &result.ghost_edges {
+            self.ghost_edges.insert(edge, ());
+        }
+        let tick = self.sim_state.tick;
+
+        // Silent nodes are silenced before their own `NodeAdded`.
+        for &(_, node) in &result.added_nodes {
+            if self
+                .graph
+                .get_node(node)
+                .is_some_and(|data| data.flags.contains(crate::graph::NodeFlags::SILENT))
+            {
+                self.event_bus.silence_node(node);
+            }
+        }
+
+        // Entities added and removed within the same batch were never
+        // observable, so they produce neither an added nor a removed event.
+        for &node in &result.removed_nodes {
+            if !result.added_nodes.iter().any(|&(_, n)| n == node) {
+                self.event_bus.emit(Event::NodeRemoved { node, tick });
+            }
+        }
+        for &edge in &result.removed_edges {
+            if !result.added_edges.iter().any(|&(_, e)| e == edge) {
+                self.event_bus.emit(Event::EdgeRemoved { edge, tick });
+            }
+        }
+        for &(_, node) in &result.added_nodes {
+            if let Some(data) = self.graph.get_node(node) {
+                self.event_bus.emit(Event::NodeAdded {
+                    node,
+                    building_type: data.building_type,
+                    tick,
+                });
+            }
+        }
+        for &(_, edge) in &result.added_edges {
+            if let Some(data) = self.graph.get_edge(edge) {
+                self.event_bus.emit(Event::EdgeAdded {
+                    edge,
+                    from: data.from,
+                    to: data.to,
+                    tick,
+                });
+            }
+        }
+
+        if self.has_operation_logger() {
+            self.log_mutations(&result, tick);
+        }
+
+        // Drop per-entity state for everything removed and let registered
+        // modules purge their own references to the dead nodes.
+        for &node in &result.removed_nodes {
+            self.remove_node_state(node);
+            for module in &mut self.modules {
+                module.on_node_removed(node);
+            }
+        }
+        for &edge in &result.removed_edges {
+            self.remove_edge_state(edge);
+        }
+        for &(_, node) in &result.added_nodes {
+            self.stock_from_template(node);
+        }
+
+        if !result.added_nodes.is_empty()
+            || !result.added_edges.is_empty()
+            || !result.removed_nodes.is_empty()
+            || !result.removed_edges.is_empty()
+        {
+            self.dirty.mark_graph();
+            self.dirty
+                .mark_partition(crate::dirty::DirtyTracker::PARTITION_GRAPH);
+            // Node set changed — rebuild the entire hash cache next bookkeeping.
+            self.hash_cache_cold = true;
+        }
+
+        result
+    }
+
     // -----------------------------------------------------------------------
     // Node management
     // -----------------------------------------------------------------------
 
+    /// Give a newly created node the inventories and starting contents of its
+    /// registry building template, if the template defines them. Contents
+    /// beyond capacity, or rejected by slot filters, are dropped.
+    fn stock_from_template(&mut self, node: NodeId) {
+        let Some(building_type) = self.graph.get_node(node).map(|data| data.building_type) else {
+            return;
+        };
+        let Some(template) = self
+            .registry
+            .as_ref()
+            .and_then(|registry| registry.get_building(building_type))
+        else {
+            return;
+        };
+        let Some(capacity) = template.inventory_capacity else {
+            return;
+        };
+
+        let mut input = Inventory::new(1, 1, capacity);
+        for &(item, quantity) in &template.initial_inputs {
+            let _ = input.insert(item, quantity);
+        }
+        let mut output = Inventory::new(1, 1, capacity);
+        for &(item, quantity) in &template.initial_outputs {
+            let mut remaining = quantity;
+            for slot in &mut output.output_slots {
+                remaining = slot.add(item, remaining);
+            }
+        }
+        self.set_input_inventory(node, input);
+        self.set_output_inventory(node, output);
+    }
+
     /// Set the processor for a node. Must be called after the node has been
-    /// added to the graph (i.e., after `apply_mutations`).
+    /// added to the graph (i.e., after `apply_mutations`); does nothing for a
+    /// node that is not in the graph.
     pub fn set_processor(&mut self, node: NodeId, processor: Processor) {
+        if !self.graph.contains_node(node) {
+            return;
+        }
         self.cache_item_type(node, &processor);
+        let state = Self::initial_state(&processor);
         self.processors.insert(node, processor);
-        self.processor_states
-            .insert(node, ProcessorState::default());
+        self.processor_states.insert(node, state);
+        self.source_sites.remove(node);
         // Derive per-node RNG from global seed ^ node raw key.
         let raw = node.data().as_ffi();
         self.node_rngs
@@ -298,12 +773,60 @@ impl Engine {
             .mark_partition(crate::dirty::DirtyTracker::PARTITION_PROCESSORS);
     }
 
+    /// Remove a node's processor, turning it back into a passive node.
+    ///
+    /// The node stays in the graph with its inventories and edges intact; its
+    /// state becomes `Idle` and it consumes and produces nothing until a new
+    /// processor is set. Any in-progress cycle is discarded. Returns the
+    /// removed processor, or `None` if the node had none.
+    pub fn clear_processor(&mut self, node: NodeId) -> Option<Processor> {
+        let removed = self.processors.remove(node)?;
+        if self.processor_states.contains_key(node) {
+            self.processor_states.insert(node, ProcessorState::Idle);
+        }
+        self.node_item_type_cache.remove(node);
+        self.node_rngs.remove(node);
+        self.source_sites.remove(node);
+        self.hash_dirty_nodes.push(node);
+        self.dirty.mark_node(node);
+        self.dirty
+            .mark_partition(crate::dirty::DirtyTracker::PARTITION_PROCESSORS);
+        Some(removed)
+    }
+
     /// Replace a node's processor and reset its processing state to Idle.
     /// Use this for dynamic recipe selection at runtime.
     pub fn swap_processor(&mut self, node: NodeId, processor: Processor) {
         self.cache_item_type(node, &processor);
+        let state = Self::initial_state(&processor);
         self.processors.insert(node, processor);
-        self.processor_states.insert(node, ProcessorState::Idle);
+        self.processor_states.insert(node, state);
+        self.source_sites.remove(node);
+    }
+
+    /// The state a freshly set processor starts in: `Storing` for storage
+    /// nodes, which never work or stall, and `Idle` for everything else.
+    fn initial_state(processor: &Processor) -> ProcessorState {
+        match processor {
+            Processor::Storage(_) => ProcessorState::Storing,
+            _ => ProcessorState::Idle,
+        }
+    }
+
+    /// Change how many output slots of a [`Processor::Storage`] node accept
+    /// deliveries; `None` makes every slot usable. Lowering the limit below
+    /// what the node holds keeps the items -- they can still be taken out,
+    /// but nothing new arrives until the usable slots have room. Returns
+    /// `false` if the node has no storage processor.
+    pub fn set_storage_limit(&mut self, node: NodeId, usable_slots: Option<u32>) -> bool {
+        let Some(Processor::Storage(storage)) = self.processors.get_mut(node) else {
+            return false;
+        };
+        storage.usable_slots = usable_slots;
+        self.dirty.mark_node(node);
+        self.dirty
+            .mark_partition(crate::dirty::DirtyTracker::PARTITION_PROCESSORS);
+        true
     }
 
     /// Switch the active recipe on a `MultiRecipe` processor.
@@ -389,6 +912,18 @@ impl Engine {
         }
     }
 
+    /// The registry recipe a `Flexible` node is crafting, or `None` if the
+    /// node is not mid-cycle or has another kind of processor.
+    pub fn get_active_recipe_id(&self, node: NodeId) -> Option<RecipeId> {
+        if !matches!(
+            self.processor_states.get(node),
+            Some(ProcessorState::Working { .. })
+        ) {
+            return None;
+        }
+        self.processors.get(node)?.active_recipe()
+    }
+
     /// Get the list of available recipes for a `MultiRecipe` processor.
     pub fn get_available_recipes(&self, node: NodeId) -> Option<&[FixedRecipe]> {
         match self.processors.get(node) {
@@ -412,10 +947,45 @@ impl Engine {
         }
     }
 
+    /// Visit every node's processor mutably, in graph order -- e.g. to scale
+    /// every recipe's duration when a mod loads.
+    ///
+    /// Processing state is kept, so a cycle in progress finishes against the
+    /// new configuration. A node whose processor the visitor replaces with a
+    /// different kind is reset to its initial state, as with
+    /// [`set_processor`](Self::set_processor). Every visited node is marked
+    /// dirty.
+    ///
+    /// The visitor sees only processors: nodes, edges and processors cannot
+    /// be added or removed during the pass.
+    pub fn for_each_processor_mut<F>(&mut self, mut visit: F)
+    where
+        F: FnMut(NodeId, &mut Processor),
+    {
+        let nodes: Vec<NodeId> = self.graph.nodes().map(|(node, _)| node).collect();
+        for node in nodes {
+            let Some(processor) = self.processors.get_mut(node) else {
+                continue;
+            };
+            let kind = std::mem::discriminant(&*processor);
+            visit(node, processor);
+            if std::mem::discriminant(&*processor) != kind {
+                let state = Self::initial_state(processor);
+                self.processor_states.insert(node, state);
+                self.hash_dirty_nodes.push(node);
+            }
+            self.dirty.mark_node(node);
+        }
+        self.rebuild_item_type_cache();
+        self.dirty
+            .mark_partition(crate::dirty::DirtyTracker::PARTITION_PROCESSORS);
+    }
+
     /// Extract and cache the output item type from a processor.
     fn cache_item_type(&mut self, node: NodeId, processor: &Processor) {
         let item_type = match processor {
             Processor::Source(src) => Some(src.output_type),
+            Processor::MultiSource(multi) => multi.outputs.first().map(|o| o.item_type),
             Processor::Fixed(recipe) => recipe.outputs.first().map(|o| o.item_type),
             Processor::Property(prop) => Some(prop.output_type),
             Processor::Demand(demand) => Some(demand.input_type),
@@ -424,6 +994,15 @@ impl Engine {
                 .recipes
                 .get(multi.active_recipe)
                 .and_then(|r| r.outputs.first().map(|o| o.item_type)),
+            Processor::WeightedRecipe(weighted) => weighted
+                .recipes
+                .get(weighted.selected.unwrap_or(0))
+                .and_then(|(r, _)| r.outputs.first().map(|o| o.item_type)),
+            Processor::Flexible(flexible) => flexible
+                .active
+                .as_ref()
+                .and_then(|(_, r)| r.outputs.first().map(|o| o.item_type)),
+            Processor::Storage(_) => None,
         };
         if let Some(it) = item_type {
             self.node_item_type_cache.insert(node, it);
@@ -438,6 +1017,7 @@ impl Engine {
             if let Some(processor) = self.processors.get(nid) {
                 let item_type = match processor {
                     Processor::Source(src) => Some(src.output_type),
+                    Processor::MultiSource(multi) => multi.outputs.first().map(|o| o.item_type),
                     Processor::Fixed(recipe) => recipe.outputs.first().map(|o| o.item_type),
                     Processor::Property(prop) => Some(prop.output_type),
                     Processor::Demand(demand) => Some(demand.input_type),
@@ -446,6 +1026,15 @@ impl Engine {
                         .recipes
                         .get(multi.active_recipe)
                         .and_then(|r| r.outputs.first().map(|o| o.item_type)),
+                    Processor::WeightedRecipe(weighted) => weighted
+                        .recipes
+                        .get(weighted.selected.unwrap_or(0))
+                        .and_then(|(r, _)| r.outputs.first().map(|o| o.item_type)),
+                    Processor::Flexible(flexible) => flexible
+                        .active
+                        .as_ref()
+                        .and_then(|(_, r)| r.outputs.first().map(|o| o.item_type)),
+                    Processor::Storage(_) => None,
                 };
                 if let Some(it) = item_type {
                     self.node_item_type_cache.insert(nid, it);
@@ -454,30 +1043,113 @@ impl Engine {
         }
     }
 
-    /// Set the input inventory for a node.
+    /// Set the input inventory for a node. Does nothing for a node that is
+    /// not in the graph.
     pub fn set_input_inventory(&mut self, node: NodeId, inventory: Inventory) {
+        if !self.graph.contains_node(node) {
+            return;
+        }
         self.inputs.insert(node, inventory);
         self.dirty.mark_node(node);
         self.dirty
             .mark_partition(crate::dirty::DirtyTracker::PARTITION_INVENTORIES);
     }
 
-    /// Set the output inventory for a node.
+    /// Set the output inventory for a node. Does nothing for a node that is
+    /// not in the graph.
     pub fn set_output_inventory(&mut self, node: NodeId, inventory: Inventory) {
+        if !self.graph.contains_node(node) {
+            return;
+        }
         self.outputs.insert(node, inventory);
         self.dirty.mark_node(node);
         self.dirty
             .mark_partition(crate::dirty::DirtyTracker::PARTITION_INVENTORIES);
     }
 
-    /// Set the modifiers for a node.
+    /// Remove a node's input inventory and return the items it held.
+    ///
+    /// The node remains valid; transports into it back up until a new input
+    /// inventory is set. Returns an empty list if the node had no inventory.
+    pub fn clear_input_inventory(&mut self, node: NodeId) -> Vec<ItemStack> {
+        let removed = self.inputs.remove(node);
+        self.take_cleared_inventory(node, removed)
+    }
+
+    /// Remove a node's output inventory and return the items it held.
+    ///
+    /// The node remains valid; its processor stalls on output space until a
+    /// new output inventory is set. Returns an empty list if the node had no
+    /// inventory.
+    pub fn clear_output_inventory(&mut self, node: NodeId) -> Vec<ItemStack> {
+        let removed = self.outputs.remove(node);
+        self.take_cleared_inventory(node, removed)
+    }
+
+    /// Flatten a removed inventory into its non-empty stacks and mark the
+    /// node dirty.
+    fn take_cleared_inventory(
+        &mut self,
+        node: NodeId,
+        inventory: Option<Inventory>,
+    ) -> Vec<ItemStack> {
+        let Some(inventory) = inventory else {
+            return Vec::new();
+        };
+        self.hash_dirty_nodes.push(node);
+        self.dirty.mark_node(node);
+        self.dirty
+            .mark_partition(crate::dirty::DirtyTracker::PARTITION_INVENTORIES);
+        inventory
+            .input_slots
+            .into_iter()
+            .chain(inventory.output_slots)
+            .flat_map(|slot| slot.stacks)
+            .filter(|stack| stack.quantity > 0)
+            .collect()
+    }
+
+    /// Set the modifiers for a node. Does nothing for a node that is not in
+    /// the graph.
     pub fn set_modifiers(&mut self, node: NodeId, mods: Vec<Modifier>) {
+        if !self.graph.contains_node(node) {
+            return;
+        }
         self.modifiers.insert(node, mods);
         self.dirty.mark_node(node);
         self.dirty
             .mark_partition(crate::dirty::DirtyTracker::PARTITION_PROCESSORS);
     }
 
+    /// Set how a node acquires its inputs. Does nothing for a node that is
+    /// not in the graph.
+    ///
+    /// With [`InputMode::Pull`] the node's incoming edges are advanced in the
+    /// process phase, just before the node runs, instead of in the transport
+    /// phase.
+    pub fn set_input_mode(&mut self, node: NodeId, mode: InputMode) {
+        if !self.graph.contains_node(node) {
+            return;
+        }
+        match mode {
+            InputMode::Push => {
+                self.input_modes.remove(node);
+            }
+            InputMode::Pull => {
+                self.input_modes.insert(node, mode);
+            }
+        }
+        self.dirty.mark_node(node);
+        self.dirty
+            .mark_partition(crate::dirty::DirtyTracker::PARTITION_PROCESSORS);
+    }
+
+    /// Get how a node acquires its inputs. Nodes default to
+    /// [`InputMode::Push`].
+    pub fn input_mode(&self, node: NodeId) -> InputMode {
+        self.input_modes.get(node).copied().unwrap_or_default()
+    }
+
     /// Get the processor configuration for a node (read-only).
     pub fn get_processor(&self, node: NodeId) -> Option<&Processor> {
         self.processors.get(node)
@@ -513,6 +1185,84 @@ impl Engine {
         self.outputs.get_mut(node)
     }
 
+    // -----------------------------------------------------------------------
+    // Recipe fluids
+    // -----------------------------------------------------------------------
+
+    /// Get a node's recipe fluid buffers, if it has any.
+    pub fn fluid_ports(&self, node: NodeId) -> Option<&crate::processor::FluidPorts> {
+        self.fluid_ports.get(node)
+    }
+
+    /// How much more of `fluid` the node's input buffer accepts: the active
+    /// recipe's per-cycle requirement minus what is already buffered.
+    ///
+    /// Returns zero if the node's recipe does not use the fluid.
+    pub fn fluid_input_space(&self, node: NodeId, fluid: ItemTypeId) -> Fixed64 {
+        let Some(processor) = self.processors.get(node) else {
+            return Fixed64::ZERO;
+        };
+        let need: Fixed64 = processor
+            .fluid_inputs()
+            .iter()
+            .filter(|f| f.fluid == fluid)
+            .map(|f| f.amount)
+            .sum();
+        let buffered = self
+            .fluid_ports
+            .get(node)
+            .map_or(Fixed64::ZERO, |p| p.input_of(fluid));
+        (need - buffered).max(Fixed64::ZERO)
+    }
+
+    /// Deliver fluid to a node's input buffer, up to
+    /// [`fluid_input_space`](Self::fluid_input_space). Returns the amount
+    /// accepted.
+    pub fn add_fluid_input(&mut self, node: NodeId, fluid: ItemTypeId, amount: Fixed64) -> Fixed64 {
+        let accepted = amount.min(self.fluid_input_space(node, fluid));
+        if accepted <= Fixed64::ZERO {
+            return Fixed64::ZERO;
+        }
+        let Some(ports) = self.fluid_ports.entry(node) else {
+            return Fixed64::ZERO;
+        };
+        *ports
+            .or_default()
+            .input
+            .entry(fluid)
+            .or_insert(Fixed64::ZERO) += accepted;
+        self.enable_extension(crate::extension::ExtensionId::Fluid);
+        self.mark_fluid_ports_dirty(node);
+        accepted
+    }
+
+    /// Remove up to `max` of `fluid` from a node's output buffer. Returns the
+    /// amount removed.
+    pub fn take_fluid_output(&mut self, node: NodeId, fluid: ItemTypeId, max: Fixed64) -> Fixed64 {
+        let Some(ports) = self.fluid_ports.get_mut(node) else {
+            return Fixed64::ZERO;
+        };
+        let held = ports.output_of(fluid);
+        let taken = held.min(max.max(Fixed64::ZERO));
+        if taken <= Fixed64::ZERO {
+            return Fixed64::ZERO;
+        }
+        if taken == held {
+            ports.output.remove(&fluid);
+        } else {
+            ports.output.insert(fluid, held - taken);
+        }
+        self.mark_fluid_ports_dirty(node);
+        taken
+    }
+
+    fn mark_fluid_ports_dirty(&mut self, node: NodeId) {
+        self.hash_dirty_nodes.push(node);
+        self.dirty.mark_node(node);
+        self.dirty
+            .mark_partition(crate::dirty::DirtyTracker::PARTITION_INVENTORIES);
+    }
+
     // -----------------------------------------------------------------------
     // Item property queries
     // -----------------------------------------------------------------------
@@ -560,16 +1310,84 @@ impl Engine {
     // -----------------------------------------------------------------------
 
     /// Set the transport for an edge. Must be called after the edge has been
-    /// added to the graph (i.e., after `apply_mutations`).
+    /// added to the graph (i.e., after `apply_mutations`); does nothing for
+    /// an edge that is not in the graph.
     pub fn set_transport(&mut self, edge: EdgeId, transport: Transport) {
+        if !self.graph.contains_edge(edge) {
+            return;
+        }
         let state = TransportState::new_for(&transport);
-        self.transports.insert(edge, transport);
+        self.replace_transport(edge, Some(transport));
         self.transport_states.insert(edge, state);
+        self.latency.clear_in_flight(edge);
         self.dirty.mark_edge(edge);
         self.dirty
             .mark_partition(crate::dirty::DirtyTracker::PARTITION_TRANSPORTS);
     }
 
+    /// Set the same transport on every edge in `edges`. Each edge gets its
+    /// own fresh transport state.
+    pub fn set_transport_bulk(&mut self, edges: &[EdgeId], transport: Transport) {
+        for &edge in edges {
+            self.set_transport(edge, transport.clone());
+        }
+    }
+
+    /// Set `transport` on every edge in the graph for which `predicate`
+    /// returns true. The predicate sees the edge, its graph data, and its
+    /// current transport (`None` if unconfigured). Edges are visited in graph
+    /// order. Returns the number of edges configured.
+    pub fn set_transport_where<F>(&mut self, mut predicate: F, transport: Transport) -> usize
+    where
+        F: FnMut(EdgeId, &crate::graph::EdgeData, Option<&Transport>) -> bool,
+    {
+        let matching: Vec<EdgeId> = self
+            .graph
+            .edges()
+            .filter(|&(edge, data)| predicate(edge, data, self.transports.get(edge)))
+            .map(|(edge, _)| edge)
+            .collect();
+        self.set_transport_bulk(&matching, transport);
+        matching.len()
+    }
+
+    /// Visit every edge's transport mutably, in graph order -- e.g. to speed
+    /// up every belt when a mod loads.
+    ///
+    /// Transport state is kept, so items in transit continue under the new
+    /// configuration. An edge whose transport the visitor changes to another
+    /// kind, or whose belt slot or lane count it changes, gets fresh state as
+    /// with [`set_transport`](Self::set_transport), dropping the items in
+    /// transit. Every visited edge is marked dirty.
+    ///
+    /// The visitor sees only transports: nodes, edges and transports cannot
+    /// be added or removed during the pass.
+    pub fn for_each_transport_mut<F>(&mut self, mut visit: F)
+    where
+        F: FnMut(EdgeId, &mut Transport),
+    {
+        let edges: Vec<EdgeId> = self.graph.edges().map(|(edge, _)| edge).collect();
+        for edge in edges {
+            let Some(mut transport) = self.transports.get(edge).cloned() else {
+                continue;
+            };
+            visit(edge, &mut transport);
+            if !self
+                .transport_states
+                .get(edge)
+                .is_some_and(|state| state.fits(&transport))
+            {
+                self.transport_states
+                    .insert(edge, TransportState::new_for(&transport));
+                self.latency.clear_in_flight(edge);
+            }
+            self.replace_transport(edge, Some(transport));
+            self.dirty.mark_edge(edge);
+        }
+        self.dirty
+            .mark_partition(crate::dirty::DirtyTracker::PARTITION_TRANSPORTS);
+    }
+
     /// Get the transport configuration for an edge (read-only).
     pub fn get_transport(&self, edge: EdgeId) -> Option<&Transport> {
         self.transports.get(edge)
@@ -580,12 +1398,47 @@ impl Engine {
         self.transport_states.get(edge)
     }
 
+    /// Items currently moving along `edge`: items on a belt, whole items in
+    /// a flow buffer (rounded down), the pending items of a batch, or a
+    /// vehicle's cargo. Unlike throughput this is an instantaneous quantity,
+    /// for feedback control such as load-balancing splitters. `0` if the
+    /// edge has no transport.
+    pub fn transport_in_transit_count(&self, edge: EdgeId) -> u32 {
+        self.transport_states
+            .get(edge)
+            .map_or(0, count_items_in_transit)
+    }
+
+    /// Where `edge`'s transport is in its delivery cycle: buffer fill, belt
+    /// head and next arrival, batch fill and dispatch, or vehicle phase.
+    /// `None` if the edge has no transport.
+    pub fn transport_progress(&self, edge: EdgeId) -> Option<TransportProgress> {
+        let transport = self.transports.get(edge)?;
+        let state = self.transport_states.get(edge)?;
+        Some(compute_progress(transport, state))
+    }
+
+    /// The delivery accumulator of a flow edge: the fraction of an item
+    /// drained from the buffer but not yet delivered. `None` if the edge has
+    /// no flow transport.
+    pub fn flow_accumulator(&self, edge: EdgeId) -> Option<Fixed64> {
+        match self.transport_states.get(edge)? {
+            TransportState::Flow(fs) => Some(fs.accumulator),
+            _ => None,
+        }
+    }
+
     // -----------------------------------------------------------------------
     // Module management
     // -----------------------------------------------------------------------
 
     /// Register a simulation module. Modules are called in registration order.
+    /// Enables the module's [`extension`](crate::module::Module::extension),
+    /// if it has one.
     pub fn register_module(&mut self, module: Box<dyn crate::module::Module>) {
+        if let Some(extension) = module.extension() {
+            self.enable_extension(extension);
+        }
         self.modules.push(module);
     }
 
@@ -647,11 +1500,47 @@ impl Engine {
         self.junctions.get(node)
     }
 
-    // -----------------------------------------------------------------------
-    // Dirty tracking
-    // -----------------------------------------------------------------------
-
-    /// Returns true if anything has been modified since the last clean.
+    /// Set how a node shares its input room between its incoming edges when
+    /// it cannot take everything that arrives. Resets the balanced cursor.
+    /// Returns `false` if the node does not exist.
+    ///
+    /// Edges into an [`InputMode::Pull`] node advance during processing and
+    /// are not ordered.
+    pub fn set_merge_priority(&mut self, node: NodeId, priority: MergePriority) -> bool {
+        if !self.graph.contains_node(node) {
+            return false;
+        }
+        self.merge_priorities.insert(node, priority);
+        self.merge_cursors.insert(node, 0);
+        self.dirty.mark_node(node);
+        self.dirty
+            .mark_partition(crate::dirty::DirtyTracker::PARTITION_JUNCTIONS);
+        true
+    }
+
+    /// Get a node's merge priority, if one is set.
+    pub fn merge_priority(&self, node: NodeId) -> Option<&MergePriority> {
+        self.merge_priorities.get(node)
+    }
+
+    /// Remove a node's merge priority and return it. Its incoming edges go
+    /// back to advancing in edge order.
+    pub fn clear_merge_priority(&mut self, node: NodeId) -> Option<MergePriority> {
+        let removed = self.merge_priorities.remove(node);
+        if removed.is_some() {
+            self.merge_cursors.remove(node);
+            self.dirty.mark_node(node);
+            self.dirty
+                .mark_partition(crate::dirty::DirtyTracker::PARTITION_JUNCTIONS);
+        }
+        removed
+    }
+
+    // -----------------------------------------------------------------------
+    // Dirty tracking
+    // -----------------------------------------------------------------------
+
+    /// Returns true if anything has been modified since the last clean.
     pub fn is_dirty(&self) -> bool {
         self.dirty.is_dirty()
     }
@@ -681,6 +1570,14 @@ impl Engine {
         self.last_state_hash
     }
 
+    /// The most recently computed state hash, split by subsystem. Its
+    /// [`combined`](HashBreakdown::combined) value equals
+    /// [`state_hash`](Engine::state_hash). Peers that disagree on the state
+    /// hash can diff their breakdowns to localize the divergence.
+    pub fn state_hash_breakdown(&self) -> HashBreakdown {
+        self.last_hash_breakdown
+    }
+
     // -----------------------------------------------------------------------
     // Pause / Resume
     // -----------------------------------------------------------------------
@@ -701,6 +1598,224 @@ impl Engine {
         self.paused
     }
 
+    /// Enable or disable strict mode, in which node additions for buildings
+    /// a registered module refuses (for example, a tech tree gating locked
+    /// buildings) are rejected by `apply_mutations`. Off by default and not
+    /// saved with the engine; servers re-enable it after loading.
+    pub fn set_strict_mode(&mut self, strict: bool) {
+        self.strict_mode = strict;
+    }
+
+    /// Returns true if strict mode is enabled.
+    pub fn is_strict_mode(&self) -> bool {
+        self.strict_mode
+    }
+
+    // -----------------------------------------------------------------------
+    // Ghost (planned) nodes and edges
+    // -----------------------------------------------------------------------
+
+    /// Set the simulation status of a node.
+    ///
+    /// A [`NodeStatus::Ghost`] node stays in the graph and in every query,
+    /// but its processor does not run and no transport moves items into or
+    /// out of it. Setting it back to [`NodeStatus::Active`] resumes normal
+    /// simulation from the next step. Returns `false` if the node does not
+    /// exist.
+    pub fn set_node_status(&mut self, node: NodeId, status: NodeStatus) -> bool {
+        if !self.graph.contains_node(node) {
+            return false;
+        }
+        match status {
+            NodeStatus::Ghost => {
+                self.ghost_nodes.insert(node, ());
+            }
+            NodeStatus::Active => {
+                self.ghost_nodes.remove(node);
+            }
+        }
+        self.dirty.mark_node(node);
+        // Ghost status is part of the graph hash.
+        self.hash_cache_cold = true;
+        self.dirty
+            .mark_partition(crate::dirty::DirtyTracker::PARTITION_GRAPH);
+        true
+    }
+
+    /// Replace a node's [`NodeFlags`](crate::graph::NodeFlags). Setting
+    /// [`SILENT`](crate::graph::NodeFlags::SILENT) drops the node's events
+    /// from now on; clearing it lets them through again. Flags are saved
+    /// with the graph but are not part of the state hash. Returns `false` if
+    /// the node does not exist.
+    pub fn set_node_flags(&mut self, node: NodeId, flags: crate::graph::NodeFlags) -> bool {
+        if !self.graph.set_node_flags(node, flags) {
+            return false;
+        }
+        if flags.contains(crate::graph::NodeFlags::SILENT) {
+            self.event_bus.silence_node(node);
+        } else {
+            self.event_bus.unsilence_node(node);
+        }
+        self.dirty
+            .mark_partition(crate::dirty::DirtyTracker::PARTITION_GRAPH);
+        true
+    }
+
+    /// Get a node's flags, or `None` if it does not exist.
+    pub fn node_flags(&self, node: NodeId) -> Option<crate::graph::NodeFlags> {
+        self.graph.get_node(node).map(|data| data.flags)
+    }
+
+    /// Stash a host value on a node, such as the handle of the host entity
+    /// it belongs to, replacing any previous value. The engine never reads
+    /// it: it is saved with the node and dropped when the node is removed,
+    /// but is not part of the state hash. Returns `false` if the node does
+    /// not exist.
+    pub fn set_node_user_data(&mut self, node: NodeId, data: u64) -> bool {
+        if !self.graph.contains_node(node) {
+            return false;
+        }
+        self.node_user_data.insert(node, data);
+        self.dirty
+            .mark_partition(crate::dirty::DirtyTracker::PARTITION_GRAPH);
+        true
+    }
+
+    /// Get the value set with [`set_node_user_data`](Self::set_node_user_data),
+    /// or `None` if the node has none or does not exist.
+    pub fn get_node_user_data(&self, node: NodeId) -> Option<u64> {
+        self.node_user_data.get(node).copied()
+    }
+
+    /// Remove a node's user data, returning it.
+    pub fn clear_node_user_data(&mut self, node: NodeId) -> Option<u64> {
+        let removed = self.node_user_data.remove(node);
+        if removed.is_some() {
+            self.dirty
+                .mark_partition(crate::dirty::DirtyTracker::PARTITION_GRAPH);
+        }
+        removed
+    }
+
+    /// Get the simulation status of a node, or `None` if it does not exist.
+    pub fn node_status(&self, node: NodeId) -> Option<NodeStatus> {
+        if !self.graph.contains_node(node) {
+            return None;
+        }
+        Some(if self.ghost_nodes.contains_key(node) {
+            NodeStatus::Ghost
+        } else {
+            NodeStatus::Active
+        })
+    }
+
+    /// Set the simulation status of an edge.
+    ///
+    /// A [`NodeStatus::Ghost`] edge keeps its transport and state but does
+    /// not advance during `step`. Returns `false` if the edge does not exist.
+    pub fn set_edge_status(&mut self, edge: EdgeId, status: NodeStatus) -> bool {
+        if !self.graph.contains_edge(edge) {
+            return false;
+        }
+        match status {
+            NodeStatus::Ghost => {
+                self.ghost_edges.insert(edge, ());
+            }
+            NodeStatus::Active => {
+                self.ghost_edges.remove(edge);
+            }
+        }
+        self.dirty.mark_edge(edge);
+        // Ghost status is part of the graph hash.
+        self.hash_cache_cold = true;
+        self.dirty
+            .mark_partition(crate::dirty::DirtyTracker::PARTITION_GRAPH);
+        true
+    }
+
+    /// Get the status of an edge, or `None` if it does not exist.
+    ///
+    /// This is the edge's own status; an active edge touching a ghost node
+    /// still reports `Active` even though it does not transport.
+    pub fn edge_status(&self, edge: EdgeId) -> Option<NodeStatus> {
+        if !self.graph.contains_edge(edge) {
+            return None;
+        }
+        Some(if self.ghost_edges.contains_key(edge) {
+            NodeStatus::Ghost
+        } else {
+            NodeStatus::Active
+        })
+    }
+
+    /// Whether a node is a ghost. False if it does not exist.
+    pub fn is_ghost(&self, node: NodeId) -> bool {
+        self.ghost_nodes.contains_key(node)
+    }
+
+    /// Number of ghost nodes.
+    pub fn ghost_count(&self) -> usize {
+        self.ghost_nodes.len()
+    }
+
+    /// Turn a ghost node into a real one in place, keeping its id and
+    /// everything configured on it. It simulates from the next step. Its
+    /// ghost edges stay ghosts until set active with
+    /// [`set_edge_status`](Self::set_edge_status). Returns `false` if the
+    /// node does not exist or is not a ghost.
+    pub fn materialize_ghost(&mut self, node: NodeId) -> bool {
+        self.is_ghost(node) && self.set_node_status(node, NodeStatus::Active)
+    }
+
+    /// Choose whether ghosts count towards the state hash. Off by default:
+    /// ghost nodes and edges, and edges touching a ghost node, are left out
+    /// of every part of the hash, so planning does not change it. Turn it on
+    /// to have peers agree on their plans as well. Session configuration,
+    /// like strict mode: it is not saved, and peers must agree on it.
+    pub fn set_hash_ghosts(&mut self, enabled: bool) {
+        if self.hash_ghosts != enabled {
+            self.hash_ghosts = enabled;
+            self.hash_cache_cold = true;
+        }
+    }
+
+    /// Whether ghosts count towards the state hash.
+    pub fn hashes_ghosts(&self) -> bool {
+        self.hash_ghosts
+    }
+
+    /// Whether a node is left out of the state hash.
+    pub(crate) fn hash_skips_node(&self, node: NodeId) -> bool {
+        !self.hash_ghosts && self.ghost_nodes.contains_key(node)
+    }
+
+    /// Whether an edge is left out of the state hash.
+    pub(crate) fn hash_skips_edge(&self, edge: EdgeId, from: NodeId, to: NodeId) -> bool {
+        !self.hash_ghosts && self.edge_is_ghosted(edge, from, to)
+    }
+
+    /// Whether an edge is skipped by the transport phase: it is a ghost, or
+    /// either endpoint is.
+    pub(crate) fn edge_is_ghosted(&self, edge: EdgeId, from: NodeId, to: NodeId) -> bool {
+        self.ghost_edges.contains_key(edge)
+            || self.ghost_nodes.contains_key(from)
+            || self.ghost_nodes.contains_key(to)
+    }
+
+    /// A node's outgoing edges the transport phase advances, i.e. without
+    /// ghosted ones, so ghosts take no share of a fan-out.
+    fn live_outputs(&self, node: NodeId) -> Vec<EdgeId> {
+        let mut outputs = self.graph.get_outputs(node).to_vec();
+        if !(self.ghost_nodes.is_empty() && self.ghost_edges.is_empty()) {
+            outputs.retain(|&edge| {
+                self.graph
+                    .get_edge(edge)
+                    .is_some_and(|data| !self.edge_is_ghosted(edge, data.from, data.to))
+            });
+        }
+        outputs
+    }
+
     /// Compact internal storage to reduce memory usage.
     /// Returns an approximate count of bytes freed.
     /// Useful on mobile platforms during background/pause.
@@ -755,14 +1870,299 @@ impl Engine {
         self.event_bus.on_reactive(kind, handler);
     }
 
+    /// Start recording every emitted event into an [`EventLog`](crate::event::EventLog),
+    /// one entry per tick. Suppressed kinds are not recorded. No-op if
+    /// already enabled.
+    ///
+    /// The log is not part of snapshots; re-enable it after deserializing.
+    pub fn enable_event_log(&mut self) {
+        if self.event_log.is_none() {
+            self.event_log = Some(crate::event::EventLog::new());
+            self.event_bus.start_recording();
+        }
+    }
+
+    /// Stop recording and return whatever was recorded since the last take.
+    pub fn disable_event_log(&mut self) -> Option<crate::event::EventLog> {
+        let log = self.event_log.take();
+        self.sync_event_recording();
+        log
+    }
+
+    /// Whether an event log is being recorded.
+    pub fn is_event_log_enabled(&self) -> bool {
+        self.event_log.is_some()
+    }
+
+    /// Take the ticks recorded so far, leaving recording enabled with an
+    /// empty log. Returns `None` if the log is not enabled.
+    pub fn take_event_log(&mut self) -> Option<crate::event::EventLog> {
+        self.event_log.as_mut().map(std::mem::take)
+    }
+
+    /// Encode `events` in the compact, versioned network format. See
+    /// [`encode_events_compact`](crate::event::encode_events_compact).
+    pub fn encode_events_compact(events: &[Event]) -> Vec<u8> {
+        crate::event::encode_events_compact(events)
+    }
+
+    /// Decode events written by [`encode_events_compact`](Self::encode_events_compact).
+    pub fn decode_events_compact(
+        data: &[u8],
+    ) -> Result<Vec<Event>, crate::event::CompactEventError> {
+        crate::event::decode_events_compact(data)
+    }
+
+    /// Retain the last `capacity` emitted events across steps, so hosts can
+    /// catch up on events from steps they did not observe. Once full, the
+    /// oldest events are dropped. Resizing keeps the newest events that fit;
+    /// a capacity of 0 disables the history and discards it.
+    ///
+    /// Storage for `capacity` events is allocated up front and never grows.
+    /// While disabled nothing is allocated or recorded. The history is only
+    /// part of snapshots when
+    /// [`set_serialize_event_history`](Self::set_serialize_event_history)
+    /// is enabled.
+    pub fn set_event_history_capacity(&mut self, capacity: usize) {
+        if capacity == 0 {
+            self.event_history = None;
+        } else if self.event_history_capacity() != capacity {
+            self.event_history = Some(match self.event_history.take() {
+                Some(old) => old.resized(capacity),
+                None => crate::event::EventBuffer::new(capacity),
+            });
+        }
+        self.sync_event_recording();
+    }
+
+    /// Capacity of the event history, or 0 if it is disabled.
+    pub fn event_history_capacity(&self) -> usize {
+        self.event_history
+            .as_ref()
+            .map_or(0, crate::event::EventBuffer::capacity)
+    }
+
+    /// Retained events stamped at or after `since_tick`, oldest first.
+    /// Empty if the history is disabled.
+    pub fn event_history_since(
+        &self,
+        since_tick: Ticks,
+    ) -> impl Iterator<Item = &crate::event::Event> {
+        self.event_history
+            .iter()
+            .flat_map(|history| history.iter())
+            .filter(move |event| event.tick() >= since_tick)
+    }
+
+    /// Retained events matching `filter`, oldest first. Empty if the history
+    /// is disabled.
+    pub fn event_history(
+        &self,
+        filter: crate::event::HistoryFilter,
+    ) -> impl Iterator<Item = &crate::event::Event> {
+        self.event_history
+            .iter()
+            .flat_map(|history| history.iter())
+            .filter(move |event| filter.matches(event))
+    }
+
+    /// Include the event history in [`serialize`](Self::serialize) output.
+    /// Off by default. Loading a snapshot that carries a history restores it
+    /// with its capacity and turns this flag on. Partitioned snapshots never
+    /// carry the history.
+    pub fn set_serialize_event_history(&mut self, enabled: bool) {
+        self.serialize_event_history = enabled;
+    }
+
+    /// Returns true if snapshots include the event history.
+    pub fn serializes_event_history(&self) -> bool {
+        self.serialize_event_history
+    }
+
+    /// Create a cursor that reads the event history matching `filter`,
+    /// starting from the oldest retained event. Each cursor keeps its own
+    /// position, so remote viewers can catch up and then tail new events
+    /// without affecting each other or the host's own polling.
+    ///
+    /// Cursors are session-only: they are not serialized and a loaded
+    /// engine has none.
+    pub fn create_event_cursor(
+        &mut self,
+        filter: crate::event::HistoryFilter,
+    ) -> crate::id::EventCursorId {
+        let next = self
+            .event_history
+            .as_ref()
+            .map_or(0, crate::event::EventBuffer::first_sequence);
+        self.event_cursors
+            .insert(crate::event::EventCursor { next, filter })
+    }
+
+    /// Remove a cursor. Returns false if it does not exist.
+    pub fn destroy_event_cursor(&mut self, cursor: crate::id::EventCursorId) -> bool {
+        self.event_cursors.remove(cursor).is_some()
+    }
+
+    /// Read up to `max` events matching the cursor's filter that were
+    /// recorded after its position, oldest first, and advance past them.
+    /// Returns `None` if the cursor does not exist.
+    ///
+    /// The flag is true if the history overwrote events the cursor had not
+    /// examined yet, whether or not they matched its filter; the cursor then
+    /// resumes from the oldest retained event. Resizing the history keeps
+    /// cursor positions, but disabling it and enabling it again counts as
+    /// lagging.
+    pub fn read_event_cursor(
+        &mut self,
+        cursor: crate::id::EventCursorId,
+        max: usize,
+    ) -> Option<(Vec<crate::event::Event>, bool)> {
+        let state = self.event_cursors.get_mut(cursor)?;
+        let Some(history) = self.event_history.as_ref() else {
+            return Some((Vec::new(), false));
+        };
+        let first = history.first_sequence();
+        let end = history.total_written();
+        let lagged = state.next < first || state.next > end;
+        if lagged {
+            state.next = first;
+        }
+        let mut events = Vec::new();
+        let skip = (state.next - first) as usize;
+        for event in history.iter().skip(skip) {
+            if events.len() == max {
+                break;
+            }
+            state.next += 1;
+            if state.filter.matches(event) {
+                events.push(event.clone());
+            }
+        }
+        Some((events, lagged))
+    }
+
+    /// Silence every node flagged [`SILENT`](crate::graph::NodeFlags::SILENT)
+    /// on the event bus. Called after deserialization, since the bus is not
+    /// saved.
+    pub(crate) fn sync_silenced_nodes(&mut self) {
+        for (node, data) in self.graph.nodes() {
+            if data.flags.contains(crate::graph::NodeFlags::SILENT) {
+                self.event_bus.silence_node(node);
+            }
+        }
+    }
+
+    /// Keep the bus recording while either the event log or the event
+    /// history needs the emitted events.
+    pub(crate) fn sync_event_recording(&mut self) {
+        if self.event_log.is_some() || self.event_history.is_some() {
+            self.event_bus.start_recording();
+        } else {
+            self.event_bus.stop_recording();
+        }
+    }
+
     // -----------------------------------------------------------------------
     // Advance
     // -----------------------------------------------------------------------
 
+    /// The configured simulation strategy.
+    pub fn strategy(&self) -> &SimulationStrategy {
+        &self.strategy
+    }
+
+    /// Switch the simulation strategy between steps. Factory state and the
+    /// tick counter are untouched.
+    ///
+    /// The delta accumulator holds time passed to [`advance`](Self::advance)
+    /// that has not yet been simulated:
+    ///
+    /// - **To delta mode**: the accumulator is kept, so time carried over
+    ///   from a previous delta timestep still counts. It is always zero
+    ///   coming from tick mode.
+    /// - **To tick mode**: the accumulator is cleared, since tick mode never
+    ///   reads it.
+    ///
+    /// Returns the ticks of accumulated time that were discarded.
+    pub fn set_strategy(&mut self, strategy: SimulationStrategy) -> Ticks {
+        let discarded = match strategy {
+            SimulationStrategy::Tick => std::mem::take(&mut self.sim_state.accumulator),
+            SimulationStrategy::Delta { .. } => 0,
+        };
+        self.strategy = strategy;
+        self.dirty
+            .mark_partition(crate::dirty::DirtyTracker::PARTITION_GRAPH);
+        discarded
+    }
+
+    /// Limit how much work a single step may do, or `None` for no limit.
+    ///
+    /// Work is counted in the units of
+    /// [`StepProfile::work_units`](crate::profiling::StepProfile::work_units)
+    /// plus events emitted, and checked after the pre-tick, transport and
+    /// process phases. Once a step is over the limit it skips its remaining
+    /// simulation phases, emits [`Event::StepTruncated`], delivers events and
+    /// finishes its bookkeeping as usual, and [`AdvanceResult::truncated`] is
+    /// set. In delta mode the advance call runs no further steps; the rest of
+    /// the accumulated time stays in the accumulator.
+    ///
+    /// The counts depend only on simulation state, never on wall-clock time,
+    /// so replays truncate the same steps. The limit is saved in snapshots.
+    pub fn set_step_work_limit(&mut self, max_work_units: Option<u64>) {
+        self.step_work_limit = max_work_units;
+        self.dirty
+            .mark_partition(crate::dirty::DirtyTracker::PARTITION_GRAPH);
+    }
+
+    /// The step work limit, if any.
+    pub fn step_work_limit(&self) -> Option<u64> {
+        self.step_work_limit
+    }
+
+    /// Limit a delta-mode [`advance`](Self::advance) call to at most
+    /// `max_steps` fixed steps, or remove the limit with `None`. A limit of
+    /// zero is treated as one.
+    ///
+    /// A large `dt` -- after a hitch, or when the window was in the
+    /// background -- would otherwise run every step it covers in one frame,
+    /// and a frame that slow can hand the next call an even larger `dt`.
+    /// When a call reaches the limit, `policy` decides what happens to the
+    /// time left in the accumulator:
+    ///
+    /// - [`CatchupPolicy::Discard`](crate::sim::CatchupPolicy::Discard)
+    ///   drops its whole steps, reported in [`AdvanceResult::discarded`],
+    ///   and keeps the remainder shorter than a step.
+    /// - [`CatchupPolicy::Retain`](crate::sim::CatchupPolicy::Retain) keeps
+    ///   all of it, so later calls run up to `max_steps` each until the
+    ///   simulation has caught up.
+    ///
+    /// A call that ends early because a step was truncated by the
+    /// [step work limit](Self::set_step_work_limit) keeps its remaining
+    /// time under either policy. Tick mode runs one step per call and
+    /// ignores the limit. The limit is saved in snapshots.
+    pub fn set_max_catchup_steps(
+        &mut self,
+        max_steps: Option<u32>,
+        policy: crate::sim::CatchupPolicy,
+    ) {
+        self.max_catchup = max_steps.map(|max| (max.max(1), policy));
+        self.dirty
+            .mark_partition(crate::dirty::DirtyTracker::PARTITION_GRAPH);
+    }
+
+    /// The catch-up limit and its policy, if any.
+    pub fn max_catchup_steps(&self) -> Option<(u32, crate::sim::CatchupPolicy)> {
+        self.max_catchup
+    }
+
     /// Advance the simulation according to the configured strategy.
     ///
     /// - **Tick mode**: `dt` is ignored; exactly one step runs.
-    /// - **Delta mode**: `dt` is accumulated; as many fixed steps run as fit.
+    /// - **Delta mode**: `dt` is accumulated; as many fixed steps run as fit,
+    ///   up to the [catch-up limit](Self::set_max_catchup_steps).
+    ///
+    /// If a [sliced tick](Self::begin_tick) is open, it is finished instead
+    /// and no further step runs; in delta mode `dt` is still accumulated.
     ///
     /// # Examples
     ///
@@ -778,6 +2178,15 @@ impl Engine {
         if self.paused {
             return AdvanceResult::default();
         }
+        if self.init.auto && !self.init.done {
+            self.initialize();
+        }
+        if self.tick_slice.is_some() {
+            if let SimulationStrategy::Delta { .. } = self.strategy {
+                self.sim_state.accumulator += dt;
+            }
+            return self.finish_open_tick();
+        }
         let mut result = AdvanceResult::default();
 
         match &self.strategy {
@@ -789,8 +2198,21 @@ impl Engine {
                 self.sim_state.accumulator += dt;
                 let step_size = fixed_timestep.max(1);
                 while self.sim_state.accumulator >= step_size {
+                    if let Some((max_steps, policy)) = self.max_catchup
+                        && result.steps_run >= u64::from(max_steps)
+                    {
+                        if policy == crate::sim::CatchupPolicy::Discard {
+                            let leftover = self.sim_state.accumulator % step_size;
+                            result.discarded = self.sim_state.accumulator - leftover;
+                            self.sim_state.accumulator = leftover;
+                        }
+                        break;
+                    }
                     self.sim_state.accumulator -= step_size;
                     self.step_internal(&mut result);
+                    if result.truncated {
+                        break;
+                    }
                 }
             }
         }
@@ -811,31 +2233,46 @@ impl Engine {
         #[cfg(feature = "profiling")]
         let step_start = std::time::Instant::now();
 
+        let profiling = self.start_step_counters();
+        let events_before = self.event_bus.total_emitted_all();
+
         // Phase 1: Pre-tick -- apply queued mutations.
         #[cfg(feature = "profiling")]
         let phase_start = std::time::Instant::now();
         self.phase_pre_tick(result);
+        self.run_tick_hooks(TickPhase::Pre);
+        let mut truncated = self.step_work_exceeded(StepPhase::PreTick, events_before);
         #[cfg(feature = "profiling")]
         let pre_tick_dur = phase_start.elapsed();
 
         // Phase 2: Transport -- move items along edges.
         #[cfg(feature = "profiling")]
         let phase_start = std::time::Instant::now();
-        self.phase_transport();
+        if !truncated {
+            self.phase_transport();
+            self.run_tick_hooks(TickPhase::PostTransports);
+            truncated = self.step_work_exceeded(StepPhase::Transport, events_before);
+        }
         #[cfg(feature = "profiling")]
         let transport_dur = phase_start.elapsed();
 
         // Phase 3: Process -- buildings consume inputs, produce outputs.
         #[cfg(feature = "profiling")]
         let phase_start = std::time::Instant::now();
-        self.phase_process();
+        if !truncated {
+            self.phase_process();
+            self.run_tick_hooks(TickPhase::PostProcessors);
+            truncated = self.step_work_exceeded(StepPhase::Process, events_before);
+        }
         #[cfg(feature = "profiling")]
         let process_dur = phase_start.elapsed();
 
         // Phase 4: Component -- placeholder for module-registered systems.
         #[cfg(feature = "profiling")]
         let phase_start = std::time::Instant::now();
-        self.phase_component();
+        if !truncated {
+            self.phase_component();
+        }
         #[cfg(feature = "profiling")]
         let component_dur = phase_start.elapsed();
 
@@ -850,10 +2287,16 @@ impl Engine {
         #[cfg(feature = "profiling")]
         let phase_start = std::time::Instant::now();
         self.phase_bookkeeping();
+        // Post-step hooks see the finished tick; rehash if they changed state.
+        if self.run_tick_hooks(TickPhase::PostStep) {
+            self.last_state_hash = self.compute_state_hash();
+        }
         #[cfg(feature = "profiling")]
         let bookkeeping_dur = phase_start.elapsed();
 
         result.steps_run += 1;
+        result.truncated = truncated;
+        self.finish_step_counters(profiling);
 
         #[cfg(feature = "profiling")]
         {
@@ -870,11 +2313,57 @@ impl Engine {
         }
     }
 
+    /// Start the step's work counters and return whether profiling was on.
+    /// The work limit is measured with the profiling counters, so they run
+    /// for the step even when profiling is off.
+    pub(crate) fn start_step_counters(&mut self) -> bool {
+        let profiling = self.step_counters.is_some();
+        if !profiling && self.step_work_limit.is_some() {
+            self.step_counters = Some(crate::profiling::StepProfile::default());
+        }
+        profiling
+    }
+
+    /// Publish the finished step's counters as the last step profile, or
+    /// drop them if they only ran for the work limit.
+    pub(crate) fn finish_step_counters(&mut self, profiling: bool) {
+        if let Some(mut counters) = self.step_counters.take()
+            && profiling
+        {
+            counters.tick = self.sim_state.tick;
+            self.last_step_profile = Some(counters);
+            self.step_counters = Some(crate::profiling::StepProfile::default());
+        }
+    }
+
+    /// Check the step's work so far against the work limit after `phase`.
+    /// When it is over, emits [`Event::StepTruncated`] and returns true.
+    pub(crate) fn step_work_exceeded(&mut self, phase: StepPhase, events_before: u64) -> bool {
+        let (Some(limit), Some(counters)) = (self.step_work_limit, self.step_counters.as_ref())
+        else {
+            return false;
+        };
+        let events = self
+            .event_bus
+            .total_emitted_all()
+            .saturating_sub(events_before);
+        let work_done = counters.work_units() + events;
+        if work_done <= limit {
+            return false;
+        }
+        self.event_bus.emit(Event::StepTruncated {
+            phase,
+            work_done,
+            tick: self.sim_state.tick,
+        });
+        true
+    }
+
     // -----------------------------------------------------------------------
     // Phase 1: Pre-tick
     // -----------------------------------------------------------------------
 
-    fn phase_pre_tick(&mut self, result: &mut AdvanceResult) {
+    pub(crate) fn phase_pre_tick(&mut self, result: &mut AdvanceResult) {
         // Apply any reactive handler mutations from the previous tick's post-tick.
         let reactive_mutations = self.event_bus.drain_mutations();
         for mutation in reactive_mutations {
@@ -895,13 +2384,15 @@ impl Engine {
         }
 
         if self.graph.has_pending_mutations() {
-            let mutation_result = self.graph.apply_mutations();
+            let mutation_result = self.apply_mutations();
+            if let Some(counters) = self.step_counters.as_mut() {
+                counters.mutations_applied += (mutation_result.added_nodes.len()
+                    + mutation_result.added_edges.len()
+                    + mutation_result.removed_nodes.len()
+                    + mutation_result.removed_edges.len())
+                    as u32;
+            }
             result.mutation_results.push(mutation_result);
-            self.dirty.mark_graph();
-            self.dirty
-                .mark_partition(crate::dirty::DirtyTracker::PARTITION_GRAPH);
-            // Node set changed — rebuild the entire hash cache next bookkeeping.
-            self.hash_cache_cold = true;
         }
     }
 
@@ -928,8 +2419,8 @@ impl Engine {
             };
             let config = config.clone(); // SplitterConfig is tiny (policy + Option<ItemTypeId>)
 
-            let outputs = self.graph.get_outputs(node_id);
-            if outputs.is_empty() {
+            let outputs_vec = self.live_outputs(node_id);
+            if outputs_vec.is_empty() {
                 continue;
             }
 
@@ -948,9 +2439,7 @@ impl Engine {
                 .cloned()
                 .unwrap_or_default();
 
-            let num_outputs = outputs.len();
-            // Clone outputs to avoid borrow conflicts.
-            let outputs_vec: Vec<EdgeId> = outputs.to_vec();
+            let num_outputs = outputs_vec.len();
 
             match config.policy {
                 crate::junction::SplitPolicy::RoundRobin => {
@@ -993,17 +2482,18 @@ impl Engine {
             if self.junctions.contains_key(node_id) {
                 continue; // Junction already handled above.
             }
-            let outputs = self.graph.get_outputs(node_id);
-            if outputs.len() <= 1 {
+            let outputs_vec = self.live_outputs(node_id);
+            if outputs_vec.len() <= 1 {
                 continue; // No fan-out needed.
             }
 
             // Check if any edge from this node already has an item_filter.
             // If ALL edges have filters, they handle their own routing.
-            let has_unfiltered = outputs.iter().any(|&eid| {
+            let has_unfiltered = outputs_vec.iter().any(|&eid| {
                 self.graph
                     .get_edge(eid)
                     .is_none_or(|e| e.item_filter.is_none())
+                    && !self.edge_category_filters.contains_key(eid)
             });
             if !has_unfiltered {
                 continue;
@@ -1014,16 +2504,16 @@ impl Engine {
                 continue;
             }
 
-            let num = outputs.len() as u32;
+            let num = outputs_vec.len() as u32;
             let share = total / num;
             let remainder = total % num;
-            let outputs_vec: Vec<EdgeId> = outputs.to_vec();
             for (i, &edge_id) in outputs_vec.iter().enumerate() {
                 // Only set budget for unfiltered edges (filtered edges handle themselves).
                 if self
                     .graph
                     .get_edge(edge_id)
                     .is_some_and(|e| e.item_filter.is_some())
+                    || self.edge_category_filters.contains_key(edge_id)
                 {
                     continue;
                 }
@@ -1034,70 +2524,119 @@ impl Engine {
     }
 
     fn phase_transport(&mut self) {
+        self.begin_transport_phase();
+        for i in 0..self.transport_edge_buf.len() {
+            self.transport_edge(i);
+        }
+        self.finish_transport_phase();
+    }
+
+    /// Set up the transport phase: junction budgets and the list of edges
+    /// to advance, in `transport_edge_buf`.
+    pub(crate) fn begin_transport_phase(&mut self) {
         // Compute junction budgets at the start of transport so they
         // take effect within this same tick.
         self.compute_junction_budgets();
 
-        let tick = self.sim_state.tick;
-
-        // Reuse a buffer for edge IDs (avoids per-tick allocation).
-        self.transport_edge_buf.clear();
-        self.transport_edge_buf.extend(self.transports.keys());
+        // Reuse a buffer for edge IDs (avoids per-tick allocation). Ghosted
+        // edges are left out, so they cost nothing per tick.
+        let any_ghosts = !(self.ghost_nodes.is_empty() && self.ghost_edges.is_empty());
+        let mut edges = std::mem::take(&mut self.transport_edge_buf);
+        edges.clear();
+        edges.extend(self.transports.keys().filter(|&edge| {
+            !any_ghosts
+                || self
+                    .graph
+                    .get_edge(edge)
+                    .is_none_or(|data| !self.edge_is_ghosted(edge, data.from, data.to))
+        }));
+        self.transport_edge_buf = edges;
+        self.plan_merges();
+    }
 
-        for i in 0..self.transport_edge_buf.len() {
-            let edge_id = self.transport_edge_buf[i];
-            let Some(edge_data) = self.graph.get_edge(edge_id) else {
+    /// Reorder the incoming edges of every merge point in
+    /// `transport_edge_buf` so they advance in priority order, and record
+    /// the turns of each balanced merge in `merge_turns`. Other edges keep
+    /// their places.
+    fn plan_merges(&mut self) {
+        self.merge_turns.clear();
+        if self.merge_priorities.is_empty() {
+            return;
+        }
+        let mut positions: SecondaryMap<EdgeId, usize> = SecondaryMap::new();
+        for (i, &edge) in self.transport_edge_buf.iter().enumerate() {
+            positions.insert(edge, i);
+        }
+        let mut slots = Vec::new();
+        for (node, priority) in &self.merge_priorities {
+            if self.input_mode(node) == InputMode::Pull {
                 continue;
-            };
-            let source_node = edge_data.from;
-            let dest_node = edge_data.to;
-            let item_filter = edge_data.item_filter;
-
-            // Determine available items at the source's output inventory.
-            // If a junction budget exists for this edge, use it instead of
-            // the node's total output (splitter distribution).
-            let available = if let Some(&budget) = self.edge_budgets.get(edge_id) {
-                budget
-            } else {
-                match item_filter {
-                    Some(item_type) => self.output_quantity_of(source_node, item_type),
-                    None => self.output_total(source_node),
+            }
+            let inputs: Vec<EdgeId> = self
+                .graph
+                .get_inputs(node)
+                .iter()
+                .copied()
+                .filter(|&edge| positions.contains_key(edge))
+                .collect();
+            if inputs.len() < 2 {
+                continue;
+            }
+            let ordered = match priority {
+                MergePriority::Balanced => {
+                    let cursor = self.merge_cursors.get(node).map_or(0, |&c| c as usize);
+                    let (before, from_cursor) = inputs.split_at(cursor % inputs.len());
+                    from_cursor.iter().chain(before).copied().collect()
+                }
+                MergePriority::LeftFirst => inputs.clone(),
+                MergePriority::Priority(listed) => {
+                    let mut ordered = Vec::with_capacity(inputs.len());
+                    for &edge in listed.iter().chain(&inputs) {
+                        if inputs.contains(&edge) && !ordered.contains(&edge) {
+                            ordered.push(edge);
+                        }
+                    }
+                    ordered
                 }
             };
-
-            // Advance the transport.
-            let transport_result = {
-                let Some(transport) = self.transports.get(edge_id) else {
-                    continue;
-                };
-                let Some(state) = self.transport_states.get_mut(edge_id) else {
-                    continue;
-                };
-                transport.advance(state, available)
-            };
-
-            // Emit transport events.
-            if transport_result.items_delivered > 0 {
-                self.event_bus.emit(Event::ItemDelivered {
-                    edge: edge_id,
-                    quantity: transport_result.items_delivered,
-                    tick,
-                });
+            slots.clear();
+            slots.extend(ordered.iter().map(|&edge| positions[edge]));
+            slots.sort_unstable();
+            for (&slot, &edge) in slots.iter().zip(&ordered) {
+                self.transport_edge_buf[slot] = edge;
             }
-
-            // Emit TransportFull when items were available but nothing moved
-            // (back-pressure from a full transport buffer).
-            if available > 0 && transport_result.items_moved == 0 {
-                self.event_bus.emit(Event::TransportFull {
-                    edge: edge_id,
-                    tick,
-                });
+            if *priority == MergePriority::Balanced {
+                let count = ordered.len() as u32;
+                for (i, &edge) in ordered.iter().enumerate() {
+                    self.merge_turns.insert(
+                        edge,
+                        MergeTurn {
+                            node,
+                            edges_left: count - i as u32,
+                            leads: i == 0,
+                        },
+                    );
+                }
             }
+        }
+    }
 
-            // Apply transport results to inventories.
-            self.apply_transport_result(source_node, dest_node, edge_id, &transport_result);
+    /// Advance the `i`-th edge of the transport phase.
+    pub(crate) fn transport_edge(&mut self, i: usize) {
+        let edge_id = self.transport_edge_buf[i];
+        // Edges into pull-mode nodes advance in the process phase.
+        if self
+            .graph
+            .get_edge(edge_id)
+            .is_some_and(|e| self.input_mode(e.to) == InputMode::Pull)
+        {
+            return;
         }
+        self.advance_edge(edge_id, self.sim_state.tick, false);
+    }
 
+    /// Finish the transport phase once every edge has advanced.
+    pub(crate) fn finish_transport_phase(&mut self) {
         // Mark partitions dirty if any transports were advanced.
         if !self.transport_edge_buf.is_empty() {
             self.dirty
@@ -1107,92 +2646,313 @@ impl Engine {
         }
     }
 
+    /// Advance one edge's transport and move its results between the two
+    /// node inventories. With `pull` set, the edge only requests what the
+    /// destination's active recipe is still missing.
+    fn advance_edge(&mut self, edge_id: EdgeId, tick: Ticks, pull: bool) {
+        let Some(edge_data) = self.graph.get_edge(edge_id) else {
+            return;
+        };
+        let source_node = edge_data.from;
+        let dest_node = edge_data.to;
+        let mut item_filter = edge_data.item_filter;
+        if self.edge_is_ghosted(edge_id, source_node, dest_node) {
+            return;
+        }
+        let category_filtered =
+            item_filter.is_none() && self.edge_category_filters.contains_key(edge_id);
+        let mut may_load = true;
+        if category_filtered {
+            // Nothing of the category has reached this edge yet.
+            let Some((item_type, load)) = self.pick_category_item(edge_id, source_node) else {
+                return;
+            };
+            item_filter = Some(item_type);
+            may_load = load;
+        }
+
+        // Determine available items at the source's output inventory.
+        // If a junction budget exists for this edge, use it instead of
+        // the node's total output (splitter distribution).
+        let available = if !may_load {
+            0
+        } else if let Some(&budget) = self.edge_budgets.get(edge_id) {
+            match item_filter {
+                Some(item_type) if category_filtered => {
+                    budget.min(self.output_quantity_of(source_node, item_type))
+                }
+                _ => budget,
+            }
+        } else {
+            match item_filter {
+                Some(item_type) => self.output_quantity_of(source_node, item_type),
+                None => self.output_total(source_node),
+            }
+        };
+
+        // Deliveries are capped by the room left in the destination's
+        // input inventory; the rest stays in the transport.
+        let item_type =
+            item_filter.unwrap_or_else(|| self.determine_item_type_for_edge(source_node));
+        let mut deliverable = if self.is_storage(dest_node) {
+            self.storage_free_space(dest_node, item_type)
+        } else {
+            self.inputs
+                .get(dest_node)
+                .map_or(0, |inv| inv.free_space_for(item_type))
+        };
+        let mut available = available;
+        if pull && let Some(missing) = self.recipe_shortfall(dest_node, item_type) {
+            available = available.min(missing);
+            deliverable = deliverable.min(missing);
+        }
+        // A balanced merge shares the room left between its edges still to
+        // advance; earlier edges' leftovers roll over to later ones.
+        let merge_turn = self.merge_turns.get(edge_id).copied();
+        if let Some(turn) = merge_turn {
+            deliverable = deliverable.div_ceil(turn.edges_left);
+        }
+
+        // Belts record the item type and a stable instance id per item.
+        let belt_item_type = match self.transports.get(edge_id) {
+            Some(Transport::Item(_)) => Some(item_type),
+            _ => None,
+        };
+
+        // Advance the transport.
+        let transport_result = {
+            let Some(transport) = self.transports.get(edge_id) else {
+                return;
+            };
+            let Some(state) = self.transport_states.get_mut(edge_id) else {
+                return;
+            };
+            match belt_item_type {
+                Some(item_type) => transport.advance_tracked(
+                    state,
+                    available,
+                    deliverable,
+                    item_type,
+                    &mut self.next_item_instance_id,
+                ),
+                None => transport.advance_limited(state, available, deliverable),
+            }
+        };
+
+        if let Some(counters) = self.step_counters.as_mut() {
+            counters.transports_advanced += 1;
+            counters.items_delivered += transport_result.items_delivered;
+        }
+        if let Some(turn) = merge_turn
+            && turn.leads
+            && transport_result.items_delivered > 0
+            && let Some(cursor) = self.merge_cursors.entry(turn.node)
+        {
+            let cursor = cursor.or_default();
+            *cursor = (*cursor % turn.edges_left + 1) % turn.edges_left;
+        }
+        if self.latency.is_active() {
+            self.latency.record(edge_id, tick, &transport_result);
+        }
+
+        // Emit transport events.
+        if transport_result.items_delivered > 0 {
+            self.event_bus.emit(Event::ItemDelivered {
+                edge: edge_id,
+                quantity: transport_result.items_delivered,
+                tick,
+            });
+            self.log_operation(
+                OperationRecord::new(OperationKind::Delivered, tick)
+                    .node(dest_node)
+                    .edge(edge_id)
+                    .amount(item_type.0, transport_result.items_delivered),
+            );
+        }
+
+        // Emit TransportFull when items were available but nothing moved
+        // (back-pressure from a full transport buffer).
+        if available > 0 && transport_result.items_moved == 0 {
+            self.event_bus.emit(Event::TransportFull {
+                edge: edge_id,
+                tick,
+            });
+        }
+
+        // Apply transport results to inventories.
+        self.apply_transport_result(
+            edge_id,
+            source_node,
+            dest_node,
+            item_type,
+            &transport_result,
+        );
+    }
+
+    /// How many more of `item_type` a recipe node needs in its input
+    /// inventory before its active recipe can start. `None` for nodes without
+    /// a recipe, which pull whatever fits.
+    fn recipe_shortfall(&self, node: NodeId, item_type: ItemTypeId) -> Option<u32> {
+        let recipe = match self.processors.get(node)? {
+            Processor::Fixed(recipe) => recipe,
+            Processor::MultiRecipe(multi) => multi.recipes.get(multi.active_recipe)?,
+            _ => return None,
+        };
+        let needed: u32 = recipe
+            .inputs
+            .iter()
+            .filter(|input| input.item_type == item_type)
+            .map(|input| input.quantity)
+            .sum();
+        let held = self
+            .inputs
+            .get(node)
+            .map_or(0, |inv| inv.count_of(item_type));
+        Some(needed.saturating_sub(held))
+    }
+
+    /// Advance every incoming edge of a pull-mode node, in the graph's input
+    /// edge order.
+    fn pull_inputs(&mut self, node: NodeId) {
+        if self.input_mode(node) != InputMode::Pull {
+            return;
+        }
+        let tick = self.sim_state.tick;
+        let edges: Vec<EdgeId> = self.graph.get_inputs(node).to_vec();
+        for edge_id in edges {
+            self.advance_edge(edge_id, tick, true);
+        }
+    }
+
     /// Get total items in a node's output inventory (across all slots and types).
     fn output_total(&self, node: NodeId) -> u32 {
-        self.outputs
-            .get(node)
-            .map(|inv| inv.output_slots.iter().map(|s| s.total()).sum())
-            .unwrap_or(0)
+        self.outputs.get(node).map(Inventory::total).unwrap_or(0)
     }
 
     /// Get total quantity of a specific item type in a node's output inventory.
-    fn output_quantity_of(&self, node: NodeId, item_type: ItemTypeId) -> u32 {
+    pub(crate) fn output_quantity_of(&self, node: NodeId, item_type: ItemTypeId) -> u32 {
         self.outputs
             .get(node)
-            .map(|inv| inv.output_slots.iter().map(|s| s.quantity(item_type)).sum())
+            .map(|inv| inv.count_of(item_type))
             .unwrap_or(0)
     }
 
     /// Apply transport results: remove items from source output, add to dest input.
+    ///
+    /// The properties of the items taken from the source are recorded in the
+    /// transport state, and delivered items get the properties recorded when
+    /// they entered.
     fn apply_transport_result(
         &mut self,
+        edge: EdgeId,
         source: NodeId,
         dest: NodeId,
-        edge_id: EdgeId,
+        item_type: ItemTypeId,
         result: &TransportResult,
     ) {
-        // Use edge filter if present, otherwise fall back to processor-based detection.
-        let item_type = self
-            .graph
-            .get_edge(edge_id)
-            .and_then(|e| e.item_filter)
-            .unwrap_or_else(|| self.determine_item_type_for_edge(source));
-
-        // Capture properties from source output BEFORE removing items.
-        let captured_properties = if result.items_moved > 0 {
-            self.outputs.get(source).and_then(|output_inv| {
-                for slot in &output_inv.output_slots {
-                    if let Some(props) = slot.get_properties(item_type)
-                        && !props.is_empty()
-                    {
-                        return Some(props.clone());
-                    }
-                }
-                None
-            })
-        } else {
-            None
-        };
-
         // Remove moved items from source output (type-filtered).
         if result.items_moved > 0 {
-            if let Some(output_inv) = self.outputs.get_mut(source) {
-                let mut remaining = result.items_moved;
-                for slot in &mut output_inv.output_slots {
-                    if remaining == 0 {
-                        break;
-                    }
-                    let removed = slot.remove(item_type, remaining);
-                    remaining -= removed;
-                }
+            let intake = self.outputs.get_mut(source).and_then(|output_inv| {
+                take_property_groups(output_inv, item_type, result.items_moved)
+            });
+            if let Some(state) = self.transport_states.get_mut(edge) {
+                record_intake(
+                    state,
+                    result.items_moved,
+                    intake.as_deref(),
+                    self.next_item_instance_id,
+                );
             }
             self.hash_dirty_nodes.push(source);
         }
 
-        // Deliver items to destination input (with properties if present).
-        if result.items_delivered > 0 {
-            if let Some(input_inv) = self.inputs.get_mut(dest) {
-                let mut remaining = result.items_delivered;
-                for slot in &mut input_inv.input_slots {
-                    if remaining == 0 {
-                        break;
-                    }
-                    if let Some(ref props) = captured_properties {
-                        let overflow = slot.add_with_properties(item_type, remaining, props);
-                        remaining = overflow;
-                    } else {
-                        let overflow = slot.add(item_type, remaining);
-                        remaining = overflow;
+        if result.items_delivered == 0 {
+            return;
+        }
+        let delivered = self
+            .transport_states
+            .get_mut(edge)
+            .and_then(|state| release_delivered(state, result.items_delivered));
+        let plain = PropertyGroup {
+            quantity: result.items_delivered,
+            properties: std::collections::BTreeMap::new(),
+        };
+        let groups = delivered.as_deref().unwrap_or(std::slice::from_ref(&plain));
+
+        // The transport only delivers what the input has room for. Storage
+        // nodes keep deliveries in their output inventory instead.
+        let storage = self.is_storage(dest);
+        for group in groups {
+            let properties = Some(&group.properties).filter(|p| !p.is_empty());
+            if storage {
+                self.store_items(dest, item_type, group.quantity, properties);
+            } else if let Some(input_inv) = self.inputs.get_mut(dest) {
+                let _ = match properties {
+                    Some(props) => {
+                        input_inv.insert_with_properties(item_type, group.quantity, props)
                     }
-                }
+                    None => input_inv.insert(item_type, group.quantity),
+                };
+            }
+        }
+        self.hash_dirty_nodes.push(dest);
+    }
+
+    fn is_storage(&self, node: NodeId) -> bool {
+        matches!(self.processors.get(node), Some(Processor::Storage(_)))
+    }
+
+    /// How many output slots of a storage node accept deliveries.
+    fn usable_storage_slots(&self, node: NodeId) -> usize {
+        match self.processors.get(node) {
+            Some(Processor::Storage(storage)) => storage
+                .usable_slots
+                .map_or(usize::MAX, |slots| slots as usize),
+            _ => 0,
+        }
+    }
+
+    /// How many more of `item_type` a storage node's usable slots can take.
+    fn storage_free_space(&self, node: NodeId, item_type: ItemTypeId) -> u32 {
+        let usable = self.usable_storage_slots(node);
+        self.outputs.get(node).map_or(0, |inv| {
+            inv.output_slots
+                .iter()
+                .take(usable)
+                .map(|slot| slot.free_space_for(item_type))
+                .fold(0u32, u32::saturating_add)
+        })
+    }
+
+    /// Add delivered items to a storage node's usable output slots, in slot
+    /// order.
+    fn store_items(
+        &mut self,
+        node: NodeId,
+        item_type: ItemTypeId,
+        quantity: u32,
+        properties: Option<&std::collections::BTreeMap<PropertyId, Fixed64>>,
+    ) {
+        let usable = self.usable_storage_slots(node);
+        let Some(output_inv) = self.outputs.get_mut(node) else {
+            return;
+        };
+        let mut remaining = quantity;
+        for slot in output_inv.output_slots.iter_mut().take(usable) {
+            if remaining == 0 {
+                break;
             }
-            self.hash_dirty_nodes.push(dest);
+            remaining = match properties {
+                Some(props) => slot.add_with_properties(item_type, remaining, props),
+                None => slot.add(item_type, remaining),
+            };
         }
+        debug_assert_eq!(remaining, 0, "free space was checked before delivering");
     }
 
     /// Determine the item type flowing through an edge based on the source node.
     /// Falls back to ItemTypeId(0) if no type can be determined.
-    fn determine_item_type_for_edge(&self, source: NodeId) -> ItemTypeId {
+    pub(crate) fn determine_item_type_for_edge(&self, source: NodeId) -> ItemTypeId {
         // Fast path: check the cached item type (populated by set_processor/swap_processor).
         if let Some(&cached) = self.node_item_type_cache.get(source) {
             return cached;
@@ -1203,6 +2963,11 @@ impl Engine {
         if let Some(processor) = self.processors.get(source) {
             match processor {
                 Processor::Source(src) => return src.output_type,
+                Processor::MultiSource(multi) => {
+                    if let Some(output) = multi.outputs.first() {
+                        return output.item_type;
+                    }
+                }
                 Processor::Fixed(recipe) => {
                     if let Some(output) = recipe.outputs.first() {
                         return output.item_type;
@@ -1210,7 +2975,7 @@ impl Engine {
                 }
                 Processor::Property(prop) => return prop.output_type,
                 Processor::Demand(demand) => return demand.input_type,
-                Processor::Passthrough => {}
+                Processor::Passthrough | Processor::Storage(_) => {}
                 Processor::MultiRecipe(multi) => {
                     if let Some(recipe) = multi.recipes.get(multi.active_recipe)
                         && let Some(output) = recipe.outputs.first()
@@ -1218,10 +2983,25 @@ impl Engine {
                         return output.item_type;
                     }
                 }
+                Processor::WeightedRecipe(weighted) => {
+                    if let Some((recipe, _)) = weighted.recipes.get(weighted.selected.unwrap_or(0))
+                        && let Some(output) = recipe.outputs.first()
+                    {
+                        return output.item_type;
+                    }
+                }
+                Processor::Flexible(flexible) => {
+                    if let Some((_, recipe)) = &flexible.active
+                        && let Some(output) = recipe.outputs.first()
+                    {
+                        return output.item_type;
+                    }
+                }
             }
         }
 
-        // Slow path for Passthrough or uncached nodes: scan output inventory.
+        // Slow path for Passthrough, Storage or uncached nodes: scan output
+        // inventory.
         if let Some(output_inv) = self.outputs.get(source) {
             for slot in &output_inv.output_slots {
                 for stack in &slot.stacks {
@@ -1239,8 +3019,18 @@ impl Engine {
     // Phase 3: Process
     // -----------------------------------------------------------------------
 
-    #[cfg(not(feature = "parallel"))]
     fn phase_process(&mut self) {
+        let units = self.begin_process_phase();
+        for unit in &units {
+            self.process_unit(unit);
+        }
+        self.finish_process_phase(units);
+    }
+
+    /// Set up the process phase and return its units of work, in order:
+    /// one node each.
+    #[cfg(not(feature = "parallel"))]
+    pub(crate) fn begin_process_phase(&mut self) -> Vec<ProcessUnit> {
         // Use feedback-aware ordering so cycles don't skip processing.
         // Back-edges naturally introduce a one-tick delay: items placed in
         // output on tick N are transported on tick N+1, so cycle nodes see
@@ -1248,14 +3038,21 @@ impl Engine {
         // Ensure cache is fresh, then swap it out to avoid borrow conflict
         // with process_node(&mut self). No allocation — reuses the cached Vec.
         let _ = self.graph.topological_order_with_feedback();
-        let order = self.graph.take_feedback_cache();
-
-        let has_processors = !order.is_empty() && !self.processors.is_empty();
+        self.graph.take_feedback_cache()
+    }
 
-        for &node_id in &order {
-            self.process_node(node_id);
-        }
+    /// Pull and process one node.
+    #[cfg(not(feature = "parallel"))]
+    pub(crate) fn process_unit(&mut self, &node_id: &ProcessUnit) {
+        self.pull_inputs(node_id);
+        self.process_node(node_id);
+    }
 
+    /// Finish the process phase, handing back the units from
+    /// [`begin_process_phase`](Self::begin_process_phase).
+    #[cfg(not(feature = "parallel"))]
+    pub(crate) fn finish_process_phase(&mut self, order: Vec<ProcessUnit>) {
+        let has_processors = !order.is_empty() && !self.processors.is_empty();
         self.graph.restore_feedback_cache(order);
 
         // Mark partitions dirty if any nodes were processed.
@@ -1267,153 +3064,211 @@ impl Engine {
         }
     }
 
+    /// Set up the process phase and return its units of work, in order:
+    /// one topological level each.
     #[cfg(feature = "parallel")]
-    fn phase_process(&mut self) {
+    pub(crate) fn begin_process_phase(&mut self) -> Vec<ProcessUnit> {
+        let (levels, _back_edges) = self.graph.topological_order_by_level();
+        levels
+    }
+
+    /// Process one topological level, its nodes in parallel.
+    #[cfg(feature = "parallel")]
+    pub(crate) fn process_unit(&mut self, level: &ProcessUnit) {
         use rayon::prelude::*;
 
-        let (levels, _back_edges) = self.graph.topological_order_by_level();
         let tick = self.sim_state.tick;
+        // PULL: pull-mode nodes fetch their inputs sequentially, in level
+        // order, before any node in the level is extracted.
+        for &node_id in level {
+            self.pull_inputs(node_id);
+        }
 
-        for level in levels {
-            // EXTRACT: gather read-only inputs and clone processor + state for each node.
-            struct NodeWork {
-                node_id: NodeId,
-                processor: Processor,
-                state: ProcessorState,
-                mods: Vec<Modifier>,
-                available_inputs: Vec<(ItemTypeId, u32)>,
-                output_space: u32,
-                prev_state: Option<ProcessorState>,
-            }
+        // EXTRACT: gather read-only inputs and clone processor + state for each node.
+        struct NodeWork {
+            node_id: NodeId,
+            processor: Processor,
+            state: ProcessorState,
+            mods: Vec<Modifier>,
+            available_inputs: Vec<(ItemTypeId, u32)>,
+            output_space: u32,
+            fluids: crate::processor::FluidPorts,
+            prev_state: Option<ProcessorState>,
+        }
 
-            let work: Vec<NodeWork> = level
-                .iter()
-                .filter_map(|&node_id| {
-                    let processor = self.processors.get(node_id)?.clone();
-                    let state = self.processor_states.get(node_id)?.clone();
-                    let mods = self.modifiers.get(node_id).cloned().unwrap_or_default();
-                    let mut available_inputs = Vec::new();
-                    Self::gather_inputs_into(&self.inputs, node_id, &mut available_inputs);
-                    let output_space = self.calculate_output_space(node_id);
-                    let prev_state = Some(state.clone());
-                    Some(NodeWork {
-                        node_id,
-                        processor,
-                        state,
-                        mods,
-                        available_inputs,
-                        output_space,
-                        prev_state,
-                    })
+        let work: Vec<NodeWork> = level
+            .iter()
+            .filter(|&&node_id| !self.ghost_nodes.contains_key(node_id))
+            .filter_map(|&node_id| {
+                let mut processor = self.processors.get(node_id)?.clone();
+                let state = self.processor_states.get(node_id)?.clone();
+                let mut mods = self.modifiers.get(node_id).cloned().unwrap_or_default();
+                mods.extend(
+                    self.warmups
+                        .get(node_id)
+                        .and_then(crate::warmup::Warmup::modifier),
+                );
+                let mut available_inputs = Vec::new();
+                Self::gather_inputs_into(&self.inputs, node_id, &mut available_inputs);
+                if let Processor::Flexible(flexible) = &mut processor
+                    && !matches!(state, ProcessorState::Working { .. })
+                {
+                    flexible.select(
+                        self.registry.as_ref(),
+                        &self.recipe_overrides,
+                        &available_inputs,
+                        &mods,
+                    );
+                }
+                if let Processor::MultiSource(multi) = &mut processor
+                    && let Some(output_inv) = self.outputs.get(node_id)
+                {
+                    multi.sample_output_space(|item| Self::output_space_for(output_inv, item));
+                }
+                let output_space = self.calculate_output_space(node_id);
+                let fluids = self.fluid_ports.get(node_id).cloned().unwrap_or_default();
+                let prev_state = Some(state.clone());
+                Some(NodeWork {
+                    node_id,
+                    processor,
+                    state,
+                    mods,
+                    available_inputs,
+                    output_space,
+                    fluids,
+                    prev_state,
                 })
-                .collect();
+            })
+            .collect();
+
+        // PROCESS: tick each node in parallel.
+        struct NodeResult {
+            node_id: NodeId,
+            processor: Processor,
+            state: ProcessorState,
+            prev_state: Option<ProcessorState>,
+            result: ProcessorResult,
+        }
+
+        let results: Vec<NodeResult> = work
+            .into_par_iter()
+            .map(|mut w| {
+                let result = w.processor.tick_with_fluids(
+                    &mut w.state,
+                    &w.mods,
+                    &w.available_inputs,
+                    w.output_space,
+                    None,
+                    &w.fluids,
+                );
+                NodeResult {
+                    node_id: w.node_id,
+                    processor: w.processor,
+                    state: w.state,
+                    prev_state: w.prev_state,
+                    result,
+                }
+            })
+            .collect();
 
-            // PROCESS: tick each node in parallel.
-            struct NodeResult {
-                node_id: NodeId,
-                processor: Processor,
-                state: ProcessorState,
-                prev_state: Option<ProcessorState>,
-                result: ProcessorResult,
+        // APPLY: write back results sequentially.
+        for mut nr in results {
+            if let Some(counters) = self.step_counters.as_mut() {
+                counters.processors_ticked += 1;
+            }
+            if let Some(warmup) = self.warmups.get_mut(nr.node_id) {
+                warmup.settle(nr.prev_state.as_ref(), &mut nr.state, &mut nr.result);
             }
 
-            let results: Vec<NodeResult> = work
-                .into_par_iter()
-                .map(|mut w| {
-                    let result = w.processor.tick(
-                        &mut w.state,
-                        &w.mods,
-                        &w.available_inputs,
-                        w.output_space,
-                    );
-                    NodeResult {
-                        node_id: w.node_id,
-                        processor: w.processor,
-                        state: w.state,
-                        prev_state: w.prev_state,
-                        result,
-                    }
-                })
-                .collect();
+            // Write back processor and state.
+            self.processors.insert(nr.node_id, nr.processor);
+            self.processor_states.insert(nr.node_id, nr.state);
 
-            // APPLY: write back results sequentially.
-            for nr in results {
-                // Write back processor and state.
-                self.processors.insert(nr.node_id, nr.processor);
-                self.processor_states.insert(nr.node_id, nr.state);
+            // Emit production events.
+            for &(item_type, quantity) in &nr.result.consumed {
+                self.event_bus.emit(Event::ItemConsumed {
+                    node: nr.node_id,
+                    item_type,
+                    quantity,
+                    tick,
+                });
+            }
+            for &(item_type, quantity) in &nr.result.produced {
+                self.event_bus.emit(Event::ItemProduced {
+                    node: nr.node_id,
+                    item_type,
+                    quantity,
+                    tick,
+                });
+                self.log_operation(
+                    OperationRecord::new(OperationKind::Produced, tick)
+                        .node(nr.node_id)
+                        .amount(item_type.0, quantity),
+                );
+            }
 
-                // Emit production events.
-                for &(item_type, quantity) in &nr.result.consumed {
-                    self.event_bus.emit(Event::ItemConsumed {
-                        node: nr.node_id,
-                        item_type,
-                        quantity,
-                        tick,
-                    });
-                }
-                for &(item_type, quantity) in &nr.result.produced {
-                    self.event_bus.emit(Event::ItemProduced {
+            // Emit state-change events.
+            if nr.result.state_changed {
+                let new_state = self.processor_states.get(nr.node_id);
+
+                if matches!(nr.prev_state.as_ref(), Some(ProcessorState::Stalled { .. }))
+                    && !matches!(new_state, Some(ProcessorState::Stalled { .. }))
+                {
+                    self.event_bus.emit(Event::BuildingResumed {
                         node: nr.node_id,
-                        item_type,
-                        quantity,
                         tick,
                     });
                 }
 
-                // Emit state-change events.
-                if nr.result.state_changed {
-                    let new_state = self.processor_states.get(nr.node_id);
-
-                    if matches!(nr.prev_state.as_ref(), Some(ProcessorState::Stalled { .. }))
-                        && !matches!(new_state, Some(ProcessorState::Stalled { .. }))
-                    {
-                        self.event_bus.emit(Event::BuildingResumed {
+                match (nr.prev_state.as_ref(), new_state) {
+                    (
+                        Some(ProcessorState::Idle) | Some(ProcessorState::Stalled { .. }),
+                        Some(ProcessorState::Working { .. }),
+                    ) => {
+                        self.event_bus.emit(Event::RecipeStarted {
                             node: nr.node_id,
+                            recipe: self
+                                .processors
+                                .get(nr.node_id)
+                                .and_then(Processor::active_recipe),
                             tick,
                         });
                     }
-
-                    match (nr.prev_state.as_ref(), new_state) {
-                        (
-                            Some(ProcessorState::Idle) | Some(ProcessorState::Stalled { .. }),
-                            Some(ProcessorState::Working { .. }),
-                        ) => {
-                            self.event_bus.emit(Event::RecipeStarted {
-                                node: nr.node_id,
-                                tick,
-                            });
-                        }
-                        (Some(ProcessorState::Working { .. }), Some(ProcessorState::Idle)) => {
-                            self.event_bus.emit(Event::RecipeCompleted {
-                                node: nr.node_id,
-                                tick,
-                            });
-                        }
-                        (_, Some(ProcessorState::Stalled { reason })) => {
-                            self.event_bus.emit(Event::BuildingStalled {
-                                node: nr.node_id,
-                                reason: *reason,
-                                tick,
-                            });
-                        }
-                        _ => {}
+                    (Some(ProcessorState::Working { .. }), Some(ProcessorState::Idle)) => {
+                        self.event_bus.emit(Event::RecipeCompleted {
+                            node: nr.node_id,
+                            tick,
+                        });
+                    }
+                    (_, Some(ProcessorState::Stalled { reason })) => {
+                        self.event_bus.emit(Event::BuildingStalled {
+                            node: nr.node_id,
+                            reason: *reason,
+                            tick,
+                        });
                     }
+                    _ => {}
                 }
+            }
 
-                // Capture input properties and apply consumed/produced.
-                let input_properties = if nr.result.property_transform.is_some() {
-                    self.capture_input_properties(nr.node_id)
-                } else {
-                    None
-                };
-                self.apply_consumed(nr.node_id, &nr.result);
-                self.apply_produced(nr.node_id, &nr.result, input_properties.as_ref());
+            // Capture input properties and apply consumed/produced.
+            let input_properties = if nr.result.property_transform.is_some() {
+                self.capture_input_properties(nr.node_id)
+            } else {
+                None
+            };
+            self.apply_consumed(nr.node_id, &nr.result);
+            self.apply_produced(nr.node_id, &nr.result, input_properties.as_ref());
+            self.apply_fluids(nr.node_id, &nr.result);
 
-                // Mark node hash dirty (progress increments every tick for Working nodes).
-                self.hash_dirty_nodes.push(nr.node_id);
-            }
+            // Mark node hash dirty (progress increments every tick for Working nodes).
+            self.hash_dirty_nodes.push(nr.node_id);
         }
+    }
 
+    /// Finish the process phase once every level has been processed.
+    #[cfg(feature = "parallel")]
+    pub(crate) fn finish_process_phase(&mut self, _levels: Vec<ProcessUnit>) {
         // Mark partitions dirty if any nodes were processed.
         if !self.processors.is_empty() {
             self.dirty
@@ -1425,6 +3280,9 @@ impl Engine {
 
     #[cfg(not(feature = "parallel"))]
     fn process_node(&mut self, node_id: NodeId) {
+        if self.ghost_nodes.contains_key(node_id) {
+            return;
+        }
         let tick = self.sim_state.tick;
 
         // Gather available inputs into reusable buffer.
@@ -1446,10 +3304,52 @@ impl Engine {
             };
             let mods = self.modifiers.get(node_id);
             let empty_mods = [];
-            let mods_slice = mods.map(|m| m.as_slice()).unwrap_or(&empty_mods);
+            let mut mods_slice = mods.map(|m| m.as_slice()).unwrap_or(&empty_mods);
+            // A warming machine ticks with its ramp appended to its modifiers.
+            let warm_mods: Vec<Modifier>;
+            if let Some(ramp) = self
+                .warmups
+                .get(node_id)
+                .and_then(crate::warmup::Warmup::modifier)
+            {
+                warm_mods = mods_slice.iter().cloned().chain([ramp]).collect();
+                mods_slice = &warm_mods;
+            }
+            // A flexible node picks its recipe whenever a cycle may start.
+            if let Processor::Flexible(flexible) = processor
+                && !matches!(state, ProcessorState::Working { .. })
+            {
+                flexible.select(
+                    self.registry.as_ref(),
+                    &self.recipe_overrides,
+                    &self.input_buf,
+                    mods_slice,
+                );
+            }
+            if let Processor::MultiSource(multi) = processor
+                && let Some(output_inv) = self.outputs.get(node_id)
+            {
+                multi.sample_output_space(|item| Self::output_space_for(output_inv, item));
+            }
             let rng = self.node_rngs.get_mut(node_id);
-            processor.tick_with_rng(state, mods_slice, &self.input_buf, output_space, rng)
+            let no_fluids = crate::processor::FluidPorts::default();
+            let fluids = self.fluid_ports.get(node_id).unwrap_or(&no_fluids);
+            let mut result = processor.tick_with_fluids(
+                state,
+                mods_slice,
+                &self.input_buf,
+                output_space,
+                rng,
+                fluids,
+            );
+            if let Some(warmup) = self.warmups.get_mut(node_id) {
+                warmup.settle(prev_state.as_ref(), state, &mut result);
+            }
+            result
         };
+        if let Some(counters) = self.step_counters.as_mut() {
+            counters.processors_ticked += 1;
+        }
 
         // Emit production events.
         for &(item_type, quantity) in &processor_result.consumed {
@@ -1467,6 +3367,11 @@ impl Engine {
                 quantity,
                 tick,
             });
+            self.log_operation(
+                OperationRecord::new(OperationKind::Produced, tick)
+                    .node(node_id)
+                    .amount(item_type.0, quantity),
+            );
         }
 
         // Emit state-change events.
@@ -1491,6 +3396,10 @@ impl Engine {
                 ) => {
                     self.event_bus.emit(Event::RecipeStarted {
                         node: node_id,
+                        recipe: self
+                            .processors
+                            .get(node_id)
+                            .and_then(Processor::active_recipe),
                         tick,
                     });
                 }
@@ -1525,6 +3434,7 @@ impl Engine {
 
         // Apply produced items to output inventory (with property propagation).
         self.apply_produced(node_id, &processor_result, input_properties.as_ref());
+        self.apply_fluids(node_id, &processor_result);
 
         // Mark node hash dirty. Processor state (including Working { progress })
         // can change every tick even without state_changed being set.
@@ -1574,6 +3484,15 @@ impl Engine {
             .sum()
     }
 
+    /// Free space for `item_type` across the output slots that accept it.
+    pub(crate) fn output_space_for(output_inv: &Inventory, item_type: ItemTypeId) -> u32 {
+        output_inv
+            .output_slots
+            .iter()
+            .map(|s| s.free_space_for(item_type))
+            .fold(0u32, u32::saturating_add)
+    }
+
     /// Capture input item properties for a node (used before consuming for PropertyProcessor).
     fn capture_input_properties(
         &self,
@@ -1667,11 +3586,35 @@ impl Engine {
         }
     }
 
+    /// Deduct consumed recipe fluid from a node's input buffer and add
+    /// produced fluid to its output buffer.
+    fn apply_fluids(&mut self, node_id: NodeId, result: &ProcessorResult) {
+        if result.fluid_consumed.is_empty() && result.fluid_produced.is_empty() {
+            return;
+        }
+        self.enable_extension(crate::extension::ExtensionId::Fluid);
+        let Some(ports) = self.fluid_ports.entry(node_id) else {
+            return;
+        };
+        let ports = ports.or_default();
+        for &(fluid, amount) in &result.fluid_consumed {
+            let left = ports.input_of(fluid) - amount;
+            if left > Fixed64::ZERO {
+                ports.input.insert(fluid, left);
+            } else {
+                ports.input.remove(&fluid);
+            }
+        }
+        for &(fluid, amount) in &result.fluid_produced {
+            *ports.output.entry(fluid).or_insert(Fixed64::ZERO) += amount;
+        }
+    }
+
     // -----------------------------------------------------------------------
     // Phase 4: Component -- junctions + modules
     // -----------------------------------------------------------------------
 
-    fn phase_component(&mut self) {
+    pub(crate) fn phase_component(&mut self) {
         // 1. Process junctions in topo order.
         if self.graph.topological_order().is_ok() {
             let order_vec = self.graph.take_topo_cache();
@@ -1681,23 +3624,19 @@ impl Engine {
                         continue;
                     };
                     let state = entry.or_default();
+                    if let Some(counters) = self.step_counters.as_mut() {
+                        counters.junctions_processed += 1;
+                    }
                     // Process junction based on type.
                     match &junction {
                         Junction::Inserter(config) => {
                             // Inserter: move items from input to output.
                             let mut to_move = config.stack_size;
                             if let Some(input_inv) = self.inputs.get(node_id) {
-                                let available: u32 = input_inv
-                                    .input_slots
-                                    .iter()
-                                    .map(|s| {
-                                        if let Some(filter) = config.filter {
-                                            s.quantity(filter)
-                                        } else {
-                                            s.total()
-                                        }
-                                    })
-                                    .sum();
+                                let available = match config.filter {
+                                    Some(filter) => input_inv.count_of(filter),
+                                    None => input_inv.total(),
+                                };
                                 to_move = to_move.min(available);
                             }
                             // Clamp by speed accumulator.
@@ -1757,6 +3696,9 @@ impl Engine {
 
         // 2. Run module on_tick() (std::mem::take pattern for borrow safety).
         let mut modules = std::mem::take(&mut self.modules);
+        if let Some(counters) = self.step_counters.as_mut() {
+            counters.modules_run += modules.len() as u32;
+        }
         for module in &mut modules {
             let mut ctx = crate::module::ModuleContext {
                 graph: &self.graph,
@@ -1766,12 +3708,26 @@ impl Engine {
                 outputs: &mut self.outputs,
                 event_bus: &mut self.event_bus,
                 tick: self.sim_state.tick,
+                work: 0,
             };
             module.on_tick(&mut ctx);
+            let work = ctx.work;
+            if let Some(counters) = self.step_counters.as_mut() {
+                counters.module_work += work;
+            }
         }
         self.modules = modules;
 
-        // 3. Reset dirty tracker at end of component phase.
+        // 3. Advance hand-crafting queues.
+        self.advance_crafting_queues();
+
+        // 4. Age perishable items and spoil the expired ones.
+        self.advance_spoilage();
+
+        // 5. Report inventory slots that filled up or gained space.
+        self.detect_capacity_transitions();
+
+        // 6. Reset dirty tracker at end of component phase.
         self.dirty.mark_clean();
     }
 
@@ -1779,10 +3735,13 @@ impl Engine {
     // Phase 5: Post-tick -- event delivery
     // -----------------------------------------------------------------------
 
-    fn phase_post_tick(&mut self) {
+    pub(crate) fn phase_post_tick(&mut self) {
         // Deliver all buffered events to subscribers. Reactive handlers
         // may produce mutations that accumulate in event_bus.pending_mutations.
         // Those mutations will be applied during the next tick's pre-tick phase.
+        if let Some(counters) = self.step_counters.as_mut() {
+            counters.events_delivered += self.event_bus.pending_event_count() as u32;
+        }
         self.event_bus.deliver();
     }
 
@@ -1790,31 +3749,97 @@ impl Engine {
     // Phase 6: Bookkeeping
     // -----------------------------------------------------------------------
 
-    fn phase_bookkeeping(&mut self) {
+    pub(crate) fn phase_bookkeeping(&mut self) {
         // Only mark Graph partition: sim_state.tick and last_state_hash live there.
         // Other partitions are marked by the phases that actually mutate them.
         self.dirty
             .mark_partition(crate::dirty::DirtyTracker::PARTITION_GRAPH);
+        if self.event_log.is_some() || self.event_history.is_some() {
+            let events = self.event_bus.take_recorded();
+            if let Some(history) = self.event_history.as_mut() {
+                for event in &events {
+                    history.push(event.clone());
+                }
+            }
+            if let Some(log) = self.event_log.as_mut() {
+                log.push_tick(self.sim_state.tick, events);
+            }
+        }
         self.sim_state.tick += 1;
         self.last_state_hash = self.compute_state_hash();
+        self.log_operation(OperationRecord::new(
+            OperationKind::StepCompleted,
+            self.sim_state.tick - 1,
+        ));
+    }
+
+    /// Log the operations of an applied mutation batch, in event order.
+    fn log_mutations(&mut self, result: &crate::graph::MutationResult, tick: Ticks) {
+        for &node in &result.removed_nodes {
+            if !result.added_nodes.iter().any(|&(_, n)| n == node) {
+                self.log_operation(
+                    OperationRecord::new(OperationKind::NodeRemoved, tick).node(node),
+                );
+            }
+        }
+        for &edge in &result.removed_edges {
+            if !result.added_edges.iter().any(|&(_, e)| e == edge) {
+                self.log_operation(
+                    OperationRecord::new(OperationKind::EdgeRemoved, tick).edge(edge),
+                );
+            }
+        }
+        for &(_, node) in &result.added_nodes {
+            if let Some(data) = self.graph.get_node(node) {
+                let building = data.building_type.0;
+                self.log_operation(
+                    OperationRecord::new(OperationKind::NodeAdded, tick)
+                        .node(node)
+                        .amount(building, 0),
+                );
+            }
+        }
+        for &(_, edge) in &result.added_edges {
+            if let Some(data) = self.graph.get_edge(edge) {
+                let from = data.from;
+                self.log_operation(
+                    OperationRecord::new(OperationKind::EdgeAdded, tick)
+                        .node(from)
+                        .edge(edge),
+                );
+            }
+        }
     }
 
-    /// Compute a deterministic hash of the current simulation state.
+    /// Compute a deterministic hash of the current simulation state and
+    /// store its per-subsystem breakdown.
     ///
     /// Uses incremental hashing: only nodes marked dirty since the last tick
     /// are rehashed. Falls back to full recomputation when the cache is cold
-    /// (after deserialization or graph mutations).
-    fn compute_state_hash(&mut self) -> u64 {
+    /// (after deserialization, graph mutations, or status changes), which is
+    /// also when the graph hash is recomputed.
+    pub(crate) fn compute_state_hash(&mut self) -> u64 {
         // When most nodes are dirty, a full linear rebuild is cheaper than
         // sort + dedup + per-node sub/add. Use incremental only when it
         // actually saves work.
         let node_count = self.graph.node_count();
+        let rehash_graph = self.hash_cache_cold;
         if self.hash_cache_cold || self.hash_dirty_nodes.len() > node_count / 2 {
             // Full rebuild: recompute every node hash.
             self.node_hash_cache.clear();
-            self.combined_node_hash = 0;
+            self.combined_node_hash = NodeHash::default();
             for (nid, _) in self.graph.nodes() {
-                let h = hash_node_state(nid, &self.inputs, &self.outputs, &self.processor_states);
+                if self.hash_skips_node(nid) {
+                    continue;
+                }
+                let h = hash_node_state(
+                    nid,
+                    &self.inputs,
+                    &self.outputs,
+                    &self.processor_states,
+                    &self.fluid_ports,
+                    &self.warmups,
+                );
                 self.node_hash_cache.insert(nid, h);
                 self.combined_node_hash = self.combined_node_hash.wrapping_add(h);
             }
@@ -1827,8 +3852,18 @@ impl Engine {
             self.hash_dirty_nodes.dedup();
 
             for &nid in &self.hash_dirty_nodes {
-                let old = self.node_hash_cache.get(nid).copied().unwrap_or(0);
-                let new = hash_node_state(nid, &self.inputs, &self.outputs, &self.processor_states);
+                if self.hash_skips_node(nid) {
+                    continue;
+                }
+                let old = self.node_hash_cache.get(nid).copied().unwrap_or_default();
+                let new = hash_node_state(
+                    nid,
+                    &self.inputs,
+                    &self.outputs,
+                    &self.processor_states,
+                    &self.fluid_ports,
+                    &self.warmups,
+                );
                 self.node_hash_cache.insert(nid, new);
                 self.combined_node_hash =
                     self.combined_node_hash.wrapping_sub(old).wrapping_add(new);
@@ -1836,13 +3871,38 @@ impl Engine {
             self.hash_dirty_nodes.clear();
         }
 
-        // Combine per-node hash with tick counter.
-        let tick_hash = {
+        let sim_state = {
             let mut h = StateHash::new();
             h.write_u64(self.sim_state.tick);
             h.finish()
         };
-        tick_hash.wrapping_add(self.combined_node_hash)
+        let graph = if rehash_graph {
+            self.hash_graph()
+        } else {
+            self.last_hash_breakdown.graph
+        };
+        let crafting = if self.crafting_queues.is_empty() {
+            0
+        } else {
+            self.crafting_queue_hash()
+        };
+        let modules = self
+            .modules
+            .iter()
+            .map(|m| m.state_hash())
+            .fold(self.extension_hash(), u64::wrapping_add);
+
+        self.last_hash_breakdown = HashBreakdown {
+            graph,
+            inventories: self.combined_node_hash.inventories,
+            processor_states: self.combined_node_hash.processor_state,
+            transports: self.hash_transports(),
+            sim_state,
+            fluid: self.combined_node_hash.fluid,
+            crafting,
+            modules,
+        };
+        self.last_hash_breakdown.combined()
     }
 
     // -----------------------------------------------------------------------
@@ -1851,9 +3911,9 @@ impl Engine {
 
     /// Get the processor's progress as a 0..1 fraction.
     ///
-    /// - `Working { progress }` on a `FixedRecipe` with `duration` returns
-    ///   `progress / duration`.
-    /// - `Idle` and `Stalled` return `Fixed64::ZERO`.
+    /// - `Working { progress }` on a `FixedRecipe` returns
+    ///   `progress / base_duration()`, which includes any per-unit time.
+    /// - `Idle`, `Stalled` and `Storing` return `Fixed64::ZERO`.
     /// - Source and Property processors always return `Fixed64::ZERO` (they
     ///   have no duration-based progress).
     pub fn get_processor_progress(&self, node: NodeId) -> Option<Fixed64> {
@@ -1862,8 +3922,9 @@ impl Engine {
             ProcessorState::Working { progress } => {
                 // Look up the processor to find the duration.
                 if let Some(Processor::Fixed(recipe)) = self.processors.get(node) {
-                    if recipe.duration > 0 {
-                        Some(Fixed64::from_num(*progress) / Fixed64::from_num(recipe.duration))
+                    let duration = recipe.base_duration();
+                    if duration > Fixed64::ZERO {
+                        Some(Fixed64::from_num(*progress) / duration)
                     } else {
                         Some(Fixed64::ZERO)
                     }
@@ -1873,7 +3934,9 @@ impl Engine {
                     Some(Fixed64::ZERO)
                 }
             }
-            ProcessorState::Idle | ProcessorState::Stalled { .. } => Some(Fixed64::ZERO),
+            ProcessorState::Idle | ProcessorState::Stalled { .. } | ProcessorState::Storing => {
+                Some(Fixed64::ZERO)
+            }
         }
     }
 
@@ -1934,6 +3997,35 @@ impl Engine {
         })
     }
 
+    /// List the items currently riding on an item (belt) transport, ordered
+    /// by lane and then from the destination end back to the source end.
+    ///
+    /// Returns an empty list for non-belt transports and `None` if the edge
+    /// has no transport.
+    pub fn transport_items(&self, edge: EdgeId) -> Option<Vec<TransportItemView>> {
+        let transport = self.transports.get(edge)?;
+        let state = self.transport_states.get(edge)?;
+        let (Transport::Item(item), TransportState::Item(belt)) = (transport, state) else {
+            return Some(Vec::new());
+        };
+
+        let travel = item.travel();
+        let views = belt
+            .items()
+            .map(|(lane, belt_item)| TransportItemView {
+                instance_id: belt_item.instance_id,
+                item_type: belt_item.item_type,
+                lane,
+                position: if travel > Fixed64::ZERO {
+                    belt_item.position.saturating_div(travel)
+                } else {
+                    Fixed64::ONE
+                },
+            })
+            .collect();
+        Some(views)
+    }
+
     /// Total number of nodes in the production graph.
     pub fn node_count(&self) -> usize {
         self.graph.node_count()
@@ -1958,6 +4050,31 @@ impl Engine {
     // Profiling / Diagnostics
     // -----------------------------------------------------------------------
 
+    /// Enable or disable per-step operation counting. While enabled, each
+    /// step records a [`StepProfile`](crate::profiling::StepProfile) readable
+    /// via [`last_step_profile`](Self::last_step_profile). Disabling clears
+    /// the last profile.
+    pub fn set_profiling(&mut self, enabled: bool) {
+        if enabled {
+            self.step_counters
+                .get_or_insert_with(crate::profiling::StepProfile::default);
+        } else {
+            self.step_counters = None;
+            self.last_step_profile = None;
+        }
+    }
+
+    /// Whether per-step operation counting is enabled.
+    pub fn is_profiling(&self) -> bool {
+        self.step_counters.is_some()
+    }
+
+    /// Get the operation counts from the most recent step. `None` if
+    /// profiling is disabled or no step has run since it was enabled.
+    pub fn last_step_profile(&self) -> Option<&crate::profiling::StepProfile> {
+        self.last_step_profile.as_ref()
+    }
+
     /// Get the timing profile from the most recent tick.
     /// Only available with the `profiling` feature.
     #[cfg(feature = "profiling")]
@@ -2066,14 +4183,36 @@ impl Engine {
         self.inputs.remove(node);
         self.outputs.remove(node);
         self.modifiers.remove(node);
+        self.input_modes.remove(node);
         self.junctions.remove(node);
         self.junction_states.remove(node);
+        self.merge_priorities.remove(node);
+        self.merge_cursors.remove(node);
+        self.node_item_type_cache.remove(node);
+        self.node_rngs.remove(node);
+        self.fluid_ports.remove(node);
+        self.ghost_nodes.remove(node);
+        self.full_slots.remove(node);
+        self.demand_categories.remove(node);
+        self.node_user_data.remove(node);
+        self.source_sites.remove(node);
+        self.warmups.remove(node);
+        self.event_bus.unsilence_node(node);
+        self.crafting_queues
+            .retain(|_, queue| queue.inventory != node);
     }
 
     /// Remove all per-edge state for an edge.
     pub fn remove_edge_state(&mut self, edge: EdgeId) {
-        self.transports.remove(edge);
+        self.replace_transport(edge, None);
         self.transport_states.remove(edge);
+        self.edge_budgets.remove(edge);
+        self.merge_turns.remove(edge);
+        self.ghost_edges.remove(edge);
+        self.edge_category_filters.remove(edge);
+        self.category_picks.remove(edge);
+        self.ungroup_edge(edge);
+        self.latency.remove_edge(edge);
     }
 }
 
@@ -2118,6 +4257,79 @@ fn compute_utilization(transport: &Transport, state: &TransportState) -> Fixed64
     }
 }
 
+/// Describe a transport's progress through its delivery cycle.
+fn compute_progress(transport: &Transport, state: &TransportState) -> TransportProgress {
+    match (transport, state) {
+        (Transport::Item(item), TransportState::Item(bs)) => {
+            let travel = item.travel();
+            let fronts = || bs.lanes.iter().filter_map(|lane| lane.first());
+            let head = fronts().map(|i| i.position).max();
+            let head_position = head.map(|position| {
+                if travel > Fixed64::ZERO {
+                    position.saturating_div(travel).min(Fixed64::ONE)
+                } else {
+                    Fixed64::ONE
+                }
+            });
+            // A lane's front item moves unobstructed, `speed` per step, and
+            // is delivered on the step it reaches the output end.
+            let ticks_until_delivery = fronts()
+                .filter_map(|front| {
+                    if front.position >= travel {
+                        return Some(1);
+                    }
+                    if item.speed <= Fixed64::ZERO {
+                        return None;
+                    }
+                    let gap = travel - front.position;
+                    let quotient = gap.saturating_div(item.speed).saturating_ceil();
+                    // Division rounds down; step once more if that fell short.
+                    let short = front
+                        .position
+                        .saturating_add(item.speed.saturating_mul(quotient))
+                        < travel;
+                    let steps = quotient
+                        .saturating_to_num::<u32>()
+                        .saturating_add(u32::from(short));
+                    Some(steps.max(1))
+                })
+                .min();
+            TransportProgress::Item {
+                items_in_transit: bs.occupied_count() as u32,
+                head_position,
+                ticks_until_delivery,
+            }
+        }
+        (Transport::Batch(batch), TransportState::Batch(bs)) => TransportProgress::Batch {
+            accumulated: bs.pending,
+            batch_size: batch.batch_size,
+            ticks_until_forced_dispatch: batch.cycle_time.saturating_sub(bs.progress).max(1),
+        },
+        (Transport::Vehicle(vehicle), TransportState::Vehicle(vs)) => {
+            let (phase, ticks) = if vs.returning {
+                (VehiclePhase::Returning, vs.position.max(1))
+            } else if vs.position == 0 && vs.cargo.is_empty() {
+                (VehiclePhase::Loading, 1)
+            } else {
+                match vehicle.travel_time.saturating_sub(vs.position).max(1) {
+                    1 => (VehiclePhase::Unloading, 1),
+                    ticks => (VehiclePhase::Traveling, ticks),
+                }
+            };
+            TransportProgress::Vehicle {
+                phase,
+                ticks_remaining_in_phase: ticks,
+                cargo_count: vs.cargo.iter().map(|s| s.quantity).sum(),
+            }
+        }
+        (Transport::Flow(flow), TransportState::Flow(fs)) => TransportProgress::Flow {
+            buffered: fs.buffered,
+            capacity: flow.buffer_capacity,
+        },
+        (transport, _) => compute_progress(transport, &TransportState::new_for(transport)),
+    }
+}
+
 /// Count items currently in transit within a transport.
 fn count_items_in_transit(state: &TransportState) -> u32 {
     match state {
@@ -2128,6 +4340,135 @@ fn count_items_in_transit(state: &TransportState) -> u32 {
     }
 }
 
+/// Take `quantity` of `item_type` from an inventory's output slots, in the
+/// order [`Inventory::take`] does. Returns the properties of the items taken,
+/// grouped, or `None` when none of them has any.
+fn take_property_groups(
+    inv: &mut Inventory,
+    item_type: ItemTypeId,
+    quantity: u32,
+) -> Option<Vec<PropertyGroup>> {
+    let tagged = inv.output_slots.iter().any(|slot| {
+        slot.get_properties(item_type)
+            .is_some_and(|p| !p.is_empty())
+    });
+    if !tagged {
+        let _ = inv.take(item_type, quantity);
+        return None;
+    }
+    let mut groups: Vec<PropertyGroup> = Vec::new();
+    let mut remaining = quantity;
+    for slot in &mut inv.output_slots {
+        if remaining == 0 {
+            break;
+        }
+        let properties = slot.get_properties(item_type).cloned().unwrap_or_default();
+        let taken = slot.remove(item_type, remaining);
+        remaining -= taken;
+        if taken == 0 {
+            continue;
+        }
+        match groups.last_mut() {
+            Some(last) if last.properties == properties => last.quantity += taken,
+            _ => groups.push(PropertyGroup {
+                quantity: taken,
+                properties,
+            }),
+        }
+    }
+    Some(groups)
+}
+
+/// Record the properties of `moved` items that just entered a transport.
+/// A belt numbered them in intake order, up to just before `next_id`.
+fn record_intake(
+    state: &mut TransportState,
+    moved: u32,
+    intake: Option<&[PropertyGroup]>,
+    next_id: u64,
+) {
+    let manifest = match state {
+        TransportState::Item(belt) => {
+            let mut id = next_id - u64::from(moved);
+            for group in intake.unwrap_or_default() {
+                if !group.properties.is_empty() {
+                    for instance_id in id..id + u64::from(group.quantity) {
+                        belt.properties
+                            .insert(instance_id, group.properties.clone());
+                    }
+                }
+                id += u64::from(group.quantity);
+            }
+            return;
+        }
+        TransportState::Flow(flow) => &mut flow.properties,
+        TransportState::Batch(batch) => &mut batch.properties,
+        TransportState::Vehicle(vehicle) => &mut vehicle.properties,
+    };
+    match intake {
+        Some(groups) => {
+            for group in groups {
+                manifest.record(group.quantity, &group.properties);
+            }
+        }
+        None => manifest.record(moved, &std::collections::BTreeMap::new()),
+    }
+}
+
+/// The properties of `delivered` items that just left a transport, grouped,
+/// or `None` when none of them has any.
+fn release_delivered(state: &mut TransportState, delivered: u32) -> Option<Vec<PropertyGroup>> {
+    let (manifest, held) = match state {
+        TransportState::Item(belt) => {
+            if belt.properties.is_empty() {
+                return None;
+            }
+            let on_belt: std::collections::BTreeSet<u64> =
+                belt.items().map(|(_, item)| item.instance_id).collect();
+            let mut tagged: Vec<PropertyGroup> = Vec::new();
+            belt.properties.retain(|id, properties| {
+                if on_belt.contains(id) {
+                    return true;
+                }
+                match tagged.iter_mut().find(|g| g.properties == *properties) {
+                    Some(group) => group.quantity += 1,
+                    None => tagged.push(PropertyGroup {
+                        quantity: 1,
+                        properties: std::mem::take(properties),
+                    }),
+                }
+                false
+            });
+            let plain = delivered.saturating_sub(tagged.iter().map(|g| g.quantity).sum());
+            if plain > 0 {
+                tagged.insert(
+                    0,
+                    PropertyGroup {
+                        quantity: plain,
+                        properties: std::collections::BTreeMap::new(),
+                    },
+                );
+            }
+            return Some(tagged);
+        }
+        TransportState::Flow(flow) => {
+            let held = flow.held();
+            (&mut flow.properties, held)
+        }
+        TransportState::Batch(batch) => (&mut batch.properties, batch.pending),
+        TransportState::Vehicle(vehicle) => {
+            let held = vehicle.cargo.iter().map(|s| s.quantity).sum();
+            (&mut vehicle.properties, held)
+        }
+    };
+    if manifest.is_empty() {
+        return None;
+    }
+    let mut groups = Vec::new();
+    manifest.release(delivered, held + delivered, &mut groups);
+    Some(groups)
+}
+
 /// Collect inventory contents into a flat list of ItemStacks.
 /// If `input` is true, reads input_slots; otherwise reads output_slots.
 fn inventory_contents(inv: Option<&Inventory>, input: bool) -> Vec<ItemStack> {
@@ -2220,9 +4561,13 @@ mod tests {
                     item_type,
                     quantity,
                     bonus: None,
+                    probability: None,
                 })
                 .collect(),
             duration,
+            fluid_inputs: Vec::new(),
+            fluid_outputs: Vec::new(),
+            duration_per_unit: None,
         })
     }
 
@@ -2401,6 +4746,189 @@ mod tests {
         assert_eq!(engine.sim_state.accumulator, 0);
     }
 
+    #[test]
+    fn switching_strategy_mid_run_keeps_factory_state() {
+        let build = || {
+            let mut engine = Engine::new(SimulationStrategy::Tick);
+            let node = test_utils::add_node(&mut engine, make_source(iron(), 1.0), 100, 1000);
+            for _ in 0..3 {
+                engine.step();
+            }
+            (engine, node)
+        };
+        let (mut switched, node) = build();
+        let (mut reference, _) = build();
+
+        assert_eq!(
+            switched.set_strategy(SimulationStrategy::Delta { fixed_timestep: 2 }),
+            0
+        );
+        assert!(matches!(
+            switched.strategy(),
+            SimulationStrategy::Delta { fixed_timestep: 2 }
+        ));
+        assert_eq!(switched.state_hash(), reference.state_hash());
+
+        // Four ticks of elapsed time are two fixed steps.
+        assert_eq!(switched.advance(4).steps_run, 2);
+        reference.step();
+        reference.step();
+        assert_eq!(switched.sim_state.tick, 5);
+        assert_eq!(switched.state_hash(), reference.state_hash());
+        assert_eq!(
+            test_utils::output_quantity(&switched, node, iron()),
+            test_utils::output_quantity(&reference, node, iron())
+        );
+
+        // A new timestep keeps the unsimulated remainder; tick mode drops it.
+        assert_eq!(switched.advance(3).steps_run, 1);
+        switched.set_strategy(SimulationStrategy::Delta { fixed_timestep: 3 });
+        assert_eq!(switched.sim_state.accumulator, 1);
+        assert_eq!(switched.advance(2).steps_run, 1);
+        assert_eq!(switched.advance(2).steps_run, 0);
+        assert_eq!(switched.set_strategy(SimulationStrategy::Tick), 2);
+        assert_eq!(switched.sim_state.accumulator, 0);
+        assert_eq!(switched.advance(100).steps_run, 1);
+        assert_eq!(switched.sim_state.tick, 8);
+    }
+
+    /// Run an explosive factory -- a source flooding a sink -- under a small
+    /// work limit, recording every truncation.
+    fn run_explosive(steps: usize) -> (Vec<(Ticks, StepPhase, u64)>, Vec<bool>, u64) {
+        let mut engine = Engine::new(SimulationStrategy::Tick);
+        let source = test_utils::add_node(&mut engine, make_source(iron(), 500.0), 10, 100_000);
+        let sink = test_utils::add_node(&mut engine, Processor::Passthrough, 1_000_000, 1_000_000);
+        test_utils::connect(
+            &mut engine,
+            source,
+            sink,
+            test_utils::make_flow_transport(500.0),
+        );
+        engine.set_step_work_limit(Some(400));
+
+        let truncations = Rc::new(RefCell::new(Vec::new()));
+        let log = Rc::clone(&truncations);
+        engine.on_passive(
+            EventKind::StepTruncated,
+            Box::new(move |event| {
+                if let Event::StepTruncated {
+                    phase,
+                    work_done,
+                    tick,
+                } = event
+                {
+                    log.borrow_mut().push((*tick, *phase, *work_done));
+                }
+            }),
+        );
+        let flags = (0..steps).map(|_| engine.step().truncated).collect();
+        let truncations = truncations.borrow().clone();
+        (truncations, flags, engine.state_hash())
+    }
+
+    #[test]
+    fn step_work_limit_truncates_at_the_same_point_every_run() {
+        let (truncations, flags, hash) = run_explosive(6);
+        assert!(!truncations.is_empty());
+        // The first delivery of 500 items blows the budget in transport.
+        assert_eq!(truncations[0].0, 1);
+        assert_eq!(truncations[0].1, StepPhase::Transport);
+        assert!(truncations[0].2 > 400);
+        assert!(!flags[0] && flags[1]);
+        assert_eq!(
+            flags.iter().filter(|&&truncated| truncated).count(),
+            truncations.len()
+        );
+
+        assert_eq!(run_explosive(6), (truncations, flags, hash));
+    }
+
+    #[test]
+    fn step_work_limit_leaves_normal_factories_alone() {
+        let mut limited = test_utils::build_large_factory(200);
+        let mut unlimited = test_utils::build_large_factory(200);
+        limited.set_step_work_limit(Some(10_000));
+        for _ in 0..100 {
+            assert!(!limited.step().truncated);
+            unlimited.step();
+        }
+        assert_eq!(limited.state_hash(), unlimited.state_hash());
+        assert_eq!(limited.last_step_profile(), None);
+    }
+
+    #[test]
+    fn truncation_ends_a_delta_advance() {
+        let mut engine = Engine::new(SimulationStrategy::Delta { fixed_timestep: 1 });
+        let source = test_utils::add_node(&mut engine, make_source(iron(), 500.0), 10, 100_000);
+        let sink = test_utils::add_node(&mut engine, Processor::Passthrough, 1_000_000, 1_000_000);
+        test_utils::connect(
+            &mut engine,
+            source,
+            sink,
+            test_utils::make_flow_transport(500.0),
+        );
+        engine.set_step_work_limit(Some(400));
+
+        let result = engine.advance(10);
+        assert_eq!(result.steps_run, 2);
+        assert!(result.truncated);
+        assert_eq!(engine.sim_state.accumulator, 8);
+
+        let restored = Engine::deserialize(&engine.serialize().unwrap()).unwrap();
+        assert_eq!(restored.step_work_limit(), Some(400));
+    }
+
+    #[test]
+    fn catchup_limit_discards_whole_steps() {
+        use crate::sim::CatchupPolicy;
+        let mut engine = Engine::new(SimulationStrategy::Delta { fixed_timestep: 2 });
+        engine.set_max_catchup_steps(Some(3), CatchupPolicy::Discard);
+
+        let result = engine.advance(21);
+        assert_eq!(result.steps_run, 3);
+        assert_eq!(result.discarded, 14);
+        assert_eq!(engine.sim_state.accumulator, 1);
+        assert_eq!(engine.sim_state.tick, 3);
+
+        // The kept fraction still counts towards the next step.
+        let result = engine.advance(1);
+        assert_eq!(result.steps_run, 1);
+        assert_eq!(result.discarded, 0);
+        assert_eq!(engine.sim_state.accumulator, 0);
+
+        // Calls within the limit are unaffected.
+        let result = engine.advance(6);
+        assert_eq!((result.steps_run, result.discarded), (3, 0));
+    }
+
+    #[test]
+    fn catchup_limit_retains_time_for_later_calls() {
+        use crate::sim::CatchupPolicy;
+        let mut engine = Engine::new(SimulationStrategy::Delta { fixed_timestep: 1 });
+        engine.set_max_catchup_steps(Some(4), CatchupPolicy::Retain);
+
+        let result = engine.advance(10);
+        assert_eq!((result.steps_run, result.discarded), (4, 0));
+        assert_eq!(engine.sim_state.accumulator, 6);
+
+        let mut restored = Engine::deserialize(&engine.serialize().unwrap()).unwrap();
+        assert_eq!(
+            restored.max_catchup_steps(),
+            Some((4, CatchupPolicy::Retain))
+        );
+        assert_eq!(restored.advance(0).steps_run, 4);
+        assert_eq!(restored.advance(0).steps_run, 2);
+        assert_eq!(restored.sim_state.accumulator, 0);
+        assert_eq!(restored.sim_state.tick, 10);
+
+        // Tick mode runs one step per call regardless.
+        restored.set_strategy(SimulationStrategy::Tick);
+        restored.set_max_catchup_steps(Some(1), CatchupPolicy::Discard);
+        assert_eq!(restored.advance(100).steps_run, 1);
+        restored.set_max_catchup_steps(None, CatchupPolicy::Discard);
+        assert_eq!(restored.max_catchup_steps(), None);
+    }
+
     // -----------------------------------------------------------------------
     // Test 4: Queued mutation -- add node mid-tick applies next tick
     // -----------------------------------------------------------------------
@@ -2877,6 +5405,71 @@ mod tests {
         assert_eq!(*completed_ticks.borrow(), vec![2]);
     }
 
+    #[test]
+    fn scaled_duration_drives_progress_and_completion_events() {
+        let mut engine = Engine::new(SimulationStrategy::Tick);
+
+        let pending = engine.graph.queue_add_node(building());
+        let result = engine.graph.apply_mutations();
+        let node = result.resolve_node(pending).unwrap();
+
+        // 4 iron -> 1 gear, 2 ticks + 2 ticks per iron = 10 ticks.
+        let Processor::Fixed(recipe) = make_recipe(vec![(iron(), 4)], vec![(gear(), 1)], 2) else {
+            unreachable!()
+        };
+        engine.set_processor(
+            node,
+            Processor::Fixed(FixedRecipe {
+                duration_per_unit: Some(Fixed64::from_num(2)),
+                ..recipe
+            }),
+        );
+
+        let mut input_inv = simple_inventory(100);
+        let _ = input_inv.input_slots[0].add(iron(), 4);
+        engine.set_input_inventory(node, input_inv);
+        engine.set_output_inventory(node, simple_inventory(100));
+
+        let started_ticks = Rc::new(RefCell::new(Vec::new()));
+        let completed_ticks = Rc::new(RefCell::new(Vec::new()));
+
+        let st = started_ticks.clone();
+        engine.on_passive(
+            EventKind::RecipeStarted,
+            Box::new(move |event| {
+                if let Event::RecipeStarted { tick, .. } = event {
+                    st.borrow_mut().push(*tick);
+                }
+            }),
+        );
+
+        let ct = completed_ticks.clone();
+        engine.on_passive(
+            EventKind::RecipeCompleted,
+            Box::new(move |event| {
+                if let Event::RecipeCompleted { tick, .. } = event {
+                    ct.borrow_mut().push(*tick);
+                }
+            }),
+        );
+
+        for _ in 0..5 {
+            engine.step();
+        }
+        // Progress is measured against the scaled duration.
+        assert_eq!(
+            engine.get_processor_progress(node),
+            Some(Fixed64::from_num(5) / Fixed64::from_num(10))
+        );
+        assert!(completed_ticks.borrow().is_empty());
+
+        for _ in 0..5 {
+            engine.step();
+        }
+        assert_eq!(*started_ticks.borrow(), vec![0]);
+        assert_eq!(*completed_ticks.borrow(), vec![9]);
+    }
+
     // -----------------------------------------------------------------------
     // Event Test 3: BuildingStalled event emitted when output full
     // -----------------------------------------------------------------------
@@ -3139,6 +5732,98 @@ mod tests {
         assert_eq!(*event_ticks.borrow(), vec![0, 1, 2]);
     }
 
+    // -----------------------------------------------------------------------
+    // Graph events from host-applied mutations carry the upcoming tick
+    // -----------------------------------------------------------------------
+    #[test]
+    fn event_graph_mutations_stamped_with_upcoming_tick() {
+        let mut engine = Engine::new(SimulationStrategy::Tick);
+
+        let seen = Rc::new(RefCell::new(Vec::new()));
+        for kind in [
+            EventKind::NodeAdded,
+            EventKind::EdgeAdded,
+            EventKind::NodeRemoved,
+            EventKind::EdgeRemoved,
+        ] {
+            let s = seen.clone();
+            engine.on_passive(
+                kind,
+                Box::new(move |event| s.borrow_mut().push(event.clone())),
+            );
+        }
+
+        engine.step();
+        engine.step();
+        assert_eq!(engine.sim_state.tick, 2);
+
+        // Apply twice between two steps.
+        let pa = engine.graph.queue_add_node(building());
+        let pb = engine.graph.queue_add_node(BuildingTypeId(7));
+        let r1 = engine.apply_mutations();
+        let a = r1.resolve_node(pa).unwrap();
+        let b = r1.resolve_node(pb).unwrap();
+        let pe = engine.graph.queue_connect(a, b);
+        let r2 = engine.apply_mutations();
+        let e = r2.resolve_edge(pe).unwrap();
+
+        // Buffered, not dispatched immediately.
+        assert!(seen.borrow().is_empty());
+
+        engine.step();
+        {
+            let events = seen.borrow();
+            assert_eq!(events.len(), 3);
+            assert!(events.contains(&Event::NodeAdded {
+                node: a,
+                building_type: building(),
+                tick: 2,
+            }));
+            assert!(events.contains(&Event::NodeAdded {
+                node: b,
+                building_type: BuildingTypeId(7),
+                tick: 2,
+            }));
+            assert!(events.contains(&Event::EdgeAdded {
+                edge: e,
+                from: a,
+                to: b,
+                tick: 2,
+            }));
+        }
+        seen.borrow_mut().clear();
+
+        // Removal via the next step's pre-tick uses that step's tick.
+        engine.graph.queue_remove_node(a);
+        engine.step();
+        let events = seen.borrow();
+        assert!(events.contains(&Event::NodeRemoved { node: a, tick: 3 }));
+        assert!(events.contains(&Event::EdgeRemoved { edge: e, tick: 3 }));
+    }
+
+    #[test]
+    fn apply_mutations_same_batch_add_and_remove_emits_nothing() {
+        let mut engine = Engine::new(SimulationStrategy::Tick);
+        let pa = engine.graph.queue_add_node(building());
+        let pb = engine.graph.queue_add_node(building());
+        let result = engine.apply_mutations();
+        let a = result.resolve_node(pa).unwrap();
+        let b = result.resolve_node(pb).unwrap();
+        engine.event_bus.clear_all();
+
+        // Connect and remove an endpoint in the same batch.
+        let pe = engine.graph.queue_connect(a, b);
+        engine.graph.queue_remove_node(b);
+        let result = engine.apply_mutations();
+        let e = result.resolve_edge(pe).unwrap();
+        assert_eq!(result.removed_nodes, vec![b]);
+        assert_eq!(result.removed_edges, vec![e]);
+
+        assert_eq!(engine.event_bus.buffered_count(EventKind::EdgeAdded), 0);
+        assert_eq!(engine.event_bus.buffered_count(EventKind::EdgeRemoved), 0);
+        assert_eq!(engine.event_bus.buffered_count(EventKind::NodeRemoved), 1);
+    }
+
     // -----------------------------------------------------------------------
     // Event Test 9: ItemConsumed events from recipe processing
     // -----------------------------------------------------------------------
@@ -3357,6 +6042,7 @@ mod tests {
             speed: Fixed64::from_num(1.0),
             slot_count: 4,
             lanes: 1,
+            min_spacing: Fixed64::ONE,
         });
         engine.set_transport(edge, belt);
 
@@ -3548,7 +6234,34 @@ mod tests {
     // Query Test 11: Transport snapshot
     // -----------------------------------------------------------------------
     #[test]
-    fn query_transport_snapshot() {
+    fn in_transit_count_fills_to_belt_capacity() {
+        let mut engine = Engine::new(SimulationStrategy::Tick);
+        let src = test_utils::add_node(&mut engine, test_utils::make_source(iron(), 1.0), 100, 100);
+        // The sink takes one item and never passes it on.
+        let sink = test_utils::add_node(&mut engine, Processor::Passthrough, 1, 1);
+        let belt = test_utils::connect(&mut engine, src, sink, test_utils::make_item_transport(8));
+        assert_eq!(engine.transport_in_transit_count(belt), 0);
+
+        for _ in 0..4 {
+            engine.step();
+        }
+        let partway = engine.transport_in_transit_count(belt);
+        assert!((1..8).contains(&partway), "got {partway}");
+        assert_eq!(
+            partway as usize,
+            engine.transport_items(belt).unwrap().len()
+        );
+
+        for _ in 0..40 {
+            engine.step();
+        }
+        assert_eq!(engine.transport_in_transit_count(belt), 8);
+        assert_eq!(engine.snapshot_transport(belt).unwrap().items_in_transit, 8);
+        assert_eq!(engine.transport_in_transit_count(EdgeId::default()), 0);
+    }
+
+    #[test]
+    fn query_transport_snapshot() {
         let (mut engine, src_node, consumer_node, edge_id) =
             setup_source_transport_consumer(5.0, 10.0, vec![(iron(), 2)], vec![(gear(), 1)], 3);
 
@@ -3568,6 +6281,175 @@ mod tests {
         assert_eq!(snap.to, consumer_node);
     }
 
+    /// An iron source feeding a chest that keeps everything it receives.
+    fn progress_fixture(transport: Transport) -> (Engine, NodeId, EdgeId) {
+        let mut engine = Engine::new(SimulationStrategy::Tick);
+        let src = test_utils::add_node(&mut engine, test_utils::make_source(iron(), 1.0), 100, 100);
+        let chest = test_utils::add_node(
+            &mut engine,
+            test_utils::make_source(test_utils::copper(), 0.0),
+            1000,
+            10,
+        );
+        let edge = test_utils::connect(&mut engine, src, chest, transport);
+        (engine, chest, edge)
+    }
+
+    /// Steps until `chest` next receives items, counted on a copy of `engine`.
+    fn steps_until_delivery(engine: &Engine, chest: NodeId) -> u32 {
+        let mut fork = Engine::deserialize(&engine.serialize().unwrap()).unwrap();
+        let before = test_utils::input_total(&fork, chest);
+        for steps in 1..=100 {
+            fork.step();
+            if test_utils::input_total(&fork, chest) > before {
+                return steps;
+            }
+        }
+        panic!("nothing was delivered");
+    }
+
+    #[test]
+    fn belt_progress_predicts_the_next_delivery() {
+        let belt = Transport::Item(ItemTransport {
+            speed: Fixed64::from_num(0.75),
+            slot_count: 5,
+            lanes: 1,
+            min_spacing: Fixed64::ONE,
+        });
+        let (mut engine, chest, edge) = progress_fixture(belt);
+        assert_eq!(
+            engine.transport_progress(edge),
+            Some(TransportProgress::Item {
+                items_in_transit: 0,
+                head_position: None,
+                ticks_until_delivery: None,
+            })
+        );
+
+        for _ in 0..20 {
+            engine.step();
+            let Some(TransportProgress::Item {
+                items_in_transit,
+                head_position,
+                ticks_until_delivery,
+            }) = engine.transport_progress(edge)
+            else {
+                panic!("expected belt progress");
+            };
+            assert_eq!(items_in_transit, engine.transport_in_transit_count(edge));
+            let views = engine.transport_items(edge).unwrap();
+            assert_eq!(head_position, views.iter().map(|v| v.position).max());
+            if items_in_transit == 0 {
+                assert_eq!(ticks_until_delivery, None);
+            } else {
+                assert_eq!(
+                    ticks_until_delivery,
+                    Some(steps_until_delivery(&engine, chest))
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn batch_progress_predicts_the_dispatch() {
+        let (mut engine, chest, edge) = progress_fixture(test_utils::make_batch_transport(10, 4));
+        for step in 0..12u32 {
+            let Some(TransportProgress::Batch {
+                accumulated,
+                batch_size,
+                ticks_until_forced_dispatch,
+            }) = engine.transport_progress(edge)
+            else {
+                panic!("expected batch progress");
+            };
+            assert_eq!(batch_size, 10);
+            assert_eq!(accumulated, engine.transport_in_transit_count(edge));
+            // The source adds one item per step, and every dispatch delivers.
+            if step > 0 {
+                assert_eq!(
+                    ticks_until_forced_dispatch,
+                    steps_until_delivery(&engine, chest)
+                );
+            }
+            engine.step();
+        }
+    }
+
+    #[test]
+    fn vehicle_progress_predicts_each_phase() {
+        let (mut engine, chest, edge) = progress_fixture(test_utils::make_vehicle_transport(3, 4));
+        let vehicle = |engine: &Engine| match engine.transport_progress(edge) {
+            Some(TransportProgress::Vehicle {
+                phase,
+                ticks_remaining_in_phase,
+                cargo_count,
+            }) => (phase, ticks_remaining_in_phase, cargo_count),
+            other => panic!("expected vehicle progress, got {other:?}"),
+        };
+        assert_eq!(vehicle(&engine), (VehiclePhase::Loading, 1, 0));
+
+        let mut seen = Vec::new();
+        for _ in 0..30 {
+            engine.step();
+            let (phase, ticks, cargo) = vehicle(&engine);
+            assert_eq!(cargo, engine.transport_in_transit_count(edge));
+            if !seen.contains(&phase) {
+                seen.push(phase);
+            }
+            let mut fork = Engine::deserialize(&engine.serialize().unwrap()).unwrap();
+            match phase {
+                VehiclePhase::Traveling | VehiclePhase::Unloading => {
+                    assert_eq!(ticks, steps_until_delivery(&engine, chest));
+                }
+                VehiclePhase::Returning => {
+                    for _ in 1..ticks {
+                        fork.step();
+                        assert_eq!(vehicle(&fork).0, VehiclePhase::Returning);
+                    }
+                    fork.step();
+                    assert_eq!(vehicle(&fork).0, VehiclePhase::Loading);
+                }
+                VehiclePhase::Loading => {
+                    assert_eq!(ticks, 1);
+                    fork.step();
+                    assert_ne!(vehicle(&fork).0, VehiclePhase::Loading);
+                }
+            }
+        }
+        assert_eq!(
+            seen,
+            vec![
+                VehiclePhase::Loading,
+                VehiclePhase::Traveling,
+                VehiclePhase::Unloading,
+                VehiclePhase::Returning,
+            ]
+        );
+    }
+
+    #[test]
+    fn flow_progress_reports_the_buffer() {
+        let flow = Transport::Flow(FlowTransport {
+            rate: Fixed64::from_num(2),
+            buffer_capacity: Fixed64::from_num(10),
+            latency: 3,
+        });
+        let (mut engine, _, edge) = progress_fixture(flow);
+        // The source makes one item per step, after the transport phase, and
+        // nothing drains during the latency.
+        for step in 1..=3 {
+            engine.step();
+            assert_eq!(
+                engine.transport_progress(edge),
+                Some(TransportProgress::Flow {
+                    buffered: Fixed64::from_num(step - 1),
+                    capacity: Fixed64::from_num(10),
+                })
+            );
+        }
+        assert_eq!(engine.transport_progress(EdgeId::default()), None);
+    }
+
     // -----------------------------------------------------------------------
     // Query Test 12: Snapshot node includes adjacency
     // -----------------------------------------------------------------------
@@ -4502,6 +7384,216 @@ mod tests {
         );
     }
 
+    // -----------------------------------------------------------------------
+    // Multi-output source with per-item output slots
+    // -----------------------------------------------------------------------
+    #[test]
+    fn multi_source_keeps_producing_when_one_output_is_full() {
+        use crate::event::Event;
+        use crate::item::InventorySlot;
+
+        let mut engine = Engine::new(SimulationStrategy::Tick);
+        let copper = test_utils::copper();
+        let mine = test_utils::add_node(
+            &mut engine,
+            Processor::MultiSource(MultiSourceProcessor::new(
+                vec![(test_utils::iron(), Fixed64::ONE), (copper, Fixed64::ONE)],
+                Depletion::Finite {
+                    remaining: Fixed64::from_num(20),
+                },
+            )),
+            1,
+            1,
+        );
+        // Room for two iron and plenty of copper.
+        engine.get_output_inventory_mut(mine).unwrap().output_slots = vec![
+            InventorySlot::filtered(2, test_utils::iron()),
+            InventorySlot::filtered(100, copper),
+        ];
+        engine.enable_event_log();
+
+        for _ in 0..5 {
+            engine.step();
+        }
+        let output = engine.get_output_inventory(mine).unwrap();
+        assert_eq!(output.count_of(test_utils::iron()), 2);
+        assert_eq!(output.count_of(copper), 5);
+        assert_eq!(
+            engine.get_processor_state(mine),
+            Some(&ProcessorState::Working { progress: 0 })
+        );
+        // Only what was placed counts against the deposit.
+        match &engine.get_processor(mine).unwrap() {
+            Processor::MultiSource(multi) => assert_eq!(
+                multi.depletion,
+                Depletion::Finite {
+                    remaining: Fixed64::from_num(13)
+                }
+            ),
+            other => panic!("unexpected processor {other:?}"),
+        }
+
+        // One ItemProduced per item type.
+        let log = engine.take_event_log().unwrap();
+        let produced = |item| {
+            log.events()
+                .filter(
+                    |e| matches!(e, Event::ItemProduced { item_type, .. } if *item_type == item),
+                )
+                .count()
+        };
+        assert_eq!(produced(test_utils::iron()), 2);
+        assert_eq!(produced(copper), 5);
+
+        // The shared deposit and accumulators survive a save and load.
+        let mut restored = Engine::deserialize(&engine.serialize().unwrap()).unwrap();
+        assert_eq!(restored.state_hash(), engine.state_hash());
+        for _ in 0..3 {
+            engine.step();
+            restored.step();
+        }
+        assert_eq!(restored.state_hash(), engine.state_hash());
+        assert_eq!(
+            restored
+                .get_output_inventory(mine)
+                .unwrap()
+                .count_of(copper),
+            8
+        );
+    }
+
+    // -----------------------------------------------------------------------
+    // Warmup and cooldown
+    // -----------------------------------------------------------------------
+    #[test]
+    fn warmup_ramps_a_source_up_to_full_rate() {
+        let mut engine = Engine::new(SimulationStrategy::Tick);
+        let mine = test_utils::add_node(
+            &mut engine,
+            test_utils::make_source(test_utils::iron(), 2.0),
+            100,
+            100,
+        );
+        let cold = test_utils::add_node(
+            &mut engine,
+            test_utils::make_source(test_utils::iron(), 2.0),
+            100,
+            100,
+        );
+        assert!(engine.set_warmup(cold, 3, 0));
+
+        let mut made = Vec::new();
+        for _ in 0..5 {
+            engine.step();
+            made.push(
+                engine
+                    .get_output_inventory(cold)
+                    .unwrap()
+                    .count_of(test_utils::iron()),
+            );
+        }
+        // 2 * (1/4, 2/4, 3/4), accumulated, then 2 per tick.
+        assert_eq!(made, vec![0, 1, 3, 5, 7]);
+        assert_eq!(
+            engine
+                .get_output_inventory(mine)
+                .unwrap()
+                .count_of(test_utils::iron()),
+            10
+        );
+        assert!(engine.warmup(cold).unwrap().is_warm());
+    }
+
+    #[test]
+    fn cooldown_rides_out_brief_input_gaps() {
+        use crate::event::{Event, EventKind};
+        use std::cell::RefCell;
+        use std::rc::Rc;
+
+        let mut engine = Engine::new(SimulationStrategy::Tick);
+        let furnace = test_utils::add_node(
+            &mut engine,
+            test_utils::make_recipe(
+                vec![(test_utils::iron(), 1)],
+                vec![(test_utils::gear(), 1)],
+                1,
+            ),
+            100,
+            100,
+        );
+        engine.set_warmup(furnace, 2, 3);
+        let stalls = Rc::new(RefCell::new(0));
+        let counter = Rc::clone(&stalls);
+        engine.on_passive(
+            EventKind::BuildingStalled,
+            Box::new(move |_: &Event| *counter.borrow_mut() += 1),
+        );
+        let feed = |engine: &mut Engine| {
+            let _ = engine
+                .get_input_inventory_mut(furnace)
+                .unwrap()
+                .insert(test_utils::iron(), 1);
+        };
+        let gears = |engine: &Engine| {
+            engine
+                .get_output_inventory(furnace)
+                .unwrap()
+                .count_of(test_utils::gear())
+        };
+
+        // Warm up on a steady supply.
+        for _ in 0..6 {
+            feed(&mut engine);
+            engine.step();
+        }
+        assert!(engine.warmup(furnace).unwrap().is_warm());
+
+        // A two-tick gap fits in the cooldown: no stall, still warm.
+        for _ in 0..2 {
+            engine.step();
+            assert_eq!(
+                engine.get_processor_state(furnace),
+                Some(&ProcessorState::Idle)
+            );
+        }
+        assert_eq!(*stalls.borrow(), 0);
+        assert!(engine.warmup(furnace).unwrap().is_warm());
+
+        // Back at full speed straight away: one gear per tick.
+        let before = gears(&engine);
+        for _ in 0..3 {
+            feed(&mut engine);
+            engine.step();
+        }
+        assert_eq!(gears(&engine), before + 3);
+
+        // The timers survive a save and load.
+        let restored = Engine::deserialize(&engine.serialize().unwrap()).unwrap();
+        assert_eq!(restored.warmup(furnace), engine.warmup(furnace));
+        assert_eq!(restored.state_hash(), engine.state_hash());
+
+        // A gap longer than the cooldown stalls it and it goes cold.
+        for _ in 0..5 {
+            engine.step();
+        }
+        assert_eq!(*stalls.borrow(), 1);
+        assert_eq!(
+            engine.get_processor_state(furnace),
+            Some(&ProcessorState::Stalled {
+                reason: StallReason::MissingInputs
+            })
+        );
+        assert!(!engine.warmup(furnace).unwrap().is_warm());
+
+        // Cold again, it no longer makes a gear every tick.
+        let before = gears(&engine);
+        for _ in 0..3 {
+            feed(&mut engine);
+            engine.step();
+        }
+        assert!(gears(&engine) < before + 3);
+    }
+
     // -----------------------------------------------------------------------
     // Source initial_properties stamped on output
     // -----------------------------------------------------------------------
@@ -4536,6 +7628,108 @@ mod tests {
         );
     }
 
+    // -----------------------------------------------------------------------
+    // Item properties carried through transports
+    // -----------------------------------------------------------------------
+    #[test]
+    fn transports_carry_item_properties_through_save_and_load() {
+        let iron = test_utils::iron();
+        let quality = PropertyId(1);
+        let fine: std::collections::BTreeMap<PropertyId, Fixed64> =
+            [(quality, Fixed64::from_num(3))].into();
+        let slow_flow = Transport::Flow(FlowTransport {
+            rate: Fixed64::ONE,
+            buffer_capacity: Fixed64::from_num(10),
+            latency: 3,
+        });
+        for transport in [
+            test_utils::make_item_transport(5),
+            slow_flow,
+            test_utils::make_batch_transport(10, 3),
+            test_utils::make_vehicle_transport(10, 3),
+        ] {
+            let mut engine = Engine::new(SimulationStrategy::Tick);
+            let forge =
+                test_utils::add_node(&mut engine, test_utils::make_source(iron, 0.0), 10, 10);
+            let chest = test_utils::add_node(
+                &mut engine,
+                test_utils::make_source(test_utils::copper(), 0.0),
+                10,
+                10,
+            );
+            test_utils::connect(&mut engine, forge, chest, transport);
+            let overflow = engine.get_output_inventory_mut(forge).unwrap().output_slots[0]
+                .add_with_properties(iron, 1, &fine);
+            assert_eq!(overflow, 0);
+
+            // The item leaves the forge but has not arrived yet.
+            engine.step();
+            assert_eq!(test_utils::output_quantity(&engine, forge, iron), 0);
+            assert_eq!(test_utils::input_quantity(&engine, chest, iron), 0);
+
+            let mut restored = Engine::deserialize(&engine.serialize().unwrap()).unwrap();
+            assert_eq!(restored.state_hash(), engine.state_hash());
+            for _ in 0..10 {
+                restored.step();
+            }
+            assert_eq!(test_utils::input_quantity(&restored, chest, iron), 1);
+            assert_eq!(
+                restored.get_input_item_property(chest, iron, quality),
+                Some(Fixed64::from_num(3))
+            );
+        }
+    }
+
+    #[test]
+    fn belt_items_keep_their_own_properties() {
+        let iron = test_utils::iron();
+        let quality = PropertyId(1);
+        let mut engine = Engine::new(SimulationStrategy::Tick);
+        let forge = test_utils::add_node(&mut engine, test_utils::make_source(iron, 0.0), 10, 10);
+        let chest = test_utils::add_node(
+            &mut engine,
+            test_utils::make_source(test_utils::copper(), 0.0),
+            10,
+            10,
+        );
+        let edge = test_utils::connect(
+            &mut engine,
+            forge,
+            chest,
+            test_utils::make_item_transport(3),
+        );
+        let grade = |value: i32| -> std::collections::BTreeMap<PropertyId, Fixed64> {
+            [(quality, Fixed64::from_num(value))].into()
+        };
+
+        // A fine item, then a plain one behind it.
+        let _ = engine.get_output_inventory_mut(forge).unwrap().output_slots[0]
+            .add_with_properties(iron, 1, &grade(3));
+        engine.step();
+        let _ = engine.get_output_inventory_mut(forge).unwrap().output_slots[0].add(iron, 1);
+        engine.step();
+        let Some(TransportState::Item(belt)) = engine.get_transport_state(edge) else {
+            panic!("expected a belt");
+        };
+        assert_eq!(belt.occupied_count(), 2);
+        assert_eq!(belt.properties.len(), 1);
+
+        // The fine item arrives with its quality even though nothing with
+        // properties is leaving the forge by then.
+        while test_utils::input_quantity(&engine, chest, iron) == 0 {
+            engine.step();
+        }
+        assert_eq!(test_utils::input_quantity(&engine, chest, iron), 1);
+        assert_eq!(
+            engine.get_input_item_property(chest, iron, quality),
+            Some(Fixed64::from_num(3))
+        );
+        let Some(TransportState::Item(belt)) = engine.get_transport_state(edge) else {
+            panic!("expected a belt");
+        };
+        assert!(belt.properties.is_empty());
+    }
+
     // -----------------------------------------------------------------------
     // Snapshot query tests (coverage for query.rs structs)
     // -----------------------------------------------------------------------
@@ -4754,13 +7948,8 @@ mod tests {
                 engine.step();
             }
 
-            // Manually compute what compute_state_hash should produce.
-            let tick_hash = {
-                let mut h = StateHash::new();
-                h.write_u64(engine.sim_state.tick);
-                h.finish()
-            };
-            let manual_combined: u64 = engine
+            // Manually sum the per-node hashes the incremental cache tracks.
+            let manual = engine
                 .graph
                 .nodes()
                 .map(|(nid, _)| {
@@ -4769,19 +7958,120 @@ mod tests {
                         &engine.inputs,
                         &engine.outputs,
                         &engine.processor_states,
+                        &engine.fluid_ports,
+                        &engine.warmups,
                     )
                 })
-                .fold(0u64, u64::wrapping_add);
-            let manual_hash = tick_hash.wrapping_add(manual_combined);
+                .fold(NodeHash::default(), NodeHash::wrapping_add);
 
+            let breakdown = engine.state_hash_breakdown();
+            assert_eq!(breakdown.inventories, manual.inventories);
+            assert_eq!(breakdown.processor_states, manual.processor_state);
+            assert_eq!(breakdown.fluid, manual.fluid);
             assert_eq!(
                 engine.state_hash(),
-                manual_hash,
+                breakdown.combined(),
                 "hash_node_state should match engine's compute_state_hash"
             );
         }
     }
 
+    /// A storage node: inventories but no processor.
+    fn hashed_chest(engine: &mut Engine) -> NodeId {
+        let pending = engine.graph.queue_add_node(building());
+        let node = engine.apply_mutations().resolve_node(pending).unwrap();
+        engine.set_input_inventory(node, test_utils::simple_inventory(10));
+        engine.set_output_inventory(node, test_utils::simple_inventory(10));
+        node
+    }
+
+    #[test]
+    fn hash_breakdown_localizes_an_inventory_change() {
+        let mut a = test_utils::build_chain_factory(5);
+        let mut b = test_utils::build_chain_factory(5);
+        hashed_chest(&mut a);
+        let chest_b = hashed_chest(&mut b);
+        for _ in 0..5 {
+            a.step();
+            b.step();
+        }
+        assert_eq!(a.state_hash_breakdown(), b.state_hash_breakdown());
+
+        let _ = b
+            .get_input_inventory_mut(chest_b)
+            .unwrap()
+            .insert(iron(), 3);
+        a.step();
+        b.step();
+        let (ha, hb) = (a.state_hash_breakdown(), b.state_hash_breakdown());
+        assert_ne!(ha.inventories, hb.inventories);
+        assert_eq!(
+            HashBreakdown {
+                inventories: 0,
+                ..ha
+            },
+            HashBreakdown {
+                inventories: 0,
+                ..hb
+            }
+        );
+        assert_eq!(a.state_hash(), ha.combined());
+        assert_eq!(b.state_hash(), hb.combined());
+    }
+
+    #[test]
+    fn hash_breakdown_survives_a_round_trip() {
+        let mut engine = test_utils::build_grid_factory(4, 4);
+        for _ in 0..20 {
+            engine.step();
+        }
+        let restored = Engine::deserialize(&engine.serialize().unwrap()).unwrap();
+        assert_eq!(
+            restored.state_hash_breakdown(),
+            engine.state_hash_breakdown()
+        );
+        assert_eq!(
+            restored.state_hash(),
+            restored.state_hash_breakdown().combined()
+        );
+    }
+
+    /// A module whose state is a single counter.
+    #[derive(Debug)]
+    struct HashedCounter(u64);
+
+    impl crate::module::Module for HashedCounter {
+        fn name(&self) -> &str {
+            "hashed_counter"
+        }
+        fn on_tick(&mut self, _ctx: &mut crate::module::ModuleContext<'_>) {
+            self.0 += 1;
+        }
+        fn state_hash(&self) -> u64 {
+            self.0
+        }
+        fn as_any(&self) -> &dyn std::any::Any {
+            self
+        }
+        fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+            self
+        }
+    }
+
+    #[test]
+    fn hash_breakdown_includes_module_state() {
+        let mut engine = test_utils::build_chain_factory(3);
+        engine.step();
+        let without = engine.state_hash_breakdown();
+        assert_eq!(without.modules, 0);
+
+        engine.register_module(Box::new(HashedCounter(0)));
+        engine.step();
+        let with = engine.state_hash_breakdown();
+        assert_eq!(with.modules, 1);
+        assert_eq!(engine.state_hash(), with.combined());
+    }
+
     /// Verify determinism: two identical engines produce the same hash
     /// regardless of whether the parallel feature is enabled.
     #[test]
@@ -4828,4 +8118,1896 @@ mod tests {
         assert!(engine.registry().is_some());
         assert_eq!(engine.registry().unwrap().item_count(), 1);
     }
+
+    /// An engine whose "chest" template (building 0) starts with `iron`
+    /// starter items and has the given per-slot capacity.
+    fn engine_with_stocked_template(capacity: u32, starter: u32) -> Engine {
+        use crate::registry::*;
+        let mut builder = RegistryBuilder::new();
+        let iron = builder.register_item("iron", vec![]);
+        let mut chest = BuildingTemplateDef::new("chest", None);
+        chest.inventory_capacity = Some(capacity);
+        if starter > 0 {
+            chest.initial_inputs = vec![(iron, starter)];
+            chest.initial_outputs = vec![(iron, 5)];
+        }
+        builder.register_building_template(chest);
+        Engine::new_with_registry(SimulationStrategy::Tick, builder.build().unwrap())
+    }
+
+    fn add_chest(engine: &mut Engine) -> NodeId {
+        let pending = engine.graph.queue_add_node(BuildingTypeId(0));
+        engine.apply_mutations().resolve_node(pending).unwrap()
+    }
+
+    #[test]
+    fn template_starter_contents_are_queryable_on_creation() {
+        let mut engine = engine_with_stocked_template(100, 50);
+        let chest = add_chest(&mut engine);
+        let input = engine.get_input_inventory(chest).unwrap();
+        assert_eq!(input.count_of(iron()), 50);
+        assert_eq!(input.free_space_for(iron()), 50);
+        assert_eq!(
+            engine.get_output_inventory(chest).unwrap().count_of(iron()),
+            5
+        );
+
+        // Nodes added through the graph directly bypass templates.
+        let pending = engine.graph.queue_add_node(BuildingTypeId(0));
+        let bare = engine
+            .graph
+            .apply_mutations()
+            .resolve_node(pending)
+            .unwrap();
+        assert!(engine.get_input_inventory(bare).is_none());
+    }
+
+    #[test]
+    fn template_starter_contents_are_clamped_to_capacity() {
+        let mut engine = engine_with_stocked_template(30, 50);
+        let chest = add_chest(&mut engine);
+        let input = engine.get_input_inventory(chest).unwrap();
+        assert_eq!(input.count_of(iron()), 30);
+        assert!(input.is_full());
+    }
+
+    #[test]
+    fn template_starter_contents_are_part_of_state_hash() {
+        let mut stocked = engine_with_stocked_template(100, 50);
+        let mut empty = engine_with_stocked_template(100, 0);
+        let mut manual = engine_with_stocked_template(100, 0);
+        add_chest(&mut stocked);
+        add_chest(&mut empty);
+        let chest = add_chest(&mut manual);
+        let _ = manual
+            .get_input_inventory_mut(chest)
+            .unwrap()
+            .insert(iron(), 50);
+        let _ = manual.get_output_inventory_mut(chest).unwrap().output_slots[0].add(iron(), 5);
+
+        for engine in [&mut stocked, &mut empty, &mut manual] {
+            engine.step();
+        }
+        assert_ne!(stocked.state_hash(), empty.state_hash());
+        assert_eq!(stocked.state_hash(), manual.state_hash());
+    }
+
+    #[test]
+    fn template_starter_contents_must_reference_known_items() {
+        use crate::registry::*;
+        let mut builder = RegistryBuilder::new();
+        let mut chest = BuildingTemplateDef::new("chest", None);
+        chest.inventory_capacity = Some(10);
+        chest.initial_inputs = vec![(ItemTypeId(7), 1)];
+        builder.register_building_template(chest);
+        assert!(matches!(
+            builder.build(),
+            Err(RegistryError::InvalidItemRef(ItemTypeId(7)))
+        ));
+    }
+
+    // -----------------------------------------------------------------------
+    // Bulk transport configuration
+    // -----------------------------------------------------------------------
+
+    /// A source fanned out to `count` sinks over unconfigured edges.
+    fn fan_out_unconfigured(count: usize) -> (Engine, Vec<EdgeId>) {
+        let mut engine = Engine::new(SimulationStrategy::Tick);
+        let source = test_utils::add_node(&mut engine, make_source(iron(), 1.0), 100, 100);
+        let edges = (0..count)
+            .map(|_| {
+                let sink = test_utils::add_node(&mut engine, Processor::Passthrough, 100, 100);
+                let pending = engine.graph.queue_connect(source, sink);
+                engine
+                    .graph
+                    .apply_mutations()
+                    .resolve_edge(pending)
+                    .unwrap()
+            })
+            .collect();
+        (engine, edges)
+    }
+
+    #[test]
+    fn set_transport_bulk_configures_every_edge_identically() {
+        let (mut engine, edges) = fan_out_unconfigured(10);
+        engine.set_transport_bulk(&edges, make_flow_transport(4.0));
+        for &edge in &edges {
+            assert!(matches!(
+                engine.get_transport(edge),
+                Some(Transport::Flow(flow)) if flow.rate == Fixed64::from_num(4)
+            ));
+            assert!(matches!(
+                engine.get_transport_state(edge),
+                Some(TransportState::Flow(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn set_transport_where_only_touches_matching_edges() {
+        let (mut engine, edges) = fan_out_unconfigured(10);
+        engine.set_transport_bulk(&edges[..3], test_utils::make_item_transport(5));
+
+        let configured = engine.set_transport_where(
+            |_, _, transport| transport.is_none(),
+            make_flow_transport(2.0),
+        );
+
+        assert_eq!(configured, 7);
+        for &edge in &edges[..3] {
+            assert!(matches!(
+                engine.get_transport(edge),
+                Some(Transport::Item(_))
+            ));
+        }
+        for &edge in &edges[3..] {
+            assert!(matches!(
+                engine.get_transport(edge),
+                Some(Transport::Flow(_))
+            ));
+        }
+        assert_eq!(
+            engine.set_transport_where(|_, _, t| t.is_none(), make_flow_transport(1.0)),
+            0
+        );
+    }
+
+    // -----------------------------------------------------------------------
+    // Bulk configuration visitors
+    // -----------------------------------------------------------------------
+
+    /// Three recipe nodes with the given durations, each stocked with iron.
+    fn recipe_row(durations: [u32; 3]) -> (Engine, Vec<NodeId>) {
+        let mut engine = Engine::new(SimulationStrategy::Tick);
+        let nodes = durations
+            .iter()
+            .map(|&duration| {
+                let node = test_utils::add_node(
+                    &mut engine,
+                    make_recipe(vec![(iron(), 1)], vec![(gear(), 1)], duration),
+                    100,
+                    100,
+                );
+                let _ = engine
+                    .get_input_inventory_mut(node)
+                    .unwrap()
+                    .insert(iron(), 50);
+                node
+            })
+            .collect();
+        (engine, nodes)
+    }
+
+    #[test]
+    fn processor_visitor_halves_every_recipe_duration() {
+        let (mut modded, nodes) = recipe_row([4, 6, 10]);
+        let (mut halved, _) = recipe_row([2, 3, 5]);
+        let (mut original, _) = recipe_row([4, 6, 10]);
+
+        let mut visited = Vec::new();
+        modded.for_each_processor_mut(|node, processor| {
+            if let Processor::Fixed(recipe) = processor {
+                recipe.duration /= 2;
+            }
+            visited.push(node);
+        });
+        assert_eq!(visited, nodes);
+        for (&node, expected) in nodes.iter().zip([2, 3, 5]) {
+            assert!(matches!(
+                modded.get_processor(node),
+                Some(Processor::Fixed(recipe)) if recipe.duration == expected
+            ));
+        }
+
+        for _ in 0..30 {
+            modded.step();
+            halved.step();
+            original.step();
+            assert_eq!(modded.state_hash(), halved.state_hash());
+        }
+        assert_ne!(modded.state_hash(), original.state_hash());
+        for &node in &nodes {
+            let made =
+                |engine: &Engine| engine.get_output_inventory(node).unwrap().count_of(gear());
+            assert_eq!(made(&modded), made(&halved));
+            assert!(made(&modded) > made(&original));
+        }
+    }
+
+    #[test]
+    fn processor_visitor_resets_nodes_that_change_kind() {
+        let (mut engine, nodes) = recipe_row([4, 6, 10]);
+        engine.step();
+        assert!(matches!(
+            engine.get_processor_state(nodes[0]),
+            Some(ProcessorState::Working { .. })
+        ));
+
+        engine.for_each_processor_mut(|node, processor| {
+            if node == nodes[0] {
+                *processor = make_source(gear(), 1.0);
+            }
+        });
+        assert!(matches!(
+            engine.get_processor_state(nodes[0]),
+            Some(ProcessorState::Idle)
+        ));
+        assert!(matches!(
+            engine.get_processor_state(nodes[1]),
+            Some(ProcessorState::Working { .. })
+        ));
+    }
+
+    #[test]
+    fn transport_visitor_keeps_items_unless_the_layout_changes() {
+        let build = |speed: f64| {
+            let mut engine = Engine::new(SimulationStrategy::Tick);
+            let a = test_utils::add_node(&mut engine, Processor::Passthrough, 100, 100);
+            let b = test_utils::add_node(&mut engine, Processor::Passthrough, 100, 100);
+            let c = test_utils::add_node(&mut engine, Processor::Passthrough, 100, 100);
+            let ab =
+                test_utils::connect(&mut engine, a, b, crate::builder::belt_transport(speed, 8));
+            let bc =
+                test_utils::connect(&mut engine, b, c, crate::builder::belt_transport(speed, 8));
+            let _ = engine
+                .get_input_inventory_mut(a)
+                .unwrap()
+                .insert(iron(), 20);
+            (engine, [ab, bc])
+        };
+        let (mut modded, edges) = build(1.0);
+        let (mut fast, _) = build(2.0);
+        // Retune from the first tick so both engines see the same history.
+        modded.for_each_transport_mut(|_, transport| {
+            if let Transport::Item(belt) = transport {
+                belt.speed = Fixed64::from_num(2);
+            }
+        });
+        for _ in 0..5 {
+            modded.step();
+            fast.step();
+            assert_eq!(modded.state_hash(), fast.state_hash());
+        }
+
+        let on_belt = |engine: &Engine| match engine.get_transport_state(edges[0]) {
+            Some(TransportState::Item(belt)) => belt.occupied_count(),
+            _ => unreachable!(),
+        };
+        let carried = on_belt(&modded);
+        assert!(carried > 0);
+        modded.for_each_transport_mut(|_, transport| {
+            if let Transport::Item(belt) = transport {
+                belt.speed = Fixed64::from_num(1);
+            }
+        });
+        assert_eq!(on_belt(&modded), carried);
+
+        modded.for_each_transport_mut(|edge, transport| {
+            if edge == edges[0] {
+                *transport = crate::builder::belt_transport(1.0, 12);
+            }
+        });
+        assert_eq!(on_belt(&modded), 0);
+        assert!(matches!(
+            modded.get_transport_state(edges[0]),
+            Some(TransportState::Item(belt)) if belt.slot_count == 12
+        ));
+    }
+
+    // -----------------------------------------------------------------------
+    // Clearing processors and inventories
+    // -----------------------------------------------------------------------
+
+    #[test]
+    fn clear_processor_stops_production() {
+        let (mut engine, src, consumer, _edge) =
+            setup_source_transport_consumer(2.0, 10.0, vec![(iron(), 1)], vec![(gear(), 1)], 2);
+        for _ in 0..5 {
+            engine.step();
+        }
+
+        let removed = engine.clear_processor(src);
+        assert!(matches!(removed, Some(Processor::Source(_))));
+        assert!(engine.get_processor(src).is_none());
+        assert_eq!(engine.get_processor_state(src), Some(&ProcessorState::Idle));
+        assert!(engine.clear_processor(src).is_none());
+
+        // Drain whatever was already produced, then production must stop.
+        for _ in 0..20 {
+            engine.step();
+        }
+        let produced = test_utils::output_quantity(&engine, consumer, gear());
+        let src_out = test_utils::output_quantity(&engine, src, iron());
+        for _ in 0..20 {
+            engine.step();
+        }
+        assert_eq!(test_utils::output_quantity(&engine, src, iron()), src_out);
+        assert_eq!(
+            test_utils::output_quantity(&engine, consumer, gear()),
+            produced
+        );
+        assert!(engine.graph.contains_node(src));
+    }
+
+    #[test]
+    fn clear_inventory_returns_contents() {
+        let mut engine = Engine::new(SimulationStrategy::Tick);
+        let node = add_node_helper(&mut engine, make_source(iron(), 0.0), 10, 10);
+        let _ = engine.get_input_inventory_mut(node).unwrap().input_slots[0].add(gear(), 7);
+        let _ = engine.get_output_inventory_mut(node).unwrap().output_slots[0].add(iron(), 3);
+
+        let items = engine.clear_input_inventory(node);
+        assert_eq!(items, vec![ItemStack::new(gear(), 7)]);
+        assert!(engine.get_input_inventory(node).is_none());
+        assert!(engine.clear_input_inventory(node).is_empty());
+
+        let items = engine.clear_output_inventory(node);
+        assert_eq!(items, vec![ItemStack::new(iron(), 3)]);
+        assert!(engine.get_output_inventory(node).is_none());
+    }
+
+    #[test]
+    fn cleared_node_can_be_reconfigured() {
+        let mut engine = Engine::new(SimulationStrategy::Tick);
+        let node = add_node_helper(&mut engine, make_source(iron(), 1.0), 10, 10);
+        engine.step();
+
+        engine.clear_processor(node);
+        engine.clear_input_inventory(node);
+        engine.clear_output_inventory(node);
+        for _ in 0..3 {
+            engine.step();
+        }
+        assert_eq!(
+            engine.get_processor_state(node),
+            Some(&ProcessorState::Idle)
+        );
+
+        engine.set_processor(node, make_source(gear(), 1.0));
+        engine.set_output_inventory(node, simple_inventory(10));
+        for _ in 0..3 {
+            engine.step();
+        }
+        assert_eq!(test_utils::output_quantity(&engine, node, gear()), 3);
+        assert_eq!(test_utils::output_quantity(&engine, node, iron()), 0);
+    }
+
+    // -----------------------------------------------------------------------
+    // Belt item instance tracking
+    // -----------------------------------------------------------------------
+
+    fn belt_chain() -> (Engine, EdgeId) {
+        let mut engine = Engine::new(SimulationStrategy::Tick);
+        let source = add_node_helper(&mut engine, make_source(iron(), 1.0), 100, 100);
+        let sink = add_node_helper(
+            &mut engine,
+            make_recipe(vec![(iron(), 1)], vec![(gear(), 1)], 1),
+            100,
+            100,
+        );
+        let edge = connect_helper(
+            &mut engine,
+            source,
+            sink,
+            test_utils::make_item_transport(31),
+        );
+        (engine, edge)
+    }
+
+    fn find_instance(engine: &Engine, edge: EdgeId, id: u64) -> TransportItemView {
+        engine
+            .transport_items(edge)
+            .unwrap()
+            .into_iter()
+            .find(|v| v.instance_id == id)
+            .unwrap()
+    }
+
+    #[test]
+    fn transport_items_track_instance_across_ticks_and_save() {
+        let (mut engine, edge) = belt_chain();
+        while engine.transport_items(edge).unwrap().is_empty() {
+            engine.step();
+        }
+        let first = engine.transport_items(edge).unwrap()[0].clone();
+        assert_eq!(first.instance_id, 1);
+        assert_eq!(first.item_type, iron());
+        assert_eq!(first.lane, 0);
+        assert_eq!(first.position, Fixed64::ZERO);
+
+        // Speed 1 => the item advances exactly one of the 31 slots per tick.
+        let position_after =
+            |ticks: usize| crate::transport::BeltState::slot_position(30 - ticks, 31);
+        let mut last = first.position;
+        for tick in 1..=20 {
+            engine.step();
+            let view = find_instance(&engine, edge, first.instance_id);
+            assert_eq!(view.position, position_after(tick));
+            assert!(view.position > last && view.position <= Fixed64::ONE);
+            last = view.position;
+        }
+
+        // Identity and the id counter survive a save/load.
+        let ids_before: Vec<u64> = engine
+            .transport_items(edge)
+            .unwrap()
+            .iter()
+            .map(|v| v.instance_id)
+            .collect();
+        let mut restored = Engine::deserialize(&engine.serialize().unwrap()).unwrap();
+        let ids_after: Vec<u64> = restored
+            .transport_items(edge)
+            .unwrap()
+            .iter()
+            .map(|v| v.instance_id)
+            .collect();
+        assert_eq!(ids_before, ids_after);
+
+        engine.step();
+        restored.step();
+        assert_eq!(
+            find_instance(&restored, edge, first.instance_id).position,
+            position_after(21)
+        );
+        assert_eq!(
+            engine.transport_items(edge).unwrap(),
+            restored.transport_items(edge).unwrap()
+        );
+        let newest = restored
+            .transport_items(edge)
+            .unwrap()
+            .iter()
+            .map(|v| v.instance_id)
+            .max()
+            .unwrap();
+        assert_eq!(newest, *ids_before.iter().max().unwrap() + 1);
+    }
+
+    #[test]
+    fn transport_items_empty_for_non_belt() {
+        let (mut engine, _src, _consumer, edge) =
+            setup_source_transport_consumer(2.0, 10.0, vec![(iron(), 1)], vec![(gear(), 1)], 2);
+        engine.step();
+        assert_eq!(engine.transport_items(edge), Some(Vec::new()));
+        let (engine, _) = belt_chain();
+        assert!(engine.transport_items(EdgeId::default()).is_none());
+    }
+
+    // -----------------------------------------------------------------------
+    // Weighted recipes
+    // -----------------------------------------------------------------------
+
+    /// A no-input node that crafts iron (weight 1) or copper (weight 3).
+    fn weighted_factory(seed: u64, duration: u32) -> (Engine, NodeId) {
+        let recipe = |item: ItemTypeId| match make_recipe(vec![], vec![(item, 1)], duration) {
+            Processor::Fixed(recipe) => recipe,
+            _ => unreachable!(),
+        };
+        let weighted = WeightedRecipeProcessor::new(vec![
+            (recipe(iron()), Fixed64::from_num(1)),
+            (recipe(test_utils::copper()), Fixed64::from_num(3)),
+        ]);
+        let mut builder = crate::builder::FactoryBuilder::new();
+        builder.seed(seed);
+        builder
+            .node("crafter")
+            .processor(Processor::WeightedRecipe(weighted))
+            .output_cap(100_000);
+        let factory = builder.build().unwrap();
+        let node = factory.node("crafter").unwrap();
+        (factory.engine, node)
+    }
+
+    /// Step `ticks` times, recording the item type of every completed cycle.
+    fn weighted_outputs(engine: &mut Engine, node: NodeId, ticks: usize) -> Vec<ItemTypeId> {
+        let mut seen = Vec::new();
+        for _ in 0..ticks {
+            let before_iron = test_utils::output_quantity(engine, node, iron());
+            let before_copper = test_utils::output_quantity(engine, node, test_utils::copper());
+            engine.step();
+            if test_utils::output_quantity(engine, node, iron()) > before_iron {
+                seen.push(iron());
+            }
+            if test_utils::output_quantity(engine, node, test_utils::copper()) > before_copper {
+                seen.push(test_utils::copper());
+            }
+        }
+        seen
+    }
+
+    #[test]
+    fn weighted_recipe_frequency_matches_weights() {
+        let (mut engine, node) = weighted_factory(99, 1);
+        let outputs = weighted_outputs(&mut engine, node, 4000);
+        assert_eq!(outputs.len(), 4000);
+        let iron_count = outputs.iter().filter(|t| **t == iron()).count();
+        // Expect ~1000 iron / ~3000 copper.
+        assert!(
+            (850..=1150).contains(&iron_count),
+            "expected ~1000 iron cycles, got {iron_count}"
+        );
+    }
+
+    #[test]
+    fn weighted_recipe_same_seed_same_sequence() {
+        let (mut a, node_a) = weighted_factory(7, 2);
+        let (mut b, node_b) = weighted_factory(7, 2);
+        let seq_a = weighted_outputs(&mut a, node_a, 400);
+        let seq_b = weighted_outputs(&mut b, node_b, 400);
+        assert_eq!(seq_a.len(), 200);
+        assert_eq!(seq_a, seq_b);
+        assert_eq!(a.state_hash(), b.state_hash());
+
+        let (mut c, node_c) = weighted_factory(8, 2);
+        assert_ne!(seq_a, weighted_outputs(&mut c, node_c, 400));
+    }
+
+    #[test]
+    fn weighted_recipe_selection_survives_save() {
+        let (mut engine, node) = weighted_factory(1234, 4);
+        weighted_outputs(&mut engine, node, 2);
+
+        let selected = |engine: &Engine| match engine.get_processor(node) {
+            Some(Processor::WeightedRecipe(w)) => w.selected,
+            _ => panic!("expected a weighted recipe processor"),
+        };
+        assert!(selected(&engine).is_some());
+
+        let mut restored = Engine::deserialize(&engine.serialize().unwrap()).unwrap();
+        assert_eq!(selected(&restored), selected(&engine));
+        assert_eq!(
+            weighted_outputs(&mut engine, node, 200),
+            weighted_outputs(&mut restored, node, 200)
+        );
+    }
+
+    // -----------------------------------------------------------------------
+    // Chance-based outputs
+    // -----------------------------------------------------------------------
+
+    /// A no-input node that crafts a gear every tick, with a `chance` of
+    /// iron alongside it.
+    fn chance_factory(seed: u64, chance: f64) -> (Engine, NodeId) {
+        let Processor::Fixed(mut recipe) = make_recipe(vec![], vec![(gear(), 1), (iron(), 1)], 1)
+        else {
+            unreachable!()
+        };
+        recipe.outputs[1].probability = Some(Fixed64::from_num(chance));
+        let mut builder = crate::builder::FactoryBuilder::new();
+        builder.seed(seed);
+        builder
+            .node("crusher")
+            .processor(Processor::Fixed(recipe))
+            .output_cap(100_000);
+        let factory = builder.build().unwrap();
+        let node = factory.node("crusher").unwrap();
+        (factory.engine, node)
+    }
+
+    #[test]
+    fn chance_outputs_match_the_seeded_rng_exactly() {
+        let (mut engine, node) = chance_factory(42, 0.25);
+        let mut replay = engine.node_rngs.get(node).cloned().unwrap();
+
+        let produced = Rc::new(RefCell::new(0u32));
+        let log = Rc::clone(&produced);
+        engine.on_passive(
+            EventKind::ItemProduced,
+            Box::new(move |event| {
+                if let Event::ItemProduced {
+                    item_type,
+                    quantity,
+                    ..
+                } = event
+                    && *item_type == iron()
+                {
+                    *log.borrow_mut() += quantity;
+                }
+            }),
+        );
+        for _ in 0..10_000 {
+            engine.step();
+        }
+
+        let crafts = test_utils::output_quantity(&engine, node, gear());
+        assert_eq!(crafts, 10_000);
+        let expected = (0..crafts)
+            .filter(|_| replay.chance(Fixed64::from_num(0.25)))
+            .count() as u32;
+        assert_eq!(test_utils::output_quantity(&engine, node, iron()), expected);
+        assert_eq!(*produced.borrow(), expected);
+        assert!((2300..=2700).contains(&expected));
+        assert_eq!(
+            engine.get_processor(node).unwrap().max_rate(iron(), &[]),
+            Some(Fixed64::from_num(0.25))
+        );
+    }
+
+    #[test]
+    fn chance_outputs_keep_their_draw_sequence_across_a_save() {
+        let (mut engine, node) = chance_factory(7, 0.25);
+        for _ in 0..500 {
+            engine.step();
+        }
+        let mut restored = Engine::deserialize(&engine.serialize().unwrap()).unwrap();
+        for _ in 0..500 {
+            engine.step();
+            restored.step();
+            assert_eq!(
+                test_utils::output_quantity(&engine, node, iron()),
+                test_utils::output_quantity(&restored, node, iron())
+            );
+        }
+        assert_eq!(engine.state_hash(), restored.state_hash());
+    }
+
+    // -----------------------------------------------------------------------
+    // Mutation lanes
+    // -----------------------------------------------------------------------
+
+    #[test]
+    fn mutation_lanes_converge_regardless_of_submission_order() {
+        let mut player_first = Engine::new(SimulationStrategy::Tick);
+        let mut automation_first = Engine::new(SimulationStrategy::Tick);
+
+        // Lane 0 is the player, lane 1 automation; each lane's own order is
+        // the same on both engines, but the interleaving is not.
+        let scrambled = [(1, 10), (0, 20), (1, 11), (0, 21)];
+        let grouped = [(0, 20), (0, 21), (1, 10), (1, 11)];
+        let mut pending = Vec::new();
+        for (lane, building) in scrambled {
+            let id = player_first
+                .graph
+                .queue_add_node_in_lane(lane, BuildingTypeId(building));
+            pending.push((id, building));
+        }
+        for (lane, building) in grouped {
+            automation_first
+                .graph
+                .queue_add_node_in_lane(lane, BuildingTypeId(building));
+        }
+        let a = player_first.apply_mutations();
+        let b = automation_first.apply_mutations();
+
+        let nodes = |result: &crate::graph::MutationResult| -> Vec<NodeId> {
+            result.added_nodes.iter().map(|&(_, node)| node).collect()
+        };
+        assert_eq!(nodes(&a), nodes(&b));
+        assert_eq!(a.added_node_lanes, vec![0, 0, 1, 1]);
+        assert_eq!(a.added_node_lanes, b.added_node_lanes);
+        assert_eq!(player_first.state_hash(), automation_first.state_hash());
+        // Pending ids still resolve to the node they were queued for.
+        for (id, building) in pending {
+            let node = a.resolve_node(id).unwrap();
+            assert_eq!(
+                player_first.graph.get_node(node).unwrap().building_type,
+                BuildingTypeId(building)
+            );
+            assert_eq!(
+                player_first.graph.resolve_pending_node(id),
+                crate::graph::PendingLookup::Resolved(node)
+            );
+        }
+
+        // Edges and removals follow the same rule.
+        let n = nodes(&a);
+        player_first.graph.queue_connect_in_lane(1, n[2], n[3]);
+        player_first.graph.queue_remove_node_in_lane(0, n[3]);
+        player_first.graph.queue_connect_in_lane(0, n[0], n[1]);
+        automation_first.graph.queue_connect_in_lane(0, n[0], n[1]);
+        automation_first.graph.queue_remove_node_in_lane(0, n[3]);
+        automation_first.graph.queue_connect_in_lane(1, n[2], n[3]);
+        let a = player_first.apply_mutations();
+        let b = automation_first.apply_mutations();
+        let edges = |result: &crate::graph::MutationResult| -> Vec<EdgeId> {
+            result.added_edges.iter().map(|&(_, edge)| edge).collect()
+        };
+        assert_eq!(edges(&a), edges(&b));
+        assert_eq!(a.added_edge_lanes, vec![0]);
+        assert_eq!(a.removed_nodes, b.removed_nodes);
+        // The lane 1 edge found its endpoint already removed by lane 0.
+        assert_eq!(a.rejected_edges.len(), 1);
+        assert_eq!(b.rejected_edges.len(), 1);
+        assert_eq!(player_first.state_hash(), automation_first.state_hash());
+    }
+
+    #[test]
+    fn flagged_filtered_and_ghost_mutations_take_a_lane() {
+        use crate::graph::NodeFlags;
+
+        let mut engine = Engine::new(SimulationStrategy::Tick);
+        let a = engine.graph.queue_add_node(BuildingTypeId(1));
+        let b = engine.graph.queue_add_node(BuildingTypeId(2));
+        let result = engine.apply_mutations();
+        let (a, b) = (
+            result.resolve_node(a).unwrap(),
+            result.resolve_node(b).unwrap(),
+        );
+
+        let ghost = engine.graph.queue_add_ghost_in_lane(1, BuildingTypeId(3));
+        let flagged =
+            engine
+                .graph
+                .queue_add_node_with_flags_in_lane(1, BuildingTypeId(4), NodeFlags::SILENT);
+        let plain = engine.graph.queue_add_node(BuildingTypeId(5));
+        let filtered = engine
+            .graph
+            .queue_connect_filtered_in_lane(1, a, b, Some(iron()));
+        let planned = engine.graph.queue_connect_ghost_in_lane(0, a, b);
+        let result = engine.apply_mutations();
+
+        let added: Vec<_> = result.added_nodes.iter().map(|&(p, _)| p).collect();
+        assert_eq!(added, vec![plain, ghost, flagged]);
+        assert_eq!(result.added_node_lanes, vec![0, 1, 1]);
+        assert!(engine.is_ghost(result.resolve_node(ghost).unwrap()));
+        assert_eq!(
+            engine.node_flags(result.resolve_node(flagged).unwrap()),
+            Some(NodeFlags::SILENT)
+        );
+
+        let added: Vec<_> = result.added_edges.iter().map(|&(p, _)| p).collect();
+        assert_eq!(added, vec![planned, filtered]);
+        assert_eq!(result.added_edge_lanes, vec![0, 1]);
+        let filtered = result.resolve_edge(filtered).unwrap();
+        assert_eq!(
+            engine.graph.get_edge(filtered).unwrap().item_filter,
+            Some(iron())
+        );
+        assert_eq!(
+            engine.edge_status(result.resolve_edge(planned).unwrap()),
+            Some(NodeStatus::Ghost)
+        );
+    }
+
+    // -----------------------------------------------------------------------
+    // Event log
+    // -----------------------------------------------------------------------
+
+    #[test]
+    fn event_log_records_one_entry_per_tick() {
+        let (mut engine, src, _consumer, _edge) =
+            setup_source_transport_consumer(2.0, 10.0, vec![(iron(), 1)], vec![(gear(), 1)], 2);
+        assert!(engine.take_event_log().is_none());
+
+        engine.enable_event_log();
+        engine.step();
+        engine.step();
+        engine.step();
+
+        let log = engine.take_event_log().unwrap();
+        assert_eq!(
+            log.ticks.iter().map(|t| t.tick).collect::<Vec<_>>(),
+            vec![0, 1, 2]
+        );
+        for frame in &log.ticks {
+            assert!(frame.events.iter().all(|e| e.tick() == frame.tick));
+        }
+        assert!(log.ticks[0].events.contains(&Event::ItemProduced {
+            node: src,
+            item_type: iron(),
+            quantity: 2,
+            tick: 0,
+        }));
+
+        // Taking leaves recording on with an empty log.
+        assert!(engine.take_event_log().unwrap().is_empty());
+        engine.step();
+        assert_eq!(engine.take_event_log().unwrap().ticks[0].tick, 3);
+
+        // Disabling stops recording; logs are not carried through snapshots.
+        engine.step();
+        assert_eq!(engine.disable_event_log().unwrap().tick_count(), 1);
+        engine.step();
+        assert!(!engine.is_event_log_enabled());
+        engine.enable_event_log();
+        let restored = Engine::deserialize(&engine.serialize().unwrap()).unwrap();
+        assert!(!restored.is_event_log_enabled());
+    }
+
+    #[test]
+    fn event_history_spans_steps_in_order() {
+        let (mut engine, src, _consumer, _edge) =
+            setup_source_transport_consumer(2.0, 10.0, vec![(iron(), 1)], vec![(gear(), 1)], 2);
+        assert_eq!(engine.event_history_since(0).count(), 0);
+
+        engine.set_event_history_capacity(1000);
+        for _ in 0..5 {
+            engine.step();
+        }
+
+        let ticks: Vec<u64> = engine.event_history_since(0).map(Event::tick).collect();
+        assert!(ticks.windows(2).all(|w| w[0] <= w[1]), "history is ordered");
+        assert_eq!(ticks.first(), Some(&0));
+        assert_eq!(ticks.last(), Some(&4));
+        for tick in 0..5 {
+            assert!(engine.event_history_since(0).any(|e| *e
+                == Event::ItemProduced {
+                    node: src,
+                    item_type: iron(),
+                    quantity: 2,
+                    tick,
+                }));
+        }
+        assert!(engine.event_history_since(3).all(|e| e.tick() >= 3));
+        assert!(engine.event_history_since(5).next().is_none());
+    }
+
+    #[test]
+    fn event_history_keeps_newest_up_to_capacity() {
+        let (mut engine, _src, _consumer, _edge) =
+            setup_source_transport_consumer(2.0, 10.0, vec![(iron(), 1)], vec![(gear(), 1)], 2);
+        engine.set_event_history_capacity(4000);
+        engine.enable_event_log();
+        for _ in 0..5 {
+            engine.step();
+        }
+        let all: Vec<Event> = engine.take_event_log().unwrap().events().cloned().collect();
+        assert_eq!(
+            engine.event_history_since(0).cloned().collect::<Vec<_>>(),
+            all
+        );
+
+        // Shrinking keeps the newest events.
+        engine.set_event_history_capacity(3);
+        assert_eq!(engine.event_history_capacity(), 3);
+        assert_eq!(
+            engine.event_history_since(0).cloned().collect::<Vec<_>>(),
+            all[all.len() - 3..]
+        );
+
+        // The event log and history share recording without interfering.
+        assert!(engine.disable_event_log().is_some());
+        engine.step();
+        assert_eq!(engine.event_history_since(5).count(), 3);
+
+        engine.set_event_history_capacity(0);
+        assert_eq!(engine.event_history_capacity(), 0);
+        assert!(!engine.event_bus.is_recording());
+        assert_eq!(engine.event_history_since(0).count(), 0);
+    }
+
+    // -----------------------------------------------------------------------
+    // Ghost nodes and edges
+    // -----------------------------------------------------------------------
+
+    #[test]
+    fn ghost_source_produces_nothing_until_promoted() {
+        let (mut engine, src, consumer, edge) =
+            setup_source_transport_consumer(2.0, 10.0, vec![(iron(), 1)], vec![(gear(), 1)], 2);
+        assert_eq!(engine.node_status(src), Some(NodeStatus::Active));
+        assert!(engine.set_node_status(src, NodeStatus::Ghost));
+        assert_eq!(engine.node_status(src), Some(NodeStatus::Ghost));
+
+        for _ in 0..5 {
+            engine.step();
+        }
+        assert_eq!(test_utils::output_quantity(&engine, src, iron()), 0);
+        assert_eq!(test_utils::input_quantity(&engine, consumer, iron()), 0);
+
+        // Ghosts still take part in topology and queries.
+        assert_eq!(engine.get_outputs(src), &[edge]);
+        assert!(engine.snapshot_node(src).is_some());
+        assert_eq!(engine.snapshot_all_nodes().len(), 2);
+
+        assert!(engine.set_node_status(src, NodeStatus::Active));
+        engine.step();
+        assert!(test_utils::output_quantity(&engine, src, iron()) > 0);
+    }
+
+    #[test]
+    fn ghost_edge_does_not_transport() {
+        let (mut engine, src, consumer, edge) =
+            setup_source_transport_consumer(2.0, 10.0, vec![(iron(), 1)], vec![(gear(), 1)], 2);
+        assert!(engine.set_edge_status(edge, NodeStatus::Ghost));
+        assert_eq!(engine.edge_status(edge), Some(NodeStatus::Ghost));
+        for _ in 0..5 {
+            engine.step();
+        }
+        assert!(test_utils::output_quantity(&engine, src, iron()) > 0);
+        assert_eq!(test_utils::input_quantity(&engine, consumer, iron()), 0);
+
+        engine.set_edge_status(edge, NodeStatus::Active);
+        for _ in 0..3 {
+            engine.step();
+        }
+        assert!(test_utils::input_quantity(&engine, consumer, iron()) > 0);
+    }
+
+    #[test]
+    fn ghost_status_serializes_and_changes_state_hash() {
+        let (mut engine, src, _consumer, edge) =
+            setup_source_transport_consumer(2.0, 10.0, vec![(iron(), 1)], vec![(gear(), 1)], 2);
+        engine.set_hash_ghosts(true);
+        let active_hash = engine.compute_state_hash();
+        engine.set_node_status(src, NodeStatus::Ghost);
+        let ghost_hash = engine.compute_state_hash();
+        assert_ne!(active_hash, ghost_hash);
+        engine.set_edge_status(edge, NodeStatus::Ghost);
+        assert_ne!(engine.compute_state_hash(), ghost_hash);
+
+        let mut restored = Engine::deserialize(&engine.serialize().unwrap()).unwrap();
+        assert_eq!(restored.node_status(src), Some(NodeStatus::Ghost));
+        assert_eq!(restored.edge_status(edge), Some(NodeStatus::Ghost));
+        restored.set_hash_ghosts(true);
+        engine.step();
+        restored.step();
+        assert_eq!(engine.state_hash(), restored.state_hash());
+
+        // Promoting everything drops the ghost contribution entirely.
+        engine.set_node_status(src, NodeStatus::Active);
+        engine.set_edge_status(edge, NodeStatus::Active);
+        assert!(engine.ghost_nodes.is_empty() && engine.ghost_edges.is_empty());
+    }
+
+    #[test]
+    fn node_user_data_survives_save_and_is_dropped_with_the_node() {
+        let (mut engine, src, consumer, _edge) =
+            setup_source_transport_consumer(2.0, 10.0, vec![(iron(), 1)], vec![(gear(), 1)], 2);
+        let hash_before = engine.compute_state_hash();
+        assert!(engine.set_node_user_data(src, 0xdead_beef));
+        assert!(engine.set_node_user_data(consumer, 7));
+        assert_eq!(engine.compute_state_hash(), hash_before);
+        assert_eq!(engine.get_node_user_data(src), Some(0xdead_beef));
+
+        let mut restored = Engine::deserialize(&engine.serialize().unwrap()).unwrap();
+        assert_eq!(restored.get_node_user_data(src), Some(0xdead_beef));
+        assert_eq!(restored.get_node_user_data(consumer), Some(7));
+        assert_eq!(restored.clear_node_user_data(consumer), Some(7));
+        assert_eq!(restored.get_node_user_data(consumer), None);
+
+        restored.graph.queue_remove_node(src);
+        restored.apply_mutations();
+        assert_eq!(restored.get_node_user_data(src), None);
+        assert!(!restored.set_node_user_data(src, 1));
+    }
+
+    /// Two sources on full flow belts into a consumer that takes one iron a
+    /// tick through a two-item input. Returns (engine, merge node, belt
+    /// connected first, belt connected second).
+    fn two_belt_merge() -> (Engine, NodeId, EdgeId, EdgeId) {
+        let mut engine = Engine::new(SimulationStrategy::Tick);
+        let pending_a = engine.graph.queue_add_node(building());
+        let pending_b = engine.graph.queue_add_node(building());
+        let pending_merge = engine.graph.queue_add_node(building());
+        let result = engine.graph.apply_mutations();
+        let a = result.resolve_node(pending_a).unwrap();
+        let b = result.resolve_node(pending_b).unwrap();
+        let merge = result.resolve_node(pending_merge).unwrap();
+        let pending_left = engine.graph.queue_connect(a, merge);
+        let pending_right = engine.graph.queue_connect(b, merge);
+        let result = engine.graph.apply_mutations();
+        let left = result.resolve_edge(pending_left).unwrap();
+        let right = result.resolve_edge(pending_right).unwrap();
+
+        for source in [a, b] {
+            engine.set_processor(source, make_source(iron(), 5.0));
+            engine.set_input_inventory(source, simple_inventory(100));
+            engine.set_output_inventory(source, simple_inventory(100));
+        }
+        engine.set_processor(merge, make_recipe(vec![(iron(), 1)], vec![(gear(), 1)], 1));
+        engine.set_input_inventory(merge, simple_inventory(2));
+        engine.set_output_inventory(merge, simple_inventory(10_000));
+        engine.set_transport(left, make_flow_transport(5.0));
+        engine.set_transport(right, make_flow_transport(5.0));
+        (engine, merge, left, right)
+    }
+
+    /// Step `ticks` times and return the items each edge delivered per tick.
+    fn merge_deliveries(engine: &mut Engine, edges: [EdgeId; 2], ticks: usize) -> Vec<[u32; 2]> {
+        use std::cell::RefCell;
+        use std::rc::Rc;
+
+        let tick_log = Rc::new(RefCell::new([0u32; 2]));
+        let log = tick_log.clone();
+        engine.on_passive(
+            EventKind::ItemDelivered,
+            Box::new(move |event| {
+                if let Event::ItemDelivered { edge, quantity, .. } = event
+                    && let Some(i) = edges.iter().position(|e| e == edge)
+                {
+                    log.borrow_mut()[i] += quantity;
+                }
+            }),
+        );
+        (0..ticks)
+            .map(|_| {
+                engine.step();
+                std::mem::take(&mut *tick_log.borrow_mut())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn balanced_merge_alternates_between_full_belts() {
+        let (mut engine, merge, left, right) = two_belt_merge();
+        assert!(engine.set_merge_priority(merge, MergePriority::Balanced));
+        let per_tick = merge_deliveries(&mut engine, [left, right], 60);
+
+        let (mut from_left, mut from_right) = (0, 0);
+        for [l, r] in per_tick {
+            from_left += l;
+            from_right += r;
+            assert!(from_left.abs_diff(from_right) <= 1);
+        }
+        assert!(from_right > 20, "right belt starved: {from_right}");
+    }
+
+    #[test]
+    fn strict_merge_priorities_starve_the_lower_belt() {
+        let (mut engine, merge, left, right) = two_belt_merge();
+        engine.set_merge_priority(merge, MergePriority::LeftFirst);
+        let totals = merge_deliveries(&mut engine, [left, right], 60)
+            .into_iter()
+            .fold([0, 0], |acc, [l, r]| [acc[0] + l, acc[1] + r]);
+        assert!(totals[0] > 20);
+        assert_eq!(totals[1], 0);
+
+        // Listing the right belt first flips which one goes hungry.
+        let (mut engine, merge, left, right) = two_belt_merge();
+        engine.set_merge_priority(merge, MergePriority::Priority(vec![right]));
+        let totals = merge_deliveries(&mut engine, [left, right], 60)
+            .into_iter()
+            .fold([0, 0], |acc, [l, r]| [acc[0] + l, acc[1] + r]);
+        assert_eq!(totals[0], 0);
+        assert!(totals[1] > 20);
+    }
+
+    #[test]
+    fn merge_cursor_serializes_deterministically() {
+        let (mut engine, merge, left, right) = two_belt_merge();
+        engine.set_merge_priority(merge, MergePriority::Balanced);
+        for _ in 0..7 {
+            engine.step();
+        }
+        let data = engine.serialize().unwrap();
+        assert_eq!(data, engine.serialize().unwrap());
+
+        let mut restored = Engine::deserialize(&data).unwrap();
+        assert_eq!(
+            restored.merge_priority(merge),
+            Some(&MergePriority::Balanced)
+        );
+        assert_eq!(
+            restored.merge_cursors.get(merge),
+            engine.merge_cursors.get(merge)
+        );
+        let partitioned = engine.serialize_partitioned().unwrap();
+        let mut from_partitions = Engine::deserialize_partitioned(&partitioned).unwrap();
+
+        let expected = merge_deliveries(&mut engine, [left, right], 20);
+        assert_eq!(merge_deliveries(&mut restored, [left, right], 20), expected);
+        assert_eq!(
+            merge_deliveries(&mut from_partitions, [left, right], 20),
+            expected
+        );
+        assert_eq!(engine.state_hash(), restored.state_hash());
+
+        // Removing the node drops its merge state.
+        assert_eq!(
+            engine.clear_merge_priority(merge),
+            Some(MergePriority::Balanced)
+        );
+        restored.graph.queue_remove_node(merge);
+        restored.apply_mutations();
+        assert!(restored.merge_priorities.is_empty() && restored.merge_cursors.is_empty());
+        assert!(!restored.set_merge_priority(merge, MergePriority::LeftFirst));
+    }
+
+    #[test]
+    fn removing_a_ghost_clears_its_status() {
+        let (mut engine, src, _consumer, _edge) =
+            setup_source_transport_consumer(2.0, 10.0, vec![(iron(), 1)], vec![(gear(), 1)], 2);
+        engine.set_node_status(src, NodeStatus::Ghost);
+        engine.graph.queue_remove_node(src);
+        engine.apply_mutations();
+        assert_eq!(engine.node_status(src), None);
+        assert!(engine.ghost_nodes.is_empty());
+        assert!(engine.ghost_edges.is_empty());
+        assert!(!engine.set_node_status(src, NodeStatus::Ghost));
+    }
+
+    #[test]
+    fn queued_ghosts_cost_nothing_and_leave_the_hash_alone() {
+        let build = || {
+            let (mut engine, src, consumer, _) =
+                setup_source_transport_consumer(2.0, 10.0, vec![(iron(), 1)], vec![(gear(), 1)], 2);
+            engine.set_profiling(true);
+            (engine, src, consumer)
+        };
+        let (mut plain, _, _) = build();
+        let (mut planned, src, consumer) = build();
+        let pending = planned.graph.queue_add_ghost(BuildingTypeId(3));
+        let result = planned.apply_mutations();
+        let ghost = result.resolve_node(pending).unwrap();
+        assert_eq!(result.ghost_nodes, vec![ghost]);
+        assert!(planned.is_ghost(ghost));
+        assert!(!planned.is_ghost(src));
+        assert_eq!(planned.ghost_count(), 1);
+        planned.set_processor(ghost, make_source(iron(), 5.0));
+        planned.set_input_inventory(ghost, simple_inventory(10));
+        planned.set_output_inventory(ghost, simple_inventory(10));
+        let feed = planned.graph.queue_connect_ghost(src, ghost);
+        let drain = planned.graph.queue_connect_ghost(ghost, consumer);
+        let result = planned.apply_mutations();
+        let edges = [feed, drain].map(|p| result.resolve_edge(p).unwrap());
+        assert_eq!(result.ghost_edges, edges.to_vec());
+        for edge in edges {
+            planned.set_transport(edge, make_flow_transport(5.0));
+        }
+
+        for _ in 0..20 {
+            plain.step();
+            planned.step();
+            assert_eq!(plain.state_hash(), planned.state_hash());
+            let (a, b) = (
+                plain.last_step_profile().unwrap(),
+                planned.last_step_profile().unwrap(),
+            );
+            assert_eq!(a.processors_ticked, b.processors_ticked);
+            assert_eq!(a.transports_advanced, b.transports_advanced);
+        }
+        assert_eq!(test_utils::output_quantity(&planned, ghost, iron()), 0);
+        // Ghosts are still in the graph and its queries.
+        assert_eq!(planned.get_inputs(ghost), &[edges[0]]);
+        assert!(planned.snapshot_node(ghost).is_some());
+
+        // Hashing ghosts is opt-in.
+        planned.set_hash_ghosts(true);
+        assert!(planned.hashes_ghosts());
+        assert_ne!(planned.compute_state_hash(), plain.compute_state_hash());
+        planned.set_hash_ghosts(false);
+        assert_eq!(planned.compute_state_hash(), plain.compute_state_hash());
+    }
+
+    #[test]
+    fn real_edges_refuse_ghost_endpoints() {
+        let (mut engine, src, consumer, _) =
+            setup_source_transport_consumer(2.0, 10.0, vec![(iron(), 1)], vec![(gear(), 1)], 2);
+        let pending = engine.graph.queue_add_ghost(BuildingTypeId(3));
+        // Real edges are refused whether the ghost was added in this batch
+        // or an earlier one.
+        let same_batch = engine.graph.queue_connect(src, consumer);
+        let result = engine.apply_mutations();
+        let ghost = result.resolve_node(pending).unwrap();
+        assert!(result.resolve_edge(same_batch).is_some());
+
+        let pending = engine.graph.queue_add_ghost(BuildingTypeId(3));
+        let into_new = engine.graph.queue_connect(src, NodeId::default());
+        let into_old = engine.graph.queue_connect(src, ghost);
+        let filtered = engine
+            .graph
+            .queue_connect_filtered(ghost, consumer, Some(iron()));
+        let planned = engine.graph.queue_connect_ghost(src, ghost);
+        let result = engine.apply_mutations();
+        let second = result.resolve_node(pending).unwrap();
+        assert_eq!(
+            result.rejected_edges,
+            vec![
+                (
+                    into_new,
+                    crate::graph::MutationRejectReason::MissingEndpoint
+                ),
+                (into_old, crate::graph::MutationRejectReason::GhostEndpoint),
+                (filtered, crate::graph::MutationRejectReason::GhostEndpoint),
+            ]
+        );
+        let planned = result.resolve_edge(planned).unwrap();
+        assert_eq!(engine.edge_status(planned), Some(NodeStatus::Ghost));
+
+        let pending = engine.graph.queue_connect(second, consumer);
+        let pending_ghost = engine.graph.queue_add_ghost(BuildingTypeId(3));
+        let result = engine.apply_mutations();
+        let third = result.resolve_node(pending_ghost).unwrap();
+        assert_eq!(
+            result.rejected_edges,
+            vec![(pending, crate::graph::MutationRejectReason::GhostEndpoint)]
+        );
+        let pending = engine.graph.queue_connect(third, second);
+        assert_eq!(
+            engine.apply_mutations().rejected_edges,
+            vec![(pending, crate::graph::MutationRejectReason::GhostEndpoint)]
+        );
+
+        // Once materialized, a node takes real edges.
+        assert!(engine.materialize_ghost(second));
+        let pending = engine.graph.queue_connect(second, consumer);
+        assert!(engine.apply_mutations().resolve_edge(pending).is_some());
+    }
+
+    #[test]
+    fn materializing_a_ghost_keeps_its_id_and_template() {
+        let mut engine = engine_with_stocked_template(100, 50);
+        let pending = engine.graph.queue_add_ghost(BuildingTypeId(0));
+        let chest = engine.apply_mutations().resolve_node(pending).unwrap();
+        engine.set_processor(chest, make_source(gear(), 1.0));
+        assert_eq!(
+            engine.get_input_inventory(chest).unwrap().count_of(iron()),
+            50
+        );
+        for _ in 0..3 {
+            engine.step();
+        }
+        assert_eq!(test_utils::output_quantity(&engine, chest, gear()), 0);
+
+        // Ghosts and their configuration survive a save.
+        let mut engine = Engine::deserialize(&engine.serialize().unwrap()).unwrap();
+        assert!(engine.is_ghost(chest));
+        assert_eq!(engine.ghost_count(), 1);
+        assert!(matches!(
+            engine.get_processor(chest),
+            Some(Processor::Source(_))
+        ));
+
+        assert!(engine.materialize_ghost(chest));
+        assert!(!engine.materialize_ghost(chest));
+        assert!(!engine.materialize_ghost(NodeId::default()));
+        assert!(!engine.is_ghost(chest));
+        assert_eq!(engine.ghost_count(), 0);
+        assert_eq!(engine.node_count(), 1);
+        assert_eq!(
+            engine.get_input_inventory(chest).unwrap().count_of(iron()),
+            50
+        );
+        engine.step();
+        assert_eq!(test_utils::output_quantity(&engine, chest, gear()), 1);
+    }
+
+    #[test]
+    fn silent_flag_drops_node_events_and_survives_a_reload() {
+        use crate::graph::NodeFlags;
+
+        let (mut engine, src, _consumer, _edge) =
+            setup_source_transport_consumer(2.0, 10.0, vec![(iron(), 1)], vec![(gear(), 1)], 2);
+        let hash = engine.compute_state_hash();
+        assert!(engine.set_node_flags(src, NodeFlags::SILENT));
+        assert_eq!(engine.node_flags(src), Some(NodeFlags::SILENT));
+        assert_eq!(engine.compute_state_hash(), hash);
+
+        let mut restored = Engine::deserialize(&engine.serialize().unwrap()).unwrap();
+        assert_eq!(restored.node_flags(src), Some(NodeFlags::SILENT));
+        for engine in [&mut engine, &mut restored] {
+            engine.enable_event_log();
+            engine.step();
+            engine.step();
+            let log = engine.take_event_log().unwrap();
+            assert!(log.events().count() > 0);
+            assert!(log.events().all(|e| e.node() != Some(src)));
+        }
+        assert_eq!(engine.state_hash(), restored.state_hash());
+
+        // Clearing the flag lets the node's events through again.
+        engine.set_node_flags(src, NodeFlags::EMPTY);
+        engine.step();
+        let log = engine.take_event_log().unwrap();
+        assert!(log.events().any(|e| e.node() == Some(src)));
+
+        // A silent node's removal is silent too, and forgets the node.
+        engine.set_node_flags(src, NodeFlags::SILENT);
+        engine.graph.queue_remove_node(src);
+        engine.apply_mutations();
+        let log = engine.take_event_log().unwrap();
+        assert!(log.events().all(|e| e.node() != Some(src)));
+        assert!(!engine.event_bus.is_node_silenced(src));
+        assert!(!engine.set_node_flags(src, NodeFlags::EMPTY));
+    }
+
+    #[test]
+    fn event_history_filter_returns_exact_matches() {
+        use crate::event::HistoryFilter;
+
+        let (mut engine, src, consumer, edge) =
+            setup_source_transport_consumer(2.0, 10.0, vec![(iron(), 1)], vec![(gear(), 1)], 2);
+        engine.set_event_history_capacity(1000);
+        engine.enable_event_log();
+        for _ in 0..6 {
+            engine.step();
+        }
+        let all: Vec<Event> = engine.take_event_log().unwrap().events().cloned().collect();
+
+        let filters = [
+            HistoryFilter::default(),
+            HistoryFilter {
+                kinds: vec![EventKind::ItemProduced, EventKind::ItemDelivered],
+                ..Default::default()
+            },
+            HistoryFilter {
+                node: Some(consumer),
+                ..Default::default()
+            },
+            HistoryFilter {
+                edge: Some(edge),
+                min_tick: Some(2),
+                ..Default::default()
+            },
+            HistoryFilter {
+                kinds: vec![EventKind::ItemProduced],
+                node: Some(src),
+                min_tick: Some(1),
+                max_tick: Some(3),
+                ..Default::default()
+            },
+        ];
+        for filter in filters {
+            let expected: Vec<&Event> = all.iter().filter(|e| filter.matches(e)).collect();
+            let got: Vec<&Event> = engine.event_history(filter.clone()).collect();
+            assert_eq!(got, expected, "{filter:?}");
+        }
+
+        let produced: Vec<u64> = engine
+            .event_history(HistoryFilter {
+                kinds: vec![EventKind::ItemProduced],
+                node: Some(src),
+                min_tick: Some(1),
+                max_tick: Some(3),
+                ..Default::default()
+            })
+            .map(Event::tick)
+            .collect();
+        assert_eq!(produced, vec![1, 2, 3]);
+        assert!(
+            engine
+                .event_history(HistoryFilter {
+                    edge: Some(edge),
+                    ..Default::default()
+                })
+                .all(|e| e.edge() == Some(edge))
+        );
+    }
+
+    #[test]
+    fn event_history_evicts_oldest_past_capacity() {
+        let (mut engine, _src, _consumer, _edge) =
+            setup_source_transport_consumer(2.0, 10.0, vec![(iron(), 1)], vec![(gear(), 1)], 2);
+        engine.set_event_history_capacity(5);
+        engine.enable_event_log();
+        for _ in 0..20 {
+            engine.step();
+        }
+        let all: Vec<Event> = engine.take_event_log().unwrap().events().cloned().collect();
+        assert!(all.len() > 5);
+        let kept: Vec<Event> = engine.event_history(Default::default()).cloned().collect();
+        assert_eq!(kept, all[all.len() - 5..]);
+    }
+
+    #[test]
+    fn event_history_off_allocates_and_records_nothing() {
+        let (mut engine, _src, _consumer, _edge) =
+            setup_source_transport_consumer(2.0, 10.0, vec![(iron(), 1)], vec![(gear(), 1)], 2);
+        for _ in 0..3 {
+            engine.step();
+        }
+        assert!(engine.event_history.is_none());
+        assert!(!engine.event_bus.is_recording());
+        assert_eq!(engine.event_history_capacity(), 0);
+        assert_eq!(engine.event_history(Default::default()).count(), 0);
+    }
+
+    #[test]
+    fn event_history_serialized_only_when_flagged() {
+        let (mut engine, _src, _consumer, _edge) =
+            setup_source_transport_consumer(2.0, 10.0, vec![(iron(), 1)], vec![(gear(), 1)], 2);
+        engine.set_event_history_capacity(64);
+        for _ in 0..3 {
+            engine.step();
+        }
+
+        let restored = Engine::deserialize(&engine.serialize().unwrap()).unwrap();
+        assert_eq!(restored.event_history_capacity(), 0);
+        assert!(!restored.serializes_event_history());
+
+        engine.set_serialize_event_history(true);
+        let mut restored = Engine::deserialize(&engine.serialize().unwrap()).unwrap();
+        assert!(restored.serializes_event_history());
+        assert_eq!(restored.event_history_capacity(), 64);
+        assert!(
+            restored
+                .event_history(Default::default())
+                .eq(engine.event_history(Default::default()))
+        );
+
+        // The restored history keeps recording.
+        engine.step();
+        restored.step();
+        assert!(
+            restored
+                .event_history(Default::default())
+                .eq(engine.event_history(Default::default()))
+        );
+    }
+
+    #[test]
+    fn event_cursors_with_different_filters_read_independently() {
+        let (mut engine, src, _consumer, _edge) =
+            setup_source_transport_consumer(2.0, 10.0, vec![(iron(), 1)], vec![(gear(), 1)], 2);
+        engine.set_event_history_capacity(1000);
+        engine.step();
+        let produced_filter = crate::event::HistoryFilter {
+            kinds: vec![EventKind::ItemProduced],
+            node: Some(src),
+            ..Default::default()
+        };
+        let produced = engine.create_event_cursor(produced_filter.clone());
+        let everything = engine.create_event_cursor(Default::default());
+        for _ in 0..4 {
+            engine.step();
+        }
+
+        // The cursors start at the oldest retained event.
+        let expected: Vec<Event> = engine.event_history(produced_filter).cloned().collect();
+        assert_eq!(expected.len(), 5);
+        let (first, lagged) = engine.read_event_cursor(produced, 2).unwrap();
+        assert!(!lagged);
+        assert_eq!(first, expected[..2]);
+        let (all, _) = engine.read_event_cursor(everything, usize::MAX).unwrap();
+        assert_eq!(
+            all,
+            engine
+                .event_history(Default::default())
+                .cloned()
+                .collect::<Vec<_>>()
+        );
+        let (rest, _) = engine.read_event_cursor(produced, usize::MAX).unwrap();
+        assert_eq!(rest, expected[2..]);
+
+        // Both are caught up and tail the next step on their own.
+        assert!(
+            engine
+                .read_event_cursor(everything, 10)
+                .unwrap()
+                .0
+                .is_empty()
+        );
+        engine.step();
+        let (tail, _) = engine.read_event_cursor(produced, 10).unwrap();
+        assert_eq!(tail.len(), 1);
+        assert_eq!(tail[0].tick(), 5);
+        assert!(
+            engine
+                .read_event_cursor(everything, usize::MAX)
+                .unwrap()
+                .0
+                .iter()
+                .all(|e| e.tick() == 5)
+        );
+
+        assert!(engine.destroy_event_cursor(produced));
+        assert!(engine.read_event_cursor(produced, 10).is_none());
+        assert!(!engine.destroy_event_cursor(produced));
+    }
+
+    #[test]
+    fn slow_event_cursor_reports_lag_exactly_when_overwritten() {
+        let (mut engine, _src, _consumer, _edge) =
+            setup_source_transport_consumer(2.0, 10.0, vec![(iron(), 1)], vec![(gear(), 1)], 2);
+        engine.set_event_history_capacity(5);
+        let cursor = engine.create_event_cursor(Default::default());
+        let (mut lagged_reads, mut clean_reads) = (0, 0);
+        for _ in 0..20 {
+            engine.step();
+            let next = engine.event_cursors[cursor].next;
+            let first = engine.event_history.as_ref().unwrap().first_sequence();
+            let (events, lagged) = engine.read_event_cursor(cursor, 1).unwrap();
+            assert_eq!(lagged, next < first);
+            if lagged {
+                lagged_reads += 1;
+                // Resumed from the oldest retained event.
+                assert_eq!(
+                    events[0],
+                    *engine.event_history(Default::default()).next().unwrap()
+                );
+            } else {
+                clean_reads += 1;
+            }
+        }
+        assert!(lagged_reads > 0 && clean_reads > 0);
+
+        // Cursors are not part of snapshots.
+        engine.set_serialize_event_history(true);
+        let restored = Engine::deserialize(&engine.serialize().unwrap()).unwrap();
+        assert!(restored.event_cursors.is_empty());
+    }
+
+    // -----------------------------------------------------------------------
+    // Inventory transfer rules
+    // -----------------------------------------------------------------------
+
+    /// Hashes of saturated reference factories, recorded before inventory
+    /// transfers moved onto the `Inventory` API. Saturation exercises the
+    /// overflow paths of every transport kind. The vehicle hash was
+    /// re-recorded when transports began holding items a full destination
+    /// cannot take instead of dropping them, and all three when the state
+    /// hash was split into a per-subsystem breakdown that also covers graph
+    /// topology and transport states.
+    #[test]
+    fn inventory_transfers_preserve_reference_state_hashes() {
+        let mut large = test_utils::build_large_factory(200);
+        let mut grid = test_utils::build_grid_factory(6, 6);
+        let (mut vehicle, _, _, edge) =
+            setup_source_transport_consumer(5.0, 10.0, vec![(iron(), 2)], vec![(gear(), 1)], 3);
+        vehicle.set_transport(edge, test_utils::make_vehicle_transport(20, 4));
+        for _ in 0..300 {
+            large.step();
+            grid.step();
+            vehicle.step();
+        }
+        assert_eq!(large.state_hash(), 0x0412_47eb_2bba_9afd);
+        assert_eq!(grid.state_hash(), 0x7286_7cef_b7c2_7a55);
+        assert_eq!(vehicle.state_hash(), 0xa1eb_d267_d6d3_4108);
+    }
+
+    // -----------------------------------------------------------------------
+    // Input modes
+    // -----------------------------------------------------------------------
+
+    /// Source -> flow -> 2 iron => 1 gear line with the consumer in `mode`.
+    fn two_stage_line(source_rate: f64, mode: InputMode) -> (Engine, NodeId, NodeId) {
+        let (mut engine, source, consumer, _) = setup_source_transport_consumer(
+            source_rate,
+            20.0,
+            vec![(iron(), 2)],
+            vec![(gear(), 1)],
+            3,
+        );
+        engine.set_input_mode(consumer, mode);
+        (engine, source, consumer)
+    }
+
+    fn ticks_until_working(engine: &mut Engine, node: NodeId) -> u32 {
+        for tick in 1..=20 {
+            engine.step();
+            if matches!(
+                engine.get_processor_state(node),
+                Some(ProcessorState::Working { .. })
+            ) {
+                return tick;
+            }
+        }
+        panic!("consumer never started working");
+    }
+
+    #[test]
+    fn pull_input_mode_reduces_startup_latency() {
+        let (mut push, _, push_consumer) = two_stage_line(1.0, InputMode::Push);
+        let (mut pull, _, pull_consumer) = two_stage_line(1.0, InputMode::Pull);
+        let push_start = ticks_until_working(&mut push, push_consumer);
+        let pull_start = ticks_until_working(&mut pull, pull_consumer);
+        assert!(
+            pull_start < push_start,
+            "pull started on tick {pull_start}, push on tick {push_start}"
+        );
+    }
+
+    #[test]
+    fn pull_input_mode_only_requests_missing_recipe_inputs() {
+        let (mut push, _, push_consumer) = two_stage_line(10.0, InputMode::Push);
+        let (mut pull, _, pull_consumer) = two_stage_line(10.0, InputMode::Pull);
+        for _ in 0..30 {
+            push.step();
+            pull.step();
+            assert!(test_utils::input_quantity(&pull, pull_consumer, iron()) <= 2);
+        }
+        assert!(test_utils::input_quantity(&push, push_consumer, iron()) > 2);
+    }
+
+    #[test]
+    fn input_mode_defaults_to_push_and_survives_a_round_trip() {
+        let (mut engine, source, consumer) = two_stage_line(1.0, InputMode::Pull);
+        assert_eq!(engine.input_mode(source), InputMode::Push);
+        engine.step();
+        let restored = Engine::deserialize(&engine.serialize().unwrap()).unwrap();
+        assert_eq!(restored.input_mode(consumer), InputMode::Pull);
+        assert_eq!(restored.input_mode(source), InputMode::Push);
+
+        engine.set_input_mode(consumer, InputMode::Push);
+        assert_eq!(engine.input_mode(consumer), InputMode::Push);
+    }
+
+    // -----------------------------------------------------------------------
+    // Flexible processors
+    // -----------------------------------------------------------------------
+
+    /// A furnace with 2-ore iron-plate and copper-plate recipes in the
+    /// engine's registry. Returns the engine, the furnace, and the item ids
+    /// `[iron ore, copper ore, iron plate, copper plate]`.
+    fn furnace(selection: RecipeSelection) -> (Engine, NodeId, [ItemTypeId; 4]) {
+        use crate::registry::*;
+        let mut builder = RegistryBuilder::new();
+        let items = ["iron-ore", "copper-ore", "iron-plate", "copper-plate"]
+            .map(|name| builder.register_item(name, vec![]));
+        for (name, ore, plate) in [
+            ("iron-plate", items[0], items[2]),
+            ("copper-plate", items[1], items[3]),
+        ] {
+            builder.register_recipe(
+                name,
+                vec![RecipeEntry {
+                    item: ore,
+                    quantity: 2,
+                    consumed: true,
+                }],
+                vec![RecipeEntry {
+                    item: plate,
+                    quantity: 1,
+                    consumed: true,
+                }],
+                3,
+            );
+        }
+        let mut engine =
+            Engine::new_with_registry(SimulationStrategy::Tick, builder.build().unwrap());
+        let furnace = test_utils::add_node(
+            &mut engine,
+            Processor::Flexible(FlexibleProcessor::new(
+                vec![RecipeId(0), RecipeId(1)],
+                selection,
+            )),
+            50,
+            50,
+        );
+        (engine, furnace, items)
+    }
+
+    /// Record the recipe of every `RecipeStarted` event.
+    fn record_started(engine: &mut Engine) -> Rc<RefCell<Vec<Option<RecipeId>>>> {
+        let started = Rc::new(RefCell::new(Vec::new()));
+        let log = Rc::clone(&started);
+        engine.on_passive(
+            EventKind::RecipeStarted,
+            Box::new(move |event| {
+                if let Event::RecipeStarted { recipe, .. } = event {
+                    log.borrow_mut().push(*recipe);
+                }
+            }),
+        );
+        started
+    }
+
+    #[test]
+    fn flexible_furnace_smelts_whichever_ore_arrives() {
+        let (mut engine, furnace, [iron_ore, copper_ore, iron_plate, copper_plate]) =
+            furnace(RecipeSelection::FirstMatch);
+        let started = record_started(&mut engine);
+
+        let _ = engine
+            .get_input_inventory_mut(furnace)
+            .unwrap()
+            .insert(copper_ore, 4);
+        engine.step();
+        assert_eq!(engine.get_active_recipe_id(furnace), Some(RecipeId(1)));
+        for _ in 0..10 {
+            engine.step();
+        }
+        assert_eq!(
+            test_utils::output_quantity(&engine, furnace, copper_plate),
+            2
+        );
+        assert_eq!(engine.get_active_recipe_id(furnace), None);
+
+        let _ = engine
+            .get_input_inventory_mut(furnace)
+            .unwrap()
+            .insert(iron_ore, 2);
+        for _ in 0..5 {
+            engine.step();
+        }
+        assert_eq!(test_utils::output_quantity(&engine, furnace, iron_plate), 1);
+        assert_eq!(
+            *started.borrow(),
+            [Some(RecipeId(1)), Some(RecipeId(1)), Some(RecipeId(0))]
+        );
+    }
+
+    #[test]
+    fn flexible_selection_follows_the_input_mix() {
+        for (selection, expected) in [
+            (RecipeSelection::FirstMatch, RecipeId(0)),
+            (RecipeSelection::LargestBatchAvailable, RecipeId(1)),
+        ] {
+            let (mut engine, furnace, [iron_ore, copper_ore, ..]) = furnace(selection);
+            let inv = engine.get_input_inventory_mut(furnace).unwrap();
+            let _ = inv.insert(iron_ore, 2);
+            let _ = inv.insert(copper_ore, 6);
+            engine.step();
+            assert_eq!(engine.get_active_recipe_id(furnace), Some(expected));
+        }
+
+        // Once copper is the only ore left, the priority furnace switches.
+        let (mut engine, furnace, [iron_ore, copper_ore, ..]) =
+            furnace(RecipeSelection::FirstMatch);
+        let started = record_started(&mut engine);
+        let inv = engine.get_input_inventory_mut(furnace).unwrap();
+        let _ = inv.insert(iron_ore, 2);
+        let _ = inv.insert(copper_ore, 2);
+        for _ in 0..10 {
+            engine.step();
+        }
+        assert_eq!(*started.borrow(), [Some(RecipeId(0)), Some(RecipeId(1))]);
+    }
+
+    #[test]
+    fn flexible_furnace_never_starts_without_full_ingredients() {
+        let (mut engine, furnace, [iron_ore, copper_ore, ..]) =
+            furnace(RecipeSelection::LargestBatchAvailable);
+        let started = record_started(&mut engine);
+        let inv = engine.get_input_inventory_mut(furnace).unwrap();
+        let _ = inv.insert(iron_ore, 1);
+        let _ = inv.insert(copper_ore, 1);
+        for _ in 0..5 {
+            engine.step();
+        }
+        assert!(started.borrow().is_empty());
+        assert_eq!(
+            engine.get_processor_state(furnace),
+            Some(&ProcessorState::Stalled {
+                reason: StallReason::MissingInputs
+            })
+        );
+        assert_eq!(test_utils::input_quantity(&engine, furnace, iron_ore), 1);
+        assert_eq!(test_utils::input_quantity(&engine, furnace, copper_ore), 1);
+        assert_eq!(engine.get_active_recipe_id(furnace), None);
+    }
+
+    #[test]
+    fn flexible_cycle_in_progress_survives_a_round_trip() {
+        let (mut engine, furnace, [iron_ore, _, iron_plate, _]) =
+            furnace(RecipeSelection::FirstMatch);
+        let _ = engine
+            .get_input_inventory_mut(furnace)
+            .unwrap()
+            .insert(iron_ore, 2);
+        engine.step();
+
+        // The restored engine has no registry, but the running cycle carries
+        // its own definition.
+        let mut restored = Engine::deserialize(&engine.serialize().unwrap()).unwrap();
+        assert_eq!(restored.state_hash(), engine.state_hash());
+        assert_eq!(restored.get_active_recipe_id(furnace), Some(RecipeId(0)));
+        for _ in 0..3 {
+            restored.step();
+        }
+        assert_eq!(
+            test_utils::output_quantity(&restored, furnace, iron_plate),
+            1
+        );
+    }
+
+    // -----------------------------------------------------------------------
+    // Storage processors
+    // -----------------------------------------------------------------------
+
+    /// A chest of four 10-item slots fed by a belt from a source making
+    /// `rate` iron per tick. Returns the engine, the source and the chest.
+    fn fed_chest(rate: f64) -> (Engine, NodeId, NodeId) {
+        let mut engine = Engine::new(SimulationStrategy::Tick);
+        let source = test_utils::add_node(
+            &mut engine,
+            test_utils::make_source(test_utils::iron(), rate),
+            10,
+            10,
+        );
+        let chest = test_utils::add_node(
+            &mut engine,
+            Processor::Storage(StorageProcessor::default()),
+            10,
+            10,
+        );
+        engine.set_output_inventory(chest, Inventory::new(1, 4, 10));
+        test_utils::connect(
+            &mut engine,
+            source,
+            chest,
+            test_utils::make_item_transport(4),
+        );
+        (engine, source, chest)
+    }
+
+    #[test]
+    fn chest_between_two_belts_buffers_the_surplus() {
+        let (mut engine, _, chest) = fed_chest(1.0);
+        let sink = test_utils::add_node(
+            &mut engine,
+            Processor::Demand(DemandProcessor {
+                input_type: test_utils::iron(),
+                base_rate: Fixed64::from_num(0.5),
+                accumulated: Fixed64::ZERO,
+                consumed_total: 0,
+                accepted_types: None,
+            }),
+            5,
+            5,
+        );
+        test_utils::connect(&mut engine, chest, sink, test_utils::make_item_transport(4));
+        assert_eq!(
+            engine.get_processor_state(chest),
+            Some(&ProcessorState::Storing)
+        );
+
+        let mut held = Vec::new();
+        for _ in 0..120 {
+            engine.step();
+            held.push(test_utils::output_quantity(
+                &engine,
+                chest,
+                test_utils::iron(),
+            ));
+            // Deliveries land in the one inventory outgoing belts take from.
+            assert_eq!(
+                test_utils::input_quantity(&engine, chest, test_utils::iron()),
+                0
+            );
+            assert_eq!(
+                engine.get_processor_state(chest),
+                Some(&ProcessorState::Storing)
+            );
+        }
+
+        // The chest fills with the surplus the slower sink leaves behind and
+        // then holds at capacity while the sink keeps draining it.
+        assert!(held.windows(2).take(100).all(|w| w[1] >= w[0]));
+        assert_eq!(held.iter().max(), Some(&40));
+        assert!(*held.last().unwrap() >= 39);
+        let Some(Processor::Demand(demand)) = engine.get_processor(sink) else {
+            panic!("sink lost its processor");
+        };
+        assert!(demand.consumed_total > 40);
+    }
+
+    #[test]
+    fn lowering_the_storage_limit_blocks_input_without_voiding_items() {
+        let (mut engine, source, chest) = fed_chest(1.0);
+        {
+            let slots = &mut engine.get_output_inventory_mut(chest).unwrap().output_slots;
+            for (slot, quantity) in slots.iter_mut().zip([10, 10, 5]) {
+                let _ = slot.add(test_utils::iron(), quantity);
+            }
+        }
+
+        // Only the full first slot is usable: nothing gets in, nothing is lost.
+        assert!(engine.set_storage_limit(chest, Some(1)));
+        for _ in 0..20 {
+            engine.step();
+        }
+        assert_eq!(
+            test_utils::output_quantity(&engine, chest, test_utils::iron()),
+            25
+        );
+
+        let restored = Engine::deserialize(&engine.serialize().unwrap()).unwrap();
+        assert_eq!(restored.state_hash(), engine.state_hash());
+        let Some(Processor::Storage(storage)) = restored.get_processor(chest) else {
+            panic!("storage processor lost in the round trip");
+        };
+        assert_eq!(storage.usable_slots, Some(1));
+
+        // Lifting the limit lets the waiting belt unload.
+        let limited = engine.subsystem_hashes().processors;
+        assert!(engine.set_storage_limit(chest, None));
+        assert_ne!(engine.subsystem_hashes().processors, limited);
+        for _ in 0..5 {
+            engine.step();
+        }
+        assert!(test_utils::output_quantity(&engine, chest, test_utils::iron()) > 25);
+        assert!(!engine.set_storage_limit(source, Some(1)));
+    }
 }
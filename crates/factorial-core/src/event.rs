@@ -14,16 +14,20 @@
 //! Event types can be suppressed via [`EventBus::suppress`], which prevents
 //! any allocation or recording for that type. Suppressed events have zero cost.
 
+use crate::capacity::InventorySide;
 use crate::fixed::Ticks;
 use crate::id::*;
 use crate::processor::StallReason;
+use crate::sim::StepPhase;
+use slotmap::Key;
+use std::collections::BTreeSet;
 
 // ---------------------------------------------------------------------------
 // Event types
 // ---------------------------------------------------------------------------
 
 /// A simulation event. All events carry the tick at which they occurred.
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 pub enum Event {
     // -- Production --
     ItemProduced {
@@ -40,6 +44,9 @@ pub enum Event {
     },
     RecipeStarted {
         node: NodeId,
+        /// The registry recipe the cycle runs, for processors that select
+        /// one ([`Processor::Flexible`](crate::processor::Processor::Flexible)).
+        recipe: Option<RecipeId>,
         tick: Ticks,
     },
     RecipeCompleted {
@@ -97,10 +104,71 @@ pub enum Event {
         new_recipe_index: usize,
         tick: Ticks,
     },
+
+    // -- Crafting queues --
+    /// A hand-crafting queue on `node` began a craft of `recipe`.
+    CraftStarted {
+        node: NodeId,
+        recipe: RecipeId,
+        tick: Ticks,
+    },
+    /// A hand-crafting queue on `node` finished a craft of `recipe`.
+    /// `remaining` counts the crafts left in that job.
+    CraftCompleted {
+        node: NodeId,
+        recipe: RecipeId,
+        remaining: u32,
+        tick: Ticks,
+    },
+
+    // -- Spoilage --
+    /// `quantity` items of `item_type` stored at `node` reached the end of
+    /// their lifetime. They became `spoiled_into`, or vanished if `None`.
+    ItemSpoiled {
+        node: NodeId,
+        item_type: ItemTypeId,
+        quantity: u32,
+        spoiled_into: Option<ItemTypeId>,
+        tick: Ticks,
+    },
+
+    // -- Inventory capacity --
+    /// Slot `slot` of `node`'s `side` inventory reached its capacity while
+    /// holding `item_type`.
+    InventoryFull {
+        node: NodeId,
+        side: InventorySide,
+        slot: u32,
+        item_type: ItemTypeId,
+        tick: Ticks,
+    },
+    /// A slot that was full dropped below its capacity. `item_type` is the
+    /// item it held when it filled.
+    InventorySpaceAvailable {
+        node: NodeId,
+        side: InventorySide,
+        slot: u32,
+        item_type: ItemTypeId,
+        tick: Ticks,
+    },
+
+    // -- Watchdog --
+    /// The step at `tick` had done `work_done` units of work by the end of
+    /// `phase`, more than the step work limit, and skipped its remaining
+    /// simulation phases.
+    StepTruncated {
+        phase: StepPhase,
+        work_done: u64,
+        tick: Ticks,
+    },
 }
 
 /// Discriminant tag for event types, used for suppression and filtering.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+///
+/// Declaration order is delivery order: [`EventBus::deliver`] hands out
+/// each tick's events grouped by kind in this order. Reordering the
+/// variants changes what subscribers see.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
 pub enum EventKind {
     ItemProduced,
     ItemConsumed,
@@ -115,10 +183,16 @@ pub enum EventKind {
     EdgeAdded,
     EdgeRemoved,
     RecipeSwitched,
+    CraftStarted,
+    CraftCompleted,
+    ItemSpoiled,
+    InventoryFull,
+    InventorySpaceAvailable,
+    StepTruncated,
 }
 
 /// Total number of event kinds.
-const EVENT_KIND_COUNT: usize = 13;
+const EVENT_KIND_COUNT: usize = 19;
 
 impl Event {
     /// Get the discriminant kind for this event.
@@ -137,10 +211,134 @@ impl Event {
             Event::EdgeAdded { .. } => EventKind::EdgeAdded,
             Event::EdgeRemoved { .. } => EventKind::EdgeRemoved,
             Event::RecipeSwitched { .. } => EventKind::RecipeSwitched,
+            Event::CraftStarted { .. } => EventKind::CraftStarted,
+            Event::CraftCompleted { .. } => EventKind::CraftCompleted,
+            Event::ItemSpoiled { .. } => EventKind::ItemSpoiled,
+            Event::InventoryFull { .. } => EventKind::InventoryFull,
+            Event::InventorySpaceAvailable { .. } => EventKind::InventorySpaceAvailable,
+            Event::StepTruncated { .. } => EventKind::StepTruncated,
+        }
+    }
+
+    /// Get the tick this event was stamped with.
+    pub fn tick(&self) -> Ticks {
+        match self {
+            Event::ItemProduced { tick, .. }
+            | Event::ItemConsumed { tick, .. }
+            | Event::RecipeStarted { tick, .. }
+            | Event::RecipeCompleted { tick, .. }
+            | Event::BuildingStalled { tick, .. }
+            | Event::BuildingResumed { tick, .. }
+            | Event::ItemDelivered { tick, .. }
+            | Event::TransportFull { tick, .. }
+            | Event::NodeAdded { tick, .. }
+            | Event::NodeRemoved { tick, .. }
+            | Event::EdgeAdded { tick, .. }
+            | Event::EdgeRemoved { tick, .. }
+            | Event::RecipeSwitched { tick, .. }
+            | Event::CraftStarted { tick, .. }
+            | Event::CraftCompleted { tick, .. }
+            | Event::ItemSpoiled { tick, .. }
+            | Event::InventoryFull { tick, .. }
+            | Event::InventorySpaceAvailable { tick, .. }
+            | Event::StepTruncated { tick, .. } => *tick,
+        }
+    }
+
+    /// The node this event is about, if any. Edge events return `None`.
+    pub fn node(&self) -> Option<NodeId> {
+        match self {
+            Event::ItemProduced { node, .. }
+            | Event::ItemConsumed { node, .. }
+            | Event::RecipeStarted { node, .. }
+            | Event::RecipeCompleted { node, .. }
+            | Event::BuildingStalled { node, .. }
+            | Event::BuildingResumed { node, .. }
+            | Event::NodeAdded { node, .. }
+            | Event::NodeRemoved { node, .. }
+            | Event::RecipeSwitched { node, .. }
+            | Event::CraftStarted { node, .. }
+            | Event::CraftCompleted { node, .. }
+            | Event::ItemSpoiled { node, .. }
+            | Event::InventoryFull { node, .. }
+            | Event::InventorySpaceAvailable { node, .. } => Some(*node),
+            Event::ItemDelivered { .. }
+            | Event::TransportFull { .. }
+            | Event::EdgeAdded { .. }
+            | Event::EdgeRemoved { .. }
+            | Event::StepTruncated { .. } => None,
+        }
+    }
+
+    /// The edge this event is about, if any.
+    pub fn edge(&self) -> Option<EdgeId> {
+        match self {
+            Event::ItemDelivered { edge, .. }
+            | Event::TransportFull { edge, .. }
+            | Event::EdgeAdded { edge, .. }
+            | Event::EdgeRemoved { edge, .. } => Some(*edge),
+            _ => None,
         }
     }
 }
 
+// ---------------------------------------------------------------------------
+// History filters
+// ---------------------------------------------------------------------------
+
+/// Selects events from the retained event history (see
+/// [`Engine::event_history`](crate::engine::Engine::event_history)).
+///
+/// Every criterion that is set must match; the default filter matches all
+/// events.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HistoryFilter {
+    /// Event kinds to keep. Empty keeps every kind.
+    pub kinds: Vec<EventKind>,
+    /// Keep only events about this node. `EdgeAdded` also matches when the
+    /// node is either endpoint.
+    pub node: Option<NodeId>,
+    /// Keep only events about this edge.
+    pub edge: Option<EdgeId>,
+    /// Keep only events stamped at or after this tick.
+    pub min_tick: Option<Ticks>,
+    /// Keep only events stamped at or before this tick.
+    pub max_tick: Option<Ticks>,
+}
+
+impl HistoryFilter {
+    /// Returns true if `event` satisfies every set criterion.
+    pub fn matches(&self, event: &Event) -> bool {
+        if !self.kinds.is_empty() && !self.kinds.contains(&event.kind()) {
+            return false;
+        }
+        if let Some(node) = self.node {
+            let endpoint = matches!(
+                event,
+                Event::EdgeAdded { from, to, .. } if *from == node || *to == node
+            );
+            if event.node() != Some(node) && !endpoint {
+                return false;
+            }
+        }
+        if self.edge.is_some() && event.edge() != self.edge {
+            return false;
+        }
+        let tick = event.tick();
+        self.min_tick.is_none_or(|min| tick >= min) && self.max_tick.is_none_or(|max| tick <= max)
+    }
+}
+
+/// A subscriber's read position in the event history, created by
+/// [`Engine::create_event_cursor`](crate::engine::Engine::create_event_cursor).
+#[derive(Debug, Clone)]
+pub(crate) struct EventCursor {
+    /// Sequence number of the next event to examine; see
+    /// [`EventBuffer::first_sequence`].
+    pub(crate) next: u64,
+    pub(crate) filter: HistoryFilter,
+}
+
 impl EventKind {
     /// Convert to usize index for array lookups.
     fn index(self) -> usize {
@@ -172,7 +370,7 @@ pub enum EventMutation {
 
 /// A pre-allocated ring buffer for events. Fixed capacity; when full, the
 /// oldest events are dropped.
-#[derive(Debug)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct EventBuffer {
     /// Pre-allocated storage.
     events: Vec<Option<Event>>,
@@ -227,6 +425,24 @@ impl EventBuffer {
         self.total_written
     }
 
+    /// Sequence number of the oldest stored event. Events are numbered from
+    /// 0 in the order they were written, so the newest is
+    /// `total_written() - 1`.
+    pub fn first_sequence(&self) -> u64 {
+        self.total_written - self.len as u64
+    }
+
+    /// A copy with room for `capacity` events, keeping the newest that fit
+    /// and the sequence numbering.
+    pub fn resized(&self, capacity: usize) -> Self {
+        let mut resized = Self::new(capacity);
+        for event in self.iter() {
+            resized.push(event.clone());
+        }
+        resized.total_written = self.total_written;
+        resized
+    }
+
     /// Number of events that were dropped because the buffer was full.
     pub fn dropped_count(&self) -> u64 {
         self.total_written.saturating_sub(self.capacity() as u64)
@@ -364,6 +580,9 @@ pub struct EventBus {
     /// Suppressed event kinds. Suppressed events are never buffered.
     suppressed: [bool; EVENT_KIND_COUNT],
 
+    /// Silenced nodes. Events naming one of them are never buffered.
+    silenced_nodes: BTreeSet<NodeId>,
+
     /// Subscribers indexed by event kind.
     subscribers: [Vec<SubscriberEntry>; EVENT_KIND_COUNT],
 
@@ -376,6 +595,10 @@ pub struct EventBus {
 
     /// Monotonically increasing counter for stable sort ordering.
     next_insertion_order: u64,
+
+    /// Copies of emitted events in emission order, when recording is on.
+    /// Used by the engine's [`EventLog`].
+    recorded: Option<Vec<Event>>,
 }
 
 impl std::fmt::Debug for EventBus {
@@ -383,6 +606,7 @@ impl std::fmt::Debug for EventBus {
         f.debug_struct("EventBus")
             .field("buffers", &self.buffers)
             .field("suppressed", &self.suppressed)
+            .field("silenced_nodes", &self.silenced_nodes)
             .field("pending_mutations", &self.pending_mutations)
             .field("default_capacity", &self.default_capacity)
             .finish_non_exhaustive()
@@ -405,6 +629,12 @@ const fn empty_subscriber_array() -> [Vec<SubscriberEntry>; EVENT_KIND_COUNT] {
         Vec::new(),
         Vec::new(),
         Vec::new(),
+        Vec::new(),
+        Vec::new(),
+        Vec::new(),
+        Vec::new(),
+        Vec::new(),
+        Vec::new(),
     ]
 }
 
@@ -414,10 +644,12 @@ impl EventBus {
         Self {
             buffers: Default::default(),
             suppressed: [false; EVENT_KIND_COUNT],
+            silenced_nodes: BTreeSet::new(),
             subscribers: empty_subscriber_array(),
             pending_mutations: Vec::new(),
             default_capacity,
             next_insertion_order: 0,
+            recorded: None,
         }
     }
 
@@ -433,8 +665,24 @@ impl EventBus {
         self.suppressed[kind.index()]
     }
 
+    /// Silence a node: events whose [`Event::node`] is `node` are dropped
+    /// like suppressed kinds. Edge events are unaffected.
+    pub fn silence_node(&mut self, node: NodeId) {
+        self.silenced_nodes.insert(node);
+    }
+
+    /// Let a silenced node's events through again.
+    pub fn unsilence_node(&mut self, node: NodeId) {
+        self.silenced_nodes.remove(&node);
+    }
+
+    /// Check if a node is silenced.
+    pub fn is_node_silenced(&self, node: NodeId) -> bool {
+        self.silenced_nodes.contains(&node)
+    }
+
     /// Emit an event. Stores it in the appropriate ring buffer. No-ops if
-    /// the event kind is suppressed.
+    /// the event kind is suppressed or the event names a silenced node.
     pub fn emit(&mut self, event: Event) {
         let kind = event.kind();
         let idx = kind.index();
@@ -442,6 +690,17 @@ impl EventBus {
         if self.suppressed[idx] {
             return;
         }
+        if !self.silenced_nodes.is_empty()
+            && event
+                .node()
+                .is_some_and(|node| self.silenced_nodes.contains(&node))
+        {
+            return;
+        }
+
+        if let Some(recorded) = self.recorded.as_mut() {
+            recorded.push(event.clone());
+        }
 
         // Lazily allocate buffer on first emit.
         if self.buffers[idx].is_none() {
@@ -501,6 +760,11 @@ impl EventBus {
 
     /// Deliver all buffered events to subscribers. Called during post-tick.
     ///
+    /// Events are delivered grouped by kind, in [`EventKind`] declaration
+    /// order, and in emission order within each kind. The emission order
+    /// across kinds is kept only by recording (see
+    /// [`take_recorded`](Self::take_recorded)).
+    ///
     /// For each event kind that has buffered events:
     /// 1. Sort subscribers by `(priority, insertion_order)`.
     /// 2. Iterate events oldest-to-newest.
@@ -579,6 +843,12 @@ impl EventBus {
             .unwrap_or(0)
     }
 
+    /// Get the number of events buffered across all kinds, i.e. the number
+    /// the next [`deliver`](Self::deliver) will hand to subscribers.
+    pub fn pending_event_count(&self) -> usize {
+        self.buffers.iter().flatten().map(|b| b.len()).sum()
+    }
+
     /// Get the total events ever emitted for a kind (including dropped).
     pub fn total_emitted(&self, kind: EventKind) -> u64 {
         self.buffers[kind.index()]
@@ -587,6 +857,16 @@ impl EventBus {
             .unwrap_or(0)
     }
 
+    /// Get the total events ever emitted across all kinds (including
+    /// dropped, excluding suppressed).
+    pub(crate) fn total_emitted_all(&self) -> u64 {
+        self.buffers
+            .iter()
+            .flatten()
+            .map(|b| b.total_written())
+            .sum()
+    }
+
     /// Clear all buffers. Does not remove subscribers or suppression settings.
     pub fn clear_all(&mut self) {
         for buffer in &mut self.buffers {
@@ -601,6 +881,31 @@ impl EventBus {
     pub fn pending_mutation_count(&self) -> usize {
         self.pending_mutations.len()
     }
+
+    /// Start keeping a copy of every emitted (non-suppressed) event,
+    /// independent of buffer capacity. No-op if already recording.
+    pub fn start_recording(&mut self) {
+        self.recorded.get_or_insert_with(Vec::new);
+    }
+
+    /// Stop recording and discard anything not yet taken.
+    pub fn stop_recording(&mut self) {
+        self.recorded = None;
+    }
+
+    /// Whether emitted events are being recorded.
+    pub fn is_recording(&self) -> bool {
+        self.recorded.is_some()
+    }
+
+    /// Take the events recorded since the last call, in emission order.
+    /// Returns an empty list when not recording.
+    pub fn take_recorded(&mut self) -> Vec<Event> {
+        self.recorded
+            .as_mut()
+            .map(std::mem::take)
+            .unwrap_or_default()
+    }
 }
 
 impl Default for EventBus {
@@ -609,6 +914,605 @@ impl Default for EventBus {
     }
 }
 
+// ---------------------------------------------------------------------------
+// Event log
+// ---------------------------------------------------------------------------
+
+/// Magic number identifying a serialized [`EventLog`].
+pub const EVENT_LOG_MAGIC: u32 = 0xFAC7_0003;
+
+/// Current event log format version. Increment when breaking the wire format.
+pub const EVENT_LOG_VERSION: u32 = 2;
+
+/// Length of the fixed header written by [`EventLog::to_bytes`]:
+/// little-endian `u32` magic followed by little-endian `u32` version.
+const EVENT_LOG_HEADER_LEN: usize = 8;
+
+/// Errors from encoding or decoding an [`EventLog`].
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum EventLogError {
+    #[error("bitcode encoding failed: {0}")]
+    Encode(String),
+    #[error("data too short for event log header")]
+    TooShort,
+    #[error("invalid magic number: expected 0x{:08X}, got 0x{:08X}", EVENT_LOG_MAGIC, .0)]
+    InvalidMagic(u32),
+    #[error("unsupported event log version: expected {}, got {}", EVENT_LOG_VERSION, .0)]
+    UnsupportedVersion(u32),
+    #[error("bitcode decoding failed: {0}")]
+    Decode(String),
+}
+
+/// The events emitted during one simulation tick.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct EventLogTick {
+    /// The tick the events were emitted in (the value of `sim_state.tick`
+    /// before the step that produced them).
+    pub tick: Ticks,
+    /// Events in emission order. Empty for ticks where nothing happened.
+    pub events: Vec<Event>,
+}
+
+/// A recorded event stream, grouped by tick.
+///
+/// Every simulated tick gets an entry, even when it emitted nothing, so
+/// consumers replaying the log see the same tick boundaries as a live
+/// listener. Recorded via [`Engine::enable_event_log`](crate::engine::Engine::enable_event_log).
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct EventLog {
+    /// Format version the log was recorded with.
+    pub version: u32,
+    /// One entry per tick, oldest first.
+    pub ticks: Vec<EventLogTick>,
+}
+
+impl Default for EventLog {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EventLog {
+    /// Create an empty log at the current format version.
+    pub fn new() -> Self {
+        Self {
+            version: EVENT_LOG_VERSION,
+            ticks: Vec::new(),
+        }
+    }
+
+    /// Append the events for one tick.
+    pub fn push_tick(&mut self, tick: Ticks, events: Vec<Event>) {
+        self.ticks.push(EventLogTick { tick, events });
+    }
+
+    /// Number of ticks recorded.
+    pub fn tick_count(&self) -> usize {
+        self.ticks.len()
+    }
+
+    /// Total number of events across all ticks.
+    pub fn event_count(&self) -> usize {
+        self.ticks.iter().map(|t| t.events.len()).sum()
+    }
+
+    /// Whether the log has no ticks.
+    pub fn is_empty(&self) -> bool {
+        self.ticks.is_empty()
+    }
+
+    /// Iterate every event in order.
+    pub fn events(&self) -> impl Iterator<Item = &Event> {
+        self.ticks.iter().flat_map(|t| t.events.iter())
+    }
+
+    /// Encode the log as a version header followed by a bitcode payload.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, EventLogError> {
+        let payload =
+            bitcode::serialize(&self.ticks).map_err(|e| EventLogError::Encode(e.to_string()))?;
+        let mut data = Vec::with_capacity(EVENT_LOG_HEADER_LEN + payload.len());
+        data.extend_from_slice(&EVENT_LOG_MAGIC.to_le_bytes());
+        data.extend_from_slice(&self.version.to_le_bytes());
+        data.extend_from_slice(&payload);
+        Ok(data)
+    }
+
+    /// Decode a log written by [`to_bytes`](Self::to_bytes). The header is
+    /// checked before the payload is decoded.
+    pub fn from_bytes(data: &[u8]) -> Result<Self, EventLogError> {
+        if data.len() < EVENT_LOG_HEADER_LEN {
+            return Err(EventLogError::TooShort);
+        }
+        let magic = u32::from_le_bytes([data[0], data[1], data[2], data[3]]);
+        if magic != EVENT_LOG_MAGIC {
+            return Err(EventLogError::InvalidMagic(magic));
+        }
+        let version = u32::from_le_bytes([data[4], data[5], data[6], data[7]]);
+        if version != EVENT_LOG_VERSION {
+            return Err(EventLogError::UnsupportedVersion(version));
+        }
+        let ticks = bitcode::deserialize(&data[EVENT_LOG_HEADER_LEN..])
+            .map_err(|e| EventLogError::Decode(e.to_string()))?;
+        Ok(Self { version, ticks })
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Compact event encoding
+// ---------------------------------------------------------------------------
+
+/// Magic number identifying a compact event batch.
+pub const COMPACT_EVENTS_MAGIC: u32 = 0xFAC7_0004;
+
+/// Current compact event encoding version. Increment when breaking the wire
+/// format.
+pub const COMPACT_EVENTS_VERSION: u32 = 2;
+
+/// Length of the fixed header written by [`encode_events_compact`]:
+/// little-endian `u32` magic followed by little-endian `u32` version.
+const COMPACT_EVENTS_HEADER_LEN: usize = 8;
+
+/// Errors from decoding a compact event batch.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum CompactEventError {
+    #[error("data too short for compact event header")]
+    TooShort,
+    #[error("invalid magic number: expected 0x{:08X}, got 0x{:08X}", COMPACT_EVENTS_MAGIC, .0)]
+    InvalidMagic(u32),
+    #[error("unsupported compact event version: expected {}, got {}", COMPACT_EVENTS_VERSION, .0)]
+    UnsupportedVersion(u32),
+    #[error("compact event data truncated at byte {0}")]
+    Truncated(usize),
+    #[error("invalid compact event tag {tag} at byte {offset}")]
+    InvalidTag { tag: u8, offset: usize },
+    #[error("invalid value at byte {0}")]
+    InvalidValue(usize),
+    #[error("{0} trailing bytes after the last event")]
+    TrailingBytes(usize),
+}
+
+/// Encode `events` as a versioned, variable-length byte stream.
+///
+/// After the header comes a varint event count, then per event a one-byte
+/// tag, the zigzag varint tick delta from the previous event, and only the
+/// fields that event carries, each as a varint. Node and edge ids are
+/// written as slot index then version. A typical production event takes
+/// 5-8 bytes instead of a fixed-size struct with zeroed fields.
+pub fn encode_events_compact(events: &[Event]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(COMPACT_EVENTS_HEADER_LEN + 2 + events.len() * 8);
+    out.extend_from_slice(&COMPACT_EVENTS_MAGIC.to_le_bytes());
+    out.extend_from_slice(&COMPACT_EVENTS_VERSION.to_le_bytes());
+    write_varint(&mut out, events.len() as u64);
+
+    let mut prev_tick: Ticks = 0;
+    for event in events {
+        out.push(compact_tag(event.kind()));
+        let delta = event.tick().wrapping_sub(prev_tick) as i64;
+        write_varint(&mut out, ((delta << 1) ^ (delta >> 63)) as u64);
+        prev_tick = event.tick();
+
+        match event {
+            Event::ItemProduced {
+                node,
+                item_type,
+                quantity,
+                ..
+            }
+            | Event::ItemConsumed {
+                node,
+                item_type,
+                quantity,
+                ..
+            } => {
+                write_key(&mut out, node.data());
+                write_varint(&mut out, u64::from(item_type.0));
+                write_varint(&mut out, u64::from(*quantity));
+            }
+            Event::RecipeStarted { node, recipe, .. } => {
+                write_key(&mut out, node.data());
+                // 0 for no recipe, otherwise the id plus one.
+                write_varint(&mut out, recipe.map_or(0, |r| u64::from(r.0) + 1));
+            }
+            Event::RecipeCompleted { node, .. }
+            | Event::BuildingResumed { node, .. }
+            | Event::NodeRemoved { node, .. } => write_key(&mut out, node.data()),
+            Event::BuildingStalled { node, reason, .. } => {
+                write_key(&mut out, node.data());
+                out.push(match reason {
+                    StallReason::MissingInputs => 0,
+                    StallReason::OutputFull => 1,
+                    StallReason::NoPower => 2,
+                    StallReason::Depleted => 3,
+                });
+            }
+            Event::ItemDelivered { edge, quantity, .. } => {
+                write_key(&mut out, edge.data());
+                write_varint(&mut out, u64::from(*quantity));
+            }
+            Event::TransportFull { edge, .. } | Event::EdgeRemoved { edge, .. } => {
+                write_key(&mut out, edge.data());
+            }
+            Event::NodeAdded {
+                node,
+                building_type,
+                ..
+            } => {
+                write_key(&mut out, node.data());
+                write_varint(&mut out, u64::from(building_type.0));
+            }
+            Event::EdgeAdded { edge, from, to, .. } => {
+                write_key(&mut out, edge.data());
+                write_key(&mut out, from.data());
+                write_key(&mut out, to.data());
+            }
+            Event::RecipeSwitched {
+                node,
+                old_recipe_index,
+                new_recipe_index,
+                ..
+            } => {
+                write_key(&mut out, node.data());
+                write_varint(&mut out, *old_recipe_index as u64);
+                write_varint(&mut out, *new_recipe_index as u64);
+            }
+            Event::CraftStarted { node, recipe, .. } => {
+                write_key(&mut out, node.data());
+                write_varint(&mut out, u64::from(recipe.0));
+            }
+            Event::CraftCompleted {
+                node,
+                recipe,
+                remaining,
+                ..
+            } => {
+                write_key(&mut out, node.data());
+                write_varint(&mut out, u64::from(recipe.0));
+                write_varint(&mut out, u64::from(*remaining));
+            }
+            Event::ItemSpoiled {
+                node,
+                item_type,
+                quantity,
+                spoiled_into,
+                ..
+            } => {
+                write_key(&mut out, node.data());
+                write_varint(&mut out, u64::from(item_type.0));
+                write_varint(&mut out, u64::from(*quantity));
+                // 0 for "vanished", otherwise the product id plus one.
+                write_varint(&mut out, spoiled_into.map_or(0, |p| u64::from(p.0) + 1));
+            }
+            Event::InventoryFull {
+                node,
+                side,
+                slot,
+                item_type,
+                ..
+            }
+            | Event::InventorySpaceAvailable {
+                node,
+                side,
+                slot,
+                item_type,
+                ..
+            } => {
+                write_key(&mut out, node.data());
+                out.push(match side {
+                    InventorySide::Input => 0,
+                    InventorySide::Output => 1,
+                });
+                write_varint(&mut out, u64::from(*slot));
+                write_varint(&mut out, u64::from(item_type.0));
+            }
+            Event::StepTruncated {
+                phase, work_done, ..
+            } => {
+                out.push(match phase {
+                    StepPhase::PreTick => 0,
+                    StepPhase::Transport => 1,
+                    StepPhase::Process => 2,
+                    StepPhase::Component => 3,
+                });
+                write_varint(&mut out, *work_done);
+            }
+        }
+    }
+    out
+}
+
+/// Decode a batch written by [`encode_events_compact`]. The header is
+/// checked before any event is decoded, and the whole input must be
+/// consumed.
+pub fn decode_events_compact(data: &[u8]) -> Result<Vec<Event>, CompactEventError> {
+    if data.len() < COMPACT_EVENTS_HEADER_LEN {
+        return Err(CompactEventError::TooShort);
+    }
+    let magic = u32::from_le_bytes([data[0], data[1], data[2], data[3]]);
+    if magic != COMPACT_EVENTS_MAGIC {
+        return Err(CompactEventError::InvalidMagic(magic));
+    }
+    let version = u32::from_le_bytes([data[4], data[5], data[6], data[7]]);
+    if version != COMPACT_EVENTS_VERSION {
+        return Err(CompactEventError::UnsupportedVersion(version));
+    }
+
+    let mut reader = CompactReader {
+        data,
+        pos: COMPACT_EVENTS_HEADER_LEN,
+    };
+    let count = reader.varint()?;
+    // Every event takes at least three bytes; reject absurd counts before
+    // allocating.
+    let capacity = usize::try_from(count)
+        .ok()
+        .filter(|&n| n <= data.len() / 3)
+        .ok_or(CompactEventError::InvalidValue(COMPACT_EVENTS_HEADER_LEN))?;
+    let mut events = Vec::with_capacity(capacity);
+
+    let mut tick: Ticks = 0;
+    for _ in 0..count {
+        let tag_offset = reader.pos;
+        let tag = reader.byte()?;
+        let zigzag = reader.varint()?;
+        let delta = ((zigzag >> 1) as i64) ^ -((zigzag & 1) as i64);
+        tick = tick.wrapping_add(delta as u64);
+
+        let event = match tag {
+            0 => Event::ItemProduced {
+                node: reader.key()?.into(),
+                item_type: ItemTypeId(reader.u32()?),
+                quantity: reader.u32()?,
+                tick,
+            },
+            1 => Event::ItemConsumed {
+                node: reader.key()?.into(),
+                item_type: ItemTypeId(reader.u32()?),
+                quantity: reader.u32()?,
+                tick,
+            },
+            2 => {
+                let node = reader.key()?.into();
+                let offset = reader.pos;
+                let recipe = match reader.varint()? {
+                    0 => None,
+                    id => Some(RecipeId(
+                        u32::try_from(id - 1)
+                            .map_err(|_| CompactEventError::InvalidValue(offset))?,
+                    )),
+                };
+                Event::RecipeStarted { node, recipe, tick }
+            }
+            3 => Event::RecipeCompleted {
+                node: reader.key()?.into(),
+                tick,
+            },
+            4 => {
+                let node = reader.key()?.into();
+                let offset = reader.pos;
+                let reason = match reader.byte()? {
+                    0 => StallReason::MissingInputs,
+                    1 => StallReason::OutputFull,
+                    2 => StallReason::NoPower,
+                    3 => StallReason::Depleted,
+                    _ => return Err(CompactEventError::InvalidValue(offset)),
+                };
+                Event::BuildingStalled { node, reason, tick }
+            }
+            5 => Event::BuildingResumed {
+                node: reader.key()?.into(),
+                tick,
+            },
+            6 => Event::ItemDelivered {
+                edge: reader.key()?.into(),
+                quantity: reader.u32()?,
+                tick,
+            },
+            7 => Event::TransportFull {
+                edge: reader.key()?.into(),
+                tick,
+            },
+            8 => Event::NodeAdded {
+                node: reader.key()?.into(),
+                building_type: BuildingTypeId(reader.u32()?),
+                tick,
+            },
+            9 => Event::NodeRemoved {
+                node: reader.key()?.into(),
+                tick,
+            },
+            10 => Event::EdgeAdded {
+                edge: reader.key()?.into(),
+                from: reader.key()?.into(),
+                to: reader.key()?.into(),
+                tick,
+            },
+            11 => Event::EdgeRemoved {
+                edge: reader.key()?.into(),
+                tick,
+            },
+            12 => Event::RecipeSwitched {
+                node: reader.key()?.into(),
+                old_recipe_index: reader.usize()?,
+                new_recipe_index: reader.usize()?,
+                tick,
+            },
+            13 => Event::CraftStarted {
+                node: reader.key()?.into(),
+                recipe: RecipeId(reader.u32()?),
+                tick,
+            },
+            14 => Event::CraftCompleted {
+                node: reader.key()?.into(),
+                recipe: RecipeId(reader.u32()?),
+                remaining: reader.u32()?,
+                tick,
+            },
+            15 => Event::ItemSpoiled {
+                node: reader.key()?.into(),
+                item_type: ItemTypeId(reader.u32()?),
+                quantity: reader.u32()?,
+                spoiled_into: {
+                    let start = reader.pos;
+                    match reader.varint()? {
+                        0 => None,
+                        v => Some(ItemTypeId(
+                            u32::try_from(v - 1)
+                                .map_err(|_| CompactEventError::InvalidValue(start))?,
+                        )),
+                    }
+                },
+                tick,
+            },
+            16 | 17 => {
+                let node = reader.key()?.into();
+                let offset = reader.pos;
+                let side = match reader.byte()? {
+                    0 => InventorySide::Input,
+                    1 => InventorySide::Output,
+                    _ => return Err(CompactEventError::InvalidValue(offset)),
+                };
+                let slot = reader.u32()?;
+                let item_type = ItemTypeId(reader.u32()?);
+                if tag == 16 {
+                    Event::InventoryFull {
+                        node,
+                        side,
+                        slot,
+                        item_type,
+                        tick,
+                    }
+                } else {
+                    Event::InventorySpaceAvailable {
+                        node,
+                        side,
+                        slot,
+                        item_type,
+                        tick,
+                    }
+                }
+            }
+            18 => {
+                let offset = reader.pos;
+                let phase = match reader.byte()? {
+                    0 => StepPhase::PreTick,
+                    1 => StepPhase::Transport,
+                    2 => StepPhase::Process,
+                    3 => StepPhase::Component,
+                    _ => return Err(CompactEventError::InvalidValue(offset)),
+                };
+                Event::StepTruncated {
+                    phase,
+                    work_done: reader.varint()?,
+                    tick,
+                }
+            }
+            _ => {
+                return Err(CompactEventError::InvalidTag {
+                    tag,
+                    offset: tag_offset,
+                });
+            }
+        };
+        events.push(event);
+    }
+
+    if reader.pos != data.len() {
+        return Err(CompactEventError::TrailingBytes(data.len() - reader.pos));
+    }
+    Ok(events)
+}
+
+/// Wire tag for each event kind. Part of the compact format: never reorder.
+fn compact_tag(kind: EventKind) -> u8 {
+    match kind {
+        EventKind::ItemProduced => 0,
+        EventKind::ItemConsumed => 1,
+        EventKind::RecipeStarted => 2,
+        EventKind::RecipeCompleted => 3,
+        EventKind::BuildingStalled => 4,
+        EventKind::BuildingResumed => 5,
+        EventKind::ItemDelivered => 6,
+        EventKind::TransportFull => 7,
+        EventKind::NodeAdded => 8,
+        EventKind::NodeRemoved => 9,
+        EventKind::EdgeAdded => 10,
+        EventKind::EdgeRemoved => 11,
+        EventKind::RecipeSwitched => 12,
+        EventKind::CraftStarted => 13,
+        EventKind::CraftCompleted => 14,
+        EventKind::ItemSpoiled => 15,
+        EventKind::InventoryFull => 16,
+        EventKind::InventorySpaceAvailable => 17,
+        EventKind::StepTruncated => 18,
+    }
+}
+
+/// Append `value` as an unsigned LEB128 varint.
+fn write_varint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+/// Append a slotmap key as its slot index then its version.
+fn write_key(out: &mut Vec<u8>, key: slotmap::KeyData) {
+    let raw = key.as_ffi();
+    write_varint(out, raw & 0xFFFF_FFFF);
+    write_varint(out, raw >> 32);
+}
+
+/// Cursor over a compact event batch.
+struct CompactReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl CompactReader<'_> {
+    fn byte(&mut self) -> Result<u8, CompactEventError> {
+        let byte = *self
+            .data
+            .get(self.pos)
+            .ok_or(CompactEventError::Truncated(self.pos))?;
+        self.pos += 1;
+        Ok(byte)
+    }
+
+    fn varint(&mut self) -> Result<u64, CompactEventError> {
+        let start = self.pos;
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = self.byte()?;
+            let bits = u64::from(byte & 0x7F);
+            if shift == 63 && bits > 1 {
+                return Err(CompactEventError::InvalidValue(start));
+            }
+            value |= bits << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(CompactEventError::InvalidValue(start))
+    }
+
+    fn u32(&mut self) -> Result<u32, CompactEventError> {
+        let start = self.pos;
+        u32::try_from(self.varint()?).map_err(|_| CompactEventError::InvalidValue(start))
+    }
+
+    fn usize(&mut self) -> Result<usize, CompactEventError> {
+        let start = self.pos;
+        usize::try_from(self.varint()?).map_err(|_| CompactEventError::InvalidValue(start))
+    }
+
+    fn key(&mut self) -> Result<slotmap::KeyData, CompactEventError> {
+        let index = u64::from(self.u32()?);
+        let version = u64::from(self.u32()?);
+        Ok(slotmap::KeyData::from_ffi((version << 32) | index))
+    }
+}
+
 // ===========================================================================
 // Tests
 // ===========================================================================
@@ -926,7 +1830,11 @@ mod tests {
                 quantity: 1,
                 tick: 0,
             },
-            Event::RecipeStarted { node, tick: 0 },
+            Event::RecipeStarted {
+                node,
+                recipe: None,
+                tick: 0,
+            },
             Event::RecipeCompleted { node, tick: 0 },
             Event::BuildingStalled {
                 node,
@@ -989,8 +1897,16 @@ mod tests {
             quantity: 1,
             tick: 1,
         });
-        bus.emit(Event::RecipeStarted { node, tick: 1 });
-        bus.emit(Event::RecipeStarted { node, tick: 2 });
+        bus.emit(Event::RecipeStarted {
+            node,
+            recipe: None,
+            tick: 1,
+        });
+        bus.emit(Event::RecipeStarted {
+            node,
+            recipe: None,
+            tick: 2,
+        });
 
         assert_eq!(bus.buffered_count(EventKind::ItemProduced), 1);
         assert_eq!(bus.buffered_count(EventKind::RecipeStarted), 2);
@@ -1239,7 +2155,11 @@ mod tests {
             quantity: 1,
             tick: 1,
         });
-        bus.emit(Event::RecipeStarted { node, tick: 1 });
+        bus.emit(Event::RecipeStarted {
+            node,
+            recipe: None,
+            tick: 1,
+        });
 
         assert_eq!(bus.buffered_count(EventKind::ItemProduced), 1);
         assert_eq!(bus.buffered_count(EventKind::RecipeStarted), 1);
@@ -1656,4 +2576,318 @@ mod tests {
         let buf = EventBuffer::new(0);
         assert_eq!(buf.capacity(), 1);
     }
+
+    // -----------------------------------------------------------------------
+    // Test 30: Recording keeps every unsuppressed event in emission order
+    // -----------------------------------------------------------------------
+    #[test]
+    fn recording_keeps_events_past_buffer_capacity() {
+        let mut bus = EventBus::new(2);
+        let node = make_node_id();
+        bus.suppress(EventKind::RecipeStarted);
+        bus.start_recording();
+
+        for tick in 0..5 {
+            bus.emit(Event::RecipeStarted {
+                node,
+                recipe: None,
+                tick,
+            });
+            bus.emit(Event::RecipeCompleted { node, tick });
+        }
+
+        let recorded = bus.take_recorded();
+        assert_eq!(recorded.len(), 5);
+        assert!(recorded.iter().enumerate().all(|(i, e)| *e
+            == Event::RecipeCompleted {
+                node,
+                tick: i as u64
+            }));
+        assert!(bus.take_recorded().is_empty());
+
+        bus.stop_recording();
+        bus.emit(Event::RecipeCompleted { node, tick: 9 });
+        assert!(bus.take_recorded().is_empty());
+    }
+
+    // -----------------------------------------------------------------------
+    // Test 31: Event log round-trips through bytes and serde
+    // -----------------------------------------------------------------------
+    #[test]
+    fn event_log_round_trips() {
+        let node = make_node_id();
+        let edge = make_edge_id();
+        let mut log = EventLog::new();
+        log.push_tick(
+            0,
+            vec![
+                Event::ItemProduced {
+                    node,
+                    item_type: iron(),
+                    quantity: 2,
+                    tick: 0,
+                },
+                Event::BuildingStalled {
+                    node,
+                    reason: StallReason::OutputFull,
+                    tick: 0,
+                },
+            ],
+        );
+        log.push_tick(1, Vec::new());
+        log.push_tick(
+            2,
+            vec![Event::ItemDelivered {
+                edge,
+                quantity: 1,
+                tick: 2,
+            }],
+        );
+
+        let bytes = log.to_bytes().unwrap();
+        assert_eq!(&bytes[..4], &EVENT_LOG_MAGIC.to_le_bytes());
+        assert_eq!(EventLog::from_bytes(&bytes).unwrap(), log);
+
+        let json = serde_json::to_string(&log).unwrap();
+        let restored: EventLog = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored, log);
+        assert_eq!(restored.tick_count(), 3);
+        assert_eq!(restored.event_count(), 3);
+    }
+
+    // -----------------------------------------------------------------------
+    // Test 32: Event log header is validated before decoding
+    // -----------------------------------------------------------------------
+    #[test]
+    fn event_log_rejects_bad_header() {
+        let mut bytes = EventLog::new().to_bytes().unwrap();
+        assert_eq!(
+            EventLog::from_bytes(&bytes[..4]),
+            Err(EventLogError::TooShort)
+        );
+
+        bytes[4..8].copy_from_slice(&(EVENT_LOG_VERSION + 1).to_le_bytes());
+        assert_eq!(
+            EventLog::from_bytes(&bytes),
+            Err(EventLogError::UnsupportedVersion(EVENT_LOG_VERSION + 1))
+        );
+
+        bytes[0..4].copy_from_slice(&0u32.to_le_bytes());
+        assert_eq!(
+            EventLog::from_bytes(&bytes),
+            Err(EventLogError::InvalidMagic(0))
+        );
+    }
+
+    // -----------------------------------------------------------------------
+    // Test 33: Compact encoding round-trips a mixed event stream
+    // -----------------------------------------------------------------------
+
+    fn mixed_events() -> Vec<Event> {
+        let mut nodes = slotmap::SlotMap::<NodeId, ()>::with_key();
+        let a = nodes.insert(());
+        let b = nodes.insert(());
+        nodes.remove(a);
+        let c = nodes.insert(()); // reuses a's slot with a newer version
+        let edge = make_edge_id();
+        vec![
+            Event::NodeAdded {
+                node: a,
+                building_type: BuildingTypeId(7),
+                tick: 120_000,
+            },
+            Event::EdgeAdded {
+                edge,
+                from: a,
+                to: b,
+                tick: 120_000,
+            },
+            Event::RecipeStarted {
+                node: b,
+                recipe: Some(RecipeId(3)),
+                tick: 120_001,
+            },
+            Event::ItemConsumed {
+                node: b,
+                item_type: iron(),
+                quantity: 2,
+                tick: 120_001,
+            },
+            Event::ItemProduced {
+                node: b,
+                item_type: ItemTypeId(300),
+                quantity: 1,
+                tick: 120_005,
+            },
+            Event::RecipeCompleted {
+                node: b,
+                tick: 120_005,
+            },
+            Event::BuildingStalled {
+                node: c,
+                reason: StallReason::Depleted,
+                tick: 120_005,
+            },
+            Event::BuildingResumed {
+                node: c,
+                tick: 120_009,
+            },
+            Event::ItemDelivered {
+                edge,
+                quantity: u32::MAX,
+                tick: 120_009,
+            },
+            Event::TransportFull {
+                edge,
+                tick: 120_010,
+            },
+            // Ticks need not be monotonic.
+            Event::RecipeSwitched {
+                node: c,
+                old_recipe_index: 0,
+                new_recipe_index: 3,
+                tick: 5,
+            },
+            Event::EdgeRemoved {
+                edge,
+                tick: u64::MAX,
+            },
+            Event::NodeRemoved { node: a, tick: 0 },
+            Event::CraftStarted {
+                node: a,
+                recipe: RecipeId(4),
+                tick: 1,
+            },
+            Event::CraftCompleted {
+                node: a,
+                recipe: RecipeId(4),
+                remaining: 2,
+                tick: 31,
+            },
+            Event::ItemSpoiled {
+                node: c,
+                item_type: ItemTypeId(u32::MAX),
+                quantity: 3,
+                spoiled_into: None,
+                tick: 40,
+            },
+            Event::ItemSpoiled {
+                node: c,
+                item_type: ItemTypeId(1),
+                quantity: 1,
+                spoiled_into: Some(ItemTypeId(u32::MAX)),
+                tick: 41,
+            },
+            Event::InventoryFull {
+                node: b,
+                side: InventorySide::Output,
+                slot: 3,
+                item_type: ItemTypeId(8),
+                tick: 42,
+            },
+            Event::InventorySpaceAvailable {
+                node: b,
+                side: InventorySide::Input,
+                slot: u32::MAX,
+                item_type: ItemTypeId(8),
+                tick: 43,
+            },
+            Event::StepTruncated {
+                phase: StepPhase::Process,
+                work_done: u64::MAX,
+                tick: 43,
+            },
+        ]
+    }
+
+    #[test]
+    fn compact_events_round_trip() {
+        let events = mixed_events();
+        let bytes = encode_events_compact(&events);
+        assert_eq!(decode_events_compact(&bytes), Ok(events));
+
+        let empty = encode_events_compact(&[]);
+        assert_eq!(empty.len(), COMPACT_EVENTS_HEADER_LEN + 1);
+        assert_eq!(decode_events_compact(&empty), Ok(Vec::new()));
+    }
+
+    // -----------------------------------------------------------------------
+    // Test 34: Compact encoding is far smaller than a flat 56-byte layout
+    // -----------------------------------------------------------------------
+    #[test]
+    fn compact_events_are_smaller_than_flat_layout() {
+        let mut nodes = slotmap::SlotMap::<NodeId, ()>::with_key();
+        let ids: Vec<NodeId> = (0..100).map(|_| nodes.insert(())).collect();
+        let events: Vec<Event> = ids
+            .iter()
+            .flat_map(|&node| {
+                [
+                    Event::ItemProduced {
+                        node,
+                        item_type: iron(),
+                        quantity: 1,
+                        tick: 36_000,
+                    },
+                    Event::RecipeCompleted { node, tick: 36_000 },
+                ]
+            })
+            .collect();
+
+        let bytes = encode_events_compact(&events);
+        let flat = events.len() * 56;
+        assert!(
+            bytes.len() * 5 < flat,
+            "compact {} bytes vs flat {flat} bytes",
+            bytes.len()
+        );
+        assert_eq!(decode_events_compact(&bytes), Ok(events));
+    }
+
+    // -----------------------------------------------------------------------
+    // Test 35: Compact decoding rejects malformed input
+    // -----------------------------------------------------------------------
+    #[test]
+    fn compact_events_reject_malformed_input() {
+        let bytes = encode_events_compact(&mixed_events());
+        assert_eq!(
+            decode_events_compact(&bytes[..4]),
+            Err(CompactEventError::TooShort)
+        );
+
+        let mut bad = bytes.clone();
+        bad[4..8].copy_from_slice(&(COMPACT_EVENTS_VERSION + 1).to_le_bytes());
+        assert_eq!(
+            decode_events_compact(&bad),
+            Err(CompactEventError::UnsupportedVersion(
+                COMPACT_EVENTS_VERSION + 1
+            ))
+        );
+        bad[0..4].copy_from_slice(&0u32.to_le_bytes());
+        assert_eq!(
+            decode_events_compact(&bad),
+            Err(CompactEventError::InvalidMagic(0))
+        );
+
+        assert_eq!(
+            decode_events_compact(&bytes[..bytes.len() - 1]),
+            Err(CompactEventError::Truncated(bytes.len() - 1))
+        );
+
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        assert_eq!(
+            decode_events_compact(&trailing),
+            Err(CompactEventError::TrailingBytes(1))
+        );
+
+        let mut bad_tag = bytes.clone();
+        bad_tag[COMPACT_EVENTS_HEADER_LEN + 1] = 200;
+        assert_eq!(
+            decode_events_compact(&bad_tag),
+            Err(CompactEventError::InvalidTag {
+                tag: 200,
+                offset: COMPACT_EVENTS_HEADER_LEN + 1,
+            })
+        );
+    }
 }
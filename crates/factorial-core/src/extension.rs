@@ -0,0 +1,254 @@
+//! Optional engine extensions and their enable flags.
+//!
+//! A title that ships only processors and transports should pay nothing for
+//! logic networks, recipe fluids, crafting queues or the other optional
+//! subsystems, and its saves and state hashes should not depend on them. The
+//! engine therefore tracks which [`ExtensionId`]s are enabled. An extension
+//! is enabled the first time the engine sees it used -- registering its
+//! module, creating a crafting queue, making an item perishable, buffering a
+//! recipe fluid -- or explicitly with [`Engine::enable_extension`], which is
+//! how hosts declare extensions that run outside the engine, such as
+//! statistics or power networks.
+//!
+//! The set of enabled extensions is part of the state hash and of every
+//! snapshot. An engine that never enabled one hashes and serializes exactly
+//! as a build without extension support would. Enabled extensions stay
+//! enabled for the life of the engine.
+//!
+//! Deserializing a snapshot that has an extension enabled which this build
+//! does not know fails with [`DeserializeError::UnknownExtensions`] instead
+//! of silently dropping that extension's state.
+//!
+//! [`DeserializeError::UnknownExtensions`]: crate::serialize::DeserializeError::UnknownExtensions
+
+use crate::dirty::DirtyTracker;
+use crate::engine::Engine;
+use crate::serialize::DeserializeError;
+use crate::sim::StateHash;
+
+/// An optional engine subsystem. The discriminant is the extension's bit in
+/// [`Engine::extension_mask`] and must never change.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[repr(u8)]
+pub enum ExtensionId {
+    /// Logic networks (`factorial-logic`).
+    Logic = 0,
+    /// Recipe fluids and fluid networks (`factorial-fluid`).
+    Fluid = 1,
+    /// Production statistics (`factorial-stats`).
+    Stats = 2,
+    /// Research (`factorial-tech-tree`).
+    TechTree = 3,
+    /// Power networks (`factorial-power`).
+    Power = 4,
+    /// Spatial placement (`factorial-spatial`).
+    Spatial = 5,
+    /// Hand-crafting queues.
+    Crafting = 6,
+    /// Item spoilage.
+    Spoilage = 7,
+}
+
+impl ExtensionId {
+    /// Every extension, in bit order.
+    pub const ALL: [ExtensionId; 8] = [
+        ExtensionId::Logic,
+        ExtensionId::Fluid,
+        ExtensionId::Stats,
+        ExtensionId::TechTree,
+        ExtensionId::Power,
+        ExtensionId::Spatial,
+        ExtensionId::Crafting,
+        ExtensionId::Spoilage,
+    ];
+
+    /// Mask of every extension this build knows.
+    pub const KNOWN_MASK: u32 = (1 << Self::ALL.len()) - 1;
+
+    /// This extension's bit in an extension mask.
+    pub fn bit(self) -> u32 {
+        1 << self as u8
+    }
+}
+
+impl Engine {
+    /// Enable `extension`. Enabling an extension that is already enabled
+    /// does nothing.
+    pub fn enable_extension(&mut self, extension: ExtensionId) {
+        if !self.is_extension_enabled(extension) {
+            self.extensions |= extension.bit();
+            self.dirty.mark_partition(DirtyTracker::PARTITION_GRAPH);
+        }
+    }
+
+    /// Whether `extension` is enabled.
+    pub fn is_extension_enabled(&self, extension: ExtensionId) -> bool {
+        self.extensions & extension.bit() != 0
+    }
+
+    /// The enabled extensions, in bit order.
+    pub fn enabled_extensions(&self) -> Vec<ExtensionId> {
+        ExtensionId::ALL
+            .into_iter()
+            .filter(|&extension| self.is_extension_enabled(extension))
+            .collect()
+    }
+
+    /// The enabled extensions as a bit mask of [`ExtensionId::bit`]s.
+    pub fn extension_mask(&self) -> u32 {
+        self.extensions
+    }
+
+    /// The enabled set's contribution to the state hash: zero while no
+    /// extension is enabled.
+    pub(crate) fn extension_hash(&self) -> u64 {
+        if self.extensions == 0 {
+            return 0;
+        }
+        let mut h = StateHash::new();
+        h.write_u32(self.extensions);
+        h.finish()
+    }
+}
+
+/// Reject a snapshot's extension mask if it has bits this build does not
+/// know.
+pub(crate) fn check_extension_mask(mask: u32) -> Result<u32, DeserializeError> {
+    match mask & !ExtensionId::KNOWN_MASK {
+        0 => Ok(mask),
+        unknown => Err(DeserializeError::UnknownExtensions(unknown)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixed::Fixed64;
+    use crate::processor::Processor;
+    use crate::sim::SimulationStrategy;
+    use crate::test_utils::{self, gear, iron};
+
+    /// Source, smelter and sink joined by a belt and a flow edge.
+    fn factory() -> Engine {
+        let mut engine = Engine::new(SimulationStrategy::Tick);
+        let src = test_utils::add_node(&mut engine, test_utils::make_source(iron(), 2.0), 50, 50);
+        let smelter = test_utils::add_node(
+            &mut engine,
+            test_utils::make_recipe(vec![(iron(), 2)], vec![(gear(), 1)], 3),
+            50,
+            50,
+        );
+        let sink = test_utils::add_node(&mut engine, Processor::Passthrough, 50, 50);
+        test_utils::connect(
+            &mut engine,
+            src,
+            smelter,
+            test_utils::make_item_transport(4),
+        );
+        test_utils::connect(
+            &mut engine,
+            smelter,
+            sink,
+            test_utils::make_flow_transport(1.0),
+        );
+        engine
+    }
+
+    #[test]
+    fn untouched_extensions_hash_like_a_build_without_them() {
+        let mut engine = factory();
+        for _ in 0..40 {
+            engine.step();
+        }
+        assert!(engine.enabled_extensions().is_empty());
+        // The hash this factory had before extension flags existed.
+        assert_eq!(engine.state_hash(), 0x30da_671f_3ad5_feef);
+        assert_eq!(engine.state_hash_breakdown().modules, 0);
+    }
+
+    #[test]
+    fn enabled_but_unused_extension_changes_the_hash() {
+        let mut plain = factory();
+        let mut with_stats = factory();
+        with_stats.enable_extension(ExtensionId::Stats);
+        for _ in 0..10 {
+            plain.step();
+            with_stats.step();
+        }
+        assert_ne!(plain.state_hash(), with_stats.state_hash());
+
+        let (a, b) = (
+            plain.state_hash_breakdown(),
+            with_stats.state_hash_breakdown(),
+        );
+        assert_ne!(a.modules, b.modules);
+        assert_eq!(
+            (a.graph, a.inventories, a.transports, a.crafting),
+            (b.graph, b.inventories, b.transports, b.crafting)
+        );
+    }
+
+    #[test]
+    fn first_use_enables_extensions() {
+        let mut engine = factory();
+        let node = engine.graph.nodes().next().unwrap().0;
+
+        engine.create_crafting_queue(node, Fixed64::ONE).unwrap();
+        engine.set_item_decay(iron(), 100, None);
+        assert_eq!(
+            engine.enabled_extensions(),
+            [ExtensionId::Crafting, ExtensionId::Spoilage]
+        );
+
+        engine.enable_extension(ExtensionId::Power);
+        engine.enable_extension(ExtensionId::Power);
+        assert_eq!(
+            engine.extension_mask(),
+            ExtensionId::Crafting.bit() | ExtensionId::Spoilage.bit() | ExtensionId::Power.bit()
+        );
+    }
+
+    #[test]
+    fn enabled_extensions_survive_serialization() {
+        let mut engine = factory();
+        engine.enable_extension(ExtensionId::Stats);
+        engine.enable_extension(ExtensionId::Logic);
+        engine.step();
+
+        let restored = Engine::deserialize(&engine.serialize().unwrap()).unwrap();
+        assert_eq!(restored.extension_mask(), engine.extension_mask());
+        assert_eq!(restored.state_hash(), engine.state_hash());
+
+        let snapshot = engine.serialize_partitioned().unwrap();
+        let restored = Engine::deserialize_partitioned(&snapshot).unwrap();
+        assert_eq!(restored.extension_mask(), engine.extension_mask());
+        assert_eq!(restored.state_hash(), engine.state_hash());
+    }
+
+    #[test]
+    fn unknown_enabled_extension_fails_to_load() {
+        let mut engine = factory();
+        // An extension from a newer build.
+        engine.extensions = 1 << 20 | ExtensionId::Stats.bit();
+        let data = engine.serialize().unwrap();
+        assert!(matches!(
+            Engine::deserialize(&data),
+            Err(DeserializeError::UnknownExtensions(mask)) if mask == 1 << 20
+        ));
+        let snapshot = engine.serialize_partitioned().unwrap();
+        assert!(matches!(
+            Engine::deserialize_partitioned(&snapshot),
+            Err(DeserializeError::UnknownExtensions(_))
+        ));
+    }
+
+    #[test]
+    fn extension_bits_are_distinct_and_known() {
+        let mut mask = 0;
+        for extension in ExtensionId::ALL {
+            assert_eq!(mask & extension.bit(), 0);
+            mask |= extension.bit();
+        }
+        assert_eq!(mask, ExtensionId::KNOWN_MASK);
+    }
+}
@@ -0,0 +1,253 @@
+//! Scenario inventory fills.
+//!
+//! Scenario and test setup often starts nodes with stocked inventories.
+//! [`Engine::fill_inventory`] replaces the contents of one of a node's
+//! inventories with a list of items, and [`Engine::fill_inventories`] does
+//! the same for many inventories at once.
+//!
+//! Fills are all-or-nothing. Items go into the slots the engine uses for
+//! that inventory -- input slots of the input inventory, output slots of the
+//! output inventory -- respecting slot filters and capacities. Each item is
+//! placed in the slots filtered to it first, then in unfiltered slots, in
+//! slot order. If any item does not fit, nothing changes.
+//!
+//! A fill is setup, not simulation: it emits no events, and the filled
+//! items carry no properties or spoilage ages.
+
+use crate::capacity::InventorySide;
+use crate::engine::Engine;
+use crate::id::{ItemTypeId, NodeId};
+use crate::item::{Inventory, InventorySlot};
+
+/// One inventory to fill with [`Engine::fill_inventories`].
+#[derive(Debug, Clone, Copy)]
+pub struct InventoryFill<'a> {
+    pub node: NodeId,
+    pub side: InventorySide,
+    /// The new contents. Repeated item types add up; zero quantities are
+    /// ignored.
+    pub items: &'a [(ItemTypeId, u32)],
+}
+
+/// Why a fill was rejected.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum InventoryFillError {
+    #[error("node {0:?} has no {1:?} inventory")]
+    NoInventory(NodeId, InventorySide),
+    #[error(
+        "{overflow} of item {item_type:?} do not fit in the {side:?} inventory of node {node:?}"
+    )]
+    DoesNotFit {
+        node: NodeId,
+        side: InventorySide,
+        item_type: ItemTypeId,
+        overflow: u32,
+    },
+}
+
+/// `inventory` with its slots emptied and `items` placed in the slots of
+/// `side`, or the item type that did not fit and by how much.
+fn filled(
+    inventory: &Inventory,
+    side: InventorySide,
+    items: &[(ItemTypeId, u32)],
+) -> Result<Inventory, (ItemTypeId, u32)> {
+    let mut inventory = inventory.clone();
+    for slot in inventory
+        .input_slots
+        .iter_mut()
+        .chain(&mut inventory.output_slots)
+    {
+        slot.stacks.clear();
+    }
+    let slots: &mut [InventorySlot] = match side {
+        InventorySide::Input => &mut inventory.input_slots,
+        InventorySide::Output => &mut inventory.output_slots,
+    };
+    for &(item_type, quantity) in items {
+        let mut remaining = quantity;
+        for filtered_pass in [true, false] {
+            for slot in slots.iter_mut() {
+                if remaining == 0 {
+                    break;
+                }
+                if slot.filter.is_some() == filtered_pass {
+                    remaining = slot.add(item_type, remaining);
+                }
+            }
+        }
+        if remaining > 0 {
+            return Err((item_type, remaining));
+        }
+    }
+    Ok(inventory)
+}
+
+impl Engine {
+    /// Replace the contents of one of `node`'s inventories with `items`.
+    ///
+    /// Fails without changing anything if the node has no such inventory or
+    /// the items do not all fit. See the [module documentation](crate::fill)
+    /// for how items are placed.
+    pub fn fill_inventory(
+        &mut self,
+        node: NodeId,
+        side: InventorySide,
+        items: &[(ItemTypeId, u32)],
+    ) -> Result<(), InventoryFillError> {
+        self.fill_inventories(&[InventoryFill { node, side, items }])
+    }
+
+    /// Apply several fills at once. Either every fill succeeds or nothing
+    /// changes, and the error names the first fill that failed. When two
+    /// fills target the same inventory, the later one wins.
+    pub fn fill_inventories(
+        &mut self,
+        fills: &[InventoryFill<'_>],
+    ) -> Result<(), InventoryFillError> {
+        let mut staged = Vec::with_capacity(fills.len());
+        for fill in fills {
+            let inventory = match fill.side {
+                InventorySide::Input => self.inputs.get(fill.node),
+                InventorySide::Output => self.outputs.get(fill.node),
+            }
+            .ok_or(InventoryFillError::NoInventory(fill.node, fill.side))?;
+            let inventory =
+                filled(inventory, fill.side, fill.items).map_err(|(item_type, overflow)| {
+                    InventoryFillError::DoesNotFit {
+                        node: fill.node,
+                        side: fill.side,
+                        item_type,
+                        overflow,
+                    }
+                })?;
+            staged.push((fill.node, fill.side, inventory));
+        }
+
+        for (node, side, inventory) in staged {
+            match side {
+                InventorySide::Input => self.inputs.insert(node, inventory),
+                InventorySide::Output => self.outputs.insert(node, inventory),
+            };
+            self.hash_dirty_nodes.push(node);
+            self.dirty.mark_node(node);
+        }
+        if !fills.is_empty() {
+            self.dirty
+                .mark_partition(crate::dirty::DirtyTracker::PARTITION_INVENTORIES);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::processor::Processor;
+    use crate::sim::SimulationStrategy;
+    use crate::test_utils::{self, copper, iron};
+
+    #[test]
+    fn fill_replaces_contents_up_to_an_exact_fit() {
+        let mut engine = Engine::new(SimulationStrategy::Tick);
+        let node = test_utils::add_node(&mut engine, Processor::Passthrough, 10, 10);
+        let _ = engine
+            .get_input_inventory_mut(node)
+            .unwrap()
+            .insert(iron(), 3);
+
+        engine
+            .fill_inventory(node, InventorySide::Input, &[(copper(), 6), (iron(), 4)])
+            .unwrap();
+        let input = engine.get_input_inventory(node).unwrap();
+        assert_eq!(input.count_of(copper()), 6);
+        assert_eq!(input.count_of(iron()), 4);
+        assert_eq!(input.total(), 10);
+
+        engine
+            .fill_inventory(node, InventorySide::Output, &[(iron(), 10)])
+            .unwrap();
+        assert_eq!(test_utils::output_quantity(&engine, node, iron()), 10);
+        assert_eq!(engine.event_bus.pending_event_count(), 0);
+    }
+
+    #[test]
+    fn rejected_fills_change_nothing() {
+        let mut engine = Engine::new(SimulationStrategy::Tick);
+        let a = test_utils::add_node(&mut engine, Processor::Passthrough, 10, 10);
+        let b = test_utils::add_node(&mut engine, Processor::Passthrough, 10, 10);
+        let _ = engine.get_input_inventory_mut(a).unwrap().insert(iron(), 5);
+
+        let err = engine
+            .fill_inventory(a, InventorySide::Input, &[(copper(), 8), (iron(), 3)])
+            .unwrap_err();
+        assert_eq!(
+            err,
+            InventoryFillError::DoesNotFit {
+                node: a,
+                side: InventorySide::Input,
+                item_type: iron(),
+                overflow: 1,
+            }
+        );
+
+        // The first fill is valid, but the second one fails, so neither applies.
+        let err = engine
+            .fill_inventories(&[
+                InventoryFill {
+                    node: b,
+                    side: InventorySide::Input,
+                    items: &[(copper(), 2)],
+                },
+                InventoryFill {
+                    node: a,
+                    side: InventorySide::Output,
+                    items: &[(iron(), 11)],
+                },
+            ])
+            .unwrap_err();
+        assert!(matches!(err, InventoryFillError::DoesNotFit { node, .. } if node == a));
+
+        assert_eq!(test_utils::input_quantity(&engine, a, iron()), 5);
+        assert_eq!(test_utils::input_quantity(&engine, a, copper()), 0);
+        assert_eq!(test_utils::input_quantity(&engine, b, copper()), 0);
+
+        let _ = engine.clear_output_inventory(b);
+        assert_eq!(
+            engine.fill_inventory(b, InventorySide::Output, &[]),
+            Err(InventoryFillError::NoInventory(b, InventorySide::Output))
+        );
+    }
+
+    #[test]
+    fn fills_respect_slot_filters() {
+        let mut engine = Engine::new(SimulationStrategy::Tick);
+        let node = test_utils::add_node(&mut engine, Processor::Passthrough, 10, 10);
+        // An unfiltered slot ahead of a slot reserved for iron.
+        let mut inventory = Inventory::new(0, 1, 10);
+        inventory.input_slots = vec![InventorySlot::new(10), InventorySlot::filtered(10, iron())];
+        engine.set_input_inventory(node, inventory);
+
+        // Iron goes to its own slot first, leaving the open slot for copper.
+        engine
+            .fill_inventory(node, InventorySide::Input, &[(iron(), 10), (copper(), 10)])
+            .unwrap();
+        let input = engine.get_input_inventory(node).unwrap();
+        assert_eq!(input.input_slots[0].quantity(copper()), 10);
+        assert_eq!(input.input_slots[1].quantity(iron()), 10);
+
+        // Copper never lands in the iron slot.
+        assert_eq!(
+            engine.fill_inventory(node, InventorySide::Input, &[(copper(), 11)]),
+            Err(InventoryFillError::DoesNotFit {
+                node,
+                side: InventorySide::Input,
+                item_type: copper(),
+                overflow: 1,
+            })
+        );
+        engine
+            .fill_inventory(node, InventorySide::Input, &[(iron(), 20)])
+            .unwrap();
+    }
+}
@@ -45,6 +45,122 @@ pub fn checked_div_64(a: Fixed64, b: Fixed64) -> Option<Fixed64> {
     a.checked_div(b)
 }
 
+/// Raise `base` to the integer power `exp`, saturating at `Fixed64::MIN` /
+/// `Fixed64::MAX` instead of overflowing. Uses exponentiation by squaring,
+/// so it takes O(log exp) multiplications and rounds fewer times than
+/// repeated multiplication. `exp == 0` yields 1.
+pub fn saturating_powi_64(base: Fixed64, mut exp: u32) -> Fixed64 {
+    let mut result = Fixed64::ONE;
+    let mut square = base;
+    while exp > 0 {
+        if exp & 1 == 1 {
+            result = result.saturating_mul(square);
+        }
+        exp >>= 1;
+        if exp > 0 {
+            square = square.saturating_mul(square);
+        }
+    }
+    result
+}
+
+/// Reciprocal `1 / v`, saturating instead of panicking. Values too close to
+/// zero (including zero itself) saturate to `Fixed64::MAX`, or
+/// `Fixed64::MIN` for negative inputs.
+pub fn saturating_recip_64(v: Fixed64) -> Fixed64 {
+    if v == Fixed64::ZERO {
+        Fixed64::MAX
+    } else {
+        v.saturating_recip()
+    }
+}
+
+/// How [`FixedDecimal`] resolves digits past the requested precision.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RoundingMode {
+    /// Round to nearest; ties away from zero (`0.125` -> `0.13`, `-0.125` -> `-0.13`).
+    HalfUp,
+    /// Round to nearest; ties to the even digit (`0.125` -> `0.12`).
+    HalfEven,
+    /// Round toward negative infinity.
+    Floor,
+    /// Round toward positive infinity.
+    Ceil,
+}
+
+/// Largest number of decimal places [`FixedDecimal`] honours. Requests for
+/// more are clamped; 19 places already resolve a Q32.32 value to within
+/// 10^-19, far below its 2^-32 step.
+pub const MAX_DECIMALS: u32 = 19;
+
+/// Decimal rounding and formatting for [`Fixed64`], computed exactly from the
+/// raw bits so every host displays the same digits for the same value.
+pub trait FixedDecimal: Sized {
+    /// The value nearest to `self` rounded to `decimals` places under `mode`.
+    /// Decimal fractions are generally not exact in binary, so the result is
+    /// the closest Fixed64 to the rounded decimal; saturates at the range.
+    fn round_dp(self, decimals: u32, mode: RoundingMode) -> Self;
+
+    /// `self` as a plain decimal string with exactly `decimals` places,
+    /// rounded half up. Locale independent: `-` sign, `.` separator, no
+    /// grouping.
+    fn to_decimal_string(self, decimals: u32) -> String {
+        self.to_decimal_string_with(decimals, RoundingMode::HalfUp)
+    }
+
+    /// As [`to_decimal_string`](FixedDecimal::to_decimal_string) with an
+    /// explicit rounding mode.
+    fn to_decimal_string_with(self, decimals: u32, mode: RoundingMode) -> String;
+}
+
+impl FixedDecimal for Fixed64 {
+    fn round_dp(self, decimals: u32, mode: RoundingMode) -> Self {
+        let decimals = decimals.min(MAX_DECIMALS);
+        let (negative, units) = decimal_units(self, decimals, mode);
+        // Back to Q32.32, rounding the binary fraction to nearest.
+        let scale = 10u128.pow(decimals);
+        let magnitude = ((units << 32) + scale / 2) / scale;
+        if negative {
+            let limit = i64::MIN.unsigned_abs() as u128;
+            Fixed64::from_bits((magnitude.min(limit) as i128).wrapping_neg() as i64)
+        } else {
+            Fixed64::from_bits(magnitude.min(i64::MAX as u128) as i64)
+        }
+    }
+
+    fn to_decimal_string_with(self, decimals: u32, mode: RoundingMode) -> String {
+        let decimals = decimals.min(MAX_DECIMALS);
+        let (negative, units) = decimal_units(self, decimals, mode);
+        let scale = 10u128.pow(decimals);
+        let sign = if negative && units != 0 { "-" } else { "" };
+        let whole = units / scale;
+        if decimals == 0 {
+            format!("{sign}{whole}")
+        } else {
+            let frac = units % scale;
+            format!("{sign}{whole}.{frac:0width$}", width = decimals as usize)
+        }
+    }
+}
+
+/// Sign and magnitude of `v` rounded to `decimals` places, in units of
+/// 10^-decimals. `decimals` must not exceed [`MAX_DECIMALS`], which keeps
+/// the intermediate product inside a `u128`.
+fn decimal_units(v: Fixed64, decimals: u32, mode: RoundingMode) -> (bool, u128) {
+    let negative = v.to_bits() < 0;
+    let scaled = v.to_bits().unsigned_abs() as u128 * 10u128.pow(decimals);
+    let units = scaled >> 32;
+    let rest = scaled & 0xFFFF_FFFF;
+    let half = 1u128 << 31;
+    let round_up = match mode {
+        RoundingMode::HalfUp => rest >= half,
+        RoundingMode::HalfEven => rest > half || (rest == half && units % 2 == 1),
+        RoundingMode::Floor => negative && rest != 0,
+        RoundingMode::Ceil => !negative && rest != 0,
+    };
+    (negative, units + round_up as u128)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -128,4 +244,121 @@ mod tests {
         assert!(result.is_some());
         assert_eq!(fixed64_to_f64(result.unwrap()), 3.0);
     }
+
+    fn iterative_pow(base: Fixed64, exp: u32) -> Fixed64 {
+        (0..exp).fold(Fixed64::ONE, |acc, _| acc.saturating_mul(base))
+    }
+
+    #[test]
+    fn saturating_powi_matches_iteration_for_small_exponents() {
+        for base in [0.0, 1.0, 1.5, 2.0, -2.0, 0.5, -0.25, 3.0] {
+            let base = f64_to_fixed64(base);
+            for exp in 0..12 {
+                assert_eq!(
+                    saturating_powi_64(base, exp),
+                    iterative_pow(base, exp),
+                    "{base}^{exp}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn saturating_powi_saturates_large_exponents() {
+        let two = f64_to_fixed64(2.0);
+        assert_eq!(saturating_powi_64(two, 30), f64_to_fixed64(1073741824.0));
+        assert_eq!(saturating_powi_64(two, 31), Fixed64::MAX);
+        assert_eq!(saturating_powi_64(two, u32::MAX), Fixed64::MAX);
+        assert_eq!(saturating_powi_64(-two, 1001), Fixed64::MIN);
+        assert_eq!(saturating_powi_64(-two, 1000), Fixed64::MAX);
+        assert_eq!(saturating_powi_64(f64_to_fixed64(0.5), 1000), Fixed64::ZERO);
+    }
+
+    #[test]
+    fn saturating_recip_handles_zero_and_tiny_values() {
+        assert_eq!(
+            saturating_recip_64(f64_to_fixed64(4.0)),
+            f64_to_fixed64(0.25)
+        );
+        assert_eq!(
+            saturating_recip_64(f64_to_fixed64(-0.5)),
+            f64_to_fixed64(-2.0)
+        );
+        assert_eq!(saturating_recip_64(Fixed64::ZERO), Fixed64::MAX);
+        assert_eq!(saturating_recip_64(Fixed64::DELTA), Fixed64::MAX);
+        assert_eq!(saturating_recip_64(-Fixed64::DELTA), Fixed64::MIN);
+    }
+
+    fn strings(v: Fixed64, decimals: u32) -> [String; 4] {
+        [
+            RoundingMode::HalfUp,
+            RoundingMode::HalfEven,
+            RoundingMode::Floor,
+            RoundingMode::Ceil,
+        ]
+        .map(|mode| v.to_decimal_string_with(decimals, mode))
+    }
+
+    #[test]
+    fn decimal_string_ties_follow_the_mode() {
+        let tie = Fixed64::from_num(0.125);
+        assert_eq!(strings(tie, 2), ["0.13", "0.12", "0.12", "0.13"]);
+        assert_eq!(strings(-tie, 2), ["-0.13", "-0.12", "-0.13", "-0.12"]);
+        assert_eq!(
+            strings(Fixed64::from_num(0.375), 2),
+            ["0.38", "0.38", "0.37", "0.38"]
+        );
+    }
+
+    #[test]
+    fn decimal_string_of_inexact_half_cent() {
+        // 0.005 has no exact binary form; the nearest Q32.32 value sits just
+        // below it, so only Ceil reaches 0.01.
+        let v = Fixed64::from_num(0.005);
+        assert_eq!(v.to_decimal_string(10), "0.0049999999");
+        assert_eq!(strings(v, 2), ["0.00", "0.00", "0.00", "0.01"]);
+        assert_eq!(strings(-v, 2), ["0.00", "0.00", "-0.01", "0.00"]);
+    }
+
+    #[test]
+    fn decimal_string_pins_awkward_values() {
+        assert_eq!(
+            (Fixed64::from_num(7) / Fixed64::from_num(3)).to_decimal_string(3),
+            "2.333"
+        );
+        assert_eq!(Fixed64::from_num(-2.5).to_decimal_string(0), "-3");
+        assert_eq!(
+            Fixed64::from_num(-2.5).to_decimal_string_with(0, RoundingMode::HalfEven),
+            "-2"
+        );
+        assert_eq!(Fixed64::ZERO.to_decimal_string(2), "0.00");
+        assert_eq!(Fixed64::MAX.to_decimal_string(2), "2147483648.00");
+        assert_eq!(
+            Fixed64::MAX.to_decimal_string_with(19, RoundingMode::Floor),
+            "2147483647.9999999997671693563"
+        );
+        assert_eq!(Fixed64::MIN.to_decimal_string(4), "-2147483648.0000");
+        assert_eq!(
+            Fixed64::DELTA.to_decimal_string(40),
+            "0.0000000002328306437"
+        );
+    }
+
+    #[test]
+    fn round_dp_saturates_and_snaps_to_nearest_fixed() {
+        assert_eq!(Fixed64::MAX.round_dp(0, RoundingMode::HalfUp), Fixed64::MAX);
+        assert_eq!(Fixed64::MIN.round_dp(0, RoundingMode::Floor), Fixed64::MIN);
+        let third = Fixed64::ONE / Fixed64::from_num(3);
+        let rounded = third.round_dp(2, RoundingMode::HalfUp);
+        assert_eq!(rounded.to_decimal_string(2), "0.33");
+        assert_eq!(rounded, Fixed64::from_num(0.33));
+        assert_eq!(
+            Fixed64::from_num(2.5).round_dp(0, RoundingMode::HalfEven),
+            Fixed64::from_num(2)
+        );
+        assert_eq!(
+            Fixed64::from_num(-2.1).round_dp(0, RoundingMode::Ceil),
+            Fixed64::from_num(-2)
+        );
+    }
 }
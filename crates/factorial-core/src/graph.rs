@@ -24,7 +24,7 @@ pub enum GraphError {
 
 /// Adjacency lists for a single node, tracking incoming and outgoing edges.
 #[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
-struct NodeAdjacency {
+pub(crate) struct NodeAdjacency {
     /// Edges whose destination is this node.
     inputs: Vec<EdgeId>,
     /// Edges whose source is this node.
@@ -36,6 +36,63 @@ struct NodeAdjacency {
 pub struct NodeData {
     /// The building template this node was created from.
     pub building_type: BuildingTypeId,
+    /// Behaviour flags set when the node was added.
+    #[serde(default)]
+    pub flags: NodeFlags,
+}
+
+/// Per-node behaviour flags, combined with `|`.
+///
+/// Flags change how the engine and its attachments observe a node, never how
+/// it simulates: a flagged node keeps its processor, inventories and
+/// transports, and contributes to the state hash exactly as an unflagged one.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct NodeFlags(u32);
+
+impl NodeFlags {
+    /// No flags.
+    pub const EMPTY: Self = Self(0);
+    /// The node emits no events: nothing that names it reaches the event
+    /// buffers, subscribers or the event log.
+    pub const SILENT: Self = Self(1);
+    /// Statistics trackers ignore the node.
+    pub const NO_STATS: Self = Self(1 << 1);
+    /// Every defined flag.
+    pub const ALL: Self = Self(Self::SILENT.0 | Self::NO_STATS.0);
+
+    /// The raw bits.
+    pub const fn bits(self) -> u32 {
+        self.0
+    }
+
+    /// Flags from raw bits, dropping any bit no flag uses.
+    pub const fn from_bits_truncate(bits: u32) -> Self {
+        Self(bits & Self::ALL.0)
+    }
+
+    /// Whether every flag in `other` is set.
+    pub const fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Whether no flag is set.
+    pub const fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+}
+
+impl std::ops::BitOr for NodeFlags {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
+}
+
+impl std::ops::BitOrAssign for NodeFlags {
+    fn bitor_assign(&mut self, rhs: Self) {
+        self.0 |= rhs.0;
+    }
 }
 
 /// Per-edge data stored in the production graph.
@@ -50,6 +107,22 @@ pub struct EdgeData {
     pub item_filter: Option<ItemTypeId>,
 }
 
+/// Simulation status of a node or edge.
+///
+/// A `Ghost` is a planned building or connection: it exists in the graph,
+/// shows up in topology queries and keeps its configuration, but its
+/// processor and transport do not run during `step`. Promote it to `Active`
+/// with [`Engine::set_node_status`](crate::engine::Engine::set_node_status)
+/// or [`Engine::set_edge_status`](crate::engine::Engine::set_edge_status).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum NodeStatus {
+    /// Planned but not yet built; skipped by the simulation.
+    Ghost,
+    /// Built and simulating normally.
+    #[default]
+    Active,
+}
+
 // ---------------------------------------------------------------------------
 // Queued mutations
 // ---------------------------------------------------------------------------
@@ -59,6 +132,8 @@ pub struct EdgeData {
 enum Mutation {
     AddNode {
         building_type: BuildingTypeId,
+        flags: NodeFlags,
+        ghost: bool,
         pending_id: PendingNodeId,
     },
     RemoveNode {
@@ -67,6 +142,7 @@ enum Mutation {
     Connect {
         from: NodeId,
         to: NodeId,
+        ghost: bool,
         pending_id: PendingEdgeId,
     },
     ConnectFiltered {
@@ -81,12 +157,54 @@ enum Mutation {
 }
 
 /// Result of applying queued mutations. Maps pending IDs to real IDs.
+///
+/// Entries are in the order the mutations were applied: by ascending lane
+/// (see [`ProductionGraph::queue_add_node_in_lane`]), then in queue order
+/// within a lane. With everything in the default lane 0, that is queue
+/// order, which is also ascending pending id.
 #[derive(Debug, Default)]
 pub struct MutationResult {
-    /// Maps each `PendingNodeId` counter to the real `NodeId` it was assigned.
+    /// Maps each `PendingNodeId` counter to the real `NodeId` it was assigned,
+    /// in apply order.
     pub added_nodes: Vec<(PendingNodeId, NodeId)>,
-    /// Maps each `PendingEdgeId` counter to the real `EdgeId` it was assigned.
+    /// The lane each entry of `added_nodes` was queued in, by index.
+    pub added_node_lanes: Vec<u8>,
+    /// Maps each `PendingEdgeId` counter to the real `EdgeId` it was assigned,
+    /// in apply order.
     pub added_edges: Vec<(PendingEdgeId, EdgeId)>,
+    /// The lane each entry of `added_edges` was queued in, by index.
+    pub added_edge_lanes: Vec<u8>,
+    /// Nodes that were removed, in the order they were removed.
+    pub removed_nodes: Vec<NodeId>,
+    /// Edges that were removed, in the order they were removed. Includes
+    /// edges removed implicitly because one of their endpoints was removed.
+    pub removed_edges: Vec<EdgeId>,
+    /// Node additions that were refused, with the reason, in apply order.
+    /// A rejected pending id never resolves to a real node.
+    pub rejected: Vec<(PendingNodeId, MutationRejectReason)>,
+    /// Edge additions that were refused, with the reason, in apply order.
+    /// A rejected pending id never resolves to a real edge.
+    pub rejected_edges: Vec<(PendingEdgeId, MutationRejectReason)>,
+    /// The added nodes that were queued as ghosts, in queue order.
+    pub ghost_nodes: Vec<NodeId>,
+    /// The added edges that were queued as ghosts, in queue order.
+    pub ghost_edges: Vec<EdgeId>,
+}
+
+/// Why a queued mutation was refused at apply time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[repr(u32)]
+pub enum MutationRejectReason {
+    /// The building type is gated behind research that is not complete.
+    /// Only reported when the engine is in strict mode.
+    BuildingLocked = 1,
+    /// An endpoint of a queued connection does not exist, either because
+    /// it was never added or because an earlier mutation in the same batch
+    /// removed it.
+    MissingEndpoint = 2,
+    /// A real connection was queued to or from a ghost node. Connect
+    /// ghosts with [`ProductionGraph::queue_connect_ghost`].
+    GhostEndpoint = 3,
 }
 
 impl MutationResult {
@@ -107,6 +225,47 @@ impl MutationResult {
     }
 }
 
+/// Outcome of looking up a pending id with
+/// [`ProductionGraph::resolve_pending_node`] or
+/// [`ProductionGraph::resolve_pending_edge`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PendingLookup<T> {
+    /// The mutation was applied and created this id.
+    Resolved(T),
+    /// The mutation is still queued.
+    Queued,
+    /// The mutation was applied, but its batch has been evicted from the
+    /// resolution history.
+    Expired,
+    /// The pending id was never issued by this graph.
+    Unknown,
+}
+
+/// Number of mutation batches whose resolutions a new graph remembers.
+pub const DEFAULT_RESOLUTION_HISTORY: usize = 16;
+
+/// The pending-to-real id pairs of one applied batch.
+#[derive(Debug, Clone, Default)]
+struct ResolvedBatch {
+    nodes: Vec<(PendingNodeId, NodeId)>,
+    edges: Vec<(PendingEdgeId, EdgeId)>,
+}
+
+fn default_resolution_history() -> usize {
+    DEFAULT_RESOLUTION_HISTORY
+}
+
+/// Classify a pending id that is not in the resolution history.
+fn unresolved_lookup<T>(queued: bool, issued: bool) -> PendingLookup<T> {
+    if queued {
+        PendingLookup::Queued
+    } else if issued {
+        PendingLookup::Expired
+    } else {
+        PendingLookup::Unknown
+    }
+}
+
 // ---------------------------------------------------------------------------
 // ProductionGraph
 // ---------------------------------------------------------------------------
@@ -142,13 +301,25 @@ pub struct ProductionGraph {
     #[serde(skip, default = "default_dirty")]
     feedback_dirty: bool,
 
-    /// Queued mutations to be applied atomically.
+    /// Queued mutations with their lanes, in queue order.
     #[serde(skip)]
-    mutations: Vec<Mutation>,
+    mutations: Vec<(u8, Mutation)>,
     /// Counter for generating unique `PendingNodeId` values.
     next_pending_node: u64,
     /// Counter for generating unique `PendingEdgeId` values.
     next_pending_edge: u64,
+
+    /// Resolutions of the most recent batches that added nodes or edges,
+    /// oldest first. Session-only: not serialized.
+    #[serde(skip)]
+    resolutions: VecDeque<ResolvedBatch>,
+    /// Maximum number of batches kept in `resolutions`.
+    #[serde(skip, default = "default_resolution_history")]
+    resolution_history_len: usize,
+
+    /// Bumped whenever the node or edge set changes. Session-only.
+    #[serde(skip)]
+    topology_generation: u64,
 }
 
 /// Default for dirty flag on deserialize -- always `true` so topo cache is recomputed.
@@ -170,6 +341,9 @@ impl Clone for ProductionGraph {
             mutations: Vec::new(), // Don't clone queued mutations.
             next_pending_node: self.next_pending_node,
             next_pending_edge: self.next_pending_edge,
+            resolutions: self.resolutions.clone(),
+            resolution_history_len: self.resolution_history_len,
+            topology_generation: self.topology_generation,
         }
     }
 }
@@ -195,6 +369,9 @@ impl ProductionGraph {
             mutations: Vec::new(),
             next_pending_node: 0,
             next_pending_edge: 0,
+            resolutions: VecDeque::new(),
+            resolution_history_len: DEFAULT_RESOLUTION_HISTORY,
+            topology_generation: 0,
         }
     }
 
@@ -209,15 +386,19 @@ impl ProductionGraph {
     // -----------------------------------------------------------------------
 
     /// Add a node immediately. Returns the assigned `NodeId`.
-    fn add_node_immediate(&mut self, building_type: BuildingTypeId) -> NodeId {
-        let node_id = self.nodes.insert(NodeData { building_type });
+    fn add_node_immediate(&mut self, building_type: BuildingTypeId, flags: NodeFlags) -> NodeId {
+        let node_id = self.nodes.insert(NodeData {
+            building_type,
+            flags,
+        });
         self.adjacency.insert(node_id, NodeAdjacency::default());
         self.invalidate_caches();
         node_id
     }
 
     /// Remove a node immediately. Also removes all connected edges.
-    fn remove_node_immediate(&mut self, node: NodeId) {
+    /// Returns the IDs of the edges that were removed along with the node.
+    fn remove_node_immediate(&mut self, node: NodeId) -> Vec<EdgeId> {
         // Collect edges to remove (both inputs and outputs).
         let edges_to_remove: Vec<EdgeId> = if let Some(adj) = self.adjacency.get(node) {
             adj.inputs
@@ -226,17 +407,20 @@ impl ProductionGraph {
                 .copied()
                 .collect()
         } else {
-            return;
+            return Vec::new();
         };
 
-        // Remove each connected edge.
-        for edge_id in edges_to_remove {
-            self.disconnect_immediate(edge_id);
-        }
+        // Remove each connected edge. A self-loop appears in both lists, so
+        // only keep the edges that were actually removed.
+        let removed_edges: Vec<EdgeId> = edges_to_remove
+            .into_iter()
+            .filter(|&edge_id| self.disconnect_immediate(edge_id))
+            .collect();
 
         self.nodes.remove(node);
         self.adjacency.remove(node);
         self.invalidate_caches();
+        removed_edges
     }
 
     /// Connect two nodes immediately. Returns the assigned `EdgeId`.
@@ -268,8 +452,8 @@ impl ProductionGraph {
         edge_id
     }
 
-    /// Disconnect (remove) an edge immediately.
-    fn disconnect_immediate(&mut self, edge: EdgeId) {
+    /// Disconnect (remove) an edge immediately. Returns true if the edge existed.
+    fn disconnect_immediate(&mut self, edge: EdgeId) -> bool {
         if let Some(edge_data) = self.edges.remove(edge) {
             // Remove from source's output list.
             if let Some(adj) = self.adjacency.get_mut(edge_data.from) {
@@ -280,6 +464,9 @@ impl ProductionGraph {
                 adj.inputs.retain(|&e| e != edge);
             }
             self.invalidate_caches();
+            true
+        } else {
+            false
         }
     }
 
@@ -302,22 +489,107 @@ impl ProductionGraph {
     /// let node_id = result.resolve_node(pending).unwrap();
     /// ```
     pub fn queue_add_node(&mut self, building_type: BuildingTypeId) -> PendingNodeId {
+        self.queue_add_node_with_flags(building_type, NodeFlags::EMPTY)
+    }
+
+    /// Queue a node addition in mutation lane `lane`.
+    ///
+    /// [`apply_mutations`](Self::apply_mutations) applies lanes in ascending
+    /// order and, within a lane, in queue order. Putting player actions in
+    /// lane 0 and automation in lane 1, say, makes their relative order
+    /// independent of which system happened to queue first, so peers that
+    /// interleave their submissions differently still converge. Each queue
+    /// method has an `_in_lane` variant; the plain ones use lane 0.
+    pub fn queue_add_node_in_lane(
+        &mut self,
+        lane: u8,
+        building_type: BuildingTypeId,
+    ) -> PendingNodeId {
+        self.queue_add_node_inner(lane, building_type, NodeFlags::EMPTY, false)
+    }
+
+    /// Queue a node addition with behaviour [`NodeFlags`], such as a
+    /// decorative building that should neither emit events nor appear in
+    /// statistics. [`NodeFlags::SILENT`] takes effect when the engine applies
+    /// the addition, through
+    /// [`Engine::apply_mutations`](crate::engine::Engine::apply_mutations) or
+    /// the next step.
+    pub fn queue_add_node_with_flags(
+        &mut self,
+        building_type: BuildingTypeId,
+        flags: NodeFlags,
+    ) -> PendingNodeId {
+        self.queue_add_node_with_flags_in_lane(0, building_type, flags)
+    }
+
+    /// Queue a node addition with [`NodeFlags`] in mutation lane `lane`; see
+    /// [`queue_add_node_in_lane`](Self::queue_add_node_in_lane).
+    pub fn queue_add_node_with_flags_in_lane(
+        &mut self,
+        lane: u8,
+        building_type: BuildingTypeId,
+        flags: NodeFlags,
+    ) -> PendingNodeId {
+        self.queue_add_node_inner(lane, building_type, flags, false)
+    }
+
+    /// Queue the addition of a ghost: a planned building that is queryable
+    /// but does not simulate until
+    /// [`Engine::materialize_ghost`](crate::engine::Engine::materialize_ghost)
+    /// turns it into a real node. Only ghost edges, queued with
+    /// [`queue_connect_ghost`](Self::queue_connect_ghost), may connect to it.
+    pub fn queue_add_ghost(&mut self, building_type: BuildingTypeId) -> PendingNodeId {
+        self.queue_add_ghost_in_lane(0, building_type)
+    }
+
+    /// Queue a ghost in mutation lane `lane`; see
+    /// [`queue_add_node_in_lane`](Self::queue_add_node_in_lane).
+    pub fn queue_add_ghost_in_lane(
+        &mut self,
+        lane: u8,
+        building_type: BuildingTypeId,
+    ) -> PendingNodeId {
+        self.queue_add_node_inner(lane, building_type, NodeFlags::EMPTY, true)
+    }
+
+    fn queue_add_node_inner(
+        &mut self,
+        lane: u8,
+        building_type: BuildingTypeId,
+        flags: NodeFlags,
+        ghost: bool,
+    ) -> PendingNodeId {
         let pending = PendingNodeId(self.next_pending_node);
         self.next_pending_node += 1;
-        self.mutations.push(Mutation::AddNode {
-            building_type,
-            pending_id: pending,
-        });
+        self.mutations.push((
+            lane,
+            Mutation::AddNode {
+                building_type,
+                flags,
+                ghost,
+                pending_id: pending,
+            },
+        ));
         pending
     }
 
     /// Queue a node for removal.
     pub fn queue_remove_node(&mut self, node: NodeId) {
-        self.mutations.push(Mutation::RemoveNode { node });
+        self.queue_remove_node_in_lane(0, node);
+    }
+
+    /// Queue a node removal in mutation lane `lane`; see
+    /// [`queue_add_node_in_lane`](Self::queue_add_node_in_lane).
+    pub fn queue_remove_node_in_lane(&mut self, lane: u8, node: NodeId) {
+        self.mutations.push((lane, Mutation::RemoveNode { node }));
     }
 
     /// Queue an edge connecting two existing nodes. Returns a `PendingEdgeId`.
     ///
+    /// If either node no longer exists when the batch is applied, including
+    /// when an earlier mutation in the batch removed it, no edge is created
+    /// and the pending id is listed in [`MutationResult::rejected_edges`].
+    ///
     /// # Examples
     ///
     /// ```
@@ -336,13 +608,50 @@ impl ProductionGraph {
     /// let edge_id = result.resolve_edge(pending_edge).unwrap();
     /// ```
     pub fn queue_connect(&mut self, from: NodeId, to: NodeId) -> PendingEdgeId {
+        self.queue_connect_inner(0, from, to, false)
+    }
+
+    /// Queue an edge in mutation lane `lane`; see
+    /// [`queue_add_node_in_lane`](Self::queue_add_node_in_lane).
+    pub fn queue_connect_in_lane(&mut self, lane: u8, from: NodeId, to: NodeId) -> PendingEdgeId {
+        self.queue_connect_inner(lane, from, to, false)
+    }
+
+    /// Queue a ghost edge: a planned connection that does not transport
+    /// until it is set active. Unlike a real edge, it may touch ghost nodes.
+    pub fn queue_connect_ghost(&mut self, from: NodeId, to: NodeId) -> PendingEdgeId {
+        self.queue_connect_ghost_in_lane(0, from, to)
+    }
+
+    /// Queue a ghost edge in mutation lane `lane`; see
+    /// [`queue_add_node_in_lane`](Self::queue_add_node_in_lane).
+    pub fn queue_connect_ghost_in_lane(
+        &mut self,
+        lane: u8,
+        from: NodeId,
+        to: NodeId,
+    ) -> PendingEdgeId {
+        self.queue_connect_inner(lane, from, to, true)
+    }
+
+    fn queue_connect_inner(
+        &mut self,
+        lane: u8,
+        from: NodeId,
+        to: NodeId,
+        ghost: bool,
+    ) -> PendingEdgeId {
         let pending = PendingEdgeId(self.next_pending_edge);
         self.next_pending_edge += 1;
-        self.mutations.push(Mutation::Connect {
-            from,
-            to,
-            pending_id: pending,
-        });
+        self.mutations.push((
+            lane,
+            Mutation::Connect {
+                from,
+                to,
+                ghost,
+                pending_id: pending,
+            },
+        ));
         pending
     }
 
@@ -353,48 +662,127 @@ impl ProductionGraph {
         from: NodeId,
         to: NodeId,
         item_filter: Option<ItemTypeId>,
+    ) -> PendingEdgeId {
+        self.queue_connect_filtered_in_lane(0, from, to, item_filter)
+    }
+
+    /// Queue a filtered edge in mutation lane `lane`; see
+    /// [`queue_add_node_in_lane`](Self::queue_add_node_in_lane).
+    pub fn queue_connect_filtered_in_lane(
+        &mut self,
+        lane: u8,
+        from: NodeId,
+        to: NodeId,
+        item_filter: Option<ItemTypeId>,
     ) -> PendingEdgeId {
         let pending = PendingEdgeId(self.next_pending_edge);
         self.next_pending_edge += 1;
-        self.mutations.push(Mutation::ConnectFiltered {
-            from,
-            to,
-            pending_id: pending,
-            item_filter,
-        });
+        self.mutations.push((
+            lane,
+            Mutation::ConnectFiltered {
+                from,
+                to,
+                pending_id: pending,
+                item_filter,
+            },
+        ));
         pending
     }
 
     /// Queue an edge for removal.
     pub fn queue_disconnect(&mut self, edge: EdgeId) {
-        self.mutations.push(Mutation::Disconnect { edge });
+        self.queue_disconnect_in_lane(0, edge);
+    }
+
+    /// Queue an edge removal in mutation lane `lane`; see
+    /// [`queue_add_node_in_lane`](Self::queue_add_node_in_lane).
+    pub fn queue_disconnect_in_lane(&mut self, lane: u8, edge: EdgeId) {
+        self.mutations.push((lane, Mutation::Disconnect { edge }));
     }
 
     /// Apply all queued mutations atomically. Returns a `MutationResult`
     /// mapping pending IDs to their real IDs.
+    ///
+    /// Mutations are applied by ascending lane and in queue order within a
+    /// lane, and the result lists entries in that same order. Pending ids
+    /// are issued in queue order, so with a single lane this is also
+    /// ascending pending-id order.
+    ///
+    /// The graph does not track ghost status; only ghosts added in the same
+    /// batch refuse real connections here. Apply through
+    /// [`Engine::apply_mutations`](crate::engine::Engine::apply_mutations) to
+    /// check against every ghost.
     pub fn apply_mutations(&mut self) -> MutationResult {
-        let mutations = std::mem::take(&mut self.mutations);
+        self.apply_mutations_checked(|_| None, |_| false)
+    }
+
+    /// Apply queued mutations, asking `check` about every node addition.
+    /// Additions for which it returns a reason are skipped and reported in
+    /// [`MutationResult::rejected`]; everything else applies as usual.
+    /// `is_ghost` tells which existing nodes are ghosts, for refusing real
+    /// connections to them.
+    pub(crate) fn apply_mutations_checked(
+        &mut self,
+        mut check: impl FnMut(BuildingTypeId) -> Option<MutationRejectReason>,
+        is_ghost: impl Fn(NodeId) -> bool,
+    ) -> MutationResult {
+        let mut mutations = std::mem::take(&mut self.mutations);
+        // Stable, so queue order is kept within a lane.
+        mutations.sort_by_key(|&(lane, _)| lane);
         let mut result = MutationResult::default();
 
-        for mutation in mutations {
+        for (lane, mutation) in mutations {
             match mutation {
                 Mutation::AddNode {
                     building_type,
+                    flags,
+                    ghost,
                     pending_id,
                 } => {
-                    let node_id = self.add_node_immediate(building_type);
+                    if let Some(reason) = check(building_type) {
+                        result.rejected.push((pending_id, reason));
+                        continue;
+                    }
+                    let node_id = self.add_node_immediate(building_type, flags);
                     result.added_nodes.push((pending_id, node_id));
+                    result.added_node_lanes.push(lane);
+                    if ghost {
+                        result.ghost_nodes.push(node_id);
+                    }
                 }
                 Mutation::RemoveNode { node } => {
-                    self.remove_node_immediate(node);
+                    if self.nodes.contains_key(node) {
+                        let removed_edges = self.remove_node_immediate(node);
+                        result.removed_edges.extend(removed_edges);
+                        result.removed_nodes.push(node);
+                    }
                 }
                 Mutation::Connect {
                     from,
                     to,
+                    ghost,
                     pending_id,
                 } => {
+                    if !self.contains_node(from) || !self.contains_node(to) {
+                        result
+                            .rejected_edges
+                            .push((pending_id, MutationRejectReason::MissingEndpoint));
+                        continue;
+                    }
+                    let ghost_endpoint =
+                        |node| is_ghost(node) || result.ghost_nodes.contains(&node);
+                    if !ghost && (ghost_endpoint(from) || ghost_endpoint(to)) {
+                        result
+                            .rejected_edges
+                            .push((pending_id, MutationRejectReason::GhostEndpoint));
+                        continue;
+                    }
                     let edge_id = self.connect_immediate(from, to);
                     result.added_edges.push((pending_id, edge_id));
+                    result.added_edge_lanes.push(lane);
+                    if ghost {
+                        result.ghost_edges.push(edge_id);
+                    }
                 }
                 Mutation::ConnectFiltered {
                     from,
@@ -402,18 +790,130 @@ impl ProductionGraph {
                     pending_id,
                     item_filter,
                 } => {
+                    if !self.contains_node(from) || !self.contains_node(to) {
+                        result
+                            .rejected_edges
+                            .push((pending_id, MutationRejectReason::MissingEndpoint));
+                        continue;
+                    }
+                    let ghost_endpoint =
+                        |node| is_ghost(node) || result.ghost_nodes.contains(&node);
+                    if ghost_endpoint(from) || ghost_endpoint(to) {
+                        result
+                            .rejected_edges
+                            .push((pending_id, MutationRejectReason::GhostEndpoint));
+                        continue;
+                    }
                     let edge_id = self.connect_immediate_filtered(from, to, item_filter);
                     result.added_edges.push((pending_id, edge_id));
+                    result.added_edge_lanes.push(lane);
                 }
                 Mutation::Disconnect { edge } => {
-                    self.disconnect_immediate(edge);
+                    if self.disconnect_immediate(edge) {
+                        result.removed_edges.push(edge);
+                    }
                 }
             }
         }
 
+        if !(result.added_nodes.is_empty()
+            && result.removed_nodes.is_empty()
+            && result.added_edges.is_empty()
+            && result.removed_edges.is_empty())
+        {
+            self.topology_generation += 1;
+        }
+        self.record_resolutions(&result);
         result
     }
 
+    /// Remember a batch's pending-to-real pairs, evicting the oldest batch
+    /// beyond the history length. Batches that added nothing are not kept.
+    fn record_resolutions(&mut self, result: &MutationResult) {
+        if self.resolution_history_len == 0
+            || (result.added_nodes.is_empty() && result.added_edges.is_empty())
+        {
+            return;
+        }
+        if self.resolutions.len() == self.resolution_history_len {
+            self.resolutions.pop_front();
+        }
+        let mut batch = ResolvedBatch {
+            nodes: result.added_nodes.clone(),
+            edges: result.added_edges.clone(),
+        };
+        // Lanes can reorder pending ids; lookups binary search.
+        batch.nodes.sort_unstable_by_key(|&(pending, _)| pending);
+        batch.edges.sort_unstable_by_key(|&(pending, _)| pending);
+        self.resolutions.push_back(batch);
+    }
+
+    /// A counter bumped every time an applied batch adds or removes nodes
+    /// or edges; batches that change nothing leave it alone. Session-only:
+    /// it restarts at 0 on load.
+    pub fn topology_generation(&self) -> u64 {
+        self.topology_generation
+    }
+
+    /// Set how many applied batches' resolutions are kept for
+    /// [`resolve_pending_node`](Self::resolve_pending_node) and
+    /// [`resolve_pending_edge`](Self::resolve_pending_edge). Only batches that
+    /// added nodes or edges count. `0` disables the history. Shrinking drops
+    /// the oldest batches.
+    pub fn set_resolution_history_len(&mut self, len: usize) {
+        self.resolution_history_len = len;
+        while self.resolutions.len() > len {
+            self.resolutions.pop_front();
+        }
+    }
+
+    /// Number of applied batches whose resolutions are kept.
+    pub fn resolution_history_len(&self) -> usize {
+        self.resolution_history_len
+    }
+
+    /// Look up the real id a pending node resolved to, after its batch was
+    /// applied.
+    pub fn resolve_pending_node(&self, pending: PendingNodeId) -> PendingLookup<NodeId> {
+        let resolved = self.resolutions.iter().find_map(|batch| {
+            batch
+                .nodes
+                .binary_search_by_key(&pending, |&(p, _)| p)
+                .ok()
+                .map(|i| batch.nodes[i].1)
+        });
+        if let Some(node) = resolved {
+            return PendingLookup::Resolved(node);
+        }
+        let queued = self.mutations.iter().any(
+            |(_, m)| matches!(m, Mutation::AddNode { pending_id, .. } if *pending_id == pending),
+        );
+        unresolved_lookup(queued, pending.0 < self.next_pending_node)
+    }
+
+    /// Look up the real id a pending edge resolved to, after its batch was
+    /// applied.
+    pub fn resolve_pending_edge(&self, pending: PendingEdgeId) -> PendingLookup<EdgeId> {
+        let resolved = self.resolutions.iter().find_map(|batch| {
+            batch
+                .edges
+                .binary_search_by_key(&pending, |&(p, _)| p)
+                .ok()
+                .map(|i| batch.edges[i].1)
+        });
+        if let Some(edge) = resolved {
+            return PendingLookup::Resolved(edge);
+        }
+        let queued = self.mutations.iter().any(|(_, m)| {
+            matches!(
+                m,
+                Mutation::Connect { pending_id, .. } | Mutation::ConnectFiltered { pending_id, .. }
+                    if *pending_id == pending
+            )
+        });
+        unresolved_lookup(queued, pending.0 < self.next_pending_edge)
+    }
+
     /// Returns true if there are queued mutations waiting to be applied.
     pub fn has_pending_mutations(&self) -> bool {
         !self.mutations.is_empty()
@@ -428,6 +928,17 @@ impl ProductionGraph {
         self.nodes.get(node)
     }
 
+    /// Replace a node's flags. Returns `false` if the node does not exist.
+    pub(crate) fn set_node_flags(&mut self, node: NodeId, flags: NodeFlags) -> bool {
+        match self.nodes.get_mut(node) {
+            Some(data) => {
+                data.flags = flags;
+                true
+            }
+            None => false,
+        }
+    }
+
     /// Get the edge data for a given edge ID.
     pub fn get_edge(&self, edge: EdgeId) -> Option<&EdgeData> {
         self.edges.get(edge)
@@ -1150,6 +1661,36 @@ mod tests {
         assert_eq!(graph.edge_count(), 0);
     }
 
+    // -----------------------------------------------------------------------
+    // Connecting to a removed node is rejected, not left dangling
+    // -----------------------------------------------------------------------
+    #[test]
+    fn connect_to_removed_node_is_rejected() {
+        let (mut graph, nodes) = make_graph_with_nodes(2);
+        let [a, b] = [nodes[0], nodes[1]];
+        graph.queue_remove_node(b);
+        let pe = graph.queue_connect(a, b);
+        let pf = graph.queue_connect_filtered(b, a, None);
+        let result = graph.apply_mutations();
+        assert_eq!(result.resolve_edge(pe), None);
+        assert_eq!(
+            result.rejected_edges,
+            vec![
+                (pe, MutationRejectReason::MissingEndpoint),
+                (pf, MutationRejectReason::MissingEndpoint),
+            ]
+        );
+        assert_eq!(graph.edge_count(), 0);
+        assert!(graph.get_outputs(a).is_empty());
+
+        // A stale id from an earlier batch is rejected the same way.
+        let pe = graph.queue_connect(b, a);
+        let result = graph.apply_mutations();
+        assert_eq!(result.rejected_edges.len(), 1);
+        assert_eq!(result.rejected_edges[0].0, pe);
+        assert_eq!(graph.edge_count(), 0);
+    }
+
     // -----------------------------------------------------------------------
     // Test 15: Remove node with inbound and outbound edges
     // -----------------------------------------------------------------------
@@ -1486,4 +2027,110 @@ mod tests {
         assert!(d_level.is_some());
         assert!(!back_edges.is_empty());
     }
+
+    #[test]
+    fn pending_ids_resolve_across_batches() {
+        let mut graph = ProductionGraph::new();
+        let b = BuildingTypeId(0);
+        let first: Vec<PendingNodeId> = (0..3).map(|_| graph.queue_add_node(b)).collect();
+        let r1 = graph.apply_mutations();
+        let second = graph.queue_add_node(b);
+        let edge = graph.queue_connect(
+            r1.resolve_node(first[0]).unwrap(),
+            r1.resolve_node(first[1]).unwrap(),
+        );
+        assert_eq!(graph.resolve_pending_node(second), PendingLookup::Queued);
+        assert_eq!(graph.resolve_pending_edge(edge), PendingLookup::Queued);
+        let r2 = graph.apply_mutations();
+
+        for &p in &first {
+            assert_eq!(
+                graph.resolve_pending_node(p),
+                PendingLookup::Resolved(r1.resolve_node(p).unwrap())
+            );
+        }
+        assert_eq!(
+            graph.resolve_pending_node(second),
+            PendingLookup::Resolved(r2.resolve_node(second).unwrap())
+        );
+        assert_eq!(
+            graph.resolve_pending_edge(edge),
+            PendingLookup::Resolved(r2.resolve_edge(edge).unwrap())
+        );
+        assert_eq!(
+            graph.resolve_pending_node(PendingNodeId(99)),
+            PendingLookup::Unknown
+        );
+    }
+
+    #[test]
+    fn mutation_result_pairs_are_sorted_by_pending_id() {
+        let (mut graph, nodes) = make_graph_with_nodes(2);
+        graph.queue_connect(nodes[0], nodes[1]);
+        graph.queue_add_node(BuildingTypeId(1));
+        graph.queue_connect_filtered(nodes[1], nodes[0], Some(ItemTypeId(3)));
+        graph.queue_add_node(BuildingTypeId(2));
+        let result = graph.apply_mutations();
+
+        assert!(result.added_nodes.windows(2).all(|w| w[0].0 < w[1].0));
+        assert!(result.added_edges.windows(2).all(|w| w[0].0 < w[1].0));
+        assert_eq!(result.added_nodes.len(), 2);
+        assert_eq!(result.added_edges.len(), 2);
+    }
+
+    #[test]
+    fn resolution_history_evicts_oldest_batches() {
+        let mut graph = ProductionGraph::new();
+        graph.set_resolution_history_len(2);
+        let b = BuildingTypeId(0);
+        let batches: Vec<PendingNodeId> = (0..3)
+            .map(|_| {
+                let p = graph.queue_add_node(b);
+                graph.apply_mutations();
+                // Batches that add nothing do not count toward the history.
+                graph.apply_mutations();
+                p
+            })
+            .collect();
+
+        assert_eq!(
+            graph.resolve_pending_node(batches[0]),
+            PendingLookup::Expired
+        );
+        assert!(matches!(
+            graph.resolve_pending_node(batches[1]),
+            PendingLookup::Resolved(_)
+        ));
+        assert!(matches!(
+            graph.resolve_pending_node(batches[2]),
+            PendingLookup::Resolved(_)
+        ));
+
+        graph.set_resolution_history_len(0);
+        assert_eq!(
+            graph.resolve_pending_node(batches[2]),
+            PendingLookup::Expired
+        );
+    }
+
+    #[test]
+    fn pending_ids_stay_unique_after_deserialize() {
+        let (mut graph, _) = make_graph_with_nodes(3);
+        let before = graph.queue_add_node(BuildingTypeId(0));
+        graph.apply_mutations();
+
+        let bytes = bitcode::serialize(&graph).expect("serialize graph");
+        let mut restored: ProductionGraph = bitcode::deserialize(&bytes).expect("deserialize");
+        let after = restored.queue_add_node(BuildingTypeId(0));
+        assert!(after > before, "pending ids must not restart after a load");
+        assert_eq!(
+            restored.resolution_history_len(),
+            DEFAULT_RESOLUTION_HISTORY
+        );
+        // Resolutions are session-only: older ids read as expired, not unknown.
+        assert_eq!(
+            restored.resolve_pending_node(before),
+            PendingLookup::Expired
+        );
+    }
 }
@@ -0,0 +1,328 @@
+//! Tick hooks: host callbacks that run at fixed points of every step.
+//!
+//! Integrations that must run "once per tick at the right moment" -- fluid
+//! bridges, lab bindings, statistics sampling -- register a [`TickHook`] with
+//! [`Engine::register_tick_hook`] instead of relying on the host to call them
+//! in the right order around [`Engine::step`].
+//!
+//! Hooks run in a deterministic order: by [`TickPhase`], then by the `order`
+//! they were registered with (lowest first), then by registration order. A
+//! hook receives an [`EngineView`] with read-only access to the graph,
+//! mutable access to node inventories, and event emission. Inventory changes
+//! are visible to later hooks and to the rest of the step.
+//!
+//! Hooks are session state: they are not serialized, and an engine produced
+//! by deserialization has none. Hosts re-register them after loading, the
+//! same way they re-register event listeners.
+//!
+//! A panicking hook unwinds out of [`Engine::step`] and drops the hooks
+//! registered on the engine. The FFI wrappers catch the panic and poison the
+//! engine.
+
+use crate::engine::Engine;
+use crate::event::Event;
+use crate::fixed::Ticks;
+use crate::graph::ProductionGraph;
+use crate::id::NodeId;
+use crate::item::Inventory;
+use crate::processor::ProcessorState;
+
+/// The point in the step at which a hook runs.
+///
+/// Variants are ordered by when they run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum TickPhase {
+    /// After queued mutations are applied, before any transport moves.
+    Pre,
+    /// After the transport phase, before processors run.
+    PostTransports,
+    /// After the process phase, before modules and the component phase.
+    PostProcessors,
+    /// After the step is complete: events are delivered, the tick counter is
+    /// incremented and the state hash is computed. Events emitted here are
+    /// delivered during the next step.
+    PostStep,
+}
+
+/// Identifies a registered tick hook. Ids are never reused by an engine.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TickHookId(pub u64);
+
+/// A callback run once per step in its [`TickPhase`].
+pub type TickHook = Box<dyn FnMut(&mut EngineView<'_>)>;
+
+/// Controlled access to the engine for a running tick hook.
+pub struct EngineView<'a> {
+    engine: &'a mut Engine,
+    phase: TickPhase,
+    touched: bool,
+}
+
+impl EngineView<'_> {
+    /// The phase the hook is running in.
+    pub fn phase(&self) -> TickPhase {
+        self.phase
+    }
+
+    /// The current simulation tick. During [`TickPhase::PostStep`] this is
+    /// already the number of the next tick.
+    pub fn tick(&self) -> Ticks {
+        self.engine.sim_state.tick
+    }
+
+    /// The production graph (read-only).
+    pub fn graph(&self) -> &ProductionGraph {
+        &self.engine.graph
+    }
+
+    /// Get the processor state for a node.
+    pub fn processor_state(&self, node: NodeId) -> Option<&ProcessorState> {
+        self.engine.processor_states.get(node)
+    }
+
+    /// Get the input inventory for a node.
+    pub fn input_inventory(&self, node: NodeId) -> Option<&Inventory> {
+        self.engine.inputs.get(node)
+    }
+
+    /// Get the output inventory for a node.
+    pub fn output_inventory(&self, node: NodeId) -> Option<&Inventory> {
+        self.engine.outputs.get(node)
+    }
+
+    /// Get the input inventory for a node (mutable). The node's state is
+    /// rehashed at the end of the step.
+    pub fn input_inventory_mut(&mut self, node: NodeId) -> Option<&mut Inventory> {
+        self.touch(node);
+        self.engine.inputs.get_mut(node)
+    }
+
+    /// Get the output inventory for a node (mutable). The node's state is
+    /// rehashed at the end of the step.
+    pub fn output_inventory_mut(&mut self, node: NodeId) -> Option<&mut Inventory> {
+        self.touch(node);
+        self.engine.outputs.get_mut(node)
+    }
+
+    /// Emit an event on the engine's event bus.
+    pub fn emit(&mut self, event: Event) {
+        self.engine.event_bus.emit(event);
+    }
+
+    fn touch(&mut self, node: NodeId) {
+        if !self.engine.inputs.contains_key(node) && !self.engine.outputs.contains_key(node) {
+            return;
+        }
+        self.touched = true;
+        self.engine.hash_dirty_nodes.push(node);
+        self.engine.dirty.mark_node(node);
+        self.engine
+            .dirty
+            .mark_partition(crate::dirty::DirtyTracker::PARTITION_INVENTORIES);
+    }
+}
+
+struct RegisteredHook {
+    phase: TickPhase,
+    order: i32,
+    id: TickHookId,
+    hook: TickHook,
+}
+
+/// The hooks registered on an engine, kept sorted by execution order.
+#[derive(Default)]
+pub(crate) struct TickHooks {
+    hooks: Vec<RegisteredHook>,
+    next_id: u64,
+}
+
+impl std::fmt::Debug for TickHooks {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TickHooks")
+            .field("count", &self.hooks.len())
+            .field("next_id", &self.next_id)
+            .finish()
+    }
+}
+
+impl Engine {
+    /// Register a hook to run once per step in `phase`. Within a phase,
+    /// hooks run by ascending `order`, then in registration order.
+    pub fn register_tick_hook(
+        &mut self,
+        phase: TickPhase,
+        order: i32,
+        hook: TickHook,
+    ) -> TickHookId {
+        let id = TickHookId(self.tick_hooks.next_id);
+        self.tick_hooks.next_id += 1;
+        let key = (phase, order, id);
+        let at = self
+            .tick_hooks
+            .hooks
+            .partition_point(|h| (h.phase, h.order, h.id) < key);
+        self.tick_hooks.hooks.insert(
+            at,
+            RegisteredHook {
+                phase,
+                order,
+                id,
+                hook,
+            },
+        );
+        id
+    }
+
+    /// Remove a tick hook. Returns `false` if no hook has that id.
+    pub fn remove_tick_hook(&mut self, id: TickHookId) -> bool {
+        let before = self.tick_hooks.hooks.len();
+        self.tick_hooks.hooks.retain(|h| h.id != id);
+        self.tick_hooks.hooks.len() != before
+    }
+
+    /// Number of registered tick hooks.
+    pub fn tick_hook_count(&self) -> usize {
+        self.tick_hooks.hooks.len()
+    }
+
+    /// Run the hooks registered for `phase`. Returns whether any of them
+    /// mutated an inventory.
+    pub(crate) fn run_tick_hooks(&mut self, phase: TickPhase) -> bool {
+        if self.tick_hooks.hooks.is_empty() {
+            return false;
+        }
+        // Take the registry out so hooks can borrow the engine mutably.
+        let mut hooks = std::mem::take(&mut self.tick_hooks.hooks);
+        let start = hooks.partition_point(|h| h.phase < phase);
+        let end = hooks.partition_point(|h| h.phase <= phase);
+        let mut touched = false;
+        for registered in &mut hooks[start..end] {
+            let mut view = EngineView {
+                engine: self,
+                phase,
+                touched: false,
+            };
+            (registered.hook)(&mut view);
+            touched |= view.touched;
+        }
+        self.tick_hooks.hooks = hooks;
+        touched
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::processor::Processor;
+    use crate::sim::SimulationStrategy;
+    use crate::test_utils::{self, gear, iron};
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    fn recorder(log: &Rc<RefCell<Vec<&'static str>>>, name: &'static str) -> TickHook {
+        let log = Rc::clone(log);
+        Box::new(move |_| log.borrow_mut().push(name))
+    }
+
+    #[test]
+    fn hooks_run_by_phase_then_order_then_registration() {
+        let mut engine = Engine::new(SimulationStrategy::Tick);
+        let log = Rc::new(RefCell::new(Vec::new()));
+        engine.register_tick_hook(TickPhase::PostStep, -5, recorder(&log, "post-step"));
+        engine.register_tick_hook(TickPhase::Pre, 10, recorder(&log, "pre-10-a"));
+        engine.register_tick_hook(TickPhase::PostProcessors, 0, recorder(&log, "processors"));
+        engine.register_tick_hook(TickPhase::Pre, 10, recorder(&log, "pre-10-b"));
+        engine.register_tick_hook(TickPhase::PostTransports, 0, recorder(&log, "transports"));
+        engine.register_tick_hook(TickPhase::Pre, -1, recorder(&log, "pre-neg"));
+
+        engine.step();
+        assert_eq!(
+            *log.borrow(),
+            [
+                "pre-neg",
+                "pre-10-a",
+                "pre-10-b",
+                "transports",
+                "processors",
+                "post-step"
+            ]
+        );
+    }
+
+    #[test]
+    fn removed_hooks_stop_running() {
+        let mut engine = Engine::new(SimulationStrategy::Tick);
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let a = engine.register_tick_hook(TickPhase::Pre, 0, recorder(&log, "a"));
+        engine.register_tick_hook(TickPhase::Pre, 0, recorder(&log, "b"));
+        engine.step();
+        assert!(engine.remove_tick_hook(a));
+        assert!(!engine.remove_tick_hook(a));
+        engine.step();
+        assert_eq!(*log.borrow(), ["a", "b", "b"]);
+        assert_eq!(engine.tick_hook_count(), 1);
+    }
+
+    #[test]
+    fn hook_inventory_changes_reach_later_hooks_and_the_process_phase() {
+        let mut engine = Engine::new(SimulationStrategy::Tick);
+        let node = test_utils::add_node(
+            &mut engine,
+            test_utils::make_recipe(vec![(iron(), 3)], vec![(gear(), 1)], 5),
+            10,
+            10,
+        );
+        engine.register_tick_hook(
+            TickPhase::Pre,
+            1,
+            Box::new(move |view| {
+                let _ = view.input_inventory_mut(node).unwrap().insert(iron(), 3);
+            }),
+        );
+        let seen = Rc::new(RefCell::new(0));
+        let seen_in_hook = Rc::clone(&seen);
+        engine.register_tick_hook(
+            TickPhase::Pre,
+            2,
+            Box::new(move |view| {
+                *seen_in_hook.borrow_mut() = view.input_inventory(node).unwrap().count_of(iron());
+            }),
+        );
+
+        engine.step();
+        assert_eq!(*seen.borrow(), 3);
+        assert!(matches!(
+            engine.get_processor_state(node),
+            Some(ProcessorState::Working { .. })
+        ));
+    }
+
+    #[test]
+    fn post_step_changes_are_in_the_state_hash() {
+        let mut hooked = Engine::new(SimulationStrategy::Tick);
+        let mut plain = Engine::new(SimulationStrategy::Tick);
+        let a = test_utils::add_node(&mut hooked, Processor::Passthrough, 10, 10);
+        let b = test_utils::add_node(&mut plain, Processor::Passthrough, 10, 10);
+        hooked.register_tick_hook(
+            TickPhase::PostStep,
+            0,
+            Box::new(move |view| {
+                let _ = view.input_inventory_mut(a).unwrap().insert(iron(), 1);
+            }),
+        );
+        hooked.step();
+        plain.step();
+        let _ = plain.get_input_inventory_mut(b).unwrap().insert(iron(), 1);
+        assert_ne!(hooked.state_hash(), plain.state_hash());
+        let restored = Engine::deserialize(&hooked.serialize().unwrap()).unwrap();
+        assert_eq!(hooked.state_hash(), restored.state_hash());
+    }
+
+    #[test]
+    fn hooks_are_not_serialized() {
+        let mut engine = Engine::new(SimulationStrategy::Tick);
+        engine.register_tick_hook(TickPhase::Pre, 0, Box::new(|_| {}));
+        let restored = Engine::deserialize(&engine.serialize().unwrap()).unwrap();
+        assert_eq!(restored.tick_hook_count(), 0);
+    }
+}
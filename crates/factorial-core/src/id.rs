@@ -10,18 +10,32 @@ new_key_type! {
 
     /// Identifies a junction (splitter/merger/inserter) in the graph.
     pub struct JunctionId;
+
+    /// Identifies a hand-crafting queue owned by the engine.
+    pub struct CraftingQueueId;
+
+    /// Identifies a group of edges that form one logical transport line.
+    pub struct EdgeGroupId;
+
+    /// Identifies a subscriber cursor over the retained event history.
+    pub struct EventCursorId;
 }
 
 /// Identifies an item type in the registry. Cheap to copy and compare.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub struct ItemTypeId(pub u32);
 
+/// Identifies a group of item types, such as "ores" or "fluids". Categories
+/// are plain numbers chosen by the host; the engine only records membership.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct ItemCategoryId(pub u32);
+
 /// Identifies a building template in the registry.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub struct BuildingTypeId(pub u32);
 
 /// Identifies a recipe in the registry.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub struct RecipeId(pub u32);
 
 /// Identifies a property on an item type.
@@ -33,11 +47,16 @@ pub struct PropertyId(pub u16);
 pub struct ModifierId(pub u32);
 
 /// A pending node ID returned from queued mutations. Resolves to NodeId on apply.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+///
+/// Pending ids are unique for the lifetime of a graph, including across
+/// serialization: they come from a monotonic 64-bit counter that is saved
+/// with the graph.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct PendingNodeId(pub u64);
 
-/// A pending edge ID returned from queued mutations.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// A pending edge ID returned from queued mutations. Unique for the lifetime
+/// of a graph, like [`PendingNodeId`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct PendingEdgeId(pub u64);
 
 #[cfg(test)]
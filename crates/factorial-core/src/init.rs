@@ -0,0 +1,307 @@
+//! Tick-zero initialization.
+//!
+//! A freshly built engine has no history: sources are idle until their first
+//! tick, the state hash reads 0 until the first step, and the ordering and
+//! item-type caches are built lazily inside it. A mid-run save has none of
+//! that, so the first step of a new engine can differ from every later one --
+//! sources report `RecipeStarted` once, and the first step pays for the cache
+//! builds.
+//!
+//! [`Engine::initialize`] primes the engine so the first step behaves like
+//! any other:
+//!
+//! 1. Builds the topological orders and the edge item-type cache.
+//! 2. Settles idle sources into the state their first tick would give them:
+//!    working while their effective rate is positive, stalled once depleted.
+//!    Nothing is consumed or produced and no events are emitted.
+//! 3. Calls [`Module::on_initialize`](crate::module::Module::on_initialize)
+//!    on every registered module, in registration order.
+//! 4. Computes the state hash, so [`Engine::state_hash`] is meaningful
+//!    before the first step.
+//!
+//! Initialization does not advance the tick and does not apply queued graph
+//! mutations; nodes added by the first step's pre-tick phase start unprimed.
+//! It is safe on a deserialized engine, whose processors are already primed,
+//! and running it again only refreshes the caches and the hash.
+//!
+//! With [`Engine::set_auto_initialize`] the first step initializes an engine
+//! that has not been initialized yet. Both flags are session state: they are
+//! not serialized, like tick hooks, so hosts set them again after loading.
+
+use crate::engine::Engine;
+use crate::module::ModuleContext;
+
+/// Whether the engine has been initialized, and whether the next step should
+/// initialize it.
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct InitState {
+    /// Initialize on the first step if not yet initialized.
+    pub(crate) auto: bool,
+    /// [`Engine::initialize`] has run.
+    pub(crate) done: bool,
+}
+
+impl Engine {
+    /// Prime the engine before its first step. See the
+    /// [module documentation](crate::init) for what it does.
+    pub fn initialize(&mut self) {
+        let _ = self.graph.topological_order();
+        let _ = self.graph.topological_order_with_feedback();
+        self.rebuild_item_type_cache();
+
+        let nodes: Vec<_> = self.graph.nodes().map(|(node, _)| node).collect();
+        for node in nodes {
+            let (Some(processor), Some(state)) =
+                (self.processors.get(node), self.processor_states.get(node))
+            else {
+                continue;
+            };
+            let modifiers = self.modifiers.get(node).map_or(&[][..], Vec::as_slice);
+            let Some(primed) = processor.primed_state(state, modifiers) else {
+                continue;
+            };
+            self.processor_states.insert(node, primed);
+            self.hash_dirty_nodes.push(node);
+            self.dirty.mark_node(node);
+            self.dirty
+                .mark_partition(crate::dirty::DirtyTracker::PARTITION_PROCESSORS);
+        }
+
+        let mut modules = std::mem::take(&mut self.modules);
+        for module in &mut modules {
+            let mut ctx = ModuleContext {
+                graph: &self.graph,
+                processors: &mut self.processors,
+                processor_states: &mut self.processor_states,
+                inputs: &mut self.inputs,
+                outputs: &mut self.outputs,
+                event_bus: &mut self.event_bus,
+                tick: self.sim_state.tick,
+                work: 0,
+            };
+            module.on_initialize(&mut ctx);
+        }
+        self.modules = modules;
+
+        // Modules may have touched any node.
+        self.hash_cache_cold = true;
+        self.last_state_hash = self.compute_state_hash();
+        self.init.done = true;
+    }
+
+    /// Whether [`initialize`](Self::initialize) has run on this engine since
+    /// it was created or loaded.
+    pub fn is_initialized(&self) -> bool {
+        self.init.done
+    }
+
+    /// Initialize automatically at the start of the first step if the engine
+    /// has not been initialized yet. Off by default.
+    pub fn set_auto_initialize(&mut self, enabled: bool) {
+        self.init.auto = enabled;
+    }
+
+    /// Whether the first step initializes the engine.
+    pub fn auto_initialize(&self) -> bool {
+        self.init.auto
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event::{Event, EventKind};
+    use crate::fixed::Fixed64;
+    use crate::processor::{Depletion, Processor, ProcessorState, SourceProcessor, StallReason};
+    use crate::sim::SimulationStrategy;
+    use crate::test_utils::{self, gear, iron};
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    /// Source, smelter and sink joined by a belt and a flow edge.
+    fn factory() -> Engine {
+        let mut engine = Engine::new(SimulationStrategy::Tick);
+        let src = test_utils::add_node(&mut engine, test_utils::make_source(iron(), 2.0), 50, 50);
+        let smelter = test_utils::add_node(
+            &mut engine,
+            test_utils::make_recipe(vec![(iron(), 2)], vec![(gear(), 1)], 3),
+            50,
+            50,
+        );
+        let sink = test_utils::add_node(&mut engine, Processor::Passthrough, 50, 50);
+        test_utils::connect(
+            &mut engine,
+            src,
+            smelter,
+            test_utils::make_item_transport(4),
+        );
+        test_utils::connect(
+            &mut engine,
+            smelter,
+            sink,
+            test_utils::make_flow_transport(1.0),
+        );
+        engine
+    }
+
+    /// Record every event the engine delivers, as `(kind, tick)` pairs.
+    fn record(engine: &mut Engine) -> Rc<RefCell<Vec<(EventKind, u64)>>> {
+        let log = Rc::new(RefCell::new(Vec::new()));
+        for kind in [
+            EventKind::RecipeStarted,
+            EventKind::RecipeCompleted,
+            EventKind::BuildingStalled,
+            EventKind::BuildingResumed,
+            EventKind::ItemProduced,
+            EventKind::ItemConsumed,
+        ] {
+            let log = Rc::clone(&log);
+            engine.on_passive(
+                kind,
+                Box::new(move |event: &Event| log.borrow_mut().push((kind, event.tick()))),
+            );
+        }
+        log
+    }
+
+    #[test]
+    fn fresh_and_loaded_engines_step_identically_after_initialize() {
+        let mut fresh = factory();
+        fresh.initialize();
+        let mut loaded = Engine::deserialize(&fresh.serialize().unwrap()).unwrap();
+        loaded.initialize();
+        assert_eq!(fresh.state_hash(), loaded.state_hash());
+
+        let fresh_log = record(&mut fresh);
+        let loaded_log = record(&mut loaded);
+        for _ in 0..20 {
+            fresh.step();
+            loaded.step();
+            assert_eq!(fresh.state_hash(), loaded.state_hash());
+        }
+        assert!(!fresh_log.borrow().is_empty());
+        assert_eq!(*fresh_log.borrow(), *loaded_log.borrow());
+    }
+
+    #[test]
+    fn initialized_sources_start_without_a_recipe_started_event() {
+        let mut plain = factory();
+        let mut primed = factory();
+        primed.initialize();
+        let source = primed.graph.nodes().next().unwrap().0;
+        assert_eq!(
+            primed.get_processor_state(source),
+            Some(&ProcessorState::Working { progress: 0 })
+        );
+
+        let plain_log = record(&mut plain);
+        let primed_log = record(&mut primed);
+        plain.step();
+        primed.step();
+        let started = |log: &Rc<RefCell<Vec<(EventKind, u64)>>>| {
+            log.borrow()
+                .iter()
+                .filter(|(kind, _)| *kind == EventKind::RecipeStarted)
+                .count()
+        };
+        assert_eq!(started(&plain_log), 1);
+        assert_eq!(started(&primed_log), 0);
+        // The first step leaves both engines in the same state.
+        assert_eq!(plain.state_hash(), primed.state_hash());
+    }
+
+    #[test]
+    fn state_hash_is_available_before_the_first_step() {
+        let mut engine = factory();
+        assert_eq!(engine.state_hash(), 0);
+        engine.initialize();
+        assert!(engine.is_initialized());
+        assert_ne!(engine.state_hash(), 0);
+        assert_eq!(engine.sim_state.tick, 0);
+
+        let restored = Engine::deserialize(&engine.serialize().unwrap()).unwrap();
+        assert_eq!(restored.state_hash(), engine.state_hash());
+        assert!(!restored.is_initialized());
+    }
+
+    #[test]
+    fn depleted_sources_prime_as_stalled() {
+        let mut engine = Engine::new(SimulationStrategy::Tick);
+        let node = test_utils::add_node(
+            &mut engine,
+            Processor::Source(SourceProcessor {
+                output_type: iron(),
+                base_rate: Fixed64::from_num(1),
+                depletion: Depletion::Finite {
+                    remaining: Fixed64::ZERO,
+                },
+                accumulated: Fixed64::ZERO,
+                initial_properties: None,
+            }),
+            10,
+            10,
+        );
+        engine.initialize();
+        assert_eq!(
+            engine.get_processor_state(node),
+            Some(&ProcessorState::Stalled {
+                reason: StallReason::Depleted
+            })
+        );
+    }
+
+    #[test]
+    fn auto_initialize_runs_on_the_first_step_only() {
+        let mut engine = factory();
+        engine.set_auto_initialize(true);
+        let log = record(&mut engine);
+        engine.step();
+        assert!(engine.is_initialized());
+        assert!(
+            !log.borrow()
+                .iter()
+                .any(|(kind, _)| *kind == EventKind::RecipeStarted)
+        );
+
+        let mut plain = factory();
+        for _ in 0..10 {
+            engine.step();
+            plain.step();
+        }
+        plain.step();
+        assert_eq!(engine.state_hash(), plain.state_hash());
+    }
+
+    #[derive(Debug, Default)]
+    struct Baseline {
+        nodes_seen: usize,
+        ticks: u32,
+    }
+
+    impl crate::module::Module for Baseline {
+        fn name(&self) -> &str {
+            "baseline"
+        }
+        fn on_initialize(&mut self, ctx: &mut ModuleContext<'_>) {
+            self.nodes_seen = ctx.graph.node_count();
+        }
+        fn on_tick(&mut self, _ctx: &mut ModuleContext<'_>) {
+            self.ticks += 1;
+        }
+        fn as_any(&self) -> &dyn std::any::Any {
+            self
+        }
+        fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+            self
+        }
+    }
+
+    #[test]
+    fn modules_see_initialize_before_their_first_tick() {
+        let mut engine = factory();
+        engine.register_module(Box::new(Baseline::default()));
+        engine.initialize();
+        let baseline = engine.find_module::<Baseline>().unwrap();
+        assert_eq!((baseline.nodes_seen, baseline.ticks), (3, 0));
+    }
+}
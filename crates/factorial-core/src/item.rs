@@ -1,4 +1,4 @@
-use crate::fixed::Fixed64;
+use crate::fixed::{Fixed64, Ticks};
 use crate::id::*;
 use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
@@ -17,6 +17,19 @@ pub struct ItemStack {
     /// Empty by default. Game code sets properties via processors or modules.
     #[serde(default)]
     pub properties: BTreeMap<PropertyId, Fixed64>,
+    /// Age cohorts for perishable item types, oldest first. Empty unless the
+    /// item type has a decay configured on the engine (see
+    /// [`Engine::set_item_decay`](crate::engine::Engine::set_item_decay)).
+    #[serde(default)]
+    pub ages: Vec<ItemAge>,
+}
+
+/// A group of items in a stack that entered the inventory on the same tick.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ItemAge {
+    /// Tick the items were first seen in the inventory.
+    pub born: Ticks,
+    pub quantity: u32,
 }
 
 impl ItemStack {
@@ -25,9 +38,16 @@ impl ItemStack {
             item_type,
             quantity,
             properties: BTreeMap::new(),
+            ages: Vec::new(),
         }
     }
 
+    /// Age in ticks of the oldest items in the stack at tick `now`, if the
+    /// stack is tracking ages.
+    pub fn oldest_age(&self, now: Ticks) -> Option<Ticks> {
+        self.ages.first().map(|a| now.saturating_sub(a.born))
+    }
+
     pub fn set_property(&mut self, id: PropertyId, value: Fixed64) {
         self.properties.insert(id, value);
     }
@@ -43,6 +63,9 @@ pub struct InventorySlot {
     /// Fungible item stacks keyed by item type.
     pub stacks: Vec<ItemStack>,
     pub capacity: u32,
+    /// When set, the slot only accepts this item type.
+    #[serde(default)]
+    pub filter: Option<ItemTypeId>,
 }
 
 impl InventorySlot {
@@ -50,14 +73,36 @@ impl InventorySlot {
         Self {
             stacks: Vec::new(),
             capacity,
+            filter: None,
+        }
+    }
+
+    /// Create a slot that only accepts `item_type`.
+    pub fn filtered(capacity: u32, item_type: ItemTypeId) -> Self {
+        Self {
+            filter: Some(item_type),
+            ..Self::new(capacity)
+        }
+    }
+
+    /// Whether the slot's filter admits `item_type`.
+    pub fn accepts(&self, item_type: ItemTypeId) -> bool {
+        self.filter.is_none_or(|filter| filter == item_type)
+    }
+
+    /// How many more of `item_type` the slot can take (0 if filtered out).
+    pub fn free_space_for(&self, item_type: ItemTypeId) -> u32 {
+        if self.accepts(item_type) {
+            self.capacity.saturating_sub(self.total())
+        } else {
+            0
         }
     }
 
     /// Add fungible items. Returns the amount that didn't fit.
     #[must_use = "overflow count indicates items that did not fit"]
     pub fn add(&mut self, item_type: ItemTypeId, quantity: u32) -> u32 {
-        let current_total: u32 = self.stacks.iter().map(|s| s.quantity).sum();
-        let space = self.capacity.saturating_sub(current_total);
+        let space = self.free_space_for(item_type);
         let to_add = quantity.min(space);
         let overflow = quantity - to_add;
 
@@ -141,8 +186,7 @@ impl InventorySlot {
         quantity: u32,
         properties: &BTreeMap<PropertyId, Fixed64>,
     ) -> u32 {
-        let current_total: u32 = self.stacks.iter().map(|s| s.quantity).sum();
-        let space = self.capacity.saturating_sub(current_total);
+        let space = self.free_space_for(item_type);
         let to_add = quantity.min(space);
         let overflow = quantity - to_add;
 
@@ -165,6 +209,12 @@ impl InventorySlot {
 }
 
 /// Inventory for a building node. Multiple input/output slots.
+///
+/// Items enter through the input slots and leave through the output slots.
+/// The engine keeps separate input and output inventories per node and uses
+/// one side of each. Prefer the query and transfer methods below over
+/// walking the slots directly, so acceptance rules (filters, capacity) are
+/// applied the same way everywhere.
 #[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
 pub struct Inventory {
     pub input_slots: Vec<InventorySlot>,
@@ -182,6 +232,116 @@ impl Inventory {
                 .collect(),
         }
     }
+
+    fn slots(&self) -> impl Iterator<Item = &InventorySlot> {
+        self.input_slots.iter().chain(&self.output_slots)
+    }
+
+    /// Item types held across all slots with their total quantities, in
+    /// ascending item-type order.
+    pub fn iter_items(&self) -> impl Iterator<Item = (ItemTypeId, u32)> + use<> {
+        let mut totals: BTreeMap<ItemTypeId, u32> = BTreeMap::new();
+        for stack in self.slots().flat_map(|slot| &slot.stacks) {
+            if stack.quantity > 0 {
+                *totals.entry(stack.item_type).or_default() += stack.quantity;
+            }
+        }
+        totals.into_iter()
+    }
+
+    /// Total quantity of `item_type` across all slots.
+    pub fn count_of(&self, item_type: ItemTypeId) -> u32 {
+        self.slots().map(|slot| slot.quantity(item_type)).sum()
+    }
+
+    /// Total items across all slots and types.
+    pub fn total(&self) -> u32 {
+        self.slots().map(InventorySlot::total).sum()
+    }
+
+    /// Whether no slot holds any items.
+    pub fn is_empty(&self) -> bool {
+        self.total() == 0
+    }
+
+    /// How many more of `item_type` the input slots can accept.
+    pub fn free_space_for(&self, item_type: ItemTypeId) -> u32 {
+        self.input_slots
+            .iter()
+            .map(|slot| slot.free_space_for(item_type))
+            .fold(0u32, u32::saturating_add)
+    }
+
+    /// Whether every input slot is at capacity.
+    pub fn is_full(&self) -> bool {
+        self.input_slots.iter().all(|slot| !slot.has_space())
+    }
+
+    /// Add items to the input slots, filling them in order. Returns the
+    /// amount that didn't fit.
+    #[must_use = "overflow count indicates items that did not fit"]
+    pub fn insert(&mut self, item_type: ItemTypeId, quantity: u32) -> u32 {
+        let mut remaining = quantity;
+        for slot in &mut self.input_slots {
+            if remaining == 0 {
+                break;
+            }
+            remaining = slot.add(item_type, remaining);
+        }
+        remaining
+    }
+
+    /// Like [`insert`](Self::insert) but merges `properties` onto the stacks.
+    #[must_use = "overflow count indicates items that did not fit"]
+    pub fn insert_with_properties(
+        &mut self,
+        item_type: ItemTypeId,
+        quantity: u32,
+        properties: &BTreeMap<PropertyId, Fixed64>,
+    ) -> u32 {
+        let mut remaining = quantity;
+        for slot in &mut self.input_slots {
+            if remaining == 0 {
+                break;
+            }
+            remaining = slot.add_with_properties(item_type, remaining, properties);
+        }
+        remaining
+    }
+
+    /// Remove up to `max` of `item_type` from the output slots, in order.
+    /// Returns the amount actually removed.
+    #[must_use = "returns the quantity actually removed, which may be less than requested"]
+    pub fn take(&mut self, item_type: ItemTypeId, max: u32) -> u32 {
+        let mut removed = 0;
+        for slot in &mut self.output_slots {
+            if removed == max {
+                break;
+            }
+            removed += slot.remove(item_type, max - removed);
+        }
+        removed
+    }
+
+    /// Move up to `max` of `item_type` from this inventory's output slots
+    /// into `dest`'s input slots, carrying stack properties along. Only as
+    /// many items as `dest` can accept leave this inventory. Returns the
+    /// amount moved.
+    pub fn transfer_to(&mut self, dest: &mut Inventory, item_type: ItemTypeId, max: u32) -> u32 {
+        let properties = self
+            .output_slots
+            .iter()
+            .find_map(|slot| slot.get_properties(item_type).filter(|p| !p.is_empty()))
+            .cloned();
+        let wanted = max.min(dest.free_space_for(item_type));
+        let moved = self.take(item_type, wanted);
+        let overflow = match properties {
+            Some(ref props) => dest.insert_with_properties(item_type, moved, props),
+            None => dest.insert(item_type, moved),
+        };
+        debug_assert_eq!(overflow, 0, "free space was checked before moving");
+        moved - overflow
+    }
 }
 
 #[cfg(test)]
@@ -247,6 +407,92 @@ mod tests {
         assert_eq!(inv.output_slots.len(), 1);
     }
 
+    #[test]
+    fn inventory_aggregates_items_across_slots() {
+        let iron = ItemTypeId(0);
+        let copper = ItemTypeId(1);
+        let mut inv = Inventory::new(2, 1, 10);
+        assert!(inv.is_empty());
+        let _ = inv.input_slots[0].add(copper, 4);
+        let _ = inv.input_slots[1].add(iron, 6);
+        let _ = inv.input_slots[1].add(copper, 1);
+        let _ = inv.output_slots[0].add(iron, 2);
+
+        assert_eq!(
+            inv.iter_items().collect::<Vec<_>>(),
+            vec![(iron, 8), (copper, 5)]
+        );
+        assert_eq!(inv.count_of(iron), 8);
+        assert_eq!(inv.count_of(ItemTypeId(9)), 0);
+        assert_eq!(inv.total(), 13);
+        assert!(!inv.is_empty());
+    }
+
+    #[test]
+    fn inventory_insert_spills_across_slots_until_full() {
+        let iron = ItemTypeId(0);
+        let mut inv = Inventory::new(2, 1, 5);
+        assert_eq!(inv.free_space_for(iron), 10);
+        assert_eq!(inv.insert(iron, 7), 0);
+        assert_eq!(inv.input_slots[0].quantity(iron), 5);
+        assert_eq!(inv.input_slots[1].quantity(iron), 2);
+        assert!(!inv.is_full());
+        assert_eq!(inv.insert(iron, 6), 3);
+        assert!(inv.is_full());
+        assert_eq!(inv.free_space_for(iron), 0);
+    }
+
+    #[test]
+    fn filtered_slots_only_accept_their_item() {
+        let iron = ItemTypeId(0);
+        let copper = ItemTypeId(1);
+        let mut slot = InventorySlot::filtered(10, iron);
+        assert!(slot.accepts(iron));
+        assert!(!slot.accepts(copper));
+        assert_eq!(slot.free_space_for(copper), 0);
+        assert_eq!(slot.add(copper, 4), 4);
+        assert_eq!(slot.add_with_properties(copper, 2, &BTreeMap::new()), 2);
+        assert_eq!(slot.total(), 0);
+
+        let mut inv = Inventory::new(0, 0, 0);
+        inv.input_slots = vec![InventorySlot::filtered(10, iron), InventorySlot::new(3)];
+        assert_eq!(inv.free_space_for(iron), 13);
+        assert_eq!(inv.free_space_for(copper), 3);
+        assert_eq!(inv.insert(copper, 5), 2);
+        assert_eq!(inv.input_slots[0].total(), 0);
+        assert_eq!(inv.input_slots[1].quantity(copper), 3);
+    }
+
+    #[test]
+    fn transfer_moves_only_what_fits() {
+        let iron = ItemTypeId(0);
+        let temp = PropertyId(0);
+        let mut src = Inventory::new(1, 2, 10);
+        let mut props = BTreeMap::new();
+        props.insert(temp, Fixed64::from_num(40));
+        let _ = src.output_slots[0].add_with_properties(iron, 4, &props);
+        let _ = src.output_slots[1].add(iron, 6);
+        let mut dest = Inventory::new(1, 1, 7);
+        let _ = dest.input_slots[0].add(ItemTypeId(1), 2);
+
+        // Room for 5; the source keeps the rest.
+        assert_eq!(src.transfer_to(&mut dest, iron, 8), 5);
+        assert_eq!(dest.count_of(iron), 5);
+        assert_eq!(src.count_of(iron), 5);
+        assert_eq!(src.output_slots[0].quantity(iron), 0);
+        assert_eq!(
+            dest.input_slots[0]
+                .get_properties(iron)
+                .and_then(|p| p.get(&temp).copied()),
+            Some(Fixed64::from_num(40))
+        );
+
+        // Full destination or missing items move nothing.
+        assert_eq!(src.transfer_to(&mut dest, iron, 3), 0);
+        assert_eq!(dest.transfer_to(&mut src, iron, 3), 0);
+        assert_eq!(src.count_of(iron), 5);
+    }
+
     #[test]
     fn inventory_slot_get_properties() {
         let mut slot = InventorySlot::new(100);
@@ -328,6 +574,7 @@ mod tests {
             item_type: ItemTypeId(0),
             quantity: 10,
             properties: Default::default(),
+            ages: Vec::new(),
         };
 
         let temp = PropertyId(0);
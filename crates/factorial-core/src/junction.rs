@@ -5,7 +5,7 @@
 //! in topological order.
 
 use crate::fixed::Fixed64;
-use crate::id::ItemTypeId;
+use crate::id::{EdgeId, ItemTypeId, NodeId};
 use serde::{Deserialize, Serialize};
 
 // ---------------------------------------------------------------------------
@@ -32,6 +32,25 @@ pub enum MergePolicy {
     Priority,
 }
 
+/// Which incoming edge of a merge point is served first when the node's
+/// input inventory cannot take everything that arrives in a tick.
+///
+/// Set with [`Engine::set_merge_priority`](crate::engine::Engine::set_merge_priority).
+/// "Left" is the edge connected first: incoming edges are ranked in
+/// connection order.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MergePriority {
+    /// Share the room between the incoming edges, starting from a cursor
+    /// that moves on whenever the edge it points at delivers.
+    #[default]
+    Balanced,
+    /// Serve the incoming edges strictly in connection order.
+    LeftFirst,
+    /// Serve the listed edges strictly in the given order, then any other
+    /// incoming edge in connection order.
+    Priority(Vec<EdgeId>),
+}
+
 // ---------------------------------------------------------------------------
 // Junction configurations
 // ---------------------------------------------------------------------------
@@ -84,6 +103,16 @@ pub struct JunctionState {
     pub accumulated: Fixed64,
 }
 
+/// An incoming edge's place in this tick's balanced merge at `node`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct MergeTurn {
+    pub node: NodeId,
+    /// Edges of the merge still to advance, this one included.
+    pub edges_left: u32,
+    /// Whether this edge is the one the node's cursor points at.
+    pub leads: bool,
+}
+
 // ---------------------------------------------------------------------------
 // Tests
 // ---------------------------------------------------------------------------
@@ -259,4 +288,29 @@ mod tests {
         let cloned = state.clone();
         assert_eq!(cloned.round_robin_index, 42);
     }
+
+    // -----------------------------------------------------------------------
+    // Test 13: merge_priority_serde_roundtrip
+    // -----------------------------------------------------------------------
+    #[test]
+    fn merge_priority_serde_roundtrip() {
+        use slotmap::{Key, KeyData};
+
+        assert_eq!(MergePriority::default(), MergePriority::Balanced);
+        let edges = [
+            EdgeId::from(KeyData::from_ffi(7)),
+            EdgeId::null(),
+            EdgeId::from(KeyData::from_ffi(3)),
+        ];
+        for priority in [
+            MergePriority::Balanced,
+            MergePriority::LeftFirst,
+            MergePriority::Priority(edges.to_vec()),
+        ] {
+            let data = bitcode::serialize(&priority).expect("serialize priority");
+            let restored: MergePriority =
+                bitcode::deserialize(&data).expect("deserialize priority");
+            assert_eq!(priority, restored);
+        }
+    }
 }
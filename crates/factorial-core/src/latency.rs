@@ -0,0 +1,347 @@
+//! Per-edge transit latency histograms.
+//!
+//! Average throughput says little about how long items sit on an edge. With
+//! latency tracking enabled, the engine records for every item an edge
+//! delivers the ticks elapsed since the item left the source's output
+//! inventory -- queueing in a batch or a vehicle load plus travel time -- and
+//! counts it into one of a fixed set of histogram buckets.
+//!
+//! Tracking is transport-agnostic: each tracked edge keeps a queue of
+//! `(departure tick, count)` entries, appended when the transport takes items
+//! from the source and drained first in, first out as it delivers them. Every
+//! transport kind delivers in arrival order, except a multi-lane belt, whose
+//! lanes may overtake each other by up to one slot.
+//!
+//! Bucket `i` counts samples `s` with `edges[i - 1] <= s < edges[i]`; the
+//! first bucket starts at zero and the last one is open-ended, so a histogram
+//! has one more bucket than there are edges.
+//!
+//! Latency data is diagnostic session state, like profiling: it is not
+//! serialized and not part of the state hash. Items already in transit when
+//! tracking starts, or when an engine is loaded, are not sampled.
+//! Untracked edges cost one branch per step.
+
+use std::collections::VecDeque;
+
+use slotmap::SecondaryMap;
+
+use crate::engine::Engine;
+use crate::fixed::Ticks;
+use crate::id::EdgeId;
+use crate::transport::TransportResult;
+
+/// Bucket edges used until [`Engine::set_latency_buckets`] is called.
+pub const DEFAULT_LATENCY_BUCKETS: [Ticks; 10] = [1, 2, 4, 8, 16, 32, 64, 128, 256, 512];
+
+/// Latency tracking state for one edge.
+#[derive(Debug, Clone)]
+struct EdgeLatency {
+    /// Items taken from the source and not yet delivered, oldest first.
+    in_flight: VecDeque<(Ticks, u32)>,
+    counts: Vec<u64>,
+}
+
+impl EdgeLatency {
+    fn new(buckets: usize) -> Self {
+        Self {
+            in_flight: VecDeque::new(),
+            counts: vec![0; buckets],
+        }
+    }
+}
+
+/// Latency histograms for the tracked edges.
+#[derive(Debug, Clone)]
+pub(crate) struct LatencyTracker {
+    /// Track every edge, including edges added later.
+    all_edges: bool,
+    bucket_edges: Vec<Ticks>,
+    edges: SecondaryMap<EdgeId, EdgeLatency>,
+}
+
+impl Default for LatencyTracker {
+    fn default() -> Self {
+        Self {
+            all_edges: false,
+            bucket_edges: DEFAULT_LATENCY_BUCKETS.to_vec(),
+            edges: SecondaryMap::new(),
+        }
+    }
+}
+
+impl LatencyTracker {
+    /// Whether any edge is tracked.
+    pub(crate) fn is_active(&self) -> bool {
+        self.all_edges || !self.edges.is_empty()
+    }
+
+    /// Record one transport advance of `edge` at `tick`.
+    pub(crate) fn record(&mut self, edge: EdgeId, tick: Ticks, result: &TransportResult) {
+        if result.items_moved == 0 && result.items_delivered == 0 {
+            return;
+        }
+        let buckets = self.bucket_edges.len() + 1;
+        let latency = match self.edges.get_mut(edge) {
+            Some(latency) => latency,
+            None if self.all_edges => {
+                self.edges.insert(edge, EdgeLatency::new(buckets));
+                &mut self.edges[edge]
+            }
+            None => return,
+        };
+        if result.items_moved > 0 {
+            latency.in_flight.push_back((tick, result.items_moved));
+        }
+        let mut delivered = result.items_delivered;
+        while delivered > 0 {
+            let Some((departed, count)) = latency.in_flight.front_mut() else {
+                // Items that were in transit before tracking started.
+                break;
+            };
+            let n = delivered.min(*count);
+            let elapsed = tick.saturating_sub(*departed);
+            let bucket = self.bucket_edges.partition_point(|&e| e <= elapsed);
+            latency.counts[bucket] += u64::from(n);
+            *count -= n;
+            delivered -= n;
+            if *count == 0 {
+                latency.in_flight.pop_front();
+            }
+        }
+    }
+
+    /// Forget items in transit on `edge`, e.g. because its transport was
+    /// replaced. Recorded samples are kept.
+    pub(crate) fn clear_in_flight(&mut self, edge: EdgeId) {
+        if let Some(latency) = self.edges.get_mut(edge) {
+            latency.in_flight.clear();
+        }
+    }
+
+    pub(crate) fn remove_edge(&mut self, edge: EdgeId) {
+        self.edges.remove(edge);
+    }
+}
+
+impl Engine {
+    /// Enable or disable latency tracking for one edge. Enabling starts an
+    /// empty histogram; disabling discards the edge's histogram. Does nothing
+    /// for an edge that is not in the graph.
+    pub fn set_latency_tracking(&mut self, edge: EdgeId, enabled: bool) {
+        if !self.graph.contains_edge(edge) {
+            return;
+        }
+        let tracker = &mut self.latency;
+        if enabled {
+            if !tracker.edges.contains_key(edge) {
+                let buckets = tracker.bucket_edges.len() + 1;
+                tracker.edges.insert(edge, EdgeLatency::new(buckets));
+            }
+        } else {
+            tracker.edges.remove(edge);
+        }
+    }
+
+    /// Enable or disable latency tracking for every edge, including edges
+    /// added later. A tracked edge gets its histogram when it first moves
+    /// items. Disabling discards all histograms.
+    pub fn set_latency_tracking_all(&mut self, enabled: bool) {
+        self.latency.all_edges = enabled;
+        if !enabled {
+            self.latency.edges.clear();
+        }
+    }
+
+    /// Whether latency is being tracked for `edge`.
+    pub fn is_latency_tracked(&self, edge: EdgeId) -> bool {
+        self.latency.edges.contains_key(edge)
+            || (self.latency.all_edges && self.graph.contains_edge(edge))
+    }
+
+    /// Set the histogram bucket edges, in ticks. They are sorted and
+    /// deduplicated; an empty list yields a single bucket. Every histogram is
+    /// reset.
+    pub fn set_latency_buckets(&mut self, mut edges: Vec<Ticks>) {
+        edges.sort_unstable();
+        edges.dedup();
+        let buckets = edges.len() + 1;
+        self.latency.bucket_edges = edges;
+        for (_, latency) in self.latency.edges.iter_mut() {
+            *latency = EdgeLatency::new(buckets);
+        }
+    }
+
+    /// The histogram bucket edges, in ticks.
+    pub fn latency_bucket_edges(&self) -> &[Ticks] {
+        &self.latency.bucket_edges
+    }
+
+    /// Sample counts per bucket for `edge`. Empty if the edge is not
+    /// tracked or, under [`set_latency_tracking_all`](Self::set_latency_tracking_all),
+    /// has not moved items yet.
+    pub fn edge_latency_histogram(&self, edge: EdgeId) -> &[u64] {
+        self.latency
+            .edges
+            .get(edge)
+            .map_or(&[], |latency| latency.counts.as_slice())
+    }
+
+    /// Zero the histogram for `edge`. Items in transit are still sampled
+    /// when they arrive.
+    pub fn reset_latency_histogram(&mut self, edge: EdgeId) {
+        if let Some(latency) = self.latency.edges.get_mut(edge) {
+            latency.counts.fill(0);
+        }
+    }
+
+    /// Zero every histogram.
+    pub fn reset_latency_histograms(&mut self) {
+        for (_, latency) in self.latency.edges.iter_mut() {
+            latency.counts.fill(0);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::id::NodeId;
+    use crate::processor::Processor;
+    use crate::sim::SimulationStrategy;
+    use crate::test_utils::{self, iron};
+
+    /// Two passthrough nodes joined by `transport`.
+    fn line(transport: crate::transport::Transport) -> (Engine, NodeId, EdgeId) {
+        let mut engine = Engine::new(SimulationStrategy::Tick);
+        let a = test_utils::add_node(&mut engine, Processor::Passthrough, 100, 100);
+        let b = test_utils::add_node(&mut engine, Processor::Passthrough, 100, 100);
+        let edge = test_utils::connect(&mut engine, a, b, transport);
+        (engine, a, edge)
+    }
+
+    fn total(engine: &Engine, edge: EdgeId) -> u64 {
+        engine.edge_latency_histogram(edge).iter().sum()
+    }
+
+    #[test]
+    fn belt_samples_land_in_one_bucket() {
+        // Speed 1 over 5 slots: every item spends the same time on the belt.
+        let (mut engine, source, edge) = line(test_utils::make_item_transport(5));
+        engine.set_latency_buckets(vec![4, 6, 8]);
+        engine.set_latency_tracking(edge, true);
+        let _ = engine
+            .get_input_inventory_mut(source)
+            .unwrap()
+            .insert(iron(), 10);
+        for _ in 0..40 {
+            engine.step();
+        }
+        let histogram = engine.edge_latency_histogram(edge);
+        assert_eq!(histogram.len(), 4);
+        assert_eq!(total(&engine, edge), 10);
+        assert_eq!(histogram.iter().filter(|&&n| n > 0).count(), 1);
+        assert_eq!(histogram[1], 10, "{histogram:?}");
+    }
+
+    #[test]
+    fn batch_accumulation_wait_is_bimodal() {
+        // One 10-tick cycle: items loaded at its start wait about the whole
+        // cycle, items loaded near its end barely wait.
+        let (mut engine, source, edge) = line(test_utils::make_batch_transport(20, 10));
+        engine.set_latency_buckets(vec![3, 7]);
+        engine.set_latency_tracking(edge, true);
+        let _ = engine
+            .get_input_inventory_mut(source)
+            .unwrap()
+            .insert(iron(), 5);
+        for tick in 0..20 {
+            if tick == 7 {
+                let _ = engine
+                    .get_input_inventory_mut(source)
+                    .unwrap()
+                    .insert(iron(), 5);
+            }
+            engine.step();
+        }
+        assert_eq!(engine.edge_latency_histogram(edge), [5, 0, 5]);
+    }
+
+    #[test]
+    fn flow_and_vehicle_latency_is_recorded() {
+        let (mut engine, source, flow) = line(test_utils::make_flow_transport(2.0));
+        let _ = engine
+            .get_input_inventory_mut(source)
+            .unwrap()
+            .insert(iron(), 10);
+        engine.set_latency_tracking_all(true);
+        for _ in 0..20 {
+            engine.step();
+        }
+        assert_eq!(total(&engine, flow), 10);
+
+        let (mut engine, source, vehicle) = line(test_utils::make_vehicle_transport(4, 3));
+        engine.set_latency_buckets(vec![2, 3]);
+        engine.set_latency_tracking(vehicle, true);
+        let _ = engine
+            .get_input_inventory_mut(source)
+            .unwrap()
+            .insert(iron(), 8);
+        for _ in 0..30 {
+            engine.step();
+        }
+        // A vehicle departs on the tick it loads, so every load is on board
+        // for `travel_time - 1` ticks.
+        assert_eq!(engine.edge_latency_histogram(vehicle), [0, 8, 0]);
+    }
+
+    #[test]
+    fn disabled_tracking_records_nothing_and_reset_zeroes() {
+        let (mut engine, source, edge) = line(test_utils::make_item_transport(3));
+        let _ = engine
+            .get_input_inventory_mut(source)
+            .unwrap()
+            .insert(iron(), 4);
+        for _ in 0..20 {
+            engine.step();
+        }
+        assert!(engine.edge_latency_histogram(edge).is_empty());
+        assert!(!engine.is_latency_tracked(edge));
+
+        engine.set_latency_tracking(edge, true);
+        let _ = engine
+            .get_input_inventory_mut(source)
+            .unwrap()
+            .insert(iron(), 4);
+        for _ in 0..20 {
+            engine.step();
+        }
+        assert_eq!(total(&engine, edge), 4);
+        engine.reset_latency_histogram(edge);
+        assert_eq!(total(&engine, edge), 0);
+        assert_eq!(
+            engine.edge_latency_histogram(edge).len(),
+            DEFAULT_LATENCY_BUCKETS.len() + 1
+        );
+
+        engine.set_latency_tracking(edge, false);
+        assert!(engine.edge_latency_histogram(edge).is_empty());
+    }
+
+    #[test]
+    fn tracking_does_not_change_the_state_hash() {
+        let (mut tracked, a, edge) = line(test_utils::make_batch_transport(3, 4));
+        let (mut plain, b, _) = line(test_utils::make_batch_transport(3, 4));
+        tracked.set_latency_tracking(edge, true);
+        for (engine, node) in [(&mut tracked, a), (&mut plain, b)] {
+            let _ = engine
+                .get_input_inventory_mut(node)
+                .unwrap()
+                .insert(iron(), 9);
+        }
+        for _ in 0..15 {
+            tracked.step();
+            plain.step();
+            assert_eq!(tracked.state_hash(), plain.state_hash());
+        }
+    }
+}
@@ -40,30 +40,56 @@
 //!   and item types (frozen at startup).
 //! - [`event::EventBus`] -- Subscription-based event bus with buffered delivery.
 //! - [`serialize`] -- Versioned serialization and snapshot support via bitcode.
+//! - [`builder::FactoryBuilder`] -- Fluent, name-based construction of a
+//!   whole production graph into a fresh engine.
 
+pub mod bottleneck;
+pub mod builder;
+pub mod capacity;
+pub mod catalog;
+pub mod category;
+pub mod census;
 pub mod component;
+pub mod crafting;
 #[cfg(feature = "data-loader")]
 pub mod data_loader;
 pub mod dirty;
+pub mod edge_group;
 pub mod engine;
 pub mod event;
+pub mod extension;
+pub mod fill;
 pub mod fixed;
+#[cfg(any(test, feature = "test-utils"))]
+pub mod fuzz;
 pub mod graph;
+pub mod hook;
 pub mod id;
+pub mod init;
 pub mod item;
 pub mod junction;
+pub mod latency;
 pub mod migration;
 pub mod module;
+pub mod oplog;
 pub mod processor;
 pub mod profiling;
 pub mod query;
+pub mod region;
 pub mod registry;
 pub mod replay;
+pub mod richness;
 pub mod rng;
 pub mod serialize;
 pub mod sim;
+pub mod slice;
+pub mod spoilage;
+pub mod summary;
+pub mod throughput;
+pub mod topology;
 pub mod transport;
 pub mod validation;
+pub mod warmup;
 
 #[cfg(any(test, feature = "test-utils"))]
 pub mod test_utils;
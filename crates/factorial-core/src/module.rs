@@ -8,7 +8,7 @@
 
 use crate::event::EventBus;
 use crate::graph::ProductionGraph;
-use crate::id::NodeId;
+use crate::id::{BuildingTypeId, NodeId};
 use crate::item::Inventory;
 use crate::processor::{Processor, ProcessorState};
 use slotmap::SecondaryMap;
@@ -21,8 +21,9 @@ use slotmap::SecondaryMap;
 ///
 /// Modules are called once per tick with a [`ModuleContext`] providing mutable
 /// access to engine state. The default implementations of `on_tick`,
-/// `serialize_state`, and `load_state` are no-ops, so modules only need to
-/// override the methods they care about.
+/// `on_initialize`, `on_node_removed`, `state_hash`, `serialize_state`, and
+/// `load_state` are no-ops, so modules only need to override the methods they
+/// care about.
 pub trait Module: std::fmt::Debug {
     /// The human-readable name of this module, used for lookup and debugging.
     fn name(&self) -> &str;
@@ -32,6 +33,44 @@ pub trait Module: std::fmt::Debug {
         let _ = ctx;
     }
 
+    /// Called once by `Engine::initialize`, before the first step. Override
+    /// to compute baselines the first `on_tick` would otherwise start from
+    /// scratch. Events emitted here are delivered during the first step.
+    fn on_initialize(&mut self, ctx: &mut ModuleContext<'_>) {
+        let _ = ctx;
+    }
+
+    /// Called when a node is removed from the graph by `Engine::apply_mutations`
+    /// (directly or during a step's pre-tick phase). Override to drop any
+    /// state the module keeps for `node`.
+    fn on_node_removed(&mut self, node: NodeId) {
+        let _ = node;
+    }
+
+    /// Whether nodes of `building_type` may be built. Only consulted by
+    /// `Engine::apply_mutations` in strict mode, where a node addition is
+    /// rejected if any registered module returns `false`. Allows everything
+    /// by default.
+    fn allows_building(&self, building_type: BuildingTypeId) -> bool {
+        let _ = building_type;
+        true
+    }
+
+    /// A deterministic hash of this module's simulation state, added to the
+    /// engine's state hash and reported in
+    /// [`HashBreakdown::modules`](crate::engine::HashBreakdown::modules).
+    /// Called once per tick. Returns 0 by default (the module contributes
+    /// nothing).
+    fn state_hash(&self) -> u64 {
+        0
+    }
+
+    /// The [`ExtensionId`](crate::extension::ExtensionId) this module
+    /// implements. `Engine::register_module` enables it. `None` by default.
+    fn extension(&self) -> Option<crate::extension::ExtensionId> {
+        None
+    }
+
     /// Serialize this module's internal state for save games.
     /// Returns an empty vec by default (stateless module).
     fn serialize_state(&self) -> Vec<u8> {
@@ -73,6 +112,11 @@ pub struct ModuleContext<'a> {
     pub event_bus: &'a mut EventBus,
     /// The current simulation tick.
     pub tick: u64,
+    /// Work units performed by the module this tick. Starts at zero; modules
+    /// that want their cost visible in
+    /// [`StepProfile::module_work`](crate::profiling::StepProfile::module_work)
+    /// add to it.
+    pub work: u32,
 }
 
 // ---------------------------------------------------------------------------
@@ -267,6 +311,7 @@ mod tests {
             outputs: &mut engine.outputs,
             event_bus: &mut engine.event_bus,
             tick: engine.sim_state.tick,
+            work: 0,
         }
     }
 
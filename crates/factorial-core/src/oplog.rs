@@ -0,0 +1,237 @@
+//! Operation log: a low-level trace of what the engine does, for tooling.
+//!
+//! Events are gameplay-facing: they can be filtered, silenced per node and
+//! suppressed by kind, and listeners see them only once the step delivers
+//! them. An operation logger set with [`Engine::set_operation_logger`] is
+//! called synchronously for every operation as the engine performs it --
+//! graph mutations applied, processors producing output, transports
+//! delivering items, steps completing -- with a flat [`OperationRecord`].
+//!
+//! Records arrive in a deterministic order: mutations in the order their
+//! events would be emitted, then per step the transport deliveries in edge
+//! order, processor output in processing order (sequentially even with the
+//! `parallel` feature), and finally [`OperationKind::StepCompleted`]. Silent
+//! nodes and suppressed event kinds are logged like any other.
+//!
+//! A logger is session state: it is not serialized and does not affect the
+//! state hash. If the logger panics, the engine catches the panic and drops
+//! the logger, so the simulation carries on without it.
+
+use std::panic::{AssertUnwindSafe, catch_unwind};
+
+use crate::engine::Engine;
+use crate::fixed::Ticks;
+use crate::id::{EdgeId, NodeId};
+
+/// What an [`OperationRecord`] describes.
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OperationKind {
+    /// A node was added. `item` is its building type.
+    NodeAdded = 0,
+    /// A node was removed.
+    NodeRemoved = 1,
+    /// An edge was added. `node` is its source node.
+    EdgeAdded = 2,
+    /// An edge was removed.
+    EdgeRemoved = 3,
+    /// A processor produced `quantity` of `item` at `node`.
+    Produced = 4,
+    /// `edge` delivered `quantity` of `item` to `node`, its destination.
+    Delivered = 5,
+    /// Step `tick` finished and its state hash is computed.
+    StepCompleted = 6,
+}
+
+/// One logged operation. Fields that do not apply to the kind are zero or
+/// the null id.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OperationRecord {
+    pub kind: OperationKind,
+    /// The tick the operation happened in.
+    pub tick: Ticks,
+    pub node: NodeId,
+    pub edge: EdgeId,
+    /// Item type, or building type for [`OperationKind::NodeAdded`].
+    pub item: u32,
+    pub quantity: u32,
+}
+
+impl OperationRecord {
+    pub(crate) fn new(kind: OperationKind, tick: Ticks) -> Self {
+        Self {
+            kind,
+            tick,
+            node: NodeId::default(),
+            edge: EdgeId::default(),
+            item: 0,
+            quantity: 0,
+        }
+    }
+
+    pub(crate) fn node(mut self, node: NodeId) -> Self {
+        self.node = node;
+        self
+    }
+
+    pub(crate) fn edge(mut self, edge: EdgeId) -> Self {
+        self.edge = edge;
+        self
+    }
+
+    pub(crate) fn amount(mut self, item: u32, quantity: u32) -> Self {
+        self.item = item;
+        self.quantity = quantity;
+        self
+    }
+}
+
+/// A callback receiving every [`OperationRecord`].
+pub type OperationLogger = Box<dyn FnMut(&OperationRecord)>;
+
+/// The engine's operation logger slot.
+#[derive(Default)]
+pub(crate) struct OperationLog {
+    logger: Option<OperationLogger>,
+}
+
+impl std::fmt::Debug for OperationLog {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("OperationLog")
+            .field("active", &self.logger.is_some())
+            .finish()
+    }
+}
+
+impl Engine {
+    /// Call `logger` for every operation the engine performs from now on,
+    /// replacing any previous logger.
+    pub fn set_operation_logger(&mut self, logger: OperationLogger) {
+        self.operation_log.logger = Some(logger);
+    }
+
+    /// Remove the operation logger, if any.
+    pub fn clear_operation_logger(&mut self) {
+        self.operation_log.logger = None;
+    }
+
+    /// Whether an operation logger is set. Turns false after a logger
+    /// panics.
+    pub fn has_operation_logger(&self) -> bool {
+        self.operation_log.logger.is_some()
+    }
+
+    /// Pass `record` to the logger, dropping the logger if it panics.
+    pub(crate) fn log_operation(&mut self, record: OperationRecord) {
+        let Some(logger) = self.operation_log.logger.as_mut() else {
+            return;
+        };
+        if catch_unwind(AssertUnwindSafe(|| logger(&record))).is_err() {
+            self.operation_log.logger = None;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::id::BuildingTypeId;
+    use crate::processor::Processor;
+    use crate::sim::SimulationStrategy;
+    use crate::test_utils::*;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    fn record_into(engine: &mut Engine) -> Rc<RefCell<Vec<OperationRecord>>> {
+        let records = Rc::new(RefCell::new(Vec::new()));
+        let sink = Rc::clone(&records);
+        engine.set_operation_logger(Box::new(move |record| sink.borrow_mut().push(*record)));
+        records
+    }
+
+    #[test]
+    fn logger_sees_mutations_deliveries_and_production_in_order() {
+        let mut engine = Engine::new(SimulationStrategy::Tick);
+        let records = record_into(&mut engine);
+        let pending_mine = engine.graph.queue_add_node(BuildingTypeId(7));
+        let pending_chest = engine.graph.queue_add_node(BuildingTypeId(8));
+        let result = engine.apply_mutations();
+        let mine = result.resolve_node(pending_mine).unwrap();
+        let chest = result.resolve_node(pending_chest).unwrap();
+        let pending = engine.graph.queue_connect(mine, chest);
+        let belt = engine.apply_mutations().resolve_edge(pending).unwrap();
+        assert_eq!(
+            records.borrow_mut().split_off(0),
+            vec![
+                OperationRecord::new(OperationKind::NodeAdded, 0)
+                    .node(mine)
+                    .amount(7, 0),
+                OperationRecord::new(OperationKind::NodeAdded, 0)
+                    .node(chest)
+                    .amount(8, 0),
+                OperationRecord::new(OperationKind::EdgeAdded, 0)
+                    .node(mine)
+                    .edge(belt),
+            ]
+        );
+        engine.set_processor(mine, make_source(iron(), 1.0));
+        engine.set_output_inventory(mine, simple_inventory(10));
+        engine.set_processor(chest, Processor::Passthrough);
+        engine.set_input_inventory(chest, simple_inventory(10));
+        engine.set_transport(belt, make_flow_transport(5.0));
+
+        engine.step();
+        engine.step();
+        let iron = iron().0;
+        assert_eq!(
+            records.borrow_mut().split_off(0),
+            vec![
+                OperationRecord::new(OperationKind::Produced, 0)
+                    .node(mine)
+                    .amount(iron, 1),
+                OperationRecord::new(OperationKind::StepCompleted, 0),
+                OperationRecord::new(OperationKind::Delivered, 1)
+                    .node(chest)
+                    .edge(belt)
+                    .amount(iron, 1),
+                OperationRecord::new(OperationKind::Produced, 1)
+                    .node(mine)
+                    .amount(iron, 1),
+                OperationRecord::new(OperationKind::StepCompleted, 1),
+            ]
+        );
+
+        // Event suppression leaves the trace alone.
+        engine.suppress_event(crate::event::EventKind::ItemProduced);
+        engine.graph.queue_remove_node(chest);
+        engine.step();
+        let kinds: Vec<_> = records.borrow().iter().map(|r| r.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                OperationKind::NodeRemoved,
+                OperationKind::EdgeRemoved,
+                OperationKind::Produced,
+                OperationKind::StepCompleted,
+            ]
+        );
+
+        engine.clear_operation_logger();
+        assert!(!engine.has_operation_logger());
+        records.borrow_mut().clear();
+        engine.step();
+        assert!(records.borrow().is_empty());
+    }
+
+    #[test]
+    fn a_panicking_logger_is_dropped_and_the_step_completes() {
+        let mut engine = Engine::new(SimulationStrategy::Tick);
+        let mine = add_node(&mut engine, make_source(iron(), 1.0), 0, 10);
+        engine.set_operation_logger(Box::new(|_| panic!("logger failed")));
+        engine.step();
+        assert!(!engine.has_operation_logger());
+        assert_eq!(engine.sim_state.tick, 1);
+        assert_eq!(output_quantity(&engine, mine, iron()), 1);
+    }
+}
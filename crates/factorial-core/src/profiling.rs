@@ -2,6 +2,9 @@
 //!
 //! - [`TickProfile`] captures per-phase timing from the most recent tick.
 //!   Only available when the `profiling` feature is enabled.
+//! - [`StepProfile`] captures per-phase operation counts from the most recent
+//!   step. Always available, opt-in at runtime via
+//!   [`Engine::set_profiling`](crate::engine::Engine::set_profiling).
 //! - [`DiagnosticInfo`] provides a detailed breakdown of why a node is in its
 //!   current state. Always available (not feature-gated).
 
@@ -42,6 +45,75 @@ impl TickProfile {
     }
 }
 
+/// Per-phase operation counts from the most recent step.
+///
+/// Recorded when profiling is enabled with
+/// [`Engine::set_profiling`](crate::engine::Engine::set_profiling). Counts are
+/// deterministic (same simulation, same counts), so tests can assert them;
+/// wall-clock timings live in [`TickProfile`] behind the `profiling` feature.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StepProfile {
+    /// Engine tick after the step completed.
+    pub tick: u64,
+    /// Pre-tick: graph mutations applied (nodes and edges added or removed).
+    pub mutations_applied: u32,
+    /// Transport: transports advanced.
+    pub transports_advanced: u32,
+    /// Transport: items delivered to destination inventories.
+    pub items_delivered: u32,
+    /// Process: processors ticked.
+    pub processors_ticked: u32,
+    /// Component: junctions processed.
+    pub junctions_processed: u32,
+    /// Component: modules run.
+    pub modules_run: u32,
+    /// Component: work units reported by modules through
+    /// [`ModuleContext::work`](crate::module::ModuleContext::work).
+    pub module_work: u32,
+    /// Post-tick: events delivered to subscribers.
+    pub events_delivered: u32,
+    /// [`Engine::topology_snapshot`](crate::engine::Engine::topology_snapshot)
+    /// cache rebuilds since the previous step, including any during this one.
+    /// Not step work.
+    pub topology_rebuilds: u32,
+}
+
+impl StepProfile {
+    /// Work units counted against the step work limit: every operation
+    /// counted here except `modules_run`, whose cost is in `module_work`.
+    /// See [`Engine::set_step_work_limit`](crate::engine::Engine::set_step_work_limit).
+    pub fn work_units(&self) -> u64 {
+        [
+            self.mutations_applied,
+            self.transports_advanced,
+            self.items_delivered,
+            self.processors_ticked,
+            self.junctions_processed,
+            self.module_work,
+            self.events_delivered,
+        ]
+        .into_iter()
+        .map(u64::from)
+        .sum()
+    }
+
+    /// Returns the name and count of the phase that performed the most
+    /// operations.
+    pub fn busiest_phase(&self) -> (&'static str, u32) {
+        let phases = [
+            ("pre_tick", self.mutations_applied),
+            ("transport", self.transports_advanced),
+            ("process", self.processors_ticked),
+            ("component", self.junctions_processed + self.modules_run),
+            ("post_tick", self.events_delivered),
+        ];
+        phases
+            .into_iter()
+            .max_by_key(|(_, n)| *n)
+            .expect("phases array is non-empty")
+    }
+}
+
 /// Diagnostic info about why a node is in its current state.
 /// Always available (not feature-gated).
 #[derive(Debug, Clone)]
@@ -168,6 +240,94 @@ mod tests {
         assert_eq!(dur, Duration::from_micros(100));
     }
 
+    // =======================================================================
+    // StepProfile tests (always available)
+    // =======================================================================
+
+    #[test]
+    fn step_profile_none_unless_enabled() {
+        let mut engine = Engine::new(SimulationStrategy::Tick);
+        engine.step();
+        assert!(engine.last_step_profile().is_none());
+
+        engine.set_profiling(true);
+        assert!(engine.is_profiling());
+        assert!(engine.last_step_profile().is_none());
+        engine.step();
+        assert_eq!(engine.last_step_profile().unwrap().tick, 2);
+
+        engine.set_profiling(false);
+        assert!(engine.last_step_profile().is_none());
+    }
+
+    #[test]
+    fn step_profile_counts_working_processors() {
+        const N: usize = 5;
+        let mut engine = Engine::new(SimulationStrategy::Tick);
+        engine.set_profiling(true);
+        let nodes: Vec<NodeId> = (0..N)
+            .map(|_| {
+                let node = add_node(&mut engine);
+                engine.set_processor(node, make_recipe(vec![(iron(), 1)], vec![(gear(), 1)], 10));
+                let mut input = simple_inventory(100);
+                let _ = input.input_slots[0].add(iron(), 50);
+                engine.set_input_inventory(node, input);
+                engine.set_output_inventory(node, simple_inventory(100));
+                node
+            })
+            .collect();
+
+        for _ in 0..4 {
+            engine.step();
+            assert!(nodes.iter().all(|&n| matches!(
+                engine.get_processor_state(n),
+                Some(ProcessorState::Working { .. })
+            )));
+            let profile = engine.last_step_profile().unwrap();
+            assert_eq!(profile.processors_ticked, N as u32);
+            assert_eq!(profile.transports_advanced, 0);
+        }
+        assert_eq!(
+            engine.last_step_profile().unwrap().busiest_phase().0,
+            "process"
+        );
+    }
+
+    #[test]
+    fn step_profile_counts_transport_mutations_and_events() {
+        let mut engine = Engine::new(SimulationStrategy::Tick);
+        engine.set_profiling(true);
+        let a = add_node(&mut engine);
+        let b = add_node(&mut engine);
+        engine.set_processor(a, make_source(iron(), 3.0));
+        engine.set_output_inventory(a, simple_inventory(100));
+        engine.set_input_inventory(b, simple_inventory(100));
+        let edge = connect(&mut engine, a, b);
+        engine.set_transport(edge, crate::test_utils::make_flow_transport(10.0));
+
+        engine.step();
+        engine.graph.queue_add_node(building());
+        engine.step();
+
+        let profile = engine.last_step_profile().unwrap().clone();
+        assert_eq!(profile.mutations_applied, 1);
+        assert_eq!(profile.transports_advanced, 1);
+        assert_eq!(profile.items_delivered, 3);
+        assert_eq!(profile.processors_ticked, 1);
+        // NodeAdded + ItemDelivered + ItemProduced.
+        assert_eq!(profile.events_delivered, 3);
+
+        // Counts are deterministic across identical runs.
+        engine.step();
+        let a_profile = engine.last_step_profile().unwrap().clone();
+        let mut copy = Engine::deserialize(&engine.serialize().unwrap()).unwrap();
+        copy.set_profiling(true);
+        engine.step();
+        copy.step();
+        assert_eq!(engine.last_step_profile(), copy.last_step_profile());
+        assert_eq!(a_profile.mutations_applied, 0);
+    }
+
     // =======================================================================
     // Profiling feature-gated tests
     // =======================================================================
@@ -5,7 +5,7 @@
 //! references into internal engine storage.
 
 use crate::fixed::Fixed64;
-use crate::id::{BuildingTypeId, EdgeId, NodeId};
+use crate::id::{BuildingTypeId, EdgeId, ItemTypeId, NodeId};
 use crate::item::ItemStack;
 use crate::processor::ProcessorState;
 
@@ -58,3 +58,94 @@ pub struct TransportSnapshot {
     /// Total items currently in transit within this transport.
     pub items_in_transit: u32,
 }
+
+// ---------------------------------------------------------------------------
+// Transport progress
+// ---------------------------------------------------------------------------
+
+/// Where a transport edge is in its delivery cycle, for host UI such as
+/// "next train departs in 14 ticks" or "batch 37/50 full".
+///
+/// Tick counts assume the destination has room: a count of `n` means the
+/// event happens during the `n`th step from now.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransportProgress {
+    /// A [`FlowTransport`](crate::transport::FlowTransport) buffer.
+    Flow {
+        /// Amount in the buffer.
+        buffered: Fixed64,
+        /// The buffer's capacity.
+        capacity: Fixed64,
+    },
+    /// An [`ItemTransport`](crate::transport::ItemTransport) belt.
+    Item {
+        /// Items on the belt.
+        items_in_transit: u32,
+        /// Position of the item closest to the destination: 0 at the source
+        /// end, 1 at the destination end. `None` for an empty belt.
+        head_position: Option<Fixed64>,
+        /// Steps until the next item is delivered. `None` for an empty or
+        /// stopped belt.
+        ticks_until_delivery: Option<u32>,
+    },
+    /// A [`BatchTransport`](crate::transport::BatchTransport).
+    Batch {
+        /// Items pending in the current batch.
+        accumulated: u32,
+        /// Most items one batch holds.
+        batch_size: u32,
+        /// Steps until the cycle ends and the pending items are delivered,
+        /// full batch or not.
+        ticks_until_forced_dispatch: u32,
+    },
+    /// A [`VehicleTransport`](crate::transport::VehicleTransport).
+    Vehicle {
+        /// What the vehicle is doing.
+        phase: VehiclePhase,
+        /// Steps until the phase ends. See [`VehiclePhase`].
+        ticks_remaining_in_phase: u32,
+        /// Items on board.
+        cargo_count: u32,
+    },
+}
+
+/// The phase of a vehicle's round trip, as reported by
+/// [`TransportProgress::Vehicle`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VehiclePhase {
+    /// Empty at the source. It loads and departs on the next step with
+    /// items at the source, so the phase always has 1 step remaining.
+    Loading,
+    /// On the way to the destination. The phase ends with the step that
+    /// arrives and unloads.
+    Traveling,
+    /// Unloads on the next step: arriving, or waiting at the destination
+    /// for room for the rest of its cargo.
+    Unloading,
+    /// Empty on the way back. The phase ends with the step that reaches the
+    /// source; the vehicle can load on the step after.
+    Returning,
+}
+
+// ---------------------------------------------------------------------------
+// Transport item view
+// ---------------------------------------------------------------------------
+
+/// A single item riding on an [`ItemTransport`](crate::transport::ItemTransport)
+/// edge, for rendering items that slide along belts.
+///
+/// `instance_id` is assigned when the item enters the belt and stays with it
+/// until delivery (and across save/load), so renderers can tween one sprite
+/// per instance instead of respawning every frame.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TransportItemView {
+    /// Engine-unique id of this item instance. `0` if the item entered the
+    /// belt untracked.
+    pub instance_id: u64,
+    /// Item type carried in the slot.
+    pub item_type: ItemTypeId,
+    /// Lane index (0-based).
+    pub lane: u8,
+    /// Position along the edge: 0 at the source end, 1 at the destination end.
+    pub position: Fixed64,
+}
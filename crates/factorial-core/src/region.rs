@@ -0,0 +1,480 @@
+//! Serialization of a region of the engine.
+//!
+//! Large worlds streamed in chunks save and load one area at a time.
+//! [`Engine::serialize_region`] writes a set of nodes -- their processors,
+//! processor state, inventories, modifiers, junctions and the rest of their
+//! per-node state -- together with every edge that runs between two of them,
+//! transport state included. [`Engine::merge_region`] grafts such a blob
+//! into an engine: the nodes and edges are added as new entities and the
+//! returned [`RegionMerge`] maps each saved id to the id it was given.
+//!
+//! Edges with an endpoint outside the region are not saved; the host
+//! reconnects a merged region to its neighbours itself. Edge groups and
+//! other engine-wide state (recipe overrides, item categories, crafting
+//! queues) are not part of a region.
+
+use std::collections::BTreeSet;
+
+use serde::{Deserialize, Serialize};
+
+use crate::engine::Engine;
+use crate::graph::{EdgeData, MutationRejectReason, NodeData};
+use crate::id::{EdgeId, ItemCategoryId, ItemTypeId, NodeId};
+use crate::item::Inventory;
+use crate::junction::{Junction, JunctionState, MergePriority};
+use crate::processor::{FluidPorts, InputMode, Modifier, Processor, ProcessorState};
+use crate::richness::SourceSite;
+use crate::rng::SimRng;
+use crate::serialize::{DeserializeError, FORMAT_VERSION, SerializeError, SnapshotHeader};
+use crate::transport::{Transport, TransportState};
+use crate::warmup::Warmup;
+
+/// Magic number identifying a region blob.
+pub const REGION_MAGIC: u32 = 0xFAC7_0005;
+
+/// One saved node and its per-node state.
+#[derive(Debug, Serialize, Deserialize)]
+struct RegionNode {
+    id: NodeId,
+    data: NodeData,
+    processor: Option<Processor>,
+    state: Option<ProcessorState>,
+    input: Option<Inventory>,
+    output: Option<Inventory>,
+    modifiers: Option<Vec<Modifier>>,
+    input_mode: Option<InputMode>,
+    junction: Option<Junction>,
+    junction_state: Option<JunctionState>,
+    merge_priority: Option<MergePriority>,
+    merge_cursor: Option<u32>,
+    rng: Option<SimRng>,
+    fluid_ports: Option<FluidPorts>,
+    ghost: bool,
+    demand_category: Option<ItemCategoryId>,
+    source_site: Option<SourceSite>,
+    warmup: Option<Warmup>,
+    user_data: Option<u64>,
+}
+
+/// One saved edge between two saved nodes.
+#[derive(Debug, Serialize, Deserialize)]
+struct RegionEdge {
+    id: EdgeId,
+    data: EdgeData,
+    transport: Option<Transport>,
+    state: Option<TransportState>,
+    ghost: bool,
+    category_filter: Option<ItemCategoryId>,
+    category_pick: Option<ItemTypeId>,
+}
+
+/// The wire format of a region.
+#[derive(Debug, Serialize, Deserialize)]
+struct RegionSnapshot {
+    header: SnapshotHeader,
+    nodes: Vec<RegionNode>,
+    edges: Vec<RegionEdge>,
+}
+
+/// The ids a merged region was given, from [`Engine::merge_region`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RegionMerge {
+    /// Each saved node id with the node it became, in ascending saved id.
+    pub nodes: Vec<(NodeId, NodeId)>,
+    /// Each saved edge id with the edge it became, in ascending saved id.
+    pub edges: Vec<(EdgeId, EdgeId)>,
+    /// Saved nodes the engine refused to add (in strict mode, buildings
+    /// still locked by research). Their edges are not added either.
+    pub rejected: Vec<(NodeId, MutationRejectReason)>,
+}
+
+impl RegionMerge {
+    /// The node a saved node id became, if it was added.
+    pub fn resolve_node(&self, saved: NodeId) -> Option<NodeId> {
+        self.nodes
+            .iter()
+            .find(|(s, _)| *s == saved)
+            .map(|(_, id)| *id)
+    }
+
+    /// The edge a saved edge id became, if it was added.
+    pub fn resolve_edge(&self, saved: EdgeId) -> Option<EdgeId> {
+        self.edges
+            .iter()
+            .find(|(s, _)| *s == saved)
+            .map(|(_, id)| *id)
+    }
+}
+
+impl Engine {
+    /// Serialize `nodes` and the edges between them to a binary blob for
+    /// [`merge_region`](Self::merge_region). Ids that are not in the graph
+    /// are ignored, as are edges leading out of the region.
+    pub fn serialize_region(&self, nodes: &[NodeId]) -> Result<Vec<u8>, SerializeError> {
+        if self.tick_slice.is_some() {
+            return Err(SerializeError::TickInProgress);
+        }
+        let region: BTreeSet<NodeId> = nodes
+            .iter()
+            .copied()
+            .filter(|&node| self.graph.contains_node(node))
+            .collect();
+        let saved_nodes = region
+            .iter()
+            .filter_map(|&node| {
+                Some(RegionNode {
+                    id: node,
+                    data: self.graph.get_node(node)?.clone(),
+                    processor: self.processors.get(node).cloned(),
+                    state: self.processor_states.get(node).cloned(),
+                    input: self.inputs.get(node).cloned(),
+                    output: self.outputs.get(node).cloned(),
+                    modifiers: self.modifiers.get(node).cloned(),
+                    input_mode: self.input_modes.get(node).copied(),
+                    junction: self.junctions.get(node).cloned(),
+                    junction_state: self.junction_states.get(node).cloned(),
+                    merge_priority: self.merge_priorities.get(node).cloned(),
+                    merge_cursor: self.merge_cursors.get(node).copied(),
+                    rng: self.node_rngs.get(node).cloned(),
+                    fluid_ports: self.fluid_ports.get(node).cloned(),
+                    ghost: self.ghost_nodes.contains_key(node),
+                    demand_category: self.demand_categories.get(node).copied(),
+                    source_site: self.source_sites.get(node).copied(),
+                    warmup: self.warmups.get(node).copied(),
+                    user_data: self.node_user_data.get(node).copied(),
+                })
+            })
+            .collect();
+        let mut edges: Vec<(EdgeId, &EdgeData)> = self
+            .graph
+            .edges()
+            .filter(|(_, data)| region.contains(&data.from) && region.contains(&data.to))
+            .collect();
+        edges.sort_by_key(|&(edge, _)| edge);
+        let saved_edges = edges
+            .into_iter()
+            .map(|(edge, data)| RegionEdge {
+                id: edge,
+                data: data.clone(),
+                transport: self.transports.get(edge).cloned(),
+                state: self.transport_states.get(edge).cloned(),
+                ghost: self.ghost_edges.contains_key(edge),
+                category_filter: self.edge_category_filters.get(edge).copied(),
+                category_pick: self.category_picks.get(edge).copied(),
+            })
+            .collect();
+        let snapshot = RegionSnapshot {
+            header: SnapshotHeader {
+                magic: REGION_MAGIC,
+                version: FORMAT_VERSION,
+                tick: self.sim_state.tick,
+            },
+            nodes: saved_nodes,
+            edges: saved_edges,
+        };
+        bitcode::serialize(&snapshot).map_err(|e| SerializeError::Encode(e.to_string()))
+    }
+
+    /// Graft a region written by [`serialize_region`](Self::serialize_region)
+    /// into this engine. Every saved node and edge is added as a new entity
+    /// with its saved state; the result maps saved ids to new ones.
+    ///
+    /// The additions go through [`apply_mutations`](Self::apply_mutations),
+    /// so they emit the usual `NodeAdded` and `EdgeAdded` events and any
+    /// mutations the host had already queued are applied with them.
+    pub fn merge_region(&mut self, data: &[u8]) -> Result<RegionMerge, DeserializeError> {
+        let snapshot: RegionSnapshot =
+            bitcode::deserialize(data).map_err(|e| DeserializeError::Decode(e.to_string()))?;
+        let header = &snapshot.header;
+        if header.magic != REGION_MAGIC {
+            return Err(DeserializeError::InvalidMagic(header.magic));
+        }
+        if header.version > FORMAT_VERSION {
+            return Err(DeserializeError::FutureVersion(header.version));
+        }
+        if header.version < FORMAT_VERSION {
+            return Err(DeserializeError::UnsupportedVersion(header.version));
+        }
+
+        let pending: Vec<_> = snapshot
+            .nodes
+            .iter()
+            .map(|saved| {
+                self.graph
+                    .queue_add_node_with_flags(saved.data.building_type, saved.data.flags)
+            })
+            .collect();
+        let added = self.apply_mutations();
+        let mut merge = RegionMerge::default();
+        for (saved, pending) in snapshot.nodes.into_iter().zip(pending) {
+            match added.resolve_node(pending) {
+                Some(node) => {
+                    merge.nodes.push((saved.id, node));
+                    self.restore_region_node(node, saved);
+                }
+                None => {
+                    let reason = added
+                        .rejected
+                        .iter()
+                        .find(|(p, _)| *p == pending)
+                        .map_or(MutationRejectReason::BuildingLocked, |&(_, r)| r);
+                    merge.rejected.push((saved.id, reason));
+                }
+            }
+        }
+
+        let mut queued = Vec::new();
+        for saved in snapshot.edges {
+            let (Some(from), Some(to)) = (
+                merge.resolve_node(saved.data.from),
+                merge.resolve_node(saved.data.to),
+            ) else {
+                continue;
+            };
+            let pending = self
+                .graph
+                .queue_connect_filtered(from, to, saved.data.item_filter);
+            queued.push((saved, pending));
+        }
+        let connected = self.apply_mutations();
+        for (saved, pending) in queued {
+            if let Some(edge) = connected.resolve_edge(pending) {
+                merge.edges.push((saved.id, edge));
+                self.restore_region_edge(edge, saved);
+            }
+        }
+        // Priority lists name saved edges; point them at the merged ones.
+        for &(_, node) in &merge.nodes {
+            if let Some(MergePriority::Priority(edges)) = self.merge_priorities.get_mut(node) {
+                *edges = edges
+                    .iter()
+                    .filter_map(|&saved| merge.resolve_edge(saved))
+                    .collect();
+            }
+        }
+
+        self.rebuild_full_slots();
+        self.hash_cache_cold = true;
+        self.dirty.mark_all_partitions();
+        Ok(merge)
+    }
+
+    /// Install a saved node's state on the freshly added `node`.
+    fn restore_region_node(&mut self, node: NodeId, saved: RegionNode) {
+        if let Some(processor) = saved.processor {
+            self.set_processor(node, processor);
+        }
+        if let Some(state) = saved.state {
+            self.processor_states.insert(node, state);
+        }
+        match saved.rng {
+            Some(rng) => {
+                self.node_rngs.insert(node, rng);
+            }
+            None => {
+                self.node_rngs.remove(node);
+            }
+        }
+        if let Some(input) = saved.input {
+            self.set_input_inventory(node, input);
+        }
+        if let Some(output) = saved.output {
+            self.set_output_inventory(node, output);
+        }
+        if let Some(modifiers) = saved.modifiers {
+            self.set_modifiers(node, modifiers);
+        }
+        if let Some(mode) = saved.input_mode {
+            self.set_input_mode(node, mode);
+        }
+        if let Some(junction) = saved.junction {
+            self.set_junction(node, junction);
+        }
+        if let Some(state) = saved.junction_state {
+            self.junction_states.insert(node, state);
+        }
+        if let Some(priority) = saved.merge_priority {
+            self.merge_priorities.insert(node, priority);
+        }
+        if let Some(cursor) = saved.merge_cursor {
+            self.merge_cursors.insert(node, cursor);
+        }
+        if let Some(ports) = saved.fluid_ports {
+            self.fluid_ports.insert(node, ports);
+        }
+        if saved.ghost {
+            self.ghost_nodes.insert(node, ());
+        }
+        if let Some(category) = saved.demand_category {
+            self.demand_categories.insert(node, category);
+        }
+        if let Some(site) = saved.source_site {
+            self.source_sites.insert(node, site);
+        }
+        if let Some(warmup) = saved.warmup {
+            self.warmups.insert(node, warmup);
+        }
+        if let Some(data) = saved.user_data {
+            self.node_user_data.insert(node, data);
+        }
+        self.hash_dirty_nodes.push(node);
+        self.dirty.mark_node(node);
+    }
+
+    /// Install a saved edge's transport and state on the freshly added
+    /// `edge`, reserving the instance ids of items on a belt.
+    fn restore_region_edge(&mut self, edge: EdgeId, saved: RegionEdge) {
+        if let Some(transport) = saved.transport {
+            self.set_transport(edge, transport);
+        }
+        if let Some(state) = saved.state {
+            if let TransportState::Item(belt) = &state
+                && let Some(max_id) = belt.items().map(|(_, i)| i.instance_id).max()
+            {
+                self.next_item_instance_id = self.next_item_instance_id.max(max_id + 1);
+            }
+            self.transport_states.insert(edge, state);
+        }
+        if saved.ghost {
+            self.ghost_edges.insert(edge, ());
+        }
+        if let Some(category) = saved.category_filter {
+            self.edge_category_filters.insert(edge, category);
+        }
+        if let Some(item) = saved.category_pick {
+            self.category_picks.insert(edge, item);
+        }
+        self.dirty.mark_edge(edge);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sim::SimulationStrategy;
+    use crate::test_utils::*;
+
+    /// Mine -> smelter -> assembler on belts, run until items are in
+    /// flight everywhere.
+    fn running_line() -> (Engine, [NodeId; 3]) {
+        let mut engine = Engine::new(SimulationStrategy::Tick);
+        let mine = add_node(&mut engine, make_source(iron(), 2.0), 0, 20);
+        let smelter = add_node(
+            &mut engine,
+            make_recipe(vec![(iron(), 1)], vec![(gear(), 1)], 3),
+            10,
+            10,
+        );
+        let assembler = add_node(
+            &mut engine,
+            make_recipe(vec![(gear(), 2)], vec![(copper(), 1)], 7),
+            10,
+            10,
+        );
+        connect(&mut engine, mine, smelter, make_item_transport(4));
+        connect(&mut engine, smelter, assembler, make_item_transport(6));
+        for _ in 0..25 {
+            engine.step();
+        }
+        (engine, [mine, smelter, assembler])
+    }
+
+    #[test]
+    fn removed_region_merges_back_with_its_config_and_contents() {
+        let (mut engine, [mine, smelter, assembler]) = running_line();
+        let belt = engine
+            .graph
+            .edges()
+            .find(|(_, data)| data.from == smelter)
+            .map(|(edge, _)| edge)
+            .unwrap();
+        let snapshot = |engine: &Engine, node: NodeId| {
+            (
+                format!("{:?}", engine.get_processor(node)),
+                engine.get_processor_state(node).cloned(),
+                engine.get_input_inventory(node).cloned(),
+                engine.get_output_inventory(node).cloned(),
+            )
+        };
+        let before = [snapshot(&engine, smelter), snapshot(&engine, assembler)];
+        let belt_before = engine.serialize_transport_state(belt).unwrap().unwrap();
+        assert!(engine.get_input_inventory(assembler).unwrap().total() > 0);
+        engine.set_merge_priority(assembler, MergePriority::Priority(vec![belt]));
+
+        let data = engine.serialize_region(&[assembler, smelter]).unwrap();
+        engine.graph.queue_remove_node(smelter);
+        engine.graph.queue_remove_node(assembler);
+        engine.apply_mutations();
+        assert_eq!(engine.graph.node_count(), 1);
+
+        let merge = engine.merge_region(&data).unwrap();
+        assert_eq!(merge.nodes.len(), 2);
+        assert!(merge.rejected.is_empty());
+        let new_smelter = merge.resolve_node(smelter).unwrap();
+        let new_assembler = merge.resolve_node(assembler).unwrap();
+        assert_eq!(snapshot(&engine, new_smelter), before[0]);
+        assert_eq!(snapshot(&engine, new_assembler), before[1]);
+
+        // Only the edge inside the region comes back, with its items.
+        assert_eq!(merge.edges.len(), 1);
+        let new_belt = merge.resolve_edge(belt).unwrap();
+        assert_eq!(
+            engine.serialize_transport_state(new_belt).unwrap().unwrap(),
+            belt_before
+        );
+        let data = engine.graph.get_edge(new_belt).unwrap();
+        assert_eq!((data.from, data.to), (new_smelter, new_assembler));
+        assert_eq!(
+            engine.merge_priority(new_assembler),
+            Some(&MergePriority::Priority(vec![new_belt]))
+        );
+        assert_eq!(engine.graph.edge_count(), 1);
+        assert!(engine.graph.get_node(mine).is_some());
+    }
+
+    #[test]
+    fn merged_region_simulates_like_the_original() {
+        let (mut original, [_, smelter, assembler]) = running_line();
+        let mut copy = Engine::new(SimulationStrategy::Tick);
+        let data = original.serialize_region(&[smelter, assembler]).unwrap();
+        let merge = copy.merge_region(&data).unwrap();
+        let new_assembler = merge.resolve_node(assembler).unwrap();
+
+        // Cut the original off from its mine so both run on what they hold.
+        let feed: Vec<EdgeId> = original
+            .graph
+            .edges()
+            .filter(|(_, data)| data.to == smelter)
+            .map(|(edge, _)| edge)
+            .collect();
+        for edge in feed {
+            original.graph.queue_disconnect(edge);
+        }
+        original.apply_mutations();
+        for _ in 0..40 {
+            original.step();
+            copy.step();
+        }
+        assert_eq!(
+            original.get_output_inventory(assembler),
+            copy.get_output_inventory(new_assembler)
+        );
+        assert!(
+            copy.get_output_inventory(new_assembler)
+                .unwrap()
+                .count_of(copper())
+                > 0
+        );
+    }
+
+    #[test]
+    fn merge_rejects_full_snapshots() {
+        let (engine, _) = running_line();
+        let full = engine.serialize().unwrap();
+        assert!(
+            Engine::new(SimulationStrategy::Tick)
+                .merge_region(&full)
+                .is_err()
+        );
+    }
+}
@@ -54,11 +54,64 @@ pub struct RecipeDef {
     pub duration: Ticks,
 }
 
+impl RecipeDef {
+    /// This recipe as a processor recipe. Registry recipes have no fluids.
+    pub fn to_fixed_recipe(&self) -> crate::processor::FixedRecipe {
+        use crate::processor::{FixedRecipe, RecipeInput, RecipeOutput};
+        FixedRecipe {
+            inputs: self
+                .inputs
+                .iter()
+                .map(|e| RecipeInput {
+                    item_type: e.item,
+                    quantity: e.quantity,
+                    consumed: e.consumed,
+                })
+                .collect(),
+            outputs: self
+                .outputs
+                .iter()
+                .map(|e| RecipeOutput {
+                    item_type: e.item,
+                    quantity: e.quantity,
+                    bonus: None,
+                    probability: None,
+                })
+                .collect(),
+            duration: u32::try_from(self.duration).unwrap_or(u32::MAX),
+            fluid_inputs: Vec::new(),
+            fluid_outputs: Vec::new(),
+            duration_per_unit: None,
+        }
+    }
+}
+
 /// A building template definition.
 #[derive(Debug, Clone)]
 pub struct BuildingTemplateDef {
     pub name: String,
     pub recipe: Option<RecipeId>,
+    /// Per-slot capacity of the single-slot input and output inventories
+    /// given to nodes of this type when they are created. `None` leaves the
+    /// inventories to the caller.
+    pub inventory_capacity: Option<u32>,
+    /// Items placed in a new node's input inventory on creation.
+    pub initial_inputs: Vec<(ItemTypeId, u32)>,
+    /// Items placed in a new node's output inventory on creation.
+    pub initial_outputs: Vec<(ItemTypeId, u32)>,
+}
+
+impl BuildingTemplateDef {
+    /// A template with no inventories or starting contents.
+    pub fn new(name: &str, recipe: Option<RecipeId>) -> Self {
+        Self {
+            name: name.to_string(),
+            recipe,
+            inventory_capacity: None,
+            initial_inputs: Vec::new(),
+            initial_outputs: Vec::new(),
+        }
+    }
 }
 
 /// Builder for constructing an immutable Registry.
@@ -123,12 +176,15 @@ impl RegistryBuilder {
 
     /// Phase 1: Register a building template. Returns its ID.
     pub fn register_building(&mut self, name: &str, recipe: Option<RecipeId>) -> BuildingTypeId {
+        self.register_building_template(BuildingTemplateDef::new(name, recipe))
+    }
+
+    /// Phase 1: Register a fully specified building template, including its
+    /// inventories and starting contents. Returns its ID.
+    pub fn register_building_template(&mut self, template: BuildingTemplateDef) -> BuildingTypeId {
         let id = BuildingTypeId(self.buildings.len() as u32);
-        self.buildings.push(BuildingTemplateDef {
-            name: name.to_string(),
-            recipe,
-        });
-        self.building_name_to_id.insert(name.to_string(), id);
+        self.building_name_to_id.insert(template.name.clone(), id);
+        self.buildings.push(template);
         id
     }
 
@@ -183,6 +239,18 @@ impl RegistryBuilder {
                 }
             }
         }
+        // Validate: starting contents must reference known items
+        for building in &self.buildings {
+            for &(item, _) in building
+                .initial_inputs
+                .iter()
+                .chain(&building.initial_outputs)
+            {
+                if item.0 as usize >= self.items.len() {
+                    return Err(RegistryError::InvalidItemRef(item));
+                }
+            }
+        }
 
         Ok(Registry {
             items: self.items,
@@ -11,11 +11,29 @@ use crate::processor::{Modifier, Processor};
 use crate::serialize::{DeserializeError, SerializeError};
 use crate::transport::Transport;
 
+mod v1;
+
+/// Magic number identifying a serialized [`ReplayLog`].
+pub const REPLAY_LOG_MAGIC: u32 = 0xFAC7_0006;
+
+/// Current replay log format version. Increment when breaking the wire format.
+///
+/// Version 1 logs, written before the header existed, still load; see
+/// [`ReplayLog::deserialize`].
+pub const REPLAY_LOG_VERSION: u32 = 2;
+
+/// Length of the fixed header written by [`ReplayLog::serialize`]:
+/// little-endian `u32` magic followed by little-endian `u32` version.
+const REPLAY_LOG_HEADER_LEN: usize = 8;
+
 // ---------------------------------------------------------------------------
 // ReplayCommand
 // ---------------------------------------------------------------------------
 
 /// A command that can be recorded and replayed.
+///
+/// The queue commands carry their mutation lane, so a replay applies them in
+/// the same order as the recording however they were interleaved.
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub enum ReplayCommand {
     Advance {
@@ -24,16 +42,20 @@ pub enum ReplayCommand {
     Step,
     QueueAddNode {
         building_type: BuildingTypeId,
+        lane: u8,
     },
     QueueRemoveNode {
         node: NodeId,
+        lane: u8,
     },
     QueueConnect {
         from: NodeId,
         to: NodeId,
+        lane: u8,
     },
     QueueDisconnect {
         edge: EdgeId,
+        lane: u8,
     },
     SetProcessor {
         node: NodeId,
@@ -117,14 +139,34 @@ impl ReplayLog {
         self.commands.len()
     }
 
-    /// Serialize the replay log to bytes (using bitcode).
+    /// Serialize the replay log to bytes: a version header followed by a
+    /// bitcode payload.
     pub fn serialize(&self) -> Result<Vec<u8>, SerializeError> {
-        bitcode::serialize(self).map_err(|e| SerializeError::Encode(e.to_string()))
+        let payload =
+            bitcode::serialize(self).map_err(|e| SerializeError::Encode(e.to_string()))?;
+        let mut data = Vec::with_capacity(REPLAY_LOG_HEADER_LEN + payload.len());
+        data.extend_from_slice(&REPLAY_LOG_MAGIC.to_le_bytes());
+        data.extend_from_slice(&REPLAY_LOG_VERSION.to_le_bytes());
+        data.extend_from_slice(&payload);
+        Ok(data)
     }
 
     /// Deserialize a replay log from bytes.
+    ///
+    /// Data without the header is decoded as a version 1 log, whose queue
+    /// commands had no lane; they are replayed in lane 0.
     pub fn deserialize(data: &[u8]) -> Result<Self, DeserializeError> {
-        bitcode::deserialize(data).map_err(|e| DeserializeError::Decode(e.to_string()))
+        if data.len() < REPLAY_LOG_HEADER_LEN
+            || u32::from_le_bytes([data[0], data[1], data[2], data[3]]) != REPLAY_LOG_MAGIC
+        {
+            return v1::decode(data);
+        }
+        let version = u32::from_le_bytes([data[4], data[5], data[6], data[7]]);
+        if version != REPLAY_LOG_VERSION {
+            return Err(DeserializeError::UnsupportedReplayVersion(version));
+        }
+        bitcode::deserialize(&data[REPLAY_LOG_HEADER_LEN..])
+            .map_err(|e| DeserializeError::Decode(e.to_string()))
     }
 }
 
@@ -156,17 +198,20 @@ fn apply_command(engine: &mut Engine, cmd: &ReplayCommand) {
         ReplayCommand::Step => {
             engine.step();
         }
-        ReplayCommand::QueueAddNode { building_type } => {
-            engine.graph.queue_add_node(*building_type);
+        ReplayCommand::QueueAddNode {
+            building_type,
+            lane,
+        } => {
+            engine.graph.queue_add_node_in_lane(*lane, *building_type);
         }
-        ReplayCommand::QueueRemoveNode { node } => {
-            engine.graph.queue_remove_node(*node);
+        ReplayCommand::QueueRemoveNode { node, lane } => {
+            engine.graph.queue_remove_node_in_lane(*lane, *node);
         }
-        ReplayCommand::QueueConnect { from, to } => {
-            engine.graph.queue_connect(*from, *to);
+        ReplayCommand::QueueConnect { from, to, lane } => {
+            engine.graph.queue_connect_in_lane(*lane, *from, *to);
         }
-        ReplayCommand::QueueDisconnect { edge } => {
-            engine.graph.queue_disconnect(*edge);
+        ReplayCommand::QueueDisconnect { edge, lane } => {
+            engine.graph.queue_disconnect_in_lane(*lane, *edge);
         }
         ReplayCommand::SetProcessor { node, processor } => {
             engine.set_processor(*node, processor.clone());
@@ -377,6 +422,7 @@ mod tests {
         // Queue add node, apply mutations via step
         log.record(ReplayCommand::QueueAddNode {
             building_type: crate::id::BuildingTypeId(0),
+            lane: 0,
         });
         log.record(ReplayCommand::ApplyMutations);
         log.record(ReplayCommand::Step);
@@ -386,6 +432,78 @@ mod tests {
         assert_eq!(replayed.sim_state.tick, 1);
     }
 
+    #[test]
+    fn replay_applies_recorded_lanes() {
+        let engine = Engine::new(SimulationStrategy::Tick);
+        let mut log = ReplayLog::new(&engine).unwrap();
+        for (lane, building) in [(1, 1), (0, 2)] {
+            log.record(ReplayCommand::QueueAddNode {
+                building_type: crate::id::BuildingTypeId(building),
+                lane,
+            });
+        }
+        log.record(ReplayCommand::ApplyMutations);
+
+        let restored = ReplayLog::deserialize(&log.serialize().unwrap()).unwrap();
+        let replayed = replay(&restored).unwrap();
+        let buildings: Vec<u32> = replayed
+            .graph
+            .nodes()
+            .map(|(_, data)| data.building_type.0)
+            .collect();
+        assert_eq!(buildings, vec![2, 1]);
+    }
+
+    /// A log recorded before the header and mutation lanes existed.
+    const V1_REPLAY: &[u8] = include_bytes!("../tests/fixtures/v1_replay.bin");
+
+    #[test]
+    fn v1_replay_log_loads() {
+        let log = ReplayLog::deserialize(V1_REPLAY).unwrap();
+        assert_eq!(log.command_count(), 27);
+        assert_eq!(log.hash_checkpoints.len(), 7);
+        let lanes: Vec<u8> = log
+            .commands
+            .iter()
+            .filter_map(|cmd| match cmd {
+                ReplayCommand::QueueAddNode { lane, .. }
+                | ReplayCommand::QueueRemoveNode { lane, .. }
+                | ReplayCommand::QueueConnect { lane, .. }
+                | ReplayCommand::QueueDisconnect { lane, .. } => Some(*lane),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(lanes, vec![0; 7]);
+
+        let replayed = replay(&log).unwrap();
+        assert_eq!(replayed.sim_state.tick, 7);
+        assert_eq!(replayed.node_count(), 2);
+        assert_eq!(replayed.edge_count(), 1);
+        let consumer = replayed.graph.nodes().nth(1).unwrap().0;
+        let output = replayed.get_output_inventory(consumer).unwrap();
+        assert_eq!(output.output_slots[0].quantity(gear()), 6);
+
+        let bytes = log.serialize().unwrap();
+        assert_eq!(&bytes[..4], &REPLAY_LOG_MAGIC.to_le_bytes());
+        let restored = ReplayLog::deserialize(&bytes).unwrap();
+        assert_eq!(restored.command_count(), 27);
+        assert_eq!(
+            replay(&restored).unwrap().state_hash(),
+            replayed.state_hash()
+        );
+    }
+
+    #[test]
+    fn replay_log_from_unknown_version_is_rejected() {
+        let engine = make_test_engine();
+        let mut bytes = ReplayLog::new(&engine).unwrap().serialize().unwrap();
+        bytes[4..8].copy_from_slice(&(REPLAY_LOG_VERSION + 1).to_le_bytes());
+        assert!(matches!(
+            ReplayLog::deserialize(&bytes),
+            Err(DeserializeError::UnsupportedReplayVersion(v)) if v == REPLAY_LOG_VERSION + 1
+        ));
+    }
+
     // -----------------------------------------------------------------------
     // Test 9: Replay set processor and transport
     // -----------------------------------------------------------------------
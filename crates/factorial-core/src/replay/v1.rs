@@ -0,0 +1,125 @@
+//! Decoding of version 1 replay logs.
+//!
+//! Version 1 logs were a bare bitcode [`ReplayLog`] with no header, written
+//! before queue commands carried a mutation lane. Their processors,
+//! inventories and transports use the version 3 snapshot layout. Queue
+//! commands are mapped to lane 0, the lane the plain `queue_*` methods use,
+//! so the log replays in its recorded order.
+
+use serde::Deserialize;
+
+use super::{ReplayCommand, ReplayLog};
+use crate::id::{BuildingTypeId, EdgeId, NodeId};
+use crate::processor::Modifier;
+use crate::serialize::DeserializeError;
+use crate::serialize::v3::{InventoryV3, ProcessorV3, TransportV3};
+
+/// Decode a version 1 replay log.
+pub(super) fn decode(data: &[u8]) -> Result<ReplayLog, DeserializeError> {
+    let log: ReplayLogV1 =
+        bitcode::deserialize(data).map_err(|e| DeserializeError::Decode(e.to_string()))?;
+    Ok(ReplayLog {
+        initial_snapshot: log.initial_snapshot,
+        commands: log
+            .commands
+            .into_iter()
+            .map(ReplayCommandV1::upgrade)
+            .collect(),
+        hash_checkpoints: log.hash_checkpoints,
+    })
+}
+
+#[derive(Deserialize)]
+struct ReplayLogV1 {
+    initial_snapshot: Vec<u8>,
+    commands: Vec<ReplayCommandV1>,
+    hash_checkpoints: Vec<(usize, u64)>,
+}
+
+#[derive(Deserialize)]
+enum ReplayCommandV1 {
+    Advance {
+        dt: u64,
+    },
+    Step,
+    QueueAddNode {
+        building_type: BuildingTypeId,
+    },
+    QueueRemoveNode {
+        node: NodeId,
+    },
+    QueueConnect {
+        from: NodeId,
+        to: NodeId,
+    },
+    QueueDisconnect {
+        edge: EdgeId,
+    },
+    SetProcessor {
+        node: NodeId,
+        processor: ProcessorV3,
+    },
+    SetInputInventory {
+        node: NodeId,
+        inventory: InventoryV3,
+    },
+    SetOutputInventory {
+        node: NodeId,
+        inventory: InventoryV3,
+    },
+    SetModifiers {
+        node: NodeId,
+        modifiers: Vec<Modifier>,
+    },
+    SetTransport {
+        edge: EdgeId,
+        transport: TransportV3,
+    },
+    ApplyMutations,
+}
+
+impl ReplayCommandV1 {
+    fn upgrade(self) -> ReplayCommand {
+        match self {
+            ReplayCommandV1::Advance { dt } => ReplayCommand::Advance { dt },
+            ReplayCommandV1::Step => ReplayCommand::Step,
+            ReplayCommandV1::QueueAddNode { building_type } => ReplayCommand::QueueAddNode {
+                building_type,
+                lane: 0,
+            },
+            ReplayCommandV1::QueueRemoveNode { node } => {
+                ReplayCommand::QueueRemoveNode { node, lane: 0 }
+            }
+            ReplayCommandV1::QueueConnect { from, to } => {
+                ReplayCommand::QueueConnect { from, to, lane: 0 }
+            }
+            ReplayCommandV1::QueueDisconnect { edge } => {
+                ReplayCommand::QueueDisconnect { edge, lane: 0 }
+            }
+            ReplayCommandV1::SetProcessor { node, processor } => ReplayCommand::SetProcessor {
+                node,
+                processor: processor.upgrade(),
+            },
+            ReplayCommandV1::SetInputInventory { node, inventory } => {
+                ReplayCommand::SetInputInventory {
+                    node,
+                    inventory: inventory.upgrade(),
+                }
+            }
+            ReplayCommandV1::SetOutputInventory { node, inventory } => {
+                ReplayCommand::SetOutputInventory {
+                    node,
+                    inventory: inventory.upgrade(),
+                }
+            }
+            ReplayCommandV1::SetModifiers { node, modifiers } => {
+                ReplayCommand::SetModifiers { node, modifiers }
+            }
+            ReplayCommandV1::SetTransport { edge, transport } => ReplayCommand::SetTransport {
+                edge,
+                transport: transport.upgrade(),
+            },
+            ReplayCommandV1::ApplyMutations => ReplayCommand::ApplyMutations,
+        }
+    }
+}
@@ -0,0 +1,304 @@
+//! Procedural ore richness.
+//!
+//! Maps generated from a seed want resource nodes whose output follows the
+//! terrain rather than being tuned one by one. [`Engine::set_richness_provider`]
+//! installs a seeded noise field over integer map coordinates, and
+//! [`Engine::add_source_at`] turns a node into a [`SourceProcessor`] whose
+//! rate and deposit size are read from the field at a coordinate.
+//!
+//! # The noise
+//!
+//! [`richness_at`] is value noise on a square lattice, computed entirely in
+//! integer and [`Fixed64`] arithmetic so it is identical on every platform:
+//!
+//! 1. Each lattice corner `(cx, cy)` gets a value in `[0, 1)`: the upper 32
+//!    bits of the first [`SimRng`] output seeded with
+//!    `seed ^ ((cx as u32 as u64) << 32 | cy as u32 as u64)`, read as a
+//!    Q32.32 fraction.
+//! 2. A coordinate lies in the cell whose lower corner is
+//!    `(x.div_euclid(cell_size), y.div_euclid(cell_size))`, at offset
+//!    `rem_euclid(cell_size) / cell_size` along each axis.
+//! 3. Its richness is the bilinear interpolation of the cell's four corner
+//!    values at that offset.
+//!
+//! Richness is uniform in `[0, 1)` at lattice points and smooths toward the
+//! middle between them, so larger cells make broader, gentler ore patches.
+//!
+//! # Stored values
+//!
+//! The rate and deposit a source gets are computed once, when it is added,
+//! and stored in its processor like any other source's. Changing the
+//! provider later affects only sources added afterwards. The coordinates and
+//! richness are kept as a [`SourceSite`] for hosts to display. Providers and
+//! sites are part of snapshots and of the state hash; an engine that never
+//! installs a provider hashes as it did before providers existed.
+
+use crate::dirty::DirtyTracker;
+use crate::engine::Engine;
+use crate::fixed::Fixed64;
+use crate::id::{ItemTypeId, NodeId};
+use crate::processor::{Depletion, Processor, SourceProcessor};
+use crate::rng::SimRng;
+
+/// How richness maps to a source's configuration.
+///
+/// Each value is interpolated linearly from its minimum at richness 0 to its
+/// maximum at richness 1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct RichnessCurve {
+    /// Width of a noise cell in map units. `0` is treated as `1`, which
+    /// gives every coordinate an independent value.
+    pub cell_size: u32,
+    /// Base rate, in items per tick, at richness 0.
+    pub min_rate: Fixed64,
+    /// Base rate at richness 1.
+    pub max_rate: Fixed64,
+    /// Deposit size at richness 0.
+    pub min_amount: Fixed64,
+    /// Deposit size at richness 1. When zero or negative, deposits never
+    /// deplete.
+    pub max_amount: Fixed64,
+}
+
+/// The noise field installed by [`Engine::set_richness_provider`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct RichnessProvider {
+    /// Seed of the noise field.
+    pub seed: u64,
+    /// How richness maps to source configuration.
+    pub curve: RichnessCurve,
+}
+
+/// Where a source added by [`Engine::add_source_at`] sits, and what the
+/// provider read there.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct SourceSite {
+    pub x: i32,
+    pub y: i32,
+    /// Richness at `(x, y)`, in `[0, 1)`.
+    pub richness: Fixed64,
+}
+
+/// Richness of the field with `seed` and `cell_size` at `(x, y)`, in
+/// `[0, 1)`. See the [module documentation](self) for the algorithm.
+pub fn richness_at(seed: u64, cell_size: u32, x: i32, y: i32) -> Fixed64 {
+    let cell = i64::from(cell_size.max(1));
+    let (x, y) = (i64::from(x), i64::from(y));
+    let (cx, cy) = (x.div_euclid(cell), y.div_euclid(cell));
+    let size = Fixed64::from_num(cell);
+    let tx = Fixed64::from_num(x.rem_euclid(cell)) / size;
+    let ty = Fixed64::from_num(y.rem_euclid(cell)) / size;
+
+    let corner = |cx: i64, cy: i64| {
+        let key = (u64::from(cx as u32) << 32) ^ u64::from(cy as u32);
+        Fixed64::from_bits((SimRng::new(seed ^ key).next_u64() >> 32) as i64)
+    };
+    let lerp = |a: Fixed64, b: Fixed64, t: Fixed64| a + (b - a) * t;
+    let bottom = lerp(corner(cx, cy), corner(cx + 1, cy), tx);
+    let top = lerp(corner(cx, cy + 1), corner(cx + 1, cy + 1), tx);
+    lerp(bottom, top, ty)
+}
+
+impl RichnessCurve {
+    /// The base rate and depletion of a source with `richness`.
+    pub fn source_config(&self, richness: Fixed64) -> (Fixed64, Depletion) {
+        let lerp = |min: Fixed64, max: Fixed64| min + (max - min) * richness;
+        let depletion = if self.max_amount > Fixed64::ZERO {
+            Depletion::Finite {
+                remaining: lerp(self.min_amount, self.max_amount),
+            }
+        } else {
+            Depletion::Infinite
+        };
+        (lerp(self.min_rate, self.max_rate), depletion)
+    }
+}
+
+impl Engine {
+    /// Install the noise field used by [`add_source_at`](Self::add_source_at),
+    /// replacing any previous one. Sources already added keep their
+    /// configuration.
+    pub fn set_richness_provider(&mut self, seed: u64, curve: RichnessCurve) {
+        self.richness_provider = Some(RichnessProvider { seed, curve });
+        self.hash_cache_cold = true;
+        self.dirty
+            .mark_partition(DirtyTracker::PARTITION_PROCESSORS);
+    }
+
+    /// The installed noise field, if any.
+    pub fn richness_provider(&self) -> Option<&RichnessProvider> {
+        self.richness_provider.as_ref()
+    }
+
+    /// Make `node` a source of `item_type` whose rate and deposit come from
+    /// the richness at `(x, y)`.
+    ///
+    /// Replaces the node's processor as [`set_processor`](Self::set_processor)
+    /// does. Returns the recorded site, or `None` if no provider is installed
+    /// or `node` does not exist.
+    pub fn add_source_at(
+        &mut self,
+        node: NodeId,
+        item_type: ItemTypeId,
+        x: i32,
+        y: i32,
+    ) -> Option<SourceSite> {
+        let provider = self.richness_provider?;
+        if !self.graph.contains_node(node) {
+            return None;
+        }
+        let richness = richness_at(provider.seed, provider.curve.cell_size, x, y);
+        let (base_rate, depletion) = provider.curve.source_config(richness);
+        self.set_processor(
+            node,
+            Processor::Source(SourceProcessor {
+                output_type: item_type,
+                base_rate,
+                depletion,
+                accumulated: Fixed64::ZERO,
+                initial_properties: None,
+            }),
+        );
+        let site = SourceSite { x, y, richness };
+        self.source_sites.insert(node, site);
+        self.hash_cache_cold = true;
+        Some(site)
+    }
+
+    /// The site of a source added by [`add_source_at`](Self::add_source_at).
+    /// `None` once the node's processor has been replaced or cleared.
+    pub fn source_site(&self, node: NodeId) -> Option<SourceSite> {
+        self.source_sites.get(node).copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sim::SimulationStrategy;
+    use crate::test_utils;
+
+    fn curve(cell_size: u32) -> RichnessCurve {
+        RichnessCurve {
+            cell_size,
+            min_rate: Fixed64::from_num(0.5),
+            max_rate: Fixed64::from_num(2.5),
+            min_amount: Fixed64::from_num(1000),
+            max_amount: Fixed64::from_num(5000),
+        }
+    }
+
+    #[test]
+    fn richness_matches_committed_goldens() {
+        // Raw Q32.32 bits. A change here changes every generated map. The
+        // first is the upper half of SplitMix64's well-known first output
+        // for seed 0.
+        let samples = [
+            (richness_at(0, 1, 0, 0), 0xE220_A839),
+            (richness_at(42, 1, 0, 0), 0xBDD7_3226),
+            (richness_at(42, 1, -7, 3), 0x63E4_2B0F),
+            (richness_at(42, 16, 5, 11), 0xA163_208C),
+            (richness_at(u64::MAX, 64, -1000, 250_000), 0xBFB7_3952),
+        ];
+        for (i, (value, golden)) in samples.into_iter().enumerate() {
+            assert_eq!(value.to_bits(), golden, "sample {i}");
+        }
+    }
+
+    #[test]
+    fn richness_stays_in_range_and_is_continuous_within_cells() {
+        let mut low = 0;
+        for x in -32..32 {
+            for y in -32..32 {
+                let r = richness_at(7, 1, x, y);
+                assert!(r >= Fixed64::ZERO && r < Fixed64::ONE);
+                if r < Fixed64::from_num(0.5) {
+                    low += 1;
+                }
+            }
+        }
+        // Lattice values are uniform: roughly half of 4096 fall below 0.5.
+        assert!((1800..=2300).contains(&low), "got {low}");
+
+        // Neighbours inside one 16-unit cell differ by at most 2/16.
+        let step = Fixed64::from_num(2) / Fixed64::from_num(16);
+        for x in 0..15 {
+            let a = richness_at(7, 16, x, 3);
+            let b = richness_at(7, 16, x + 1, 3);
+            assert!((a - b).abs() <= step);
+        }
+        // Lattice points read the corner value directly.
+        assert_eq!(richness_at(7, 16, 32, -16), richness_at(7, 1, 2, -1));
+    }
+
+    #[test]
+    fn sources_take_their_configuration_from_the_field() {
+        let mut engine = Engine::new(SimulationStrategy::Tick);
+        let node = test_utils::add_node(&mut engine, Processor::Passthrough, 100, 100);
+        assert_eq!(engine.add_source_at(node, test_utils::iron(), 3, 4), None);
+
+        engine.set_richness_provider(42, curve(1));
+        let site = engine
+            .add_source_at(node, test_utils::iron(), -7, 3)
+            .unwrap();
+        assert_eq!(site.richness, richness_at(42, 1, -7, 3));
+        let (rate, depletion) = curve(1).source_config(site.richness);
+        match engine.get_processor(node) {
+            Some(Processor::Source(source)) => {
+                assert_eq!(source.base_rate, rate);
+                assert_eq!(source.depletion, depletion);
+                assert!(matches!(depletion, Depletion::Finite { .. }));
+            }
+            other => panic!("expected a source, got {other:?}"),
+        }
+
+        // A new provider leaves the existing source alone.
+        engine.set_richness_provider(43, curve(1));
+        assert_eq!(engine.source_site(node), Some(site));
+        match engine.get_processor(node) {
+            Some(Processor::Source(source)) => assert_eq!(source.base_rate, rate),
+            other => panic!("expected a source, got {other:?}"),
+        }
+
+        engine.set_processor(node, Processor::Passthrough);
+        assert_eq!(engine.source_site(node), None);
+    }
+
+    #[test]
+    fn sites_and_providers_survive_serialization() {
+        let mut engine = Engine::new(SimulationStrategy::Tick);
+        let before = engine.state_hash();
+        let node = test_utils::add_node(&mut engine, Processor::Passthrough, 100, 100);
+        engine.set_richness_provider(
+            9,
+            RichnessCurve {
+                max_amount: Fixed64::ZERO,
+                ..curve(8)
+            },
+        );
+        let site = engine
+            .add_source_at(node, test_utils::iron(), 100, -20)
+            .unwrap();
+        engine.step();
+        assert_ne!(engine.state_hash(), before);
+
+        let mut restored = Engine::deserialize(&engine.serialize().unwrap()).unwrap();
+        assert_eq!(restored.state_hash(), engine.state_hash());
+        assert_eq!(restored.source_site(node), Some(site));
+        assert_eq!(restored.richness_provider(), engine.richness_provider());
+
+        // Adding the same site after loading derives the same source.
+        let other = test_utils::add_node(&mut restored, Processor::Passthrough, 100, 100);
+        let again = restored
+            .add_source_at(other, test_utils::iron(), 100, -20)
+            .unwrap();
+        assert_eq!(again.richness, site.richness);
+        match (restored.get_processor(node), restored.get_processor(other)) {
+            (Some(Processor::Source(a)), Some(Processor::Source(b))) => {
+                assert_eq!(a.base_rate, b.base_rate);
+                assert_eq!(a.depletion, Depletion::Infinite);
+            }
+            other => panic!("expected sources, got {other:?}"),
+        }
+    }
+}
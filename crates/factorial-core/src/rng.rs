@@ -51,6 +51,37 @@ impl SimRng {
         (upper as u64) < raw
     }
 
+    /// Pick an index with probability proportional to its weight.
+    ///
+    /// Non-positive weights are never selected. Returns `None` when no
+    /// weight is positive. Always consumes exactly one value from the
+    /// sequence when a selection is made.
+    pub fn weighted_index(&mut self, weights: &[Fixed64]) -> Option<usize> {
+        let total = weights
+            .iter()
+            .filter(|w| **w > Fixed64::ZERO)
+            .fold(Fixed64::ZERO, |acc, w| acc.saturating_add(*w));
+        if total <= Fixed64::ZERO {
+            return None;
+        }
+        // Upper 32 bits as a Q32.32 fraction: uniform in [0, 1).
+        let unit = Fixed64::from_bits((self.next_u64() >> 32) as i64);
+        let target = total.saturating_mul(unit);
+        let mut cumulative = Fixed64::ZERO;
+        let mut last = None;
+        for (i, w) in weights.iter().enumerate() {
+            if *w <= Fixed64::ZERO {
+                continue;
+            }
+            cumulative = cumulative.saturating_add(*w);
+            if target < cumulative {
+                return Some(i);
+            }
+            last = Some(i);
+        }
+        last
+    }
+
     /// Get the internal state (for hashing/serialization).
     pub fn state(&self) -> u64 {
         self.state
@@ -121,6 +152,32 @@ mod tests {
         assert!((4000..=6000).contains(&hits), "expected ~5000, got {hits}");
     }
 
+    #[test]
+    fn weighted_index_skips_non_positive_weights() {
+        let mut rng = SimRng::new(7);
+        let weights = [Fixed64::ZERO, Fixed64::from_num(-1), Fixed64::from_num(2)];
+        for _ in 0..100 {
+            assert_eq!(rng.weighted_index(&weights), Some(2));
+        }
+        assert_eq!(rng.weighted_index(&[Fixed64::ZERO]), None);
+        assert_eq!(rng.weighted_index(&[]), None);
+    }
+
+    #[test]
+    fn weighted_index_follows_weights() {
+        let mut rng = SimRng::new(2024);
+        let weights = [Fixed64::from_num(1), Fixed64::from_num(3)];
+        let mut counts = [0u32; 2];
+        for _ in 0..10_000 {
+            counts[rng.weighted_index(&weights).unwrap()] += 1;
+        }
+        // Expect ~2500 / ~7500.
+        assert!(
+            (2200..=2800).contains(&counts[0]),
+            "expected ~2500, got {counts:?}"
+        );
+    }
+
     #[test]
     fn serialization_round_trip() {
         let mut rng = SimRng::new(42);
@@ -6,6 +6,7 @@
 
 use crate::engine::Engine;
 use crate::event::EventBus;
+use crate::fixed::Fixed64;
 use crate::graph::ProductionGraph;
 
 /// Serde default function returning `true`. Used for `#[serde(skip, default)]`
@@ -13,13 +14,20 @@ use crate::graph::ProductionGraph;
 pub fn default_true() -> bool {
     true
 }
-use crate::id::{EdgeId, NodeId};
+use crate::id::{
+    CraftingQueueId, EdgeGroupId, EdgeId, ItemCategoryId, ItemTypeId, NodeId, PropertyId, RecipeId,
+};
 use crate::item::Inventory;
-use crate::processor::{Modifier, Processor, ProcessorState};
+use crate::processor::{Depletion, FixedRecipe, InputMode, Modifier, Processor, ProcessorState};
+use crate::replay::REPLAY_LOG_VERSION;
+use crate::richness::{RichnessProvider, SourceSite};
 use crate::sim::{SimState, SimulationStrategy, StateHash};
-use crate::transport::{Transport, TransportState};
+use crate::transport::{PropertyManifest, Transport, TransportState};
 use serde::{Deserialize, Serialize};
-use slotmap::SecondaryMap;
+use slotmap::{SecondaryMap, SlotMap};
+use std::collections::BTreeMap;
+
+pub(crate) mod v3;
 
 // ---------------------------------------------------------------------------
 // Constants
@@ -29,7 +37,10 @@ use slotmap::SecondaryMap;
 pub const SNAPSHOT_MAGIC: u32 = 0xFAC7_0001;
 
 /// Current format version. Increment when breaking the wire format.
-pub const FORMAT_VERSION: u32 = 3;
+///
+/// Version 3 snapshots, full and partitioned, still load: they are decoded
+/// with the version 3 layout and converted.
+pub const FORMAT_VERSION: u32 = 4;
 
 /// Magic number for partitioned snapshots.
 pub const PARTITIONED_SNAPSHOT_MAGIC: u32 = 0xFAC7_0002;
@@ -43,6 +54,8 @@ pub const PARTITIONED_SNAPSHOT_MAGIC: u32 = 0xFAC7_0002;
 pub enum SerializeError {
     #[error("bitcode encoding failed: {0}")]
     Encode(String),
+    #[error("cannot serialize while a sliced tick is in progress")]
+    TickInProgress,
 }
 
 /// Errors that can occur during deserialization.
@@ -62,6 +75,16 @@ pub enum DeserializeError {
     MissingPartition(usize),
     #[error("partition {index} decode failed: {reason}")]
     PartitionDecode { index: usize, reason: String },
+    #[error("edge has no transport")]
+    NoTransport,
+    #[error("transport state does not match the edge's transport configuration")]
+    TransportMismatch,
+    #[error("snapshot enables extensions unknown to this build (mask 0x{0:08X})")]
+    UnknownExtensions(u32),
+    #[error("save summary checksum mismatch")]
+    SummaryChecksum,
+    #[error("unsupported replay log version {0} (this build reads up to {REPLAY_LOG_VERSION})")]
+    UnsupportedReplayVersion(u32),
 }
 
 // ---------------------------------------------------------------------------
@@ -114,9 +137,25 @@ pub fn read_snapshot_header(data: &[u8]) -> Result<SnapshotHeader, DeserializeEr
     // If the version doesn't match, the decode might still work for
     // header extraction. We decode the whole thing because bitcode
     // doesn't support partial deserialization.
-    let snapshot: EngineSnapshot =
-        bitcode::deserialize(data).map_err(|e| DeserializeError::Decode(e.to_string()))?;
-    Ok(snapshot.header)
+    let body = crate::summary::snapshot_body(data)?;
+    match bitcode::deserialize::<EngineSnapshot>(body) {
+        Ok(snapshot) => Ok(snapshot.header),
+        Err(e) => v3::decode_header(body).ok_or_else(|| DeserializeError::Decode(e.to_string())),
+    }
+}
+
+/// Decode a snapshot body and validate its header, converting a version 3
+/// body to the current layout.
+fn decode_snapshot(body: &[u8]) -> Result<EngineSnapshot, DeserializeError> {
+    let current = bitcode::deserialize::<EngineSnapshot>(body)
+        .map_err(|e| DeserializeError::Decode(e.to_string()))
+        .and_then(|snapshot| snapshot.header.validate().map(|()| snapshot));
+    match current {
+        Err(e @ (DeserializeError::Decode(_) | DeserializeError::UnsupportedVersion(_))) => {
+            v3::decode_snapshot(body).ok_or(e)
+        }
+        other => other,
+    }
 }
 
 // ---------------------------------------------------------------------------
@@ -142,13 +181,61 @@ struct EngineSnapshot {
     #[serde(default)]
     paused: bool,
     #[serde(default)]
+    step_work_limit: Option<u64>,
+    #[serde(default)]
     junctions: SecondaryMap<NodeId, crate::junction::Junction>,
     #[serde(default)]
     junction_states: SecondaryMap<NodeId, crate::junction::JunctionState>,
     #[serde(default)]
+    merge_priorities: SecondaryMap<NodeId, crate::junction::MergePriority>,
+    #[serde(default)]
+    merge_cursors: SecondaryMap<NodeId, u32>,
+    #[serde(default)]
     rng_seed: u64,
     #[serde(default)]
     node_rngs: SecondaryMap<NodeId, crate::rng::SimRng>,
+    #[serde(default)]
+    next_item_instance_id: u64,
+    #[serde(default)]
+    fluid_ports: SecondaryMap<NodeId, crate::processor::FluidPorts>,
+    #[serde(default)]
+    ghost_nodes: SecondaryMap<NodeId, ()>,
+    #[serde(default)]
+    ghost_edges: SecondaryMap<EdgeId, ()>,
+    #[serde(default)]
+    event_history: Option<crate::event::EventBuffer>,
+    #[serde(default)]
+    crafting_queues: SlotMap<CraftingQueueId, crate::crafting::CraftingQueue>,
+    #[serde(default)]
+    item_decay: BTreeMap<ItemTypeId, crate::spoilage::ItemDecay>,
+    #[serde(default)]
+    input_modes: SecondaryMap<NodeId, InputMode>,
+    #[serde(default)]
+    extensions: u32,
+    #[serde(default)]
+    edge_groups: SlotMap<EdgeGroupId, Vec<EdgeId>>,
+    #[serde(default)]
+    edge_group_of: SecondaryMap<EdgeId, EdgeGroupId>,
+    #[serde(default)]
+    item_categories: BTreeMap<ItemTypeId, ItemCategoryId>,
+    #[serde(default)]
+    edge_category_filters: SecondaryMap<EdgeId, ItemCategoryId>,
+    #[serde(default)]
+    category_picks: SecondaryMap<EdgeId, ItemTypeId>,
+    #[serde(default)]
+    demand_categories: SecondaryMap<NodeId, ItemCategoryId>,
+    #[serde(default)]
+    recipe_overrides: BTreeMap<RecipeId, FixedRecipe>,
+    #[serde(default)]
+    richness_provider: Option<RichnessProvider>,
+    #[serde(default)]
+    source_sites: SecondaryMap<NodeId, SourceSite>,
+    #[serde(default)]
+    warmups: SecondaryMap<NodeId, crate::warmup::Warmup>,
+    #[serde(default)]
+    max_catchup: Option<(u32, crate::sim::CatchupPolicy)>,
+    #[serde(default)]
+    node_user_data: SecondaryMap<NodeId, u64>,
 }
 
 // ---------------------------------------------------------------------------
@@ -283,6 +370,9 @@ impl Engine {
     /// The EventBus is excluded (it contains closures that cannot be
     /// serialized). On deserialize, a fresh EventBus is created.
     pub fn serialize(&self) -> Result<Vec<u8>, SerializeError> {
+        if self.tick_slice.is_some() {
+            return Err(SerializeError::TickInProgress);
+        }
         let snapshot = EngineSnapshot {
             header: SnapshotHeader::new(self.sim_state.tick),
             graph: self.graph.clone(),
@@ -297,13 +387,42 @@ impl Engine {
             transport_states: self.transport_states.clone(),
             last_state_hash: self.last_state_hash,
             paused: self.paused,
+            step_work_limit: self.step_work_limit,
             junctions: self.junctions.clone(),
             junction_states: self.junction_states.clone(),
+            merge_priorities: self.merge_priorities.clone(),
+            merge_cursors: self.merge_cursors.clone(),
             rng_seed: self.rng_seed,
             node_rngs: self.node_rngs.clone(),
+            next_item_instance_id: self.next_item_instance_id,
+            fluid_ports: self.fluid_ports.clone(),
+            ghost_nodes: self.ghost_nodes.clone(),
+            ghost_edges: self.ghost_edges.clone(),
+            event_history: self
+                .event_history
+                .clone()
+                .filter(|_| self.serialize_event_history),
+            crafting_queues: self.crafting_queues.clone(),
+            item_decay: self.item_decay.clone(),
+            input_modes: self.input_modes.clone(),
+            extensions: self.extensions,
+            edge_groups: self.edge_groups.clone(),
+            edge_group_of: self.edge_group_of.clone(),
+            item_categories: self.item_categories.clone(),
+            edge_category_filters: self.edge_category_filters.clone(),
+            category_picks: self.category_picks.clone(),
+            demand_categories: self.demand_categories.clone(),
+            recipe_overrides: self.recipe_overrides.clone(),
+            richness_provider: self.richness_provider,
+            source_sites: self.source_sites.clone(),
+            warmups: self.warmups.clone(),
+            max_catchup: self.max_catchup,
+            node_user_data: self.node_user_data.clone(),
         };
 
-        bitcode::serialize(&snapshot).map_err(|e| SerializeError::Encode(e.to_string()))
+        let body =
+            bitcode::serialize(&snapshot).map_err(|e| SerializeError::Encode(e.to_string()))?;
+        self.frame_with_summary(body)
     }
 
     /// Deserialize an engine from a binary blob.
@@ -318,11 +437,9 @@ impl Engine {
         // We need at least enough bytes for a bitcode-encoded header.
         // Rather than trying to parse the header separately, we decode
         // the full snapshot and then validate the header.
-        let snapshot: EngineSnapshot =
-            bitcode::deserialize(data).map_err(|e| DeserializeError::Decode(e.to_string()))?;
-
-        // Validate the header.
-        snapshot.header.validate()?;
+        let body = crate::summary::snapshot_body(data)?;
+        let snapshot = decode_snapshot(body)?;
+        let extensions = crate::extension::check_extension_mask(snapshot.extensions)?;
 
         let mut engine = Engine {
             graph: snapshot.graph,
@@ -333,30 +450,75 @@ impl Engine {
             inputs: snapshot.inputs,
             outputs: snapshot.outputs,
             modifiers: snapshot.modifiers,
+            input_modes: snapshot.input_modes,
             transports: snapshot.transports,
             transport_states: snapshot.transport_states,
             last_state_hash: snapshot.last_state_hash,
+            last_hash_breakdown: Default::default(),
             paused: snapshot.paused,
+            step_work_limit: snapshot.step_work_limit,
+            max_catchup: snapshot.max_catchup,
             event_bus: EventBus::default(),
             modules: Vec::new(),
+            tick_hooks: Default::default(),
             dirty: crate::dirty::DirtyTracker::new(),
             junctions: snapshot.junctions,
             junction_states: snapshot.junction_states,
             edge_budgets: SecondaryMap::new(),
+            merge_priorities: snapshot.merge_priorities,
+            merge_cursors: snapshot.merge_cursors,
+            merge_turns: SecondaryMap::new(),
             transport_edge_buf: Vec::new(),
             input_buf: Vec::new(),
             node_item_type_cache: SecondaryMap::new(),
             registry: None,
             node_hash_cache: SecondaryMap::new(),
-            combined_node_hash: 0,
+            combined_node_hash: crate::engine::NodeHash::default(),
             hash_dirty_nodes: Vec::new(),
             hash_cache_cold: true,
             rng_seed: snapshot.rng_seed,
             node_rngs: snapshot.node_rngs,
+            next_item_instance_id: snapshot.next_item_instance_id.max(1),
+            fluid_ports: snapshot.fluid_ports,
+            strict_mode: false,
+            ghost_nodes: snapshot.ghost_nodes,
+            ghost_edges: snapshot.ghost_edges,
+            hash_ghosts: false,
+            crafting_queues: snapshot.crafting_queues,
+            item_decay: snapshot.item_decay,
+            edge_groups: snapshot.edge_groups,
+            edge_group_of: snapshot.edge_group_of,
+            item_categories: snapshot.item_categories,
+            edge_category_filters: snapshot.edge_category_filters,
+            category_picks: snapshot.category_picks,
+            demand_categories: snapshot.demand_categories,
+            node_user_data: snapshot.node_user_data,
+            recipe_overrides: snapshot.recipe_overrides,
+            richness_provider: snapshot.richness_provider,
+            source_sites: snapshot.source_sites,
+            warmups: snapshot.warmups,
+            full_slots: SecondaryMap::new(),
+            event_log: None,
+            serialize_event_history: snapshot.event_history.is_some(),
+            event_cursors: slotmap::SlotMap::with_key(),
+            summary_item_totals: false,
+            event_history: snapshot.event_history,
+            latency: Default::default(),
+            operation_log: Default::default(),
+            extensions,
+            init: Default::default(),
+            step_counters: None,
+            last_step_profile: None,
             #[cfg(feature = "profiling")]
             last_profile: None,
+            tick_slice: None,
+            topology: Default::default(),
         };
         engine.rebuild_item_type_cache();
+        engine.rebuild_full_slots();
+        engine.sync_silenced_nodes();
+        engine.compute_state_hash();
+        engine.sync_event_recording();
         Ok(engine)
     }
 
@@ -377,7 +539,11 @@ impl Engine {
             Err(DeserializeError::UnsupportedVersion(old_version)) => {
                 // Try to migrate.
                 let migrated_data = migrations
-                    .migrate(data, old_version, FORMAT_VERSION)
+                    .migrate(
+                        crate::summary::snapshot_body(data)?,
+                        old_version,
+                        FORMAT_VERSION,
+                    )
                     .map_err(|e| DeserializeError::Decode(format!("migration failed: {e}")))?;
                 Self::deserialize(&migrated_data)
             }
@@ -402,16 +568,28 @@ impl Engine {
 
     // -- Subsystem hash helpers --
 
-    fn hash_graph(&self) -> u64 {
+    pub(crate) fn hash_graph(&self) -> u64 {
         let mut h = StateHash::new();
-        h.write_u64(self.graph.node_count() as u64);
-        h.write_u64(self.graph.edge_count() as u64);
-        for (node_id, node_data) in self.graph.nodes() {
+        // Ghosts left out of the hash are left out of the counts too, so
+        // the hash is the one the graph would have without them.
+        let nodes: Vec<_> = self
+            .graph
+            .nodes()
+            .filter(|&(node_id, _)| !self.hash_skips_node(node_id))
+            .collect();
+        let edges: Vec<_> = self
+            .graph
+            .edges()
+            .filter(|&(edge_id, data)| !self.hash_skips_edge(edge_id, data.from, data.to))
+            .collect();
+        h.write_u64(nodes.len() as u64);
+        h.write_u64(edges.len() as u64);
+        for (node_id, node_data) in nodes {
             // Hash the raw slot key bits for determinism.
             h.write(&serde_json_key_bytes(node_id));
             h.write_u32(node_data.building_type.0);
         }
-        for (edge_id, edge_data) in self.graph.edges() {
+        for (edge_id, edge_data) in edges {
             h.write(&serde_json_key_bytes(edge_id));
             h.write(&serde_json_key_bytes(edge_data.from));
             h.write(&serde_json_key_bytes(edge_data.to));
@@ -423,12 +601,102 @@ impl Engine {
                 None => h.write_u32(0),
             }
         }
+        // Ghost status is hashed only when present so existing hashes of
+        // fully-active graphs are unchanged.
+        if self.hash_ghosts && (!self.ghost_nodes.is_empty() || !self.ghost_edges.is_empty()) {
+            for (node_id, ()) in &self.ghost_nodes {
+                h.write(&serde_json_key_bytes(node_id));
+            }
+            h.write_u32(u32::MAX);
+            for (edge_id, ()) in &self.ghost_edges {
+                h.write(&serde_json_key_bytes(edge_id));
+            }
+        }
+        // Likewise for categories.
+        if !self.item_categories.is_empty()
+            || !self.edge_category_filters.is_empty()
+            || !self.demand_categories.is_empty()
+        {
+            for (item, category) in &self.item_categories {
+                h.write_u32(item.0);
+                h.write_u32(category.0);
+            }
+            h.write_u32(u32::MAX);
+            for (edge_id, category) in &self.edge_category_filters {
+                if self
+                    .graph
+                    .get_edge(edge_id)
+                    .is_some_and(|data| self.hash_skips_edge(edge_id, data.from, data.to))
+                {
+                    continue;
+                }
+                h.write(&serde_json_key_bytes(edge_id));
+                h.write_u32(category.0);
+            }
+            h.write_u32(u32::MAX);
+            for (node_id, category) in &self.demand_categories {
+                if self.hash_skips_node(node_id) {
+                    continue;
+                }
+                h.write(&serde_json_key_bytes(node_id));
+                h.write_u32(category.0);
+            }
+        }
+        // Likewise for runtime recipe updates.
+        if !self.recipe_overrides.is_empty() {
+            h.write_u32(u32::MAX);
+            for (id, recipe) in &self.recipe_overrides {
+                h.write_u32(id.0);
+                hash_fixed_recipe(&mut h, recipe);
+            }
+        }
+        // Likewise for procedural sources.
+        if let Some(provider) = &self.richness_provider {
+            h.write_u32(u32::MAX);
+            h.write_u64(provider.seed);
+            let curve = &provider.curve;
+            h.write_u32(curve.cell_size);
+            for value in [
+                curve.min_rate,
+                curve.max_rate,
+                curve.min_amount,
+                curve.max_amount,
+            ] {
+                h.write_fixed64(value);
+            }
+        }
+        if !self.source_sites.is_empty() {
+            h.write_u32(u32::MAX);
+            for (node_id, site) in &self.source_sites {
+                if self.hash_skips_node(node_id) {
+                    continue;
+                }
+                h.write(&serde_json_key_bytes(node_id));
+                h.write_u32(site.x as u32);
+                h.write_u32(site.y as u32);
+                h.write_fixed64(site.richness);
+            }
+        }
+        // Likewise for warmup timers.
+        if !self.warmups.is_empty() {
+            h.write_u32(u32::MAX);
+            for (node_id, warmup) in &self.warmups {
+                if self.hash_skips_node(node_id) {
+                    continue;
+                }
+                h.write(&serde_json_key_bytes(node_id));
+                crate::warmup::hash_warmup(&mut h, warmup);
+            }
+        }
         h.finish()
     }
 
     fn hash_processors(&self) -> u64 {
         let mut h = StateHash::new();
         for (node_id, _) in self.graph.nodes() {
+            if self.hash_skips_node(node_id) {
+                continue;
+            }
             if let Some(proc) = self.processors.get(node_id) {
                 h.write(&serde_json_key_bytes(node_id));
                 // Hash the processor variant discriminant.
@@ -450,29 +718,7 @@ impl Engine {
                     }
                     Processor::Fixed(recipe) => {
                         h.write_u32(1);
-                        h.write_u32(recipe.duration);
-                        h.write_u32(recipe.inputs.len() as u32);
-                        for input in &recipe.inputs {
-                            h.write_u32(input.item_type.0);
-                            h.write_u32(input.quantity);
-                            h.write_u32(u32::from(input.consumed));
-                        }
-                        h.write_u32(recipe.outputs.len() as u32);
-                        for output in &recipe.outputs {
-                            h.write_u32(output.item_type.0);
-                            h.write_u32(output.quantity);
-                            match &output.bonus {
-                                Some(bonus) => {
-                                    h.write_u32(1);
-                                    h.write_fixed64(bonus.chance);
-                                    h.write_u32(bonus.quantity);
-                                    h.write_u32(
-                                        bonus.bonus_item_type.map(|t| t.0).unwrap_or(u32::MAX),
-                                    );
-                                }
-                                None => h.write_u32(0),
-                            }
-                        }
+                        hash_fixed_recipe(&mut h, recipe);
                     }
                     Processor::Property(prop) => {
                         h.write_u32(2);
@@ -509,6 +755,65 @@ impl Engine {
                             h.write_u32(0);
                         }
                     }
+                    Processor::WeightedRecipe(weighted) => {
+                        h.write_u32(6);
+                        h.write_u32(weighted.recipes.len() as u32);
+                        for (_, weight) in &weighted.recipes {
+                            h.write_fixed64(*weight);
+                        }
+                        if let Some(selected) = weighted.selected {
+                            h.write_u32(1);
+                            h.write_u32(selected as u32);
+                        } else {
+                            h.write_u32(0);
+                        }
+                    }
+                    Processor::Flexible(flexible) => {
+                        h.write_u32(7);
+                        h.write_u32(flexible.recipes.len() as u32);
+                        for recipe in &flexible.recipes {
+                            h.write_u32(recipe.0);
+                        }
+                        h.write_u32(flexible.selection as u32);
+                        match flexible.active_recipe() {
+                            Some(recipe) => {
+                                h.write_u32(1);
+                                h.write_u32(recipe.0);
+                            }
+                            None => h.write_u32(0),
+                        }
+                    }
+                    Processor::Storage(storage) => {
+                        h.write_u32(8);
+                        match storage.usable_slots {
+                            Some(slots) => {
+                                h.write_u32(1);
+                                h.write_u32(slots);
+                            }
+                            None => h.write_u32(0),
+                        }
+                        h.write_u32(storage.read_signal as u32);
+                    }
+                    Processor::MultiSource(multi) => {
+                        h.write_u32(9);
+                        h.write_u32(multi.outputs.len() as u32);
+                        for output in &multi.outputs {
+                            h.write_u32(output.item_type.0);
+                            h.write_fixed64(output.base_rate);
+                            h.write_fixed64(output.accumulated);
+                        }
+                        match multi.depletion {
+                            Depletion::Infinite => h.write_u32(0),
+                            Depletion::Finite { remaining } => {
+                                h.write_u32(1);
+                                h.write_fixed64(remaining);
+                            }
+                            Depletion::Decaying { half_life } => {
+                                h.write_u32(2);
+                                h.write_u64(half_life);
+                            }
+                        }
+                    }
                 }
             }
         }
@@ -518,6 +823,9 @@ impl Engine {
     fn hash_processor_states(&self) -> u64 {
         let mut h = StateHash::new();
         for (node_id, _) in self.graph.nodes() {
+            if self.hash_skips_node(node_id) {
+                continue;
+            }
             if let Some(ps) = self.processor_states.get(node_id) {
                 h.write(&serde_json_key_bytes(node_id));
                 match ps {
@@ -530,6 +838,7 @@ impl Engine {
                         h.write_u32(2);
                         h.write_u32(*reason as u32);
                     }
+                    ProcessorState::Storing => h.write_u32(3),
                 }
             }
         }
@@ -539,6 +848,9 @@ impl Engine {
     fn hash_inventories(&self) -> u64 {
         let mut h = StateHash::new();
         for (node_id, _) in self.graph.nodes() {
+            if self.hash_skips_node(node_id) {
+                continue;
+            }
             h.write(&serde_json_key_bytes(node_id));
             if let Some(inv) = self.inputs.get(node_id) {
                 for slot in &inv.input_slots {
@@ -566,13 +878,23 @@ impl Engine {
                     }
                 }
             }
+            if let Some(ports) = self.fluid_ports.get(node_id).filter(|p| !p.is_empty()) {
+                h.write_u32(ports.input.len() as u32);
+                for (fluid, amount) in ports.input.iter().chain(ports.output.iter()) {
+                    h.write_u32(fluid.0);
+                    h.write_fixed64(*amount);
+                }
+            }
         }
         h.finish()
     }
 
-    fn hash_transports(&self) -> u64 {
+    pub(crate) fn hash_transports(&self) -> u64 {
         let mut h = StateHash::new();
-        for (edge_id, _) in self.graph.edges() {
+        for (edge_id, edge_data) in self.graph.edges() {
+            if self.hash_skips_edge(edge_id, edge_data.from, edge_data.to) {
+                continue;
+            }
             if let Some(state) = self.transport_states.get(edge_id) {
                 h.write(&serde_json_key_bytes(edge_id));
                 match state {
@@ -580,23 +902,42 @@ impl Engine {
                         h.write_u32(0);
                         h.write_fixed64(fs.buffered);
                         h.write_u32(fs.latency_remaining);
+                        // Remainders are only non-zero for fractional rates;
+                        // skipping zeros keeps integer-rate hashes stable.
+                        if fs.intake_remainder != crate::fixed::Fixed64::ZERO
+                            || fs.accumulator != crate::fixed::Fixed64::ZERO
+                        {
+                            h.write_fixed64(fs.intake_remainder);
+                            h.write_fixed64(fs.accumulator);
+                        }
+                        hash_manifest(&mut h, &fs.properties);
                     }
                     TransportState::Item(bs) => {
                         h.write_u32(1);
                         h.write_u32(bs.occupied_count() as u32);
+                        // Only items with properties are listed.
+                        for (&instance_id, properties) in &bs.properties {
+                            h.write_u64(instance_id);
+                            hash_properties(&mut h, properties);
+                        }
                     }
                     TransportState::Batch(bs) => {
                         h.write_u32(2);
                         h.write_u32(bs.progress);
                         h.write_u32(bs.pending);
+                        hash_manifest(&mut h, &bs.properties);
                     }
                     TransportState::Vehicle(vs) => {
                         h.write_u32(3);
                         h.write_u32(vs.position);
                         let cargo_total: u32 = vs.cargo.iter().map(|s| s.quantity).sum();
                         h.write_u32(cargo_total);
+                        hash_manifest(&mut h, &vs.properties);
                     }
                 }
+                if let Some(item) = self.category_picks.get(edge_id) {
+                    h.write_u32(item.0);
+                }
             }
         }
         h.finish()
@@ -616,6 +957,52 @@ impl Engine {
     }
 }
 
+/// Hash a recipe's duration, item inputs and item outputs.
+fn hash_fixed_recipe(h: &mut StateHash, recipe: &FixedRecipe) {
+    h.write_u32(recipe.duration);
+    if let Some(per_unit) = recipe.duration_per_unit {
+        h.write_fixed64(per_unit);
+    }
+    h.write_u32(recipe.inputs.len() as u32);
+    for input in &recipe.inputs {
+        h.write_u32(input.item_type.0);
+        h.write_u32(input.quantity);
+        h.write_u32(u32::from(input.consumed));
+    }
+    h.write_u32(recipe.outputs.len() as u32);
+    for output in &recipe.outputs {
+        h.write_u32(output.item_type.0);
+        h.write_u32(output.quantity);
+        match &output.bonus {
+            Some(bonus) => {
+                h.write_u32(1);
+                h.write_fixed64(bonus.chance);
+                h.write_u32(bonus.quantity);
+                h.write_u32(bonus.bonus_item_type.map(|t| t.0).unwrap_or(u32::MAX));
+            }
+            None => h.write_u32(0),
+        }
+    }
+}
+
+/// Hash a property map: its length, then each property and value.
+fn hash_properties(h: &mut StateHash, properties: &BTreeMap<PropertyId, Fixed64>) {
+    h.write_u32(properties.len() as u32);
+    for (k, v) in properties {
+        h.write_u32(k.0 as u32);
+        h.write_fixed64(*v);
+    }
+}
+
+/// Hash the groups of a transport's property manifest. Nothing is written
+/// for an empty manifest, so transports without properties hash as before.
+fn hash_manifest(h: &mut StateHash, manifest: &PropertyManifest) {
+    for group in manifest.groups() {
+        h.write_u32(group.quantity);
+        hash_properties(h, &group.properties);
+    }
+}
+
 /// Convert a slotmap key to deterministic bytes for hashing.
 /// We use the raw FFI representation (version + index packed into u64).
 fn serde_json_key_bytes<K: slotmap::Key>(key: K) -> [u8; 8] {
@@ -654,6 +1041,78 @@ impl Engine {
     }
 }
 
+// ---------------------------------------------------------------------------
+// Per-edge transport state
+// ---------------------------------------------------------------------------
+
+/// Versioned wrapper for a single edge's transport state blob.
+#[derive(Debug, Serialize, Deserialize)]
+struct TransportStateBlob {
+    version: u32,
+    state: TransportState,
+}
+
+impl Engine {
+    /// Serialize the internal state of one edge's transport (buffer, belt
+    /// slots, batch progress or vehicle cargo) for save editing and repro
+    /// harnesses. Returns `Ok(None)` if the edge has no transport.
+    pub fn serialize_transport_state(
+        &self,
+        edge: EdgeId,
+    ) -> Result<Option<Vec<u8>>, SerializeError> {
+        let Some(state) = self.transport_states.get(edge) else {
+            return Ok(None);
+        };
+        let blob = TransportStateBlob {
+            version: FORMAT_VERSION,
+            state: state.clone(),
+        };
+        bitcode::serialize(&blob)
+            .map(Some)
+            .map_err(|e| SerializeError::Encode(e.to_string()))
+    }
+
+    /// Replace one edge's transport state with a blob produced by
+    /// [`serialize_transport_state`](Engine::serialize_transport_state).
+    ///
+    /// The state must match the edge's current transport: same variant, and
+    /// for item transports the same slot count and lanes with every item
+    /// within the belt's length. Item instance ids carried
+    /// by the blob are reserved so newly spawned items never reuse them.
+    pub fn deserialize_transport_state(
+        &mut self,
+        edge: EdgeId,
+        data: &[u8],
+    ) -> Result<(), DeserializeError> {
+        let blob: TransportStateBlob =
+            bitcode::deserialize(data).map_err(|e| DeserializeError::Decode(e.to_string()))?;
+        if blob.version > FORMAT_VERSION {
+            return Err(DeserializeError::FutureVersion(blob.version));
+        }
+        if blob.version < FORMAT_VERSION {
+            return Err(DeserializeError::UnsupportedVersion(blob.version));
+        }
+        let transport = self
+            .transports
+            .get(edge)
+            .ok_or(DeserializeError::NoTransport)?;
+        if !blob.state.fits(transport) {
+            return Err(DeserializeError::TransportMismatch);
+        }
+        let state = blob.state;
+        if let TransportState::Item(belt) = &state
+            && let Some(max_id) = belt.items().map(|(_, i)| i.instance_id).max()
+        {
+            self.next_item_instance_id = self.next_item_instance_id.max(max_id + 1);
+        }
+        self.transport_states.insert(edge, state);
+        self.dirty.mark_edge(edge);
+        self.dirty
+            .mark_partition(crate::dirty::DirtyTracker::PARTITION_TRANSPORTS);
+        Ok(())
+    }
+}
+
 // ---------------------------------------------------------------------------
 // Partitioned snapshot types
 // ---------------------------------------------------------------------------
@@ -673,6 +1132,24 @@ struct GraphPartition {
     strategy: SimulationStrategy,
     last_state_hash: u64,
     paused: bool,
+    #[serde(default)]
+    ghost_nodes: SecondaryMap<NodeId, ()>,
+    #[serde(default)]
+    ghost_edges: SecondaryMap<EdgeId, ()>,
+    #[serde(default)]
+    extensions: u32,
+    #[serde(default)]
+    item_categories: BTreeMap<ItemTypeId, ItemCategoryId>,
+    #[serde(default)]
+    edge_groups: SlotMap<EdgeGroupId, Vec<EdgeId>>,
+    #[serde(default)]
+    edge_group_of: SecondaryMap<EdgeId, EdgeGroupId>,
+    #[serde(default)]
+    step_work_limit: Option<u64>,
+    #[serde(default)]
+    max_catchup: Option<(u32, crate::sim::CatchupPolicy)>,
+    #[serde(default)]
+    node_user_data: SecondaryMap<NodeId, u64>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -680,24 +1157,72 @@ struct ProcessorPartition {
     processors: SecondaryMap<NodeId, Processor>,
     processor_states: SecondaryMap<NodeId, ProcessorState>,
     modifiers: SecondaryMap<NodeId, Vec<Modifier>>,
+    #[serde(default)]
+    input_modes: SecondaryMap<NodeId, InputMode>,
+    #[serde(default)]
+    demand_categories: SecondaryMap<NodeId, ItemCategoryId>,
+    #[serde(default)]
+    recipe_overrides: BTreeMap<RecipeId, FixedRecipe>,
+    #[serde(default)]
+    richness_provider: Option<RichnessProvider>,
+    #[serde(default)]
+    source_sites: SecondaryMap<NodeId, SourceSite>,
+    #[serde(default)]
+    warmups: SecondaryMap<NodeId, crate::warmup::Warmup>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 struct InventoryPartition {
     inputs: SecondaryMap<NodeId, Inventory>,
     outputs: SecondaryMap<NodeId, Inventory>,
+    #[serde(default)]
+    fluid_ports: SecondaryMap<NodeId, crate::processor::FluidPorts>,
+    #[serde(default)]
+    crafting_queues: SlotMap<CraftingQueueId, crate::crafting::CraftingQueue>,
+    #[serde(default)]
+    item_decay: BTreeMap<ItemTypeId, crate::spoilage::ItemDecay>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 struct TransportPartition {
     transports: SecondaryMap<EdgeId, Transport>,
     transport_states: SecondaryMap<EdgeId, TransportState>,
+    #[serde(default)]
+    next_item_instance_id: u64,
+    #[serde(default)]
+    edge_category_filters: SecondaryMap<EdgeId, ItemCategoryId>,
+    #[serde(default)]
+    category_picks: SecondaryMap<EdgeId, ItemTypeId>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 struct JunctionPartition {
     junctions: SecondaryMap<NodeId, crate::junction::Junction>,
     junction_states: SecondaryMap<NodeId, crate::junction::JunctionState>,
+    #[serde(default)]
+    merge_priorities: SecondaryMap<NodeId, crate::junction::MergePriority>,
+    #[serde(default)]
+    merge_cursors: SecondaryMap<NodeId, u32>,
+}
+
+/// The decoded partitions of a [`PartitionedSnapshot`], in index order.
+type Partitions = (
+    GraphPartition,
+    ProcessorPartition,
+    InventoryPartition,
+    TransportPartition,
+    JunctionPartition,
+);
+
+/// Decode the partition at `index`.
+fn decode_partition<T: serde::de::DeserializeOwned>(
+    partitions: &[Vec<u8>; 5],
+    index: usize,
+) -> Result<T, DeserializeError> {
+    bitcode::deserialize(&partitions[index]).map_err(|e| DeserializeError::PartitionDecode {
+        index,
+        reason: e.to_string(),
+    })
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -740,7 +1265,7 @@ impl PartitionedSnapshot {
         if snap.header.version > FORMAT_VERSION {
             return Err(DeserializeError::FutureVersion(snap.header.version));
         }
-        if snap.header.version < FORMAT_VERSION {
+        if snap.header.version < v3::VERSION {
             return Err(DeserializeError::UnsupportedVersion(snap.header.version));
         }
         Ok(snap)
@@ -760,12 +1285,16 @@ impl Engine {
         {
             return SnapshotFormat::Partitioned;
         }
-        // Try legacy.
-        if let Ok(snap) = bitcode::deserialize::<EngineSnapshot>(data)
+        // Try legacy, with or without a summary section.
+        let body = crate::summary::snapshot_body(data).unwrap_or(data);
+        if let Ok(snap) = bitcode::deserialize::<EngineSnapshot>(body)
             && snap.header.magic == SNAPSHOT_MAGIC
         {
             return SnapshotFormat::Legacy;
         }
+        if v3::decode_snapshot(body).is_some() {
+            return SnapshotFormat::Legacy;
+        }
         SnapshotFormat::Unknown
     }
 
@@ -780,27 +1309,50 @@ impl Engine {
                 strategy: self.strategy.clone(),
                 last_state_hash: self.last_state_hash,
                 paused: self.paused,
+                ghost_nodes: self.ghost_nodes.clone(),
+                ghost_edges: self.ghost_edges.clone(),
+                extensions: self.extensions,
+                item_categories: self.item_categories.clone(),
+                edge_groups: self.edge_groups.clone(),
+                edge_group_of: self.edge_group_of.clone(),
+                step_work_limit: self.step_work_limit,
+                max_catchup: self.max_catchup,
+                node_user_data: self.node_user_data.clone(),
             })
             .map_err(map_err),
             1 => bitcode::serialize(&ProcessorPartition {
                 processors: self.processors.clone(),
                 processor_states: self.processor_states.clone(),
                 modifiers: self.modifiers.clone(),
+                input_modes: self.input_modes.clone(),
+                demand_categories: self.demand_categories.clone(),
+                recipe_overrides: self.recipe_overrides.clone(),
+                richness_provider: self.richness_provider,
+                source_sites: self.source_sites.clone(),
+                warmups: self.warmups.clone(),
             })
             .map_err(map_err),
             2 => bitcode::serialize(&InventoryPartition {
                 inputs: self.inputs.clone(),
                 outputs: self.outputs.clone(),
+                fluid_ports: self.fluid_ports.clone(),
+                crafting_queues: self.crafting_queues.clone(),
+                item_decay: self.item_decay.clone(),
             })
             .map_err(map_err),
             3 => bitcode::serialize(&TransportPartition {
                 transports: self.transports.clone(),
                 transport_states: self.transport_states.clone(),
+                next_item_instance_id: self.next_item_instance_id,
+                edge_category_filters: self.edge_category_filters.clone(),
+                category_picks: self.category_picks.clone(),
             })
             .map_err(map_err),
             4 => bitcode::serialize(&JunctionPartition {
                 junctions: self.junctions.clone(),
                 junction_states: self.junction_states.clone(),
+                merge_priorities: self.merge_priorities.clone(),
+                merge_cursors: self.merge_cursors.clone(),
             })
             .map_err(map_err),
             _ => unreachable!("partition index out of range"),
@@ -813,6 +1365,9 @@ impl Engine {
     /// baseline for [`serialize_incremental`](Engine::serialize_incremental),
     /// or converted to bytes via [`PartitionedSnapshot::to_bytes`].
     pub fn serialize_partitioned(&self) -> Result<PartitionedSnapshot, SerializeError> {
+        if self.tick_slice.is_some() {
+            return Err(SerializeError::TickInProgress);
+        }
         let mut partitions: [Vec<u8>; 5] = Default::default();
         for (i, partition) in partitions.iter_mut().enumerate() {
             *partition = self.serialize_single_partition(i)?;
@@ -838,7 +1393,12 @@ impl Engine {
         &mut self,
         baseline: Option<&PartitionedSnapshot>,
     ) -> Result<PartitionedSnapshot, SerializeError> {
+        if self.tick_slice.is_some() {
+            return Err(SerializeError::TickInProgress);
+        }
         let dirty = *self.dirty.dirty_partitions();
+        // Blobs of an older version cannot be mixed with current ones.
+        let baseline = baseline.filter(|base| base.header.version == FORMAT_VERSION);
 
         let mut partitions: [Vec<u8>; 5] = Default::default();
         for (i, partition) in partitions.iter_mut().enumerate() {
@@ -876,47 +1436,24 @@ impl Engine {
         if snapshot.header.version > FORMAT_VERSION {
             return Err(DeserializeError::FutureVersion(snapshot.header.version));
         }
-        if snapshot.header.version < FORMAT_VERSION {
+        if snapshot.header.version < v3::VERSION {
             return Err(DeserializeError::UnsupportedVersion(
                 snapshot.header.version,
             ));
         }
 
-        let graph_p: GraphPartition =
-            bitcode::deserialize(&snapshot.partitions[0]).map_err(|e| {
-                DeserializeError::PartitionDecode {
-                    index: 0,
-                    reason: e.to_string(),
-                }
-            })?;
-        let proc_p: ProcessorPartition =
-            bitcode::deserialize(&snapshot.partitions[1]).map_err(|e| {
-                DeserializeError::PartitionDecode {
-                    index: 1,
-                    reason: e.to_string(),
-                }
-            })?;
-        let inv_p: InventoryPartition =
-            bitcode::deserialize(&snapshot.partitions[2]).map_err(|e| {
-                DeserializeError::PartitionDecode {
-                    index: 2,
-                    reason: e.to_string(),
-                }
-            })?;
-        let trans_p: TransportPartition =
-            bitcode::deserialize(&snapshot.partitions[3]).map_err(|e| {
-                DeserializeError::PartitionDecode {
-                    index: 3,
-                    reason: e.to_string(),
-                }
-            })?;
-        let junc_p: JunctionPartition =
-            bitcode::deserialize(&snapshot.partitions[4]).map_err(|e| {
-                DeserializeError::PartitionDecode {
-                    index: 4,
-                    reason: e.to_string(),
-                }
-            })?;
+        let (graph_p, proc_p, inv_p, trans_p, junc_p) = if snapshot.header.version == v3::VERSION {
+            v3::decode_partitions(&snapshot.partitions)?
+        } else {
+            (
+                decode_partition(&snapshot.partitions, 0)?,
+                decode_partition(&snapshot.partitions, 1)?,
+                decode_partition(&snapshot.partitions, 2)?,
+                decode_partition(&snapshot.partitions, 3)?,
+                decode_partition(&snapshot.partitions, 4)?,
+            )
+        };
+        let extensions = crate::extension::check_extension_mask(graph_p.extensions)?;
 
         let mut engine = Engine {
             graph: graph_p.graph,
@@ -927,30 +1464,74 @@ impl Engine {
             inputs: inv_p.inputs,
             outputs: inv_p.outputs,
             modifiers: proc_p.modifiers,
+            input_modes: proc_p.input_modes,
             transports: trans_p.transports,
             transport_states: trans_p.transport_states,
             last_state_hash: graph_p.last_state_hash,
+            last_hash_breakdown: Default::default(),
             paused: graph_p.paused,
+            step_work_limit: graph_p.step_work_limit,
+            max_catchup: graph_p.max_catchup,
             event_bus: EventBus::default(),
             modules: Vec::new(),
+            tick_hooks: Default::default(),
             dirty: crate::dirty::DirtyTracker::new(),
             junctions: junc_p.junctions,
             junction_states: junc_p.junction_states,
             edge_budgets: SecondaryMap::new(),
+            merge_priorities: junc_p.merge_priorities,
+            merge_cursors: junc_p.merge_cursors,
+            merge_turns: SecondaryMap::new(),
             transport_edge_buf: Vec::new(),
             input_buf: Vec::new(),
             node_item_type_cache: SecondaryMap::new(),
             registry: None,
             node_hash_cache: SecondaryMap::new(),
-            combined_node_hash: 0,
+            combined_node_hash: crate::engine::NodeHash::default(),
             hash_dirty_nodes: Vec::new(),
             hash_cache_cold: true,
             rng_seed: 0,
             node_rngs: SecondaryMap::new(),
+            next_item_instance_id: trans_p.next_item_instance_id.max(1),
+            fluid_ports: inv_p.fluid_ports,
+            strict_mode: false,
+            ghost_nodes: graph_p.ghost_nodes,
+            ghost_edges: graph_p.ghost_edges,
+            hash_ghosts: false,
+            crafting_queues: inv_p.crafting_queues,
+            item_decay: inv_p.item_decay,
+            edge_groups: graph_p.edge_groups,
+            edge_group_of: graph_p.edge_group_of,
+            item_categories: graph_p.item_categories,
+            edge_category_filters: trans_p.edge_category_filters,
+            category_picks: trans_p.category_picks,
+            demand_categories: proc_p.demand_categories,
+            node_user_data: graph_p.node_user_data,
+            recipe_overrides: proc_p.recipe_overrides,
+            richness_provider: proc_p.richness_provider,
+            source_sites: proc_p.source_sites,
+            warmups: proc_p.warmups,
+            full_slots: SecondaryMap::new(),
+            event_log: None,
+            event_history: None,
+            serialize_event_history: false,
+            event_cursors: slotmap::SlotMap::with_key(),
+            summary_item_totals: false,
+            latency: Default::default(),
+            operation_log: Default::default(),
+            extensions,
+            init: Default::default(),
+            step_counters: None,
+            last_step_profile: None,
             #[cfg(feature = "profiling")]
             last_profile: None,
+            tick_slice: None,
+            topology: Default::default(),
         };
         engine.rebuild_item_type_cache();
+        engine.rebuild_full_slots();
+        engine.sync_silenced_nodes();
+        engine.compute_state_hash();
         Ok(engine)
     }
 }
@@ -1018,9 +1599,13 @@ mod tests {
                     item_type,
                     quantity,
                     bonus: None,
+                    probability: None,
                 })
                 .collect(),
             duration,
+            fluid_inputs: Vec::new(),
+            fluid_outputs: Vec::new(),
+            duration_per_unit: None,
         })
     }
 
@@ -1671,6 +2256,7 @@ mod tests {
                 speed: Fixed64::from_num(1.0),
                 slot_count: 5,
                 lanes: 1,
+                min_spacing: Fixed64::ONE,
             }),
         );
         engine.set_transport(
@@ -1703,6 +2289,165 @@ mod tests {
         );
     }
 
+    #[test]
+    fn transport_state_blob_round_trips_mid_transit() {
+        let mut engine = Engine::new(SimulationStrategy::Tick);
+        let pa = engine.graph.queue_add_node(building());
+        let pb = engine.graph.queue_add_node(building());
+        let r = engine.graph.apply_mutations();
+        let (a, b) = (r.resolve_node(pa).unwrap(), r.resolve_node(pb).unwrap());
+        let pe = engine.graph.queue_connect(a, b);
+        let edge = engine.graph.apply_mutations().resolve_edge(pe).unwrap();
+        engine.set_processor(a, make_source(iron(), 1.0));
+        engine.set_input_inventory(a, simple_inventory(100));
+        engine.set_output_inventory(a, simple_inventory(100));
+        engine.set_input_inventory(b, simple_inventory(100));
+        engine.set_output_inventory(b, simple_inventory(100));
+        let belt = Transport::Item(ItemTransport {
+            speed: Fixed64::from_num(1.0),
+            slot_count: 8,
+            lanes: 1,
+            min_spacing: Fixed64::ONE,
+        });
+        engine.set_transport(edge, belt.clone());
+        for _ in 0..4 {
+            engine.step();
+        }
+
+        let blob = engine.serialize_transport_state(edge).unwrap().unwrap();
+        let mut reference = Engine::deserialize(&engine.serialize().unwrap()).unwrap();
+
+        // Resetting the belt empties it; re-applying the blob restores it.
+        engine.set_transport(edge, belt);
+        assert_ne!(
+            engine.subsystem_hashes().transports,
+            reference.subsystem_hashes().transports
+        );
+        engine.deserialize_transport_state(edge, &blob).unwrap();
+        assert_eq!(engine.subsystem_hashes(), reference.subsystem_hashes());
+        assert_eq!(engine.state_hash(), reference.state_hash());
+
+        for _ in 0..10 {
+            engine.step();
+            reference.step();
+            assert_eq!(engine.state_hash(), reference.state_hash());
+        }
+        assert_eq!(engine.subsystem_hashes(), reference.subsystem_hashes());
+
+        // A belt blob cannot be applied to a flow edge.
+        engine.set_transport(edge, make_flow_transport(1.0));
+        assert!(matches!(
+            engine.deserialize_transport_state(edge, &blob),
+            Err(DeserializeError::TransportMismatch)
+        ));
+        assert!(matches!(
+            engine.deserialize_transport_state(edge, &[1, 2, 3]),
+            Err(DeserializeError::Decode(_))
+        ));
+    }
+
+    // -----------------------------------------------------------------------
+    // Version 3 saves
+    // -----------------------------------------------------------------------
+
+    /// A version 3 save: an iron source feeding a smelter over an 8-slot
+    /// belt, the smelter feeding an assembler over a flow transport, saved
+    /// after 6 steps with 5 iron on the belt and 1 in the source's output.
+    const V3_ENGINE: &[u8] = include_bytes!("../tests/fixtures/v3_engine.bin");
+    /// The same engine saved as a version 3 partitioned snapshot.
+    const V3_PARTITIONED: &[u8] = include_bytes!("../tests/fixtures/v3_partitioned.bin");
+
+    fn assert_v3_fixture_loaded(mut engine: Engine) {
+        assert_eq!(engine.sim_state.tick, 6);
+        assert_eq!(engine.node_count(), 3);
+        assert_eq!(engine.edge_count(), 2);
+        assert_eq!(engine.crafting_queues().count(), 0);
+        let nodes: Vec<NodeId> = engine.graph.nodes().map(|(id, _)| id).collect();
+        let (mine, smelter) = (nodes[0], nodes[1]);
+        let output = engine.get_output_inventory(mine).unwrap();
+        assert_eq!(output.output_slots[0].quantity(iron()), 1);
+        assert_eq!(
+            engine.get_processor_state(smelter),
+            Some(&ProcessorState::Stalled {
+                reason: StallReason::MissingInputs
+            })
+        );
+
+        // The loaded engine runs and saves in the current format.
+        for _ in 0..20 {
+            engine.step();
+        }
+        let output = engine.get_output_inventory(smelter).unwrap();
+        assert!(output.output_slots[0].quantity(gear()) > 0);
+        let data = engine.serialize().unwrap();
+        assert_eq!(read_snapshot_header(&data).unwrap().version, FORMAT_VERSION);
+        let restored = Engine::deserialize(&data).unwrap();
+        assert_eq!(restored.state_hash(), engine.state_hash());
+    }
+
+    #[test]
+    fn v3_save_loads() {
+        assert_eq!(read_snapshot_header(V3_ENGINE).unwrap().version, 3);
+        assert_eq!(
+            Engine::detect_snapshot_format(V3_ENGINE),
+            SnapshotFormat::Legacy
+        );
+        assert_v3_fixture_loaded(Engine::deserialize(V3_ENGINE).unwrap());
+    }
+
+    #[test]
+    fn v3_partitioned_save_loads() {
+        let snapshot = PartitionedSnapshot::from_bytes(V3_PARTITIONED).unwrap();
+        assert_eq!(snapshot.header.version, 3);
+        assert_v3_fixture_loaded(Engine::deserialize_partitioned(&snapshot).unwrap());
+    }
+
+    #[test]
+    fn v3_belt_slots_load_as_positions() {
+        let snapshot = PartitionedSnapshot::from_bytes(V3_PARTITIONED).unwrap();
+        for mut engine in [
+            Engine::deserialize(V3_ENGINE).unwrap(),
+            Engine::deserialize_partitioned(&snapshot).unwrap(),
+        ] {
+            let belt_edge = engine.graph.edges().next().unwrap().0;
+            let Some(TransportState::Item(belt)) = engine.transport_states.get(belt_edge) else {
+                panic!("expected a belt");
+            };
+            // The save held iron in slots 3 to 7 of 8, slot 0 at the output
+            // end: the front item sits 4 spacings in, the last at the input.
+            assert_eq!(belt.slot_count, 8);
+            assert_eq!(belt.occupied_count(), 5);
+            let items: Vec<(u8, ItemTypeId, Fixed64)> = belt
+                .items()
+                .map(|(lane, i)| (lane, i.item_type, i.position))
+                .collect();
+            let expected: Vec<(u8, ItemTypeId, Fixed64)> = (0..5u32)
+                .rev()
+                .map(|p| (0, iron(), Fixed64::from_num(p)))
+                .collect();
+            assert_eq!(items, expected);
+
+            // The items keep their order as the belt runs on.
+            engine.step();
+            let Some(TransportState::Item(belt)) = engine.transport_states.get(belt_edge) else {
+                panic!("expected a belt");
+            };
+            let positions: Vec<Fixed64> = belt.items().map(|(_, i)| i.position).collect();
+            assert_eq!(positions[0], Fixed64::from_num(5));
+            assert!(positions.windows(2).all(|w| w[0] > w[1]));
+        }
+    }
+
+    #[test]
+    fn v3_partitioned_baseline_is_not_reused() {
+        let snapshot = PartitionedSnapshot::from_bytes(V3_PARTITIONED).unwrap();
+        let mut engine = Engine::deserialize_partitioned(&snapshot).unwrap();
+        let incremental = engine.serialize_incremental(Some(&snapshot)).unwrap();
+        assert_eq!(incremental.header.version, FORMAT_VERSION);
+        let restored = Engine::deserialize_partitioned(&incremental).unwrap();
+        assert_eq!(restored.state_hash(), engine.state_hash());
+    }
+
     // -----------------------------------------------------------------------
     // Partitioned serialization tests
     // -----------------------------------------------------------------------
@@ -2093,4 +2838,52 @@ mod tests {
             Err(DeserializeError::InvalidMagic(0xDEADBEEF))
         ));
     }
+
+    #[test]
+    fn fluid_recipes_and_buffers_survive_both_formats() {
+        let water = ItemTypeId(5);
+        let sludge = ItemTypeId(6);
+        let mut engine = make_test_engine();
+        let pending = engine.graph.queue_add_node(building());
+        let result = engine.graph.apply_mutations();
+        let washer = result.resolve_node(pending).unwrap();
+        let Processor::Fixed(mut recipe) = make_recipe(vec![(iron(), 1)], vec![(gear(), 1)], 3)
+        else {
+            unreachable!()
+        };
+        recipe.fluid_inputs = vec![FluidAmount {
+            fluid: water,
+            amount: Fixed64::from_num(5),
+        }];
+        recipe.fluid_outputs = vec![FluidAmount {
+            fluid: sludge,
+            amount: Fixed64::from_num(2),
+        }];
+        engine.set_processor(washer, Processor::Fixed(recipe));
+        let mut input = simple_inventory(100);
+        let _ = input.input_slots[0].add(iron(), 10);
+        engine.set_input_inventory(washer, input);
+        engine.set_output_inventory(washer, simple_inventory(100));
+        for _ in 0..4 {
+            engine.add_fluid_input(washer, water, Fixed64::from_num(5));
+            engine.step();
+        }
+        // One cycle finished; its undrained sludge blocks the next one, which
+        // keeps the refilled water buffered.
+        let ports = engine.fluid_ports(washer).unwrap().clone();
+        assert_eq!(ports.output_of(sludge), Fixed64::from_num(2));
+        assert_eq!(ports.input_of(water), Fixed64::from_num(5));
+
+        let legacy = Engine::deserialize(&engine.serialize().unwrap()).unwrap();
+        let partitioned =
+            Engine::deserialize_partitioned(&engine.serialize_partitioned().unwrap()).unwrap();
+        for restored in [legacy, partitioned] {
+            assert_eq!(restored.fluid_ports(washer), Some(&ports));
+            assert_eq!(
+                restored.get_processor(washer).unwrap().fluid_inputs(),
+                engine.get_processor(washer).unwrap().fluid_inputs()
+            );
+            assert_eq!(restored.state_hash(), engine.state_hash());
+        }
+    }
 }
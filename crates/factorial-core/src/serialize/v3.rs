@@ -0,0 +1,547 @@
+//! Decoding of version 3 snapshots.
+//!
+//! bitcode is not self-describing, so a field added to a serialized type
+//! changes the layout even when it has a serde default. The types below are
+//! frozen copies of every serialized type whose layout changed since
+//! version 3; unchanged types are decoded with the current definitions.
+//! Each decoded snapshot is converted to the current layout with the new
+//! state left at its defaults: no crafting queues, no fluid ports, no item
+//! ages and so on.
+//!
+//! Version 1 replay logs embed processors, inventories and transports in
+//! this layout too, so [`crate::replay`] decodes them with the same types.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use slotmap::{Key, SecondaryMap, SlotMap};
+
+use super::{
+    DeserializeError, EngineSnapshot, GraphPartition, InventoryPartition, JunctionPartition,
+    Partitions, ProcessorPartition, SNAPSHOT_MAGIC, SnapshotHeader, TransportPartition,
+    decode_partition,
+};
+use crate::fixed::Fixed64;
+use crate::graph::{EdgeData, NodeAdjacency, NodeData, ProductionGraph};
+use crate::id::{BuildingTypeId, EdgeId, ItemTypeId, NodeId, PropertyId};
+use crate::item::{Inventory, InventorySlot, ItemStack};
+use crate::junction::{Junction, JunctionState};
+use crate::processor::{
+    BonusOutput, DemandProcessor, FixedRecipe, Modifier, MultiRecipeProcessor, Processor,
+    ProcessorState, PropertyProcessor, RecipeInput, RecipeOutput, RecipeSwitchPolicy,
+    SourceProcessor,
+};
+use crate::rng::SimRng;
+use crate::sim::{SimState, SimulationStrategy};
+use crate::transport::{
+    BatchState, BatchTransport, BeltState, FlowState, FlowTransport, ItemTransport,
+    PropertyManifest, Transport, TransportState, VehicleState, VehicleTransport,
+};
+
+/// The format version decoded by this module.
+pub(super) const VERSION: u32 = 3;
+
+/// Decode a version 3 snapshot body. `None` if `body` is not one.
+pub(super) fn decode_snapshot(body: &[u8]) -> Option<EngineSnapshot> {
+    let snapshot: EngineSnapshotV3 = bitcode::deserialize(body).ok()?;
+    if snapshot.header.magic != SNAPSHOT_MAGIC || snapshot.header.version != VERSION {
+        return None;
+    }
+    snapshot.upgrade().ok()
+}
+
+/// Read the header of a version 3 snapshot body.
+pub(super) fn decode_header(body: &[u8]) -> Option<SnapshotHeader> {
+    let snapshot: EngineSnapshotV3 = bitcode::deserialize(body).ok()?;
+    Some(snapshot.header)
+}
+
+/// Decode the partitions of a version 3 partitioned snapshot.
+pub(super) fn decode_partitions(partitions: &[Vec<u8>; 5]) -> Result<Partitions, DeserializeError> {
+    let graph: GraphPartitionV3 = decode_partition(partitions, 0)?;
+    let processors: ProcessorPartitionV3 = decode_partition(partitions, 1)?;
+    let inventories: InventoryPartitionV3 = decode_partition(partitions, 2)?;
+    let transports: TransportPartitionV3 = decode_partition(partitions, 3)?;
+    let junctions: JunctionPartitionV3 = decode_partition(partitions, 4)?;
+    let transport_map = remap(transports.transports, TransportV3::upgrade);
+    let transport_states = upgrade_transport_states(transports.transport_states, &transport_map);
+    Ok((
+        GraphPartition {
+            graph: graph.graph.upgrade()?,
+            sim_state: graph.sim_state,
+            strategy: graph.strategy,
+            last_state_hash: graph.last_state_hash,
+            paused: graph.paused,
+            ghost_nodes: SecondaryMap::new(),
+            ghost_edges: SecondaryMap::new(),
+            extensions: 0,
+            item_categories: BTreeMap::new(),
+            edge_groups: SlotMap::with_key(),
+            edge_group_of: SecondaryMap::new(),
+            step_work_limit: None,
+            max_catchup: None,
+            node_user_data: SecondaryMap::new(),
+        },
+        ProcessorPartition {
+            processors: remap(processors.processors, ProcessorV3::upgrade),
+            processor_states: processors.processor_states,
+            modifiers: processors.modifiers,
+            input_modes: SecondaryMap::new(),
+            demand_categories: SecondaryMap::new(),
+            recipe_overrides: BTreeMap::new(),
+            richness_provider: None,
+            source_sites: SecondaryMap::new(),
+            warmups: SecondaryMap::new(),
+        },
+        InventoryPartition {
+            inputs: remap(inventories.inputs, InventoryV3::upgrade),
+            outputs: remap(inventories.outputs, InventoryV3::upgrade),
+            fluid_ports: SecondaryMap::new(),
+            crafting_queues: SlotMap::with_key(),
+            item_decay: BTreeMap::new(),
+        },
+        TransportPartition {
+            transports: transport_map,
+            transport_states,
+            next_item_instance_id: 0,
+            edge_category_filters: SecondaryMap::new(),
+            category_picks: SecondaryMap::new(),
+        },
+        JunctionPartition {
+            junctions: junctions.junctions,
+            junction_states: junctions.junction_states,
+            merge_priorities: SecondaryMap::new(),
+            merge_cursors: SecondaryMap::new(),
+        },
+    ))
+}
+
+/// Convert every value of a secondary map, keeping the keys.
+fn remap<K: Key, A, B>(map: SecondaryMap<K, A>, f: impl Fn(A) -> B) -> SecondaryMap<K, B> {
+    map.into_iter()
+        .map(|(key, value)| (key, f(value)))
+        .collect()
+}
+
+/// Convert transport states, laying out belt slots with their edge's
+/// transport. A belt state whose edge has no item transport is dropped.
+fn upgrade_transport_states(
+    states: SecondaryMap<EdgeId, TransportStateV3>,
+    transports: &SecondaryMap<EdgeId, Transport>,
+) -> SecondaryMap<EdgeId, TransportState> {
+    states
+        .into_iter()
+        .filter_map(|(edge, state)| Some((edge, state.upgrade(transports.get(edge))?)))
+        .collect()
+}
+
+// ---------------------------------------------------------------------------
+// Snapshot layouts
+// ---------------------------------------------------------------------------
+
+#[derive(Deserialize)]
+struct EngineSnapshotV3 {
+    header: SnapshotHeader,
+    graph: ProductionGraphV3,
+    strategy: SimulationStrategy,
+    sim_state: SimState,
+    processors: SecondaryMap<NodeId, ProcessorV3>,
+    processor_states: SecondaryMap<NodeId, ProcessorState>,
+    inputs: SecondaryMap<NodeId, InventoryV3>,
+    outputs: SecondaryMap<NodeId, InventoryV3>,
+    modifiers: SecondaryMap<NodeId, Vec<Modifier>>,
+    transports: SecondaryMap<EdgeId, TransportV3>,
+    transport_states: SecondaryMap<EdgeId, TransportStateV3>,
+    last_state_hash: u64,
+    paused: bool,
+    junctions: SecondaryMap<NodeId, Junction>,
+    junction_states: SecondaryMap<NodeId, JunctionState>,
+    rng_seed: u64,
+    node_rngs: SecondaryMap<NodeId, SimRng>,
+}
+
+impl EngineSnapshotV3 {
+    fn upgrade(self) -> Result<EngineSnapshot, DeserializeError> {
+        let transports = remap(self.transports, TransportV3::upgrade);
+        let transport_states = upgrade_transport_states(self.transport_states, &transports);
+        Ok(EngineSnapshot {
+            header: self.header,
+            graph: self.graph.upgrade()?,
+            strategy: self.strategy,
+            sim_state: self.sim_state,
+            processors: remap(self.processors, ProcessorV3::upgrade),
+            processor_states: self.processor_states,
+            inputs: remap(self.inputs, InventoryV3::upgrade),
+            outputs: remap(self.outputs, InventoryV3::upgrade),
+            modifiers: self.modifiers,
+            transports,
+            transport_states,
+            last_state_hash: self.last_state_hash,
+            paused: self.paused,
+            step_work_limit: None,
+            junctions: self.junctions,
+            junction_states: self.junction_states,
+            merge_priorities: SecondaryMap::new(),
+            merge_cursors: SecondaryMap::new(),
+            rng_seed: self.rng_seed,
+            node_rngs: self.node_rngs,
+            next_item_instance_id: 0,
+            fluid_ports: SecondaryMap::new(),
+            ghost_nodes: SecondaryMap::new(),
+            ghost_edges: SecondaryMap::new(),
+            event_history: None,
+            crafting_queues: SlotMap::with_key(),
+            item_decay: BTreeMap::new(),
+            input_modes: SecondaryMap::new(),
+            extensions: 0,
+            edge_groups: SlotMap::with_key(),
+            edge_group_of: SecondaryMap::new(),
+            item_categories: BTreeMap::new(),
+            edge_category_filters: SecondaryMap::new(),
+            category_picks: SecondaryMap::new(),
+            demand_categories: SecondaryMap::new(),
+            recipe_overrides: BTreeMap::new(),
+            richness_provider: None,
+            source_sites: SecondaryMap::new(),
+            warmups: SecondaryMap::new(),
+            max_catchup: None,
+            node_user_data: SecondaryMap::new(),
+        })
+    }
+}
+
+#[derive(Deserialize)]
+struct GraphPartitionV3 {
+    graph: ProductionGraphV3,
+    sim_state: SimState,
+    strategy: SimulationStrategy,
+    last_state_hash: u64,
+    paused: bool,
+}
+
+#[derive(Deserialize)]
+struct ProcessorPartitionV3 {
+    processors: SecondaryMap<NodeId, ProcessorV3>,
+    processor_states: SecondaryMap<NodeId, ProcessorState>,
+    modifiers: SecondaryMap<NodeId, Vec<Modifier>>,
+}
+
+#[derive(Deserialize)]
+struct InventoryPartitionV3 {
+    inputs: SecondaryMap<NodeId, InventoryV3>,
+    outputs: SecondaryMap<NodeId, InventoryV3>,
+}
+
+#[derive(Deserialize)]
+struct TransportPartitionV3 {
+    transports: SecondaryMap<EdgeId, TransportV3>,
+    transport_states: SecondaryMap<EdgeId, TransportStateV3>,
+}
+
+#[derive(Deserialize)]
+struct JunctionPartitionV3 {
+    junctions: SecondaryMap<NodeId, Junction>,
+    junction_states: SecondaryMap<NodeId, JunctionState>,
+}
+
+// ---------------------------------------------------------------------------
+// Graph
+// ---------------------------------------------------------------------------
+
+/// One slot of a serialized `SlotMap`, as slotmap writes it.
+#[derive(Serialize, Deserialize)]
+struct Slot<T> {
+    value: Option<T>,
+    version: u32,
+}
+
+#[derive(Deserialize)]
+struct NodeDataV3 {
+    building_type: BuildingTypeId,
+}
+
+#[derive(Deserialize)]
+struct ProductionGraphV3 {
+    nodes: Vec<Slot<NodeDataV3>>,
+    edges: SlotMap<EdgeId, EdgeData>,
+    adjacency: SecondaryMap<NodeId, NodeAdjacency>,
+    next_pending_node: u64,
+    next_pending_edge: u64,
+}
+
+/// The serialized fields of the current [`ProductionGraph`], in order.
+#[derive(Serialize)]
+struct ProductionGraphFields {
+    nodes: Vec<Slot<NodeData>>,
+    edges: SlotMap<EdgeId, EdgeData>,
+    adjacency: SecondaryMap<NodeId, NodeAdjacency>,
+    next_pending_node: u64,
+    next_pending_edge: u64,
+}
+
+impl ProductionGraphV3 {
+    /// `SlotMap` cannot insert at a given key, so the node data is
+    /// converted slot by slot and the graph re-encoded and decoded with the
+    /// current layout, which keeps every node id.
+    fn upgrade(self) -> Result<ProductionGraph, DeserializeError> {
+        let fields = ProductionGraphFields {
+            nodes: self
+                .nodes
+                .into_iter()
+                .map(|slot| Slot {
+                    value: slot.value.map(|node| NodeData {
+                        building_type: node.building_type,
+                        flags: Default::default(),
+                    }),
+                    version: slot.version,
+                })
+                .collect(),
+            edges: self.edges,
+            adjacency: self.adjacency,
+            next_pending_node: self.next_pending_node,
+            next_pending_edge: self.next_pending_edge,
+        };
+        bitcode::serialize(&fields)
+            .and_then(|bytes| bitcode::deserialize(&bytes))
+            .map_err(|e| DeserializeError::Decode(e.to_string()))
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Processors
+// ---------------------------------------------------------------------------
+
+#[derive(Deserialize)]
+struct RecipeOutputV3 {
+    item_type: ItemTypeId,
+    quantity: u32,
+    bonus: Option<BonusOutput>,
+}
+
+#[derive(Deserialize)]
+pub(crate) struct FixedRecipeV3 {
+    inputs: Vec<RecipeInput>,
+    outputs: Vec<RecipeOutputV3>,
+    duration: u32,
+}
+
+impl FixedRecipeV3 {
+    fn upgrade(self) -> FixedRecipe {
+        FixedRecipe {
+            inputs: self.inputs,
+            outputs: self
+                .outputs
+                .into_iter()
+                .map(|output| RecipeOutput {
+                    item_type: output.item_type,
+                    quantity: output.quantity,
+                    bonus: output.bonus,
+                    probability: None,
+                })
+                .collect(),
+            duration: self.duration,
+            fluid_inputs: Vec::new(),
+            fluid_outputs: Vec::new(),
+            duration_per_unit: None,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub(crate) struct MultiRecipeProcessorV3 {
+    recipes: Vec<FixedRecipeV3>,
+    active_recipe: usize,
+    switch_policy: RecipeSwitchPolicy,
+    pending_switch: Option<usize>,
+    in_progress_inputs: Vec<(ItemTypeId, u32)>,
+}
+
+#[derive(Deserialize)]
+pub(crate) enum ProcessorV3 {
+    Source(SourceProcessor),
+    Fixed(FixedRecipeV3),
+    Property(PropertyProcessor),
+    Demand(DemandProcessor),
+    Passthrough,
+    MultiRecipe(MultiRecipeProcessorV3),
+}
+
+impl ProcessorV3 {
+    pub(crate) fn upgrade(self) -> Processor {
+        match self {
+            ProcessorV3::Source(source) => Processor::Source(source),
+            ProcessorV3::Fixed(recipe) => Processor::Fixed(recipe.upgrade()),
+            ProcessorV3::Property(property) => Processor::Property(property),
+            ProcessorV3::Demand(demand) => Processor::Demand(demand),
+            ProcessorV3::Passthrough => Processor::Passthrough,
+            ProcessorV3::MultiRecipe(multi) => Processor::MultiRecipe(MultiRecipeProcessor {
+                recipes: multi
+                    .recipes
+                    .into_iter()
+                    .map(FixedRecipeV3::upgrade)
+                    .collect(),
+                active_recipe: multi.active_recipe,
+                switch_policy: multi.switch_policy,
+                pending_switch: multi.pending_switch,
+                in_progress_inputs: multi.in_progress_inputs,
+            }),
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Items
+// ---------------------------------------------------------------------------
+
+#[derive(Deserialize)]
+struct ItemStackV3 {
+    item_type: ItemTypeId,
+    quantity: u32,
+    properties: BTreeMap<PropertyId, Fixed64>,
+}
+
+impl ItemStackV3 {
+    fn upgrade(self) -> ItemStack {
+        ItemStack {
+            item_type: self.item_type,
+            quantity: self.quantity,
+            properties: self.properties,
+            ages: Vec::new(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct InventorySlotV3 {
+    stacks: Vec<ItemStackV3>,
+    capacity: u32,
+}
+
+#[derive(Deserialize)]
+pub(crate) struct InventoryV3 {
+    input_slots: Vec<InventorySlotV3>,
+    output_slots: Vec<InventorySlotV3>,
+}
+
+impl InventoryV3 {
+    pub(crate) fn upgrade(self) -> Inventory {
+        let upgrade_slots = |slots: Vec<InventorySlotV3>| {
+            slots
+                .into_iter()
+                .map(|slot| InventorySlot {
+                    stacks: slot.stacks.into_iter().map(ItemStackV3::upgrade).collect(),
+                    capacity: slot.capacity,
+                    filter: None,
+                })
+                .collect()
+        };
+        Inventory {
+            input_slots: upgrade_slots(self.input_slots),
+            output_slots: upgrade_slots(self.output_slots),
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Transports
+// ---------------------------------------------------------------------------
+
+#[derive(Deserialize)]
+pub(crate) struct ItemTransportV3 {
+    speed: Fixed64,
+    slot_count: u32,
+    lanes: u8,
+}
+
+#[derive(Deserialize)]
+pub(crate) enum TransportV3 {
+    Flow(FlowTransport),
+    Item(ItemTransportV3),
+    Batch(BatchTransport),
+    Vehicle(VehicleTransport),
+}
+
+impl TransportV3 {
+    pub(crate) fn upgrade(self) -> Transport {
+        match self {
+            TransportV3::Flow(flow) => Transport::Flow(flow),
+            TransportV3::Item(item) => Transport::Item(ItemTransport {
+                speed: item.speed,
+                slot_count: item.slot_count,
+                lanes: item.lanes,
+                min_spacing: Fixed64::ONE,
+            }),
+            TransportV3::Batch(batch) => Transport::Batch(batch),
+            TransportV3::Vehicle(vehicle) => Transport::Vehicle(vehicle),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct FlowStateV3 {
+    buffered: Fixed64,
+    latency_remaining: u32,
+}
+
+/// Belts held one entry per slot, slot 0 at the output end of lane 0; see
+/// [`BeltState::from_slots`].
+#[derive(Deserialize)]
+struct BeltStateV3 {
+    slots: Vec<Option<ItemTypeId>>,
+}
+
+#[derive(Deserialize)]
+struct BatchStateV3 {
+    progress: u32,
+    pending: u32,
+}
+
+#[derive(Deserialize)]
+struct VehicleStateV3 {
+    position: u32,
+    cargo: Vec<ItemStackV3>,
+    returning: bool,
+}
+
+#[derive(Deserialize)]
+enum TransportStateV3 {
+    Flow(FlowStateV3),
+    Item(BeltStateV3),
+    Batch(BatchStateV3),
+    Vehicle(VehicleStateV3),
+}
+
+impl TransportStateV3 {
+    fn upgrade(self, transport: Option<&Transport>) -> Option<TransportState> {
+        Some(match self {
+            TransportStateV3::Flow(flow) => TransportState::Flow(FlowState {
+                buffered: flow.buffered,
+                latency_remaining: flow.latency_remaining,
+                intake_remainder: Fixed64::ZERO,
+                accumulator: Fixed64::ZERO,
+                properties: PropertyManifest::default(),
+            }),
+            TransportStateV3::Item(belt) => {
+                let Some(Transport::Item(item)) = transport else {
+                    return None;
+                };
+                TransportState::Item(BeltState::from_slots(&belt.slots, item))
+            }
+            TransportStateV3::Batch(batch) => TransportState::Batch(BatchState {
+                progress: batch.progress,
+                pending: batch.pending,
+                properties: PropertyManifest::default(),
+            }),
+            TransportStateV3::Vehicle(vehicle) => TransportState::Vehicle(VehicleState {
+                position: vehicle.position,
+                cargo: vehicle
+                    .cargo
+                    .into_iter()
+                    .map(ItemStackV3::upgrade)
+                    .collect(),
+                returning: vehicle.returning,
+                properties: PropertyManifest::default(),
+            }),
+        })
+    }
+}
@@ -11,7 +11,8 @@ use crate::graph::MutationResult;
 // Simulation strategy
 // ---------------------------------------------------------------------------
 
-/// How the engine advances time. Chosen at engine construction.
+/// How the engine advances time. Chosen at engine construction and
+/// switchable between steps with `Engine::set_strategy`.
 ///
 /// All strategies execute the same six-phase step internally. The strategy
 /// only controls how many steps are run when `Engine::advance()` is called.
@@ -77,6 +78,51 @@ pub struct AdvanceResult {
     /// Mutation results from the pre-tick phase of each step.
     /// One entry per step that had pending mutations.
     pub mutation_results: Vec<MutationResult>,
+
+    /// The last step run exceeded the step work limit and skipped its
+    /// remaining simulation phases. See `Engine::set_step_work_limit`.
+    pub truncated: bool,
+
+    /// Ticks of accumulated time dropped because the call reached the
+    /// catch-up limit under [`CatchupPolicy::Discard`]. See
+    /// `Engine::set_max_catchup_steps`.
+    pub discarded: Ticks,
+}
+
+/// What `Engine::advance` does with the time it could not simulate because
+/// it reached the catch-up limit set with `Engine::set_max_catchup_steps`.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize,
+)]
+pub enum CatchupPolicy {
+    /// Drop the whole steps that did not run, keeping only the part of a
+    /// step left over. The simulation falls behind wall-clock time instead
+    /// of trying to catch up, so a slow frame cannot cause slower ones.
+    #[default]
+    Discard,
+    /// Keep the time in the accumulator, so later calls catch up, at most
+    /// the limit per call.
+    Retain,
+}
+
+// ---------------------------------------------------------------------------
+// Step phases
+// ---------------------------------------------------------------------------
+
+/// A simulation phase of a step, in the order they run. Used to report
+/// where a step was truncated.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, serde::Serialize, serde::Deserialize,
+)]
+pub enum StepPhase {
+    /// Queued graph mutations are applied.
+    PreTick,
+    /// Transports move items.
+    Transport,
+    /// Processors run.
+    Process,
+    /// Modules, crafting queues, spoilage and capacity tracking run.
+    Component,
 }
 
 // ---------------------------------------------------------------------------
@@ -0,0 +1,291 @@
+//! Sliced ticks: one step spread across several host frames.
+//!
+//! Hosts that cannot afford a whole step in one frame open a tick with
+//! [`Engine::begin_tick`] and drive it with repeated calls to
+//! [`Engine::continue_tick`], each doing at most `work_budget` units of the
+//! step, until it returns [`TickSliceResult::Complete`]. The step runs the
+//! same phases in the same order as [`Engine::step`], so the final state,
+//! state hash and events are bit-identical to a monolithic step.
+//!
+//! The budget counts units of the step:
+//!
+//! - one for the pre-tick phase (queued mutations and `Pre` hooks);
+//! - one per edge in the transport phase;
+//! - one per node in the process phase (one per topological level with the
+//!   `parallel` feature);
+//! - one for the component phase;
+//! - one for the rest of the step: event delivery, bookkeeping and
+//!   `PostStep` hooks.
+//!
+//! Every call makes progress, so a budget of zero counts as one. The work
+//! counters in [`Engine::last_step_profile`] give a host the size of the
+//! previous step to pick a budget from.
+//!
+//! While a tick is open the engine is mid-step. Hosts must not change the
+//! graph, processors, inventories or configuration until it completes: the
+//! FFI layer rejects such calls with `TickInProgress`, and serialization
+//! returns [`SerializeError::TickInProgress`](crate::serialize::SerializeError).
+//! Mutations queued on the graph in the meantime are applied by the next
+//! step, as usual. [`Engine::advance`] finishes an open tick instead of
+//! starting a new one.
+//!
+//! The step work limit applies to sliced ticks as it does to whole steps.
+//! With the `profiling` feature, sliced ticks do not record a
+//! [`TickProfile`](crate::profiling::TickProfile): wall-clock phase times
+//! mean little once a phase spans frames.
+
+use crate::engine::{Engine, ProcessUnit};
+use crate::hook::TickPhase;
+use crate::sim::{AdvanceResult, StepPhase};
+
+/// Outcome of [`Engine::continue_tick`].
+#[derive(Debug)]
+pub enum TickSliceResult {
+    /// The tick still has work left; call `continue_tick` again.
+    InProgress,
+    /// The tick is complete, with the result `step` would have returned.
+    Complete(AdvanceResult),
+}
+
+/// Why [`Engine::begin_tick`] could not open a tick.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+pub enum TickSliceError {
+    #[error("a sliced tick is already in progress")]
+    InProgress,
+    #[error("the simulation is paused")]
+    Paused,
+}
+
+/// Where an open tick stands.
+#[derive(Debug)]
+enum Stage {
+    PreTick,
+    /// Advancing `transport_edge_buf[next..]`.
+    Transport {
+        next: usize,
+    },
+    /// Processing `units[next..]`.
+    Process {
+        units: Vec<ProcessUnit>,
+        next: usize,
+    },
+    Component,
+    Finish,
+}
+
+/// An open sliced tick. Session state: never serialized.
+#[derive(Debug)]
+pub(crate) struct TickSlice {
+    stage: Stage,
+    result: AdvanceResult,
+    events_before: u64,
+    profiling: bool,
+    truncated: bool,
+}
+
+impl Engine {
+    /// Open a sliced tick, to be run with
+    /// [`continue_tick`](Self::continue_tick). The tick is one fixed step
+    /// whatever the strategy; in delta mode the accumulator is not touched.
+    /// Fails if a tick is already open or the simulation is paused.
+    pub fn begin_tick(&mut self) -> Result<(), TickSliceError> {
+        if self.tick_slice.is_some() {
+            return Err(TickSliceError::InProgress);
+        }
+        if self.paused {
+            return Err(TickSliceError::Paused);
+        }
+        if self.init.auto && !self.init.done {
+            self.initialize();
+        }
+        let profiling = self.start_step_counters();
+        self.tick_slice = Some(TickSlice {
+            stage: Stage::PreTick,
+            result: AdvanceResult::default(),
+            events_before: self.event_bus.total_emitted_all(),
+            profiling,
+            truncated: false,
+        });
+        Ok(())
+    }
+
+    /// Run up to `work_budget` units of the open tick. Returns
+    /// [`TickSliceResult::Complete`] once the step is done, or an empty
+    /// result if no tick is open.
+    pub fn continue_tick(&mut self, work_budget: u32) -> TickSliceResult {
+        let Some(mut slice) = self.tick_slice.take() else {
+            return TickSliceResult::Complete(AdvanceResult::default());
+        };
+        let mut budget = work_budget.max(1);
+        while budget > 0 {
+            budget -= 1;
+            if self.run_slice_unit(&mut slice) {
+                let mut result = slice.result;
+                result.steps_run += 1;
+                result.truncated = slice.truncated;
+                self.finish_step_counters(slice.profiling);
+                return TickSliceResult::Complete(result);
+            }
+        }
+        self.tick_slice = Some(slice);
+        TickSliceResult::InProgress
+    }
+
+    /// Whether a sliced tick is open.
+    pub fn is_tick_in_progress(&self) -> bool {
+        self.tick_slice.is_some()
+    }
+
+    /// Finish the open tick in one go.
+    pub(crate) fn finish_open_tick(&mut self) -> AdvanceResult {
+        loop {
+            if let TickSliceResult::Complete(result) = self.continue_tick(u32::MAX) {
+                return result;
+            }
+        }
+    }
+
+    /// Run one unit of the tick. Returns true once the step is complete.
+    fn run_slice_unit(&mut self, slice: &mut TickSlice) -> bool {
+        match &mut slice.stage {
+            Stage::PreTick => {
+                self.phase_pre_tick(&mut slice.result);
+                self.run_tick_hooks(TickPhase::Pre);
+                slice.truncated = self.step_work_exceeded(StepPhase::PreTick, slice.events_before);
+                slice.stage = if slice.truncated {
+                    Stage::Finish
+                } else {
+                    self.begin_transport_phase();
+                    Stage::Transport { next: 0 }
+                };
+            }
+            Stage::Transport { next } => {
+                if *next < self.transport_edge_buf.len() {
+                    self.transport_edge(*next);
+                    *next += 1;
+                }
+                if *next >= self.transport_edge_buf.len() {
+                    self.finish_transport_phase();
+                    self.run_tick_hooks(TickPhase::PostTransports);
+                    slice.truncated =
+                        self.step_work_exceeded(StepPhase::Transport, slice.events_before);
+                    slice.stage = if slice.truncated {
+                        Stage::Finish
+                    } else {
+                        Stage::Process {
+                            units: self.begin_process_phase(),
+                            next: 0,
+                        }
+                    };
+                }
+            }
+            Stage::Process { units, next } => {
+                if let Some(unit) = units.get(*next) {
+                    self.process_unit(unit);
+                    *next += 1;
+                }
+                if *next >= units.len() {
+                    self.finish_process_phase(std::mem::take(units));
+                    self.run_tick_hooks(TickPhase::PostProcessors);
+                    slice.truncated =
+                        self.step_work_exceeded(StepPhase::Process, slice.events_before);
+                    slice.stage = if slice.truncated {
+                        Stage::Finish
+                    } else {
+                        Stage::Component
+                    };
+                }
+            }
+            Stage::Component => {
+                self.phase_component();
+                slice.stage = Stage::Finish;
+            }
+            Stage::Finish => {
+                self.phase_post_tick();
+                self.phase_bookkeeping();
+                if self.run_tick_hooks(TickPhase::PostStep) {
+                    self.last_state_hash = self.compute_state_hash();
+                }
+                return true;
+            }
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event::Event;
+    use crate::rng::SimRng;
+    use crate::sim::SimulationStrategy;
+    use crate::test_utils::*;
+
+    /// Run 200 ticks and return the state hash after each with every event
+    /// logged, slicing each tick into random budgets when `seed` is given.
+    fn run(seed: Option<u64>) -> (Vec<u64>, Vec<Event>) {
+        let mut engine = build_large_factory(100);
+        engine.enable_event_log();
+        let mut rng = seed.map(SimRng::new);
+        let mut hashes = Vec::new();
+        let mut events = Vec::new();
+        for _ in 0..200 {
+            match rng.as_mut() {
+                None => {
+                    engine.step();
+                }
+                Some(rng) => {
+                    engine.begin_tick().unwrap();
+                    let mut slices = 0;
+                    while let TickSliceResult::InProgress =
+                        engine.continue_tick(rng.next_u64() as u32 % 8)
+                    {
+                        slices += 1;
+                        assert!(engine.is_tick_in_progress());
+                    }
+                    assert!(slices > 0);
+                }
+            }
+            hashes.push(engine.state_hash());
+            events.extend(engine.take_event_log().unwrap().events().cloned());
+        }
+        (hashes, events)
+    }
+
+    #[test]
+    fn sliced_ticks_match_monolithic_steps() {
+        let (whole_hashes, whole_events) = run(None);
+        let (sliced_hashes, sliced_events) = run(Some(17));
+        assert_eq!(whole_hashes, sliced_hashes);
+        assert!(!whole_events.is_empty());
+        assert_eq!(whole_events, sliced_events);
+    }
+
+    #[test]
+    fn open_ticks_refuse_a_second_begin_and_saves() {
+        let mut engine = Engine::new(SimulationStrategy::Tick);
+        let mine = add_node(&mut engine, make_source(iron(), 1.0), 0, 10);
+        engine.begin_tick().unwrap();
+        assert_eq!(engine.begin_tick(), Err(TickSliceError::InProgress));
+        assert!(matches!(
+            engine.serialize(),
+            Err(crate::serialize::SerializeError::TickInProgress)
+        ));
+
+        // `advance` finishes the open tick rather than starting another.
+        let result = engine.advance(0);
+        assert_eq!(result.steps_run, 1);
+        assert_eq!(engine.sim_state.tick, 1);
+        assert!(!engine.is_tick_in_progress());
+        assert_eq!(output_quantity(&engine, mine, iron()), 1);
+        assert!(engine.serialize().is_ok());
+
+        // Without an open tick there is nothing to continue.
+        match engine.continue_tick(4) {
+            TickSliceResult::Complete(result) => assert_eq!(result.steps_run, 0),
+            TickSliceResult::InProgress => panic!("no tick was open"),
+        }
+        engine.pause();
+        assert_eq!(engine.begin_tick(), Err(TickSliceError::Paused));
+    }
+}
@@ -0,0 +1,425 @@
+//! Item decay for perishable goods.
+//!
+//! An item type with a decay configured ages while it sits in an inventory.
+//! Each stack of a perishable type keeps age cohorts ([`ItemAge`]): groups of
+//! items that entered the inventory on the same tick. Once per tick, during
+//! the component phase, the engine reconciles the cohorts with the stack:
+//! items that appeared since the last tick form a new cohort, and items that
+//! left are taken from the oldest cohorts first. Cohorts that have reached
+//! their lifetime spoil -- they are replaced by the configured product, or
+//! vanish -- and an [`Event::ItemSpoiled`] is emitted.
+//!
+//! Ages belong to the inventory holding the items. Items moved along an edge
+//! arrive fresh at the destination.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::engine::Engine;
+use crate::event::Event;
+use crate::fixed::Ticks;
+use crate::id::{ItemTypeId, NodeId};
+use crate::item::{InventorySlot, ItemAge, ItemStack};
+use crate::sim::StateHash;
+
+/// Decay settings for one perishable item type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ItemDecay {
+    /// Ticks an item may sit in an inventory before it spoils.
+    pub ticks_to_spoil: Ticks,
+    /// What spoiled items turn into. `None` means they vanish.
+    pub spoils_into: Option<ItemTypeId>,
+}
+
+impl Engine {
+    /// Make `item_type` perishable: items of that type spoil `ticks_to_spoil`
+    /// ticks after entering an inventory, turning into `spoils_into` or
+    /// vanishing if `None`. Replaces any previous decay for the type. Items
+    /// already stored start aging on the next tick.
+    pub fn set_item_decay(
+        &mut self,
+        item_type: ItemTypeId,
+        ticks_to_spoil: Ticks,
+        spoils_into: Option<ItemTypeId>,
+    ) {
+        self.enable_extension(crate::extension::ExtensionId::Spoilage);
+        self.item_decay.insert(
+            item_type,
+            ItemDecay {
+                ticks_to_spoil,
+                spoils_into,
+            },
+        );
+    }
+
+    /// Stop `item_type` from decaying and drop the ages of stored items.
+    /// Returns `false` if the type had no decay.
+    pub fn clear_item_decay(&mut self, item_type: ItemTypeId) -> bool {
+        if self.item_decay.remove(&item_type).is_none() {
+            return false;
+        }
+        let nodes: Vec<NodeId> = self.graph.nodes().map(|(node, _)| node).collect();
+        for node in nodes {
+            let mut changed = false;
+            for inventory in [self.inputs.get_mut(node), self.outputs.get_mut(node)]
+                .into_iter()
+                .flatten()
+            {
+                for slot in inventory
+                    .input_slots
+                    .iter_mut()
+                    .chain(inventory.output_slots.iter_mut())
+                {
+                    for stack in &mut slot.stacks {
+                        if stack.item_type == item_type && !stack.ages.is_empty() {
+                            stack.ages.clear();
+                            changed = true;
+                        }
+                    }
+                }
+            }
+            if changed {
+                self.hash_dirty_nodes.push(node);
+            }
+        }
+        true
+    }
+
+    /// The decay configured for `item_type`, if it is perishable.
+    pub fn item_decay(&self, item_type: ItemTypeId) -> Option<&ItemDecay> {
+        self.item_decay.get(&item_type)
+    }
+
+    /// Age in ticks of the oldest stored `item_type` at `node`, across its
+    /// input and output inventories. `None` if the node holds none or the
+    /// type is not perishable.
+    pub fn oldest_item_age(&self, node: NodeId, item_type: ItemTypeId) -> Option<Ticks> {
+        let now = self.sim_state.tick;
+        [self.inputs.get(node), self.outputs.get(node)]
+            .into_iter()
+            .flatten()
+            .flat_map(|inv| inv.input_slots.iter().chain(&inv.output_slots))
+            .flat_map(|slot| &slot.stacks)
+            .filter(|stack| stack.item_type == item_type)
+            .filter_map(|stack| stack.oldest_age(now))
+            .max()
+    }
+
+    /// Age every perishable stack and spoil the expired cohorts. Called once
+    /// per tick during the component phase.
+    pub(crate) fn advance_spoilage(&mut self) {
+        if self.item_decay.is_empty() {
+            return;
+        }
+        let tick = self.sim_state.tick;
+        let nodes: Vec<NodeId> = self.graph.nodes().map(|(node, _)| node).collect();
+        for node in nodes {
+            if self.ghost_nodes.contains_key(node) {
+                continue;
+            }
+            let mut changed = false;
+            let mut spoiled: BTreeMap<ItemTypeId, u32> = BTreeMap::new();
+            if let Some(inventory) = self.inputs.get_mut(node) {
+                changed |= age_slots(
+                    &mut inventory.input_slots,
+                    &self.item_decay,
+                    tick,
+                    &mut spoiled,
+                );
+            }
+            if let Some(inventory) = self.outputs.get_mut(node) {
+                changed |= age_slots(
+                    &mut inventory.output_slots,
+                    &self.item_decay,
+                    tick,
+                    &mut spoiled,
+                );
+            }
+            if changed {
+                self.hash_dirty_nodes.push(node);
+                self.dirty
+                    .mark_partition(crate::dirty::DirtyTracker::PARTITION_INVENTORIES);
+            }
+            for (item_type, quantity) in spoiled {
+                self.event_bus.emit(Event::ItemSpoiled {
+                    node,
+                    item_type,
+                    quantity,
+                    spoiled_into: self.item_decay[&item_type].spoils_into,
+                    tick,
+                });
+            }
+        }
+    }
+}
+
+/// Age the perishable stacks in one side of an inventory and spoil expired
+/// items, adding spoiled counts to `spoiled`. Products go to the first slot
+/// that accepts them; any that fit nowhere are discarded. Returns whether
+/// any stack changed.
+fn age_slots(
+    slots: &mut [InventorySlot],
+    decay: &BTreeMap<ItemTypeId, ItemDecay>,
+    tick: Ticks,
+    spoiled: &mut BTreeMap<ItemTypeId, u32>,
+) -> bool {
+    let mut changed = false;
+    let mut products: Vec<(ItemTypeId, u32)> = Vec::new();
+    for slot in slots.iter_mut() {
+        for stack in &mut slot.stacks {
+            let Some(item_decay) = decay.get(&stack.item_type) else {
+                continue;
+            };
+            changed |= reconcile_ages(stack, tick);
+            let mut expired = 0;
+            while let Some(oldest) = stack.ages.first()
+                && tick.saturating_sub(oldest.born) >= item_decay.ticks_to_spoil
+            {
+                expired += oldest.quantity;
+                stack.ages.remove(0);
+            }
+            if expired > 0 {
+                stack.quantity -= expired;
+                *spoiled.entry(stack.item_type).or_insert(0) += expired;
+                if let Some(product) = item_decay.spoils_into {
+                    products.push((product, expired));
+                }
+                changed = true;
+            }
+        }
+        slot.stacks.retain(|stack| stack.quantity > 0);
+    }
+
+    for (product, quantity) in products {
+        let mut remaining = quantity;
+        for slot in slots.iter_mut() {
+            if remaining == 0 {
+                break;
+            }
+            let overflow = slot.add(product, remaining);
+            let added = remaining - overflow;
+            remaining = overflow;
+            // Perishable products start aging now, so the next
+            // reconciliation does not count them as new arrivals.
+            if added > 0
+                && decay.contains_key(&product)
+                && let Some(stack) = slot.stacks.iter_mut().find(|s| s.item_type == product)
+            {
+                push_cohort(stack, tick, added);
+            }
+        }
+    }
+    changed
+}
+
+/// Bring a stack's cohorts in line with its quantity: new items form a
+/// cohort born this tick, and removed items come out of the oldest cohorts.
+fn reconcile_ages(stack: &mut ItemStack, tick: Ticks) -> bool {
+    let tracked: u32 = stack.ages.iter().map(|a| a.quantity).sum();
+    if tracked < stack.quantity {
+        push_cohort(stack, tick, stack.quantity - tracked);
+        true
+    } else if tracked > stack.quantity {
+        let mut excess = tracked - stack.quantity;
+        while excess > 0 {
+            let oldest = &mut stack.ages[0];
+            let taken = oldest.quantity.min(excess);
+            oldest.quantity -= taken;
+            excess -= taken;
+            if oldest.quantity == 0 {
+                stack.ages.remove(0);
+            }
+        }
+        true
+    } else {
+        false
+    }
+}
+
+/// Append `quantity` items born at `tick`, merging with a cohort from the
+/// same tick.
+fn push_cohort(stack: &mut ItemStack, tick: Ticks, quantity: u32) {
+    match stack.ages.last_mut() {
+        Some(last) if last.born == tick => last.quantity += quantity,
+        _ => stack.ages.push(ItemAge {
+            born: tick,
+            quantity,
+        }),
+    }
+}
+
+/// Feed a stack's age cohorts into a node hash. Stacks without ages add
+/// nothing, so factories without perishables keep their existing hashes.
+pub(crate) fn hash_item_ages(hasher: &mut StateHash, ages: &[ItemAge]) {
+    for age in ages {
+        hasher.write_u64(age.born);
+        hasher.write_u32(age.quantity);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event::{EventKind, HistoryFilter};
+    use crate::id::BuildingTypeId;
+    use crate::item::Inventory;
+    use crate::sim::SimulationStrategy;
+
+    const MEAT: ItemTypeId = ItemTypeId(0);
+    const ROT: ItemTypeId = ItemTypeId(1);
+
+    fn setup() -> (Engine, NodeId) {
+        let mut engine = Engine::new(SimulationStrategy::Tick);
+        let pending = engine.graph.queue_add_node(BuildingTypeId(0));
+        let node = engine.apply_mutations().resolve_node(pending).unwrap();
+        engine.set_input_inventory(node, Inventory::new(1, 0, 100));
+        engine.set_event_history_capacity(256);
+        (engine, node)
+    }
+
+    fn store(engine: &mut Engine, node: NodeId, item: ItemTypeId, quantity: u32) {
+        let overflow = engine
+            .get_input_inventory_mut(node)
+            .unwrap()
+            .insert(item, quantity);
+        assert_eq!(overflow, 0);
+    }
+
+    fn count(engine: &Engine, node: NodeId, item: ItemTypeId) -> u32 {
+        engine.get_input_inventory(node).unwrap().count_of(item)
+    }
+
+    fn spoil_events(engine: &Engine) -> Vec<Event> {
+        engine
+            .event_history(HistoryFilter {
+                kinds: vec![EventKind::ItemSpoiled],
+                ..HistoryFilter::default()
+            })
+            .cloned()
+            .collect()
+    }
+
+    #[test]
+    fn items_spoil_exactly_after_their_lifetime() {
+        let (mut engine, node) = setup();
+        engine.set_item_decay(MEAT, 5, None);
+        store(&mut engine, node, MEAT, 3);
+
+        // First seen on tick 0, so the items spoil on tick 5.
+        for _ in 0..5 {
+            engine.step();
+        }
+        assert_eq!(count(&engine, node, MEAT), 3);
+        assert_eq!(engine.oldest_item_age(node, MEAT), Some(5));
+        engine.step();
+        assert_eq!(count(&engine, node, MEAT), 0);
+        assert_eq!(
+            spoil_events(&engine),
+            vec![Event::ItemSpoiled {
+                node,
+                item_type: MEAT,
+                quantity: 3,
+                spoiled_into: None,
+                tick: 5,
+            }]
+        );
+    }
+
+    #[test]
+    fn spoiled_items_convert_into_the_configured_product() {
+        let (mut engine, node) = setup();
+        engine.set_item_decay(MEAT, 3, Some(ROT));
+        engine.set_item_decay(ROT, 4, None);
+        store(&mut engine, node, MEAT, 4);
+
+        for _ in 0..4 {
+            engine.step();
+        }
+        assert_eq!(count(&engine, node, MEAT), 0);
+        assert_eq!(count(&engine, node, ROT), 4);
+        // The product starts aging on the tick it was made.
+        assert_eq!(engine.oldest_item_age(node, ROT), Some(1));
+
+        for _ in 0..4 {
+            engine.step();
+        }
+        assert_eq!(count(&engine, node, ROT), 0);
+        let ticks: Vec<Ticks> = spoil_events(&engine).iter().map(Event::tick).collect();
+        assert_eq!(ticks, vec![3, 7]);
+    }
+
+    #[test]
+    fn fresh_items_age_separately_and_consumption_takes_the_oldest() {
+        let (mut engine, node) = setup();
+        engine.set_item_decay(MEAT, 10, None);
+        store(&mut engine, node, MEAT, 2);
+        for _ in 0..4 {
+            engine.step();
+        }
+        store(&mut engine, node, MEAT, 3);
+        engine.step();
+        let ages = &engine.get_input_inventory(node).unwrap().input_slots[0].stacks[0].ages;
+        assert_eq!(
+            ages,
+            &vec![
+                ItemAge {
+                    born: 0,
+                    quantity: 2
+                },
+                ItemAge {
+                    born: 4,
+                    quantity: 3
+                },
+            ]
+        );
+
+        // Taking one item removes it from the oldest cohort.
+        let taken = engine.get_input_inventory_mut(node).unwrap().input_slots[0].remove(MEAT, 1);
+        assert_eq!(taken, 1);
+        for _ in 0..6 {
+            engine.step();
+        }
+        // Tick 10: the one remaining original item spoils; the rest are fresh.
+        assert_eq!(count(&engine, node, MEAT), 3);
+        assert_eq!(engine.oldest_item_age(node, MEAT), Some(7));
+        for _ in 0..4 {
+            engine.step();
+        }
+        assert_eq!(count(&engine, node, MEAT), 0);
+
+        // Once the stack is gone, new items start from age zero.
+        store(&mut engine, node, MEAT, 1);
+        engine.step();
+        assert_eq!(engine.oldest_item_age(node, MEAT), Some(1));
+    }
+
+    #[test]
+    fn ages_survive_serialization_and_affect_the_hash() {
+        let (mut engine, node) = setup();
+        let (mut plain, plain_node) = setup();
+        engine.set_item_decay(MEAT, 6, Some(ROT));
+        engine.set_item_decay(ROT, 100, None);
+        store(&mut engine, node, MEAT, 5);
+        store(&mut plain, plain_node, MEAT, 5);
+        for _ in 0..3 {
+            engine.step();
+            plain.step();
+        }
+        assert_ne!(engine.state_hash(), plain.state_hash());
+
+        let mut restored = Engine::deserialize(&engine.serialize().unwrap()).unwrap();
+        assert_eq!(restored.item_decay(MEAT), engine.item_decay(MEAT));
+        for _ in 0..5 {
+            engine.step();
+            restored.step();
+            assert_eq!(engine.state_hash(), restored.state_hash());
+        }
+        assert_eq!(count(&restored, node, ROT), 5);
+        assert_eq!(restored.oldest_item_age(node, ROT), Some(2));
+
+        assert!(restored.clear_item_decay(ROT));
+        assert!(!restored.clear_item_decay(ROT));
+        assert_eq!(restored.oldest_item_age(node, ROT), None);
+    }
+}
@@ -0,0 +1,362 @@
+//! Save summaries readable without decoding the engine.
+//!
+//! Tools that list many saves -- a save browser, a server picking the latest
+//! autosave -- need a few numbers per file, not a running engine.
+//! [`Engine::serialize`] therefore writes a small summary section in front of
+//! the snapshot body:
+//!
+//! ```text
+//! magic: u32 LE | length: u32 LE | summary (bitcode) | checksum: u64 LE | body
+//! ```
+//!
+//! The checksum covers the magic, the length and the summary bytes.
+//! [`Engine::inspect`] reads only this section: it builds no slotmaps,
+//! processors or inventories, and corruption in the body goes unnoticed
+//! until the save is actually loaded. Saves written before the section
+//! existed, and partitioned snapshots, have none; `inspect` falls back to a
+//! full decode for them.
+//!
+//! The body is the same snapshot as before, so the format version is
+//! unchanged and every loader skips the section.
+//!
+//! Item totals are only written when enabled with
+//! [`Engine::set_summary_item_totals`], since computing them walks every
+//! inventory and transport on each save.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::engine::Engine;
+use crate::fixed::Ticks;
+use crate::id::ItemTypeId;
+use crate::serialize::{DeserializeError, FORMAT_VERSION, PartitionedSnapshot, SerializeError};
+use crate::sim::StateHash;
+
+/// Magic number opening a save's summary section.
+pub const SUMMARY_MAGIC: u32 = 0xFAC7_0003;
+
+/// Bytes in the section before the summary: magic and length.
+const PREFIX_LEN: usize = 8;
+
+/// Bytes in the section after the summary: the checksum.
+const CHECKSUM_LEN: usize = 8;
+
+/// Summary information about a save.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SaveSummary {
+    /// Format version of the snapshot body.
+    pub version: u32,
+    /// Simulation tick the save was taken at.
+    pub tick: Ticks,
+    /// Number of nodes in the graph, ghosts included.
+    pub node_count: u32,
+    /// Number of edges in the graph, ghosts included.
+    pub edge_count: u32,
+    /// Enabled extensions, as an [`Engine::extension_mask`].
+    pub extensions: u32,
+    /// The engine's state hash when it was saved.
+    pub state_hash: u64,
+    /// Units of each item type in the factory, if the save recorded them.
+    /// Always present after a full decode.
+    pub item_totals: Option<BTreeMap<ItemTypeId, u64>>,
+    /// Whether the save had no summary section and was fully decoded.
+    pub full_decode: bool,
+}
+
+/// The summary section's payload.
+#[derive(Debug, Serialize, Deserialize)]
+struct SummarySection {
+    version: u32,
+    tick: Ticks,
+    node_count: u32,
+    edge_count: u32,
+    extensions: u32,
+    state_hash: u64,
+    item_totals: Option<Vec<(ItemTypeId, u64)>>,
+}
+
+impl Engine {
+    /// Read the summary of a save produced by [`serialize`](Self::serialize)
+    /// without deserializing it. Saves without a summary section, including
+    /// partitioned snapshots, are fully decoded instead.
+    ///
+    /// Only the summary section is validated: a checksum mismatch is an
+    /// error, a corrupt body is not noticed.
+    pub fn inspect(data: &[u8]) -> Result<SaveSummary, DeserializeError> {
+        let Some(Section { summary, .. }) = split_section(data)? else {
+            return Self::inspect_by_decoding(data);
+        };
+        let checked = PREFIX_LEN + summary.len();
+        let stored = u64::from_le_bytes(
+            data[checked..checked + CHECKSUM_LEN]
+                .try_into()
+                .expect("split_section checked the length"),
+        );
+        if stored != checksum(&data[..checked]) {
+            return Err(DeserializeError::SummaryChecksum);
+        }
+        let section: SummarySection =
+            bitcode::deserialize(summary).map_err(|e| DeserializeError::Decode(e.to_string()))?;
+        if section.version > FORMAT_VERSION {
+            return Err(DeserializeError::FutureVersion(section.version));
+        }
+        Ok(SaveSummary {
+            version: section.version,
+            tick: section.tick,
+            node_count: section.node_count,
+            edge_count: section.edge_count,
+            extensions: section.extensions,
+            state_hash: section.state_hash,
+            item_totals: section
+                .item_totals
+                .map(|totals| totals.into_iter().collect()),
+            full_decode: false,
+        })
+    }
+
+    /// Record per-item totals in the summary section of every save. Off by
+    /// default. Not serialized.
+    pub fn set_summary_item_totals(&mut self, enabled: bool) {
+        self.summary_item_totals = enabled;
+    }
+
+    /// Whether saves record per-item totals in their summary section.
+    pub fn summary_item_totals(&self) -> bool {
+        self.summary_item_totals
+    }
+
+    fn inspect_by_decoding(data: &[u8]) -> Result<SaveSummary, DeserializeError> {
+        let engine = match Self::deserialize(data) {
+            Ok(engine) => engine,
+            Err(legacy) => match PartitionedSnapshot::from_bytes(data) {
+                Ok(snapshot) => Self::deserialize_partitioned(&snapshot)?,
+                Err(_) => return Err(legacy),
+            },
+        };
+        Ok(SaveSummary {
+            full_decode: true,
+            item_totals: Some(engine.item_totals()),
+            ..engine.summary_fields()
+        })
+    }
+
+    /// The summary fields that are always recorded.
+    fn summary_fields(&self) -> SaveSummary {
+        SaveSummary {
+            version: FORMAT_VERSION,
+            tick: self.sim_state.tick,
+            node_count: self.graph.node_count() as u32,
+            edge_count: self.graph.edge_count() as u32,
+            extensions: self.extensions,
+            state_hash: self.last_state_hash,
+            item_totals: None,
+            full_decode: false,
+        }
+    }
+
+    fn item_totals(&self) -> BTreeMap<ItemTypeId, u64> {
+        self.census()
+            .into_iter()
+            .map(|(item, census)| (item, census.total))
+            .collect()
+    }
+
+    /// Prepend the summary section to a snapshot body.
+    pub(crate) fn frame_with_summary(&self, body: Vec<u8>) -> Result<Vec<u8>, SerializeError> {
+        let fields = self.summary_fields();
+        let section = SummarySection {
+            version: fields.version,
+            tick: fields.tick,
+            node_count: fields.node_count,
+            edge_count: fields.edge_count,
+            extensions: fields.extensions,
+            state_hash: fields.state_hash,
+            item_totals: self
+                .summary_item_totals
+                .then(|| self.item_totals().into_iter().collect()),
+        };
+        let summary =
+            bitcode::serialize(&section).map_err(|e| SerializeError::Encode(e.to_string()))?;
+        let len = u32::try_from(summary.len())
+            .map_err(|_| SerializeError::Encode("summary section too large".into()))?;
+
+        let mut out = Vec::with_capacity(PREFIX_LEN + summary.len() + CHECKSUM_LEN + body.len());
+        out.extend_from_slice(&SUMMARY_MAGIC.to_le_bytes());
+        out.extend_from_slice(&len.to_le_bytes());
+        out.extend_from_slice(&summary);
+        let sum = checksum(&out);
+        out.extend_from_slice(&sum.to_le_bytes());
+        out.extend_from_slice(&body);
+        Ok(out)
+    }
+}
+
+/// The snapshot body of `data`: everything after the summary section, or all
+/// of `data` if it has none.
+pub(crate) fn snapshot_body(data: &[u8]) -> Result<&[u8], DeserializeError> {
+    Ok(split_section(data)?.map_or(data, |section| section.body))
+}
+
+/// A save split at the end of its summary section.
+struct Section<'a> {
+    summary: &'a [u8],
+    body: &'a [u8],
+}
+
+/// Split `data` into its summary bytes and the body. `None` if `data` does
+/// not start with a summary section.
+fn split_section(data: &[u8]) -> Result<Option<Section<'_>>, DeserializeError> {
+    if data.len() < PREFIX_LEN || data[..4] != SUMMARY_MAGIC.to_le_bytes() {
+        return Ok(None);
+    }
+    let len = u32::from_le_bytes(data[4..PREFIX_LEN].try_into().expect("four bytes")) as usize;
+    let rest = &data[PREFIX_LEN..];
+    if rest.len() < len + CHECKSUM_LEN {
+        return Err(DeserializeError::TooShort);
+    }
+    let (summary, rest) = rest.split_at(len);
+    Ok(Some(Section {
+        summary,
+        body: &rest[CHECKSUM_LEN..],
+    }))
+}
+
+fn checksum(bytes: &[u8]) -> u64 {
+    let mut h = StateHash::new();
+    h.write(bytes);
+    h.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::extension::ExtensionId;
+    use crate::processor::Processor;
+    use crate::sim::SimulationStrategy;
+    use crate::test_utils::{self, gear, iron};
+
+    /// Source, smelter and sink joined by a belt and a flow edge.
+    fn factory() -> Engine {
+        let mut engine = Engine::new(SimulationStrategy::Tick);
+        let src = test_utils::add_node(&mut engine, test_utils::make_source(iron(), 2.0), 50, 50);
+        let smelter = test_utils::add_node(
+            &mut engine,
+            test_utils::make_recipe(vec![(iron(), 2)], vec![(gear(), 1)], 3),
+            50,
+            50,
+        );
+        let sink = test_utils::add_node(&mut engine, Processor::Passthrough, 50, 50);
+        test_utils::connect(
+            &mut engine,
+            src,
+            smelter,
+            test_utils::make_item_transport(4),
+        );
+        test_utils::connect(
+            &mut engine,
+            smelter,
+            sink,
+            test_utils::make_flow_transport(1.0),
+        );
+        for _ in 0..30 {
+            engine.step();
+        }
+        engine
+    }
+
+    /// The summary of a fully loaded engine.
+    fn expected(engine: &Engine) -> SaveSummary {
+        SaveSummary {
+            item_totals: Some(engine.item_totals()),
+            ..engine.summary_fields()
+        }
+    }
+
+    #[test]
+    fn inspect_matches_the_deserialized_engine() {
+        let mut engine = factory();
+        engine.enable_extension(ExtensionId::Stats);
+        engine.set_summary_item_totals(true);
+        let data = engine.serialize().unwrap();
+
+        let summary = Engine::inspect(&data).unwrap();
+        let loaded = Engine::deserialize(&data).unwrap();
+        assert_eq!(summary, expected(&loaded));
+        assert_eq!(summary.tick, 30);
+        assert_eq!((summary.node_count, summary.edge_count), (3, 2));
+        assert_eq!(summary.extensions, ExtensionId::Stats.bit());
+        assert!(!summary.full_decode);
+        assert!(summary.item_totals.unwrap()[&iron()] > 0);
+    }
+
+    #[test]
+    fn item_totals_are_opt_in() {
+        let engine = factory();
+        let summary = Engine::inspect(&engine.serialize().unwrap()).unwrap();
+        assert_eq!(summary.item_totals, None);
+        assert_eq!(summary.state_hash, engine.state_hash());
+    }
+
+    #[test]
+    fn inspect_ignores_a_corrupt_body_but_not_a_corrupt_summary() {
+        let mut engine = factory();
+        engine.set_summary_item_totals(true);
+        let data = engine.serialize().unwrap();
+        let Section { summary, body } = split_section(&data).unwrap().unwrap();
+        let body_start = data.len() - body.len();
+
+        let mut corrupt_body = data.clone();
+        corrupt_body.truncate(body_start + body.len() / 2);
+        for byte in &mut corrupt_body[body_start..] {
+            *byte = !*byte;
+        }
+        assert_eq!(
+            Engine::inspect(&corrupt_body).unwrap(),
+            Engine::inspect(&data).unwrap()
+        );
+        assert!(Engine::deserialize(&corrupt_body).is_err());
+
+        let mut corrupt_summary = data.clone();
+        corrupt_summary[PREFIX_LEN + summary.len() / 2] ^= 0xFF;
+        assert!(matches!(
+            Engine::inspect(&corrupt_summary),
+            Err(DeserializeError::SummaryChecksum)
+        ));
+    }
+
+    #[test]
+    fn saves_without_a_summary_are_fully_decoded() {
+        let engine = factory();
+        let data = engine.serialize().unwrap();
+        let body = snapshot_body(&data).unwrap();
+
+        let summary = Engine::inspect(body).unwrap();
+        assert!(summary.full_decode);
+        assert_eq!(
+            summary,
+            SaveSummary {
+                full_decode: true,
+                ..expected(&engine)
+            }
+        );
+        // Loaders still accept the bare body.
+        assert_eq!(
+            Engine::deserialize(body).unwrap().state_hash(),
+            engine.state_hash()
+        );
+
+        let partitioned = engine.serialize_partitioned().unwrap().to_bytes().unwrap();
+        assert_eq!(Engine::inspect(&partitioned).unwrap(), summary);
+    }
+
+    #[test]
+    fn truncated_summary_section_is_too_short() {
+        let data = factory().serialize().unwrap();
+        assert!(matches!(
+            Engine::inspect(&data[..PREFIX_LEN + 2]),
+            Err(DeserializeError::TooShort)
+        ));
+        assert!(Engine::inspect(&[]).is_err());
+    }
+}
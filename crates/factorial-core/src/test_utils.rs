@@ -4,13 +4,14 @@
 //! are available in unit tests, integration tests, and benchmarks (via the
 //! `test-utils` feature).
 
+use crate::builder::{self, FactoryBuilder};
 use crate::engine::Engine;
 use crate::fixed::Fixed64;
 use crate::id::*;
 use crate::item::Inventory;
-use crate::processor::*;
+use crate::processor::Processor;
 use crate::sim::SimulationStrategy;
-use crate::transport::*;
+use crate::transport::Transport;
 
 // ===========================================================================
 // Fixed-point helper
@@ -150,13 +151,7 @@ pub fn simple_inventory(capacity: u32) -> Inventory {
 // ===========================================================================
 
 pub fn make_source(item: ItemTypeId, rate: f64) -> Processor {
-    Processor::Source(SourceProcessor {
-        output_type: item,
-        base_rate: fixed(rate),
-        depletion: Depletion::Infinite,
-        accumulated: fixed(0.0),
-        initial_properties: None,
-    })
+    builder::source_processor(item, rate)
 }
 
 pub fn make_recipe(
@@ -164,25 +159,7 @@ pub fn make_recipe(
     outputs: Vec<(ItemTypeId, u32)>,
     duration: u32,
 ) -> Processor {
-    Processor::Fixed(FixedRecipe {
-        inputs: inputs
-            .into_iter()
-            .map(|(item_type, quantity)| RecipeInput {
-                item_type,
-                quantity,
-                consumed: true,
-            })
-            .collect(),
-        outputs: outputs
-            .into_iter()
-            .map(|(item_type, quantity)| RecipeOutput {
-                item_type,
-                quantity,
-                bonus: None,
-            })
-            .collect(),
-        duration,
-    })
+    builder::recipe_processor(inputs, outputs, duration)
 }
 
 // ===========================================================================
@@ -190,33 +167,19 @@ pub fn make_recipe(
 // ===========================================================================
 
 pub fn make_flow_transport(rate: f64) -> Transport {
-    Transport::Flow(FlowTransport {
-        rate: fixed(rate),
-        buffer_capacity: fixed(1000.0),
-        latency: 0,
-    })
+    builder::flow_transport(rate)
 }
 
 pub fn make_item_transport(slot_count: u32) -> Transport {
-    Transport::Item(ItemTransport {
-        speed: fixed(1.0),
-        slot_count,
-        lanes: 1,
-    })
+    builder::belt_transport(1.0, slot_count)
 }
 
 pub fn make_batch_transport(batch_size: u32, cycle_time: u32) -> Transport {
-    Transport::Batch(BatchTransport {
-        batch_size,
-        cycle_time,
-    })
+    builder::batch_transport(batch_size, cycle_time)
 }
 
 pub fn make_vehicle_transport(capacity: u32, travel_time: u32) -> Transport {
-    Transport::Vehicle(VehicleTransport {
-        capacity,
-        travel_time,
-    })
+    builder::vehicle_transport(capacity, travel_time)
 }
 
 // ===========================================================================
@@ -319,48 +282,49 @@ pub fn output_total(engine: &Engine, node: NodeId) -> u32 {
 /// Build a linear chain of N nodes: Source -> Assembler -> ... -> Assembler.
 /// Deep graph with 1 node per topological level.
 pub fn build_chain_factory(length: usize) -> Engine {
-    let mut engine = Engine::new(SimulationStrategy::Tick);
-
-    if length == 0 {
-        return engine;
-    }
-
-    // First node is a source.
-    let mut prev = add_node(&mut engine, make_source(iron(), 2.0), 100, 100);
-
-    // Remaining nodes are assemblers chained linearly.
-    for _ in 1..length {
-        let node = add_node(
-            &mut engine,
-            make_recipe(vec![(iron(), 1)], vec![(gear(), 1)], 5),
-            100,
-            100,
-        );
-        connect(&mut engine, prev, node, make_flow_transport(10.0));
-        prev = node;
+    let mut builder = FactoryBuilder::new();
+
+    if length > 0 {
+        // First node is a source; remaining nodes are assemblers chained linearly.
+        builder.node("n0").source(iron(), 2.0);
+        for i in 1..length {
+            builder
+                .node(format!("n{i}"))
+                .recipe(vec![(iron(), 1)], vec![(gear(), 1)], 5);
+            builder.flow(format!("n{}", i - 1), format!("n{i}"), 10.0);
+        }
     }
 
-    engine
+    build_engine(&builder)
 }
 
 /// Build a wide factory: 1 source feeding N consumer assemblers.
 /// 2 topological levels — best case for level parallelism.
 pub fn build_wide_factory(fan_out: usize) -> Engine {
-    let mut engine = Engine::new(SimulationStrategy::Tick);
-
-    let source = add_node(&mut engine, make_source(iron(), 100.0), 1000, 1000);
-
-    for _ in 0..fan_out {
-        let consumer = add_node(
-            &mut engine,
-            make_recipe(vec![(iron(), 1)], vec![(gear(), 1)], 5),
-            100,
-            100,
-        );
-        connect(&mut engine, source, consumer, make_flow_transport(10.0));
+    let mut builder = FactoryBuilder::new();
+
+    builder
+        .node("source")
+        .source(iron(), 100.0)
+        .input_cap(1000)
+        .output_cap(1000);
+
+    for i in 0..fan_out {
+        builder
+            .node(format!("consumer{i}"))
+            .recipe(vec![(iron(), 1)], vec![(gear(), 1)], 5);
+        builder.flow("source", format!("consumer{i}"), 10.0);
     }
 
-    engine
+    build_engine(&builder)
+}
+
+/// Materialize a builder whose names are known to be valid.
+fn build_engine(builder: &FactoryBuilder) -> Engine {
+    builder
+        .build()
+        .expect("test factory declarations are valid")
+        .engine
 }
 
 /// Build a grid factory: rows x cols mesh of assembler nodes.
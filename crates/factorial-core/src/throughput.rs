@@ -0,0 +1,305 @@
+//! Theoretical throughput of a production path.
+//!
+//! [`Engine::path_max_throughput`] answers "how much could this line deliver
+//! if everything upstream were fully supplied?". It finds a path from one
+//! node to another and takes the minimum capacity over its stages: each
+//! node's processor rate and inventory size, and each edge's transport rate.
+//! The stage that sets the minimum is reported as the limiter.
+//!
+//! This is static analysis of the current configuration. Inventory contents,
+//! processor progress, items in transit and source depletion are ignored, so
+//! the result is a ceiling for the runtime bottleneck queries to be measured
+//! against, not a prediction.
+
+use std::collections::VecDeque;
+
+use slotmap::SecondaryMap;
+
+use crate::engine::Engine;
+use crate::fixed::Fixed64;
+use crate::id::{EdgeId, ItemTypeId, NodeId};
+use crate::item::InventorySlot;
+use crate::transport::Transport;
+
+/// One stage of a production path.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PathStage {
+    /// A node: its processor and inventories.
+    Node(NodeId),
+    /// An edge: its transport.
+    Edge(EdgeId),
+}
+
+/// The result of [`Engine::path_max_throughput`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct PathThroughput {
+    /// The most of the item the path can deliver per tick.
+    pub rate: Fixed64,
+    /// The first stage whose capacity equals `rate`.
+    pub limiter: PathStage,
+    /// The edges of the path, from source to destination.
+    pub edges: Vec<EdgeId>,
+}
+
+impl Engine {
+    /// The theoretical maximum rate, in items per tick, at which `item_type`
+    /// can travel from `from` to `to`, and the stage that limits it.
+    ///
+    /// The path is the shortest chain of edges that may carry `item_type`,
+    /// preferring earlier edges in graph order on ties. Stage capacities:
+    ///
+    /// - nodes: [`Processor::max_rate`](crate::processor::Processor::max_rate)
+    ///   with the node's modifiers, and the capacity of the inventory slots
+    ///   that accept the item, which bounds how much can enter or leave in
+    ///   one tick;
+    /// - flow edges: `rate`, or `buffer_capacity` if smaller;
+    /// - item edges: `lanes * speed / min_spacing`;
+    /// - batch edges: `batch_size / cycle_time`;
+    /// - vehicle edges: `capacity / (2 * travel_time)`.
+    ///
+    /// Returns `None` if no such path exists or no stage on it has a
+    /// ceiling.
+    pub fn path_max_throughput(
+        &self,
+        from: NodeId,
+        to: NodeId,
+        item_type: ItemTypeId,
+    ) -> Option<PathThroughput> {
+        let edges = self.find_item_path(from, to, item_type)?;
+
+        let mut best: Option<(Fixed64, PathStage)> = None;
+        let mut consider = |capacity: Option<Fixed64>, stage: PathStage| {
+            if let Some(capacity) = capacity
+                && best.is_none_or(|(rate, _)| capacity < rate)
+            {
+                best = Some((capacity, stage));
+            }
+        };
+
+        consider(
+            self.node_capacity(from, item_type, false, !edges.is_empty()),
+            PathStage::Node(from),
+        );
+        for (i, &edge) in edges.iter().enumerate() {
+            consider(self.edge_capacity(edge), PathStage::Edge(edge));
+            let node = self.graph.get_edge(edge)?.to;
+            consider(
+                self.node_capacity(node, item_type, true, i + 1 < edges.len()),
+                PathStage::Node(node),
+            );
+        }
+
+        let (rate, limiter) = best?;
+        Some(PathThroughput {
+            rate,
+            limiter,
+            edges,
+        })
+    }
+
+    /// Breadth-first search over edges that may carry `item_type`.
+    fn find_item_path(
+        &self,
+        from: NodeId,
+        to: NodeId,
+        item_type: ItemTypeId,
+    ) -> Option<Vec<EdgeId>> {
+        if !self.graph.contains_node(from) || !self.graph.contains_node(to) {
+            return None;
+        }
+        let mut reached_by: SecondaryMap<NodeId, Option<EdgeId>> = SecondaryMap::new();
+        reached_by.insert(from, None);
+        let mut queue = VecDeque::from([from]);
+        while let Some(node) = queue.pop_front() {
+            if node == to {
+                break;
+            }
+            for &edge in self.graph.get_outputs(node) {
+                let Some(data) = self.graph.get_edge(edge) else {
+                    continue;
+                };
+                if reached_by.contains_key(data.to) || !self.edge_may_carry(edge, item_type) {
+                    continue;
+                }
+                reached_by.insert(data.to, Some(edge));
+                queue.push_back(data.to);
+            }
+        }
+
+        let mut edges = Vec::new();
+        let mut node = to;
+        while let Some(edge) = *reached_by.get(node)? {
+            edges.push(edge);
+            node = self.graph.get_edge(edge)?.from;
+        }
+        edges.reverse();
+        Some(edges)
+    }
+
+    /// Whether the edge's item or category filter lets `item_type` through.
+    fn edge_may_carry(&self, edge: EdgeId, item_type: ItemTypeId) -> bool {
+        if let Some(filter) = self.graph.get_edge(edge).and_then(|data| data.item_filter) {
+            return filter == item_type;
+        }
+        match self.edge_category_filter(edge) {
+            Some(category) => self.item_category(item_type) == Some(category),
+            None => true,
+        }
+    }
+
+    /// The per-tick ceiling a node puts on `item_type`, from its processor
+    /// and the inventory sides the path uses.
+    fn node_capacity(
+        &self,
+        node: NodeId,
+        item_type: ItemTypeId,
+        receives: bool,
+        sends: bool,
+    ) -> Option<Fixed64> {
+        let slot_capacity = |slots: &[InventorySlot]| -> Fixed64 {
+            Fixed64::from_num(
+                slots
+                    .iter()
+                    .filter(|slot| slot.accepts(item_type))
+                    .map(|slot| u64::from(slot.capacity))
+                    .sum::<u64>(),
+            )
+        };
+
+        let mut capacity = self.get_processor(node).and_then(|processor| {
+            let modifiers = self.get_modifiers(node).map_or(&[][..], Vec::as_slice);
+            processor.max_rate(item_type, modifiers)
+        });
+        let mut limit = |bound: Fixed64| {
+            capacity = Some(capacity.map_or(bound, |c| c.min(bound)));
+        };
+        if receives && let Some(inventory) = self.get_input_inventory(node) {
+            limit(slot_capacity(&inventory.input_slots));
+        }
+        if sends && let Some(inventory) = self.get_output_inventory(node) {
+            limit(slot_capacity(&inventory.output_slots));
+        }
+        capacity
+    }
+
+    /// The per-tick ceiling of the edge's transport, or `None` without one.
+    fn edge_capacity(&self, edge: EdgeId) -> Option<Fixed64> {
+        Some(match self.get_transport(edge)? {
+            Transport::Flow(flow) => flow.rate.min(flow.buffer_capacity),
+            Transport::Item(belt) => Fixed64::from_num(belt.lanes)
+                .saturating_mul(belt.speed.max(Fixed64::ZERO))
+                .saturating_div(belt.spacing()),
+            Transport::Batch(batch) => {
+                Fixed64::from_num(batch.batch_size) / Fixed64::from_num(batch.cycle_time.max(1))
+            }
+            Transport::Vehicle(vehicle) => {
+                Fixed64::from_num(vehicle.capacity)
+                    / Fixed64::from_num(vehicle.travel_time.max(1) * 2)
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::id::ModifierId;
+    use crate::processor::{Modifier, ModifierKind, Processor, StackingRule};
+    use crate::sim::SimulationStrategy;
+    use crate::test_utils::{self, copper, iron};
+
+    /// Source -> passthrough -> passthrough -> sink over flow edges with
+    /// the given rates.
+    fn flow_line(rates: [f64; 3]) -> (Engine, Vec<NodeId>, Vec<EdgeId>) {
+        let mut engine = Engine::new(SimulationStrategy::Tick);
+        let nodes = vec![
+            test_utils::add_node(&mut engine, test_utils::make_source(iron(), 5.0), 100, 100),
+            test_utils::add_node(&mut engine, Processor::Passthrough, 100, 100),
+            test_utils::add_node(&mut engine, Processor::Passthrough, 100, 100),
+            test_utils::add_node(&mut engine, Processor::Passthrough, 100, 100),
+        ];
+        let edges = nodes
+            .windows(2)
+            .zip(rates)
+            .map(|(pair, rate)| {
+                test_utils::connect(
+                    &mut engine,
+                    pair[0],
+                    pair[1],
+                    test_utils::make_flow_transport(rate),
+                )
+            })
+            .collect();
+        (engine, nodes, edges)
+    }
+
+    #[test]
+    fn narrowest_middle_transport_limits_the_line() {
+        let (engine, nodes, edges) = flow_line([4.0, 1.5, 3.0]);
+        let result = engine
+            .path_max_throughput(nodes[0], nodes[3], iron())
+            .unwrap();
+        assert_eq!(result.rate, Fixed64::from_num(1.5));
+        assert_eq!(result.limiter, PathStage::Edge(edges[1]));
+        assert_eq!(result.edges, edges);
+    }
+
+    #[test]
+    fn processors_and_modifiers_set_node_ceilings() {
+        let (mut engine, nodes, edges) = flow_line([10.0, 10.0, 10.0]);
+        let result = engine
+            .path_max_throughput(nodes[0], nodes[3], iron())
+            .unwrap();
+        assert_eq!(result.rate, Fixed64::from_num(5));
+        assert_eq!(result.limiter, PathStage::Node(nodes[0]));
+
+        // 2 iron every 4 ticks, doubled in speed: 1 iron per tick.
+        engine.set_processor(
+            nodes[2],
+            test_utils::make_recipe(vec![(iron(), 2)], vec![(copper(), 1)], 4),
+        );
+        engine.set_modifiers(
+            nodes[2],
+            vec![Modifier {
+                id: ModifierId(0),
+                kind: ModifierKind::Speed(Fixed64::from_num(2)),
+                stacking: StackingRule::default(),
+            }],
+        );
+        let result = engine
+            .path_max_throughput(nodes[0], nodes[2], iron())
+            .unwrap();
+        assert_eq!(result.rate, Fixed64::ONE);
+        assert_eq!(result.limiter, PathStage::Node(nodes[2]));
+        assert_eq!(result.edges, edges[..2]);
+    }
+
+    #[test]
+    fn filtered_edges_and_missing_paths() {
+        let (mut engine, nodes, edges) = flow_line([1.0, 1.0, 1.0]);
+        assert!(
+            engine
+                .path_max_throughput(nodes[3], nodes[0], iron())
+                .is_none()
+        );
+
+        engine.graph.queue_disconnect(edges[1]);
+        engine.step();
+        let bypass = engine
+            .graph
+            .queue_connect_filtered(nodes[1], nodes[2], Some(copper()));
+        let result = engine.apply_mutations();
+        let bypass = result.resolve_edge(bypass).unwrap();
+        engine.set_transport(bypass, test_utils::make_flow_transport(1.0));
+        assert!(
+            engine
+                .path_max_throughput(nodes[0], nodes[3], iron())
+                .is_none()
+        );
+        assert!(
+            engine
+                .path_max_throughput(nodes[1], nodes[3], copper())
+                .is_some()
+        );
+    }
+}
@@ -0,0 +1,295 @@
+//! Adjacency snapshots for host-side pathfinding and analysis.
+//!
+//! [`Engine::topology_snapshot`] lays the production graph out as compressed
+//! sparse rows: the node ids in ascending order, one offset per node into a
+//! flat edge array, and for each outgoing edge its target (as an index into
+//! the node array), its id and its transport kind. A logistics bot walking
+//! belts or an analysis script can copy the three arrays once and index them
+//! directly, with no per-node calls.
+//!
+//! The snapshot is cached. It is rebuilt, in one pass over the graph, only
+//! when [`Engine::topology_generation`] has moved on since it was built --
+//! an applied mutation batch added or removed nodes or edges -- or an edge's
+//! transport was set, changed or removed with a different kind. Processor,
+//! inventory and same-kind transport changes leave it alone. Hosts that keep
+//! their own copy compare the generation to know when the graph changed;
+//! a transport kind change does not move it.
+
+use crate::engine::Engine;
+use crate::id::{EdgeId, NodeId};
+use crate::transport::{Transport, TransportKind};
+
+/// One outgoing edge in a [`TopologySnapshot`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TopologyEdge {
+    /// Index of the edge's target in [`TopologySnapshot::nodes`].
+    pub target: u32,
+    /// The edge.
+    pub edge: EdgeId,
+    /// The edge's transport kind, or `None` if no transport is set.
+    pub kind: Option<TransportKind>,
+}
+
+/// The production graph as compressed sparse rows.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TopologySnapshot {
+    /// The topology generation the snapshot was built at.
+    pub generation: u64,
+    /// Every node, in ascending id order.
+    pub nodes: Vec<NodeId>,
+    /// `nodes.len() + 1` offsets: the outgoing edges of `nodes[i]` are
+    /// `edges[offsets[i]..offsets[i + 1]]`.
+    pub offsets: Vec<u32>,
+    /// Outgoing edges grouped by source node, each group in ascending edge
+    /// id order.
+    pub edges: Vec<TopologyEdge>,
+}
+
+impl TopologySnapshot {
+    /// Index of `node` in [`nodes`](Self::nodes), if it is in the snapshot.
+    pub fn index_of(&self, node: NodeId) -> Option<usize> {
+        self.nodes.binary_search(&node).ok()
+    }
+
+    /// The outgoing edges of the node at `index`. Empty if out of range.
+    pub fn outgoing(&self, index: usize) -> &[TopologyEdge] {
+        match (self.offsets.get(index), self.offsets.get(index + 1)) {
+            (Some(&start), Some(&end)) => &self.edges[start as usize..end as usize],
+            _ => &[],
+        }
+    }
+}
+
+/// The engine's cached snapshot. Session state: never serialized.
+#[derive(Debug, Default)]
+pub(crate) struct TopologyCache {
+    snapshot: Option<TopologySnapshot>,
+    /// Set when an edge's transport kind changed since the snapshot was
+    /// built.
+    kinds_changed: bool,
+}
+
+impl Engine {
+    /// The current topology generation. It changes whenever an applied
+    /// mutation batch adds or removes nodes or edges. Session-only: it
+    /// restarts at 0 on load.
+    pub fn topology_generation(&self) -> u64 {
+        self.graph.topology_generation()
+    }
+
+    /// The graph's adjacency as compressed sparse rows, rebuilt only if the
+    /// topology generation moved on or a transport changed kind since the
+    /// last call. Rebuilds are counted in the step profile's
+    /// [`topology_rebuilds`](crate::profiling::StepProfile::topology_rebuilds).
+    pub fn topology_snapshot(&mut self) -> &TopologySnapshot {
+        let generation = self.graph.topology_generation();
+        let stale = self.topology.kinds_changed
+            || self
+                .topology
+                .snapshot
+                .as_ref()
+                .is_none_or(|snapshot| snapshot.generation != generation);
+        if stale {
+            let mut snapshot = self.topology.snapshot.take().unwrap_or_default();
+            self.build_topology_snapshot(&mut snapshot, generation);
+            self.topology.snapshot = Some(snapshot);
+            self.topology.kinds_changed = false;
+            if let Some(counters) = self.step_counters.as_mut() {
+                counters.topology_rebuilds += 1;
+            }
+        }
+        self.topology
+            .snapshot
+            .as_ref()
+            .expect("snapshot was just built")
+    }
+
+    /// Refill `snapshot` in place, reusing its allocations.
+    fn build_topology_snapshot(&self, snapshot: &mut TopologySnapshot, generation: u64) {
+        snapshot.generation = generation;
+        snapshot.nodes.clear();
+        snapshot
+            .nodes
+            .extend(self.graph.nodes().map(|(node, _)| node));
+        snapshot.nodes.sort_unstable();
+        snapshot.offsets.clear();
+        snapshot.edges.clear();
+        let mut outgoing = Vec::new();
+        for &node in &snapshot.nodes {
+            snapshot.offsets.push(snapshot.edges.len() as u32);
+            outgoing.clear();
+            outgoing.extend_from_slice(self.graph.get_outputs(node));
+            outgoing.sort_unstable();
+            for &edge in &outgoing {
+                let Some(data) = self.graph.get_edge(edge) else {
+                    continue;
+                };
+                let Ok(target) = snapshot.nodes.binary_search(&data.to) else {
+                    continue;
+                };
+                snapshot.edges.push(TopologyEdge {
+                    target: target as u32,
+                    edge,
+                    kind: self.transports.get(edge).map(Transport::kind),
+                });
+            }
+        }
+        snapshot.offsets.push(snapshot.edges.len() as u32);
+    }
+
+    /// Set or, with `None`, remove an edge's transport and return the old
+    /// one. Every write to the transport map goes through here so the
+    /// cached snapshot notices a change of kind.
+    pub(crate) fn replace_transport(
+        &mut self,
+        edge: EdgeId,
+        transport: Option<Transport>,
+    ) -> Option<Transport> {
+        let kind = transport.as_ref().map(Transport::kind);
+        let old = match transport {
+            Some(transport) => self.transports.insert(edge, transport),
+            None => self.transports.remove(edge),
+        };
+        if old.as_ref().map(Transport::kind) != kind {
+            self.topology.kinds_changed = true;
+        }
+        old
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::processor::Processor;
+    use crate::sim::SimulationStrategy;
+    use crate::test_utils::*;
+
+    /// Mine -> smelter -> {assembler, chest}, plus a mine -> chest bypass
+    /// and an edge with no transport.
+    fn build() -> (Engine, [NodeId; 4], [EdgeId; 5]) {
+        let mut engine = Engine::new(SimulationStrategy::Tick);
+        let mine = add_node(&mut engine, make_source(iron(), 1.0), 10, 10);
+        let smelter = add_node(&mut engine, Processor::Passthrough, 10, 10);
+        let assembler = add_node(&mut engine, Processor::Passthrough, 10, 10);
+        let chest = add_node(&mut engine, Processor::Passthrough, 10, 10);
+        let ore = connect(&mut engine, mine, smelter, make_item_transport(4));
+        let plates = connect(&mut engine, smelter, assembler, make_flow_transport(1.0));
+        let overflow = connect(&mut engine, smelter, chest, make_batch_transport(2, 3));
+        let bypass = connect(&mut engine, mine, chest, make_vehicle_transport(5, 2));
+        let pending = engine.graph.queue_connect(assembler, chest);
+        let bare = engine
+            .apply_mutations()
+            .resolve_edge(pending)
+            .expect("edge added");
+        (
+            engine,
+            [mine, smelter, assembler, chest],
+            [ore, plates, overflow, bypass, bare],
+        )
+    }
+
+    #[test]
+    fn snapshot_is_csr_over_sorted_nodes() {
+        let (mut engine, nodes, [ore, plates, overflow, bypass, bare]) = build();
+        let snapshot = engine.topology_snapshot().clone();
+
+        let mut sorted = nodes.to_vec();
+        sorted.sort();
+        assert_eq!(snapshot.nodes, sorted);
+        assert_eq!(snapshot.offsets.len(), 5);
+        assert_eq!(snapshot.edges.len(), 5);
+
+        let [mine, smelter, assembler, chest] = nodes.map(|n| snapshot.index_of(n).unwrap());
+        let edge = |target: usize, edge, kind| TopologyEdge {
+            target: target as u32,
+            edge,
+            kind,
+        };
+        let mut from_mine = vec![
+            edge(smelter, ore, Some(TransportKind::Item)),
+            edge(chest, bypass, Some(TransportKind::Vehicle)),
+        ];
+        from_mine.sort_by_key(|e| e.edge);
+        assert_eq!(snapshot.outgoing(mine), from_mine.as_slice());
+        let mut from_smelter = vec![
+            edge(assembler, plates, Some(TransportKind::Flow)),
+            edge(chest, overflow, Some(TransportKind::Batch)),
+        ];
+        from_smelter.sort_by_key(|e| e.edge);
+        assert_eq!(snapshot.outgoing(smelter), from_smelter.as_slice());
+        assert_eq!(snapshot.outgoing(assembler), &[edge(chest, bare, None)]);
+        assert!(snapshot.outgoing(chest).is_empty());
+        assert!(snapshot.outgoing(99).is_empty());
+        assert_eq!(snapshot.generation, engine.topology_generation());
+    }
+
+    /// Step and return how many times the topology cache was rebuilt since
+    /// the previous step.
+    fn rebuilds(engine: &mut Engine) -> u32 {
+        engine.step();
+        engine.last_step_profile().unwrap().topology_rebuilds
+    }
+
+    fn kind_of(engine: &mut Engine, edge: EdgeId) -> Option<TransportKind> {
+        let snapshot = engine.topology_snapshot();
+        snapshot.edges.iter().find(|e| e.edge == edge).unwrap().kind
+    }
+
+    #[test]
+    fn generation_tracks_topology_and_the_cache_follows_it() {
+        let (mut engine, [mine, smelter, _, chest], [ore, plates, ..]) = build();
+        engine.set_profiling(true);
+        let gone = add_node(&mut engine, Processor::Passthrough, 1, 1);
+        engine.graph.queue_remove_node(gone);
+        engine.apply_mutations();
+        engine.topology_snapshot();
+        let generation = engine.topology_generation();
+        assert_eq!(rebuilds(&mut engine), 1);
+
+        // Configuration changes and empty batches leave both alone.
+        engine.set_processor(
+            smelter,
+            make_recipe(vec![(iron(), 1)], vec![(gear(), 1)], 2),
+        );
+        engine.set_input_inventory(chest, simple_inventory(50));
+        engine.set_transport(ore, make_item_transport(8));
+        engine.for_each_transport_mut(|_, transport| {
+            if let Transport::Flow(flow) = transport {
+                flow.rate *= crate::fixed::Fixed64::from_num(2);
+            }
+        });
+        engine.apply_mutations();
+        engine.graph.queue_remove_node(gone);
+        engine.apply_mutations();
+        engine.topology_snapshot();
+        assert_eq!(engine.topology_generation(), generation);
+        assert_eq!(rebuilds(&mut engine), 0);
+
+        // A different transport kind is visible in the snapshot, however it
+        // was written, without moving the generation.
+        engine.set_transport(ore, make_flow_transport(2.0));
+        assert_eq!(kind_of(&mut engine, ore), Some(TransportKind::Flow));
+        assert_eq!(rebuilds(&mut engine), 1);
+        engine.for_each_transport_mut(|edge, transport| {
+            if edge == plates {
+                *transport = make_batch_transport(2, 3);
+            }
+        });
+        assert_eq!(kind_of(&mut engine, plates), Some(TransportKind::Batch));
+        assert_eq!(rebuilds(&mut engine), 1);
+        engine.remove_edge_state(plates);
+        assert_eq!(kind_of(&mut engine, plates), None);
+        assert_eq!(rebuilds(&mut engine), 1);
+        assert_eq!(engine.topology_generation(), generation);
+
+        // Removing a node bumps once per batch, however much it removes.
+        engine.graph.queue_remove_node(mine);
+        engine.graph.queue_disconnect(ore);
+        engine.apply_mutations();
+        assert_eq!(engine.topology_generation(), generation + 1);
+        let snapshot = engine.topology_snapshot();
+        assert_eq!(snapshot.nodes.len(), 3);
+        assert_eq!(snapshot.edges.len(), 3);
+        assert_eq!(rebuilds(&mut engine), 1);
+    }
+}
@@ -11,12 +11,22 @@
 //! # Transport Types
 //!
 //! - [`FlowTransport`] — continuous rate-based flow (pipes, conveyors)
-//! - [`ItemTransport`] — discrete belt with slots (Factorio-style belts)
+//! - [`ItemTransport`] — belt of spaced items that compress when blocked (Factorio-style belts)
 //! - [`BatchTransport`] — discrete chunks per cycle (train loads, pallets)
 //! - [`VehicleTransport`] — vehicle with capacity and travel time (trucks, drones)
+//!
+//! # Item properties
+//!
+//! Transports only count items; the engine keeps the properties of the items
+//! in transit in their state. A belt maps each tracked item's instance id to
+//! its properties ([`BeltState::properties`]). Flow, batch and vehicle
+//! transports carry fungible quantities, so they keep a [`PropertyManifest`]
+//! of property-tagged groups and deliver them first in, first out.
+
+use std::collections::{BTreeMap, VecDeque};
 
 use crate::fixed::Fixed64;
-use crate::id::ItemTypeId;
+use crate::id::{ItemTypeId, PropertyId};
 use crate::item::ItemStack;
 
 // ---------------------------------------------------------------------------
@@ -31,7 +41,7 @@ use crate::item::ItemStack;
 pub enum Transport {
     /// Continuous rate-based flow (pipes in Builderment/Satisfactory).
     Flow(FlowTransport),
-    /// Discrete belt with slots (Factorio conveyor belts).
+    /// Belt of spaced items that compress when blocked (Factorio conveyor belts).
     Item(ItemTransport),
     /// Discrete chunks per cycle (train loads, pallets).
     Batch(BatchTransport),
@@ -39,11 +49,27 @@ pub enum Transport {
     Vehicle(VehicleTransport),
 }
 
+/// Which strategy a [`Transport`] uses, without its configuration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum TransportKind {
+    Flow,
+    Item,
+    Batch,
+    Vehicle,
+}
+
 /// Continuous rate-based flow transport.
 ///
 /// Items flow at a fixed rate per tick, with an optional latency delay
 /// before items appear at the destination. A buffer accumulates fractional
 /// items between ticks.
+///
+/// Inventories hold whole items, so both ends of the flow round the same
+/// way: fractional amounts accumulate in [`FlowState`] and a whole item is
+/// taken from the source (or delivered to the destination) only once its
+/// accumulator reaches 1, with the remainder carried to the next tick. Over
+/// any run, the items delivered equal the floor of the cumulative amount
+/// drained from the buffer, regardless of phase or save/load boundaries.
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct FlowTransport {
     /// Items per tick (fractional via fixed-point).
@@ -54,18 +80,56 @@ pub struct FlowTransport {
     pub latency: u32,
 }
 
-/// Discrete belt transport with individually tracked slots.
+/// Belt transport carrying individually tracked items.
 ///
-/// Models conveyor belts where each slot can hold one item. Items advance
-/// through slots each tick at the configured speed.
+/// Each lane is `slot_count * min_spacing` long and holds at most
+/// `slot_count` items. Items advance up to `speed` per tick but never come
+/// closer than `min_spacing` to the item ahead, so a blocked belt compresses
+/// and a released one spreads back out to the spacing its input gives it.
+/// Belt math saturates, so a lane longer than `Fixed64::MAX` is clamped to
+/// it rather than overflowing.
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct ItemTransport {
-    /// Slots advanced per tick (fractional via fixed-point).
+    /// Distance advanced per tick, in the same units as `min_spacing`
+    /// (fractional via fixed-point).
     pub speed: Fixed64,
-    /// Total number of slots on the belt.
+    /// Number of items one lane holds when fully compressed.
     pub slot_count: u32,
     /// Number of parallel lanes (typically 1-2).
     pub lanes: u8,
+    /// Closest distance between two items on a lane; one slot by default.
+    /// Non-positive values are treated as one.
+    #[serde(default = "default_min_spacing")]
+    pub min_spacing: Fixed64,
+}
+
+fn default_min_spacing() -> Fixed64 {
+    Fixed64::ONE
+}
+
+impl ItemTransport {
+    /// Length of one lane: `slot_count * min_spacing`, saturating at
+    /// `Fixed64::MAX`.
+    pub fn length(&self) -> Fixed64 {
+        Fixed64::saturating_from_num(self.slot_count).saturating_mul(self.spacing())
+    }
+
+    /// The spacing items keep, with the non-positive fallback applied.
+    pub(crate) fn spacing(&self) -> Fixed64 {
+        if self.min_spacing > Fixed64::ZERO {
+            self.min_spacing
+        } else {
+            Fixed64::ONE
+        }
+    }
+
+    /// Distance an item travels from entering a lane to reaching its output
+    /// end: the lane length less the item's own spacing, saturating like
+    /// [`length`](Self::length).
+    pub(crate) fn travel(&self) -> Fixed64 {
+        Fixed64::saturating_from_num(self.slot_count.saturating_sub(1))
+            .saturating_mul(self.spacing())
+    }
 }
 
 /// Discrete batch transport delivering chunks per cycle.
@@ -113,18 +177,48 @@ pub struct FlowState {
     pub buffered: Fixed64,
     /// Remaining latency ticks before buffered items start delivering.
     pub latency_remaining: u32,
+    /// Fraction of an item accepted into the buffer but not yet taken from
+    /// the source. Always in `[0, 1)`.
+    #[serde(default)]
+    pub intake_remainder: Fixed64,
+    /// Fraction of an item drained from the buffer but not yet delivered.
+    /// Always in `[0, 1)`; an item is delivered each time it reaches 1.
+    #[serde(default)]
+    pub accumulator: Fixed64,
+    /// Properties of the items in the buffer.
+    #[serde(default)]
+    pub properties: PropertyManifest,
 }
 
 /// State for [`ItemTransport`].
 ///
-/// Slots are stored as a flat array. Each slot is `None` (empty) or
-/// `Some(ItemTypeId)`. The array is pre-allocated at creation to the belt's
-/// declared length times lane count. No runtime reallocation.
+/// Each lane lists its items from the output end back to the input end.
+/// An item's `position` is the distance it has travelled: `0` when it
+/// enters and the transport's travel distance (`(slot_count - 1) *
+/// min_spacing`) when it reaches the output end.
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct BeltState {
-    /// Flat array of slots: `lanes * slot_count` entries.
-    /// Layout: lane 0 slots [0..slot_count), lane 1 slots [slot_count..2*slot_count), etc.
-    pub slots: Vec<Option<ItemTypeId>>,
+    /// Items on each lane, output end first.
+    #[serde(default)]
+    pub lanes: Vec<Vec<BeltItem>>,
+    /// The transport's `slot_count` when the state was created. A belt
+    /// changed to another slot count starts empty.
+    #[serde(default)]
+    pub slot_count: u32,
+    /// Properties of the items that entered with any, by instance id.
+    #[serde(default)]
+    pub properties: BTreeMap<u64, BTreeMap<PropertyId, Fixed64>>,
+}
+
+/// One item on a belt lane.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct BeltItem {
+    pub item_type: ItemTypeId,
+    /// Stable instance id. `0` means the item entered untracked (via
+    /// [`Transport::advance`]); the engine assigns ids starting at 1.
+    pub instance_id: u64,
+    /// Distance travelled from the input end.
+    pub position: Fixed64,
 }
 
 /// State for [`BatchTransport`].
@@ -134,6 +228,9 @@ pub struct BatchState {
     pub progress: u32,
     /// Items pending delivery in the current batch.
     pub pending: u32,
+    /// Properties of the pending items.
+    #[serde(default)]
+    pub properties: PropertyManifest,
 }
 
 /// State for [`VehicleTransport`].
@@ -145,6 +242,88 @@ pub struct VehicleState {
     pub cargo: Vec<ItemStack>,
     /// Whether the vehicle is on the return trip.
     pub returning: bool,
+    /// Properties of the cargo.
+    #[serde(default)]
+    pub properties: PropertyManifest,
+}
+
+/// Properties of the items in transit on a fungible transport, oldest first.
+///
+/// A manifest stays empty until an item with properties enters. From then on
+/// it records every entering item, with or without properties, until all of
+/// them have been delivered. Items in transit beyond the manifest's
+/// [`quantity`](Self::quantity) entered before it started recording, carry no
+/// properties and leave first.
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct PropertyManifest {
+    groups: VecDeque<PropertyGroup>,
+}
+
+/// A run of items in transit that share the same properties.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct PropertyGroup {
+    pub quantity: u32,
+    pub properties: BTreeMap<PropertyId, Fixed64>,
+}
+
+impl PropertyManifest {
+    /// Whether the manifest records no items.
+    pub fn is_empty(&self) -> bool {
+        self.groups.is_empty()
+    }
+
+    /// Number of items the manifest records.
+    pub fn quantity(&self) -> u32 {
+        self.groups.iter().map(|g| g.quantity).sum()
+    }
+
+    /// The recorded groups, oldest first.
+    pub fn groups(&self) -> impl Iterator<Item = &PropertyGroup> {
+        self.groups.iter()
+    }
+
+    /// Record `quantity` items entering with `properties`.
+    pub(crate) fn record(&mut self, quantity: u32, properties: &BTreeMap<PropertyId, Fixed64>) {
+        if quantity == 0 || (properties.is_empty() && self.groups.is_empty()) {
+            return;
+        }
+        match self.groups.back_mut() {
+            Some(last) if last.properties == *properties => last.quantity += quantity,
+            _ => self.groups.push_back(PropertyGroup {
+                quantity,
+                properties: properties.clone(),
+            }),
+        }
+    }
+
+    /// Remove the oldest `quantity` of the `in_transit` items and append
+    /// their groups to `out`. Items the manifest does not record come first,
+    /// as one group without properties.
+    pub(crate) fn release(&mut self, quantity: u32, in_transit: u32, out: &mut Vec<PropertyGroup>) {
+        let unrecorded = in_transit.saturating_sub(self.quantity()).min(quantity);
+        if unrecorded > 0 {
+            out.push(PropertyGroup {
+                quantity: unrecorded,
+                properties: BTreeMap::new(),
+            });
+        }
+        let mut remaining = quantity - unrecorded;
+        while remaining > 0 {
+            let Some(front) = self.groups.front_mut() else {
+                break;
+            };
+            let take = front.quantity.min(remaining);
+            front.quantity -= take;
+            remaining -= take;
+            out.push(PropertyGroup {
+                quantity: take,
+                properties: front.properties.clone(),
+            });
+            if front.quantity == 0 {
+                self.groups.pop_front();
+            }
+        }
+    }
 }
 
 // ---------------------------------------------------------------------------
@@ -171,24 +350,59 @@ impl TransportState {
             Transport::Flow(flow) => TransportState::Flow(FlowState {
                 buffered: Fixed64::ZERO,
                 latency_remaining: flow.latency,
+                intake_remainder: Fixed64::ZERO,
+                accumulator: Fixed64::ZERO,
+                properties: PropertyManifest::default(),
+            }),
+            Transport::Item(item) => TransportState::Item(BeltState {
+                lanes: (0..item.lanes)
+                    .map(|_| Vec::with_capacity(item.slot_count as usize))
+                    .collect(),
+                slot_count: item.slot_count,
+                properties: BTreeMap::new(),
             }),
-            Transport::Item(item) => {
-                let total_slots = item.slot_count as usize * item.lanes as usize;
-                TransportState::Item(BeltState {
-                    slots: vec![None; total_slots],
-                })
-            }
             Transport::Batch(_) => TransportState::Batch(BatchState {
                 progress: 0,
                 pending: 0,
+                properties: PropertyManifest::default(),
             }),
             Transport::Vehicle(_) => TransportState::Vehicle(VehicleState {
                 position: 0,
                 cargo: Vec::new(),
                 returning: false,
+                properties: PropertyManifest::default(),
             }),
         }
     }
+
+    /// Whether no whole item is in transit. A flow buffer holding less than
+    /// half an item counts as empty, as it does in the item census.
+    pub(crate) fn is_empty(&self) -> bool {
+        match self {
+            TransportState::Item(belt) => belt.occupied_count() == 0,
+            TransportState::Flow(flow) => flow.held() == 0,
+            TransportState::Batch(batch) => batch.pending == 0,
+            TransportState::Vehicle(vehicle) => vehicle.cargo.iter().all(|s| s.quantity == 0),
+        }
+    }
+
+    /// Whether this state can keep running under `transport`: same kind and,
+    /// for a belt, the same slot count and lanes with every item within the
+    /// belt's length.
+    pub(crate) fn fits(&self, transport: &Transport) -> bool {
+        match (self, transport) {
+            (TransportState::Item(belt), Transport::Item(item)) => {
+                let travel = item.travel();
+                belt.slot_count == item.slot_count
+                    && belt.lanes.len() == item.lanes as usize
+                    && belt.items().all(|(_, i)| i.position <= travel)
+            }
+            (TransportState::Flow(_), Transport::Flow(_))
+            | (TransportState::Batch(_), Transport::Batch(_))
+            | (TransportState::Vehicle(_), Transport::Vehicle(_)) => true,
+            _ => false,
+        }
+    }
 }
 
 // ---------------------------------------------------------------------------
@@ -196,6 +410,16 @@ impl TransportState {
 // ---------------------------------------------------------------------------
 
 impl Transport {
+    /// The strategy this transport uses.
+    pub fn kind(&self) -> TransportKind {
+        match self {
+            Transport::Flow(_) => TransportKind::Flow,
+            Transport::Item(_) => TransportKind::Item,
+            Transport::Batch(_) => TransportKind::Batch,
+            Transport::Vehicle(_) => TransportKind::Vehicle,
+        }
+    }
+
     /// Advance this transport by one tick.
     ///
     /// - `state`: mutable transport state (must match `self` variant).
@@ -207,14 +431,66 @@ impl Transport {
     ///
     /// Panics if `state` variant does not match `self` variant.
     pub fn advance(&self, state: &mut TransportState, available: u32) -> TransportResult {
+        self.advance_inner(state, available, u32::MAX, None)
+    }
+
+    /// Advance this transport by one tick, delivering at most `deliverable`
+    /// items.
+    ///
+    /// Identical to [`advance`](Self::advance), except that items that would
+    /// arrive beyond `deliverable` stay in the transport: a belt keeps them
+    /// at its output end, a flow returns them to its buffer, and batches and
+    /// vehicles keep them loaded and retry on the next tick.
+    pub fn advance_limited(
+        &self,
+        state: &mut TransportState,
+        available: u32,
+        deliverable: u32,
+    ) -> TransportResult {
+        self.advance_inner(state, available, deliverable, None)
+    }
+
+    /// Advance this transport by one tick, tagging items that enter a belt.
+    ///
+    /// Identical to [`advance_limited`](Self::advance_limited), except that
+    /// every item inserted into an [`ItemTransport`] is recorded as
+    /// `item_type` and receives the instance id `*next_instance_id`, which
+    /// is then incremented. Other transport kinds ignore the extra arguments.
+    pub fn advance_tracked(
+        &self,
+        state: &mut TransportState,
+        available: u32,
+        deliverable: u32,
+        item_type: ItemTypeId,
+        next_instance_id: &mut u64,
+    ) -> TransportResult {
+        self.advance_inner(
+            state,
+            available,
+            deliverable,
+            Some((item_type, next_instance_id)),
+        )
+    }
+
+    fn advance_inner(
+        &self,
+        state: &mut TransportState,
+        available: u32,
+        deliverable: u32,
+        tracking: Option<(ItemTypeId, &mut u64)>,
+    ) -> TransportResult {
         match (self, state) {
-            (Transport::Flow(flow), TransportState::Flow(fs)) => advance_flow(flow, fs, available),
-            (Transport::Item(item), TransportState::Item(bs)) => advance_item(item, bs, available),
+            (Transport::Flow(flow), TransportState::Flow(fs)) => {
+                advance_flow(flow, fs, available, deliverable)
+            }
+            (Transport::Item(item), TransportState::Item(bs)) => {
+                advance_item(item, bs, available, deliverable, tracking)
+            }
             (Transport::Batch(batch), TransportState::Batch(bs)) => {
-                advance_batch(batch, bs, available)
+                advance_batch(batch, bs, available, deliverable)
             }
             (Transport::Vehicle(vehicle), TransportState::Vehicle(vs)) => {
-                advance_vehicle(vehicle, vs, available)
+                advance_vehicle(vehicle, vs, available, deliverable)
             }
             _ => {
                 debug_assert!(
@@ -238,13 +514,23 @@ impl Transport {
 ///
 /// Behavior:
 /// 1. Accept up to `rate` items from source, limited by buffer capacity.
-/// 2. If latency has expired, deliver buffered items (up to rate) to destination.
+///    Whole items are taken from the source as the intake remainder crosses 1.
+/// 2. If latency has expired, drain up to `rate` from the buffer into the
+///    delivery accumulator and deliver its whole part, carrying the fraction.
 /// 3. If latency has not expired, decrement latency counter (no delivery).
-fn advance_flow(flow: &FlowTransport, state: &mut FlowState, available: u32) -> TransportResult {
+///
+/// Whole items beyond `deliverable` go back into the buffer.
+fn advance_flow(
+    flow: &FlowTransport,
+    state: &mut FlowState,
+    available: u32,
+    deliverable: u32,
+) -> TransportResult {
     let available_fixed = Fixed64::from_num(available);
     let rate = flow.rate;
 
-    // How much can we accept? Limited by rate and remaining buffer capacity.
+    // How much can we accept? Limited by rate, remaining buffer capacity, and
+    // the items the source can still cover after the carried remainder.
     let space_in_buffer = flow.buffer_capacity - state.buffered;
     let can_accept = rate.min(available_fixed).min(space_in_buffer);
     // Clamp to zero in case of negative from rounding.
@@ -256,22 +542,31 @@ fn advance_flow(flow: &FlowTransport, state: &mut FlowState, available: u32) ->
 
     state.buffered += accepted;
 
-    let items_moved: u32 = accepted.to_num();
+    // `intake_remainder < 1` and `accepted <= available`, so the whole part
+    // never exceeds what the source holds.
+    let intake = state.intake_remainder + accepted;
+    let items_moved: u32 = intake.to_num();
+    state.intake_remainder = intake.frac();
 
     // Delivery: only after latency has expired.
     let items_delivered = if state.latency_remaining > 0 {
         state.latency_remaining -= 1;
         0
     } else {
-        // Deliver up to rate from the buffer.
+        // Drain up to rate from the buffer.
         let can_deliver = rate.min(state.buffered);
-        let delivered = if can_deliver > Fixed64::ZERO {
+        let drained = if can_deliver > Fixed64::ZERO {
             can_deliver
         } else {
             Fixed64::ZERO
         };
-        state.buffered -= delivered;
-        delivered.to_num()
+        state.buffered -= drained;
+        let total = state.accumulator + drained;
+        state.accumulator = total.frac();
+        let whole: u32 = total.to_num();
+        let delivered = whole.min(deliverable);
+        state.buffered += Fixed64::from_num(whole - delivered);
+        delivered
     };
 
     TransportResult {
@@ -286,53 +581,108 @@ fn advance_flow(flow: &FlowTransport, state: &mut FlowState, available: u32) ->
 
 /// Advance a belt transport by one tick.
 ///
-/// Items advance through slots from high index toward low index (slot 0 is
-/// the output end, slot N-1 is the input end). Each tick:
-/// 1. Move items forward through slots (respecting occupancy — no passing).
-/// 2. Try to insert new items at the input end if slots are free.
+/// Each lane is processed independently, front item first:
+/// 1. Every item advances up to `speed`, stopping `min_spacing` behind the
+///    item ahead; the front item stops at the output end.
+/// 2. An item reaching the output end is delivered while fewer than
+///    `deliverable` items have been. An undelivered item waits there and
+///    the lane compresses behind it. An item that was already waiting
+///    needs one spacing of its movement to clear the end, so the item behind
+///    it does not arrive in the same tick.
+/// 3. New items enter at the input end, one spacing behind the last item,
+///    as long as they fit on the lane and could have entered during this
+///    tick: positions below `speed`.
 ///
 /// Returns items that fell off the output end (delivered) and items inserted
-/// at the input end (moved).
+/// at the input end (moved). All arithmetic is saturating `Fixed64`, so a
+/// given configuration and input always produce the same positions, and
+/// extreme speeds or spacings clamp instead of overflowing.
 ///
-/// For simplicity in this initial implementation, `speed` is treated as
-/// integer slots per tick (the integer part of the fixed-point value).
-fn advance_item(item: &ItemTransport, state: &mut BeltState, available: u32) -> TransportResult {
-    let slot_count = item.slot_count as usize;
-    let lanes = item.lanes as usize;
-    let steps: usize = item.speed.to_num::<u32>() as usize;
-    let steps = steps.max(1); // At least 1 step per tick.
-
+/// When `tracking` is set, inserted items are recorded with the given item
+/// type and a fresh instance id, which stays with the item.
+fn advance_item(
+    item: &ItemTransport,
+    state: &mut BeltState,
+    available: u32,
+    deliverable: u32,
+    mut tracking: Option<(ItemTypeId, &mut u64)>,
+) -> TransportResult {
     let mut items_delivered = 0u32;
     let mut items_moved = 0u32;
+    if item.slot_count == 0 {
+        return TransportResult {
+            items_moved,
+            items_delivered,
+        };
+    }
 
-    // Process each lane independently.
-    for lane in 0..lanes {
-        let base = lane * slot_count;
-
-        for _step in 0..steps {
-            // Phase 1: Advance items toward slot 0.
-            // Walk from slot 1 to slot N-1; if current slot has item and
-            // previous slot is empty, move it forward.
-            for i in 1..slot_count {
-                if state.slots[base + i].is_some() && state.slots[base + i - 1].is_none() {
-                    state.slots[base + i - 1] = state.slots[base + i].take();
-                }
-            }
+    state.lanes.resize_with(item.lanes as usize, Vec::new);
+
+    let speed = item.speed;
+    let spacing = item.spacing();
+    let travel = item.travel();
+    // Where the first item entering an empty lane lands: the furthest
+    // whole number of spacings it could have covered this tick.
+    let entry = if speed > Fixed64::ZERO {
+        let whole = speed.saturating_div(spacing).saturating_ceil() - Fixed64::ONE;
+        whole.saturating_mul(spacing).min(travel)
+    } else {
+        -spacing
+    };
 
-            // Phase 2: Check if output slot (index 0) has an item to deliver.
-            if state.slots[base].is_some() {
-                state.slots[base] = None;
+    for lane in &mut state.lanes {
+        // Furthest position the next item may reach.
+        let mut limit = travel;
+        let mut delivered = 0;
+        for (index, belt_item) in lane.iter_mut().enumerate() {
+            let start = belt_item.position;
+            belt_item.position = start.max(start.saturating_add(speed).min(limit));
+            if index == delivered && belt_item.position >= travel && items_delivered < deliverable {
+                delivered += 1;
                 items_delivered += 1;
+                let clearing = if start >= travel {
+                    spacing
+                } else {
+                    Fixed64::ZERO
+                };
+                limit = start
+                    .saturating_add(speed)
+                    .saturating_sub(clearing)
+                    .saturating_sub(spacing)
+                    .min(travel);
+            } else {
+                limit = belt_item.position.saturating_sub(spacing);
             }
+        }
+        lane.drain(..delivered);
 
-            // Phase 3: Insert new item at input end if available and slot is free.
-            let input_slot = base + slot_count - 1;
-            if available > items_moved && state.slots[input_slot].is_none() {
-                // Use a placeholder ItemTypeId since we track count, not type,
-                // in this simplified model.
-                state.slots[input_slot] = Some(ItemTypeId(0));
-                items_moved += 1;
+        let mut at = match lane.last() {
+            Some(tail) if tail.position.saturating_sub(spacing) < speed => {
+                tail.position.saturating_sub(spacing)
             }
+            _ => entry,
+        };
+        while at >= Fixed64::ZERO
+            && available > items_moved
+            && lane.len() < item.slot_count as usize
+        {
+            // Untracked advances use a placeholder ItemTypeId since only
+            // the count matters to the caller.
+            let (item_type, instance_id) = match tracking.as_mut() {
+                Some((item_type, next_id)) => {
+                    let id = **next_id;
+                    **next_id += 1;
+                    (*item_type, id)
+                }
+                None => (ItemTypeId(0), 0),
+            };
+            lane.push(BeltItem {
+                item_type,
+                instance_id,
+                position: at,
+            });
+            items_moved += 1;
+            at = at.saturating_sub(spacing);
         }
     }
 
@@ -352,10 +702,13 @@ fn advance_item(item: &ItemTransport, state: &mut BeltState, available: u32) ->
 /// 1. Increment progress counter.
 /// 2. Accept items into the pending buffer (up to batch_size).
 /// 3. When progress reaches cycle_time, deliver pending items and reset.
+///    Items beyond `deliverable` stay pending and the delivery is retried on
+///    the next tick.
 fn advance_batch(
     batch: &BatchTransport,
     state: &mut BatchState,
     available: u32,
+    deliverable: u32,
 ) -> TransportResult {
     // Accept items into pending (up to batch_size).
     let space = batch.batch_size.saturating_sub(state.pending);
@@ -368,9 +721,13 @@ fn advance_batch(
     // Check if cycle is complete.
     let items_delivered;
     if state.progress >= batch.cycle_time {
-        items_delivered = state.pending;
-        state.pending = 0;
-        state.progress = 0;
+        items_delivered = state.pending.min(deliverable);
+        state.pending -= items_delivered;
+        state.progress = if state.pending == 0 {
+            0
+        } else {
+            batch.cycle_time.saturating_sub(1)
+        };
     } else {
         items_delivered = 0;
     }
@@ -393,10 +750,14 @@ fn advance_batch(
 /// 3. At destination (position >= travel_time): deliver cargo, begin return.
 /// 4. Return trip (returning=true): position decrements each tick.
 /// 5. Back at source (position=0, returning): ready for next load.
+///
+/// A vehicle that can unload only part of its cargo (at most `deliverable`
+/// items) waits at the destination and unloads the rest on later ticks.
 fn advance_vehicle(
     vehicle: &VehicleTransport,
     state: &mut VehicleState,
     available: u32,
+    deliverable: u32,
 ) -> TransportResult {
     let mut items_moved = 0u32;
     let mut items_delivered = 0u32;
@@ -429,10 +790,14 @@ fn advance_vehicle(
 
             if state.position >= vehicle.travel_time {
                 // Arrived at destination — deliver cargo.
-                items_delivered = state.cargo.iter().map(|s| s.quantity).sum();
-                state.cargo.clear();
-                state.returning = true;
-                // Position stays at travel_time; will decrement on return.
+                items_delivered = unload(&mut state.cargo, deliverable);
+                if state.cargo.is_empty() {
+                    state.returning = true;
+                    // Position stays at travel_time; will decrement on return.
+                } else {
+                    // Wait at the destination to unload the rest.
+                    state.position = vehicle.travel_time.saturating_sub(1);
+                }
             }
         }
     }
@@ -443,14 +808,86 @@ fn advance_vehicle(
     }
 }
 
+/// Remove up to `max` items from the front of `cargo`, dropping emptied
+/// stacks. Returns the number removed.
+fn unload(cargo: &mut Vec<ItemStack>, max: u32) -> u32 {
+    let mut unloaded = 0;
+    for stack in cargo.iter_mut() {
+        let take = stack.quantity.min(max - unloaded);
+        stack.quantity -= take;
+        unloaded += take;
+    }
+    cargo.retain(|stack| stack.quantity > 0);
+    unloaded
+}
+
+impl FlowState {
+    /// Whole items taken from the source and not yet delivered. Fractional
+    /// intake and drainage cancel out, so this is exact; it is rounded only
+    /// to absorb states edited by hand.
+    pub fn held(&self) -> u32 {
+        let held = (self.buffered - self.intake_remainder + self.accumulator).round();
+        held.max(Fixed64::ZERO).to_num::<u32>()
+    }
+}
+
 // ---------------------------------------------------------------------------
 // Helper: count occupied slots on a belt
 // ---------------------------------------------------------------------------
 
 impl BeltState {
-    /// Count total occupied slots across all lanes.
+    /// Count total items across all lanes.
     pub fn occupied_count(&self) -> usize {
-        self.slots.iter().filter(|s| s.is_some()).count()
+        self.lanes.iter().map(Vec::len).sum()
+    }
+
+    /// Every item on the belt with its lane, lane by lane from the output
+    /// end back.
+    pub fn items(&self) -> impl Iterator<Item = (u8, &BeltItem)> {
+        self.lanes
+            .iter()
+            .enumerate()
+            .flat_map(|(lane, items)| items.iter().map(move |item| (lane as u8, item)))
+    }
+
+    /// Normalized position of a slot along its lane: `0` at the input end,
+    /// `1` at the output end. A single-slot lane is always at `1`.
+    pub fn slot_position(index_in_lane: usize, slot_count: usize) -> Fixed64 {
+        if slot_count <= 1 {
+            return Fixed64::ONE;
+        }
+        let from_input = (slot_count - 1).saturating_sub(index_in_lane);
+        Fixed64::from_num(from_input as u64) / Fixed64::from_num((slot_count - 1) as u64)
+    }
+
+    /// Build a belt from the slot array belts were saved as before they
+    /// tracked positions: one entry per slot, slot 0 at the output end of
+    /// lane 0. The item in slot `i` of a lane sits `slot_count - 1 - i`
+    /// spacings from the input end, which is where the slot model kept it.
+    /// The items enter untracked.
+    pub(crate) fn from_slots(slots: &[Option<ItemTypeId>], item: &ItemTransport) -> Self {
+        let slot_count = item.slot_count as usize;
+        let spacing = item.spacing();
+        let mut lanes = vec![Vec::new(); item.lanes as usize];
+        for (index, slot) in slots.iter().enumerate() {
+            let lane = index
+                .checked_div(slot_count)
+                .and_then(|lane| lanes.get_mut(lane));
+            let (Some(item_type), Some(lane)) = (*slot, lane) else {
+                continue;
+            };
+            let from_input = (slot_count - 1).saturating_sub(index % slot_count);
+            lane.push(BeltItem {
+                item_type,
+                instance_id: 0,
+                position: Fixed64::saturating_from_num(from_input).saturating_mul(spacing),
+            });
+        }
+        Self {
+            lanes,
+            slot_count: item.slot_count,
+            properties: BTreeMap::new(),
+        }
     }
 }
 
@@ -481,6 +918,7 @@ mod tests {
             speed: Fixed64::from_num(speed),
             slot_count,
             lanes,
+            min_spacing: Fixed64::ONE,
         });
         let s = TransportState::new_for(&t);
         (t, s)
@@ -563,6 +1001,27 @@ mod tests {
         assert_eq!(r.items_delivered, 5);
     }
 
+    #[test]
+    fn flow_fractional_rate_carries_remainder() {
+        let (t, mut s) = make_flow(0.5, 100.0, 0);
+        let mut moved = 0;
+        let mut delivered = Vec::new();
+        for _ in 0..60 {
+            let r = t.advance(&mut s, 10);
+            moved += r.items_moved;
+            delivered.push(r.items_delivered);
+        }
+        // Half an item per tick: one whole item every second tick, at both ends.
+        assert_eq!(moved, 30);
+        assert_eq!(delivered.iter().sum::<u32>(), 30);
+        assert_eq!(&delivered[..4], &[0, 1, 0, 1]);
+        let TransportState::Flow(fs) = &s else {
+            panic!("expected flow state");
+        };
+        assert_eq!(fs.accumulator, Fixed64::ZERO);
+        assert_eq!(fs.buffered, Fixed64::ZERO);
+    }
+
     // -----------------------------------------------------------------------
     // Test 4: ItemTransport — items advance through belt
     // -----------------------------------------------------------------------
@@ -595,18 +1054,22 @@ mod tests {
         // 3-slot belt, speed=1, 1 lane.
         let (t, mut s) = make_belt(1.0, 3, 1);
 
-        // Manually fill all slots to simulate a full belt.
+        // Manually fill the lane to simulate a full, compressed belt.
         if let TransportState::Item(ref mut bs) = s {
-            bs.slots[0] = Some(ItemTypeId(0));
-            bs.slots[1] = Some(ItemTypeId(0));
-            bs.slots[2] = Some(ItemTypeId(0));
+            bs.lanes[0] = (0..3)
+                .rev()
+                .map(|position| BeltItem {
+                    item_type: ItemTypeId(0),
+                    instance_id: 0,
+                    position: Fixed64::from_num(position),
+                })
+                .collect();
         }
 
-        // Belt is completely full. Advance tries to shift items forward,
-        // but all slots are occupied so nothing shifts. Then slot 0 is
-        // delivered, freeing it. However, items can't cascade-shift within
-        // the same tick step after delivery, so the input slot (slot 2)
-        // is still occupied and no new item can be inserted.
+        // Belt is completely full. The front item was waiting at the output
+        // end, so it spends this tick clearing it and the items behind it
+        // cannot move up yet. The input end stays occupied and no new item
+        // can be inserted.
         let r = t.advance(&mut s, 10);
         assert_eq!(r.items_delivered, 1);
         assert_eq!(
@@ -614,13 +1077,13 @@ mod tests {
             "cannot insert when input slot is occupied"
         );
 
-        // After the tick, belt has 2 items: slots 1 and 2 occupied, slot 0 free.
+        // After the tick, belt has 2 items and the output end is free.
         if let TransportState::Item(ref bs) = s {
             assert_eq!(bs.occupied_count(), 2);
         }
 
-        // Next tick: items shift forward (slot 1->0, slot 2->1), slot 0
-        // delivers, and now input slot (2) is free for a new item.
+        // Next tick: both items move up one spacing, the front one is
+        // delivered, and the input end is free for a new item.
         let r = t.advance(&mut s, 10);
         assert_eq!(r.items_delivered, 1);
         assert_eq!(r.items_moved, 1, "input slot is now free after shift");
@@ -717,10 +1180,12 @@ mod tests {
             speed: Fixed64::from_num(1),
             slot_count: 10,
             lanes: 2,
+            min_spacing: Fixed64::ONE,
         });
         let state = TransportState::new_for(&item);
         if let TransportState::Item(bs) = &state {
-            assert_eq!(bs.slots.len(), 20); // 10 slots * 2 lanes
+            assert_eq!(bs.lanes.len(), 2);
+            assert_eq!(bs.slot_count, 10);
         } else {
             panic!("expected BeltState");
         }
@@ -735,6 +1200,7 @@ mod tests {
             TransportState::Batch(BatchState {
                 progress: 0,
                 pending: 0,
+                ..
             })
         ));
 
@@ -832,9 +1298,9 @@ mod tests {
         // 3-slot belt, speed=1, 2 lanes.
         let (t, mut s) = make_belt(1.0, 3, 2);
 
-        // Verify state has 6 slots (3 * 2).
+        // Verify state has 2 lanes.
         if let TransportState::Item(ref bs) = s {
-            assert_eq!(bs.slots.len(), 6);
+            assert_eq!(bs.lanes.len(), 2);
         }
 
         // Tick 1: insert items on both lanes.
@@ -843,6 +1309,283 @@ mod tests {
         assert_eq!(r.items_delivered, 0);
     }
 
+    // -----------------------------------------------------------------------
+    // Test 13a: Limited delivery holds items in every transport kind
+    // -----------------------------------------------------------------------
+    #[test]
+    fn limited_delivery_holds_items_back() {
+        // Belt: the front item waits at the output end while nothing may be
+        // delivered.
+        let (t, mut s) = make_belt(1.0, 2, 1);
+        t.advance(&mut s, 1);
+        t.advance_limited(&mut s, 1, 0);
+        let r = t.advance_limited(&mut s, 1, 0);
+        assert_eq!(r.items_delivered, 0);
+        assert_eq!(r.items_moved, 0, "full belt accepts nothing");
+        let r = t.advance_limited(&mut s, 0, 1);
+        assert_eq!(r.items_delivered, 1);
+
+        // Flow: undeliverable whole items return to the buffer.
+        let (t, mut s) = make_flow(2.0, 100.0, 0);
+        let r = t.advance_limited(&mut s, 2, 1);
+        assert_eq!(r.items_delivered, 1);
+        let TransportState::Flow(ref fs) = s else {
+            panic!("expected FlowState");
+        };
+        assert_eq!(fs.buffered, Fixed64::from_num(1));
+
+        // Batch: leftovers stay pending and are retried next tick.
+        let (t, mut s) = make_batch(5, 1);
+        let r = t.advance_limited(&mut s, 5, 3);
+        assert_eq!(r.items_delivered, 3);
+        let r = t.advance_limited(&mut s, 0, 10);
+        assert_eq!(r.items_delivered, 2);
+
+        // Vehicle: waits at the destination until the cargo is unloaded.
+        let (t, mut s) = make_vehicle(4, 2);
+        t.advance(&mut s, 4);
+        let r = t.advance_limited(&mut s, 0, 1);
+        assert_eq!(r.items_delivered, 1);
+        let r = t.advance_limited(&mut s, 0, 10);
+        assert_eq!(r.items_delivered, 3);
+        let TransportState::Vehicle(ref vs) = s else {
+            panic!("expected VehicleState");
+        };
+        assert!(vs.returning);
+        assert_eq!(vs.position, 2);
+    }
+
+    // -----------------------------------------------------------------------
+    // Test 13b: ItemTransport — tracked advance assigns ids that follow items
+    // -----------------------------------------------------------------------
+    #[test]
+    fn belt_tracked_ids_follow_items() {
+        let (t, mut s) = make_belt(1.0, 3, 1);
+        let mut next_id = 1;
+
+        t.advance_tracked(&mut s, 1, u32::MAX, ItemTypeId(7), &mut next_id);
+        t.advance_tracked(&mut s, 1, u32::MAX, ItemTypeId(7), &mut next_id);
+        assert_eq!(next_id, 3);
+        let lane = |s: &TransportState| -> Vec<(ItemTypeId, u64, Fixed64)> {
+            let TransportState::Item(bs) = s else {
+                panic!("expected BeltState");
+            };
+            bs.lanes[0]
+                .iter()
+                .map(|i| (i.item_type, i.instance_id, i.position))
+                .collect()
+        };
+        assert_eq!(
+            lane(&s),
+            vec![
+                (ItemTypeId(7), 1, Fixed64::ONE),
+                (ItemTypeId(7), 2, Fixed64::ZERO),
+            ]
+        );
+
+        // Item 1 reaches the output end and is delivered; untracked inserts
+        // carry id 0.
+        t.advance(&mut s, 1);
+        assert_eq!(
+            lane(&s),
+            vec![
+                (ItemTypeId(7), 2, Fixed64::ONE),
+                (ItemTypeId(0), 0, Fixed64::ZERO),
+            ]
+        );
+
+        assert_eq!(BeltState::slot_position(2, 3), Fixed64::ZERO);
+        assert_eq!(BeltState::slot_position(1, 3), Fixed64::from_num(0.5));
+        assert_eq!(BeltState::slot_position(0, 3), Fixed64::ONE);
+        assert_eq!(BeltState::slot_position(0, 1), Fixed64::ONE);
+    }
+
+    // -----------------------------------------------------------------------
+    // Test 13c: ItemTransport — compression and recovery after a stall
+    // -----------------------------------------------------------------------
+    #[test]
+    fn belt_recovers_nominal_rate_after_stall() {
+        // 1.5 spacings per tick: nominal 1.5 items per tick.
+        let (t, mut s) = make_belt(1.5, 10, 1);
+        let mut on_belt = 0u32;
+        let mut run = |s: &mut TransportState, ticks: u32, deliverable: u32| -> u32 {
+            let mut delivered = 0;
+            for _ in 0..ticks {
+                let r = t.advance_limited(s, u32::MAX, deliverable);
+                on_belt += r.items_moved;
+                on_belt -= r.items_delivered;
+                delivered += r.items_delivered;
+                let TransportState::Item(bs) = &*s else {
+                    panic!("expected BeltState");
+                };
+                assert_eq!(bs.occupied_count(), on_belt as usize, "items conserved");
+            }
+            delivered
+        };
+
+        run(&mut s, 20, u32::MAX);
+        let before = run(&mut s, 100, u32::MAX);
+        assert_eq!(before, 150);
+
+        // Stall: the lane compresses to one item per slot.
+        run(&mut s, 100, 0);
+        let TransportState::Item(ref bs) = s else {
+            panic!("expected BeltState");
+        };
+        let positions: Vec<Fixed64> = bs.lanes[0].iter().map(|i| i.position).collect();
+        let packed: Vec<Fixed64> = (0..10).rev().map(Fixed64::from_num).collect();
+        assert_eq!(positions, packed);
+
+        // Released: after draining the backlog the rate is nominal again.
+        run(&mut s, 20, u32::MAX);
+        let after = run(&mut s, 100, u32::MAX);
+        assert_eq!(after, before, "no permanent gap after the stall");
+    }
+
+    #[test]
+    fn belt_spacing_sets_length_and_rate() {
+        // Two slots of spacing 0.5 at speed 1: two items enter per tick.
+        let t = Transport::Item(ItemTransport {
+            speed: Fixed64::ONE,
+            slot_count: 6,
+            lanes: 1,
+            min_spacing: Fixed64::from_num(0.5),
+        });
+        let Transport::Item(ref item) = t else {
+            unreachable!()
+        };
+        assert_eq!(item.length(), Fixed64::from_num(3));
+        let mut s = TransportState::new_for(&t);
+        let r = t.advance(&mut s, 10);
+        assert_eq!(r.items_moved, 2);
+        for _ in 0..10 {
+            t.advance(&mut s, 10);
+        }
+        let delivered: u32 = (0..20).map(|_| t.advance(&mut s, 10).items_delivered).sum();
+        assert_eq!(delivered, 40);
+    }
+
+    #[test]
+    fn belt_with_tiny_spacing_does_not_overflow() {
+        let t = Transport::Item(ItemTransport {
+            speed: Fixed64::ONE,
+            slot_count: 4,
+            lanes: 1,
+            min_spacing: Fixed64::DELTA,
+        });
+        let mut s = TransportState::new_for(&t);
+        let mut delivered = 0;
+        for _ in 0..10 {
+            let r = t.advance(&mut s, 10);
+            assert!(r.items_moved <= 4);
+            delivered += r.items_delivered;
+        }
+        assert!(delivered > 0);
+    }
+
+    #[test]
+    fn belt_longer_than_fixed64_range_saturates() {
+        let t = Transport::Item(ItemTransport {
+            speed: Fixed64::from_num(1_000_000),
+            slot_count: 1000,
+            lanes: 1,
+            min_spacing: Fixed64::from_num(10_000_000),
+        });
+        let Transport::Item(ref item) = t else {
+            unreachable!()
+        };
+        assert_eq!(item.length(), Fixed64::MAX);
+        assert_eq!(item.travel(), Fixed64::MAX);
+        let mut s = TransportState::new_for(&t);
+        assert_eq!(t.advance(&mut s, 10).items_moved, 1);
+        for _ in 0..10 {
+            t.advance(&mut s, 10);
+        }
+        let TransportState::Item(ref bs) = s else {
+            panic!("expected BeltState");
+        };
+        let front = bs.lanes[0][0].position;
+        assert!(front > Fixed64::ZERO && front < Fixed64::MAX);
+    }
+
+    #[test]
+    fn slot_array_converts_to_positions() {
+        let item = ItemTransport {
+            speed: Fixed64::ONE,
+            slot_count: 3,
+            lanes: 2,
+            min_spacing: Fixed64::ONE,
+        };
+        let belt = BeltState::from_slots(
+            &[
+                Some(ItemTypeId(1)),
+                None,
+                Some(ItemTypeId(2)),
+                None,
+                Some(ItemTypeId(3)),
+                None,
+            ],
+            &item,
+        );
+        assert_eq!(belt.slot_count, 3);
+        let transport = Transport::Item(item);
+        assert!(TransportState::Item(belt.clone()).fits(&transport));
+        let items: Vec<(u8, ItemTypeId, Fixed64)> = belt
+            .items()
+            .map(|(lane, i)| (lane, i.item_type, i.position))
+            .collect();
+        assert_eq!(
+            items,
+            vec![
+                (0, ItemTypeId(1), Fixed64::from_num(2)),
+                (0, ItemTypeId(2), Fixed64::ZERO),
+                (1, ItemTypeId(3), Fixed64::ONE),
+            ]
+        );
+
+        // The converted belt delivers exactly what the slot model would
+        // have: the lane 0 front item and the lane 1 item now, the last one
+        // next tick.
+        let mut s = TransportState::Item(belt);
+        assert_eq!(transport.advance(&mut s, 0).items_delivered, 2);
+        assert_eq!(transport.advance(&mut s, 0).items_delivered, 1);
+        let TransportState::Item(ref bs) = s else {
+            panic!("expected BeltState");
+        };
+        assert_eq!(bs.occupied_count(), 0);
+    }
+
+    // -----------------------------------------------------------------------
+    // Test 13b: Property manifests release items oldest first
+    // -----------------------------------------------------------------------
+    #[test]
+    fn property_manifest_releases_oldest_first() {
+        let group = |quantity: u32, properties: &BTreeMap<PropertyId, Fixed64>| PropertyGroup {
+            quantity,
+            properties: properties.clone(),
+        };
+        let plain = BTreeMap::new();
+        let fine: BTreeMap<PropertyId, Fixed64> = [(PropertyId(0), Fixed64::from_num(3))].into();
+        let mut manifest = PropertyManifest::default();
+
+        // Items without properties entering an empty manifest are not recorded.
+        manifest.record(2, &plain);
+        assert!(manifest.is_empty());
+        manifest.record(3, &fine);
+        manifest.record(1, &plain);
+        assert_eq!(manifest.quantity(), 4);
+
+        // The two unrecorded items in transit leave first.
+        let mut out = Vec::new();
+        manifest.release(3, 6, &mut out);
+        assert_eq!(out, vec![group(2, &plain), group(1, &fine)]);
+
+        out.clear();
+        manifest.release(3, 3, &mut out);
+        assert_eq!(out, vec![group(2, &fine), group(1, &plain)]);
+        assert!(manifest.is_empty());
+    }
+
     // -----------------------------------------------------------------------
     // Test 14: Mismatched variant panics in debug, returns no-op in release
     // -----------------------------------------------------------------------
@@ -858,6 +1601,7 @@ mod tests {
         let mut s = TransportState::Batch(BatchState {
             progress: 0,
             pending: 0,
+            properties: PropertyManifest::default(),
         });
         t.advance(&mut s, 10);
     }
@@ -873,6 +1617,7 @@ mod tests {
         let mut s = TransportState::Batch(BatchState {
             progress: 0,
             pending: 0,
+            properties: PropertyManifest::default(),
         });
         let result = t.advance(&mut s, 10);
         assert_eq!(
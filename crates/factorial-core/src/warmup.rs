@@ -0,0 +1,244 @@
+//! Warmup and cooldown for processors.
+//!
+//! Furnaces and reactors do not go from cold to full production at once. A
+//! node with a [`Warmup`] runs at reduced speed for its first
+//! `warmup_ticks` ticks of work: on the `n`-th such tick its speed is
+//! multiplied by `n / (warmup_ticks + 1)`, on top of its modifiers. After
+//! that it runs at full speed.
+//!
+//! When a warm machine runs out of inputs it cools down instead of stalling
+//! at once. For up to `cooldown_ticks` ticks it reports `Idle`, keeps its
+//! heat, and emits no `BuildingStalled`; if inputs return within that window
+//! it carries on at the speed it had. Once the window passes it stalls with
+//! `MissingInputs` and goes cold, so the next start warms up again. Any
+//! other stall -- output full, no power, depleted -- cools it at once.
+//!
+//! The timers are part of the node's processor state: they are saved with
+//! the engine and included in the state hash.
+
+use serde::{Deserialize, Serialize};
+
+use crate::engine::Engine;
+use crate::fixed::Fixed64;
+use crate::id::{ModifierId, NodeId};
+use crate::processor::{
+    Modifier, ModifierKind, ProcessorResult, ProcessorState, StackingRule, StallReason,
+};
+use crate::sim::StateHash;
+
+/// Warmup and cooldown settings of one node, with its timers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Warmup {
+    /// Ticks of work at ramped speed before the machine runs at full speed.
+    pub warmup_ticks: u32,
+    /// Ticks a warm machine waits for inputs before it stalls and goes cold.
+    pub cooldown_ticks: u32,
+    /// Ticks of warmup completed, up to `warmup_ticks`.
+    pub heat: u32,
+    /// Consecutive ticks without work since the machine last worked, or
+    /// `None` while it is cold.
+    pub cooling: Option<u32>,
+}
+
+impl Warmup {
+    /// A cold machine with the given warmup and cooldown.
+    pub fn new(warmup_ticks: u32, cooldown_ticks: u32) -> Self {
+        Self {
+            warmup_ticks,
+            cooldown_ticks,
+            heat: 0,
+            cooling: None,
+        }
+    }
+
+    /// Whether the machine has finished warming up.
+    pub fn is_warm(&self) -> bool {
+        self.heat >= self.warmup_ticks
+    }
+
+    /// Speed multiplier for the next tick of work: `(heat + 1) /
+    /// (warmup_ticks + 1)` while warming up, 1 once warm.
+    pub fn efficiency(&self) -> Fixed64 {
+        if self.is_warm() {
+            Fixed64::ONE
+        } else {
+            Fixed64::from_num(self.heat + 1) / Fixed64::from_num(self.warmup_ticks + 1)
+        }
+    }
+
+    /// The speed modifier to apply for the next tick, if the machine is
+    /// still warming up. Its id sorts after every other modifier, so it
+    /// scales the folded speed.
+    pub(crate) fn modifier(&self) -> Option<Modifier> {
+        (!self.is_warm()).then(|| Modifier {
+            id: ModifierId(u32::MAX),
+            kind: ModifierKind::Speed(self.efficiency()),
+            stacking: StackingRule::Multiplicative,
+        })
+    }
+
+    /// Advance the timers after the processor ticked from `prev` into
+    /// `state`. A stall for missing inputs within the cooldown window is
+    /// turned back into `Idle`.
+    pub(crate) fn settle(
+        &mut self,
+        prev: Option<&ProcessorState>,
+        state: &mut ProcessorState,
+        result: &mut ProcessorResult,
+    ) {
+        let worked = matches!(state, ProcessorState::Working { .. })
+            || !result.consumed.is_empty()
+            || !result.produced.is_empty();
+        if worked {
+            self.heat = (self.heat + 1).min(self.warmup_ticks);
+            self.cooling = Some(0);
+            return;
+        }
+        let waiting = matches!(
+            state,
+            ProcessorState::Idle
+                | ProcessorState::Stalled {
+                    reason: StallReason::MissingInputs
+                }
+        );
+        match self.cooling {
+            Some(idle) if waiting && idle < self.cooldown_ticks => {
+                self.cooling = Some(idle + 1);
+                if *state != ProcessorState::Idle {
+                    *state = ProcessorState::Idle;
+                    result.state_changed = prev != Some(&ProcessorState::Idle);
+                }
+            }
+            _ => {
+                self.heat = 0;
+                self.cooling = None;
+            }
+        }
+    }
+}
+
+/// Fold a node's warmup timers into its processor-state hash.
+pub(crate) fn hash_warmup(h: &mut StateHash, warmup: &Warmup) {
+    h.write_u32(warmup.warmup_ticks);
+    h.write_u32(warmup.cooldown_ticks);
+    h.write_u32(warmup.heat);
+    match warmup.cooling {
+        Some(idle) => {
+            h.write_u32(1);
+            h.write_u32(idle);
+        }
+        None => h.write_u32(0),
+    }
+}
+
+impl Engine {
+    /// Give `node` a warmup of `warmup_ticks` and a cooldown of
+    /// `cooldown_ticks`, starting cold. Replaces any previous warmup.
+    /// Returns `false` if the node is not in the graph.
+    pub fn set_warmup(&mut self, node: NodeId, warmup_ticks: u32, cooldown_ticks: u32) -> bool {
+        if !self.graph.contains_node(node) {
+            return false;
+        }
+        self.warmups
+            .insert(node, Warmup::new(warmup_ticks, cooldown_ticks));
+        self.mark_warmup_dirty(node);
+        true
+    }
+
+    /// Remove a node's warmup, so it runs at full speed and stalls at once.
+    /// Returns the removed settings and timers.
+    pub fn clear_warmup(&mut self, node: NodeId) -> Option<Warmup> {
+        let removed = self.warmups.remove(node)?;
+        self.mark_warmup_dirty(node);
+        Some(removed)
+    }
+
+    /// A node's warmup settings and timers, if it has any.
+    pub fn warmup(&self, node: NodeId) -> Option<&Warmup> {
+        self.warmups.get(node)
+    }
+
+    fn mark_warmup_dirty(&mut self, node: NodeId) {
+        self.hash_dirty_nodes.push(node);
+        self.dirty.mark_node(node);
+        self.dirty
+            .mark_partition(crate::dirty::DirtyTracker::PARTITION_PROCESSORS);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn starved() -> ProcessorState {
+        ProcessorState::Stalled {
+            reason: StallReason::MissingInputs,
+        }
+    }
+
+    #[test]
+    fn efficiency_ramps_until_warm() {
+        let mut warmup = Warmup::new(3, 0);
+        let mut state = ProcessorState::Working { progress: 0 };
+        let mut seen = Vec::new();
+        for _ in 0..5 {
+            seen.push(warmup.efficiency());
+            warmup.settle(None, &mut state, &mut ProcessorResult::default());
+        }
+        assert_eq!(
+            seen,
+            vec![
+                Fixed64::from_num(0.25),
+                Fixed64::from_num(0.5),
+                Fixed64::from_num(0.75),
+                Fixed64::ONE,
+                Fixed64::ONE,
+            ]
+        );
+        assert!(warmup.modifier().is_none());
+    }
+
+    #[test]
+    fn cooldown_holds_a_stall_back_then_goes_cold() {
+        let mut warmup = Warmup::new(1, 2);
+        let mut working = ProcessorState::Working { progress: 0 };
+        warmup.settle(None, &mut working, &mut ProcessorResult::default());
+        assert!(warmup.is_warm());
+
+        for idle in 1..=2 {
+            let mut state = starved();
+            let mut result = ProcessorResult {
+                state_changed: true,
+                ..Default::default()
+            };
+            warmup.settle(Some(&ProcessorState::Idle), &mut state, &mut result);
+            assert_eq!(state, ProcessorState::Idle);
+            assert!(!result.state_changed);
+            assert_eq!(warmup.cooling, Some(idle));
+            assert!(warmup.is_warm());
+        }
+
+        let mut state = starved();
+        warmup.settle(
+            Some(&ProcessorState::Idle),
+            &mut state,
+            &mut ProcessorResult::default(),
+        );
+        assert_eq!(state, starved());
+        assert_eq!((warmup.heat, warmup.cooling), (0, None));
+    }
+
+    #[test]
+    fn other_stalls_cool_at_once() {
+        let mut warmup = Warmup::new(1, 10);
+        let mut working = ProcessorState::Working { progress: 0 };
+        warmup.settle(None, &mut working, &mut ProcessorResult::default());
+
+        let mut state = ProcessorState::Stalled {
+            reason: StallReason::OutputFull,
+        };
+        warmup.settle(None, &mut state, &mut ProcessorResult::default());
+        assert!(matches!(state, ProcessorState::Stalled { .. }));
+        assert_eq!((warmup.heat, warmup.cooling), (0, None));
+    }
+}
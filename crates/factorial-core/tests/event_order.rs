@@ -0,0 +1,302 @@
+//! Conformance test for the order of events within one tick.
+//!
+//! Hosts rely on this order (for example, that a recipe's `ItemConsumed`
+//! arrives before its `RecipeStarted`), so it is pinned here. The scenario
+//! makes every event kind a normal step can emit happen in the same tick and
+//! asserts both orders the engine exposes:
+//!
+//! - **emission order**, seen by the event log and the event history;
+//! - **delivery order**, seen by subscribers and therefore by the FFI event
+//!   cache: grouped by `EventKind` in declaration order, emission order
+//!   within each kind.
+//!
+//! Changing either order on purpose means editing the expected lists below.
+//! See the "Event order within a tick" section of the events chapter.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use factorial_core::engine::Engine;
+use factorial_core::event::{Event, EventKind};
+use factorial_core::fixed::Fixed64;
+use factorial_core::id::*;
+use factorial_core::processor::*;
+use factorial_core::registry::{RecipeDef, RecipeEntry};
+use factorial_core::sim::{SimulationStrategy, StepPhase};
+use factorial_core::test_utils::*;
+
+/// Every event kind, in declaration order.
+const ALL_KINDS: [EventKind; 19] = [
+    EventKind::ItemProduced,
+    EventKind::ItemConsumed,
+    EventKind::RecipeStarted,
+    EventKind::RecipeCompleted,
+    EventKind::BuildingStalled,
+    EventKind::BuildingResumed,
+    EventKind::ItemDelivered,
+    EventKind::TransportFull,
+    EventKind::NodeAdded,
+    EventKind::NodeRemoved,
+    EventKind::EdgeAdded,
+    EventKind::EdgeRemoved,
+    EventKind::RecipeSwitched,
+    EventKind::CraftStarted,
+    EventKind::CraftCompleted,
+    EventKind::ItemSpoiled,
+    EventKind::InventoryFull,
+    EventKind::InventorySpaceAvailable,
+    EventKind::StepTruncated,
+];
+
+/// Names for the nodes and edges of a scenario, so events read as
+/// `(kind, "smelter")`.
+#[derive(Default)]
+struct Names {
+    nodes: HashMap<NodeId, &'static str>,
+    edges: HashMap<EdgeId, &'static str>,
+}
+
+impl Names {
+    fn label(&self, event: &Event) -> (EventKind, &'static str) {
+        let name = match (event.node(), event.edge()) {
+            (Some(node), _) => self.nodes.get(&node).copied().unwrap_or("?"),
+            (None, Some(edge)) => self.edges.get(&edge).copied().unwrap_or("?"),
+            (None, None) => "-",
+        };
+        (event.kind(), name)
+    }
+
+    fn labels(&self, events: &[Event]) -> Vec<(EventKind, &'static str)> {
+        events.iter().map(|e| self.label(e)).collect()
+    }
+}
+
+/// Record every delivered event, across all kinds, in delivery order.
+fn record_deliveries(engine: &mut Engine) -> Arc<Mutex<Vec<Event>>> {
+    let delivered = Arc::new(Mutex::new(Vec::new()));
+    for kind in ALL_KINDS {
+        let delivered = Arc::clone(&delivered);
+        engine.on_passive(
+            kind,
+            Box::new(move |event: &Event| delivered.lock().unwrap().push(event.clone())),
+        );
+    }
+    delivered
+}
+
+fn fixed_recipe(input: ItemTypeId, output: ItemTypeId, duration: u32) -> FixedRecipe {
+    match make_recipe(vec![(input, 1)], vec![(output, 1)], duration) {
+        Processor::Fixed(recipe) => recipe,
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn every_event_kind_in_one_tick_arrives_in_the_canonical_order() {
+    use EventKind::*;
+
+    let mut engine = Engine::new(SimulationStrategy::Tick);
+    let mut names = Names::default();
+    let idle = || make_source(coal(), 0.0);
+
+    // A mine feeding a smelter whose one-item output fills at tick 2 and
+    // stalls it at tick 3.
+    let mine = add_node(&mut engine, make_source(iron(), 1.0), 100, 100);
+    let smelter = add_node(
+        &mut engine,
+        make_recipe(vec![(iron(), 1)], vec![(gear(), 1)], 2),
+        100,
+        1,
+    );
+    let feed = connect(&mut engine, mine, smelter, make_flow_transport(5.0));
+    // A belt into a node with no room, which backs up.
+    let jam = add_node(&mut engine, make_source(copper(), 1.0), 100, 100);
+    let wall = add_node(&mut engine, idle(), 0, 100);
+    let jammed = connect(&mut engine, jam, wall, make_item_transport(1));
+    // A full one-item chest whose only item spoils at tick 3.
+    let vault = add_node(&mut engine, idle(), 1, 1);
+    engine.set_item_decay(wood(), 3, None);
+    let _ = engine
+        .get_input_inventory_mut(vault)
+        .unwrap()
+        .insert(wood(), 1);
+    // Removed, with its edge, at the start of tick 3.
+    let doomed = add_node(&mut engine, idle(), 1, 1);
+    let doomed_feed = connect(&mut engine, mine, doomed, make_flow_transport(1.0));
+    // Stalled for want of stone until the host supplies some for tick 3.
+    let starved = add_node(
+        &mut engine,
+        make_recipe(vec![(stone(), 1)], vec![(gear(), 1)], 2),
+        100,
+        100,
+    );
+    // A hand-crafting queue that gets a one-tick craft for tick 3.
+    let player = add_node(&mut engine, idle(), 10, 10);
+    let queue = engine.create_crafting_queue(player, Fixed64::ONE).unwrap();
+    let _ = engine
+        .get_input_inventory_mut(player)
+        .unwrap()
+        .insert(copper(), 5);
+    // Starts a cycle at tick 2 and finishes it, filling its output, at tick 3.
+    let press = add_node(
+        &mut engine,
+        make_recipe(vec![(stone(), 1)], vec![(gear(), 1)], 2),
+        100,
+        1,
+    );
+    // A multi-recipe building the host switches before tick 3.
+    let selector = add_node(
+        &mut engine,
+        Processor::MultiRecipe(MultiRecipeProcessor {
+            recipes: vec![
+                fixed_recipe(sand(), glass(), 5),
+                fixed_recipe(wood(), wood_plank(), 5),
+            ],
+            active_recipe: 0,
+            switch_policy: RecipeSwitchPolicy::default(),
+            pending_switch: None,
+            in_progress_inputs: Vec::new(),
+        }),
+        10,
+        10,
+    );
+
+    for (node, name) in [
+        (mine, "mine"),
+        (smelter, "smelter"),
+        (jam, "jam"),
+        (wall, "wall"),
+        (vault, "vault"),
+        (doomed, "doomed"),
+        (starved, "starved"),
+        (player, "player"),
+        (press, "press"),
+        (selector, "selector"),
+    ] {
+        names.nodes.insert(node, name);
+    }
+    for (edge, name) in [
+        (feed, "feed"),
+        (jammed, "jammed"),
+        (doomed_feed, "doomed feed"),
+    ] {
+        names.edges.insert(edge, name);
+    }
+
+    let delivered = record_deliveries(&mut engine);
+    engine.enable_event_log();
+    engine.step();
+    engine.step();
+    let _ = engine
+        .get_input_inventory_mut(press)
+        .unwrap()
+        .insert(stone(), 1);
+    engine.step();
+    engine.take_event_log();
+    delivered.lock().unwrap().clear();
+
+    // Host calls between steps: they emit at call time or during the next
+    // pre-tick, ahead of everything the simulation phases emit.
+    engine.set_active_recipe(selector, 1).unwrap();
+    engine.graph.queue_remove_node(doomed);
+    engine.graph.queue_add_node(building());
+    engine.graph.queue_connect(jam, press);
+    let _ = engine
+        .get_input_inventory_mut(starved)
+        .unwrap()
+        .insert(stone(), 1);
+    let plate = RecipeEntry {
+        item: copper(),
+        quantity: 1,
+        consumed: true,
+    };
+    let def = RecipeDef {
+        name: "plate".to_string(),
+        inputs: vec![plate.clone()],
+        outputs: vec![plate],
+        duration: 1,
+    };
+    engine.queue_craft_def(queue, RecipeId(0), &def, 1).unwrap();
+    engine.step();
+
+    let log = engine.take_event_log().unwrap();
+    let emitted: Vec<Event> = log.events().cloned().collect();
+    assert!(emitted.iter().all(|e| e.tick() == 3));
+    if let Some(Event::NodeAdded { node, .. }) = emitted.iter().find(|e| e.kind() == NodeAdded) {
+        names.nodes.insert(*node, "added");
+    }
+    if let Some(Event::EdgeAdded { edge, .. }) = emitted.iter().find(|e| e.kind() == EdgeAdded) {
+        names.edges.insert(*edge, "link");
+    }
+
+    let expected_emission = [
+        // Between steps, in call order.
+        (RecipeSwitched, "selector"),
+        // Pre-tick: removals before additions, nodes before edges.
+        (NodeRemoved, "doomed"),
+        (EdgeRemoved, "doomed feed"),
+        (NodeAdded, "added"),
+        (EdgeAdded, "link"),
+        // Transport, edge by edge.
+        (ItemDelivered, "feed"),
+        (TransportFull, "jammed"),
+        // Process, node by node in topological order (the smelter and the
+        // newly linked press are downstream): consumed, produced, resumed,
+        // then one of started / completed / stalled.
+        (ItemProduced, "mine"),
+        (ItemProduced, "jam"),
+        (ItemConsumed, "starved"),
+        (BuildingResumed, "starved"),
+        (RecipeStarted, "starved"),
+        (BuildingStalled, "smelter"),
+        (ItemProduced, "press"),
+        (RecipeCompleted, "press"),
+        // Component: crafting, spoilage, then capacity transitions.
+        (CraftStarted, "player"),
+        (CraftCompleted, "player"),
+        (ItemSpoiled, "vault"),
+        (InventorySpaceAvailable, "vault"),
+        (InventoryFull, "press"),
+    ];
+    assert_eq!(names.labels(&emitted), expected_emission);
+
+    // Every kind a normal step emits is covered; `StepTruncated` has its own
+    // test below.
+    for kind in ALL_KINDS {
+        assert_eq!(
+            emitted.iter().any(|e| e.kind() == kind),
+            kind != StepTruncated,
+            "{kind:?}"
+        );
+    }
+
+    // Delivery groups by kind in declaration order and keeps emission order
+    // within a kind.
+    let mut expected_delivery = expected_emission.to_vec();
+    expected_delivery.sort_by_key(|&(kind, _)| kind as usize);
+    assert_eq!(names.labels(&delivered.lock().unwrap()), expected_delivery);
+}
+
+#[test]
+fn step_truncated_is_the_last_event_of_its_tick() {
+    let mut engine = Engine::new(SimulationStrategy::Tick);
+    for _ in 0..4 {
+        add_node(&mut engine, make_source(iron(), 1.0), 10, 10);
+    }
+    engine.set_step_work_limit(Some(2));
+    engine.enable_event_log();
+    engine.step();
+
+    let log = engine.take_event_log().unwrap();
+    let emitted: Vec<Event> = log.events().cloned().collect();
+    let (last, rest) = emitted.split_last().unwrap();
+    assert!(matches!(
+        last,
+        Event::StepTruncated {
+            phase: StepPhase::Process,
+            ..
+        }
+    ));
+    assert!(!rest.is_empty());
+    assert!(rest.iter().all(|e| e.kind() != EventKind::StepTruncated));
+}
@@ -0,0 +1,418 @@
+//! State-machine fuzzing of graph mutation, stepping, and serialization.
+//!
+//! Generates random interleavings of queued node/edge additions and
+//! removals, mutation application, steps, and serialize/deserialize round
+//! trips, and checks the engine against a simple model after every
+//! operation. proptest shrinks any failure to a minimal sequence.
+//!
+//! The smoke version runs with the normal test suite. The long version is
+//! gated behind the `fuzz-long` feature:
+//!   cargo test --package factorial-core --features fuzz-long --test fuzz_state_machine
+
+use std::cell::Cell;
+use std::collections::BTreeSet;
+use std::rc::Rc;
+
+use factorial_core::engine::Engine;
+use factorial_core::event::{Event, EventKind};
+use factorial_core::graph::{MutationRejectReason, MutationResult};
+use factorial_core::id::*;
+use factorial_core::processor::Processor;
+use factorial_core::sim::SimulationStrategy;
+use factorial_core::test_utils::*;
+use proptest::prelude::*;
+
+// ===========================================================================
+// Operations
+// ===========================================================================
+
+/// The kinds of building the fuzzer places.
+#[derive(Debug, Clone, Copy)]
+enum NodeKind {
+    Source,
+    Passthrough,
+    Recipe,
+}
+
+impl NodeKind {
+    fn processor(self) -> Processor {
+        match self {
+            NodeKind::Source => make_source(iron(), 1.0),
+            NodeKind::Passthrough => Processor::Passthrough,
+            NodeKind::Recipe => make_recipe(vec![(iron(), 2)], vec![(gear(), 1)], 3),
+        }
+    }
+}
+
+/// One step of a generated sequence. Node and edge indices pick from every
+/// id the model has ever seen, so removed (stale) ids are exercised too.
+#[derive(Debug, Clone)]
+enum Op {
+    QueueAddNode(NodeKind),
+    QueueRemoveNode(usize),
+    QueueConnect(usize, usize),
+    QueueDisconnect(usize),
+    ApplyMutations,
+    Step,
+    RoundTrip,
+}
+
+fn arb_op() -> impl Strategy<Value = Op> {
+    prop_oneof![
+        3 => prop_oneof![
+            Just(NodeKind::Source),
+            Just(NodeKind::Passthrough),
+            Just(NodeKind::Recipe),
+        ]
+        .prop_map(Op::QueueAddNode),
+        1 => any::<usize>().prop_map(Op::QueueRemoveNode),
+        3 => (any::<usize>(), any::<usize>()).prop_map(|(a, b)| Op::QueueConnect(a, b)),
+        1 => any::<usize>().prop_map(Op::QueueDisconnect),
+        2 => Just(Op::ApplyMutations),
+        3 => Just(Op::Step),
+        1 => Just(Op::RoundTrip),
+    ]
+}
+
+fn arb_ops(max_ops: usize) -> impl Strategy<Value = Vec<Op>> {
+    proptest::collection::vec(arb_op(), 1..=max_ops)
+}
+
+// ===========================================================================
+// Model
+// ===========================================================================
+
+/// A mutation as the model queued it.
+#[derive(Debug)]
+enum Queued {
+    AddNode(PendingNodeId, NodeKind),
+    RemoveNode(NodeId),
+    Connect(PendingEdgeId, NodeId, NodeId),
+    Disconnect(EdgeId),
+}
+
+/// The engine under test plus what the model expects of it.
+struct Harness {
+    engine: Engine,
+    produced: Rc<Cell<u64>>,
+    consumed: Rc<Cell<u64>>,
+    queued: Vec<Queued>,
+    /// Every node id ever created, live or not.
+    known_nodes: Vec<NodeId>,
+    /// Every edge id ever created, live or not.
+    known_edges: Vec<EdgeId>,
+    live_nodes: BTreeSet<NodeId>,
+    /// Live edges with their endpoints.
+    live_edges: Vec<(EdgeId, NodeId, NodeId)>,
+    /// Items the engine should hold across inventories and transports.
+    expected_items: u64,
+}
+
+impl Harness {
+    fn new() -> Self {
+        let mut harness = Self {
+            engine: Engine::new(SimulationStrategy::Tick),
+            produced: Rc::new(Cell::new(0)),
+            consumed: Rc::new(Cell::new(0)),
+            queued: Vec::new(),
+            known_nodes: Vec::new(),
+            known_edges: Vec::new(),
+            live_nodes: BTreeSet::new(),
+            live_edges: Vec::new(),
+            expected_items: 0,
+        };
+        harness.listen();
+        harness
+    }
+
+    /// Count produced and consumed items. Listeners are session state, so
+    /// this is repeated after every deserialize.
+    fn listen(&mut self) {
+        let produced = self.produced.clone();
+        self.engine.on_passive(
+            EventKind::ItemProduced,
+            Box::new(move |event| {
+                if let Event::ItemProduced { quantity, .. } = event {
+                    produced.set(produced.get() + u64::from(*quantity));
+                }
+            }),
+        );
+        let consumed = self.consumed.clone();
+        self.engine.on_passive(
+            EventKind::ItemConsumed,
+            Box::new(move |event| {
+                if let Event::ItemConsumed { quantity, .. } = event {
+                    consumed.set(consumed.get() + u64::from(*quantity));
+                }
+            }),
+        );
+    }
+
+    fn node_items(&self, node: NodeId) -> u64 {
+        let input = self
+            .engine
+            .get_input_inventory(node)
+            .map_or(0, |i| i.total());
+        let output = self
+            .engine
+            .get_output_inventory(node)
+            .map_or(0, |i| i.total());
+        u64::from(input) + u64::from(output)
+    }
+
+    fn edge_items(&self, edge: EdgeId) -> u64 {
+        match self.engine.get_transport_state(edge) {
+            Some(factorial_core::transport::TransportState::Item(belt)) => {
+                belt.occupied_count() as u64
+            }
+            _ => 0,
+        }
+    }
+
+    fn total_items(&self) -> u64 {
+        let nodes: u64 = self.live_nodes.iter().map(|&n| self.node_items(n)).sum();
+        let edges: u64 = self
+            .live_edges
+            .iter()
+            .map(|&(e, _, _)| self.edge_items(e))
+            .sum();
+        nodes + edges
+    }
+
+    fn run(&mut self, op: &Op) -> Result<(), TestCaseError> {
+        match *op {
+            Op::QueueAddNode(kind) => {
+                let pending = self.engine.graph.queue_add_node(building());
+                self.queued.push(Queued::AddNode(pending, kind));
+            }
+            Op::QueueRemoveNode(i) => {
+                if let Some(node) = pick(&self.known_nodes, i) {
+                    self.engine.graph.queue_remove_node(node);
+                    self.queued.push(Queued::RemoveNode(node));
+                }
+            }
+            Op::QueueConnect(a, b) => {
+                if let (Some(from), Some(to)) =
+                    (pick(&self.known_nodes, a), pick(&self.known_nodes, b))
+                {
+                    let pending = self.engine.graph.queue_connect(from, to);
+                    self.queued.push(Queued::Connect(pending, from, to));
+                }
+            }
+            Op::QueueDisconnect(i) => {
+                if let Some(edge) = pick(&self.known_edges, i) {
+                    self.engine.graph.queue_disconnect(edge);
+                    self.queued.push(Queued::Disconnect(edge));
+                }
+            }
+            Op::ApplyMutations => self.apply()?,
+            Op::Step => {
+                // Apply explicitly so the model can configure new nodes
+                // before the step's own pre-tick phase would.
+                self.apply()?;
+                let (produced, consumed) = (self.produced.get(), self.consumed.get());
+                self.engine.step();
+                self.expected_items += self.produced.get() - produced;
+                self.expected_items -= self.consumed.get() - consumed;
+            }
+            Op::RoundTrip => {
+                // Queued mutations are session state and not serialized.
+                self.apply()?;
+                let hash = self.engine.state_hash();
+                let data = self.engine.serialize().expect("serialize should succeed");
+                self.engine = Engine::deserialize(&data).expect("deserialize should succeed");
+                self.listen();
+                prop_assert_eq!(
+                    self.engine.state_hash(),
+                    hash,
+                    "hash changed across round trip"
+                );
+            }
+        }
+        self.check()
+    }
+
+    /// Apply the queue, checking the result against the model's replay.
+    fn apply(&mut self) -> Result<(), TestCaseError> {
+        let queued = std::mem::take(&mut self.queued);
+        let held_nodes: Vec<(NodeId, u64)> = self
+            .live_nodes
+            .iter()
+            .map(|&n| (n, self.node_items(n)))
+            .collect();
+        let held_edges: Vec<(EdgeId, u64)> = self
+            .live_edges
+            .iter()
+            .map(|&(e, _, _)| (e, self.edge_items(e)))
+            .collect();
+
+        let result = self.engine.apply_mutations();
+
+        // Replay the batch against the model in queue order.
+        let mut rejected = Vec::new();
+        for mutation in &queued {
+            match *mutation {
+                Queued::AddNode(pending, kind) => {
+                    let node = result.resolve_node(pending);
+                    prop_assert!(node.is_some(), "node addition {:?} was dropped", pending);
+                    let node = node.unwrap();
+                    self.engine.set_processor(node, kind.processor());
+                    self.engine.set_input_inventory(node, simple_inventory(20));
+                    self.engine.set_output_inventory(node, simple_inventory(20));
+                    self.known_nodes.push(node);
+                    self.live_nodes.insert(node);
+                }
+                Queued::RemoveNode(node) => {
+                    if self.live_nodes.remove(&node) {
+                        self.expected_items -= lookup(&held_nodes, node);
+                        let (dead, live) = self
+                            .live_edges
+                            .iter()
+                            .partition(|&&(_, from, to)| from == node || to == node);
+                        let dead: Vec<_> = dead;
+                        self.live_edges = live;
+                        for (edge, _, _) in dead {
+                            self.expected_items -= lookup(&held_edges, edge);
+                        }
+                    }
+                }
+                Queued::Connect(pending, from, to) => {
+                    if self.live_nodes.contains(&from) && self.live_nodes.contains(&to) {
+                        let edge = result.resolve_edge(pending);
+                        prop_assert!(edge.is_some(), "connection {:?} was dropped", pending);
+                        let edge = edge.unwrap();
+                        self.engine.set_transport(edge, make_item_transport(3));
+                        self.known_edges.push(edge);
+                        self.live_edges.push((edge, from, to));
+                    } else {
+                        rejected.push((pending, MutationRejectReason::MissingEndpoint));
+                    }
+                }
+                Queued::Disconnect(edge) => {
+                    if let Some(i) = self.live_edges.iter().position(|&(e, _, _)| e == edge) {
+                        self.live_edges.remove(i);
+                        self.expected_items -= lookup(&held_edges, edge);
+                    }
+                }
+            }
+        }
+        prop_assert_eq!(&result.rejected_edges, &rejected);
+        check_removed(&result, &queued)
+    }
+
+    /// Check every invariant against the current engine.
+    fn check(&mut self) -> Result<(), TestCaseError> {
+        self.check_graph()?;
+        let live = self.live_nodes.len();
+        let (order, _) = self.engine.graph.topological_order_with_feedback();
+        prop_assert_eq!(order.len(), live);
+        prop_assert_eq!(
+            self.total_items(),
+            self.expected_items,
+            "items not conserved"
+        );
+        Ok(())
+    }
+
+    /// Counts, endpoints, adjacency, and per-entity state.
+    fn check_graph(&self) -> Result<(), TestCaseError> {
+        let graph = &self.engine.graph;
+        prop_assert_eq!(graph.node_count(), self.live_nodes.len());
+        prop_assert_eq!(graph.edge_count(), self.live_edges.len());
+
+        // No dangling edges, and adjacency agrees with the edge table.
+        for (edge, data) in graph.edges() {
+            prop_assert!(
+                graph.contains_node(data.from),
+                "edge {:?} has a dangling source",
+                edge
+            );
+            prop_assert!(
+                graph.contains_node(data.to),
+                "edge {:?} has a dangling target",
+                edge
+            );
+            prop_assert!(graph.get_outputs(data.from).contains(&edge));
+            prop_assert!(graph.get_inputs(data.to).contains(&edge));
+        }
+        for &node in &self.live_nodes {
+            for &edge in graph.get_inputs(node).iter().chain(graph.get_outputs(node)) {
+                prop_assert!(
+                    graph.contains_edge(edge),
+                    "node {:?} lists dead edge {:?}",
+                    node,
+                    edge
+                );
+            }
+        }
+
+        // Removed entities leave no per-entity state behind.
+        for &node in &self.known_nodes {
+            if !self.live_nodes.contains(&node) {
+                prop_assert!(self.engine.get_processor(node).is_none());
+                prop_assert!(self.engine.get_input_inventory(node).is_none());
+            }
+        }
+        for &edge in &self.known_edges {
+            if !graph.contains_edge(edge) {
+                prop_assert!(self.engine.get_transport_state(edge).is_none());
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Every removed node and edge must have been live and named by the batch.
+fn check_removed(result: &MutationResult, queued: &[Queued]) -> Result<(), TestCaseError> {
+    for node in &result.removed_nodes {
+        prop_assert!(
+            queued
+                .iter()
+                .any(|q| matches!(q, Queued::RemoveNode(n) if n == node)),
+            "node {:?} removed without being queued",
+            node
+        );
+    }
+    Ok(())
+}
+
+fn pick<T: Copy>(ids: &[T], index: usize) -> Option<T> {
+    (!ids.is_empty()).then(|| ids[index % ids.len()])
+}
+
+fn lookup<T: PartialEq>(held: &[(T, u64)], id: T) -> u64 {
+    held.iter().find(|(k, _)| *k == id).map_or(0, |&(_, n)| n)
+}
+
+fn run_sequence(ops: &[Op]) -> Result<(), TestCaseError> {
+    let mut harness = Harness::new();
+    for op in ops {
+        harness.run(op)?;
+    }
+    Ok(())
+}
+
+// ===========================================================================
+// Properties
+// ===========================================================================
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(64))]
+
+    /// Short smoke run for the normal test suite.
+    #[test]
+    fn mutation_step_state_machine_smoke(ops in arb_ops(60)) {
+        run_sequence(&ops)?;
+    }
+}
+
+#[cfg(feature = "fuzz-long")]
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(4096))]
+
+    /// Long run; enable with `--features fuzz-long`.
+    #[test]
+    fn mutation_step_state_machine_long(ops in arb_ops(400)) {
+        run_sequence(&ops)?;
+    }
+}
@@ -203,6 +203,7 @@ fn belt_with_inserters() {
             speed: fixed(1.0),
             slot_count: 5,
             lanes: 1,
+            min_spacing: Fixed64::ONE,
         }),
     );
 
@@ -228,10 +229,9 @@ fn belt_with_inserters() {
     if let TransportState::Item(bs) = belt_state {
         // The belt state should be accessible and valid (slot count matches config).
         assert_eq!(
-            bs.slots.len(),
-            5,
+            bs.slot_count, 5,
             "belt should have 5 slots, got {}",
-            bs.slots.len()
+            bs.slot_count
         );
         // After 20 ticks with speed 1 and continuous input, belt should have
         // items flowing through it (some slots occupied).
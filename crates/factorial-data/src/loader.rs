@@ -515,9 +515,13 @@ fn resolve_processor(
                         item_type: e.item,
                         quantity: e.quantity,
                         bonus: None,
+                        probability: None,
                     })
                     .collect(),
                 duration: recipe_def.duration as u32,
+                fluid_inputs: Vec::new(),
+                fluid_outputs: Vec::new(),
+                duration_per_unit: None,
             }))
         }
         ProcessorData::Demand { items } => {
@@ -547,6 +551,13 @@ fn resolve_processor(
             }))
         }
         ProcessorData::Passthrough => Ok(Processor::Passthrough),
+        ProcessorData::Storage {
+            usable_slots,
+            read_signal,
+        } => Ok(Processor::Storage(StorageProcessor {
+            usable_slots: *usable_slots,
+            read_signal: *read_signal,
+        })),
         ProcessorData::MultiRecipe {
             recipes: recipe_list,
             default_recipe,
@@ -585,9 +596,13 @@ fn resolve_processor(
                             item_type: e.item,
                             quantity: e.quantity,
                             bonus: None,
+                            probability: None,
                         })
                         .collect(),
                     duration: recipe_def.duration as u32,
+                    fluid_inputs: Vec::new(),
+                    fluid_outputs: Vec::new(),
+                    duration_per_unit: None,
                 });
             }
 
@@ -135,6 +135,12 @@ pub enum ProcessorData {
         items: Vec<String>,
     },
     Passthrough,
+    Storage {
+        #[serde(default)]
+        usable_slots: Option<u32>,
+        #[serde(default)]
+        read_signal: bool,
+    },
     MultiRecipe {
         recipes: Vec<String>,
         #[serde(default)]
@@ -446,6 +452,24 @@ mod tests {
         assert_eq!(building.inventories.input_capacity, 50);
     }
 
+    #[test]
+    fn storage_building_from_ron() {
+        let ron = r#"
+            (
+                name: "chest",
+                processor: Storage(usable_slots: Some(4)),
+            )
+        "#;
+        let building: BuildingData = ron::from_str(ron).unwrap();
+        assert!(matches!(
+            building.processor,
+            ProcessorData::Storage {
+                usable_slots: Some(4),
+                read_signal: false,
+            }
+        ));
+    }
+
     #[test]
     fn building_data_defaults_from_ron() {
         let ron = r#"
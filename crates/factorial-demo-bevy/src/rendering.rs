@@ -209,6 +209,7 @@ fn state_color(state: &ProcessorState) -> Color {
         ProcessorState::Idle => Color::srgb(0.4, 0.4, 0.4),
         ProcessorState::Working { .. } => Color::srgb(0.2, 0.7, 0.2),
         ProcessorState::Stalled { .. } => Color::srgb(0.8, 0.2, 0.2),
+        ProcessorState::Storing => Color::srgb(0.6, 0.5, 0.3),
     }
 }
 
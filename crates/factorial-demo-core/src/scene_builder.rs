@@ -553,6 +553,7 @@ pub fn build_scene(scene_dir: &Path) -> Result<ActiveScene, DemoError> {
                         left: SignalSelector::Signal(signal_id),
                         op,
                         right: SignalSelector::Constant(Fixed64::from_num(cond_data.value)),
+                        epsilon: Fixed64::ZERO,
                     };
                     bridge
                         .logic_mut()
@@ -679,6 +680,7 @@ fn convert_transport(data: &TransportData) -> (Transport, String) {
                 speed: Fixed64::from_num(*speed),
                 slot_count: *slot_count,
                 lanes: *lanes,
+                min_spacing: Fixed64::ONE,
             }),
             "item".to_string(),
         ),
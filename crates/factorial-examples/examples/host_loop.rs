@@ -0,0 +1,60 @@
+//! Scripted host loop: core, fluid, tech tree, and stats driven together.
+//!
+//! Runs the 10,000-tick session from `factorial_examples::host_loop`, with
+//! mid-run construction, research completions, a fluid brownout, a
+//! demolition, and two save/load cycles, then prints a session report.
+//! See the module docs for the per-frame call ordering between modules.
+//!
+//! Run with: `cargo run -p factorial-examples --example host_loop`
+
+use factorial_examples::host_loop::{self, ORE, PLATE, SCIENCE, STEAM, WATER};
+
+fn main() {
+    let host = host_loop::run_session(host_loop::SESSION_TICKS);
+    let ledger = host.ledger();
+
+    println!("=== Host Loop Session ===");
+    println!("ticks simulated: {}", host.engine.sim_state.tick);
+    println!("state hash: {:#018x}", host.engine.state_hash());
+
+    println!("\n--- Items ---");
+    let held = host.items_on_hand();
+    for (name, item) in [
+        ("ore", ORE),
+        ("plate", PLATE),
+        ("science", SCIENCE),
+        ("water", WATER),
+        ("steam", STEAM),
+    ] {
+        println!(
+            "{name:>8}: produced {:>6}, consumed {:>6}, held {:>6}, rate {:.3}/tick",
+            ledger.produced.get(&item).copied().unwrap_or(0)
+                + ledger.bridged.get(&item).copied().unwrap_or(0),
+            ledger.consumed.get(&item).copied().unwrap_or(0),
+            held.get(&item).copied().unwrap_or(0),
+            host.stats
+                .borrow()
+                .get_total_production(item)
+                .to_num::<f64>(),
+        );
+    }
+
+    println!("\n--- Research ---");
+    for (tech, tick) in &ledger.research {
+        let name = &host.tech.get_technology(*tech).unwrap().name;
+        println!("{name} completed at tick {tick}");
+    }
+
+    println!("\n--- Fluid ---");
+    for event in &ledger.fluid_events {
+        println!("{event:?}");
+    }
+
+    println!("\n--- Save/load ---");
+    for (before, after) in &ledger.save_hashes {
+        println!("{before:#018x} -> {after:#018x}");
+    }
+
+    println!("\nremoved nodes: {}", ledger.removed_nodes.len());
+    println!("dangling references: {}", host.dangling_references().len());
+}
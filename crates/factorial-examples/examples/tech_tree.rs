@@ -76,12 +76,14 @@ fn main() {
     // Contribute points in batches.
     let consumed = tree
         .contribute_points(basic_smelting_id, 60, 1)
-        .expect("contribute 60 points");
+        .expect("contribute 60 points")
+        .consumed;
     println!("Contributed {} points (60 offered).", consumed);
 
     let consumed = tree
         .contribute_points(basic_smelting_id, 60, 2)
-        .expect("contribute remaining points");
+        .expect("contribute remaining points")
+        .consumed;
     println!("Contributed {} points (60 offered).", consumed);
 
     assert!(tree.is_completed(basic_smelting_id));
@@ -114,7 +116,8 @@ fn main() {
 
     let consumed = tree
         .contribute_points(advanced_smelting_id, 200, 4)
-        .expect("contribute all points");
+        .expect("contribute all points")
+        .consumed;
     println!("Contributed {} points. Completed!", consumed);
 
     let events = tree.drain_events();
@@ -0,0 +1,557 @@
+//! A scripted host loop wiring core, fluid, tech-tree, and stats together the
+//! way a game host would.
+//!
+//! [`Host::frame`] documents the intended call ordering between the modules;
+//! [`run_session`] drives a 10,000-tick session with mid-run construction,
+//! research-driven construction, a fluid brownout, a demolition, and two
+//! save/load cycles. The [`Ledger`] keeps the host-side bookkeeping that the
+//! session invariants are checked against.
+//!
+//! Per-frame call order:
+//!
+//! 1. Apply this frame's construction: queue graph mutations, call
+//!    `Engine::apply_mutations`, then configure the new nodes and edges
+//!    (processors, inventories, transports) before stepping.
+//! 2. Step the engine. Its tick hooks do the per-tick module work: a
+//!    [`TickPhase::Pre`] hook ticks the fluid module and applies the fluid
+//!    bridge, so consumed fluid is in the node inventories before transport,
+//!    and a [`TickPhase::PostStep`] hook closes the stats tick. Passive
+//!    listeners feed stats as events are delivered, which happens before
+//!    post-step hooks run.
+//! 3. Route the captured events to the fluid module and to research.
+//! 4. Drain tech-tree events and react to completions (unlocks queue
+//!    construction for the next frame).
+//!
+//! Saving serializes the engine, fluid module, and tech tree. Loading must
+//! re-register passive listeners and tick hooks on the new engine before its
+//! first step; host-owned stats simply carry on.
+
+use std::cell::RefCell;
+use std::collections::{BTreeMap, VecDeque};
+use std::rc::Rc;
+
+use factorial_core::builder::{FactoryBuilder, belt_transport, source_processor};
+use factorial_core::engine::Engine;
+use factorial_core::event::{Event, EventKind};
+use factorial_core::fixed::{Fixed64, Ticks};
+use factorial_core::hook::TickPhase;
+use factorial_core::id::{BuildingTypeId, EdgeId, ItemTypeId, NodeId};
+use factorial_core::item::Inventory;
+use factorial_fluid::{FluidBridge, FluidConsumer, FluidEvent, FluidModule, FluidProducer};
+use factorial_stats::{ProductionStats, StatsConfig};
+use factorial_tech_tree::{ResearchCost, TechEvent, TechId, TechTree, Technology, Unlock};
+
+/// Length of the scripted session.
+pub const SESSION_TICKS: Ticks = 10_000;
+
+/// Iron ore mined by the mines.
+pub const ORE: ItemTypeId = ItemTypeId(0);
+/// Plates smelted from ore.
+pub const PLATE: ItemTypeId = ItemTypeId(1);
+/// Science packs assembled from plates and consumed by the lab.
+pub const SCIENCE: ItemTypeId = ItemTypeId(2);
+/// Water delivered into the boiler by the fluid bridge.
+pub const WATER: ItemTypeId = ItemTypeId(3);
+/// Steam boiled from water.
+pub const STEAM: ItemTypeId = ItemTypeId(4);
+
+/// Building unlocked by the first technology.
+pub const MINE: BuildingTypeId = BuildingTypeId(1);
+/// Fluid-hungry building placed to cause a brownout.
+pub const HEATER: BuildingTypeId = BuildingTypeId(2);
+
+/// First technology: unlocks a second mine.
+pub const AUTOMATION: TechId = TechId(0);
+/// Second technology, requires [`AUTOMATION`].
+pub const STEEL: TechId = TechId(1);
+
+/// Tick at which the heater is built (fluid demand exceeds supply).
+pub const BROWNOUT_START: Ticks = 2_500;
+/// Tick at which the heater is demolished again.
+pub const BROWNOUT_END: Ticks = 5_000;
+/// Ticks at which the host saves and reloads everything.
+pub const SAVE_TICKS: [Ticks; 2] = [3_000, 7_000];
+/// Tick at which the research-unlocked mine is demolished.
+pub const DEMOLISH_TICK: Ticks = 8_000;
+
+/// Generous capacity so no delivery ever overflows and loses items.
+const CAPACITY: u32 = 100_000;
+
+/// Host-side bookkeeping built from the events the host observed.
+#[derive(Debug, Default)]
+pub struct Ledger {
+    /// Total `ItemProduced` quantity per item type.
+    pub produced: BTreeMap<ItemTypeId, u64>,
+    /// Total `ItemConsumed` quantity per item type.
+    pub consumed: BTreeMap<ItemTypeId, u64>,
+    /// Items added to inventories by fluid bridges (no engine event).
+    pub bridged: BTreeMap<ItemTypeId, u64>,
+    /// Items destroyed with demolished nodes and edges.
+    pub demolished: BTreeMap<ItemTypeId, u64>,
+    /// Per-tick production for the most recent stats window, newest last.
+    pub recent_production: VecDeque<BTreeMap<ItemTypeId, u64>>,
+    /// Technologies completed, with the tick they completed on.
+    pub research: Vec<(TechId, Ticks)>,
+    /// Fluid pressure events seen during the session.
+    pub fluid_events: Vec<FluidEvent>,
+    /// Nodes removed from the graph during the session.
+    pub removed_nodes: Vec<NodeId>,
+    /// State hash immediately before and after each save/load cycle.
+    pub save_hashes: Vec<(u64, u64)>,
+}
+
+/// The game host: the engine plus the host-owned modules.
+///
+/// State touched by tick hooks and listeners is shared with them.
+#[derive(Debug)]
+pub struct Host {
+    pub engine: Engine,
+    pub fluid: Rc<RefCell<FluidModule>>,
+    pub tech: TechTree,
+    pub stats: Rc<RefCell<ProductionStats>>,
+    /// Named nodes currently in the factory.
+    pub nodes: BTreeMap<&'static str, NodeId>,
+    ledger: Rc<RefCell<Ledger>>,
+    bridge: Rc<RefCell<FluidBridge>>,
+    captured: Rc<RefCell<Vec<Event>>>,
+    pending_construction: Vec<BuildingTypeId>,
+}
+
+impl Host {
+    /// Build the starting factory: a mine feeding a smelter, an assembler,
+    /// and a lab, plus a pump-fed boiler whose steam is belted to a chest.
+    pub fn new() -> Self {
+        let mut builder = FactoryBuilder::new();
+        builder.seed(0xFAC7);
+        builder.node("mine").building(MINE).source(ORE, 1.0);
+        builder
+            .node("smelter")
+            .recipe(vec![(ORE, 1)], vec![(PLATE, 1)], 2)
+            .input_cap(CAPACITY)
+            .output_cap(CAPACITY);
+        builder
+            .node("assembler")
+            .recipe(vec![(PLATE, 2)], vec![(SCIENCE, 1)], 5)
+            .input_cap(CAPACITY)
+            .output_cap(CAPACITY);
+        builder
+            .node("lab")
+            .recipe(vec![(SCIENCE, 1)], vec![], 10)
+            .input_cap(CAPACITY);
+        builder.node("pump");
+        builder
+            .node("boiler")
+            .recipe(vec![(WATER, 8)], vec![(STEAM, 1)], 1)
+            .input_cap(CAPACITY)
+            .output_cap(CAPACITY);
+        builder.node("chest").input_cap(CAPACITY);
+        builder.belt("mine", "smelter", 1.0);
+        builder.belt("smelter", "assembler", 1.0);
+        builder.belt("assembler", "lab", 1.0);
+        builder.belt("boiler", "chest", 1.0);
+        let factory = builder.build().expect("starting factory");
+        let (engine, named) = factory.into_parts();
+        let nodes: BTreeMap<&'static str, NodeId> = [
+            "mine",
+            "smelter",
+            "assembler",
+            "lab",
+            "pump",
+            "boiler",
+            "chest",
+        ]
+        .into_iter()
+        .map(|name| (name, named[name]))
+        .collect();
+
+        let mut fluid = FluidModule::new();
+        let water = fluid.create_network(WATER);
+        fluid.add_producer(
+            water,
+            nodes["pump"],
+            FluidProducer {
+                rate: Fixed64::from_num(10),
+            },
+        );
+        fluid.add_consumer(
+            water,
+            nodes["boiler"],
+            FluidConsumer {
+                rate: Fixed64::from_num(8),
+            },
+        );
+        let bridge = FluidBridge::new(water, nodes["boiler"], WATER);
+
+        let mut tech = TechTree::new();
+        for (id, name, prerequisites, science, unlocks) in [
+            (
+                AUTOMATION,
+                "Automation",
+                vec![],
+                10,
+                vec![Unlock::Building(MINE)],
+            ),
+            (STEEL, "Steel", vec![AUTOMATION], 30, vec![]),
+        ] {
+            tech.register(Technology {
+                id,
+                name: name.to_string(),
+                prerequisites,
+                cost: ResearchCost::Items(vec![(SCIENCE, science)]),
+                unlocks,
+                repeatable: false,
+                cost_scaling: None,
+            })
+            .expect("register technology");
+        }
+        tech.start_research(AUTOMATION, 0)
+            .expect("start first research");
+
+        let mut host = Self {
+            engine,
+            fluid: Rc::new(RefCell::new(fluid)),
+            tech,
+            stats: Rc::new(RefCell::new(ProductionStats::new(StatsConfig::default()))),
+            nodes,
+            ledger: Rc::new(RefCell::new(Ledger::default())),
+            bridge: Rc::new(RefCell::new(bridge)),
+            captured: Rc::new(RefCell::new(Vec::new())),
+            pending_construction: Vec::new(),
+        };
+        host.register_listeners();
+        host.register_hooks();
+        host
+    }
+
+    /// The host-side bookkeeping.
+    pub fn ledger(&self) -> std::cell::Ref<'_, Ledger> {
+        self.ledger.borrow()
+    }
+
+    /// Feed every engine event to stats and capture it for the host. Must
+    /// be repeated on every engine produced by deserialization.
+    fn register_listeners(&mut self) {
+        for kind in [
+            EventKind::ItemProduced,
+            EventKind::ItemConsumed,
+            EventKind::BuildingStalled,
+            EventKind::BuildingResumed,
+            EventKind::ItemDelivered,
+            EventKind::TransportFull,
+            EventKind::NodeRemoved,
+            EventKind::EdgeRemoved,
+        ] {
+            let captured = Rc::clone(&self.captured);
+            let stats = Rc::clone(&self.stats);
+            self.engine.on_passive(
+                kind,
+                Box::new(move |event: &Event| {
+                    stats.borrow_mut().process_event(event);
+                    captured.borrow_mut().push(event.clone());
+                }),
+            );
+        }
+    }
+
+    /// Run the fluid module and bridge before each step's transport, and
+    /// close the stats tick after it. Must be repeated on every engine
+    /// produced by deserialization.
+    fn register_hooks(&mut self) {
+        let fluid = Rc::clone(&self.fluid);
+        let bridge = Rc::clone(&self.bridge);
+        let ledger = Rc::clone(&self.ledger);
+        self.engine.register_tick_hook(
+            TickPhase::Pre,
+            0,
+            Box::new(move |view| {
+                let mut fluid = fluid.borrow_mut();
+                let mut bridge = bridge.borrow_mut();
+                let mut ledger = ledger.borrow_mut();
+                ledger.fluid_events.extend(fluid.tick(view.tick()));
+                let consumed = fluid.get_consumed_this_tick(bridge.network, bridge.node);
+                let before = input_quantity(view.input_inventory(bridge.node), WATER);
+                bridge.apply_view(view, consumed);
+                let added = input_quantity(view.input_inventory(bridge.node), WATER) - before;
+                *ledger.bridged.entry(WATER).or_default() += added;
+            }),
+        );
+
+        let stats = Rc::clone(&self.stats);
+        self.engine.register_tick_hook(
+            TickPhase::PostStep,
+            0,
+            Box::new(move |view| stats.borrow_mut().end_tick(view.tick())),
+        );
+    }
+
+    /// Run one host frame in the documented call order.
+    pub fn frame(&mut self) {
+        let tick = self.engine.sim_state.tick;
+
+        // 1. Construction.
+        self.script(tick);
+        for building in std::mem::take(&mut self.pending_construction) {
+            self.construct(building);
+        }
+
+        // 2. Simulate; tick hooks run fluid, bridges and stats.
+        self.engine.step();
+
+        // 3. Route events to the fluid module and research.
+        let events = std::mem::take(&mut *self.captured.borrow_mut());
+        let mut produced_this_tick = BTreeMap::new();
+        for event in &events {
+            self.fluid.borrow_mut().process_event(event);
+            match *event {
+                Event::ItemProduced {
+                    item_type,
+                    quantity,
+                    ..
+                } => {
+                    *self
+                        .ledger
+                        .borrow_mut()
+                        .produced
+                        .entry(item_type)
+                        .or_default() += quantity as u64;
+                    *produced_this_tick.entry(item_type).or_default() += quantity as u64;
+                }
+                Event::ItemConsumed {
+                    node,
+                    item_type,
+                    quantity,
+                    ..
+                } => {
+                    *self
+                        .ledger
+                        .borrow_mut()
+                        .consumed
+                        .entry(item_type)
+                        .or_default() += quantity as u64;
+                    if node == self.nodes["lab"] {
+                        self.research(item_type, quantity, tick);
+                    }
+                }
+                Event::NodeRemoved { node, .. } => {
+                    self.ledger.borrow_mut().removed_nodes.push(node);
+                }
+                _ => {}
+            }
+        }
+        {
+            let mut ledger = self.ledger.borrow_mut();
+            ledger.recent_production.push_back(produced_this_tick);
+            if ledger.recent_production.len() > StatsConfig::default().window_size as usize {
+                ledger.recent_production.pop_front();
+            }
+        }
+
+        // 4. React to research.
+        for event in self.tech.drain_events() {
+            if let TechEvent::ResearchCompleted {
+                tech_id, unlocks, ..
+            } = event
+            {
+                self.ledger.borrow_mut().research.push((tech_id, tick));
+                for unlock in unlocks {
+                    if let Unlock::Building(building) = unlock {
+                        self.pending_construction.push(building);
+                    }
+                }
+                if tech_id == AUTOMATION {
+                    self.tech
+                        .start_research(STEEL, tick)
+                        .expect("steel prerequisites met");
+                }
+            }
+        }
+
+        // Save/load between frames, like a host autosave.
+        if SAVE_TICKS.contains(&self.engine.sim_state.tick) {
+            self.save_and_reload();
+        }
+    }
+
+    /// Scripted, tick-driven construction and demolition.
+    fn script(&mut self, tick: Ticks) {
+        match tick {
+            BROWNOUT_START => self.construct(HEATER),
+            BROWNOUT_END => self.demolish("heater"),
+            DEMOLISH_TICK => self.demolish("mine2"),
+            _ => {}
+        }
+    }
+
+    /// Contribute lab consumption to the active research.
+    fn research(&mut self, item_type: ItemTypeId, quantity: u32, tick: Ticks) {
+        for id in [AUTOMATION, STEEL] {
+            if self.tech.is_in_progress(id) {
+                let _ = self
+                    .tech
+                    .contribute_items(id, &[(item_type, quantity)], tick);
+                return;
+            }
+        }
+    }
+
+    /// Place a building, wiring it into the engine and the fluid module.
+    fn construct(&mut self, building: BuildingTypeId) {
+        let pending = self.engine.graph.queue_add_node(building);
+        let result = self.engine.apply_mutations();
+        let node = result.resolve_node(pending).expect("node added");
+        match building {
+            MINE => {
+                self.engine.set_processor(node, source_processor(ORE, 0.5));
+                self.engine
+                    .set_input_inventory(node, Inventory::new(1, 1, CAPACITY));
+                self.engine
+                    .set_output_inventory(node, Inventory::new(1, 1, CAPACITY));
+                let pending = self.engine.graph.queue_connect(node, self.nodes["smelter"]);
+                let result = self.engine.apply_mutations();
+                let edge = result.resolve_edge(pending).expect("edge added");
+                self.engine.set_transport(edge, belt_transport(1.0, 8));
+                self.nodes.insert("mine2", node);
+            }
+            HEATER => {
+                let network = self.bridge.borrow().network;
+                self.fluid.borrow_mut().add_consumer(
+                    network,
+                    node,
+                    FluidConsumer {
+                        rate: Fixed64::from_num(30),
+                    },
+                );
+                self.nodes.insert("heater", node);
+            }
+            _ => {}
+        }
+    }
+
+    /// Remove a named building. Items held by it or on its belts are lost
+    /// and recorded as demolished.
+    fn demolish(&mut self, name: &str) {
+        let Some(node) = self.nodes.remove(name) else {
+            return;
+        };
+        let mut ledger = self.ledger.borrow_mut();
+        for inventory in [
+            self.engine.get_input_inventory(node),
+            self.engine.get_output_inventory(node),
+        ]
+        .into_iter()
+        .flatten()
+        {
+            for slot in inventory.input_slots.iter().chain(&inventory.output_slots) {
+                for stack in &slot.stacks {
+                    *ledger.demolished.entry(stack.item_type).or_default() += stack.quantity as u64;
+                }
+            }
+        }
+        let edges: Vec<EdgeId> = self
+            .engine
+            .graph
+            .get_inputs(node)
+            .iter()
+            .chain(self.engine.graph.get_outputs(node))
+            .copied()
+            .collect();
+        for edge in edges {
+            for item in self.engine.transport_items(edge).unwrap_or_default() {
+                *ledger.demolished.entry(item.item_type).or_default() += 1;
+            }
+        }
+        drop(ledger);
+        self.engine.graph.queue_remove_node(node);
+        self.engine.apply_mutations();
+    }
+
+    /// Save the engine, fluid module, and tech tree, then load them back.
+    fn save_and_reload(&mut self) {
+        let before = self.engine.state_hash();
+        let engine = self.engine.serialize().expect("serialize engine");
+        let fluid = bitcode::serialize(&*self.fluid.borrow()).expect("serialize fluid");
+        let tech = bitcode::serialize(&self.tech).expect("serialize tech tree");
+
+        self.engine = Engine::deserialize(&engine).expect("deserialize engine");
+        *self.fluid.borrow_mut() = bitcode::deserialize(&fluid).expect("deserialize fluid");
+        self.tech = bitcode::deserialize(&tech).expect("deserialize tech tree");
+        self.register_listeners();
+        self.register_hooks();
+        self.ledger
+            .borrow_mut()
+            .save_hashes
+            .push((before, self.engine.state_hash()));
+    }
+
+    /// Items currently held anywhere in the factory: node inventories plus
+    /// items in transit on belts.
+    pub fn items_on_hand(&self) -> BTreeMap<ItemTypeId, u64> {
+        let mut held: BTreeMap<ItemTypeId, u64> = BTreeMap::new();
+        for (node, _) in self.engine.graph.nodes() {
+            for inventory in [
+                self.engine.get_input_inventory(node),
+                self.engine.get_output_inventory(node),
+            ]
+            .into_iter()
+            .flatten()
+            {
+                for slot in inventory.input_slots.iter().chain(&inventory.output_slots) {
+                    for stack in &slot.stacks {
+                        *held.entry(stack.item_type).or_default() += stack.quantity as u64;
+                    }
+                }
+            }
+        }
+        for (edge, _) in self.engine.graph.edges() {
+            for item in self.engine.transport_items(edge).unwrap_or_default() {
+                *held.entry(item.item_type).or_default() += 1;
+            }
+        }
+        held
+    }
+
+    /// Node ids referenced by the host-owned modules that are no longer in
+    /// the graph.
+    pub fn dangling_references(&self) -> Vec<NodeId> {
+        let graph = &self.engine.graph;
+        let fluid = self.fluid.borrow();
+        let mut referenced: Vec<NodeId> = Vec::new();
+        referenced.extend(fluid.producers.keys());
+        referenced.extend(fluid.consumers.keys());
+        for network in fluid.networks.values() {
+            referenced.extend(&network.producers);
+            referenced.extend(&network.consumers);
+        }
+        referenced.push(self.bridge.borrow().node);
+        referenced.extend(self.nodes.values());
+        referenced.retain(|&node| graph.get_node(node).is_none());
+        referenced
+    }
+}
+
+impl Default for Host {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Quantity of `item` in an input inventory.
+fn input_quantity(inventory: Option<&Inventory>, item: ItemTypeId) -> u64 {
+    inventory
+        .map(|inv| {
+            inv.input_slots
+                .iter()
+                .map(|s| s.quantity(item) as u64)
+                .sum()
+        })
+        .unwrap_or(0)
+}
+
+/// Run the scripted session for `ticks` frames and return the host.
+pub fn run_session(ticks: Ticks) -> Host {
+    let mut host = Host::new();
+    while host.engine.sim_state.tick < ticks {
+        host.frame();
+    }
+    host
+}
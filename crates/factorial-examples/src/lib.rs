@@ -1,2 +1,4 @@
 //! Example crate demonstrating cross-module Factorial usage.
 //! See the `examples/` directory for runnable demonstrations.
+
+pub mod host_loop;
@@ -0,0 +1,99 @@
+//! End-of-session invariants for the scripted host loop.
+
+use std::collections::BTreeSet;
+
+use factorial_core::fixed::Fixed64;
+use factorial_examples::host_loop::{self, AUTOMATION, Host, STEEL};
+use factorial_stats::StatsConfig;
+
+/// State hash at the end of the session. Any change to simulation
+/// behaviour, the save format, the state hash composition, or the session
+/// script moves this value.
+const GOLDEN_STATE_HASH: u64 = 0x7694_e44a_c7a0_b699;
+
+fn session() -> Host {
+    host_loop::run_session(host_loop::SESSION_TICKS)
+}
+
+#[test]
+fn session_matches_golden_state_hash() {
+    let host = session();
+    assert_eq!(host.engine.sim_state.tick, host_loop::SESSION_TICKS);
+    assert_eq!(host.engine.state_hash(), GOLDEN_STATE_HASH);
+}
+
+#[test]
+fn items_are_conserved_across_the_factory() {
+    let host = session();
+    let ledger = host.ledger();
+    let held = host.items_on_hand();
+    let items: BTreeSet<_> = ledger
+        .produced
+        .keys()
+        .chain(ledger.bridged.keys())
+        .chain(held.keys())
+        .collect();
+    assert!(!items.is_empty());
+    for item in items {
+        let get = |map: &std::collections::BTreeMap<_, u64>| map.get(item).copied().unwrap_or(0);
+        let entered = get(&ledger.produced) + get(&ledger.bridged);
+        let left = get(&ledger.consumed) + get(&ledger.demolished);
+        assert_eq!(entered - left, get(&held), "item {item:?} not conserved");
+    }
+}
+
+#[test]
+fn stats_totals_match_event_counts() {
+    let host = session();
+    let window = StatsConfig::default().window_size as usize;
+    assert_eq!(host.ledger().recent_production.len(), window);
+    for item in host.ledger().produced.keys() {
+        let count: u64 = host
+            .ledger()
+            .recent_production
+            .iter()
+            .map(|tick| tick.get(item).copied().unwrap_or(0))
+            .sum();
+        let expected = Fixed64::from_num(count) / Fixed64::from_num(window as u64);
+        assert_eq!(
+            host.stats.borrow().get_total_production(*item),
+            expected,
+            "stats rate for {item:?} disagrees with observed events"
+        );
+    }
+}
+
+#[test]
+fn scripted_events_all_happen() {
+    let host = session();
+    let ledger = host.ledger();
+    let completed: Vec<_> = ledger.research.iter().map(|(tech, _)| *tech).collect();
+    assert_eq!(completed, vec![AUTOMATION, STEEL]);
+    assert!(host.tech.is_completed(STEEL));
+    assert_eq!(
+        ledger.fluid_events.len(),
+        2,
+        "one brownout and one recovery"
+    );
+    assert_eq!(ledger.removed_nodes.len(), 2, "heater and research mine");
+    assert!(ledger.demolished.values().sum::<u64>() > 0);
+}
+
+#[test]
+fn save_load_cycles_preserve_state() {
+    let host = session();
+    assert_eq!(host.ledger().save_hashes.len(), 2);
+    for (before, after) in &host.ledger().save_hashes {
+        assert_eq!(before, after);
+    }
+}
+
+#[test]
+fn no_dangling_module_references_after_removals() {
+    let host = session();
+    assert!(host.dangling_references().is_empty());
+    for node in &host.ledger().removed_nodes {
+        assert!(host.engine.graph.get_node(*node).is_none());
+        assert!(!host.fluid.borrow().consumers.contains_key(node));
+    }
+}
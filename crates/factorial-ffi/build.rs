@@ -6,6 +6,12 @@ fn main() {
     let output_dir = PathBuf::from(&crate_dir);
     let output_file = output_dir.join("factorial.h");
 
+    // The C ABI contract test compiles C code for the same target.
+    println!(
+        "cargo:rustc-env=FACTORIAL_FFI_TARGET={}",
+        env::var("TARGET").unwrap()
+    );
+
     // Only regenerate when sources change.
     println!("cargo:rerun-if-changed=src/lib.rs");
     println!("cargo:rerun-if-changed=cbindgen.toml");
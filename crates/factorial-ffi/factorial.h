@@ -6,10 +6,62 @@
 #include <stdint.h>
 #include <stdbool.h>
 #include <stddef.h>
+/* Rust engine, for embedders using factorial_wrap_borrowed. */
+typedef struct Engine Engine;
 /* Opaque engine handle. */
 typedef struct FactorialEngine FactorialEngine;
+/* Opaque engine pool handle. */
+typedef struct FactorialEnginePool FactorialEnginePool;
+/* Opaque read-only engine inspector handle. */
+typedef struct FactorialEngineInspector FactorialEngineInspector;
 
 
+/**
+ * Default maximum number of events held in the poll cache per step.
+ */
+#define DEFAULT_EVENT_CACHE_LIMIT 262144
+
+/**
+ * Flag for [`factorial_add_node_ex`]: the node emits no events.
+ */
+#define NODE_FLAG_SILENT 1
+
+/**
+ * Flag for [`factorial_add_node_ex`]: statistics trackers ignore the node.
+ */
+#define NODE_FLAG_NO_STATS 2
+
+/**
+ * Written to `ticks_until_delivery` of [`FfiTransportProgress`] for a belt
+ * with no item on its way.
+ */
+#define NO_DELIVERY UINT32_MAX
+
+/**
+ * Bundle component: the tech tree registered on the engine.
+ */
+#define BUNDLE_TECH_TREE 1
+
+/**
+ * Bundle component: a fluid module.
+ */
+#define BUNDLE_FLUID 2
+
+/**
+ * Bundle component: a statistics configuration.
+ */
+#define BUNDLE_STATS_CONFIG 4
+
+/**
+ * Written by [`factorial_get_active_recipe`] when no recipe is in progress.
+ */
+#define NO_RECIPE UINT32_MAX
+
+/**
+ * Pass to [`factorial_set_storage_limit`] to make every slot usable.
+ */
+#define NO_STORAGE_LIMIT UINT32_MAX
+
 /**
  * Status codes returned by all FFI functions.
  */
@@ -50,8 +102,103 @@ typedef enum FactorialResult {
    * The engine is poisoned (a previous panic left it in an inconsistent state).
    */
   POISONED = 8,
+  /**
+   * The pending id's mutation has not been applied yet.
+   */
+  PENDING_QUEUED = 9,
+  /**
+   * The pending id's batch is older than the resolution history.
+   */
+  PENDING_EXPIRED = 10,
+  /**
+   * The pending id was never issued by this engine.
+   */
+  PENDING_UNKNOWN = 11,
+  /**
+   * The requested crafting queue was not found.
+   */
+  CRAFTING_QUEUE_NOT_FOUND = 12,
+  /**
+   * A crafting request was rejected: missing ingredients, unknown recipe,
+   * invalid speed, or job index out of range.
+   */
+  CRAFTING_REJECTED = 13,
+  /**
+   * The caller's output buffer is too small; the size needed was written.
+   */
+  BUFFER_TOO_SMALL = 14,
+  /**
+   * An enum-valued argument was out of range.
+   */
+  INVALID_ARGUMENT = 15,
+  /**
+   * The node's processor or the edge's transport is a different kind
+   * from the one the getter reads.
+   */
+  KIND_MISMATCH = 16,
+  /**
+   * A read-only call overlapped a mutating call on the same handle and
+   * read nothing. Only detected in debug builds.
+   */
+  CONCURRENT_MUTATION = 17,
+  /**
+   * The requested edge group was not found.
+   */
+  EDGE_GROUP_NOT_FOUND = 18,
+  /**
+   * The step ran, but exceeded the step work limit and skipped its
+   * remaining simulation phases.
+   */
+  WORK_LIMIT_REACHED = 19,
+  /**
+   * A sliced tick is open: the call would change the engine mid-step.
+   * Finish the tick with `factorial_continue_tick` first.
+   */
+  TICK_IN_PROGRESS = 20,
+  /**
+   * The requested event cursor was not found.
+   */
+  EVENT_CURSOR_NOT_FOUND = 21,
 } FactorialResult;
 
+/**
+ * Where a sliced tick stands after `factorial_continue_tick`.
+ */
+typedef enum FfiTickSliceStatus {
+  /**
+   * The tick has work left; call `factorial_continue_tick` again.
+   */
+  TICK_SLICE_IN_PROGRESS = 0,
+  /**
+   * The tick is complete, or no tick was open.
+   */
+  TICK_SLICE_COMPLETE = 1,
+} FfiTickSliceStatus;
+
+/**
+ * C-compatible simulation strategy kind for `factorial_set_strategy`.
+ */
+typedef enum FfiSimulationStrategy {
+  /**
+   * One step per `factorial_step` or `factorial_advance` call.
+   */
+  TICK = 0,
+  /**
+   * `factorial_advance` accumulates `dt` and runs fixed steps.
+   */
+  DELTA = 1,
+} FfiSimulationStrategy;
+
+/**
+ * C-compatible transport kind for [`FfiTransportConfig`].
+ */
+typedef enum FfiTransportKind {
+  FLOW = 0,
+  ITEM = 1,
+  BATCH = 2,
+  VEHICLE = 3,
+} FfiTransportKind;
+
 /**
  * C-compatible processor state tag.
  */
@@ -62,14 +209,44 @@ typedef enum FfiProcessorState {
   STALLED_OUTPUT_FULL = 3,
   STALLED_NO_POWER = 4,
   STALLED_DEPLETED = 5,
+  STORING = 6,
 } FfiProcessorState;
 
+/**
+ * C-compatible vehicle phase for [`FfiTransportProgress`]. See
+ * `VehiclePhase` in factorial-core.
+ */
+typedef enum FfiVehiclePhase {
+  LOADING = 0,
+  TRAVELING = 1,
+  UNLOADING = 2,
+  RETURNING = 3,
+} FfiVehiclePhase;
+
+/**
+ * C-compatible node/edge simulation status.
+ */
+typedef enum FfiNodeStatus {
+  /**
+   * Planned; stays in the graph but does not simulate.
+   */
+  GHOST = 0,
+  /**
+   * Built and simulating normally.
+   */
+  ACTIVE = 1,
+} FfiNodeStatus;
+
 /**
  * C-compatible event tag.
  */
 typedef enum FfiEventKind {
   ITEM_PRODUCED = 0,
   ITEM_CONSUMED = 1,
+  /**
+   * For a flexible processor `item_type` holds the selected recipe id and
+   * `quantity` is 1; otherwise both are 0.
+   */
   RECIPE_STARTED = 2,
   RECIPE_COMPLETED = 3,
   BUILDING_STALLED = 4,
@@ -81,8 +258,74 @@ typedef enum FfiEventKind {
   EDGE_ADDED = 10,
   EDGE_REMOVED = 11,
   RECIPE_SWITCHED = 12,
+  /**
+   * `item_type` holds the recipe id.
+   */
+  CRAFT_STARTED = 13,
+  /**
+   * `item_type` holds the recipe id and `quantity` the crafts left in
+   * the job.
+   */
+  CRAFT_COMPLETED = 14,
+  /**
+   * `item_type` and `quantity` describe the items that spoiled. The
+   * product, if any, is the item type's configured decay product.
+   */
+  ITEM_SPOILED = 15,
+  /**
+   * `item_type` is the item the slot holds, `quantity` the slot index,
+   * and `building_type` 0 for the input inventory or 1 for the output
+   * inventory.
+   */
+  INVENTORY_FULL = 16,
+  /**
+   * Fields as for `InventoryFull`; `item_type` is the item the slot held
+   * when it filled.
+   */
+  INVENTORY_SPACE_AVAILABLE = 17,
+  /**
+   * `quantity` is the work done, saturated to `u32::MAX`, and
+   * `building_type` the phase after which the step stopped: 0 pre-tick,
+   * 1 transport, 2 process, 3 component.
+   */
+  STEP_TRUNCATED = 18,
 } FfiEventKind;
 
+/**
+ * C-compatible source depletion kind.
+ */
+typedef enum FfiDepletionKind {
+  /**
+   * Never runs out (`Depletion::Infinite`; renamed so the C name does
+   * not collide with the Windows `INFINITE` macro).
+   */
+  UNLIMITED = 0,
+  /**
+   * Stops once `remaining` reaches zero.
+   */
+  FINITE = 1,
+  /**
+   * Rate halves every `half_life` ticks.
+   */
+  DECAYING = 2,
+} FfiDepletionKind;
+
+/**
+ * C-compatible processor kind, from [`factorial_get_processor_config`].
+ */
+typedef enum FfiProcessorKind {
+  PROCESSOR_KIND_SOURCE = 0,
+  PROCESSOR_KIND_FIXED = 1,
+  PROCESSOR_KIND_PROPERTY = 2,
+  PROCESSOR_KIND_DEMAND = 3,
+  PROCESSOR_KIND_PASSTHROUGH = 4,
+  PROCESSOR_KIND_MULTI_RECIPE = 5,
+  PROCESSOR_KIND_WEIGHTED_RECIPE = 6,
+  PROCESSOR_KIND_FLEXIBLE = 7,
+  PROCESSOR_KIND_STORAGE = 8,
+  PROCESSOR_KIND_MULTI_SOURCE = 9,
+} FfiProcessorKind;
+
 /**
  * C-compatible wire color.
  */
@@ -132,6 +375,8 @@ typedef enum FfiDeciderOutputKind {
   EVERYTHING = 2,
 } FfiDeciderOutputKind;
 
+typedef struct Option_FactorialOperationLogger Option_FactorialOperationLogger;
+
 /**
  * C-compatible representation of a PendingNodeId.
  */
@@ -153,13 +398,30 @@ typedef uint64_t FfiPendingEdgeId;
 typedef uint64_t FfiEdgeId;
 
 /**
- * A pair of (pending_id, real_id) for mutation results.
+ * A pair of (pending_id, real_id) for mutation results. Pairs are in the
+ * order the mutations were applied: by ascending lane, then in call order.
  */
 typedef struct FfiIdPair {
   uint64_t pending_id;
   uint64_t real_id;
+  /**
+   * The mutation lane the addition was queued in.
+   */
+  uint8_t lane;
 } FfiIdPair;
 
+/**
+ * A node addition refused at apply time. Listed in the order the mutations
+ * were applied, like [`FfiIdPair`].
+ */
+typedef struct FfiRejectedMutation {
+  uint64_t pending_id;
+  /**
+   * Reason code: `1` = building locked behind incomplete research.
+   */
+  uint32_t reason;
+} FfiRejectedMutation;
+
 /**
  * Result of applying mutations. Maps pending IDs to real IDs.
  */
@@ -174,8 +436,80 @@ typedef struct FfiMutationResult {
    */
   const struct FfiIdPair *added_edges;
   uint32_t added_edge_count;
+  /**
+   * Array of node additions refused in strict mode.
+   */
+  const struct FfiRejectedMutation *rejected;
+  uint32_t rejected_count;
 } FfiMutationResult;
 
+/**
+ * C-compatible outgoing edge from `factorial_get_topology`. `kind` is only
+ * meaningful when `has_transport` is true.
+ */
+typedef struct FfiTopologyEdge {
+  FfiEdgeId edge;
+  /**
+   * Index of the edge's target in the node array.
+   */
+  uint32_t target;
+  enum FfiTransportKind kind;
+  bool has_transport;
+} FfiTopologyEdge;
+
+/**
+ * C-compatible per-subsystem state hashes. The wrapping sum of all fields
+ * equals `factorial_get_state_hash`. See `HashBreakdown` in factorial-core
+ * for field meanings.
+ */
+typedef struct FfiHashBreakdown {
+  uint64_t graph;
+  uint64_t inventories;
+  uint64_t processor_states;
+  uint64_t transports;
+  uint64_t sim_state;
+  uint64_t fluid;
+  uint64_t crafting;
+  uint64_t modules;
+} FfiHashBreakdown;
+
+/**
+ * C-compatible factory-wide count of one item type. See `ItemCensus` in
+ * factorial-core for field meanings.
+ */
+typedef struct FfiItemCensus {
+  uint64_t in_input_inventories;
+  uint64_t in_output_inventories;
+  uint64_t in_transit;
+  uint64_t total;
+} FfiItemCensus;
+
+/**
+ * C-compatible stall summary for one item type. See `ItemBottleneck` in
+ * factorial-core for field meanings.
+ */
+typedef struct FfiItemBottleneck {
+  uint32_t item_type;
+  uint32_t starved_nodes;
+  uint32_t blocked_nodes;
+  /**
+   * The starved node missing the most units. 0 if none.
+   */
+  FfiNodeId worst_starved;
+  /**
+   * The blocked node holding the most units in its output. 0 if none.
+   */
+  FfiNodeId worst_blocked;
+} FfiItemBottleneck;
+
+/**
+ * Engine-owned array returned by `factorial_get_bottleneck_digest`.
+ */
+typedef struct FfiBottleneckBuffer {
+  const struct FfiItemBottleneck *items;
+  uint32_t count;
+} FfiBottleneckBuffer;
+
 /**
  * C-compatible processor state with progress.
  */
@@ -187,6 +521,57 @@ typedef struct FfiProcessorInfo {
   uint32_t progress;
 } FfiProcessorInfo;
 
+/**
+ * C-compatible view of one item riding on a belt (item transport) edge.
+ */
+typedef struct FfiTransportItem {
+  /**
+   * Stable per-engine instance id; unchanged while the item is on the belt.
+   */
+  uint64_t instance_id;
+  uint32_t item_type;
+  uint32_t lane;
+  /**
+   * Position along the edge as raw Fixed64 bits: 0 at the source end,
+   * `1 << 32` at the destination end.
+   */
+  int64_t position;
+} FfiTransportItem;
+
+/**
+ * Engine-owned array returned by `factorial_get_transport_items`.
+ */
+typedef struct FfiTransportItemBuffer {
+  const struct FfiTransportItem *items;
+  uint32_t count;
+} FfiTransportItemBuffer;
+
+/**
+ * C-compatible transport progress. Only the fields for `kind` are set; the
+ * others are zero:
+ *
+ * - `Flow`: `buffered`, `capacity` (raw Fixed64 bits).
+ * - `Item`: `items_in_transit`, `head_position` (raw Fixed64 bits in
+ *   `[0, 1 << 32]`, 0 for an empty belt), `ticks_until_delivery`
+ *   (`NO_DELIVERY` for an empty or stopped belt).
+ * - `Batch`: `accumulated`, `batch_size`, `ticks_until_forced_dispatch`.
+ * - `Vehicle`: `phase`, `ticks_remaining_in_phase`, `cargo_count`.
+ */
+typedef struct FfiTransportProgress {
+  enum FfiTransportKind kind;
+  int64_t buffered;
+  int64_t capacity;
+  uint32_t items_in_transit;
+  int64_t head_position;
+  uint32_t ticks_until_delivery;
+  uint32_t accumulated;
+  uint32_t batch_size;
+  uint32_t ticks_until_forced_dispatch;
+  enum FfiVehiclePhase phase;
+  uint32_t ticks_remaining_in_phase;
+  uint32_t cargo_count;
+} FfiTransportProgress;
+
 /**
  * C-compatible event data. Union fields are determined by `kind`.
  * We use a flat struct with all possible fields to keep it simple and
@@ -226,380 +611,2516 @@ typedef struct FfiEvent {
 } FfiEvent;
 
 /**
- * Result of polling events: a pointer to engine-owned event buffer and count.
+ * Result of polling events: a pointer to engine-owned event buffer and count.
+ */
+typedef struct FfiEventBuffer {
+  /**
+   * Pointer to an array of `FfiEvent`. Valid until next step/destroy.
+   */
+  const struct FfiEvent *events;
+  /**
+   * Number of events in the buffer.
+   */
+  uint32_t count;
+} FfiEventBuffer;
+
+/**
+ * An engine-allocated byte buffer returned from serialization.
+ */
+typedef struct FfiByteBuffer {
+  /**
+   * Pointer to the data. Null on error.
+   */
+  uint8_t *data;
+  /**
+   * Length in bytes.
+   */
+  uintptr_t len;
+} FfiByteBuffer;
+
+/**
+ * Filter for `factorial_query_event_history`. Zeroed fields (and
+ * `max_tick = UINT64_MAX`) mean "no restriction".
+ */
+typedef struct FfiEventFilter {
+  /**
+   * Bit `1 << kind` for each `FfiEventKind` to keep; 0 keeps every kind.
+   */
+  uint32_t kind_mask;
+  /**
+   * Keep only events about this node (0 = any node).
+   */
+  FfiNodeId node;
+  /**
+   * Keep only events about this edge (0 = any edge).
+   */
+  FfiEdgeId edge;
+  /**
+   * Keep only events stamped at or after this tick.
+   */
+  uint64_t min_tick;
+  /**
+   * Keep only events stamped at or before this tick.
+   */
+  uint64_t max_tick;
+} FfiEventFilter;
+
+/**
+ * C-compatible representation of an EventCursorId (u64 ffi key).
+ */
+typedef uint64_t FfiEventCursorId;
+
+/**
+ * C-compatible save summary. See `SaveSummary` in factorial-core for field
+ * meanings. Per-item totals are reduced to their count and sum.
+ */
+typedef struct FfiSaveSummary {
+  uint32_t version;
+  uint64_t tick;
+  uint32_t node_count;
+  uint32_t edge_count;
+  uint32_t extensions;
+  uint64_t state_hash;
+  /**
+   * Whether the save recorded item totals. The next two fields are zero
+   * otherwise.
+   */
+  bool has_item_totals;
+  /**
+   * Number of item types with at least one unit.
+   */
+  uint32_t item_type_count;
+  /**
+   * Units of all item types together.
+   */
+  uint64_t total_items;
+  /**
+   * Whether the save had no summary section and was fully decoded.
+   */
+  bool full_decode;
+} FfiSaveSummary;
+
+/**
+ * C-compatible source depletion. Only the field matching `kind` is
+ * meaningful; the other is zero.
+ */
+typedef struct FfiDepletion {
+  enum FfiDepletionKind kind;
+  /**
+   * Raw Fixed64 bits of the amount left (`Finite`).
+   */
+  int64_t remaining;
+  /**
+   * Half-life in ticks (`Decaying`).
+   */
+  uint64_t half_life;
+} FfiDepletion;
+
+/**
+ * C-compatible item stack (item type + quantity).
+ */
+typedef struct FfiItemStack {
+  uint32_t item_type;
+  uint32_t quantity;
+} FfiItemStack;
+
+/**
+ * C-compatible description of a node's processor, filled by
+ * [`factorial_get_processor_config`].
+ *
+ * The caller sets `inputs`/`input_capacity` and `outputs`/`output_capacity`
+ * to buffers for the recipe's item stacks (null with capacity 0 to ask for
+ * the counts only). Everything else is written by the call; fields that do
+ * not apply to `kind` are zero.
+ */
+typedef struct FfiProcessorConfig {
+  enum FfiProcessorKind kind;
+  /**
+   * Item produced (`Source`, `Property`) or consumed (`Demand`).
+   */
+  uint32_t item;
+  /**
+   * Item consumed by a `Property` processor.
+   */
+  uint32_t input_item;
+  /**
+   * Raw Fixed64 bits of the base rate (`Source`, `Demand`).
+   */
+  int64_t rate;
+  /**
+   * Deposit depletion (`Source`, `MultiSource`).
+   */
+  struct FfiDepletion depletion;
+  /**
+   * Recipes held: 1 for `Fixed`, the list length for `MultiRecipe`,
+   * `WeightedRecipe` and `Flexible`.
+   */
+  uint32_t recipe_count;
+  /**
+   * Index of the recipe described below (`MultiRecipe`).
+   */
+  uint32_t active_recipe;
+  /**
+   * Cycle length in ticks of the described recipe.
+   */
+  uint32_t duration;
+  /**
+   * Caller buffer for the described recipe's item inputs.
+   */
+  struct FfiItemStack *inputs;
+  uint32_t input_capacity;
+  /**
+   * Number of item inputs, written even if the buffer is too small.
+   */
+  uint32_t input_count;
+  /**
+   * Caller buffer for the described recipe's item outputs.
+   */
+  struct FfiItemStack *outputs;
+  uint32_t output_capacity;
+  /**
+   * Number of item outputs, written even if the buffer is too small.
+   */
+  uint32_t output_count;
+} FfiProcessorConfig;
+
+/**
+ * C-compatible output of a multi-output source: item type plus raw
+ * Fixed64 bits (Q32.32) of items per tick.
+ */
+typedef struct FfiSourceOutput {
+  uint32_t item_type;
+  int64_t rate;
+} FfiSourceOutput;
+
+/**
+ * C-compatible richness curve. Every `Fixed64` field is raw Q32.32 bits.
+ */
+typedef struct FfiRichnessCurve {
+  /**
+   * Width of a noise cell in map units (`0` behaves as `1`).
+   */
+  uint32_t cell_size;
+  int64_t min_rate;
+  int64_t max_rate;
+  int64_t min_amount;
+  /**
+   * Deposit size at richness 1; zero or negative makes deposits infinite.
+   */
+  int64_t max_amount;
+} FfiRichnessCurve;
+
+/**
+ * C-compatible record of where a procedural source sits.
+ */
+typedef struct FfiSourceSite {
+  int32_t x;
+  int32_t y;
+  /**
+   * Richness in `[0, 1)`, as raw Fixed64 bits.
+   */
+  int64_t richness;
+} FfiSourceSite;
+
+/**
+ * C-compatible recipe for FixedRecipe processor.
+ */
+typedef struct FfiRecipe {
+  uint32_t input_count;
+  const struct FfiItemStack *inputs;
+  uint32_t output_count;
+  const struct FfiItemStack *outputs;
+  uint32_t duration;
+} FfiRecipe;
+
+/**
+ * One entry of [`factorial_set_processors_bulk`]: a node and the
+ * FixedRecipe to give it.
+ *
+ * Layout: the 8-byte node id followed by an `FfiRecipe`, with the C
+ * alignment rules of the target.
+ */
+typedef struct FfiProcessorAssignment {
+  FfiNodeId node;
+  struct FfiRecipe recipe;
+} FfiProcessorAssignment;
+
+/**
+ * C-compatible fluid ingredient or product: fluid type plus raw Fixed64
+ * bits (Q32.32) per craft.
+ */
+typedef struct FfiFluidAmount {
+  uint32_t fluid;
+  int64_t amount;
+} FfiFluidAmount;
+
+/**
+ * Version 2 of [`FfiRecipe`]: adds fluid ingredient and product arrays.
+ *
+ * The leading fields match `FfiRecipe`.
+ */
+typedef struct FfiRecipeV2 {
+  uint32_t input_count;
+  const struct FfiItemStack *inputs;
+  uint32_t output_count;
+  const struct FfiItemStack *outputs;
+  uint32_t duration;
+  uint32_t fluid_input_count;
+  const struct FfiFluidAmount *fluid_inputs;
+  uint32_t fluid_output_count;
+  const struct FfiFluidAmount *fluid_outputs;
+} FfiRecipeV2;
+
+/**
+ * Version 3 of [`FfiRecipe`]: adds a chance for each item output.
+ *
+ * The leading fields match `FfiRecipeV2`. `output_probabilities` is null
+ * when every output is always produced, or points to `output_count` raw
+ * Fixed64 bits (Q32.32), the chance in [0, 1] of producing each output.
+ */
+typedef struct FfiRecipeV3 {
+  uint32_t input_count;
+  const struct FfiItemStack *inputs;
+  uint32_t output_count;
+  const struct FfiItemStack *outputs;
+  uint32_t duration;
+  uint32_t fluid_input_count;
+  const struct FfiFluidAmount *fluid_inputs;
+  uint32_t fluid_output_count;
+  const struct FfiFluidAmount *fluid_outputs;
+  const int64_t *output_probabilities;
+} FfiRecipeV3;
+
+/**
+ * C-compatible transport configuration. Only the fields used by `kind`
+ * are read:
+ *
+ * - `Flow`: `rate` (raw Fixed64 bits), with the same default buffer and
+ *   latency as `factorial_set_flow_transport`.
+ * - `Item`: `rate` as belt speed (raw Fixed64 bits), `slot_count`, `lanes`.
+ * - `Batch`: `batch_size`, `cycle_time`.
+ * - `Vehicle`: `capacity`, `travel_time`.
+ */
+typedef struct FfiTransportConfig {
+  enum FfiTransportKind kind;
+  int64_t rate;
+  uint32_t slot_count;
+  uint8_t lanes;
+  uint32_t batch_size;
+  uint32_t cycle_time;
+  uint32_t capacity;
+  uint32_t travel_time;
+} FfiTransportConfig;
+
+/**
+ * C-compatible representation of an EdgeGroupId (u64 ffi key).
+ */
+typedef uint64_t FfiEdgeGroupId;
+
+/**
+ * Engine-owned array of item stacks returned by the inventory clear calls.
+ */
+typedef struct FfiItemBuffer {
+  const struct FfiItemStack *items;
+  uint32_t count;
+} FfiItemBuffer;
+
+/**
+ * One inventory to fill with [`factorial_fill_inventories_bulk`]: `count`
+ * items starting at `offset` in the shared item array go into the node's
+ * input (`which` 0) or output (`which` 1) inventory.
+ */
+typedef struct FfiInventoryFill {
+  FfiNodeId node;
+  uint32_t which;
+  uint32_t offset;
+  uint32_t count;
+} FfiInventoryFill;
+
+/**
+ * C-compatible representation of a CraftingQueueId (u64 ffi key).
+ */
+typedef uint64_t FfiCraftingQueueId;
+
+/**
+ * C-compatible crafting queue progress.
+ */
+typedef struct FfiCraftProgress {
+  /**
+   * Recipe id of the front job. Only meaningful when `jobs > 0`.
+   */
+  uint32_t recipe;
+  /**
+   * Completion of the front craft as raw Fixed64 bits (0..1).
+   */
+  int64_t progress;
+  /**
+   * Crafts left across all jobs.
+   */
+  uint32_t remaining;
+  /**
+   * Number of jobs in the queue.
+   */
+  uint32_t jobs;
+} FfiCraftProgress;
+
+/**
+ * C-compatible per-phase operation counts from the most recent step.
+ * See `StepProfile` in factorial-core for field meanings.
+ */
+typedef struct FfiStepProfile {
+  /**
+   * Engine tick after the step. 0 means no profile is available.
+   */
+  uint64_t tick;
+  uint32_t mutations_applied;
+  uint32_t transports_advanced;
+  uint32_t items_delivered;
+  uint32_t processors_ticked;
+  uint32_t junctions_processed;
+  uint32_t modules_run;
+  uint32_t events_delivered;
+  uint32_t module_work;
+  uint32_t topology_rebuilds;
+} FfiStepProfile;
+
+/**
+ * C-compatible wire network ID.
+ */
+typedef uint32_t FfiWireNetworkId;
+
+/**
+ * One entry of the per-tick changed-signal report. `old` and `new` are raw
+ * Fixed64 bits.
+ */
+typedef struct FfiSignalChange {
+  FfiNodeId node;
+  enum FfiWireColor wire;
+  uint32_t signal;
+  FfiWireNetworkId network;
+  int64_t old;
+  int64_t new_;
+} FfiSignalChange;
+
+/**
+ * Engine-owned array returned by `factorial_logic_get_changed_signals`.
+ */
+typedef struct FfiSignalChangeBuffer {
+  const struct FfiSignalChange *changes;
+  uint32_t count;
+} FfiSignalChangeBuffer;
+
+/**
+ * Create a new engine with `Tick` simulation strategy.
+ * Returns a heap-allocated engine pointer. The caller must eventually
+ * call `factorial_destroy` to free the memory.
+ *
+ * Returns null on internal error.
+ */
+FactorialEngine *factorial_create(void);
+
+/**
+ * Create a new engine with `Delta` simulation strategy.
+ * `fixed_timestep` is the number of ticks per fixed simulation step.
+ *
+ * Returns null on internal error.
+ */
+FactorialEngine *factorial_create_delta(uint64_t fixed_timestep);
+
+/**
+ * Destroy an engine and free its memory.
+ *
+ * # Safety
+ *
+ * `engine` must be a pointer returned by `factorial_create` that has not
+ * yet been destroyed. After this call the pointer is invalid. Handles from
+ * `factorial_wrap_borrowed` are released with `factorial_unwrap` instead;
+ * destroying one releases the handle but never frees the borrowed engine.
+ */
+enum FactorialResult factorial_destroy(FactorialEngine *engine);
+
+/**
+ * Wrap an `Engine` owned by Rust code in an FFI handle without taking
+ * ownership, so an embedder can hand its existing engine to C plugins.
+ * The handle registers the same event listeners and caches as one from
+ * `factorial_create`.
+ *
+ * Release the handle with `factorial_unwrap`, never `factorial_destroy`.
+ * The engine keeps the handle's event listeners after unwrapping; they
+ * ignore events from then on.
+ *
+ * Returns null if `engine` is null or on internal error.
+ *
+ * # Safety
+ *
+ * `engine` must point to a live `Engine` that outlives the handle. Until
+ * `factorial_unwrap` returns, the caller must not access, move or drop the
+ * engine except through the handle. If the engine has listeners, modules or
+ * hooks that are not `Send`, the handle must stay on the engine's thread
+ * and must not be added to an engine pool.
+ */
+FactorialEngine *factorial_wrap_borrowed(Engine *engine);
+
+/**
+ * Release a handle from `factorial_wrap_borrowed`, returning control of
+ * the engine to its owner. The engine is not freed. Buffers the handle
+ * handed out are invalid afterwards.
+ *
+ * Returns `InvalidHandle` and leaves the handle untouched if it owns its
+ * engine (use `factorial_destroy` for those). Returns `Poisoned` if a
+ * panic interrupted an earlier call; the handle is still released, but the
+ * engine may be in an inconsistent state.
+ *
+ * # Safety
+ *
+ * `engine` must be a pointer returned by `factorial_wrap_borrowed` that has
+ * not yet been released. After this call the pointer is invalid.
+ */
+enum FactorialResult factorial_unwrap(FactorialEngine *engine);
+
+/**
+ * Advance the simulation by one tick (tick mode) or by `dt` ticks (delta mode).
+ *
+ * Returns `WorkLimitReached` if the step exceeded the work limit set with
+ * `factorial_set_step_work_limit`.
+ *
+ * # Safety
+ *
+ * `engine` must be a valid engine pointer.
+ */
+enum FactorialResult factorial_step(FactorialEngine *engine);
+
+/**
+ * Advance the simulation by `dt` ticks. In tick mode `dt` is ignored
+ * and exactly one step runs. In delta mode, `dt` is accumulated and one
+ * step runs per whole fixed timestep.
+ *
+ * The event buffer is cleared once, before the first step, and then
+ * collects the events of every step this call runs, in order. Each event
+ * carries the tick of the step that emitted it.
+ *
+ * Returns `WorkLimitReached` if a step exceeded the work limit set with
+ * `factorial_set_step_work_limit`. No further steps run in that call.
+ *
+ * # Safety
+ *
+ * `engine` must be a valid engine pointer.
+ */
+enum FactorialResult factorial_advance(FactorialEngine *engine, uint64_t dt);
+
+/**
+ * Open a sliced tick: one step run in pieces by `factorial_continue_tick`,
+ * ending in the same state and events as `factorial_step`. Clears the event
+ * buffer, which collects the tick's events when it completes.
+ *
+ * Until the tick completes, mutating calls return `TickInProgress` and
+ * `factorial_serialize` fails the same way. `factorial_step` and
+ * `factorial_advance` finish the open tick instead of starting another.
+ *
+ * Returns `TickInProgress` if a tick is already open. A paused engine opens
+ * no tick and returns `Ok`, like `factorial_step`.
+ *
+ * # Safety
+ *
+ * `engine` must be a valid engine pointer.
+ */
+enum FactorialResult factorial_begin_tick(FactorialEngine *engine);
+
+/**
+ * Run up to `budget` units of the open sliced tick: the pre-tick phase,
+ * one edge or one node each, the component phase, and the end of the
+ * step each count as one. A budget of 0 counts as 1. Writes whether the
+ * tick completed to `out_status`.
+ *
+ * Returns `WorkLimitReached` when the tick completes over the step work
+ * limit set with `factorial_set_step_work_limit`.
+ *
+ * # Safety
+ *
+ * `engine` and `out_status` must be valid pointers.
+ */
+enum FactorialResult factorial_continue_tick(FactorialEngine *engine,
+                                             uint32_t budget,
+                                             enum FfiTickSliceStatus *out_status);
+
+/**
+ * Limit the work a single step may do; 0 removes the limit.
+ *
+ * Work is counted deterministically from the step's operations (processors
+ * ticked, transports advanced, items delivered, events emitted and so on),
+ * never from wall-clock time. A step over the limit skips its remaining
+ * simulation phases, emits a `StepTruncated` event and makes
+ * `factorial_step` or `factorial_advance` return `WorkLimitReached`.
+ *
+ * # Safety
+ *
+ * `engine` must be a valid engine pointer.
+ */
+enum FactorialResult factorial_set_step_work_limit(FactorialEngine *engine,
+                                                   uint64_t max_work_units);
+
+/**
+ * Switch the simulation strategy between steps, without recreating the
+ * engine. `fixed_timestep` is the ticks per step for `Delta` and ignored
+ * for `Tick`. Factory state and the tick counter are untouched.
+ *
+ * Time accumulated by `factorial_advance` but not yet simulated is kept
+ * when switching to `Delta` and discarded when switching to `Tick`. The
+ * discarded ticks are written to `out_discarded` when it is non-null.
+ *
+ * # Safety
+ *
+ * `engine` must be a valid engine pointer. `out_discarded` must be valid
+ * or null.
+ */
+enum FactorialResult factorial_set_strategy(FactorialEngine *engine,
+                                            enum FfiSimulationStrategy strategy,
+                                            uint64_t fixed_timestep,
+                                            uint64_t *out_discarded);
+
+/**
+ * Read the simulation strategy. `out_fixed_timestep` is 0 in tick mode.
+ *
+ * # Safety
+ *
+ * `engine` and both out pointers must be valid.
+ */
+enum FactorialResult factorial_get_strategy(const FactorialEngine *engine,
+                                            enum FfiSimulationStrategy *out_strategy,
+                                            uint64_t *out_fixed_timestep);
+
+/**
+ * Create an empty engine pool. The caller must eventually call
+ * `factorial_pool_destroy`.
+ *
+ * Returns null on internal error.
+ */
+FactorialEnginePool *factorial_pool_create(void);
+
+/**
+ * Destroy a pool. The engines in it are not destroyed.
+ *
+ * # Safety
+ *
+ * `pool` must be a pointer returned by `factorial_pool_create` that has not
+ * yet been destroyed. After this call the pointer is invalid.
+ */
+enum FactorialResult factorial_pool_destroy(FactorialEnginePool *pool);
+
+/**
+ * Add an engine to a pool. Returns `InvalidHandle` if the engine is already
+ * in the pool.
+ *
+ * # Safety
+ *
+ * `pool` must be a valid pointer. `engine` is not dereferenced here, but
+ * it must be a live engine that stays alive until it is removed from the
+ * pool or the pool is destroyed.
+ */
+enum FactorialResult factorial_pool_add(FactorialEnginePool *pool, FactorialEngine *engine);
+
+/**
+ * Remove an engine from a pool without destroying it. Returns
+ * `InvalidHandle` if the engine is not in the pool.
+ *
+ * # Safety
+ *
+ * `pool` must be a valid pointer. `engine` is only compared, never
+ * dereferenced, so it may already have been destroyed.
+ */
+enum FactorialResult factorial_pool_remove(FactorialEnginePool *pool, FactorialEngine *engine);
+
+/**
+ * Get the number of engines in a pool.
+ *
+ * # Safety
+ *
+ * `pool` and `out_count` must be valid pointers.
+ */
+enum FactorialResult factorial_pool_len(const FactorialEnginePool *pool, uint32_t *out_count);
+
+/**
+ * Step every engine in the pool once, exactly as `factorial_step` would.
+ *
+ * With `thread_count` of 0 or 1 the engines are stepped in order on the
+ * calling thread; otherwise they are split into up to `thread_count`
+ * contiguous groups, each stepped on its own thread. Engines share no
+ * state, so each one's result is the same either way. The call returns
+ * once every engine has stepped.
+ *
+ * If `out_results` is non-null, the result of each engine's step is written
+ * to it in pool order. Returns `Ok` if every engine stepped, otherwise the
+ * first failing engine's result.
+ *
+ * # Safety
+ *
+ * `pool` must be a valid pointer, and every engine in it must be alive and
+ * unused by other threads for the duration of the call. `out_results`, if
+ * non-null, must point to room for one result per engine.
+ */
+enum FactorialResult factorial_pool_step_all(FactorialEnginePool *pool,
+                                             uint32_t thread_count,
+                                             enum FactorialResult *out_results);
+
+/**
+ * Queue a node to be added to the graph. Returns a pending node ID
+ * via `out_pending`. The real node ID is assigned after `factorial_apply_mutations`.
+ *
+ * # Safety
+ *
+ * `engine` and `out_pending` must be valid pointers.
+ */
+enum FactorialResult factorial_add_node(FactorialEngine *engine,
+                                        uint32_t building_type,
+                                        FfiPendingNodeId *out_pending);
+
+/**
+ * Queue a node to be added to the graph with behaviour flags, a bitwise OR
+ * of `NODE_FLAG_*` values. Unknown bits are ignored. Otherwise behaves like
+ * [`factorial_add_node`].
+ *
+ * # Safety
+ *
+ * `engine` and `out_pending` must be valid pointers.
+ */
+enum FactorialResult factorial_add_node_ex(FactorialEngine *engine,
+                                           uint32_t building_type,
+                                           uint32_t flags,
+                                           FfiPendingNodeId *out_pending);
+
+/**
+ * Queue a ghost node: a planned building that is visible to queries and
+ * logistics but does not simulate, costs nothing per tick and is left out
+ * of the state hash. Turn it into a real node with
+ * [`factorial_materialize_ghost`]; it keeps its id and configuration.
+ * Otherwise behaves like [`factorial_add_node`].
+ *
+ * # Safety
+ *
+ * `engine` and `out_pending` must be valid pointers.
+ */
+enum FactorialResult factorial_add_ghost(FactorialEngine *engine,
+                                         uint32_t building_type,
+                                         FfiPendingNodeId *out_pending);
+
+/**
+ * Queue a node for removal.
+ *
+ * # Safety
+ *
+ * `engine` must be a valid engine pointer.
+ */
+enum FactorialResult factorial_remove_node(FactorialEngine *engine, FfiNodeId node_id);
+
+/**
+ * Queue an edge connecting two nodes. Returns a pending edge ID via `out_pending`.
+ *
+ * # Safety
+ *
+ * `engine` and `out_pending` must be valid pointers.
+ */
+enum FactorialResult factorial_connect(FactorialEngine *engine,
+                                       FfiNodeId from_node,
+                                       FfiNodeId to_node,
+                                       FfiPendingEdgeId *out_pending);
+
+/**
+ * Queue a ghost edge between two nodes, either of which may be a ghost.
+ * [`factorial_connect`] refuses ghost endpoints; planned edges use this
+ * instead. Returns a pending edge ID via `out_pending`.
+ *
+ * # Safety
+ *
+ * `engine` and `out_pending` must be valid pointers.
+ */
+enum FactorialResult factorial_connect_ghost(FactorialEngine *engine,
+                                             FfiNodeId from_node,
+                                             FfiNodeId to_node,
+                                             FfiPendingEdgeId *out_pending);
+
+/**
+ * Queue an edge for removal (disconnect).
+ *
+ * # Safety
+ *
+ * `engine` must be a valid engine pointer.
+ */
+enum FactorialResult factorial_disconnect(FactorialEngine *engine, FfiEdgeId edge_id);
+
+/**
+ * Queue a node addition in mutation lane `lane`. `factorial_apply_mutations`
+ * applies lanes in ascending order and calls within a lane in call order,
+ * so the relative order of, say, player actions in lane 0 and automation
+ * in lane 1 does not depend on which was queued first. The other queue
+ * calls use lane 0. Otherwise behaves like [`factorial_add_node`].
+ *
+ * # Safety
+ *
+ * `engine` and `out_pending` must be valid pointers.
+ */
+enum FactorialResult factorial_add_node_in_lane(FactorialEngine *engine,
+                                                uint8_t lane,
+                                                uint32_t building_type,
+                                                FfiPendingNodeId *out_pending);
+
+/**
+ * Queue a node removal in mutation lane `lane`; see
+ * [`factorial_add_node_in_lane`].
+ *
+ * # Safety
+ *
+ * `engine` must be a valid engine pointer.
+ */
+enum FactorialResult factorial_remove_node_in_lane(FactorialEngine *engine,
+                                                   uint8_t lane,
+                                                   FfiNodeId node_id);
+
+/**
+ * Queue an edge in mutation lane `lane`; see [`factorial_add_node_in_lane`].
+ * Returns a pending edge ID via `out_pending`.
+ *
+ * # Safety
+ *
+ * `engine` and `out_pending` must be valid pointers.
+ */
+enum FactorialResult factorial_connect_in_lane(FactorialEngine *engine,
+                                               uint8_t lane,
+                                               FfiNodeId from_node,
+                                               FfiNodeId to_node,
+                                               FfiPendingEdgeId *out_pending);
+
+/**
+ * Queue an edge removal in mutation lane `lane`; see
+ * [`factorial_add_node_in_lane`].
+ *
+ * # Safety
+ *
+ * `engine` must be a valid engine pointer.
+ */
+enum FactorialResult factorial_disconnect_in_lane(FactorialEngine *engine,
+                                                  uint8_t lane,
+                                                  FfiEdgeId edge_id);
+
+/**
+ * Apply all queued graph mutations atomically. Results (pending->real ID
+ * mappings) are written to `out_result`.
+ *
+ * Correlate results with queue calls by pending id only, never by index:
+ * an entry's position does not match the order of the calls that queued
+ * it. `added_nodes` holds the node additions that were applied and
+ * `added_edges` the edges that were created. Node additions refused by a
+ * registered module in strict mode are listed in `rejected` instead, and
+ * edges that could not be added, because an endpoint is missing or a
+ * non-ghost edge touches a ghost node, are left out.
+ *
+ * Each array is in the order the mutations were applied: by ascending lane
+ * (see `factorial_add_node_in_lane`), then in call order within a lane.
+ * Calls without a lane use lane 0. Every pair also carries its lane.
+ *
+ * The resulting graph events are stamped with the upcoming tick and are
+ * returned by `factorial_poll_events` after the next step, not immediately.
+ *
+ * # Safety
+ *
+ * `engine` and `out_result` must be valid pointers. The pointers in the
+ * returned `FfiMutationResult` are valid until the next call to
+ * `factorial_apply_mutations` or `factorial_destroy`.
+ */
+enum FactorialResult factorial_apply_mutations(FactorialEngine *engine,
+                                               struct FfiMutationResult *out_result);
+
+/**
+ * Look up the real node id a pending node resolved to.
+ *
+ * Works for any batch still in the resolution history (see
+ * `factorial_set_resolution_history`), so hosts need not read
+ * `FfiMutationResult` inline. Returns `PendingQueued` before the mutation
+ * is applied, `PendingExpired` once its batch has left the history, and
+ * `PendingUnknown` for ids this engine never issued.
+ *
+ * # Safety
+ *
+ * `engine` and `out_real` must be valid pointers.
+ */
+enum FactorialResult factorial_resolve_pending_node(const FactorialEngine *engine,
+                                                    FfiPendingNodeId pending,
+                                                    FfiNodeId *out_real);
+
+/**
+ * Look up the real edge id a pending edge resolved to. Result codes match
+ * `factorial_resolve_pending_node`.
+ *
+ * # Safety
+ *
+ * `engine` and `out_real` must be valid pointers.
+ */
+enum FactorialResult factorial_resolve_pending_edge(const FactorialEngine *engine,
+                                                    FfiPendingEdgeId pending,
+                                                    FfiEdgeId *out_real);
+
+/**
+ * Set how many applied mutation batches the engine remembers for
+ * `factorial_resolve_pending_node` / `factorial_resolve_pending_edge`.
+ * Only batches that add nodes or edges count. `0` disables the history.
+ *
+ * # Safety
+ *
+ * `engine` must be a valid engine pointer.
+ */
+enum FactorialResult factorial_set_resolution_history(FactorialEngine *engine, uint32_t batches);
+
+/**
+ * Get the number of nodes in the graph.
+ *
+ * # Safety
+ *
+ * `engine` and `out_count` must be valid pointers.
+ */
+enum FactorialResult factorial_node_count(const FactorialEngine *engine, uint32_t *out_count);
+
+/**
+ * Get the number of edges in the graph.
+ *
+ * # Safety
+ *
+ * `engine` and `out_count` must be valid pointers.
+ */
+enum FactorialResult factorial_edge_count(const FactorialEngine *engine, uint32_t *out_count);
+
+/**
+ * Get the topology generation: a counter that changes whenever applied
+ * mutations add or remove nodes or edges. Hosts holding a copy of
+ * `factorial_get_topology` refetch it when this changes, and after changing
+ * an edge's transport to another kind.
+ *
+ * # Safety
+ *
+ * `engine` and `out_generation` must be valid pointers.
+ */
+enum FactorialResult factorial_topology_generation(const FactorialEngine *engine,
+                                                   uint64_t *out_generation);
+
+/**
+ * Copy the graph's adjacency as compressed sparse rows: every node id in
+ * ascending order into `out_nodes`, `node_count + 1` offsets into
+ * `out_offsets`, and the outgoing edges into `out_edges`. The edges of
+ * node `i` are `out_edges[out_offsets[i]..out_offsets[i + 1]]`, in
+ * ascending edge id order.
+ *
+ * Writes the node and edge counts to `*out_node_count` and
+ * `*out_edge_count`. If any buffer is too small, nothing else is written
+ * and `BufferTooSmall` is returned. The engine caches the arrays and only
+ * rebuilds them when the topology generation changes or a transport
+ * changes kind.
+ *
+ * # Safety
+ *
+ * `engine`, `out_node_count` and `out_edge_count` must be valid pointers.
+ * Each buffer must be valid for its length (it may be null when the length
+ * is 0).
+ */
+enum FactorialResult factorial_get_topology(FactorialEngine *engine,
+                                            FfiNodeId *out_nodes,
+                                            uint32_t nodes_len,
+                                            uint32_t *out_offsets,
+                                            uint32_t offsets_len,
+                                            struct FfiTopologyEdge *out_edges,
+                                            uint32_t edges_len,
+                                            uint32_t *out_node_count,
+                                            uint32_t *out_edge_count);
+
+/**
+ * Get the current tick counter.
+ *
+ * # Safety
+ *
+ * `engine` and `out_tick` must be valid pointers.
+ */
+enum FactorialResult factorial_get_tick(const FactorialEngine *engine, uint64_t *out_tick);
+
+/**
+ * Get the state hash of the engine (for desync detection).
+ *
+ * # Safety
+ *
+ * `engine` and `out_hash` must be valid pointers.
+ */
+enum FactorialResult factorial_get_state_hash(const FactorialEngine *engine, uint64_t *out_hash);
+
+/**
+ * Get the most recent state hash split by subsystem, so two desynced peers
+ * can tell which subsystem diverged.
+ *
+ * # Safety
+ *
+ * `engine` and `out_breakdown` must be valid pointers.
+ */
+enum FactorialResult factorial_get_state_hash_breakdown(const FactorialEngine *engine,
+                                                        struct FfiHashBreakdown *out_breakdown);
+
+/**
+ * Get the enabled extensions as a bit mask: bit `n` is set when the
+ * extension with id `n` is enabled (0 logic, 1 fluid, 2 stats, 3 tech tree,
+ * 4 power, 5 spatial, 6 crafting, 7 spoilage).
+ *
+ * # Safety
+ *
+ * `engine` and `out_mask` must be valid pointers.
+ */
+enum FactorialResult factorial_enabled_extensions(const FactorialEngine *engine,
+                                                  uint32_t *out_mask);
+
+/**
+ * Enable an extension by id (see `factorial_enabled_extensions`), for
+ * hosts that run an extension outside the engine and want saves and state
+ * hashes to record it.
+ *
+ * Returns `InvalidArgument` for an unknown id.
+ *
+ * # Safety
+ *
+ * `engine` must be a valid engine pointer.
+ */
+enum FactorialResult factorial_enable_extension(FactorialEngine *engine, uint32_t extension);
+
+/**
+ * Count every unit of `item_type` in node inventories and transports.
+ *
+ * # Safety
+ *
+ * `engine` and `out_census` must be valid pointers.
+ */
+enum FactorialResult factorial_item_census(const FactorialEngine *engine,
+                                           uint32_t item_type,
+                                           struct FfiItemCensus *out_census);
+
+/**
+ * Summarise every item type currently involved in a stall, one record per
+ * item type in ascending item-type order. See `Engine::bottleneck_digest`.
+ *
+ * # Safety
+ *
+ * `engine` and `out_buffer` must be valid pointers. The pointer in the
+ * returned buffer is valid until the next call to this function.
+ */
+enum FactorialResult factorial_get_bottleneck_digest(const FactorialEngine *engine,
+                                                     struct FfiBottleneckBuffer *out_buffer);
+
+/**
+ * Get the processor state for a node.
+ *
+ * # Safety
+ *
+ * `engine` and `out_info` must be valid pointers.
+ */
+enum FactorialResult factorial_get_processor_state(const FactorialEngine *engine,
+                                                   FfiNodeId node_id,
+                                                   struct FfiProcessorInfo *out_info);
+
+/**
+ * Get the total item count in a node's input inventory.
+ *
+ * # Safety
+ *
+ * `engine` and `out_count` must be valid pointers.
+ */
+enum FactorialResult factorial_get_input_inventory_count(const FactorialEngine *engine,
+                                                         FfiNodeId node_id,
+                                                         uint32_t *out_count);
+
+/**
+ * Get the total item count in a node's output inventory.
+ *
+ * # Safety
+ *
+ * `engine` and `out_count` must be valid pointers.
+ */
+enum FactorialResult factorial_get_output_inventory_count(const FactorialEngine *engine,
+                                                          FfiNodeId node_id,
+                                                          uint32_t *out_count);
+
+/**
+ * List the items currently on a belt edge, with stable instance ids for
+ * animation. Non-belt transports report zero items.
+ *
+ * Returns `EdgeNotFound` if the edge has no transport.
+ *
+ * # Safety
+ *
+ * `engine` and `out_buffer` must be valid pointers. The pointer in the
+ * returned buffer is valid until the next call to this function.
+ */
+enum FactorialResult factorial_get_transport_items(const FactorialEngine *engine,
+                                                   FfiEdgeId edge_id,
+                                                   struct FfiTransportItemBuffer *out_buffer);
+
+/**
+ * Write the delivery accumulator of a flow edge to `out_value` as raw
+ * Fixed64 bits: the fraction of an item drained from the buffer but not yet
+ * delivered. Always in `[0, 1)`.
+ *
+ * Returns `EdgeNotFound` if the edge has no flow transport.
+ *
+ * # Safety
+ *
+ * `engine` and `out_value` must be valid pointers.
+ */
+enum FactorialResult factorial_get_flow_accumulator(const FactorialEngine *engine,
+                                                    FfiEdgeId edge_id,
+                                                    int64_t *out_value);
+
+/**
+ * Write the number of items currently moving along an edge to `out_count`:
+ * belt items, whole items in a flow buffer, a batch's pending items, or a
+ * vehicle's cargo.
+ *
+ * Returns `EdgeNotFound` if the edge has no transport.
+ *
+ * # Safety
+ *
+ * `engine` and `out_count` must be valid pointers.
+ */
+enum FactorialResult factorial_get_transport_in_transit(const FactorialEngine *engine,
+                                                        FfiEdgeId edge_id,
+                                                        uint32_t *out_count);
+
+/**
+ * Write where an edge's transport is in its delivery cycle to `out`: buffer
+ * fill, belt head and next arrival, batch fill and dispatch countdown, or
+ * vehicle phase. Tick counts assume the destination has room; a count of
+ * `n` means the delivery or phase change happens during the `n`th
+ * `factorial_step` from now.
+ *
+ * Returns `EdgeNotFound` if the edge has no transport.
+ *
+ * # Safety
+ *
+ * `engine` and `out` must be valid pointers.
+ */
+enum FactorialResult factorial_get_transport_progress(const FactorialEngine *engine,
+                                                      FfiEdgeId edge_id,
+                                                      struct FfiTransportProgress *out);
+
+/**
+ * Create a read-only inspector for `engine`. The caller must eventually
+ * call `factorial_destroy_inspector`.
+ *
+ * Returns null if `engine` is null or on internal error.
+ *
+ * # Safety
+ *
+ * `engine` must be null or a valid engine pointer.
+ */
+const FactorialEngineInspector *factorial_create_inspector(const FactorialEngine *engine);
+
+/**
+ * Free an inspector. Its engine is not affected.
+ *
+ * # Safety
+ *
+ * `inspector` must be a pointer returned by `factorial_create_inspector`
+ * that has not yet been destroyed. After this call the pointer is invalid.
+ */
+enum FactorialResult factorial_destroy_inspector(const FactorialEngineInspector *inspector);
+
+/**
+ * Get the number of nodes in the graph. Inspector version of [`factorial_node_count`].
+ *
+ * # Safety
+ *
+ * `inspector` and `out_count` must be valid pointers.
+ */
+enum FactorialResult factorial_inspector_node_count(const FactorialEngineInspector *inspector,
+                                                    uint32_t *out_count);
+
+/**
+ * Get the number of edges in the graph. Inspector version of [`factorial_edge_count`].
+ *
+ * # Safety
+ *
+ * `inspector` and `out_count` must be valid pointers.
+ */
+enum FactorialResult factorial_inspector_edge_count(const FactorialEngineInspector *inspector,
+                                                    uint32_t *out_count);
+
+/**
+ * Get the current tick. Inspector version of [`factorial_get_tick`].
+ *
+ * # Safety
+ *
+ * `inspector` and `out_tick` must be valid pointers.
+ */
+enum FactorialResult factorial_inspector_get_tick(const FactorialEngineInspector *inspector,
+                                                  uint64_t *out_tick);
+
+/**
+ * Get the state hash of the most recent step. Inspector version of [`factorial_get_state_hash`].
+ *
+ * # Safety
+ *
+ * `inspector` and `out_hash` must be valid pointers.
+ */
+enum FactorialResult factorial_inspector_get_state_hash(const FactorialEngineInspector *inspector,
+                                                        uint64_t *out_hash);
+
+/**
+ * Get the most recent state hash split by subsystem. Inspector version of [`factorial_get_state_hash_breakdown`].
+ *
+ * # Safety
+ *
+ * `inspector` and `out_breakdown` must be valid pointers.
+ */
+enum FactorialResult factorial_inspector_get_state_hash_breakdown(const FactorialEngineInspector *inspector,
+                                                                  struct FfiHashBreakdown *out_breakdown);
+
+/**
+ * Count every unit of an item type in the factory. Inspector version of [`factorial_item_census`].
+ *
+ * # Safety
+ *
+ * `inspector` and `out_census` must be valid pointers.
+ */
+enum FactorialResult factorial_inspector_item_census(const FactorialEngineInspector *inspector,
+                                                     uint32_t item_type,
+                                                     struct FfiItemCensus *out_census);
+
+/**
+ * Get the processor state of a node. Inspector version of [`factorial_get_processor_state`].
+ *
+ * # Safety
+ *
+ * `inspector` and `out_info` must be valid pointers.
+ */
+enum FactorialResult factorial_inspector_get_processor_state(const FactorialEngineInspector *inspector,
+                                                             FfiNodeId node_id,
+                                                             struct FfiProcessorInfo *out_info);
+
+/**
+ * Get the total item count in a node's input inventory. Inspector version of [`factorial_get_input_inventory_count`].
+ *
+ * # Safety
+ *
+ * `inspector` and `out_count` must be valid pointers.
+ */
+enum FactorialResult factorial_inspector_get_input_inventory_count(const FactorialEngineInspector *inspector,
+                                                                   FfiNodeId node_id,
+                                                                   uint32_t *out_count);
+
+/**
+ * Get the total item count in a node's output inventory. Inspector version of [`factorial_get_output_inventory_count`].
+ *
+ * # Safety
+ *
+ * `inspector` and `out_count` must be valid pointers.
+ */
+enum FactorialResult factorial_inspector_get_output_inventory_count(const FactorialEngineInspector *inspector,
+                                                                    FfiNodeId node_id,
+                                                                    uint32_t *out_count);
+
+/**
+ * Get the simulation status of a node. Inspector version of [`factorial_get_node_status`].
+ *
+ * # Safety
+ *
+ * `inspector` and `out_status` must be valid pointers.
+ */
+enum FactorialResult factorial_inspector_get_node_status(const FactorialEngineInspector *inspector,
+                                                         FfiNodeId node_id,
+                                                         enum FfiNodeStatus *out_status);
+
+/**
+ * Get the simulation status of an edge. Inspector version of [`factorial_get_edge_status`].
+ *
+ * # Safety
+ *
+ * `inspector` and `out_status` must be valid pointers.
+ */
+enum FactorialResult factorial_inspector_get_edge_status(const FactorialEngineInspector *inspector,
+                                                         FfiEdgeId edge_id,
+                                                         enum FfiNodeStatus *out_status);
+
+/**
+ * Poll all buffered events since the last step. Returns a pointer to an
+ * engine-owned buffer of `FfiEvent` structs. The buffer is valid until the
+ * next `factorial_step`, `factorial_advance`, or `factorial_destroy`.
+ *
+ * After a `factorial_advance` that ran several steps, the buffer holds the
+ * events of all of them in tick order, not just the last.
+ *
+ * # Safety
+ *
+ * `engine` and `out_buffer` must be valid pointers.
+ */
+enum FactorialResult factorial_poll_events(const FactorialEngine *engine,
+                                           struct FfiEventBuffer *out_buffer);
+
+/**
+ * Encode the events `factorial_poll_events` would return in the compact,
+ * versioned network format (readable with `Engine::decode_events_compact`).
+ * Each event is a tag plus only the fields it carries, so the buffer is far
+ * smaller than the flat `FfiEvent` array.
+ *
+ * The caller must free the buffer with `factorial_free_buffer`.
+ *
+ * # Safety
+ *
+ * `engine` and `out_buffer` must be valid pointers.
+ */
+enum FactorialResult factorial_encode_events_compact(const FactorialEngine *engine,
+                                                     struct FfiByteBuffer *out_buffer);
+
+/**
+ * Set the maximum number of events cached for `factorial_poll_events`
+ * per step. Once the cache holds `max_events`, further events are counted
+ * by `factorial_dropped_event_count` instead of being cached. The cap only
+ * affects observation; the simulation itself is unchanged. Defaults to
+ * `DEFAULT_EVENT_CACHE_LIMIT`.
+ *
+ * # Safety
+ *
+ * `engine` must be a valid engine pointer.
+ */
+enum FactorialResult factorial_set_event_cache_limit(FactorialEngine *engine, uint32_t max_events);
+
+/**
+ * Get the number of events dropped by the event cache limit since the last
+ * step. A non-zero count means the buffer from `factorial_poll_events` is
+ * incomplete.
+ *
+ * # Safety
+ *
+ * `engine` and `out_count` must be valid pointers.
+ */
+enum FactorialResult factorial_dropped_event_count(const FactorialEngine *engine,
+                                                   uint64_t *out_count);
+
+/**
+ * Retain the last `capacity` events across steps for
+ * `factorial_poll_event_history`. Once full, the oldest events are
+ * dropped. A capacity of 0 disables the history.
+ *
+ * # Safety
+ *
+ * `engine` must be a valid engine pointer.
+ */
+enum FactorialResult factorial_set_event_history_capacity(FactorialEngine *engine,
+                                                          uint32_t capacity);
+
+/**
+ * Copy retained history events stamped at or after `since_tick` into the
+ * caller-provided `out_events` array, oldest first, writing at most `max`
+ * events. The number written is stored in `*out_written`. Unlike
+ * `factorial_poll_events`, the history is not cleared by stepping.
+ *
+ * # Safety
+ *
+ * `engine` and `out_written` must be valid pointers. `out_events` must point
+ * to at least `max` `FfiEvent` slots (it may be null when `max` is 0).
+ */
+enum FactorialResult factorial_poll_event_history(const FactorialEngine *engine,
+                                                  uint64_t since_tick,
+                                                  struct FfiEvent *out_events,
+                                                  uint32_t max,
+                                                  uint32_t *out_written);
+
+/**
+ * Copy retained history events matching `filter` into `out_events`, oldest
+ * first, writing at most `max` events. The number written is stored in
+ * `*out_count`. A null `filter` matches every event.
+ *
+ * # Safety
+ *
+ * `engine` and `out_count` must be valid pointers. `filter` must be null or
+ * valid. `out_events` must point to at least `max` `FfiEvent` slots (it may
+ * be null when `max` is 0).
+ */
+enum FactorialResult factorial_query_event_history(const FactorialEngine *engine,
+                                                   const struct FfiEventFilter *filter,
+                                                   struct FfiEvent *out_events,
+                                                   uint32_t max,
+                                                   uint32_t *out_count);
+
+/**
+ * Create a cursor over the event history that reads events matching
+ * `filter` (null matches every event), starting from the oldest retained
+ * event. Each cursor keeps its own position and does not affect
+ * `factorial_poll_events`. Cursors are not serialized. The cursor id is
+ * written to `out_cursor`.
+ *
+ * # Safety
+ *
+ * `engine` and `out_cursor` must be valid pointers. `filter` must be null
+ * or valid.
+ */
+enum FactorialResult factorial_create_event_cursor(FactorialEngine *engine,
+                                                   const struct FfiEventFilter *filter,
+                                                   FfiEventCursorId *out_cursor);
+
+/**
+ * Destroy an event cursor.
+ *
+ * # Safety
+ *
+ * `engine` must be a valid engine pointer.
+ */
+enum FactorialResult factorial_destroy_event_cursor(FactorialEngine *engine,
+                                                    FfiEventCursorId cursor);
+
+/**
+ * Copy up to `max` history events matching the cursor's filter that were
+ * recorded after its position into `out_events`, oldest first, and advance
+ * the cursor past them. The number written is stored in `*out_count`.
+ * `*out_lagged` is set if the history overwrote events the cursor had not
+ * read; the cursor then resumed from the oldest retained event.
+ *
+ * # Safety
+ *
+ * `engine`, `out_count` and `out_lagged` must be valid pointers.
+ * `out_events` must point to at least `max` `FfiEvent` slots (it may be
+ * null when `max` is 0).
+ */
+enum FactorialResult factorial_read_event_cursor(FactorialEngine *engine,
+                                                 FfiEventCursorId cursor,
+                                                 struct FfiEvent *out_events,
+                                                 uint32_t max,
+                                                 uint32_t *out_count,
+                                                 bool *out_lagged);
+
+/**
+ * Include the event history in `factorial_serialize` output. Off by
+ * default; a snapshot that carries a history restores it on load.
+ *
+ * # Safety
+ *
+ * `engine` must be a valid engine pointer.
+ */
+enum FactorialResult factorial_set_serialize_event_history(FactorialEngine *engine, bool enabled);
+
+/**
+ * Start recording an event log. Every tick's events are kept until taken
+ * with `factorial_take_event_log`, independent of the poll buffer. No-op if
+ * already enabled.
+ *
+ * # Safety
+ *
+ * `engine` must be a valid engine pointer.
+ */
+enum FactorialResult factorial_enable_event_log(FactorialEngine *engine);
+
+/**
+ * Stop recording the event log and discard anything not yet taken.
+ *
+ * # Safety
+ *
+ * `engine` must be a valid engine pointer.
+ */
+enum FactorialResult factorial_disable_event_log(FactorialEngine *engine);
+
+/**
+ * Take the event log recorded since the last take, encoded in the
+ * versioned `EventLog` byte format (readable with `EventLog::from_bytes`).
+ * Recording stays enabled. If the log is not enabled, an empty log is
+ * returned.
+ *
+ * The caller must free the buffer with `factorial_free_buffer`.
+ *
+ * # Safety
+ *
+ * `engine` and `out_buffer` must be valid pointers.
+ */
+enum FactorialResult factorial_take_event_log(FactorialEngine *engine,
+                                              struct FfiByteBuffer *out_buffer);
+
+/**
+ * Serialize the engine state to a binary buffer. The returned
+ * `FfiByteBuffer` contains a pointer and length. The caller must free the
+ * buffer with `factorial_free_buffer` when done.
+ *
+ * # Safety
+ *
+ * `engine` and `out_buffer` must be valid pointers.
+ */
+enum FactorialResult factorial_serialize(const FactorialEngine *engine,
+                                         struct FfiByteBuffer *out_buffer);
+
+/**
+ * Deserialize an engine from a binary buffer. Returns a new engine pointer
+ * via `out_engine`. The caller takes ownership.
+ *
+ * # Safety
+ *
+ * `data` must point to `len` valid bytes. `out_engine` must be a valid pointer.
+ */
+enum FactorialResult factorial_deserialize(const uint8_t *data,
+                                           uintptr_t len,
+                                           FactorialEngine **out_engine);
+
+/**
+ * Capture the engine as a scenario bundle. `flags` is a bitwise OR of the
+ * `BUNDLE_*` components to include alongside it; only
+ * [`BUNDLE_TECH_TREE`] applies to an FFI engine, and only if a tech tree is
+ * registered on it. The caller must free the buffer with
+ * `factorial_free_buffer`.
+ *
+ * # Safety
+ *
+ * `engine` and `out_buffer` must be valid pointers.
+ */
+enum FactorialResult factorial_bundle_capture(const FactorialEngine *engine,
+                                              uint32_t flags,
+                                              struct FfiByteBuffer *out_buffer);
+
+/**
+ * Restore a scenario bundle into a new engine, returned via `out_engine`.
+ * The caller takes ownership. A tech tree that was registered on the
+ * captured engine is registered on the new one. `out_components` receives
+ * the `BUNDLE_*` components the bundle holds; standalone tech trees, fluid
+ * modules and statistics configurations belong to Rust hosts and are not
+ * restored here. Unknown sections are skipped.
+ *
+ * # Safety
+ *
+ * `data` must point to `len` valid bytes. `out_engine` and
+ * `out_components` must be valid pointers.
+ */
+enum FactorialResult factorial_bundle_restore(const uint8_t *data,
+                                              uintptr_t len,
+                                              FactorialEngine **out_engine,
+                                              uint32_t *out_components);
+
+/**
+ * Read the summary of a buffer produced by `factorial_serialize` without
+ * deserializing the engine. Saves without a summary section are fully
+ * decoded. Only the summary section is validated: a corrupt body is not
+ * noticed, a checksum mismatch returns `FACTORIAL_RESULT_DESERIALIZE_ERROR`.
+ *
+ * # Safety
+ *
+ * `data` must point to `len` valid bytes. `out_summary` must be a valid
+ * pointer.
+ */
+enum FactorialResult factorial_inspect(const uint8_t *data,
+                                       uintptr_t len,
+                                       struct FfiSaveSummary *out_summary);
+
+/**
+ * Record per-item totals in the summary section of every
+ * `factorial_serialize` output, for `factorial_inspect`. Off by default.
+ *
+ * # Safety
+ *
+ * `engine` must be a valid engine pointer.
+ */
+enum FactorialResult factorial_set_summary_item_totals(FactorialEngine *engine, bool enabled);
+
+/**
+ * Free a byte buffer returned by `factorial_serialize` or `factorial_take_event_log`.
+ *
+ * # Safety
+ *
+ * `buffer` must be a buffer originally returned by `factorial_serialize` or
+ * `factorial_take_event_log`.
+ * After this call the buffer's data pointer is invalid.
+ */
+enum FactorialResult factorial_free_buffer(struct FfiByteBuffer buffer);
+
+/**
+ * Set a node's processor to Source.
+ *
+ * `rate` is raw Fixed64 bits (Q32.32). Use `Fixed64::to_bits()` on the Rust
+ * side or shift an integer left by 32 on the C side to construct it.
+ *
+ * # Safety
+ *
+ * `engine` must be a valid engine pointer.
+ */
+enum FactorialResult factorial_set_source(FactorialEngine *engine,
+                                          FfiNodeId node_id,
+                                          uint32_t item_type,
+                                          int64_t rate);
+
+/**
+ * Read back a Source processor's configuration. `out_rate` receives raw
+ * Fixed64 bits.
+ *
+ * Returns `NodeNotFound` if the node has no processor and `KindMismatch`
+ * if its processor is not a Source.
+ *
+ * # Safety
+ *
+ * `engine` and all out pointers must be valid.
+ */
+enum FactorialResult factorial_get_source(const FactorialEngine *engine,
+                                          FfiNodeId node_id,
+                                          uint32_t *out_item,
+                                          int64_t *out_rate,
+                                          struct FfiDepletion *out_depletion);
+
+/**
+ * Read back a node's processor: its kind and parameters. The recipe of a
+ * `Fixed` processor, or the active recipe of a `MultiRecipe` one, is
+ * described by `duration` and the item stacks copied into the caller's
+ * `inputs` and `outputs` buffers, in recipe order. Fluid ingredients and
+ * output chances are not reported.
+ *
+ * The counts are always written. If either buffer is too small, no stacks
+ * are copied and `BufferTooSmall` is returned; retry with larger buffers.
+ * Returns `NodeNotFound` if the node has no processor.
+ *
+ * # Safety
+ *
+ * `engine` and `out_config` must be valid pointers. `out_config.inputs`
+ * and `out_config.outputs` must be valid for their capacities (they may be
+ * null when the capacity is 0).
+ */
+enum FactorialResult factorial_get_processor_config(const FactorialEngine *engine,
+                                                    FfiNodeId node_id,
+                                                    struct FfiProcessorConfig *out_config);
+
+/**
+ * Set a node's processor to MultiSource: one deposit yielding every item
+ * in `outputs`, each at its own rate, with every extracted item counting
+ * against the shared `depletion`.
+ *
+ * # Safety
+ *
+ * `engine` must be a valid engine pointer and `outputs` must be valid for
+ * `output_count` elements.
+ */
+enum FactorialResult factorial_set_multi_source(FactorialEngine *engine,
+                                                FfiNodeId node_id,
+                                                const struct FfiSourceOutput *outputs,
+                                                uint32_t output_count,
+                                                struct FfiDepletion depletion);
+
+/**
+ * Install the seeded noise field used by `factorial_add_source_at`.
+ * Sources already added keep their configuration.
+ *
+ * # Safety
+ *
+ * `engine` and `curve` must be valid pointers.
+ */
+enum FactorialResult factorial_set_richness_provider(FactorialEngine *engine,
+                                                     uint64_t seed,
+                                                     const struct FfiRichnessCurve *curve);
+
+/**
+ * Make a node a Source whose rate and deposit are read from the richness
+ * provider at `(x, y)`. `out_site` may be null; otherwise it receives the
+ * recorded site.
+ *
+ * Returns `InvalidArgument` if no provider is installed and `NodeNotFound`
+ * if the node does not exist.
+ *
+ * # Safety
+ *
+ * `engine` must be a valid engine pointer; `out_site` must be null or
+ * valid.
+ */
+enum FactorialResult factorial_add_source_at(FactorialEngine *engine,
+                                             FfiNodeId node_id,
+                                             uint32_t item_type,
+                                             int32_t x,
+                                             int32_t y,
+                                             struct FfiSourceSite *out_site);
+
+/**
+ * Read the coordinates and richness of a source added with
+ * `factorial_add_source_at`.
+ *
+ * Returns `NodeNotFound` if the node has no such source, including after
+ * its processor was replaced.
+ *
+ * # Safety
+ *
+ * `engine` and `out_site` must be valid pointers.
+ */
+enum FactorialResult factorial_get_source_site(const FactorialEngine *engine,
+                                               FfiNodeId node_id,
+                                               struct FfiSourceSite *out_site);
+
+/**
+ * Set a node's processor to FixedRecipe.
+ *
+ * The `recipe` pointer must point to a valid `FfiRecipe` whose `inputs` and
+ * `outputs` arrays have the declared counts.
+ *
+ * # Safety
+ *
+ * `engine` and `recipe` must be valid pointers. The arrays referenced by
+ * `recipe.inputs` and `recipe.outputs` must be valid for the declared counts.
+ */
+enum FactorialResult factorial_set_fixed_processor(FactorialEngine *engine,
+                                                   FfiNodeId node_id,
+                                                   const struct FfiRecipe *recipe);
+
+/**
+ * Give each of `count` nodes a FixedRecipe processor in one call, as
+ * [`factorial_set_fixed_processor`] does for one.
+ *
+ * Each assignment is applied independently, in order. `out_results`
+ * receives one result per assignment: `Ok`, or `NodeNotFound` for a node
+ * not in the graph, which leaves the rest unaffected. The call itself
+ * returns `Ok` unless its arguments are invalid.
+ *
+ * # Safety
+ *
+ * `engine` must be a valid pointer. `assignments` and `out_results` must
+ * be valid for `count` elements (they may be null when `count` is 0), and
+ * every recipe's arrays must be valid for their declared counts.
+ */
+enum FactorialResult factorial_set_processors_bulk(FactorialEngine *engine,
+                                                   const struct FfiProcessorAssignment *assignments,
+                                                   uint32_t count,
+                                                   enum FactorialResult *out_results);
+
+/**
+ * Set a node's processor to a FixedRecipe with fluid ingredients.
+ *
+ * Fluid inputs are drawn from the node's fluid buffer when a cycle starts
+ * and fluid outputs are added to it when a cycle completes. Fill and drain
+ * the buffer with [`factorial_add_fluid_input`] and
+ * [`factorial_take_fluid_output`].
+ *
+ * # Safety
+ *
+ * `engine` and `recipe` must be valid pointers. Every array referenced by
+ * `recipe` must be valid for its declared count.
+ */
+enum FactorialResult factorial_set_fixed_processor_v2(FactorialEngine *engine,
+                                                      FfiNodeId node_id,
+                                                      const struct FfiRecipeV2 *recipe);
+
+/**
+ * Set a node's processor to a FixedRecipe with fluid ingredients and
+ * chance-based outputs.
+ *
+ * Like [`factorial_set_fixed_processor_v2`]. When a cycle completes, each
+ * output with a chance is rolled on the node's deterministic RNG, in output
+ * order, and only produced outputs reach the output inventory and events.
+ *
+ * # Safety
+ *
+ * `engine` and `recipe` must be valid pointers. Every array referenced by
+ * `recipe` must be valid for its declared count; `output_probabilities`,
+ * when non-null, for `output_count` elements.
+ */
+enum FactorialResult factorial_set_fixed_processor_v3(FactorialEngine *engine,
+                                                      FfiNodeId node_id,
+                                                      const struct FfiRecipeV3 *recipe);
+
+/**
+ * Set a node's processor to a flexible processor that picks one of
+ * `recipe_count` registry recipes each time a cycle starts, from the
+ * ingredients in its input inventory.
+ *
+ * `selection` is 0 = first match in list order, 1 = the recipe with the
+ * largest batch available. Returns `InvalidArgument` for any other value.
+ * Recipes are looked up in the engine's registry; ids it does not contain
+ * are never selected.
+ *
+ * # Safety
+ *
+ * `engine` must be a valid pointer. `recipes` must be valid for
+ * `recipe_count` elements when `recipe_count` is non-zero.
+ */
+enum FactorialResult factorial_set_flexible_processor(FactorialEngine *engine,
+                                                      FfiNodeId node_id,
+                                                      const uint32_t *recipes,
+                                                      uint32_t recipe_count,
+                                                      uint32_t selection);
+
+/**
+ * Write the registry recipe a flexible node is crafting to `out_recipe`,
+ * or [`NO_RECIPE`] if it is not mid-cycle.
+ *
+ * Returns `NodeNotFound` if the node has no processor and `KindMismatch`
+ * if its processor is not flexible.
+ *
+ * # Safety
+ *
+ * `engine` and `out_recipe` must be valid pointers.
+ */
+enum FactorialResult factorial_get_active_recipe(const FactorialEngine *engine,
+                                                 FfiNodeId node_id,
+                                                 uint32_t *out_recipe);
+
+/**
+ * Turn a node into a storage node (chest, warehouse) with `slot_count`
+ * slots of `slot_capacity` items each, all usable.
+ *
+ * Storage keeps its items in the output inventory, which this call
+ * replaces: incoming transports deliver there and outgoing transports take
+ * from there. With `read_signal` set, the node publishes its contents on
+ * the logic networks it belongs to every tick. Returns `InvalidArgument` if
+ * `slot_count` is 0.
+ *
+ * # Safety
+ *
+ * `engine` must be a valid engine pointer.
+ */
+enum FactorialResult factorial_set_storage_processor(FactorialEngine *engine,
+                                                     FfiNodeId node_id,
+                                                     uint32_t slot_count,
+                                                     uint32_t slot_capacity,
+                                                     bool read_signal);
+
+/**
+ * Limit how many of a storage node's slots accept deliveries, counted from
+ * the first, or pass [`NO_STORAGE_LIMIT`] to lift the limit. Items already
+ * in slots past the limit stay there and can still be taken out.
+ *
+ * Returns `NodeNotFound` if the node has no processor and `KindMismatch`
+ * if its processor is not storage.
+ *
+ * # Safety
+ *
+ * `engine` must be a valid engine pointer.
+ */
+enum FactorialResult factorial_set_storage_limit(FactorialEngine *engine,
+                                                 FfiNodeId node_id,
+                                                 uint32_t usable_slots);
+
+/**
+ * Replace the definition of a recipe in a running engine, for hot-reloading
+ * recipe data. Flexible nodes use the new definition the next time they
+ * select the recipe.
+ *
+ * `policy` decides what happens to crafts of the recipe already in
+ * progress: 0 = finish them under the old definition, 1 = cancel them and
+ * refund their consumed ingredients to the input inventory. Returns
+ * `InvalidArgument` for any other value.
+ *
+ * # Safety
+ *
+ * `engine` and `recipe` must be valid pointers. The arrays referenced by
+ * `recipe.inputs` and `recipe.outputs` must be valid for the declared counts.
+ */
+enum FactorialResult factorial_update_recipe(FactorialEngine *engine,
+                                             uint32_t recipe_id,
+                                             const struct FfiRecipe *recipe,
+                                             uint32_t policy);
+
+/**
+ * Deliver fluid to a node's recipe fluid buffer.
+ *
+ * `amount` is raw Fixed64 bits. The buffer accepts at most one cycle's
+ * worth of the active recipe's fluid input; the accepted amount (raw
+ * bits) is written to `out_accepted`.
+ *
+ * # Safety
+ *
+ * `engine` and `out_accepted` must be valid pointers.
+ */
+enum FactorialResult factorial_add_fluid_input(FactorialEngine *engine,
+                                               FfiNodeId node_id,
+                                               uint32_t fluid,
+                                               int64_t amount,
+                                               int64_t *out_accepted);
+
+/**
+ * Remove up to `max` (raw Fixed64 bits) of a fluid from a node's recipe
+ * output buffer. The amount removed (raw bits) is written to `out_taken`.
+ *
+ * # Safety
+ *
+ * `engine` and `out_taken` must be valid pointers.
+ */
+enum FactorialResult factorial_take_fluid_output(FactorialEngine *engine,
+                                                 FfiNodeId node_id,
+                                                 uint32_t fluid,
+                                                 int64_t max,
+                                                 int64_t *out_taken);
+
+/**
+ * Set an edge's transport to FlowTransport with default buffer/latency.
+ *
+ * `rate` is raw Fixed64 bits (Q32.32).
+ *
+ * # Safety
+ *
+ * `engine` must be a valid engine pointer.
+ */
+enum FactorialResult factorial_set_flow_transport(FactorialEngine *engine,
+                                                  FfiEdgeId edge_id,
+                                                  int64_t rate);
+
+/**
+ * Set an edge's transport to ItemTransport.
+ *
+ * `speed` is raw Fixed64 bits (Q32.32).
+ *
+ * # Safety
+ *
+ * `engine` must be a valid engine pointer.
+ */
+enum FactorialResult factorial_set_item_transport(FactorialEngine *engine,
+                                                  FfiEdgeId edge_id,
+                                                  int64_t speed,
+                                                  uint32_t slot_count,
+                                                  uint8_t lanes);
+
+/**
+ * Set an edge's transport to BatchTransport.
+ *
+ * # Safety
+ *
+ * `engine` must be a valid engine pointer.
+ */
+enum FactorialResult factorial_set_batch_transport(FactorialEngine *engine,
+                                                   FfiEdgeId edge_id,
+                                                   uint32_t batch_size,
+                                                   uint32_t cycle_time);
+
+/**
+ * Set an edge's transport to VehicleTransport.
+ *
+ * # Safety
+ *
+ * `engine` must be a valid engine pointer.
+ */
+enum FactorialResult factorial_set_vehicle_transport(FactorialEngine *engine,
+                                                     FfiEdgeId edge_id,
+                                                     uint32_t capacity,
+                                                     uint32_t travel_time);
+
+/**
+ * Read back a FlowTransport's configuration. `out_rate` and
+ * `out_capacity` (the buffer capacity) receive raw Fixed64 bits.
+ *
+ * Returns `EdgeNotFound` if the edge has no transport and `KindMismatch`
+ * if it is not a FlowTransport.
+ *
+ * # Safety
+ *
+ * `engine` and all out pointers must be valid.
+ */
+enum FactorialResult factorial_get_flow_transport(const FactorialEngine *engine,
+                                                  FfiEdgeId edge_id,
+                                                  int64_t *out_rate,
+                                                  int64_t *out_capacity,
+                                                  uint32_t *out_latency);
+
+/**
+ * Read back an ItemTransport's configuration. `out_speed` receives raw
+ * Fixed64 bits.
+ *
+ * Returns `EdgeNotFound` if the edge has no transport and `KindMismatch`
+ * if it is not an ItemTransport.
+ *
+ * # Safety
+ *
+ * `engine` and all out pointers must be valid.
+ */
+enum FactorialResult factorial_get_item_transport(const FactorialEngine *engine,
+                                                  FfiEdgeId edge_id,
+                                                  int64_t *out_speed,
+                                                  uint32_t *out_slot_count,
+                                                  uint8_t *out_lanes);
+
+/**
+ * Read back a BatchTransport's configuration.
+ *
+ * Returns `EdgeNotFound` if the edge has no transport and `KindMismatch`
+ * if it is not a BatchTransport.
+ *
+ * # Safety
+ *
+ * `engine` and all out pointers must be valid.
+ */
+enum FactorialResult factorial_get_batch_transport(const FactorialEngine *engine,
+                                                   FfiEdgeId edge_id,
+                                                   uint32_t *out_batch_size,
+                                                   uint32_t *out_cycle_time);
+
+/**
+ * Read back a VehicleTransport's configuration.
+ *
+ * Returns `EdgeNotFound` if the edge has no transport and `KindMismatch`
+ * if it is not a VehicleTransport.
+ *
+ * # Safety
+ *
+ * `engine` and all out pointers must be valid.
+ */
+enum FactorialResult factorial_get_vehicle_transport(const FactorialEngine *engine,
+                                                     FfiEdgeId edge_id,
+                                                     uint32_t *out_capacity,
+                                                     uint32_t *out_travel_time);
+
+/**
+ * Apply one transport configuration to `count` edges in a single call.
+ *
+ * Each edge gets its own fresh transport state, exactly as if the matching
+ * single-edge setter had been called for it.
+ *
+ * # Safety
+ *
+ * `engine` and `config` must be valid pointers. `edges` must point to
+ * `count` edge ids (it may be null when `count` is 0).
+ */
+enum FactorialResult factorial_set_transport_bulk(FactorialEngine *engine,
+                                                  const FfiEdgeId *edges,
+                                                  uint32_t count,
+                                                  const struct FfiTransportConfig *config);
+
+/**
+ * Group `count` edges into one logical transport line and write its id to
+ * `*out_group`. Edges not in the graph and repeated edges are skipped; an
+ * edge already in another group moves to the new one.
+ *
+ * # Safety
+ *
+ * `engine` and `out_group` must be valid pointers. `edges` must point to
+ * `count` edge ids (it may be null when `count` is 0).
+ */
+enum FactorialResult factorial_create_edge_group(FactorialEngine *engine,
+                                                 const FfiEdgeId *edges,
+                                                 uint32_t count,
+                                                 FfiEdgeGroupId *out_group);
+
+/**
+ * Dissolve an edge group. Its edges are untouched.
+ *
+ * Returns `EdgeGroupNotFound` if the group does not exist.
+ *
+ * # Safety
+ *
+ * `engine` must be a valid engine pointer.
+ */
+enum FactorialResult factorial_remove_edge_group(FactorialEngine *engine, FfiEdgeGroupId group);
+
+/**
+ * Copy the edges of a group, in the order they were grouped, into
+ * `out_edges`.
+ *
+ * Writes the number of edges to `*out_written`. If `len` is smaller than
+ * that, nothing else is written and `BufferTooSmall` is returned. Returns
+ * `EdgeGroupNotFound` if the group does not exist.
+ *
+ * # Safety
+ *
+ * `engine` and `out_written` must be valid pointers. `out_edges` must be
+ * valid for `len` elements (it may be null when `len` is 0).
+ */
+enum FactorialResult factorial_get_group_edges(const FactorialEngine *engine,
+                                               FfiEdgeGroupId group,
+                                               FfiEdgeId *out_edges,
+                                               uint32_t len,
+                                               uint32_t *out_written);
+
+/**
+ * Apply one transport configuration to every edge of a group. Each edge
+ * keeps the items in transit when its state fits the new transport (same
+ * kind and, for belts, the same slot layout) and starts empty otherwise.
+ *
+ * Returns `EdgeGroupNotFound` if the group does not exist.
+ *
+ * # Safety
+ *
+ * `engine` and `config` must be valid pointers.
+ */
+enum FactorialResult factorial_set_group_transport(FactorialEngine *engine,
+                                                   FfiEdgeGroupId group,
+                                                   const struct FfiTransportConfig *config);
+
+/**
+ * Write whether any edge of a group is at capacity to `*out_congested`.
+ *
+ * Returns `EdgeGroupNotFound` if the group does not exist.
+ *
+ * # Safety
+ *
+ * `engine` and `out_congested` must be valid pointers.
  */
-typedef struct FfiEventBuffer {
-  /**
-   * Pointer to an array of `FfiEvent`. Valid until next step/destroy.
-   */
-  const struct FfiEvent *events;
-  /**
-   * Number of events in the buffer.
-   */
-  uint32_t count;
-} FfiEventBuffer;
+enum FactorialResult factorial_group_is_congested(const FactorialEngine *engine,
+                                                  FfiEdgeGroupId group,
+                                                  bool *out_congested);
 
 /**
- * An engine-allocated byte buffer returned from serialization.
+ * Serialize the internal state of one edge's transport (flow buffer, belt
+ * slots, batch progress or vehicle cargo) into a byte buffer. The caller
+ * must free the buffer with `factorial_free_buffer`.
+ *
+ * Returns `EdgeNotFound` if the edge has no transport.
+ *
+ * # Safety
+ *
+ * `engine` and `out_buffer` must be valid pointers.
  */
-typedef struct FfiByteBuffer {
-  /**
-   * Pointer to the data. Null on error.
-   */
-  uint8_t *data;
-  /**
-   * Length in bytes.
-   */
-  uintptr_t len;
-} FfiByteBuffer;
+enum FactorialResult factorial_get_transport_state(const FactorialEngine *engine,
+                                                   FfiEdgeId edge_id,
+                                                   struct FfiByteBuffer *out_buffer);
 
 /**
- * C-compatible item stack (item type + quantity).
+ * Restore one edge's transport state from a buffer produced by
+ * `factorial_get_transport_state`.
+ *
+ * Returns `EdgeNotFound` if the edge has no transport, and
+ * `DeserializeError` if the data is malformed or does not match the edge's
+ * current transport configuration. The engine is unchanged on error.
+ *
+ * # Safety
+ *
+ * `engine` must be a valid pointer. `data` must point to `len` valid bytes.
  */
-typedef struct FfiItemStack {
-  uint32_t item_type;
-  uint32_t quantity;
-} FfiItemStack;
+enum FactorialResult factorial_set_transport_state(FactorialEngine *engine,
+                                                   FfiEdgeId edge_id,
+                                                   const uint8_t *data,
+                                                   uintptr_t len);
 
 /**
- * C-compatible recipe for FixedRecipe processor.
+ * Set the input inventory for a node with the given capacity.
+ *
+ * Creates an inventory with 1 input slot and 1 output slot.
+ *
+ * # Safety
+ *
+ * `engine` must be a valid engine pointer.
  */
-typedef struct FfiRecipe {
-  uint32_t input_count;
-  const struct FfiItemStack *inputs;
-  uint32_t output_count;
-  const struct FfiItemStack *outputs;
-  uint32_t duration;
-} FfiRecipe;
+enum FactorialResult factorial_set_input_capacity(FactorialEngine *engine,
+                                                  FfiNodeId node_id,
+                                                  uint32_t capacity);
 
 /**
- * C-compatible wire network ID.
+ * Set the output inventory for a node with the given capacity.
+ *
+ * Creates an inventory with 1 input slot and 1 output slot.
+ *
+ * # Safety
+ *
+ * `engine` must be a valid engine pointer.
  */
-typedef uint32_t FfiWireNetworkId;
+enum FactorialResult factorial_set_output_capacity(FactorialEngine *engine,
+                                                   FfiNodeId node_id,
+                                                   uint32_t capacity);
 
 /**
- * Create a new engine with `Tick` simulation strategy.
- * Returns a heap-allocated engine pointer. The caller must eventually
- * call `factorial_destroy` to free the memory.
+ * Read back the shape of one of a node's inventories. `which` is 0 for
+ * the input inventory and 1 for the output inventory. `out_capacity` is the
+ * capacity of the first slot, which every slot shares when the inventory
+ * was set through `factorial_set_input_capacity` or
+ * `factorial_set_output_capacity`; it is 0 if the inventory has no slots.
  *
- * Returns null on internal error.
+ * Returns `InvalidArgument` for any other `which`, and `NodeNotFound` if
+ * the node has no such inventory.
+ *
+ * # Safety
+ *
+ * `engine` and all out pointers must be valid.
  */
-FactorialEngine *factorial_create(void);
+enum FactorialResult factorial_get_inventory_config(const FactorialEngine *engine,
+                                                    FfiNodeId node_id,
+                                                    uint32_t which,
+                                                    uint32_t *out_slots_in,
+                                                    uint32_t *out_slots_out,
+                                                    uint32_t *out_capacity);
 
 /**
- * Create a new engine with `Delta` simulation strategy.
- * `fixed_timestep` is the number of ticks per fixed simulation step.
+ * Set a node's simulation status. A ghost node keeps its configuration and
+ * stays visible to queries, but its processor does not run and no transport
+ * moves items into or out of it.
  *
- * Returns null on internal error.
+ * Returns `NodeNotFound` if the node does not exist.
+ *
+ * # Safety
+ *
+ * `engine` must be a valid engine pointer.
  */
-FactorialEngine *factorial_create_delta(uint64_t fixed_timestep);
+enum FactorialResult factorial_set_node_status(FactorialEngine *engine,
+                                               FfiNodeId node_id,
+                                               enum FfiNodeStatus status);
 
 /**
- * Destroy an engine and free its memory.
+ * Write a node's simulation status to `out_status`.
+ *
+ * Returns `NodeNotFound` if the node does not exist.
  *
  * # Safety
  *
- * `engine` must be a pointer returned by `factorial_create` that has not
- * yet been destroyed. After this call the pointer is invalid.
+ * `engine` and `out_status` must be valid pointers.
  */
-enum FactorialResult factorial_destroy(FactorialEngine *engine);
+enum FactorialResult factorial_get_node_status(const FactorialEngine *engine,
+                                               FfiNodeId node_id,
+                                               enum FfiNodeStatus *out_status);
 
 /**
- * Advance the simulation by one tick (tick mode) or by `dt` ticks (delta mode).
+ * Attach an opaque host value to a node, replacing any previous value. The
+ * engine never interprets it; it is saved with the node and dropped when the
+ * node is removed.
+ *
+ * Returns `NodeNotFound` if the node does not exist.
  *
  * # Safety
  *
  * `engine` must be a valid engine pointer.
  */
-enum FactorialResult factorial_step(FactorialEngine *engine);
+enum FactorialResult factorial_set_node_user_data(FactorialEngine *engine,
+                                                  FfiNodeId node_id,
+                                                  uint64_t data);
 
 /**
- * Advance the simulation by `dt` ticks. In tick mode `dt` is ignored
- * and exactly one step runs. In delta mode, `dt` is accumulated.
+ * Write a node's host value to `out_data`.
+ *
+ * Returns `NodeNotFound` if the node does not exist or has no value set.
  *
  * # Safety
  *
- * `engine` must be a valid engine pointer.
+ * `engine` and `out_data` must be valid pointers.
  */
-enum FactorialResult factorial_advance(FactorialEngine *engine, uint64_t dt);
+enum FactorialResult factorial_get_node_user_data(const FactorialEngine *engine,
+                                                  FfiNodeId node_id,
+                                                  uint64_t *out_data);
 
 /**
- * Queue a node to be added to the graph. Returns a pending node ID
- * via `out_pending`. The real node ID is assigned after `factorial_apply_mutations`.
+ * Remove a node's host value, if any.
+ *
+ * Returns `NodeNotFound` if the node does not exist.
  *
  * # Safety
  *
- * `engine` and `out_pending` must be valid pointers.
+ * `engine` must be a valid engine pointer.
  */
-enum FactorialResult factorial_add_node(FactorialEngine *engine,
-                                        uint32_t building_type,
-                                        FfiPendingNodeId *out_pending);
+enum FactorialResult factorial_clear_node_user_data(FactorialEngine *engine, FfiNodeId node_id);
 
 /**
- * Queue a node for removal.
+ * Set an edge's simulation status. A ghost edge keeps its transport and
+ * state but does not advance.
+ *
+ * Returns `EdgeNotFound` if the edge does not exist.
  *
  * # Safety
  *
  * `engine` must be a valid engine pointer.
  */
-enum FactorialResult factorial_remove_node(FactorialEngine *engine, FfiNodeId node_id);
+enum FactorialResult factorial_set_edge_status(FactorialEngine *engine,
+                                               FfiEdgeId edge_id,
+                                               enum FfiNodeStatus status);
 
 /**
- * Queue an edge connecting two nodes. Returns a pending edge ID via `out_pending`.
+ * Write an edge's own simulation status to `out_status`.
+ *
+ * Returns `EdgeNotFound` if the edge does not exist.
  *
  * # Safety
  *
- * `engine` and `out_pending` must be valid pointers.
+ * `engine` and `out_status` must be valid pointers.
  */
-enum FactorialResult factorial_connect(FactorialEngine *engine,
-                                       FfiNodeId from_node,
-                                       FfiNodeId to_node,
-                                       FfiPendingEdgeId *out_pending);
+enum FactorialResult factorial_get_edge_status(const FactorialEngine *engine,
+                                               FfiEdgeId edge_id,
+                                               enum FfiNodeStatus *out_status);
 
 /**
- * Queue an edge for removal (disconnect).
+ * Turn a ghost node into a real one. It keeps its id, processor,
+ * inventories and edges, and starts simulating on the next step.
+ *
+ * Returns `NodeNotFound` if the node does not exist or is not a ghost.
  *
  * # Safety
  *
  * `engine` must be a valid engine pointer.
  */
-enum FactorialResult factorial_disconnect(FactorialEngine *engine, FfiEdgeId edge_id);
+enum FactorialResult factorial_materialize_ghost(FactorialEngine *engine, FfiNodeId node_id);
 
 /**
- * Apply all queued graph mutations atomically. Results (pending->real ID
- * mappings) are written to `out_result`.
+ * Write the number of ghost nodes to `out_count`.
  *
  * # Safety
  *
- * `engine` and `out_result` must be valid pointers. The pointers in the
- * returned `FfiMutationResult` are valid until the next call to
- * `factorial_apply_mutations` or `factorial_destroy`.
+ * `engine` and `out_count` must be valid pointers.
  */
-enum FactorialResult factorial_apply_mutations(FactorialEngine *engine,
-                                               struct FfiMutationResult *out_result);
+enum FactorialResult factorial_ghost_count(const FactorialEngine *engine, uint32_t *out_count);
 
 /**
- * Get the number of nodes in the graph.
+ * Choose whether ghost nodes and edges are folded into the state hash.
+ * Off by default, so planned construction does not change the hash; turn
+ * it on for desync checks that should cover plans too.
  *
  * # Safety
  *
- * `engine` and `out_count` must be valid pointers.
+ * `engine` must be a valid engine pointer.
  */
-enum FactorialResult factorial_node_count(const FactorialEngine *engine, uint32_t *out_count);
+enum FactorialResult factorial_set_hash_ghosts(FactorialEngine *engine, bool enabled);
 
 /**
- * Get the number of edges in the graph.
+ * Remove a node's processor, turning it into a passive node that consumes
+ * and produces nothing. The node keeps its inventories and edges.
+ *
+ * Returns `NodeNotFound` if the node does not exist. Clearing a node that
+ * has no processor is a no-op that returns `Ok`.
  *
  * # Safety
  *
- * `engine` and `out_count` must be valid pointers.
+ * `engine` must be a valid engine pointer.
  */
-enum FactorialResult factorial_edge_count(const FactorialEngine *engine, uint32_t *out_count);
+enum FactorialResult factorial_clear_processor(FactorialEngine *engine, FfiNodeId node_id);
 
 /**
- * Get the current tick counter.
+ * Remove a node's input inventory. The items it held are written to
+ * `out_items` so the host can drop or refund them.
  *
  * # Safety
  *
- * `engine` and `out_tick` must be valid pointers.
+ * `engine` and `out_items` must be valid pointers. The pointer in the
+ * returned `FfiItemBuffer` is valid until the next inventory clear call.
  */
-enum FactorialResult factorial_get_tick(const FactorialEngine *engine, uint64_t *out_tick);
+enum FactorialResult factorial_clear_input_inventory(FactorialEngine *engine,
+                                                     FfiNodeId node_id,
+                                                     struct FfiItemBuffer *out_items);
 
 /**
- * Get the state hash of the engine (for desync detection).
+ * Remove a node's output inventory. The items it held are written to
+ * `out_items` so the host can drop or refund them.
  *
  * # Safety
  *
- * `engine` and `out_hash` must be valid pointers.
+ * `engine` and `out_items` must be valid pointers. The pointer in the
+ * returned `FfiItemBuffer` is valid until the next inventory clear call.
  */
-enum FactorialResult factorial_get_state_hash(const FactorialEngine *engine, uint64_t *out_hash);
+enum FactorialResult factorial_clear_output_inventory(FactorialEngine *engine,
+                                                      FfiNodeId node_id,
+                                                      struct FfiItemBuffer *out_items);
 
 /**
- * Get the processor state for a node.
+ * Replace the contents of one of a node's inventories with `items`.
+ * `which` is 0 for the input inventory and 1 for the output inventory.
+ *
+ * Items are placed respecting slot filters and capacities. The fill is
+ * all-or-nothing: if the items do not all fit, returns `InvalidArgument`
+ * and the inventory is unchanged. Returns `NodeNotFound` if the node has no
+ * such inventory. Fills emit no events. `items` may be null when
+ * `item_count` is 0, which empties the inventory.
  *
  * # Safety
  *
- * `engine` and `out_info` must be valid pointers.
+ * `engine` must be a valid pointer. `items` must be valid for `item_count`
+ * elements when `item_count` is non-zero.
  */
-enum FactorialResult factorial_get_processor_state(const FactorialEngine *engine,
-                                                   FfiNodeId node_id,
-                                                   struct FfiProcessorInfo *out_info);
+enum FactorialResult factorial_fill_inventory(FactorialEngine *engine,
+                                              FfiNodeId node_id,
+                                              uint32_t which,
+                                              const struct FfiItemStack *items,
+                                              uint32_t item_count);
 
 /**
- * Get the total item count in a node's input inventory.
+ * Fill many inventories in one call. Each entry names an inventory as
+ * `factorial_fill_inventory` does and a range of the shared `items` array.
+ *
+ * Either every fill applies or none does. Returns `InvalidArgument` for a
+ * bad `which`, a range outside `items`, or items that do not fit, and
+ * `NodeNotFound` for a missing inventory. When two entries target the same
+ * inventory, the later one wins.
  *
  * # Safety
  *
- * `engine` and `out_count` must be valid pointers.
+ * `engine` must be a valid pointer. `entries` and `items` must be valid for
+ * `entry_count` and `item_count` elements when those are non-zero.
  */
-enum FactorialResult factorial_get_input_inventory_count(const FactorialEngine *engine,
-                                                         FfiNodeId node_id,
-                                                         uint32_t *out_count);
+enum FactorialResult factorial_fill_inventories_bulk(FactorialEngine *engine,
+                                                     const struct FfiInventoryFill *entries,
+                                                     uint32_t entry_count,
+                                                     const struct FfiItemStack *items,
+                                                     uint32_t item_count);
 
 /**
- * Get the total item count in a node's output inventory.
+ * Create a hand-crafting queue that draws ingredients from and deposits
+ * products into the input inventory of `node_id`. `speed` is raw Fixed64
+ * bits and must be positive. The new queue id is written to `out_queue`.
  *
  * # Safety
  *
- * `engine` and `out_count` must be valid pointers.
+ * `engine` and `out_queue` must be valid pointers.
  */
-enum FactorialResult factorial_get_output_inventory_count(const FactorialEngine *engine,
-                                                          FfiNodeId node_id,
-                                                          uint32_t *out_count);
+enum FactorialResult factorial_create_crafting_queue(FactorialEngine *engine,
+                                                     FfiNodeId node_id,
+                                                     int64_t speed,
+                                                     FfiCraftingQueueId *out_queue);
 
 /**
- * Poll all buffered events since the last step. Returns a pointer to an
- * engine-owned buffer of `FfiEvent` structs. The buffer is valid until the
- * next `factorial_step`, `factorial_advance`, or `factorial_destroy`.
+ * Remove a crafting queue, refunding the ingredients of every craft that
+ * has not completed.
  *
  * # Safety
  *
- * `engine` and `out_buffer` must be valid pointers.
+ * `engine` must be a valid engine pointer.
  */
-enum FactorialResult factorial_poll_events(const FactorialEngine *engine,
-                                           struct FfiEventBuffer *out_buffer);
+enum FactorialResult factorial_remove_crafting_queue(FactorialEngine *engine,
+                                                     FfiCraftingQueueId queue);
 
 /**
- * Serialize the engine state to a binary buffer. The returned
- * `FfiByteBuffer` contains a pointer and length. The caller must free the
- * buffer with `factorial_free_buffer` when done.
+ * Enable or disable recursive crafting of missing intermediates. Recursive
+ * crafting looks up intermediate recipes in the engine's registry.
  *
  * # Safety
  *
- * `engine` and `out_buffer` must be valid pointers.
+ * `engine` must be a valid engine pointer.
  */
-enum FactorialResult factorial_serialize(const FactorialEngine *engine,
-                                         struct FfiByteBuffer *out_buffer);
+enum FactorialResult factorial_set_crafting_recursive(FactorialEngine *engine,
+                                                      FfiCraftingQueueId queue,
+                                                      bool recursive);
 
 /**
- * Deserialize an engine from a binary buffer. Returns a new engine pointer
- * via `out_engine`. The caller takes ownership.
+ * Queue `count` crafts, taking all of their ingredients from the queue's
+ * inventory now. When `recipe` is non-null it defines the recipe and
+ * `recipe_id` only tags the job and its events; when null, `recipe_id` is
+ * looked up in the engine's registry.
+ *
+ * Returns `CraftingRejected` without queueing anything if ingredients are
+ * missing or the recipe is unknown.
  *
  * # Safety
  *
- * `data` must point to `len` valid bytes. `out_engine` must be a valid pointer.
+ * `engine` must be a valid engine pointer. `recipe` must be null or a
+ * valid pointer whose arrays are valid for their declared counts.
  */
-enum FactorialResult factorial_deserialize(const uint8_t *data,
-                                           uintptr_t len,
-                                           FactorialEngine **out_engine);
+enum FactorialResult factorial_queue_craft(FactorialEngine *engine,
+                                           FfiCraftingQueueId queue,
+                                           uint32_t recipe_id,
+                                           const struct FfiRecipe *recipe,
+                                           uint32_t count);
 
 /**
- * Free a byte buffer returned by `factorial_serialize`.
+ * Cancel the job at `index` and refund ingredients for its crafts that have
+ * not started; a craft in progress keeps running. Intermediates queued by
+ * recursive crafting are cancelled with the job they feed. The number of
+ * crafts removed is written to `out_cancelled` when it is non-null.
  *
  * # Safety
  *
- * `buffer` must be a buffer originally returned by `factorial_serialize`.
- * After this call the buffer's data pointer is invalid.
+ * `engine` must be a valid engine pointer. `out_cancelled` must be null or
+ * a valid pointer.
  */
-enum FactorialResult factorial_free_buffer(struct FfiByteBuffer buffer);
+enum FactorialResult factorial_cancel_craft(FactorialEngine *engine,
+                                            FfiCraftingQueueId queue,
+                                            uint32_t index,
+                                            uint32_t *out_cancelled);
 
 /**
- * Set a node's processor to Source.
+ * Write a crafting queue's progress to `out_progress`.
  *
- * `rate` is raw Fixed64 bits (Q32.32). Use `Fixed64::to_bits()` on the Rust
- * side or shift an integer left by 32 on the C side to construct it.
+ * # Safety
+ *
+ * `engine` and `out_progress` must be valid pointers.
+ */
+enum FactorialResult factorial_get_crafting_progress(const FactorialEngine *engine,
+                                                     FfiCraftingQueueId queue,
+                                                     struct FfiCraftProgress *out_progress);
+
+/**
+ * Enable or disable per-step operation counting. Disabling clears the
+ * last profile.
  *
  * # Safety
  *
  * `engine` must be a valid engine pointer.
  */
-enum FactorialResult factorial_set_source(FactorialEngine *engine,
-                                          FfiNodeId node_id,
-                                          uint32_t item_type,
-                                          int64_t rate);
+enum FactorialResult factorial_set_profiling(FactorialEngine *engine, bool enabled);
 
 /**
- * Set a node's processor to FixedRecipe.
- *
- * The `recipe` pointer must point to a valid `FfiRecipe` whose `inputs` and
- * `outputs` arrays have the declared counts.
+ * Get the operation counts from the most recent step. Writes an all-zero
+ * profile (`tick == 0`) if profiling is disabled or no step has run since
+ * it was enabled.
  *
  * # Safety
  *
- * `engine` and `recipe` must be valid pointers. The arrays referenced by
- * `recipe.inputs` and `recipe.outputs` must be valid for the declared counts.
+ * `engine` and `out_profile` must be valid pointers.
  */
-enum FactorialResult factorial_set_fixed_processor(FactorialEngine *engine,
-                                                   FfiNodeId node_id,
-                                                   const struct FfiRecipe *recipe);
+enum FactorialResult factorial_get_step_profile(const FactorialEngine *engine,
+                                                struct FfiStepProfile *out_profile);
 
 /**
- * Set an edge's transport to FlowTransport with default buffer/latency.
+ * Enable or disable transit latency tracking for one edge.
  *
- * `rate` is raw Fixed64 bits (Q32.32).
+ * Returns `EdgeNotFound` if the edge does not exist.
  *
  * # Safety
  *
  * `engine` must be a valid engine pointer.
  */
-enum FactorialResult factorial_set_flow_transport(FactorialEngine *engine,
-                                                  FfiEdgeId edge_id,
-                                                  int64_t rate);
+enum FactorialResult factorial_set_latency_tracking(FactorialEngine *engine,
+                                                    FfiEdgeId edge_id,
+                                                    bool enabled);
 
 /**
- * Set an edge's transport to ItemTransport.
- *
- * `speed` is raw Fixed64 bits (Q32.32).
+ * Set the latency histogram bucket edges, in ticks, and reset every
+ * histogram. A histogram has `count + 1` buckets.
  *
  * # Safety
  *
- * `engine` must be a valid engine pointer.
+ * `engine` must be a valid engine pointer. `edges` must point to `count`
+ * values (it may be null when `count` is 0).
  */
-enum FactorialResult factorial_set_item_transport(FactorialEngine *engine,
-                                                  FfiEdgeId edge_id,
-                                                  int64_t speed,
-                                                  uint32_t slot_count,
-                                                  uint8_t lanes);
+enum FactorialResult factorial_set_latency_buckets(FactorialEngine *engine,
+                                                   const uint64_t *edges,
+                                                   uint32_t count);
 
 /**
- * Set an edge's transport to BatchTransport.
+ * Copy an edge's latency histogram into `out_counts`.
+ *
+ * Writes the number of buckets to `*out_written` (0 if the edge is not
+ * tracked). If `len` is smaller than that, nothing else is written and
+ * `BufferTooSmall` is returned. Returns `EdgeNotFound` if the edge does not
+ * exist.
  *
  * # Safety
  *
- * `engine` must be a valid engine pointer.
+ * `engine` and `out_written` must be valid pointers. `out_counts` must be
+ * valid for `len` elements (it may be null when `len` is 0).
  */
-enum FactorialResult factorial_set_batch_transport(FactorialEngine *engine,
-                                                   FfiEdgeId edge_id,
-                                                   uint32_t batch_size,
-                                                   uint32_t cycle_time);
+enum FactorialResult factorial_get_edge_latency_histogram(const FactorialEngine *engine,
+                                                          FfiEdgeId edge_id,
+                                                          uint64_t *out_counts,
+                                                          uint32_t len,
+                                                          uint32_t *out_written);
 
 /**
- * Set an edge's transport to VehicleTransport.
+ * Zero every latency histogram.
  *
  * # Safety
  *
  * `engine` must be a valid engine pointer.
  */
-enum FactorialResult factorial_set_vehicle_transport(FactorialEngine *engine,
-                                                     FfiEdgeId edge_id,
-                                                     uint32_t capacity,
-                                                     uint32_t travel_time);
+enum FactorialResult factorial_reset_latency_histograms(FactorialEngine *engine);
 
 /**
- * Set the input inventory for a node with the given capacity.
- *
- * Creates an inventory with 1 input slot and 1 output slot.
+ * Call `logger` synchronously, with `user_data`, for every operation the
+ * engine performs from now on: graph mutations applied, processor output,
+ * transport deliveries and completed steps, in a deterministic order. This
+ * is a tooling trace, separate from the event queue and unaffected by
+ * event suppression. Pass a null `logger` to remove it.
  *
  * # Safety
  *
- * `engine` must be a valid engine pointer.
+ * `engine` must be a valid engine pointer. `logger` must not unwind, and it
+ * and `user_data` must be usable from whichever thread drives the engine.
+ * The logger must not call back into this engine.
  */
-enum FactorialResult factorial_set_input_capacity(FactorialEngine *engine,
-                                                  FfiNodeId node_id,
-                                                  uint32_t capacity);
+enum FactorialResult factorial_set_operation_logger(FactorialEngine *engine,
+                                                    struct Option_FactorialOperationLogger logger,
+                                                    void *user_data);
 
 /**
- * Set the output inventory for a node with the given capacity.
+ * Format raw Fixed64 `bits` as a decimal string with exactly `decimals`
+ * places (at most 19), so every host shows the same digits for the same
+ * value. `mode` is 0 = half up, 1 = half even, 2 = floor, 3 = ceil.
  *
- * Creates an inventory with 1 input slot and 1 output slot.
+ * Writes the string and a NUL terminator to `out_buf`, and its length
+ * without the terminator to `*out_written`. If `len` is too small nothing
+ * but `*out_written` is written and `BufferTooSmall` is returned; retry with
+ * at least `*out_written + 1` bytes.
  *
  * # Safety
  *
- * `engine` must be a valid engine pointer.
+ * `out_buf` must be valid for `len` bytes and `out_written` must be a valid
+ * pointer.
  */
-enum FactorialResult factorial_set_output_capacity(FactorialEngine *engine,
-                                                   FfiNodeId node_id,
-                                                   uint32_t capacity);
+enum FactorialResult factorial_format_fixed(int64_t bits,
+                                            uint32_t decimals,
+                                            uint32_t mode,
+                                            uint8_t *out_buf,
+                                            uint32_t len,
+                                            uint32_t *out_written);
 
 /**
  * Check whether the engine is poisoned (a previous panic left it in an
@@ -785,6 +3306,18 @@ enum FactorialResult factorial_logic_get_network_signal(FactorialEngine *engine,
                                                         uint32_t item_id,
                                                         int64_t *out_value);
 
+/**
+ * List the signals whose value on some node's wire changed during the most
+ * recent step, ordered by (node, wire, signal, network).
+ *
+ * # Safety
+ *
+ * `engine` and `out_buffer` must be valid pointers. The pointer in the
+ * returned buffer is valid until the next call to this function.
+ */
+enum FactorialResult factorial_logic_get_changed_signals(const FactorialEngine *engine,
+                                                         struct FfiSignalChangeBuffer *out_buffer);
+
 /**
  * Remove all logic state for a node (constant, combinators, circuit
  * control, and network memberships).
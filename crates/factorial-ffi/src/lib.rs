@@ -14,21 +14,78 @@
 //! After each `factorial_step` call, the caller polls events via
 //! `factorial_poll_events`. This returns an engine-owned buffer that is
 //! valid until the next `factorial_step` or `factorial_destroy`.
-
+//!
+//! Graph events (`NodeAdded`, `EdgeAdded`, ...) produced by
+//! `factorial_apply_mutations` between steps are stamped with the upcoming
+//! tick and appear in the event buffer of the *next* step, alongside that
+//! step's other events.
+//!
+//! # Concurrent reads
+//!
+//! A handle is used by one thread at a time, with one exception: the
+//! following read-only calls may run concurrently with each other on the
+//! same handle. They read engine state in place and touch no caches,
+//! locks or other interior mutability:
+//!
+//! - `factorial_node_count`, `factorial_edge_count`
+//! - `factorial_get_tick`
+//! - `factorial_get_state_hash`, `factorial_get_state_hash_breakdown`
+//! - `factorial_item_census`
+//! - `factorial_get_processor_state`
+//! - `factorial_get_input_inventory_count`,
+//!   `factorial_get_output_inventory_count`
+//! - `factorial_get_node_status`, `factorial_get_edge_status`
+//!
+//! They must not overlap any other call on the handle. Every other call,
+//! including `factorial_poll_events` and the getters that fill engine-owned
+//! buffers, needs exclusive access. Debug builds check this: a listed call
+//! that overlaps a call taking `*mut FactorialEngine` returns
+//! `ConcurrentMutation` without reading, and the mutating call waits for
+//! listed calls already in progress. Release builds do not check.
+//!
+//! # Read-only handles
+//!
+//! Calls that take `*const FactorialEngine` never change the simulation,
+//! though most still fill engine-owned buffers. A host subsystem that must
+//! not step or mutate the engine can instead hold a
+//! [`FactorialEngineInspector`] from `factorial_create_inspector`, which only
+//! the `factorial_inspector_*` versions of the concurrent reads accept.
+
+use std::cell::RefCell;
+use std::ops::{Deref, DerefMut};
 use std::panic::catch_unwind;
-use std::ptr;
-
+use std::ptr::{self, NonNull};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError, Weak};
+
+use factorial_bundle::{Bundle, BundleError, Section};
+use factorial_core::capacity::InventorySide;
+use factorial_core::catalog::InFlightPolicy;
+use factorial_core::crafting::CraftingError;
 use factorial_core::engine::Engine;
-use factorial_core::event::{Event, EventKind};
-use factorial_core::fixed::Fixed64;
-use factorial_core::id::{BuildingTypeId, EdgeId, ItemTypeId, NodeId};
+use factorial_core::event::{Event, EventKind, HistoryFilter};
+use factorial_core::extension::ExtensionId;
+use factorial_core::fill::{InventoryFill, InventoryFillError};
+use factorial_core::fixed::{Fixed64, FixedDecimal, RoundingMode};
+use factorial_core::graph::{NodeFlags, NodeStatus, PendingLookup};
+use factorial_core::id::{
+    BuildingTypeId, CraftingQueueId, EdgeGroupId, EdgeId, EventCursorId, ItemTypeId, NodeId,
+    PendingEdgeId, PendingNodeId, RecipeId,
+};
 use factorial_core::item::Inventory;
+use factorial_core::oplog::{OperationKind, OperationRecord};
 use factorial_core::processor::{
-    Depletion, FixedRecipe, Processor, ProcessorState, RecipeInput, RecipeOutput, SourceProcessor,
+    Depletion, FixedRecipe, FlexibleProcessor, FluidAmount, MultiSourceProcessor, Processor,
+    ProcessorState, RecipeInput, RecipeOutput, RecipeSelection, SourceProcessor, StorageProcessor,
 };
-use factorial_core::sim::SimulationStrategy;
+use factorial_core::query::{TransportProgress, VehiclePhase};
+use factorial_core::registry::{RecipeDef, RecipeEntry};
+use factorial_core::richness::{RichnessCurve, SourceSite};
+use factorial_core::serialize::{DeserializeError, SerializeError};
+use factorial_core::sim::{SimulationStrategy, StepPhase};
+use factorial_core::slice::{TickSliceError, TickSliceResult};
 use factorial_core::transport::{
-    BatchTransport, FlowTransport, ItemTransport, Transport, VehicleTransport,
+    BatchTransport, FlowTransport, ItemTransport, Transport, TransportKind, VehicleTransport,
 };
 
 use factorial_logic::combinator::{
@@ -36,6 +93,7 @@ use factorial_logic::combinator::{
 };
 use factorial_logic::condition::{ComparisonOp, Condition, InventorySource};
 use factorial_logic::{LogicModuleBridge, SignalSet, WireColor, WireNetworkId};
+use factorial_tech_tree::TechTree;
 
 use slotmap::{Key, KeyData};
 
@@ -65,6 +123,37 @@ pub enum FactorialResult {
     InternalError = 7,
     /// The engine is poisoned (a previous panic left it in an inconsistent state).
     Poisoned = 8,
+    /// The pending id's mutation has not been applied yet.
+    PendingQueued = 9,
+    /// The pending id's batch is older than the resolution history.
+    PendingExpired = 10,
+    /// The pending id was never issued by this engine.
+    PendingUnknown = 11,
+    /// The requested crafting queue was not found.
+    CraftingQueueNotFound = 12,
+    /// A crafting request was rejected: missing ingredients, unknown recipe,
+    /// invalid speed, or job index out of range.
+    CraftingRejected = 13,
+    /// The caller's output buffer is too small; the size needed was written.
+    BufferTooSmall = 14,
+    /// An enum-valued argument was out of range.
+    InvalidArgument = 15,
+    /// The node's processor or the edge's transport is a different kind
+    /// from the one the getter reads.
+    KindMismatch = 16,
+    /// A read-only call overlapped a mutating call on the same handle and
+    /// read nothing. Only detected in debug builds.
+    ConcurrentMutation = 17,
+    /// The requested edge group was not found.
+    EdgeGroupNotFound = 18,
+    /// The step ran, but exceeded the step work limit and skipped its
+    /// remaining simulation phases.
+    WorkLimitReached = 19,
+    /// A sliced tick is open: the call would change the engine mid-step.
+    /// Finish the tick with `factorial_continue_tick` first.
+    TickInProgress = 20,
+    /// The requested event cursor was not found.
+    EventCursorNotFound = 21,
 }
 
 // ---------------------------------------------------------------------------
@@ -73,10 +162,223 @@ pub enum FactorialResult {
 
 /// Opaque engine handle. Callers receive `*mut FactorialEngine` from
 /// `factorial_create` and pass it to all subsequent calls.
+///
+/// Each handle owns every buffer the API hands out for it (events, mutation
+/// results, query results), so handles are independent of each other and of
+/// the thread that created them. A handle may be moved to another thread
+/// between calls, but must not be used from two threads at once -- except
+/// for the read-only calls listed under "Concurrent reads" in the crate
+/// documentation, which may overlap each other.
 #[repr(C)]
 pub struct FactorialEngine {
-    inner: Engine,
+    inner: EngineSlot,
     poisoned: bool,
+    /// Events from the most recent step, filled by the passive listeners.
+    events: Arc<Mutex<EventCache>>,
+    mutation_nodes: Vec<FfiIdPair>,
+    mutation_edges: Vec<FfiIdPair>,
+    mutation_rejects: Vec<FfiRejectedMutation>,
+    cleared_items: Vec<FfiItemStack>,
+    transport_items: RefCell<Vec<FfiTransportItem>>,
+    bottlenecks: RefCell<Vec<FfiItemBottleneck>>,
+    signal_changes: RefCell<Vec<FfiSignalChange>>,
+    /// Calls in progress, for debug-build overlap detection: the number of
+    /// concurrent read-only calls plus [`MUTATING`] while a mutating call
+    /// runs. Kept in its own allocation so racing calls never access the
+    /// same handle bytes mutably.
+    access: Arc<AtomicU32>,
+}
+
+// SAFETY: `Engine` is not `Send` only because its listener, logger and module
+// slots are untyped boxed trait objects. Everything the FFI stores in them is
+// `Send`: the event listeners capture an `Arc<Mutex<EventCache>>`, the
+// only modules it registers are `LogicModuleBridge` and a tech tree restored
+// from a bundle (asserted below), and the operation logger holds a C function
+// pointer and user data whose thread safety `factorial_set_operation_logger`
+// makes the caller's contract. C callers
+// cannot register Rust closures or modules of their own. A borrowed engine may
+// carry the embedder's non-`Send` closures; `factorial_wrap_borrowed` makes
+// keeping such a handle on one thread part of the caller's contract.
+unsafe impl Send for FactorialEngine {}
+
+const _: () = {
+    const fn assert_send<T: Send>() {}
+    assert_send::<LogicModuleBridge>();
+    assert_send::<TechTree>();
+    assert_send::<EventCache>();
+};
+
+/// The engine behind a handle: owned by the handle, or borrowed from a Rust
+/// embedder through `factorial_wrap_borrowed`.
+enum EngineSlot {
+    Owned(Box<Engine>),
+    Borrowed(NonNull<Engine>),
+}
+
+impl Deref for EngineSlot {
+    type Target = Engine;
+
+    fn deref(&self) -> &Engine {
+        match self {
+            Self::Owned(engine) => engine,
+            // SAFETY: the embedder keeps a borrowed engine alive and
+            // unaliased until `factorial_unwrap`.
+            Self::Borrowed(engine) => unsafe { engine.as_ref() },
+        }
+    }
+}
+
+impl DerefMut for EngineSlot {
+    fn deref_mut(&mut self) -> &mut Engine {
+        match self {
+            Self::Owned(engine) => engine,
+            // SAFETY: as for `deref`.
+            Self::Borrowed(engine) => unsafe { engine.as_mut() },
+        }
+    }
+}
+
+impl FactorialEngine {
+    /// Wrap `engine`, registering the event-cache listeners.
+    fn new(engine: Engine) -> Self {
+        Self::with_slot(EngineSlot::Owned(Box::new(engine)))
+    }
+
+    fn with_slot(mut inner: EngineSlot) -> Self {
+        let events = Arc::new(Mutex::new(EventCache::default()));
+        register_ffi_event_listeners(&mut inner, &events);
+        Self {
+            inner,
+            poisoned: false,
+            events,
+            mutation_nodes: Vec::new(),
+            mutation_edges: Vec::new(),
+            mutation_rejects: Vec::new(),
+            cleared_items: Vec::new(),
+            transport_items: RefCell::new(Vec::new()),
+            bottlenecks: RefCell::new(Vec::new()),
+            signal_changes: RefCell::new(Vec::new()),
+            access: Arc::new(AtomicU32::new(0)),
+        }
+    }
+
+    /// Lock the event cache. A panic inside a listener cannot leave the
+    /// cache inconsistent, so a poisoned lock is recovered.
+    fn event_cache(&self) -> MutexGuard<'_, EventCache> {
+        self.events.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// The result a mutating call returns without acting: `Poisoned` after
+    /// a caught panic, `TickInProgress` while a sliced tick is open.
+    fn refuse_mutation(&self) -> Option<FactorialResult> {
+        if self.poisoned {
+            Some(FactorialResult::Poisoned)
+        } else if self.inner.is_tick_in_progress() {
+            Some(FactorialResult::TickInProgress)
+        } else {
+            None
+        }
+    }
+
+    /// Empty the event cache and reset the dropped-event counter.
+    fn clear_event_cache(&self) {
+        let mut cache = self.event_cache();
+        cache.events.clear();
+        cache.raw.clear();
+        cache.dropped = 0;
+    }
+}
+
+impl Drop for FactorialEngine {
+    fn drop(&mut self) {
+        // A borrowed engine outlives its handle, and so do the listeners
+        // registered on it. Stop them from filling a cache nobody reads.
+        if matches!(self.inner, EngineSlot::Borrowed(_)) {
+            let mut cache = self.event_cache();
+            cache.detached = true;
+            cache.events = Vec::new();
+            cache.raw = Vec::new();
+        }
+    }
+}
+
+/// Set in [`FactorialEngine::access`] while a mutating call runs.
+const MUTATING: u32 = 1 << 31;
+
+/// Clone a handle's access counter without creating a reference to the
+/// handle, which a racing call may hold mutably.
+///
+/// # Safety
+///
+/// `engine` must be a valid engine pointer.
+unsafe fn access_counter(engine: *const FactorialEngine) -> Arc<AtomicU32> {
+    Arc::clone(unsafe { &*ptr::addr_of!((*engine).access) })
+}
+
+/// Marks a mutating call in progress on a handle until dropped. Does
+/// nothing in release builds.
+struct MutationGuard(Option<Arc<AtomicU32>>);
+
+impl MutationGuard {
+    /// Wait for overlapping read-only calls to finish, then mark the handle
+    /// as being mutated.
+    ///
+    /// # Safety
+    ///
+    /// `engine` must be a valid engine pointer.
+    unsafe fn begin(engine: *const FactorialEngine) -> Self {
+        if !cfg!(debug_assertions) {
+            return Self(None);
+        }
+        let access = unsafe { access_counter(engine) };
+        while access
+            .compare_exchange_weak(0, MUTATING, Ordering::Acquire, Ordering::Relaxed)
+            .is_err()
+        {
+            std::thread::yield_now();
+        }
+        Self(Some(access))
+    }
+}
+
+impl Drop for MutationGuard {
+    fn drop(&mut self) {
+        if let Some(access) = &self.0 {
+            access.fetch_and(!MUTATING, Ordering::Release);
+        }
+    }
+}
+
+/// Marks a read-only call in progress on a handle until dropped. Does
+/// nothing in release builds.
+struct ReadGuard(Option<Arc<AtomicU32>>);
+
+impl ReadGuard {
+    /// Register a read-only call, or return `None` if a mutating call is in
+    /// progress on the handle.
+    ///
+    /// # Safety
+    ///
+    /// `engine` must be a valid engine pointer.
+    unsafe fn begin(engine: *const FactorialEngine) -> Option<Self> {
+        if !cfg!(debug_assertions) {
+            return Some(Self(None));
+        }
+        let access = unsafe { access_counter(engine) };
+        if access.fetch_add(1, Ordering::Acquire) & MUTATING != 0 {
+            access.fetch_sub(1, Ordering::Release);
+            return None;
+        }
+        Some(Self(Some(access)))
+    }
+}
+
+impl Drop for ReadGuard {
+    fn drop(&mut self) {
+        if let Some(access) = &self.0 {
+            access.fetch_sub(1, Ordering::Release);
+        }
+    }
 }
 
 // ---------------------------------------------------------------------------
@@ -95,6 +397,15 @@ pub type FfiPendingNodeId = u64;
 /// C-compatible representation of a PendingEdgeId.
 pub type FfiPendingEdgeId = u64;
 
+/// C-compatible representation of a CraftingQueueId (u64 ffi key).
+pub type FfiCraftingQueueId = u64;
+
+/// C-compatible representation of an EdgeGroupId (u64 ffi key).
+pub type FfiEdgeGroupId = u64;
+
+/// C-compatible representation of an EventCursorId (u64 ffi key).
+pub type FfiEventCursorId = u64;
+
 // ---------------------------------------------------------------------------
 // FFI-safe processor state
 // ---------------------------------------------------------------------------
@@ -109,6 +420,7 @@ pub enum FfiProcessorState {
     StalledOutputFull = 3,
     StalledNoPower = 4,
     StalledDepleted = 5,
+    Storing = 6,
 }
 
 /// C-compatible processor state with progress.
@@ -120,6 +432,119 @@ pub struct FfiProcessorInfo {
     pub progress: u32,
 }
 
+// ---------------------------------------------------------------------------
+// FFI-safe state hash breakdown
+// ---------------------------------------------------------------------------
+
+/// C-compatible per-subsystem state hashes. The wrapping sum of all fields
+/// equals `factorial_get_state_hash`. See `HashBreakdown` in factorial-core
+/// for field meanings.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FfiHashBreakdown {
+    pub graph: u64,
+    pub inventories: u64,
+    pub processor_states: u64,
+    pub transports: u64,
+    pub sim_state: u64,
+    pub fluid: u64,
+    pub crafting: u64,
+    pub modules: u64,
+}
+
+/// C-compatible factory-wide count of one item type. See `ItemCensus` in
+/// factorial-core for field meanings.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FfiItemCensus {
+    pub in_input_inventories: u64,
+    pub in_output_inventories: u64,
+    pub in_transit: u64,
+    pub total: u64,
+}
+
+/// C-compatible stall summary for one item type. See `ItemBottleneck` in
+/// factorial-core for field meanings.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FfiItemBottleneck {
+    pub item_type: u32,
+    pub starved_nodes: u32,
+    pub blocked_nodes: u32,
+    /// The starved node missing the most units. 0 if none.
+    pub worst_starved: FfiNodeId,
+    /// The blocked node holding the most units in its output. 0 if none.
+    pub worst_blocked: FfiNodeId,
+}
+
+/// Engine-owned array returned by `factorial_get_bottleneck_digest`.
+#[repr(C)]
+#[derive(Debug)]
+pub struct FfiBottleneckBuffer {
+    pub items: *const FfiItemBottleneck,
+    pub count: u32,
+}
+
+/// C-compatible save summary. See `SaveSummary` in factorial-core for field
+/// meanings. Per-item totals are reduced to their count and sum.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FfiSaveSummary {
+    pub version: u32,
+    pub tick: u64,
+    pub node_count: u32,
+    pub edge_count: u32,
+    pub extensions: u32,
+    pub state_hash: u64,
+    /// Whether the save recorded item totals. The next two fields are zero
+    /// otherwise.
+    pub has_item_totals: bool,
+    /// Number of item types with at least one unit.
+    pub item_type_count: u32,
+    /// Units of all item types together.
+    pub total_items: u64,
+    /// Whether the save had no summary section and was fully decoded.
+    pub full_decode: bool,
+}
+
+// ---------------------------------------------------------------------------
+// FFI-safe step profile
+// ---------------------------------------------------------------------------
+
+/// C-compatible per-phase operation counts from the most recent step.
+/// See `StepProfile` in factorial-core for field meanings.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FfiStepProfile {
+    /// Engine tick after the step. 0 means no profile is available.
+    pub tick: u64,
+    pub mutations_applied: u32,
+    pub transports_advanced: u32,
+    pub items_delivered: u32,
+    pub processors_ticked: u32,
+    pub junctions_processed: u32,
+    pub modules_run: u32,
+    pub events_delivered: u32,
+    pub module_work: u32,
+    pub topology_rebuilds: u32,
+}
+
+// ---------------------------------------------------------------------------
+// FFI-safe topology
+// ---------------------------------------------------------------------------
+
+/// C-compatible outgoing edge from `factorial_get_topology`. `kind` is only
+/// meaningful when `has_transport` is true.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FfiTopologyEdge {
+    pub edge: FfiEdgeId,
+    /// Index of the edge's target in the node array.
+    pub target: u32,
+    pub kind: FfiTransportKind,
+    pub has_transport: bool,
+}
+
 // ---------------------------------------------------------------------------
 // FFI-safe event types
 // ---------------------------------------------------------------------------
@@ -130,6 +555,8 @@ pub struct FfiProcessorInfo {
 pub enum FfiEventKind {
     ItemProduced = 0,
     ItemConsumed = 1,
+    /// For a flexible processor `item_type` holds the selected recipe id and
+    /// `quantity` is 1; otherwise both are 0.
     RecipeStarted = 2,
     RecipeCompleted = 3,
     BuildingStalled = 4,
@@ -141,6 +568,25 @@ pub enum FfiEventKind {
     EdgeAdded = 10,
     EdgeRemoved = 11,
     RecipeSwitched = 12,
+    /// `item_type` holds the recipe id.
+    CraftStarted = 13,
+    /// `item_type` holds the recipe id and `quantity` the crafts left in
+    /// the job.
+    CraftCompleted = 14,
+    /// `item_type` and `quantity` describe the items that spoiled. The
+    /// product, if any, is the item type's configured decay product.
+    ItemSpoiled = 15,
+    /// `item_type` is the item the slot holds, `quantity` the slot index,
+    /// and `building_type` 0 for the input inventory or 1 for the output
+    /// inventory.
+    InventoryFull = 16,
+    /// Fields as for `InventoryFull`; `item_type` is the item the slot held
+    /// when it filled.
+    InventorySpaceAvailable = 17,
+    /// `quantity` is the work done, saturated to `u32::MAX`, and
+    /// `building_type` the phase after which the step stopped: 0 pre-tick,
+    /// 1 transport, 2 process, 3 component.
+    StepTruncated = 18,
 }
 
 /// C-compatible event data. Union fields are determined by `kind`.
@@ -191,6 +637,16 @@ pub struct FfiByteBuffer {
     pub len: usize,
 }
 
+/// Hand ownership of `data` to the caller as an `FfiByteBuffer`. Freed by
+/// `factorial_free_buffer`.
+fn byte_buffer_from_vec(data: Vec<u8>) -> FfiByteBuffer {
+    let len = data.len();
+    let mut boxed = data.into_boxed_slice();
+    let ptr = boxed.as_mut_ptr();
+    std::mem::forget(boxed);
+    FfiByteBuffer { data: ptr, len }
+}
+
 // ---------------------------------------------------------------------------
 // Mutation result
 // ---------------------------------------------------------------------------
@@ -205,14 +661,30 @@ pub struct FfiMutationResult {
     /// Array of (pending_edge_id, real_edge_id) pairs.
     pub added_edges: *const FfiIdPair,
     pub added_edge_count: u32,
+    /// Array of node additions refused in strict mode.
+    pub rejected: *const FfiRejectedMutation,
+    pub rejected_count: u32,
+}
+
+/// A node addition refused at apply time. Listed in the order the mutations
+/// were applied, like [`FfiIdPair`].
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct FfiRejectedMutation {
+    pub pending_id: u64,
+    /// Reason code: `1` = building locked behind incomplete research.
+    pub reason: u32,
 }
 
-/// A pair of (pending_id, real_id) for mutation results.
+/// A pair of (pending_id, real_id) for mutation results. Pairs are in the
+/// order the mutations were applied: by ascending lane, then in call order.
 #[repr(C)]
 #[derive(Debug, Clone, Copy)]
 pub struct FfiIdPair {
     pub pending_id: u64,
     pub real_id: u64,
+    /// The mutation lane the addition was queued in.
+    pub lane: u8,
 }
 
 // ---------------------------------------------------------------------------
@@ -258,6 +730,10 @@ fn convert_processor_state(state: &ProcessorState) -> FfiProcessorInfo {
                 progress: 0,
             }
         }
+        ProcessorState::Storing => FfiProcessorInfo {
+            state: FfiProcessorState::Storing,
+            progress: 0,
+        },
     }
 }
 
@@ -295,13 +771,13 @@ fn convert_event(event: &Event) -> FfiEvent {
             from_node: 0,
             to_node: 0,
         },
-        Event::RecipeStarted { node, tick } => FfiEvent {
+        Event::RecipeStarted { node, recipe, tick } => FfiEvent {
             kind: FfiEventKind::RecipeStarted,
             tick: *tick,
             node: node_id_to_ffi(*node),
             edge: 0,
-            item_type: 0,
-            quantity: 0,
+            item_type: recipe.map_or(0, |r| r.0),
+            quantity: u32::from(recipe.is_some()),
             building_type: 0,
             from_node: 0,
             to_node: 0,
@@ -438,53 +914,182 @@ fn convert_event(event: &Event) -> FfiEvent {
             from_node: 0,
             to_node: 0,
         },
+        Event::CraftStarted { node, recipe, tick } => FfiEvent {
+            kind: FfiEventKind::CraftStarted,
+            tick: *tick,
+            node: node_id_to_ffi(*node),
+            edge: 0,
+            item_type: recipe.0,
+            quantity: 0,
+            building_type: 0,
+            from_node: 0,
+            to_node: 0,
+        },
+        Event::CraftCompleted {
+            node,
+            recipe,
+            remaining,
+            tick,
+        } => FfiEvent {
+            kind: FfiEventKind::CraftCompleted,
+            tick: *tick,
+            node: node_id_to_ffi(*node),
+            edge: 0,
+            item_type: recipe.0,
+            quantity: *remaining,
+            building_type: 0,
+            from_node: 0,
+            to_node: 0,
+        },
+        Event::ItemSpoiled {
+            node,
+            item_type,
+            quantity,
+            tick,
+            ..
+        } => FfiEvent {
+            kind: FfiEventKind::ItemSpoiled,
+            tick: *tick,
+            node: node_id_to_ffi(*node),
+            edge: 0,
+            item_type: item_type.0,
+            quantity: *quantity,
+            building_type: 0,
+            from_node: 0,
+            to_node: 0,
+        },
+        Event::InventoryFull {
+            node,
+            side,
+            slot,
+            item_type,
+            tick,
+        }
+        | Event::InventorySpaceAvailable {
+            node,
+            side,
+            slot,
+            item_type,
+            tick,
+        } => FfiEvent {
+            kind: if matches!(event, Event::InventoryFull { .. }) {
+                FfiEventKind::InventoryFull
+            } else {
+                FfiEventKind::InventorySpaceAvailable
+            },
+            tick: *tick,
+            node: node_id_to_ffi(*node),
+            edge: 0,
+            item_type: item_type.0,
+            quantity: *slot,
+            building_type: match side {
+                InventorySide::Input => 0,
+                InventorySide::Output => 1,
+            },
+            from_node: 0,
+            to_node: 0,
+        },
+        Event::StepTruncated {
+            phase,
+            work_done,
+            tick,
+        } => FfiEvent {
+            kind: FfiEventKind::StepTruncated,
+            tick: *tick,
+            node: 0,
+            edge: 0,
+            item_type: 0,
+            quantity: u32::try_from(*work_done).unwrap_or(u32::MAX),
+            building_type: match phase {
+                StepPhase::PreTick => 0,
+                StepPhase::Transport => 1,
+                StepPhase::Process => 2,
+                StepPhase::Component => 3,
+            },
+            from_node: 0,
+            to_node: 0,
+        },
     }
 }
 
 // ---------------------------------------------------------------------------
-// Engine-owned caches (thread-local)
+// Engine-owned caches
 // ---------------------------------------------------------------------------
 
-// We store cached events and mutation results in thread-locals so the engine
-// pointer remains a plain *mut Engine. The caches are valid until the next
-// step/apply_mutations/destroy call.
-//
-// NOTE: For a real multi-engine scenario you'd use a side-table keyed by
-// engine pointer. For simplicity we use a thread-local vec here since the
-// C API is single-threaded per engine.
-thread_local! {
-    static EVENT_CACHE: std::cell::RefCell<Vec<FfiEvent>> = const { std::cell::RefCell::new(Vec::new()) };
-    static MUTATION_NODE_CACHE: std::cell::RefCell<Vec<FfiIdPair>> = const { std::cell::RefCell::new(Vec::new()) };
-    static MUTATION_EDGE_CACHE: std::cell::RefCell<Vec<FfiIdPair>> = const { std::cell::RefCell::new(Vec::new()) };
+// Buffers returned to C live in the `FactorialEngine` they came from and stay
+// valid until the next call that refills them (step/advance for events,
+// apply_mutations for mutation results, the same query for query results)
+// or until the engine is destroyed.
+
+/// Default maximum number of events held in the poll cache per step.
+pub const DEFAULT_EVENT_CACHE_LIMIT: u32 = 262_144;
+
+/// Per-engine event cache, shared with the passive listeners. Events past
+/// `limit` are counted in `dropped` instead of being cached.
+struct EventCache {
+    events: Vec<FfiEvent>,
+    /// The same events as `events`, unflattened, for compact encoding.
+    raw: Vec<Event>,
+    limit: usize,
+    dropped: u64,
+    /// Set once the handle is gone; listeners ignore further events.
+    detached: bool,
+}
+
+impl Default for EventCache {
+    fn default() -> Self {
+        Self {
+            events: Vec::new(),
+            raw: Vec::new(),
+            limit: DEFAULT_EVENT_CACHE_LIMIT as usize,
+            dropped: 0,
+            detached: false,
+        }
+    }
 }
 
+/// Every event kind, indexed by its `FfiEventKind` value.
+const ALL_EVENT_KINDS: [EventKind; 19] = [
+    EventKind::ItemProduced,
+    EventKind::ItemConsumed,
+    EventKind::RecipeStarted,
+    EventKind::RecipeCompleted,
+    EventKind::BuildingStalled,
+    EventKind::BuildingResumed,
+    EventKind::ItemDelivered,
+    EventKind::TransportFull,
+    EventKind::NodeAdded,
+    EventKind::NodeRemoved,
+    EventKind::EdgeAdded,
+    EventKind::EdgeRemoved,
+    EventKind::RecipeSwitched,
+    EventKind::CraftStarted,
+    EventKind::CraftCompleted,
+    EventKind::ItemSpoiled,
+    EventKind::InventoryFull,
+    EventKind::InventorySpaceAvailable,
+    EventKind::StepTruncated,
+];
+
 /// Register passive listeners on all event kinds that capture events into
-/// the thread-local `EVENT_CACHE`. This must be called once after creating
-/// an engine to enable pull-based event polling.
-fn register_ffi_event_listeners(engine: &mut Engine) {
-    let all_kinds = [
-        EventKind::ItemProduced,
-        EventKind::ItemConsumed,
-        EventKind::RecipeStarted,
-        EventKind::RecipeCompleted,
-        EventKind::BuildingStalled,
-        EventKind::BuildingResumed,
-        EventKind::ItemDelivered,
-        EventKind::TransportFull,
-        EventKind::NodeAdded,
-        EventKind::NodeRemoved,
-        EventKind::EdgeAdded,
-        EventKind::EdgeRemoved,
-        EventKind::RecipeSwitched,
-    ];
-
-    for kind in all_kinds {
+/// `cache`, up to its limit. This must be called once after creating an
+/// engine to enable pull-based event polling.
+fn register_ffi_event_listeners(engine: &mut Engine, cache: &Arc<Mutex<EventCache>>) {
+    for kind in ALL_EVENT_KINDS {
+        let cache = Arc::clone(cache);
         engine.on_passive(
             kind,
             Box::new(move |event: &Event| {
-                EVENT_CACHE.with(|c| {
-                    c.borrow_mut().push(convert_event(event));
-                });
+                let mut cache = cache.lock().unwrap_or_else(PoisonError::into_inner);
+                if cache.detached {
+                    return;
+                }
+                if cache.events.len() < cache.limit {
+                    cache.events.push(convert_event(event));
+                    cache.raw.push(event.clone());
+                } else {
+                    cache.dropped += 1;
+                }
             }),
         );
     }
@@ -506,12 +1111,8 @@ fn register_ffi_event_listeners(engine: &mut Engine) {
 #[unsafe(no_mangle)]
 pub extern "C" fn factorial_create() -> *mut FactorialEngine {
     match catch_unwind(|| {
-        let mut engine = Engine::new(SimulationStrategy::Tick);
-        register_ffi_event_listeners(&mut engine);
-        Box::into_raw(Box::new(FactorialEngine {
-            inner: engine,
-            poisoned: false,
-        }))
+        let engine = Engine::new(SimulationStrategy::Tick);
+        Box::into_raw(Box::new(FactorialEngine::new(engine)))
     }) {
         Ok(ptr) => ptr,
         Err(_) => ptr::null_mut(),
@@ -525,12 +1126,8 @@ pub extern "C" fn factorial_create() -> *mut FactorialEngine {
 #[unsafe(no_mangle)]
 pub extern "C" fn factorial_create_delta(fixed_timestep: u64) -> *mut FactorialEngine {
     match catch_unwind(|| {
-        let mut engine = Engine::new(SimulationStrategy::Delta { fixed_timestep });
-        register_ffi_event_listeners(&mut engine);
-        Box::into_raw(Box::new(FactorialEngine {
-            inner: engine,
-            poisoned: false,
-        }))
+        let engine = Engine::new(SimulationStrategy::Delta { fixed_timestep });
+        Box::into_raw(Box::new(FactorialEngine::new(engine)))
     }) {
         Ok(ptr) => ptr,
         Err(_) => ptr::null_mut(),
@@ -542,29 +1139,104 @@ pub extern "C" fn factorial_create_delta(fixed_timestep: u64) -> *mut FactorialE
 /// # Safety
 ///
 /// `engine` must be a pointer returned by `factorial_create` that has not
-/// yet been destroyed. After this call the pointer is invalid.
+/// yet been destroyed. After this call the pointer is invalid. Handles from
+/// `factorial_wrap_borrowed` are released with `factorial_unwrap` instead;
+/// destroying one releases the handle but never frees the borrowed engine.
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn factorial_destroy(engine: *mut FactorialEngine) -> FactorialResult {
     if engine.is_null() {
         return FactorialResult::NullPointer;
     }
+    // The guard keeps the access flag alive past the handle.
+    let _mutation = unsafe { MutationGuard::begin(engine) };
     match catch_unwind(std::panic::AssertUnwindSafe(|| {
         // SAFETY: caller guarantees `engine` was returned by factorial_create.
         let _ = unsafe { Box::from_raw(engine) };
-        // Clear event cache.
-        EVENT_CACHE.with(|c| c.borrow_mut().clear());
     })) {
         Ok(()) => FactorialResult::Ok,
         Err(_) => FactorialResult::InternalError,
     }
 }
 
+/// Wrap an `Engine` owned by Rust code in an FFI handle without taking
+/// ownership, so an embedder can hand its existing engine to C plugins.
+/// The handle registers the same event listeners and caches as one from
+/// `factorial_create`.
+///
+/// Release the handle with `factorial_unwrap`, never `factorial_destroy`.
+/// The engine keeps the handle's event listeners after unwrapping; they
+/// ignore events from then on.
+///
+/// Returns null if `engine` is null or on internal error.
+///
+/// # Safety
+///
+/// `engine` must point to a live `Engine` that outlives the handle. Until
+/// `factorial_unwrap` returns, the caller must not access, move or drop the
+/// engine except through the handle. If the engine has listeners, modules or
+/// hooks that are not `Send`, the handle must stay on the engine's thread
+/// and must not be added to an engine pool.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_wrap_borrowed(engine: *mut Engine) -> *mut FactorialEngine {
+    let Some(engine) = NonNull::new(engine) else {
+        return ptr::null_mut();
+    };
+    match catch_unwind(std::panic::AssertUnwindSafe(|| {
+        Box::into_raw(Box::new(FactorialEngine::with_slot(EngineSlot::Borrowed(
+            engine,
+        ))))
+    })) {
+        Ok(ptr) => ptr,
+        Err(_) => ptr::null_mut(),
+    }
+}
+
+/// Release a handle from `factorial_wrap_borrowed`, returning control of
+/// the engine to its owner. The engine is not freed. Buffers the handle
+/// handed out are invalid afterwards.
+///
+/// Returns `InvalidHandle` and leaves the handle untouched if it owns its
+/// engine (use `factorial_destroy` for those). Returns `Poisoned` if a
+/// panic interrupted an earlier call; the handle is still released, but the
+/// engine may be in an inconsistent state.
+///
+/// # Safety
+///
+/// `engine` must be a pointer returned by `factorial_wrap_borrowed` that has
+/// not yet been released. After this call the pointer is invalid.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_unwrap(engine: *mut FactorialEngine) -> FactorialResult {
+    if engine.is_null() {
+        return FactorialResult::NullPointer;
+    }
+    let _mutation = unsafe { MutationGuard::begin(engine) };
+    match catch_unwind(std::panic::AssertUnwindSafe(|| {
+        // SAFETY: caller guarantees `engine` is a live handle.
+        if matches!(unsafe { &(*engine).inner }, EngineSlot::Owned(_)) {
+            return FactorialResult::InvalidHandle;
+        }
+        // SAFETY: the handle was allocated by `factorial_wrap_borrowed`.
+        let handle = unsafe { Box::from_raw(engine) };
+        if handle.poisoned {
+            FactorialResult::Poisoned
+        } else {
+            FactorialResult::Ok
+        }
+    })) {
+        Ok(result) => result,
+        Err(_) => FactorialResult::InternalError,
+    }
+}
+
 // ---------------------------------------------------------------------------
 // Simulation
 // ---------------------------------------------------------------------------
 
 /// Advance the simulation by one tick (tick mode) or by `dt` ticks (delta mode).
 ///
+/// Returns `WorkLimitReached` if the step exceeded the work limit set with
+/// `factorial_set_step_work_limit`.
+///
 /// # Safety
 ///
 /// `engine` must be a valid engine pointer.
@@ -573,6 +1245,7 @@ pub unsafe extern "C" fn factorial_step(engine: *mut FactorialEngine) -> Factori
     if engine.is_null() {
         return FactorialResult::NullPointer;
     }
+    let _mutation = unsafe { MutationGuard::begin(engine) };
     match catch_unwind(std::panic::AssertUnwindSafe(|| {
         let engine = unsafe { &mut *engine };
         if engine.poisoned {
@@ -580,8 +1253,10 @@ pub unsafe extern "C" fn factorial_step(engine: *mut FactorialEngine) -> Factori
         }
         // Clear previous events before stepping so the cache only contains
         // events from this step.
-        EVENT_CACHE.with(|c| c.borrow_mut().clear());
-        engine.inner.step();
+        engine.clear_event_cache();
+        if engine.inner.step().truncated {
+            return FactorialResult::WorkLimitReached;
+        }
         FactorialResult::Ok
     })) {
         Ok(result) => result,
@@ -594,7 +1269,15 @@ pub unsafe extern "C" fn factorial_step(engine: *mut FactorialEngine) -> Factori
 }
 
 /// Advance the simulation by `dt` ticks. In tick mode `dt` is ignored
-/// and exactly one step runs. In delta mode, `dt` is accumulated.
+/// and exactly one step runs. In delta mode, `dt` is accumulated and one
+/// step runs per whole fixed timestep.
+///
+/// The event buffer is cleared once, before the first step, and then
+/// collects the events of every step this call runs, in order. Each event
+/// carries the tick of the step that emitted it.
+///
+/// Returns `WorkLimitReached` if a step exceeded the work limit set with
+/// `factorial_set_step_work_limit`. No further steps run in that call.
 ///
 /// # Safety
 ///
@@ -607,14 +1290,17 @@ pub unsafe extern "C" fn factorial_advance(
     if engine.is_null() {
         return FactorialResult::NullPointer;
     }
+    let _mutation = unsafe { MutationGuard::begin(engine) };
     match catch_unwind(std::panic::AssertUnwindSafe(|| {
         let engine = unsafe { &mut *engine };
         if engine.poisoned {
             return FactorialResult::Poisoned;
         }
         // Clear previous events before advancing.
-        EVENT_CACHE.with(|c| c.borrow_mut().clear());
-        engine.inner.advance(dt);
+        engine.clear_event_cache();
+        if engine.inner.advance(dt).truncated {
+            return FactorialResult::WorkLimitReached;
+        }
         FactorialResult::Ok
     })) {
         Ok(result) => result,
@@ -626,36 +1312,36 @@ pub unsafe extern "C" fn factorial_advance(
     }
 }
 
-// ---------------------------------------------------------------------------
-// Graph mutation: queue operations
-// ---------------------------------------------------------------------------
-
-/// Queue a node to be added to the graph. Returns a pending node ID
-/// via `out_pending`. The real node ID is assigned after `factorial_apply_mutations`.
+/// Open a sliced tick: one step run in pieces by `factorial_continue_tick`,
+/// ending in the same state and events as `factorial_step`. Clears the event
+/// buffer, which collects the tick's events when it completes.
+///
+/// Until the tick completes, mutating calls return `TickInProgress` and
+/// `factorial_serialize` fails the same way. `factorial_step` and
+/// `factorial_advance` finish the open tick instead of starting another.
+///
+/// Returns `TickInProgress` if a tick is already open. A paused engine opens
+/// no tick and returns `Ok`, like `factorial_step`.
 ///
 /// # Safety
 ///
-/// `engine` and `out_pending` must be valid pointers.
+/// `engine` must be a valid engine pointer.
 #[unsafe(no_mangle)]
-pub unsafe extern "C" fn factorial_add_node(
-    engine: *mut FactorialEngine,
-    building_type: u32,
-    out_pending: *mut FfiPendingNodeId,
-) -> FactorialResult {
-    if engine.is_null() || out_pending.is_null() {
+pub unsafe extern "C" fn factorial_begin_tick(engine: *mut FactorialEngine) -> FactorialResult {
+    if engine.is_null() {
         return FactorialResult::NullPointer;
     }
+    let _mutation = unsafe { MutationGuard::begin(engine) };
     match catch_unwind(std::panic::AssertUnwindSafe(|| {
         let engine = unsafe { &mut *engine };
-        if engine.poisoned {
-            return FactorialResult::Poisoned;
+        if let Some(refused) = engine.refuse_mutation() {
+            return refused;
+        }
+        engine.clear_event_cache();
+        match engine.inner.begin_tick() {
+            Ok(()) | Err(TickSliceError::Paused) => FactorialResult::Ok,
+            Err(TickSliceError::InProgress) => FactorialResult::TickInProgress,
         }
-        let pending = engine
-            .inner
-            .graph
-            .queue_add_node(BuildingTypeId(building_type));
-        unsafe { *out_pending = pending.0 };
-        FactorialResult::Ok
     })) {
         Ok(result) => result,
         Err(_) => {
@@ -666,29 +1352,55 @@ pub unsafe extern "C" fn factorial_add_node(
     }
 }
 
-/// Queue a node for removal.
+/// Where a sliced tick stands after `factorial_continue_tick`.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FfiTickSliceStatus {
+    /// The tick has work left; call `factorial_continue_tick` again.
+    TickSliceInProgress = 0,
+    /// The tick is complete, or no tick was open.
+    TickSliceComplete = 1,
+}
+
+/// Run up to `budget` units of the open sliced tick: the pre-tick phase,
+/// one edge or one node each, the component phase, and the end of the
+/// step each count as one. A budget of 0 counts as 1. Writes whether the
+/// tick completed to `out_status`.
+///
+/// Returns `WorkLimitReached` when the tick completes over the step work
+/// limit set with `factorial_set_step_work_limit`.
 ///
 /// # Safety
 ///
-/// `engine` must be a valid engine pointer.
+/// `engine` and `out_status` must be valid pointers.
 #[unsafe(no_mangle)]
-pub unsafe extern "C" fn factorial_remove_node(
+pub unsafe extern "C" fn factorial_continue_tick(
     engine: *mut FactorialEngine,
-    node_id: FfiNodeId,
+    budget: u32,
+    out_status: *mut FfiTickSliceStatus,
 ) -> FactorialResult {
-    if engine.is_null() {
+    if engine.is_null() || out_status.is_null() {
         return FactorialResult::NullPointer;
     }
+    let _mutation = unsafe { MutationGuard::begin(engine) };
     match catch_unwind(std::panic::AssertUnwindSafe(|| {
         let engine = unsafe { &mut *engine };
         if engine.poisoned {
             return FactorialResult::Poisoned;
         }
-        engine
-            .inner
-            .graph
-            .queue_remove_node(ffi_to_node_id(node_id));
-        FactorialResult::Ok
+        match engine.inner.continue_tick(budget) {
+            TickSliceResult::InProgress => {
+                unsafe { *out_status = FfiTickSliceStatus::TickSliceInProgress };
+                FactorialResult::Ok
+            }
+            TickSliceResult::Complete(result) => {
+                unsafe { *out_status = FfiTickSliceStatus::TickSliceComplete };
+                if result.truncated {
+                    return FactorialResult::WorkLimitReached;
+                }
+                FactorialResult::Ok
+            }
+        }
     })) {
         Ok(result) => result,
         Err(_) => {
@@ -699,31 +1411,34 @@ pub unsafe extern "C" fn factorial_remove_node(
     }
 }
 
-/// Queue an edge connecting two nodes. Returns a pending edge ID via `out_pending`.
+/// Limit the work a single step may do; 0 removes the limit.
+///
+/// Work is counted deterministically from the step's operations (processors
+/// ticked, transports advanced, items delivered, events emitted and so on),
+/// never from wall-clock time. A step over the limit skips its remaining
+/// simulation phases, emits a `StepTruncated` event and makes
+/// `factorial_step` or `factorial_advance` return `WorkLimitReached`.
 ///
 /// # Safety
 ///
-/// `engine` and `out_pending` must be valid pointers.
+/// `engine` must be a valid engine pointer.
 #[unsafe(no_mangle)]
-pub unsafe extern "C" fn factorial_connect(
+pub unsafe extern "C" fn factorial_set_step_work_limit(
     engine: *mut FactorialEngine,
-    from_node: FfiNodeId,
-    to_node: FfiNodeId,
-    out_pending: *mut FfiPendingEdgeId,
+    max_work_units: u64,
 ) -> FactorialResult {
-    if engine.is_null() || out_pending.is_null() {
+    if engine.is_null() {
         return FactorialResult::NullPointer;
     }
+    let _mutation = unsafe { MutationGuard::begin(engine) };
     match catch_unwind(std::panic::AssertUnwindSafe(|| {
         let engine = unsafe { &mut *engine };
-        if engine.poisoned {
-            return FactorialResult::Poisoned;
+        if let Some(refused) = engine.refuse_mutation() {
+            return refused;
         }
-        let pending = engine
+        engine
             .inner
-            .graph
-            .queue_connect(ffi_to_node_id(from_node), ffi_to_node_id(to_node));
-        unsafe { *out_pending = pending.0 };
+            .set_step_work_limit((max_work_units > 0).then_some(max_work_units));
         FactorialResult::Ok
     })) {
         Ok(result) => result,
@@ -735,25 +1450,52 @@ pub unsafe extern "C" fn factorial_connect(
     }
 }
 
-/// Queue an edge for removal (disconnect).
+/// C-compatible simulation strategy kind for `factorial_set_strategy`.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FfiSimulationStrategy {
+    /// One step per `factorial_step` or `factorial_advance` call.
+    Tick = 0,
+    /// `factorial_advance` accumulates `dt` and runs fixed steps.
+    Delta = 1,
+}
+
+/// Switch the simulation strategy between steps, without recreating the
+/// engine. `fixed_timestep` is the ticks per step for `Delta` and ignored
+/// for `Tick`. Factory state and the tick counter are untouched.
+///
+/// Time accumulated by `factorial_advance` but not yet simulated is kept
+/// when switching to `Delta` and discarded when switching to `Tick`. The
+/// discarded ticks are written to `out_discarded` when it is non-null.
 ///
 /// # Safety
 ///
-/// `engine` must be a valid engine pointer.
+/// `engine` must be a valid engine pointer. `out_discarded` must be valid
+/// or null.
 #[unsafe(no_mangle)]
-pub unsafe extern "C" fn factorial_disconnect(
+pub unsafe extern "C" fn factorial_set_strategy(
     engine: *mut FactorialEngine,
-    edge_id: FfiEdgeId,
+    strategy: FfiSimulationStrategy,
+    fixed_timestep: u64,
+    out_discarded: *mut u64,
 ) -> FactorialResult {
     if engine.is_null() {
         return FactorialResult::NullPointer;
     }
+    let _mutation = unsafe { MutationGuard::begin(engine) };
     match catch_unwind(std::panic::AssertUnwindSafe(|| {
         let engine = unsafe { &mut *engine };
-        if engine.poisoned {
-            return FactorialResult::Poisoned;
+        if let Some(refused) = engine.refuse_mutation() {
+            return refused;
+        }
+        let strategy = match strategy {
+            FfiSimulationStrategy::Tick => SimulationStrategy::Tick,
+            FfiSimulationStrategy::Delta => SimulationStrategy::Delta { fixed_timestep },
+        };
+        let discarded = engine.inner.set_strategy(strategy);
+        if !out_discarded.is_null() {
+            unsafe { *out_discarded = discarded };
         }
-        engine.inner.graph.queue_disconnect(ffi_to_edge_id(edge_id));
         FactorialResult::Ok
     })) {
         Ok(result) => result,
@@ -765,133 +1507,120 @@ pub unsafe extern "C" fn factorial_disconnect(
     }
 }
 
-/// Apply all queued graph mutations atomically. Results (pending->real ID
-/// mappings) are written to `out_result`.
+/// Read the simulation strategy. `out_fixed_timestep` is 0 in tick mode.
 ///
 /// # Safety
 ///
-/// `engine` and `out_result` must be valid pointers. The pointers in the
-/// returned `FfiMutationResult` are valid until the next call to
-/// `factorial_apply_mutations` or `factorial_destroy`.
+/// `engine` and both out pointers must be valid.
 #[unsafe(no_mangle)]
-pub unsafe extern "C" fn factorial_apply_mutations(
-    engine: *mut FactorialEngine,
-    out_result: *mut FfiMutationResult,
+pub unsafe extern "C" fn factorial_get_strategy(
+    engine: *const FactorialEngine,
+    out_strategy: *mut FfiSimulationStrategy,
+    out_fixed_timestep: *mut u64,
 ) -> FactorialResult {
-    if engine.is_null() || out_result.is_null() {
+    if engine.is_null() || out_strategy.is_null() || out_fixed_timestep.is_null() {
         return FactorialResult::NullPointer;
     }
     match catch_unwind(std::panic::AssertUnwindSafe(|| {
-        let engine = unsafe { &mut *engine };
+        let engine = unsafe { &*engine };
         if engine.poisoned {
             return FactorialResult::Poisoned;
         }
-        let result = engine.inner.graph.apply_mutations();
-
-        // Convert to FFI-safe pairs and store in thread-local caches.
-        let node_pairs: Vec<FfiIdPair> = result
-            .added_nodes
-            .iter()
-            .map(|(pending, real)| FfiIdPair {
-                pending_id: pending.0,
-                real_id: node_id_to_ffi(*real),
-            })
-            .collect();
-
-        let edge_pairs: Vec<FfiIdPair> = result
-            .added_edges
-            .iter()
-            .map(|(pending, real)| FfiIdPair {
-                pending_id: pending.0,
-                real_id: edge_id_to_ffi(*real),
-            })
-            .collect();
-
-        MUTATION_NODE_CACHE.with(|c| *c.borrow_mut() = node_pairs);
-        MUTATION_EDGE_CACHE.with(|c| *c.borrow_mut() = edge_pairs);
-
-        MUTATION_NODE_CACHE.with(|c| {
-            let nodes = c.borrow();
-            MUTATION_EDGE_CACHE.with(|e| {
-                let edges = e.borrow();
-                unsafe {
-                    *out_result = FfiMutationResult {
-                        added_nodes: if nodes.is_empty() {
-                            ptr::null()
-                        } else {
-                            nodes.as_ptr()
-                        },
-                        added_node_count: nodes.len() as u32,
-                        added_edges: if edges.is_empty() {
-                            ptr::null()
-                        } else {
-                            edges.as_ptr()
-                        },
-                        added_edge_count: edges.len() as u32,
-                    };
-                }
-            });
-        });
+        let (strategy, fixed_timestep) = match engine.inner.strategy() {
+            SimulationStrategy::Tick => (FfiSimulationStrategy::Tick, 0),
+            SimulationStrategy::Delta { fixed_timestep } => {
+                (FfiSimulationStrategy::Delta, *fixed_timestep)
+            }
+        };
+        unsafe {
+            *out_strategy = strategy;
+            *out_fixed_timestep = fixed_timestep;
+        }
         FactorialResult::Ok
     })) {
         Ok(result) => result,
-        Err(_) => {
-            let engine = unsafe { &mut *engine };
-            engine.poisoned = true;
-            FactorialResult::InternalError
-        }
+        Err(_) => FactorialResult::InternalError,
     }
 }
 
 // ---------------------------------------------------------------------------
-// Queries
+// Engine pools
 // ---------------------------------------------------------------------------
 
-/// Get the number of nodes in the graph.
+/// A set of independent engines stepped together, optionally in parallel.
+///
+/// The pool borrows its engines: adding one does not transfer ownership, and
+/// destroying the pool leaves them alive. While `factorial_pool_step_all`
+/// runs, its engines must not be used from any other thread.
+pub struct FactorialEnginePool {
+    engines: Vec<*mut FactorialEngine>,
+}
+
+/// An engine pointer handed to one worker thread of a pool step.
+struct PooledEngine(*mut FactorialEngine);
+
+// SAFETY: `FactorialEngine` is `Send`, and a pool never holds the same engine
+// twice, so each pointer is used by exactly one worker at a time.
+unsafe impl Send for PooledEngine {}
+
+/// Create an empty engine pool. The caller must eventually call
+/// `factorial_pool_destroy`.
+///
+/// Returns null on internal error.
+#[unsafe(no_mangle)]
+pub extern "C" fn factorial_pool_create() -> *mut FactorialEnginePool {
+    match catch_unwind(|| {
+        Box::into_raw(Box::new(FactorialEnginePool {
+            engines: Vec::new(),
+        }))
+    }) {
+        Ok(ptr) => ptr,
+        Err(_) => ptr::null_mut(),
+    }
+}
+
+/// Destroy a pool. The engines in it are not destroyed.
 ///
 /// # Safety
 ///
-/// `engine` and `out_count` must be valid pointers.
+/// `pool` must be a pointer returned by `factorial_pool_create` that has not
+/// yet been destroyed. After this call the pointer is invalid.
 #[unsafe(no_mangle)]
-pub unsafe extern "C" fn factorial_node_count(
-    engine: *const FactorialEngine,
-    out_count: *mut u32,
-) -> FactorialResult {
-    if engine.is_null() || out_count.is_null() {
+pub unsafe extern "C" fn factorial_pool_destroy(pool: *mut FactorialEnginePool) -> FactorialResult {
+    if pool.is_null() {
         return FactorialResult::NullPointer;
     }
     match catch_unwind(std::panic::AssertUnwindSafe(|| {
-        let engine = unsafe { &*engine };
-        if engine.poisoned {
-            return FactorialResult::Poisoned;
-        }
-        unsafe { *out_count = engine.inner.node_count() as u32 };
-        FactorialResult::Ok
+        // SAFETY: caller guarantees `pool` was returned by factorial_pool_create.
+        let _ = unsafe { Box::from_raw(pool) };
     })) {
-        Ok(result) => result,
+        Ok(()) => FactorialResult::Ok,
         Err(_) => FactorialResult::InternalError,
     }
 }
 
-/// Get the number of edges in the graph.
+/// Add an engine to a pool. Returns `InvalidHandle` if the engine is already
+/// in the pool.
 ///
 /// # Safety
 ///
-/// `engine` and `out_count` must be valid pointers.
+/// `pool` must be a valid pointer. `engine` is not dereferenced here, but
+/// it must be a live engine that stays alive until it is removed from the
+/// pool or the pool is destroyed.
 #[unsafe(no_mangle)]
-pub unsafe extern "C" fn factorial_edge_count(
-    engine: *const FactorialEngine,
-    out_count: *mut u32,
+pub unsafe extern "C" fn factorial_pool_add(
+    pool: *mut FactorialEnginePool,
+    engine: *mut FactorialEngine,
 ) -> FactorialResult {
-    if engine.is_null() || out_count.is_null() {
+    if pool.is_null() || engine.is_null() {
         return FactorialResult::NullPointer;
     }
     match catch_unwind(std::panic::AssertUnwindSafe(|| {
-        let engine = unsafe { &*engine };
-        if engine.poisoned {
-            return FactorialResult::Poisoned;
+        let pool = unsafe { &mut *pool };
+        if pool.engines.contains(&engine) {
+            return FactorialResult::InvalidHandle;
         }
-        unsafe { *out_count = engine.inner.edge_count() as u32 };
+        pool.engines.push(engine);
         FactorialResult::Ok
     })) {
         Ok(result) => result,
@@ -899,51 +1628,52 @@ pub unsafe extern "C" fn factorial_edge_count(
     }
 }
 
-/// Get the current tick counter.
+/// Remove an engine from a pool without destroying it. Returns
+/// `InvalidHandle` if the engine is not in the pool.
 ///
 /// # Safety
 ///
-/// `engine` and `out_tick` must be valid pointers.
+/// `pool` must be a valid pointer. `engine` is only compared, never
+/// dereferenced, so it may already have been destroyed.
 #[unsafe(no_mangle)]
-pub unsafe extern "C" fn factorial_get_tick(
-    engine: *const FactorialEngine,
-    out_tick: *mut u64,
+pub unsafe extern "C" fn factorial_pool_remove(
+    pool: *mut FactorialEnginePool,
+    engine: *mut FactorialEngine,
 ) -> FactorialResult {
-    if engine.is_null() || out_tick.is_null() {
+    if pool.is_null() || engine.is_null() {
         return FactorialResult::NullPointer;
     }
     match catch_unwind(std::panic::AssertUnwindSafe(|| {
-        let engine = unsafe { &*engine };
-        if engine.poisoned {
-            return FactorialResult::Poisoned;
+        let pool = unsafe { &mut *pool };
+        match pool.engines.iter().position(|&e| e == engine) {
+            Some(index) => {
+                pool.engines.remove(index);
+                FactorialResult::Ok
+            }
+            None => FactorialResult::InvalidHandle,
         }
-        unsafe { *out_tick = engine.inner.sim_state.tick };
-        FactorialResult::Ok
     })) {
         Ok(result) => result,
         Err(_) => FactorialResult::InternalError,
     }
 }
 
-/// Get the state hash of the engine (for desync detection).
+/// Get the number of engines in a pool.
 ///
 /// # Safety
 ///
-/// `engine` and `out_hash` must be valid pointers.
+/// `pool` and `out_count` must be valid pointers.
 #[unsafe(no_mangle)]
-pub unsafe extern "C" fn factorial_get_state_hash(
-    engine: *const FactorialEngine,
-    out_hash: *mut u64,
+pub unsafe extern "C" fn factorial_pool_len(
+    pool: *const FactorialEnginePool,
+    out_count: *mut u32,
 ) -> FactorialResult {
-    if engine.is_null() || out_hash.is_null() {
+    if pool.is_null() || out_count.is_null() {
         return FactorialResult::NullPointer;
     }
     match catch_unwind(std::panic::AssertUnwindSafe(|| {
-        let engine = unsafe { &*engine };
-        if engine.poisoned {
-            return FactorialResult::Poisoned;
-        }
-        unsafe { *out_hash = engine.inner.state_hash() };
+        let pool = unsafe { &*pool };
+        unsafe { *out_count = pool.engines.len() as u32 };
         FactorialResult::Ok
     })) {
         Ok(result) => result,
@@ -951,324 +1681,337 @@ pub unsafe extern "C" fn factorial_get_state_hash(
     }
 }
 
-/// Get the processor state for a node.
+/// Step every engine in the pool once, exactly as `factorial_step` would.
+///
+/// With `thread_count` of 0 or 1 the engines are stepped in order on the
+/// calling thread; otherwise they are split into up to `thread_count`
+/// contiguous groups, each stepped on its own thread. Engines share no
+/// state, so each one's result is the same either way. The call returns
+/// once every engine has stepped.
+///
+/// If `out_results` is non-null, the result of each engine's step is written
+/// to it in pool order. Returns `Ok` if every engine stepped, otherwise the
+/// first failing engine's result.
 ///
 /// # Safety
 ///
-/// `engine` and `out_info` must be valid pointers.
+/// `pool` must be a valid pointer, and every engine in it must be alive and
+/// unused by other threads for the duration of the call. `out_results`, if
+/// non-null, must point to room for one result per engine.
 #[unsafe(no_mangle)]
-pub unsafe extern "C" fn factorial_get_processor_state(
-    engine: *const FactorialEngine,
-    node_id: FfiNodeId,
-    out_info: *mut FfiProcessorInfo,
+pub unsafe extern "C" fn factorial_pool_step_all(
+    pool: *mut FactorialEnginePool,
+    thread_count: u32,
+    out_results: *mut FactorialResult,
 ) -> FactorialResult {
-    if engine.is_null() || out_info.is_null() {
+    if pool.is_null() {
         return FactorialResult::NullPointer;
     }
     match catch_unwind(std::panic::AssertUnwindSafe(|| {
-        let engine = unsafe { &*engine };
-        if engine.poisoned {
-            return FactorialResult::Poisoned;
-        }
-        let nid = ffi_to_node_id(node_id);
-        match engine.inner.get_processor_state(nid) {
-            Some(state) => {
-                unsafe { *out_info = convert_processor_state(state) };
-                FactorialResult::Ok
+        let pool = unsafe { &*pool };
+        let engines: Vec<PooledEngine> = pool.engines.iter().map(|&e| PooledEngine(e)).collect();
+        let threads = (thread_count as usize).clamp(1, engines.len().max(1));
+        let results: Vec<FactorialResult> = if threads == 1 {
+            engines
+                .iter()
+                .map(|engine| unsafe { factorial_step(engine.0) })
+                .collect()
+        } else {
+            let group_size = engines.len().div_ceil(threads);
+            let mut groups: Vec<Vec<PooledEngine>> = Vec::with_capacity(threads);
+            let mut engines = engines.into_iter().peekable();
+            while engines.peek().is_some() {
+                groups.push(engines.by_ref().take(group_size).collect());
+            }
+            std::thread::scope(|scope| {
+                let workers: Vec<_> = groups
+                    .into_iter()
+                    .map(|group| {
+                        scope.spawn(move || {
+                            group
+                                .iter()
+                                .map(|engine| unsafe { factorial_step(engine.0) })
+                                .collect::<Vec<_>>()
+                        })
+                    })
+                    .collect();
+                workers
+                    .into_iter()
+                    .flat_map(|worker| worker.join().expect("pool worker panicked"))
+                    .collect()
+            })
+        };
+        if !out_results.is_null() {
+            for (i, &result) in results.iter().enumerate() {
+                unsafe { *out_results.add(i) = result };
             }
-            None => FactorialResult::NodeNotFound,
         }
+        results
+            .into_iter()
+            .find(|&result| result != FactorialResult::Ok)
+            .unwrap_or(FactorialResult::Ok)
     })) {
         Ok(result) => result,
         Err(_) => FactorialResult::InternalError,
     }
 }
 
-/// Get the total item count in a node's input inventory.
+// ---------------------------------------------------------------------------
+// Graph mutation: queue operations
+// ---------------------------------------------------------------------------
+
+/// Queue a node to be added to the graph. Returns a pending node ID
+/// via `out_pending`. The real node ID is assigned after `factorial_apply_mutations`.
 ///
 /// # Safety
 ///
-/// `engine` and `out_count` must be valid pointers.
+/// `engine` and `out_pending` must be valid pointers.
 #[unsafe(no_mangle)]
-pub unsafe extern "C" fn factorial_get_input_inventory_count(
-    engine: *const FactorialEngine,
-    node_id: FfiNodeId,
-    out_count: *mut u32,
+pub unsafe extern "C" fn factorial_add_node(
+    engine: *mut FactorialEngine,
+    building_type: u32,
+    out_pending: *mut FfiPendingNodeId,
 ) -> FactorialResult {
-    if engine.is_null() || out_count.is_null() {
+    if engine.is_null() || out_pending.is_null() {
         return FactorialResult::NullPointer;
     }
+    let _mutation = unsafe { MutationGuard::begin(engine) };
     match catch_unwind(std::panic::AssertUnwindSafe(|| {
-        let engine = unsafe { &*engine };
-        if engine.poisoned {
-            return FactorialResult::Poisoned;
-        }
-        let nid = ffi_to_node_id(node_id);
-        match engine.inner.get_input_inventory(nid) {
-            Some(inv) => {
-                let total: u32 = inv.input_slots.iter().map(|s| s.total()).sum();
-                unsafe { *out_count = total };
-                FactorialResult::Ok
-            }
-            None => FactorialResult::NodeNotFound,
+        let engine = unsafe { &mut *engine };
+        if let Some(refused) = engine.refuse_mutation() {
+            return refused;
         }
+        let pending = engine
+            .inner
+            .graph
+            .queue_add_node(BuildingTypeId(building_type));
+        unsafe { *out_pending = pending.0 };
+        FactorialResult::Ok
     })) {
         Ok(result) => result,
-        Err(_) => FactorialResult::InternalError,
+        Err(_) => {
+            let engine = unsafe { &mut *engine };
+            engine.poisoned = true;
+            FactorialResult::InternalError
+        }
     }
 }
 
-/// Get the total item count in a node's output inventory.
+/// Flag for [`factorial_add_node_ex`]: the node emits no events.
+pub const NODE_FLAG_SILENT: u32 = 1;
+
+/// Flag for [`factorial_add_node_ex`]: statistics trackers ignore the node.
+pub const NODE_FLAG_NO_STATS: u32 = 2;
+
+/// Queue a node to be added to the graph with behaviour flags, a bitwise OR
+/// of `NODE_FLAG_*` values. Unknown bits are ignored. Otherwise behaves like
+/// [`factorial_add_node`].
 ///
 /// # Safety
 ///
-/// `engine` and `out_count` must be valid pointers.
+/// `engine` and `out_pending` must be valid pointers.
 #[unsafe(no_mangle)]
-pub unsafe extern "C" fn factorial_get_output_inventory_count(
-    engine: *const FactorialEngine,
-    node_id: FfiNodeId,
-    out_count: *mut u32,
+pub unsafe extern "C" fn factorial_add_node_ex(
+    engine: *mut FactorialEngine,
+    building_type: u32,
+    flags: u32,
+    out_pending: *mut FfiPendingNodeId,
 ) -> FactorialResult {
-    if engine.is_null() || out_count.is_null() {
+    if engine.is_null() || out_pending.is_null() {
         return FactorialResult::NullPointer;
     }
+    let _mutation = unsafe { MutationGuard::begin(engine) };
     match catch_unwind(std::panic::AssertUnwindSafe(|| {
-        let engine = unsafe { &*engine };
-        if engine.poisoned {
-            return FactorialResult::Poisoned;
-        }
-        let nid = ffi_to_node_id(node_id);
-        match engine.inner.get_output_inventory(nid) {
-            Some(inv) => {
-                let total: u32 = inv.output_slots.iter().map(|s| s.total()).sum();
-                unsafe { *out_count = total };
-                FactorialResult::Ok
-            }
-            None => FactorialResult::NodeNotFound,
+        let engine = unsafe { &mut *engine };
+        if let Some(refused) = engine.refuse_mutation() {
+            return refused;
         }
+        let pending = engine.inner.graph.queue_add_node_with_flags(
+            BuildingTypeId(building_type),
+            NodeFlags::from_bits_truncate(flags),
+        );
+        unsafe { *out_pending = pending.0 };
+        FactorialResult::Ok
     })) {
         Ok(result) => result,
-        Err(_) => FactorialResult::InternalError,
+        Err(_) => {
+            let engine = unsafe { &mut *engine };
+            engine.poisoned = true;
+            FactorialResult::InternalError
+        }
     }
 }
 
-// ---------------------------------------------------------------------------
-// Events (pull-based)
-// ---------------------------------------------------------------------------
-
-/// Poll all buffered events since the last step. Returns a pointer to an
-/// engine-owned buffer of `FfiEvent` structs. The buffer is valid until the
-/// next `factorial_step`, `factorial_advance`, or `factorial_destroy`.
+/// Queue a ghost node: a planned building that is visible to queries and
+/// logistics but does not simulate, costs nothing per tick and is left out
+/// of the state hash. Turn it into a real node with
+/// [`factorial_materialize_ghost`]; it keeps its id and configuration.
+/// Otherwise behaves like [`factorial_add_node`].
 ///
 /// # Safety
 ///
-/// `engine` and `out_buffer` must be valid pointers.
+/// `engine` and `out_pending` must be valid pointers.
 #[unsafe(no_mangle)]
-pub unsafe extern "C" fn factorial_poll_events(
-    engine: *const FactorialEngine,
-    out_buffer: *mut FfiEventBuffer,
+pub unsafe extern "C" fn factorial_add_ghost(
+    engine: *mut FactorialEngine,
+    building_type: u32,
+    out_pending: *mut FfiPendingNodeId,
 ) -> FactorialResult {
-    if engine.is_null() || out_buffer.is_null() {
+    if engine.is_null() || out_pending.is_null() {
         return FactorialResult::NullPointer;
     }
+    let _mutation = unsafe { MutationGuard::begin(engine) };
     match catch_unwind(std::panic::AssertUnwindSafe(|| {
-        let engine = unsafe { &*engine };
-        if engine.poisoned {
-            return FactorialResult::Poisoned;
+        let engine = unsafe { &mut *engine };
+        if let Some(refused) = engine.refuse_mutation() {
+            return refused;
         }
-        // Events were captured into EVENT_CACHE by passive listeners during
-        // the most recent step/advance call. Return a pointer into the cache.
-        EVENT_CACHE.with(|c| {
-            let cache = c.borrow();
-            unsafe {
-                *out_buffer = FfiEventBuffer {
-                    events: if cache.is_empty() {
-                        ptr::null()
-                    } else {
-                        cache.as_ptr()
-                    },
-                    count: cache.len() as u32,
-                };
-            }
-        });
+        let pending = engine
+            .inner
+            .graph
+            .queue_add_ghost(BuildingTypeId(building_type));
+        unsafe { *out_pending = pending.0 };
         FactorialResult::Ok
     })) {
         Ok(result) => result,
-        Err(_) => FactorialResult::InternalError,
+        Err(_) => {
+            let engine = unsafe { &mut *engine };
+            engine.poisoned = true;
+            FactorialResult::InternalError
+        }
     }
 }
 
-// ---------------------------------------------------------------------------
-// Serialization
-// ---------------------------------------------------------------------------
-
-/// Serialize the engine state to a binary buffer. The returned
-/// `FfiByteBuffer` contains a pointer and length. The caller must free the
-/// buffer with `factorial_free_buffer` when done.
+/// Queue a node for removal.
 ///
 /// # Safety
 ///
-/// `engine` and `out_buffer` must be valid pointers.
+/// `engine` must be a valid engine pointer.
 #[unsafe(no_mangle)]
-pub unsafe extern "C" fn factorial_serialize(
-    engine: *const FactorialEngine,
-    out_buffer: *mut FfiByteBuffer,
+pub unsafe extern "C" fn factorial_remove_node(
+    engine: *mut FactorialEngine,
+    node_id: FfiNodeId,
 ) -> FactorialResult {
-    if engine.is_null() || out_buffer.is_null() {
+    if engine.is_null() {
         return FactorialResult::NullPointer;
     }
+    let _mutation = unsafe { MutationGuard::begin(engine) };
     match catch_unwind(std::panic::AssertUnwindSafe(|| {
-        let engine = unsafe { &*engine };
-        if engine.poisoned {
-            return FactorialResult::Poisoned;
-        }
-        match engine.inner.serialize() {
-            Ok(data) => {
-                let len = data.len();
-                let mut boxed = data.into_boxed_slice();
-                let ptr = boxed.as_mut_ptr();
-                std::mem::forget(boxed);
-                unsafe {
-                    *out_buffer = FfiByteBuffer { data: ptr, len };
-                }
-                FactorialResult::Ok
-            }
-            Err(_) => {
-                unsafe {
-                    *out_buffer = FfiByteBuffer {
-                        data: ptr::null_mut(),
-                        len: 0,
-                    };
-                }
-                FactorialResult::SerializeError
-            }
+        let engine = unsafe { &mut *engine };
+        if let Some(refused) = engine.refuse_mutation() {
+            return refused;
         }
+        engine
+            .inner
+            .graph
+            .queue_remove_node(ffi_to_node_id(node_id));
+        FactorialResult::Ok
     })) {
         Ok(result) => result,
-        Err(_) => FactorialResult::InternalError,
+        Err(_) => {
+            let engine = unsafe { &mut *engine };
+            engine.poisoned = true;
+            FactorialResult::InternalError
+        }
     }
 }
 
-/// Deserialize an engine from a binary buffer. Returns a new engine pointer
-/// via `out_engine`. The caller takes ownership.
+/// Queue an edge connecting two nodes. Returns a pending edge ID via `out_pending`.
 ///
 /// # Safety
 ///
-/// `data` must point to `len` valid bytes. `out_engine` must be a valid pointer.
+/// `engine` and `out_pending` must be valid pointers.
 #[unsafe(no_mangle)]
-pub unsafe extern "C" fn factorial_deserialize(
-    data: *const u8,
-    len: usize,
-    out_engine: *mut *mut FactorialEngine,
+pub unsafe extern "C" fn factorial_connect(
+    engine: *mut FactorialEngine,
+    from_node: FfiNodeId,
+    to_node: FfiNodeId,
+    out_pending: *mut FfiPendingEdgeId,
 ) -> FactorialResult {
-    if data.is_null() || out_engine.is_null() {
+    if engine.is_null() || out_pending.is_null() {
         return FactorialResult::NullPointer;
     }
+    let _mutation = unsafe { MutationGuard::begin(engine) };
     match catch_unwind(std::panic::AssertUnwindSafe(|| {
-        let slice = unsafe { std::slice::from_raw_parts(data, len) };
-        match Engine::deserialize(slice) {
-            Ok(mut engine) => {
-                register_ffi_event_listeners(&mut engine);
-                unsafe {
-                    *out_engine = Box::into_raw(Box::new(FactorialEngine {
-                        inner: engine,
-                        poisoned: false,
-                    }))
-                };
-                FactorialResult::Ok
-            }
-            Err(_) => {
-                unsafe { *out_engine = ptr::null_mut() };
-                FactorialResult::DeserializeError
-            }
+        let engine = unsafe { &mut *engine };
+        if let Some(refused) = engine.refuse_mutation() {
+            return refused;
         }
+        let pending = engine
+            .inner
+            .graph
+            .queue_connect(ffi_to_node_id(from_node), ffi_to_node_id(to_node));
+        unsafe { *out_pending = pending.0 };
+        FactorialResult::Ok
     })) {
         Ok(result) => result,
-        Err(_) => FactorialResult::InternalError,
+        Err(_) => {
+            let engine = unsafe { &mut *engine };
+            engine.poisoned = true;
+            FactorialResult::InternalError
+        }
     }
 }
 
-/// Free a byte buffer returned by `factorial_serialize`.
+/// Queue a ghost edge between two nodes, either of which may be a ghost.
+/// [`factorial_connect`] refuses ghost endpoints; planned edges use this
+/// instead. Returns a pending edge ID via `out_pending`.
 ///
 /// # Safety
 ///
-/// `buffer` must be a buffer originally returned by `factorial_serialize`.
-/// After this call the buffer's data pointer is invalid.
+/// `engine` and `out_pending` must be valid pointers.
 #[unsafe(no_mangle)]
-pub unsafe extern "C" fn factorial_free_buffer(buffer: FfiByteBuffer) -> FactorialResult {
-    if buffer.data.is_null() {
-        // Null data is a no-op (not an error).
-        return FactorialResult::Ok;
+pub unsafe extern "C" fn factorial_connect_ghost(
+    engine: *mut FactorialEngine,
+    from_node: FfiNodeId,
+    to_node: FfiNodeId,
+    out_pending: *mut FfiPendingEdgeId,
+) -> FactorialResult {
+    if engine.is_null() || out_pending.is_null() {
+        return FactorialResult::NullPointer;
     }
+    let _mutation = unsafe { MutationGuard::begin(engine) };
     match catch_unwind(std::panic::AssertUnwindSafe(|| {
-        // Reconstruct the boxed slice and let it drop.
-        let _ =
-            unsafe { Box::from_raw(std::ptr::slice_from_raw_parts_mut(buffer.data, buffer.len)) };
+        let engine = unsafe { &mut *engine };
+        if let Some(refused) = engine.refuse_mutation() {
+            return refused;
+        }
+        let pending = engine
+            .inner
+            .graph
+            .queue_connect_ghost(ffi_to_node_id(from_node), ffi_to_node_id(to_node));
+        unsafe { *out_pending = pending.0 };
+        FactorialResult::Ok
     })) {
-        Ok(()) => FactorialResult::Ok,
-        Err(_) => FactorialResult::InternalError,
+        Ok(result) => result,
+        Err(_) => {
+            let engine = unsafe { &mut *engine };
+            engine.poisoned = true;
+            FactorialResult::InternalError
+        }
     }
 }
 
-// ---------------------------------------------------------------------------
-// FFI-safe configuration structs
-// ---------------------------------------------------------------------------
-
-/// C-compatible item stack (item type + quantity).
-#[repr(C)]
-#[derive(Debug, Clone, Copy)]
-pub struct FfiItemStack {
-    pub item_type: u32,
-    pub quantity: u32,
-}
-
-/// C-compatible recipe for FixedRecipe processor.
-#[repr(C)]
-#[derive(Debug)]
-pub struct FfiRecipe {
-    pub input_count: u32,
-    pub inputs: *const FfiItemStack,
-    pub output_count: u32,
-    pub outputs: *const FfiItemStack,
-    pub duration: u32,
-}
-
-// ---------------------------------------------------------------------------
-// Configuration: Processors
-// ---------------------------------------------------------------------------
-
-/// Set a node's processor to Source.
-///
-/// `rate` is raw Fixed64 bits (Q32.32). Use `Fixed64::to_bits()` on the Rust
-/// side or shift an integer left by 32 on the C side to construct it.
+/// Queue an edge for removal (disconnect).
 ///
 /// # Safety
 ///
 /// `engine` must be a valid engine pointer.
 #[unsafe(no_mangle)]
-pub unsafe extern "C" fn factorial_set_source(
+pub unsafe extern "C" fn factorial_disconnect(
     engine: *mut FactorialEngine,
-    node_id: FfiNodeId,
-    item_type: u32,
-    rate: i64,
+    edge_id: FfiEdgeId,
 ) -> FactorialResult {
     if engine.is_null() {
         return FactorialResult::NullPointer;
     }
+    let _mutation = unsafe { MutationGuard::begin(engine) };
     match catch_unwind(std::panic::AssertUnwindSafe(|| {
         let engine = unsafe { &mut *engine };
-        if engine.poisoned {
-            return FactorialResult::Poisoned;
+        if let Some(refused) = engine.refuse_mutation() {
+            return refused;
         }
-        let nid = ffi_to_node_id(node_id);
-        let processor = Processor::Source(SourceProcessor {
-            output_type: ItemTypeId(item_type),
-            base_rate: Fixed64::from_bits(rate),
-            depletion: Depletion::Infinite,
-            accumulated: Fixed64::from_num(0),
-            initial_properties: None,
-        });
-        engine.inner.set_processor(nid, processor);
+        engine.inner.graph.queue_disconnect(ffi_to_edge_id(edge_id));
         FactorialResult::Ok
     })) {
         Ok(result) => result,
@@ -1280,65 +2023,72 @@ pub unsafe extern "C" fn factorial_set_source(
     }
 }
 
-/// Set a node's processor to FixedRecipe.
+/// Queue a node addition in mutation lane `lane`. `factorial_apply_mutations`
+/// applies lanes in ascending order and calls within a lane in call order,
+/// so the relative order of, say, player actions in lane 0 and automation
+/// in lane 1 does not depend on which was queued first. The other queue
+/// calls use lane 0. Otherwise behaves like [`factorial_add_node`].
 ///
-/// The `recipe` pointer must point to a valid `FfiRecipe` whose `inputs` and
-/// `outputs` arrays have the declared counts.
+/// # Safety
+///
+/// `engine` and `out_pending` must be valid pointers.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_add_node_in_lane(
+    engine: *mut FactorialEngine,
+    lane: u8,
+    building_type: u32,
+    out_pending: *mut FfiPendingNodeId,
+) -> FactorialResult {
+    if engine.is_null() || out_pending.is_null() {
+        return FactorialResult::NullPointer;
+    }
+    let _mutation = unsafe { MutationGuard::begin(engine) };
+    match catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let engine = unsafe { &mut *engine };
+        if let Some(refused) = engine.refuse_mutation() {
+            return refused;
+        }
+        let pending = engine
+            .inner
+            .graph
+            .queue_add_node_in_lane(lane, BuildingTypeId(building_type));
+        unsafe { *out_pending = pending.0 };
+        FactorialResult::Ok
+    })) {
+        Ok(result) => result,
+        Err(_) => {
+            let engine = unsafe { &mut *engine };
+            engine.poisoned = true;
+            FactorialResult::InternalError
+        }
+    }
+}
+
+/// Queue a node removal in mutation lane `lane`; see
+/// [`factorial_add_node_in_lane`].
 ///
 /// # Safety
 ///
-/// `engine` and `recipe` must be valid pointers. The arrays referenced by
-/// `recipe.inputs` and `recipe.outputs` must be valid for the declared counts.
+/// `engine` must be a valid engine pointer.
 #[unsafe(no_mangle)]
-pub unsafe extern "C" fn factorial_set_fixed_processor(
+pub unsafe extern "C" fn factorial_remove_node_in_lane(
     engine: *mut FactorialEngine,
+    lane: u8,
     node_id: FfiNodeId,
-    recipe: *const FfiRecipe,
 ) -> FactorialResult {
-    if engine.is_null() || recipe.is_null() {
+    if engine.is_null() {
         return FactorialResult::NullPointer;
     }
+    let _mutation = unsafe { MutationGuard::begin(engine) };
     match catch_unwind(std::panic::AssertUnwindSafe(|| {
         let engine = unsafe { &mut *engine };
-        if engine.poisoned {
-            return FactorialResult::Poisoned;
+        if let Some(refused) = engine.refuse_mutation() {
+            return refused;
         }
-        let recipe = unsafe { &*recipe };
-        let inputs = if recipe.input_count > 0 && !recipe.inputs.is_null() {
-            let ffi_inputs =
-                unsafe { std::slice::from_raw_parts(recipe.inputs, recipe.input_count as usize) };
-            ffi_inputs
-                .iter()
-                .map(|fi| RecipeInput {
-                    item_type: ItemTypeId(fi.item_type),
-                    quantity: fi.quantity,
-                    consumed: true,
-                })
-                .collect()
-        } else {
-            Vec::new()
-        };
-        let outputs = if recipe.output_count > 0 && !recipe.outputs.is_null() {
-            let ffi_outputs =
-                unsafe { std::slice::from_raw_parts(recipe.outputs, recipe.output_count as usize) };
-            ffi_outputs
-                .iter()
-                .map(|fo| RecipeOutput {
-                    item_type: ItemTypeId(fo.item_type),
-                    quantity: fo.quantity,
-                    bonus: None,
-                })
-                .collect()
-        } else {
-            Vec::new()
-        };
-        let nid = ffi_to_node_id(node_id);
-        let processor = Processor::Fixed(FixedRecipe {
-            inputs,
-            outputs,
-            duration: recipe.duration,
-        });
-        engine.inner.set_processor(nid, processor);
+        engine
+            .inner
+            .graph
+            .queue_remove_node_in_lane(lane, ffi_to_node_id(node_id));
         FactorialResult::Ok
     })) {
         Ok(result) => result,
@@ -1350,38 +2100,35 @@ pub unsafe extern "C" fn factorial_set_fixed_processor(
     }
 }
 
-// ---------------------------------------------------------------------------
-// Configuration: Transports
-// ---------------------------------------------------------------------------
-
-/// Set an edge's transport to FlowTransport with default buffer/latency.
-///
-/// `rate` is raw Fixed64 bits (Q32.32).
+/// Queue an edge in mutation lane `lane`; see [`factorial_add_node_in_lane`].
+/// Returns a pending edge ID via `out_pending`.
 ///
 /// # Safety
 ///
-/// `engine` must be a valid engine pointer.
+/// `engine` and `out_pending` must be valid pointers.
 #[unsafe(no_mangle)]
-pub unsafe extern "C" fn factorial_set_flow_transport(
+pub unsafe extern "C" fn factorial_connect_in_lane(
     engine: *mut FactorialEngine,
-    edge_id: FfiEdgeId,
-    rate: i64,
+    lane: u8,
+    from_node: FfiNodeId,
+    to_node: FfiNodeId,
+    out_pending: *mut FfiPendingEdgeId,
 ) -> FactorialResult {
-    if engine.is_null() {
+    if engine.is_null() || out_pending.is_null() {
         return FactorialResult::NullPointer;
     }
+    let _mutation = unsafe { MutationGuard::begin(engine) };
     match catch_unwind(std::panic::AssertUnwindSafe(|| {
         let engine = unsafe { &mut *engine };
-        if engine.poisoned {
-            return FactorialResult::Poisoned;
+        if let Some(refused) = engine.refuse_mutation() {
+            return refused;
         }
-        let eid = ffi_to_edge_id(edge_id);
-        let transport = Transport::Flow(FlowTransport {
-            rate: Fixed64::from_bits(rate),
-            buffer_capacity: Fixed64::from_num(1000),
-            latency: 0,
-        });
-        engine.inner.set_transport(eid, transport);
+        let pending = engine.inner.graph.queue_connect_in_lane(
+            lane,
+            ffi_to_node_id(from_node),
+            ffi_to_node_id(to_node),
+        );
+        unsafe { *out_pending = pending.0 };
         FactorialResult::Ok
     })) {
         Ok(result) => result,
@@ -1393,36 +2140,31 @@ pub unsafe extern "C" fn factorial_set_flow_transport(
     }
 }
 
-/// Set an edge's transport to ItemTransport.
-///
-/// `speed` is raw Fixed64 bits (Q32.32).
+/// Queue an edge removal in mutation lane `lane`; see
+/// [`factorial_add_node_in_lane`].
 ///
 /// # Safety
 ///
 /// `engine` must be a valid engine pointer.
 #[unsafe(no_mangle)]
-pub unsafe extern "C" fn factorial_set_item_transport(
+pub unsafe extern "C" fn factorial_disconnect_in_lane(
     engine: *mut FactorialEngine,
+    lane: u8,
     edge_id: FfiEdgeId,
-    speed: i64,
-    slot_count: u32,
-    lanes: u8,
 ) -> FactorialResult {
     if engine.is_null() {
         return FactorialResult::NullPointer;
     }
+    let _mutation = unsafe { MutationGuard::begin(engine) };
     match catch_unwind(std::panic::AssertUnwindSafe(|| {
         let engine = unsafe { &mut *engine };
-        if engine.poisoned {
-            return FactorialResult::Poisoned;
+        if let Some(refused) = engine.refuse_mutation() {
+            return refused;
         }
-        let eid = ffi_to_edge_id(edge_id);
-        let transport = Transport::Item(ItemTransport {
-            speed: Fixed64::from_bits(speed),
-            slot_count,
-            lanes,
-        });
-        engine.inner.set_transport(eid, transport);
+        engine
+            .inner
+            .graph
+            .queue_disconnect_in_lane(lane, ffi_to_edge_id(edge_id));
         FactorialResult::Ok
     })) {
         Ok(result) => result,
@@ -1434,32 +2176,106 @@ pub unsafe extern "C" fn factorial_set_item_transport(
     }
 }
 
-/// Set an edge's transport to BatchTransport.
+/// Apply all queued graph mutations atomically. Results (pending->real ID
+/// mappings) are written to `out_result`.
+///
+/// Correlate results with queue calls by pending id only, never by index:
+/// an entry's position does not match the order of the calls that queued
+/// it. `added_nodes` holds the node additions that were applied and
+/// `added_edges` the edges that were created. Node additions refused by a
+/// registered module in strict mode are listed in `rejected` instead, and
+/// edges that could not be added, because an endpoint is missing or a
+/// non-ghost edge touches a ghost node, are left out.
+///
+/// Each array is in the order the mutations were applied: by ascending lane
+/// (see `factorial_add_node_in_lane`), then in call order within a lane.
+/// Calls without a lane use lane 0. Every pair also carries its lane.
+///
+/// The resulting graph events are stamped with the upcoming tick and are
+/// returned by `factorial_poll_events` after the next step, not immediately.
 ///
 /// # Safety
 ///
-/// `engine` must be a valid engine pointer.
+/// `engine` and `out_result` must be valid pointers. The pointers in the
+/// returned `FfiMutationResult` are valid until the next call to
+/// `factorial_apply_mutations` or `factorial_destroy`.
 #[unsafe(no_mangle)]
-pub unsafe extern "C" fn factorial_set_batch_transport(
+pub unsafe extern "C" fn factorial_apply_mutations(
     engine: *mut FactorialEngine,
-    edge_id: FfiEdgeId,
-    batch_size: u32,
-    cycle_time: u32,
+    out_result: *mut FfiMutationResult,
 ) -> FactorialResult {
-    if engine.is_null() {
+    if engine.is_null() || out_result.is_null() {
         return FactorialResult::NullPointer;
     }
+    let _mutation = unsafe { MutationGuard::begin(engine) };
     match catch_unwind(std::panic::AssertUnwindSafe(|| {
         let engine = unsafe { &mut *engine };
-        if engine.poisoned {
-            return FactorialResult::Poisoned;
+        if let Some(refused) = engine.refuse_mutation() {
+            return refused;
+        }
+        let result = engine.inner.apply_mutations();
+
+        // Convert to FFI-safe pairs and store in thread-local caches.
+        let node_pairs: Vec<FfiIdPair> = result
+            .added_nodes
+            .iter()
+            .zip(&result.added_node_lanes)
+            .map(|((pending, real), &lane)| FfiIdPair {
+                pending_id: pending.0,
+                real_id: node_id_to_ffi(*real),
+                lane,
+            })
+            .collect();
+
+        let edge_pairs: Vec<FfiIdPair> = result
+            .added_edges
+            .iter()
+            .zip(&result.added_edge_lanes)
+            .map(|((pending, real), &lane)| FfiIdPair {
+                pending_id: pending.0,
+                real_id: edge_id_to_ffi(*real),
+                lane,
+            })
+            .collect();
+
+        let rejected: Vec<FfiRejectedMutation> = result
+            .rejected
+            .iter()
+            .map(|&(pending, reason)| FfiRejectedMutation {
+                pending_id: pending.0,
+                reason: reason as u32,
+            })
+            .collect();
+
+        engine.mutation_nodes = node_pairs;
+        engine.mutation_edges = edge_pairs;
+        engine.mutation_rejects = rejected;
+
+        let nodes = &engine.mutation_nodes;
+        let edges = &engine.mutation_edges;
+        let rejected = &engine.mutation_rejects;
+        unsafe {
+            *out_result = FfiMutationResult {
+                added_nodes: if nodes.is_empty() {
+                    ptr::null()
+                } else {
+                    nodes.as_ptr()
+                },
+                added_node_count: nodes.len() as u32,
+                added_edges: if edges.is_empty() {
+                    ptr::null()
+                } else {
+                    edges.as_ptr()
+                },
+                added_edge_count: edges.len() as u32,
+                rejected: if rejected.is_empty() {
+                    ptr::null()
+                } else {
+                    rejected.as_ptr()
+                },
+                rejected_count: rejected.len() as u32,
+            };
         }
-        let eid = ffi_to_edge_id(edge_id);
-        let transport = Transport::Batch(BatchTransport {
-            batch_size,
-            cycle_time,
-        });
-        engine.inner.set_transport(eid, transport);
         FactorialResult::Ok
     })) {
         Ok(result) => result,
@@ -1471,106 +2287,115 @@ pub unsafe extern "C" fn factorial_set_batch_transport(
     }
 }
 
-/// Set an edge's transport to VehicleTransport.
+/// Map a pending-id lookup to a result code, writing resolved ids with `to_ffi`.
+fn write_pending_lookup<T>(
+    lookup: PendingLookup<T>,
+    to_ffi: fn(T) -> u64,
+    out_real: *mut u64,
+) -> FactorialResult {
+    match lookup {
+        PendingLookup::Resolved(id) => {
+            unsafe { *out_real = to_ffi(id) };
+            FactorialResult::Ok
+        }
+        PendingLookup::Queued => FactorialResult::PendingQueued,
+        PendingLookup::Expired => FactorialResult::PendingExpired,
+        PendingLookup::Unknown => FactorialResult::PendingUnknown,
+    }
+}
+
+/// Look up the real node id a pending node resolved to.
+///
+/// Works for any batch still in the resolution history (see
+/// `factorial_set_resolution_history`), so hosts need not read
+/// `FfiMutationResult` inline. Returns `PendingQueued` before the mutation
+/// is applied, `PendingExpired` once its batch has left the history, and
+/// `PendingUnknown` for ids this engine never issued.
 ///
 /// # Safety
 ///
-/// `engine` must be a valid engine pointer.
+/// `engine` and `out_real` must be valid pointers.
 #[unsafe(no_mangle)]
-pub unsafe extern "C" fn factorial_set_vehicle_transport(
-    engine: *mut FactorialEngine,
-    edge_id: FfiEdgeId,
-    capacity: u32,
-    travel_time: u32,
+pub unsafe extern "C" fn factorial_resolve_pending_node(
+    engine: *const FactorialEngine,
+    pending: FfiPendingNodeId,
+    out_real: *mut FfiNodeId,
 ) -> FactorialResult {
-    if engine.is_null() {
+    if engine.is_null() || out_real.is_null() {
         return FactorialResult::NullPointer;
     }
     match catch_unwind(std::panic::AssertUnwindSafe(|| {
-        let engine = unsafe { &mut *engine };
+        let engine = unsafe { &*engine };
         if engine.poisoned {
             return FactorialResult::Poisoned;
         }
-        let eid = ffi_to_edge_id(edge_id);
-        let transport = Transport::Vehicle(VehicleTransport {
-            capacity,
-            travel_time,
-        });
-        engine.inner.set_transport(eid, transport);
-        FactorialResult::Ok
+        let lookup = engine
+            .inner
+            .graph
+            .resolve_pending_node(PendingNodeId(pending));
+        write_pending_lookup(lookup, node_id_to_ffi, out_real)
     })) {
         Ok(result) => result,
-        Err(_) => {
-            let engine = unsafe { &mut *engine };
-            engine.poisoned = true;
-            FactorialResult::InternalError
-        }
+        Err(_) => FactorialResult::InternalError,
     }
 }
 
-// ---------------------------------------------------------------------------
-// Configuration: Inventories
-// ---------------------------------------------------------------------------
-
-/// Set the input inventory for a node with the given capacity.
-///
-/// Creates an inventory with 1 input slot and 1 output slot.
+/// Look up the real edge id a pending edge resolved to. Result codes match
+/// `factorial_resolve_pending_node`.
 ///
 /// # Safety
 ///
-/// `engine` must be a valid engine pointer.
+/// `engine` and `out_real` must be valid pointers.
 #[unsafe(no_mangle)]
-pub unsafe extern "C" fn factorial_set_input_capacity(
-    engine: *mut FactorialEngine,
-    node_id: FfiNodeId,
-    capacity: u32,
+pub unsafe extern "C" fn factorial_resolve_pending_edge(
+    engine: *const FactorialEngine,
+    pending: FfiPendingEdgeId,
+    out_real: *mut FfiEdgeId,
 ) -> FactorialResult {
-    if engine.is_null() {
+    if engine.is_null() || out_real.is_null() {
         return FactorialResult::NullPointer;
     }
     match catch_unwind(std::panic::AssertUnwindSafe(|| {
-        let engine = unsafe { &mut *engine };
+        let engine = unsafe { &*engine };
         if engine.poisoned {
             return FactorialResult::Poisoned;
         }
-        let nid = ffi_to_node_id(node_id);
-        let inventory = Inventory::new(1, 1, capacity);
-        engine.inner.set_input_inventory(nid, inventory);
-        FactorialResult::Ok
+        let lookup = engine
+            .inner
+            .graph
+            .resolve_pending_edge(PendingEdgeId(pending));
+        write_pending_lookup(lookup, edge_id_to_ffi, out_real)
     })) {
         Ok(result) => result,
-        Err(_) => {
-            let engine = unsafe { &mut *engine };
-            engine.poisoned = true;
-            FactorialResult::InternalError
-        }
+        Err(_) => FactorialResult::InternalError,
     }
 }
 
-/// Set the output inventory for a node with the given capacity.
-///
-/// Creates an inventory with 1 input slot and 1 output slot.
+/// Set how many applied mutation batches the engine remembers for
+/// `factorial_resolve_pending_node` / `factorial_resolve_pending_edge`.
+/// Only batches that add nodes or edges count. `0` disables the history.
 ///
 /// # Safety
 ///
 /// `engine` must be a valid engine pointer.
 #[unsafe(no_mangle)]
-pub unsafe extern "C" fn factorial_set_output_capacity(
+pub unsafe extern "C" fn factorial_set_resolution_history(
     engine: *mut FactorialEngine,
-    node_id: FfiNodeId,
-    capacity: u32,
+    batches: u32,
 ) -> FactorialResult {
     if engine.is_null() {
         return FactorialResult::NullPointer;
     }
+    let _mutation = unsafe { MutationGuard::begin(engine) };
     match catch_unwind(std::panic::AssertUnwindSafe(|| {
         let engine = unsafe { &mut *engine };
-        if engine.poisoned {
-            return FactorialResult::Poisoned;
+        if let Some(refused) = engine.refuse_mutation() {
+            return refused;
         }
-        let nid = ffi_to_node_id(node_id);
-        let inventory = Inventory::new(1, 1, capacity);
-        engine.inner.set_output_inventory(nid, inventory);
+        engine
+            .inner
+            .graph
+            .set_resolution_history_len(batches as usize);
         FactorialResult::Ok
     })) {
         Ok(result) => result,
@@ -1583,161 +2408,178 @@ pub unsafe extern "C" fn factorial_set_output_capacity(
 }
 
 // ---------------------------------------------------------------------------
-// Poison checking
+// Queries
 // ---------------------------------------------------------------------------
 
-/// Check whether the engine is poisoned (a previous panic left it in an
-/// inconsistent state). Returns `false` if the engine pointer is null.
+/// Get the number of nodes in the graph.
 ///
 /// # Safety
 ///
-/// `engine` must be a valid engine pointer or null.
+/// `engine` and `out_count` must be valid pointers.
 #[unsafe(no_mangle)]
-pub unsafe extern "C" fn factorial_is_poisoned(engine: *const FactorialEngine) -> bool {
-    if engine.is_null() {
-        return false;
+pub unsafe extern "C" fn factorial_node_count(
+    engine: *const FactorialEngine,
+    out_count: *mut u32,
+) -> FactorialResult {
+    if engine.is_null() || out_count.is_null() {
+        return FactorialResult::NullPointer;
+    }
+    let Some(_read) = (unsafe { ReadGuard::begin(engine) }) else {
+        return FactorialResult::ConcurrentMutation;
+    };
+    match catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let engine = unsafe { &*engine };
+        if engine.poisoned {
+            return FactorialResult::Poisoned;
+        }
+        unsafe { *out_count = engine.inner.node_count() as u32 };
+        FactorialResult::Ok
+    })) {
+        Ok(result) => result,
+        Err(_) => FactorialResult::InternalError,
     }
-    let engine = unsafe { &*engine };
-    engine.poisoned
 }
 
-/// Clear the poisoned flag on an engine, allowing it to be used again.
+/// Get the number of edges in the graph.
 ///
 /// # Safety
 ///
-/// `engine` must be a valid engine pointer.
+/// `engine` and `out_count` must be valid pointers.
 #[unsafe(no_mangle)]
-pub unsafe extern "C" fn factorial_clear_poison(engine: *mut FactorialEngine) -> FactorialResult {
-    if engine.is_null() {
+pub unsafe extern "C" fn factorial_edge_count(
+    engine: *const FactorialEngine,
+    out_count: *mut u32,
+) -> FactorialResult {
+    if engine.is_null() || out_count.is_null() {
         return FactorialResult::NullPointer;
     }
-    let engine = unsafe { &mut *engine };
-    engine.poisoned = false;
-    FactorialResult::Ok
+    let Some(_read) = (unsafe { ReadGuard::begin(engine) }) else {
+        return FactorialResult::ConcurrentMutation;
+    };
+    match catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let engine = unsafe { &*engine };
+        if engine.poisoned {
+            return FactorialResult::Poisoned;
+        }
+        unsafe { *out_count = engine.inner.edge_count() as u32 };
+        FactorialResult::Ok
+    })) {
+        Ok(result) => result,
+        Err(_) => FactorialResult::InternalError,
+    }
 }
 
-// ---------------------------------------------------------------------------
-// FFI-safe logic network types
-// ---------------------------------------------------------------------------
-
-/// C-compatible wire color.
-#[repr(C)]
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum FfiWireColor {
-    Red = 0,
-    Green = 1,
-}
-
-/// C-compatible signal selector kind.
-#[repr(C)]
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum FfiSelectorKind {
-    Signal = 0,
-    Constant = 1,
-    Each = 2,
-}
-
-/// C-compatible arithmetic operation.
-#[repr(C)]
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum FfiArithmeticOp {
-    Add = 0,
-    Sub = 1,
-    Mul = 2,
-    Div = 3,
-    Mod = 4,
-}
-
-/// C-compatible comparison operation.
-#[repr(C)]
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum FfiComparisonOp {
-    Gt = 0,
-    Lt = 1,
-    Eq = 2,
-    Gte = 3,
-    Lte = 4,
-    Ne = 5,
-}
-
-/// C-compatible decider output kind.
-#[repr(C)]
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum FfiDeciderOutputKind {
-    One = 0,
-    InputCount = 1,
-    Everything = 2,
-}
-
-/// C-compatible wire network ID.
-pub type FfiWireNetworkId = u32;
-
-// ---------------------------------------------------------------------------
-// Logic FFI helpers
-// ---------------------------------------------------------------------------
-
-fn ffi_to_wire_color(color: FfiWireColor) -> WireColor {
-    match color {
-        FfiWireColor::Red => WireColor::Red,
-        FfiWireColor::Green => WireColor::Green,
-    }
-}
-
-fn ffi_to_selector(kind: FfiSelectorKind, value: u64) -> SignalSelector {
-    match kind {
-        FfiSelectorKind::Signal => SignalSelector::Signal(ItemTypeId(value as u32)),
-        FfiSelectorKind::Constant => SignalSelector::Constant(Fixed64::from_bits(value as i64)),
-        FfiSelectorKind::Each => SignalSelector::Each,
-    }
-}
-
-fn ffi_to_arithmetic_op(op: FfiArithmeticOp) -> ArithmeticOp {
-    match op {
-        FfiArithmeticOp::Add => ArithmeticOp::Add,
-        FfiArithmeticOp::Sub => ArithmeticOp::Subtract,
-        FfiArithmeticOp::Mul => ArithmeticOp::Multiply,
-        FfiArithmeticOp::Div => ArithmeticOp::Divide,
-        FfiArithmeticOp::Mod => ArithmeticOp::Modulo,
+/// Get the topology generation: a counter that changes whenever applied
+/// mutations add or remove nodes or edges. Hosts holding a copy of
+/// `factorial_get_topology` refetch it when this changes, and after changing
+/// an edge's transport to another kind.
+///
+/// # Safety
+///
+/// `engine` and `out_generation` must be valid pointers.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_topology_generation(
+    engine: *const FactorialEngine,
+    out_generation: *mut u64,
+) -> FactorialResult {
+    if engine.is_null() || out_generation.is_null() {
+        return FactorialResult::NullPointer;
     }
-}
-
-fn ffi_to_comparison_op(op: FfiComparisonOp) -> ComparisonOp {
-    match op {
-        FfiComparisonOp::Gt => ComparisonOp::Gt,
-        FfiComparisonOp::Lt => ComparisonOp::Lt,
-        FfiComparisonOp::Eq => ComparisonOp::Eq,
-        FfiComparisonOp::Gte => ComparisonOp::Gte,
-        FfiComparisonOp::Lte => ComparisonOp::Lte,
-        FfiComparisonOp::Ne => ComparisonOp::Ne,
+    let Some(_read) = (unsafe { ReadGuard::begin(engine) }) else {
+        return FactorialResult::ConcurrentMutation;
+    };
+    match catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let engine = unsafe { &*engine };
+        if engine.poisoned {
+            return FactorialResult::Poisoned;
+        }
+        unsafe { *out_generation = engine.inner.topology_generation() };
+        FactorialResult::Ok
+    })) {
+        Ok(result) => result,
+        Err(_) => FactorialResult::InternalError,
     }
 }
 
-// ---------------------------------------------------------------------------
-// Logic network FFI functions
-// ---------------------------------------------------------------------------
-
-/// Register the logic module with the engine.
+/// Copy the graph's adjacency as compressed sparse rows: every node id in
+/// ascending order into `out_nodes`, `node_count + 1` offsets into
+/// `out_offsets`, and the outgoing edges into `out_edges`. The edges of
+/// node `i` are `out_edges[out_offsets[i]..out_offsets[i + 1]]`, in
+/// ascending edge id order.
 ///
-/// Must be called before any other `factorial_logic_*` function. Calling
-/// this more than once on the same engine is a no-op (the second bridge
-/// is simply added; prefer calling only once).
+/// Writes the node and edge counts to `*out_node_count` and
+/// `*out_edge_count`. If any buffer is too small, nothing else is written
+/// and `BufferTooSmall` is returned. The engine caches the arrays and only
+/// rebuilds them when the topology generation changes or a transport
+/// changes kind.
 ///
 /// # Safety
 ///
-/// `engine` must be a valid engine pointer.
+/// `engine`, `out_node_count` and `out_edge_count` must be valid pointers.
+/// Each buffer must be valid for its length (it may be null when the length
+/// is 0).
 #[unsafe(no_mangle)]
-pub unsafe extern "C" fn factorial_logic_register(engine: *mut FactorialEngine) -> FactorialResult {
-    if engine.is_null() {
+pub unsafe extern "C" fn factorial_get_topology(
+    engine: *mut FactorialEngine,
+    out_nodes: *mut FfiNodeId,
+    nodes_len: u32,
+    out_offsets: *mut u32,
+    offsets_len: u32,
+    out_edges: *mut FfiTopologyEdge,
+    edges_len: u32,
+    out_node_count: *mut u32,
+    out_edge_count: *mut u32,
+) -> FactorialResult {
+    if engine.is_null()
+        || out_node_count.is_null()
+        || out_edge_count.is_null()
+        || (out_nodes.is_null() && nodes_len > 0)
+        || (out_offsets.is_null() && offsets_len > 0)
+        || (out_edges.is_null() && edges_len > 0)
+    {
         return FactorialResult::NullPointer;
     }
+    let _mutation = unsafe { MutationGuard::begin(engine) };
     match catch_unwind(std::panic::AssertUnwindSafe(|| {
         let engine = unsafe { &mut *engine };
         if engine.poisoned {
             return FactorialResult::Poisoned;
         }
-        engine
-            .inner
-            .register_module(Box::new(LogicModuleBridge::new()));
+        let snapshot = engine.inner.topology_snapshot();
+        unsafe {
+            *out_node_count = snapshot.nodes.len() as u32;
+            *out_edge_count = snapshot.edges.len() as u32;
+        }
+        if (nodes_len as usize) < snapshot.nodes.len()
+            || (offsets_len as usize) < snapshot.offsets.len()
+            || (edges_len as usize) < snapshot.edges.len()
+        {
+            return FactorialResult::BufferTooSmall;
+        }
+        if !snapshot.nodes.is_empty() {
+            let out = unsafe { std::slice::from_raw_parts_mut(out_nodes, snapshot.nodes.len()) };
+            for (slot, &node) in out.iter_mut().zip(&snapshot.nodes) {
+                *slot = node_id_to_ffi(node);
+            }
+        }
+        let out = unsafe { std::slice::from_raw_parts_mut(out_offsets, snapshot.offsets.len()) };
+        out.copy_from_slice(&snapshot.offsets);
+        if !snapshot.edges.is_empty() {
+            let out = unsafe { std::slice::from_raw_parts_mut(out_edges, snapshot.edges.len()) };
+            for (slot, edge) in out.iter_mut().zip(&snapshot.edges) {
+                *slot = FfiTopologyEdge {
+                    edge: edge_id_to_ffi(edge.edge),
+                    target: edge.target,
+                    kind: match edge.kind {
+                        Some(TransportKind::Item) => FfiTransportKind::Item,
+                        Some(TransportKind::Batch) => FfiTransportKind::Batch,
+                        Some(TransportKind::Vehicle) => FfiTransportKind::Vehicle,
+                        Some(TransportKind::Flow) | None => FfiTransportKind::Flow,
+                    },
+                    has_transport: edge.kind.is_some(),
+                };
+            }
+        }
         FactorialResult::Ok
     })) {
         Ok(result) => result,
@@ -1749,196 +2591,161 @@ pub unsafe extern "C" fn factorial_logic_register(engine: *mut FactorialEngine)
     }
 }
 
-/// Create a wire network with the given color. The new network ID is
-/// written to `out_id`.
+/// Get the current tick counter.
 ///
 /// # Safety
 ///
-/// `engine` and `out_id` must be valid pointers.
+/// `engine` and `out_tick` must be valid pointers.
 #[unsafe(no_mangle)]
-pub unsafe extern "C" fn factorial_logic_create_network(
-    engine: *mut FactorialEngine,
-    color: FfiWireColor,
-    out_id: *mut FfiWireNetworkId,
+pub unsafe extern "C" fn factorial_get_tick(
+    engine: *const FactorialEngine,
+    out_tick: *mut u64,
 ) -> FactorialResult {
-    if engine.is_null() || out_id.is_null() {
+    if engine.is_null() || out_tick.is_null() {
         return FactorialResult::NullPointer;
     }
+    let Some(_read) = (unsafe { ReadGuard::begin(engine) }) else {
+        return FactorialResult::ConcurrentMutation;
+    };
     match catch_unwind(std::panic::AssertUnwindSafe(|| {
-        let engine = unsafe { &mut *engine };
+        let engine = unsafe { &*engine };
         if engine.poisoned {
             return FactorialResult::Poisoned;
         }
-        let bridge = match engine.inner.find_module_mut::<LogicModuleBridge>() {
-            Some(b) => b,
-            None => return FactorialResult::InternalError,
-        };
-        let net_id = bridge.logic_mut().create_network(ffi_to_wire_color(color));
-        unsafe { *out_id = net_id.0 };
+        unsafe { *out_tick = engine.inner.sim_state.tick };
         FactorialResult::Ok
     })) {
         Ok(result) => result,
-        Err(_) => {
-            let engine = unsafe { &mut *engine };
-            engine.poisoned = true;
-            FactorialResult::InternalError
-        }
+        Err(_) => FactorialResult::InternalError,
     }
 }
 
-/// Remove a wire network.
+/// Get the state hash of the engine (for desync detection).
 ///
 /// # Safety
 ///
-/// `engine` must be a valid engine pointer.
+/// `engine` and `out_hash` must be valid pointers.
 #[unsafe(no_mangle)]
-pub unsafe extern "C" fn factorial_logic_remove_network(
-    engine: *mut FactorialEngine,
-    network_id: FfiWireNetworkId,
+pub unsafe extern "C" fn factorial_get_state_hash(
+    engine: *const FactorialEngine,
+    out_hash: *mut u64,
 ) -> FactorialResult {
-    if engine.is_null() {
+    if engine.is_null() || out_hash.is_null() {
         return FactorialResult::NullPointer;
     }
+    let Some(_read) = (unsafe { ReadGuard::begin(engine) }) else {
+        return FactorialResult::ConcurrentMutation;
+    };
     match catch_unwind(std::panic::AssertUnwindSafe(|| {
-        let engine = unsafe { &mut *engine };
+        let engine = unsafe { &*engine };
         if engine.poisoned {
             return FactorialResult::Poisoned;
         }
-        let bridge = match engine.inner.find_module_mut::<LogicModuleBridge>() {
-            Some(b) => b,
-            None => return FactorialResult::InternalError,
-        };
-        bridge.logic_mut().remove_network(WireNetworkId(network_id));
+        unsafe { *out_hash = engine.inner.state_hash() };
         FactorialResult::Ok
     })) {
         Ok(result) => result,
-        Err(_) => {
-            let engine = unsafe { &mut *engine };
-            engine.poisoned = true;
-            FactorialResult::InternalError
-        }
+        Err(_) => FactorialResult::InternalError,
     }
 }
 
-/// Add a node to a wire network.
+/// Get the most recent state hash split by subsystem, so two desynced peers
+/// can tell which subsystem diverged.
 ///
 /// # Safety
 ///
-/// `engine` must be a valid engine pointer.
+/// `engine` and `out_breakdown` must be valid pointers.
 #[unsafe(no_mangle)]
-pub unsafe extern "C" fn factorial_logic_add_to_network(
-    engine: *mut FactorialEngine,
-    network_id: FfiWireNetworkId,
-    node_id: FfiNodeId,
+pub unsafe extern "C" fn factorial_get_state_hash_breakdown(
+    engine: *const FactorialEngine,
+    out_breakdown: *mut FfiHashBreakdown,
 ) -> FactorialResult {
-    if engine.is_null() {
+    if engine.is_null() || out_breakdown.is_null() {
         return FactorialResult::NullPointer;
     }
+    let Some(_read) = (unsafe { ReadGuard::begin(engine) }) else {
+        return FactorialResult::ConcurrentMutation;
+    };
     match catch_unwind(std::panic::AssertUnwindSafe(|| {
-        let engine = unsafe { &mut *engine };
+        let engine = unsafe { &*engine };
         if engine.poisoned {
             return FactorialResult::Poisoned;
         }
-        let bridge = match engine.inner.find_module_mut::<LogicModuleBridge>() {
-            Some(b) => b,
-            None => return FactorialResult::InternalError,
+        let b = engine.inner.state_hash_breakdown();
+        unsafe {
+            *out_breakdown = FfiHashBreakdown {
+                graph: b.graph,
+                inventories: b.inventories,
+                processor_states: b.processor_states,
+                transports: b.transports,
+                sim_state: b.sim_state,
+                fluid: b.fluid,
+                crafting: b.crafting,
+                modules: b.modules,
+            }
         };
-        bridge
-            .logic_mut()
-            .add_to_network(WireNetworkId(network_id), ffi_to_node_id(node_id));
         FactorialResult::Ok
     })) {
         Ok(result) => result,
-        Err(_) => {
-            let engine = unsafe { &mut *engine };
-            engine.poisoned = true;
-            FactorialResult::InternalError
-        }
+        Err(_) => FactorialResult::InternalError,
     }
 }
 
-/// Remove a node from a wire network.
+/// Get the enabled extensions as a bit mask: bit `n` is set when the
+/// extension with id `n` is enabled (0 logic, 1 fluid, 2 stats, 3 tech tree,
+/// 4 power, 5 spatial, 6 crafting, 7 spoilage).
 ///
 /// # Safety
 ///
-/// `engine` must be a valid engine pointer.
+/// `engine` and `out_mask` must be valid pointers.
 #[unsafe(no_mangle)]
-pub unsafe extern "C" fn factorial_logic_remove_from_network(
-    engine: *mut FactorialEngine,
-    network_id: FfiWireNetworkId,
-    node_id: FfiNodeId,
+pub unsafe extern "C" fn factorial_enabled_extensions(
+    engine: *const FactorialEngine,
+    out_mask: *mut u32,
 ) -> FactorialResult {
-    if engine.is_null() {
+    if engine.is_null() || out_mask.is_null() {
         return FactorialResult::NullPointer;
     }
     match catch_unwind(std::panic::AssertUnwindSafe(|| {
-        let engine = unsafe { &mut *engine };
+        let engine = unsafe { &*engine };
         if engine.poisoned {
             return FactorialResult::Poisoned;
         }
-        let bridge = match engine.inner.find_module_mut::<LogicModuleBridge>() {
-            Some(b) => b,
-            None => return FactorialResult::InternalError,
-        };
-        bridge
-            .logic_mut()
-            .remove_from_network(WireNetworkId(network_id), ffi_to_node_id(node_id));
+        unsafe { *out_mask = engine.inner.extension_mask() };
         FactorialResult::Ok
     })) {
         Ok(result) => result,
-        Err(_) => {
-            let engine = unsafe { &mut *engine };
-            engine.poisoned = true;
-            FactorialResult::InternalError
-        }
+        Err(_) => FactorialResult::InternalError,
     }
 }
 
-/// Set a constant combinator on a node.
+/// Enable an extension by id (see `factorial_enabled_extensions`), for
+/// hosts that run an extension outside the engine and want saves and state
+/// hashes to record it.
 ///
-/// `item_ids_ptr` and `values_ptr` are parallel arrays of length `count`.
-/// Each pair defines a signal: `item_ids[i]` -> `values[i]` (raw Fixed64 bits).
-/// `enabled` is non-zero for enabled.
+/// Returns `InvalidArgument` for an unknown id.
 ///
 /// # Safety
 ///
-/// `engine` must be a valid engine pointer. `item_ids_ptr` and `values_ptr`
-/// must point to arrays of at least `count` elements.
+/// `engine` must be a valid engine pointer.
 #[unsafe(no_mangle)]
-pub unsafe extern "C" fn factorial_logic_set_constant(
+pub unsafe extern "C" fn factorial_enable_extension(
     engine: *mut FactorialEngine,
-    node_id: FfiNodeId,
-    item_ids_ptr: *const u32,
-    values_ptr: *const i64,
-    count: u32,
-    enabled: u8,
+    extension: u32,
 ) -> FactorialResult {
     if engine.is_null() {
         return FactorialResult::NullPointer;
     }
-    if count > 0 && (item_ids_ptr.is_null() || values_ptr.is_null()) {
-        return FactorialResult::NullPointer;
-    }
+    let _mutation = unsafe { MutationGuard::begin(engine) };
     match catch_unwind(std::panic::AssertUnwindSafe(|| {
         let engine = unsafe { &mut *engine };
-        if engine.poisoned {
-            return FactorialResult::Poisoned;
+        if let Some(refused) = engine.refuse_mutation() {
+            return refused;
         }
-        let bridge = match engine.inner.find_module_mut::<LogicModuleBridge>() {
-            Some(b) => b,
-            None => return FactorialResult::InternalError,
+        let Some(&extension) = ExtensionId::ALL.get(extension as usize) else {
+            return FactorialResult::InvalidArgument;
         };
-        let mut signals = SignalSet::new();
-        if count > 0 {
-            let item_ids = unsafe { std::slice::from_raw_parts(item_ids_ptr, count as usize) };
-            let values = unsafe { std::slice::from_raw_parts(values_ptr, count as usize) };
-            for i in 0..count as usize {
-                signals.insert(ItemTypeId(item_ids[i]), Fixed64::from_bits(values[i]));
-            }
-        }
-        bridge
-            .logic_mut()
-            .set_constant(ffi_to_node_id(node_id), signals, enabled != 0);
+        engine.inner.enable_extension(extension);
         FactorialResult::Ok
     })) {
         Ok(result) => result,
@@ -1950,895 +2757,9317 @@ pub unsafe extern "C" fn factorial_logic_set_constant(
     }
 }
 
-/// Set an arithmetic combinator on a node.
-///
-/// Left/right operands are specified as (kind, value) pairs where `value`
-/// is the item ID for `Signal`, raw Fixed64 bits for `Constant`, or
-/// ignored for `Each`.
+/// Count every unit of `item_type` in node inventories and transports.
 ///
 /// # Safety
 ///
-/// `engine` must be a valid engine pointer.
+/// `engine` and `out_census` must be valid pointers.
 #[unsafe(no_mangle)]
-pub unsafe extern "C" fn factorial_logic_set_arithmetic(
-    engine: *mut FactorialEngine,
-    node_id: FfiNodeId,
-    left_kind: FfiSelectorKind,
-    left_value: u64,
-    op: FfiArithmeticOp,
-    right_kind: FfiSelectorKind,
-    right_value: u64,
-    output_item: u32,
+pub unsafe extern "C" fn factorial_item_census(
+    engine: *const FactorialEngine,
+    item_type: u32,
+    out_census: *mut FfiItemCensus,
 ) -> FactorialResult {
-    if engine.is_null() {
+    if engine.is_null() || out_census.is_null() {
         return FactorialResult::NullPointer;
     }
+    let Some(_read) = (unsafe { ReadGuard::begin(engine) }) else {
+        return FactorialResult::ConcurrentMutation;
+    };
     match catch_unwind(std::panic::AssertUnwindSafe(|| {
-        let engine = unsafe { &mut *engine };
+        let engine = unsafe { &*engine };
         if engine.poisoned {
             return FactorialResult::Poisoned;
         }
-        let bridge = match engine.inner.find_module_mut::<LogicModuleBridge>() {
-            Some(b) => b,
-            None => return FactorialResult::InternalError,
-        };
-        let combinator = ArithmeticCombinator {
-            left: ffi_to_selector(left_kind, left_value),
-            op: ffi_to_arithmetic_op(op),
-            right: ffi_to_selector(right_kind, right_value),
-            output: ItemTypeId(output_item),
+        let c = engine.inner.item_census(ItemTypeId(item_type));
+        unsafe {
+            *out_census = FfiItemCensus {
+                in_input_inventories: c.in_input_inventories,
+                in_output_inventories: c.in_output_inventories,
+                in_transit: c.in_transit,
+                total: c.total,
+            }
         };
-        bridge
-            .logic_mut()
-            .set_arithmetic(ffi_to_node_id(node_id), combinator);
         FactorialResult::Ok
     })) {
         Ok(result) => result,
-        Err(_) => {
-            let engine = unsafe { &mut *engine };
-            engine.poisoned = true;
-            FactorialResult::InternalError
-        }
+        Err(_) => FactorialResult::InternalError,
     }
 }
 
-/// Set a decider combinator on a node.
-///
-/// Left/right operands are specified as (kind, value) pairs. The output
-/// kind determines what is emitted when the condition is true.
+/// Summarise every item type currently involved in a stall, one record per
+/// item type in ascending item-type order. See `Engine::bottleneck_digest`.
 ///
 /// # Safety
 ///
-/// `engine` must be a valid engine pointer.
+/// `engine` and `out_buffer` must be valid pointers. The pointer in the
+/// returned buffer is valid until the next call to this function.
 #[unsafe(no_mangle)]
-pub unsafe extern "C" fn factorial_logic_set_decider(
-    engine: *mut FactorialEngine,
-    node_id: FfiNodeId,
-    left_kind: FfiSelectorKind,
-    left_value: u64,
-    cmp_op: FfiComparisonOp,
-    right_kind: FfiSelectorKind,
-    right_value: u64,
-    output_kind: FfiDeciderOutputKind,
-    output_item: u32,
+pub unsafe extern "C" fn factorial_get_bottleneck_digest(
+    engine: *const FactorialEngine,
+    out_buffer: *mut FfiBottleneckBuffer,
 ) -> FactorialResult {
-    if engine.is_null() {
+    if engine.is_null() || out_buffer.is_null() {
         return FactorialResult::NullPointer;
     }
     match catch_unwind(std::panic::AssertUnwindSafe(|| {
-        let engine = unsafe { &mut *engine };
+        let engine = unsafe { &*engine };
         if engine.poisoned {
             return FactorialResult::Poisoned;
         }
-        let bridge = match engine.inner.find_module_mut::<LogicModuleBridge>() {
-            Some(b) => b,
-            None => return FactorialResult::InternalError,
-        };
-        let condition = Condition {
-            left: ffi_to_selector(left_kind, left_value),
-            op: ffi_to_comparison_op(cmp_op),
-            right: ffi_to_selector(right_kind, right_value),
-        };
-        let output = match output_kind {
-            FfiDeciderOutputKind::One => DeciderOutput::One(ItemTypeId(output_item)),
-            FfiDeciderOutputKind::InputCount => DeciderOutput::InputCount(ItemTypeId(output_item)),
-            FfiDeciderOutputKind::Everything => DeciderOutput::Everything,
-        };
-        let combinator = DeciderCombinator { condition, output };
-        bridge
-            .logic_mut()
-            .set_decider(ffi_to_node_id(node_id), combinator);
+        let digest = engine.inner.bottleneck_digest();
+        let mut cache = engine.bottlenecks.borrow_mut();
+        *cache = digest
+            .items
+            .iter()
+            .map(|item| FfiItemBottleneck {
+                item_type: item.item_type.0,
+                starved_nodes: item.starved_nodes,
+                blocked_nodes: item.blocked_nodes,
+                worst_starved: item.worst_starved.map_or(0, node_id_to_ffi),
+                worst_blocked: item.worst_blocked.map_or(0, node_id_to_ffi),
+            })
+            .collect();
+        unsafe {
+            *out_buffer = FfiBottleneckBuffer {
+                items: if cache.is_empty() {
+                    ptr::null()
+                } else {
+                    cache.as_ptr()
+                },
+                count: cache.len() as u32,
+            };
+        }
         FactorialResult::Ok
     })) {
         Ok(result) => result,
-        Err(_) => {
-            let engine = unsafe { &mut *engine };
-            engine.poisoned = true;
-            FactorialResult::InternalError
-        }
+        Err(_) => FactorialResult::InternalError,
     }
 }
 
-/// Set circuit control on a node.
-///
-/// The condition is specified as left/right (kind, value) pairs and a
-/// comparison operator. The wire color determines which network to read
-/// signals from.
+/// Get the processor state for a node.
 ///
 /// # Safety
 ///
-/// `engine` must be a valid engine pointer.
+/// `engine` and `out_info` must be valid pointers.
 #[unsafe(no_mangle)]
-pub unsafe extern "C" fn factorial_logic_set_circuit_control(
-    engine: *mut FactorialEngine,
+pub unsafe extern "C" fn factorial_get_processor_state(
+    engine: *const FactorialEngine,
     node_id: FfiNodeId,
-    left_kind: FfiSelectorKind,
-    left_value: u64,
-    cmp_op: FfiComparisonOp,
-    right_kind: FfiSelectorKind,
-    right_value: u64,
-    wire_color: FfiWireColor,
+    out_info: *mut FfiProcessorInfo,
 ) -> FactorialResult {
-    if engine.is_null() {
+    if engine.is_null() || out_info.is_null() {
         return FactorialResult::NullPointer;
     }
+    let Some(_read) = (unsafe { ReadGuard::begin(engine) }) else {
+        return FactorialResult::ConcurrentMutation;
+    };
     match catch_unwind(std::panic::AssertUnwindSafe(|| {
-        let engine = unsafe { &mut *engine };
+        let engine = unsafe { &*engine };
         if engine.poisoned {
             return FactorialResult::Poisoned;
         }
-        let bridge = match engine.inner.find_module_mut::<LogicModuleBridge>() {
-            Some(b) => b,
-            None => return FactorialResult::InternalError,
-        };
-        let condition = Condition {
-            left: ffi_to_selector(left_kind, left_value),
-            op: ffi_to_comparison_op(cmp_op),
-            right: ffi_to_selector(right_kind, right_value),
-        };
-        bridge.logic_mut().set_circuit_control(
-            ffi_to_node_id(node_id),
-            condition,
-            ffi_to_wire_color(wire_color),
-        );
-        FactorialResult::Ok
+        let nid = ffi_to_node_id(node_id);
+        match engine.inner.get_processor_state(nid) {
+            Some(state) => {
+                unsafe { *out_info = convert_processor_state(state) };
+                FactorialResult::Ok
+            }
+            None => FactorialResult::NodeNotFound,
+        }
     })) {
         Ok(result) => result,
-        Err(_) => {
-            let engine = unsafe { &mut *engine };
-            engine.poisoned = true;
-            FactorialResult::InternalError
-        }
+        Err(_) => FactorialResult::InternalError,
     }
 }
 
-/// Query whether a node's circuit control condition is active.
-///
-/// Writes `1` to `out_active` if active, `0` if inactive. Returns
-/// `NodeNotFound` if the node has no circuit control.
+/// Get the total item count in a node's input inventory.
 ///
 /// # Safety
 ///
-/// `engine` and `out_active` must be valid pointers.
+/// `engine` and `out_count` must be valid pointers.
 #[unsafe(no_mangle)]
-pub unsafe extern "C" fn factorial_logic_is_active(
-    engine: *mut FactorialEngine,
+pub unsafe extern "C" fn factorial_get_input_inventory_count(
+    engine: *const FactorialEngine,
     node_id: FfiNodeId,
-    out_active: *mut u8,
+    out_count: *mut u32,
 ) -> FactorialResult {
-    if engine.is_null() || out_active.is_null() {
+    if engine.is_null() || out_count.is_null() {
         return FactorialResult::NullPointer;
     }
+    let Some(_read) = (unsafe { ReadGuard::begin(engine) }) else {
+        return FactorialResult::ConcurrentMutation;
+    };
     match catch_unwind(std::panic::AssertUnwindSafe(|| {
-        let engine = unsafe { &mut *engine };
+        let engine = unsafe { &*engine };
         if engine.poisoned {
             return FactorialResult::Poisoned;
         }
-        let bridge = match engine.inner.find_module::<LogicModuleBridge>() {
-            Some(b) => b,
-            None => return FactorialResult::InternalError,
-        };
-        match bridge.logic().is_active(ffi_to_node_id(node_id)) {
-            Some(active) => {
-                unsafe { *out_active = u8::from(active) };
+        let nid = ffi_to_node_id(node_id);
+        match engine.inner.get_input_inventory(nid) {
+            Some(inv) => {
+                unsafe { *out_count = inv.total() };
                 FactorialResult::Ok
             }
             None => FactorialResult::NodeNotFound,
         }
     })) {
         Ok(result) => result,
-        Err(_) => {
-            let engine = unsafe { &mut *engine };
-            engine.poisoned = true;
-            FactorialResult::InternalError
-        }
+        Err(_) => FactorialResult::InternalError,
     }
 }
 
-/// Query a signal value from a wire network.
-///
-/// Writes the raw Fixed64 bits to `out_value`. If the signal is not
-/// present, writes 0.
+/// Get the total item count in a node's output inventory.
 ///
 /// # Safety
 ///
-/// `engine` and `out_value` must be valid pointers.
+/// `engine` and `out_count` must be valid pointers.
 #[unsafe(no_mangle)]
-pub unsafe extern "C" fn factorial_logic_get_network_signal(
-    engine: *mut FactorialEngine,
-    network_id: FfiWireNetworkId,
-    item_id: u32,
-    out_value: *mut i64,
+pub unsafe extern "C" fn factorial_get_output_inventory_count(
+    engine: *const FactorialEngine,
+    node_id: FfiNodeId,
+    out_count: *mut u32,
 ) -> FactorialResult {
-    if engine.is_null() || out_value.is_null() {
+    if engine.is_null() || out_count.is_null() {
         return FactorialResult::NullPointer;
     }
+    let Some(_read) = (unsafe { ReadGuard::begin(engine) }) else {
+        return FactorialResult::ConcurrentMutation;
+    };
     match catch_unwind(std::panic::AssertUnwindSafe(|| {
-        let engine = unsafe { &mut *engine };
+        let engine = unsafe { &*engine };
         if engine.poisoned {
             return FactorialResult::Poisoned;
         }
-        let bridge = match engine.inner.find_module::<LogicModuleBridge>() {
-            Some(b) => b,
-            None => return FactorialResult::InternalError,
-        };
-        let zero = Fixed64::from_num(0);
-        let value = bridge
-            .logic()
-            .network_signals(WireNetworkId(network_id))
-            .and_then(|signals| signals.get(&ItemTypeId(item_id)).copied())
-            .unwrap_or(zero);
-        unsafe { *out_value = value.to_bits() };
-        FactorialResult::Ok
+        let nid = ffi_to_node_id(node_id);
+        match engine.inner.get_output_inventory(nid) {
+            Some(inv) => {
+                unsafe { *out_count = inv.total() };
+                FactorialResult::Ok
+            }
+            None => FactorialResult::NodeNotFound,
+        }
     })) {
         Ok(result) => result,
-        Err(_) => {
-            let engine = unsafe { &mut *engine };
-            engine.poisoned = true;
-            FactorialResult::InternalError
-        }
+        Err(_) => FactorialResult::InternalError,
     }
 }
 
-/// Remove all logic state for a node (constant, combinators, circuit
-/// control, and network memberships).
+/// C-compatible view of one item riding on a belt (item transport) edge.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct FfiTransportItem {
+    /// Stable per-engine instance id; unchanged while the item is on the belt.
+    pub instance_id: u64,
+    pub item_type: u32,
+    pub lane: u32,
+    /// Position along the edge as raw Fixed64 bits: 0 at the source end,
+    /// `1 << 32` at the destination end.
+    pub position: i64,
+}
+
+/// Engine-owned array returned by `factorial_get_transport_items`.
+#[repr(C)]
+#[derive(Debug)]
+pub struct FfiTransportItemBuffer {
+    pub items: *const FfiTransportItem,
+    pub count: u32,
+}
+
+/// List the items currently on a belt edge, with stable instance ids for
+/// animation. Non-belt transports report zero items.
+///
+/// Returns `EdgeNotFound` if the edge has no transport.
 ///
 /// # Safety
 ///
-/// `engine` must be a valid engine pointer.
+/// `engine` and `out_buffer` must be valid pointers. The pointer in the
+/// returned buffer is valid until the next call to this function.
 #[unsafe(no_mangle)]
-pub unsafe extern "C" fn factorial_logic_remove_node(
-    engine: *mut FactorialEngine,
-    node_id: FfiNodeId,
+pub unsafe extern "C" fn factorial_get_transport_items(
+    engine: *const FactorialEngine,
+    edge_id: FfiEdgeId,
+    out_buffer: *mut FfiTransportItemBuffer,
 ) -> FactorialResult {
-    if engine.is_null() {
+    if engine.is_null() || out_buffer.is_null() {
         return FactorialResult::NullPointer;
     }
     match catch_unwind(std::panic::AssertUnwindSafe(|| {
-        let engine = unsafe { &mut *engine };
+        let engine = unsafe { &*engine };
         if engine.poisoned {
             return FactorialResult::Poisoned;
         }
-        let bridge = match engine.inner.find_module_mut::<LogicModuleBridge>() {
-            Some(b) => b,
-            None => return FactorialResult::InternalError,
+        let eid = ffi_to_edge_id(edge_id);
+        let Some(views) = engine.inner.transport_items(eid) else {
+            return FactorialResult::EdgeNotFound;
         };
-        bridge.logic_mut().remove_node(ffi_to_node_id(node_id));
+        let mut cache = engine.transport_items.borrow_mut();
+        *cache = views
+            .iter()
+            .map(|v| FfiTransportItem {
+                instance_id: v.instance_id,
+                item_type: v.item_type.0,
+                lane: v.lane as u32,
+                position: v.position.to_bits(),
+            })
+            .collect();
+        unsafe {
+            *out_buffer = FfiTransportItemBuffer {
+                items: if cache.is_empty() {
+                    ptr::null()
+                } else {
+                    cache.as_ptr()
+                },
+                count: cache.len() as u32,
+            };
+        }
         FactorialResult::Ok
     })) {
         Ok(result) => result,
-        Err(_) => {
-            let engine = unsafe { &mut *engine };
-            engine.poisoned = true;
-            FactorialResult::InternalError
+        Err(_) => FactorialResult::InternalError,
+    }
+}
+
+/// Write the delivery accumulator of a flow edge to `out_value` as raw
+/// Fixed64 bits: the fraction of an item drained from the buffer but not yet
+/// delivered. Always in `[0, 1)`.
+///
+/// Returns `EdgeNotFound` if the edge has no flow transport.
+///
+/// # Safety
+///
+/// `engine` and `out_value` must be valid pointers.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_get_flow_accumulator(
+    engine: *const FactorialEngine,
+    edge_id: FfiEdgeId,
+    out_value: *mut i64,
+) -> FactorialResult {
+    if engine.is_null() || out_value.is_null() {
+        return FactorialResult::NullPointer;
+    }
+    match catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let engine = unsafe { &*engine };
+        if engine.poisoned {
+            return FactorialResult::Poisoned;
         }
+        match engine.inner.flow_accumulator(ffi_to_edge_id(edge_id)) {
+            Some(value) => {
+                unsafe { *out_value = value.to_bits() };
+                FactorialResult::Ok
+            }
+            None => FactorialResult::EdgeNotFound,
+        }
+    })) {
+        Ok(result) => result,
+        Err(_) => FactorialResult::InternalError,
     }
 }
 
-/// Set an inventory reader on a node. The reader watches `target_node_id`
-/// and emits signals based on its inventory contents.
+/// Write the number of items currently moving along an edge to `out_count`:
+/// belt items, whole items in a flow buffer, a batch's pending items, or a
+/// vehicle's cargo.
 ///
-/// `source` is `0` for Input, `1` for Output.
+/// Returns `EdgeNotFound` if the edge has no transport.
 ///
 /// # Safety
 ///
-/// `engine` must be a valid engine pointer.
+/// `engine` and `out_count` must be valid pointers.
 #[unsafe(no_mangle)]
-pub unsafe extern "C" fn factorial_logic_set_inventory_reader(
-    engine: *mut FactorialEngine,
-    node_id: FfiNodeId,
-    target_node_id: FfiNodeId,
-    source: u8,
+pub unsafe extern "C" fn factorial_get_transport_in_transit(
+    engine: *const FactorialEngine,
+    edge_id: FfiEdgeId,
+    out_count: *mut u32,
 ) -> FactorialResult {
-    if engine.is_null() {
+    if engine.is_null() || out_count.is_null() {
         return FactorialResult::NullPointer;
     }
     match catch_unwind(std::panic::AssertUnwindSafe(|| {
-        let engine = unsafe { &mut *engine };
+        let engine = unsafe { &*engine };
         if engine.poisoned {
             return FactorialResult::Poisoned;
         }
-        let bridge = match engine.inner.find_module_mut::<LogicModuleBridge>() {
-            Some(b) => b,
-            None => return FactorialResult::InternalError,
-        };
-        let inv_source = if source == 0 {
-            InventorySource::Input
-        } else {
-            InventorySource::Output
-        };
-        bridge.logic_mut().set_inventory_reader(
-            ffi_to_node_id(node_id),
-            ffi_to_node_id(target_node_id),
-            inv_source,
-        );
+        let eid = ffi_to_edge_id(edge_id);
+        if engine.inner.get_transport(eid).is_none() {
+            return FactorialResult::EdgeNotFound;
+        }
+        unsafe { *out_count = engine.inner.transport_in_transit_count(eid) };
         FactorialResult::Ok
     })) {
         Ok(result) => result,
-        Err(_) => {
-            let engine = unsafe { &mut *engine };
-            engine.poisoned = true;
-            FactorialResult::InternalError
-        }
+        Err(_) => FactorialResult::InternalError,
     }
 }
 
-// ===========================================================================
-// Tests
-// ===========================================================================
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    // -----------------------------------------------------------------------
-    // Helpers (imported from core test_utils where possible)
-    // -----------------------------------------------------------------------
+/// C-compatible vehicle phase for [`FfiTransportProgress`]. See
+/// `VehiclePhase` in factorial-core.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FfiVehiclePhase {
+    Loading = 0,
+    Traveling = 1,
+    Unloading = 2,
+    Returning = 3,
+}
 
-    use factorial_core::test_utils::{gear, iron, make_recipe, make_source, simple_inventory};
+/// Written to `ticks_until_delivery` of [`FfiTransportProgress`] for a belt
+/// with no item on its way.
+pub const NO_DELIVERY: u32 = u32::MAX;
 
-    // -----------------------------------------------------------------------
-    // Test 1: Create and destroy engine lifecycle
-    // -----------------------------------------------------------------------
-    #[test]
-    fn create_and_destroy() {
-        let engine = factorial_create();
-        assert!(!engine.is_null());
+/// C-compatible transport progress. Only the fields for `kind` are set; the
+/// others are zero:
+///
+/// - `Flow`: `buffered`, `capacity` (raw Fixed64 bits).
+/// - `Item`: `items_in_transit`, `head_position` (raw Fixed64 bits in
+///   `[0, 1 << 32]`, 0 for an empty belt), `ticks_until_delivery`
+///   (`NO_DELIVERY` for an empty or stopped belt).
+/// - `Batch`: `accumulated`, `batch_size`, `ticks_until_forced_dispatch`.
+/// - `Vehicle`: `phase`, `ticks_remaining_in_phase`, `cargo_count`.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FfiTransportProgress {
+    pub kind: FfiTransportKind,
+    pub buffered: i64,
+    pub capacity: i64,
+    pub items_in_transit: u32,
+    pub head_position: i64,
+    pub ticks_until_delivery: u32,
+    pub accumulated: u32,
+    pub batch_size: u32,
+    pub ticks_until_forced_dispatch: u32,
+    pub phase: FfiVehiclePhase,
+    pub ticks_remaining_in_phase: u32,
+    pub cargo_count: u32,
+}
 
-        let result = unsafe { factorial_destroy(engine) };
-        assert_eq!(result, FactorialResult::Ok);
+fn transport_progress_to_ffi(progress: TransportProgress) -> FfiTransportProgress {
+    let mut out = FfiTransportProgress {
+        kind: FfiTransportKind::Flow,
+        buffered: 0,
+        capacity: 0,
+        items_in_transit: 0,
+        head_position: 0,
+        ticks_until_delivery: 0,
+        accumulated: 0,
+        batch_size: 0,
+        ticks_until_forced_dispatch: 0,
+        phase: FfiVehiclePhase::Loading,
+        ticks_remaining_in_phase: 0,
+        cargo_count: 0,
+    };
+    match progress {
+        TransportProgress::Flow { buffered, capacity } => {
+            out.buffered = buffered.to_bits();
+            out.capacity = capacity.to_bits();
+        }
+        TransportProgress::Item {
+            items_in_transit,
+            head_position,
+            ticks_until_delivery,
+        } => {
+            out.kind = FfiTransportKind::Item;
+            out.items_in_transit = items_in_transit;
+            out.head_position = head_position.map_or(0, Fixed64::to_bits);
+            out.ticks_until_delivery = ticks_until_delivery.unwrap_or(NO_DELIVERY);
+        }
+        TransportProgress::Batch {
+            accumulated,
+            batch_size,
+            ticks_until_forced_dispatch,
+        } => {
+            out.kind = FfiTransportKind::Batch;
+            out.accumulated = accumulated;
+            out.batch_size = batch_size;
+            out.ticks_until_forced_dispatch = ticks_until_forced_dispatch;
+        }
+        TransportProgress::Vehicle {
+            phase,
+            ticks_remaining_in_phase,
+            cargo_count,
+        } => {
+            out.kind = FfiTransportKind::Vehicle;
+            out.phase = match phase {
+                VehiclePhase::Loading => FfiVehiclePhase::Loading,
+                VehiclePhase::Traveling => FfiVehiclePhase::Traveling,
+                VehiclePhase::Unloading => FfiVehiclePhase::Unloading,
+                VehiclePhase::Returning => FfiVehiclePhase::Returning,
+            };
+            out.ticks_remaining_in_phase = ticks_remaining_in_phase;
+            out.cargo_count = cargo_count;
+        }
     }
+    out
+}
 
-    // -----------------------------------------------------------------------
-    // Test 2: Destroy null pointer returns NullPointer
-    // -----------------------------------------------------------------------
-    #[test]
-    fn destroy_null_returns_null_pointer() {
-        let result = unsafe { factorial_destroy(ptr::null_mut()) };
-        assert_eq!(result, FactorialResult::NullPointer);
+/// Write where an edge's transport is in its delivery cycle to `out`: buffer
+/// fill, belt head and next arrival, batch fill and dispatch countdown, or
+/// vehicle phase. Tick counts assume the destination has room; a count of
+/// `n` means the delivery or phase change happens during the `n`th
+/// `factorial_step` from now.
+///
+/// Returns `EdgeNotFound` if the edge has no transport.
+///
+/// # Safety
+///
+/// `engine` and `out` must be valid pointers.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_get_transport_progress(
+    engine: *const FactorialEngine,
+    edge_id: FfiEdgeId,
+    out: *mut FfiTransportProgress,
+) -> FactorialResult {
+    if engine.is_null() || out.is_null() {
+        return FactorialResult::NullPointer;
     }
-
-    // -----------------------------------------------------------------------
-    // Test 3: Step null pointer returns NullPointer
-    // -----------------------------------------------------------------------
-    #[test]
-    fn step_null_returns_null_pointer() {
-        let result = unsafe { factorial_step(ptr::null_mut()) };
-        assert_eq!(result, FactorialResult::NullPointer);
+    match catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let engine = unsafe { &*engine };
+        if engine.poisoned {
+            return FactorialResult::Poisoned;
+        }
+        match engine.inner.transport_progress(ffi_to_edge_id(edge_id)) {
+            Some(progress) => {
+                unsafe { *out = transport_progress_to_ffi(progress) };
+                FactorialResult::Ok
+            }
+            None => FactorialResult::EdgeNotFound,
+        }
+    })) {
+        Ok(result) => result,
+        Err(_) => FactorialResult::InternalError,
     }
+}
 
-    // -----------------------------------------------------------------------
-    // Test 4: Add node and apply mutations
-    // -----------------------------------------------------------------------
-    #[test]
-    fn add_node_and_apply_mutations() {
-        let engine = factorial_create();
-        assert!(!engine.is_null());
+// ---------------------------------------------------------------------------
+// Inspector handles
+// ---------------------------------------------------------------------------
 
-        // Add a node.
-        let mut pending: FfiPendingNodeId = 0;
-        let result = unsafe { factorial_add_node(engine, 0, &mut pending) };
-        assert_eq!(result, FactorialResult::Ok);
+/// Read-only view of an engine handle, for host subsystems such as rendering
+/// or analytics that must never change the simulation.
+///
+/// An inspector can only be passed to the `factorial_inspector_*` queries,
+/// which mirror the calls listed under "Concurrent reads" in the crate
+/// documentation: they read engine state in place and may run concurrently
+/// with each other, on any number of inspectors of the same engine. No
+/// function that steps, mutates or fills an engine-owned buffer accepts an
+/// inspector.
+///
+/// An inspector does not keep its engine alive. Once the engine is
+/// destroyed its inspectors return `InvalidHandle`; they must still be
+/// freed with `factorial_destroy_inspector`.
+pub struct FactorialEngineInspector {
+    engine: *const FactorialEngine,
+    /// The engine's access counter, which lives exactly as long as the
+    /// engine.
+    alive: Weak<AtomicU32>,
+}
 
-        // Apply mutations.
-        let mut mutation_result = FfiMutationResult {
-            added_nodes: ptr::null(),
-            added_node_count: 0,
-            added_edges: ptr::null(),
-            added_edge_count: 0,
-        };
-        let result = unsafe { factorial_apply_mutations(engine, &mut mutation_result) };
-        assert_eq!(result, FactorialResult::Ok);
-        assert_eq!(mutation_result.added_node_count, 1);
+/// Create a read-only inspector for `engine`. The caller must eventually
+/// call `factorial_destroy_inspector`.
+///
+/// Returns null if `engine` is null or on internal error.
+///
+/// # Safety
+///
+/// `engine` must be null or a valid engine pointer.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_create_inspector(
+    engine: *const FactorialEngine,
+) -> *const FactorialEngineInspector {
+    if engine.is_null() {
+        return ptr::null();
+    }
+    match catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let alive = Arc::downgrade(&unsafe { access_counter(engine) });
+        Box::into_raw(Box::new(FactorialEngineInspector { engine, alive })).cast_const()
+    })) {
+        Ok(inspector) => inspector,
+        Err(_) => ptr::null(),
+    }
+}
 
-        // Read back the real node ID.
-        let pairs = unsafe { std::slice::from_raw_parts(mutation_result.added_nodes, 1) };
-        assert_eq!(pairs[0].pending_id, pending);
-        assert_ne!(pairs[0].real_id, 0);
+/// Free an inspector. Its engine is not affected.
+///
+/// # Safety
+///
+/// `inspector` must be a pointer returned by `factorial_create_inspector`
+/// that has not yet been destroyed. After this call the pointer is invalid.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_destroy_inspector(
+    inspector: *const FactorialEngineInspector,
+) -> FactorialResult {
+    if inspector.is_null() {
+        return FactorialResult::NullPointer;
+    }
+    match catch_unwind(std::panic::AssertUnwindSafe(|| {
+        // SAFETY: caller guarantees `inspector` came from factorial_create_inspector.
+        let _ = unsafe { Box::from_raw(inspector.cast_mut()) };
+    })) {
+        Ok(()) => FactorialResult::Ok,
+        Err(_) => FactorialResult::InternalError,
+    }
+}
 
-        // Verify node count.
-        let mut count: u32 = 0;
-        let result = unsafe { factorial_node_count(engine, &mut count) };
-        assert_eq!(result, FactorialResult::Ok);
-        assert_eq!(count, 1);
+/// The engine behind an inspector, or the result to return: `NullPointer`
+/// for a null inspector, `InvalidHandle` once the engine is destroyed.
+///
+/// # Safety
+///
+/// `inspector` must be null or a valid inspector pointer.
+unsafe fn inspected_engine(
+    inspector: *const FactorialEngineInspector,
+) -> Result<*const FactorialEngine, FactorialResult> {
+    if inspector.is_null() {
+        return Err(FactorialResult::NullPointer);
+    }
+    let inspector = unsafe { &*inspector };
+    if inspector.alive.strong_count() == 0 {
+        return Err(FactorialResult::InvalidHandle);
+    }
+    Ok(inspector.engine)
+}
 
-        unsafe { factorial_destroy(engine) };
+/// Get the number of nodes in the graph. Inspector version of [`factorial_node_count`].
+///
+/// # Safety
+///
+/// `inspector` and `out_count` must be valid pointers.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_inspector_node_count(
+    inspector: *const FactorialEngineInspector,
+    out_count: *mut u32,
+) -> FactorialResult {
+    match unsafe { inspected_engine(inspector) } {
+        Ok(engine) => unsafe { factorial_node_count(engine, out_count) },
+        Err(result) => result,
     }
+}
 
-    // -----------------------------------------------------------------------
-    // Test 5: Add node, step, check tick incremented
-    // -----------------------------------------------------------------------
-    #[test]
-    fn step_increments_tick() {
-        let engine = factorial_create();
-        assert!(!engine.is_null());
+/// Get the number of edges in the graph. Inspector version of [`factorial_edge_count`].
+///
+/// # Safety
+///
+/// `inspector` and `out_count` must be valid pointers.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_inspector_edge_count(
+    inspector: *const FactorialEngineInspector,
+    out_count: *mut u32,
+) -> FactorialResult {
+    match unsafe { inspected_engine(inspector) } {
+        Ok(engine) => unsafe { factorial_edge_count(engine, out_count) },
+        Err(result) => result,
+    }
+}
 
-        let mut tick: u64 = 0;
-        unsafe { factorial_get_tick(engine, &mut tick) };
-        assert_eq!(tick, 0);
+/// Get the current tick. Inspector version of [`factorial_get_tick`].
+///
+/// # Safety
+///
+/// `inspector` and `out_tick` must be valid pointers.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_inspector_get_tick(
+    inspector: *const FactorialEngineInspector,
+    out_tick: *mut u64,
+) -> FactorialResult {
+    match unsafe { inspected_engine(inspector) } {
+        Ok(engine) => unsafe { factorial_get_tick(engine, out_tick) },
+        Err(result) => result,
+    }
+}
 
-        unsafe { factorial_step(engine) };
+/// Get the state hash of the most recent step. Inspector version of [`factorial_get_state_hash`].
+///
+/// # Safety
+///
+/// `inspector` and `out_hash` must be valid pointers.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_inspector_get_state_hash(
+    inspector: *const FactorialEngineInspector,
+    out_hash: *mut u64,
+) -> FactorialResult {
+    match unsafe { inspected_engine(inspector) } {
+        Ok(engine) => unsafe { factorial_get_state_hash(engine, out_hash) },
+        Err(result) => result,
+    }
+}
 
-        unsafe { factorial_get_tick(engine, &mut tick) };
+/// Get the most recent state hash split by subsystem. Inspector version of [`factorial_get_state_hash_breakdown`].
+///
+/// # Safety
+///
+/// `inspector` and `out_breakdown` must be valid pointers.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_inspector_get_state_hash_breakdown(
+    inspector: *const FactorialEngineInspector,
+    out_breakdown: *mut FfiHashBreakdown,
+) -> FactorialResult {
+    match unsafe { inspected_engine(inspector) } {
+        Ok(engine) => unsafe { factorial_get_state_hash_breakdown(engine, out_breakdown) },
+        Err(result) => result,
+    }
+}
+
+/// Count every unit of an item type in the factory. Inspector version of [`factorial_item_census`].
+///
+/// # Safety
+///
+/// `inspector` and `out_census` must be valid pointers.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_inspector_item_census(
+    inspector: *const FactorialEngineInspector,
+    item_type: u32,
+    out_census: *mut FfiItemCensus,
+) -> FactorialResult {
+    match unsafe { inspected_engine(inspector) } {
+        Ok(engine) => unsafe { factorial_item_census(engine, item_type, out_census) },
+        Err(result) => result,
+    }
+}
+
+/// Get the processor state of a node. Inspector version of [`factorial_get_processor_state`].
+///
+/// # Safety
+///
+/// `inspector` and `out_info` must be valid pointers.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_inspector_get_processor_state(
+    inspector: *const FactorialEngineInspector,
+    node_id: FfiNodeId,
+    out_info: *mut FfiProcessorInfo,
+) -> FactorialResult {
+    match unsafe { inspected_engine(inspector) } {
+        Ok(engine) => unsafe { factorial_get_processor_state(engine, node_id, out_info) },
+        Err(result) => result,
+    }
+}
+
+/// Get the total item count in a node's input inventory. Inspector version of [`factorial_get_input_inventory_count`].
+///
+/// # Safety
+///
+/// `inspector` and `out_count` must be valid pointers.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_inspector_get_input_inventory_count(
+    inspector: *const FactorialEngineInspector,
+    node_id: FfiNodeId,
+    out_count: *mut u32,
+) -> FactorialResult {
+    match unsafe { inspected_engine(inspector) } {
+        Ok(engine) => unsafe { factorial_get_input_inventory_count(engine, node_id, out_count) },
+        Err(result) => result,
+    }
+}
+
+/// Get the total item count in a node's output inventory. Inspector version of [`factorial_get_output_inventory_count`].
+///
+/// # Safety
+///
+/// `inspector` and `out_count` must be valid pointers.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_inspector_get_output_inventory_count(
+    inspector: *const FactorialEngineInspector,
+    node_id: FfiNodeId,
+    out_count: *mut u32,
+) -> FactorialResult {
+    match unsafe { inspected_engine(inspector) } {
+        Ok(engine) => unsafe { factorial_get_output_inventory_count(engine, node_id, out_count) },
+        Err(result) => result,
+    }
+}
+
+/// Get the simulation status of a node. Inspector version of [`factorial_get_node_status`].
+///
+/// # Safety
+///
+/// `inspector` and `out_status` must be valid pointers.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_inspector_get_node_status(
+    inspector: *const FactorialEngineInspector,
+    node_id: FfiNodeId,
+    out_status: *mut FfiNodeStatus,
+) -> FactorialResult {
+    match unsafe { inspected_engine(inspector) } {
+        Ok(engine) => unsafe { factorial_get_node_status(engine, node_id, out_status) },
+        Err(result) => result,
+    }
+}
+
+/// Get the simulation status of an edge. Inspector version of [`factorial_get_edge_status`].
+///
+/// # Safety
+///
+/// `inspector` and `out_status` must be valid pointers.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_inspector_get_edge_status(
+    inspector: *const FactorialEngineInspector,
+    edge_id: FfiEdgeId,
+    out_status: *mut FfiNodeStatus,
+) -> FactorialResult {
+    match unsafe { inspected_engine(inspector) } {
+        Ok(engine) => unsafe { factorial_get_edge_status(engine, edge_id, out_status) },
+        Err(result) => result,
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Events (pull-based)
+// ---------------------------------------------------------------------------
+
+/// Poll all buffered events since the last step. Returns a pointer to an
+/// engine-owned buffer of `FfiEvent` structs. The buffer is valid until the
+/// next `factorial_step`, `factorial_advance`, or `factorial_destroy`.
+///
+/// After a `factorial_advance` that ran several steps, the buffer holds the
+/// events of all of them in tick order, not just the last.
+///
+/// # Safety
+///
+/// `engine` and `out_buffer` must be valid pointers.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_poll_events(
+    engine: *const FactorialEngine,
+    out_buffer: *mut FfiEventBuffer,
+) -> FactorialResult {
+    if engine.is_null() || out_buffer.is_null() {
+        return FactorialResult::NullPointer;
+    }
+    match catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let engine = unsafe { &*engine };
+        if engine.poisoned {
+            return FactorialResult::Poisoned;
+        }
+        // Events were captured into the engine's cache by passive listeners
+        // during the most recent step/advance call. Return a pointer into it.
+        let cache = engine.event_cache();
+        unsafe {
+            *out_buffer = FfiEventBuffer {
+                events: if cache.events.is_empty() {
+                    ptr::null()
+                } else {
+                    cache.events.as_ptr()
+                },
+                count: cache.events.len() as u32,
+            };
+        }
+        FactorialResult::Ok
+    })) {
+        Ok(result) => result,
+        Err(_) => FactorialResult::InternalError,
+    }
+}
+
+/// Encode the events `factorial_poll_events` would return in the compact,
+/// versioned network format (readable with `Engine::decode_events_compact`).
+/// Each event is a tag plus only the fields it carries, so the buffer is far
+/// smaller than the flat `FfiEvent` array.
+///
+/// The caller must free the buffer with `factorial_free_buffer`.
+///
+/// # Safety
+///
+/// `engine` and `out_buffer` must be valid pointers.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_encode_events_compact(
+    engine: *const FactorialEngine,
+    out_buffer: *mut FfiByteBuffer,
+) -> FactorialResult {
+    if engine.is_null() || out_buffer.is_null() {
+        return FactorialResult::NullPointer;
+    }
+    match catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let engine = unsafe { &*engine };
+        if engine.poisoned {
+            return FactorialResult::Poisoned;
+        }
+        let data = Engine::encode_events_compact(&engine.event_cache().raw);
+        unsafe { *out_buffer = byte_buffer_from_vec(data) };
+        FactorialResult::Ok
+    })) {
+        Ok(result) => result,
+        Err(_) => FactorialResult::InternalError,
+    }
+}
+
+/// Set the maximum number of events cached for `factorial_poll_events`
+/// per step. Once the cache holds `max_events`, further events are counted
+/// by `factorial_dropped_event_count` instead of being cached. The cap only
+/// affects observation; the simulation itself is unchanged. Defaults to
+/// `DEFAULT_EVENT_CACHE_LIMIT`.
+///
+/// # Safety
+///
+/// `engine` must be a valid engine pointer.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_set_event_cache_limit(
+    engine: *mut FactorialEngine,
+    max_events: u32,
+) -> FactorialResult {
+    if engine.is_null() {
+        return FactorialResult::NullPointer;
+    }
+    let _mutation = unsafe { MutationGuard::begin(engine) };
+    match catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let engine = unsafe { &mut *engine };
+        if let Some(refused) = engine.refuse_mutation() {
+            return refused;
+        }
+        engine.event_cache().limit = max_events as usize;
+        FactorialResult::Ok
+    })) {
+        Ok(result) => result,
+        Err(_) => {
+            let engine = unsafe { &mut *engine };
+            engine.poisoned = true;
+            FactorialResult::InternalError
+        }
+    }
+}
+
+/// Get the number of events dropped by the event cache limit since the last
+/// step. A non-zero count means the buffer from `factorial_poll_events` is
+/// incomplete.
+///
+/// # Safety
+///
+/// `engine` and `out_count` must be valid pointers.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_dropped_event_count(
+    engine: *const FactorialEngine,
+    out_count: *mut u64,
+) -> FactorialResult {
+    if engine.is_null() || out_count.is_null() {
+        return FactorialResult::NullPointer;
+    }
+    match catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let engine = unsafe { &*engine };
+        if engine.poisoned {
+            return FactorialResult::Poisoned;
+        }
+        unsafe { *out_count = engine.event_cache().dropped };
+        FactorialResult::Ok
+    })) {
+        Ok(result) => result,
+        Err(_) => FactorialResult::InternalError,
+    }
+}
+
+/// Retain the last `capacity` events across steps for
+/// `factorial_poll_event_history`. Once full, the oldest events are
+/// dropped. A capacity of 0 disables the history.
+///
+/// # Safety
+///
+/// `engine` must be a valid engine pointer.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_set_event_history_capacity(
+    engine: *mut FactorialEngine,
+    capacity: u32,
+) -> FactorialResult {
+    if engine.is_null() {
+        return FactorialResult::NullPointer;
+    }
+    let _mutation = unsafe { MutationGuard::begin(engine) };
+    match catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let engine = unsafe { &mut *engine };
+        if let Some(refused) = engine.refuse_mutation() {
+            return refused;
+        }
+        engine.inner.set_event_history_capacity(capacity as usize);
+        FactorialResult::Ok
+    })) {
+        Ok(result) => result,
+        Err(_) => {
+            let engine = unsafe { &mut *engine };
+            engine.poisoned = true;
+            FactorialResult::InternalError
+        }
+    }
+}
+
+/// Copy retained history events stamped at or after `since_tick` into the
+/// caller-provided `out_events` array, oldest first, writing at most `max`
+/// events. The number written is stored in `*out_written`. Unlike
+/// `factorial_poll_events`, the history is not cleared by stepping.
+///
+/// # Safety
+///
+/// `engine` and `out_written` must be valid pointers. `out_events` must point
+/// to at least `max` `FfiEvent` slots (it may be null when `max` is 0).
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_poll_event_history(
+    engine: *const FactorialEngine,
+    since_tick: u64,
+    out_events: *mut FfiEvent,
+    max: u32,
+    out_written: *mut u32,
+) -> FactorialResult {
+    if engine.is_null() || out_written.is_null() || (out_events.is_null() && max > 0) {
+        return FactorialResult::NullPointer;
+    }
+    match catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let engine = unsafe { &*engine };
+        if engine.poisoned {
+            return FactorialResult::Poisoned;
+        }
+        let mut written = 0u32;
+        for event in engine
+            .inner
+            .event_history_since(since_tick)
+            .take(max as usize)
+        {
+            unsafe { *out_events.add(written as usize) = convert_event(event) };
+            written += 1;
+        }
+        unsafe { *out_written = written };
+        FactorialResult::Ok
+    })) {
+        Ok(result) => result,
+        Err(_) => FactorialResult::InternalError,
+    }
+}
+
+/// Filter for `factorial_query_event_history`. Zeroed fields (and
+/// `max_tick = UINT64_MAX`) mean "no restriction".
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct FfiEventFilter {
+    /// Bit `1 << kind` for each `FfiEventKind` to keep; 0 keeps every kind.
+    pub kind_mask: u32,
+    /// Keep only events about this node (0 = any node).
+    pub node: FfiNodeId,
+    /// Keep only events about this edge (0 = any edge).
+    pub edge: FfiEdgeId,
+    /// Keep only events stamped at or after this tick.
+    pub min_tick: u64,
+    /// Keep only events stamped at or before this tick.
+    pub max_tick: u64,
+}
+
+fn ffi_to_history_filter(filter: &FfiEventFilter) -> HistoryFilter {
+    HistoryFilter {
+        kinds: ALL_EVENT_KINDS
+            .iter()
+            .enumerate()
+            .filter(|&(i, _)| filter.kind_mask & (1 << i) != 0)
+            .map(|(_, &kind)| kind)
+            .collect(),
+        node: (filter.node != 0).then(|| ffi_to_node_id(filter.node)),
+        edge: (filter.edge != 0).then(|| ffi_to_edge_id(filter.edge)),
+        min_tick: Some(filter.min_tick),
+        max_tick: Some(filter.max_tick),
+    }
+}
+
+/// Copy retained history events matching `filter` into `out_events`, oldest
+/// first, writing at most `max` events. The number written is stored in
+/// `*out_count`. A null `filter` matches every event.
+///
+/// # Safety
+///
+/// `engine` and `out_count` must be valid pointers. `filter` must be null or
+/// valid. `out_events` must point to at least `max` `FfiEvent` slots (it may
+/// be null when `max` is 0).
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_query_event_history(
+    engine: *const FactorialEngine,
+    filter: *const FfiEventFilter,
+    out_events: *mut FfiEvent,
+    max: u32,
+    out_count: *mut u32,
+) -> FactorialResult {
+    if engine.is_null() || out_count.is_null() || (out_events.is_null() && max > 0) {
+        return FactorialResult::NullPointer;
+    }
+    match catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let engine = unsafe { &*engine };
+        if engine.poisoned {
+            return FactorialResult::Poisoned;
+        }
+        let filter = match unsafe { filter.as_ref() } {
+            Some(filter) => ffi_to_history_filter(filter),
+            None => HistoryFilter::default(),
+        };
+        let mut written = 0u32;
+        for event in engine.inner.event_history(filter).take(max as usize) {
+            unsafe { *out_events.add(written as usize) = convert_event(event) };
+            written += 1;
+        }
+        unsafe { *out_count = written };
+        FactorialResult::Ok
+    })) {
+        Ok(result) => result,
+        Err(_) => FactorialResult::InternalError,
+    }
+}
+
+fn event_cursor_id_to_ffi(id: EventCursorId) -> FfiEventCursorId {
+    id.data().as_ffi()
+}
+
+fn ffi_to_event_cursor_id(ffi: FfiEventCursorId) -> EventCursorId {
+    KeyData::from_ffi(ffi).into()
+}
+
+/// Create a cursor over the event history that reads events matching
+/// `filter` (null matches every event), starting from the oldest retained
+/// event. Each cursor keeps its own position and does not affect
+/// `factorial_poll_events`. Cursors are not serialized. The cursor id is
+/// written to `out_cursor`.
+///
+/// # Safety
+///
+/// `engine` and `out_cursor` must be valid pointers. `filter` must be null
+/// or valid.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_create_event_cursor(
+    engine: *mut FactorialEngine,
+    filter: *const FfiEventFilter,
+    out_cursor: *mut FfiEventCursorId,
+) -> FactorialResult {
+    if engine.is_null() || out_cursor.is_null() {
+        return FactorialResult::NullPointer;
+    }
+    let _mutation = unsafe { MutationGuard::begin(engine) };
+    match catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let engine = unsafe { &mut *engine };
+        if engine.poisoned {
+            return FactorialResult::Poisoned;
+        }
+        let filter = match unsafe { filter.as_ref() } {
+            Some(filter) => ffi_to_history_filter(filter),
+            None => HistoryFilter::default(),
+        };
+        let cursor = engine.inner.create_event_cursor(filter);
+        unsafe { *out_cursor = event_cursor_id_to_ffi(cursor) };
+        FactorialResult::Ok
+    })) {
+        Ok(result) => result,
+        Err(_) => {
+            let engine = unsafe { &mut *engine };
+            engine.poisoned = true;
+            FactorialResult::InternalError
+        }
+    }
+}
+
+/// Destroy an event cursor.
+///
+/// # Safety
+///
+/// `engine` must be a valid engine pointer.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_destroy_event_cursor(
+    engine: *mut FactorialEngine,
+    cursor: FfiEventCursorId,
+) -> FactorialResult {
+    if engine.is_null() {
+        return FactorialResult::NullPointer;
+    }
+    let _mutation = unsafe { MutationGuard::begin(engine) };
+    match catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let engine = unsafe { &mut *engine };
+        if engine.poisoned {
+            return FactorialResult::Poisoned;
+        }
+        if engine
+            .inner
+            .destroy_event_cursor(ffi_to_event_cursor_id(cursor))
+        {
+            FactorialResult::Ok
+        } else {
+            FactorialResult::EventCursorNotFound
+        }
+    })) {
+        Ok(result) => result,
+        Err(_) => {
+            let engine = unsafe { &mut *engine };
+            engine.poisoned = true;
+            FactorialResult::InternalError
+        }
+    }
+}
+
+/// Copy up to `max` history events matching the cursor's filter that were
+/// recorded after its position into `out_events`, oldest first, and advance
+/// the cursor past them. The number written is stored in `*out_count`.
+/// `*out_lagged` is set if the history overwrote events the cursor had not
+/// read; the cursor then resumed from the oldest retained event.
+///
+/// # Safety
+///
+/// `engine`, `out_count` and `out_lagged` must be valid pointers.
+/// `out_events` must point to at least `max` `FfiEvent` slots (it may be
+/// null when `max` is 0).
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_read_event_cursor(
+    engine: *mut FactorialEngine,
+    cursor: FfiEventCursorId,
+    out_events: *mut FfiEvent,
+    max: u32,
+    out_count: *mut u32,
+    out_lagged: *mut bool,
+) -> FactorialResult {
+    if engine.is_null()
+        || out_count.is_null()
+        || out_lagged.is_null()
+        || (out_events.is_null() && max > 0)
+    {
+        return FactorialResult::NullPointer;
+    }
+    let _mutation = unsafe { MutationGuard::begin(engine) };
+    match catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let engine = unsafe { &mut *engine };
+        if engine.poisoned {
+            return FactorialResult::Poisoned;
+        }
+        let Some((events, lagged)) = engine
+            .inner
+            .read_event_cursor(ffi_to_event_cursor_id(cursor), max as usize)
+        else {
+            return FactorialResult::EventCursorNotFound;
+        };
+        for (i, event) in events.iter().enumerate() {
+            unsafe { *out_events.add(i) = convert_event(event) };
+        }
+        unsafe {
+            *out_count = events.len() as u32;
+            *out_lagged = lagged;
+        }
+        FactorialResult::Ok
+    })) {
+        Ok(result) => result,
+        Err(_) => {
+            let engine = unsafe { &mut *engine };
+            engine.poisoned = true;
+            FactorialResult::InternalError
+        }
+    }
+}
+
+/// Include the event history in `factorial_serialize` output. Off by
+/// default; a snapshot that carries a history restores it on load.
+///
+/// # Safety
+///
+/// `engine` must be a valid engine pointer.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_set_serialize_event_history(
+    engine: *mut FactorialEngine,
+    enabled: bool,
+) -> FactorialResult {
+    if engine.is_null() {
+        return FactorialResult::NullPointer;
+    }
+    let _mutation = unsafe { MutationGuard::begin(engine) };
+    match catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let engine = unsafe { &mut *engine };
+        if let Some(refused) = engine.refuse_mutation() {
+            return refused;
+        }
+        engine.inner.set_serialize_event_history(enabled);
+        FactorialResult::Ok
+    })) {
+        Ok(result) => result,
+        Err(_) => {
+            let engine = unsafe { &mut *engine };
+            engine.poisoned = true;
+            FactorialResult::InternalError
+        }
+    }
+}
+
+/// Start recording an event log. Every tick's events are kept until taken
+/// with `factorial_take_event_log`, independent of the poll buffer. No-op if
+/// already enabled.
+///
+/// # Safety
+///
+/// `engine` must be a valid engine pointer.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_enable_event_log(
+    engine: *mut FactorialEngine,
+) -> FactorialResult {
+    if engine.is_null() {
+        return FactorialResult::NullPointer;
+    }
+    let _mutation = unsafe { MutationGuard::begin(engine) };
+    match catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let engine = unsafe { &mut *engine };
+        if let Some(refused) = engine.refuse_mutation() {
+            return refused;
+        }
+        engine.inner.enable_event_log();
+        FactorialResult::Ok
+    })) {
+        Ok(result) => result,
+        Err(_) => {
+            let engine = unsafe { &mut *engine };
+            engine.poisoned = true;
+            FactorialResult::InternalError
+        }
+    }
+}
+
+/// Stop recording the event log and discard anything not yet taken.
+///
+/// # Safety
+///
+/// `engine` must be a valid engine pointer.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_disable_event_log(
+    engine: *mut FactorialEngine,
+) -> FactorialResult {
+    if engine.is_null() {
+        return FactorialResult::NullPointer;
+    }
+    let _mutation = unsafe { MutationGuard::begin(engine) };
+    match catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let engine = unsafe { &mut *engine };
+        if let Some(refused) = engine.refuse_mutation() {
+            return refused;
+        }
+        engine.inner.disable_event_log();
+        FactorialResult::Ok
+    })) {
+        Ok(result) => result,
+        Err(_) => {
+            let engine = unsafe { &mut *engine };
+            engine.poisoned = true;
+            FactorialResult::InternalError
+        }
+    }
+}
+
+/// Take the event log recorded since the last take, encoded in the
+/// versioned `EventLog` byte format (readable with `EventLog::from_bytes`).
+/// Recording stays enabled. If the log is not enabled, an empty log is
+/// returned.
+///
+/// The caller must free the buffer with `factorial_free_buffer`.
+///
+/// # Safety
+///
+/// `engine` and `out_buffer` must be valid pointers.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_take_event_log(
+    engine: *mut FactorialEngine,
+    out_buffer: *mut FfiByteBuffer,
+) -> FactorialResult {
+    if engine.is_null() || out_buffer.is_null() {
+        return FactorialResult::NullPointer;
+    }
+    let _mutation = unsafe { MutationGuard::begin(engine) };
+    match catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let engine = unsafe { &mut *engine };
+        if engine.poisoned {
+            return FactorialResult::Poisoned;
+        }
+        let log = engine.inner.take_event_log().unwrap_or_default();
+        match log.to_bytes() {
+            Ok(data) => {
+                unsafe { *out_buffer = byte_buffer_from_vec(data) };
+                FactorialResult::Ok
+            }
+            Err(_) => {
+                unsafe {
+                    *out_buffer = FfiByteBuffer {
+                        data: ptr::null_mut(),
+                        len: 0,
+                    };
+                }
+                FactorialResult::SerializeError
+            }
+        }
+    })) {
+        Ok(result) => result,
+        Err(_) => {
+            let engine = unsafe { &mut *engine };
+            engine.poisoned = true;
+            FactorialResult::InternalError
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Serialization
+// ---------------------------------------------------------------------------
+
+/// Serialize the engine state to a binary buffer. The returned
+/// `FfiByteBuffer` contains a pointer and length. The caller must free the
+/// buffer with `factorial_free_buffer` when done.
+///
+/// # Safety
+///
+/// `engine` and `out_buffer` must be valid pointers.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_serialize(
+    engine: *const FactorialEngine,
+    out_buffer: *mut FfiByteBuffer,
+) -> FactorialResult {
+    if engine.is_null() || out_buffer.is_null() {
+        return FactorialResult::NullPointer;
+    }
+    match catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let engine = unsafe { &*engine };
+        if engine.poisoned {
+            return FactorialResult::Poisoned;
+        }
+        match engine.inner.serialize() {
+            Ok(data) => {
+                unsafe { *out_buffer = byte_buffer_from_vec(data) };
+                FactorialResult::Ok
+            }
+            Err(e) => {
+                unsafe {
+                    *out_buffer = FfiByteBuffer {
+                        data: ptr::null_mut(),
+                        len: 0,
+                    };
+                }
+                match e {
+                    SerializeError::TickInProgress => FactorialResult::TickInProgress,
+                    _ => FactorialResult::SerializeError,
+                }
+            }
+        }
+    })) {
+        Ok(result) => result,
+        Err(_) => FactorialResult::InternalError,
+    }
+}
+
+/// Deserialize an engine from a binary buffer. Returns a new engine pointer
+/// via `out_engine`. The caller takes ownership.
+///
+/// # Safety
+///
+/// `data` must point to `len` valid bytes. `out_engine` must be a valid pointer.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_deserialize(
+    data: *const u8,
+    len: usize,
+    out_engine: *mut *mut FactorialEngine,
+) -> FactorialResult {
+    if data.is_null() || out_engine.is_null() {
+        return FactorialResult::NullPointer;
+    }
+    match catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let slice = unsafe { std::slice::from_raw_parts(data, len) };
+        match Engine::deserialize(slice) {
+            Ok(engine) => {
+                unsafe { *out_engine = Box::into_raw(Box::new(FactorialEngine::new(engine))) };
+                FactorialResult::Ok
+            }
+            Err(_) => {
+                unsafe { *out_engine = ptr::null_mut() };
+                FactorialResult::DeserializeError
+            }
+        }
+    })) {
+        Ok(result) => result,
+        Err(_) => FactorialResult::InternalError,
+    }
+}
+
+/// Bundle component: the tech tree registered on the engine.
+pub const BUNDLE_TECH_TREE: u32 = 1;
+
+/// Bundle component: a fluid module.
+pub const BUNDLE_FLUID: u32 = 2;
+
+/// Bundle component: a statistics configuration.
+pub const BUNDLE_STATS_CONFIG: u32 = 4;
+
+/// Capture the engine as a scenario bundle. `flags` is a bitwise OR of the
+/// `BUNDLE_*` components to include alongside it; only
+/// [`BUNDLE_TECH_TREE`] applies to an FFI engine, and only if a tech tree is
+/// registered on it. The caller must free the buffer with
+/// `factorial_free_buffer`.
+///
+/// # Safety
+///
+/// `engine` and `out_buffer` must be valid pointers.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_bundle_capture(
+    engine: *const FactorialEngine,
+    flags: u32,
+    out_buffer: *mut FfiByteBuffer,
+) -> FactorialResult {
+    if engine.is_null() || out_buffer.is_null() {
+        return FactorialResult::NullPointer;
+    }
+    match catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let engine = unsafe { &*engine };
+        if engine.poisoned {
+            return FactorialResult::Poisoned;
+        }
+        let tech_tree = if flags & BUNDLE_TECH_TREE != 0 {
+            engine.inner.find_module::<TechTree>()
+        } else {
+            None
+        };
+        match Bundle::capture(&engine.inner, tech_tree, None, None) {
+            Ok(data) => {
+                unsafe { *out_buffer = byte_buffer_from_vec(data) };
+                FactorialResult::Ok
+            }
+            Err(e) => {
+                unsafe {
+                    *out_buffer = FfiByteBuffer {
+                        data: ptr::null_mut(),
+                        len: 0,
+                    };
+                }
+                match e {
+                    BundleError::Serialize(SerializeError::TickInProgress) => {
+                        FactorialResult::TickInProgress
+                    }
+                    _ => FactorialResult::SerializeError,
+                }
+            }
+        }
+    })) {
+        Ok(result) => result,
+        Err(_) => FactorialResult::InternalError,
+    }
+}
+
+/// Restore a scenario bundle into a new engine, returned via `out_engine`.
+/// The caller takes ownership. A tech tree that was registered on the
+/// captured engine is registered on the new one. `out_components` receives
+/// the `BUNDLE_*` components the bundle holds; standalone tech trees, fluid
+/// modules and statistics configurations belong to Rust hosts and are not
+/// restored here. Unknown sections are skipped.
+///
+/// # Safety
+///
+/// `data` must point to `len` valid bytes. `out_engine` and
+/// `out_components` must be valid pointers.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_bundle_restore(
+    data: *const u8,
+    len: usize,
+    out_engine: *mut *mut FactorialEngine,
+    out_components: *mut u32,
+) -> FactorialResult {
+    if data.is_null() || out_engine.is_null() || out_components.is_null() {
+        return FactorialResult::NullPointer;
+    }
+    match catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let slice = unsafe { std::slice::from_raw_parts(data, len) };
+        match Bundle::restore(slice) {
+            Ok(contents) => {
+                let components = contents.sections.iter().fold(0, |mask, section| {
+                    mask | match section {
+                        Section::Engine => 0,
+                        Section::TechTree | Section::TechTreeModule => BUNDLE_TECH_TREE,
+                        Section::Fluid => BUNDLE_FLUID,
+                        Section::StatsConfig => BUNDLE_STATS_CONFIG,
+                    }
+                });
+                unsafe {
+                    *out_engine = Box::into_raw(Box::new(FactorialEngine::new(contents.engine)));
+                    *out_components = components;
+                }
+                FactorialResult::Ok
+            }
+            Err(_) => {
+                unsafe {
+                    *out_engine = ptr::null_mut();
+                    *out_components = 0;
+                }
+                FactorialResult::DeserializeError
+            }
+        }
+    })) {
+        Ok(result) => result,
+        Err(_) => FactorialResult::InternalError,
+    }
+}
+
+/// Read the summary of a buffer produced by `factorial_serialize` without
+/// deserializing the engine. Saves without a summary section are fully
+/// decoded. Only the summary section is validated: a corrupt body is not
+/// noticed, a checksum mismatch returns `FACTORIAL_RESULT_DESERIALIZE_ERROR`.
+///
+/// # Safety
+///
+/// `data` must point to `len` valid bytes. `out_summary` must be a valid
+/// pointer.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_inspect(
+    data: *const u8,
+    len: usize,
+    out_summary: *mut FfiSaveSummary,
+) -> FactorialResult {
+    if data.is_null() || out_summary.is_null() {
+        return FactorialResult::NullPointer;
+    }
+    match catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let slice = unsafe { std::slice::from_raw_parts(data, len) };
+        let Ok(summary) = Engine::inspect(slice) else {
+            return FactorialResult::DeserializeError;
+        };
+        let totals = summary.item_totals.as_ref();
+        unsafe {
+            *out_summary = FfiSaveSummary {
+                version: summary.version,
+                tick: summary.tick,
+                node_count: summary.node_count,
+                edge_count: summary.edge_count,
+                extensions: summary.extensions,
+                state_hash: summary.state_hash,
+                has_item_totals: totals.is_some(),
+                item_type_count: totals.map_or(0, |t| t.len() as u32),
+                total_items: totals.map_or(0, |t| t.values().sum()),
+                full_decode: summary.full_decode,
+            };
+        }
+        FactorialResult::Ok
+    })) {
+        Ok(result) => result,
+        Err(_) => FactorialResult::InternalError,
+    }
+}
+
+/// Record per-item totals in the summary section of every
+/// `factorial_serialize` output, for `factorial_inspect`. Off by default.
+///
+/// # Safety
+///
+/// `engine` must be a valid engine pointer.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_set_summary_item_totals(
+    engine: *mut FactorialEngine,
+    enabled: bool,
+) -> FactorialResult {
+    if engine.is_null() {
+        return FactorialResult::NullPointer;
+    }
+    let _mutation = unsafe { MutationGuard::begin(engine) };
+    match catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let engine = unsafe { &mut *engine };
+        if let Some(refused) = engine.refuse_mutation() {
+            return refused;
+        }
+        engine.inner.set_summary_item_totals(enabled);
+        FactorialResult::Ok
+    })) {
+        Ok(result) => result,
+        Err(_) => {
+            let engine = unsafe { &mut *engine };
+            engine.poisoned = true;
+            FactorialResult::InternalError
+        }
+    }
+}
+
+/// Free a byte buffer returned by `factorial_serialize` or `factorial_take_event_log`.
+///
+/// # Safety
+///
+/// `buffer` must be a buffer originally returned by `factorial_serialize` or
+/// `factorial_take_event_log`.
+/// After this call the buffer's data pointer is invalid.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_free_buffer(buffer: FfiByteBuffer) -> FactorialResult {
+    if buffer.data.is_null() {
+        // Null data is a no-op (not an error).
+        return FactorialResult::Ok;
+    }
+    match catch_unwind(std::panic::AssertUnwindSafe(|| {
+        // Reconstruct the boxed slice and let it drop.
+        let _ =
+            unsafe { Box::from_raw(std::ptr::slice_from_raw_parts_mut(buffer.data, buffer.len)) };
+    })) {
+        Ok(()) => FactorialResult::Ok,
+        Err(_) => FactorialResult::InternalError,
+    }
+}
+
+// ---------------------------------------------------------------------------
+// FFI-safe configuration structs
+// ---------------------------------------------------------------------------
+
+/// C-compatible item stack (item type + quantity).
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct FfiItemStack {
+    pub item_type: u32,
+    pub quantity: u32,
+}
+
+/// C-compatible recipe for FixedRecipe processor.
+#[repr(C)]
+#[derive(Debug)]
+pub struct FfiRecipe {
+    pub input_count: u32,
+    pub inputs: *const FfiItemStack,
+    pub output_count: u32,
+    pub outputs: *const FfiItemStack,
+    pub duration: u32,
+}
+
+/// C-compatible fluid ingredient or product: fluid type plus raw Fixed64
+/// bits (Q32.32) per craft.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct FfiFluidAmount {
+    pub fluid: u32,
+    pub amount: i64,
+}
+
+/// Version 2 of [`FfiRecipe`]: adds fluid ingredient and product arrays.
+///
+/// The leading fields match `FfiRecipe`.
+#[repr(C)]
+#[derive(Debug)]
+pub struct FfiRecipeV2 {
+    pub input_count: u32,
+    pub inputs: *const FfiItemStack,
+    pub output_count: u32,
+    pub outputs: *const FfiItemStack,
+    pub duration: u32,
+    pub fluid_input_count: u32,
+    pub fluid_inputs: *const FfiFluidAmount,
+    pub fluid_output_count: u32,
+    pub fluid_outputs: *const FfiFluidAmount,
+}
+
+/// Version 3 of [`FfiRecipe`]: adds a chance for each item output.
+///
+/// The leading fields match `FfiRecipeV2`. `output_probabilities` is null
+/// when every output is always produced, or points to `output_count` raw
+/// Fixed64 bits (Q32.32), the chance in [0, 1] of producing each output.
+#[repr(C)]
+#[derive(Debug)]
+pub struct FfiRecipeV3 {
+    pub input_count: u32,
+    pub inputs: *const FfiItemStack,
+    pub output_count: u32,
+    pub outputs: *const FfiItemStack,
+    pub duration: u32,
+    pub fluid_input_count: u32,
+    pub fluid_inputs: *const FfiFluidAmount,
+    pub fluid_output_count: u32,
+    pub fluid_outputs: *const FfiFluidAmount,
+    pub output_probabilities: *const i64,
+}
+
+/// Engine-owned array of item stacks returned by the inventory clear calls.
+#[repr(C)]
+#[derive(Debug)]
+pub struct FfiItemBuffer {
+    pub items: *const FfiItemStack,
+    pub count: u32,
+}
+
+/// C-compatible transport kind for [`FfiTransportConfig`].
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FfiTransportKind {
+    Flow = 0,
+    Item = 1,
+    Batch = 2,
+    Vehicle = 3,
+}
+
+/// C-compatible transport configuration. Only the fields used by `kind`
+/// are read:
+///
+/// - `Flow`: `rate` (raw Fixed64 bits), with the same default buffer and
+///   latency as `factorial_set_flow_transport`.
+/// - `Item`: `rate` as belt speed (raw Fixed64 bits), `slot_count`, `lanes`.
+/// - `Batch`: `batch_size`, `cycle_time`.
+/// - `Vehicle`: `capacity`, `travel_time`.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct FfiTransportConfig {
+    pub kind: FfiTransportKind,
+    pub rate: i64,
+    pub slot_count: u32,
+    pub lanes: u8,
+    pub batch_size: u32,
+    pub cycle_time: u32,
+    pub capacity: u32,
+    pub travel_time: u32,
+}
+
+fn ffi_to_transport(config: &FfiTransportConfig) -> Transport {
+    match config.kind {
+        FfiTransportKind::Flow => Transport::Flow(FlowTransport {
+            rate: Fixed64::from_bits(config.rate),
+            buffer_capacity: Fixed64::from_num(1000),
+            latency: 0,
+        }),
+        FfiTransportKind::Item => Transport::Item(ItemTransport {
+            speed: Fixed64::from_bits(config.rate),
+            slot_count: config.slot_count,
+            lanes: config.lanes,
+            min_spacing: Fixed64::ONE,
+        }),
+        FfiTransportKind::Batch => Transport::Batch(BatchTransport {
+            batch_size: config.batch_size,
+            cycle_time: config.cycle_time,
+        }),
+        FfiTransportKind::Vehicle => Transport::Vehicle(VehicleTransport {
+            capacity: config.capacity,
+            travel_time: config.travel_time,
+        }),
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Configuration: Processors
+// ---------------------------------------------------------------------------
+
+/// Set a node's processor to Source.
+///
+/// `rate` is raw Fixed64 bits (Q32.32). Use `Fixed64::to_bits()` on the Rust
+/// side or shift an integer left by 32 on the C side to construct it.
+///
+/// # Safety
+///
+/// `engine` must be a valid engine pointer.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_set_source(
+    engine: *mut FactorialEngine,
+    node_id: FfiNodeId,
+    item_type: u32,
+    rate: i64,
+) -> FactorialResult {
+    if engine.is_null() {
+        return FactorialResult::NullPointer;
+    }
+    let _mutation = unsafe { MutationGuard::begin(engine) };
+    match catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let engine = unsafe { &mut *engine };
+        if let Some(refused) = engine.refuse_mutation() {
+            return refused;
+        }
+        let nid = ffi_to_node_id(node_id);
+        let processor = Processor::Source(SourceProcessor {
+            output_type: ItemTypeId(item_type),
+            base_rate: Fixed64::from_bits(rate),
+            depletion: Depletion::Infinite,
+            accumulated: Fixed64::from_num(0),
+            initial_properties: None,
+        });
+        engine.inner.set_processor(nid, processor);
+        FactorialResult::Ok
+    })) {
+        Ok(result) => result,
+        Err(_) => {
+            let engine = unsafe { &mut *engine };
+            engine.poisoned = true;
+            FactorialResult::InternalError
+        }
+    }
+}
+
+/// C-compatible source depletion kind.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FfiDepletionKind {
+    /// Never runs out (`Depletion::Infinite`; renamed so the C name does
+    /// not collide with the Windows `INFINITE` macro).
+    #[default]
+    Unlimited = 0,
+    /// Stops once `remaining` reaches zero.
+    Finite = 1,
+    /// Rate halves every `half_life` ticks.
+    Decaying = 2,
+}
+
+/// C-compatible source depletion. Only the field matching `kind` is
+/// meaningful; the other is zero.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FfiDepletion {
+    pub kind: FfiDepletionKind,
+    /// Raw Fixed64 bits of the amount left (`Finite`).
+    pub remaining: i64,
+    /// Half-life in ticks (`Decaying`).
+    pub half_life: u64,
+}
+
+fn depletion_to_ffi(depletion: &Depletion) -> FfiDepletion {
+    match *depletion {
+        Depletion::Infinite => FfiDepletion::default(),
+        Depletion::Finite { remaining } => FfiDepletion {
+            kind: FfiDepletionKind::Finite,
+            remaining: remaining.to_bits(),
+            half_life: 0,
+        },
+        Depletion::Decaying { half_life } => FfiDepletion {
+            kind: FfiDepletionKind::Decaying,
+            remaining: 0,
+            half_life,
+        },
+    }
+}
+
+/// Read back a Source processor's configuration. `out_rate` receives raw
+/// Fixed64 bits.
+///
+/// Returns `NodeNotFound` if the node has no processor and `KindMismatch`
+/// if its processor is not a Source.
+///
+/// # Safety
+///
+/// `engine` and all out pointers must be valid.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_get_source(
+    engine: *const FactorialEngine,
+    node_id: FfiNodeId,
+    out_item: *mut u32,
+    out_rate: *mut i64,
+    out_depletion: *mut FfiDepletion,
+) -> FactorialResult {
+    if engine.is_null() || out_item.is_null() || out_rate.is_null() || out_depletion.is_null() {
+        return FactorialResult::NullPointer;
+    }
+    match catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let engine = unsafe { &*engine };
+        if engine.poisoned {
+            return FactorialResult::Poisoned;
+        }
+        match engine.inner.get_processor(ffi_to_node_id(node_id)) {
+            Some(Processor::Source(source)) => {
+                unsafe {
+                    *out_item = source.output_type.0;
+                    *out_rate = source.base_rate.to_bits();
+                    *out_depletion = depletion_to_ffi(&source.depletion);
+                }
+                FactorialResult::Ok
+            }
+            Some(_) => FactorialResult::KindMismatch,
+            None => FactorialResult::NodeNotFound,
+        }
+    })) {
+        Ok(result) => result,
+        Err(_) => FactorialResult::InternalError,
+    }
+}
+
+/// C-compatible processor kind, from [`factorial_get_processor_config`].
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FfiProcessorKind {
+    ProcessorKindSource = 0,
+    ProcessorKindFixed = 1,
+    ProcessorKindProperty = 2,
+    ProcessorKindDemand = 3,
+    ProcessorKindPassthrough = 4,
+    ProcessorKindMultiRecipe = 5,
+    ProcessorKindWeightedRecipe = 6,
+    ProcessorKindFlexible = 7,
+    ProcessorKindStorage = 8,
+    ProcessorKindMultiSource = 9,
+}
+
+/// C-compatible description of a node's processor, filled by
+/// [`factorial_get_processor_config`].
+///
+/// The caller sets `inputs`/`input_capacity` and `outputs`/`output_capacity`
+/// to buffers for the recipe's item stacks (null with capacity 0 to ask for
+/// the counts only). Everything else is written by the call; fields that do
+/// not apply to `kind` are zero.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct FfiProcessorConfig {
+    pub kind: FfiProcessorKind,
+    /// Item produced (`Source`, `Property`) or consumed (`Demand`).
+    pub item: u32,
+    /// Item consumed by a `Property` processor.
+    pub input_item: u32,
+    /// Raw Fixed64 bits of the base rate (`Source`, `Demand`).
+    pub rate: i64,
+    /// Deposit depletion (`Source`, `MultiSource`).
+    pub depletion: FfiDepletion,
+    /// Recipes held: 1 for `Fixed`, the list length for `MultiRecipe`,
+    /// `WeightedRecipe` and `Flexible`.
+    pub recipe_count: u32,
+    /// Index of the recipe described below (`MultiRecipe`).
+    pub active_recipe: u32,
+    /// Cycle length in ticks of the described recipe.
+    pub duration: u32,
+    /// Caller buffer for the described recipe's item inputs.
+    pub inputs: *mut FfiItemStack,
+    pub input_capacity: u32,
+    /// Number of item inputs, written even if the buffer is too small.
+    pub input_count: u32,
+    /// Caller buffer for the described recipe's item outputs.
+    pub outputs: *mut FfiItemStack,
+    pub output_capacity: u32,
+    /// Number of item outputs, written even if the buffer is too small.
+    pub output_count: u32,
+}
+
+/// Read back a node's processor: its kind and parameters. The recipe of a
+/// `Fixed` processor, or the active recipe of a `MultiRecipe` one, is
+/// described by `duration` and the item stacks copied into the caller's
+/// `inputs` and `outputs` buffers, in recipe order. Fluid ingredients and
+/// output chances are not reported.
+///
+/// The counts are always written. If either buffer is too small, no stacks
+/// are copied and `BufferTooSmall` is returned; retry with larger buffers.
+/// Returns `NodeNotFound` if the node has no processor.
+///
+/// # Safety
+///
+/// `engine` and `out_config` must be valid pointers. `out_config.inputs`
+/// and `out_config.outputs` must be valid for their capacities (they may be
+/// null when the capacity is 0).
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_get_processor_config(
+    engine: *const FactorialEngine,
+    node_id: FfiNodeId,
+    out_config: *mut FfiProcessorConfig,
+) -> FactorialResult {
+    if engine.is_null() || out_config.is_null() {
+        return FactorialResult::NullPointer;
+    }
+    let config = unsafe { &mut *out_config };
+    if (config.inputs.is_null() && config.input_capacity > 0)
+        || (config.outputs.is_null() && config.output_capacity > 0)
+    {
+        return FactorialResult::NullPointer;
+    }
+    match catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let engine = unsafe { &*engine };
+        if engine.poisoned {
+            return FactorialResult::Poisoned;
+        }
+        let Some(processor) = engine.inner.get_processor(ffi_to_node_id(node_id)) else {
+            return FactorialResult::NodeNotFound;
+        };
+        config.item = 0;
+        config.input_item = 0;
+        config.rate = 0;
+        config.depletion = FfiDepletion::default();
+        config.recipe_count = 0;
+        config.active_recipe = 0;
+        config.duration = 0;
+        config.input_count = 0;
+        config.output_count = 0;
+        let mut recipe = None;
+        config.kind = match processor {
+            Processor::Source(source) => {
+                config.item = source.output_type.0;
+                config.rate = source.base_rate.to_bits();
+                config.depletion = depletion_to_ffi(&source.depletion);
+                FfiProcessorKind::ProcessorKindSource
+            }
+            Processor::Fixed(fixed) => {
+                config.recipe_count = 1;
+                recipe = Some(fixed);
+                FfiProcessorKind::ProcessorKindFixed
+            }
+            Processor::Property(property) => {
+                config.item = property.output_type.0;
+                config.input_item = property.input_type.0;
+                FfiProcessorKind::ProcessorKindProperty
+            }
+            Processor::Demand(demand) => {
+                config.item = demand.input_type.0;
+                config.rate = demand.base_rate.to_bits();
+                FfiProcessorKind::ProcessorKindDemand
+            }
+            Processor::Passthrough => FfiProcessorKind::ProcessorKindPassthrough,
+            Processor::MultiRecipe(multi) => {
+                config.recipe_count = multi.recipes.len() as u32;
+                config.active_recipe = multi.active_recipe as u32;
+                recipe = multi.recipes.get(multi.active_recipe);
+                FfiProcessorKind::ProcessorKindMultiRecipe
+            }
+            Processor::WeightedRecipe(weighted) => {
+                config.recipe_count = weighted.recipes.len() as u32;
+                FfiProcessorKind::ProcessorKindWeightedRecipe
+            }
+            Processor::Flexible(flexible) => {
+                config.recipe_count = flexible.recipes.len() as u32;
+                FfiProcessorKind::ProcessorKindFlexible
+            }
+            Processor::Storage(_) => FfiProcessorKind::ProcessorKindStorage,
+            Processor::MultiSource(multi) => {
+                config.depletion = depletion_to_ffi(&multi.depletion);
+                FfiProcessorKind::ProcessorKindMultiSource
+            }
+        };
+        let Some(recipe) = recipe else {
+            return FactorialResult::Ok;
+        };
+        config.duration = recipe.duration;
+        config.input_count = recipe.inputs.len() as u32;
+        config.output_count = recipe.outputs.len() as u32;
+        if config.input_capacity < config.input_count
+            || config.output_capacity < config.output_count
+        {
+            return FactorialResult::BufferTooSmall;
+        }
+        if !recipe.inputs.is_empty() {
+            let out = unsafe { std::slice::from_raw_parts_mut(config.inputs, recipe.inputs.len()) };
+            for (slot, input) in out.iter_mut().zip(&recipe.inputs) {
+                *slot = FfiItemStack {
+                    item_type: input.item_type.0,
+                    quantity: input.quantity,
+                };
+            }
+        }
+        if !recipe.outputs.is_empty() {
+            let out =
+                unsafe { std::slice::from_raw_parts_mut(config.outputs, recipe.outputs.len()) };
+            for (slot, output) in out.iter_mut().zip(&recipe.outputs) {
+                *slot = FfiItemStack {
+                    item_type: output.item_type.0,
+                    quantity: output.quantity,
+                };
+            }
+        }
+        FactorialResult::Ok
+    })) {
+        Ok(result) => result,
+        Err(_) => FactorialResult::InternalError,
+    }
+}
+
+fn ffi_to_depletion(depletion: &FfiDepletion) -> Depletion {
+    match depletion.kind {
+        FfiDepletionKind::Unlimited => Depletion::Infinite,
+        FfiDepletionKind::Finite => Depletion::Finite {
+            remaining: Fixed64::from_bits(depletion.remaining),
+        },
+        FfiDepletionKind::Decaying => Depletion::Decaying {
+            half_life: depletion.half_life,
+        },
+    }
+}
+
+/// C-compatible output of a multi-output source: item type plus raw
+/// Fixed64 bits (Q32.32) of items per tick.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct FfiSourceOutput {
+    pub item_type: u32,
+    pub rate: i64,
+}
+
+/// Set a node's processor to MultiSource: one deposit yielding every item
+/// in `outputs`, each at its own rate, with every extracted item counting
+/// against the shared `depletion`.
+///
+/// # Safety
+///
+/// `engine` must be a valid engine pointer and `outputs` must be valid for
+/// `output_count` elements.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_set_multi_source(
+    engine: *mut FactorialEngine,
+    node_id: FfiNodeId,
+    outputs: *const FfiSourceOutput,
+    output_count: u32,
+    depletion: FfiDepletion,
+) -> FactorialResult {
+    if engine.is_null() || (outputs.is_null() && output_count > 0) {
+        return FactorialResult::NullPointer;
+    }
+    let _mutation = unsafe { MutationGuard::begin(engine) };
+    match catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let engine = unsafe { &mut *engine };
+        if let Some(refused) = engine.refuse_mutation() {
+            return refused;
+        }
+        let outputs = if output_count == 0 {
+            &[][..]
+        } else {
+            unsafe { std::slice::from_raw_parts(outputs, output_count as usize) }
+        };
+        let processor = Processor::MultiSource(MultiSourceProcessor::new(
+            outputs
+                .iter()
+                .map(|o| (ItemTypeId(o.item_type), Fixed64::from_bits(o.rate)))
+                .collect(),
+            ffi_to_depletion(&depletion),
+        ));
+        engine
+            .inner
+            .set_processor(ffi_to_node_id(node_id), processor);
+        FactorialResult::Ok
+    })) {
+        Ok(result) => result,
+        Err(_) => {
+            let engine = unsafe { &mut *engine };
+            engine.poisoned = true;
+            FactorialResult::InternalError
+        }
+    }
+}
+
+/// C-compatible richness curve. Every `Fixed64` field is raw Q32.32 bits.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct FfiRichnessCurve {
+    /// Width of a noise cell in map units (`0` behaves as `1`).
+    pub cell_size: u32,
+    pub min_rate: i64,
+    pub max_rate: i64,
+    pub min_amount: i64,
+    /// Deposit size at richness 1; zero or negative makes deposits infinite.
+    pub max_amount: i64,
+}
+
+/// C-compatible record of where a procedural source sits.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct FfiSourceSite {
+    pub x: i32,
+    pub y: i32,
+    /// Richness in `[0, 1)`, as raw Fixed64 bits.
+    pub richness: i64,
+}
+
+fn source_site_to_ffi(site: SourceSite) -> FfiSourceSite {
+    FfiSourceSite {
+        x: site.x,
+        y: site.y,
+        richness: site.richness.to_bits(),
+    }
+}
+
+/// Install the seeded noise field used by `factorial_add_source_at`.
+/// Sources already added keep their configuration.
+///
+/// # Safety
+///
+/// `engine` and `curve` must be valid pointers.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_set_richness_provider(
+    engine: *mut FactorialEngine,
+    seed: u64,
+    curve: *const FfiRichnessCurve,
+) -> FactorialResult {
+    if engine.is_null() || curve.is_null() {
+        return FactorialResult::NullPointer;
+    }
+    let _mutation = unsafe { MutationGuard::begin(engine) };
+    match catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let engine = unsafe { &mut *engine };
+        if let Some(refused) = engine.refuse_mutation() {
+            return refused;
+        }
+        let curve = unsafe { &*curve };
+        engine.inner.set_richness_provider(
+            seed,
+            RichnessCurve {
+                cell_size: curve.cell_size,
+                min_rate: Fixed64::from_bits(curve.min_rate),
+                max_rate: Fixed64::from_bits(curve.max_rate),
+                min_amount: Fixed64::from_bits(curve.min_amount),
+                max_amount: Fixed64::from_bits(curve.max_amount),
+            },
+        );
+        FactorialResult::Ok
+    })) {
+        Ok(result) => result,
+        Err(_) => {
+            let engine = unsafe { &mut *engine };
+            engine.poisoned = true;
+            FactorialResult::InternalError
+        }
+    }
+}
+
+/// Make a node a Source whose rate and deposit are read from the richness
+/// provider at `(x, y)`. `out_site` may be null; otherwise it receives the
+/// recorded site.
+///
+/// Returns `InvalidArgument` if no provider is installed and `NodeNotFound`
+/// if the node does not exist.
+///
+/// # Safety
+///
+/// `engine` must be a valid engine pointer; `out_site` must be null or
+/// valid.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_add_source_at(
+    engine: *mut FactorialEngine,
+    node_id: FfiNodeId,
+    item_type: u32,
+    x: i32,
+    y: i32,
+    out_site: *mut FfiSourceSite,
+) -> FactorialResult {
+    if engine.is_null() {
+        return FactorialResult::NullPointer;
+    }
+    let _mutation = unsafe { MutationGuard::begin(engine) };
+    match catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let engine = unsafe { &mut *engine };
+        if let Some(refused) = engine.refuse_mutation() {
+            return refused;
+        }
+        if engine.inner.richness_provider().is_none() {
+            return FactorialResult::InvalidArgument;
+        }
+        let nid = ffi_to_node_id(node_id);
+        match engine.inner.add_source_at(nid, ItemTypeId(item_type), x, y) {
+            Some(site) => {
+                if !out_site.is_null() {
+                    unsafe { *out_site = source_site_to_ffi(site) };
+                }
+                FactorialResult::Ok
+            }
+            None => FactorialResult::NodeNotFound,
+        }
+    })) {
+        Ok(result) => result,
+        Err(_) => {
+            let engine = unsafe { &mut *engine };
+            engine.poisoned = true;
+            FactorialResult::InternalError
+        }
+    }
+}
+
+/// Read the coordinates and richness of a source added with
+/// `factorial_add_source_at`.
+///
+/// Returns `NodeNotFound` if the node has no such source, including after
+/// its processor was replaced.
+///
+/// # Safety
+///
+/// `engine` and `out_site` must be valid pointers.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_get_source_site(
+    engine: *const FactorialEngine,
+    node_id: FfiNodeId,
+    out_site: *mut FfiSourceSite,
+) -> FactorialResult {
+    if engine.is_null() || out_site.is_null() {
+        return FactorialResult::NullPointer;
+    }
+    match catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let engine = unsafe { &*engine };
+        if engine.poisoned {
+            return FactorialResult::Poisoned;
+        }
+        match engine.inner.source_site(ffi_to_node_id(node_id)) {
+            Some(site) => {
+                unsafe { *out_site = source_site_to_ffi(site) };
+                FactorialResult::Ok
+            }
+            None => FactorialResult::NodeNotFound,
+        }
+    })) {
+        Ok(result) => result,
+        Err(_) => FactorialResult::InternalError,
+    }
+}
+
+/// Convert an FFI item stack array into recipe inputs. Null or empty
+/// arrays yield no inputs.
+///
+/// # Safety
+///
+/// `ptr` must be valid for `count` elements when non-null.
+unsafe fn ffi_to_recipe_inputs(ptr: *const FfiItemStack, count: u32) -> Vec<RecipeInput> {
+    if count == 0 || ptr.is_null() {
+        return Vec::new();
+    }
+    let stacks = unsafe { std::slice::from_raw_parts(ptr, count as usize) };
+    stacks
+        .iter()
+        .map(|fi| RecipeInput {
+            item_type: ItemTypeId(fi.item_type),
+            quantity: fi.quantity,
+            consumed: true,
+        })
+        .collect()
+}
+
+/// Convert an FFI item stack array into recipe outputs. Null or empty
+/// arrays yield no outputs.
+///
+/// # Safety
+///
+/// `ptr` must be valid for `count` elements when non-null.
+unsafe fn ffi_to_recipe_outputs(ptr: *const FfiItemStack, count: u32) -> Vec<RecipeOutput> {
+    if count == 0 || ptr.is_null() {
+        return Vec::new();
+    }
+    let stacks = unsafe { std::slice::from_raw_parts(ptr, count as usize) };
+    stacks
+        .iter()
+        .map(|fo| RecipeOutput {
+            item_type: ItemTypeId(fo.item_type),
+            quantity: fo.quantity,
+            bonus: None,
+            probability: None,
+        })
+        .collect()
+}
+
+/// Convert an FFI item stack array into registry recipe entries. Null or
+/// empty arrays yield no entries.
+///
+/// # Safety
+///
+/// `ptr` must be valid for `count` elements when non-null.
+unsafe fn ffi_to_recipe_entries(ptr: *const FfiItemStack, count: u32) -> Vec<RecipeEntry> {
+    if count == 0 || ptr.is_null() {
+        return Vec::new();
+    }
+    let stacks = unsafe { std::slice::from_raw_parts(ptr, count as usize) };
+    stacks
+        .iter()
+        .map(|fi| RecipeEntry {
+            item: ItemTypeId(fi.item_type),
+            quantity: fi.quantity,
+            consumed: true,
+        })
+        .collect()
+}
+
+/// Convert an FFI fluid amount array. Null or empty arrays yield nothing.
+///
+/// # Safety
+///
+/// `ptr` must be valid for `count` elements when non-null.
+unsafe fn ffi_to_fluid_amounts(ptr: *const FfiFluidAmount, count: u32) -> Vec<FluidAmount> {
+    if count == 0 || ptr.is_null() {
+        return Vec::new();
+    }
+    let amounts = unsafe { std::slice::from_raw_parts(ptr, count as usize) };
+    amounts
+        .iter()
+        .map(|f| FluidAmount {
+            fluid: ItemTypeId(f.fluid),
+            amount: Fixed64::from_bits(f.amount),
+        })
+        .collect()
+}
+
+/// Convert an FFI recipe into a FixedRecipe processor.
+///
+/// # Safety
+///
+/// The arrays referenced by `recipe.inputs` and `recipe.outputs` must be
+/// valid for the declared counts.
+unsafe fn ffi_to_fixed_processor(recipe: &FfiRecipe) -> Processor {
+    Processor::Fixed(FixedRecipe {
+        inputs: unsafe { ffi_to_recipe_inputs(recipe.inputs, recipe.input_count) },
+        outputs: unsafe { ffi_to_recipe_outputs(recipe.outputs, recipe.output_count) },
+        duration: recipe.duration,
+        fluid_inputs: Vec::new(),
+        fluid_outputs: Vec::new(),
+        duration_per_unit: None,
+    })
+}
+
+/// Set a node's processor to FixedRecipe.
+///
+/// The `recipe` pointer must point to a valid `FfiRecipe` whose `inputs` and
+/// `outputs` arrays have the declared counts.
+///
+/// # Safety
+///
+/// `engine` and `recipe` must be valid pointers. The arrays referenced by
+/// `recipe.inputs` and `recipe.outputs` must be valid for the declared counts.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_set_fixed_processor(
+    engine: *mut FactorialEngine,
+    node_id: FfiNodeId,
+    recipe: *const FfiRecipe,
+) -> FactorialResult {
+    if engine.is_null() || recipe.is_null() {
+        return FactorialResult::NullPointer;
+    }
+    let _mutation = unsafe { MutationGuard::begin(engine) };
+    match catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let engine = unsafe { &mut *engine };
+        if let Some(refused) = engine.refuse_mutation() {
+            return refused;
+        }
+        let processor = unsafe { ffi_to_fixed_processor(&*recipe) };
+        engine
+            .inner
+            .set_processor(ffi_to_node_id(node_id), processor);
+        FactorialResult::Ok
+    })) {
+        Ok(result) => result,
+        Err(_) => {
+            let engine = unsafe { &mut *engine };
+            engine.poisoned = true;
+            FactorialResult::InternalError
+        }
+    }
+}
+
+/// One entry of [`factorial_set_processors_bulk`]: a node and the
+/// FixedRecipe to give it.
+///
+/// Layout: the 8-byte node id followed by an `FfiRecipe`, with the C
+/// alignment rules of the target.
+#[repr(C)]
+#[derive(Debug)]
+pub struct FfiProcessorAssignment {
+    pub node: FfiNodeId,
+    pub recipe: FfiRecipe,
+}
+
+/// Give each of `count` nodes a FixedRecipe processor in one call, as
+/// [`factorial_set_fixed_processor`] does for one.
+///
+/// Each assignment is applied independently, in order. `out_results`
+/// receives one result per assignment: `Ok`, or `NodeNotFound` for a node
+/// not in the graph, which leaves the rest unaffected. The call itself
+/// returns `Ok` unless its arguments are invalid.
+///
+/// # Safety
+///
+/// `engine` must be a valid pointer. `assignments` and `out_results` must
+/// be valid for `count` elements (they may be null when `count` is 0), and
+/// every recipe's arrays must be valid for their declared counts.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_set_processors_bulk(
+    engine: *mut FactorialEngine,
+    assignments: *const FfiProcessorAssignment,
+    count: u32,
+    out_results: *mut FactorialResult,
+) -> FactorialResult {
+    if engine.is_null() || (count > 0 && (assignments.is_null() || out_results.is_null())) {
+        return FactorialResult::NullPointer;
+    }
+    let _mutation = unsafe { MutationGuard::begin(engine) };
+    match catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let engine = unsafe { &mut *engine };
+        if let Some(refused) = engine.refuse_mutation() {
+            return refused;
+        }
+        if count == 0 {
+            return FactorialResult::Ok;
+        }
+        let assignments = unsafe { std::slice::from_raw_parts(assignments, count as usize) };
+        let results = unsafe { std::slice::from_raw_parts_mut(out_results, count as usize) };
+        for (assignment, result) in assignments.iter().zip(results) {
+            let node = ffi_to_node_id(assignment.node);
+            *result = if engine.inner.graph.contains_node(node) {
+                let processor = unsafe { ffi_to_fixed_processor(&assignment.recipe) };
+                engine.inner.set_processor(node, processor);
+                FactorialResult::Ok
+            } else {
+                FactorialResult::NodeNotFound
+            };
+        }
+        FactorialResult::Ok
+    })) {
+        Ok(result) => result,
+        Err(_) => {
+            let engine = unsafe { &mut *engine };
+            engine.poisoned = true;
+            FactorialResult::InternalError
+        }
+    }
+}
+
+/// Set a node's processor to a FixedRecipe with fluid ingredients.
+///
+/// Fluid inputs are drawn from the node's fluid buffer when a cycle starts
+/// and fluid outputs are added to it when a cycle completes. Fill and drain
+/// the buffer with [`factorial_add_fluid_input`] and
+/// [`factorial_take_fluid_output`].
+///
+/// # Safety
+///
+/// `engine` and `recipe` must be valid pointers. Every array referenced by
+/// `recipe` must be valid for its declared count.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_set_fixed_processor_v2(
+    engine: *mut FactorialEngine,
+    node_id: FfiNodeId,
+    recipe: *const FfiRecipeV2,
+) -> FactorialResult {
+    if engine.is_null() || recipe.is_null() {
+        return FactorialResult::NullPointer;
+    }
+    let _mutation = unsafe { MutationGuard::begin(engine) };
+    match catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let engine = unsafe { &mut *engine };
+        if let Some(refused) = engine.refuse_mutation() {
+            return refused;
+        }
+        let recipe = unsafe { &*recipe };
+        let processor = Processor::Fixed(FixedRecipe {
+            inputs: unsafe { ffi_to_recipe_inputs(recipe.inputs, recipe.input_count) },
+            outputs: unsafe { ffi_to_recipe_outputs(recipe.outputs, recipe.output_count) },
+            duration: recipe.duration,
+            fluid_inputs: unsafe {
+                ffi_to_fluid_amounts(recipe.fluid_inputs, recipe.fluid_input_count)
+            },
+            fluid_outputs: unsafe {
+                ffi_to_fluid_amounts(recipe.fluid_outputs, recipe.fluid_output_count)
+            },
+            duration_per_unit: None,
+        });
+        engine
+            .inner
+            .set_processor(ffi_to_node_id(node_id), processor);
+        FactorialResult::Ok
+    })) {
+        Ok(result) => result,
+        Err(_) => {
+            let engine = unsafe { &mut *engine };
+            engine.poisoned = true;
+            FactorialResult::InternalError
+        }
+    }
+}
+
+/// Set a node's processor to a FixedRecipe with fluid ingredients and
+/// chance-based outputs.
+///
+/// Like [`factorial_set_fixed_processor_v2`]. When a cycle completes, each
+/// output with a chance is rolled on the node's deterministic RNG, in output
+/// order, and only produced outputs reach the output inventory and events.
+///
+/// # Safety
+///
+/// `engine` and `recipe` must be valid pointers. Every array referenced by
+/// `recipe` must be valid for its declared count; `output_probabilities`,
+/// when non-null, for `output_count` elements.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_set_fixed_processor_v3(
+    engine: *mut FactorialEngine,
+    node_id: FfiNodeId,
+    recipe: *const FfiRecipeV3,
+) -> FactorialResult {
+    if engine.is_null() || recipe.is_null() {
+        return FactorialResult::NullPointer;
+    }
+    let _mutation = unsafe { MutationGuard::begin(engine) };
+    match catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let engine = unsafe { &mut *engine };
+        if let Some(refused) = engine.refuse_mutation() {
+            return refused;
+        }
+        let recipe = unsafe { &*recipe };
+        let mut outputs = unsafe { ffi_to_recipe_outputs(recipe.outputs, recipe.output_count) };
+        if !recipe.output_probabilities.is_null() && !outputs.is_empty() {
+            let chances =
+                unsafe { std::slice::from_raw_parts(recipe.output_probabilities, outputs.len()) };
+            for (output, &bits) in outputs.iter_mut().zip(chances) {
+                output.probability = Some(Fixed64::from_bits(bits));
+            }
+        }
+        let processor = Processor::Fixed(FixedRecipe {
+            inputs: unsafe { ffi_to_recipe_inputs(recipe.inputs, recipe.input_count) },
+            outputs,
+            duration: recipe.duration,
+            fluid_inputs: unsafe {
+                ffi_to_fluid_amounts(recipe.fluid_inputs, recipe.fluid_input_count)
+            },
+            fluid_outputs: unsafe {
+                ffi_to_fluid_amounts(recipe.fluid_outputs, recipe.fluid_output_count)
+            },
+            duration_per_unit: None,
+        });
+        engine
+            .inner
+            .set_processor(ffi_to_node_id(node_id), processor);
+        FactorialResult::Ok
+    })) {
+        Ok(result) => result,
+        Err(_) => {
+            let engine = unsafe { &mut *engine };
+            engine.poisoned = true;
+            FactorialResult::InternalError
+        }
+    }
+}
+
+/// Written by [`factorial_get_active_recipe`] when no recipe is in progress.
+pub const NO_RECIPE: u32 = u32::MAX;
+
+/// Set a node's processor to a flexible processor that picks one of
+/// `recipe_count` registry recipes each time a cycle starts, from the
+/// ingredients in its input inventory.
+///
+/// `selection` is 0 = first match in list order, 1 = the recipe with the
+/// largest batch available. Returns `InvalidArgument` for any other value.
+/// Recipes are looked up in the engine's registry; ids it does not contain
+/// are never selected.
+///
+/// # Safety
+///
+/// `engine` must be a valid pointer. `recipes` must be valid for
+/// `recipe_count` elements when `recipe_count` is non-zero.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_set_flexible_processor(
+    engine: *mut FactorialEngine,
+    node_id: FfiNodeId,
+    recipes: *const u32,
+    recipe_count: u32,
+    selection: u32,
+) -> FactorialResult {
+    if engine.is_null() || (recipes.is_null() && recipe_count > 0) {
+        return FactorialResult::NullPointer;
+    }
+    let selection = match selection {
+        0 => RecipeSelection::FirstMatch,
+        1 => RecipeSelection::LargestBatchAvailable,
+        _ => return FactorialResult::InvalidArgument,
+    };
+    let _mutation = unsafe { MutationGuard::begin(engine) };
+    match catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let engine = unsafe { &mut *engine };
+        if let Some(refused) = engine.refuse_mutation() {
+            return refused;
+        }
+        let recipes = if recipe_count == 0 {
+            Vec::new()
+        } else {
+            unsafe { std::slice::from_raw_parts(recipes, recipe_count as usize) }
+                .iter()
+                .map(|&id| RecipeId(id))
+                .collect()
+        };
+        engine.inner.set_processor(
+            ffi_to_node_id(node_id),
+            Processor::Flexible(FlexibleProcessor::new(recipes, selection)),
+        );
+        FactorialResult::Ok
+    })) {
+        Ok(result) => result,
+        Err(_) => {
+            let engine = unsafe { &mut *engine };
+            engine.poisoned = true;
+            FactorialResult::InternalError
+        }
+    }
+}
+
+/// Write the registry recipe a flexible node is crafting to `out_recipe`,
+/// or [`NO_RECIPE`] if it is not mid-cycle.
+///
+/// Returns `NodeNotFound` if the node has no processor and `KindMismatch`
+/// if its processor is not flexible.
+///
+/// # Safety
+///
+/// `engine` and `out_recipe` must be valid pointers.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_get_active_recipe(
+    engine: *const FactorialEngine,
+    node_id: FfiNodeId,
+    out_recipe: *mut u32,
+) -> FactorialResult {
+    if engine.is_null() || out_recipe.is_null() {
+        return FactorialResult::NullPointer;
+    }
+    match catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let engine = unsafe { &*engine };
+        if engine.poisoned {
+            return FactorialResult::Poisoned;
+        }
+        let node = ffi_to_node_id(node_id);
+        match engine.inner.get_processor(node) {
+            Some(Processor::Flexible(_)) => {
+                let recipe = engine.inner.get_active_recipe_id(node);
+                unsafe { *out_recipe = recipe.map_or(NO_RECIPE, |r| r.0) };
+                FactorialResult::Ok
+            }
+            Some(_) => FactorialResult::KindMismatch,
+            None => FactorialResult::NodeNotFound,
+        }
+    })) {
+        Ok(result) => result,
+        Err(_) => FactorialResult::InternalError,
+    }
+}
+
+/// Pass to [`factorial_set_storage_limit`] to make every slot usable.
+pub const NO_STORAGE_LIMIT: u32 = u32::MAX;
+
+/// Turn a node into a storage node (chest, warehouse) with `slot_count`
+/// slots of `slot_capacity` items each, all usable.
+///
+/// Storage keeps its items in the output inventory, which this call
+/// replaces: incoming transports deliver there and outgoing transports take
+/// from there. With `read_signal` set, the node publishes its contents on
+/// the logic networks it belongs to every tick. Returns `InvalidArgument` if
+/// `slot_count` is 0.
+///
+/// # Safety
+///
+/// `engine` must be a valid engine pointer.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_set_storage_processor(
+    engine: *mut FactorialEngine,
+    node_id: FfiNodeId,
+    slot_count: u32,
+    slot_capacity: u32,
+    read_signal: bool,
+) -> FactorialResult {
+    if engine.is_null() {
+        return FactorialResult::NullPointer;
+    }
+    if slot_count == 0 {
+        return FactorialResult::InvalidArgument;
+    }
+    let _mutation = unsafe { MutationGuard::begin(engine) };
+    match catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let engine = unsafe { &mut *engine };
+        if let Some(refused) = engine.refuse_mutation() {
+            return refused;
+        }
+        let nid = ffi_to_node_id(node_id);
+        engine.inner.set_processor(
+            nid,
+            Processor::Storage(StorageProcessor {
+                usable_slots: None,
+                read_signal,
+            }),
+        );
+        engine
+            .inner
+            .set_output_inventory(nid, Inventory::new(1, slot_count as usize, slot_capacity));
+        FactorialResult::Ok
+    })) {
+        Ok(result) => result,
+        Err(_) => {
+            let engine = unsafe { &mut *engine };
+            engine.poisoned = true;
+            FactorialResult::InternalError
+        }
+    }
+}
+
+/// Limit how many of a storage node's slots accept deliveries, counted from
+/// the first, or pass [`NO_STORAGE_LIMIT`] to lift the limit. Items already
+/// in slots past the limit stay there and can still be taken out.
+///
+/// Returns `NodeNotFound` if the node has no processor and `KindMismatch`
+/// if its processor is not storage.
+///
+/// # Safety
+///
+/// `engine` must be a valid engine pointer.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_set_storage_limit(
+    engine: *mut FactorialEngine,
+    node_id: FfiNodeId,
+    usable_slots: u32,
+) -> FactorialResult {
+    if engine.is_null() {
+        return FactorialResult::NullPointer;
+    }
+    let _mutation = unsafe { MutationGuard::begin(engine) };
+    match catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let engine = unsafe { &mut *engine };
+        if let Some(refused) = engine.refuse_mutation() {
+            return refused;
+        }
+        let nid = ffi_to_node_id(node_id);
+        let limit = (usable_slots != NO_STORAGE_LIMIT).then_some(usable_slots);
+        if engine.inner.set_storage_limit(nid, limit) {
+            FactorialResult::Ok
+        } else if engine.inner.get_processor(nid).is_some() {
+            FactorialResult::KindMismatch
+        } else {
+            FactorialResult::NodeNotFound
+        }
+    })) {
+        Ok(result) => result,
+        Err(_) => {
+            let engine = unsafe { &mut *engine };
+            engine.poisoned = true;
+            FactorialResult::InternalError
+        }
+    }
+}
+
+/// Replace the definition of a recipe in a running engine, for hot-reloading
+/// recipe data. Flexible nodes use the new definition the next time they
+/// select the recipe.
+///
+/// `policy` decides what happens to crafts of the recipe already in
+/// progress: 0 = finish them under the old definition, 1 = cancel them and
+/// refund their consumed ingredients to the input inventory. Returns
+/// `InvalidArgument` for any other value.
+///
+/// # Safety
+///
+/// `engine` and `recipe` must be valid pointers. The arrays referenced by
+/// `recipe.inputs` and `recipe.outputs` must be valid for the declared counts.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_update_recipe(
+    engine: *mut FactorialEngine,
+    recipe_id: u32,
+    recipe: *const FfiRecipe,
+    policy: u32,
+) -> FactorialResult {
+    if engine.is_null() || recipe.is_null() {
+        return FactorialResult::NullPointer;
+    }
+    let policy = match policy {
+        0 => InFlightPolicy::Finish,
+        1 => InFlightPolicy::Cancel,
+        _ => return FactorialResult::InvalidArgument,
+    };
+    let _mutation = unsafe { MutationGuard::begin(engine) };
+    match catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let engine = unsafe { &mut *engine };
+        if let Some(refused) = engine.refuse_mutation() {
+            return refused;
+        }
+        let recipe = unsafe { &*recipe };
+        let recipe = FixedRecipe {
+            inputs: unsafe { ffi_to_recipe_inputs(recipe.inputs, recipe.input_count) },
+            outputs: unsafe { ffi_to_recipe_outputs(recipe.outputs, recipe.output_count) },
+            duration: recipe.duration,
+            fluid_inputs: Vec::new(),
+            fluid_outputs: Vec::new(),
+            duration_per_unit: None,
+        };
+        engine
+            .inner
+            .update_recipe(RecipeId(recipe_id), recipe, policy);
+        FactorialResult::Ok
+    })) {
+        Ok(result) => result,
+        Err(_) => {
+            let engine = unsafe { &mut *engine };
+            engine.poisoned = true;
+            FactorialResult::InternalError
+        }
+    }
+}
+
+/// Deliver fluid to a node's recipe fluid buffer.
+///
+/// `amount` is raw Fixed64 bits. The buffer accepts at most one cycle's
+/// worth of the active recipe's fluid input; the accepted amount (raw
+/// bits) is written to `out_accepted`.
+///
+/// # Safety
+///
+/// `engine` and `out_accepted` must be valid pointers.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_add_fluid_input(
+    engine: *mut FactorialEngine,
+    node_id: FfiNodeId,
+    fluid: u32,
+    amount: i64,
+    out_accepted: *mut i64,
+) -> FactorialResult {
+    if engine.is_null() || out_accepted.is_null() {
+        return FactorialResult::NullPointer;
+    }
+    let _mutation = unsafe { MutationGuard::begin(engine) };
+    match catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let engine = unsafe { &mut *engine };
+        if let Some(refused) = engine.refuse_mutation() {
+            return refused;
+        }
+        let accepted = engine.inner.add_fluid_input(
+            ffi_to_node_id(node_id),
+            ItemTypeId(fluid),
+            Fixed64::from_bits(amount),
+        );
+        unsafe { *out_accepted = accepted.to_bits() };
+        FactorialResult::Ok
+    })) {
+        Ok(result) => result,
+        Err(_) => {
+            let engine = unsafe { &mut *engine };
+            engine.poisoned = true;
+            FactorialResult::InternalError
+        }
+    }
+}
+
+/// Remove up to `max` (raw Fixed64 bits) of a fluid from a node's recipe
+/// output buffer. The amount removed (raw bits) is written to `out_taken`.
+///
+/// # Safety
+///
+/// `engine` and `out_taken` must be valid pointers.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_take_fluid_output(
+    engine: *mut FactorialEngine,
+    node_id: FfiNodeId,
+    fluid: u32,
+    max: i64,
+    out_taken: *mut i64,
+) -> FactorialResult {
+    if engine.is_null() || out_taken.is_null() {
+        return FactorialResult::NullPointer;
+    }
+    let _mutation = unsafe { MutationGuard::begin(engine) };
+    match catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let engine = unsafe { &mut *engine };
+        if let Some(refused) = engine.refuse_mutation() {
+            return refused;
+        }
+        let taken = engine.inner.take_fluid_output(
+            ffi_to_node_id(node_id),
+            ItemTypeId(fluid),
+            Fixed64::from_bits(max),
+        );
+        unsafe { *out_taken = taken.to_bits() };
+        FactorialResult::Ok
+    })) {
+        Ok(result) => result,
+        Err(_) => {
+            let engine = unsafe { &mut *engine };
+            engine.poisoned = true;
+            FactorialResult::InternalError
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Configuration: Transports
+// ---------------------------------------------------------------------------
+
+/// Set an edge's transport to FlowTransport with default buffer/latency.
+///
+/// `rate` is raw Fixed64 bits (Q32.32).
+///
+/// # Safety
+///
+/// `engine` must be a valid engine pointer.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_set_flow_transport(
+    engine: *mut FactorialEngine,
+    edge_id: FfiEdgeId,
+    rate: i64,
+) -> FactorialResult {
+    if engine.is_null() {
+        return FactorialResult::NullPointer;
+    }
+    let _mutation = unsafe { MutationGuard::begin(engine) };
+    match catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let engine = unsafe { &mut *engine };
+        if let Some(refused) = engine.refuse_mutation() {
+            return refused;
+        }
+        let eid = ffi_to_edge_id(edge_id);
+        let transport = Transport::Flow(FlowTransport {
+            rate: Fixed64::from_bits(rate),
+            buffer_capacity: Fixed64::from_num(1000),
+            latency: 0,
+        });
+        engine.inner.set_transport(eid, transport);
+        FactorialResult::Ok
+    })) {
+        Ok(result) => result,
+        Err(_) => {
+            let engine = unsafe { &mut *engine };
+            engine.poisoned = true;
+            FactorialResult::InternalError
+        }
+    }
+}
+
+/// Set an edge's transport to ItemTransport.
+///
+/// `speed` is raw Fixed64 bits (Q32.32).
+///
+/// # Safety
+///
+/// `engine` must be a valid engine pointer.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_set_item_transport(
+    engine: *mut FactorialEngine,
+    edge_id: FfiEdgeId,
+    speed: i64,
+    slot_count: u32,
+    lanes: u8,
+) -> FactorialResult {
+    if engine.is_null() {
+        return FactorialResult::NullPointer;
+    }
+    let _mutation = unsafe { MutationGuard::begin(engine) };
+    match catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let engine = unsafe { &mut *engine };
+        if let Some(refused) = engine.refuse_mutation() {
+            return refused;
+        }
+        let eid = ffi_to_edge_id(edge_id);
+        let transport = Transport::Item(ItemTransport {
+            speed: Fixed64::from_bits(speed),
+            slot_count,
+            lanes,
+            min_spacing: Fixed64::ONE,
+        });
+        engine.inner.set_transport(eid, transport);
+        FactorialResult::Ok
+    })) {
+        Ok(result) => result,
+        Err(_) => {
+            let engine = unsafe { &mut *engine };
+            engine.poisoned = true;
+            FactorialResult::InternalError
+        }
+    }
+}
+
+/// Set an edge's transport to BatchTransport.
+///
+/// # Safety
+///
+/// `engine` must be a valid engine pointer.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_set_batch_transport(
+    engine: *mut FactorialEngine,
+    edge_id: FfiEdgeId,
+    batch_size: u32,
+    cycle_time: u32,
+) -> FactorialResult {
+    if engine.is_null() {
+        return FactorialResult::NullPointer;
+    }
+    let _mutation = unsafe { MutationGuard::begin(engine) };
+    match catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let engine = unsafe { &mut *engine };
+        if let Some(refused) = engine.refuse_mutation() {
+            return refused;
+        }
+        let eid = ffi_to_edge_id(edge_id);
+        let transport = Transport::Batch(BatchTransport {
+            batch_size,
+            cycle_time,
+        });
+        engine.inner.set_transport(eid, transport);
+        FactorialResult::Ok
+    })) {
+        Ok(result) => result,
+        Err(_) => {
+            let engine = unsafe { &mut *engine };
+            engine.poisoned = true;
+            FactorialResult::InternalError
+        }
+    }
+}
+
+/// Set an edge's transport to VehicleTransport.
+///
+/// # Safety
+///
+/// `engine` must be a valid engine pointer.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_set_vehicle_transport(
+    engine: *mut FactorialEngine,
+    edge_id: FfiEdgeId,
+    capacity: u32,
+    travel_time: u32,
+) -> FactorialResult {
+    if engine.is_null() {
+        return FactorialResult::NullPointer;
+    }
+    let _mutation = unsafe { MutationGuard::begin(engine) };
+    match catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let engine = unsafe { &mut *engine };
+        if let Some(refused) = engine.refuse_mutation() {
+            return refused;
+        }
+        let eid = ffi_to_edge_id(edge_id);
+        let transport = Transport::Vehicle(VehicleTransport {
+            capacity,
+            travel_time,
+        });
+        engine.inner.set_transport(eid, transport);
+        FactorialResult::Ok
+    })) {
+        Ok(result) => result,
+        Err(_) => {
+            let engine = unsafe { &mut *engine };
+            engine.poisoned = true;
+            FactorialResult::InternalError
+        }
+    }
+}
+
+/// Shared body of the transport getters: look up the edge's transport and
+/// let `read` copy it out. `read` returns `false` on a kind mismatch.
+///
+/// # Safety
+///
+/// `engine` must be a valid, non-null engine pointer.
+unsafe fn read_transport(
+    engine: *const FactorialEngine,
+    edge_id: FfiEdgeId,
+    read: impl FnOnce(&Transport) -> bool,
+) -> FactorialResult {
+    match catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let engine = unsafe { &*engine };
+        if engine.poisoned {
+            return FactorialResult::Poisoned;
+        }
+        match engine.inner.get_transport(ffi_to_edge_id(edge_id)) {
+            Some(transport) if read(transport) => FactorialResult::Ok,
+            Some(_) => FactorialResult::KindMismatch,
+            None => FactorialResult::EdgeNotFound,
+        }
+    })) {
+        Ok(result) => result,
+        Err(_) => FactorialResult::InternalError,
+    }
+}
+
+/// Read back a FlowTransport's configuration. `out_rate` and
+/// `out_capacity` (the buffer capacity) receive raw Fixed64 bits.
+///
+/// Returns `EdgeNotFound` if the edge has no transport and `KindMismatch`
+/// if it is not a FlowTransport.
+///
+/// # Safety
+///
+/// `engine` and all out pointers must be valid.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_get_flow_transport(
+    engine: *const FactorialEngine,
+    edge_id: FfiEdgeId,
+    out_rate: *mut i64,
+    out_capacity: *mut i64,
+    out_latency: *mut u32,
+) -> FactorialResult {
+    if engine.is_null() || out_rate.is_null() || out_capacity.is_null() || out_latency.is_null() {
+        return FactorialResult::NullPointer;
+    }
+    unsafe {
+        read_transport(engine, edge_id, |transport| {
+            let Transport::Flow(flow) = transport else {
+                return false;
+            };
+            *out_rate = flow.rate.to_bits();
+            *out_capacity = flow.buffer_capacity.to_bits();
+            *out_latency = flow.latency;
+            true
+        })
+    }
+}
+
+/// Read back an ItemTransport's configuration. `out_speed` receives raw
+/// Fixed64 bits.
+///
+/// Returns `EdgeNotFound` if the edge has no transport and `KindMismatch`
+/// if it is not an ItemTransport.
+///
+/// # Safety
+///
+/// `engine` and all out pointers must be valid.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_get_item_transport(
+    engine: *const FactorialEngine,
+    edge_id: FfiEdgeId,
+    out_speed: *mut i64,
+    out_slot_count: *mut u32,
+    out_lanes: *mut u8,
+) -> FactorialResult {
+    if engine.is_null() || out_speed.is_null() || out_slot_count.is_null() || out_lanes.is_null() {
+        return FactorialResult::NullPointer;
+    }
+    unsafe {
+        read_transport(engine, edge_id, |transport| {
+            let Transport::Item(belt) = transport else {
+                return false;
+            };
+            *out_speed = belt.speed.to_bits();
+            *out_slot_count = belt.slot_count;
+            *out_lanes = belt.lanes;
+            true
+        })
+    }
+}
+
+/// Read back a BatchTransport's configuration.
+///
+/// Returns `EdgeNotFound` if the edge has no transport and `KindMismatch`
+/// if it is not a BatchTransport.
+///
+/// # Safety
+///
+/// `engine` and all out pointers must be valid.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_get_batch_transport(
+    engine: *const FactorialEngine,
+    edge_id: FfiEdgeId,
+    out_batch_size: *mut u32,
+    out_cycle_time: *mut u32,
+) -> FactorialResult {
+    if engine.is_null() || out_batch_size.is_null() || out_cycle_time.is_null() {
+        return FactorialResult::NullPointer;
+    }
+    unsafe {
+        read_transport(engine, edge_id, |transport| {
+            let Transport::Batch(batch) = transport else {
+                return false;
+            };
+            *out_batch_size = batch.batch_size;
+            *out_cycle_time = batch.cycle_time;
+            true
+        })
+    }
+}
+
+/// Read back a VehicleTransport's configuration.
+///
+/// Returns `EdgeNotFound` if the edge has no transport and `KindMismatch`
+/// if it is not a VehicleTransport.
+///
+/// # Safety
+///
+/// `engine` and all out pointers must be valid.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_get_vehicle_transport(
+    engine: *const FactorialEngine,
+    edge_id: FfiEdgeId,
+    out_capacity: *mut u32,
+    out_travel_time: *mut u32,
+) -> FactorialResult {
+    if engine.is_null() || out_capacity.is_null() || out_travel_time.is_null() {
+        return FactorialResult::NullPointer;
+    }
+    unsafe {
+        read_transport(engine, edge_id, |transport| {
+            let Transport::Vehicle(vehicle) = transport else {
+                return false;
+            };
+            *out_capacity = vehicle.capacity;
+            *out_travel_time = vehicle.travel_time;
+            true
+        })
+    }
+}
+
+/// Apply one transport configuration to `count` edges in a single call.
+///
+/// Each edge gets its own fresh transport state, exactly as if the matching
+/// single-edge setter had been called for it.
+///
+/// # Safety
+///
+/// `engine` and `config` must be valid pointers. `edges` must point to
+/// `count` edge ids (it may be null when `count` is 0).
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_set_transport_bulk(
+    engine: *mut FactorialEngine,
+    edges: *const FfiEdgeId,
+    count: u32,
+    config: *const FfiTransportConfig,
+) -> FactorialResult {
+    if engine.is_null() || config.is_null() || (edges.is_null() && count > 0) {
+        return FactorialResult::NullPointer;
+    }
+    let _mutation = unsafe { MutationGuard::begin(engine) };
+    match catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let engine = unsafe { &mut *engine };
+        if let Some(refused) = engine.refuse_mutation() {
+            return refused;
+        }
+        if count == 0 {
+            return FactorialResult::Ok;
+        }
+        let transport = ffi_to_transport(unsafe { &*config });
+        let ids: Vec<EdgeId> = unsafe { std::slice::from_raw_parts(edges, count as usize) }
+            .iter()
+            .map(|&edge| ffi_to_edge_id(edge))
+            .collect();
+        engine.inner.set_transport_bulk(&ids, transport);
+        FactorialResult::Ok
+    })) {
+        Ok(result) => result,
+        Err(_) => {
+            let engine = unsafe { &mut *engine };
+            engine.poisoned = true;
+            FactorialResult::InternalError
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Edge groups
+// ---------------------------------------------------------------------------
+
+fn edge_group_id_to_ffi(id: EdgeGroupId) -> FfiEdgeGroupId {
+    id.data().as_ffi()
+}
+
+fn ffi_to_edge_group_id(ffi: FfiEdgeGroupId) -> EdgeGroupId {
+    KeyData::from_ffi(ffi).into()
+}
+
+/// Group `count` edges into one logical transport line and write its id to
+/// `*out_group`. Edges not in the graph and repeated edges are skipped; an
+/// edge already in another group moves to the new one.
+///
+/// # Safety
+///
+/// `engine` and `out_group` must be valid pointers. `edges` must point to
+/// `count` edge ids (it may be null when `count` is 0).
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_create_edge_group(
+    engine: *mut FactorialEngine,
+    edges: *const FfiEdgeId,
+    count: u32,
+    out_group: *mut FfiEdgeGroupId,
+) -> FactorialResult {
+    if engine.is_null() || out_group.is_null() || (edges.is_null() && count > 0) {
+        return FactorialResult::NullPointer;
+    }
+    let _mutation = unsafe { MutationGuard::begin(engine) };
+    match catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let engine = unsafe { &mut *engine };
+        if let Some(refused) = engine.refuse_mutation() {
+            return refused;
+        }
+        let ids: Vec<EdgeId> = if count == 0 {
+            Vec::new()
+        } else {
+            unsafe { std::slice::from_raw_parts(edges, count as usize) }
+                .iter()
+                .map(|&edge| ffi_to_edge_id(edge))
+                .collect()
+        };
+        let group = engine.inner.create_edge_group(&ids);
+        unsafe { *out_group = edge_group_id_to_ffi(group) };
+        FactorialResult::Ok
+    })) {
+        Ok(result) => result,
+        Err(_) => {
+            let engine = unsafe { &mut *engine };
+            engine.poisoned = true;
+            FactorialResult::InternalError
+        }
+    }
+}
+
+/// Dissolve an edge group. Its edges are untouched.
+///
+/// Returns `EdgeGroupNotFound` if the group does not exist.
+///
+/// # Safety
+///
+/// `engine` must be a valid engine pointer.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_remove_edge_group(
+    engine: *mut FactorialEngine,
+    group: FfiEdgeGroupId,
+) -> FactorialResult {
+    if engine.is_null() {
+        return FactorialResult::NullPointer;
+    }
+    let _mutation = unsafe { MutationGuard::begin(engine) };
+    match catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let engine = unsafe { &mut *engine };
+        if let Some(refused) = engine.refuse_mutation() {
+            return refused;
+        }
+        if engine.inner.remove_edge_group(ffi_to_edge_group_id(group)) {
+            FactorialResult::Ok
+        } else {
+            FactorialResult::EdgeGroupNotFound
+        }
+    })) {
+        Ok(result) => result,
+        Err(_) => {
+            let engine = unsafe { &mut *engine };
+            engine.poisoned = true;
+            FactorialResult::InternalError
+        }
+    }
+}
+
+/// Copy the edges of a group, in the order they were grouped, into
+/// `out_edges`.
+///
+/// Writes the number of edges to `*out_written`. If `len` is smaller than
+/// that, nothing else is written and `BufferTooSmall` is returned. Returns
+/// `EdgeGroupNotFound` if the group does not exist.
+///
+/// # Safety
+///
+/// `engine` and `out_written` must be valid pointers. `out_edges` must be
+/// valid for `len` elements (it may be null when `len` is 0).
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_get_group_edges(
+    engine: *const FactorialEngine,
+    group: FfiEdgeGroupId,
+    out_edges: *mut FfiEdgeId,
+    len: u32,
+    out_written: *mut u32,
+) -> FactorialResult {
+    if engine.is_null() || out_written.is_null() || (out_edges.is_null() && len > 0) {
+        return FactorialResult::NullPointer;
+    }
+    match catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let engine = unsafe { &*engine };
+        if engine.poisoned {
+            return FactorialResult::Poisoned;
+        }
+        let group = ffi_to_edge_group_id(group);
+        if !engine.inner.contains_edge_group(group) {
+            return FactorialResult::EdgeGroupNotFound;
+        }
+        let edges = engine.inner.group_edges(group);
+        unsafe { *out_written = edges.len() as u32 };
+        if edges.is_empty() {
+            return FactorialResult::Ok;
+        }
+        if (len as usize) < edges.len() {
+            return FactorialResult::BufferTooSmall;
+        }
+        let out = unsafe { std::slice::from_raw_parts_mut(out_edges, edges.len()) };
+        for (slot, &edge) in out.iter_mut().zip(edges) {
+            *slot = edge_id_to_ffi(edge);
+        }
+        FactorialResult::Ok
+    })) {
+        Ok(result) => result,
+        Err(_) => FactorialResult::InternalError,
+    }
+}
+
+/// Apply one transport configuration to every edge of a group. Each edge
+/// keeps the items in transit when its state fits the new transport (same
+/// kind and, for belts, the same slot layout) and starts empty otherwise.
+///
+/// Returns `EdgeGroupNotFound` if the group does not exist.
+///
+/// # Safety
+///
+/// `engine` and `config` must be valid pointers.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_set_group_transport(
+    engine: *mut FactorialEngine,
+    group: FfiEdgeGroupId,
+    config: *const FfiTransportConfig,
+) -> FactorialResult {
+    if engine.is_null() || config.is_null() {
+        return FactorialResult::NullPointer;
+    }
+    let _mutation = unsafe { MutationGuard::begin(engine) };
+    match catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let engine = unsafe { &mut *engine };
+        if let Some(refused) = engine.refuse_mutation() {
+            return refused;
+        }
+        let group = ffi_to_edge_group_id(group);
+        if !engine.inner.contains_edge_group(group) {
+            return FactorialResult::EdgeGroupNotFound;
+        }
+        let transport = ffi_to_transport(unsafe { &*config });
+        engine.inner.set_group_transport(group, transport);
+        FactorialResult::Ok
+    })) {
+        Ok(result) => result,
+        Err(_) => {
+            let engine = unsafe { &mut *engine };
+            engine.poisoned = true;
+            FactorialResult::InternalError
+        }
+    }
+}
+
+/// Write whether any edge of a group is at capacity to `*out_congested`.
+///
+/// Returns `EdgeGroupNotFound` if the group does not exist.
+///
+/// # Safety
+///
+/// `engine` and `out_congested` must be valid pointers.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_group_is_congested(
+    engine: *const FactorialEngine,
+    group: FfiEdgeGroupId,
+    out_congested: *mut bool,
+) -> FactorialResult {
+    if engine.is_null() || out_congested.is_null() {
+        return FactorialResult::NullPointer;
+    }
+    match catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let engine = unsafe { &*engine };
+        if engine.poisoned {
+            return FactorialResult::Poisoned;
+        }
+        let group = ffi_to_edge_group_id(group);
+        if !engine.inner.contains_edge_group(group) {
+            return FactorialResult::EdgeGroupNotFound;
+        }
+        unsafe { *out_congested = engine.inner.group_is_congested(group) };
+        FactorialResult::Ok
+    })) {
+        Ok(result) => result,
+        Err(_) => FactorialResult::InternalError,
+    }
+}
+
+/// Serialize the internal state of one edge's transport (flow buffer, belt
+/// slots, batch progress or vehicle cargo) into a byte buffer. The caller
+/// must free the buffer with `factorial_free_buffer`.
+///
+/// Returns `EdgeNotFound` if the edge has no transport.
+///
+/// # Safety
+///
+/// `engine` and `out_buffer` must be valid pointers.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_get_transport_state(
+    engine: *const FactorialEngine,
+    edge_id: FfiEdgeId,
+    out_buffer: *mut FfiByteBuffer,
+) -> FactorialResult {
+    if engine.is_null() || out_buffer.is_null() {
+        return FactorialResult::NullPointer;
+    }
+    match catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let engine = unsafe { &*engine };
+        if engine.poisoned {
+            return FactorialResult::Poisoned;
+        }
+        unsafe {
+            *out_buffer = FfiByteBuffer {
+                data: ptr::null_mut(),
+                len: 0,
+            };
+        }
+        match engine
+            .inner
+            .serialize_transport_state(ffi_to_edge_id(edge_id))
+        {
+            Ok(Some(data)) => {
+                unsafe { *out_buffer = byte_buffer_from_vec(data) };
+                FactorialResult::Ok
+            }
+            Ok(None) => FactorialResult::EdgeNotFound,
+            Err(_) => FactorialResult::SerializeError,
+        }
+    })) {
+        Ok(result) => result,
+        Err(_) => FactorialResult::InternalError,
+    }
+}
+
+/// Restore one edge's transport state from a buffer produced by
+/// `factorial_get_transport_state`.
+///
+/// Returns `EdgeNotFound` if the edge has no transport, and
+/// `DeserializeError` if the data is malformed or does not match the edge's
+/// current transport configuration. The engine is unchanged on error.
+///
+/// # Safety
+///
+/// `engine` must be a valid pointer. `data` must point to `len` valid bytes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_set_transport_state(
+    engine: *mut FactorialEngine,
+    edge_id: FfiEdgeId,
+    data: *const u8,
+    len: usize,
+) -> FactorialResult {
+    if engine.is_null() || data.is_null() {
+        return FactorialResult::NullPointer;
+    }
+    let _mutation = unsafe { MutationGuard::begin(engine) };
+    match catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let engine = unsafe { &mut *engine };
+        if let Some(refused) = engine.refuse_mutation() {
+            return refused;
+        }
+        let slice = unsafe { std::slice::from_raw_parts(data, len) };
+        match engine
+            .inner
+            .deserialize_transport_state(ffi_to_edge_id(edge_id), slice)
+        {
+            Ok(()) => FactorialResult::Ok,
+            Err(DeserializeError::NoTransport) => FactorialResult::EdgeNotFound,
+            Err(_) => FactorialResult::DeserializeError,
+        }
+    })) {
+        Ok(result) => result,
+        Err(_) => {
+            let engine = unsafe { &mut *engine };
+            engine.poisoned = true;
+            FactorialResult::InternalError
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Configuration: Inventories
+// ---------------------------------------------------------------------------
+
+/// Set the input inventory for a node with the given capacity.
+///
+/// Creates an inventory with 1 input slot and 1 output slot.
+///
+/// # Safety
+///
+/// `engine` must be a valid engine pointer.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_set_input_capacity(
+    engine: *mut FactorialEngine,
+    node_id: FfiNodeId,
+    capacity: u32,
+) -> FactorialResult {
+    if engine.is_null() {
+        return FactorialResult::NullPointer;
+    }
+    let _mutation = unsafe { MutationGuard::begin(engine) };
+    match catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let engine = unsafe { &mut *engine };
+        if let Some(refused) = engine.refuse_mutation() {
+            return refused;
+        }
+        let nid = ffi_to_node_id(node_id);
+        let inventory = Inventory::new(1, 1, capacity);
+        engine.inner.set_input_inventory(nid, inventory);
+        FactorialResult::Ok
+    })) {
+        Ok(result) => result,
+        Err(_) => {
+            let engine = unsafe { &mut *engine };
+            engine.poisoned = true;
+            FactorialResult::InternalError
+        }
+    }
+}
+
+/// Set the output inventory for a node with the given capacity.
+///
+/// Creates an inventory with 1 input slot and 1 output slot.
+///
+/// # Safety
+///
+/// `engine` must be a valid engine pointer.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_set_output_capacity(
+    engine: *mut FactorialEngine,
+    node_id: FfiNodeId,
+    capacity: u32,
+) -> FactorialResult {
+    if engine.is_null() {
+        return FactorialResult::NullPointer;
+    }
+    let _mutation = unsafe { MutationGuard::begin(engine) };
+    match catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let engine = unsafe { &mut *engine };
+        if let Some(refused) = engine.refuse_mutation() {
+            return refused;
+        }
+        let nid = ffi_to_node_id(node_id);
+        let inventory = Inventory::new(1, 1, capacity);
+        engine.inner.set_output_inventory(nid, inventory);
+        FactorialResult::Ok
+    })) {
+        Ok(result) => result,
+        Err(_) => {
+            let engine = unsafe { &mut *engine };
+            engine.poisoned = true;
+            FactorialResult::InternalError
+        }
+    }
+}
+
+/// Read back the shape of one of a node's inventories. `which` is 0 for
+/// the input inventory and 1 for the output inventory. `out_capacity` is the
+/// capacity of the first slot, which every slot shares when the inventory
+/// was set through `factorial_set_input_capacity` or
+/// `factorial_set_output_capacity`; it is 0 if the inventory has no slots.
+///
+/// Returns `InvalidArgument` for any other `which`, and `NodeNotFound` if
+/// the node has no such inventory.
+///
+/// # Safety
+///
+/// `engine` and all out pointers must be valid.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_get_inventory_config(
+    engine: *const FactorialEngine,
+    node_id: FfiNodeId,
+    which: u32,
+    out_slots_in: *mut u32,
+    out_slots_out: *mut u32,
+    out_capacity: *mut u32,
+) -> FactorialResult {
+    if engine.is_null()
+        || out_slots_in.is_null()
+        || out_slots_out.is_null()
+        || out_capacity.is_null()
+    {
+        return FactorialResult::NullPointer;
+    }
+    match catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let engine = unsafe { &*engine };
+        if engine.poisoned {
+            return FactorialResult::Poisoned;
+        }
+        let nid = ffi_to_node_id(node_id);
+        let inventory = match which {
+            0 => engine.inner.get_input_inventory(nid),
+            1 => engine.inner.get_output_inventory(nid),
+            _ => return FactorialResult::InvalidArgument,
+        };
+        let Some(inventory) = inventory else {
+            return FactorialResult::NodeNotFound;
+        };
+        let capacity = inventory
+            .input_slots
+            .iter()
+            .chain(&inventory.output_slots)
+            .next()
+            .map_or(0, |slot| slot.capacity);
+        unsafe {
+            *out_slots_in = inventory.input_slots.len() as u32;
+            *out_slots_out = inventory.output_slots.len() as u32;
+            *out_capacity = capacity;
+        }
+        FactorialResult::Ok
+    })) {
+        Ok(result) => result,
+        Err(_) => FactorialResult::InternalError,
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Configuration: Ghost status
+// ---------------------------------------------------------------------------
+
+/// C-compatible node/edge simulation status.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FfiNodeStatus {
+    /// Planned; stays in the graph but does not simulate.
+    Ghost = 0,
+    /// Built and simulating normally.
+    Active = 1,
+}
+
+fn ffi_to_node_status(status: FfiNodeStatus) -> NodeStatus {
+    match status {
+        FfiNodeStatus::Ghost => NodeStatus::Ghost,
+        FfiNodeStatus::Active => NodeStatus::Active,
+    }
+}
+
+fn node_status_to_ffi(status: NodeStatus) -> FfiNodeStatus {
+    match status {
+        NodeStatus::Ghost => FfiNodeStatus::Ghost,
+        NodeStatus::Active => FfiNodeStatus::Active,
+    }
+}
+
+/// Set a node's simulation status. A ghost node keeps its configuration and
+/// stays visible to queries, but its processor does not run and no transport
+/// moves items into or out of it.
+///
+/// Returns `NodeNotFound` if the node does not exist.
+///
+/// # Safety
+///
+/// `engine` must be a valid engine pointer.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_set_node_status(
+    engine: *mut FactorialEngine,
+    node_id: FfiNodeId,
+    status: FfiNodeStatus,
+) -> FactorialResult {
+    if engine.is_null() {
+        return FactorialResult::NullPointer;
+    }
+    let _mutation = unsafe { MutationGuard::begin(engine) };
+    match catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let engine = unsafe { &mut *engine };
+        if let Some(refused) = engine.refuse_mutation() {
+            return refused;
+        }
+        let nid = ffi_to_node_id(node_id);
+        if engine
+            .inner
+            .set_node_status(nid, ffi_to_node_status(status))
+        {
+            FactorialResult::Ok
+        } else {
+            FactorialResult::NodeNotFound
+        }
+    })) {
+        Ok(result) => result,
+        Err(_) => {
+            let engine = unsafe { &mut *engine };
+            engine.poisoned = true;
+            FactorialResult::InternalError
+        }
+    }
+}
+
+/// Write a node's simulation status to `out_status`.
+///
+/// Returns `NodeNotFound` if the node does not exist.
+///
+/// # Safety
+///
+/// `engine` and `out_status` must be valid pointers.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_get_node_status(
+    engine: *const FactorialEngine,
+    node_id: FfiNodeId,
+    out_status: *mut FfiNodeStatus,
+) -> FactorialResult {
+    if engine.is_null() || out_status.is_null() {
+        return FactorialResult::NullPointer;
+    }
+    let Some(_read) = (unsafe { ReadGuard::begin(engine) }) else {
+        return FactorialResult::ConcurrentMutation;
+    };
+    match catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let engine = unsafe { &*engine };
+        if engine.poisoned {
+            return FactorialResult::Poisoned;
+        }
+        match engine.inner.node_status(ffi_to_node_id(node_id)) {
+            Some(status) => {
+                unsafe { *out_status = node_status_to_ffi(status) };
+                FactorialResult::Ok
+            }
+            None => FactorialResult::NodeNotFound,
+        }
+    })) {
+        Ok(result) => result,
+        Err(_) => FactorialResult::InternalError,
+    }
+}
+
+/// Attach an opaque host value to a node, replacing any previous value. The
+/// engine never interprets it; it is saved with the node and dropped when the
+/// node is removed.
+///
+/// Returns `NodeNotFound` if the node does not exist.
+///
+/// # Safety
+///
+/// `engine` must be a valid engine pointer.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_set_node_user_data(
+    engine: *mut FactorialEngine,
+    node_id: FfiNodeId,
+    data: u64,
+) -> FactorialResult {
+    if engine.is_null() {
+        return FactorialResult::NullPointer;
+    }
+    let _mutation = unsafe { MutationGuard::begin(engine) };
+    match catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let engine = unsafe { &mut *engine };
+        if let Some(refused) = engine.refuse_mutation() {
+            return refused;
+        }
+        if engine
+            .inner
+            .set_node_user_data(ffi_to_node_id(node_id), data)
+        {
+            FactorialResult::Ok
+        } else {
+            FactorialResult::NodeNotFound
+        }
+    })) {
+        Ok(result) => result,
+        Err(_) => {
+            let engine = unsafe { &mut *engine };
+            engine.poisoned = true;
+            FactorialResult::InternalError
+        }
+    }
+}
+
+/// Write a node's host value to `out_data`.
+///
+/// Returns `NodeNotFound` if the node does not exist or has no value set.
+///
+/// # Safety
+///
+/// `engine` and `out_data` must be valid pointers.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_get_node_user_data(
+    engine: *const FactorialEngine,
+    node_id: FfiNodeId,
+    out_data: *mut u64,
+) -> FactorialResult {
+    if engine.is_null() || out_data.is_null() {
+        return FactorialResult::NullPointer;
+    }
+    let Some(_read) = (unsafe { ReadGuard::begin(engine) }) else {
+        return FactorialResult::ConcurrentMutation;
+    };
+    match catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let engine = unsafe { &*engine };
+        if engine.poisoned {
+            return FactorialResult::Poisoned;
+        }
+        match engine.inner.get_node_user_data(ffi_to_node_id(node_id)) {
+            Some(data) => {
+                unsafe { *out_data = data };
+                FactorialResult::Ok
+            }
+            None => FactorialResult::NodeNotFound,
+        }
+    })) {
+        Ok(result) => result,
+        Err(_) => FactorialResult::InternalError,
+    }
+}
+
+/// Remove a node's host value, if any.
+///
+/// Returns `NodeNotFound` if the node does not exist.
+///
+/// # Safety
+///
+/// `engine` must be a valid engine pointer.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_clear_node_user_data(
+    engine: *mut FactorialEngine,
+    node_id: FfiNodeId,
+) -> FactorialResult {
+    if engine.is_null() {
+        return FactorialResult::NullPointer;
+    }
+    let _mutation = unsafe { MutationGuard::begin(engine) };
+    match catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let engine = unsafe { &mut *engine };
+        if let Some(refused) = engine.refuse_mutation() {
+            return refused;
+        }
+        let nid = ffi_to_node_id(node_id);
+        if engine.inner.graph.contains_node(nid) {
+            engine.inner.clear_node_user_data(nid);
+            FactorialResult::Ok
+        } else {
+            FactorialResult::NodeNotFound
+        }
+    })) {
+        Ok(result) => result,
+        Err(_) => {
+            let engine = unsafe { &mut *engine };
+            engine.poisoned = true;
+            FactorialResult::InternalError
+        }
+    }
+}
+
+/// Set an edge's simulation status. A ghost edge keeps its transport and
+/// state but does not advance.
+///
+/// Returns `EdgeNotFound` if the edge does not exist.
+///
+/// # Safety
+///
+/// `engine` must be a valid engine pointer.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_set_edge_status(
+    engine: *mut FactorialEngine,
+    edge_id: FfiEdgeId,
+    status: FfiNodeStatus,
+) -> FactorialResult {
+    if engine.is_null() {
+        return FactorialResult::NullPointer;
+    }
+    let _mutation = unsafe { MutationGuard::begin(engine) };
+    match catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let engine = unsafe { &mut *engine };
+        if let Some(refused) = engine.refuse_mutation() {
+            return refused;
+        }
+        let eid = ffi_to_edge_id(edge_id);
+        if engine
+            .inner
+            .set_edge_status(eid, ffi_to_node_status(status))
+        {
+            FactorialResult::Ok
+        } else {
+            FactorialResult::EdgeNotFound
+        }
+    })) {
+        Ok(result) => result,
+        Err(_) => {
+            let engine = unsafe { &mut *engine };
+            engine.poisoned = true;
+            FactorialResult::InternalError
+        }
+    }
+}
+
+/// Write an edge's own simulation status to `out_status`.
+///
+/// Returns `EdgeNotFound` if the edge does not exist.
+///
+/// # Safety
+///
+/// `engine` and `out_status` must be valid pointers.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_get_edge_status(
+    engine: *const FactorialEngine,
+    edge_id: FfiEdgeId,
+    out_status: *mut FfiNodeStatus,
+) -> FactorialResult {
+    if engine.is_null() || out_status.is_null() {
+        return FactorialResult::NullPointer;
+    }
+    let Some(_read) = (unsafe { ReadGuard::begin(engine) }) else {
+        return FactorialResult::ConcurrentMutation;
+    };
+    match catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let engine = unsafe { &*engine };
+        if engine.poisoned {
+            return FactorialResult::Poisoned;
+        }
+        match engine.inner.edge_status(ffi_to_edge_id(edge_id)) {
+            Some(status) => {
+                unsafe { *out_status = node_status_to_ffi(status) };
+                FactorialResult::Ok
+            }
+            None => FactorialResult::EdgeNotFound,
+        }
+    })) {
+        Ok(result) => result,
+        Err(_) => FactorialResult::InternalError,
+    }
+}
+
+/// Turn a ghost node into a real one. It keeps its id, processor,
+/// inventories and edges, and starts simulating on the next step.
+///
+/// Returns `NodeNotFound` if the node does not exist or is not a ghost.
+///
+/// # Safety
+///
+/// `engine` must be a valid engine pointer.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_materialize_ghost(
+    engine: *mut FactorialEngine,
+    node_id: FfiNodeId,
+) -> FactorialResult {
+    if engine.is_null() {
+        return FactorialResult::NullPointer;
+    }
+    let _mutation = unsafe { MutationGuard::begin(engine) };
+    match catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let engine = unsafe { &mut *engine };
+        if let Some(refused) = engine.refuse_mutation() {
+            return refused;
+        }
+        if engine.inner.materialize_ghost(ffi_to_node_id(node_id)) {
+            FactorialResult::Ok
+        } else {
+            FactorialResult::NodeNotFound
+        }
+    })) {
+        Ok(result) => result,
+        Err(_) => {
+            let engine = unsafe { &mut *engine };
+            engine.poisoned = true;
+            FactorialResult::InternalError
+        }
+    }
+}
+
+/// Write the number of ghost nodes to `out_count`.
+///
+/// # Safety
+///
+/// `engine` and `out_count` must be valid pointers.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_ghost_count(
+    engine: *const FactorialEngine,
+    out_count: *mut u32,
+) -> FactorialResult {
+    if engine.is_null() || out_count.is_null() {
+        return FactorialResult::NullPointer;
+    }
+    let Some(_read) = (unsafe { ReadGuard::begin(engine) }) else {
+        return FactorialResult::ConcurrentMutation;
+    };
+    match catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let engine = unsafe { &*engine };
+        if engine.poisoned {
+            return FactorialResult::Poisoned;
+        }
+        unsafe { *out_count = engine.inner.ghost_count() as u32 };
+        FactorialResult::Ok
+    })) {
+        Ok(result) => result,
+        Err(_) => FactorialResult::InternalError,
+    }
+}
+
+/// Choose whether ghost nodes and edges are folded into the state hash.
+/// Off by default, so planned construction does not change the hash; turn
+/// it on for desync checks that should cover plans too.
+///
+/// # Safety
+///
+/// `engine` must be a valid engine pointer.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_set_hash_ghosts(
+    engine: *mut FactorialEngine,
+    enabled: bool,
+) -> FactorialResult {
+    if engine.is_null() {
+        return FactorialResult::NullPointer;
+    }
+    let _mutation = unsafe { MutationGuard::begin(engine) };
+    match catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let engine = unsafe { &mut *engine };
+        if let Some(refused) = engine.refuse_mutation() {
+            return refused;
+        }
+        engine.inner.set_hash_ghosts(enabled);
+        FactorialResult::Ok
+    })) {
+        Ok(result) => result,
+        Err(_) => {
+            let engine = unsafe { &mut *engine };
+            engine.poisoned = true;
+            FactorialResult::InternalError
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Configuration: Clearing
+// ---------------------------------------------------------------------------
+
+/// Remove a node's processor, turning it into a passive node that consumes
+/// and produces nothing. The node keeps its inventories and edges.
+///
+/// Returns `NodeNotFound` if the node does not exist. Clearing a node that
+/// has no processor is a no-op that returns `Ok`.
+///
+/// # Safety
+///
+/// `engine` must be a valid engine pointer.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_clear_processor(
+    engine: *mut FactorialEngine,
+    node_id: FfiNodeId,
+) -> FactorialResult {
+    if engine.is_null() {
+        return FactorialResult::NullPointer;
+    }
+    let _mutation = unsafe { MutationGuard::begin(engine) };
+    match catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let engine = unsafe { &mut *engine };
+        if let Some(refused) = engine.refuse_mutation() {
+            return refused;
+        }
+        let nid = ffi_to_node_id(node_id);
+        if !engine.inner.graph.contains_node(nid) {
+            return FactorialResult::NodeNotFound;
+        }
+        engine.inner.clear_processor(nid);
+        FactorialResult::Ok
+    })) {
+        Ok(result) => result,
+        Err(_) => {
+            let engine = unsafe { &mut *engine };
+            engine.poisoned = true;
+            FactorialResult::InternalError
+        }
+    }
+}
+
+/// Remove a node's input inventory. The items it held are written to
+/// `out_items` so the host can drop or refund them.
+///
+/// # Safety
+///
+/// `engine` and `out_items` must be valid pointers. The pointer in the
+/// returned `FfiItemBuffer` is valid until the next inventory clear call.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_clear_input_inventory(
+    engine: *mut FactorialEngine,
+    node_id: FfiNodeId,
+    out_items: *mut FfiItemBuffer,
+) -> FactorialResult {
+    unsafe { clear_inventory_ffi(engine, node_id, out_items, Engine::clear_input_inventory) }
+}
+
+/// Remove a node's output inventory. The items it held are written to
+/// `out_items` so the host can drop or refund them.
+///
+/// # Safety
+///
+/// `engine` and `out_items` must be valid pointers. The pointer in the
+/// returned `FfiItemBuffer` is valid until the next inventory clear call.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_clear_output_inventory(
+    engine: *mut FactorialEngine,
+    node_id: FfiNodeId,
+    out_items: *mut FfiItemBuffer,
+) -> FactorialResult {
+    unsafe { clear_inventory_ffi(engine, node_id, out_items, Engine::clear_output_inventory) }
+}
+
+/// Shared body of the inventory clear calls.
+///
+/// # Safety
+///
+/// Same contract as `factorial_clear_input_inventory`.
+unsafe fn clear_inventory_ffi(
+    engine: *mut FactorialEngine,
+    node_id: FfiNodeId,
+    out_items: *mut FfiItemBuffer,
+    clear: fn(&mut Engine, NodeId) -> Vec<factorial_core::item::ItemStack>,
+) -> FactorialResult {
+    if engine.is_null() || out_items.is_null() {
+        return FactorialResult::NullPointer;
+    }
+    let _mutation = unsafe { MutationGuard::begin(engine) };
+    match catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let engine = unsafe { &mut *engine };
+        if let Some(refused) = engine.refuse_mutation() {
+            return refused;
+        }
+        let nid = ffi_to_node_id(node_id);
+        if !engine.inner.graph.contains_node(nid) {
+            return FactorialResult::NodeNotFound;
+        }
+        let items: Vec<FfiItemStack> = clear(&mut engine.inner, nid)
+            .into_iter()
+            .map(|stack| FfiItemStack {
+                item_type: stack.item_type.0,
+                quantity: stack.quantity,
+            })
+            .collect();
+        engine.cleared_items = items;
+        let cache = &engine.cleared_items;
+        unsafe {
+            *out_items = FfiItemBuffer {
+                items: if cache.is_empty() {
+                    ptr::null()
+                } else {
+                    cache.as_ptr()
+                },
+                count: cache.len() as u32,
+            };
+        }
+        FactorialResult::Ok
+    })) {
+        Ok(result) => result,
+        Err(_) => {
+            let engine = unsafe { &mut *engine };
+            engine.poisoned = true;
+            FactorialResult::InternalError
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Inventory fills
+// ---------------------------------------------------------------------------
+
+/// One inventory to fill with [`factorial_fill_inventories_bulk`]: `count`
+/// items starting at `offset` in the shared item array go into the node's
+/// input (`which` 0) or output (`which` 1) inventory.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct FfiInventoryFill {
+    pub node: FfiNodeId,
+    pub which: u32,
+    pub offset: u32,
+    pub count: u32,
+}
+
+/// Map a `which` selector to an inventory side.
+fn ffi_to_inventory_side(which: u32) -> Option<InventorySide> {
+    match which {
+        0 => Some(InventorySide::Input),
+        1 => Some(InventorySide::Output),
+        _ => None,
+    }
+}
+
+fn ffi_to_fill_items(stacks: &[FfiItemStack]) -> Vec<(ItemTypeId, u32)> {
+    stacks
+        .iter()
+        .map(|stack| (ItemTypeId(stack.item_type), stack.quantity))
+        .collect()
+}
+
+fn fill_error_to_ffi(err: InventoryFillError) -> FactorialResult {
+    match err {
+        InventoryFillError::NoInventory(..) => FactorialResult::NodeNotFound,
+        InventoryFillError::DoesNotFit { .. } => FactorialResult::InvalidArgument,
+    }
+}
+
+/// Replace the contents of one of a node's inventories with `items`.
+/// `which` is 0 for the input inventory and 1 for the output inventory.
+///
+/// Items are placed respecting slot filters and capacities. The fill is
+/// all-or-nothing: if the items do not all fit, returns `InvalidArgument`
+/// and the inventory is unchanged. Returns `NodeNotFound` if the node has no
+/// such inventory. Fills emit no events. `items` may be null when
+/// `item_count` is 0, which empties the inventory.
+///
+/// # Safety
+///
+/// `engine` must be a valid pointer. `items` must be valid for `item_count`
+/// elements when `item_count` is non-zero.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_fill_inventory(
+    engine: *mut FactorialEngine,
+    node_id: FfiNodeId,
+    which: u32,
+    items: *const FfiItemStack,
+    item_count: u32,
+) -> FactorialResult {
+    if engine.is_null() || (items.is_null() && item_count > 0) {
+        return FactorialResult::NullPointer;
+    }
+    let _mutation = unsafe { MutationGuard::begin(engine) };
+    match catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let engine = unsafe { &mut *engine };
+        if let Some(refused) = engine.refuse_mutation() {
+            return refused;
+        }
+        let Some(side) = ffi_to_inventory_side(which) else {
+            return FactorialResult::InvalidArgument;
+        };
+        let stacks = if item_count == 0 {
+            &[][..]
+        } else {
+            unsafe { std::slice::from_raw_parts(items, item_count as usize) }
+        };
+        match engine
+            .inner
+            .fill_inventory(ffi_to_node_id(node_id), side, &ffi_to_fill_items(stacks))
+        {
+            Ok(()) => FactorialResult::Ok,
+            Err(err) => fill_error_to_ffi(err),
+        }
+    })) {
+        Ok(result) => result,
+        Err(_) => {
+            let engine = unsafe { &mut *engine };
+            engine.poisoned = true;
+            FactorialResult::InternalError
+        }
+    }
+}
+
+/// Fill many inventories in one call. Each entry names an inventory as
+/// `factorial_fill_inventory` does and a range of the shared `items` array.
+///
+/// Either every fill applies or none does. Returns `InvalidArgument` for a
+/// bad `which`, a range outside `items`, or items that do not fit, and
+/// `NodeNotFound` for a missing inventory. When two entries target the same
+/// inventory, the later one wins.
+///
+/// # Safety
+///
+/// `engine` must be a valid pointer. `entries` and `items` must be valid for
+/// `entry_count` and `item_count` elements when those are non-zero.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_fill_inventories_bulk(
+    engine: *mut FactorialEngine,
+    entries: *const FfiInventoryFill,
+    entry_count: u32,
+    items: *const FfiItemStack,
+    item_count: u32,
+) -> FactorialResult {
+    if engine.is_null()
+        || (entries.is_null() && entry_count > 0)
+        || (items.is_null() && item_count > 0)
+    {
+        return FactorialResult::NullPointer;
+    }
+    let _mutation = unsafe { MutationGuard::begin(engine) };
+    match catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let engine = unsafe { &mut *engine };
+        if let Some(refused) = engine.refuse_mutation() {
+            return refused;
+        }
+        let entries = if entry_count == 0 {
+            &[][..]
+        } else {
+            unsafe { std::slice::from_raw_parts(entries, entry_count as usize) }
+        };
+        let items = if item_count == 0 {
+            Vec::new()
+        } else {
+            ffi_to_fill_items(unsafe { std::slice::from_raw_parts(items, item_count as usize) })
+        };
+        let mut fills = Vec::with_capacity(entries.len());
+        for entry in entries {
+            let Some(side) = ffi_to_inventory_side(entry.which) else {
+                return FactorialResult::InvalidArgument;
+            };
+            let start = entry.offset as usize;
+            let Some(range) = items.get(start..start + entry.count as usize) else {
+                return FactorialResult::InvalidArgument;
+            };
+            fills.push(InventoryFill {
+                node: ffi_to_node_id(entry.node),
+                side,
+                items: range,
+            });
+        }
+        match engine.inner.fill_inventories(&fills) {
+            Ok(()) => FactorialResult::Ok,
+            Err(err) => fill_error_to_ffi(err),
+        }
+    })) {
+        Ok(result) => result,
+        Err(_) => {
+            let engine = unsafe { &mut *engine };
+            engine.poisoned = true;
+            FactorialResult::InternalError
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Crafting queues
+// ---------------------------------------------------------------------------
+
+/// C-compatible crafting queue progress.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FfiCraftProgress {
+    /// Recipe id of the front job. Only meaningful when `jobs > 0`.
+    pub recipe: u32,
+    /// Completion of the front craft as raw Fixed64 bits (0..1).
+    pub progress: i64,
+    /// Crafts left across all jobs.
+    pub remaining: u32,
+    /// Number of jobs in the queue.
+    pub jobs: u32,
+}
+
+fn crafting_queue_id_to_ffi(id: CraftingQueueId) -> FfiCraftingQueueId {
+    id.data().as_ffi()
+}
+
+fn ffi_to_crafting_queue_id(ffi: FfiCraftingQueueId) -> CraftingQueueId {
+    KeyData::from_ffi(ffi).into()
+}
+
+fn crafting_error_to_result(error: CraftingError) -> FactorialResult {
+    match error {
+        CraftingError::UnknownQueue => FactorialResult::CraftingQueueNotFound,
+        CraftingError::NoInventory(_) => FactorialResult::NodeNotFound,
+        CraftingError::InvalidSpeed
+        | CraftingError::UnknownRecipe(_)
+        | CraftingError::MissingIngredients { .. }
+        | CraftingError::IndexOutOfBounds(_) => FactorialResult::CraftingRejected,
+    }
+}
+
+/// Create a hand-crafting queue that draws ingredients from and deposits
+/// products into the input inventory of `node_id`. `speed` is raw Fixed64
+/// bits and must be positive. The new queue id is written to `out_queue`.
+///
+/// # Safety
+///
+/// `engine` and `out_queue` must be valid pointers.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_create_crafting_queue(
+    engine: *mut FactorialEngine,
+    node_id: FfiNodeId,
+    speed: i64,
+    out_queue: *mut FfiCraftingQueueId,
+) -> FactorialResult {
+    if engine.is_null() || out_queue.is_null() {
+        return FactorialResult::NullPointer;
+    }
+    let _mutation = unsafe { MutationGuard::begin(engine) };
+    match catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let engine = unsafe { &mut *engine };
+        if let Some(refused) = engine.refuse_mutation() {
+            return refused;
+        }
+        match engine
+            .inner
+            .create_crafting_queue(ffi_to_node_id(node_id), Fixed64::from_bits(speed))
+        {
+            Ok(queue) => {
+                unsafe { *out_queue = crafting_queue_id_to_ffi(queue) };
+                FactorialResult::Ok
+            }
+            Err(e) => crafting_error_to_result(e),
+        }
+    })) {
+        Ok(result) => result,
+        Err(_) => {
+            let engine = unsafe { &mut *engine };
+            engine.poisoned = true;
+            FactorialResult::InternalError
+        }
+    }
+}
+
+/// Remove a crafting queue, refunding the ingredients of every craft that
+/// has not completed.
+///
+/// # Safety
+///
+/// `engine` must be a valid engine pointer.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_remove_crafting_queue(
+    engine: *mut FactorialEngine,
+    queue: FfiCraftingQueueId,
+) -> FactorialResult {
+    if engine.is_null() {
+        return FactorialResult::NullPointer;
+    }
+    let _mutation = unsafe { MutationGuard::begin(engine) };
+    match catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let engine = unsafe { &mut *engine };
+        if let Some(refused) = engine.refuse_mutation() {
+            return refused;
+        }
+        match engine
+            .inner
+            .remove_crafting_queue(ffi_to_crafting_queue_id(queue))
+        {
+            Some(_) => FactorialResult::Ok,
+            None => FactorialResult::CraftingQueueNotFound,
+        }
+    })) {
+        Ok(result) => result,
+        Err(_) => {
+            let engine = unsafe { &mut *engine };
+            engine.poisoned = true;
+            FactorialResult::InternalError
+        }
+    }
+}
+
+/// Enable or disable recursive crafting of missing intermediates. Recursive
+/// crafting looks up intermediate recipes in the engine's registry.
+///
+/// # Safety
+///
+/// `engine` must be a valid engine pointer.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_set_crafting_recursive(
+    engine: *mut FactorialEngine,
+    queue: FfiCraftingQueueId,
+    recursive: bool,
+) -> FactorialResult {
+    if engine.is_null() {
+        return FactorialResult::NullPointer;
+    }
+    let _mutation = unsafe { MutationGuard::begin(engine) };
+    match catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let engine = unsafe { &mut *engine };
+        if let Some(refused) = engine.refuse_mutation() {
+            return refused;
+        }
+        if engine
+            .inner
+            .set_crafting_recursive(ffi_to_crafting_queue_id(queue), recursive)
+        {
+            FactorialResult::Ok
+        } else {
+            FactorialResult::CraftingQueueNotFound
+        }
+    })) {
+        Ok(result) => result,
+        Err(_) => {
+            let engine = unsafe { &mut *engine };
+            engine.poisoned = true;
+            FactorialResult::InternalError
+        }
+    }
+}
+
+/// Queue `count` crafts, taking all of their ingredients from the queue's
+/// inventory now. When `recipe` is non-null it defines the recipe and
+/// `recipe_id` only tags the job and its events; when null, `recipe_id` is
+/// looked up in the engine's registry.
+///
+/// Returns `CraftingRejected` without queueing anything if ingredients are
+/// missing or the recipe is unknown.
+///
+/// # Safety
+///
+/// `engine` must be a valid engine pointer. `recipe` must be null or a
+/// valid pointer whose arrays are valid for their declared counts.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_queue_craft(
+    engine: *mut FactorialEngine,
+    queue: FfiCraftingQueueId,
+    recipe_id: u32,
+    recipe: *const FfiRecipe,
+    count: u32,
+) -> FactorialResult {
+    if engine.is_null() {
+        return FactorialResult::NullPointer;
+    }
+    let _mutation = unsafe { MutationGuard::begin(engine) };
+    match catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let engine = unsafe { &mut *engine };
+        if let Some(refused) = engine.refuse_mutation() {
+            return refused;
+        }
+        let queue = ffi_to_crafting_queue_id(queue);
+        let result = if recipe.is_null() {
+            engine.inner.queue_craft(queue, RecipeId(recipe_id), count)
+        } else {
+            let recipe = unsafe { &*recipe };
+            let def = RecipeDef {
+                name: String::new(),
+                inputs: unsafe { ffi_to_recipe_entries(recipe.inputs, recipe.input_count) },
+                outputs: unsafe { ffi_to_recipe_entries(recipe.outputs, recipe.output_count) },
+                duration: u64::from(recipe.duration),
+            };
+            engine
+                .inner
+                .queue_craft_def(queue, RecipeId(recipe_id), &def, count)
+        };
+        match result {
+            Ok(()) => FactorialResult::Ok,
+            Err(e) => crafting_error_to_result(e),
+        }
+    })) {
+        Ok(result) => result,
+        Err(_) => {
+            let engine = unsafe { &mut *engine };
+            engine.poisoned = true;
+            FactorialResult::InternalError
+        }
+    }
+}
+
+/// Cancel the job at `index` and refund ingredients for its crafts that have
+/// not started; a craft in progress keeps running. Intermediates queued by
+/// recursive crafting are cancelled with the job they feed. The number of
+/// crafts removed is written to `out_cancelled` when it is non-null.
+///
+/// # Safety
+///
+/// `engine` must be a valid engine pointer. `out_cancelled` must be null or
+/// a valid pointer.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_cancel_craft(
+    engine: *mut FactorialEngine,
+    queue: FfiCraftingQueueId,
+    index: u32,
+    out_cancelled: *mut u32,
+) -> FactorialResult {
+    if engine.is_null() {
+        return FactorialResult::NullPointer;
+    }
+    let _mutation = unsafe { MutationGuard::begin(engine) };
+    match catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let engine = unsafe { &mut *engine };
+        if let Some(refused) = engine.refuse_mutation() {
+            return refused;
+        }
+        match engine
+            .inner
+            .cancel_craft(ffi_to_crafting_queue_id(queue), index as usize)
+        {
+            Ok(refund) => {
+                if !out_cancelled.is_null() {
+                    unsafe { *out_cancelled = refund.crafts };
+                }
+                FactorialResult::Ok
+            }
+            Err(e) => crafting_error_to_result(e),
+        }
+    })) {
+        Ok(result) => result,
+        Err(_) => {
+            let engine = unsafe { &mut *engine };
+            engine.poisoned = true;
+            FactorialResult::InternalError
+        }
+    }
+}
+
+/// Write a crafting queue's progress to `out_progress`.
+///
+/// # Safety
+///
+/// `engine` and `out_progress` must be valid pointers.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_get_crafting_progress(
+    engine: *const FactorialEngine,
+    queue: FfiCraftingQueueId,
+    out_progress: *mut FfiCraftProgress,
+) -> FactorialResult {
+    if engine.is_null() || out_progress.is_null() {
+        return FactorialResult::NullPointer;
+    }
+    match catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let engine = unsafe { &*engine };
+        if engine.poisoned {
+            return FactorialResult::Poisoned;
+        }
+        match engine
+            .inner
+            .crafting_progress(ffi_to_crafting_queue_id(queue))
+        {
+            Some(progress) => {
+                unsafe {
+                    *out_progress = FfiCraftProgress {
+                        recipe: progress.recipe.map_or(0, |r| r.0),
+                        progress: progress.progress.to_bits(),
+                        remaining: progress.remaining,
+                        jobs: progress.jobs,
+                    }
+                };
+                FactorialResult::Ok
+            }
+            None => FactorialResult::CraftingQueueNotFound,
+        }
+    })) {
+        Ok(result) => result,
+        Err(_) => FactorialResult::InternalError,
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Profiling
+// ---------------------------------------------------------------------------
+
+/// Enable or disable per-step operation counting. Disabling clears the
+/// last profile.
+///
+/// # Safety
+///
+/// `engine` must be a valid engine pointer.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_set_profiling(
+    engine: *mut FactorialEngine,
+    enabled: bool,
+) -> FactorialResult {
+    if engine.is_null() {
+        return FactorialResult::NullPointer;
+    }
+    let _mutation = unsafe { MutationGuard::begin(engine) };
+    match catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let engine = unsafe { &mut *engine };
+        if let Some(refused) = engine.refuse_mutation() {
+            return refused;
+        }
+        engine.inner.set_profiling(enabled);
+        FactorialResult::Ok
+    })) {
+        Ok(result) => result,
+        Err(_) => {
+            let engine = unsafe { &mut *engine };
+            engine.poisoned = true;
+            FactorialResult::InternalError
+        }
+    }
+}
+
+/// Get the operation counts from the most recent step. Writes an all-zero
+/// profile (`tick == 0`) if profiling is disabled or no step has run since
+/// it was enabled.
+///
+/// # Safety
+///
+/// `engine` and `out_profile` must be valid pointers.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_get_step_profile(
+    engine: *const FactorialEngine,
+    out_profile: *mut FfiStepProfile,
+) -> FactorialResult {
+    if engine.is_null() || out_profile.is_null() {
+        return FactorialResult::NullPointer;
+    }
+    match catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let engine = unsafe { &*engine };
+        if engine.poisoned {
+            return FactorialResult::Poisoned;
+        }
+        let profile = engine
+            .inner
+            .last_step_profile()
+            .map(|p| FfiStepProfile {
+                tick: p.tick,
+                mutations_applied: p.mutations_applied,
+                transports_advanced: p.transports_advanced,
+                items_delivered: p.items_delivered,
+                processors_ticked: p.processors_ticked,
+                junctions_processed: p.junctions_processed,
+                modules_run: p.modules_run,
+                events_delivered: p.events_delivered,
+                module_work: p.module_work,
+                topology_rebuilds: p.topology_rebuilds,
+            })
+            .unwrap_or_default();
+        unsafe { *out_profile = profile };
+        FactorialResult::Ok
+    })) {
+        Ok(result) => result,
+        Err(_) => FactorialResult::InternalError,
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Latency histograms
+// ---------------------------------------------------------------------------
+
+/// Enable or disable transit latency tracking for one edge.
+///
+/// Returns `EdgeNotFound` if the edge does not exist.
+///
+/// # Safety
+///
+/// `engine` must be a valid engine pointer.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_set_latency_tracking(
+    engine: *mut FactorialEngine,
+    edge_id: FfiEdgeId,
+    enabled: bool,
+) -> FactorialResult {
+    if engine.is_null() {
+        return FactorialResult::NullPointer;
+    }
+    let _mutation = unsafe { MutationGuard::begin(engine) };
+    match catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let engine = unsafe { &mut *engine };
+        if let Some(refused) = engine.refuse_mutation() {
+            return refused;
+        }
+        let eid = ffi_to_edge_id(edge_id);
+        if !engine.inner.graph.contains_edge(eid) {
+            return FactorialResult::EdgeNotFound;
+        }
+        engine.inner.set_latency_tracking(eid, enabled);
+        FactorialResult::Ok
+    })) {
+        Ok(result) => result,
+        Err(_) => {
+            let engine = unsafe { &mut *engine };
+            engine.poisoned = true;
+            FactorialResult::InternalError
+        }
+    }
+}
+
+/// Set the latency histogram bucket edges, in ticks, and reset every
+/// histogram. A histogram has `count + 1` buckets.
+///
+/// # Safety
+///
+/// `engine` must be a valid engine pointer. `edges` must point to `count`
+/// values (it may be null when `count` is 0).
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_set_latency_buckets(
+    engine: *mut FactorialEngine,
+    edges: *const u64,
+    count: u32,
+) -> FactorialResult {
+    if engine.is_null() || (edges.is_null() && count > 0) {
+        return FactorialResult::NullPointer;
+    }
+    let _mutation = unsafe { MutationGuard::begin(engine) };
+    match catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let engine = unsafe { &mut *engine };
+        if let Some(refused) = engine.refuse_mutation() {
+            return refused;
+        }
+        let edges = if count == 0 {
+            Vec::new()
+        } else {
+            unsafe { std::slice::from_raw_parts(edges, count as usize) }.to_vec()
+        };
+        engine.inner.set_latency_buckets(edges);
+        FactorialResult::Ok
+    })) {
+        Ok(result) => result,
+        Err(_) => {
+            let engine = unsafe { &mut *engine };
+            engine.poisoned = true;
+            FactorialResult::InternalError
+        }
+    }
+}
+
+/// Copy an edge's latency histogram into `out_counts`.
+///
+/// Writes the number of buckets to `*out_written` (0 if the edge is not
+/// tracked). If `len` is smaller than that, nothing else is written and
+/// `BufferTooSmall` is returned. Returns `EdgeNotFound` if the edge does not
+/// exist.
+///
+/// # Safety
+///
+/// `engine` and `out_written` must be valid pointers. `out_counts` must be
+/// valid for `len` elements (it may be null when `len` is 0).
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_get_edge_latency_histogram(
+    engine: *const FactorialEngine,
+    edge_id: FfiEdgeId,
+    out_counts: *mut u64,
+    len: u32,
+    out_written: *mut u32,
+) -> FactorialResult {
+    if engine.is_null() || out_written.is_null() || (out_counts.is_null() && len > 0) {
+        return FactorialResult::NullPointer;
+    }
+    match catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let engine = unsafe { &*engine };
+        if engine.poisoned {
+            return FactorialResult::Poisoned;
+        }
+        let eid = ffi_to_edge_id(edge_id);
+        if !engine.inner.graph.contains_edge(eid) {
+            return FactorialResult::EdgeNotFound;
+        }
+        let counts = engine.inner.edge_latency_histogram(eid);
+        unsafe { *out_written = counts.len() as u32 };
+        if counts.is_empty() {
+            return FactorialResult::Ok;
+        }
+        if (len as usize) < counts.len() {
+            return FactorialResult::BufferTooSmall;
+        }
+        unsafe { std::slice::from_raw_parts_mut(out_counts, counts.len()) }.copy_from_slice(counts);
+        FactorialResult::Ok
+    })) {
+        Ok(result) => result,
+        Err(_) => FactorialResult::InternalError,
+    }
+}
+
+/// Zero every latency histogram.
+///
+/// # Safety
+///
+/// `engine` must be a valid engine pointer.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_reset_latency_histograms(
+    engine: *mut FactorialEngine,
+) -> FactorialResult {
+    if engine.is_null() {
+        return FactorialResult::NullPointer;
+    }
+    let _mutation = unsafe { MutationGuard::begin(engine) };
+    match catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let engine = unsafe { &mut *engine };
+        if let Some(refused) = engine.refuse_mutation() {
+            return refused;
+        }
+        engine.inner.reset_latency_histograms();
+        FactorialResult::Ok
+    })) {
+        Ok(result) => result,
+        Err(_) => {
+            let engine = unsafe { &mut *engine };
+            engine.poisoned = true;
+            FactorialResult::InternalError
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Operation log
+// ---------------------------------------------------------------------------
+
+/// What an [`FfiOperationRecord`] describes.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FfiOperationKind {
+    /// `item` is the building type.
+    OpNodeAdded = 0,
+    OpNodeRemoved = 1,
+    /// `node` is the edge's source.
+    OpEdgeAdded = 2,
+    OpEdgeRemoved = 3,
+    /// A processor at `node` produced `quantity` of `item`.
+    OpProduced = 4,
+    /// `edge` delivered `quantity` of `item` to `node`.
+    OpDelivered = 5,
+    /// Step `tick` finished.
+    OpStepCompleted = 6,
+}
+
+/// One logged engine operation. Ids that do not apply are 0, which is never
+/// a valid id; other unused fields are 0 too.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FfiOperationRecord {
+    pub kind: FfiOperationKind,
+    pub tick: u64,
+    pub node: FfiNodeId,
+    pub edge: FfiEdgeId,
+    pub item: u32,
+    pub quantity: u32,
+}
+
+/// Called with each [`FfiOperationRecord`] and the user data passed to
+/// [`factorial_set_operation_logger`]. The record is valid for the call only.
+pub type FactorialOperationLogger =
+    unsafe extern "C" fn(record: *const FfiOperationRecord, user_data: *mut std::ffi::c_void);
+
+fn operation_record_to_ffi(record: &OperationRecord) -> FfiOperationRecord {
+    let kind = match record.kind {
+        OperationKind::NodeAdded => FfiOperationKind::OpNodeAdded,
+        OperationKind::NodeRemoved => FfiOperationKind::OpNodeRemoved,
+        OperationKind::EdgeAdded => FfiOperationKind::OpEdgeAdded,
+        OperationKind::EdgeRemoved => FfiOperationKind::OpEdgeRemoved,
+        OperationKind::Produced => FfiOperationKind::OpProduced,
+        OperationKind::Delivered => FfiOperationKind::OpDelivered,
+        OperationKind::StepCompleted => FfiOperationKind::OpStepCompleted,
+    };
+    let node = if record.node.is_null() {
+        0
+    } else {
+        node_id_to_ffi(record.node)
+    };
+    let edge = if record.edge.is_null() {
+        0
+    } else {
+        edge_id_to_ffi(record.edge)
+    };
+    FfiOperationRecord {
+        kind,
+        tick: record.tick,
+        node,
+        edge,
+        item: record.item,
+        quantity: record.quantity,
+    }
+}
+
+/// Call `logger` synchronously, with `user_data`, for every operation the
+/// engine performs from now on: graph mutations applied, processor output,
+/// transport deliveries and completed steps, in a deterministic order. This
+/// is a tooling trace, separate from the event queue and unaffected by
+/// event suppression. Pass a null `logger` to remove it.
+///
+/// # Safety
+///
+/// `engine` must be a valid engine pointer. `logger` must not unwind, and it
+/// and `user_data` must be usable from whichever thread drives the engine.
+/// The logger must not call back into this engine.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_set_operation_logger(
+    engine: *mut FactorialEngine,
+    logger: Option<FactorialOperationLogger>,
+    user_data: *mut std::ffi::c_void,
+) -> FactorialResult {
+    if engine.is_null() {
+        return FactorialResult::NullPointer;
+    }
+    let _mutation = unsafe { MutationGuard::begin(engine) };
+    match catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let engine = unsafe { &mut *engine };
+        if let Some(refused) = engine.refuse_mutation() {
+            return refused;
+        }
+        match logger {
+            Some(logger) => engine.inner.set_operation_logger(Box::new(move |record| {
+                let record = operation_record_to_ffi(record);
+                unsafe { logger(&record, user_data) };
+            })),
+            None => engine.inner.clear_operation_logger(),
+        }
+        FactorialResult::Ok
+    })) {
+        Ok(result) => result,
+        Err(_) => {
+            let engine = unsafe { &mut *engine };
+            engine.poisoned = true;
+            FactorialResult::InternalError
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Number formatting
+// ---------------------------------------------------------------------------
+
+/// Format raw Fixed64 `bits` as a decimal string with exactly `decimals`
+/// places (at most 19), so every host shows the same digits for the same
+/// value. `mode` is 0 = half up, 1 = half even, 2 = floor, 3 = ceil.
+///
+/// Writes the string and a NUL terminator to `out_buf`, and its length
+/// without the terminator to `*out_written`. If `len` is too small nothing
+/// but `*out_written` is written and `BufferTooSmall` is returned; retry with
+/// at least `*out_written + 1` bytes.
+///
+/// # Safety
+///
+/// `out_buf` must be valid for `len` bytes and `out_written` must be a valid
+/// pointer.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_format_fixed(
+    bits: i64,
+    decimals: u32,
+    mode: u32,
+    out_buf: *mut u8,
+    len: u32,
+    out_written: *mut u32,
+) -> FactorialResult {
+    if out_buf.is_null() || out_written.is_null() {
+        return FactorialResult::NullPointer;
+    }
+    let mode = match mode {
+        0 => RoundingMode::HalfUp,
+        1 => RoundingMode::HalfEven,
+        2 => RoundingMode::Floor,
+        3 => RoundingMode::Ceil,
+        _ => return FactorialResult::InvalidArgument,
+    };
+    let text = Fixed64::from_bits(bits).to_decimal_string_with(decimals, mode);
+    unsafe { *out_written = text.len() as u32 };
+    if (len as usize) <= text.len() {
+        return FactorialResult::BufferTooSmall;
+    }
+    let buf = unsafe { std::slice::from_raw_parts_mut(out_buf, text.len() + 1) };
+    buf[..text.len()].copy_from_slice(text.as_bytes());
+    buf[text.len()] = 0;
+    FactorialResult::Ok
+}
+
+// ---------------------------------------------------------------------------
+// Poison checking
+// ---------------------------------------------------------------------------
+
+/// Check whether the engine is poisoned (a previous panic left it in an
+/// inconsistent state). Returns `false` if the engine pointer is null.
+///
+/// # Safety
+///
+/// `engine` must be a valid engine pointer or null.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_is_poisoned(engine: *const FactorialEngine) -> bool {
+    if engine.is_null() {
+        return false;
+    }
+    let engine = unsafe { &*engine };
+    engine.poisoned
+}
+
+/// Clear the poisoned flag on an engine, allowing it to be used again.
+///
+/// # Safety
+///
+/// `engine` must be a valid engine pointer.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_clear_poison(engine: *mut FactorialEngine) -> FactorialResult {
+    if engine.is_null() {
+        return FactorialResult::NullPointer;
+    }
+    let _mutation = unsafe { MutationGuard::begin(engine) };
+    let engine = unsafe { &mut *engine };
+    engine.poisoned = false;
+    FactorialResult::Ok
+}
+
+// ---------------------------------------------------------------------------
+// FFI-safe logic network types
+// ---------------------------------------------------------------------------
+
+/// C-compatible wire color.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FfiWireColor {
+    Red = 0,
+    Green = 1,
+}
+
+/// C-compatible signal selector kind.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FfiSelectorKind {
+    Signal = 0,
+    Constant = 1,
+    Each = 2,
+}
+
+/// C-compatible arithmetic operation.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FfiArithmeticOp {
+    Add = 0,
+    Sub = 1,
+    Mul = 2,
+    Div = 3,
+    Mod = 4,
+}
+
+/// C-compatible comparison operation.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FfiComparisonOp {
+    Gt = 0,
+    Lt = 1,
+    Eq = 2,
+    Gte = 3,
+    Lte = 4,
+    Ne = 5,
+}
+
+/// C-compatible decider output kind.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FfiDeciderOutputKind {
+    One = 0,
+    InputCount = 1,
+    Everything = 2,
+}
+
+/// C-compatible wire network ID.
+pub type FfiWireNetworkId = u32;
+
+/// One entry of the per-tick changed-signal report. `old` and `new` are raw
+/// Fixed64 bits.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FfiSignalChange {
+    pub node: FfiNodeId,
+    pub wire: FfiWireColor,
+    pub signal: u32,
+    pub network: FfiWireNetworkId,
+    pub old: i64,
+    pub new: i64,
+}
+
+/// Engine-owned array returned by `factorial_logic_get_changed_signals`.
+#[repr(C)]
+#[derive(Debug)]
+pub struct FfiSignalChangeBuffer {
+    pub changes: *const FfiSignalChange,
+    pub count: u32,
+}
+
+// ---------------------------------------------------------------------------
+// Logic FFI helpers
+// ---------------------------------------------------------------------------
+
+fn ffi_to_wire_color(color: FfiWireColor) -> WireColor {
+    match color {
+        FfiWireColor::Red => WireColor::Red,
+        FfiWireColor::Green => WireColor::Green,
+    }
+}
+
+fn wire_color_to_ffi(color: WireColor) -> FfiWireColor {
+    match color {
+        WireColor::Red => FfiWireColor::Red,
+        WireColor::Green => FfiWireColor::Green,
+    }
+}
+
+fn ffi_to_selector(kind: FfiSelectorKind, value: u64) -> SignalSelector {
+    match kind {
+        FfiSelectorKind::Signal => SignalSelector::Signal(ItemTypeId(value as u32)),
+        FfiSelectorKind::Constant => SignalSelector::Constant(Fixed64::from_bits(value as i64)),
+        FfiSelectorKind::Each => SignalSelector::Each,
+    }
+}
+
+fn ffi_to_arithmetic_op(op: FfiArithmeticOp) -> ArithmeticOp {
+    match op {
+        FfiArithmeticOp::Add => ArithmeticOp::Add,
+        FfiArithmeticOp::Sub => ArithmeticOp::Subtract,
+        FfiArithmeticOp::Mul => ArithmeticOp::Multiply,
+        FfiArithmeticOp::Div => ArithmeticOp::Divide,
+        FfiArithmeticOp::Mod => ArithmeticOp::Modulo,
+    }
+}
+
+fn ffi_to_comparison_op(op: FfiComparisonOp) -> ComparisonOp {
+    match op {
+        FfiComparisonOp::Gt => ComparisonOp::Gt,
+        FfiComparisonOp::Lt => ComparisonOp::Lt,
+        FfiComparisonOp::Eq => ComparisonOp::Eq,
+        FfiComparisonOp::Gte => ComparisonOp::Gte,
+        FfiComparisonOp::Lte => ComparisonOp::Lte,
+        FfiComparisonOp::Ne => ComparisonOp::Ne,
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Logic network FFI functions
+// ---------------------------------------------------------------------------
+
+/// Register the logic module with the engine.
+///
+/// Must be called before any other `factorial_logic_*` function. Calling
+/// this more than once on the same engine is a no-op (the second bridge
+/// is simply added; prefer calling only once).
+///
+/// # Safety
+///
+/// `engine` must be a valid engine pointer.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_logic_register(engine: *mut FactorialEngine) -> FactorialResult {
+    if engine.is_null() {
+        return FactorialResult::NullPointer;
+    }
+    let _mutation = unsafe { MutationGuard::begin(engine) };
+    match catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let engine = unsafe { &mut *engine };
+        if let Some(refused) = engine.refuse_mutation() {
+            return refused;
+        }
+        engine
+            .inner
+            .register_module(Box::new(LogicModuleBridge::new()));
+        FactorialResult::Ok
+    })) {
+        Ok(result) => result,
+        Err(_) => {
+            let engine = unsafe { &mut *engine };
+            engine.poisoned = true;
+            FactorialResult::InternalError
+        }
+    }
+}
+
+/// Create a wire network with the given color. The new network ID is
+/// written to `out_id`.
+///
+/// # Safety
+///
+/// `engine` and `out_id` must be valid pointers.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_logic_create_network(
+    engine: *mut FactorialEngine,
+    color: FfiWireColor,
+    out_id: *mut FfiWireNetworkId,
+) -> FactorialResult {
+    if engine.is_null() || out_id.is_null() {
+        return FactorialResult::NullPointer;
+    }
+    let _mutation = unsafe { MutationGuard::begin(engine) };
+    match catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let engine = unsafe { &mut *engine };
+        if let Some(refused) = engine.refuse_mutation() {
+            return refused;
+        }
+        let bridge = match engine.inner.find_module_mut::<LogicModuleBridge>() {
+            Some(b) => b,
+            None => return FactorialResult::InternalError,
+        };
+        let net_id = bridge.logic_mut().create_network(ffi_to_wire_color(color));
+        unsafe { *out_id = net_id.0 };
+        FactorialResult::Ok
+    })) {
+        Ok(result) => result,
+        Err(_) => {
+            let engine = unsafe { &mut *engine };
+            engine.poisoned = true;
+            FactorialResult::InternalError
+        }
+    }
+}
+
+/// Remove a wire network.
+///
+/// # Safety
+///
+/// `engine` must be a valid engine pointer.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_logic_remove_network(
+    engine: *mut FactorialEngine,
+    network_id: FfiWireNetworkId,
+) -> FactorialResult {
+    if engine.is_null() {
+        return FactorialResult::NullPointer;
+    }
+    let _mutation = unsafe { MutationGuard::begin(engine) };
+    match catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let engine = unsafe { &mut *engine };
+        if let Some(refused) = engine.refuse_mutation() {
+            return refused;
+        }
+        let bridge = match engine.inner.find_module_mut::<LogicModuleBridge>() {
+            Some(b) => b,
+            None => return FactorialResult::InternalError,
+        };
+        bridge.logic_mut().remove_network(WireNetworkId(network_id));
+        FactorialResult::Ok
+    })) {
+        Ok(result) => result,
+        Err(_) => {
+            let engine = unsafe { &mut *engine };
+            engine.poisoned = true;
+            FactorialResult::InternalError
+        }
+    }
+}
+
+/// Add a node to a wire network.
+///
+/// # Safety
+///
+/// `engine` must be a valid engine pointer.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_logic_add_to_network(
+    engine: *mut FactorialEngine,
+    network_id: FfiWireNetworkId,
+    node_id: FfiNodeId,
+) -> FactorialResult {
+    if engine.is_null() {
+        return FactorialResult::NullPointer;
+    }
+    let _mutation = unsafe { MutationGuard::begin(engine) };
+    match catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let engine = unsafe { &mut *engine };
+        if let Some(refused) = engine.refuse_mutation() {
+            return refused;
+        }
+        let bridge = match engine.inner.find_module_mut::<LogicModuleBridge>() {
+            Some(b) => b,
+            None => return FactorialResult::InternalError,
+        };
+        bridge
+            .logic_mut()
+            .add_to_network(WireNetworkId(network_id), ffi_to_node_id(node_id));
+        FactorialResult::Ok
+    })) {
+        Ok(result) => result,
+        Err(_) => {
+            let engine = unsafe { &mut *engine };
+            engine.poisoned = true;
+            FactorialResult::InternalError
+        }
+    }
+}
+
+/// Remove a node from a wire network.
+///
+/// # Safety
+///
+/// `engine` must be a valid engine pointer.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_logic_remove_from_network(
+    engine: *mut FactorialEngine,
+    network_id: FfiWireNetworkId,
+    node_id: FfiNodeId,
+) -> FactorialResult {
+    if engine.is_null() {
+        return FactorialResult::NullPointer;
+    }
+    let _mutation = unsafe { MutationGuard::begin(engine) };
+    match catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let engine = unsafe { &mut *engine };
+        if let Some(refused) = engine.refuse_mutation() {
+            return refused;
+        }
+        let bridge = match engine.inner.find_module_mut::<LogicModuleBridge>() {
+            Some(b) => b,
+            None => return FactorialResult::InternalError,
+        };
+        bridge
+            .logic_mut()
+            .remove_from_network(WireNetworkId(network_id), ffi_to_node_id(node_id));
+        FactorialResult::Ok
+    })) {
+        Ok(result) => result,
+        Err(_) => {
+            let engine = unsafe { &mut *engine };
+            engine.poisoned = true;
+            FactorialResult::InternalError
+        }
+    }
+}
+
+/// Set a constant combinator on a node.
+///
+/// `item_ids_ptr` and `values_ptr` are parallel arrays of length `count`.
+/// Each pair defines a signal: `item_ids[i]` -> `values[i]` (raw Fixed64 bits).
+/// `enabled` is non-zero for enabled.
+///
+/// # Safety
+///
+/// `engine` must be a valid engine pointer. `item_ids_ptr` and `values_ptr`
+/// must point to arrays of at least `count` elements.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_logic_set_constant(
+    engine: *mut FactorialEngine,
+    node_id: FfiNodeId,
+    item_ids_ptr: *const u32,
+    values_ptr: *const i64,
+    count: u32,
+    enabled: u8,
+) -> FactorialResult {
+    if engine.is_null() {
+        return FactorialResult::NullPointer;
+    }
+    if count > 0 && (item_ids_ptr.is_null() || values_ptr.is_null()) {
+        return FactorialResult::NullPointer;
+    }
+    let _mutation = unsafe { MutationGuard::begin(engine) };
+    match catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let engine = unsafe { &mut *engine };
+        if let Some(refused) = engine.refuse_mutation() {
+            return refused;
+        }
+        let bridge = match engine.inner.find_module_mut::<LogicModuleBridge>() {
+            Some(b) => b,
+            None => return FactorialResult::InternalError,
+        };
+        let mut signals = SignalSet::new();
+        if count > 0 {
+            let item_ids = unsafe { std::slice::from_raw_parts(item_ids_ptr, count as usize) };
+            let values = unsafe { std::slice::from_raw_parts(values_ptr, count as usize) };
+            for i in 0..count as usize {
+                signals.insert(ItemTypeId(item_ids[i]), Fixed64::from_bits(values[i]));
+            }
+        }
+        bridge
+            .logic_mut()
+            .set_constant(ffi_to_node_id(node_id), signals, enabled != 0);
+        FactorialResult::Ok
+    })) {
+        Ok(result) => result,
+        Err(_) => {
+            let engine = unsafe { &mut *engine };
+            engine.poisoned = true;
+            FactorialResult::InternalError
+        }
+    }
+}
+
+/// Set an arithmetic combinator on a node.
+///
+/// Left/right operands are specified as (kind, value) pairs where `value`
+/// is the item ID for `Signal`, raw Fixed64 bits for `Constant`, or
+/// ignored for `Each`.
+///
+/// # Safety
+///
+/// `engine` must be a valid engine pointer.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_logic_set_arithmetic(
+    engine: *mut FactorialEngine,
+    node_id: FfiNodeId,
+    left_kind: FfiSelectorKind,
+    left_value: u64,
+    op: FfiArithmeticOp,
+    right_kind: FfiSelectorKind,
+    right_value: u64,
+    output_item: u32,
+) -> FactorialResult {
+    if engine.is_null() {
+        return FactorialResult::NullPointer;
+    }
+    let _mutation = unsafe { MutationGuard::begin(engine) };
+    match catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let engine = unsafe { &mut *engine };
+        if let Some(refused) = engine.refuse_mutation() {
+            return refused;
+        }
+        let bridge = match engine.inner.find_module_mut::<LogicModuleBridge>() {
+            Some(b) => b,
+            None => return FactorialResult::InternalError,
+        };
+        let combinator = ArithmeticCombinator {
+            left: ffi_to_selector(left_kind, left_value),
+            op: ffi_to_arithmetic_op(op),
+            right: ffi_to_selector(right_kind, right_value),
+            output: ItemTypeId(output_item),
+        };
+        bridge
+            .logic_mut()
+            .set_arithmetic(ffi_to_node_id(node_id), combinator);
+        FactorialResult::Ok
+    })) {
+        Ok(result) => result,
+        Err(_) => {
+            let engine = unsafe { &mut *engine };
+            engine.poisoned = true;
+            FactorialResult::InternalError
+        }
+    }
+}
+
+/// Set a decider combinator on a node.
+///
+/// Left/right operands are specified as (kind, value) pairs. The output
+/// kind determines what is emitted when the condition is true.
+///
+/// # Safety
+///
+/// `engine` must be a valid engine pointer.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_logic_set_decider(
+    engine: *mut FactorialEngine,
+    node_id: FfiNodeId,
+    left_kind: FfiSelectorKind,
+    left_value: u64,
+    cmp_op: FfiComparisonOp,
+    right_kind: FfiSelectorKind,
+    right_value: u64,
+    output_kind: FfiDeciderOutputKind,
+    output_item: u32,
+) -> FactorialResult {
+    if engine.is_null() {
+        return FactorialResult::NullPointer;
+    }
+    let _mutation = unsafe { MutationGuard::begin(engine) };
+    match catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let engine = unsafe { &mut *engine };
+        if let Some(refused) = engine.refuse_mutation() {
+            return refused;
+        }
+        let bridge = match engine.inner.find_module_mut::<LogicModuleBridge>() {
+            Some(b) => b,
+            None => return FactorialResult::InternalError,
+        };
+        let condition = Condition {
+            left: ffi_to_selector(left_kind, left_value),
+            op: ffi_to_comparison_op(cmp_op),
+            right: ffi_to_selector(right_kind, right_value),
+            epsilon: Fixed64::ZERO,
+        };
+        let output = match output_kind {
+            FfiDeciderOutputKind::One => DeciderOutput::One(ItemTypeId(output_item)),
+            FfiDeciderOutputKind::InputCount => DeciderOutput::InputCount(ItemTypeId(output_item)),
+            FfiDeciderOutputKind::Everything => DeciderOutput::Everything,
+        };
+        let combinator = DeciderCombinator { condition, output };
+        bridge
+            .logic_mut()
+            .set_decider(ffi_to_node_id(node_id), combinator);
+        FactorialResult::Ok
+    })) {
+        Ok(result) => result,
+        Err(_) => {
+            let engine = unsafe { &mut *engine };
+            engine.poisoned = true;
+            FactorialResult::InternalError
+        }
+    }
+}
+
+/// Set circuit control on a node.
+///
+/// The condition is specified as left/right (kind, value) pairs and a
+/// comparison operator. The wire color determines which network to read
+/// signals from.
+///
+/// # Safety
+///
+/// `engine` must be a valid engine pointer.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_logic_set_circuit_control(
+    engine: *mut FactorialEngine,
+    node_id: FfiNodeId,
+    left_kind: FfiSelectorKind,
+    left_value: u64,
+    cmp_op: FfiComparisonOp,
+    right_kind: FfiSelectorKind,
+    right_value: u64,
+    wire_color: FfiWireColor,
+) -> FactorialResult {
+    if engine.is_null() {
+        return FactorialResult::NullPointer;
+    }
+    let _mutation = unsafe { MutationGuard::begin(engine) };
+    match catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let engine = unsafe { &mut *engine };
+        if let Some(refused) = engine.refuse_mutation() {
+            return refused;
+        }
+        let bridge = match engine.inner.find_module_mut::<LogicModuleBridge>() {
+            Some(b) => b,
+            None => return FactorialResult::InternalError,
+        };
+        let condition = Condition {
+            left: ffi_to_selector(left_kind, left_value),
+            op: ffi_to_comparison_op(cmp_op),
+            right: ffi_to_selector(right_kind, right_value),
+            epsilon: Fixed64::ZERO,
+        };
+        bridge.logic_mut().set_circuit_control(
+            ffi_to_node_id(node_id),
+            condition,
+            ffi_to_wire_color(wire_color),
+        );
+        FactorialResult::Ok
+    })) {
+        Ok(result) => result,
+        Err(_) => {
+            let engine = unsafe { &mut *engine };
+            engine.poisoned = true;
+            FactorialResult::InternalError
+        }
+    }
+}
+
+/// Query whether a node's circuit control condition is active.
+///
+/// Writes `1` to `out_active` if active, `0` if inactive. Returns
+/// `NodeNotFound` if the node has no circuit control.
+///
+/// # Safety
+///
+/// `engine` and `out_active` must be valid pointers.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_logic_is_active(
+    engine: *mut FactorialEngine,
+    node_id: FfiNodeId,
+    out_active: *mut u8,
+) -> FactorialResult {
+    if engine.is_null() || out_active.is_null() {
+        return FactorialResult::NullPointer;
+    }
+    let _mutation = unsafe { MutationGuard::begin(engine) };
+    match catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let engine = unsafe { &mut *engine };
+        if engine.poisoned {
+            return FactorialResult::Poisoned;
+        }
+        let bridge = match engine.inner.find_module::<LogicModuleBridge>() {
+            Some(b) => b,
+            None => return FactorialResult::InternalError,
+        };
+        match bridge.logic().is_active(ffi_to_node_id(node_id)) {
+            Some(active) => {
+                unsafe { *out_active = u8::from(active) };
+                FactorialResult::Ok
+            }
+            None => FactorialResult::NodeNotFound,
+        }
+    })) {
+        Ok(result) => result,
+        Err(_) => {
+            let engine = unsafe { &mut *engine };
+            engine.poisoned = true;
+            FactorialResult::InternalError
+        }
+    }
+}
+
+/// Query a signal value from a wire network.
+///
+/// Writes the raw Fixed64 bits to `out_value`. If the signal is not
+/// present, writes 0.
+///
+/// # Safety
+///
+/// `engine` and `out_value` must be valid pointers.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_logic_get_network_signal(
+    engine: *mut FactorialEngine,
+    network_id: FfiWireNetworkId,
+    item_id: u32,
+    out_value: *mut i64,
+) -> FactorialResult {
+    if engine.is_null() || out_value.is_null() {
+        return FactorialResult::NullPointer;
+    }
+    let _mutation = unsafe { MutationGuard::begin(engine) };
+    match catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let engine = unsafe { &mut *engine };
+        if engine.poisoned {
+            return FactorialResult::Poisoned;
+        }
+        let bridge = match engine.inner.find_module::<LogicModuleBridge>() {
+            Some(b) => b,
+            None => return FactorialResult::InternalError,
+        };
+        let zero = Fixed64::from_num(0);
+        let value = bridge
+            .logic()
+            .network_signals(WireNetworkId(network_id))
+            .and_then(|signals| signals.get(&ItemTypeId(item_id)).copied())
+            .unwrap_or(zero);
+        unsafe { *out_value = value.to_bits() };
+        FactorialResult::Ok
+    })) {
+        Ok(result) => result,
+        Err(_) => {
+            let engine = unsafe { &mut *engine };
+            engine.poisoned = true;
+            FactorialResult::InternalError
+        }
+    }
+}
+
+/// List the signals whose value on some node's wire changed during the most
+/// recent step, ordered by (node, wire, signal, network).
+///
+/// # Safety
+///
+/// `engine` and `out_buffer` must be valid pointers. The pointer in the
+/// returned buffer is valid until the next call to this function.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_logic_get_changed_signals(
+    engine: *const FactorialEngine,
+    out_buffer: *mut FfiSignalChangeBuffer,
+) -> FactorialResult {
+    if engine.is_null() || out_buffer.is_null() {
+        return FactorialResult::NullPointer;
+    }
+    match catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let engine = unsafe { &*engine };
+        if engine.poisoned {
+            return FactorialResult::Poisoned;
+        }
+        let bridge = match engine.inner.find_module::<LogicModuleBridge>() {
+            Some(b) => b,
+            None => return FactorialResult::InternalError,
+        };
+        let mut cache = engine.signal_changes.borrow_mut();
+        *cache = bridge
+            .changed_signals()
+            .iter()
+            .map(|change| FfiSignalChange {
+                node: node_id_to_ffi(change.node),
+                wire: wire_color_to_ffi(change.wire),
+                signal: change.signal.0,
+                network: change.network.0,
+                old: change.old.to_bits(),
+                new: change.new.to_bits(),
+            })
+            .collect();
+        unsafe {
+            *out_buffer = FfiSignalChangeBuffer {
+                changes: if cache.is_empty() {
+                    ptr::null()
+                } else {
+                    cache.as_ptr()
+                },
+                count: cache.len() as u32,
+            };
+        }
+        FactorialResult::Ok
+    })) {
+        Ok(result) => result,
+        Err(_) => FactorialResult::InternalError,
+    }
+}
+
+/// Remove all logic state for a node (constant, combinators, circuit
+/// control, and network memberships).
+///
+/// # Safety
+///
+/// `engine` must be a valid engine pointer.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_logic_remove_node(
+    engine: *mut FactorialEngine,
+    node_id: FfiNodeId,
+) -> FactorialResult {
+    if engine.is_null() {
+        return FactorialResult::NullPointer;
+    }
+    let _mutation = unsafe { MutationGuard::begin(engine) };
+    match catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let engine = unsafe { &mut *engine };
+        if let Some(refused) = engine.refuse_mutation() {
+            return refused;
+        }
+        let bridge = match engine.inner.find_module_mut::<LogicModuleBridge>() {
+            Some(b) => b,
+            None => return FactorialResult::InternalError,
+        };
+        bridge.logic_mut().remove_node(ffi_to_node_id(node_id));
+        FactorialResult::Ok
+    })) {
+        Ok(result) => result,
+        Err(_) => {
+            let engine = unsafe { &mut *engine };
+            engine.poisoned = true;
+            FactorialResult::InternalError
+        }
+    }
+}
+
+/// Set an inventory reader on a node. The reader watches `target_node_id`
+/// and emits signals based on its inventory contents.
+///
+/// `source` is `0` for Input, `1` for Output.
+///
+/// # Safety
+///
+/// `engine` must be a valid engine pointer.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_logic_set_inventory_reader(
+    engine: *mut FactorialEngine,
+    node_id: FfiNodeId,
+    target_node_id: FfiNodeId,
+    source: u8,
+) -> FactorialResult {
+    if engine.is_null() {
+        return FactorialResult::NullPointer;
+    }
+    let _mutation = unsafe { MutationGuard::begin(engine) };
+    match catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let engine = unsafe { &mut *engine };
+        if let Some(refused) = engine.refuse_mutation() {
+            return refused;
+        }
+        let bridge = match engine.inner.find_module_mut::<LogicModuleBridge>() {
+            Some(b) => b,
+            None => return FactorialResult::InternalError,
+        };
+        let inv_source = if source == 0 {
+            InventorySource::Input
+        } else {
+            InventorySource::Output
+        };
+        bridge.logic_mut().set_inventory_reader(
+            ffi_to_node_id(node_id),
+            ffi_to_node_id(target_node_id),
+            inv_source,
+        );
+        FactorialResult::Ok
+    })) {
+        Ok(result) => result,
+        Err(_) => {
+            let engine = unsafe { &mut *engine };
+            engine.poisoned = true;
+            FactorialResult::InternalError
+        }
+    }
+}
+
+// ===========================================================================
+// Tests
+// ===========================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // -----------------------------------------------------------------------
+    // Helpers (imported from core test_utils where possible)
+    // -----------------------------------------------------------------------
+
+    use factorial_core::builder::FactoryBuilder;
+    use factorial_core::test_utils::{
+        copper, gear, iron, make_recipe, make_source, simple_inventory,
+    };
+    use std::collections::BTreeMap;
+
+    // -----------------------------------------------------------------------
+    // Test 1: Create and destroy engine lifecycle
+    // -----------------------------------------------------------------------
+    #[test]
+    fn create_and_destroy() {
+        let engine = factorial_create();
+        assert!(!engine.is_null());
+
+        let result = unsafe { factorial_destroy(engine) };
+        assert_eq!(result, FactorialResult::Ok);
+    }
+
+    // -----------------------------------------------------------------------
+    // Test 2: Destroy null pointer returns NullPointer
+    // -----------------------------------------------------------------------
+    #[test]
+    fn destroy_null_returns_null_pointer() {
+        let result = unsafe { factorial_destroy(ptr::null_mut()) };
+        assert_eq!(result, FactorialResult::NullPointer);
+    }
+
+    // -----------------------------------------------------------------------
+    // Test 3: Step null pointer returns NullPointer
+    // -----------------------------------------------------------------------
+    #[test]
+    fn step_null_returns_null_pointer() {
+        let result = unsafe { factorial_step(ptr::null_mut()) };
+        assert_eq!(result, FactorialResult::NullPointer);
+    }
+
+    // -----------------------------------------------------------------------
+    // Test 4: Add node and apply mutations
+    // -----------------------------------------------------------------------
+    #[test]
+    fn add_node_and_apply_mutations() {
+        let engine = factorial_create();
+        assert!(!engine.is_null());
+
+        // Add a node.
+        let mut pending: FfiPendingNodeId = 0;
+        let result = unsafe { factorial_add_node(engine, 0, &mut pending) };
+        assert_eq!(result, FactorialResult::Ok);
+
+        // Apply mutations.
+        let mut mutation_result = FfiMutationResult {
+            added_nodes: ptr::null(),
+            added_node_count: 0,
+            added_edges: ptr::null(),
+            added_edge_count: 0,
+            rejected: ptr::null(),
+            rejected_count: 0,
+        };
+        let result = unsafe { factorial_apply_mutations(engine, &mut mutation_result) };
+        assert_eq!(result, FactorialResult::Ok);
+        assert_eq!(mutation_result.added_node_count, 1);
+
+        // Read back the real node ID.
+        let pairs = unsafe { std::slice::from_raw_parts(mutation_result.added_nodes, 1) };
+        assert_eq!(pairs[0].pending_id, pending);
+        assert_ne!(pairs[0].real_id, 0);
+
+        // Verify node count.
+        let mut count: u32 = 0;
+        let result = unsafe { factorial_node_count(engine, &mut count) };
+        assert_eq!(result, FactorialResult::Ok);
+        assert_eq!(count, 1);
+
+        unsafe { factorial_destroy(engine) };
+    }
+
+    #[test]
+    fn add_node_ex_applies_flags() {
+        let engine = factorial_create();
+        let mut pending: FfiPendingNodeId = 0;
+        let flags = NODE_FLAG_SILENT | NODE_FLAG_NO_STATS | 1 << 31;
+        let result = unsafe { factorial_add_node_ex(engine, 0, flags, &mut pending) };
+        assert_eq!(result, FactorialResult::Ok);
+        let mut mutation_result = FfiMutationResult {
+            added_nodes: ptr::null(),
+            added_node_count: 0,
+            added_edges: ptr::null(),
+            added_edge_count: 0,
+            rejected: ptr::null(),
+            rejected_count: 0,
+        };
+        unsafe { factorial_apply_mutations(engine, &mut mutation_result) };
+        let pairs = unsafe { std::slice::from_raw_parts(mutation_result.added_nodes, 1) };
+        let node = ffi_to_node_id(pairs[0].real_id);
+
+        assert_eq!(NODE_FLAG_SILENT, NodeFlags::SILENT.bits());
+        assert_eq!(NODE_FLAG_NO_STATS, NodeFlags::NO_STATS.bits());
+        let inner = &unsafe { &*engine }.inner;
+        assert_eq!(
+            inner.node_flags(node),
+            Some(NodeFlags::SILENT | NodeFlags::NO_STATS)
+        );
+        assert!(inner.event_bus.is_node_silenced(node));
+        assert_eq!(
+            unsafe { factorial_add_node_ex(engine, 0, 0, ptr::null_mut()) },
+            FactorialResult::NullPointer
+        );
+
+        unsafe { factorial_destroy(engine) };
+    }
+
+    /// Refuses building type 7.
+    #[derive(Debug)]
+    struct LockSeven;
+
+    impl factorial_core::module::Module for LockSeven {
+        fn name(&self) -> &str {
+            "lock_seven"
+        }
+        fn allows_building(&self, building_type: BuildingTypeId) -> bool {
+            building_type != BuildingTypeId(7)
+        }
+        fn as_any(&self) -> &dyn std::any::Any {
+            self
+        }
+        fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+            self
+        }
+    }
+
+    #[test]
+    fn apply_mutations_reports_rejected_additions() {
+        let engine = factorial_create();
+        {
+            let inner = &mut unsafe { &mut *engine }.inner;
+            inner.register_module(Box::new(LockSeven));
+            inner.set_strict_mode(true);
+        }
+        let mut allowed: FfiPendingNodeId = 0;
+        let mut locked: FfiPendingNodeId = 0;
+        unsafe { factorial_add_node(engine, 7, &mut locked) };
+        unsafe { factorial_add_node(engine, 1, &mut allowed) };
+
+        let mut mutation_result = FfiMutationResult {
+            added_nodes: ptr::null(),
+            added_node_count: 0,
+            added_edges: ptr::null(),
+            added_edge_count: 0,
+            rejected: ptr::null(),
+            rejected_count: 0,
+        };
+        let result = unsafe { factorial_apply_mutations(engine, &mut mutation_result) };
+        assert_eq!(result, FactorialResult::Ok);
+        assert_eq!(mutation_result.added_node_count, 1);
+        let pairs = unsafe { std::slice::from_raw_parts(mutation_result.added_nodes, 1) };
+        assert_eq!(pairs[0].pending_id, allowed);
+        assert_eq!(mutation_result.rejected_count, 1);
+        let rejected = unsafe { std::slice::from_raw_parts(mutation_result.rejected, 1) };
+        assert_eq!(rejected[0].pending_id, locked);
+        assert_eq!(rejected[0].reason, 1);
+
+        // An empty batch clears the list.
+        unsafe { factorial_apply_mutations(engine, &mut mutation_result) };
+        assert_eq!(mutation_result.rejected_count, 0);
+        assert!(mutation_result.rejected.is_null());
+        unsafe { factorial_destroy(engine) };
+    }
+
+    // -----------------------------------------------------------------------
+    // Test 5: Add node, step, check tick incremented
+    // -----------------------------------------------------------------------
+    #[test]
+    fn step_increments_tick() {
+        let engine = factorial_create();
+        assert!(!engine.is_null());
+
+        let mut tick: u64 = 0;
+        unsafe { factorial_get_tick(engine, &mut tick) };
+        assert_eq!(tick, 0);
+
+        unsafe { factorial_step(engine) };
+
+        unsafe { factorial_get_tick(engine, &mut tick) };
+        assert_eq!(tick, 1);
+
+        unsafe { factorial_step(engine) };
+        unsafe { factorial_step(engine) };
+
+        unsafe { factorial_get_tick(engine, &mut tick) };
+        assert_eq!(tick, 3);
+
+        unsafe { factorial_destroy(engine) };
+    }
+
+    // -----------------------------------------------------------------------
+    // Test 6: Add node with source processor, step, check output inventory
+    // -----------------------------------------------------------------------
+    #[test]
+    fn add_node_step_produces_output() {
+        let engine_ptr = factorial_create();
+        assert!(!engine_ptr.is_null());
+
+        // Add a node via FFI.
+        let mut pending: FfiPendingNodeId = 0;
+        unsafe { factorial_add_node(engine_ptr, 0, &mut pending) };
+
+        let mut mutation_result = FfiMutationResult {
+            added_nodes: ptr::null(),
+            added_node_count: 0,
+            added_edges: ptr::null(),
+            added_edge_count: 0,
+            rejected: ptr::null(),
+            rejected_count: 0,
+        };
+        unsafe { factorial_apply_mutations(engine_ptr, &mut mutation_result) };
+        let pairs = unsafe { std::slice::from_raw_parts(mutation_result.added_nodes, 1) };
+        let node_ffi_id = pairs[0].real_id;
+
+        // Set up the processor and inventories on the Rust side (direct access
+        // for test setup -- real users would have dedicated FFI for this).
+        let engine = unsafe { &mut *engine_ptr };
+        let node_id = ffi_to_node_id(node_ffi_id);
+        engine
+            .inner
+            .set_processor(node_id, make_source(iron(), 3.0));
+        engine
+            .inner
+            .set_input_inventory(node_id, simple_inventory(100));
+        engine
+            .inner
+            .set_output_inventory(node_id, simple_inventory(100));
+
+        // Step once via FFI.
+        let result = unsafe { factorial_step(engine_ptr) };
+        assert_eq!(result, FactorialResult::Ok);
+
+        // Query output inventory count.
+        let mut count: u32 = 0;
+        let result =
+            unsafe { factorial_get_output_inventory_count(engine_ptr, node_ffi_id, &mut count) };
+        assert_eq!(result, FactorialResult::Ok);
+        assert_eq!(count, 3, "source should produce 3 items per tick");
+
+        // Query processor state.
+        let mut info = FfiProcessorInfo {
+            state: FfiProcessorState::Idle,
+            progress: 0,
+        };
+        let result = unsafe { factorial_get_processor_state(engine_ptr, node_ffi_id, &mut info) };
+        assert_eq!(result, FactorialResult::Ok);
+        assert_eq!(info.state, FfiProcessorState::Working);
+
+        unsafe { factorial_destroy(engine_ptr) };
+    }
+
+    // -----------------------------------------------------------------------
+    // Test 7: Serialize and deserialize through C API
+    // -----------------------------------------------------------------------
+    #[test]
+    fn serialize_deserialize_round_trip() {
+        let engine_ptr = factorial_create();
+
+        // Add a node and step.
+        let mut pending: FfiPendingNodeId = 0;
+        unsafe { factorial_add_node(engine_ptr, 42, &mut pending) };
+        let mut mutation_result = FfiMutationResult {
+            added_nodes: ptr::null(),
+            added_node_count: 0,
+            added_edges: ptr::null(),
+            added_edge_count: 0,
+            rejected: ptr::null(),
+            rejected_count: 0,
+        };
+        unsafe { factorial_apply_mutations(engine_ptr, &mut mutation_result) };
+
+        // Set up processor and inventories.
+        let pairs = unsafe { std::slice::from_raw_parts(mutation_result.added_nodes, 1) };
+        let node_ffi_id = pairs[0].real_id;
+        let engine = unsafe { &mut *engine_ptr };
+        let node_id = ffi_to_node_id(node_ffi_id);
+        engine
+            .inner
+            .set_processor(node_id, make_source(iron(), 2.0));
+        engine
+            .inner
+            .set_input_inventory(node_id, simple_inventory(100));
+        engine
+            .inner
+            .set_output_inventory(node_id, simple_inventory(100));
+
+        // Step a few times.
+        for _ in 0..5 {
+            unsafe { factorial_step(engine_ptr) };
+        }
+
+        // Get state hash before serialize.
+        let mut hash_before: u64 = 0;
+        unsafe { factorial_get_state_hash(engine_ptr, &mut hash_before) };
+
+        // Serialize.
+        let mut buffer = FfiByteBuffer {
+            data: ptr::null_mut(),
+            len: 0,
+        };
+        let result = unsafe { factorial_serialize(engine_ptr, &mut buffer) };
+        assert_eq!(result, FactorialResult::Ok);
+        assert!(!buffer.data.is_null());
+        assert!(buffer.len > 0);
+
+        // Deserialize into a new engine.
+        let mut restored_ptr: *mut FactorialEngine = ptr::null_mut();
+        let result = unsafe { factorial_deserialize(buffer.data, buffer.len, &mut restored_ptr) };
+        assert_eq!(result, FactorialResult::Ok);
+        assert!(!restored_ptr.is_null());
+
+        // Verify state hash matches.
+        let mut hash_after: u64 = 0;
+        unsafe { factorial_get_state_hash(restored_ptr, &mut hash_after) };
+        assert_eq!(hash_before, hash_after);
+
+        // Verify node count.
+        let mut count: u32 = 0;
+        unsafe { factorial_node_count(restored_ptr, &mut count) };
+        assert_eq!(count, 1);
+
+        // Verify tick.
+        let mut tick: u64 = 0;
+        unsafe { factorial_get_tick(restored_ptr, &mut tick) };
+        assert_eq!(tick, 5);
+
+        // Clean up.
+        let result = unsafe { factorial_free_buffer(buffer) };
+        assert_eq!(result, FactorialResult::Ok);
+        unsafe { factorial_destroy(restored_ptr) };
+        unsafe { factorial_destroy(engine_ptr) };
+    }
+
+    // -----------------------------------------------------------------------
+    // Test 8: Poll events returns correct data
+    // -----------------------------------------------------------------------
+    #[test]
+    fn poll_events_returns_data() {
+        let engine_ptr = factorial_create();
+
+        // Add a source node that produces items.
+        let mut pending: FfiPendingNodeId = 0;
+        unsafe { factorial_add_node(engine_ptr, 7, &mut pending) };
+        let mut mutation_result = FfiMutationResult {
+            added_nodes: ptr::null(),
+            added_node_count: 0,
+            added_edges: ptr::null(),
+            added_edge_count: 0,
+            rejected: ptr::null(),
+            rejected_count: 0,
+        };
+        unsafe { factorial_apply_mutations(engine_ptr, &mut mutation_result) };
+        let pairs = unsafe { std::slice::from_raw_parts(mutation_result.added_nodes, 1) };
+        let node_ffi_id = pairs[0].real_id;
+
+        let engine = unsafe { &mut *engine_ptr };
+        let node_id = ffi_to_node_id(node_ffi_id);
+        engine
+            .inner
+            .set_processor(node_id, make_source(iron(), 2.0));
+        engine
+            .inner
+            .set_input_inventory(node_id, simple_inventory(100));
+        engine
+            .inner
+            .set_output_inventory(node_id, simple_inventory(100));
+
+        // Step.
+        unsafe { factorial_step(engine_ptr) };
+
+        // Poll events.
+        let mut event_buffer = FfiEventBuffer {
+            events: ptr::null(),
+            count: 0,
+        };
+        let result = unsafe { factorial_poll_events(engine_ptr, &mut event_buffer) };
+        assert_eq!(result, FactorialResult::Ok);
+
+        // The source produces items, so we should see at least an ItemProduced event.
+        assert!(event_buffer.count > 0, "expected at least one event");
+
+        let events =
+            unsafe { std::slice::from_raw_parts(event_buffer.events, event_buffer.count as usize) };
+
+        // Find the ItemProduced event.
+        let produced = events.iter().find(|e| e.kind == FfiEventKind::ItemProduced);
+        assert!(produced.is_some(), "expected an ItemProduced event");
+
+        let produced = produced.unwrap();
+        assert_eq!(produced.node, node_ffi_id);
+        assert_eq!(produced.item_type, 0); // iron = ItemTypeId(0)
+        assert_eq!(produced.quantity, 2);
+
+        unsafe { factorial_destroy(engine_ptr) };
+    }
+
+    // -----------------------------------------------------------------------
+    // Test 9: Null pointer inputs handled gracefully across all functions
+    // -----------------------------------------------------------------------
+    #[test]
+    fn null_pointer_handling() {
+        // All functions that take engine should return NullPointer when null.
+        assert_eq!(
+            unsafe { factorial_step(ptr::null_mut()) },
+            FactorialResult::NullPointer
+        );
+        assert_eq!(
+            unsafe { factorial_advance(ptr::null_mut(), 0) },
+            FactorialResult::NullPointer
+        );
+        assert_eq!(
+            unsafe { factorial_add_node(ptr::null_mut(), 0, &mut 0u64) },
+            FactorialResult::NullPointer
+        );
+        assert_eq!(
+            unsafe { factorial_remove_node(ptr::null_mut(), 0) },
+            FactorialResult::NullPointer
+        );
+        assert_eq!(
+            unsafe { factorial_connect(ptr::null_mut(), 0, 0, &mut 0u64) },
+            FactorialResult::NullPointer
+        );
+        assert_eq!(
+            unsafe { factorial_disconnect(ptr::null_mut(), 0) },
+            FactorialResult::NullPointer
+        );
+
+        let mut mr = FfiMutationResult {
+            added_nodes: ptr::null(),
+            added_node_count: 0,
+            added_edges: ptr::null(),
+            added_edge_count: 0,
+            rejected: ptr::null(),
+            rejected_count: 0,
+        };
+        assert_eq!(
+            unsafe { factorial_apply_mutations(ptr::null_mut(), &mut mr) },
+            FactorialResult::NullPointer
+        );
+
+        assert_eq!(
+            unsafe { factorial_node_count(ptr::null(), &mut 0u32) },
+            FactorialResult::NullPointer
+        );
+        assert_eq!(
+            unsafe { factorial_edge_count(ptr::null(), &mut 0u32) },
+            FactorialResult::NullPointer
+        );
+        assert_eq!(
+            unsafe { factorial_get_tick(ptr::null(), &mut 0u64) },
+            FactorialResult::NullPointer
+        );
+        assert_eq!(
+            unsafe { factorial_get_state_hash(ptr::null(), &mut 0u64) },
+            FactorialResult::NullPointer
+        );
+
+        let mut info = FfiProcessorInfo {
+            state: FfiProcessorState::Idle,
+            progress: 0,
+        };
+        assert_eq!(
+            unsafe { factorial_get_processor_state(ptr::null(), 0, &mut info) },
+            FactorialResult::NullPointer
+        );
+        assert_eq!(
+            unsafe { factorial_get_input_inventory_count(ptr::null(), 0, &mut 0u32) },
+            FactorialResult::NullPointer
+        );
+        assert_eq!(
+            unsafe { factorial_get_output_inventory_count(ptr::null(), 0, &mut 0u32) },
+            FactorialResult::NullPointer
+        );
+
+        let mut eb = FfiEventBuffer {
+            events: ptr::null(),
+            count: 0,
+        };
+        assert_eq!(
+            unsafe { factorial_poll_events(ptr::null(), &mut eb) },
+            FactorialResult::NullPointer
+        );
+
+        let mut buf = FfiByteBuffer {
+            data: ptr::null_mut(),
+            len: 0,
+        };
+        assert_eq!(
+            unsafe { factorial_serialize(ptr::null(), &mut buf) },
+            FactorialResult::NullPointer
+        );
+        assert_eq!(
+            unsafe { factorial_deserialize(ptr::null(), 0, &mut ptr::null_mut()) },
+            FactorialResult::NullPointer
+        );
+
+        // Also test null output pointer variants.
+        let engine = factorial_create();
+        assert_eq!(
+            unsafe { factorial_add_node(engine, 0, ptr::null_mut()) },
+            FactorialResult::NullPointer
+        );
+        assert_eq!(
+            unsafe { factorial_connect(engine, 0, 0, ptr::null_mut()) },
+            FactorialResult::NullPointer
+        );
+        assert_eq!(
+            unsafe { factorial_node_count(engine, ptr::null_mut()) },
+            FactorialResult::NullPointer
+        );
+        assert_eq!(
+            unsafe { factorial_edge_count(engine, ptr::null_mut()) },
+            FactorialResult::NullPointer
+        );
+        assert_eq!(
+            unsafe { factorial_apply_mutations(engine, ptr::null_mut()) },
+            FactorialResult::NullPointer
+        );
+        assert_eq!(
+            unsafe { factorial_serialize(engine, ptr::null_mut()) },
+            FactorialResult::NullPointer
+        );
+
+        unsafe { factorial_destroy(engine) };
+    }
+
+    // -----------------------------------------------------------------------
+    // Test 10: Connect two nodes and query edge count
+    // -----------------------------------------------------------------------
+    #[test]
+    fn connect_nodes_and_query_edge_count() {
+        let engine = factorial_create();
+
+        // Add two nodes.
+        let mut pending_a: FfiPendingNodeId = 0;
+        let mut pending_b: FfiPendingNodeId = 0;
+        unsafe { factorial_add_node(engine, 0, &mut pending_a) };
+        unsafe { factorial_add_node(engine, 0, &mut pending_b) };
+
+        let mut mr = FfiMutationResult {
+            added_nodes: ptr::null(),
+            added_node_count: 0,
+            added_edges: ptr::null(),
+            added_edge_count: 0,
+            rejected: ptr::null(),
+            rejected_count: 0,
+        };
+        unsafe { factorial_apply_mutations(engine, &mut mr) };
+        assert_eq!(mr.added_node_count, 2);
+
+        let node_pairs = unsafe { std::slice::from_raw_parts(mr.added_nodes, 2) };
+        let node_a = node_pairs[0].real_id;
+        let node_b = node_pairs[1].real_id;
+
+        // Connect A -> B.
+        let mut pending_edge: FfiPendingEdgeId = 0;
+        let result = unsafe { factorial_connect(engine, node_a, node_b, &mut pending_edge) };
+        assert_eq!(result, FactorialResult::Ok);
+
+        let mut mr2 = FfiMutationResult {
+            added_nodes: ptr::null(),
+            added_node_count: 0,
+            added_edges: ptr::null(),
+            added_edge_count: 0,
+            rejected: ptr::null(),
+            rejected_count: 0,
+        };
+        unsafe { factorial_apply_mutations(engine, &mut mr2) };
+        assert_eq!(mr2.added_edge_count, 1);
+        assert_eq!(mr2.added_node_count, 0);
+
+        // Verify edge count.
+        let mut edge_count: u32 = 0;
+        unsafe { factorial_edge_count(engine, &mut edge_count) };
+        assert_eq!(edge_count, 1);
+
+        unsafe { factorial_destroy(engine) };
+    }
+
+    // -----------------------------------------------------------------------
+    // Test 11: Remove node via FFI
+    // -----------------------------------------------------------------------
+    #[test]
+    fn remove_node_via_ffi() {
+        let engine = factorial_create();
+
+        // Add two nodes.
+        let mut pending_a: FfiPendingNodeId = 0;
+        let mut pending_b: FfiPendingNodeId = 0;
+        unsafe { factorial_add_node(engine, 0, &mut pending_a) };
+        unsafe { factorial_add_node(engine, 0, &mut pending_b) };
+
+        let mut mr = FfiMutationResult {
+            added_nodes: ptr::null(),
+            added_node_count: 0,
+            added_edges: ptr::null(),
+            added_edge_count: 0,
+            rejected: ptr::null(),
+            rejected_count: 0,
+        };
+        unsafe { factorial_apply_mutations(engine, &mut mr) };
+        let node_pairs = unsafe { std::slice::from_raw_parts(mr.added_nodes, 2) };
+        let node_a = node_pairs[0].real_id;
+
+        // Verify 2 nodes.
+        let mut count: u32 = 0;
+        unsafe { factorial_node_count(engine, &mut count) };
+        assert_eq!(count, 2);
+
+        // Remove node A.
+        let result = unsafe { factorial_remove_node(engine, node_a) };
+        assert_eq!(result, FactorialResult::Ok);
+
+        let mut mr2 = FfiMutationResult {
+            added_nodes: ptr::null(),
+            added_node_count: 0,
+            added_edges: ptr::null(),
+            added_edge_count: 0,
+            rejected: ptr::null(),
+            rejected_count: 0,
+        };
+        unsafe { factorial_apply_mutations(engine, &mut mr2) };
+
+        // Verify 1 node remaining.
+        unsafe { factorial_node_count(engine, &mut count) };
+        assert_eq!(count, 1);
+
+        unsafe { factorial_destroy(engine) };
+    }
+
+    // -----------------------------------------------------------------------
+    // Test 12: Disconnect edge via FFI
+    // -----------------------------------------------------------------------
+    #[test]
+    fn disconnect_edge_via_ffi() {
+        let engine = factorial_create();
+
+        // Add two nodes and connect them.
+        let mut pending_a: FfiPendingNodeId = 0;
+        let mut pending_b: FfiPendingNodeId = 0;
+        unsafe { factorial_add_node(engine, 0, &mut pending_a) };
+        unsafe { factorial_add_node(engine, 0, &mut pending_b) };
+
+        let mut mr = FfiMutationResult {
+            added_nodes: ptr::null(),
+            added_node_count: 0,
+            added_edges: ptr::null(),
+            added_edge_count: 0,
+            rejected: ptr::null(),
+            rejected_count: 0,
+        };
+        unsafe { factorial_apply_mutations(engine, &mut mr) };
+        let node_pairs = unsafe { std::slice::from_raw_parts(mr.added_nodes, 2) };
+        let node_a = node_pairs[0].real_id;
+        let node_b = node_pairs[1].real_id;
+
+        let mut pending_edge: FfiPendingEdgeId = 0;
+        unsafe { factorial_connect(engine, node_a, node_b, &mut pending_edge) };
+        let mut mr2 = FfiMutationResult {
+            added_nodes: ptr::null(),
+            added_node_count: 0,
+            added_edges: ptr::null(),
+            added_edge_count: 0,
+            rejected: ptr::null(),
+            rejected_count: 0,
+        };
+        unsafe { factorial_apply_mutations(engine, &mut mr2) };
+        let edge_pairs = unsafe { std::slice::from_raw_parts(mr2.added_edges, 1) };
+        let edge_id = edge_pairs[0].real_id;
+
+        // Verify edge exists.
+        let mut edge_count: u32 = 0;
+        unsafe { factorial_edge_count(engine, &mut edge_count) };
+        assert_eq!(edge_count, 1);
+
+        // Disconnect.
+        let result = unsafe { factorial_disconnect(engine, edge_id) };
+        assert_eq!(result, FactorialResult::Ok);
+        let mut mr3 = FfiMutationResult {
+            added_nodes: ptr::null(),
+            added_node_count: 0,
+            added_edges: ptr::null(),
+            added_edge_count: 0,
+            rejected: ptr::null(),
+            rejected_count: 0,
+        };
+        unsafe { factorial_apply_mutations(engine, &mut mr3) };
+
+        // Verify edge removed.
+        unsafe { factorial_edge_count(engine, &mut edge_count) };
+        assert_eq!(edge_count, 0);
+
+        unsafe { factorial_destroy(engine) };
+    }
+
+    // -----------------------------------------------------------------------
+    // Test 13: Deserialize invalid data returns DeserializeError
+    // -----------------------------------------------------------------------
+    #[test]
+    fn deserialize_invalid_data() {
+        let garbage = [0u8; 10];
+        let mut engine_ptr: *mut FactorialEngine = ptr::null_mut();
+        let result =
+            unsafe { factorial_deserialize(garbage.as_ptr(), garbage.len(), &mut engine_ptr) };
+        assert_eq!(result, FactorialResult::DeserializeError);
+        assert!(engine_ptr.is_null());
+    }
+
+    // -----------------------------------------------------------------------
+    // Test 14: Free null buffer is a no-op (not an error)
+    // -----------------------------------------------------------------------
+    #[test]
+    fn free_null_buffer_is_noop() {
+        let buffer = FfiByteBuffer {
+            data: ptr::null_mut(),
+            len: 0,
+        };
+        let result = unsafe { factorial_free_buffer(buffer) };
+        assert_eq!(result, FactorialResult::Ok);
+    }
+
+    // -----------------------------------------------------------------------
+    // Test 15: Create delta engine
+    // -----------------------------------------------------------------------
+    #[test]
+    fn create_delta_engine() {
+        let engine = factorial_create_delta(2);
+        assert!(!engine.is_null());
+
+        // Advance by 5 ticks with fixed_timestep=2 should run 2 steps.
+        let result = unsafe { factorial_advance(engine, 5) };
+        assert_eq!(result, FactorialResult::Ok);
+
+        let mut tick: u64 = 0;
+        unsafe { factorial_get_tick(engine, &mut tick) };
+        assert_eq!(tick, 2); // 5 / 2 = 2 full steps
+
+        unsafe { factorial_destroy(engine) };
+    }
+
+    #[test]
+    fn set_strategy_switches_stepping_via_ffi() {
+        let (engine, miner, _) = two_node_ffi_engine();
+        unsafe { factorial_step(engine) };
+        let mut strategy = FfiSimulationStrategy::Delta;
+        let (mut timestep, mut discarded, mut tick) = (7u64, 7u64, 0u64);
+        unsafe { factorial_get_strategy(engine, &mut strategy, &mut timestep) };
+        assert_eq!((strategy, timestep), (FfiSimulationStrategy::Tick, 0));
+
+        let result = unsafe {
+            factorial_set_strategy(engine, FfiSimulationStrategy::Delta, 3, &mut discarded)
+        };
+        assert_eq!(result, FactorialResult::Ok);
+        assert_eq!(discarded, 0);
+        unsafe { factorial_get_strategy(engine, &mut strategy, &mut timestep) };
+        assert_eq!((strategy, timestep), (FfiSimulationStrategy::Delta, 3));
+
+        // Seven ticks run two steps and leave one tick behind.
+        unsafe { factorial_advance(engine, 7) };
+        unsafe { factorial_get_tick(engine, &mut tick) };
+        assert_eq!(tick, 3);
+        let mut produced = 0u32;
+        unsafe { factorial_get_output_inventory_count(engine, miner, &mut produced) };
+        assert!(produced > 0);
+
+        let result = unsafe {
+            factorial_set_strategy(engine, FfiSimulationStrategy::Tick, 0, &mut discarded)
+        };
+        assert_eq!(result, FactorialResult::Ok);
+        assert_eq!(discarded, 1);
+        unsafe { factorial_advance(engine, 50) };
+        unsafe { factorial_get_tick(engine, &mut tick) };
+        assert_eq!(tick, 4);
+
+        let result = unsafe {
+            factorial_set_strategy(engine, FfiSimulationStrategy::Delta, 1, ptr::null_mut())
+        };
+        assert_eq!(result, FactorialResult::Ok);
+        assert_eq!(
+            unsafe {
+                factorial_set_strategy(
+                    ptr::null_mut(),
+                    FfiSimulationStrategy::Tick,
+                    0,
+                    ptr::null_mut(),
+                )
+            },
+            FactorialResult::NullPointer
+        );
+
+        unsafe { factorial_destroy(engine) };
+    }
+
+    #[test]
+    fn sliced_ticks_refuse_mutations_until_complete() {
+        let (whole, _, _) = two_node_ffi_engine();
+        let (sliced, miner, _) = two_node_ffi_engine();
+        let mut status = FfiTickSliceStatus::TickSliceComplete;
+        for _ in 0..5 {
+            unsafe { factorial_step(whole) };
+            assert_eq!(unsafe { factorial_begin_tick(sliced) }, FactorialResult::Ok);
+            assert_eq!(
+                unsafe { factorial_begin_tick(sliced) },
+                FactorialResult::TickInProgress
+            );
+            assert_eq!(
+                unsafe { factorial_set_input_capacity(sliced, miner, 5) },
+                FactorialResult::TickInProgress
+            );
+            let mut slices = 0;
+            loop {
+                assert_eq!(
+                    unsafe { factorial_continue_tick(sliced, 1, &mut status) },
+                    FactorialResult::Ok
+                );
+                slices += 1;
+                if status == FfiTickSliceStatus::TickSliceComplete {
+                    break;
+                }
+            }
+            assert!(slices > 1);
+        }
+        let (mut a, mut b) = (0u64, 0u64);
+        unsafe { factorial_get_state_hash(whole, &mut a) };
+        unsafe { factorial_get_state_hash(sliced, &mut b) };
+        assert_eq!(a, b);
+        assert_eq!(
+            unsafe { factorial_set_input_capacity(sliced, miner, 5) },
+            FactorialResult::Ok
+        );
+        assert_eq!(
+            unsafe { factorial_continue_tick(sliced, 1, ptr::null_mut()) },
+            FactorialResult::NullPointer
+        );
+        unsafe { factorial_destroy(whole) };
+        unsafe { factorial_destroy(sliced) };
+    }
+
+    #[test]
+    fn step_work_limit_reports_truncated_steps() {
+        let (engine, _, _) = two_node_ffi_engine();
+        let mut tick = 0u64;
+        assert_eq!(
+            unsafe { factorial_set_step_work_limit(engine, 1) },
+            FactorialResult::Ok
+        );
+        assert_eq!(
+            unsafe { factorial_step(engine) },
+            FactorialResult::WorkLimitReached
+        );
+        unsafe { factorial_get_tick(engine, &mut tick) };
         assert_eq!(tick, 1);
 
+        let mut buffer = FfiEventBuffer {
+            events: ptr::null(),
+            count: 0,
+        };
+        unsafe { factorial_poll_events(engine, &mut buffer) };
+        let events = unsafe { std::slice::from_raw_parts(buffer.events, buffer.count as usize) };
+        let truncated = events
+            .iter()
+            .find(|e| e.kind == FfiEventKind::StepTruncated)
+            .unwrap();
+        assert_eq!(truncated.tick, 0);
+        assert!(truncated.quantity > 1);
+        assert!(truncated.building_type <= 3);
+
+        unsafe { factorial_set_step_work_limit(engine, 0) };
+        assert_eq!(unsafe { factorial_step(engine) }, FactorialResult::Ok);
+        assert_eq!(
+            unsafe { factorial_set_step_work_limit(ptr::null_mut(), 5) },
+            FactorialResult::NullPointer
+        );
+        unsafe { factorial_destroy(engine) };
+    }
+
+    #[test]
+    fn storage_limit_caps_what_a_chest_accepts() {
+        let mut builder = FactoryBuilder::new();
+        builder.node("miner").source(iron(), 1.0);
+        builder.node("chest").passthrough();
+        builder.flow("miner", "chest", 1.0);
+        let (engine, nodes) = ffi_engine_from_builder(&builder);
+        let (miner, chest) = (
+            node_id_to_ffi(nodes["miner"]),
+            node_id_to_ffi(nodes["chest"]),
+        );
+        assert_eq!(
+            unsafe { factorial_set_storage_processor(engine, chest, 0, 3, false) },
+            FactorialResult::InvalidArgument
+        );
+        assert_eq!(
+            unsafe { factorial_set_storage_processor(engine, chest, 2, 3, false) },
+            FactorialResult::Ok
+        );
+        assert_eq!(
+            unsafe { factorial_set_storage_limit(engine, chest, 1) },
+            FactorialResult::Ok
+        );
+
+        let held = |engine| {
+            let mut count = 0u32;
+            unsafe { factorial_get_output_inventory_count(engine, chest, &mut count) };
+            count
+        };
+        for _ in 0..10 {
+            unsafe { factorial_step(engine) };
+        }
+        assert_eq!(held(engine), 3);
+        let mut info = FfiProcessorInfo {
+            state: FfiProcessorState::Idle,
+            progress: 0,
+        };
+        unsafe { factorial_get_processor_state(engine, chest, &mut info) };
+        assert_eq!(info.state, FfiProcessorState::Storing);
+
+        unsafe { factorial_set_storage_limit(engine, chest, NO_STORAGE_LIMIT) };
+        for _ in 0..10 {
+            unsafe { factorial_step(engine) };
+        }
+        assert_eq!(held(engine), 6);
+        assert_eq!(
+            unsafe { factorial_set_storage_limit(engine, miner, 1) },
+            FactorialResult::KindMismatch
+        );
+        unsafe { factorial_destroy(engine) };
+    }
+
+    // -----------------------------------------------------------------------
+    // Test 16: Multiple steps accumulate state correctly
+    // -----------------------------------------------------------------------
+    #[test]
+    fn multiple_steps_accumulate() {
+        let engine_ptr = factorial_create();
+
+        // Add a source node.
+        let mut pending: FfiPendingNodeId = 0;
+        unsafe { factorial_add_node(engine_ptr, 0, &mut pending) };
+        let mut mr = FfiMutationResult {
+            added_nodes: ptr::null(),
+            added_node_count: 0,
+            added_edges: ptr::null(),
+            added_edge_count: 0,
+            rejected: ptr::null(),
+            rejected_count: 0,
+        };
+        unsafe { factorial_apply_mutations(engine_ptr, &mut mr) };
+        let pairs = unsafe { std::slice::from_raw_parts(mr.added_nodes, 1) };
+        let node_ffi_id = pairs[0].real_id;
+
+        let engine = unsafe { &mut *engine_ptr };
+        let node_id = ffi_to_node_id(node_ffi_id);
+        engine
+            .inner
+            .set_processor(node_id, make_source(iron(), 1.0));
+        engine
+            .inner
+            .set_input_inventory(node_id, simple_inventory(100));
+        engine
+            .inner
+            .set_output_inventory(node_id, simple_inventory(100));
+
+        // Step 10 times.
+        for _ in 0..10 {
+            unsafe { factorial_step(engine_ptr) };
+        }
+
+        // Output should have 10 items.
+        let mut count: u32 = 0;
+        unsafe { factorial_get_output_inventory_count(engine_ptr, node_ffi_id, &mut count) };
+        assert_eq!(count, 10);
+
+        unsafe { factorial_destroy(engine_ptr) };
+    }
+
+    // -----------------------------------------------------------------------
+    // Test 17: Processor state for non-existent node returns NodeNotFound
+    // -----------------------------------------------------------------------
+    #[test]
+    fn processor_state_node_not_found() {
+        let engine = factorial_create();
+
+        let mut info = FfiProcessorInfo {
+            state: FfiProcessorState::Idle,
+            progress: 0,
+        };
+        // Use a bogus node ID.
+        let result = unsafe { factorial_get_processor_state(engine, 9999, &mut info) };
+        assert_eq!(result, FactorialResult::NodeNotFound);
+
+        unsafe { factorial_destroy(engine) };
+    }
+
+    // -----------------------------------------------------------------------
+    // Test 18: Inventory count for non-existent node returns NodeNotFound
+    // -----------------------------------------------------------------------
+    #[test]
+    fn inventory_count_node_not_found() {
+        let engine = factorial_create();
+
+        let mut count: u32 = 0;
+        let result = unsafe { factorial_get_input_inventory_count(engine, 9999, &mut count) };
+        assert_eq!(result, FactorialResult::NodeNotFound);
+
+        let result = unsafe { factorial_get_output_inventory_count(engine, 9999, &mut count) };
+        assert_eq!(result, FactorialResult::NodeNotFound);
+
+        unsafe { factorial_destroy(engine) };
+    }
+
+    // -----------------------------------------------------------------------
+    // Test 19: Full lifecycle -- add nodes, connect, step, serialize, restore
+    // -----------------------------------------------------------------------
+    #[test]
+    fn full_lifecycle_integration() {
+        let engine_ptr = factorial_create();
+
+        // Add source and consumer nodes.
+        let mut pending_src: FfiPendingNodeId = 0;
+        let mut pending_consumer: FfiPendingNodeId = 0;
+        unsafe { factorial_add_node(engine_ptr, 0, &mut pending_src) };
+        unsafe { factorial_add_node(engine_ptr, 1, &mut pending_consumer) };
+
+        let mut mr = FfiMutationResult {
+            added_nodes: ptr::null(),
+            added_node_count: 0,
+            added_edges: ptr::null(),
+            added_edge_count: 0,
+            rejected: ptr::null(),
+            rejected_count: 0,
+        };
+        unsafe { factorial_apply_mutations(engine_ptr, &mut mr) };
+        assert_eq!(mr.added_node_count, 2);
+
+        let pairs = unsafe { std::slice::from_raw_parts(mr.added_nodes, 2) };
+        let src_ffi = pairs[0].real_id;
+        let consumer_ffi = pairs[1].real_id;
+
+        // Connect source -> consumer.
+        let mut pending_edge: FfiPendingEdgeId = 0;
+        unsafe { factorial_connect(engine_ptr, src_ffi, consumer_ffi, &mut pending_edge) };
+        let mut mr2 = FfiMutationResult {
+            added_nodes: ptr::null(),
+            added_node_count: 0,
+            added_edges: ptr::null(),
+            added_edge_count: 0,
+            rejected: ptr::null(),
+            rejected_count: 0,
+        };
+        unsafe { factorial_apply_mutations(engine_ptr, &mut mr2) };
+        assert_eq!(mr2.added_edge_count, 1);
+
+        // Set up processors via direct access.
+        let engine = unsafe { &mut *engine_ptr };
+        let src_id = ffi_to_node_id(src_ffi);
+        let consumer_id = ffi_to_node_id(consumer_ffi);
+
+        engine.inner.set_processor(src_id, make_source(iron(), 5.0));
+        engine
+            .inner
+            .set_input_inventory(src_id, simple_inventory(100));
+        engine
+            .inner
+            .set_output_inventory(src_id, simple_inventory(100));
+
+        engine.inner.set_processor(
+            consumer_id,
+            make_recipe(vec![(iron(), 2)], vec![(gear(), 1)], 3),
+        );
+        let mut consumer_input = simple_inventory(100);
+        let _ = consumer_input.input_slots[0].add(iron(), 10);
+        engine
+            .inner
+            .set_input_inventory(consumer_id, consumer_input);
+        engine
+            .inner
+            .set_output_inventory(consumer_id, simple_inventory(100));
+
+        // Step 10 times.
+        for _ in 0..10 {
+            unsafe { factorial_step(engine_ptr) };
+        }
+
+        // Verify counts.
+        let mut node_count: u32 = 0;
+        let mut edge_count: u32 = 0;
+        unsafe { factorial_node_count(engine_ptr, &mut node_count) };
+        unsafe { factorial_edge_count(engine_ptr, &mut edge_count) };
+        assert_eq!(node_count, 2);
+        assert_eq!(edge_count, 1);
+
+        // Serialize.
+        let mut buffer = FfiByteBuffer {
+            data: ptr::null_mut(),
+            len: 0,
+        };
+        let result = unsafe { factorial_serialize(engine_ptr, &mut buffer) };
+        assert_eq!(result, FactorialResult::Ok);
+        assert!(buffer.len > 0);
+
+        // Get original hash.
+        let mut original_hash: u64 = 0;
+        unsafe { factorial_get_state_hash(engine_ptr, &mut original_hash) };
+
+        // Deserialize.
+        let mut restored_ptr: *mut FactorialEngine = ptr::null_mut();
+        let result = unsafe { factorial_deserialize(buffer.data, buffer.len, &mut restored_ptr) };
+        assert_eq!(result, FactorialResult::Ok);
+
+        // Verify restored state.
+        let mut restored_hash: u64 = 0;
+        unsafe { factorial_get_state_hash(restored_ptr, &mut restored_hash) };
+        assert_eq!(original_hash, restored_hash);
+
+        let mut restored_tick: u64 = 0;
+        unsafe { factorial_get_tick(restored_ptr, &mut restored_tick) };
+        assert_eq!(restored_tick, 10);
+
+        // Clean up.
+        unsafe { factorial_free_buffer(buffer) };
+        unsafe { factorial_destroy(restored_ptr) };
+        unsafe { factorial_destroy(engine_ptr) };
+    }
+
+    // -----------------------------------------------------------------------
+    // Test 20: Event polling for empty engine returns zero events
+    // -----------------------------------------------------------------------
+    #[test]
+    fn poll_events_empty_engine() {
+        let engine = factorial_create();
+
+        // Step without any nodes.
+        unsafe { factorial_step(engine) };
+
+        let mut eb = FfiEventBuffer {
+            events: ptr::null(),
+            count: 0,
+        };
+        let result = unsafe { factorial_poll_events(engine, &mut eb) };
+        assert_eq!(result, FactorialResult::Ok);
+        assert_eq!(eb.count, 0);
+
+        unsafe { factorial_destroy(engine) };
+    }
+
+    // -----------------------------------------------------------------------
+    // Test 21: Poisoned engine blocks calls
+    // -----------------------------------------------------------------------
+    #[test]
+    fn poisoned_engine_blocks_calls() {
+        let engine = factorial_create();
+        assert!(!engine.is_null());
+        let engine_ref = unsafe { &mut *engine };
+        engine_ref.poisoned = true;
+        assert!(unsafe { factorial_is_poisoned(engine) });
+        let result = unsafe { factorial_step(engine) };
+        assert_eq!(result, FactorialResult::Poisoned);
+        let mut tick: u64 = 0;
+        let result = unsafe { factorial_get_tick(engine, &mut tick) };
+        assert_eq!(result, FactorialResult::Poisoned);
+        unsafe { factorial_destroy(engine) };
+    }
+
+    // -----------------------------------------------------------------------
+    // Test 22: Clear poison allows resume
+    // -----------------------------------------------------------------------
+    #[test]
+    fn clear_poison_allows_resume() {
+        let engine = factorial_create();
+        let engine_ref = unsafe { &mut *engine };
+        engine_ref.poisoned = true;
+        assert!(unsafe { factorial_is_poisoned(engine) });
+        let result = unsafe { factorial_clear_poison(engine) };
+        assert_eq!(result, FactorialResult::Ok);
+        assert!(!unsafe { factorial_is_poisoned(engine) });
+        let result = unsafe { factorial_step(engine) };
+        assert_eq!(result, FactorialResult::Ok);
+        unsafe { factorial_destroy(engine) };
+    }
+
+    #[test]
+    fn panicking_tick_hook_poisons_engine() {
+        let engine = factorial_create();
+        let engine_ref = unsafe { &mut *engine };
+        engine_ref.inner.register_tick_hook(
+            factorial_core::hook::TickPhase::PostProcessors,
+            0,
+            Box::new(|_| panic!("hook failure")),
+        );
+        let result = unsafe { factorial_step(engine) };
+        assert_eq!(result, FactorialResult::InternalError);
+        assert!(unsafe { factorial_is_poisoned(engine) });
+        let result = unsafe { factorial_step(engine) };
+        assert_eq!(result, FactorialResult::Poisoned);
+
+        // The hook went with the unwind, so clearing poison resumes.
+        assert_eq!(
+            unsafe { factorial_clear_poison(engine) },
+            FactorialResult::Ok
+        );
+        assert_eq!(unsafe { factorial_step(engine) }, FactorialResult::Ok);
+        unsafe { factorial_destroy(engine) };
+    }
+
+    // -----------------------------------------------------------------------
+    // Test 23: New engine not poisoned
+    // -----------------------------------------------------------------------
+    #[test]
+    fn new_engine_not_poisoned() {
+        let engine = factorial_create();
+        assert!(!unsafe { factorial_is_poisoned(engine) });
+        unsafe { factorial_destroy(engine) };
+    }
+
+    #[test]
+    fn latency_histogram_fills_caller_buffer() {
+        let mut builder = FactoryBuilder::new();
+        builder.node("miner").source(iron(), 1.0);
+        builder.node("chest").passthrough();
+        builder.belt("miner", "chest", 1.0);
+        let (engine, _) = ffi_engine_from_builder(&builder);
+        let edge = edge_id_to_ffi(unsafe { &*engine }.inner.graph.edges().next().unwrap().0);
+
+        let bucket_edges = [2u64, 10];
+        unsafe {
+            factorial_set_latency_buckets(engine, bucket_edges.as_ptr(), 2);
+            factorial_set_latency_tracking(engine, edge, true);
+        }
+        for _ in 0..30 {
+            unsafe { factorial_step(engine) };
+        }
+
+        let mut written = 0u32;
+        let rc = unsafe {
+            factorial_get_edge_latency_histogram(engine, edge, ptr::null_mut(), 0, &mut written)
+        };
+        assert_eq!(rc, FactorialResult::BufferTooSmall);
+        assert_eq!(written, 3);
+
+        let mut counts = [0u64; 3];
+        let rc = unsafe {
+            factorial_get_edge_latency_histogram(engine, edge, counts.as_mut_ptr(), 3, &mut written)
+        };
+        assert_eq!(rc, FactorialResult::Ok);
+        let core = unsafe { &*engine }
+            .inner
+            .edge_latency_histogram(ffi_to_edge_id(edge));
+        assert_eq!(counts, core);
+        assert!(counts[1] > 0);
+
+        unsafe { factorial_reset_latency_histograms(engine) };
+        unsafe {
+            factorial_get_edge_latency_histogram(engine, edge, counts.as_mut_ptr(), 3, &mut written)
+        };
+        assert_eq!(counts, [0; 3]);
+
+        unsafe { factorial_set_latency_tracking(engine, edge, false) };
+        let rc = unsafe {
+            factorial_get_edge_latency_histogram(engine, edge, ptr::null_mut(), 0, &mut written)
+        };
+        assert_eq!((rc, written), (FactorialResult::Ok, 0));
+        let missing = edge_id_to_ffi(EdgeId::default());
+        assert_eq!(
+            unsafe { factorial_set_latency_tracking(engine, missing, true) },
+            FactorialResult::EdgeNotFound
+        );
+        unsafe { factorial_destroy(engine) };
+    }
+
+    // -----------------------------------------------------------------------
+    // Configuration read-back
+    // -----------------------------------------------------------------------
+    fn two_node_ffi_engine() -> (*mut FactorialEngine, FfiNodeId, FfiEdgeId) {
+        let mut builder = FactoryBuilder::new();
+        builder.node("miner").source(iron(), 1.0);
+        builder.node("chest").passthrough();
+        builder.flow("miner", "chest", 1.0);
+        let (engine, nodes) = ffi_engine_from_builder(&builder);
+        let edge = unsafe { &*engine }.inner.graph.edges().next().unwrap().0;
+        (engine, node_id_to_ffi(nodes["miner"]), edge_id_to_ffi(edge))
+    }
+
+    #[test]
+    fn ghosts_are_planned_connected_and_materialized() {
+        let (engine, miner, _) = two_node_ffi_engine();
+        let apply = || {
+            let mut result = FfiMutationResult {
+                added_nodes: ptr::null(),
+                added_node_count: 0,
+                added_edges: ptr::null(),
+                added_edge_count: 0,
+                rejected: ptr::null(),
+                rejected_count: 0,
+            };
+            assert_eq!(
+                unsafe { factorial_apply_mutations(engine, &mut result) },
+                FactorialResult::Ok
+            );
+            result
+        };
+        let mut pending = 0;
+        assert_eq!(
+            unsafe { factorial_add_ghost(engine, 2, &mut pending) },
+            FactorialResult::Ok
+        );
+        let ghost = unsafe { std::slice::from_raw_parts(apply().added_nodes, 1) }[0].real_id;
+
+        // Real edges to a ghost are dropped; ghost edges are added.
+        let mut real = 0;
+        let mut planned = 0;
+        unsafe {
+            factorial_connect(engine, miner, ghost, &mut real);
+            factorial_connect_ghost(engine, miner, ghost, &mut planned);
+        }
+        let result = apply();
+        assert_eq!(result.added_edge_count, 1);
+        let pair = unsafe { std::slice::from_raw_parts(result.added_edges, 1) }[0];
+        assert_eq!(pair.pending_id, planned);
+        let mut status = FfiNodeStatus::Active;
+        assert_eq!(
+            unsafe { factorial_get_edge_status(engine, pair.real_id, &mut status) },
+            FactorialResult::Ok
+        );
+        assert_eq!(status, FfiNodeStatus::Ghost);
+
+        let mut count = 0;
+        unsafe { factorial_ghost_count(engine, &mut count) };
+        assert_eq!(count, 1);
+        assert_eq!(
+            unsafe { factorial_set_hash_ghosts(engine, true) },
+            FactorialResult::Ok
+        );
+        assert!(unsafe { &*engine }.inner.hashes_ghosts());
+        assert_eq!(
+            unsafe { factorial_materialize_ghost(engine, ghost) },
+            FactorialResult::Ok
+        );
+        assert_eq!(
+            unsafe { factorial_materialize_ghost(engine, ghost) },
+            FactorialResult::NodeNotFound
+        );
+        unsafe { factorial_ghost_count(engine, &mut count) };
+        assert_eq!(count, 0);
+        unsafe { factorial_destroy(engine) };
+    }
+
+    #[test]
+    fn operation_logger_receives_records_until_cleared() {
+        unsafe extern "C" fn collect(
+            record: *const FfiOperationRecord,
+            user_data: *mut std::ffi::c_void,
+        ) {
+            let records = unsafe { &mut *(user_data as *mut Vec<FfiOperationRecord>) };
+            records.push(unsafe { *record });
+        }
+
+        let (engine, miner, edge) = two_node_ffi_engine();
+        let mut records: Vec<FfiOperationRecord> = Vec::new();
+        let user_data = &mut records as *mut Vec<FfiOperationRecord> as *mut std::ffi::c_void;
+        assert_eq!(
+            unsafe { factorial_set_operation_logger(engine, Some(collect), user_data) },
+            FactorialResult::Ok
+        );
+        unsafe { factorial_step(engine) };
+        unsafe { factorial_step(engine) };
+        let kinds: Vec<_> = records.iter().map(|r| r.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                FfiOperationKind::OpProduced,
+                FfiOperationKind::OpStepCompleted,
+                FfiOperationKind::OpDelivered,
+                // The miner, then the chest passing its delivery through.
+                FfiOperationKind::OpProduced,
+                FfiOperationKind::OpProduced,
+                FfiOperationKind::OpStepCompleted,
+            ]
+        );
+        assert_eq!((records[0].node, records[0].edge), (miner, 0));
+        assert_eq!(records[2].edge, edge);
+        assert_eq!(records[3].node, miner);
+        assert_eq!(records[4].node, records[2].node);
+        assert_eq!((records[5].tick, records[5].node), (1, 0));
+
+        assert_eq!(
+            unsafe { factorial_set_operation_logger(engine, None, ptr::null_mut()) },
+            FactorialResult::Ok
+        );
+        records.clear();
+        unsafe { factorial_step(engine) };
+        assert!(records.is_empty());
+        unsafe { factorial_destroy(engine) };
+    }
+
+    #[test]
+    fn topology_is_copied_as_csr_and_refetched_by_generation() {
+        let (engine, miner, edge) = two_node_ffi_engine();
+        let (mut nodes, mut edges) = (0u32, 0u32);
+        assert_eq!(
+            unsafe {
+                factorial_get_topology(
+                    engine,
+                    ptr::null_mut(),
+                    0,
+                    ptr::null_mut(),
+                    0,
+                    ptr::null_mut(),
+                    0,
+                    &mut nodes,
+                    &mut edges,
+                )
+            },
+            FactorialResult::BufferTooSmall
+        );
+        assert_eq!((nodes, edges), (2, 1));
+
+        let mut node_ids = [0 as FfiNodeId; 2];
+        let mut offsets = [0u32; 3];
+        let mut out_edges = [FfiTopologyEdge {
+            edge: 0,
+            target: 0,
+            kind: FfiTransportKind::Item,
+            has_transport: false,
+        }];
+        let fetch = |node_ids: &mut [FfiNodeId; 2],
+                     offsets: &mut [u32; 3],
+                     out_edges: &mut [FfiTopologyEdge; 1]| {
+            let (mut nodes, mut edges) = (0u32, 0u32);
+            unsafe {
+                factorial_get_topology(
+                    engine,
+                    node_ids.as_mut_ptr(),
+                    2,
+                    offsets.as_mut_ptr(),
+                    3,
+                    out_edges.as_mut_ptr(),
+                    1,
+                    &mut nodes,
+                    &mut edges,
+                )
+            }
+        };
+        assert_eq!(
+            fetch(&mut node_ids, &mut offsets, &mut out_edges),
+            FactorialResult::Ok
+        );
+        let source = node_ids.iter().position(|&n| n == miner).unwrap();
+        assert_eq!(offsets[source + 1] - offsets[source], 1);
+        assert_eq!(offsets[2], 1);
+        assert_eq!(out_edges[0].edge, edge);
+        assert_eq!(out_edges[0].target as usize, 1 - source);
+        assert_eq!(out_edges[0].kind, FfiTransportKind::Flow);
+        assert!(out_edges[0].has_transport);
+
+        let mut before = 0u64;
+        let mut after = 0u64;
+        unsafe { factorial_topology_generation(engine, &mut before) };
+        unsafe { factorial_set_input_capacity(engine, miner, 5) };
+        unsafe { factorial_topology_generation(engine, &mut after) };
+        assert_eq!(before, after);
+        unsafe { factorial_set_batch_transport(engine, edge, 4, 10) };
+        unsafe { factorial_topology_generation(engine, &mut after) };
+        assert_eq!(before, after);
+        fetch(&mut node_ids, &mut offsets, &mut out_edges);
+        assert_eq!(out_edges[0].kind, FfiTransportKind::Batch);
+        unsafe { factorial_destroy(engine) };
+    }
+
+    #[test]
+    fn transport_getters_round_trip_their_setters() {
+        let (engine, _, edge) = two_node_ffi_engine();
+        let rate = Fixed64::from_num(2.5).to_bits();
+        let (mut a, mut b, mut c) = (0i64, 0i64, 0u32);
+        let (mut x, mut y, mut lanes) = (0u32, 0u32, 0u8);
+
+        unsafe { factorial_set_flow_transport(engine, edge, rate) };
+        assert_eq!(
+            unsafe { factorial_get_flow_transport(engine, edge, &mut a, &mut b, &mut c) },
+            FactorialResult::Ok
+        );
+        assert_eq!((a, b, c), (rate, Fixed64::from_num(1000).to_bits(), 0));
+
+        unsafe { factorial_set_item_transport(engine, edge, rate, 12, 2) };
+        assert_eq!(
+            unsafe { factorial_get_item_transport(engine, edge, &mut a, &mut x, &mut lanes) },
+            FactorialResult::Ok
+        );
+        assert_eq!((a, x, lanes), (rate, 12, 2));
+
+        unsafe { factorial_set_batch_transport(engine, edge, 8, 30) };
+        assert_eq!(
+            unsafe { factorial_get_batch_transport(engine, edge, &mut x, &mut y) },
+            FactorialResult::Ok
+        );
+        assert_eq!((x, y), (8, 30));
+
+        unsafe { factorial_set_vehicle_transport(engine, edge, 40, 90) };
+        assert_eq!(
+            unsafe { factorial_get_vehicle_transport(engine, edge, &mut x, &mut y) },
+            FactorialResult::Ok
+        );
+        assert_eq!((x, y), (40, 90));
+
+        unsafe { factorial_destroy(engine) };
+    }
+
+    #[test]
+    fn transport_getters_report_kind_mismatch_and_missing_edges() {
+        let (engine, _, edge) = two_node_ffi_engine();
+        let (mut a, mut b, mut c) = (0i64, 0i64, 0u32);
+        let (mut x, mut y, mut lanes) = (0u32, 0u32, 0u8);
+
+        unsafe { factorial_set_vehicle_transport(engine, edge, 40, 90) };
+        assert_eq!(
+            unsafe { factorial_get_flow_transport(engine, edge, &mut a, &mut b, &mut c) },
+            FactorialResult::KindMismatch
+        );
+        assert_eq!(
+            unsafe { factorial_get_item_transport(engine, edge, &mut a, &mut x, &mut lanes) },
+            FactorialResult::KindMismatch
+        );
+        assert_eq!(
+            unsafe { factorial_get_batch_transport(engine, edge, &mut x, &mut y) },
+            FactorialResult::KindMismatch
+        );
+        unsafe { factorial_set_flow_transport(engine, edge, 1 << 32) };
+        assert_eq!(
+            unsafe { factorial_get_vehicle_transport(engine, edge, &mut x, &mut y) },
+            FactorialResult::KindMismatch
+        );
+
+        let missing = edge_id_to_ffi(EdgeId::default());
+        assert_eq!(
+            unsafe { factorial_get_batch_transport(engine, missing, &mut x, &mut y) },
+            FactorialResult::EdgeNotFound
+        );
+        assert_eq!(
+            unsafe { factorial_get_batch_transport(engine, edge, ptr::null_mut(), &mut y) },
+            FactorialResult::NullPointer
+        );
+        unsafe { factorial_destroy(engine) };
+    }
+
+    #[test]
+    fn source_getter_round_trips_and_reports_mismatch() {
+        let (engine, miner, _) = two_node_ffi_engine();
+        let (mut item, mut rate) = (0u32, 0i64);
+        let mut depletion = FfiDepletion::default();
+
+        let set_rate = Fixed64::from_num(0.75).to_bits();
+        unsafe { factorial_set_source(engine, miner, 7, set_rate) };
+        assert_eq!(
+            unsafe { factorial_get_source(engine, miner, &mut item, &mut rate, &mut depletion) },
+            FactorialResult::Ok
+        );
+        assert_eq!((item, rate), (7, set_rate));
+        assert_eq!(depletion, FfiDepletion::default());
+
+        // Depletion set from Rust reads back too.
+        let nid = ffi_to_node_id(miner);
+        unsafe { &mut *engine }.inner.set_processor(
+            nid,
+            Processor::Source(SourceProcessor {
+                output_type: ItemTypeId(7),
+                base_rate: Fixed64::from_bits(set_rate),
+                depletion: Depletion::Finite {
+                    remaining: Fixed64::from_num(300),
+                },
+                accumulated: Fixed64::ZERO,
+                initial_properties: None,
+            }),
+        );
+        unsafe { factorial_get_source(engine, miner, &mut item, &mut rate, &mut depletion) };
+        assert_eq!(depletion.kind, FfiDepletionKind::Finite);
+        assert_eq!(depletion.remaining, Fixed64::from_num(300).to_bits());
+
+        unsafe { &mut *engine }
+            .inner
+            .set_processor(nid, Processor::Passthrough);
+        assert_eq!(
+            unsafe { factorial_get_source(engine, miner, &mut item, &mut rate, &mut depletion) },
+            FactorialResult::KindMismatch
+        );
+        let missing = node_id_to_ffi(NodeId::default());
+        assert_eq!(
+            unsafe { factorial_get_source(engine, missing, &mut item, &mut rate, &mut depletion) },
+            FactorialResult::NodeNotFound
+        );
+        unsafe { factorial_destroy(engine) };
+    }
+
+    fn empty_processor_config() -> FfiProcessorConfig {
+        FfiProcessorConfig {
+            kind: FfiProcessorKind::ProcessorKindPassthrough,
+            item: 0,
+            input_item: 0,
+            rate: 0,
+            depletion: FfiDepletion::default(),
+            recipe_count: 0,
+            active_recipe: 0,
+            duration: 0,
+            inputs: ptr::null_mut(),
+            input_capacity: 0,
+            input_count: 0,
+            outputs: ptr::null_mut(),
+            output_capacity: 0,
+            output_count: 0,
+        }
+    }
+
+    #[test]
+    fn processor_config_reads_back_a_fixed_recipe() {
+        let (engine, miner, _) = two_node_ffi_engine();
+        let inputs = [
+            FfiItemStack {
+                item_type: 0,
+                quantity: 2,
+            },
+            FfiItemStack {
+                item_type: 1,
+                quantity: 3,
+            },
+        ];
+        let outputs = [FfiItemStack {
+            item_type: 2,
+            quantity: 1,
+        }];
+        let recipe = FfiRecipe {
+            input_count: 2,
+            inputs: inputs.as_ptr(),
+            output_count: 1,
+            outputs: outputs.as_ptr(),
+            duration: 5,
+        };
+        unsafe { factorial_set_fixed_processor(engine, miner, &recipe) };
+
+        // Ask for the counts first.
+        let mut config = empty_processor_config();
+        assert_eq!(
+            unsafe { factorial_get_processor_config(engine, miner, &mut config) },
+            FactorialResult::BufferTooSmall
+        );
+        assert_eq!(config.kind, FfiProcessorKind::ProcessorKindFixed);
+        assert_eq!((config.input_count, config.output_count), (2, 1));
+
+        let mut read_inputs = [FfiItemStack {
+            item_type: 0,
+            quantity: 0,
+        }; 2];
+        let mut read_outputs = [FfiItemStack {
+            item_type: 0,
+            quantity: 0,
+        }; 1];
+        config.inputs = read_inputs.as_mut_ptr();
+        config.input_capacity = 2;
+        config.outputs = read_outputs.as_mut_ptr();
+        config.output_capacity = 1;
+        assert_eq!(
+            unsafe { factorial_get_processor_config(engine, miner, &mut config) },
+            FactorialResult::Ok
+        );
+        assert_eq!((config.duration, config.recipe_count), (5, 1));
+        assert_eq!(
+            read_inputs.map(|s| (s.item_type, s.quantity)),
+            inputs.map(|s| (s.item_type, s.quantity))
+        );
+        assert_eq!(
+            read_outputs.map(|s| (s.item_type, s.quantity)),
+            outputs.map(|s| (s.item_type, s.quantity))
+        );
+
+        // Sources report their rate; other kinds leave the recipe empty.
+        let rate = Fixed64::from_num(1.5).to_bits();
+        unsafe { factorial_set_source(engine, miner, 7, rate) };
+        assert_eq!(
+            unsafe { factorial_get_processor_config(engine, miner, &mut config) },
+            FactorialResult::Ok
+        );
+        assert_eq!(config.kind, FfiProcessorKind::ProcessorKindSource);
+        assert_eq!((config.item, config.rate), (7, rate));
+        assert_eq!((config.duration, config.input_count), (0, 0));
+
+        let missing = node_id_to_ffi(NodeId::default());
+        assert_eq!(
+            unsafe { factorial_get_processor_config(engine, missing, &mut config) },
+            FactorialResult::NodeNotFound
+        );
+        assert_eq!(
+            unsafe { factorial_get_processor_config(engine, miner, ptr::null_mut()) },
+            FactorialResult::NullPointer
+        );
+        unsafe { factorial_destroy(engine) };
+    }
+
+    #[test]
+    fn multi_source_via_ffi_shares_one_deposit() {
+        let engine = factorial_create();
+        let node_id = ffi_add_node_and_apply(engine, 0);
+        let outputs = [
+            FfiSourceOutput {
+                item_type: 0,
+                rate: Fixed64::from_num(2).to_bits(),
+            },
+            FfiSourceOutput {
+                item_type: 1,
+                rate: Fixed64::from_num(0.5).to_bits(),
+            },
+        ];
+        let depletion = FfiDepletion {
+            kind: FfiDepletionKind::Finite,
+            remaining: Fixed64::from_num(100).to_bits(),
+            half_life: 0,
+        };
+        assert_eq!(
+            unsafe { factorial_set_multi_source(engine, node_id, outputs.as_ptr(), 2, depletion) },
+            FactorialResult::Ok
+        );
+        unsafe { factorial_set_output_capacity(engine, node_id, 100) };
+        for _ in 0..4 {
+            unsafe { factorial_step(engine) };
+        }
+
+        let inner = &unsafe { &*engine }.inner;
+        let nid = ffi_to_node_id(node_id);
+        let output = inner.get_output_inventory(nid).unwrap();
+        assert_eq!(output.count_of(ItemTypeId(0)), 8);
+        assert_eq!(output.count_of(ItemTypeId(1)), 2);
+        match inner.get_processor(nid) {
+            Some(Processor::MultiSource(multi)) => assert_eq!(
+                multi.depletion,
+                Depletion::Finite {
+                    remaining: Fixed64::from_num(90)
+                }
+            ),
+            other => panic!("unexpected processor {other:?}"),
+        }
+        // A multi-output source is not a Source.
+        let (mut item, mut rate, mut read) = (0u32, 0i64, FfiDepletion::default());
+        assert_eq!(
+            unsafe { factorial_get_source(engine, node_id, &mut item, &mut rate, &mut read) },
+            FactorialResult::KindMismatch
+        );
+
+        assert_eq!(
+            unsafe { factorial_set_multi_source(engine, node_id, ptr::null(), 1, depletion) },
+            FactorialResult::NullPointer
+        );
+        unsafe { factorial_destroy(engine) };
+    }
+
+    #[test]
+    fn procedural_sources_read_the_richness_provider() {
+        let (engine, miner, _) = two_node_ffi_engine();
+        let mut site = FfiSourceSite::default();
+        assert_eq!(
+            unsafe { factorial_add_source_at(engine, miner, 3, 10, -4, &mut site) },
+            FactorialResult::InvalidArgument
+        );
+
+        let curve = FfiRichnessCurve {
+            cell_size: 4,
+            min_rate: Fixed64::from_num(1).to_bits(),
+            max_rate: Fixed64::from_num(3).to_bits(),
+            min_amount: 0,
+            max_amount: 0,
+        };
+        assert_eq!(
+            unsafe { factorial_set_richness_provider(engine, 42, &curve) },
+            FactorialResult::Ok
+        );
+        assert_eq!(
+            unsafe { factorial_add_source_at(engine, miner, 3, 10, -4, &mut site) },
+            FactorialResult::Ok
+        );
+        let richness = factorial_core::richness::richness_at(42, 4, 10, -4);
+        assert_eq!(
+            site,
+            FfiSourceSite {
+                x: 10,
+                y: -4,
+                richness: richness.to_bits(),
+            }
+        );
+
+        let mut read = FfiSourceSite::default();
+        assert_eq!(
+            unsafe { factorial_get_source_site(engine, miner, &mut read) },
+            FactorialResult::Ok
+        );
+        assert_eq!(read, site);
+        let (mut item, mut rate) = (0u32, 0i64);
+        let mut depletion = FfiDepletion::default();
+        unsafe { factorial_get_source(engine, miner, &mut item, &mut rate, &mut depletion) };
+        let expected = Fixed64::from_num(1) + Fixed64::from_num(2) * richness;
+        assert_eq!((item, rate), (3, expected.to_bits()));
+        assert_eq!(depletion.kind, FfiDepletionKind::Unlimited);
+
+        unsafe { factorial_set_source(engine, miner, 3, rate) };
+        assert_eq!(
+            unsafe { factorial_get_source_site(engine, miner, &mut read) },
+            FactorialResult::NodeNotFound
+        );
+        let missing = node_id_to_ffi(NodeId::default());
+        assert_eq!(
+            unsafe { factorial_add_source_at(engine, missing, 3, 0, 0, ptr::null_mut()) },
+            FactorialResult::NodeNotFound
+        );
+        unsafe { factorial_destroy(engine) };
+    }
+
+    #[test]
+    fn inventory_config_round_trips_capacity_setters() {
+        let (engine, miner, _) = two_node_ffi_engine();
+        let (mut slots_in, mut slots_out, mut capacity) = (0u32, 0u32, 0u32);
+
+        unsafe { factorial_set_input_capacity(engine, miner, 25) };
+        unsafe { factorial_set_output_capacity(engine, miner, 60) };
+        assert_eq!(
+            unsafe {
+                factorial_get_inventory_config(
+                    engine,
+                    miner,
+                    0,
+                    &mut slots_in,
+                    &mut slots_out,
+                    &mut capacity,
+                )
+            },
+            FactorialResult::Ok
+        );
+        assert_eq!((slots_in, slots_out, capacity), (1, 1, 25));
+        unsafe {
+            factorial_get_inventory_config(
+                engine,
+                miner,
+                1,
+                &mut slots_in,
+                &mut slots_out,
+                &mut capacity,
+            )
+        };
+        assert_eq!(capacity, 60);
+
+        assert_eq!(
+            unsafe {
+                factorial_get_inventory_config(
+                    engine,
+                    miner,
+                    2,
+                    &mut slots_in,
+                    &mut slots_out,
+                    &mut capacity,
+                )
+            },
+            FactorialResult::InvalidArgument
+        );
+        let missing = node_id_to_ffi(NodeId::default());
+        assert_eq!(
+            unsafe {
+                factorial_get_inventory_config(
+                    engine,
+                    missing,
+                    0,
+                    &mut slots_in,
+                    &mut slots_out,
+                    &mut capacity,
+                )
+            },
+            FactorialResult::NodeNotFound
+        );
+        unsafe { factorial_destroy(engine) };
+    }
+
+    // -----------------------------------------------------------------------
+    // Borrowed engines
+    // -----------------------------------------------------------------------
+    #[test]
+    fn borrowed_engine_is_stepped_in_place() {
+        let mut builder = FactoryBuilder::new();
+        builder.node("miner").source(iron(), 2.0);
+        builder.node("chest").passthrough();
+        builder.flow("miner", "chest", 5.0);
+        let (mut owned, nodes) = builder.build().unwrap().into_parts();
+
+        let handle = unsafe { factorial_wrap_borrowed(&mut owned) };
+        assert!(!handle.is_null());
+        for _ in 0..5 {
+            assert_eq!(unsafe { factorial_step(handle) }, FactorialResult::Ok);
+        }
+        let mut tick = 0;
+        unsafe { factorial_get_tick(handle, &mut tick) };
+        assert_eq!(tick, 5);
+        let mut buffer = FfiEventBuffer {
+            events: ptr::null(),
+            count: 0,
+        };
+        unsafe { factorial_poll_events(handle, &mut buffer) };
+        assert!(buffer.count > 0);
+        assert_eq!(unsafe { factorial_unwrap(handle) }, FactorialResult::Ok);
+
+        // The caller's engine holds everything the FFI did.
+        assert_eq!(owned.sim_state.tick, 5);
+        let chest = nodes["chest"];
+        let held = owned.get_input_inventory(chest).unwrap().count_of(iron())
+            + owned.get_output_inventory(chest).unwrap().count_of(iron());
+        assert!(held > 0);
+
+        // The handle's listeners outlive it without effect, and the engine
+        // can be wrapped again.
+        owned.step();
+        let handle = unsafe { factorial_wrap_borrowed(&mut owned) };
+        assert_eq!(unsafe { factorial_step(handle) }, FactorialResult::Ok);
+        assert_eq!(unsafe { factorial_unwrap(handle) }, FactorialResult::Ok);
+        assert_eq!(owned.sim_state.tick, 7);
+    }
+
+    #[test]
+    fn unwrap_only_releases_borrowed_handles() {
+        assert!(unsafe { factorial_wrap_borrowed(ptr::null_mut()) }.is_null());
+        assert_eq!(
+            unsafe { factorial_unwrap(ptr::null_mut()) },
+            FactorialResult::NullPointer
+        );
+
+        let engine = factorial_create();
+        assert_eq!(
+            unsafe { factorial_unwrap(engine) },
+            FactorialResult::InvalidHandle
+        );
+        assert_eq!(unsafe { factorial_step(engine) }, FactorialResult::Ok);
+        assert_eq!(unsafe { factorial_destroy(engine) }, FactorialResult::Ok);
+    }
+
+    #[test]
+    fn unwrapping_a_poisoned_handle_reports_it() {
+        let mut owned = Engine::new(SimulationStrategy::Tick);
+        owned.register_tick_hook(
+            factorial_core::hook::TickPhase::Pre,
+            0,
+            Box::new(|_| panic!("hook failure")),
+        );
+        let handle = unsafe { factorial_wrap_borrowed(&mut owned) };
+        assert_eq!(
+            unsafe { factorial_step(handle) },
+            FactorialResult::InternalError
+        );
+        assert_eq!(
+            unsafe { factorial_unwrap(handle) },
+            FactorialResult::Poisoned
+        );
+        assert_eq!(owned.tick_hook_count(), 0);
+    }
+
+    // -----------------------------------------------------------------------
+    // Helper: materialize a FactoryBuilder behind an FFI handle
+    // -----------------------------------------------------------------------
+    fn ffi_engine_from_builder(
+        builder: &FactoryBuilder,
+    ) -> (*mut FactorialEngine, BTreeMap<String, NodeId>) {
+        let (engine, nodes) = builder.build().unwrap().into_parts();
+        let ptr = Box::into_raw(Box::new(FactorialEngine::new(engine)));
+        (ptr, nodes)
+    }
+
+    #[test]
+    fn flexible_processor_through_ffi() {
+        use factorial_core::registry::RegistryBuilder;
+        let mut registry = RegistryBuilder::new();
+        for name in ["iron", "copper", "gear"] {
+            registry.register_item(name, vec![]);
+        }
+        for ore in [iron(), copper()] {
+            registry.register_recipe(
+                &format!("{ore:?}-gear"),
+                vec![RecipeEntry {
+                    item: ore,
+                    quantity: 2,
+                    consumed: true,
+                }],
+                vec![RecipeEntry {
+                    item: gear(),
+                    quantity: 1,
+                    consumed: true,
+                }],
+                3,
+            );
+        }
+        let mut core =
+            Engine::new_with_registry(SimulationStrategy::Tick, registry.build().unwrap());
+        let node = factorial_core::test_utils::add_node(&mut core, Processor::Passthrough, 50, 50);
+        let _ = core
+            .get_input_inventory_mut(node)
+            .unwrap()
+            .insert(copper(), 2);
+        let engine = Box::into_raw(Box::new(FactorialEngine::new(core)));
+        let node = node_id_to_ffi(node);
+
+        let mut recipe = 0;
+        assert_eq!(
+            unsafe { factorial_get_active_recipe(engine, node, &mut recipe) },
+            FactorialResult::KindMismatch
+        );
+        let recipes = [0, 1];
+        assert_eq!(
+            unsafe { factorial_set_flexible_processor(engine, node, recipes.as_ptr(), 2, 2) },
+            FactorialResult::InvalidArgument
+        );
+        assert_eq!(
+            unsafe { factorial_set_flexible_processor(engine, node, recipes.as_ptr(), 2, 1) },
+            FactorialResult::Ok
+        );
+        unsafe { factorial_get_active_recipe(engine, node, &mut recipe) };
+        assert_eq!(recipe, NO_RECIPE);
+
+        unsafe { factorial_step(engine) };
+        assert_eq!(
+            unsafe { factorial_get_active_recipe(engine, node, &mut recipe) },
+            FactorialResult::Ok
+        );
+        assert_eq!(recipe, 1);
+        let mut buffer = FfiEventBuffer {
+            events: ptr::null(),
+            count: 0,
+        };
+        unsafe { factorial_poll_events(engine, &mut buffer) };
+        let events = unsafe { std::slice::from_raw_parts(buffer.events, buffer.count as usize) };
+        let started = events
+            .iter()
+            .find(|e| e.kind == FfiEventKind::RecipeStarted)
+            .unwrap();
+        assert_eq!((started.item_type, started.quantity), (1, 1));
+        unsafe { factorial_destroy(engine) };
+    }
+
+    #[test]
+    fn update_recipe_cancels_in_flight_crafts() {
+        use factorial_core::registry::RegistryBuilder;
+        let mut registry = RegistryBuilder::new();
+        for name in ["iron", "copper", "gear"] {
+            registry.register_item(name, vec![]);
+        }
+        registry.register_recipe(
+            "gear",
+            vec![RecipeEntry {
+                item: copper(),
+                quantity: 2,
+                consumed: true,
+            }],
+            vec![RecipeEntry {
+                item: gear(),
+                quantity: 1,
+                consumed: true,
+            }],
+            3,
+        );
+        let mut core =
+            Engine::new_with_registry(SimulationStrategy::Tick, registry.build().unwrap());
+        let node = factorial_core::test_utils::add_node(
+            &mut core,
+            Processor::Flexible(FlexibleProcessor::new(
+                vec![RecipeId(0)],
+                RecipeSelection::FirstMatch,
+            )),
+            50,
+            50,
+        );
+        let _ = core
+            .get_input_inventory_mut(node)
+            .unwrap()
+            .insert(copper(), 4);
+        let engine = Box::into_raw(Box::new(FactorialEngine::new(core)));
+        unsafe { factorial_step(engine) };
+
+        let inputs = [FfiItemStack {
+            item_type: copper().0,
+            quantity: 2,
+        }];
+        let outputs = [FfiItemStack {
+            item_type: gear().0,
+            quantity: 1,
+        }];
+        let recipe = FfiRecipe {
+            input_count: 1,
+            inputs: inputs.as_ptr(),
+            output_count: 1,
+            outputs: outputs.as_ptr(),
+            duration: 1,
+        };
+        assert_eq!(
+            unsafe { factorial_update_recipe(engine, 0, &recipe, 2) },
+            FactorialResult::InvalidArgument
+        );
+        assert_eq!(
+            unsafe { factorial_update_recipe(engine, 0, &recipe, 1) },
+            FactorialResult::Ok
+        );
+        let core = &unsafe { &*engine }.inner;
+        let inventory = core.get_input_inventory(node).unwrap();
+        assert_eq!(inventory.count_of(copper()), 4);
+
+        // One-tick crafts now finish on the tick they start.
         unsafe { factorial_step(engine) };
         unsafe { factorial_step(engine) };
+        let core = &unsafe { &*engine }.inner;
+        assert_eq!(core.get_output_inventory(node).unwrap().count_of(gear()), 2);
+        assert_eq!(
+            core.get_input_inventory(node).unwrap().count_of(copper()),
+            0
+        );
+        unsafe { factorial_destroy(engine) };
+    }
 
-        unsafe { factorial_get_tick(engine, &mut tick) };
-        assert_eq!(tick, 3);
+    #[test]
+    fn in_transit_count_reports_vehicle_cargo() {
+        let mut builder = FactoryBuilder::new();
+        builder.node("miner").source(iron(), 5.0);
+        builder.node("chest").passthrough();
+        builder.connect(
+            "miner",
+            "chest",
+            Transport::Vehicle(VehicleTransport {
+                capacity: 4,
+                travel_time: 10,
+            }),
+        );
+        let (engine, _) = ffi_engine_from_builder(&builder);
+        let edge = unsafe { &*engine }.inner.graph.edges().next().unwrap().0;
+        let edge_ffi = edge_id_to_ffi(edge);
+
+        let mut count = u32::MAX;
+        assert_eq!(
+            unsafe { factorial_get_transport_in_transit(engine, edge_ffi, &mut count) },
+            FactorialResult::Ok
+        );
+        assert_eq!(count, 0);
+        for _ in 0..3 {
+            unsafe { factorial_step(engine) };
+        }
+        unsafe { factorial_get_transport_in_transit(engine, edge_ffi, &mut count) };
+        assert_eq!(count, 4);
 
+        let missing = edge_id_to_ffi(EdgeId::default());
+        assert_eq!(
+            unsafe { factorial_get_transport_in_transit(engine, missing, &mut count) },
+            FactorialResult::EdgeNotFound
+        );
+        assert_eq!(
+            unsafe { factorial_get_transport_in_transit(engine, edge_ffi, ptr::null_mut()) },
+            FactorialResult::NullPointer
+        );
         unsafe { factorial_destroy(engine) };
     }
 
-    // -----------------------------------------------------------------------
-    // Test 6: Add node with source processor, step, check output inventory
-    // -----------------------------------------------------------------------
     #[test]
-    fn add_node_step_produces_output() {
-        let engine_ptr = factorial_create();
-        assert!(!engine_ptr.is_null());
+    fn transport_progress_reports_vehicle_phase() {
+        let mut builder = FactoryBuilder::new();
+        builder.node("miner").source(iron(), 5.0);
+        builder.node("chest").passthrough();
+        builder.connect(
+            "miner",
+            "chest",
+            Transport::Vehicle(VehicleTransport {
+                capacity: 4,
+                travel_time: 10,
+            }),
+        );
+        let (engine, _) = ffi_engine_from_builder(&builder);
+        let edge = unsafe { &*engine }.inner.graph.edges().next().unwrap().0;
+        let edge_ffi = edge_id_to_ffi(edge);
 
-        // Add a node via FFI.
-        let mut pending: FfiPendingNodeId = 0;
-        unsafe { factorial_add_node(engine_ptr, 0, &mut pending) };
+        let mut progress = std::mem::MaybeUninit::<FfiTransportProgress>::uninit();
+        for _ in 0..3 {
+            unsafe { factorial_step(engine) };
+        }
+        assert_eq!(
+            unsafe { factorial_get_transport_progress(engine, edge_ffi, progress.as_mut_ptr()) },
+            FactorialResult::Ok
+        );
+        let progress = unsafe { progress.assume_init() };
+        assert_eq!(progress.kind, FfiTransportKind::Vehicle);
+        assert_eq!(progress.phase, FfiVehiclePhase::Traveling);
+        assert_eq!(progress.cargo_count, 4);
+        assert_eq!(
+            progress.ticks_remaining_in_phase,
+            match unsafe { &*engine }.inner.transport_progress(edge) {
+                Some(TransportProgress::Vehicle {
+                    ticks_remaining_in_phase,
+                    ..
+                }) => ticks_remaining_in_phase,
+                other => panic!("expected vehicle progress, got {other:?}"),
+            }
+        );
+        assert_eq!(progress.batch_size, 0);
 
-        let mut mutation_result = FfiMutationResult {
-            added_nodes: ptr::null(),
-            added_node_count: 0,
-            added_edges: ptr::null(),
-            added_edge_count: 0,
-        };
-        unsafe { factorial_apply_mutations(engine_ptr, &mut mutation_result) };
-        let pairs = unsafe { std::slice::from_raw_parts(mutation_result.added_nodes, 1) };
-        let node_ffi_id = pairs[0].real_id;
+        let mut out = progress;
+        let missing = edge_id_to_ffi(EdgeId::default());
+        assert_eq!(
+            unsafe { factorial_get_transport_progress(engine, missing, &mut out) },
+            FactorialResult::EdgeNotFound
+        );
+        assert_eq!(
+            unsafe { factorial_get_transport_progress(engine, edge_ffi, ptr::null_mut()) },
+            FactorialResult::NullPointer
+        );
+        unsafe { factorial_destroy(engine) };
+    }
 
-        // Set up the processor and inventories on the Rust side (direct access
-        // for test setup -- real users would have dedicated FFI for this).
-        let engine = unsafe { &mut *engine_ptr };
-        let node_id = ffi_to_node_id(node_ffi_id);
-        engine
-            .inner
-            .set_processor(node_id, make_source(iron(), 3.0));
-        engine
-            .inner
-            .set_input_inventory(node_id, simple_inventory(100));
-        engine
-            .inner
-            .set_output_inventory(node_id, simple_inventory(100));
+    #[test]
+    fn transport_progress_marks_an_empty_belt() {
+        let progress = transport_progress_to_ffi(TransportProgress::Item {
+            items_in_transit: 0,
+            head_position: None,
+            ticks_until_delivery: None,
+        });
+        assert_eq!(progress.kind, FfiTransportKind::Item);
+        assert_eq!(progress.head_position, 0);
+        assert_eq!(progress.ticks_until_delivery, NO_DELIVERY);
+    }
 
-        // Step once via FFI.
-        let result = unsafe { factorial_step(engine_ptr) };
+    #[test]
+    fn flow_accumulator_carries_fraction() {
+        let mut builder = FactoryBuilder::new();
+        builder.node("miner").source(iron(), 5.0);
+        builder.node("chest").passthrough();
+        builder.flow("miner", "chest", 0.25);
+        let (engine, _) = ffi_engine_from_builder(&builder);
+        let edge = unsafe { &*engine }.inner.graph.edges().next().unwrap().0;
+        let edge_ffi = edge_id_to_ffi(edge);
+
+        // Tick 0 has nothing to move; ticks 1 and 2 drain a quarter each.
+        for _ in 0..3 {
+            unsafe { factorial_step(engine) };
+        }
+        let mut value: i64 = -1;
+        let result = unsafe { factorial_get_flow_accumulator(engine, edge_ffi, &mut value) };
         assert_eq!(result, FactorialResult::Ok);
+        assert_eq!(Fixed64::from_bits(value), Fixed64::from_num(0.5));
 
-        // Query output inventory count.
-        let mut count: u32 = 0;
-        let result =
-            unsafe { factorial_get_output_inventory_count(engine_ptr, node_ffi_id, &mut count) };
-        assert_eq!(result, FactorialResult::Ok);
-        assert_eq!(count, 3, "source should produce 3 items per tick");
+        unsafe {
+            factorial_set_item_transport(engine, edge_ffi, Fixed64::from_num(1).to_bits(), 4, 1)
+        };
+        assert_eq!(
+            unsafe { factorial_get_flow_accumulator(engine, edge_ffi, &mut value) },
+            FactorialResult::EdgeNotFound
+        );
+        assert_eq!(
+            unsafe { factorial_get_flow_accumulator(engine, edge_ffi, ptr::null_mut()) },
+            FactorialResult::NullPointer
+        );
+        unsafe { factorial_destroy(engine) };
+    }
 
-        // Query processor state.
-        let mut info = FfiProcessorInfo {
-            state: FfiProcessorState::Idle,
-            progress: 0,
+    #[test]
+    fn ghost_status_round_trips_and_stops_production() {
+        let mut builder = FactoryBuilder::new();
+        builder.node("miner").source(iron(), 5.0);
+        builder.node("chest").passthrough();
+        builder.flow("miner", "chest", 5.0);
+        let (engine, nodes) = ffi_engine_from_builder(&builder);
+        let miner = node_id_to_ffi(nodes["miner"]);
+        let edge = edge_id_to_ffi(unsafe { &*engine }.inner.graph.edges().next().unwrap().0);
+
+        assert_eq!(
+            unsafe { factorial_set_node_status(engine, miner, FfiNodeStatus::Ghost) },
+            FactorialResult::Ok
+        );
+        assert_eq!(
+            unsafe { factorial_set_edge_status(engine, edge, FfiNodeStatus::Ghost) },
+            FactorialResult::Ok
+        );
+        let mut status = FfiNodeStatus::Active;
+        unsafe { factorial_get_node_status(engine, miner, &mut status) };
+        assert_eq!(status, FfiNodeStatus::Ghost);
+        unsafe { factorial_get_edge_status(engine, edge, &mut status) };
+        assert_eq!(status, FfiNodeStatus::Ghost);
+
+        for _ in 0..3 {
+            unsafe { factorial_step(engine) };
+        }
+        let mut count = 0u32;
+        unsafe { factorial_get_output_inventory_count(engine, miner, &mut count) };
+        assert_eq!(count, 0);
+
+        unsafe { factorial_set_node_status(engine, miner, FfiNodeStatus::Active) };
+        unsafe { factorial_step(engine) };
+        unsafe { factorial_get_output_inventory_count(engine, miner, &mut count) };
+        assert!(count > 0);
+
+        let missing: FfiNodeId = u64::MAX;
+        assert_eq!(
+            unsafe { factorial_set_node_status(engine, missing, FfiNodeStatus::Ghost) },
+            FactorialResult::NodeNotFound
+        );
+        assert_eq!(
+            unsafe { factorial_get_node_status(engine, miner, ptr::null_mut()) },
+            FactorialResult::NullPointer
+        );
+        unsafe { factorial_destroy(engine) };
+    }
+
+    #[test]
+    fn node_user_data_round_trips_through_save_and_load() {
+        let (engine, miner, _) = two_node_ffi_engine();
+        let mut data = 0u64;
+        assert_eq!(
+            unsafe { factorial_get_node_user_data(engine, miner, &mut data) },
+            FactorialResult::NodeNotFound
+        );
+        assert_eq!(
+            unsafe { factorial_set_node_user_data(engine, miner, 42) },
+            FactorialResult::Ok
+        );
+
+        let mut buffer = FfiByteBuffer {
+            data: ptr::null_mut(),
+            len: 0,
         };
-        let result = unsafe { factorial_get_processor_state(engine_ptr, node_ffi_id, &mut info) };
-        assert_eq!(result, FactorialResult::Ok);
-        assert_eq!(info.state, FfiProcessorState::Working);
+        unsafe { factorial_serialize(engine, &mut buffer) };
+        let mut restored: *mut FactorialEngine = ptr::null_mut();
+        assert_eq!(
+            unsafe { factorial_deserialize(buffer.data, buffer.len, &mut restored) },
+            FactorialResult::Ok
+        );
+        unsafe { factorial_free_buffer(buffer) };
+        assert_eq!(
+            unsafe { factorial_get_node_user_data(restored, miner, &mut data) },
+            FactorialResult::Ok
+        );
+        assert_eq!(data, 42);
 
-        unsafe { factorial_destroy(engine_ptr) };
+        assert_eq!(
+            unsafe { factorial_clear_node_user_data(restored, miner) },
+            FactorialResult::Ok
+        );
+        assert_eq!(
+            unsafe { factorial_get_node_user_data(restored, miner, &mut data) },
+            FactorialResult::NodeNotFound
+        );
+        assert_eq!(
+            unsafe { factorial_set_node_user_data(restored, u64::MAX, 1) },
+            FactorialResult::NodeNotFound
+        );
+        unsafe { factorial_destroy(restored) };
+        unsafe { factorial_destroy(engine) };
     }
 
-    // -----------------------------------------------------------------------
-    // Test 7: Serialize and deserialize through C API
-    // -----------------------------------------------------------------------
     #[test]
-    fn serialize_deserialize_round_trip() {
-        let engine_ptr = factorial_create();
+    fn transport_state_round_trips_mid_transit() {
+        let mut builder = FactoryBuilder::new();
+        builder.node("miner").source(iron(), 1.0);
+        builder.node("chest").passthrough();
+        builder.flow("miner", "chest", 1.0);
+        let (engine, nodes) = ffi_engine_from_builder(&builder);
+        let chest = node_id_to_ffi(nodes["chest"]);
+        let edge = unsafe { &*engine }.inner.graph.edges().next().unwrap().0;
+        let edge = edge_id_to_ffi(edge);
+        let speed = Fixed64::from_num(1).to_bits();
+        unsafe { factorial_set_item_transport(engine, edge, speed, 6, 1) };
+        for _ in 0..3 {
+            unsafe { factorial_step(engine) };
+        }
+
+        let mut blob = FfiByteBuffer {
+            data: ptr::null_mut(),
+            len: 0,
+        };
+        assert_eq!(
+            unsafe { factorial_get_transport_state(engine, edge, &mut blob) },
+            FactorialResult::Ok
+        );
+        assert!(blob.len > 0);
+
+        // Fork a reference engine that keeps the original belt contents.
+        let mut saved = FfiByteBuffer {
+            data: ptr::null_mut(),
+            len: 0,
+        };
+        unsafe { factorial_serialize(engine, &mut saved) };
+        let mut reference: *mut FactorialEngine = ptr::null_mut();
+        unsafe { factorial_deserialize(saved.data, saved.len, &mut reference) };
+        unsafe { factorial_free_buffer(saved) };
+
+        // Clear the belt, then re-apply the captured state.
+        unsafe { factorial_set_item_transport(engine, edge, speed, 6, 1) };
+        assert_eq!(
+            unsafe { factorial_set_transport_state(engine, edge, blob.data, blob.len) },
+            FactorialResult::Ok
+        );
+
+        for _ in 0..10 {
+            unsafe { factorial_step(engine) };
+            unsafe { factorial_step(reference) };
+            let (mut a, mut b) = (0u64, 0u64);
+            unsafe { factorial_get_state_hash(engine, &mut a) };
+            unsafe { factorial_get_state_hash(reference, &mut b) };
+            assert_eq!(a, b);
+        }
+        let (mut a, mut b) = (0u32, 0u32);
+        unsafe { factorial_get_output_inventory_count(engine, chest, &mut a) };
+        unsafe { factorial_get_output_inventory_count(reference, chest, &mut b) };
+        assert!(a > 0);
+        assert_eq!(a, b);
+
+        // A belt blob does not fit a flow transport, and garbage is rejected.
+        let rate = Fixed64::from_num(1).to_bits();
+        unsafe { factorial_set_flow_transport(engine, edge, rate) };
+        assert_eq!(
+            unsafe { factorial_set_transport_state(engine, edge, blob.data, blob.len) },
+            FactorialResult::DeserializeError
+        );
+        let garbage = [0xFFu8; 3];
+        assert_eq!(
+            unsafe { factorial_set_transport_state(engine, edge, garbage.as_ptr(), 3) },
+            FactorialResult::DeserializeError
+        );
+        unsafe { factorial_free_buffer(blob) };
+        let mut missing = FfiByteBuffer {
+            data: ptr::null_mut(),
+            len: 0,
+        };
+        assert_eq!(
+            unsafe { factorial_get_transport_state(engine, 0, &mut missing) },
+            FactorialResult::EdgeNotFound
+        );
+        assert_eq!(
+            unsafe { factorial_set_transport_state(engine, edge, ptr::null(), 0) },
+            FactorialResult::NullPointer
+        );
+
+        unsafe { factorial_destroy(engine) };
+        unsafe { factorial_destroy(reference) };
+    }
+
+    #[test]
+    fn crafting_queue_round_trip_through_ffi() {
+        let mut builder = FactoryBuilder::new();
+        builder.node("player").with_input(iron(), 10);
+        let (engine, nodes) = ffi_engine_from_builder(&builder);
+        let player = node_id_to_ffi(nodes["player"]);
+
+        let mut queue: FfiCraftingQueueId = 0;
+        let speed = Fixed64::from_num(1).to_bits();
+        assert_eq!(
+            unsafe { factorial_create_crafting_queue(engine, player, speed, &mut queue) },
+            FactorialResult::Ok
+        );
+        let inputs = [FfiItemStack {
+            item_type: iron().0,
+            quantity: 2,
+        }];
+        let outputs = [FfiItemStack {
+            item_type: gear().0,
+            quantity: 1,
+        }];
+        let recipe = FfiRecipe {
+            input_count: 1,
+            inputs: inputs.as_ptr(),
+            output_count: 1,
+            outputs: outputs.as_ptr(),
+            duration: 4,
+        };
+        for count in [3, 2] {
+            assert_eq!(
+                unsafe { factorial_queue_craft(engine, queue, 7, &recipe, count) },
+                FactorialResult::Ok
+            );
+        }
+        assert_eq!(
+            unsafe { factorial_queue_craft(engine, queue, 7, &recipe, 1) },
+            FactorialResult::CraftingRejected
+        );
+
+        for _ in 0..6 {
+            unsafe { factorial_step(engine) };
+        }
+        let mut progress = FfiCraftProgress::default();
+        unsafe { factorial_get_crafting_progress(engine, queue, &mut progress) };
+        assert_eq!(progress.recipe, 7);
+        assert_eq!(progress.remaining, 4);
+        assert_eq!(progress.jobs, 2);
+        assert_eq!(
+            Fixed64::from_bits(progress.progress),
+            Fixed64::from_num(0.5)
+        );
+
+        let mut cancelled = 0u32;
+        assert_eq!(
+            unsafe { factorial_cancel_craft(engine, queue, 1, &mut cancelled) },
+            FactorialResult::Ok
+        );
+        assert_eq!(cancelled, 2);
+        let mut iron_count = 0u32;
+        unsafe { factorial_get_input_inventory_count(engine, player, &mut iron_count) };
+        // 4 refunded iron plus 1 gear already crafted.
+        assert_eq!(iron_count, 5);
+
+        // The second gear finishes on the eighth step.
+        unsafe { factorial_step(engine) };
+        unsafe { factorial_step(engine) };
+        let mut events = FfiEventBuffer {
+            events: ptr::null(),
+            count: 0,
+        };
+        unsafe { factorial_poll_events(engine, &mut events) };
+        let events = unsafe { std::slice::from_raw_parts(events.events, events.count as usize) };
+        assert!(events.iter().any(|e| e.kind == FfiEventKind::CraftCompleted
+            && e.node == player
+            && e.item_type == 7
+            && e.quantity == 1));
+
+        assert_eq!(
+            unsafe { factorial_cancel_craft(engine, queue, 5, ptr::null_mut()) },
+            FactorialResult::CraftingRejected
+        );
+        assert_eq!(
+            unsafe { factorial_remove_crafting_queue(engine, queue) },
+            FactorialResult::Ok
+        );
+        assert_eq!(
+            unsafe { factorial_get_crafting_progress(engine, queue, &mut progress) },
+            FactorialResult::CraftingQueueNotFound
+        );
+        unsafe { factorial_destroy(engine) };
+    }
+
+    #[test]
+    fn builder_factory_steps_through_ffi() {
+        let mut builder = FactoryBuilder::new();
+        builder.node("miner").source(iron(), 5.0);
+        builder
+            .node("assembler")
+            .recipe(vec![(iron(), 2)], vec![(gear(), 1)], 3)
+            .with_input(iron(), 10);
+        builder.flow("miner", "assembler", 5.0);
+        let (engine_ptr, nodes) = ffi_engine_from_builder(&builder);
+
+        let mut node_count: u32 = 0;
+        let mut edge_count: u32 = 0;
+        unsafe { factorial_node_count(engine_ptr, &mut node_count) };
+        unsafe { factorial_edge_count(engine_ptr, &mut edge_count) };
+        assert_eq!(node_count, 2);
+        assert_eq!(edge_count, 1);
+
+        for _ in 0..10 {
+            assert_eq!(unsafe { factorial_step(engine_ptr) }, FactorialResult::Ok);
+        }
+
+        let assembler = node_id_to_ffi(nodes["assembler"]);
+        let mut count: u32 = 0;
+        let result =
+            unsafe { factorial_get_output_inventory_count(engine_ptr, assembler, &mut count) };
+        assert_eq!(result, FactorialResult::Ok);
+        assert!(count > 0);
 
-        // Add a node and step.
-        let mut pending: FfiPendingNodeId = 0;
-        unsafe { factorial_add_node(engine_ptr, 42, &mut pending) };
-        let mut mutation_result = FfiMutationResult {
-            added_nodes: ptr::null(),
-            added_node_count: 0,
-            added_edges: ptr::null(),
-            added_edge_count: 0,
-        };
-        unsafe { factorial_apply_mutations(engine_ptr, &mut mutation_result) };
+        unsafe { factorial_destroy(engine_ptr) };
+    }
 
-        // Set up processor and inventories.
-        let pairs = unsafe { std::slice::from_raw_parts(mutation_result.added_nodes, 1) };
-        let node_ffi_id = pairs[0].real_id;
-        let engine = unsafe { &mut *engine_ptr };
-        let node_id = ffi_to_node_id(node_ffi_id);
-        engine
-            .inner
-            .set_processor(node_id, make_source(iron(), 2.0));
-        engine
-            .inner
-            .set_input_inventory(node_id, simple_inventory(100));
-        engine
-            .inner
-            .set_output_inventory(node_id, simple_inventory(100));
+    #[test]
+    fn clear_processor_and_inventories_via_ffi() {
+        let mut builder = FactoryBuilder::new();
+        builder
+            .node("press")
+            .recipe(vec![(iron(), 1)], vec![(gear(), 1)], 1)
+            .with_input(iron(), 6)
+            .with_output(gear(), 2);
+        let (engine, nodes) = ffi_engine_from_builder(&builder);
+        let press = node_id_to_ffi(nodes["press"]);
 
-        // Step a few times.
+        assert_eq!(
+            unsafe { factorial_clear_processor(engine, press) },
+            FactorialResult::Ok
+        );
         for _ in 0..5 {
-            unsafe { factorial_step(engine_ptr) };
+            unsafe { factorial_step(engine) };
         }
 
-        // Get state hash before serialize.
-        let mut hash_before: u64 = 0;
-        unsafe { factorial_get_state_hash(engine_ptr, &mut hash_before) };
-
-        // Serialize.
-        let mut buffer = FfiByteBuffer {
-            data: ptr::null_mut(),
-            len: 0,
+        let mut items = FfiItemBuffer {
+            items: ptr::null(),
+            count: 0,
         };
-        let result = unsafe { factorial_serialize(engine_ptr, &mut buffer) };
+        let result = unsafe { factorial_clear_input_inventory(engine, press, &mut items) };
         assert_eq!(result, FactorialResult::Ok);
-        assert!(!buffer.data.is_null());
-        assert!(buffer.len > 0);
+        assert_eq!(items.count, 1);
+        let stacks = unsafe { std::slice::from_raw_parts(items.items, 1) };
+        assert_eq!(stacks[0].item_type, iron().0);
+        assert_eq!(stacks[0].quantity, 6, "cleared processor consumed nothing");
 
-        // Deserialize into a new engine.
-        let mut restored_ptr: *mut FactorialEngine = ptr::null_mut();
-        let result = unsafe { factorial_deserialize(buffer.data, buffer.len, &mut restored_ptr) };
+        let result = unsafe { factorial_clear_output_inventory(engine, press, &mut items) };
         assert_eq!(result, FactorialResult::Ok);
-        assert!(!restored_ptr.is_null());
+        assert_eq!(items.count, 1);
+        let stacks = unsafe { std::slice::from_raw_parts(items.items, 1) };
+        assert_eq!(stacks[0].item_type, gear().0);
+        assert_eq!(stacks[0].quantity, 2);
 
-        // Verify state hash matches.
-        let mut hash_after: u64 = 0;
-        unsafe { factorial_get_state_hash(restored_ptr, &mut hash_after) };
-        assert_eq!(hash_before, hash_after);
+        // Clearing again yields nothing; the node is still valid.
+        let result = unsafe { factorial_clear_output_inventory(engine, press, &mut items) };
+        assert_eq!(result, FactorialResult::Ok);
+        assert_eq!(items.count, 0);
+        assert!(items.items.is_null());
+        assert_eq!(
+            unsafe { factorial_set_output_capacity(engine, press, 10) },
+            FactorialResult::Ok
+        );
+        assert_eq!(unsafe { factorial_step(engine) }, FactorialResult::Ok);
 
-        // Verify node count.
-        let mut count: u32 = 0;
-        unsafe { factorial_node_count(restored_ptr, &mut count) };
-        assert_eq!(count, 1);
+        assert_eq!(
+            unsafe { factorial_clear_processor(engine, 9999) },
+            FactorialResult::NodeNotFound
+        );
+        let result = unsafe { factorial_clear_input_inventory(engine, 9999, &mut items) };
+        assert_eq!(result, FactorialResult::NodeNotFound);
+        let result = unsafe { factorial_clear_input_inventory(engine, press, ptr::null_mut()) };
+        assert_eq!(result, FactorialResult::NullPointer);
 
-        // Verify tick.
-        let mut tick: u64 = 0;
-        unsafe { factorial_get_tick(restored_ptr, &mut tick) };
-        assert_eq!(tick, 5);
+        unsafe { factorial_destroy(engine) };
+    }
 
-        // Clean up.
-        let result = unsafe { factorial_free_buffer(buffer) };
+    #[test]
+    fn fill_inventory_is_exact_and_atomic_via_ffi() {
+        use factorial_core::item::InventorySlot;
+
+        let mut builder = FactoryBuilder::new();
+        builder.node("chest").passthrough().input_cap(10);
+        let (engine, nodes) = ffi_engine_from_builder(&builder);
+        let chest = node_id_to_ffi(nodes["chest"]);
+        let stack = |item: ItemTypeId, quantity| FfiItemStack {
+            item_type: item.0,
+            quantity,
+        };
+
+        let exact = [stack(iron(), 7), stack(copper(), 3)];
+        let result = unsafe { factorial_fill_inventory(engine, chest, 0, exact.as_ptr(), 2) };
         assert_eq!(result, FactorialResult::Ok);
-        unsafe { factorial_destroy(restored_ptr) };
-        unsafe { factorial_destroy(engine_ptr) };
+        let inner = &unsafe { &*engine }.inner;
+        let input = inner.get_input_inventory(nodes["chest"]).unwrap();
+        assert_eq!((input.count_of(iron()), input.count_of(copper())), (7, 3));
+
+        let over = [stack(copper(), 4), stack(iron(), 7)];
+        let result = unsafe { factorial_fill_inventory(engine, chest, 0, over.as_ptr(), 2) };
+        assert_eq!(result, FactorialResult::InvalidArgument);
+        let input = unsafe { &*engine }
+            .inner
+            .get_input_inventory(nodes["chest"])
+            .unwrap();
+        assert_eq!((input.count_of(iron()), input.count_of(copper())), (7, 3));
+
+        // Slots filtered to another item type are not used.
+        unsafe { &mut *engine }.inner.set_input_inventory(
+            nodes["chest"],
+            Inventory {
+                input_slots: vec![InventorySlot::filtered(10, iron()), InventorySlot::new(5)],
+                output_slots: Vec::new(),
+            },
+        );
+        let copper_only = [stack(copper(), 6)];
+        let result = unsafe { factorial_fill_inventory(engine, chest, 0, copper_only.as_ptr(), 1) };
+        assert_eq!(result, FactorialResult::InvalidArgument);
+        let mixed = [stack(copper(), 5), stack(iron(), 10)];
+        let result = unsafe { factorial_fill_inventory(engine, chest, 0, mixed.as_ptr(), 2) };
+        assert_eq!(result, FactorialResult::Ok);
+
+        let result = unsafe { factorial_fill_inventory(engine, chest, 1, ptr::null(), 0) };
+        assert_eq!(result, FactorialResult::Ok);
+        let result = unsafe { factorial_fill_inventory(engine, chest, 2, ptr::null(), 0) };
+        assert_eq!(result, FactorialResult::InvalidArgument);
+        let result = unsafe { factorial_fill_inventory(engine, 9999, 0, ptr::null(), 0) };
+        assert_eq!(result, FactorialResult::NodeNotFound);
+        let result = unsafe { factorial_fill_inventory(engine, chest, 0, ptr::null(), 1) };
+        assert_eq!(result, FactorialResult::NullPointer);
+
+        unsafe { factorial_destroy(engine) };
     }
 
-    // -----------------------------------------------------------------------
-    // Test 8: Poll events returns correct data
-    // -----------------------------------------------------------------------
     #[test]
-    fn poll_events_returns_data() {
-        let engine_ptr = factorial_create();
+    fn bulk_fill_stocks_a_hundred_nodes_via_ffi() {
+        let mut builder = FactoryBuilder::new();
+        for i in 0..100 {
+            builder
+                .node(format!("chest{i}"))
+                .passthrough()
+                .input_cap(20)
+                .output_cap(20);
+        }
+        let (engine, nodes) = ffi_engine_from_builder(&builder);
+        let items = [
+            FfiItemStack {
+                item_type: iron().0,
+                quantity: 12,
+            },
+            FfiItemStack {
+                item_type: copper().0,
+                quantity: 8,
+            },
+        ];
+        // Even nodes get iron and copper in their input, odd nodes get copper
+        // in their output.
+        let mut entries: Vec<FfiInventoryFill> = (0..100)
+            .map(|i| FfiInventoryFill {
+                node: node_id_to_ffi(nodes[&format!("chest{i}")]),
+                which: i % 2,
+                offset: i % 2,
+                count: 2 - i % 2,
+            })
+            .collect();
 
-        // Add a source node that produces items.
-        let mut pending: FfiPendingNodeId = 0;
-        unsafe { factorial_add_node(engine_ptr, 7, &mut pending) };
-        let mut mutation_result = FfiMutationResult {
-            added_nodes: ptr::null(),
-            added_node_count: 0,
-            added_edges: ptr::null(),
-            added_edge_count: 0,
+        // One bad range rejects the whole batch.
+        entries[99].count = 2;
+        let bulk = |entries: &[FfiInventoryFill]| unsafe {
+            factorial_fill_inventories_bulk(
+                engine,
+                entries.as_ptr(),
+                entries.len() as u32,
+                items.as_ptr(),
+                items.len() as u32,
+            )
         };
-        unsafe { factorial_apply_mutations(engine_ptr, &mut mutation_result) };
-        let pairs = unsafe { std::slice::from_raw_parts(mutation_result.added_nodes, 1) };
-        let node_ffi_id = pairs[0].real_id;
-
-        let engine = unsafe { &mut *engine_ptr };
-        let node_id = ffi_to_node_id(node_ffi_id);
-        engine
-            .inner
-            .set_processor(node_id, make_source(iron(), 2.0));
-        engine
-            .inner
-            .set_input_inventory(node_id, simple_inventory(100));
-        engine
-            .inner
-            .set_output_inventory(node_id, simple_inventory(100));
+        assert_eq!(bulk(&entries), FactorialResult::InvalidArgument);
+        entries[99].count = 1;
+        entries[98].which = 1;
+        entries[98].count = 2;
+        entries.push(FfiInventoryFill {
+            node: entries[98].node,
+            which: 1,
+            offset: 0,
+            count: 1,
+        });
+        let inner = &unsafe { &*engine }.inner;
+        assert!(nodes.values().all(|&n| {
+            inner.get_input_inventory(n).unwrap().is_empty()
+                && inner.get_output_inventory(n).unwrap().is_empty()
+        }));
+
+        // The duplicate entry for chest98 replaces its earlier fill.
+        assert_eq!(bulk(&entries), FactorialResult::Ok);
+        let inner = &unsafe { &*engine }.inner;
+        for i in 0..100 {
+            let node = nodes[&format!("chest{i}")];
+            let input = inner.get_input_inventory(node).unwrap();
+            let output = inner.get_output_inventory(node).unwrap();
+            match i {
+                98 => assert_eq!(output.iter_items().collect::<Vec<_>>(), [(iron(), 12)]),
+                _ if i % 2 == 0 => {
+                    assert_eq!(
+                        input.iter_items().collect::<Vec<_>>(),
+                        [(iron(), 12), (copper(), 8)]
+                    );
+                    assert!(output.is_empty());
+                }
+                _ => {
+                    assert!(input.is_empty());
+                    assert_eq!(output.iter_items().collect::<Vec<_>>(), [(copper(), 8)]);
+                }
+            }
+        }
 
-        // Step.
-        unsafe { factorial_step(engine_ptr) };
+        unsafe { factorial_destroy(engine) };
+    }
 
-        // Poll events.
-        let mut event_buffer = FfiEventBuffer {
-            events: ptr::null(),
+    #[test]
+    fn transport_items_keep_identity_via_ffi() {
+        let mut builder = FactoryBuilder::new();
+        builder.node("miner").source(iron(), 1.0);
+        builder.node("chest");
+        builder.belt("miner", "chest", 1.0);
+        let factory = builder.build().unwrap();
+        let edge = edge_id_to_ffi(factory.edges[0]);
+        let engine = factory.engine;
+        let engine = Box::into_raw(Box::new(FactorialEngine::new(engine)));
+
+        let mut buffer = FfiTransportItemBuffer {
+            items: ptr::null(),
             count: 0,
         };
-        let result = unsafe { factorial_poll_events(engine_ptr, &mut event_buffer) };
+        for _ in 0..3 {
+            unsafe { factorial_step(engine) };
+        }
+        let result = unsafe { factorial_get_transport_items(engine, edge, &mut buffer) };
         assert_eq!(result, FactorialResult::Ok);
+        assert_eq!(buffer.count, 2);
+        let items = unsafe { std::slice::from_raw_parts(buffer.items, 2) };
+        let (id, position) = (items[0].instance_id, items[0].position);
+        assert_eq!(items[0].item_type, iron().0);
+        assert!(position > 0);
 
-        // The source produces items, so we should see at least an ItemProduced event.
-        assert!(event_buffer.count > 0, "expected at least one event");
-
-        let events =
-            unsafe { std::slice::from_raw_parts(event_buffer.events, event_buffer.count as usize) };
-
-        // Find the ItemProduced event.
-        let produced = events.iter().find(|e| e.kind == FfiEventKind::ItemProduced);
-        assert!(produced.is_some(), "expected an ItemProduced event");
+        unsafe { factorial_step(engine) };
+        unsafe { factorial_get_transport_items(engine, edge, &mut buffer) };
+        let items = unsafe { std::slice::from_raw_parts(buffer.items, buffer.count as usize) };
+        let same = items.iter().find(|i| i.instance_id == id).unwrap();
+        assert!(same.position > position);
 
-        let produced = produced.unwrap();
-        assert_eq!(produced.node, node_ffi_id);
-        assert_eq!(produced.item_type, 0); // iron = ItemTypeId(0)
-        assert_eq!(produced.quantity, 2);
+        let result = unsafe { factorial_get_transport_items(engine, 9999, &mut buffer) };
+        assert_eq!(result, FactorialResult::EdgeNotFound);
 
-        unsafe { factorial_destroy(engine_ptr) };
+        unsafe { factorial_destroy(engine) };
     }
 
-    // -----------------------------------------------------------------------
-    // Test 9: Null pointer inputs handled gracefully across all functions
-    // -----------------------------------------------------------------------
     #[test]
-    fn null_pointer_handling() {
-        // All functions that take engine should return NullPointer when null.
-        assert_eq!(
-            unsafe { factorial_step(ptr::null_mut()) },
-            FactorialResult::NullPointer
-        );
-        assert_eq!(
-            unsafe { factorial_advance(ptr::null_mut(), 0) },
-            FactorialResult::NullPointer
-        );
-        assert_eq!(
-            unsafe { factorial_add_node(ptr::null_mut(), 0, &mut 0u64) },
-            FactorialResult::NullPointer
-        );
-        assert_eq!(
-            unsafe { factorial_remove_node(ptr::null_mut(), 0) },
-            FactorialResult::NullPointer
-        );
-        assert_eq!(
-            unsafe { factorial_connect(ptr::null_mut(), 0, 0, &mut 0u64) },
-            FactorialResult::NullPointer
-        );
-        assert_eq!(
-            unsafe { factorial_disconnect(ptr::null_mut(), 0) },
-            FactorialResult::NullPointer
+    fn bottleneck_digest_via_ffi() {
+        let mut core = Engine::new(SimulationStrategy::Tick);
+        let mine = factorial_core::test_utils::add_node(&mut core, make_source(iron(), 1.0), 0, 2);
+        let smelter = factorial_core::test_utils::add_node(
+            &mut core,
+            make_recipe(vec![(copper(), 1)], vec![(gear(), 1)], 1),
+            5,
+            5,
         );
+        let engine = Box::into_raw(Box::new(FactorialEngine::new(core)));
+        for _ in 0..5 {
+            unsafe { factorial_step(engine) };
+        }
 
-        let mut mr = FfiMutationResult {
-            added_nodes: ptr::null(),
-            added_node_count: 0,
-            added_edges: ptr::null(),
-            added_edge_count: 0,
+        let mut buffer = FfiBottleneckBuffer {
+            items: ptr::null(),
+            count: 0,
         };
+        let result = unsafe { factorial_get_bottleneck_digest(engine, &mut buffer) };
+        assert_eq!(result, FactorialResult::Ok);
+        let items = unsafe { std::slice::from_raw_parts(buffer.items, buffer.count as usize) };
         assert_eq!(
-            unsafe { factorial_apply_mutations(ptr::null_mut(), &mut mr) },
-            FactorialResult::NullPointer
-        );
-
-        assert_eq!(
-            unsafe { factorial_node_count(ptr::null(), &mut 0u32) },
-            FactorialResult::NullPointer
-        );
-        assert_eq!(
-            unsafe { factorial_edge_count(ptr::null(), &mut 0u32) },
-            FactorialResult::NullPointer
+            items,
+            [
+                FfiItemBottleneck {
+                    item_type: iron().0,
+                    starved_nodes: 0,
+                    blocked_nodes: 1,
+                    worst_starved: 0,
+                    worst_blocked: node_id_to_ffi(mine),
+                },
+                FfiItemBottleneck {
+                    item_type: copper().0,
+                    starved_nodes: 1,
+                    blocked_nodes: 0,
+                    worst_starved: node_id_to_ffi(smelter),
+                    worst_blocked: 0,
+                },
+            ]
         );
         assert_eq!(
-            unsafe { factorial_get_tick(ptr::null(), &mut 0u64) },
+            unsafe { factorial_get_bottleneck_digest(engine, ptr::null_mut()) },
             FactorialResult::NullPointer
         );
+
+        unsafe { factorial_destroy(engine) };
+    }
+
+    #[test]
+    fn step_profile_counts_via_ffi() {
+        let mut builder = FactoryBuilder::new();
+        for i in 0..3 {
+            builder
+                .node(format!("assembler{i}"))
+                .recipe(vec![(iron(), 1)], vec![(gear(), 1)], 10)
+                .with_input(iron(), 10);
+        }
+        let (engine, _) = ffi_engine_from_builder(&builder);
+
+        let mut profile = FfiStepProfile::default();
+        unsafe { factorial_step(engine) };
+        unsafe { factorial_get_step_profile(engine, &mut profile) };
+        assert_eq!(profile, FfiStepProfile::default());
+
         assert_eq!(
-            unsafe { factorial_get_state_hash(ptr::null(), &mut 0u64) },
-            FactorialResult::NullPointer
+            unsafe { factorial_set_profiling(engine, true) },
+            FactorialResult::Ok
         );
+        unsafe { factorial_step(engine) };
+        let result = unsafe { factorial_get_step_profile(engine, &mut profile) };
+        assert_eq!(result, FactorialResult::Ok);
+        assert_eq!(profile.tick, 2);
+        assert_eq!(profile.processors_ticked, 3);
 
-        let mut info = FfiProcessorInfo {
-            state: FfiProcessorState::Idle,
-            progress: 0,
+        unsafe { factorial_set_profiling(engine, false) };
+        unsafe { factorial_get_step_profile(engine, &mut profile) };
+        assert_eq!(profile.tick, 0);
+        unsafe { factorial_destroy(engine) };
+    }
+
+    #[test]
+    fn compact_events_match_polled_events() {
+        let mut builder = FactoryBuilder::new();
+        builder.node("miner").source(iron(), 2.0);
+        builder.node("other").source(iron(), 1.0);
+        let (engine, nodes) = ffi_engine_from_builder(&builder);
+        unsafe { factorial_step(engine) };
+
+        let mut polled = FfiEventBuffer {
+            events: ptr::null(),
+            count: 0,
         };
+        unsafe { factorial_poll_events(engine, &mut polled) };
+        assert!(polled.count > 0);
+        let flat = unsafe { std::slice::from_raw_parts(polled.events, polled.count as usize) };
+
+        let mut buffer = FfiByteBuffer {
+            data: ptr::null_mut(),
+            len: 0,
+        };
+        let result = unsafe { factorial_encode_events_compact(engine, &mut buffer) };
+        assert_eq!(result, FactorialResult::Ok);
+        let bytes = unsafe { std::slice::from_raw_parts(buffer.data, buffer.len) };
+        assert!(bytes.len() < std::mem::size_of_val(flat));
+
+        let events = Engine::decode_events_compact(bytes).unwrap();
+        assert_eq!(events.len(), flat.len());
+        for (event, flat) in events.iter().zip(flat) {
+            let converted = convert_event(event);
+            assert_eq!(converted.kind, flat.kind);
+            assert_eq!(converted.tick, flat.tick);
+            assert_eq!(converted.node, flat.node);
+            assert_eq!(converted.item_type, flat.item_type);
+            assert_eq!(converted.quantity, flat.quantity);
+        }
+        assert!(events.contains(&Event::ItemProduced {
+            node: nodes["miner"],
+            item_type: iron(),
+            quantity: 2,
+            tick: 0,
+        }));
+        unsafe { factorial_free_buffer(buffer) };
+
         assert_eq!(
-            unsafe { factorial_get_processor_state(ptr::null(), 0, &mut info) },
-            FactorialResult::NullPointer
-        );
-        assert_eq!(
-            unsafe { factorial_get_input_inventory_count(ptr::null(), 0, &mut 0u32) },
-            FactorialResult::NullPointer
-        );
-        assert_eq!(
-            unsafe { factorial_get_output_inventory_count(ptr::null(), 0, &mut 0u32) },
+            unsafe { factorial_encode_events_compact(engine, ptr::null_mut()) },
             FactorialResult::NullPointer
         );
+        unsafe { factorial_destroy(engine) };
+    }
 
-        let mut eb = FfiEventBuffer {
-            events: ptr::null(),
-            count: 0,
+    #[test]
+    fn event_log_exported_as_bytes() {
+        let mut builder = FactoryBuilder::new();
+        builder.node("miner").source(iron(), 2.0);
+        let (engine, nodes) = ffi_engine_from_builder(&builder);
+        let miner = nodes["miner"];
+
+        let mut buffer = FfiByteBuffer {
+            data: ptr::null_mut(),
+            len: 0,
         };
         assert_eq!(
-            unsafe { factorial_poll_events(ptr::null(), &mut eb) },
-            FactorialResult::NullPointer
+            unsafe { factorial_enable_event_log(engine) },
+            FactorialResult::Ok
         );
+        for _ in 0..4 {
+            unsafe { factorial_step(engine) };
+        }
+        let result = unsafe { factorial_take_event_log(engine, &mut buffer) };
+        assert_eq!(result, FactorialResult::Ok);
 
-        let mut buf = FfiByteBuffer {
+        let bytes = unsafe { std::slice::from_raw_parts(buffer.data, buffer.len) };
+        let log = factorial_core::event::EventLog::from_bytes(bytes).unwrap();
+        assert_eq!(log.tick_count(), 4);
+        assert!(log.ticks[0].events.contains(&Event::ItemProduced {
+            node: miner,
+            item_type: iron(),
+            quantity: 2,
+            tick: 0,
+        }));
+        unsafe { factorial_free_buffer(buffer) };
+
+        // Disabled logs export as empty but valid logs.
+        unsafe { factorial_disable_event_log(engine) };
+        unsafe { factorial_step(engine) };
+        let mut buffer = FfiByteBuffer {
             data: ptr::null_mut(),
             len: 0,
         };
-        assert_eq!(
-            unsafe { factorial_serialize(ptr::null(), &mut buf) },
-            FactorialResult::NullPointer
-        );
-        assert_eq!(
-            unsafe { factorial_deserialize(ptr::null(), 0, &mut ptr::null_mut()) },
-            FactorialResult::NullPointer
+        unsafe { factorial_take_event_log(engine, &mut buffer) };
+        let bytes = unsafe { std::slice::from_raw_parts(buffer.data, buffer.len) };
+        assert!(
+            factorial_core::event::EventLog::from_bytes(bytes)
+                .unwrap()
+                .is_empty()
         );
+        unsafe { factorial_free_buffer(buffer) };
 
-        // Also test null output pointer variants.
-        let engine = factorial_create();
-        assert_eq!(
-            unsafe { factorial_add_node(engine, 0, ptr::null_mut()) },
-            FactorialResult::NullPointer
-        );
-        assert_eq!(
-            unsafe { factorial_connect(engine, 0, 0, ptr::null_mut()) },
-            FactorialResult::NullPointer
-        );
         assert_eq!(
-            unsafe { factorial_node_count(engine, ptr::null_mut()) },
+            unsafe { factorial_take_event_log(engine, ptr::null_mut()) },
             FactorialResult::NullPointer
         );
+        unsafe { factorial_destroy(engine) };
+    }
+
+    /// Step a factory of 10k miners (one `ItemProduced` each per step) with
+    /// the given cache limit. Returns (cached, dropped, state hash).
+    fn step_ten_thousand_miners(limit: Option<u32>) -> (u32, u64, u64) {
+        let mut builder = FactoryBuilder::new();
+        for i in 0..10_000 {
+            builder.node(format!("miner{i}")).source(iron(), 1.0);
+        }
+        let (mut engine, _) = builder.build().unwrap().into_parts();
+        engine.event_bus = factorial_core::event::EventBus::new(20_000);
+        let engine = Box::into_raw(Box::new(FactorialEngine::new(engine)));
+        // The first step also delivers the 10k `NodeAdded` events.
+        assert_eq!(unsafe { factorial_step(engine) }, FactorialResult::Ok);
+        if let Some(limit) = limit {
+            assert_eq!(
+                unsafe { factorial_set_event_cache_limit(engine, limit) },
+                FactorialResult::Ok
+            );
+        }
+
+        assert_eq!(unsafe { factorial_step(engine) }, FactorialResult::Ok);
+        let mut buffer = FfiEventBuffer {
+            events: ptr::null(),
+            count: 0,
+        };
+        let mut dropped = 0u64;
+        let mut hash = 0u64;
+        unsafe {
+            factorial_poll_events(engine, &mut buffer);
+            factorial_dropped_event_count(engine, &mut dropped);
+            factorial_get_state_hash(engine, &mut hash);
+            factorial_destroy(engine);
+        }
+        (buffer.count, dropped, hash)
+    }
+
+    #[test]
+    fn event_cache_limit_drops_and_reports() {
+        let (cached, dropped, capped_hash) = step_ten_thousand_miners(Some(1_000));
+        assert_eq!(cached, 1_000);
+        assert_eq!(dropped, 9_000);
+
+        let (cached, dropped, hash) = step_ten_thousand_miners(None);
+        assert_eq!(cached, 10_000);
+        assert_eq!(dropped, 0);
+        assert_eq!(hash, capped_hash, "the cap must not affect simulation");
+    }
+
+    #[test]
+    fn dropped_event_count_resets_each_step() {
+        let mut builder = FactoryBuilder::new();
+        builder.node("a").source(iron(), 1.0);
+        builder.node("b").source(iron(), 1.0);
+        let (engine, _) = ffi_engine_from_builder(&builder);
+        let mut dropped = 0u64;
+
+        unsafe { factorial_step(engine) };
+        unsafe { factorial_set_event_cache_limit(engine, 0) };
+        unsafe { factorial_step(engine) };
+        unsafe { factorial_dropped_event_count(engine, &mut dropped) };
+        assert_eq!(dropped, 2);
+
+        unsafe { factorial_set_event_cache_limit(engine, DEFAULT_EVENT_CACHE_LIMIT) };
+        unsafe { factorial_step(engine) };
+        unsafe { factorial_dropped_event_count(engine, &mut dropped) };
+        assert_eq!(dropped, 0);
+
         assert_eq!(
-            unsafe { factorial_edge_count(engine, ptr::null_mut()) },
+            unsafe { factorial_dropped_event_count(engine, ptr::null_mut()) },
             FactorialResult::NullPointer
         );
-        assert_eq!(
-            unsafe { factorial_apply_mutations(engine, ptr::null_mut()) },
-            FactorialResult::NullPointer
+        unsafe { factorial_destroy(engine) };
+    }
+
+    #[test]
+    fn advance_returns_events_from_every_sub_step() {
+        let mut builder = FactoryBuilder::new();
+        builder.strategy(SimulationStrategy::Delta { fixed_timestep: 2 });
+        builder.node("miner").source(iron(), 1.0);
+        let (engine, nodes) = ffi_engine_from_builder(&builder);
+
+        // 6 ticks at a fixed timestep of 2: three sub-steps in one call.
+        assert_eq!(unsafe { factorial_advance(engine, 6) }, FactorialResult::Ok);
+        let mut buffer = FfiEventBuffer {
+            events: ptr::null(),
+            count: 0,
+        };
+        unsafe { factorial_poll_events(engine, &mut buffer) };
+        let events = unsafe { std::slice::from_raw_parts(buffer.events, buffer.count as usize) };
+        let produced: Vec<(u64, u64)> = events
+            .iter()
+            .filter(|e| e.kind == FfiEventKind::ItemProduced)
+            .map(|e| (e.tick, e.node))
+            .collect();
+        let miner = nodes["miner"].data().as_ffi();
+        assert_eq!(produced, vec![(0, miner), (1, miner), (2, miner)]);
+
+        // The next call starts a fresh buffer; a call that runs no sub-step
+        // returns nothing.
+        unsafe { factorial_advance(engine, 1) };
+        unsafe { factorial_poll_events(engine, &mut buffer) };
+        assert_eq!(buffer.count, 0);
+        unsafe { factorial_destroy(engine) };
+    }
+
+    #[test]
+    fn event_history_query_applies_filter() {
+        let mut builder = FactoryBuilder::new();
+        builder.node("miner").source(iron(), 2.0);
+        builder.node("chest").passthrough();
+        builder.flow("miner", "chest", 5.0);
+        let (engine, nodes) = ffi_engine_from_builder(&builder);
+        unsafe { factorial_set_event_history_capacity(engine, 256) };
+        for _ in 0..6 {
+            unsafe { factorial_step(engine) };
+        }
+
+        let blank = convert_event(&Event::NodeRemoved {
+            node: NodeId::default(),
+            tick: 0,
+        });
+        let mut events = [blank; 256];
+        let mut count = 0u32;
+        let filter = FfiEventFilter {
+            kind_mask: 1 << FfiEventKind::ItemProduced as u32,
+            node: node_id_to_ffi(nodes["miner"]),
+            edge: 0,
+            min_tick: 2,
+            max_tick: 4,
+        };
+        let result = unsafe {
+            factorial_query_event_history(engine, &filter, events.as_mut_ptr(), 256, &mut count)
+        };
+        assert_eq!(result, FactorialResult::Ok);
+        let ticks: Vec<u64> = events[..count as usize].iter().map(|e| e.tick).collect();
+        assert_eq!(ticks, vec![2, 3, 4]);
+        assert!(
+            events[..count as usize]
+                .iter()
+                .all(|e| e.kind == FfiEventKind::ItemProduced)
         );
+
+        // A null filter matches everything the history holds.
+        let mut all = 0u32;
+        unsafe {
+            factorial_query_event_history(engine, ptr::null(), events.as_mut_ptr(), 256, &mut all)
+        };
         assert_eq!(
-            unsafe { factorial_serialize(engine, ptr::null_mut()) },
-            FactorialResult::NullPointer
+            all as usize,
+            unsafe { &*engine }
+                .inner
+                .event_history(HistoryFilter::default())
+                .count()
         );
-
+        assert!(all > count);
         unsafe { factorial_destroy(engine) };
     }
 
-    // -----------------------------------------------------------------------
-    // Test 10: Connect two nodes and query edge count
-    // -----------------------------------------------------------------------
     #[test]
-    fn connect_nodes_and_query_edge_count() {
-        let engine = factorial_create();
+    fn event_cursors_read_without_touching_the_poll_cache() {
+        let (engine, miner, _edge) = two_node_ffi_engine();
+        unsafe { factorial_set_event_history_capacity(engine, 256) };
+        let filter = FfiEventFilter {
+            kind_mask: 1 << FfiEventKind::ItemProduced as u32,
+            node: miner,
+            edge: 0,
+            min_tick: 0,
+            max_tick: u64::MAX,
+        };
+        let (mut produced, mut everything) = (0, 0);
+        unsafe {
+            assert_eq!(
+                factorial_create_event_cursor(engine, &filter, &mut produced),
+                FactorialResult::Ok
+            );
+            factorial_create_event_cursor(engine, ptr::null(), &mut everything);
+        }
+        for _ in 0..3 {
+            unsafe { factorial_step(engine) };
+        }
 
-        // Add two nodes.
-        let mut pending_a: FfiPendingNodeId = 0;
-        let mut pending_b: FfiPendingNodeId = 0;
-        unsafe { factorial_add_node(engine, 0, &mut pending_a) };
-        unsafe { factorial_add_node(engine, 0, &mut pending_b) };
+        let blank = convert_event(&Event::NodeRemoved {
+            node: NodeId::default(),
+            tick: 0,
+        });
+        let mut polled = FfiEventBuffer {
+            events: ptr::null(),
+            count: 0,
+        };
+        unsafe { factorial_poll_events(engine, &mut polled) };
+        let before = unsafe { std::slice::from_raw_parts(polled.events, polled.count as usize) }
+            .iter()
+            .map(|e| (e.kind, e.tick))
+            .collect::<Vec<_>>();
 
-        let mut mr = FfiMutationResult {
-            added_nodes: ptr::null(),
-            added_node_count: 0,
-            added_edges: ptr::null(),
-            added_edge_count: 0,
+        let mut events = [blank; 64];
+        let (mut count, mut lagged) = (0u32, true);
+        let result = unsafe {
+            factorial_read_event_cursor(
+                engine,
+                produced,
+                events.as_mut_ptr(),
+                64,
+                &mut count,
+                &mut lagged,
+            )
         };
-        unsafe { factorial_apply_mutations(engine, &mut mr) };
-        assert_eq!(mr.added_node_count, 2);
+        assert_eq!(result, FactorialResult::Ok);
+        assert!(!lagged);
+        let ticks: Vec<u64> = events[..count as usize].iter().map(|e| e.tick).collect();
+        assert_eq!(ticks, vec![0, 1, 2]);
+        unsafe {
+            factorial_read_event_cursor(
+                engine,
+                everything,
+                events.as_mut_ptr(),
+                64,
+                &mut count,
+                &mut lagged,
+            )
+        };
+        assert!(count > 3);
 
-        let node_pairs = unsafe { std::slice::from_raw_parts(mr.added_nodes, 2) };
-        let node_a = node_pairs[0].real_id;
-        let node_b = node_pairs[1].real_id;
+        // The poll cache still holds the last step's events.
+        unsafe { factorial_poll_events(engine, &mut polled) };
+        let after = unsafe { std::slice::from_raw_parts(polled.events, polled.count as usize) }
+            .iter()
+            .map(|e| (e.kind, e.tick))
+            .collect::<Vec<_>>();
+        assert_eq!(before, after);
 
-        // Connect A -> B.
-        let mut pending_edge: FfiPendingEdgeId = 0;
-        let result = unsafe { factorial_connect(engine, node_a, node_b, &mut pending_edge) };
-        assert_eq!(result, FactorialResult::Ok);
+        unsafe {
+            assert_eq!(
+                factorial_destroy_event_cursor(engine, produced),
+                FactorialResult::Ok
+            );
+            assert_eq!(
+                factorial_read_event_cursor(
+                    engine,
+                    produced,
+                    events.as_mut_ptr(),
+                    64,
+                    &mut count,
+                    &mut lagged,
+                ),
+                FactorialResult::EventCursorNotFound
+            );
+            factorial_destroy(engine);
+        }
+    }
 
-        let mut mr2 = FfiMutationResult {
-            added_nodes: ptr::null(),
-            added_node_count: 0,
-            added_edges: ptr::null(),
-            added_edge_count: 0,
+    #[test]
+    fn event_history_polled_across_steps() {
+        let mut builder = FactoryBuilder::new();
+        builder.node("miner").source(iron(), 2.0);
+        let (engine, _) = ffi_engine_from_builder(&builder);
+        assert_eq!(
+            unsafe { factorial_set_event_history_capacity(engine, 64) },
+            FactorialResult::Ok
+        );
+        for _ in 0..5 {
+            unsafe { factorial_step(engine) };
+        }
+
+        let blank = convert_event(&Event::NodeRemoved {
+            node: NodeId::default(),
+            tick: 0,
+        });
+        let mut events = [blank; 64];
+        let mut written = 0u32;
+        let result = unsafe {
+            factorial_poll_event_history(engine, 0, events.as_mut_ptr(), 64, &mut written)
         };
-        unsafe { factorial_apply_mutations(engine, &mut mr2) };
-        assert_eq!(mr2.added_edge_count, 1);
-        assert_eq!(mr2.added_node_count, 0);
+        assert_eq!(result, FactorialResult::Ok);
+        let produced: Vec<u64> = events[..written as usize]
+            .iter()
+            .filter(|e| e.kind == FfiEventKind::ItemProduced)
+            .map(|e| e.tick)
+            .collect();
+        assert_eq!(produced, vec![0, 1, 2, 3, 4]);
 
-        // Verify edge count.
-        let mut edge_count: u32 = 0;
-        unsafe { factorial_edge_count(engine, &mut edge_count) };
-        assert_eq!(edge_count, 1);
+        // `since_tick` filters, `max` truncates to the oldest matches.
+        unsafe { factorial_poll_event_history(engine, 3, events.as_mut_ptr(), 1, &mut written) };
+        assert_eq!(written, 1);
+        assert_eq!(events[0].tick, 3);
+        unsafe { factorial_poll_event_history(engine, 9, events.as_mut_ptr(), 64, &mut written) };
+        assert_eq!(written, 0);
 
+        assert_eq!(
+            unsafe { factorial_poll_event_history(engine, 0, ptr::null_mut(), 1, &mut written) },
+            FactorialResult::NullPointer
+        );
         unsafe { factorial_destroy(engine) };
     }
 
     // -----------------------------------------------------------------------
-    // Test 11: Remove node via FFI
+    // Helper: add a node via FFI, apply mutations, return real FfiNodeId
     // -----------------------------------------------------------------------
-    #[test]
-    fn remove_node_via_ffi() {
-        let engine = factorial_create();
-
-        // Add two nodes.
-        let mut pending_a: FfiPendingNodeId = 0;
-        let mut pending_b: FfiPendingNodeId = 0;
-        unsafe { factorial_add_node(engine, 0, &mut pending_a) };
-        unsafe { factorial_add_node(engine, 0, &mut pending_b) };
-
+    fn ffi_add_node_and_apply(engine: *mut FactorialEngine, building_type: u32) -> FfiNodeId {
+        let mut pending: FfiPendingNodeId = 0;
+        unsafe { factorial_add_node(engine, building_type, &mut pending) };
         let mut mr = FfiMutationResult {
             added_nodes: ptr::null(),
             added_node_count: 0,
             added_edges: ptr::null(),
             added_edge_count: 0,
+            rejected: ptr::null(),
+            rejected_count: 0,
         };
         unsafe { factorial_apply_mutations(engine, &mut mr) };
-        let node_pairs = unsafe { std::slice::from_raw_parts(mr.added_nodes, 2) };
-        let node_a = node_pairs[0].real_id;
-
-        // Verify 2 nodes.
-        let mut count: u32 = 0;
-        unsafe { factorial_node_count(engine, &mut count) };
-        assert_eq!(count, 2);
-
-        // Remove node A.
-        let result = unsafe { factorial_remove_node(engine, node_a) };
-        assert_eq!(result, FactorialResult::Ok);
-
-        let mut mr2 = FfiMutationResult {
-            added_nodes: ptr::null(),
-            added_node_count: 0,
-            added_edges: ptr::null(),
-            added_edge_count: 0,
-        };
-        unsafe { factorial_apply_mutations(engine, &mut mr2) };
-
-        // Verify 1 node remaining.
-        unsafe { factorial_node_count(engine, &mut count) };
-        assert_eq!(count, 1);
-
-        unsafe { factorial_destroy(engine) };
+        assert_eq!(mr.added_node_count, 1);
+        let pairs = unsafe { std::slice::from_raw_parts(mr.added_nodes, 1) };
+        pairs[0].real_id
     }
 
     // -----------------------------------------------------------------------
-    // Test 12: Disconnect edge via FFI
+    // Helper: add two nodes via FFI, connect them, apply, return (node_a, node_b, edge)
     // -----------------------------------------------------------------------
-    #[test]
-    fn disconnect_edge_via_ffi() {
-        let engine = factorial_create();
-
-        // Add two nodes and connect them.
+    fn ffi_add_two_nodes_and_connect(
+        engine: *mut FactorialEngine,
+    ) -> (FfiNodeId, FfiNodeId, FfiEdgeId) {
+        // Add two nodes.
         let mut pending_a: FfiPendingNodeId = 0;
         let mut pending_b: FfiPendingNodeId = 0;
         unsafe { factorial_add_node(engine, 0, &mut pending_a) };
-        unsafe { factorial_add_node(engine, 0, &mut pending_b) };
-
+        unsafe { factorial_add_node(engine, 1, &mut pending_b) };
         let mut mr = FfiMutationResult {
             added_nodes: ptr::null(),
             added_node_count: 0,
             added_edges: ptr::null(),
             added_edge_count: 0,
+            rejected: ptr::null(),
+            rejected_count: 0,
         };
         unsafe { factorial_apply_mutations(engine, &mut mr) };
+        assert_eq!(mr.added_node_count, 2);
         let node_pairs = unsafe { std::slice::from_raw_parts(mr.added_nodes, 2) };
         let node_a = node_pairs[0].real_id;
         let node_b = node_pairs[1].real_id;
 
+        // Connect A -> B.
         let mut pending_edge: FfiPendingEdgeId = 0;
         unsafe { factorial_connect(engine, node_a, node_b, &mut pending_edge) };
         let mut mr2 = FfiMutationResult {
@@ -2846,1348 +12075,2262 @@ mod tests {
             added_node_count: 0,
             added_edges: ptr::null(),
             added_edge_count: 0,
+            rejected: ptr::null(),
+            rejected_count: 0,
         };
         unsafe { factorial_apply_mutations(engine, &mut mr2) };
+        assert_eq!(mr2.added_edge_count, 1);
         let edge_pairs = unsafe { std::slice::from_raw_parts(mr2.added_edges, 1) };
         let edge_id = edge_pairs[0].real_id;
 
-        // Verify edge exists.
-        let mut edge_count: u32 = 0;
-        unsafe { factorial_edge_count(engine, &mut edge_count) };
-        assert_eq!(edge_count, 1);
+        (node_a, node_b, edge_id)
+    }
+
+    // -----------------------------------------------------------------------
+    // Graph events from apply_mutations are stamped with the upcoming tick
+    // -----------------------------------------------------------------------
+    fn poll_graph_events(engine: *mut FactorialEngine) -> Vec<FfiEvent> {
+        let mut buf = FfiEventBuffer {
+            events: ptr::null(),
+            count: 0,
+        };
+        assert_eq!(
+            unsafe { factorial_poll_events(engine, &mut buf) },
+            FactorialResult::Ok
+        );
+        if buf.count == 0 {
+            return Vec::new();
+        }
+        unsafe { std::slice::from_raw_parts(buf.events, buf.count as usize) }
+            .iter()
+            .filter(|e| {
+                matches!(
+                    e.kind,
+                    FfiEventKind::NodeAdded
+                        | FfiEventKind::NodeRemoved
+                        | FfiEventKind::EdgeAdded
+                        | FfiEventKind::EdgeRemoved
+                )
+            })
+            .copied()
+            .collect()
+    }
+
+    #[test]
+    fn graph_events_delivered_with_next_step() {
+        let engine = factorial_create();
+        unsafe { factorial_step(engine) };
+        unsafe { factorial_step(engine) };
+
+        // Two apply calls between step 2 and step 3.
+        let (node_a, node_b, edge) = ffi_add_two_nodes_and_connect(engine);
+        assert!(poll_graph_events(engine).is_empty());
+
+        unsafe { factorial_step(engine) };
+        let events = poll_graph_events(engine);
+        assert_eq!(events.len(), 3);
+        assert!(events.iter().all(|e| e.tick == 2));
+        let added: Vec<FfiNodeId> = events
+            .iter()
+            .filter(|e| e.kind == FfiEventKind::NodeAdded)
+            .map(|e| e.node)
+            .collect();
+        assert!(added.contains(&node_a) && added.contains(&node_b));
+        let edge_event = events
+            .iter()
+            .find(|e| e.kind == FfiEventKind::EdgeAdded)
+            .unwrap();
+        assert_eq!(edge_event.edge, edge);
+        assert_eq!(edge_event.from_node, node_a);
+        assert_eq!(edge_event.to_node, node_b);
+
+        // The following step carries no stale graph events.
+        unsafe { factorial_step(engine) };
+        assert!(poll_graph_events(engine).is_empty());
 
-        // Disconnect.
-        let result = unsafe { factorial_disconnect(engine, edge_id) };
-        assert_eq!(result, FactorialResult::Ok);
-        let mut mr3 = FfiMutationResult {
+        // Removal between step 4 and step 5 is stamped with tick 4.
+        unsafe { factorial_remove_node(engine, node_b) };
+        let mut mr = FfiMutationResult {
             added_nodes: ptr::null(),
             added_node_count: 0,
             added_edges: ptr::null(),
             added_edge_count: 0,
+            rejected: ptr::null(),
+            rejected_count: 0,
         };
-        unsafe { factorial_apply_mutations(engine, &mut mr3) };
-
-        // Verify edge removed.
-        unsafe { factorial_edge_count(engine, &mut edge_count) };
-        assert_eq!(edge_count, 0);
+        unsafe { factorial_apply_mutations(engine, &mut mr) };
+        unsafe { factorial_step(engine) };
+        let events = poll_graph_events(engine);
+        assert_eq!(events.len(), 2);
+        assert!(events.iter().all(|e| e.tick == 4));
 
         unsafe { factorial_destroy(engine) };
     }
 
     // -----------------------------------------------------------------------
-    // Test 13: Deserialize invalid data returns DeserializeError
+    // Test 24: Set source via FFI
     // -----------------------------------------------------------------------
     #[test]
-    fn deserialize_invalid_data() {
-        let garbage = [0u8; 10];
-        let mut engine_ptr: *mut FactorialEngine = ptr::null_mut();
-        let result =
-            unsafe { factorial_deserialize(garbage.as_ptr(), garbage.len(), &mut engine_ptr) };
-        assert_eq!(result, FactorialResult::DeserializeError);
-        assert!(engine_ptr.is_null());
-    }
+    fn set_source_via_ffi() {
+        let engine = factorial_create();
+        let node_id = ffi_add_node_and_apply(engine, 0);
 
-    // -----------------------------------------------------------------------
-    // Test 14: Free null buffer is a no-op (not an error)
-    // -----------------------------------------------------------------------
-    #[test]
-    fn free_null_buffer_is_noop() {
-        let buffer = FfiByteBuffer {
-            data: ptr::null_mut(),
-            len: 0,
+        // Set source: item_type=0 (iron), rate=3.0 as Fixed64 bits.
+        let rate_bits = Fixed64::from_num(3).to_bits();
+        let result = unsafe { factorial_set_source(engine, node_id, 0, rate_bits) };
+        assert_eq!(result, FactorialResult::Ok);
+
+        // Set inventories.
+        let result = unsafe { factorial_set_input_capacity(engine, node_id, 100) };
+        assert_eq!(result, FactorialResult::Ok);
+        let result = unsafe { factorial_set_output_capacity(engine, node_id, 100) };
+        assert_eq!(result, FactorialResult::Ok);
+
+        // Step once.
+        let result = unsafe { factorial_step(engine) };
+        assert_eq!(result, FactorialResult::Ok);
+
+        // Check output: source at rate 3 should produce 3 items.
+        let mut count: u32 = 0;
+        let result = unsafe { factorial_get_output_inventory_count(engine, node_id, &mut count) };
+        assert_eq!(result, FactorialResult::Ok);
+        assert_eq!(count, 3, "source should produce 3 items per tick");
+
+        // Verify processor state is Working.
+        let mut info = FfiProcessorInfo {
+            state: FfiProcessorState::Idle,
+            progress: 0,
         };
-        let result = unsafe { factorial_free_buffer(buffer) };
+        let result = unsafe { factorial_get_processor_state(engine, node_id, &mut info) };
         assert_eq!(result, FactorialResult::Ok);
+        assert_eq!(info.state, FfiProcessorState::Working);
+
+        unsafe { factorial_destroy(engine) };
     }
 
     // -----------------------------------------------------------------------
-    // Test 15: Create delta engine
+    // Test 25: Set fixed processor via FFI
     // -----------------------------------------------------------------------
     #[test]
-    fn create_delta_engine() {
-        let engine = factorial_create_delta(2);
-        assert!(!engine.is_null());
+    fn set_fixed_processor_via_ffi() {
+        let engine = factorial_create();
+        let node_id = ffi_add_node_and_apply(engine, 0);
 
-        // Advance by 5 ticks with fixed_timestep=2 should run 2 steps.
-        let result = unsafe { factorial_advance(engine, 5) };
+        // Build a recipe: 2 iron(0) -> 1 gear(2), duration 5.
+        let inputs = [FfiItemStack {
+            item_type: 0,
+            quantity: 2,
+        }];
+        let outputs = [FfiItemStack {
+            item_type: 2,
+            quantity: 1,
+        }];
+        let recipe = FfiRecipe {
+            input_count: 1,
+            inputs: inputs.as_ptr(),
+            output_count: 1,
+            outputs: outputs.as_ptr(),
+            duration: 5,
+        };
+
+        let result = unsafe { factorial_set_fixed_processor(engine, node_id, &recipe) };
         assert_eq!(result, FactorialResult::Ok);
 
-        let mut tick: u64 = 0;
-        unsafe { factorial_get_tick(engine, &mut tick) };
-        assert_eq!(tick, 2); // 5 / 2 = 2 full steps
+        // Set inventories.
+        unsafe { factorial_set_input_capacity(engine, node_id, 100) };
+        unsafe { factorial_set_output_capacity(engine, node_id, 100) };
+
+        // Verify processor was set by checking state (should be Idle initially).
+        let mut info = FfiProcessorInfo {
+            state: FfiProcessorState::Working,
+            progress: 99,
+        };
+        let result = unsafe { factorial_get_processor_state(engine, node_id, &mut info) };
+        assert_eq!(result, FactorialResult::Ok);
+        assert_eq!(info.state, FfiProcessorState::Idle);
+
+        // Step once -- should stall on missing inputs since input inventory is empty.
+        unsafe { factorial_step(engine) };
+
+        let result = unsafe { factorial_get_processor_state(engine, node_id, &mut info) };
+        assert_eq!(result, FactorialResult::Ok);
+        assert_eq!(info.state, FfiProcessorState::StalledMissingInputs);
+
+        // Null checks.
+        let result = unsafe { factorial_set_fixed_processor(ptr::null_mut(), node_id, &recipe) };
+        assert_eq!(result, FactorialResult::NullPointer);
+        let result = unsafe { factorial_set_fixed_processor(engine, node_id, ptr::null()) };
+        assert_eq!(result, FactorialResult::NullPointer);
 
         unsafe { factorial_destroy(engine) };
     }
 
     // -----------------------------------------------------------------------
-    // Test 16: Multiple steps accumulate state correctly
+    // Test 26: Set transport types via FFI
     // -----------------------------------------------------------------------
     #[test]
-    fn multiple_steps_accumulate() {
-        let engine_ptr = factorial_create();
+    fn set_transport_types_via_ffi() {
+        let engine = factorial_create();
 
-        // Add a source node.
-        let mut pending: FfiPendingNodeId = 0;
-        unsafe { factorial_add_node(engine_ptr, 0, &mut pending) };
+        // We need 4 separate edges for 4 transport types.
+        // Add 5 nodes and 4 edges: A->B, B->C, C->D, D->E.
+        let mut pending_ids: [FfiPendingNodeId; 5] = [0; 5];
+        for pid in &mut pending_ids {
+            unsafe { factorial_add_node(engine, 0, pid) };
+        }
         let mut mr = FfiMutationResult {
             added_nodes: ptr::null(),
             added_node_count: 0,
             added_edges: ptr::null(),
             added_edge_count: 0,
+            rejected: ptr::null(),
+            rejected_count: 0,
         };
-        unsafe { factorial_apply_mutations(engine_ptr, &mut mr) };
-        let pairs = unsafe { std::slice::from_raw_parts(mr.added_nodes, 1) };
-        let node_ffi_id = pairs[0].real_id;
-
-        let engine = unsafe { &mut *engine_ptr };
-        let node_id = ffi_to_node_id(node_ffi_id);
-        engine
-            .inner
-            .set_processor(node_id, make_source(iron(), 1.0));
-        engine
-            .inner
-            .set_input_inventory(node_id, simple_inventory(100));
-        engine
-            .inner
-            .set_output_inventory(node_id, simple_inventory(100));
+        unsafe { factorial_apply_mutations(engine, &mut mr) };
+        assert_eq!(mr.added_node_count, 5);
+        let nodes = unsafe { std::slice::from_raw_parts(mr.added_nodes, 5) };
+        let node_ids: Vec<FfiNodeId> = nodes.iter().map(|p| p.real_id).collect();
 
-        // Step 10 times.
-        for _ in 0..10 {
-            unsafe { factorial_step(engine_ptr) };
+        // Connect 4 edges.
+        let mut pending_edges: [FfiPendingEdgeId; 4] = [0; 4];
+        for i in 0..4 {
+            unsafe {
+                factorial_connect(engine, node_ids[i], node_ids[i + 1], &mut pending_edges[i])
+            };
         }
+        let mut mr2 = FfiMutationResult {
+            added_nodes: ptr::null(),
+            added_node_count: 0,
+            added_edges: ptr::null(),
+            added_edge_count: 0,
+            rejected: ptr::null(),
+            rejected_count: 0,
+        };
+        unsafe { factorial_apply_mutations(engine, &mut mr2) };
+        assert_eq!(mr2.added_edge_count, 4);
+        let edges = unsafe { std::slice::from_raw_parts(mr2.added_edges, 4) };
+        let edge_ids: Vec<FfiEdgeId> = edges.iter().map(|p| p.real_id).collect();
 
-        // Output should have 10 items.
-        let mut count: u32 = 0;
-        unsafe { factorial_get_output_inventory_count(engine_ptr, node_ffi_id, &mut count) };
-        assert_eq!(count, 10);
+        // Set FlowTransport on edge 0.
+        let flow_rate_bits = Fixed64::from_num(5).to_bits();
+        let result = unsafe { factorial_set_flow_transport(engine, edge_ids[0], flow_rate_bits) };
+        assert_eq!(result, FactorialResult::Ok);
 
-        unsafe { factorial_destroy(engine_ptr) };
-    }
+        // Set ItemTransport on edge 1.
+        let speed_bits = Fixed64::from_num(1).to_bits();
+        let result =
+            unsafe { factorial_set_item_transport(engine, edge_ids[1], speed_bits, 10, 1) };
+        assert_eq!(result, FactorialResult::Ok);
 
-    // -----------------------------------------------------------------------
-    // Test 17: Processor state for non-existent node returns NodeNotFound
-    // -----------------------------------------------------------------------
-    #[test]
-    fn processor_state_node_not_found() {
-        let engine = factorial_create();
+        // Set BatchTransport on edge 2.
+        let result = unsafe { factorial_set_batch_transport(engine, edge_ids[2], 20, 5) };
+        assert_eq!(result, FactorialResult::Ok);
 
-        let mut info = FfiProcessorInfo {
-            state: FfiProcessorState::Idle,
-            progress: 0,
-        };
-        // Use a bogus node ID.
-        let result = unsafe { factorial_get_processor_state(engine, 9999, &mut info) };
-        assert_eq!(result, FactorialResult::NodeNotFound);
+        // Set VehicleTransport on edge 3.
+        let result = unsafe { factorial_set_vehicle_transport(engine, edge_ids[3], 50, 10) };
+        assert_eq!(result, FactorialResult::Ok);
+
+        // Verify edge count is still 4.
+        let mut edge_count: u32 = 0;
+        unsafe { factorial_edge_count(engine, &mut edge_count) };
+        assert_eq!(edge_count, 4);
+
+        // Step should not panic with the configured transports.
+        let result = unsafe { factorial_step(engine) };
+        assert_eq!(result, FactorialResult::Ok);
 
         unsafe { factorial_destroy(engine) };
     }
 
-    // -----------------------------------------------------------------------
-    // Test 18: Inventory count for non-existent node returns NodeNotFound
-    // -----------------------------------------------------------------------
     #[test]
-    fn inventory_count_node_not_found() {
-        let engine = factorial_create();
+    fn set_processors_bulk_reports_each_assignment() {
+        let mut builder = FactoryBuilder::new();
+        for i in 0..10 {
+            builder.node(format!("press{i}")).passthrough();
+        }
+        let (engine, nodes) = ffi_engine_from_builder(&builder);
+        let inputs = [FfiItemStack {
+            item_type: iron().0,
+            quantity: 2,
+        }];
+        let outputs = [FfiItemStack {
+            item_type: gear().0,
+            quantity: 1,
+        }];
+        let recipe = |duration| FfiRecipe {
+            input_count: 1,
+            inputs: inputs.as_ptr(),
+            output_count: 1,
+            outputs: outputs.as_ptr(),
+            duration,
+        };
+        let mut assignments: Vec<FfiProcessorAssignment> = (0..10)
+            .map(|i| FfiProcessorAssignment {
+                node: node_id_to_ffi(nodes[&format!("press{i}")]),
+                recipe: recipe(i + 1),
+            })
+            .collect();
+        // A missing node in the middle fails alone.
+        assignments.insert(
+            4,
+            FfiProcessorAssignment {
+                node: 9999,
+                recipe: recipe(99),
+            },
+        );
 
-        let mut count: u32 = 0;
-        let result = unsafe { factorial_get_input_inventory_count(engine, 9999, &mut count) };
-        assert_eq!(result, FactorialResult::NodeNotFound);
+        let mut results = [FactorialResult::InternalError; 11];
+        let result = unsafe {
+            factorial_set_processors_bulk(engine, assignments.as_ptr(), 11, results.as_mut_ptr())
+        };
+        assert_eq!(result, FactorialResult::Ok);
+        for (i, result) in results.iter().enumerate() {
+            let expected = if i == 4 {
+                FactorialResult::NodeNotFound
+            } else {
+                FactorialResult::Ok
+            };
+            assert_eq!(*result, expected, "assignment {i}");
+        }
+        let inner = unsafe { &(*engine).inner };
+        for i in 0..10 {
+            match inner.get_processor(nodes[&format!("press{i}")]) {
+                Some(Processor::Fixed(fixed)) => {
+                    assert_eq!(fixed.duration, i + 1);
+                    assert_eq!(fixed.inputs[0].item_type, iron());
+                    assert_eq!(fixed.outputs[0].item_type, gear());
+                }
+                other => panic!("press{i} has {other:?}"),
+            }
+        }
 
-        let result = unsafe { factorial_get_output_inventory_count(engine, 9999, &mut count) };
-        assert_eq!(result, FactorialResult::NodeNotFound);
+        let result = unsafe {
+            factorial_set_processors_bulk(engine, assignments.as_ptr(), 11, ptr::null_mut())
+        };
+        assert_eq!(result, FactorialResult::NullPointer);
+        let result =
+            unsafe { factorial_set_processors_bulk(engine, ptr::null(), 0, ptr::null_mut()) };
+        assert_eq!(result, FactorialResult::Ok);
 
         unsafe { factorial_destroy(engine) };
     }
 
     // -----------------------------------------------------------------------
-    // Test 19: Full lifecycle -- add nodes, connect, step, serialize, restore
+    // Bulk transport configuration
     // -----------------------------------------------------------------------
     #[test]
-    fn full_lifecycle_integration() {
-        let engine_ptr = factorial_create();
-
-        // Add source and consumer nodes.
-        let mut pending_src: FfiPendingNodeId = 0;
-        let mut pending_consumer: FfiPendingNodeId = 0;
-        unsafe { factorial_add_node(engine_ptr, 0, &mut pending_src) };
-        unsafe { factorial_add_node(engine_ptr, 1, &mut pending_consumer) };
-
+    fn set_transport_bulk_configures_all_edges() {
+        let engine = factorial_create();
+        let mut pending_ids: [FfiPendingNodeId; 11] = [0; 11];
+        for pid in &mut pending_ids {
+            unsafe { factorial_add_node(engine, 0, pid) };
+        }
         let mut mr = FfiMutationResult {
             added_nodes: ptr::null(),
             added_node_count: 0,
             added_edges: ptr::null(),
             added_edge_count: 0,
+            rejected: ptr::null(),
+            rejected_count: 0,
         };
-        unsafe { factorial_apply_mutations(engine_ptr, &mut mr) };
-        assert_eq!(mr.added_node_count, 2);
-
-        let pairs = unsafe { std::slice::from_raw_parts(mr.added_nodes, 2) };
-        let src_ffi = pairs[0].real_id;
-        let consumer_ffi = pairs[1].real_id;
+        unsafe { factorial_apply_mutations(engine, &mut mr) };
+        let nodes = unsafe { std::slice::from_raw_parts(mr.added_nodes, 11) };
+        let node_ids: Vec<FfiNodeId> = nodes.iter().map(|p| p.real_id).collect();
+        for &sink in &node_ids[1..] {
+            let mut pending: FfiPendingEdgeId = 0;
+            unsafe { factorial_connect(engine, node_ids[0], sink, &mut pending) };
+        }
+        unsafe { factorial_apply_mutations(engine, &mut mr) };
+        assert_eq!(mr.added_edge_count, 10);
+        let edge_ids: Vec<FfiEdgeId> = unsafe { std::slice::from_raw_parts(mr.added_edges, 10) }
+            .iter()
+            .map(|p| p.real_id)
+            .collect();
 
-        // Connect source -> consumer.
-        let mut pending_edge: FfiPendingEdgeId = 0;
-        unsafe { factorial_connect(engine_ptr, src_ffi, consumer_ffi, &mut pending_edge) };
-        let mut mr2 = FfiMutationResult {
-            added_nodes: ptr::null(),
-            added_node_count: 0,
-            added_edges: ptr::null(),
-            added_edge_count: 0,
+        let config = FfiTransportConfig {
+            kind: FfiTransportKind::Flow,
+            rate: Fixed64::from_num(3).to_bits(),
+            slot_count: 0,
+            lanes: 0,
+            batch_size: 0,
+            cycle_time: 0,
+            capacity: 0,
+            travel_time: 0,
         };
-        unsafe { factorial_apply_mutations(engine_ptr, &mut mr2) };
-        assert_eq!(mr2.added_edge_count, 1);
+        let result =
+            unsafe { factorial_set_transport_bulk(engine, edge_ids.as_ptr(), 10, &config) };
+        assert_eq!(result, FactorialResult::Ok);
+        let inner = unsafe { &(*engine).inner };
+        for &edge in &edge_ids {
+            assert!(matches!(
+                inner.get_transport(ffi_to_edge_id(edge)),
+                Some(Transport::Flow(flow)) if flow.rate == Fixed64::from_num(3)
+            ));
+        }
 
-        // Set up processors via direct access.
-        let engine = unsafe { &mut *engine_ptr };
-        let src_id = ffi_to_node_id(src_ffi);
-        let consumer_id = ffi_to_node_id(consumer_ffi);
+        // Null checks; an empty batch is a no-op.
+        let result = unsafe { factorial_set_transport_bulk(engine, ptr::null(), 1, &config) };
+        assert_eq!(result, FactorialResult::NullPointer);
+        let result =
+            unsafe { factorial_set_transport_bulk(engine, edge_ids.as_ptr(), 10, ptr::null()) };
+        assert_eq!(result, FactorialResult::NullPointer);
+        let result = unsafe { factorial_set_transport_bulk(engine, ptr::null(), 0, &config) };
+        assert_eq!(result, FactorialResult::Ok);
 
-        engine.inner.set_processor(src_id, make_source(iron(), 5.0));
-        engine
-            .inner
-            .set_input_inventory(src_id, simple_inventory(100));
-        engine
-            .inner
-            .set_output_inventory(src_id, simple_inventory(100));
+        unsafe { factorial_destroy(engine) };
+    }
 
-        engine.inner.set_processor(
-            consumer_id,
-            make_recipe(vec![(iron(), 2)], vec![(gear(), 1)], 3),
+    #[test]
+    fn edge_groups_through_ffi() {
+        let mut core = Engine::new(SimulationStrategy::Tick);
+        let mut prev = factorial_core::test_utils::add_node(
+            &mut core,
+            factorial_core::test_utils::make_source(factorial_core::test_utils::iron(), 1.0),
+            10,
+            10,
         );
-        let mut consumer_input = simple_inventory(100);
-        let _ = consumer_input.input_slots[0].add(iron(), 10);
-        engine
-            .inner
-            .set_input_inventory(consumer_id, consumer_input);
-        engine
-            .inner
-            .set_output_inventory(consumer_id, simple_inventory(100));
-
-        // Step 10 times.
-        for _ in 0..10 {
-            unsafe { factorial_step(engine_ptr) };
+        let mut edges = Vec::new();
+        for _ in 0..3 {
+            let next =
+                factorial_core::test_utils::add_node(&mut core, Processor::Passthrough, 10, 10);
+            edges.push(edge_id_to_ffi(factorial_core::test_utils::connect(
+                &mut core,
+                prev,
+                next,
+                factorial_core::test_utils::make_item_transport(4),
+            )));
+            prev = next;
         }
+        let engine = Box::into_raw(Box::new(FactorialEngine::new(core)));
 
-        // Verify counts.
-        let mut node_count: u32 = 0;
-        let mut edge_count: u32 = 0;
-        unsafe { factorial_node_count(engine_ptr, &mut node_count) };
-        unsafe { factorial_edge_count(engine_ptr, &mut edge_count) };
-        assert_eq!(node_count, 2);
-        assert_eq!(edge_count, 1);
+        let mut group: FfiEdgeGroupId = 0;
+        let result = unsafe { factorial_create_edge_group(engine, edges.as_ptr(), 3, &mut group) };
+        assert_eq!(result, FactorialResult::Ok);
 
-        // Serialize.
-        let mut buffer = FfiByteBuffer {
-            data: ptr::null_mut(),
-            len: 0,
+        let mut written = 0u32;
+        let result =
+            unsafe { factorial_get_group_edges(engine, group, ptr::null_mut(), 0, &mut written) };
+        assert_eq!((result, written), (FactorialResult::BufferTooSmall, 3));
+        let mut out = [0 as FfiEdgeId; 3];
+        let result =
+            unsafe { factorial_get_group_edges(engine, group, out.as_mut_ptr(), 3, &mut written) };
+        assert_eq!(result, FactorialResult::Ok);
+        assert_eq!(out.as_slice(), edges.as_slice());
+
+        let config = FfiTransportConfig {
+            kind: FfiTransportKind::Item,
+            rate: Fixed64::from_num(2).to_bits(),
+            slot_count: 4,
+            lanes: 1,
+            batch_size: 0,
+            cycle_time: 0,
+            capacity: 0,
+            travel_time: 0,
         };
-        let result = unsafe { factorial_serialize(engine_ptr, &mut buffer) };
+        let result = unsafe { factorial_set_group_transport(engine, group, &config) };
         assert_eq!(result, FactorialResult::Ok);
-        assert!(buffer.len > 0);
-
-        // Get original hash.
-        let mut original_hash: u64 = 0;
-        unsafe { factorial_get_state_hash(engine_ptr, &mut original_hash) };
+        let inner = unsafe { &(*engine).inner };
+        for &edge in &edges {
+            assert!(matches!(
+                inner.get_transport(ffi_to_edge_id(edge)),
+                Some(Transport::Item(belt)) if belt.speed == Fixed64::from_num(2)
+            ));
+        }
 
-        // Deserialize.
-        let mut restored_ptr: *mut FactorialEngine = ptr::null_mut();
-        let result = unsafe { factorial_deserialize(buffer.data, buffer.len, &mut restored_ptr) };
+        let mut congested = true;
+        let result = unsafe { factorial_group_is_congested(engine, group, &mut congested) };
         assert_eq!(result, FactorialResult::Ok);
+        assert!(!congested);
 
-        // Verify restored state.
-        let mut restored_hash: u64 = 0;
-        unsafe { factorial_get_state_hash(restored_ptr, &mut restored_hash) };
-        assert_eq!(original_hash, restored_hash);
-
-        let mut restored_tick: u64 = 0;
-        unsafe { factorial_get_tick(restored_ptr, &mut restored_tick) };
-        assert_eq!(restored_tick, 10);
+        assert_eq!(
+            unsafe { factorial_remove_edge_group(engine, group) },
+            FactorialResult::Ok
+        );
+        assert_eq!(
+            unsafe { factorial_group_is_congested(engine, group, &mut congested) },
+            FactorialResult::EdgeGroupNotFound
+        );
+        assert_eq!(
+            unsafe { factorial_set_group_transport(engine, group, &config) },
+            FactorialResult::EdgeGroupNotFound
+        );
 
-        // Clean up.
-        unsafe { factorial_free_buffer(buffer) };
-        unsafe { factorial_destroy(restored_ptr) };
-        unsafe { factorial_destroy(engine_ptr) };
+        unsafe { factorial_destroy(engine) };
     }
 
     // -----------------------------------------------------------------------
-    // Test 20: Event polling for empty engine returns zero events
+    // Test 27: Full FFI lifecycle -- no direct access
     // -----------------------------------------------------------------------
     #[test]
-    fn poll_events_empty_engine() {
+    fn full_ffi_lifecycle_no_direct_access() {
         let engine = factorial_create();
 
-        // Step without any nodes.
-        unsafe { factorial_step(engine) };
+        // Add source node and consumer node.
+        let (src_id, consumer_id, edge_id) = ffi_add_two_nodes_and_connect(engine);
 
-        let mut eb = FfiEventBuffer {
-            events: ptr::null(),
-            count: 0,
+        // Configure source: item_type=0 (iron), rate=5.0.
+        let rate_bits = Fixed64::from_num(5).to_bits();
+        assert_eq!(
+            unsafe { factorial_set_source(engine, src_id, 0, rate_bits) },
+            FactorialResult::Ok
+        );
+
+        // Configure consumer: 2 iron -> 1 gear, 3 ticks.
+        let inputs = [FfiItemStack {
+            item_type: 0,
+            quantity: 2,
+        }];
+        let outputs = [FfiItemStack {
+            item_type: 2,
+            quantity: 1,
+        }];
+        let recipe = FfiRecipe {
+            input_count: 1,
+            inputs: inputs.as_ptr(),
+            output_count: 1,
+            outputs: outputs.as_ptr(),
+            duration: 3,
         };
-        let result = unsafe { factorial_poll_events(engine, &mut eb) };
-        assert_eq!(result, FactorialResult::Ok);
-        assert_eq!(eb.count, 0);
+        assert_eq!(
+            unsafe { factorial_set_fixed_processor(engine, consumer_id, &recipe) },
+            FactorialResult::Ok
+        );
 
-        unsafe { factorial_destroy(engine) };
-    }
+        // Set inventories for both nodes.
+        assert_eq!(
+            unsafe { factorial_set_input_capacity(engine, src_id, 100) },
+            FactorialResult::Ok
+        );
+        assert_eq!(
+            unsafe { factorial_set_output_capacity(engine, src_id, 100) },
+            FactorialResult::Ok
+        );
+        assert_eq!(
+            unsafe { factorial_set_input_capacity(engine, consumer_id, 100) },
+            FactorialResult::Ok
+        );
+        assert_eq!(
+            unsafe { factorial_set_output_capacity(engine, consumer_id, 100) },
+            FactorialResult::Ok
+        );
 
-    // -----------------------------------------------------------------------
-    // Test 21: Poisoned engine blocks calls
-    // -----------------------------------------------------------------------
-    #[test]
-    fn poisoned_engine_blocks_calls() {
-        let engine = factorial_create();
-        assert!(!engine.is_null());
-        let engine_ref = unsafe { &mut *engine };
-        engine_ref.poisoned = true;
-        assert!(unsafe { factorial_is_poisoned(engine) });
-        let result = unsafe { factorial_step(engine) };
-        assert_eq!(result, FactorialResult::Poisoned);
+        // Set transport: flow at rate 10.
+        let transport_rate_bits = Fixed64::from_num(10).to_bits();
+        assert_eq!(
+            unsafe { factorial_set_flow_transport(engine, edge_id, transport_rate_bits) },
+            FactorialResult::Ok
+        );
+
+        // Step 20 times.
+        for _ in 0..20 {
+            let result = unsafe { factorial_step(engine) };
+            assert_eq!(result, FactorialResult::Ok);
+        }
+
+        // Verify tick is 20.
         let mut tick: u64 = 0;
-        let result = unsafe { factorial_get_tick(engine, &mut tick) };
-        assert_eq!(result, FactorialResult::Poisoned);
+        unsafe { factorial_get_tick(engine, &mut tick) };
+        assert_eq!(tick, 20);
+
+        // The source produces 5 iron/tick. Over 20 ticks that's 100 iron produced.
+        // Some will be in the source output, some transported, some consumed.
+        // We just verify the system ran without errors and something happened.
+
+        // Check source output: it should have items (source produces 5/tick, transport
+        // moves up to 10/tick, so source output shouldn't be full).
+        let mut src_output: u32 = 0;
+        unsafe { factorial_get_output_inventory_count(engine, src_id, &mut src_output) };
+
+        // Check consumer output: after ~20 ticks with a 3-tick recipe consuming 2 iron
+        // and receiving a flow of iron, the consumer should have produced some gears.
+        let mut consumer_output: u32 = 0;
+        unsafe { factorial_get_output_inventory_count(engine, consumer_id, &mut consumer_output) };
+
+        // The consumer should have produced at least some gears by now.
+        // With 5 iron/tick flowing in and the recipe consuming 2 iron every 3 ticks,
+        // there should be meaningful output.
+        assert!(
+            consumer_output > 0,
+            "consumer should have produced gears after 20 ticks, got {consumer_output}"
+        );
+
+        // Verify processor states are not stalled.
+        let mut src_info = FfiProcessorInfo {
+            state: FfiProcessorState::Idle,
+            progress: 0,
+        };
+        unsafe { factorial_get_processor_state(engine, src_id, &mut src_info) };
+        assert_eq!(src_info.state, FfiProcessorState::Working);
+
         unsafe { factorial_destroy(engine) };
     }
 
     // -----------------------------------------------------------------------
-    // Test 22: Clear poison allows resume
+    // Test 28: Deserialize with null out_engine pointer
     // -----------------------------------------------------------------------
     #[test]
-    fn clear_poison_allows_resume() {
-        let engine = factorial_create();
-        let engine_ref = unsafe { &mut *engine };
-        engine_ref.poisoned = true;
-        assert!(unsafe { factorial_is_poisoned(engine) });
-        let result = unsafe { factorial_clear_poison(engine) };
-        assert_eq!(result, FactorialResult::Ok);
-        assert!(!unsafe { factorial_is_poisoned(engine) });
-        let result = unsafe { factorial_step(engine) };
-        assert_eq!(result, FactorialResult::Ok);
-        unsafe { factorial_destroy(engine) };
+    fn deserialize_null_out_engine() {
+        let data = [1u8; 10];
+        let result = unsafe { factorial_deserialize(data.as_ptr(), data.len(), ptr::null_mut()) };
+        assert_eq!(result, FactorialResult::NullPointer);
     }
 
     // -----------------------------------------------------------------------
-    // Test 23: New engine not poisoned
+    // Test 29: Serialize null output pointer
     // -----------------------------------------------------------------------
     #[test]
-    fn new_engine_not_poisoned() {
+    fn serialize_null_output() {
         let engine = factorial_create();
-        assert!(!unsafe { factorial_is_poisoned(engine) });
+        let result = unsafe { factorial_serialize(engine, ptr::null_mut()) };
+        assert_eq!(result, FactorialResult::NullPointer);
         unsafe { factorial_destroy(engine) };
     }
 
     // -----------------------------------------------------------------------
-    // Helper: add a node via FFI, apply mutations, return real FfiNodeId
+    // Test 30: Deserialize zero-length data
     // -----------------------------------------------------------------------
-    fn ffi_add_node_and_apply(engine: *mut FactorialEngine, building_type: u32) -> FfiNodeId {
-        let mut pending: FfiPendingNodeId = 0;
-        unsafe { factorial_add_node(engine, building_type, &mut pending) };
-        let mut mr = FfiMutationResult {
-            added_nodes: ptr::null(),
-            added_node_count: 0,
-            added_edges: ptr::null(),
-            added_edge_count: 0,
-        };
-        unsafe { factorial_apply_mutations(engine, &mut mr) };
-        assert_eq!(mr.added_node_count, 1);
-        let pairs = unsafe { std::slice::from_raw_parts(mr.added_nodes, 1) };
-        pairs[0].real_id
+    #[test]
+    fn deserialize_zero_length_data() {
+        let data = [0u8; 0];
+        let mut engine_ptr: *mut FactorialEngine = ptr::null_mut();
+        let result = unsafe { factorial_deserialize(data.as_ptr(), 0, &mut engine_ptr) };
+        assert_eq!(result, FactorialResult::DeserializeError);
+        assert!(engine_ptr.is_null());
     }
 
     // -----------------------------------------------------------------------
-    // Helper: add two nodes via FFI, connect them, apply, return (node_a, node_b, edge)
+    // Test 31: Operations on poisoned engine all return Poisoned
     // -----------------------------------------------------------------------
-    fn ffi_add_two_nodes_and_connect(
-        engine: *mut FactorialEngine,
-    ) -> (FfiNodeId, FfiNodeId, FfiEdgeId) {
-        // Add two nodes.
-        let mut pending_a: FfiPendingNodeId = 0;
-        let mut pending_b: FfiPendingNodeId = 0;
-        unsafe { factorial_add_node(engine, 0, &mut pending_a) };
-        unsafe { factorial_add_node(engine, 1, &mut pending_b) };
+    #[test]
+    fn poisoned_engine_rejects_all_operations() {
+        let engine = factorial_create();
+        let engine_ref = unsafe { &mut *engine };
+        engine_ref.poisoned = true;
+
+        // Mutation operations
+        let mut pending: FfiPendingNodeId = 0;
+        assert_eq!(
+            unsafe { factorial_add_node(engine, 0, &mut pending) },
+            FactorialResult::Poisoned
+        );
+        assert_eq!(
+            unsafe { factorial_remove_node(engine, 0) },
+            FactorialResult::Poisoned
+        );
+        assert_eq!(
+            unsafe { factorial_connect(engine, 0, 0, &mut 0u64) },
+            FactorialResult::Poisoned
+        );
+        assert_eq!(
+            unsafe { factorial_disconnect(engine, 0) },
+            FactorialResult::Poisoned
+        );
+
         let mut mr = FfiMutationResult {
             added_nodes: ptr::null(),
             added_node_count: 0,
             added_edges: ptr::null(),
             added_edge_count: 0,
+            rejected: ptr::null(),
+            rejected_count: 0,
         };
-        unsafe { factorial_apply_mutations(engine, &mut mr) };
-        assert_eq!(mr.added_node_count, 2);
-        let node_pairs = unsafe { std::slice::from_raw_parts(mr.added_nodes, 2) };
-        let node_a = node_pairs[0].real_id;
-        let node_b = node_pairs[1].real_id;
+        assert_eq!(
+            unsafe { factorial_apply_mutations(engine, &mut mr) },
+            FactorialResult::Poisoned
+        );
 
-        // Connect A -> B.
-        let mut pending_edge: FfiPendingEdgeId = 0;
-        unsafe { factorial_connect(engine, node_a, node_b, &mut pending_edge) };
-        let mut mr2 = FfiMutationResult {
-            added_nodes: ptr::null(),
-            added_node_count: 0,
-            added_edges: ptr::null(),
-            added_edge_count: 0,
+        // Advance
+        assert_eq!(
+            unsafe { factorial_advance(engine, 10) },
+            FactorialResult::Poisoned
+        );
+
+        // Queries
+        let mut count: u32 = 0;
+        assert_eq!(
+            unsafe { factorial_node_count(engine, &mut count) },
+            FactorialResult::Poisoned
+        );
+        assert_eq!(
+            unsafe { factorial_edge_count(engine, &mut count) },
+            FactorialResult::Poisoned
+        );
+        let mut hash: u64 = 0;
+        assert_eq!(
+            unsafe { factorial_get_state_hash(engine, &mut hash) },
+            FactorialResult::Poisoned
+        );
+
+        // Serialization
+        let mut buf = FfiByteBuffer {
+            data: ptr::null_mut(),
+            len: 0,
         };
-        unsafe { factorial_apply_mutations(engine, &mut mr2) };
-        assert_eq!(mr2.added_edge_count, 1);
-        let edge_pairs = unsafe { std::slice::from_raw_parts(mr2.added_edges, 1) };
-        let edge_id = edge_pairs[0].real_id;
+        assert_eq!(
+            unsafe { factorial_serialize(engine, &mut buf) },
+            FactorialResult::Poisoned
+        );
 
-        (node_a, node_b, edge_id)
+        // Events
+        let mut eb = FfiEventBuffer {
+            events: ptr::null(),
+            count: 0,
+        };
+        assert_eq!(
+            unsafe { factorial_poll_events(engine, &mut eb) },
+            FactorialResult::Poisoned
+        );
+
+        // Configuration
+        let rate_bits = Fixed64::from_num(1).to_bits();
+        assert_eq!(
+            unsafe { factorial_set_source(engine, 0, 0, rate_bits) },
+            FactorialResult::Poisoned
+        );
+        let inputs = [FfiItemStack {
+            item_type: 0,
+            quantity: 1,
+        }];
+        let outputs = [FfiItemStack {
+            item_type: 1,
+            quantity: 1,
+        }];
+        let recipe = FfiRecipe {
+            input_count: 1,
+            inputs: inputs.as_ptr(),
+            output_count: 1,
+            outputs: outputs.as_ptr(),
+            duration: 5,
+        };
+        assert_eq!(
+            unsafe { factorial_set_fixed_processor(engine, 0, &recipe) },
+            FactorialResult::Poisoned
+        );
+        assert_eq!(
+            unsafe { factorial_set_flow_transport(engine, 0, rate_bits) },
+            FactorialResult::Poisoned
+        );
+
+        unsafe { factorial_destroy(engine) };
     }
 
     // -----------------------------------------------------------------------
-    // Test 24: Set source via FFI
+    // Test 32: Set transport null pointer checks
     // -----------------------------------------------------------------------
     #[test]
-    fn set_source_via_ffi() {
-        let engine = factorial_create();
-        let node_id = ffi_add_node_and_apply(engine, 0);
+    fn transport_null_pointer_checks() {
+        let rate_bits = Fixed64::from_num(1).to_bits();
+        assert_eq!(
+            unsafe { factorial_set_flow_transport(ptr::null_mut(), 0, rate_bits) },
+            FactorialResult::NullPointer
+        );
+        assert_eq!(
+            unsafe { factorial_set_item_transport(ptr::null_mut(), 0, rate_bits, 10, 1) },
+            FactorialResult::NullPointer
+        );
+        assert_eq!(
+            unsafe { factorial_set_batch_transport(ptr::null_mut(), 0, 10, 5) },
+            FactorialResult::NullPointer
+        );
+        assert_eq!(
+            unsafe { factorial_set_vehicle_transport(ptr::null_mut(), 0, 50, 10) },
+            FactorialResult::NullPointer
+        );
+    }
 
-        // Set source: item_type=0 (iron), rate=3.0 as Fixed64 bits.
-        let rate_bits = Fixed64::from_num(3).to_bits();
-        let result = unsafe { factorial_set_source(engine, node_id, 0, rate_bits) };
-        assert_eq!(result, FactorialResult::Ok);
+    // -----------------------------------------------------------------------
+    // Test 33: Set capacity null pointer checks
+    // -----------------------------------------------------------------------
+    #[test]
+    fn capacity_null_pointer_checks() {
+        assert_eq!(
+            unsafe { factorial_set_input_capacity(ptr::null_mut(), 0, 100) },
+            FactorialResult::NullPointer
+        );
+        assert_eq!(
+            unsafe { factorial_set_output_capacity(ptr::null_mut(), 0, 100) },
+            FactorialResult::NullPointer
+        );
+    }
 
-        // Set inventories.
-        let result = unsafe { factorial_set_input_capacity(engine, node_id, 100) };
-        assert_eq!(result, FactorialResult::Ok);
-        let result = unsafe { factorial_set_output_capacity(engine, node_id, 100) };
-        assert_eq!(result, FactorialResult::Ok);
+    // -----------------------------------------------------------------------
+    // Test 34: Source null pointer check
+    // -----------------------------------------------------------------------
+    #[test]
+    fn source_null_pointer_check() {
+        let rate_bits = Fixed64::from_num(1).to_bits();
+        assert_eq!(
+            unsafe { factorial_set_source(ptr::null_mut(), 0, 0, rate_bits) },
+            FactorialResult::NullPointer
+        );
+    }
 
-        // Step once.
-        let result = unsafe { factorial_step(engine) };
-        assert_eq!(result, FactorialResult::Ok);
+    // -----------------------------------------------------------------------
+    // Test 35: Multiple engines interleaved
+    // -----------------------------------------------------------------------
+    #[test]
+    fn multiple_engines_interleaved() {
+        let engine_a = factorial_create();
+        let engine_b = factorial_create();
 
-        // Check output: source at rate 3 should produce 3 items.
-        let mut count: u32 = 0;
-        let result = unsafe { factorial_get_output_inventory_count(engine, node_id, &mut count) };
-        assert_eq!(result, FactorialResult::Ok);
-        assert_eq!(count, 3, "source should produce 3 items per tick");
+        for _ in 0..5 {
+            unsafe { factorial_step(engine_a) };
+        }
+        for _ in 0..3 {
+            unsafe { factorial_step(engine_b) };
+        }
 
-        // Verify processor state is Working.
-        let mut info = FfiProcessorInfo {
-            state: FfiProcessorState::Idle,
-            progress: 0,
-        };
-        let result = unsafe { factorial_get_processor_state(engine, node_id, &mut info) };
-        assert_eq!(result, FactorialResult::Ok);
-        assert_eq!(info.state, FfiProcessorState::Working);
+        let mut tick_a: u64 = 0;
+        let mut tick_b: u64 = 0;
+        unsafe { factorial_get_tick(engine_a, &mut tick_a) };
+        unsafe { factorial_get_tick(engine_b, &mut tick_b) };
+        assert_eq!(tick_a, 5);
+        assert_eq!(tick_b, 3);
 
-        unsafe { factorial_destroy(engine) };
+        unsafe { factorial_destroy(engine_a) };
+        unsafe { factorial_destroy(engine_b) };
     }
 
     // -----------------------------------------------------------------------
-    // Test 25: Set fixed processor via FFI
+    // Test 36: Serialize then deserialize large engine
     // -----------------------------------------------------------------------
     #[test]
-    fn set_fixed_processor_via_ffi() {
-        let engine = factorial_create();
-        let node_id = ffi_add_node_and_apply(engine, 0);
-
-        // Build a recipe: 2 iron(0) -> 1 gear(2), duration 5.
-        let inputs = [FfiItemStack {
-            item_type: 0,
-            quantity: 2,
-        }];
-        let outputs = [FfiItemStack {
-            item_type: 2,
-            quantity: 1,
-        }];
-        let recipe = FfiRecipe {
-            input_count: 1,
-            inputs: inputs.as_ptr(),
-            output_count: 1,
-            outputs: outputs.as_ptr(),
-            duration: 5,
-        };
+    fn serialize_large_engine() {
+        let engine_ptr = factorial_create();
 
-        let result = unsafe { factorial_set_fixed_processor(engine, node_id, &recipe) };
-        assert_eq!(result, FactorialResult::Ok);
+        // Add many nodes
+        for _ in 0..50 {
+            ffi_add_node_and_apply(engine_ptr, 0);
+        }
 
-        // Set inventories.
-        unsafe { factorial_set_input_capacity(engine, node_id, 100) };
-        unsafe { factorial_set_output_capacity(engine, node_id, 100) };
+        let mut count: u32 = 0;
+        unsafe { factorial_node_count(engine_ptr, &mut count) };
+        assert_eq!(count, 50);
 
-        // Verify processor was set by checking state (should be Idle initially).
-        let mut info = FfiProcessorInfo {
-            state: FfiProcessorState::Working,
-            progress: 99,
+        // Serialize
+        let mut buf = FfiByteBuffer {
+            data: ptr::null_mut(),
+            len: 0,
         };
-        let result = unsafe { factorial_get_processor_state(engine, node_id, &mut info) };
+        let result = unsafe { factorial_serialize(engine_ptr, &mut buf) };
         assert_eq!(result, FactorialResult::Ok);
-        assert_eq!(info.state, FfiProcessorState::Idle);
-
-        // Step once -- should stall on missing inputs since input inventory is empty.
-        unsafe { factorial_step(engine) };
+        assert!(buf.len > 0);
 
-        let result = unsafe { factorial_get_processor_state(engine, node_id, &mut info) };
+        // Deserialize
+        let mut restored: *mut FactorialEngine = ptr::null_mut();
+        let result = unsafe { factorial_deserialize(buf.data, buf.len, &mut restored) };
         assert_eq!(result, FactorialResult::Ok);
-        assert_eq!(info.state, FfiProcessorState::StalledMissingInputs);
 
-        // Null checks.
-        let result = unsafe { factorial_set_fixed_processor(ptr::null_mut(), node_id, &recipe) };
-        assert_eq!(result, FactorialResult::NullPointer);
-        let result = unsafe { factorial_set_fixed_processor(engine, node_id, ptr::null()) };
-        assert_eq!(result, FactorialResult::NullPointer);
+        let mut restored_count: u32 = 0;
+        unsafe { factorial_node_count(restored, &mut restored_count) };
+        assert_eq!(restored_count, 50);
 
-        unsafe { factorial_destroy(engine) };
+        unsafe { factorial_free_buffer(buf) };
+        unsafe { factorial_destroy(restored) };
+        unsafe { factorial_destroy(engine_ptr) };
     }
 
     // -----------------------------------------------------------------------
-    // Test 26: Set transport types via FFI
+    // Test 37: Logic -- register, create network, set constant, step, query
     // -----------------------------------------------------------------------
     #[test]
-    fn set_transport_types_via_ffi() {
+    fn logic_constant_signal_round_trip() {
         let engine = factorial_create();
 
-        // We need 4 separate edges for 4 transport types.
-        // Add 5 nodes and 4 edges: A->B, B->C, C->D, D->E.
-        let mut pending_ids: [FfiPendingNodeId; 5] = [0; 5];
-        for pid in &mut pending_ids {
-            unsafe { factorial_add_node(engine, 0, pid) };
-        }
-        let mut mr = FfiMutationResult {
-            added_nodes: ptr::null(),
-            added_node_count: 0,
-            added_edges: ptr::null(),
-            added_edge_count: 0,
-        };
-        unsafe { factorial_apply_mutations(engine, &mut mr) };
-        assert_eq!(mr.added_node_count, 5);
-        let nodes = unsafe { std::slice::from_raw_parts(mr.added_nodes, 5) };
-        let node_ids: Vec<FfiNodeId> = nodes.iter().map(|p| p.real_id).collect();
-
-        // Connect 4 edges.
-        let mut pending_edges: [FfiPendingEdgeId; 4] = [0; 4];
-        for i in 0..4 {
-            unsafe {
-                factorial_connect(engine, node_ids[i], node_ids[i + 1], &mut pending_edges[i])
-            };
-        }
-        let mut mr2 = FfiMutationResult {
-            added_nodes: ptr::null(),
-            added_node_count: 0,
-            added_edges: ptr::null(),
-            added_edge_count: 0,
-        };
-        unsafe { factorial_apply_mutations(engine, &mut mr2) };
-        assert_eq!(mr2.added_edge_count, 4);
-        let edges = unsafe { std::slice::from_raw_parts(mr2.added_edges, 4) };
-        let edge_ids: Vec<FfiEdgeId> = edges.iter().map(|p| p.real_id).collect();
-
-        // Set FlowTransport on edge 0.
-        let flow_rate_bits = Fixed64::from_num(5).to_bits();
-        let result = unsafe { factorial_set_flow_transport(engine, edge_ids[0], flow_rate_bits) };
+        // Register logic module.
+        let result = unsafe { factorial_logic_register(engine) };
         assert_eq!(result, FactorialResult::Ok);
 
-        // Set ItemTransport on edge 1.
-        let speed_bits = Fixed64::from_num(1).to_bits();
+        // Add a node via FFI.
+        let node_ffi = ffi_add_node_and_apply(engine, 0);
+
+        // Create a red wire network.
+        let mut net_id: FfiWireNetworkId = 0;
         let result =
-            unsafe { factorial_set_item_transport(engine, edge_ids[1], speed_bits, 10, 1) };
+            unsafe { factorial_logic_create_network(engine, FfiWireColor::Red, &mut net_id) };
         assert_eq!(result, FactorialResult::Ok);
 
-        // Set BatchTransport on edge 2.
-        let result = unsafe { factorial_set_batch_transport(engine, edge_ids[2], 20, 5) };
+        // Add node to network.
+        let result = unsafe { factorial_logic_add_to_network(engine, net_id, node_ffi) };
         assert_eq!(result, FactorialResult::Ok);
 
-        // Set VehicleTransport on edge 3.
-        let result = unsafe { factorial_set_vehicle_transport(engine, edge_ids[3], 50, 10) };
+        // Set constant combinator: iron(0) = 42.
+        let item_ids: [u32; 1] = [0];
+        let values: [i64; 1] = [Fixed64::from_num(42).to_bits()];
+        let result = unsafe {
+            factorial_logic_set_constant(
+                engine,
+                node_ffi,
+                item_ids.as_ptr(),
+                values.as_ptr(),
+                1,
+                1, // enabled
+            )
+        };
         assert_eq!(result, FactorialResult::Ok);
 
-        // Verify edge count is still 4.
-        let mut edge_count: u32 = 0;
-        unsafe { factorial_edge_count(engine, &mut edge_count) };
-        assert_eq!(edge_count, 4);
-
-        // Step should not panic with the configured transports.
+        // Step to propagate signals.
         let result = unsafe { factorial_step(engine) };
         assert_eq!(result, FactorialResult::Ok);
 
+        // Query the signal value.
+        let mut signal_value: i64 = 0;
+        let result =
+            unsafe { factorial_logic_get_network_signal(engine, net_id, 0, &mut signal_value) };
+        assert_eq!(result, FactorialResult::Ok);
+        assert_eq!(
+            signal_value,
+            Fixed64::from_num(42).to_bits(),
+            "constant combinator should output 42 for iron"
+        );
+
         unsafe { factorial_destroy(engine) };
     }
 
-    // -----------------------------------------------------------------------
-    // Test 27: Full FFI lifecycle -- no direct access
-    // -----------------------------------------------------------------------
     #[test]
-    fn full_ffi_lifecycle_no_direct_access() {
+    fn logic_changed_signals_report() {
         let engine = factorial_create();
+        unsafe { factorial_logic_register(engine) };
+        let node_ffi = ffi_add_node_and_apply(engine, 0);
+        let mut net_id: FfiWireNetworkId = 0;
+        unsafe { factorial_logic_create_network(engine, FfiWireColor::Green, &mut net_id) };
+        unsafe { factorial_logic_add_to_network(engine, net_id, node_ffi) };
 
-        // Add source node and consumer node.
-        let (src_id, consumer_id, edge_id) = ffi_add_two_nodes_and_connect(engine);
-
-        // Configure source: item_type=0 (iron), rate=5.0.
-        let rate_bits = Fixed64::from_num(5).to_bits();
-        assert_eq!(
-            unsafe { factorial_set_source(engine, src_id, 0, rate_bits) },
-            FactorialResult::Ok
-        );
-
-        // Configure consumer: 2 iron -> 1 gear, 3 ticks.
-        let inputs = [FfiItemStack {
-            item_type: 0,
-            quantity: 2,
-        }];
-        let outputs = [FfiItemStack {
-            item_type: 2,
-            quantity: 1,
-        }];
-        let recipe = FfiRecipe {
-            input_count: 1,
-            inputs: inputs.as_ptr(),
-            output_count: 1,
-            outputs: outputs.as_ptr(),
-            duration: 3,
+        let item_ids: [u32; 1] = [3];
+        let values: [i64; 1] = [Fixed64::from_num(7).to_bits()];
+        unsafe {
+            factorial_logic_set_constant(engine, node_ffi, item_ids.as_ptr(), values.as_ptr(), 1, 1)
         };
-        assert_eq!(
-            unsafe { factorial_set_fixed_processor(engine, consumer_id, &recipe) },
-            FactorialResult::Ok
-        );
-
-        // Set inventories for both nodes.
-        assert_eq!(
-            unsafe { factorial_set_input_capacity(engine, src_id, 100) },
-            FactorialResult::Ok
-        );
-        assert_eq!(
-            unsafe { factorial_set_output_capacity(engine, src_id, 100) },
-            FactorialResult::Ok
-        );
-        assert_eq!(
-            unsafe { factorial_set_input_capacity(engine, consumer_id, 100) },
-            FactorialResult::Ok
-        );
-        assert_eq!(
-            unsafe { factorial_set_output_capacity(engine, consumer_id, 100) },
-            FactorialResult::Ok
-        );
-
-        // Set transport: flow at rate 10.
-        let transport_rate_bits = Fixed64::from_num(10).to_bits();
-        assert_eq!(
-            unsafe { factorial_set_flow_transport(engine, edge_id, transport_rate_bits) },
-            FactorialResult::Ok
-        );
-
-        // Step 20 times.
-        for _ in 0..20 {
-            let result = unsafe { factorial_step(engine) };
-            assert_eq!(result, FactorialResult::Ok);
-        }
-
-        // Verify tick is 20.
-        let mut tick: u64 = 0;
-        unsafe { factorial_get_tick(engine, &mut tick) };
-        assert_eq!(tick, 20);
-
-        // The source produces 5 iron/tick. Over 20 ticks that's 100 iron produced.
-        // Some will be in the source output, some transported, some consumed.
-        // We just verify the system ran without errors and something happened.
-
-        // Check source output: it should have items (source produces 5/tick, transport
-        // moves up to 10/tick, so source output shouldn't be full).
-        let mut src_output: u32 = 0;
-        unsafe { factorial_get_output_inventory_count(engine, src_id, &mut src_output) };
-
-        // Check consumer output: after ~20 ticks with a 3-tick recipe consuming 2 iron
-        // and receiving a flow of iron, the consumer should have produced some gears.
-        let mut consumer_output: u32 = 0;
-        unsafe { factorial_get_output_inventory_count(engine, consumer_id, &mut consumer_output) };
-
-        // The consumer should have produced at least some gears by now.
-        // With 5 iron/tick flowing in and the recipe consuming 2 iron every 3 ticks,
-        // there should be meaningful output.
-        assert!(
-            consumer_output > 0,
-            "consumer should have produced gears after 20 ticks, got {consumer_output}"
-        );
+        unsafe { factorial_step(engine) };
 
-        // Verify processor states are not stalled.
-        let mut src_info = FfiProcessorInfo {
-            state: FfiProcessorState::Idle,
-            progress: 0,
+        let mut buffer = FfiSignalChangeBuffer {
+            changes: ptr::null(),
+            count: 0,
         };
-        unsafe { factorial_get_processor_state(engine, src_id, &mut src_info) };
-        assert_eq!(src_info.state, FfiProcessorState::Working);
+        let result = unsafe { factorial_logic_get_changed_signals(engine, &mut buffer) };
+        assert_eq!(result, FactorialResult::Ok);
+        assert_eq!(buffer.count, 1);
+        let change = unsafe { *buffer.changes };
+        assert_eq!(
+            change,
+            FfiSignalChange {
+                node: node_ffi,
+                wire: FfiWireColor::Green,
+                signal: 3,
+                network: net_id,
+                old: 0,
+                new: Fixed64::from_num(7).to_bits(),
+            }
+        );
 
-        unsafe { factorial_destroy(engine) };
-    }
+        // Nothing changes on the next step.
+        unsafe { factorial_step(engine) };
+        unsafe { factorial_logic_get_changed_signals(engine, &mut buffer) };
+        assert_eq!(buffer.count, 0);
+        assert!(buffer.changes.is_null());
 
-    // -----------------------------------------------------------------------
-    // Test 28: Deserialize with null out_engine pointer
-    // -----------------------------------------------------------------------
-    #[test]
-    fn deserialize_null_out_engine() {
-        let data = [1u8; 10];
-        let result = unsafe { factorial_deserialize(data.as_ptr(), data.len(), ptr::null_mut()) };
+        let result = unsafe { factorial_logic_get_changed_signals(engine, ptr::null_mut()) };
         assert_eq!(result, FactorialResult::NullPointer);
-    }
 
-    // -----------------------------------------------------------------------
-    // Test 29: Serialize null output pointer
-    // -----------------------------------------------------------------------
-    #[test]
-    fn serialize_null_output() {
-        let engine = factorial_create();
-        let result = unsafe { factorial_serialize(engine, ptr::null_mut()) };
-        assert_eq!(result, FactorialResult::NullPointer);
         unsafe { factorial_destroy(engine) };
     }
 
     // -----------------------------------------------------------------------
-    // Test 30: Deserialize zero-length data
+    // Test 38: Logic -- circuit control activation
     // -----------------------------------------------------------------------
     #[test]
-    fn deserialize_zero_length_data() {
-        let data = [0u8; 0];
-        let mut engine_ptr: *mut FactorialEngine = ptr::null_mut();
-        let result = unsafe { factorial_deserialize(data.as_ptr(), 0, &mut engine_ptr) };
-        assert_eq!(result, FactorialResult::DeserializeError);
-        assert!(engine_ptr.is_null());
+    fn logic_circuit_control_activation() {
+        let engine = factorial_create();
+
+        // Register logic module.
+        let result = unsafe { factorial_logic_register(engine) };
+        assert_eq!(result, FactorialResult::Ok);
+
+        // Add two nodes: one for constant, one for circuit control.
+        let const_node = ffi_add_node_and_apply(engine, 0);
+        let ctrl_node = ffi_add_node_and_apply(engine, 1);
+
+        // Create a red network with both nodes.
+        let mut net_id: FfiWireNetworkId = 0;
+        unsafe { factorial_logic_create_network(engine, FfiWireColor::Red, &mut net_id) };
+        unsafe { factorial_logic_add_to_network(engine, net_id, const_node) };
+        unsafe { factorial_logic_add_to_network(engine, net_id, ctrl_node) };
+
+        // Set constant: iron(0) = 100.
+        let item_ids: [u32; 1] = [0];
+        let values: [i64; 1] = [Fixed64::from_num(100).to_bits()];
+        unsafe {
+            factorial_logic_set_constant(
+                engine,
+                const_node,
+                item_ids.as_ptr(),
+                values.as_ptr(),
+                1,
+                1,
+            )
+        };
+
+        // Set circuit control on ctrl_node: iron(0) > 50 on red wire.
+        let result = unsafe {
+            factorial_logic_set_circuit_control(
+                engine,
+                ctrl_node,
+                FfiSelectorKind::Signal,
+                0, // iron item ID
+                FfiComparisonOp::Gt,
+                FfiSelectorKind::Constant,
+                Fixed64::from_num(50).to_bits() as u64,
+                FfiWireColor::Red,
+            )
+        };
+        assert_eq!(result, FactorialResult::Ok);
+
+        // Step to evaluate.
+        unsafe { factorial_step(engine) };
+
+        // Query is_active -- should be true (100 > 50).
+        let mut active: u8 = 0;
+        let result = unsafe { factorial_logic_is_active(engine, ctrl_node, &mut active) };
+        assert_eq!(result, FactorialResult::Ok);
+        assert_eq!(active, 1, "circuit control should be active (100 > 50)");
+
+        unsafe { factorial_destroy(engine) };
     }
 
     // -----------------------------------------------------------------------
-    // Test 31: Operations on poisoned engine all return Poisoned
+    // Test 39: Logic -- null pointer handling
     // -----------------------------------------------------------------------
     #[test]
-    fn poisoned_engine_rejects_all_operations() {
-        let engine = factorial_create();
-        let engine_ref = unsafe { &mut *engine };
-        engine_ref.poisoned = true;
-
-        // Mutation operations
-        let mut pending: FfiPendingNodeId = 0;
-        assert_eq!(
-            unsafe { factorial_add_node(engine, 0, &mut pending) },
-            FactorialResult::Poisoned
-        );
-        assert_eq!(
-            unsafe { factorial_remove_node(engine, 0) },
-            FactorialResult::Poisoned
-        );
+    fn logic_null_pointer_handling() {
+        // All logic functions should return NullPointer when engine is null.
         assert_eq!(
-            unsafe { factorial_connect(engine, 0, 0, &mut 0u64) },
-            FactorialResult::Poisoned
+            unsafe { factorial_logic_register(ptr::null_mut()) },
+            FactorialResult::NullPointer
         );
         assert_eq!(
-            unsafe { factorial_disconnect(engine, 0) },
-            FactorialResult::Poisoned
+            unsafe {
+                factorial_logic_create_network(ptr::null_mut(), FfiWireColor::Red, &mut 0u32)
+            },
+            FactorialResult::NullPointer
         );
-
-        let mut mr = FfiMutationResult {
-            added_nodes: ptr::null(),
-            added_node_count: 0,
-            added_edges: ptr::null(),
-            added_edge_count: 0,
-        };
         assert_eq!(
-            unsafe { factorial_apply_mutations(engine, &mut mr) },
-            FactorialResult::Poisoned
+            unsafe { factorial_logic_remove_network(ptr::null_mut(), 0) },
+            FactorialResult::NullPointer
         );
-
-        // Advance
         assert_eq!(
-            unsafe { factorial_advance(engine, 10) },
-            FactorialResult::Poisoned
+            unsafe { factorial_logic_add_to_network(ptr::null_mut(), 0, 0) },
+            FactorialResult::NullPointer
         );
-
-        // Queries
-        let mut count: u32 = 0;
         assert_eq!(
-            unsafe { factorial_node_count(engine, &mut count) },
-            FactorialResult::Poisoned
+            unsafe { factorial_logic_remove_from_network(ptr::null_mut(), 0, 0) },
+            FactorialResult::NullPointer
         );
         assert_eq!(
-            unsafe { factorial_edge_count(engine, &mut count) },
-            FactorialResult::Poisoned
+            unsafe {
+                factorial_logic_set_constant(ptr::null_mut(), 0, ptr::null(), ptr::null(), 0, 1)
+            },
+            FactorialResult::NullPointer
         );
-        let mut hash: u64 = 0;
         assert_eq!(
-            unsafe { factorial_get_state_hash(engine, &mut hash) },
-            FactorialResult::Poisoned
+            unsafe {
+                factorial_logic_set_arithmetic(
+                    ptr::null_mut(),
+                    0,
+                    FfiSelectorKind::Constant,
+                    0,
+                    FfiArithmeticOp::Add,
+                    FfiSelectorKind::Constant,
+                    0,
+                    0,
+                )
+            },
+            FactorialResult::NullPointer
         );
-
-        // Serialization
-        let mut buf = FfiByteBuffer {
-            data: ptr::null_mut(),
-            len: 0,
-        };
         assert_eq!(
-            unsafe { factorial_serialize(engine, &mut buf) },
-            FactorialResult::Poisoned
+            unsafe {
+                factorial_logic_set_decider(
+                    ptr::null_mut(),
+                    0,
+                    FfiSelectorKind::Constant,
+                    0,
+                    FfiComparisonOp::Gt,
+                    FfiSelectorKind::Constant,
+                    0,
+                    FfiDeciderOutputKind::One,
+                    0,
+                )
+            },
+            FactorialResult::NullPointer
         );
-
-        // Events
-        let mut eb = FfiEventBuffer {
-            events: ptr::null(),
-            count: 0,
-        };
         assert_eq!(
-            unsafe { factorial_poll_events(engine, &mut eb) },
-            FactorialResult::Poisoned
+            unsafe {
+                factorial_logic_set_circuit_control(
+                    ptr::null_mut(),
+                    0,
+                    FfiSelectorKind::Constant,
+                    0,
+                    FfiComparisonOp::Gt,
+                    FfiSelectorKind::Constant,
+                    0,
+                    FfiWireColor::Red,
+                )
+            },
+            FactorialResult::NullPointer
         );
-
-        // Configuration
-        let rate_bits = Fixed64::from_num(1).to_bits();
         assert_eq!(
-            unsafe { factorial_set_source(engine, 0, 0, rate_bits) },
-            FactorialResult::Poisoned
+            unsafe { factorial_logic_is_active(ptr::null_mut(), 0, &mut 0u8) },
+            FactorialResult::NullPointer
         );
-        let inputs = [FfiItemStack {
-            item_type: 0,
-            quantity: 1,
-        }];
-        let outputs = [FfiItemStack {
-            item_type: 1,
-            quantity: 1,
-        }];
-        let recipe = FfiRecipe {
-            input_count: 1,
-            inputs: inputs.as_ptr(),
-            output_count: 1,
-            outputs: outputs.as_ptr(),
-            duration: 5,
-        };
         assert_eq!(
-            unsafe { factorial_set_fixed_processor(engine, 0, &recipe) },
-            FactorialResult::Poisoned
+            unsafe { factorial_logic_get_network_signal(ptr::null_mut(), 0, 0, &mut 0i64) },
+            FactorialResult::NullPointer
         );
         assert_eq!(
-            unsafe { factorial_set_flow_transport(engine, 0, rate_bits) },
-            FactorialResult::Poisoned
+            unsafe { factorial_logic_remove_node(ptr::null_mut(), 0) },
+            FactorialResult::NullPointer
         );
-
-        unsafe { factorial_destroy(engine) };
-    }
-
-    // -----------------------------------------------------------------------
-    // Test 32: Set transport null pointer checks
-    // -----------------------------------------------------------------------
-    #[test]
-    fn transport_null_pointer_checks() {
-        let rate_bits = Fixed64::from_num(1).to_bits();
         assert_eq!(
-            unsafe { factorial_set_flow_transport(ptr::null_mut(), 0, rate_bits) },
+            unsafe { factorial_logic_set_inventory_reader(ptr::null_mut(), 0, 0, 0) },
             FactorialResult::NullPointer
         );
+
+        // Test null output pointer variants.
+        let engine = factorial_create();
+        unsafe { factorial_logic_register(engine) };
+
         assert_eq!(
-            unsafe { factorial_set_item_transport(ptr::null_mut(), 0, rate_bits, 10, 1) },
+            unsafe { factorial_logic_create_network(engine, FfiWireColor::Red, ptr::null_mut()) },
             FactorialResult::NullPointer
         );
         assert_eq!(
-            unsafe { factorial_set_batch_transport(ptr::null_mut(), 0, 10, 5) },
+            unsafe { factorial_logic_is_active(engine, 0, ptr::null_mut()) },
             FactorialResult::NullPointer
         );
         assert_eq!(
-            unsafe { factorial_set_vehicle_transport(ptr::null_mut(), 0, 50, 10) },
+            unsafe { factorial_logic_get_network_signal(engine, 0, 0, ptr::null_mut()) },
             FactorialResult::NullPointer
         );
+
+        unsafe { factorial_destroy(engine) };
     }
 
     // -----------------------------------------------------------------------
-    // Test 33: Set capacity null pointer checks
+    // Test 40: Logic -- remove network and remove node
     // -----------------------------------------------------------------------
     #[test]
-    fn capacity_null_pointer_checks() {
-        assert_eq!(
-            unsafe { factorial_set_input_capacity(ptr::null_mut(), 0, 100) },
-            FactorialResult::NullPointer
-        );
+    fn logic_remove_network_and_node() {
+        let engine = factorial_create();
+        unsafe { factorial_logic_register(engine) };
+
+        let node_ffi = ffi_add_node_and_apply(engine, 0);
+
+        // Create network and add node.
+        let mut net_id: FfiWireNetworkId = 0;
+        unsafe { factorial_logic_create_network(engine, FfiWireColor::Red, &mut net_id) };
+        unsafe { factorial_logic_add_to_network(engine, net_id, node_ffi) };
+
+        // Set constant.
+        let item_ids: [u32; 1] = [0];
+        let values: [i64; 1] = [Fixed64::from_num(10).to_bits()];
+        unsafe {
+            factorial_logic_set_constant(engine, node_ffi, item_ids.as_ptr(), values.as_ptr(), 1, 1)
+        };
+
+        // Step to populate signals.
+        unsafe { factorial_step(engine) };
+
+        // Verify signal is present.
+        let mut val: i64 = 0;
+        unsafe { factorial_logic_get_network_signal(engine, net_id, 0, &mut val) };
+        assert_eq!(val, Fixed64::from_num(10).to_bits());
+
+        // Remove node from logic.
+        let result = unsafe { factorial_logic_remove_node(engine, node_ffi) };
+        assert_eq!(result, FactorialResult::Ok);
+
+        // Step again -- signal should be gone since node was removed.
+        unsafe { factorial_step(engine) };
+        unsafe { factorial_logic_get_network_signal(engine, net_id, 0, &mut val) };
+        assert_eq!(val, Fixed64::from_num(0).to_bits());
+
+        // Remove the network itself.
+        let result = unsafe { factorial_logic_remove_network(engine, net_id) };
+        assert_eq!(result, FactorialResult::Ok);
+
+        unsafe { factorial_destroy(engine) };
+    }
+
+    // -----------------------------------------------------------------------
+    // Test 41: Logic -- module not registered returns InternalError
+    // -----------------------------------------------------------------------
+    #[test]
+    fn logic_module_not_registered() {
+        let engine = factorial_create();
+
+        // Do NOT register logic module. All logic calls should return InternalError.
+        let mut net_id: FfiWireNetworkId = 0;
         assert_eq!(
-            unsafe { factorial_set_output_capacity(ptr::null_mut(), 0, 100) },
-            FactorialResult::NullPointer
+            unsafe { factorial_logic_create_network(engine, FfiWireColor::Red, &mut net_id) },
+            FactorialResult::InternalError
         );
+
+        unsafe { factorial_destroy(engine) };
     }
 
     // -----------------------------------------------------------------------
-    // Test 34: Source null pointer check
+    // Test 42: Logic -- set arithmetic combinator
     // -----------------------------------------------------------------------
     #[test]
-    fn source_null_pointer_check() {
-        let rate_bits = Fixed64::from_num(1).to_bits();
+    fn logic_arithmetic_combinator() {
+        let engine = factorial_create();
+        unsafe { factorial_logic_register(engine) };
+
+        let const_node = ffi_add_node_and_apply(engine, 0);
+        let arith_node = ffi_add_node_and_apply(engine, 1);
+
+        // Create network.
+        let mut net_id: FfiWireNetworkId = 0;
+        unsafe { factorial_logic_create_network(engine, FfiWireColor::Red, &mut net_id) };
+        unsafe { factorial_logic_add_to_network(engine, net_id, const_node) };
+        unsafe { factorial_logic_add_to_network(engine, net_id, arith_node) };
+
+        // Set constant: iron(0) = 10.
+        let item_ids: [u32; 1] = [0];
+        let values: [i64; 1] = [Fixed64::from_num(10).to_bits()];
+        unsafe {
+            factorial_logic_set_constant(
+                engine,
+                const_node,
+                item_ids.as_ptr(),
+                values.as_ptr(),
+                1,
+                1,
+            )
+        };
+
+        // Set arithmetic: iron(0) * 3 -> steel(2).
+        let result = unsafe {
+            factorial_logic_set_arithmetic(
+                engine,
+                arith_node,
+                FfiSelectorKind::Signal,
+                0, // iron
+                FfiArithmeticOp::Mul,
+                FfiSelectorKind::Constant,
+                Fixed64::from_num(3).to_bits() as u64,
+                2, // steel output
+            )
+        };
+        assert_eq!(result, FactorialResult::Ok);
+
+        // Tick 1: combinator computes, output delayed.
+        unsafe { factorial_step(engine) };
+
+        // Tick 2: combinator output appears on network.
+        unsafe { factorial_step(engine) };
+
+        let mut val: i64 = 0;
+        unsafe { factorial_logic_get_network_signal(engine, net_id, 2, &mut val) };
         assert_eq!(
-            unsafe { factorial_set_source(ptr::null_mut(), 0, 0, rate_bits) },
-            FactorialResult::NullPointer
+            val,
+            Fixed64::from_num(30).to_bits(),
+            "arithmetic combinator: 10 * 3 = 30 for steel"
         );
+
+        unsafe { factorial_destroy(engine) };
     }
 
     // -----------------------------------------------------------------------
-    // Test 35: Multiple engines interleaved
+    // Test 43: Logic -- set inventory reader
     // -----------------------------------------------------------------------
     #[test]
-    fn multiple_engines_interleaved() {
-        let engine_a = factorial_create();
-        let engine_b = factorial_create();
+    fn logic_set_inventory_reader() {
+        let engine = factorial_create();
+        unsafe { factorial_logic_register(engine) };
 
-        for _ in 0..5 {
-            unsafe { factorial_step(engine_a) };
-        }
-        for _ in 0..3 {
-            unsafe { factorial_step(engine_b) };
-        }
+        let node_ffi = ffi_add_node_and_apply(engine, 0);
 
-        let mut tick_a: u64 = 0;
-        let mut tick_b: u64 = 0;
-        unsafe { factorial_get_tick(engine_a, &mut tick_a) };
-        unsafe { factorial_get_tick(engine_b, &mut tick_b) };
-        assert_eq!(tick_a, 5);
-        assert_eq!(tick_b, 3);
+        let result = unsafe { factorial_logic_set_inventory_reader(engine, node_ffi, node_ffi, 0) };
+        assert_eq!(result, FactorialResult::Ok);
 
-        unsafe { factorial_destroy(engine_a) };
-        unsafe { factorial_destroy(engine_b) };
+        unsafe { factorial_destroy(engine) };
     }
 
     // -----------------------------------------------------------------------
-    // Test 36: Serialize then deserialize large engine
+    // Test 44: Logic -- remove from network
     // -----------------------------------------------------------------------
     #[test]
-    fn serialize_large_engine() {
-        let engine_ptr = factorial_create();
+    fn logic_remove_from_network() {
+        let engine = factorial_create();
+        unsafe { factorial_logic_register(engine) };
 
-        // Add many nodes
-        for _ in 0..50 {
-            ffi_add_node_and_apply(engine_ptr, 0);
-        }
+        let node_ffi = ffi_add_node_and_apply(engine, 0);
 
-        let mut count: u32 = 0;
-        unsafe { factorial_node_count(engine_ptr, &mut count) };
-        assert_eq!(count, 50);
+        let mut net_id: FfiWireNetworkId = 0;
+        unsafe { factorial_logic_create_network(engine, FfiWireColor::Red, &mut net_id) };
+        unsafe { factorial_logic_add_to_network(engine, net_id, node_ffi) };
 
-        // Serialize
-        let mut buf = FfiByteBuffer {
-            data: ptr::null_mut(),
-            len: 0,
+        // Set constant and step.
+        let item_ids: [u32; 1] = [0];
+        let values: [i64; 1] = [Fixed64::from_num(77).to_bits()];
+        unsafe {
+            factorial_logic_set_constant(engine, node_ffi, item_ids.as_ptr(), values.as_ptr(), 1, 1)
         };
-        let result = unsafe { factorial_serialize(engine_ptr, &mut buf) };
-        assert_eq!(result, FactorialResult::Ok);
-        assert!(buf.len > 0);
+        unsafe { factorial_step(engine) };
 
-        // Deserialize
-        let mut restored: *mut FactorialEngine = ptr::null_mut();
-        let result = unsafe { factorial_deserialize(buf.data, buf.len, &mut restored) };
+        // Signal should be present.
+        let mut val: i64 = 0;
+        unsafe { factorial_logic_get_network_signal(engine, net_id, 0, &mut val) };
+        assert_eq!(val, Fixed64::from_num(77).to_bits());
+
+        // Remove node from network.
+        let result = unsafe { factorial_logic_remove_from_network(engine, net_id, node_ffi) };
         assert_eq!(result, FactorialResult::Ok);
 
-        let mut restored_count: u32 = 0;
-        unsafe { factorial_node_count(restored, &mut restored_count) };
-        assert_eq!(restored_count, 50);
+        // Step again -- signal should be gone.
+        unsafe { factorial_step(engine) };
+        unsafe { factorial_logic_get_network_signal(engine, net_id, 0, &mut val) };
+        assert_eq!(val, Fixed64::from_num(0).to_bits());
 
-        unsafe { factorial_free_buffer(buf) };
-        unsafe { factorial_destroy(restored) };
-        unsafe { factorial_destroy(engine_ptr) };
+        unsafe { factorial_destroy(engine) };
     }
 
-    // -----------------------------------------------------------------------
-    // Test 37: Logic -- register, create network, set constant, step, query
-    // -----------------------------------------------------------------------
     #[test]
-    fn logic_constant_signal_round_trip() {
+    fn fixed_processor_v3_rolls_output_chances() {
         let engine = factorial_create();
-
-        // Register logic module.
-        let result = unsafe { factorial_logic_register(engine) };
+        let node_id = ffi_add_node_and_apply(engine, 0);
+        let outputs = [
+            FfiItemStack {
+                item_type: 2,
+                quantity: 1,
+            },
+            FfiItemStack {
+                item_type: 5,
+                quantity: 1,
+            },
+        ];
+        let chances = [
+            Fixed64::from_num(1).to_bits(),
+            Fixed64::from_num(0.25).to_bits(),
+        ];
+        let recipe = FfiRecipeV3 {
+            input_count: 0,
+            inputs: ptr::null(),
+            output_count: 2,
+            outputs: outputs.as_ptr(),
+            duration: 1,
+            fluid_input_count: 0,
+            fluid_inputs: ptr::null(),
+            fluid_output_count: 0,
+            fluid_outputs: ptr::null(),
+            output_probabilities: chances.as_ptr(),
+        };
+        let result = unsafe { factorial_set_fixed_processor_v3(engine, node_id, &recipe) };
         assert_eq!(result, FactorialResult::Ok);
+        unsafe { factorial_set_output_capacity(engine, node_id, 10_000) };
+        for _ in 0..400 {
+            unsafe { factorial_step(engine) };
+        }
 
-        // Add a node via FFI.
-        let node_ffi = ffi_add_node_and_apply(engine, 0);
-
-        // Create a red wire network.
-        let mut net_id: FfiWireNetworkId = 0;
-        let result =
-            unsafe { factorial_logic_create_network(engine, FfiWireColor::Red, &mut net_id) };
-        assert_eq!(result, FactorialResult::Ok);
+        let inner = unsafe { &(*engine).inner };
+        let node = ffi_to_node_id(node_id);
+        let output = inner.get_output_inventory(node).unwrap();
+        assert_eq!(output.count_of(ItemTypeId(2)), 400);
+        let rare = output.count_of(ItemTypeId(5));
+        assert!(rare > 50 && rare < 150, "expected ~100, got {rare}");
+        match inner.get_processor(node) {
+            Some(Processor::Fixed(fixed)) => {
+                assert_eq!(fixed.outputs[1].probability, Some(Fixed64::from_num(0.25)));
+            }
+            other => panic!("expected a fixed recipe, got {other:?}"),
+        }
+        unsafe { factorial_destroy(engine) };
+    }
 
-        // Add node to network.
-        let result = unsafe { factorial_logic_add_to_network(engine, net_id, node_ffi) };
+    #[test]
+    fn fixed_processor_v2_consumes_and_produces_fluids() {
+        let engine = factorial_create();
+        let node_id = ffi_add_node_and_apply(engine, 0);
+        let outputs = [FfiItemStack {
+            item_type: 2,
+            quantity: 1,
+        }];
+        let fluid_inputs = [FfiFluidAmount {
+            fluid: 3,
+            amount: Fixed64::from_num(5).to_bits(),
+        }];
+        let fluid_outputs = [FfiFluidAmount {
+            fluid: 4,
+            amount: Fixed64::from_num(2).to_bits(),
+        }];
+        let recipe = FfiRecipeV2 {
+            input_count: 0,
+            inputs: ptr::null(),
+            output_count: 1,
+            outputs: outputs.as_ptr(),
+            duration: 1,
+            fluid_input_count: 1,
+            fluid_inputs: fluid_inputs.as_ptr(),
+            fluid_output_count: 1,
+            fluid_outputs: fluid_outputs.as_ptr(),
+        };
+        let result = unsafe { factorial_set_fixed_processor_v2(engine, node_id, &recipe) };
         assert_eq!(result, FactorialResult::Ok);
+        unsafe { factorial_set_input_capacity(engine, node_id, 100) };
+        unsafe { factorial_set_output_capacity(engine, node_id, 100) };
 
-        // Set constant combinator: iron(0) = 42.
-        let item_ids: [u32; 1] = [0];
-        let values: [i64; 1] = [Fixed64::from_num(42).to_bits()];
+        // No water yet: the recipe starves.
+        unsafe { factorial_step(engine) };
+        let mut info = FfiProcessorInfo {
+            state: FfiProcessorState::Idle,
+            progress: 0,
+        };
+        unsafe { factorial_get_processor_state(engine, node_id, &mut info) };
+        assert_eq!(info.state, FfiProcessorState::StalledMissingInputs);
+
+        // The buffer takes one craft's worth.
+        let mut accepted = 0i64;
         let result = unsafe {
-            factorial_logic_set_constant(
+            factorial_add_fluid_input(
                 engine,
-                node_ffi,
-                item_ids.as_ptr(),
-                values.as_ptr(),
-                1,
-                1, // enabled
+                node_id,
+                3,
+                Fixed64::from_num(8).to_bits(),
+                &mut accepted,
             )
         };
         assert_eq!(result, FactorialResult::Ok);
+        assert_eq!(accepted, Fixed64::from_num(5).to_bits());
 
-        // Step to propagate signals.
-        let result = unsafe { factorial_step(engine) };
-        assert_eq!(result, FactorialResult::Ok);
+        unsafe { factorial_step(engine) };
+        let mut count = 0u32;
+        unsafe { factorial_get_output_inventory_count(engine, node_id, &mut count) };
+        assert_eq!(count, 1);
 
-        // Query the signal value.
-        let mut signal_value: i64 = 0;
-        let result =
-            unsafe { factorial_logic_get_network_signal(engine, net_id, 0, &mut signal_value) };
+        let mut taken = 0i64;
+        let result = unsafe {
+            factorial_take_fluid_output(
+                engine,
+                node_id,
+                4,
+                Fixed64::from_num(10).to_bits(),
+                &mut taken,
+            )
+        };
         assert_eq!(result, FactorialResult::Ok);
-        assert_eq!(
-            signal_value,
-            Fixed64::from_num(42).to_bits(),
-            "constant combinator should output 42 for iron"
-        );
+        assert_eq!(taken, Fixed64::from_num(2).to_bits());
+
+        let result = unsafe { factorial_set_fixed_processor_v2(engine, node_id, ptr::null()) };
+        assert_eq!(result, FactorialResult::NullPointer);
+        let result = unsafe { factorial_add_fluid_input(engine, node_id, 3, 0, ptr::null_mut()) };
+        assert_eq!(result, FactorialResult::NullPointer);
 
         unsafe { factorial_destroy(engine) };
     }
 
-    // -----------------------------------------------------------------------
-    // Test 38: Logic -- circuit control activation
-    // -----------------------------------------------------------------------
     #[test]
-    fn logic_circuit_control_activation() {
+    fn resolve_pending_ids_across_batches_and_reloads() {
         let engine = factorial_create();
+        unsafe { factorial_set_resolution_history(engine, 2) };
+        let mut out = FfiMutationResult {
+            added_nodes: ptr::null(),
+            added_node_count: 0,
+            added_edges: ptr::null(),
+            added_edge_count: 0,
+            rejected: ptr::null(),
+            rejected_count: 0,
+        };
 
-        // Register logic module.
-        let result = unsafe { factorial_logic_register(engine) };
-        assert_eq!(result, FactorialResult::Ok);
-
-        // Add two nodes: one for constant, one for circuit control.
-        let const_node = ffi_add_node_and_apply(engine, 0);
-        let ctrl_node = ffi_add_node_and_apply(engine, 1);
+        let mut batches = Vec::new();
+        for _ in 0..3 {
+            let mut pending: FfiPendingNodeId = 0;
+            unsafe { factorial_add_node(engine, 0, &mut pending) };
+            let mut real: FfiNodeId = 0;
+            assert_eq!(
+                unsafe { factorial_resolve_pending_node(engine, pending, &mut real) },
+                FactorialResult::PendingQueued
+            );
+            unsafe { factorial_apply_mutations(engine, &mut out) };
+            let pairs = unsafe { std::slice::from_raw_parts(out.added_nodes, 1) };
+            batches.push((pending, pairs[0].real_id));
+        }
 
-        // Create a red network with both nodes.
-        let mut net_id: FfiWireNetworkId = 0;
-        unsafe { factorial_logic_create_network(engine, FfiWireColor::Red, &mut net_id) };
-        unsafe { factorial_logic_add_to_network(engine, net_id, const_node) };
-        unsafe { factorial_logic_add_to_network(engine, net_id, ctrl_node) };
+        // The oldest of three batches fell out of a two-batch history.
+        let mut real: FfiNodeId = 0;
+        assert_eq!(
+            unsafe { factorial_resolve_pending_node(engine, batches[0].0, &mut real) },
+            FactorialResult::PendingExpired
+        );
+        for &(pending, expected) in &batches[1..] {
+            assert_eq!(
+                unsafe { factorial_resolve_pending_node(engine, pending, &mut real) },
+                FactorialResult::Ok
+            );
+            assert_eq!(real, expected);
+        }
+        assert_eq!(
+            unsafe { factorial_resolve_pending_node(engine, 1000, &mut real) },
+            FactorialResult::PendingUnknown
+        );
+        let mut edge: FfiEdgeId = 0;
+        assert_eq!(
+            unsafe { factorial_resolve_pending_edge(engine, 0, &mut edge) },
+            FactorialResult::PendingUnknown
+        );
 
-        // Set constant: iron(0) = 100.
-        let item_ids: [u32; 1] = [0];
-        let values: [i64; 1] = [Fixed64::from_num(100).to_bits()];
-        unsafe {
-            factorial_logic_set_constant(
-                engine,
-                const_node,
-                item_ids.as_ptr(),
-                values.as_ptr(),
-                1,
-                1,
-            )
+        // A reloaded engine keeps issuing fresh pending ids.
+        let mut buffer = FfiByteBuffer {
+            data: ptr::null_mut(),
+            len: 0,
         };
+        unsafe { factorial_serialize(engine, &mut buffer) };
+        let mut restored: *mut FactorialEngine = ptr::null_mut();
+        unsafe { factorial_deserialize(buffer.data, buffer.len, &mut restored) };
+        let mut pending: FfiPendingNodeId = 0;
+        unsafe { factorial_add_node(restored, 0, &mut pending) };
+        assert!(batches.iter().all(|&(old, _)| old < pending));
 
-        // Set circuit control on ctrl_node: iron(0) > 50 on red wire.
-        let result = unsafe {
-            factorial_logic_set_circuit_control(
-                engine,
-                ctrl_node,
-                FfiSelectorKind::Signal,
-                0, // iron item ID
-                FfiComparisonOp::Gt,
-                FfiSelectorKind::Constant,
-                Fixed64::from_num(50).to_bits() as u64,
-                FfiWireColor::Red,
-            )
+        assert_eq!(
+            unsafe { factorial_resolve_pending_node(engine, 0, ptr::null_mut()) },
+            FactorialResult::NullPointer
+        );
+
+        unsafe { factorial_free_buffer(buffer) };
+        unsafe { factorial_destroy(restored) };
+        unsafe { factorial_destroy(engine) };
+    }
+
+    #[test]
+    fn apply_mutations_results_follow_call_order() {
+        let engine = factorial_create();
+        let mut out = FfiMutationResult {
+            added_nodes: ptr::null(),
+            added_node_count: 0,
+            added_edges: ptr::null(),
+            added_edge_count: 0,
+            rejected: ptr::null(),
+            rejected_count: 0,
         };
-        assert_eq!(result, FactorialResult::Ok);
 
-        // Step to evaluate.
-        unsafe { factorial_step(engine) };
+        let mut real_nodes = Vec::new();
+        for cycle in 0..3u32 {
+            // A, B, C with distinct building types so each is identifiable.
+            let pending: Vec<FfiPendingNodeId> = (0..3)
+                .map(|i| {
+                    let mut p: FfiPendingNodeId = 0;
+                    unsafe { factorial_add_node(engine, cycle * 3 + i, &mut p) };
+                    p
+                })
+                .collect();
+            unsafe { factorial_apply_mutations(engine, &mut out) };
+            assert_eq!(out.added_node_count, 3);
+            let pairs = unsafe { std::slice::from_raw_parts(out.added_nodes, 3) };
+            for (i, pair) in pairs.iter().enumerate() {
+                assert_eq!(pair.pending_id, pending[i], "cycle {cycle}, index {i}");
+                let nid = ffi_to_node_id(pair.real_id);
+                let building = unsafe { &*engine }
+                    .inner
+                    .graph
+                    .get_node(nid)
+                    .unwrap()
+                    .building_type;
+                assert_eq!(building, BuildingTypeId(cycle * 3 + i as u32));
+            }
+            real_nodes.extend(pairs.iter().map(|p| p.real_id));
+        }
 
-        // Query is_active -- should be true (100 > 50).
-        let mut active: u8 = 0;
-        let result = unsafe { factorial_logic_is_active(engine, ctrl_node, &mut active) };
-        assert_eq!(result, FactorialResult::Ok);
-        assert_eq!(active, 1, "circuit control should be active (100 > 50)");
+        // Edges follow connect-call order too: C->A, A->B, B->C.
+        let order = [(2, 0), (0, 1), (1, 2)];
+        let pending: Vec<FfiPendingEdgeId> = order
+            .iter()
+            .map(|&(from, to)| {
+                let mut p: FfiPendingEdgeId = 0;
+                unsafe { factorial_connect(engine, real_nodes[from], real_nodes[to], &mut p) };
+                p
+            })
+            .collect();
+        unsafe { factorial_apply_mutations(engine, &mut out) };
+        let pairs = unsafe { std::slice::from_raw_parts(out.added_edges, 3) };
+        for (i, pair) in pairs.iter().enumerate() {
+            assert_eq!(pair.pending_id, pending[i]);
+            let data = unsafe { &*engine }
+                .inner
+                .graph
+                .get_edge(ffi_to_edge_id(pair.real_id))
+                .unwrap()
+                .clone();
+            assert_eq!(node_id_to_ffi(data.from), real_nodes[order[i].0]);
+            assert_eq!(node_id_to_ffi(data.to), real_nodes[order[i].1]);
+        }
 
         unsafe { factorial_destroy(engine) };
     }
 
-    // -----------------------------------------------------------------------
-    // Test 39: Logic -- null pointer handling
-    // -----------------------------------------------------------------------
     #[test]
-    fn logic_null_pointer_handling() {
-        // All logic functions should return NullPointer when engine is null.
+    fn mutation_lanes_apply_in_lane_order_over_ffi() {
+        let mut results = Vec::new();
+        for order in [
+            [(1u8, 10u32), (0, 20), (1, 11)],
+            [(0, 20), (1, 10), (1, 11)],
+        ] {
+            let engine = factorial_create();
+            for (lane, building) in order {
+                let mut pending: FfiPendingNodeId = 0;
+                let result =
+                    unsafe { factorial_add_node_in_lane(engine, lane, building, &mut pending) };
+                assert_eq!(result, FactorialResult::Ok);
+            }
+            let mut out = FfiMutationResult {
+                added_nodes: ptr::null(),
+                added_node_count: 0,
+                added_edges: ptr::null(),
+                added_edge_count: 0,
+                rejected: ptr::null(),
+                rejected_count: 0,
+            };
+            unsafe { factorial_apply_mutations(engine, &mut out) };
+            let pairs = unsafe { std::slice::from_raw_parts(out.added_nodes, 3) };
+            let applied: Vec<(u64, u8, BuildingTypeId)> = pairs
+                .iter()
+                .map(|pair| {
+                    let building = unsafe { &*engine }
+                        .inner
+                        .graph
+                        .get_node(ffi_to_node_id(pair.real_id))
+                        .unwrap()
+                        .building_type;
+                    (pair.real_id, pair.lane, building)
+                })
+                .collect();
+
+            // Lane variants of removal and connection queue like the rest.
+            let mut edge: FfiPendingEdgeId = 0;
+            unsafe {
+                factorial_connect_in_lane(engine, 1, pairs[1].real_id, pairs[2].real_id, &mut edge);
+                factorial_remove_node_in_lane(engine, 0, pairs[1].real_id);
+                factorial_apply_mutations(engine, &mut out);
+            }
+            assert_eq!(out.added_edge_count, 0, "lane 0 removed the endpoint first");
+            let mut hash = 0u64;
+            unsafe { factorial_get_state_hash(engine, &mut hash) };
+            results.push((applied, hash));
+            unsafe { factorial_destroy(engine) };
+        }
         assert_eq!(
-            unsafe { factorial_logic_register(ptr::null_mut()) },
-            FactorialResult::NullPointer
+            results[0].0.iter().map(|a| (a.1, a.2)).collect::<Vec<_>>(),
+            vec![
+                (0, BuildingTypeId(20)),
+                (1, BuildingTypeId(10)),
+                (1, BuildingTypeId(11))
+            ]
         );
+        assert_eq!(results[0], results[1]);
+    }
+
+    #[test]
+    fn pool_step_all_matches_stepping_alone() {
+        let build = |rate: f64| {
+            let mut builder = FactoryBuilder::new();
+            builder.node("miner").source(iron(), rate);
+            builder.node("chest").passthrough();
+            builder.flow("miner", "chest", 1.0);
+            ffi_engine_from_builder(&builder).0
+        };
+        let rates = [1.0, 2.0, 3.0, 5.0];
+        let pooled: Vec<_> = rates.iter().map(|&rate| build(rate)).collect();
+        let alone: Vec<_> = rates.iter().map(|&rate| build(rate)).collect();
+
+        let pool = factorial_pool_create();
+        for &engine in &pooled {
+            assert_eq!(
+                unsafe { factorial_pool_add(pool, engine) },
+                FactorialResult::Ok
+            );
+        }
         assert_eq!(
-            unsafe {
-                factorial_logic_create_network(ptr::null_mut(), FfiWireColor::Red, &mut 0u32)
-            },
-            FactorialResult::NullPointer
+            unsafe { factorial_pool_add(pool, pooled[0]) },
+            FactorialResult::InvalidHandle
         );
+        let mut count = 0u32;
+        unsafe { factorial_pool_len(pool, &mut count) };
+        assert_eq!(count, 4);
+
+        let mut results = [FactorialResult::InternalError; 4];
+        for _ in 0..50 {
+            assert_eq!(
+                unsafe { factorial_pool_step_all(pool, 3, results.as_mut_ptr()) },
+                FactorialResult::Ok
+            );
+            assert!(results.iter().all(|&r| r == FactorialResult::Ok));
+        }
+        for &engine in &alone {
+            for _ in 0..50 {
+                unsafe { factorial_step(engine) };
+            }
+        }
+
+        for (&a, &b) in pooled.iter().zip(&alone) {
+            let (mut hash_a, mut hash_b) = (0u64, 0u64);
+            unsafe { factorial_get_state_hash(a, &mut hash_a) };
+            unsafe { factorial_get_state_hash(b, &mut hash_b) };
+            assert_eq!(hash_a, hash_b);
+
+            // Each engine's event cache belongs to it, not the stepping thread.
+            let (mut events_a, mut events_b) = (
+                FfiEventBuffer {
+                    events: ptr::null(),
+                    count: 0,
+                },
+                FfiEventBuffer {
+                    events: ptr::null(),
+                    count: 0,
+                },
+            );
+            unsafe { factorial_poll_events(a, &mut events_a) };
+            unsafe { factorial_poll_events(b, &mut events_b) };
+            assert_eq!(events_a.count, events_b.count);
+        }
+
         assert_eq!(
-            unsafe { factorial_logic_remove_network(ptr::null_mut(), 0) },
-            FactorialResult::NullPointer
+            unsafe { factorial_pool_remove(pool, pooled[1]) },
+            FactorialResult::Ok
         );
         assert_eq!(
-            unsafe { factorial_logic_add_to_network(ptr::null_mut(), 0, 0) },
-            FactorialResult::NullPointer
+            unsafe { factorial_pool_remove(pool, pooled[1]) },
+            FactorialResult::InvalidHandle
         );
+        // Removing only compares the pointer, so a destroyed engine can
+        // still be taken out of the pool.
+        let destroyed = pooled[2];
+        unsafe { factorial_destroy(destroyed) };
         assert_eq!(
-            unsafe { factorial_logic_remove_from_network(ptr::null_mut(), 0, 0) },
-            FactorialResult::NullPointer
+            unsafe { factorial_pool_remove(pool, destroyed) },
+            FactorialResult::Ok
         );
         assert_eq!(
-            unsafe {
-                factorial_logic_set_constant(ptr::null_mut(), 0, ptr::null(), ptr::null(), 0, 1)
-            },
+            unsafe { factorial_pool_step_all(ptr::null_mut(), 2, ptr::null_mut()) },
             FactorialResult::NullPointer
         );
+        assert_eq!(unsafe { factorial_pool_destroy(pool) }, FactorialResult::Ok);
+
+        // Destroying the pool leaves its engines alive.
+        for engine in pooled.into_iter().chain(alone) {
+            if engine == destroyed {
+                continue;
+            }
+            assert_eq!(unsafe { factorial_step(engine) }, FactorialResult::Ok);
+            unsafe { factorial_destroy(engine) };
+        }
+    }
+
+    /// An engine handle shared with reader threads in the concurrency tests.
+    #[derive(Clone, Copy)]
+    struct SharedHandle(*mut FactorialEngine);
+
+    // SAFETY: the tests only make the documented concurrent-read calls
+    // through a shared handle.
+    unsafe impl Send for SharedHandle {}
+    unsafe impl Sync for SharedHandle {}
+
+    impl SharedHandle {
+        fn get(self) -> *mut FactorialEngine {
+            self.0
+        }
+    }
+
+    /// Tick, state hash and iron census as seen by the concurrent-read calls.
+    fn concurrent_snapshot(engine: *const FactorialEngine) -> (u64, u64, FfiItemCensus) {
+        let mut tick = 0;
+        let mut hash = 0;
+        let mut census = FfiItemCensus::default();
         assert_eq!(
-            unsafe {
-                factorial_logic_set_arithmetic(
-                    ptr::null_mut(),
-                    0,
-                    FfiSelectorKind::Constant,
-                    0,
-                    FfiArithmeticOp::Add,
-                    FfiSelectorKind::Constant,
-                    0,
-                    0,
-                )
-            },
-            FactorialResult::NullPointer
+            unsafe { factorial_get_tick(engine, &mut tick) },
+            FactorialResult::Ok
         );
         assert_eq!(
-            unsafe {
-                factorial_logic_set_decider(
-                    ptr::null_mut(),
-                    0,
-                    FfiSelectorKind::Constant,
-                    0,
-                    FfiComparisonOp::Gt,
-                    FfiSelectorKind::Constant,
-                    0,
-                    FfiDeciderOutputKind::One,
-                    0,
-                )
-            },
-            FactorialResult::NullPointer
+            unsafe { factorial_get_state_hash(engine, &mut hash) },
+            FactorialResult::Ok
+        );
+        assert_eq!(
+            unsafe { factorial_item_census(engine, iron().0, &mut census) },
+            FactorialResult::Ok
+        );
+        (tick, hash, census)
+    }
+
+    #[test]
+    fn concurrent_reads_agree_between_steps() {
+        let mut builder = FactoryBuilder::new();
+        builder.node("miner").source(iron(), 2.0);
+        builder.node("chest").passthrough();
+        builder.belt("miner", "chest", 1.0);
+        let (engine, nodes) = ffi_engine_from_builder(&builder);
+        let chest = node_id_to_ffi(nodes["chest"]);
+        let shared = SharedHandle(engine);
+        let (rounds, reads) = if cfg!(miri) { (2, 3) } else { (20, 200) };
+
+        for _ in 0..rounds {
+            assert_eq!(unsafe { factorial_step(engine) }, FactorialResult::Ok);
+            let expected = concurrent_snapshot(engine);
+            std::thread::scope(|scope| {
+                for _ in 0..4 {
+                    scope.spawn(move || {
+                        let engine = shared.get().cast_const();
+                        for _ in 0..reads {
+                            assert_eq!(concurrent_snapshot(engine), expected);
+                            let mut count = 0;
+                            assert_eq!(
+                                unsafe {
+                                    factorial_get_input_inventory_count(engine, chest, &mut count)
+                                },
+                                FactorialResult::Ok
+                            );
+                        }
+                    });
+                }
+            });
+        }
+
+        unsafe { factorial_destroy(engine) };
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    fn reads_during_a_step_report_concurrent_mutation() {
+        use factorial_core::hook::TickPhase;
+        use std::sync::atomic::AtomicBool;
+
+        let mut builder = FactoryBuilder::new();
+        builder.node("miner").source(iron(), 1.0);
+        builder.node("chest").passthrough();
+        builder.flow("miner", "chest", 1.0);
+        let (engine, _) = ffi_engine_from_builder(&builder);
+        let shared = SharedHandle(engine);
+
+        // Hold the step open until a reader has been turned away.
+        let stepping = Arc::new(AtomicBool::new(false));
+        let rejected = Arc::new(AtomicBool::new(false));
+        let (hook_stepping, hook_rejected) = (Arc::clone(&stepping), Arc::clone(&rejected));
+        unsafe { &mut *engine }.inner.register_tick_hook(
+            TickPhase::Pre,
+            0,
+            Box::new(move |_| {
+                hook_stepping.store(true, Ordering::Release);
+                while !hook_rejected.load(Ordering::Acquire) {
+                    std::thread::yield_now();
+                }
+            }),
+        );
+
+        std::thread::scope(|scope| {
+            scope.spawn(move || {
+                let engine = shared.get().cast_const();
+                while !stepping.load(Ordering::Acquire) {
+                    std::thread::yield_now();
+                }
+                let mut tick = u64::MAX;
+                let mut count = 0;
+                assert_eq!(
+                    unsafe { factorial_get_tick(engine, &mut tick) },
+                    FactorialResult::ConcurrentMutation
+                );
+                assert_eq!(
+                    unsafe { factorial_node_count(engine, &mut count) },
+                    FactorialResult::ConcurrentMutation
+                );
+                assert_eq!(tick, u64::MAX, "a rejected read writes nothing");
+                rejected.store(true, Ordering::Release);
+            });
+            assert_eq!(unsafe { factorial_step(shared.get()) }, FactorialResult::Ok);
+        });
+
+        let mut tick = 0;
+        assert_eq!(
+            unsafe { factorial_get_tick(engine, &mut tick) },
+            FactorialResult::Ok
+        );
+        assert_eq!(tick, 1);
+        unsafe { factorial_destroy(engine) };
+    }
+
+    #[test]
+    fn inspector_answers_the_same_queries_as_its_engine() {
+        let mut builder = FactoryBuilder::new();
+        builder.node("miner").source(iron(), 2.0);
+        builder.node("chest").passthrough();
+        builder.flow("miner", "chest", 1.0);
+        let (engine, nodes) = ffi_engine_from_builder(&builder);
+        let chest = node_id_to_ffi(nodes["chest"]);
+        let edge = edge_id_to_ffi(unsafe { &*engine }.inner.graph.edges().next().unwrap().0);
+        // Mutators take `*mut FactorialEngine`, so an inspector cannot be
+        // passed to them; it only reaches the engine through these queries.
+        let inspector = unsafe { factorial_create_inspector(engine) };
+        assert!(!inspector.is_null());
+        for _ in 0..3 {
+            unsafe { factorial_step(engine) };
+        }
+
+        let (mut tick, mut hash, mut nodes_seen, mut edges_seen) = (0, 0, 0, 0);
+        let mut breakdown = FfiHashBreakdown::default();
+        let mut census = FfiItemCensus::default();
+        let mut info = FfiProcessorInfo {
+            state: FfiProcessorState::Idle,
+            progress: u32::MAX,
+        };
+        let (mut input, mut output) = (u32::MAX, u32::MAX);
+        let (mut node_status, mut edge_status) = (FfiNodeStatus::Ghost, FfiNodeStatus::Ghost);
+        unsafe {
+            for result in [
+                factorial_inspector_get_tick(inspector, &mut tick),
+                factorial_inspector_get_state_hash(inspector, &mut hash),
+                factorial_inspector_get_state_hash_breakdown(inspector, &mut breakdown),
+                factorial_inspector_node_count(inspector, &mut nodes_seen),
+                factorial_inspector_edge_count(inspector, &mut edges_seen),
+                factorial_inspector_item_census(inspector, iron().0, &mut census),
+                factorial_inspector_get_processor_state(inspector, chest, &mut info),
+                factorial_inspector_get_input_inventory_count(inspector, chest, &mut input),
+                factorial_inspector_get_output_inventory_count(inspector, chest, &mut output),
+                factorial_inspector_get_node_status(inspector, chest, &mut node_status),
+                factorial_inspector_get_edge_status(inspector, edge, &mut edge_status),
+            ] {
+                assert_eq!(result, FactorialResult::Ok);
+            }
+        }
+        assert_eq!(concurrent_snapshot(engine), (tick, hash, census));
+        assert_eq!((tick, nodes_seen, edges_seen), (3, 2, 1));
+        let mut expected = FfiHashBreakdown::default();
+        unsafe { factorial_get_state_hash_breakdown(engine, &mut expected) };
+        assert_eq!(breakdown, expected);
+        let mut count = 0;
+        unsafe { factorial_get_input_inventory_count(engine, chest, &mut count) };
+        assert_eq!(input, count);
+        unsafe { factorial_get_output_inventory_count(engine, chest, &mut count) };
+        assert_eq!(output, count);
+        assert_eq!(info.progress, 0);
+        assert_eq!(
+            (node_status, edge_status),
+            (FfiNodeStatus::Active, FfiNodeStatus::Active)
+        );
+        assert_eq!(
+            unsafe { factorial_inspector_get_processor_state(inspector, 9999, &mut info) },
+            FactorialResult::NodeNotFound
         );
+
+        // Inspectors are independent of each other and of the engine.
+        let second = unsafe { factorial_create_inspector(engine) };
         assert_eq!(
-            unsafe {
-                factorial_logic_set_circuit_control(
-                    ptr::null_mut(),
-                    0,
-                    FfiSelectorKind::Constant,
-                    0,
-                    FfiComparisonOp::Gt,
-                    FfiSelectorKind::Constant,
-                    0,
-                    FfiWireColor::Red,
-                )
-            },
-            FactorialResult::NullPointer
+            unsafe { factorial_destroy_inspector(second) },
+            FactorialResult::Ok
         );
+        unsafe { factorial_destroy(engine) };
         assert_eq!(
-            unsafe { factorial_logic_is_active(ptr::null_mut(), 0, &mut 0u8) },
-            FactorialResult::NullPointer
+            unsafe { factorial_inspector_get_tick(inspector, &mut tick) },
+            FactorialResult::InvalidHandle
         );
         assert_eq!(
-            unsafe { factorial_logic_get_network_signal(ptr::null_mut(), 0, 0, &mut 0i64) },
-            FactorialResult::NullPointer
+            unsafe { factorial_destroy_inspector(inspector) },
+            FactorialResult::Ok
         );
         assert_eq!(
-            unsafe { factorial_logic_remove_node(ptr::null_mut(), 0) },
+            unsafe { factorial_inspector_get_tick(ptr::null(), &mut tick) },
             FactorialResult::NullPointer
         );
+        assert!(unsafe { factorial_create_inspector(ptr::null()) }.is_null());
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    fn inspector_reads_are_refused_while_the_engine_mutates() {
+        let mut builder = FactoryBuilder::new();
+        builder.node("chest").passthrough();
+        let (engine, _) = ffi_engine_from_builder(&builder);
+        let inspector = unsafe { factorial_create_inspector(engine) };
+        let mut tick = u64::MAX;
+        {
+            let _mutation = unsafe { MutationGuard::begin(engine) };
+            assert_eq!(
+                unsafe { factorial_inspector_get_tick(inspector, &mut tick) },
+                FactorialResult::ConcurrentMutation
+            );
+            assert_eq!(tick, u64::MAX);
+        }
         assert_eq!(
-            unsafe { factorial_logic_set_inventory_reader(ptr::null_mut(), 0, 0, 0) },
-            FactorialResult::NullPointer
+            unsafe { factorial_inspector_get_tick(inspector, &mut tick) },
+            FactorialResult::Ok
         );
+        unsafe { factorial_destroy_inspector(inspector) };
+        unsafe { factorial_destroy(engine) };
+    }
 
-        // Test null output pointer variants.
-        let engine = factorial_create();
-        unsafe { factorial_logic_register(engine) };
+    #[test]
+    fn extension_mask_reports_first_use_and_explicit_enables() {
+        let mut builder = FactoryBuilder::new();
+        builder.node("chest").passthrough();
+        let (engine, nodes) = ffi_engine_from_builder(&builder);
+        let mut mask = u32::MAX;
+        assert_eq!(
+            unsafe { factorial_enabled_extensions(engine, &mut mask) },
+            FactorialResult::Ok
+        );
+        assert_eq!(mask, 0);
 
+        let inner = &mut unsafe { &mut *engine }.inner;
+        inner
+            .create_crafting_queue(nodes["chest"], Fixed64::ONE)
+            .unwrap();
         assert_eq!(
-            unsafe { factorial_logic_create_network(engine, FfiWireColor::Red, ptr::null_mut()) },
-            FactorialResult::NullPointer
+            unsafe { factorial_enable_extension(engine, ExtensionId::Stats as u32) },
+            FactorialResult::Ok
         );
         assert_eq!(
-            unsafe { factorial_logic_is_active(engine, 0, ptr::null_mut()) },
-            FactorialResult::NullPointer
+            unsafe { factorial_enable_extension(engine, 99) },
+            FactorialResult::InvalidArgument
         );
+        unsafe { factorial_enabled_extensions(engine, &mut mask) };
+        assert_eq!(mask, ExtensionId::Crafting.bit() | ExtensionId::Stats.bit());
         assert_eq!(
-            unsafe { factorial_logic_get_network_signal(engine, 0, 0, ptr::null_mut()) },
+            unsafe { factorial_enabled_extensions(engine, ptr::null_mut()) },
             FactorialResult::NullPointer
         );
-
         unsafe { factorial_destroy(engine) };
     }
 
-    // -----------------------------------------------------------------------
-    // Test 40: Logic -- remove network and remove node
-    // -----------------------------------------------------------------------
     #[test]
-    fn logic_remove_network_and_node() {
-        let engine = factorial_create();
-        unsafe { factorial_logic_register(engine) };
-
-        let node_ffi = ffi_add_node_and_apply(engine, 0);
-
-        // Create network and add node.
-        let mut net_id: FfiWireNetworkId = 0;
-        unsafe { factorial_logic_create_network(engine, FfiWireColor::Red, &mut net_id) };
-        unsafe { factorial_logic_add_to_network(engine, net_id, node_ffi) };
-
-        // Set constant.
-        let item_ids: [u32; 1] = [0];
-        let values: [i64; 1] = [Fixed64::from_num(10).to_bits()];
-        unsafe {
-            factorial_logic_set_constant(engine, node_ffi, item_ids.as_ptr(), values.as_ptr(), 1, 1)
-        };
-
-        // Step to populate signals.
-        unsafe { factorial_step(engine) };
-
-        // Verify signal is present.
-        let mut val: i64 = 0;
-        unsafe { factorial_logic_get_network_signal(engine, net_id, 0, &mut val) };
-        assert_eq!(val, Fixed64::from_num(10).to_bits());
-
-        // Remove node from logic.
-        let result = unsafe { factorial_logic_remove_node(engine, node_ffi) };
-        assert_eq!(result, FactorialResult::Ok);
-
-        // Step again -- signal should be gone since node was removed.
-        unsafe { factorial_step(engine) };
-        unsafe { factorial_logic_get_network_signal(engine, net_id, 0, &mut val) };
-        assert_eq!(val, Fixed64::from_num(0).to_bits());
+    fn format_fixed_shares_the_core_rounding() {
+        let tie = Fixed64::from_num(0.125).to_bits();
+        let mut buf = [0xffu8; 16];
+        let mut written = 0;
+        for (mode, expected) in [(0, "0.13"), (1, "0.12"), (2, "0.12"), (3, "0.13")] {
+            let rc =
+                unsafe { factorial_format_fixed(tie, 2, mode, buf.as_mut_ptr(), 16, &mut written) };
+            assert_eq!(rc, FactorialResult::Ok);
+            assert_eq!(&buf[..written as usize], expected.as_bytes());
+            assert_eq!(buf[written as usize], 0);
+        }
 
-        // Remove the network itself.
-        let result = unsafe { factorial_logic_remove_network(engine, net_id) };
-        assert_eq!(result, FactorialResult::Ok);
+        let min = Fixed64::MIN.to_bits();
+        let rc = unsafe { factorial_format_fixed(min, 2, 0, buf.as_mut_ptr(), 4, &mut written) };
+        assert_eq!(rc, FactorialResult::BufferTooSmall);
+        assert_eq!(written, "-2147483648.00".len() as u32);
+        let rc = unsafe { factorial_format_fixed(min, 2, 0, buf.as_mut_ptr(), 16, &mut written) };
+        assert_eq!(rc, FactorialResult::Ok);
+        assert_eq!(&buf[..written as usize], b"-2147483648.00");
 
-        unsafe { factorial_destroy(engine) };
+        let rc = unsafe { factorial_format_fixed(tie, 2, 4, buf.as_mut_ptr(), 16, &mut written) };
+        assert_eq!(rc, FactorialResult::InvalidArgument);
     }
 
-    // -----------------------------------------------------------------------
-    // Test 41: Logic -- module not registered returns InternalError
-    // -----------------------------------------------------------------------
     #[test]
-    fn logic_module_not_registered() {
-        let engine = factorial_create();
+    fn state_hash_breakdown_sums_to_state_hash() {
+        let mut builder = FactoryBuilder::new();
+        builder.node("mine").source(iron(), 2.0);
+        builder.node("sink").passthrough();
+        builder.flow("mine", "sink", 5.0);
+        let (engine, _) = ffi_engine_from_builder(&builder);
+        for _ in 0..10 {
+            unsafe { factorial_step(engine) };
+        }
 
-        // Do NOT register logic module. All logic calls should return InternalError.
-        let mut net_id: FfiWireNetworkId = 0;
+        let mut hash = 0;
+        let mut breakdown = FfiHashBreakdown::default();
         assert_eq!(
-            unsafe { factorial_logic_create_network(engine, FfiWireColor::Red, &mut net_id) },
-            FactorialResult::InternalError
+            unsafe { factorial_get_state_hash(engine, &mut hash) },
+            FactorialResult::Ok
+        );
+        assert_eq!(
+            unsafe { factorial_get_state_hash_breakdown(engine, &mut breakdown) },
+            FactorialResult::Ok
         );
+        let sum = [
+            breakdown.graph,
+            breakdown.inventories,
+            breakdown.processor_states,
+            breakdown.transports,
+            breakdown.sim_state,
+            breakdown.fluid,
+            breakdown.crafting,
+            breakdown.modules,
+        ]
+        .into_iter()
+        .fold(0u64, u64::wrapping_add);
+        assert_eq!(sum, hash);
+        assert_ne!(breakdown.transports, 0);
 
         unsafe { factorial_destroy(engine) };
     }
 
-    // -----------------------------------------------------------------------
-    // Test 42: Logic -- set arithmetic combinator
-    // -----------------------------------------------------------------------
     #[test]
-    fn logic_arithmetic_combinator() {
-        let engine = factorial_create();
-        unsafe { factorial_logic_register(engine) };
-
-        let const_node = ffi_add_node_and_apply(engine, 0);
-        let arith_node = ffi_add_node_and_apply(engine, 1);
-
-        // Create network.
-        let mut net_id: FfiWireNetworkId = 0;
-        unsafe { factorial_logic_create_network(engine, FfiWireColor::Red, &mut net_id) };
-        unsafe { factorial_logic_add_to_network(engine, net_id, const_node) };
-        unsafe { factorial_logic_add_to_network(engine, net_id, arith_node) };
-
-        // Set constant: iron(0) = 10.
-        let item_ids: [u32; 1] = [0];
-        let values: [i64; 1] = [Fixed64::from_num(10).to_bits()];
-        unsafe {
-            factorial_logic_set_constant(
-                engine,
-                const_node,
-                item_ids.as_ptr(),
-                values.as_ptr(),
-                1,
-                1,
-            )
-        };
-
-        // Set arithmetic: iron(0) * 3 -> steel(2).
-        let result = unsafe {
-            factorial_logic_set_arithmetic(
-                engine,
-                arith_node,
-                FfiSelectorKind::Signal,
-                0, // iron
-                FfiArithmeticOp::Mul,
-                FfiSelectorKind::Constant,
-                Fixed64::from_num(3).to_bits() as u64,
-                2, // steel output
-            )
-        };
-        assert_eq!(result, FactorialResult::Ok);
-
-        // Tick 1: combinator computes, output delayed.
-        unsafe { factorial_step(engine) };
-
-        // Tick 2: combinator output appears on network.
-        unsafe { factorial_step(engine) };
+    fn item_census_matches_core() {
+        let mut builder = FactoryBuilder::new();
+        builder.node("mine").source(iron(), 2.0);
+        builder.node("sink").passthrough();
+        builder.flow("mine", "sink", 1.0);
+        let (engine, _) = ffi_engine_from_builder(&builder);
+        for _ in 0..10 {
+            unsafe { factorial_step(engine) };
+        }
 
-        let mut val: i64 = 0;
-        unsafe { factorial_logic_get_network_signal(engine, net_id, 2, &mut val) };
+        let mut census = FfiItemCensus::default();
         assert_eq!(
-            val,
-            Fixed64::from_num(30).to_bits(),
-            "arithmetic combinator: 10 * 3 = 30 for steel"
+            unsafe { factorial_item_census(engine, iron().0, &mut census) },
+            FactorialResult::Ok
+        );
+        let core = unsafe { &*engine }.inner.item_census(iron());
+        assert_eq!(census.total, core.total);
+        assert_eq!(census.in_transit, core.in_transit);
+        assert!(census.in_output_inventories > 0);
+        assert_eq!(
+            census.total,
+            census.in_input_inventories + census.in_output_inventories + census.in_transit
+        );
+        assert_eq!(
+            unsafe { factorial_item_census(engine, iron().0, std::ptr::null_mut()) },
+            FactorialResult::NullPointer
         );
 
         unsafe { factorial_destroy(engine) };
     }
 
-    // -----------------------------------------------------------------------
-    // Test 43: Logic -- set inventory reader
-    // -----------------------------------------------------------------------
     #[test]
-    fn logic_set_inventory_reader() {
-        let engine = factorial_create();
-        unsafe { factorial_logic_register(engine) };
-
-        let node_ffi = ffi_add_node_and_apply(engine, 0);
+    fn inspect_reads_the_save_summary() {
+        let mut core = Engine::new(SimulationStrategy::Tick);
+        let node = factorial_core::test_utils::add_node(&mut core, Processor::Passthrough, 50, 50);
+        let _ = core
+            .get_input_inventory_mut(node)
+            .unwrap()
+            .insert(iron(), 7);
+        core.step();
+        let engine = Box::into_raw(Box::new(FactorialEngine::new(core)));
+        assert_eq!(
+            unsafe { factorial_set_summary_item_totals(engine, true) },
+            FactorialResult::Ok
+        );
 
-        let result = unsafe { factorial_logic_set_inventory_reader(engine, node_ffi, node_ffi, 0) };
-        assert_eq!(result, FactorialResult::Ok);
+        let mut buffer = FfiByteBuffer {
+            data: ptr::null_mut(),
+            len: 0,
+        };
+        unsafe { factorial_serialize(engine, &mut buffer) };
+        let mut summary = FfiSaveSummary::default();
+        assert_eq!(
+            unsafe { factorial_inspect(buffer.data, buffer.len, &mut summary) },
+            FactorialResult::Ok
+        );
+        let mut hash = 0;
+        unsafe { factorial_get_state_hash(engine, &mut hash) };
+        assert_eq!(
+            summary,
+            FfiSaveSummary {
+                version: factorial_core::serialize::FORMAT_VERSION,
+                tick: 1,
+                node_count: 1,
+                edge_count: 0,
+                extensions: 0,
+                state_hash: hash,
+                has_item_totals: true,
+                item_type_count: 1,
+                total_items: 7,
+                full_decode: false,
+            }
+        );
 
-        unsafe { factorial_destroy(engine) };
+        let garbage = [0u8; 10];
+        assert_eq!(
+            unsafe { factorial_inspect(garbage.as_ptr(), garbage.len(), &mut summary) },
+            FactorialResult::DeserializeError
+        );
+        assert_eq!(
+            unsafe { factorial_inspect(ptr::null(), 0, &mut summary) },
+            FactorialResult::NullPointer
+        );
+        unsafe {
+            factorial_free_buffer(buffer);
+            factorial_destroy(engine);
+        }
     }
 
-    // -----------------------------------------------------------------------
-    // Test 44: Logic -- remove from network
-    // -----------------------------------------------------------------------
     #[test]
-    fn logic_remove_from_network() {
-        let engine = factorial_create();
-        unsafe { factorial_logic_register(engine) };
-
-        let node_ffi = ffi_add_node_and_apply(engine, 0);
-
-        let mut net_id: FfiWireNetworkId = 0;
-        unsafe { factorial_logic_create_network(engine, FfiWireColor::Red, &mut net_id) };
-        unsafe { factorial_logic_add_to_network(engine, net_id, node_ffi) };
+    fn bundles_restore_the_registered_tech_tree() {
+        use factorial_tech_tree::{ResearchCost, TechId, Technology};
+
+        let (engine, _, _) = two_node_ffi_engine();
+        let mut tree = TechTree::new();
+        tree.register(Technology {
+            id: TechId(0),
+            name: "mining".to_string(),
+            prerequisites: vec![],
+            cost: ResearchCost::Points(20),
+            unlocks: vec![],
+            repeatable: false,
+            cost_scaling: None,
+        })
+        .unwrap();
+        tree.start_research(TechId(0), 0).unwrap();
+        tree.contribute_points(TechId(0), 5, 0).unwrap();
+        unsafe { &mut *engine }
+            .inner
+            .register_module(Box::new(tree));
+        for _ in 0..10 {
+            unsafe { factorial_step(engine) };
+        }
 
-        // Set constant and step.
-        let item_ids: [u32; 1] = [0];
-        let values: [i64; 1] = [Fixed64::from_num(77).to_bits()];
-        unsafe {
-            factorial_logic_set_constant(engine, node_ffi, item_ids.as_ptr(), values.as_ptr(), 1, 1)
+        let mut without = FfiByteBuffer {
+            data: ptr::null_mut(),
+            len: 0,
         };
-        unsafe { factorial_step(engine) };
-
-        // Signal should be present.
-        let mut val: i64 = 0;
-        unsafe { factorial_logic_get_network_signal(engine, net_id, 0, &mut val) };
-        assert_eq!(val, Fixed64::from_num(77).to_bits());
+        let mut with = FfiByteBuffer {
+            data: ptr::null_mut(),
+            len: 0,
+        };
+        assert_eq!(
+            unsafe { factorial_bundle_capture(engine, 0, &mut without) },
+            FactorialResult::Ok
+        );
+        assert_eq!(
+            unsafe { factorial_bundle_capture(engine, BUNDLE_TECH_TREE | BUNDLE_FLUID, &mut with) },
+            FactorialResult::Ok
+        );
 
-        // Remove node from network.
-        let result = unsafe { factorial_logic_remove_from_network(engine, net_id, node_ffi) };
+        let mut restored = ptr::null_mut();
+        let mut components = u32::MAX;
+        let result = unsafe {
+            factorial_bundle_restore(without.data, without.len, &mut restored, &mut components)
+        };
         assert_eq!(result, FactorialResult::Ok);
+        assert_eq!(components, 0);
+        assert_eq!(unsafe { &*restored }.inner.module_count(), 0);
+        unsafe { factorial_destroy(restored) };
 
-        // Step again -- signal should be gone.
-        unsafe { factorial_step(engine) };
-        unsafe { factorial_logic_get_network_signal(engine, net_id, 0, &mut val) };
-        assert_eq!(val, Fixed64::from_num(0).to_bits());
+        let result = unsafe {
+            factorial_bundle_restore(with.data, with.len, &mut restored, &mut components)
+        };
+        assert_eq!(result, FactorialResult::Ok);
+        assert_eq!(components, BUNDLE_TECH_TREE);
+        let tree = unsafe { &*restored }
+            .inner
+            .find_module::<TechTree>()
+            .unwrap();
+        assert_eq!(
+            tree.progress_fraction(TechId(0)),
+            Some(Fixed64::from_num(0.25))
+        );
+        for _ in 0..10 {
+            unsafe { factorial_step(engine) };
+            unsafe { factorial_step(restored) };
+            let (mut a, mut b) = (0u64, 0u64);
+            unsafe { factorial_get_state_hash(engine, &mut a) };
+            unsafe { factorial_get_state_hash(restored, &mut b) };
+            assert_eq!(a, b);
+        }
 
-        unsafe { factorial_destroy(engine) };
+        let result =
+            unsafe { factorial_bundle_restore(with.data, 3, &mut restored, &mut components) };
+        assert_eq!(result, FactorialResult::DeserializeError);
+        assert!(restored.is_null());
+        unsafe {
+            factorial_free_buffer(without);
+            factorial_free_buffer(with);
+            factorial_destroy(engine);
+        }
     }
 }
@@ -0,0 +1,102 @@
+//! C ABI contract tests.
+//!
+//! The unit tests in `src/lib.rs` call the `extern "C"` functions from Rust,
+//! which never checks what a C compiler makes of `factorial.h`: enum sizes,
+//! struct layouts, by-value struct arguments. This test compiles
+//! `tests/c_abi/lifecycle.c` with the system C compiler against the
+//! generated header, links it to the `factorial_ffi` shared library, runs
+//! it and checks its exit code.
+//!
+//! It is skipped, with a note on stderr, when no GCC- or Clang-style C
+//! compiler is available or the shared library is not next to the test
+//! binary.
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use factorial_ffi::{FactorialResult, FfiByteBuffer, FfiEvent, FfiEventBuffer, FfiMutationResult};
+
+const SHARED_LIBRARY: &str = if cfg!(target_os = "macos") {
+    "libfactorial_ffi.dylib"
+} else {
+    "libfactorial_ffi.so"
+};
+
+/// The directory cargo places the shared library in: the parent of the
+/// `deps` directory holding this test binary.
+fn library_dir() -> Option<PathBuf> {
+    let exe = std::env::current_exe().ok()?;
+    let dir = exe.parent()?.parent()?.to_path_buf();
+    dir.join(SHARED_LIBRARY).exists().then_some(dir)
+}
+
+/// A command running the target's C compiler, if it is GCC or Clang.
+fn c_compiler() -> Option<Command> {
+    let target = env!("FACTORIAL_FFI_TARGET");
+    let tool = cc::Build::new()
+        .cargo_metadata(false)
+        .target(target)
+        .host(target)
+        .opt_level(0)
+        .try_get_compiler()
+        .ok()?;
+    (tool.is_like_gnu() || tool.is_like_clang()).then(|| tool.to_command())
+}
+
+fn skip(reason: &str) {
+    eprintln!("skipping C ABI test: {reason}");
+}
+
+#[test]
+fn c_program_runs_the_core_lifecycle() {
+    if cfg!(windows) {
+        return skip("only GCC- and Clang-style toolchains are supported");
+    }
+    let Some(lib_dir) = library_dir() else {
+        return skip("the factorial_ffi shared library was not found");
+    };
+    let Some(mut compile) = c_compiler() else {
+        return skip("no C compiler found");
+    };
+
+    let crate_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let exe = Path::new(env!("CARGO_TARGET_TMPDIR")).join("c_abi_lifecycle");
+    compile
+        .args(["-std=c11", "-Wall", "-Wextra", "-Werror"])
+        .arg("-I")
+        .arg(crate_dir)
+        .arg(crate_dir.join("tests/c_abi/lifecycle.c"))
+        .arg("-o")
+        .arg(&exe)
+        .arg("-L")
+        .arg(&lib_dir)
+        .arg("-lfactorial_ffi")
+        .arg(format!("-Wl,-rpath,{}", lib_dir.display()));
+    let output = match compile.output() {
+        Ok(output) => output,
+        Err(err) => return skip(&format!("could not run the C compiler: {err}")),
+    };
+    assert!(
+        output.status.success(),
+        "lifecycle.c failed to compile:\n{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let sizes = [
+        size_of::<FactorialResult>(),
+        size_of::<FfiEvent>(),
+        size_of::<FfiEventBuffer>(),
+        size_of::<FfiMutationResult>(),
+        size_of::<FfiByteBuffer>(),
+    ];
+    let output = Command::new(&exe)
+        .args(sizes.map(|size| size.to_string()))
+        .output()
+        .expect("failed to run the compiled C program");
+    assert!(
+        output.status.success(),
+        "lifecycle.c exited with {}:\n{}",
+        output.status,
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
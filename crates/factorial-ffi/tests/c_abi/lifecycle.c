@@ -0,0 +1,119 @@
+/*
+ * C ABI contract test for factorial.h.
+ *
+ * Built against the generated header and linked to the factorial_ffi shared
+ * library by tests/c_abi.rs, then run as a separate process. Exits 0 when
+ * every check passes; otherwise prints the failing check and exits 1.
+ *
+ * Arguments: the sizes Rust sees for FactorialResult, FfiEvent,
+ * FfiEventBuffer, FfiMutationResult and FfiByteBuffer, in that order, so
+ * layout drift between the header and the library is caught here.
+ */
+
+#include <stdio.h>
+#include <stdlib.h>
+
+#include "factorial.h"
+
+#define CHECK(cond)                                                         \
+    do {                                                                    \
+        if (!(cond)) {                                                      \
+            fprintf(stderr, "%s:%d: check failed: %s\n", __FILE__, __LINE__, \
+                    #cond);                                                 \
+            exit(1);                                                        \
+        }                                                                   \
+    } while (0)
+
+#define CHECK_OK(call) CHECK((call) == OK)
+
+#define ORE 7u
+#define RATE_TWO ((int64_t)2 << 32)
+
+static void check_size(const char *name, size_t c_size, const char *rust_size) {
+    size_t expected = (size_t)strtoul(rust_size, NULL, 10);
+    if (c_size != expected) {
+        fprintf(stderr, "sizeof(%s) is %zu in C but %zu in Rust\n", name, c_size,
+                expected);
+        exit(1);
+    }
+}
+
+int main(int argc, char **argv) {
+    CHECK(argc == 6);
+    check_size("FactorialResult", sizeof(enum FactorialResult), argv[1]);
+    check_size("FfiEvent", sizeof(struct FfiEvent), argv[2]);
+    check_size("FfiEventBuffer", sizeof(struct FfiEventBuffer), argv[3]);
+    check_size("FfiMutationResult", sizeof(struct FfiMutationResult), argv[4]);
+    check_size("FfiByteBuffer", sizeof(struct FfiByteBuffer), argv[5]);
+
+    /* Error codes come back as the values the header declares. */
+    CHECK(factorial_destroy(NULL) == NULL_POINTER);
+
+    FactorialEngine *engine = factorial_create();
+    CHECK(engine != NULL);
+
+    /* Add a node and resolve its id. */
+    FfiPendingNodeId pending = 0;
+    CHECK_OK(factorial_add_node(engine, 3, &pending));
+    struct FfiMutationResult mutations = {0};
+    CHECK_OK(factorial_apply_mutations(engine, &mutations));
+    CHECK(mutations.added_node_count == 1);
+    CHECK(mutations.added_nodes[0].pending_id == pending);
+    FfiNodeId node = mutations.added_nodes[0].real_id;
+    FfiNodeId resolved = 0;
+    CHECK_OK(factorial_resolve_pending_node(engine, pending, &resolved));
+    CHECK(resolved == node);
+
+    /* A miner producing two ore per tick into a roomy output. */
+    CHECK_OK(factorial_set_source(engine, node, ORE, RATE_TWO));
+    CHECK_OK(factorial_set_output_capacity(engine, node, 100));
+
+    CHECK_OK(factorial_step(engine));
+    struct FfiEventBuffer events = {0};
+    CHECK_OK(factorial_poll_events(engine, &events));
+    uint32_t added = 0, produced = 0;
+    for (uint32_t i = 0; i < events.count; i++) {
+        const struct FfiEvent *event = &events.events[i];
+        CHECK(event->tick == 0);
+        if (event->kind == NODE_ADDED) {
+            CHECK(event->node == node);
+            CHECK(event->building_type == 3);
+            added++;
+        } else if (event->kind == ITEM_PRODUCED) {
+            CHECK(event->node == node);
+            CHECK(event->item_type == ORE);
+            produced += event->quantity;
+        }
+    }
+    CHECK(added == 1);
+    CHECK(produced == 2);
+
+    CHECK_OK(factorial_step(engine));
+    CHECK_OK(factorial_step(engine));
+    uint64_t tick = 0;
+    CHECK_OK(factorial_get_tick(engine, &tick));
+    CHECK(tick == 3);
+    uint32_t stored = 0;
+    CHECK_OK(factorial_get_output_inventory_count(engine, node, &stored));
+    CHECK(stored == 6);
+
+    /* Save, load, and free the buffer by value. */
+    struct FfiByteBuffer saved = {0};
+    CHECK_OK(factorial_serialize(engine, &saved));
+    CHECK(saved.data != NULL && saved.len > 0);
+    FactorialEngine *loaded = NULL;
+    CHECK_OK(factorial_deserialize(saved.data, saved.len, &loaded));
+    CHECK(loaded != NULL);
+    CHECK_OK(factorial_free_buffer(saved));
+
+    uint64_t hash = 0, loaded_hash = 0;
+    CHECK_OK(factorial_get_state_hash(engine, &hash));
+    CHECK_OK(factorial_get_state_hash(loaded, &loaded_hash));
+    CHECK(hash == loaded_hash);
+    CHECK_OK(factorial_get_tick(loaded, &tick));
+    CHECK(tick == 3);
+
+    CHECK_OK(factorial_destroy(loaded));
+    CHECK_OK(factorial_destroy(engine));
+    return 0;
+}
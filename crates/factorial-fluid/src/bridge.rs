@@ -1,11 +1,16 @@
 //! Fluid-to-item bridge for converting fluid consumption into inventory items.
+//!
+//! A bridge can also feed recipes that take fluids directly: see
+//! [`FluidBridge::supply_recipe`] and [`FluidBridge::drain_recipe_output`].
 
 use factorial_core::engine::Engine;
 use factorial_core::fixed::Fixed64;
+use factorial_core::hook::EngineView;
 use factorial_core::id::{ItemTypeId, NodeId};
+use factorial_core::item::Inventory;
 use serde::{Deserialize, Serialize};
 
-use crate::FluidNetworkId;
+use crate::{FluidModule, FluidNetworkId};
 
 /// Bridges a fluid network consumer to an engine node's input inventory.
 ///
@@ -39,20 +44,70 @@ impl FluidBridge {
     /// `consumed` is the amount of fluid consumed this tick (from
     /// `FluidModule::get_consumed_this_tick`).
     pub fn apply(&mut self, engine: &mut Engine, consumed: Fixed64) {
+        let whole_items = self.accumulate(consumed);
+        if whole_items > 0 {
+            self.deposit(engine.get_input_inventory_mut(self.node), whole_items);
+        }
+    }
+
+    /// [`apply`](Self::apply) from inside a tick hook, so the bridge runs at
+    /// a fixed point of every step instead of between host calls.
+    pub fn apply_view(&mut self, view: &mut EngineView<'_>, consumed: Fixed64) {
+        let whole_items = self.accumulate(consumed);
+        if whole_items > 0 {
+            self.deposit(view.input_inventory_mut(self.node), whole_items);
+        }
+    }
+
+    /// Add `consumed` to the accumulator and take out the whole items.
+    fn accumulate(&mut self, consumed: Fixed64) -> u32 {
         self.accumulated += consumed;
         let whole_items = self.accumulated.to_num::<i64>().max(0) as u32;
-        if whole_items > 0 {
-            self.accumulated -= Fixed64::from_num(whole_items);
-            if let Some(inv) = engine.get_input_inventory_mut(self.node) {
-                for slot in &mut inv.input_slots {
-                    let overflow = slot.add(self.item_type, whole_items);
-                    if overflow == 0 {
-                        break;
-                    }
+        self.accumulated -= Fixed64::from_num(whole_items);
+        whole_items
+    }
+
+    /// Add `whole_items` to the inventory's input slots in order. Items
+    /// that fit nowhere are dropped.
+    fn deposit(&self, inventory: Option<&mut Inventory>, whole_items: u32) {
+        if let Some(inv) = inventory {
+            let mut remaining = whole_items;
+            for slot in &mut inv.input_slots {
+                remaining = slot.add(self.item_type, remaining);
+                if remaining == 0 {
+                    break;
                 }
             }
         }
     }
+
+    /// Deliver consumed fluid to the node's recipe fluid buffer instead of
+    /// its item inventory.
+    ///
+    /// The buffer holds at most one cycle's worth of the recipe's fluid
+    /// input; fluid beyond that is dropped. A recipe starves (stalls with
+    /// `MissingInputs`) until the buffer reaches the full amount. Returns
+    /// the amount accepted.
+    pub fn supply_recipe(&self, engine: &mut Engine, consumed: Fixed64) -> Fixed64 {
+        engine.add_fluid_input(self.node, self.item_type, consumed)
+    }
+
+    /// Move fluid produced by the node's recipe into this bridge's network
+    /// storage.
+    ///
+    /// Whatever the network's tanks cannot take stays in the node, which
+    /// then stalls with `OutputFull` before its next cycle. Returns the
+    /// amount moved.
+    pub fn drain_recipe_output(&self, engine: &mut Engine, fluid: &mut FluidModule) -> Fixed64 {
+        let held = engine
+            .fluid_ports(self.node)
+            .map_or(Fixed64::ZERO, |p| p.output_of(self.item_type));
+        if held <= Fixed64::ZERO {
+            return Fixed64::ZERO;
+        }
+        let accepted = fluid.inject(self.network, held);
+        engine.take_fluid_output(self.node, self.item_type, accepted)
+    }
 }
 
 #[cfg(test)]
@@ -114,4 +169,270 @@ mod tests {
         // Should have deposited ~100 items (10/tick * 10 ticks).
         assert_eq!(water_in_inventory, 100);
     }
+
+    #[test]
+    fn fluid_bridge_runs_as_a_tick_hook() {
+        use factorial_core::hook::TickPhase;
+        use std::cell::RefCell;
+        use std::rc::Rc;
+
+        let water = ItemTypeId(3);
+        let mut engine = Engine::new(SimulationStrategy::Tick);
+        let bridge_node = test_utils::add_node(&mut engine, Processor::Passthrough, 100, 100);
+        let well_node = test_utils::add_node(&mut engine, Processor::Passthrough, 0, 0);
+
+        let mut fluid = FluidModule::new();
+        let net = fluid.create_network(water);
+        fluid.add_producer(
+            net,
+            well_node,
+            FluidProducer {
+                rate: Fixed64::from_num(10),
+            },
+        );
+        fluid.add_consumer(
+            net,
+            bridge_node,
+            FluidConsumer {
+                rate: Fixed64::from_num(10),
+            },
+        );
+        let fluid = Rc::new(RefCell::new(fluid));
+        let mut bridge = FluidBridge::new(net, bridge_node, water);
+        let hook_fluid = Rc::clone(&fluid);
+        engine.register_tick_hook(
+            TickPhase::Pre,
+            0,
+            Box::new(move |view| {
+                let mut fluid = hook_fluid.borrow_mut();
+                fluid.tick(view.tick());
+                let consumed = fluid.get_consumed_this_tick(net, bridge_node);
+                bridge.apply_view(view, consumed);
+            }),
+        );
+
+        for _ in 0..10 {
+            engine.step();
+        }
+        // The passthrough moves bridged water on to its output each step.
+        let bridged = test_utils::input_quantity(&engine, bridge_node, water)
+            + test_utils::output_quantity(&engine, bridge_node, water);
+        assert_eq!(bridged, 100);
+    }
+
+    // -----------------------------------------------------------------------
+    // Recipe fluids: a washing plant turns 1 ore + 5 water into 1 washed ore
+    // + 3 sludge over 2 ticks.
+    // -----------------------------------------------------------------------
+
+    use factorial_core::processor::{FixedRecipe, FluidAmount, ProcessorState, StallReason};
+
+    fn ore() -> ItemTypeId {
+        ItemTypeId(0)
+    }
+    fn washed() -> ItemTypeId {
+        ItemTypeId(1)
+    }
+    fn water() -> ItemTypeId {
+        ItemTypeId(3)
+    }
+    fn sludge() -> ItemTypeId {
+        ItemTypeId(4)
+    }
+
+    struct WashingPlant {
+        engine: Engine,
+        fluid: FluidModule,
+        plant: NodeId,
+        water_in: FluidBridge,
+        sludge_out: FluidBridge,
+        tank: NodeId,
+        water_used: Fixed64,
+    }
+
+    impl WashingPlant {
+        fn new(tank_capacity: f64) -> Self {
+            let mut engine = Engine::new(SimulationStrategy::Tick);
+            let Processor::Fixed(recipe) =
+                test_utils::make_recipe(vec![(ore(), 1)], vec![(washed(), 1)], 2)
+            else {
+                unreachable!()
+            };
+            let washer = Processor::Fixed(FixedRecipe {
+                fluid_inputs: vec![FluidAmount {
+                    fluid: water(),
+                    amount: Fixed64::from_num(5),
+                }],
+                fluid_outputs: vec![FluidAmount {
+                    fluid: sludge(),
+                    amount: Fixed64::from_num(3),
+                }],
+                ..recipe
+            });
+            let plant = test_utils::add_node(&mut engine, washer, 1000, 1000);
+            let overflow = engine
+                .get_input_inventory_mut(plant)
+                .unwrap()
+                .insert(ore(), 1000);
+            assert_eq!(overflow, 0);
+            let pump = test_utils::add_node(&mut engine, Processor::Passthrough, 0, 0);
+            let tank = test_utils::add_node(&mut engine, Processor::Passthrough, 0, 0);
+
+            let mut fluid = FluidModule::new();
+            let water_net = fluid.create_network(water());
+            fluid.add_producer(
+                water_net,
+                pump,
+                FluidProducer {
+                    rate: Fixed64::from_num(5),
+                },
+            );
+            fluid.add_consumer(
+                water_net,
+                plant,
+                FluidConsumer {
+                    rate: Fixed64::from_num(5),
+                },
+            );
+            let sludge_net = fluid.create_network(sludge());
+            fluid.add_storage(
+                sludge_net,
+                tank,
+                crate::FluidStorage {
+                    capacity: Fixed64::from_num(tank_capacity),
+                    current: Fixed64::ZERO,
+                    fill_rate: Fixed64::from_num(100),
+                },
+            );
+
+            Self {
+                engine,
+                fluid,
+                plant,
+                water_in: FluidBridge::new(water_net, plant, water()),
+                sludge_out: FluidBridge::new(sludge_net, plant, sludge()),
+                tank,
+                water_used: Fixed64::ZERO,
+            }
+        }
+
+        fn step(&mut self) {
+            let tick = self.engine.sim_state.tick;
+            self.fluid.tick(tick);
+            let consumed = self
+                .fluid
+                .get_consumed_this_tick(self.water_in.network, self.plant);
+            let before = self.buffered_water();
+            let supplied = self.water_in.supply_recipe(&mut self.engine, consumed);
+            self.engine.step();
+            self.water_used += before + supplied - self.buffered_water();
+            self.sludge_out
+                .drain_recipe_output(&mut self.engine, &mut self.fluid);
+        }
+
+        fn buffered_water(&self) -> Fixed64 {
+            self.engine
+                .fluid_ports(self.plant)
+                .map_or(Fixed64::ZERO, |p| p.input_of(water()))
+        }
+
+        fn crafts_started(&self) -> u32 {
+            let working = matches!(
+                self.engine.get_processor_state(self.plant),
+                Some(ProcessorState::Working { .. })
+            );
+            test_utils::output_quantity(&self.engine, self.plant, washed()) + u32::from(working)
+        }
+
+        fn tank_level(&self) -> Fixed64 {
+            self.fluid.storage[&self.tank].current
+        }
+    }
+
+    #[test]
+    fn washing_plant_uses_water_per_craft_and_fills_sludge_tank() {
+        let mut rig = WashingPlant::new(1000.0);
+        for _ in 0..40 {
+            rig.step();
+        }
+
+        let crafts = rig.crafts_started();
+        assert!(crafts >= 10, "plant should run steadily, got {crafts}");
+        assert_eq!(rig.water_used, Fixed64::from_num(5 * crafts));
+        let finished = test_utils::output_quantity(&rig.engine, rig.plant, washed());
+        assert_eq!(rig.tank_level(), Fixed64::from_num(3 * finished));
+    }
+
+    #[test]
+    fn washing_plant_stalls_during_brownout() {
+        let mut rig = WashingPlant::new(1000.0);
+        for _ in 0..10 {
+            rig.step();
+        }
+        let before = rig.crafts_started();
+
+        // A large consumer joins the water network: pressure drops to 25%.
+        let water_net = rig.water_in.network;
+        let hog = test_utils::add_node(&mut rig.engine, Processor::Passthrough, 0, 0);
+        rig.fluid.add_consumer(
+            water_net,
+            hog,
+            FluidConsumer {
+                rate: Fixed64::from_num(15),
+            },
+        );
+        let mut starved_ticks = 0;
+        for _ in 0..20 {
+            rig.step();
+            if rig.engine.get_processor_state(rig.plant)
+                == Some(&ProcessorState::Stalled {
+                    reason: StallReason::MissingInputs,
+                })
+            {
+                starved_ticks += 1;
+            }
+        }
+        assert!(starved_ticks > 0, "plant should starve for water");
+        let during = rig.crafts_started() - before;
+        assert!(
+            during <= 5,
+            "brownout should slow the plant, got {during} crafts"
+        );
+        assert_eq!(rig.water_used, Fixed64::from_num(5 * rig.crafts_started()));
+
+        // The hog leaves, pressure recovers, and so does the plant.
+        rig.fluid.remove_node(hog);
+        let resumed = rig.crafts_started();
+        for _ in 0..10 {
+            rig.step();
+        }
+        assert!(rig.crafts_started() - resumed >= 4);
+    }
+
+    #[test]
+    fn full_sludge_tank_stalls_plant_with_output_full() {
+        let mut rig = WashingPlant::new(12.0);
+        for _ in 0..40 {
+            rig.step();
+        }
+
+        assert_eq!(rig.tank_level(), Fixed64::from_num(12));
+        assert_eq!(
+            rig.engine.get_processor_state(rig.plant),
+            Some(&ProcessorState::Stalled {
+                reason: StallReason::OutputFull
+            })
+        );
+        // Four batches filled the tank; the fifth waits in the plant.
+        let held = rig
+            .engine
+            .fluid_ports(rig.plant)
+            .unwrap()
+            .output_of(sludge());
+        assert_eq!(held, Fixed64::from_num(3));
+        assert_eq!(
+            test_utils::output_quantity(&rig.engine, rig.plant, washed()),
+            5
+        );
+    }
 }
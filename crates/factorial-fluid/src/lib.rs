@@ -13,6 +13,14 @@
 //! - Each network tracks its own producers, consumers, storage, and pipe nodes.
 //! - Per-node fluid specs are stored in the module (not in the core ECS).
 //! - Pressure ratio affects building performance (applied externally).
+//! - Producers and consumers can follow a processor's working state
+//!   ([`FluidModule::link_to_processor`]).
+//! - Consumers can deposit what they draw into a node's input inventory
+//!   ([`FluidModule::deliver_to_inventory`]).
+//! - A reverse index answers which networks a node belongs to, and in what
+//!   role, without scanning them ([`FluidModule::networks_for_node`]).
+//! - Pumps force fluid from one network's tanks into another's, regardless
+//!   of pressure, for closed-loop circulation ([`FluidPump`]).
 //! - Events fire only on *transitions*, not every tick.
 
 pub mod bridge;
@@ -20,9 +28,14 @@ pub use bridge::FluidBridge;
 
 use std::collections::BTreeMap;
 
+use factorial_core::engine::Engine;
+use factorial_core::event::Event;
 use factorial_core::fixed::{Fixed64, Ticks};
+use factorial_core::hook::EngineView;
 use factorial_core::id::{ItemTypeId, NodeId};
+use factorial_core::processor::{ProcessorState, speed_multiplier};
 use serde::{Deserialize, Serialize};
+use slotmap::SecondaryMap;
 
 // ---------------------------------------------------------------------------
 // Network identifier
@@ -68,6 +81,33 @@ pub struct FluidStorage {
     pub fill_rate: Fixed64,
 }
 
+/// A pump that forces fluid from one section of a circuit into another.
+///
+/// Sections are fluid networks of the same fluid type: split a loop into one
+/// network per section and join them with pumps. Each tick, before the
+/// networks balance, the pump drains up to `rate` fluid from the tanks of
+/// `from_section` into the tanks of `to_section`, whatever their pressure
+/// or fill levels. It moves less only when the source tanks run dry or the
+/// destination tanks run out of headroom.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FluidPump {
+    /// Fluid moved per tick (Fixed64).
+    pub rate: Fixed64,
+    /// Network the pump draws from.
+    pub from_section: FluidNetworkId,
+    /// Network the pump pushes into.
+    pub to_section: FluidNetworkId,
+}
+
+/// The part a node plays in a fluid network.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum FluidRole {
+    Producer,
+    Consumer,
+    Storage,
+    Pipe,
+}
+
 // ---------------------------------------------------------------------------
 // Fluid network
 // ---------------------------------------------------------------------------
@@ -97,6 +137,16 @@ pub struct FluidNetwork {
     /// Whether this network was in low-pressure state last tick.
     /// Used to detect transitions for event emission.
     pub was_low_pressure: bool,
+    /// Fluid produced in the last tick.
+    #[serde(default)]
+    pub production: Fixed64,
+    /// Fluid demanded in the last tick.
+    #[serde(default)]
+    pub demand: Fixed64,
+    /// Net fluid storage supplied in the last tick: positive when tanks
+    /// drained to cover a deficit, negative when they took in surplus.
+    #[serde(default)]
+    pub storage_flow: Fixed64,
 }
 
 impl FluidNetwork {
@@ -111,6 +161,9 @@ impl FluidNetwork {
             pipes: Vec::new(),
             pressure: Fixed64::from_num(1),
             was_low_pressure: false,
+            production: Fixed64::ZERO,
+            demand: Fixed64::ZERO,
+            storage_flow: Fixed64::ZERO,
         }
     }
 
@@ -162,6 +215,21 @@ impl FluidNetwork {
         self.pipes.retain(|n| *n != node);
     }
 
+    /// Every member with its role: producers, then consumers, storage and
+    /// pipes, each in list order.
+    pub fn members(&self) -> impl Iterator<Item = (NodeId, FluidRole)> + '_ {
+        fn tagged(
+            nodes: &[NodeId],
+            role: FluidRole,
+        ) -> impl Iterator<Item = (NodeId, FluidRole)> + '_ {
+            nodes.iter().map(move |&node| (node, role))
+        }
+        tagged(&self.producers, FluidRole::Producer)
+            .chain(tagged(&self.consumers, FluidRole::Consumer))
+            .chain(tagged(&self.storage, FluidRole::Storage))
+            .chain(tagged(&self.pipes, FluidRole::Pipe))
+    }
+
     /// Remove a node from any role in this network.
     pub fn remove_node(&mut self, node: NodeId) {
         self.remove_producer(node);
@@ -203,6 +271,21 @@ pub enum FluidEvent {
     },
 }
 
+/// Supply and demand of a fluid network in its last tick, for balancing UI.
+/// Returned by [`FluidModule::network_balance`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NetworkBalance {
+    /// Fluid the producers supplied.
+    pub total_production: Fixed64,
+    /// Fluid the consumers asked for.
+    pub total_demand: Fixed64,
+    /// Net fluid the tanks supplied: positive when they drained to cover a
+    /// deficit, negative when they took in surplus.
+    pub total_storage: Fixed64,
+    /// Share of the demand that was met, 0 to 1.
+    pub pressure: Fixed64,
+}
+
 // ---------------------------------------------------------------------------
 // Fluid module
 // ---------------------------------------------------------------------------
@@ -229,6 +312,29 @@ pub struct FluidModule {
     /// Updated each tick during fluid distribution.
     #[serde(default)]
     pub consumer_consumption: BTreeMap<(FluidNetworkId, NodeId), Fixed64>,
+    /// Producers/consumers whose rate follows a processor, keyed by fluid
+    /// node, valued by the node whose processor drives it.
+    #[serde(default)]
+    pub rate_links: BTreeMap<NodeId, NodeId>,
+    /// Last sampled rate scale of each linked node (see
+    /// [`sync_activity`](Self::sync_activity)).
+    #[serde(default)]
+    pub activity: BTreeMap<NodeId, Fixed64>,
+    /// Consumers whose draw is deposited into a node's input inventory,
+    /// keyed by (network, consumer node).
+    #[serde(default)]
+    pub deliveries: BTreeMap<(FluidNetworkId, NodeId), FluidBridge>,
+    /// Reverse index: every (network, role) a node holds, sorted. Kept up
+    /// to date by the module's add and remove methods.
+    #[serde(default)]
+    node_networks: SecondaryMap<NodeId, Vec<(FluidNetworkId, FluidRole)>>,
+    /// Per-node pump specs. Pumps link two networks rather than belong to
+    /// one, so they are not network members.
+    #[serde(default)]
+    pub pumps: BTreeMap<NodeId, FluidPump>,
+    /// Fluid each pump moved this tick.
+    #[serde(default)]
+    pub pump_flow: BTreeMap<NodeId, Fixed64>,
 }
 
 impl Default for FluidModule {
@@ -248,6 +354,12 @@ impl FluidModule {
             pipes: BTreeMap::new(),
             next_network_id: 0,
             consumer_consumption: BTreeMap::new(),
+            rate_links: BTreeMap::new(),
+            activity: BTreeMap::new(),
+            deliveries: BTreeMap::new(),
+            node_networks: SecondaryMap::new(),
+            pumps: BTreeMap::new(),
+            pump_flow: BTreeMap::new(),
         }
     }
 
@@ -271,7 +383,74 @@ impl FluidModule {
 
     /// Remove a fluid network entirely.
     pub fn remove_network(&mut self, id: FluidNetworkId) {
-        self.networks.remove(&id);
+        if let Some(network) = self.networks.remove(&id) {
+            for (node, _) in network.members() {
+                self.unindex(node, |(net, _)| *net == id);
+            }
+        }
+        self.deliveries.retain(|&(net, _), _| net != id);
+    }
+
+    /// The networks `node` belongs to, in ascending id order. A constant-time
+    /// index lookup rather than a scan of every network.
+    pub fn networks_for_node(&self, node: NodeId) -> Vec<FluidNetworkId> {
+        let mut networks: Vec<FluidNetworkId> = self
+            .node_networks
+            .get(node)
+            .map_or(&[][..], Vec::as_slice)
+            .iter()
+            .map(|&(network, _)| network)
+            .collect();
+        networks.dedup();
+        networks
+    }
+
+    /// The role `node` plays in `network`, or `None` if it is not a member.
+    /// A node registered in several roles on one network reports the first
+    /// of producer, consumer, storage and pipe.
+    pub fn role_in_network(&self, node: NodeId, network: FluidNetworkId) -> Option<FluidRole> {
+        self.node_networks
+            .get(node)?
+            .iter()
+            .find(|&&(net, _)| net == network)
+            .map(|&(_, role)| role)
+    }
+
+    /// Rebuild the node-to-network index from the networks' member lists.
+    /// Only needed after editing a [`FluidNetwork`] directly (through
+    /// [`network_mut`](Self::network_mut) or the public fields) or loading
+    /// a module saved before the index existed.
+    pub fn rebuild_node_index(&mut self) {
+        self.node_networks.clear();
+        let members: Vec<(NodeId, FluidNetworkId, FluidRole)> = self
+            .networks
+            .iter()
+            .flat_map(|(&id, network)| network.members().map(move |(node, role)| (node, id, role)))
+            .collect();
+        for (node, network, role) in members {
+            self.index(node, network, role);
+        }
+    }
+
+    /// Record that `node` holds `role` on `network`, keeping the entries sorted.
+    fn index(&mut self, node: NodeId, network: FluidNetworkId, role: FluidRole) {
+        let Some(entries) = self.node_networks.entry(node) else {
+            return;
+        };
+        let entries = entries.or_default();
+        if let Err(at) = entries.binary_search(&(network, role)) {
+            entries.insert(at, (network, role));
+        }
+    }
+
+    /// Drop the index entries of `node` that match `remove`.
+    fn unindex(&mut self, node: NodeId, remove: impl Fn(&(FluidNetworkId, FluidRole)) -> bool) {
+        if let Some(entries) = self.node_networks.get_mut(node) {
+            entries.retain(|entry| !remove(entry));
+            if entries.is_empty() {
+                self.node_networks.remove(node);
+            }
+        }
     }
 
     /// Register a producer node and add it to a network.
@@ -284,6 +463,7 @@ impl FluidModule {
         self.producers.insert(node, producer);
         if let Some(network) = self.networks.get_mut(&network_id) {
             network.add_producer(node);
+            self.index(node, network_id, FluidRole::Producer);
         }
     }
 
@@ -297,6 +477,7 @@ impl FluidModule {
         self.consumers.insert(node, consumer);
         if let Some(network) = self.networks.get_mut(&network_id) {
             network.add_consumer(node);
+            self.index(node, network_id, FluidRole::Consumer);
         }
     }
 
@@ -305,6 +486,7 @@ impl FluidModule {
         self.storage.insert(node, storage);
         if let Some(network) = self.networks.get_mut(&network_id) {
             network.add_storage(node);
+            self.index(node, network_id, FluidRole::Storage);
         }
     }
 
@@ -313,6 +495,136 @@ impl FluidModule {
         self.pipes.insert(node, pipe);
         if let Some(network) = self.networks.get_mut(&network_id) {
             network.add_pipe(node);
+            self.index(node, network_id, FluidRole::Pipe);
+        }
+    }
+
+    /// Register a pump node. Pumps run in `NodeId` order. A pump's rate can
+    /// follow a processor with [`link_to_processor`](Self::link_to_processor),
+    /// like a producer's.
+    pub fn add_pump(&mut self, node: NodeId, pump: FluidPump) {
+        self.pumps.insert(node, pump);
+    }
+
+    /// Scale a producer's or consumer's rate by the working state of the
+    /// processor on `processor_node` (often the same node).
+    ///
+    /// A linked node runs at `rate * speed` while the processor is working
+    /// and at zero while it is idle or stalled. The state is sampled by
+    /// [`sync_activity`](Self::sync_activity); until the first sample the
+    /// node is treated as idle.
+    pub fn link_to_processor(&mut self, node: NodeId, processor_node: NodeId) {
+        self.rate_links.insert(node, processor_node);
+    }
+
+    /// Remove a node's processor linkage; it returns to its constant rate.
+    pub fn unlink_processor(&mut self, node: NodeId) {
+        self.rate_links.remove(&node);
+        self.activity.remove(&node);
+    }
+
+    /// Sample the processor state behind every linked node.
+    ///
+    /// Working processors scale their node by their speed multiplier; idle,
+    /// stalled, or missing processors scale it to zero.
+    pub fn sync_activity(&mut self, engine: &Engine) {
+        for (&node, &processor_node) in &self.rate_links {
+            let scale = match engine.get_processor_state(processor_node) {
+                Some(ProcessorState::Working { .. }) => speed_multiplier(
+                    engine
+                        .get_modifiers(processor_node)
+                        .map_or(&[][..], |m| m.as_slice()),
+                ),
+                _ => Fixed64::ZERO,
+            };
+            self.activity.insert(node, scale);
+        }
+    }
+
+    /// Compute every network's pressure from `engine` without moving fluid,
+    /// so consumers see the right pressure before the first
+    /// [`tick`](Self::tick). Call it alongside
+    /// [`Engine::initialize`](factorial_core::engine::Engine::initialize).
+    /// Storage levels are unchanged and no events are emitted; a network that
+    /// starts under pressure still reports `PressureLow` on its first tick.
+    pub fn initialize(&mut self, engine: &Engine) {
+        self.sync_activity(engine);
+        let mut probe = self.clone();
+        let _ = probe.tick(engine.sim_state.tick);
+        for (id, network) in &mut self.networks {
+            if let Some(primed) = probe.networks.get(id) {
+                network.pressure = primed.pressure;
+            }
+        }
+    }
+
+    /// [`sync_activity`](Self::sync_activity) followed by [`tick`](Self::tick).
+    pub fn tick_with_engine(&mut self, engine: &Engine, current_tick: Ticks) -> Vec<FluidEvent> {
+        self.sync_activity(engine);
+        self.tick(current_tick)
+    }
+
+    /// Deposit what `consumer` draws from `network` into the input inventory
+    /// of `node` (often the same node) as the network's fluid item type.
+    ///
+    /// Deliveries run in [`deliver`](Self::deliver) or
+    /// [`deliver_view`](Self::deliver_view). Fractional amounts carry over
+    /// between ticks; whole items that do not fit in the inventory are
+    /// dropped. Binding a consumer again replaces its target and discards
+    /// its carried fraction. Returns `false` if the network does not exist.
+    pub fn deliver_to_inventory(
+        &mut self,
+        network: FluidNetworkId,
+        consumer: NodeId,
+        node: NodeId,
+    ) -> bool {
+        let Some(fluid_type) = self.networks.get(&network).map(|n| n.fluid_type) else {
+            return false;
+        };
+        self.deliveries.insert(
+            (network, consumer),
+            FluidBridge::new(network, node, fluid_type),
+        );
+        true
+    }
+
+    /// Stop depositing what `consumer` draws from `network`.
+    pub fn stop_delivery(&mut self, network: FluidNetworkId, consumer: NodeId) {
+        self.deliveries.remove(&(network, consumer));
+    }
+
+    /// Deposit this tick's consumption of every bound consumer into its
+    /// target inventory. Call after [`tick`](Self::tick) and before
+    /// [`Engine::step`] so the processors see the fluid in the same step.
+    pub fn deliver(&mut self, engine: &mut Engine) {
+        for (&(network, consumer), bridge) in &mut self.deliveries {
+            let consumed = self
+                .consumer_consumption
+                .get(&(network, consumer))
+                .copied()
+                .unwrap_or(Fixed64::ZERO);
+            bridge.apply(engine, consumed);
+        }
+    }
+
+    /// [`deliver`](Self::deliver) from inside a tick hook.
+    pub fn deliver_view(&mut self, view: &mut EngineView<'_>) {
+        for (&(network, consumer), bridge) in &mut self.deliveries {
+            let consumed = self
+                .consumer_consumption
+                .get(&(network, consumer))
+                .copied()
+                .unwrap_or(Fixed64::ZERO);
+            bridge.apply_view(view, consumed);
+        }
+    }
+
+    /// A node's rate after its processor linkage, if any.
+    fn effective_rate(&self, node: NodeId, rate: Fixed64) -> Fixed64 {
+        if self.rate_links.contains_key(&node) {
+            rate * self.activity.get(&node).copied().unwrap_or(Fixed64::ZERO)
+        } else {
+            rate
         }
     }
 
@@ -322,9 +634,28 @@ impl FluidModule {
         self.consumers.remove(&node);
         self.storage.remove(&node);
         self.pipes.remove(&node);
+        self.pumps.remove(&node);
+        self.pump_flow.remove(&node);
+        self.consumer_consumption.retain(|&(_, n), _| n != node);
+        self.rate_links.remove(&node);
+        self.activity.remove(&node);
+        self.deliveries
+            .retain(|&(_, consumer), bridge| consumer != node && bridge.node != node);
         for network in self.networks.values_mut() {
             network.remove_node(node);
         }
+        self.node_networks.remove(node);
+    }
+
+    /// React to an engine event. A `NodeRemoved` event removes the node
+    /// from the fluid system; all other events are ignored.
+    ///
+    /// Feed this the engine's events (e.g. from a passive listener) so
+    /// nodes removed from the graph never linger in fluid networks.
+    pub fn process_event(&mut self, event: &Event) {
+        if let Event::NodeRemoved { node, .. } = event {
+            self.remove_node(*node);
+        }
     }
 
     /// Get the pressure ratio for a network.
@@ -332,6 +663,18 @@ impl FluidModule {
         self.networks.get(&network_id).map(|n| n.pressure)
     }
 
+    /// Supply, demand, storage contribution and pressure of a network in
+    /// the last [`tick`](Self::tick). All zero, with full pressure, before
+    /// the first tick.
+    pub fn network_balance(&self, network_id: FluidNetworkId) -> Option<NetworkBalance> {
+        self.networks.get(&network_id).map(|n| NetworkBalance {
+            total_production: n.production,
+            total_demand: n.demand,
+            total_storage: n.storage_flow,
+            pressure: n.pressure,
+        })
+    }
+
     /// Get how much fluid a consumer received this tick.
     pub fn get_consumed_this_tick(&self, network: FluidNetworkId, node: NodeId) -> Fixed64 {
         self.consumer_consumption
@@ -340,11 +683,102 @@ impl FluidModule {
             .unwrap_or(Fixed64::ZERO)
     }
 
+    /// How much fluid a pump moved this tick.
+    pub fn pumped_this_tick(&self, node: NodeId) -> Fixed64 {
+        self.pump_flow.get(&node).copied().unwrap_or(Fixed64::ZERO)
+    }
+
+    /// Run every pump once, in `NodeId` order. Pumps between networks of
+    /// different fluid types, or from a network to itself, move nothing.
+    fn run_pumps(&mut self) {
+        self.pump_flow.clear();
+        let pumps: Vec<(NodeId, FluidPump)> = self
+            .pumps
+            .iter()
+            .map(|(&node, pump)| (node, pump.clone()))
+            .collect();
+        for (node, pump) in pumps {
+            let (Some(from), Some(to)) = (
+                self.networks.get(&pump.from_section),
+                self.networks.get(&pump.to_section),
+            ) else {
+                continue;
+            };
+            if from.id == to.id || from.fluid_type != to.fluid_type {
+                continue;
+            }
+            let mut sources = from.storage.clone();
+            sources.sort_unstable();
+            let mut sinks = to.storage.clone();
+            sinks.sort_unstable();
+            let held = sources
+                .iter()
+                .filter_map(|n| self.storage.get(n))
+                .fold(Fixed64::ZERO, |acc, s| acc + s.current);
+            let headroom = sinks
+                .iter()
+                .filter_map(|n| self.storage.get(n))
+                .fold(Fixed64::ZERO, |acc, s| acc + (s.capacity - s.current));
+            let amount = self.effective_rate(node, pump.rate).min(held).min(headroom);
+            if amount <= Fixed64::ZERO {
+                continue;
+            }
+            let mut drain = amount;
+            for n in &sources {
+                if let Some(s) = self.storage.get_mut(n) {
+                    let take = drain.min(s.current);
+                    s.current -= take;
+                    drain -= take;
+                }
+            }
+            let mut fill = amount;
+            for n in &sinks {
+                if let Some(s) = self.storage.get_mut(n) {
+                    let put = fill.min(s.capacity - s.current);
+                    s.current += put;
+                    fill -= put;
+                }
+            }
+            self.pump_flow.insert(node, amount);
+        }
+    }
+
+    /// Push fluid into a network's storage from outside the producer model,
+    /// such as a recipe's fluid output.
+    ///
+    /// Tanks are filled in `NodeId` order up to capacity; `fill_rate` does
+    /// not apply. Returns the amount accepted, which is
+    /// less than `amount` when the tanks run out of headroom.
+    pub fn inject(&mut self, network_id: FluidNetworkId, amount: Fixed64) -> Fixed64 {
+        let Some(network) = self.networks.get(&network_id) else {
+            return Fixed64::ZERO;
+        };
+        let mut tanks = network.storage.clone();
+        tanks.sort_unstable();
+        let mut remaining = amount;
+        for node_id in &tanks {
+            if remaining <= Fixed64::ZERO {
+                break;
+            }
+            if let Some(s) = self.storage.get_mut(node_id) {
+                let fill = remaining.min(s.capacity - s.current);
+                if fill > Fixed64::ZERO {
+                    s.current += fill;
+                    remaining -= fill;
+                }
+            }
+        }
+        amount - remaining.max(Fixed64::ZERO)
+    }
+
     /// Advance all fluid networks by one tick.
     ///
-    /// For each network:
+    /// First every [`FluidPump`] moves its fluid between tanks. Then, for
+    /// each network:
     /// 1. Sum total production from all producer nodes.
-    /// 2. Sum total demand from all consumer nodes.
+    /// 2. Sum total demand from all consumer nodes. Nodes linked with
+    ///    [`link_to_processor`](Self::link_to_processor) contribute their
+    ///    rate scaled by their last sampled activity.
     /// 3. If production >= demand: pressure = 1.0, fill storage with excess
     ///    (respecting fill_rate and capacity).
     /// 4. If production < demand: drain storage to cover deficit
@@ -355,6 +789,9 @@ impl FluidModule {
     /// 6. Emit StorageFull when storage reaches capacity, StorageEmpty when
     ///    storage reaches 0.
     ///
+    /// Storage fills and drains in `NodeId` order, not the order tanks were
+    /// added, so a rebuilt or deserialized network behaves the same.
+    ///
     /// Returns a list of events emitted this tick.
     pub fn tick(&mut self, current_tick: Ticks) -> Vec<FluidEvent> {
         let mut events = Vec::new();
@@ -364,6 +801,9 @@ impl FluidModule {
         // Clear per-consumer consumption tracking from last tick.
         self.consumer_consumption.clear();
 
+        // Forced circulation runs ahead of the supply/demand balance.
+        self.run_pumps();
+
         // Collect network IDs to iterate, then process each.
         let network_ids: Vec<FluidNetworkId> = self.networks.keys().copied().collect();
 
@@ -376,24 +816,31 @@ impl FluidModule {
             let total_production: Fixed64 = network
                 .producers
                 .iter()
-                .filter_map(|node_id| self.producers.get(node_id))
-                .map(|p| p.rate)
+                .filter_map(|node_id| {
+                    let p = self.producers.get(node_id)?;
+                    Some(self.effective_rate(*node_id, p.rate))
+                })
                 .fold(zero, |acc, val| acc + val);
 
             // Step 2: Sum total demand.
             let total_demand: Fixed64 = network
                 .consumers
                 .iter()
-                .filter_map(|node_id| self.consumers.get(node_id))
-                .map(|c| c.rate)
+                .filter_map(|node_id| {
+                    let c = self.consumers.get(node_id)?;
+                    Some(self.effective_rate(*node_id, c.rate))
+                })
                 .fold(zero, |acc, val| acc + val);
 
             // Collect storage node IDs for this network so we can mutate storage.
-            let storage_nodes: Vec<NodeId> = network.storage.clone();
+            // Sorted so that fill/drain priority never depends on add order.
+            let mut storage_nodes: Vec<NodeId> = network.storage.clone();
+            storage_nodes.sort_unstable();
             let was_low_pressure = network.was_low_pressure;
 
             // Step 3 & 4: Balance production vs demand with storage.
             let pressure;
+            let storage_flow;
 
             if total_demand == zero {
                 // No demand: fully satisfied. Fill storage with all production.
@@ -412,10 +859,12 @@ impl FluidModule {
                         }
                     }
                 }
+                storage_flow = excess.max(zero) - total_production;
             } else if total_production >= total_demand {
                 // Surplus: fully satisfied, fill storage with excess.
                 pressure = one;
-                let mut excess = total_production - total_demand;
+                let surplus = total_production - total_demand;
+                let mut excess = surplus;
                 for node_id in &storage_nodes {
                     if excess <= zero {
                         break;
@@ -429,9 +878,11 @@ impl FluidModule {
                         }
                     }
                 }
+                storage_flow = excess.max(zero) - surplus;
             } else {
                 // Deficit: try to cover with storage.
-                let mut remaining_deficit = total_demand - total_production;
+                let deficit = total_demand - total_production;
+                let mut remaining_deficit = deficit;
                 for node_id in &storage_nodes {
                     if remaining_deficit <= zero {
                         break;
@@ -445,6 +896,8 @@ impl FluidModule {
                     }
                 }
 
+                storage_flow = deficit - remaining_deficit.max(zero);
+
                 if remaining_deficit <= zero {
                     // Storage covered the deficit.
                     pressure = one;
@@ -470,10 +923,11 @@ impl FluidModule {
             let consumer_nodes: Vec<NodeId> = self.networks.get(&net_id).unwrap().consumers.clone();
             for &node_id in &consumer_nodes {
                 if let Some(consumer) = self.consumers.get(&node_id) {
+                    let rate = self.effective_rate(node_id, consumer.rate);
                     let consumed = if pressure >= one {
-                        consumer.rate
+                        rate
                     } else {
-                        consumer.rate * pressure
+                        rate * pressure
                     };
                     self.consumer_consumption
                         .insert((net_id, node_id), consumed);
@@ -505,6 +959,9 @@ impl FluidModule {
                 continue;
             };
             network.pressure = pressure;
+            network.production = total_production;
+            network.demand = total_demand;
+            network.storage_flow = storage_flow;
 
             let is_low_pressure = pressure < one;
 
@@ -1744,4 +2201,607 @@ mod tests {
             .collect();
         assert!(pressure_events.is_empty());
     }
+
+    // -----------------------------------------------------------------------
+    // Test 36: Tank priority does not depend on add order
+    // -----------------------------------------------------------------------
+    #[test]
+    fn storage_priority_is_independent_of_add_order() {
+        let nodes = make_node_ids(4);
+        let tank = |current: f64| FluidStorage {
+            capacity: fixed(100.0),
+            current: fixed(current),
+            fill_rate: fixed(30.0),
+        };
+        let build = |order: &[usize]| {
+            let mut module = FluidModule::new();
+            let net = module.create_network(water());
+            module.add_producer(net, nodes[0], FluidProducer { rate: fixed(50.0) });
+            for &i in order {
+                module.add_storage(net, nodes[i], tank(10.0 * i as f64));
+            }
+            (module, net)
+        };
+        let levels = |module: &FluidModule| -> Vec<Fixed64> {
+            nodes[1..]
+                .iter()
+                .map(|n| module.storage[n].current)
+                .collect()
+        };
+
+        let (mut forward, net) = build(&[1, 2, 3]);
+        let (reversed, _) = build(&[3, 1, 2]);
+        // A save/load round trip keeps whatever order the network had.
+        let data = bitcode::serialize(&reversed).unwrap();
+        let mut reversed: FluidModule = bitcode::deserialize(&data).unwrap();
+
+        for tick in 1..=3 {
+            forward.tick(tick);
+            reversed.tick(tick);
+            assert_eq!(levels(&forward), levels(&reversed));
+        }
+        // Excess fills the lowest NodeId first, limited by its fill rate.
+        assert_eq!(levels(&forward)[0], fixed(100.0));
+
+        // Drains and injections follow the same order.
+        forward.add_consumer(net, nodes[0], FluidConsumer { rate: fixed(90.0) });
+        reversed.add_consumer(net, nodes[0], FluidConsumer { rate: fixed(90.0) });
+        for tick in 4..=5 {
+            forward.tick(tick);
+            reversed.tick(tick);
+            assert_eq!(levels(&forward), levels(&reversed));
+        }
+        assert_eq!(
+            forward.inject(net, fixed(45.0)),
+            reversed.inject(net, fixed(45.0))
+        );
+        assert_eq!(levels(&forward), levels(&reversed));
+    }
+
+    // -----------------------------------------------------------------------
+    // Test 37: Reverse index of a node's networks
+    // -----------------------------------------------------------------------
+    #[test]
+    fn node_on_two_networks_reports_both() {
+        let mut module = FluidModule::new();
+        let water_net = module.create_network(water());
+        let steam_net = module.create_network(ItemTypeId(100));
+        let nodes = make_node_ids(2);
+        let (boiler, pipe) = (nodes[0], nodes[1]);
+
+        module.add_producer(steam_net, boiler, FluidProducer { rate: fixed(5.0) });
+        module.add_consumer(water_net, boiler, FluidConsumer { rate: fixed(10.0) });
+        module.add_pipe(
+            water_net,
+            pipe,
+            FluidPipe {
+                capacity: fixed(50.0),
+            },
+        );
+
+        assert_eq!(module.networks_for_node(boiler), vec![water_net, steam_net]);
+        assert_eq!(
+            module.role_in_network(boiler, water_net),
+            Some(FluidRole::Consumer)
+        );
+        assert_eq!(
+            module.role_in_network(boiler, steam_net),
+            Some(FluidRole::Producer)
+        );
+        assert_eq!(module.role_in_network(pipe, steam_net), None);
+
+        // The index survives a save/load round trip and matches a rebuild.
+        let data = bitcode::serialize(&module).unwrap();
+        let mut restored: FluidModule = bitcode::deserialize(&data).unwrap();
+        assert_eq!(
+            restored.networks_for_node(boiler),
+            vec![water_net, steam_net]
+        );
+        let saved = restored.node_networks.clone();
+        restored.rebuild_node_index();
+        assert_eq!(
+            restored.node_networks.iter().collect::<Vec<_>>(),
+            saved.iter().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn removal_updates_the_network_index() {
+        let mut module = FluidModule::new();
+        let water_net = module.create_network(water());
+        let steam_net = module.create_network(ItemTypeId(100));
+        let nodes = make_node_ids(2);
+        let (boiler, pipe) = (nodes[0], nodes[1]);
+        module.add_producer(steam_net, boiler, FluidProducer { rate: fixed(5.0) });
+        module.add_consumer(water_net, boiler, FluidConsumer { rate: fixed(10.0) });
+        module.add_pipe(
+            water_net,
+            pipe,
+            FluidPipe {
+                capacity: fixed(50.0),
+            },
+        );
+
+        module.remove_network(steam_net);
+        assert_eq!(module.networks_for_node(boiler), vec![water_net]);
+        assert_eq!(module.role_in_network(boiler, steam_net), None);
+
+        module.remove_node(boiler);
+        assert!(module.networks_for_node(boiler).is_empty());
+        assert_eq!(module.networks_for_node(pipe), vec![water_net]);
+        assert_eq!(
+            module.role_in_network(pipe, water_net),
+            Some(FluidRole::Pipe)
+        );
+    }
+
+    // -----------------------------------------------------------------------
+    // Test 38: Network balance reports the last tick's totals
+    // -----------------------------------------------------------------------
+
+    /// A network with one producer, one consumer, and one tank.
+    fn balance_fixture(
+        production: f64,
+        demand: f64,
+        tank: f64,
+    ) -> (FluidModule, FluidNetworkId, [NodeId; 3]) {
+        let mut module = FluidModule::new();
+        let net = module.create_network(water());
+        let nodes = make_node_ids(3);
+        module.add_producer(
+            net,
+            nodes[0],
+            FluidProducer {
+                rate: fixed(production),
+            },
+        );
+        module.add_consumer(
+            net,
+            nodes[1],
+            FluidConsumer {
+                rate: fixed(demand),
+            },
+        );
+        module.add_storage(
+            net,
+            nodes[2],
+            FluidStorage {
+                capacity: fixed(100.0),
+                current: fixed(tank),
+                fill_rate: fixed(25.0),
+            },
+        );
+        (module, net, [nodes[0], nodes[1], nodes[2]])
+    }
+
+    #[test]
+    fn network_balance_reports_balanced_surplus_and_deficit() {
+        // Balanced: the tank is untouched.
+        let (mut module, net, [_, _, tank]) = balance_fixture(100.0, 100.0, 50.0);
+        assert_eq!(
+            module.network_balance(net),
+            Some(NetworkBalance {
+                total_production: Fixed64::ZERO,
+                total_demand: Fixed64::ZERO,
+                total_storage: Fixed64::ZERO,
+                pressure: Fixed64::ONE,
+            })
+        );
+        module.tick(1);
+        assert_eq!(
+            module.network_balance(net),
+            Some(NetworkBalance {
+                total_production: fixed(100.0),
+                total_demand: fixed(100.0),
+                total_storage: Fixed64::ZERO,
+                pressure: Fixed64::ONE,
+            })
+        );
+        assert_eq!(module.storage.get(&tank).unwrap().current, fixed(50.0));
+
+        // Surplus: the tank takes in what its fill rate allows.
+        let (mut module, net, [_, consumer_s, tank_s]) = balance_fixture(100.0, 40.0, 0.0);
+        module.tick(1);
+        let balance = module.network_balance(net).unwrap();
+        assert_eq!(balance.total_production, fixed(100.0));
+        assert_eq!(balance.total_demand, fixed(40.0));
+        assert_eq!(balance.total_storage, fixed(-25.0));
+        assert_eq!(balance.pressure, Fixed64::ONE);
+        assert_eq!(module.storage.get(&tank_s).unwrap().current, fixed(25.0));
+        assert_eq!(module.get_consumed_this_tick(net, consumer_s), fixed(40.0));
+
+        // Deficit: the tank drains what it holds, and pressure reflects the
+        // production plus that contribution.
+        let (mut module, net, [_, consumer_d, tank_d]) = balance_fixture(40.0, 100.0, 20.0);
+        module.tick(1);
+        let balance = module.network_balance(net).unwrap();
+        assert_eq!(balance.total_production, fixed(40.0));
+        assert_eq!(balance.total_demand, fixed(100.0));
+        assert_eq!(balance.total_storage, fixed(20.0));
+        assert_eq!(balance.pressure, module.pressure(net).unwrap());
+        assert_eq!(
+            balance.pressure,
+            (balance.total_production + balance.total_storage) / balance.total_demand
+        );
+        assert_eq!(module.storage.get(&tank_d).unwrap().current, Fixed64::ZERO);
+        // Consumers draw their rate scaled by pressure.
+        assert_eq!(
+            module.get_consumed_this_tick(net, consumer_d),
+            balance.total_demand * balance.pressure
+        );
+
+        // With the tank empty, the next tick has no storage contribution.
+        module.tick(2);
+        let balance = module.network_balance(net).unwrap();
+        assert_eq!(balance.total_storage, Fixed64::ZERO);
+        assert_eq!(balance.pressure, fixed(0.4));
+
+        assert_eq!(module.network_balance(FluidNetworkId(99)), None);
+    }
+
+    #[test]
+    fn network_balance_without_demand_counts_stored_production() {
+        let (mut module, net, [_, consumer, _]) = balance_fixture(10.0, 0.0, 0.0);
+        module.remove_node(consumer);
+        module.tick(1);
+        let balance = module.network_balance(net).unwrap();
+        assert_eq!(balance.total_production, fixed(10.0));
+        assert_eq!(balance.total_demand, Fixed64::ZERO);
+        assert_eq!(balance.total_storage, fixed(-10.0));
+        assert_eq!(balance.pressure, Fixed64::ONE);
+
+        // The totals survive a save/load round trip.
+        let data = bitcode::serialize(&module).unwrap();
+        let restored: FluidModule = bitcode::deserialize(&data).unwrap();
+        assert_eq!(restored.network_balance(net), Some(balance));
+    }
+
+    // -----------------------------------------------------------------------
+    // Processor-linked rates
+    // -----------------------------------------------------------------------
+
+    /// An engine with a pump and a boiler whose recipe burns 1 coal per 5
+    /// ticks, stocked with `coal`.
+    fn boiler_engine(coal: u32) -> (Engine, NodeId, NodeId) {
+        use factorial_core::processor::Processor;
+        use factorial_core::sim::SimulationStrategy;
+        use factorial_core::test_utils;
+
+        let mut engine = Engine::new(SimulationStrategy::Tick);
+        let boiler = test_utils::add_node(
+            &mut engine,
+            test_utils::make_recipe(vec![(test_utils::coal(), 1)], vec![], 5),
+            100,
+            100,
+        );
+        let overflow = engine
+            .get_input_inventory_mut(boiler)
+            .unwrap()
+            .insert(test_utils::coal(), coal);
+        assert_eq!(overflow, 0);
+        let pump = test_utils::add_node(&mut engine, Processor::Passthrough, 0, 0);
+        (engine, boiler, pump)
+    }
+
+    #[test]
+    fn linked_consumer_draws_nothing_while_processor_is_stalled() {
+        let (mut engine, boiler, pump) = boiler_engine(0);
+        engine.step();
+        assert!(matches!(
+            engine.get_processor_state(boiler),
+            Some(ProcessorState::Stalled { .. })
+        ));
+
+        let mut module = FluidModule::new();
+        let net = module.create_network(water());
+        module.add_producer(net, pump, FluidProducer { rate: fixed(10.0) });
+        module.add_consumer(net, boiler, FluidConsumer { rate: fixed(20.0) });
+        module.link_to_processor(boiler, boiler);
+
+        module.tick_with_engine(&engine, 1);
+        assert_eq!(module.get_consumed_this_tick(net, boiler), Fixed64::ZERO);
+        assert_eq!(module.pressure(net), Some(fixed(1.0)));
+    }
+
+    #[test]
+    fn linked_consumer_draws_full_rate_while_processor_works() {
+        let (mut engine, boiler, pump) = boiler_engine(10);
+        engine.step();
+        assert!(matches!(
+            engine.get_processor_state(boiler),
+            Some(ProcessorState::Working { .. })
+        ));
+
+        let mut module = FluidModule::new();
+        let net = module.create_network(water());
+        module.add_producer(net, pump, FluidProducer { rate: fixed(30.0) });
+        module.add_consumer(net, boiler, FluidConsumer { rate: fixed(20.0) });
+        module.link_to_processor(boiler, boiler);
+
+        module.tick_with_engine(&engine, 1);
+        assert_eq!(module.get_consumed_this_tick(net, boiler), fixed(20.0));
+        assert_eq!(module.pressure(net), Some(fixed(1.0)));
+
+        // Doubling the boiler's speed doubles its draw and starves the pump.
+        engine.set_modifiers(
+            boiler,
+            vec![factorial_core::processor::Modifier {
+                id: factorial_core::id::ModifierId(0),
+                kind: factorial_core::processor::ModifierKind::Speed(fixed(2.0)),
+                stacking: Default::default(),
+            }],
+        );
+        let events = module.tick_with_engine(&engine, 2);
+        assert_eq!(module.pressure(net), Some(fixed(0.75)));
+        assert_eq!(module.get_consumed_this_tick(net, boiler), fixed(30.0));
+        assert!(
+            events
+                .iter()
+                .any(|e| matches!(e, FluidEvent::PressureLow { .. }))
+        );
+    }
+
+    #[test]
+    fn pressure_tracks_linked_consumer_as_processor_runs_dry() {
+        // One coal: the boiler works for one 5-tick cycle, then stalls.
+        let (mut engine, boiler, pump) = boiler_engine(1);
+        let mut module = FluidModule::new();
+        let net = module.create_network(water());
+        module.add_producer(net, pump, FluidProducer { rate: fixed(10.0) });
+        module.add_consumer(net, boiler, FluidConsumer { rate: fixed(20.0) });
+        module.link_to_processor(boiler, boiler);
+
+        let mut pressures = Vec::new();
+        for tick in 0..8 {
+            engine.step();
+            module.tick_with_engine(&engine, tick);
+            pressures.push(module.pressure(net).unwrap());
+        }
+        assert_eq!(pressures[0], fixed(0.5), "working boiler outdraws the pump");
+        assert_eq!(pressures[7], fixed(1.0), "stalled boiler draws nothing");
+    }
+
+    #[test]
+    fn linked_producer_is_idle_until_sampled_and_unlink_restores_rate() {
+        let nodes = make_node_ids(2);
+        let (pump, sink) = (nodes[0], nodes[1]);
+        let mut module = FluidModule::new();
+        let net = module.create_network(water());
+        module.add_producer(net, pump, FluidProducer { rate: fixed(10.0) });
+        module.add_consumer(net, sink, FluidConsumer { rate: fixed(10.0) });
+        module.link_to_processor(pump, pump);
+
+        module.tick(1);
+        assert_eq!(module.pressure(net), Some(fixed(0.0)));
+
+        module.unlink_processor(pump);
+        module.tick(2);
+        assert_eq!(module.pressure(net), Some(fixed(1.0)));
+    }
+
+    // -----------------------------------------------------------------------
+    // Inventory deliveries
+    // -----------------------------------------------------------------------
+
+    /// A plant whose recipe turns 6 water into a gear every 2 ticks, with
+    /// `capacity` input slots, and a pump.
+    fn water_plant(capacity: u32) -> (Engine, NodeId, NodeId) {
+        use factorial_core::processor::Processor;
+        use factorial_core::sim::SimulationStrategy;
+        use factorial_core::test_utils;
+
+        let mut engine = Engine::new(SimulationStrategy::Tick);
+        let plant = test_utils::add_node(
+            &mut engine,
+            test_utils::make_recipe(vec![(water(), 6)], vec![(test_utils::gear(), 1)], 2),
+            capacity,
+            100,
+        );
+        let pump = test_utils::add_node(&mut engine, Processor::Passthrough, 0, 0);
+        (engine, plant, pump)
+    }
+
+    #[test]
+    fn delivered_fluid_accumulates_at_the_pressure_rate_and_feeds_the_recipe() {
+        use factorial_core::test_utils;
+
+        let (mut engine, plant, pump) = water_plant(100);
+        let mut module = FluidModule::new();
+        let net = module.create_network(water());
+        module.add_producer(net, pump, FluidProducer { rate: fixed(3.0) });
+        module.add_consumer(net, plant, FluidConsumer { rate: fixed(6.0) });
+        assert!(module.deliver_to_inventory(net, plant, plant));
+
+        // Half pressure: the plant receives 3 water per tick.
+        for tick in 0..4 {
+            module.tick(tick);
+            module.deliver(&mut engine);
+            assert_eq!(module.pressure(net), Some(fixed(0.5)));
+            assert_eq!(
+                test_utils::input_quantity(&engine, plant, water()),
+                3 * (tick as u32 + 1)
+            );
+        }
+
+        for tick in 4..20 {
+            module.tick(tick);
+            module.deliver(&mut engine);
+            engine.step();
+        }
+        let gears = test_utils::output_quantity(&engine, plant, test_utils::gear());
+        assert!(gears >= 5, "plant crafted only {gears} gears");
+        // Everything delivered is either still buffered or was crafted.
+        let buffered = test_utils::input_quantity(&engine, plant, water());
+        let in_progress = match engine.get_processor_state(plant) {
+            Some(ProcessorState::Working { .. }) => 6,
+            _ => 0,
+        };
+        assert_eq!(buffered + 6 * gears + in_progress, 3 * 20);
+    }
+
+    #[test]
+    fn delivery_carries_fractions_and_clamps_to_capacity() {
+        use factorial_core::test_utils;
+
+        let (mut engine, plant, pump) = water_plant(10);
+        let mut module = FluidModule::new();
+        let net = module.create_network(water());
+        module.add_producer(net, pump, FluidProducer { rate: fixed(10.0) });
+        module.add_consumer(net, pump, FluidConsumer { rate: fixed(2.5) });
+        assert!(module.deliver_to_inventory(net, pump, plant));
+        assert!(!module.deliver_to_inventory(FluidNetworkId(99), pump, plant));
+
+        for tick in 0..3 {
+            module.tick(tick);
+            module.deliver(&mut engine);
+        }
+        assert_eq!(test_utils::input_quantity(&engine, plant, water()), 7);
+
+        for tick in 3..20 {
+            module.tick(tick);
+            module.deliver(&mut engine);
+        }
+        assert_eq!(test_utils::input_quantity(&engine, plant, water()), 10);
+
+        module.remove_node(plant);
+        assert!(module.deliveries.is_empty());
+    }
+
+    #[test]
+    fn initialize_sets_pressure_without_moving_fluid() {
+        let (engine, plant, pump) = water_plant(100);
+        let mut module = FluidModule::new();
+        let net = module.create_network(water());
+        module.add_producer(net, pump, FluidProducer { rate: fixed(3.0) });
+        module.add_consumer(net, plant, FluidConsumer { rate: fixed(6.0) });
+        let tank = NodeId::default();
+        module.add_storage(
+            net,
+            tank,
+            FluidStorage {
+                capacity: fixed(100.0),
+                current: fixed(1.0),
+                fill_rate: fixed(10.0),
+            },
+        );
+        assert_eq!(module.pressure(net), Some(fixed(1.0)));
+
+        module.initialize(&engine);
+        // 3 produced plus 1 drained from the tank against a demand of 6.
+        let primed = fixed(4.0) / fixed(6.0);
+        assert_eq!(module.pressure(net), Some(primed));
+        assert_eq!(module.storage[&tank].current, fixed(1.0));
+        assert!(module.consumer_consumption.is_empty());
+
+        let events = module.tick(0);
+        assert_eq!(module.pressure(net), Some(primed));
+        assert!(
+            events
+                .iter()
+                .any(|e| matches!(e, FluidEvent::PressureLow { .. }))
+        );
+    }
+
+    fn tank(capacity: f64, current: f64) -> FluidStorage {
+        FluidStorage {
+            capacity: fixed(capacity),
+            current: fixed(current),
+            fill_rate: fixed(1.0),
+        }
+    }
+
+    #[test]
+    fn pumps_circulate_a_closed_loop_and_conserve_fluid() {
+        let mut module = FluidModule::new();
+        let hot = module.create_network(water());
+        let exchanger = module.create_network(water());
+        let cold = module.create_network(water());
+        let nodes = make_node_ids(6);
+        let (hot_tank, buffer, cold_tank) = (nodes[0], nodes[1], nodes[2]);
+        let (feed, drain, ret) = (nodes[3], nodes[4], nodes[5]);
+        module.add_storage(hot, hot_tank, tank(1000.0, 600.0));
+        module.add_storage(exchanger, buffer, tank(10.0, 0.0));
+        module.add_storage(cold, cold_tank, tank(1000.0, 0.0));
+        let pump = |rate, from_section, to_section| FluidPump {
+            rate: fixed(rate),
+            from_section,
+            to_section,
+        };
+        // Through the consumer at 10/tick, back to the hot tank at 4/tick.
+        module.add_pump(feed, pump(10.0, hot, exchanger));
+        module.add_pump(drain, pump(10.0, exchanger, cold));
+        module.add_pump(ret, pump(4.0, cold, hot));
+
+        let level = |m: &FluidModule, n: NodeId| m.storage[&n].current;
+        for tick in 1..=50 {
+            module.tick(tick);
+            assert_eq!(module.pumped_this_tick(feed), fixed(10.0));
+            assert_eq!(module.pumped_this_tick(drain), fixed(10.0));
+            assert_eq!(module.pumped_this_tick(ret), fixed(4.0));
+            let total =
+                level(&module, hot_tank) + level(&module, buffer) + level(&module, cold_tank);
+            assert_eq!(total, fixed(600.0));
+        }
+        assert_eq!(level(&module, hot_tank), fixed(300.0));
+        assert_eq!(level(&module, buffer), fixed(0.0));
+        assert_eq!(level(&module, cold_tank), fixed(300.0));
+        // Pumps ignore `fill_rate` and leave pressure alone.
+        for net in [hot, exchanger, cold] {
+            assert_eq!(module.pressure(net), Some(fixed(1.0)));
+        }
+
+        module.remove_node(ret);
+        module.tick(51);
+        assert_eq!(module.pumped_this_tick(ret), fixed(0.0));
+        assert_eq!(level(&module, cold_tank), fixed(310.0));
+    }
+
+    #[test]
+    fn pumps_drain_tanks_ahead_of_consumers() {
+        let mut module = FluidModule::new();
+        let supply = module.create_network(water());
+        let reservoir = module.create_network(water());
+        let crude = module.create_network(oil());
+        let nodes = make_node_ids(5);
+        module.add_storage(
+            supply,
+            nodes[0],
+            FluidStorage {
+                capacity: fixed(100.0),
+                current: fixed(20.0),
+                fill_rate: fixed(100.0),
+            },
+        );
+        module.add_consumer(supply, nodes[1], FluidConsumer { rate: fixed(5.0) });
+        module.add_storage(reservoir, nodes[2], tank(100.0, 0.0));
+        module.add_storage(crude, nodes[3], tank(100.0, 0.0));
+        let to = |to_section| FluidPump {
+            rate: fixed(10.0),
+            from_section: supply,
+            to_section,
+        };
+        module.add_pump(nodes[4], to(reservoir));
+
+        module.tick(1);
+        assert_eq!(module.pressure(supply), Some(fixed(1.0)));
+        assert_eq!(module.storage[&nodes[0]].current, fixed(5.0));
+
+        // The pump takes what is left before the consumer can draw on it.
+        let events = module.tick(2);
+        assert_eq!(module.pumped_this_tick(nodes[4]), fixed(5.0));
+        assert_eq!(module.pressure(supply), Some(fixed(0.0)));
+        assert!(
+            events
+                .iter()
+                .any(|e| matches!(e, FluidEvent::PressureLow { .. }))
+        );
+        assert_eq!(module.storage[&nodes[2]].current, fixed(15.0));
+
+        // A pump between different fluids moves nothing.
+        module.storage.get_mut(&nodes[0]).unwrap().current = fixed(50.0);
+        module.add_pump(nodes[4], to(crude));
+        module.tick(3);
+        assert_eq!(module.pumped_this_tick(nodes[4]), fixed(0.0));
+        assert_eq!(module.storage[&nodes[3]].current, fixed(0.0));
+    }
 }
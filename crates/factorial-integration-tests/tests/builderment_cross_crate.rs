@@ -1048,3 +1048,73 @@ fn stats_tracking_and_bottleneck_detection() {
         "stats should track production nodes"
     );
 }
+
+#[test]
+fn stats_rebuilt_from_event_log_match_live_stats() {
+    let (mut engine, nodes) = build_builderment_factory();
+    let config = StatsConfig {
+        window_size: 50,
+        history_capacity: 10,
+    };
+    let mut live = ProductionStats::new(config.clone());
+
+    let event_buffer: Rc<RefCell<Vec<Event>>> = Rc::new(RefCell::new(Vec::new()));
+    let kinds = [
+        EventKind::ItemProduced,
+        EventKind::ItemConsumed,
+        EventKind::BuildingStalled,
+        EventKind::BuildingResumed,
+        EventKind::ItemDelivered,
+        EventKind::TransportFull,
+    ];
+    for kind in kinds {
+        let buf = Rc::clone(&event_buffer);
+        engine.on_passive(
+            kind,
+            Box::new(move |event| {
+                buf.borrow_mut().push(event.clone());
+            }),
+        );
+    }
+
+    engine.enable_event_log();
+    for _ in 0..300 {
+        engine.step();
+        for event in event_buffer.borrow_mut().drain(..) {
+            live.process_event(&event);
+        }
+        live.end_tick(engine.sim_state.tick);
+    }
+
+    // Round-trip through the on-disk format before replaying.
+    let bytes = engine.take_event_log().unwrap().to_bytes().unwrap();
+    let log = factorial_core::event::EventLog::from_bytes(&bytes).unwrap();
+    assert_eq!(log.tick_count(), 300);
+    let replayed = ProductionStats::from_event_log(&log, config);
+
+    assert_eq!(replayed.current_tick(), live.current_tick());
+    assert_eq!(replayed.tracked_node_count(), live.tracked_node_count());
+    assert_eq!(replayed.tracked_edge_count(), live.tracked_edge_count());
+    for item in [iron_ingot(), copper_wire()] {
+        assert_eq!(
+            replayed.get_total_production(item),
+            live.get_total_production(item)
+        );
+        assert_eq!(
+            replayed.get_total_consumption(item),
+            live.get_total_consumption(item)
+        );
+    }
+    for node in [
+        nodes.iron_furnace,
+        nodes.copper_furnace,
+        nodes.wire_workshop,
+    ] {
+        assert_eq!(
+            replayed.get_production_rate(node, iron_ingot()),
+            live.get_production_rate(node, iron_ingot())
+        );
+        assert_eq!(replayed.get_stall_ratio(node), live.get_stall_ratio(node));
+        assert_eq!(replayed.get_uptime(node), live.get_uptime(node));
+    }
+}
@@ -141,6 +141,7 @@ fn dual_lane_belt() -> Transport {
         speed: Fixed64::from_num(1),
         slot_count: 8,
         lanes: 2,
+        min_spacing: Fixed64::ONE,
     })
 }
 
@@ -86,6 +86,7 @@ fn circuit_controlled_inserter_activates_on_threshold() {
                 left: SignalSelector::Signal(l_iron_ore()),
                 op: ComparisonOp::Gt,
                 right: SignalSelector::Constant(f(10.0)),
+                epsilon: Fixed64::ZERO,
             },
             WireColor::Red,
         );
@@ -164,6 +165,7 @@ fn decider_combinator_one_tick_delay_gates_production() {
                     left: SignalSelector::Signal(l_iron_plate()),
                     op: ComparisonOp::Lt,
                     right: SignalSelector::Constant(f(100.0)),
+                    epsilon: Fixed64::ZERO,
                 },
                 output: DeciderOutput::One(l_steel()),
             },
@@ -176,6 +178,7 @@ fn decider_combinator_one_tick_delay_gates_production() {
                 left: SignalSelector::Signal(l_steel()),
                 op: ComparisonOp::Gt,
                 right: SignalSelector::Constant(f(0.0)),
+                epsilon: Fixed64::ZERO,
             },
             WireColor::Red,
         );
@@ -310,6 +313,7 @@ fn dual_network_arithmetic_feedback() {
                 left: SignalSelector::Signal(l_ratio()),
                 op: ComparisonOp::Gt,
                 right: SignalSelector::Constant(f(15.0)),
+                epsilon: Fixed64::ZERO,
             },
             WireColor::Green,
         );
@@ -0,0 +1,130 @@
+//! Node flags across the engine and the stats tracker.
+//!
+//! Decorative buildings added with `NodeFlags::SILENT | NodeFlags::NO_STATS`
+//! simulate exactly like unflagged ones -- same state hash -- but emit no
+//! events and never show up in `ProductionStats`.
+
+use factorial_core::engine::Engine;
+use factorial_core::event::Event;
+use factorial_core::graph::NodeFlags;
+use factorial_core::id::*;
+use factorial_core::processor::Processor;
+use factorial_core::sim::SimulationStrategy;
+use factorial_core::test_utils::*;
+
+use factorial_stats::{ProductionStats, StatsConfig};
+
+/// Add a node through the engine's mutation queue, so its flags take effect.
+fn add_flagged(
+    engine: &mut Engine,
+    processor: Processor,
+    capacity: u32,
+    flags: NodeFlags,
+) -> NodeId {
+    let pending = engine.graph.queue_add_node_with_flags(building(), flags);
+    let node = engine.apply_mutations().resolve_node(pending).unwrap();
+    engine.set_processor(node, processor);
+    engine.set_input_inventory(node, simple_inventory(capacity));
+    engine.set_output_inventory(node, simple_inventory(capacity));
+    node
+}
+
+/// A mine feeding a smelter, next to three decorative fountains that run
+/// sources of their own, with `decor` flags on the fountains.
+struct Run {
+    engine: Engine,
+    stats: ProductionStats,
+    events: Vec<Event>,
+    fountains: Vec<NodeId>,
+}
+
+impl Run {
+    fn new(decor: NodeFlags) -> Self {
+        let mut engine = Engine::new(SimulationStrategy::Tick);
+        engine.enable_event_log();
+        let mine = add_flagged(&mut engine, make_source(iron(), 1.0), 50, NodeFlags::EMPTY);
+        let smelter = add_flagged(
+            &mut engine,
+            make_recipe(vec![(iron(), 1)], vec![(gear(), 1)], 2),
+            50,
+            NodeFlags::EMPTY,
+        );
+        connect(&mut engine, mine, smelter, make_flow_transport(2.0));
+        let fountains = (0..3)
+            .map(|_| add_flagged(&mut engine, make_source(wood(), 0.5), 20, decor))
+            .collect();
+
+        let mut stats = ProductionStats::new(StatsConfig::default());
+        stats.initialize(&engine);
+        Self {
+            engine,
+            stats,
+            events: Vec::new(),
+            fountains,
+        }
+    }
+
+    fn step(&mut self, ticks: usize) {
+        for _ in 0..ticks {
+            self.engine.step();
+            let log = self.engine.take_event_log().unwrap();
+            self.stats.replay_event_log(&log);
+            self.events.extend(log.events().cloned());
+        }
+    }
+}
+
+#[test]
+fn flagged_decor_simulates_identically_but_stays_out_of_events_and_stats() {
+    let mut plain = Run::new(NodeFlags::EMPTY);
+    let mut decor = Run::new(NodeFlags::SILENT | NodeFlags::NO_STATS);
+    plain.step(30);
+    decor.step(30);
+
+    assert_eq!(plain.engine.state_hash(), decor.engine.state_hash());
+    for (&a, &b) in plain.fountains.iter().zip(&decor.fountains) {
+        assert_eq!(
+            plain
+                .engine
+                .get_output_inventory(a)
+                .unwrap()
+                .count_of(wood()),
+            decor
+                .engine
+                .get_output_inventory(b)
+                .unwrap()
+                .count_of(wood()),
+        );
+    }
+
+    assert!(decor.events.len() < plain.events.len());
+    assert!(
+        decor
+            .events
+            .iter()
+            .all(|e| e.node().is_none_or(|n| !decor.fountains.contains(&n)))
+    );
+    assert_eq!(plain.stats.tracked_node_count(), 5);
+    assert_eq!(decor.stats.tracked_node_count(), 2);
+    assert!(decor.stats.tracked_item_type_count() < plain.stats.tracked_item_type_count());
+}
+
+#[test]
+fn no_stats_alone_keeps_events_but_skips_statistics() {
+    let mut plain = Run::new(NodeFlags::EMPTY);
+    let mut quiet = Run::new(NodeFlags::NO_STATS);
+    plain.step(10);
+    quiet.step(10);
+
+    assert_eq!(plain.events.len(), quiet.events.len());
+    assert_eq!(quiet.stats.tracked_node_count(), 2);
+    for &fountain in &quiet.fountains {
+        assert!(quiet.stats.is_ignored(fountain));
+    }
+
+    // Removing an ignored node forgets it.
+    let fountain = quiet.fountains[0];
+    quiet.engine.graph.queue_remove_node(fountain);
+    quiet.step(1);
+    assert!(!quiet.stats.is_ignored(fountain));
+}
@@ -0,0 +1,200 @@
+//! Cross-module node removal tests.
+//!
+//! Removing a node through the engine's mutation queue must clean every
+//! subsystem: core per-node state, registered modules (logic) via
+//! `Module::on_node_removed`, and host-owned modules (stats, fluid) fed the
+//! engine's `NodeRemoved` event. Queries for the dead id return empty data.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use factorial_core::builder::FactoryBuilder;
+use factorial_core::engine::Engine;
+use factorial_core::event::{Event, EventKind};
+use factorial_core::fixed::Fixed64;
+use factorial_core::id::*;
+use factorial_core::test_utils::*;
+
+use factorial_fluid::{FluidConsumer, FluidModule, FluidNetworkId, FluidProducer};
+use factorial_logic::condition::InventorySource;
+use factorial_logic::{LogicModuleBridge, SignalSet, WireColor, WireNetworkId};
+use factorial_stats::{ProductionStats, StatsConfig};
+
+fn water() -> ItemTypeId {
+    ItemTypeId(3)
+}
+
+/// A miner feeding a chest, with the miner wired into every subsystem.
+struct Rig {
+    engine: Engine,
+    miner: NodeId,
+    chest: NodeId,
+    belt: EdgeId,
+    stats: ProductionStats,
+    fluid: FluidModule,
+    fluid_net: FluidNetworkId,
+    wire: WireNetworkId,
+    events: Rc<RefCell<Vec<Event>>>,
+}
+
+impl Rig {
+    fn new() -> Self {
+        let mut builder = FactoryBuilder::new();
+        builder.node("miner").source(iron(), 1.0);
+        builder.node("chest");
+        builder.belt("miner", "chest", 1.0);
+        let factory = builder.build().unwrap();
+        let miner = factory.node("miner").unwrap();
+        let chest = factory.node("chest").unwrap();
+        let belt = factory.edges[0];
+        let mut engine = factory.engine;
+
+        let events = Rc::new(RefCell::new(Vec::new()));
+        for kind in [
+            EventKind::ItemProduced,
+            EventKind::ItemDelivered,
+            EventKind::NodeRemoved,
+            EventKind::EdgeRemoved,
+        ] {
+            let sink = Rc::clone(&events);
+            engine.on_passive(
+                kind,
+                Box::new(move |event: &Event| sink.borrow_mut().push(event.clone())),
+            );
+        }
+
+        engine.register_module(Box::new(LogicModuleBridge::new()));
+        let logic = engine
+            .find_module_mut::<LogicModuleBridge>()
+            .unwrap()
+            .logic_mut();
+        let wire = logic.create_network(WireColor::Red);
+        logic.add_to_network(wire, miner);
+        let mut signals = SignalSet::new();
+        signals.insert(iron(), Fixed64::from_num(1));
+        logic.set_constant(miner, signals, true);
+        logic.set_inventory_reader(chest, miner, InventorySource::Output);
+
+        let mut fluid = FluidModule::new();
+        let fluid_net = fluid.create_network(water());
+        fluid.add_producer(
+            fluid_net,
+            miner,
+            FluidProducer {
+                rate: Fixed64::from_num(10),
+            },
+        );
+        fluid.add_consumer(
+            fluid_net,
+            chest,
+            FluidConsumer {
+                rate: Fixed64::from_num(5),
+            },
+        );
+
+        Self {
+            engine,
+            miner,
+            chest,
+            belt,
+            stats: ProductionStats::new(StatsConfig::default()),
+            fluid,
+            fluid_net,
+            wire,
+            events,
+        }
+    }
+
+    /// Step the engine and feed its events to the host-owned modules.
+    fn step(&mut self) {
+        self.engine.step();
+        let tick = self.engine.sim_state.tick;
+        for event in self.events.borrow_mut().drain(..) {
+            self.stats.process_event(&event);
+            self.fluid.process_event(&event);
+        }
+        self.stats.end_tick(tick);
+        self.fluid.tick(tick);
+    }
+}
+
+#[test]
+fn removed_node_leaves_no_stale_state_in_any_subsystem() {
+    let mut rig = Rig::new();
+    for _ in 0..20 {
+        rig.step();
+    }
+    let miner = rig.miner;
+    assert!(rig.stats.get_production_rate(miner, iron()) > Fixed64::ZERO);
+    assert!(rig.stats.get_throughput(rig.belt) > Fixed64::ZERO);
+    assert!(
+        rig.fluid.get_consumed_this_tick(rig.fluid_net, rig.chest) > Fixed64::ZERO,
+        "the miner feeds the fluid network before removal"
+    );
+
+    rig.engine.graph.queue_remove_node(miner);
+    rig.step();
+
+    // Core.
+    assert!(rig.engine.get_processor_state(miner).is_none());
+    assert!(rig.engine.get_output_inventory(miner).is_none());
+    assert!(rig.engine.get_transport_state(rig.belt).is_none());
+
+    // Stats.
+    assert_eq!(rig.stats.get_production_rate(miner, iron()), Fixed64::ZERO);
+    assert!(rig.stats.get_history(miner, iron()).is_empty());
+    assert!(rig.stats.get_edge_history(rig.belt).is_empty());
+    assert_eq!(rig.stats.tracked_edge_count(), 0);
+
+    // Fluid.
+    assert!(!rig.fluid.producers.contains_key(&miner));
+    let network = rig.fluid.network(rig.fluid_net).unwrap();
+    assert!(!network.producers.contains(&miner));
+    assert_eq!(
+        rig.fluid.get_consumed_this_tick(rig.fluid_net, rig.chest),
+        Fixed64::ZERO
+    );
+
+    // Logic.
+    let logic = rig
+        .engine
+        .find_module::<LogicModuleBridge>()
+        .unwrap()
+        .logic();
+    assert!(!logic.constants.contains_key(&miner));
+    assert!(!logic.inventory_readers.contains_key(&rig.chest));
+    assert!(
+        !logic.networks[&rig.wire].members.contains(&miner),
+        "the dead node must leave its wire network"
+    );
+    assert!(logic.network_signals(rig.wire).is_some());
+}
+
+#[test]
+fn subsystems_keep_running_after_node_removal() {
+    let mut rig = Rig::new();
+    for _ in 0..5 {
+        rig.step();
+    }
+    rig.engine.graph.queue_remove_node(rig.miner);
+    for _ in 0..10 {
+        rig.step();
+    }
+
+    // Queries on the dangling id are empty rather than panicking.
+    assert!(rig.engine.get_input_inventory(rig.miner).is_none());
+    assert_eq!(rig.stats.get_idle_ratio(rig.miner), Fixed64::ZERO);
+    assert_eq!(
+        rig.fluid.get_consumed_this_tick(rig.fluid_net, rig.miner),
+        Fixed64::ZERO
+    );
+    let logic = rig
+        .engine
+        .find_module::<LogicModuleBridge>()
+        .unwrap()
+        .logic();
+    assert!(logic.is_active(rig.miner).is_none());
+
+    // The surviving node is still simulated.
+    assert!(rig.engine.get_input_inventory(rig.chest).is_some());
+}
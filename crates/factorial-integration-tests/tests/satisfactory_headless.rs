@@ -130,6 +130,7 @@ fn mk3_belt() -> Transport {
         speed: Fixed64::from_num(4.5),
         slot_count: 8,
         lanes: 1,
+        min_spacing: Fixed64::ONE,
     })
 }
 
@@ -140,6 +141,7 @@ fn mk5_belt() -> Transport {
         speed: Fixed64::from_num(13.0),
         slot_count: 8,
         lanes: 1,
+        min_spacing: Fixed64::ONE,
     })
 }
 
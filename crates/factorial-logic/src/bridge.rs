@@ -4,6 +4,8 @@
 //! [`factorial_core::module::Module`], so that logic networks are
 //! automatically ticked in the engine's phase-4 component pass.
 
+use factorial_core::extension::ExtensionId;
+use factorial_core::id::NodeId;
 use factorial_core::module::{Module, ModuleContext, ModuleError};
 use factorial_core::processor::Processor;
 
@@ -52,6 +54,12 @@ impl LogicModuleBridge {
     pub fn last_events(&self) -> &[crate::LogicEvent] {
         &self.last_events
     }
+
+    /// Signals that changed on some node's wire during the most recent tick.
+    /// See [`LogicModule::changed_signals`].
+    pub fn changed_signals(&self) -> &[crate::SignalChange] {
+        self.logic.changed_signals()
+    }
 }
 
 impl Module for LogicModuleBridge {
@@ -59,8 +67,29 @@ impl Module for LogicModuleBridge {
         "logic"
     }
 
+    fn extension(&self) -> Option<ExtensionId> {
+        Some(ExtensionId::Logic)
+    }
+
     fn on_tick(&mut self, ctx: &mut ModuleContext<'_>) {
+        // Storage nodes with `read_signal` set publish their contents on
+        // every network they belong to.
+        let storage_readers = self
+            .logic
+            .networks
+            .values()
+            .flat_map(|network| &network.members)
+            .copied()
+            .filter(|&node| {
+                matches!(
+                    ctx.processors.get(node),
+                    Some(Processor::Storage(storage)) if storage.read_signal
+                )
+            })
+            .collect();
+        self.logic.set_storage_readers(storage_readers);
         self.last_events = self.logic.tick(ctx.inputs, ctx.outputs, ctx.tick);
+        ctx.work += self.logic.signals_compared();
 
         // Apply signal-driven recipe switches for MultiRecipe processors.
         for (&node, control) in &self.logic.circuit_controls {
@@ -75,6 +104,10 @@ impl Module for LogicModuleBridge {
         }
     }
 
+    fn on_node_removed(&mut self, node: NodeId) {
+        self.logic.remove_node(node);
+    }
+
     fn serialize_state(&self) -> Vec<u8> {
         bitcode::serialize(&self.logic).unwrap_or_default()
     }
@@ -149,6 +182,7 @@ mod tests {
                     left: SignalSelector::Signal(iron),
                     op: ComparisonOp::Gt,
                     right: SignalSelector::Constant(fixed(50.0)),
+                    epsilon: Fixed64::ZERO,
                 },
                 WireColor::Red,
             );
@@ -246,4 +280,128 @@ mod tests {
         let bridge = LogicModuleBridge::default();
         assert_eq!(bridge.logic().networks.len(), 0);
     }
+
+    #[test]
+    fn changed_signals_scale_with_changes_not_total_signals() {
+        let mut engine = Engine::new(SimulationStrategy::Tick);
+        engine.register_module(Box::new(LogicModuleBridge::new()));
+        engine.set_profiling(true);
+        let nodes = make_node_ids(1000);
+        let iron = ItemTypeId(0);
+
+        {
+            let logic = engine
+                .find_module_mut::<LogicModuleBridge>()
+                .unwrap()
+                .logic_mut();
+            for (i, &node) in nodes.iter().enumerate() {
+                let net = logic.create_network(WireColor::Red);
+                logic.add_to_network(net, node);
+                let mut signals = SignalSet::new();
+                signals.insert(iron, fixed(i as f64));
+                logic.set_constant(node, signals, true);
+            }
+        }
+
+        // The first tick reports every non-zero constant.
+        engine.step();
+        let bridge = engine.find_module::<LogicModuleBridge>().unwrap();
+        assert_eq!(bridge.changed_signals().len(), 999);
+        let ordered: Vec<_> = bridge.changed_signals().iter().map(|c| c.node).collect();
+        assert!(ordered.windows(2).all(|w| w[0] < w[1]));
+        let full_work = engine.last_step_profile().unwrap().module_work;
+        assert!(full_work >= 999);
+
+        for tick in 0..10 {
+            let node = nodes[tick * 37];
+            {
+                let logic = engine
+                    .find_module_mut::<LogicModuleBridge>()
+                    .unwrap()
+                    .logic_mut();
+                let mut signals = SignalSet::new();
+                signals.insert(iron, fixed(5000.0 + tick as f64));
+                logic.set_constant(node, signals, true);
+            }
+            engine.step();
+
+            let bridge = engine.find_module::<LogicModuleBridge>().unwrap();
+            let changes = bridge.changed_signals();
+            assert_eq!(changes.len(), 1);
+            assert_eq!(changes[0].node, node);
+            assert_eq!(changes[0].wire, WireColor::Red);
+            assert_eq!(changes[0].signal, iron);
+            assert_eq!(changes[0].new, fixed(5000.0 + tick as f64));
+            assert!(engine.last_step_profile().unwrap().module_work <= 2);
+        }
+
+        // With nothing changed, the report is empty and no signals are compared.
+        engine.step();
+        let bridge = engine.find_module::<LogicModuleBridge>().unwrap();
+        assert!(bridge.changed_signals().is_empty());
+        assert_eq!(engine.last_step_profile().unwrap().module_work, 0);
+    }
+
+    #[test]
+    fn storage_with_read_signal_publishes_its_contents_each_tick() {
+        use factorial_core::item::Inventory;
+        use factorial_core::processor::StorageProcessor;
+        use factorial_core::test_utils;
+
+        let mut engine = Engine::new(SimulationStrategy::Tick);
+        engine.register_module(Box::new(LogicModuleBridge::new()));
+        let iron = test_utils::iron();
+        let source = test_utils::add_node(&mut engine, test_utils::make_source(iron, 1.0), 10, 10);
+        let chest = test_utils::add_node(
+            &mut engine,
+            Processor::Storage(StorageProcessor {
+                usable_slots: None,
+                read_signal: true,
+            }),
+            10,
+            10,
+        );
+        engine.set_output_inventory(chest, Inventory::new(1, 2, 5));
+        test_utils::connect(
+            &mut engine,
+            source,
+            chest,
+            test_utils::make_item_transport(2),
+        );
+        let net = {
+            let logic = engine
+                .find_module_mut::<LogicModuleBridge>()
+                .unwrap()
+                .logic_mut();
+            let net = logic.create_network(WireColor::Red);
+            logic.add_to_network(net, chest);
+            net
+        };
+
+        let mut published = 0;
+        for _ in 0..20 {
+            engine.step();
+            let held = test_utils::output_quantity(&engine, chest, iron);
+            let bridge = engine.find_module::<LogicModuleBridge>().unwrap();
+            let signal = bridge.logic().network_signals(net).unwrap().get(&iron);
+            assert_eq!(signal.copied(), (held > 0).then(|| fixed(held as f64)));
+            published = published.max(held);
+        }
+        assert_eq!(published, 10);
+
+        // Clearing the flag stops publishing on the next tick.
+        let Some(Processor::Storage(storage)) = engine.get_processor(chest).cloned() else {
+            panic!("chest lost its processor");
+        };
+        engine.set_processor(
+            chest,
+            Processor::Storage(StorageProcessor {
+                read_signal: false,
+                ..storage
+            }),
+        );
+        engine.step();
+        let bridge = engine.find_module::<LogicModuleBridge>().unwrap();
+        assert!(bridge.logic().network_signals(net).unwrap().is_empty());
+    }
 }
@@ -7,6 +7,20 @@ use serde::{Deserialize, Serialize};
 use crate::SignalSet;
 use crate::condition::{Condition, evaluate_condition};
 
+// ---------------------------------------------------------------------------
+// Combinator kind
+// ---------------------------------------------------------------------------
+
+/// The kinds of combinator a node can carry. Combinators are keyed by the
+/// node they sit on, and a node holds at most one of each kind, so a node
+/// and a kind identify a combinator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum CombinatorKind {
+    Constant,
+    Arithmetic,
+    Decider,
+}
+
 // ---------------------------------------------------------------------------
 // Signal selector
 // ---------------------------------------------------------------------------
@@ -284,6 +298,7 @@ mod tests {
                 left: SignalSelector::Signal(iron()),
                 op: ComparisonOp::Gt,
                 right: SignalSelector::Constant(fixed(50.0)),
+                epsilon: Fixed64::ZERO,
             },
             output: DeciderOutput::One(steel()),
         };
@@ -299,6 +314,7 @@ mod tests {
                 left: SignalSelector::Signal(iron()),
                 op: ComparisonOp::Gt,
                 right: SignalSelector::Constant(fixed(50.0)),
+                epsilon: Fixed64::ZERO,
             },
             output: DeciderOutput::One(steel()),
         };
@@ -314,6 +330,7 @@ mod tests {
                 left: SignalSelector::Signal(iron()),
                 op: ComparisonOp::Gte,
                 right: SignalSelector::Constant(fixed(50.0)),
+                epsilon: Fixed64::ZERO,
             },
             output: DeciderOutput::InputCount(iron()),
         };
@@ -329,6 +346,7 @@ mod tests {
                 left: SignalSelector::Each,
                 op: ComparisonOp::Gt,
                 right: SignalSelector::Constant(fixed(0.0)),
+                epsilon: Fixed64::ZERO,
             },
             output: DeciderOutput::Everything,
         };
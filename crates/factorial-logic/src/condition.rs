@@ -1,5 +1,6 @@
 //! Conditions and circuit control for signal-driven building behavior.
 
+use factorial_core::fixed::Fixed64;
 use factorial_core::id::NodeId;
 use serde::{Deserialize, Serialize};
 
@@ -31,19 +32,26 @@ pub struct Condition {
     pub left: SignalSelector,
     pub op: ComparisonOp,
     pub right: SignalSelector,
+    /// Tolerance for `Eq` and `Ne`: values at most `epsilon` apart count as
+    /// equal. Zero (the default) compares exactly; negative values act as
+    /// zero. Ordering comparisons ignore it.
+    #[serde(default)]
+    pub epsilon: Fixed64,
 }
 
 /// Evaluate a condition against a signal set.
 pub fn evaluate_condition(condition: &Condition, signals: &SignalSet) -> bool {
     let left = resolve_selector(&condition.left, signals);
     let right = resolve_selector(&condition.right, signals);
+    let equal =
+        || left.saturating_sub(right).saturating_abs() <= condition.epsilon.max(Fixed64::ZERO);
     match condition.op {
         ComparisonOp::Gt => left > right,
         ComparisonOp::Lt => left < right,
-        ComparisonOp::Eq => left == right,
+        ComparisonOp::Eq => equal(),
         ComparisonOp::Gte => left >= right,
         ComparisonOp::Lte => left <= right,
-        ComparisonOp::Ne => left != right,
+        ComparisonOp::Ne => !equal(),
     }
 }
 
@@ -132,6 +140,7 @@ mod tests {
             left: SignalSelector::Signal(iron()),
             op: ComparisonOp::Gt,
             right: SignalSelector::Constant(fixed(40.0)),
+            epsilon: Fixed64::ZERO,
         };
         assert!(evaluate_condition(&cond, &signals));
 
@@ -139,6 +148,7 @@ mod tests {
             left: SignalSelector::Signal(iron()),
             op: ComparisonOp::Gt,
             right: SignalSelector::Constant(fixed(50.0)),
+            epsilon: Fixed64::ZERO,
         };
         assert!(!evaluate_condition(&cond, &signals));
 
@@ -147,6 +157,7 @@ mod tests {
             left: SignalSelector::Signal(iron()),
             op: ComparisonOp::Lt,
             right: SignalSelector::Constant(fixed(60.0)),
+            epsilon: Fixed64::ZERO,
         };
         assert!(evaluate_condition(&cond, &signals));
 
@@ -154,6 +165,7 @@ mod tests {
             left: SignalSelector::Signal(iron()),
             op: ComparisonOp::Lt,
             right: SignalSelector::Constant(fixed(50.0)),
+            epsilon: Fixed64::ZERO,
         };
         assert!(!evaluate_condition(&cond, &signals));
 
@@ -162,6 +174,7 @@ mod tests {
             left: SignalSelector::Signal(iron()),
             op: ComparisonOp::Eq,
             right: SignalSelector::Constant(fixed(50.0)),
+            epsilon: Fixed64::ZERO,
         };
         assert!(evaluate_condition(&cond, &signals));
 
@@ -169,6 +182,7 @@ mod tests {
             left: SignalSelector::Signal(iron()),
             op: ComparisonOp::Eq,
             right: SignalSelector::Constant(fixed(49.0)),
+            epsilon: Fixed64::ZERO,
         };
         assert!(!evaluate_condition(&cond, &signals));
 
@@ -177,6 +191,7 @@ mod tests {
             left: SignalSelector::Signal(iron()),
             op: ComparisonOp::Gte,
             right: SignalSelector::Constant(fixed(50.0)),
+            epsilon: Fixed64::ZERO,
         };
         assert!(evaluate_condition(&cond, &signals));
 
@@ -184,6 +199,7 @@ mod tests {
             left: SignalSelector::Signal(iron()),
             op: ComparisonOp::Gte,
             right: SignalSelector::Constant(fixed(51.0)),
+            epsilon: Fixed64::ZERO,
         };
         assert!(!evaluate_condition(&cond, &signals));
 
@@ -192,6 +208,7 @@ mod tests {
             left: SignalSelector::Signal(iron()),
             op: ComparisonOp::Lte,
             right: SignalSelector::Constant(fixed(50.0)),
+            epsilon: Fixed64::ZERO,
         };
         assert!(evaluate_condition(&cond, &signals));
 
@@ -199,6 +216,7 @@ mod tests {
             left: SignalSelector::Signal(iron()),
             op: ComparisonOp::Lte,
             right: SignalSelector::Constant(fixed(49.0)),
+            epsilon: Fixed64::ZERO,
         };
         assert!(!evaluate_condition(&cond, &signals));
 
@@ -207,6 +225,7 @@ mod tests {
             left: SignalSelector::Signal(iron()),
             op: ComparisonOp::Ne,
             right: SignalSelector::Constant(fixed(40.0)),
+            epsilon: Fixed64::ZERO,
         };
         assert!(evaluate_condition(&cond, &signals));
 
@@ -214,6 +233,7 @@ mod tests {
             left: SignalSelector::Signal(iron()),
             op: ComparisonOp::Ne,
             right: SignalSelector::Constant(fixed(50.0)),
+            epsilon: Fixed64::ZERO,
         };
         assert!(!evaluate_condition(&cond, &signals));
     }
@@ -227,6 +247,7 @@ mod tests {
                 left: SignalSelector::Signal(iron()),
                 op: ComparisonOp::Gt,
                 right: SignalSelector::Constant(fixed(50.0)),
+                epsilon: Fixed64::ZERO,
             },
             wire_color: WireColor::Red,
             active: false,
@@ -237,4 +258,63 @@ mod tests {
         update_circuit_control(&mut control, &signals);
         assert!(control.active);
     }
+
+    #[test]
+    fn epsilon_makes_equality_approximate() {
+        let one = fixed(1.0);
+        let nudged = Fixed64::from_bits(one.to_bits() + 1);
+        let signals = signals_with(&[(iron(), 0.0)]);
+        let compare = |op, epsilon| {
+            evaluate_condition(
+                &Condition {
+                    left: SignalSelector::Constant(one),
+                    op,
+                    right: SignalSelector::Constant(nudged),
+                    epsilon,
+                },
+                &signals,
+            )
+        };
+        let small = Fixed64::from_bits(4);
+
+        assert!(!compare(ComparisonOp::Eq, Fixed64::ZERO));
+        assert!(compare(ComparisonOp::Ne, Fixed64::ZERO));
+        assert!(compare(ComparisonOp::Eq, small));
+        assert!(!compare(ComparisonOp::Ne, small));
+        // A negative epsilon is exact.
+        assert!(!compare(ComparisonOp::Eq, -small));
+
+        // Ordering comparisons are unaffected.
+        for epsilon in [Fixed64::ZERO, small] {
+            assert!(compare(ComparisonOp::Lt, epsilon));
+            assert!(compare(ComparisonOp::Lte, epsilon));
+            assert!(!compare(ComparisonOp::Gt, epsilon));
+            assert!(!compare(ComparisonOp::Gte, epsilon));
+        }
+    }
+
+    #[test]
+    fn epsilon_equality_handles_extreme_values() {
+        let signals = SignalSet::new();
+        let cond = Condition {
+            left: SignalSelector::Constant(Fixed64::MAX),
+            op: ComparisonOp::Eq,
+            right: SignalSelector::Constant(Fixed64::MIN),
+            epsilon: Fixed64::from_num(1),
+        };
+        assert!(!evaluate_condition(&cond, &signals));
+    }
+
+    #[test]
+    fn epsilon_is_serialized_with_the_condition() {
+        let cond = Condition {
+            left: SignalSelector::Signal(iron()),
+            op: ComparisonOp::Eq,
+            right: SignalSelector::Constant(fixed(2.5)),
+            epsilon: Fixed64::from_bits(16),
+        };
+        let bytes = bitcode::serialize(&cond).unwrap();
+        let restored: Condition = bitcode::deserialize(&bytes).unwrap();
+        assert_eq!(restored.epsilon, cond.epsilon);
+    }
 }
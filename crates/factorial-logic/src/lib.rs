@@ -13,7 +13,7 @@ pub mod condition;
 
 pub use bridge::LogicModuleBridge;
 
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 
 use factorial_core::fixed::{Fixed64, Ticks};
 use factorial_core::id::{ItemTypeId, NodeId};
@@ -21,7 +21,7 @@ use factorial_core::item::Inventory;
 use serde::{Deserialize, Serialize};
 use slotmap::SecondaryMap;
 
-use combinator::{ArithmeticCombinator, DeciderCombinator};
+use combinator::{ArithmeticCombinator, CombinatorKind, DeciderCombinator};
 use condition::{CircuitControl, Condition, InventoryReader, InventorySource};
 
 // ---------------------------------------------------------------------------
@@ -92,6 +92,26 @@ pub enum LogicEvent {
     NetworkSignalsChanged { network: WireNetworkId, tick: Ticks },
 }
 
+// ---------------------------------------------------------------------------
+// Signal change report
+// ---------------------------------------------------------------------------
+
+/// One signal whose value on a network a node is wired to differs from the
+/// previous tick.
+///
+/// A signal that appeared has `old == 0`; one that disappeared has
+/// `new == 0`. A node normally sits on at most one network per color; if it
+/// sits on several, each contributes its own entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SignalChange {
+    pub node: NodeId,
+    pub wire: WireColor,
+    pub signal: ItemTypeId,
+    pub network: WireNetworkId,
+    pub old: Fixed64,
+    pub new: Fixed64,
+}
+
 // ---------------------------------------------------------------------------
 // Constant combinator
 // ---------------------------------------------------------------------------
@@ -103,6 +123,68 @@ pub struct ConstantCombinator {
     pub enabled: bool,
 }
 
+/// The constant combinators of a [`LogicModule`], keyed by node.
+///
+/// Dereferences to the underlying map for reads. Mutable access goes through
+/// the methods below, which remember the touched nodes so the next tick only
+/// recomputes the networks they sit on.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct ConstantMap {
+    map: BTreeMap<NodeId, ConstantCombinator>,
+    #[serde(skip)]
+    touched: BTreeSet<NodeId>,
+    #[serde(skip)]
+    touched_all: bool,
+}
+
+impl ConstantMap {
+    pub fn get_mut(&mut self, node: &NodeId) -> Option<&mut ConstantCombinator> {
+        self.touched.insert(*node);
+        self.map.get_mut(node)
+    }
+
+    pub fn insert(
+        &mut self,
+        node: NodeId,
+        constant: ConstantCombinator,
+    ) -> Option<ConstantCombinator> {
+        self.touched.insert(node);
+        self.map.insert(node, constant)
+    }
+
+    pub fn remove(&mut self, node: &NodeId) -> Option<ConstantCombinator> {
+        self.touched.insert(*node);
+        self.map.remove(node)
+    }
+
+    pub fn iter_mut(
+        &mut self,
+    ) -> std::collections::btree_map::IterMut<'_, NodeId, ConstantCombinator> {
+        self.touched_all = true;
+        self.map.iter_mut()
+    }
+
+    /// Drain the set of nodes touched since the last call. `None` means
+    /// every constant may have changed.
+    fn take_touched(&mut self) -> Option<BTreeSet<NodeId>> {
+        let touched = std::mem::take(&mut self.touched);
+        if std::mem::take(&mut self.touched_all) {
+            None
+        } else {
+            Some(touched)
+        }
+    }
+}
+
+impl std::ops::Deref for ConstantMap {
+    type Target = BTreeMap<NodeId, ConstantCombinator>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.map
+    }
+}
+
 // ---------------------------------------------------------------------------
 // Logic module
 // ---------------------------------------------------------------------------
@@ -114,8 +196,12 @@ pub struct LogicModule {
     pub networks: BTreeMap<WireNetworkId, WireNetwork>,
 
     // Per-node signal sources
-    pub constants: BTreeMap<NodeId, ConstantCombinator>,
+    pub constants: ConstantMap,
     pub inventory_readers: BTreeMap<NodeId, InventoryReader>,
+    /// Storage nodes that publish their own contents. Kept in sync with the
+    /// engine's processors by [`LogicModuleBridge`] before every tick.
+    #[serde(skip)]
+    pub storage_readers: BTreeSet<NodeId>,
     pub arithmetic_combinators: BTreeMap<NodeId, ArithmeticCombinator>,
     pub decider_combinators: BTreeMap<NodeId, DeciderCombinator>,
 
@@ -129,6 +215,24 @@ pub struct LogicModule {
     prev_signals: BTreeMap<WireNetworkId, SignalSet>,
 
     next_network_id: u32,
+
+    // Internal: networks whose merged signals must be recomputed next tick
+    // even if they have no volatile members.
+    #[serde(skip)]
+    dirty_networks: BTreeSet<WireNetworkId>,
+
+    // Internal: false until the first tick after construction, load, or
+    // `invalidate_signals`; forces every network to be recomputed.
+    #[serde(skip)]
+    synced: bool,
+
+    // Internal: report from the most recent tick.
+    #[serde(skip)]
+    changed_signals: Vec<SignalChange>,
+
+    // Internal: signal entries compared while diffing in the last tick.
+    #[serde(skip)]
+    signals_compared: u32,
 }
 
 impl Default for LogicModule {
@@ -141,14 +245,19 @@ impl LogicModule {
     pub fn new() -> Self {
         Self {
             networks: BTreeMap::new(),
-            constants: BTreeMap::new(),
+            constants: ConstantMap::default(),
             inventory_readers: BTreeMap::new(),
+            storage_readers: BTreeSet::new(),
             arithmetic_combinators: BTreeMap::new(),
             decider_combinators: BTreeMap::new(),
             circuit_controls: BTreeMap::new(),
             combinator_outputs: BTreeMap::new(),
             prev_signals: BTreeMap::new(),
             next_network_id: 0,
+            dirty_networks: BTreeSet::new(),
+            synced: false,
+            changed_signals: Vec::new(),
+            signals_compared: 0,
         }
     }
 
@@ -158,23 +267,45 @@ impl LogicModule {
         let id = WireNetworkId(self.next_network_id);
         self.next_network_id += 1;
         self.networks.insert(id, WireNetwork::new(id, color));
+        self.dirty_networks.insert(id);
         id
     }
 
     pub fn remove_network(&mut self, id: WireNetworkId) {
         self.networks.remove(&id);
         self.prev_signals.remove(&id);
+        self.dirty_networks.remove(&id);
     }
 
     pub fn add_to_network(&mut self, network: WireNetworkId, node: NodeId) {
         if let Some(net) = self.networks.get_mut(&network) {
             net.add_member(node);
+            self.dirty_networks.insert(network);
         }
     }
 
     pub fn remove_from_network(&mut self, network: WireNetworkId, node: NodeId) {
         if let Some(net) = self.networks.get_mut(&network) {
             net.remove_member(node);
+            self.dirty_networks.insert(network);
+        }
+    }
+
+    /// Force every network to be recomputed and diffed on the next tick.
+    ///
+    /// Only needed after adding or removing entries of the public reader or
+    /// combinator maps directly; the setters and [`ConstantMap`] track which
+    /// networks they affect.
+    pub fn invalidate_signals(&mut self) {
+        self.synced = false;
+    }
+
+    /// Mark every network containing `node` for recomputation.
+    fn mark_node_dirty(&mut self, node: NodeId) {
+        for (&id, network) in &self.networks {
+            if network.members.contains(&node) {
+                self.dirty_networks.insert(id);
+            }
         }
     }
 
@@ -188,6 +319,60 @@ impl LogicModule {
         self.circuit_controls.get(&node).map(|c| c.active)
     }
 
+    /// The combinators on `node`, in [`CombinatorKind`] order. The
+    /// combinator maps are keyed by node, so this is three map lookups.
+    pub fn combinators_at(&self, node: NodeId) -> Vec<CombinatorKind> {
+        let mut kinds = Vec::new();
+        if self.constants.contains_key(&node) {
+            kinds.push(CombinatorKind::Constant);
+        }
+        if self.arithmetic_combinators.contains_key(&node) {
+            kinds.push(CombinatorKind::Arithmetic);
+        }
+        if self.decider_combinators.contains_key(&node) {
+            kinds.push(CombinatorKind::Decider);
+        }
+        kinds
+    }
+
+    /// Every combinator as `(node, kind)`, ordered by node, then kind.
+    pub fn combinators(&self) -> Vec<(NodeId, CombinatorKind)> {
+        let mut all: Vec<(NodeId, CombinatorKind)> = self
+            .constants
+            .keys()
+            .map(|&node| (node, CombinatorKind::Constant))
+            .chain(
+                self.arithmetic_combinators
+                    .keys()
+                    .map(|&node| (node, CombinatorKind::Arithmetic)),
+            )
+            .chain(
+                self.decider_combinators
+                    .keys()
+                    .map(|&node| (node, CombinatorKind::Decider)),
+            )
+            .collect();
+        all.sort_unstable();
+        all
+    }
+
+    /// Signals whose value on some node's wire changed during the most
+    /// recent tick, ordered by `(node, wire, signal, network)`.
+    ///
+    /// Cleared at the start of every tick. Only networks whose inputs may
+    /// have changed (topology edits, constant edits, inventory readers and
+    /// combinators) are diffed, so the cost follows the number of changes
+    /// rather than the total number of signals.
+    pub fn changed_signals(&self) -> &[SignalChange] {
+        &self.changed_signals
+    }
+
+    /// Number of signal entries compared while building the most recent
+    /// [`changed_signals`](Self::changed_signals) report.
+    pub fn signals_compared(&self) -> u32 {
+        self.signals_compared
+    }
+
     // --- Signal sources ---
 
     pub fn set_constant(&mut self, node: NodeId, signals: SignalSet, enabled: bool) {
@@ -203,14 +388,34 @@ impl LogicModule {
                 source,
             },
         );
+        self.mark_node_dirty(node);
+    }
+
+    /// Replace the set of storage nodes that publish their output inventory
+    /// as signals. Networks of nodes that join or leave the set are
+    /// recomputed on the next tick.
+    pub fn set_storage_readers(&mut self, nodes: BTreeSet<NodeId>) {
+        if nodes == self.storage_readers {
+            return;
+        }
+        let changed: Vec<NodeId> = nodes
+            .symmetric_difference(&self.storage_readers)
+            .copied()
+            .collect();
+        for node in changed {
+            self.mark_node_dirty(node);
+        }
+        self.storage_readers = nodes;
     }
 
     pub fn set_arithmetic(&mut self, node: NodeId, combinator: ArithmeticCombinator) {
         self.arithmetic_combinators.insert(node, combinator);
+        self.mark_node_dirty(node);
     }
 
     pub fn set_decider(&mut self, node: NodeId, combinator: DeciderCombinator) {
         self.decider_combinators.insert(node, combinator);
+        self.mark_node_dirty(node);
     }
 
     // --- Signal consumption ---
@@ -255,9 +460,42 @@ impl LogicModule {
 
     // --- Cleanup ---
 
+    /// Remove the `kind` combinator from `node`, leaving the node's other
+    /// logic state in place. Returns `false` if there was none.
+    pub fn remove_combinator(&mut self, node: NodeId, kind: CombinatorKind) -> bool {
+        let removed = match kind {
+            CombinatorKind::Constant => self.constants.remove(&node).is_some(),
+            CombinatorKind::Arithmetic => self.arithmetic_combinators.remove(&node).is_some(),
+            CombinatorKind::Decider => self.decider_combinators.remove(&node).is_some(),
+        };
+        if removed {
+            if !self.arithmetic_combinators.contains_key(&node)
+                && !self.decider_combinators.contains_key(&node)
+            {
+                self.combinator_outputs.remove(&node);
+            }
+            self.mark_node_dirty(node);
+        }
+        removed
+    }
+
+    /// Remove every piece of logic state for `node`, including inventory
+    /// readers on other nodes that target it.
     pub fn remove_node(&mut self, node: NodeId) {
+        self.mark_node_dirty(node);
         self.constants.remove(&node);
         self.inventory_readers.remove(&node);
+        self.storage_readers.remove(&node);
+        let orphaned_readers: Vec<NodeId> = self
+            .inventory_readers
+            .iter()
+            .filter(|(_, reader)| reader.target_node == node)
+            .map(|(&reader, _)| reader)
+            .collect();
+        for reader in orphaned_readers {
+            self.inventory_readers.remove(&reader);
+            self.mark_node_dirty(reader);
+        }
         self.arithmetic_combinators.remove(&node);
         self.decider_combinators.remove(&node);
         self.circuit_controls.remove(&node);
@@ -267,10 +505,23 @@ impl LogicModule {
         }
     }
 
+    /// Whether `node`'s contribution to its networks can change without a
+    /// setter being called (inventory and storage readers, and combinator
+    /// outputs).
+    fn is_volatile(&self, node: NodeId) -> bool {
+        self.inventory_readers.contains_key(&node)
+            || self.storage_readers.contains(&node)
+            || self.combinator_outputs.contains_key(&node)
+            || self.arithmetic_combinators.contains_key(&node)
+            || self.decider_combinators.contains_key(&node)
+    }
+
     /// Advance all logic networks by one tick.
     ///
-    /// 1. Collect signals from constants, inventory readers, last-tick combinator outputs
-    /// 2. Merge signals per network
+    /// 1. Collect signals from constants, inventory and storage readers,
+    ///    last-tick combinator outputs
+    /// 2. Merge signals per network, diffing against last tick (only networks
+    ///    whose inputs may have changed are visited)
     /// 3. Evaluate combinators, store outputs for next tick
     /// 4. Evaluate circuit controls
     /// 5. Emit transition events
@@ -282,9 +533,40 @@ impl LogicModule {
     ) -> Vec<LogicEvent> {
         let zero = Fixed64::from_num(0);
         let mut events = Vec::new();
+        self.changed_signals.clear();
+        self.signals_compared = 0;
+
+        // --- Step 1 & 2: Collect and merge signals per dirty network ---
+        match self.constants.take_touched() {
+            Some(touched) => {
+                for node in touched {
+                    self.mark_node_dirty(node);
+                }
+            }
+            None => self.synced = false,
+        }
+        let mut dirty = std::mem::take(&mut self.dirty_networks);
+        if !self.synced {
+            dirty.extend(self.networks.keys().copied());
+            self.synced = true;
+        } else if !self.inventory_readers.is_empty()
+            || !self.storage_readers.is_empty()
+            || !self.combinator_outputs.is_empty()
+            || !self.arithmetic_combinators.is_empty()
+            || !self.decider_combinators.is_empty()
+        {
+            for (&id, network) in &self.networks {
+                if network.members.iter().any(|&n| self.is_volatile(n)) {
+                    dirty.insert(id);
+                }
+            }
+        }
 
-        // --- Step 1 & 2: Collect and merge signals per network ---
-        for network in self.networks.values_mut() {
+        let mut changed_networks = Vec::new();
+        for &id in &dirty {
+            let Some(network) = self.networks.get(&id) else {
+                continue;
+            };
             let mut merged = SignalSet::new();
 
             for &node in &network.members {
@@ -319,6 +601,18 @@ impl LogicModule {
                     }
                 }
 
+                // Storage node publishing its own contents
+                if self.storage_readers.contains(&node)
+                    && let Some(inv) = outputs.get(node)
+                {
+                    for stack in inv.output_slots.iter().flat_map(|slot| &slot.stacks) {
+                        if stack.quantity > 0 {
+                            *merged.entry(stack.item_type).or_insert(zero) +=
+                                Fixed64::from_num(stack.quantity);
+                        }
+                    }
+                }
+
                 // Combinator outputs from last tick (one-tick delay)
                 if let Some(prev_output) = self.combinator_outputs.get(&node) {
                     for (&item, &value) in prev_output {
@@ -327,19 +621,47 @@ impl LogicModule {
                 }
             }
 
-            network.signals = merged;
+            let empty = SignalSet::new();
+            let prev = self.prev_signals.get(&id).unwrap_or(&empty);
+            let (diff, compared) = diff_signals(prev, &merged);
+            self.signals_compared += compared;
+            if !diff.is_empty() {
+                for &node in &network.members {
+                    for &(signal, old, new) in &diff {
+                        self.changed_signals.push(SignalChange {
+                            node,
+                            wire: network.color,
+                            signal,
+                            network: id,
+                            old,
+                            new,
+                        });
+                    }
+                }
+                changed_networks.push(id);
+            }
+
+            // Store current signals for next tick's change detection.
+            self.prev_signals.insert(id, merged.clone());
+            if let Some(network) = self.networks.get_mut(&id) {
+                network.signals = merged;
+            }
         }
+        self.changed_signals
+            .sort_by_key(|c| (c.node, c.wire, c.signal, c.network));
 
         // --- Step 3: Evaluate combinators, store new outputs for next tick ---
         let mut new_combinator_outputs: BTreeMap<NodeId, SignalSet> = BTreeMap::new();
 
         let node_to_network_signals: BTreeMap<NodeId, SignalSet> = {
             let mut map = BTreeMap::new();
-            for network in self.networks.values() {
-                for &node in &network.members {
-                    let entry = map.entry(node).or_insert_with(SignalSet::new);
-                    for (&item, &value) in &network.signals {
-                        *entry.entry(item).or_insert(zero) += value;
+            if !self.arithmetic_combinators.is_empty() || !self.decider_combinators.is_empty() {
+                for network in self.networks.values() {
+                    for &node in &network.members {
+                        let entry = map.entry(node).or_insert_with(SignalSet::new);
+                        for (&item, &value) in &network.signals {
+                            *entry.entry(item).or_insert(zero) += value;
+                        }
                     }
                 }
             }
@@ -393,30 +715,70 @@ impl LogicModule {
         }
 
         // NetworkSignalsChanged events
-        for (id, network) in &self.networks {
-            let changed = match self.prev_signals.get(id) {
-                Some(prev) => prev != &network.signals,
-                None => !network.signals.is_empty(),
-            };
-            if changed {
-                events.push(LogicEvent::NetworkSignalsChanged {
-                    network: *id,
-                    tick: current_tick,
-                });
-            }
+        for network in changed_networks {
+            events.push(LogicEvent::NetworkSignalsChanged {
+                network,
+                tick: current_tick,
+            });
         }
 
-        // Store current signals for next tick's change detection.
-        self.prev_signals = self
-            .networks
-            .iter()
-            .map(|(&id, net)| (id, net.signals.clone()))
-            .collect();
-
         events
     }
 }
 
+/// Merge-walk two sorted signal sets, returning every `(signal, old, new)`
+/// whose value differs and the number of entries visited.
+fn diff_signals(prev: &SignalSet, next: &SignalSet) -> (Vec<(ItemTypeId, Fixed64, Fixed64)>, u32) {
+    let zero = Fixed64::from_num(0);
+    let mut changes = Vec::new();
+    let mut compared = 0u32;
+    let mut a = prev.iter().peekable();
+    let mut b = next.iter().peekable();
+    loop {
+        compared += 1;
+        match (a.peek(), b.peek()) {
+            (Some(&(&ka, &va)), Some(&(&kb, &vb))) => match ka.cmp(&kb) {
+                std::cmp::Ordering::Less => {
+                    if va != zero {
+                        changes.push((ka, va, zero));
+                    }
+                    a.next();
+                }
+                std::cmp::Ordering::Greater => {
+                    if vb != zero {
+                        changes.push((kb, zero, vb));
+                    }
+                    b.next();
+                }
+                std::cmp::Ordering::Equal => {
+                    if va != vb {
+                        changes.push((ka, va, vb));
+                    }
+                    a.next();
+                    b.next();
+                }
+            },
+            (Some(&(&ka, &va)), None) => {
+                if va != zero {
+                    changes.push((ka, va, zero));
+                }
+                a.next();
+            }
+            (None, Some(&(&kb, &vb))) => {
+                if vb != zero {
+                    changes.push((kb, zero, vb));
+                }
+                b.next();
+            }
+            (None, None) => {
+                compared -= 1;
+                break;
+            }
+        }
+    }
+    (changes, compared)
+}
+
 // ===========================================================================
 // Tests
 // ===========================================================================
@@ -550,6 +912,7 @@ mod tests {
                 left: SignalSelector::Signal(ItemTypeId(0)),
                 op: ComparisonOp::Gt,
                 right: SignalSelector::Constant(fixed(50.0)),
+                epsilon: Fixed64::ZERO,
             },
             WireColor::Red,
         );
@@ -586,6 +949,7 @@ mod tests {
                     left: SignalSelector::Constant(fixed(1.0)),
                     op: ComparisonOp::Gt,
                     right: SignalSelector::Constant(fixed(0.0)),
+                    epsilon: Fixed64::ZERO,
                 },
                 output: DeciderOutput::One(ItemTypeId(0)),
             },
@@ -596,6 +960,7 @@ mod tests {
                 left: SignalSelector::Constant(fixed(1.0)),
                 op: ComparisonOp::Gt,
                 right: SignalSelector::Constant(fixed(0.0)),
+                epsilon: Fixed64::ZERO,
             },
             WireColor::Red,
         );
@@ -613,6 +978,19 @@ mod tests {
         assert!(module.is_active(nodes[0]).is_none());
     }
 
+    #[test]
+    fn remove_node_drops_readers_targeting_it() {
+        let mut module = LogicModule::new();
+        let nodes = make_node_ids(3);
+        module.set_inventory_reader(nodes[0], nodes[1], InventorySource::Output);
+        module.set_inventory_reader(nodes[2], nodes[0], InventorySource::Input);
+
+        module.remove_node(nodes[1]);
+
+        assert!(!module.inventory_readers.contains_key(&nodes[0]));
+        assert!(module.inventory_readers.contains_key(&nodes[2]));
+    }
+
     // -----------------------------------------------------------------------
     // Task 6: Tick pipeline
     // -----------------------------------------------------------------------
@@ -809,6 +1187,7 @@ mod tests {
                 left: SignalSelector::Signal(iron),
                 op: ComparisonOp::Gt,
                 right: SignalSelector::Constant(fixed(50.0)),
+                epsilon: Fixed64::ZERO,
             },
             WireColor::Red,
         );
@@ -838,6 +1217,7 @@ mod tests {
                 left: SignalSelector::Signal(iron),
                 op: ComparisonOp::Gt,
                 right: SignalSelector::Constant(fixed(50.0)),
+                epsilon: Fixed64::ZERO,
             },
             WireColor::Red,
         );
@@ -907,6 +1287,7 @@ mod tests {
                 left: SignalSelector::Signal(iron),
                 op: ComparisonOp::Gt,
                 right: SignalSelector::Constant(fixed(50.0)),
+                epsilon: Fixed64::ZERO,
             },
             WireColor::Red,
         );
@@ -964,6 +1345,7 @@ mod tests {
                 left: SignalSelector::Signal(iron),
                 op: ComparisonOp::Gt,
                 right: SignalSelector::Constant(fixed(50.0)),
+                epsilon: Fixed64::ZERO,
             },
             WireColor::Red,
         );
@@ -1001,4 +1383,159 @@ mod tests {
             module.network_signals(net).unwrap().get(&iron),
         );
     }
+
+    #[test]
+    fn combinators_are_found_by_node_across_saves_and_removal() {
+        let mut module = LogicModule::new();
+        let nodes = make_node_ids(2);
+        let iron = ItemTypeId(0);
+        let net = module.create_network(WireColor::Red);
+        module.add_to_network(net, nodes[1]);
+        module.set_arithmetic(
+            nodes[1],
+            ArithmeticCombinator {
+                left: SignalSelector::Signal(iron),
+                op: ArithmeticOp::Add,
+                right: SignalSelector::Constant(fixed(1.0)),
+                output: iron,
+            },
+        );
+        module.set_decider(
+            nodes[1],
+            DeciderCombinator {
+                condition: Condition {
+                    left: SignalSelector::Signal(iron),
+                    op: ComparisonOp::Gt,
+                    right: SignalSelector::Constant(fixed(0.0)),
+                    epsilon: Fixed64::ZERO,
+                },
+                output: DeciderOutput::One(iron),
+            },
+        );
+        module.set_constant(nodes[0], SignalSet::new(), true);
+
+        let both = vec![CombinatorKind::Arithmetic, CombinatorKind::Decider];
+        assert_eq!(module.combinators_at(nodes[1]), both);
+        assert_eq!(
+            module.combinators_at(nodes[0]),
+            vec![CombinatorKind::Constant]
+        );
+        let mut expected = vec![
+            (nodes[0], CombinatorKind::Constant),
+            (nodes[1], CombinatorKind::Arithmetic),
+            (nodes[1], CombinatorKind::Decider),
+        ];
+        expected.sort_unstable();
+        assert_eq!(module.combinators(), expected);
+
+        let data = bitcode::serialize(&module).expect("serialize");
+        let mut restored: LogicModule = bitcode::deserialize(&data).expect("deserialize");
+        assert_eq!(restored.combinators_at(nodes[1]), both);
+        assert_eq!(restored.combinators(), expected);
+
+        assert!(restored.remove_combinator(nodes[1], CombinatorKind::Arithmetic));
+        assert!(!restored.remove_combinator(nodes[1], CombinatorKind::Arithmetic));
+        assert_eq!(
+            restored.combinators_at(nodes[1]),
+            vec![CombinatorKind::Decider]
+        );
+        assert!(restored.networks[&net].members.contains(&nodes[1]));
+        restored.remove_node(nodes[1]);
+        assert!(restored.combinators_at(nodes[1]).is_empty());
+        assert_eq!(restored.combinators().len(), 1);
+    }
+
+    #[test]
+    fn changed_signals_are_ordered_by_node_wire_signal() {
+        let mut module = LogicModule::new();
+        let nodes = make_node_ids(3);
+        let iron = ItemTypeId(0);
+        let copper = ItemTypeId(1);
+        let red_a = module.create_network(WireColor::Red);
+        let red_b = module.create_network(WireColor::Red);
+        let green = module.create_network(WireColor::Green);
+        module.add_to_network(red_a, nodes[0]);
+        module.add_to_network(red_b, nodes[1]);
+        module.add_to_network(green, nodes[1]);
+        module.add_to_network(green, nodes[2]);
+
+        let mut a = SignalSet::new();
+        a.insert(copper, fixed(1.0));
+        a.insert(iron, fixed(3.0));
+        module.set_constant(nodes[0], a, true);
+        let mut b = SignalSet::new();
+        b.insert(iron, fixed(4.0));
+        module.set_constant(nodes[1], b, true);
+
+        let (inputs, outputs) = make_inventories();
+        module.tick(&inputs, &outputs, 1);
+        let report: Vec<_> = module
+            .changed_signals()
+            .iter()
+            .map(|c| (c.node, c.wire, c.signal, c.new))
+            .collect();
+        assert_eq!(
+            report,
+            vec![
+                (nodes[0], WireColor::Red, iron, fixed(3.0)),
+                (nodes[0], WireColor::Red, copper, fixed(1.0)),
+                (nodes[1], WireColor::Red, iron, fixed(4.0)),
+                (nodes[1], WireColor::Green, iron, fixed(4.0)),
+                (nodes[2], WireColor::Green, iron, fixed(4.0)),
+            ]
+        );
+
+        // Disabling a constant reports its signals dropping to zero.
+        module.constants.get_mut(&nodes[1]).unwrap().enabled = false;
+        module.tick(&inputs, &outputs, 2);
+        let report: Vec<_> = module
+            .changed_signals()
+            .iter()
+            .map(|c| (c.node, c.network, c.old, c.new))
+            .collect();
+        assert_eq!(
+            report,
+            vec![
+                (nodes[1], red_b, fixed(4.0), fixed(0.0)),
+                (nodes[1], green, fixed(4.0), fixed(0.0)),
+                (nodes[2], green, fixed(4.0), fixed(0.0)),
+            ]
+        );
+
+        module.tick(&inputs, &outputs, 3);
+        assert!(module.changed_signals().is_empty());
+        assert_eq!(module.signals_compared(), 0);
+    }
+
+    #[test]
+    fn changed_signals_track_volatile_sources() {
+        let mut module = LogicModule::new();
+        let nodes = make_node_ids(2);
+        let iron = ItemTypeId(0);
+        let net = module.create_network(WireColor::Red);
+        module.add_to_network(net, nodes[0]);
+        module.set_inventory_reader(nodes[0], nodes[1], InventorySource::Output);
+
+        let empty = SecondaryMap::new();
+        let full = make_output_inventory_with(nodes[1], iron, 10);
+        module.tick(&empty, &full, 1);
+        assert_eq!(module.changed_signals().len(), 1);
+
+        // Same inventory: the reader's network is re-read but nothing changed.
+        module.tick(&empty, &full, 2);
+        assert!(module.changed_signals().is_empty());
+
+        module.tick(&empty, &empty, 3);
+        assert_eq!(
+            module.changed_signals(),
+            &[SignalChange {
+                node: nodes[0],
+                wire: WireColor::Red,
+                network: net,
+                signal: iron,
+                old: fixed(10.0),
+                new: fixed(0.0),
+            }]
+        );
+    }
 }
@@ -400,7 +400,7 @@ impl PowerModule {
                 .collect();
             // Sort by priority descending (High > Medium > Low).
             // Ord for PowerPriority: Low < Medium < High, so reverse.
-            consumer_entries.sort_by(|a, b| b.2.cmp(&a.2));
+            consumer_entries.sort_by_key(|e| std::cmp::Reverse(e.2));
 
             // Collect storage node IDs for this network so we can mutate storage.
             let storage_nodes: Vec<NodeId> = network.storage.clone();
@@ -691,6 +691,7 @@ impl Default for Blueprint {
 mod tests {
     use super::*;
     use factorial_core::engine::Engine;
+    use factorial_core::graph::NodeStatus;
     use factorial_core::serialize::SnapshotRingBuffer;
     use factorial_core::sim::SimulationStrategy;
     use factorial_core::test_utils::{building, iron, make_flow_transport, make_source};
@@ -1418,6 +1419,49 @@ mod tests {
         assert_eq!(bp.connections().len(), 1);
     }
 
+    #[test]
+    fn capture_region_includes_ghost_nodes() {
+        let (mut engine, mut spatial) = setup_engine_and_spatial();
+
+        let p1 = engine.graph.queue_add_node(building());
+        let p2 = engine.graph.queue_add_node(building());
+        let r = engine.graph.apply_mutations();
+        let n1 = r.resolve_node(p1).unwrap();
+        let n2 = r.resolve_node(p2).unwrap();
+
+        engine.set_processor(n1, make_source(iron(), 1.0));
+        engine.set_input_inventory(n1, Inventory::new(1, 1, 100));
+        engine.set_output_inventory(n1, Inventory::new(1, 1, 100));
+        engine.set_processor(n2, make_source(iron(), 1.0));
+        engine.set_input_inventory(n2, Inventory::new(1, 1, 100));
+        engine.set_output_inventory(n2, Inventory::new(1, 1, 100));
+
+        spatial
+            .place(n1, GridPosition::new(0, 0), BuildingFootprint::single())
+            .unwrap();
+        spatial
+            .place(n2, GridPosition::new(1, 0), BuildingFootprint::single())
+            .unwrap();
+
+        let pe = engine.graph.queue_connect(n1, n2);
+        let er = engine.graph.apply_mutations();
+        let eid = er.resolve_edge(pe).unwrap();
+        engine.set_transport(eid, make_flow_transport(1.0));
+
+        engine.set_node_status(n2, NodeStatus::Ghost);
+        engine.set_edge_status(eid, NodeStatus::Ghost);
+
+        let bp = Blueprint::capture_region(
+            &engine,
+            &spatial,
+            GridPosition::new(0, 0),
+            GridPosition::new(5, 5),
+            GridPosition::new(0, 0),
+        );
+        assert_eq!(bp.len(), 2);
+        assert_eq!(bp.connections().len(), 1);
+    }
+
     #[test]
     fn capture_region_excludes_outside() {
         let (mut engine, mut spatial) = setup_engine_and_spatial();
@@ -5,6 +5,15 @@
 //! `BuildingStalled`, `BuildingResumed`, `ItemDelivered`, `TransportFull`)
 //! and aggregates them into rolling metrics using [`Fixed64`] arithmetic.
 //!
+//! Nodes and edges are tracked from the moment they are registered, either
+//! by a `NodeAdded`/`EdgeAdded` event or an explicit
+//! [`register_node`](ProductionStats::register_node) /
+//! [`register_edge`](ProductionStats::register_edge) call, so ticks spent
+//! idle before the first activity count towards ratios. Entities that are
+//! never registered start counting at their first tracked event.
+//! [`initialize`](ProductionStats::initialize) registers everything an engine
+//! already has, which is how a tracker attached to a loaded engine starts.
+//!
 //! # Usage
 //!
 //! ```ignore
@@ -16,19 +25,37 @@
 //! // Query metrics:
 //! let rate = stats.get_production_rate(node, item_type);
 //! ```
+//!
+//! Every map is ordered by id, so anything that walks the tracked nodes,
+//! edges or item types -- the end-of-tick history snapshot,
+//! [`export_all_history`](ProductionStats::export_all_history) -- does so in
+//! the same order on every run, whatever order the entities were first seen
+//! in.
+//!
+//! Nodes flagged [`NodeFlags::NO_STATS`] are left out: `initialize` marks
+//! them ignored, and every event naming an ignored node is dropped, so they
+//! appear in no per-node or global metric. A flagged node added after
+//! `initialize` is picked up by calling it again, or by
+//! [`ignore_node`](ProductionStats::ignore_node).
+//!
+//! Statistics can also be rebuilt offline from a recorded [`EventLog`]
+//! with [`ProductionStats::from_event_log`], without re-simulating.
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet};
 
-use factorial_core::event::Event;
+use factorial_core::engine::Engine;
+use factorial_core::event::{Event, EventLog};
 use factorial_core::fixed::{Fixed64, Ticks};
-use factorial_core::id::{EdgeId, ItemTypeId, NodeId};
+use factorial_core::graph::NodeFlags;
+use factorial_core::id::{EdgeGroupId, EdgeId, ItemTypeId, NodeId};
+use serde::{Deserialize, Serialize};
 
 // ---------------------------------------------------------------------------
 // Configuration
 // ---------------------------------------------------------------------------
 
 /// Configuration for the statistics module.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct StatsConfig {
     /// Window size in ticks for rolling averages (e.g., 60 ticks).
     pub window_size: Ticks,
@@ -273,9 +300,9 @@ impl RollingWindow {
 #[derive(Debug, Clone)]
 struct NodeStats {
     /// Rolling production counts keyed by item type.
-    production: HashMap<ItemTypeId, RollingWindow>,
+    production: BTreeMap<ItemTypeId, RollingWindow>,
     /// Rolling consumption counts keyed by item type.
-    consumption: HashMap<ItemTypeId, RollingWindow>,
+    consumption: BTreeMap<ItemTypeId, RollingWindow>,
     /// Number of ticks this node has been idle (within the window).
     idle_ticks: RollingWindow,
     /// Number of ticks this node has been stalled (within the window).
@@ -283,7 +310,7 @@ struct NodeStats {
     /// Number of ticks this node has been working (within the window).
     working_ticks: RollingWindow,
     /// Historical production rate snapshots.
-    production_history: HashMap<ItemTypeId, RingBuffer>,
+    production_history: BTreeMap<ItemTypeId, RingBuffer>,
     /// Current state for this tick (set by events, reset each tick).
     current_state: NodeState,
     /// Window size for creating new rolling windows.
@@ -304,12 +331,12 @@ enum NodeState {
 impl NodeStats {
     fn new(window_size: usize, history_capacity: usize) -> Self {
         Self {
-            production: HashMap::new(),
-            consumption: HashMap::new(),
+            production: BTreeMap::new(),
+            consumption: BTreeMap::new(),
             idle_ticks: RollingWindow::new(window_size),
             stall_ticks: RollingWindow::new(window_size),
             working_ticks: RollingWindow::new(window_size),
-            production_history: HashMap::new(),
+            production_history: BTreeMap::new(),
             current_state: NodeState::default(),
             window_size,
             history_capacity,
@@ -528,6 +555,16 @@ impl GlobalItemStats {
 // ProductionStats — main module struct
 // ---------------------------------------------------------------------------
 
+/// Identifies one series returned by
+/// [`ProductionStats::export_all_history`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum HistorySeries {
+    /// A node's production rate of one item type.
+    Production { node: NodeId, item_type: ItemTypeId },
+    /// An edge's delivery throughput.
+    Throughput { edge: EdgeId },
+}
+
 /// Main production statistics aggregator.
 ///
 /// Accepts events via [`process_event`](ProductionStats::process_event), advances
@@ -538,9 +575,11 @@ impl GlobalItemStats {
 #[derive(Debug)]
 pub struct ProductionStats {
     config: StatsConfig,
-    nodes: HashMap<NodeId, NodeStats>,
-    edges: HashMap<EdgeId, EdgeStats>,
-    global: HashMap<ItemTypeId, GlobalItemStats>,
+    nodes: BTreeMap<NodeId, NodeStats>,
+    edges: BTreeMap<EdgeId, EdgeStats>,
+    global: BTreeMap<ItemTypeId, GlobalItemStats>,
+    /// Nodes whose events are dropped.
+    ignored_nodes: BTreeSet<NodeId>,
     /// Current tick (set by end_tick).
     current_tick: Ticks,
 }
@@ -550,9 +589,10 @@ impl ProductionStats {
     pub fn new(config: StatsConfig) -> Self {
         Self {
             config,
-            nodes: HashMap::new(),
-            edges: HashMap::new(),
-            global: HashMap::new(),
+            nodes: BTreeMap::new(),
+            edges: BTreeMap::new(),
+            global: BTreeMap::new(),
+            ignored_nodes: BTreeSet::new(),
             current_tick: 0,
         }
     }
@@ -574,6 +614,12 @@ impl ProductionStats {
     /// Call this for each event in a tick, then call [`end_tick`](Self::end_tick)
     /// to finalize the tick and advance rolling windows.
     pub fn process_event(&mut self, event: &Event) {
+        if let Some(node) = event.node().filter(|n| self.ignored_nodes.contains(n)) {
+            if matches!(event, Event::NodeRemoved { .. }) {
+                self.ignored_nodes.remove(&node);
+            }
+            return;
+        }
         match event {
             Event::ItemProduced {
                 node,
@@ -617,6 +663,14 @@ impl ProductionStats {
                 self.get_or_create_edge(*edge).record_full();
             }
 
+            Event::NodeAdded { node, tick, .. } => self.register_node(*node, *tick),
+            Event::EdgeAdded { edge, tick, .. } => self.register_edge(*edge, *tick),
+
+            // Removed entities drop their statistics so queries never
+            // return data for a dead id.
+            Event::NodeRemoved { node, .. } => self.remove_node(*node),
+            Event::EdgeRemoved { edge, .. } => self.remove_edge(*edge),
+
             // Other events are not tracked by the stats module.
             _ => {}
         }
@@ -640,6 +694,107 @@ impl ProductionStats {
         }
     }
 
+    // -- Registration -------------------------------------------------------
+
+    /// Start tracking `node` as of `tick`, so idle ticks before its first
+    /// production event count towards its ratios. `tick` uses the same
+    /// numbering as [`end_tick`](Self::end_tick); ticks that already ended
+    /// since then are filled in as idle, up to the window size. Does nothing
+    /// if the node is already tracked.
+    pub fn register_node(&mut self, node: NodeId, tick: Ticks) {
+        if self.nodes.contains_key(&node) {
+            return;
+        }
+        let missed = self.missed_ticks(tick);
+        let node_stats = self.get_or_create_node(node);
+        for _ in 0..missed {
+            node_stats.end_tick();
+        }
+    }
+
+    /// Start tracking `edge` as of `tick`, so idle ticks before its first
+    /// delivery count towards its utilization. Ticks that already ended since
+    /// `tick` are filled in as idle, up to the window size. Does nothing if
+    /// the edge is already tracked.
+    pub fn register_edge(&mut self, edge: EdgeId, tick: Ticks) {
+        if self.edges.contains_key(&edge) {
+            return;
+        }
+        let missed = self.missed_ticks(tick);
+        let edge_stats = self.get_or_create_edge(edge);
+        for _ in 0..missed {
+            edge_stats.end_tick();
+        }
+    }
+
+    /// Register every node and edge of `engine` as of its current tick, and
+    /// ignore every node flagged [`NodeFlags::NO_STATS`].
+    ///
+    /// A loaded engine emits no `NodeAdded`/`EdgeAdded` events for the
+    /// entities it already has; call this after loading, or alongside
+    /// [`Engine::initialize`], so their idle ticks count from the start.
+    pub fn initialize(&mut self, engine: &Engine) {
+        let tick = engine.sim_state.tick;
+        for (node, data) in engine.graph.nodes() {
+            if data.flags.contains(NodeFlags::NO_STATS) {
+                self.ignore_node(node);
+            } else {
+                self.register_node(node, tick);
+            }
+        }
+        for (edge, _) in engine.graph.edges() {
+            self.register_edge(edge, tick);
+        }
+    }
+
+    /// Stop tracking `node` and drop its statistics; its later events are
+    /// ignored until its `NodeRemoved`. Totals it already added to the
+    /// global metrics stay.
+    pub fn ignore_node(&mut self, node: NodeId) {
+        self.nodes.remove(&node);
+        self.ignored_nodes.insert(node);
+    }
+
+    /// Whether events naming `node` are ignored.
+    pub fn is_ignored(&self, node: NodeId) -> bool {
+        self.ignored_nodes.contains(&node)
+    }
+
+    /// Number of already-ended ticks an entity registered at `tick` has
+    /// missed, capped at the longest span any window or history retains.
+    fn missed_ticks(&self, tick: Ticks) -> u64 {
+        let retained = self
+            .config
+            .window_size
+            .max(self.config.history_capacity as Ticks);
+        self.current_tick.saturating_sub(tick).min(retained)
+    }
+
+    // -- Event log replay ---------------------------------------------------
+
+    /// Build statistics from a recorded event log.
+    ///
+    /// Produces the same results as a live tracker fed every event of each
+    /// tick followed by `end_tick(engine.sim_state.tick)` after each step.
+    pub fn from_event_log(log: &EventLog, config: StatsConfig) -> Self {
+        let mut stats = Self::new(config);
+        stats.replay_event_log(log);
+        stats
+    }
+
+    /// Feed every tick of a recorded event log into this tracker, calling
+    /// [`end_tick`](Self::end_tick) at each recorded tick boundary.
+    pub fn replay_event_log(&mut self, log: &EventLog) {
+        for frame in &log.ticks {
+            for event in &frame.events {
+                self.process_event(event);
+            }
+            // Recorded ticks are the tick the events were emitted in; the
+            // engine's tick counter has advanced by one once the step ends.
+            self.end_tick(frame.tick + 1);
+        }
+    }
+
     // -- Per-node queries ---------------------------------------------------
 
     /// Get the production rate (items/tick) for a node and item type.
@@ -705,6 +860,18 @@ impl ProductionStats {
             .unwrap_or(Fixed64::ZERO)
     }
 
+    /// Get the throughput (items/tick) of an edge group in `engine`: the
+    /// lowest throughput of its edges, since a line moves no more than its
+    /// slowest segment delivers. Zero for an empty or unknown group.
+    pub fn get_group_throughput(&self, engine: &Engine, group: EdgeGroupId) -> Fixed64 {
+        engine
+            .group_edges(group)
+            .iter()
+            .map(|&edge| self.get_throughput(edge))
+            .min()
+            .unwrap_or(Fixed64::ZERO)
+    }
+
     // -- Global queries -----------------------------------------------------
 
     /// Get the total production rate (items/tick) for an item type across all nodes.
@@ -745,6 +912,30 @@ impl ProductionStats {
             .unwrap_or_default()
     }
 
+    /// Every recorded history series: each tracked node's production history
+    /// per item type, then each tracked edge's throughput history. Nodes,
+    /// edges and item types are in ascending id order.
+    pub fn export_all_history(&self) -> Vec<(HistorySeries, Vec<Fixed64>)> {
+        let nodes = self.nodes.iter().flat_map(|(&node, stats)| {
+            stats
+                .production_history
+                .iter()
+                .map(move |(&item_type, history)| {
+                    (
+                        HistorySeries::Production { node, item_type },
+                        history.to_vec(),
+                    )
+                })
+        });
+        let edges = self.edges.iter().map(|(&edge, stats)| {
+            (
+                HistorySeries::Throughput { edge },
+                stats.throughput_history.to_vec(),
+            )
+        });
+        nodes.chain(edges).collect()
+    }
+
     // -- Utility ------------------------------------------------------------
 
     /// Remove all statistics for a node (e.g., when the node is destroyed).
@@ -1204,6 +1395,82 @@ mod tests {
         assert_fixed_approx(stats.get_utilization(edge), 0.3, 0.01);
     }
 
+    #[test]
+    fn registered_edge_counts_idle_ticks_before_first_delivery() {
+        let config = StatsConfig {
+            window_size: 200,
+            history_capacity: 16,
+        };
+        let mut stats = ProductionStats::new(config.clone());
+        let edge = make_edge_id();
+        stats.register_edge(edge, 0);
+
+        // Idle for 90 ticks, then full for 10.
+        for tick in 0..90 {
+            stats.end_tick(tick + 1);
+        }
+        for tick in 90..100 {
+            stats.process_event(&Event::ItemDelivered {
+                edge,
+                quantity: 1,
+                tick,
+            });
+            stats.process_event(&Event::TransportFull { edge, tick });
+            stats.end_tick(tick + 1);
+        }
+        assert_fixed_approx(stats.get_utilization(edge), 0.1, 0.001);
+
+        // Without registration the idle ticks are invisible.
+        let mut late = ProductionStats::new(config.clone());
+        for tick in 0..90 {
+            late.end_tick(tick + 1);
+        }
+        late.process_event(&Event::TransportFull { edge, tick: 90 });
+        late.end_tick(91);
+        assert_fixed_approx(late.get_utilization(edge), 1.0, 0.001);
+
+        // Registering after ticks have ended fills them in as idle, and the
+        // EdgeAdded event registers just like the explicit call.
+        let mut backfilled = ProductionStats::new(config);
+        for tick in 0..90 {
+            backfilled.end_tick(tick + 1);
+        }
+        backfilled.process_event(&Event::EdgeAdded {
+            edge,
+            from: make_node_id(),
+            to: make_node_id(),
+            tick: 0,
+        });
+        for tick in 90..100 {
+            backfilled.process_event(&Event::TransportFull { edge, tick });
+            backfilled.end_tick(tick + 1);
+        }
+        assert_eq!(
+            backfilled.get_utilization(edge),
+            stats.get_utilization(edge)
+        );
+    }
+
+    #[test]
+    fn registration_backfill_is_capped_at_the_window() {
+        let mut stats = ProductionStats::new(small_config());
+        for tick in 0..1_000 {
+            stats.end_tick(tick + 1);
+        }
+        let node = make_node_id();
+        stats.register_node(node, 0);
+        stats.process_event(&Event::ItemProduced {
+            node,
+            item_type: iron(),
+            quantity: 1,
+            tick: 1_000,
+        });
+        stats.end_tick(1_001);
+        // One working tick out of a 10-tick window.
+        assert_fixed_approx(stats.get_uptime(node), 0.1, 0.001);
+        assert_eq!(stats.get_history(node, iron()).len(), 1);
+    }
+
     // -----------------------------------------------------------------------
     // Test 15: Global production/consumption totals
     // -----------------------------------------------------------------------
@@ -1618,15 +1885,25 @@ mod tests {
         let mut stats = ProductionStats::new(small_config());
         let node = make_node_id();
 
-        // NodeAdded is not tracked by stats.
-        stats.process_event(&Event::NodeAdded {
+        // Recipe switches are not tracked by stats.
+        stats.process_event(&Event::RecipeSwitched {
             node,
-            building_type: BuildingTypeId(0),
+            old_recipe_index: 0,
+            new_recipe_index: 1,
             tick: 1,
         });
         stats.end_tick(1);
-
         assert_eq!(stats.tracked_node_count(), 0);
+
+        // NodeAdded registers the node even before it does anything.
+        stats.process_event(&Event::NodeAdded {
+            node,
+            building_type: BuildingTypeId(0),
+            tick: 2,
+        });
+        stats.end_tick(2);
+        assert_eq!(stats.tracked_node_count(), 1);
+        assert_fixed_approx(stats.get_idle_ratio(node), 1.0, 0.001);
     }
 
     // -----------------------------------------------------------------------
@@ -1719,4 +1996,215 @@ mod tests {
         // All production has rolled off the window.
         assert_fixed_approx(stats.get_production_rate(node, iron()), 0.0, 0.01);
     }
+
+    // -----------------------------------------------------------------------
+    // Test 33: Replaying an event log matches live processing
+    // -----------------------------------------------------------------------
+    #[test]
+    fn event_log_replay_matches_live() {
+        let node = make_node_id();
+        let edge = make_edge_id();
+        let mut live = ProductionStats::new(small_config());
+        let mut log = EventLog::new();
+
+        for tick in 0..12u64 {
+            let mut events = Vec::new();
+            // Produce on even ticks, stall on every third, idle otherwise.
+            if tick % 2 == 0 {
+                events.push(Event::ItemProduced {
+                    node,
+                    item_type: iron(),
+                    quantity: 3,
+                    tick,
+                });
+                events.push(Event::ItemDelivered {
+                    edge,
+                    quantity: 3,
+                    tick,
+                });
+            } else if tick % 3 == 0 {
+                events.push(Event::BuildingStalled {
+                    node,
+                    reason: StallReason::MissingInputs,
+                    tick,
+                });
+            }
+            for event in &events {
+                live.process_event(event);
+            }
+            live.end_tick(tick + 1);
+            log.push_tick(tick, events);
+        }
+
+        let bytes = log.to_bytes().unwrap();
+        let replayed =
+            ProductionStats::from_event_log(&EventLog::from_bytes(&bytes).unwrap(), small_config());
+
+        assert_eq!(replayed.current_tick(), live.current_tick());
+        assert_eq!(
+            replayed.get_production_rate(node, iron()),
+            live.get_production_rate(node, iron())
+        );
+        assert_eq!(replayed.get_idle_ratio(node), live.get_idle_ratio(node));
+        assert_eq!(replayed.get_stall_ratio(node), live.get_stall_ratio(node));
+        assert_eq!(replayed.get_throughput(edge), live.get_throughput(edge));
+        assert_eq!(
+            replayed.get_history(node, iron()),
+            live.get_history(node, iron())
+        );
+    }
+
+    #[test]
+    fn initialize_registers_existing_nodes_and_edges() {
+        use factorial_core::id::BuildingTypeId;
+        use factorial_core::sim::SimulationStrategy;
+
+        let mut engine = Engine::new(SimulationStrategy::Tick);
+        let a = engine.graph.queue_add_node(BuildingTypeId(0));
+        let b = engine.graph.queue_add_node(BuildingTypeId(0));
+        let result = engine.graph.apply_mutations();
+        let (a, b) = (
+            result.resolve_node(a).unwrap(),
+            result.resolve_node(b).unwrap(),
+        );
+        let edge = engine.graph.queue_connect(a, b);
+        let edge = engine.graph.apply_mutations().resolve_edge(edge).unwrap();
+
+        let mut stats = ProductionStats::new(StatsConfig::default());
+        stats.initialize(&engine);
+        assert_eq!(stats.tracked_node_count(), 2);
+        assert_eq!(stats.tracked_edge_count(), 1);
+
+        for tick in 1..=5 {
+            stats.end_tick(tick);
+        }
+        assert_eq!(stats.get_idle_ratio(a), Fixed64::ONE);
+        assert_eq!(stats.get_utilization(edge), Fixed64::ZERO);
+        assert_eq!(stats.get_edge_history(edge).len(), 5);
+    }
+
+    #[test]
+    fn exported_history_ignores_insertion_order() {
+        use slotmap::SlotMap;
+        let mut node_keys = SlotMap::<NodeId, ()>::with_key();
+        let nodes: Vec<NodeId> = (0..4).map(|_| node_keys.insert(())).collect();
+        let mut edge_keys = SlotMap::<EdgeId, ()>::with_key();
+        let edges: Vec<EdgeId> = (0..3).map(|_| edge_keys.insert(())).collect();
+
+        // The same ticks, with each tick's events in a different order.
+        let run = |order: &[usize]| {
+            let mut stats = ProductionStats::new(small_config());
+            for tick in 1..=6u64 {
+                for &i in order {
+                    let item_type = if (i + tick as usize).is_multiple_of(2) {
+                        iron()
+                    } else {
+                        copper()
+                    };
+                    stats.process_event(&Event::ItemProduced {
+                        node: nodes[i],
+                        item_type,
+                        quantity: i as u32 + 1,
+                        tick,
+                    });
+                    if let Some(&edge) = edges.get(i) {
+                        stats.process_event(&Event::ItemDelivered {
+                            edge,
+                            quantity: i as u32,
+                            tick,
+                        });
+                    }
+                }
+                stats.end_tick(tick);
+            }
+            format!("{:?}", stats.export_all_history())
+        };
+
+        let forward = run(&[0, 1, 2, 3]);
+        assert_eq!(forward, run(&[3, 1, 0, 2]));
+        assert_eq!(forward, run(&[2, 3, 1, 0]));
+    }
+
+    #[test]
+    fn exported_history_is_in_id_order() {
+        let node = make_node_id();
+        let edge = make_edge_id();
+        let mut stats = ProductionStats::new(small_config());
+        stats.process_event(&Event::ItemDelivered {
+            edge,
+            quantity: 1,
+            tick: 1,
+        });
+        for item_type in [copper(), iron()] {
+            stats.process_event(&Event::ItemProduced {
+                node,
+                item_type,
+                quantity: 2,
+                tick: 1,
+            });
+        }
+        stats.end_tick(1);
+
+        let series: Vec<HistorySeries> = stats
+            .export_all_history()
+            .into_iter()
+            .map(|(series, samples)| {
+                assert_eq!(samples.len(), 1);
+                series
+            })
+            .collect();
+        assert_eq!(
+            series,
+            [
+                HistorySeries::Production {
+                    node,
+                    item_type: iron()
+                },
+                HistorySeries::Production {
+                    node,
+                    item_type: copper()
+                },
+                HistorySeries::Throughput { edge },
+            ]
+        );
+    }
+
+    #[test]
+    fn group_throughput_is_the_bottleneck_segment() {
+        use factorial_core::processor::Processor;
+        use factorial_core::sim::SimulationStrategy;
+        use factorial_core::test_utils;
+
+        // A source feeding five flow segments; the middle one is the slowest.
+        let mut engine = Engine::new(SimulationStrategy::Tick);
+        let mut prev =
+            test_utils::add_node(&mut engine, test_utils::make_source(iron(), 3.0), 100, 100);
+        let mut edges = Vec::new();
+        for rate in [3.0, 3.0, 1.0, 3.0, 3.0] {
+            let next = test_utils::add_node(&mut engine, Processor::Passthrough, 100, 100);
+            edges.push(test_utils::connect(
+                &mut engine,
+                prev,
+                next,
+                test_utils::make_flow_transport(rate),
+            ));
+            prev = next;
+        }
+        let group = engine.create_edge_group(&edges);
+
+        engine.enable_event_log();
+        for _ in 0..60 {
+            engine.step();
+        }
+        let stats =
+            ProductionStats::from_event_log(&engine.take_event_log().unwrap(), small_config());
+        let throughput = stats.get_group_throughput(&engine, group);
+        assert_eq!(throughput, Fixed64::ONE);
+        assert_eq!(throughput, stats.get_throughput(edges[4]));
+        assert!(stats.get_throughput(edges[0]) >= throughput);
+        assert_eq!(
+            stats.get_group_throughput(&engine, EdgeGroupId::default()),
+            Fixed64::ZERO
+        );
+    }
 }
@@ -15,19 +15,39 @@
 //! completed, the tech tree emits a [`TechEvent::ResearchCompleted`] event and
 //! records which [`Unlock`]s should be applied.
 //!
+//! Rate research runs at the pace of the labs doing it: pass
+//! [`lab_throughput`] of the lab nodes to [`TechTree::tick_rate_scaled`].
+//!
+//! Threshold research completes by itself once enough of an item has been
+//! produced: feed production to the tree with [`TechTree::process_event`]
+//! (or [`TechTree::record_production`]).
+//!
+//! For "research 50% complete" notifications, configure thresholds with
+//! [`TechTree::set_progress_thresholds`]; the tree then emits
+//! [`TechEvent::ResearchProgress`] as contributions cross them.
+//!
 //! # Cost Models
 //!
-//! The module supports six cost models matching real factory games:
+//! The module supports seven cost models matching real factory games:
 //!
 //! - **Items** (Factorio/DSP): consume specific items
 //! - **Points** (ONI): accumulate science points
 //! - **Delivery** (Satisfactory): one-time delivery of items
 //! - **Rate** (Captain of Industry): points per tick over time
 //! - **ItemRate** (Shapez): deliver items at a target rate
+//! - **Threshold**: produce a cumulative amount of an item
 //! - **Custom**: game-defined completion logic via callback ID
 
-use factorial_core::fixed::{Fixed64, Ticks};
-use factorial_core::id::{BuildingTypeId, ItemTypeId, RecipeId};
+use factorial_core::engine::Engine;
+use factorial_core::event::Event;
+use factorial_core::extension::ExtensionId;
+use factorial_core::fixed::{
+    Fixed64, FixedDecimal, RoundingMode, Ticks, checked_div_64, saturating_powi_64,
+};
+use factorial_core::id::{BuildingTypeId, ItemTypeId, NodeId, RecipeId};
+use factorial_core::module::Module;
+use factorial_core::processor::{ProcessorState, speed_multiplier};
+use factorial_core::sim::StateHash;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
@@ -77,6 +97,12 @@ pub enum ResearchCost {
         duration: Ticks,
     },
 
+    /// Completes by itself once `amount` of `item` has been produced in
+    /// total, counted by the tree from the production it is fed (see
+    /// [`TechTree::record_production`]). Production before the research
+    /// started counts.
+    Threshold { item: ItemTypeId, amount: u32 },
+
     /// Game-defined completion logic. The game registers a callback ID
     /// and handles completion checks externally.
     Custom(ResearchCostFnId),
@@ -99,6 +125,11 @@ pub enum Unlock {
     /// Game-defined unlock. The string key is opaque to the engine;
     /// game code interprets it.
     Custom(String),
+
+    /// Speeds up all later research: each completion multiplies the tree's
+    /// [research speed](TechTree::research_speed_multiplier) by
+    /// `1 + bonus`. A bonus of `0.5` is +50%.
+    ResearchSpeed(Fixed64),
 }
 
 // ---------------------------------------------------------------------------
@@ -125,19 +156,7 @@ impl CostScaling {
                 base.saturating_add(increment.saturating_mul(level))
             }
             CostScaling::Exponential { base, multiplier } => {
-                let mut cost = Fixed64::from_num(*base);
-                for _ in 0..level {
-                    cost = cost.saturating_mul(*multiplier);
-                }
-                // Clamp to u32 range.
-                let result: i64 = cost.to_num();
-                if result < 0 {
-                    0
-                } else if result > u32::MAX as i64 {
-                    u32::MAX
-                } else {
-                    result as u32
-                }
+                clamp_to_u32(exponential_cost(*base, *multiplier, level))
             }
         }
     }
@@ -173,6 +192,21 @@ pub struct Technology {
     pub cost_scaling: Option<CostScaling>,
 }
 
+/// A named set of technologies, reached when the last of them completes.
+/// Games use milestones for achievements and story beats such as "all
+/// tier-1 technologies researched".
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Milestone {
+    /// Unique name.
+    pub name: String,
+
+    /// Technologies that must all be completed.
+    pub required: Vec<TechId>,
+
+    /// Whether the milestone has been reached. Stays set once reached.
+    pub reached: bool,
+}
+
 // ---------------------------------------------------------------------------
 // Research state (runtime)
 // ---------------------------------------------------------------------------
@@ -213,6 +247,10 @@ pub enum ResearchProgress {
     /// For ItemRate cost model: tracks ticks elapsed.
     ItemRate(Ticks),
 
+    /// For Threshold cost model: the item's cumulative production when last
+    /// updated.
+    Threshold(u64),
+
     /// For Custom cost model: game manages progress externally.
     Custom,
 }
@@ -235,6 +273,21 @@ pub enum TechEvent {
         level: u32,
         tick: Ticks,
     },
+
+    /// The last required technology of a milestone has completed. Emitted
+    /// right after that technology's `ResearchCompleted`, once per milestone.
+    MilestoneReached { name: String, tick: Ticks },
+
+    /// A contribution carried a technology's progress past one of the
+    /// thresholds set with [`TechTree::set_progress_thresholds`]. `fraction`
+    /// is the threshold crossed. Emitted once per threshold per research,
+    /// in ascending order, and before `ResearchCompleted` if the same
+    /// contribution completes it.
+    ResearchProgress {
+        tech_id: TechId,
+        fraction: Fixed64,
+        tick: Ticks,
+    },
 }
 
 // ---------------------------------------------------------------------------
@@ -264,6 +317,18 @@ pub enum TechTreeError {
 
     #[error("wrong cost model for technology {0:?}: expected {1}")]
     WrongCostModel(TechId, &'static str),
+
+    #[error("duplicate milestone name: {0}")]
+    DuplicateMilestone(String),
+
+    #[error("invalid completion bitset: {0}")]
+    InvalidBitset(&'static str),
+
+    #[error("technology {0:?} is not being researched")]
+    NotInProgress(TechId),
+
+    #[error("progress threshold {0} is not strictly between 0 and 1")]
+    InvalidProgressThreshold(Fixed64),
 }
 
 // ---------------------------------------------------------------------------
@@ -290,11 +355,57 @@ pub struct TechTree {
     /// Next auto-assigned TechId (used by `register`).
     next_id: u32,
 
+    /// Building types that may only be built once a technology is complete.
+    #[serde(default)]
+    building_gates: HashMap<BuildingTypeId, TechId>,
+
+    /// Milestones in definition order, with their completion state.
+    #[serde(default)]
+    milestones: Vec<Milestone>,
+
+    /// Progress fractions that emit `ResearchProgress`, ascending.
+    #[serde(default)]
+    progress_thresholds: Vec<Fixed64>,
+
+    /// For each in-progress technology, how many of `progress_thresholds`
+    /// its progress has crossed. Absent means none.
+    #[serde(default)]
+    progress_crossed: HashMap<TechId, u32>,
+
+    /// Product of every completed `Unlock::ResearchSpeed` bonus, applied
+    /// to point and rate contributions.
+    #[serde(default = "one")]
+    research_speed: Fixed64,
+
+    /// For each in-progress Points research, the fraction of a point its
+    /// scaled contributions have credited beyond whole points. Absent
+    /// means zero.
+    #[serde(default)]
+    point_carry: HashMap<TechId, Fixed64>,
+
+    /// Cumulative production per item, fed by `record_production`.
+    #[serde(default)]
+    produced: HashMap<ItemTypeId, u64>,
+
     /// Events emitted since last drain. Not serialized (transient).
     #[serde(skip)]
     events: Vec<TechEvent>,
 }
 
+fn one() -> Fixed64 {
+    Fixed64::ONE
+}
+
+/// Outcome of [`TechTree::contribute_points`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PointsContribution {
+    /// Points taken from the contribution.
+    pub consumed: u32,
+    /// Points credited to the research: `consumed` scaled by the research
+    /// speed multiplier.
+    pub credited: u32,
+}
+
 impl TechTree {
     /// Create a new, empty tech tree.
     pub fn new() -> Self {
@@ -303,6 +414,13 @@ impl TechTree {
             states: HashMap::new(),
             completions: HashMap::new(),
             next_id: 0,
+            building_gates: HashMap::new(),
+            milestones: Vec::new(),
+            progress_thresholds: Vec::new(),
+            progress_crossed: HashMap::new(),
+            research_speed: Fixed64::ONE,
+            point_carry: HashMap::new(),
+            produced: HashMap::new(),
             events: Vec::new(),
         }
     }
@@ -347,6 +465,100 @@ impl TechTree {
         id
     }
 
+    /// Define a milestone that is reached when every technology in `required`
+    /// has completed. A milestone whose technologies are all complete
+    /// already is marked reached without emitting an event.
+    pub fn define_milestone(
+        &mut self,
+        name: impl Into<String>,
+        required: Vec<TechId>,
+    ) -> Result<(), TechTreeError> {
+        let name = name.into();
+        if self.milestones.iter().any(|m| m.name == name) {
+            return Err(TechTreeError::DuplicateMilestone(name));
+        }
+        if let Some(&missing) = required
+            .iter()
+            .find(|id| !self.technologies.contains_key(id))
+        {
+            return Err(TechTreeError::TechNotFound(missing));
+        }
+        let reached = required.iter().all(|&id| self.is_completed(id));
+        self.milestones.push(Milestone {
+            name,
+            required,
+            reached,
+        });
+        Ok(())
+    }
+
+    /// Look up a milestone by name.
+    pub fn milestone(&self, name: &str) -> Option<&Milestone> {
+        self.milestones.iter().find(|m| m.name == name)
+    }
+
+    /// All milestones, in definition order.
+    pub fn milestones(&self) -> &[Milestone] {
+        &self.milestones
+    }
+
+    /// Emit [`TechEvent::ResearchProgress`] whenever a contribution carries
+    /// a technology's [`progress_fraction`](Self::progress_fraction) to or
+    /// past one of `thresholds`, e.g. `[0.25, 0.5, 0.75]`. Thresholds must
+    /// be strictly between 0 and 1; they are sorted and deduplicated. An
+    /// empty list turns the events off.
+    ///
+    /// Research already in progress keeps the thresholds it has crossed,
+    /// counted against the new list.
+    pub fn set_progress_thresholds(
+        &mut self,
+        mut thresholds: Vec<Fixed64>,
+    ) -> Result<(), TechTreeError> {
+        if let Some(&bad) = thresholds
+            .iter()
+            .find(|&&t| t <= Fixed64::ZERO || t >= Fixed64::ONE)
+        {
+            return Err(TechTreeError::InvalidProgressThreshold(bad));
+        }
+        thresholds.sort_unstable();
+        thresholds.dedup();
+        self.progress_thresholds = thresholds;
+        Ok(())
+    }
+
+    /// The configured progress thresholds, ascending.
+    pub fn progress_thresholds(&self) -> &[Fixed64] {
+        &self.progress_thresholds
+    }
+
+    /// Gate a building type behind a technology. While the technology has
+    /// not been completed, an engine in strict mode with this tree registered
+    /// as a module rejects new nodes of that type. Building types without a
+    /// gate are never restricted. Re-gating a building replaces its gate.
+    pub fn gate_building(
+        &mut self,
+        building: BuildingTypeId,
+        tech: TechId,
+    ) -> Result<(), TechTreeError> {
+        if !self.technologies.contains_key(&tech) {
+            return Err(TechTreeError::TechNotFound(tech));
+        }
+        self.building_gates.insert(building, tech);
+        Ok(())
+    }
+
+    /// The technology gating a building type, if any.
+    pub fn building_gate(&self, building: BuildingTypeId) -> Option<TechId> {
+        self.building_gates.get(&building).copied()
+    }
+
+    /// Check whether a building type may be built: it is ungated, or its
+    /// gating technology has been completed.
+    pub fn is_building_unlocked(&self, building: BuildingTypeId) -> bool {
+        self.building_gate(building)
+            .is_none_or(|tech| self.is_completed(tech))
+    }
+
     // -- Query API --
 
     /// Get a technology definition by ID.
@@ -385,6 +597,18 @@ impl TechTree {
         matches!(self.states.get(&id), Some(ResearchState::InProgress(_)))
     }
 
+    /// How far an in-progress research is toward its effective cost, from 0
+    /// to 1. Items and Delivery research count every required item equally.
+    /// Returns `None` if the technology is not in progress or uses the
+    /// Custom cost model, whose progress the game tracks itself.
+    pub fn progress_fraction(&self, id: TechId) -> Option<Fixed64> {
+        let Some(ResearchState::InProgress(progress)) = self.states.get(&id) else {
+            return None;
+        };
+        let cost = self.effective_cost(id).ok()?;
+        fraction_of(progress, &cost)
+    }
+
     /// Get the number of times a repeatable technology has been completed.
     /// Returns 0 if never completed.
     pub fn completion_count(&self, id: TechId) -> u32 {
@@ -404,6 +628,18 @@ impl TechTree {
         unlocks
     }
 
+    /// How much faster research runs thanks to completed
+    /// [`Unlock::ResearchSpeed`] bonuses: the product of `1 + bonus` over
+    /// every completion, in `TechId` order. Bonuses below -100% count as
+    /// -100%. Starts at 1.
+    ///
+    /// Scales [`contribute_points`](Self::contribute_points) and
+    /// [`tick_rate_scaled`](Self::tick_rate_scaled). Item costs, deliveries
+    /// and item-rate durations are unaffected.
+    pub fn research_speed_multiplier(&self) -> Fixed64 {
+        self.research_speed
+    }
+
     /// Get the number of registered technologies.
     pub fn technology_count(&self) -> usize {
         self.technologies.len()
@@ -460,24 +696,35 @@ impl TechTree {
             _ => {}
         }
 
-        // Initialize progress based on cost model.
-        let progress = match &tech.cost {
-            ResearchCost::Items(items) => {
-                ResearchProgress::Items(items.iter().map(|(item, _)| (*item, 0)).collect())
-            }
-            ResearchCost::Points(_) => ResearchProgress::Points(0),
-            ResearchCost::Delivery(items) => {
-                ResearchProgress::Delivery(items.iter().map(|(item, _)| (*item, 0)).collect())
-            }
-            ResearchCost::Rate { .. } => ResearchProgress::Rate(Fixed64::ZERO),
-            ResearchCost::ItemRate { .. } => ResearchProgress::ItemRate(0),
-            ResearchCost::Custom(_) => ResearchProgress::Custom,
-        };
-
+        let progress = initial_progress(&tech.cost);
         self.states.insert(id, ResearchState::InProgress(progress));
+        self.progress_crossed.remove(&id);
+        self.point_carry.remove(&id);
         self.events
             .push(TechEvent::ResearchStarted { tech_id: id, tick });
 
+        // Threshold research may already be met by earlier production.
+        self.update_threshold(id, tick);
+        Ok(())
+    }
+
+    /// Abandon an in-progress research, discarding its progress. The
+    /// technology returns to not started, or to completed if it is a
+    /// repeatable technology completed before. Emits no event.
+    pub fn cancel_research(&mut self, id: TechId) -> Result<(), TechTreeError> {
+        if !self.technologies.contains_key(&id) {
+            return Err(TechTreeError::TechNotFound(id));
+        }
+        if !self.is_in_progress(id) {
+            return Err(TechTreeError::NotInProgress(id));
+        }
+        let state = match self.completion_count(id) {
+            0 => ResearchState::NotStarted,
+            times_completed => ResearchState::Completed { times_completed },
+        };
+        self.states.insert(id, state);
+        self.progress_crossed.remove(&id);
+        self.point_carry.remove(&id);
         Ok(())
     }
 
@@ -552,6 +799,7 @@ impl TechTree {
             false
         };
 
+        self.emit_progress(id, &effective_cost, tick);
         if is_complete {
             self.complete_research(id, &tech, tick);
         }
@@ -559,15 +807,22 @@ impl TechTree {
         Ok(consumed)
     }
 
-    /// Contribute science points toward a Points-cost research.
-    /// Returns the number of points actually consumed. Completes research
-    /// when the target is met.
+    /// Contribute science points toward a Points-cost research. Completes
+    /// research when the target is met.
+    ///
+    /// Each consumed point credits [`research_speed_multiplier`] points.
+    /// Fractions of a point carry over to the next contribution, so the
+    /// research credits `floor(total consumed * multiplier)` however the
+    /// points are batched. Only the points needed to reach the target are
+    /// consumed: the fewest whose scaled credit covers what remains.
+    ///
+    /// [`research_speed_multiplier`]: Self::research_speed_multiplier
     pub fn contribute_points(
         &mut self,
         id: TechId,
         points: u32,
         tick: Ticks,
-    ) -> Result<u32, TechTreeError> {
+    ) -> Result<PointsContribution, TechTreeError> {
         let tech = self
             .technologies
             .get(&id)
@@ -592,21 +847,46 @@ impl TechTree {
         };
 
         let remaining = required.saturating_sub(*current);
-        let to_consume = points.min(remaining);
-        *current += to_consume;
+        let carry = self.point_carry.get(&id).copied().unwrap_or(Fixed64::ZERO);
+        let (contribution, carry) = scale_points(points, remaining, self.research_speed, carry);
+        *current += contribution.credited;
 
         let is_complete = *current >= required;
+        if carry > Fixed64::ZERO && !is_complete {
+            self.point_carry.insert(id, carry);
+        } else {
+            self.point_carry.remove(&id);
+        }
 
+        self.emit_progress(id, &effective_cost, tick);
         if is_complete {
             self.complete_research(id, &tech, tick);
         }
 
-        Ok(to_consume)
+        Ok(contribution)
     }
 
     /// Advance a Rate-cost research by one tick. The rate is determined by
     /// the technology's cost definition. Completes research when total is met.
+    ///
+    /// Equivalent to [`tick_rate_scaled`](Self::tick_rate_scaled) with a
+    /// throughput of 1.
     pub fn tick_rate(&mut self, id: TechId, tick: Ticks) -> Result<bool, TechTreeError> {
+        self.tick_rate_scaled(id, Fixed64::ONE, tick)
+    }
+
+    /// Advance a Rate-cost research by one tick at `throughput`, typically
+    /// the number of operating labs weighted by their speed (see
+    /// [`lab_throughput`]). The tick adds `points_per_tick * throughput`,
+    /// scaled by the [research speed](Self::research_speed_multiplier);
+    /// negative throughput counts as zero. Completes research when total is
+    /// met.
+    pub fn tick_rate_scaled(
+        &mut self,
+        id: TechId,
+        throughput: Fixed64,
+        tick: Ticks,
+    ) -> Result<bool, TechTreeError> {
         let tech = self
             .technologies
             .get(&id)
@@ -633,10 +913,14 @@ impl TechTree {
             _ => return Err(TechTreeError::WrongCostModel(id, "Rate")),
         };
 
-        *accumulated = accumulated.saturating_add(points_per_tick);
+        let points = points_per_tick
+            .saturating_mul(throughput.max(Fixed64::ZERO))
+            .saturating_mul(self.research_speed);
+        *accumulated = accumulated.saturating_add(points);
 
         let is_complete = *accumulated >= total;
 
+        self.emit_progress(id, &effective_cost, tick);
         if is_complete {
             self.complete_research(id, &tech, tick);
         }
@@ -676,6 +960,7 @@ impl TechTree {
 
         let is_complete = *elapsed >= duration;
 
+        self.emit_progress(id, &effective_cost, tick);
         if is_complete {
             self.complete_research(id, &tech, tick);
         }
@@ -683,6 +968,59 @@ impl TechTree {
         Ok(is_complete)
     }
 
+    /// Feed an engine event to the tree. `ItemProduced` events go to
+    /// [`record_production`](Self::record_production); everything else is
+    /// ignored. Hosts pass each event they drain from the engine, as they
+    /// do for production statistics.
+    pub fn process_event(&mut self, event: &Event) {
+        if let Event::ItemProduced {
+            item_type,
+            quantity,
+            tick,
+            ..
+        } = event
+        {
+            self.record_production(*item_type, *quantity, *tick);
+        }
+    }
+
+    /// Add `quantity` of `item` to its cumulative production, and advance
+    /// every in-progress Threshold research on that item, completing those
+    /// whose amount is reached, in `TechId` order.
+    pub fn record_production(&mut self, item: ItemTypeId, quantity: u32, tick: Ticks) {
+        if quantity == 0 {
+            return;
+        }
+        let total = self.produced.entry(item).or_insert(0);
+        *total = total.saturating_add(u64::from(quantity));
+        let mut waiting: Vec<TechId> = self
+            .states
+            .iter()
+            .filter(|(_, state)| {
+                matches!(
+                    state,
+                    ResearchState::InProgress(ResearchProgress::Threshold(_))
+                )
+            })
+            .map(|(&id, _)| id)
+            .filter(|&id| {
+                matches!(
+                    self.effective_cost(id),
+                    Ok(ResearchCost::Threshold { item: target, .. }) if target == item
+                )
+            })
+            .collect();
+        waiting.sort_unstable_by_key(|id| id.0);
+        for id in waiting {
+            self.update_threshold(id, tick);
+        }
+    }
+
+    /// Cumulative production of `item` recorded so far.
+    pub fn produced_total(&self, item: ItemTypeId) -> u64 {
+        self.produced.get(&item).copied().unwrap_or(0)
+    }
+
     /// Mark a Custom-cost research as complete. Game code decides when the
     /// custom condition is met.
     pub fn complete_custom(&mut self, id: TechId, tick: Ticks) -> Result<(), TechTreeError> {
@@ -725,6 +1063,74 @@ impl TechTree {
 
     // -- Internal helpers --
 
+    /// Bring an in-progress Threshold research up to its item's cumulative
+    /// production, completing it if the amount is reached. Does nothing for
+    /// other research.
+    fn update_threshold(&mut self, id: TechId, tick: Ticks) {
+        let Ok(cost @ ResearchCost::Threshold { item, amount }) = self.effective_cost(id) else {
+            return;
+        };
+        let total = self.produced_total(item);
+        let Some(ResearchState::InProgress(ResearchProgress::Threshold(seen))) =
+            self.states.get_mut(&id)
+        else {
+            return;
+        };
+        *seen = total;
+        self.emit_progress(id, &cost, tick);
+        if total >= u64::from(amount) {
+            let tech = self.technologies[&id].clone();
+            self.complete_research(id, &tech, tick);
+        }
+    }
+
+    /// Emit `ResearchProgress` for each threshold `id`'s progress has
+    /// reached since the last call, and record them as crossed.
+    fn emit_progress(&mut self, id: TechId, cost: &ResearchCost, tick: Ticks) {
+        if self.progress_thresholds.is_empty() {
+            return;
+        }
+        let Some(ResearchState::InProgress(progress)) = self.states.get(&id) else {
+            return;
+        };
+        let Some(fraction) = fraction_of(progress, cost) else {
+            return;
+        };
+        let crossed = self.progress_crossed.get(&id).copied().unwrap_or(0) as usize;
+        let reached = self.progress_thresholds.partition_point(|&t| t <= fraction);
+        if reached <= crossed {
+            return;
+        }
+        for &threshold in &self.progress_thresholds[crossed..reached] {
+            self.events.push(TechEvent::ResearchProgress {
+                tech_id: id,
+                fraction: threshold,
+                tick,
+            });
+        }
+        self.progress_crossed.insert(id, reached as u32);
+    }
+
+    /// Rebuild the research speed multiplier from the completion counts.
+    fn recompute_research_speed(&mut self) {
+        let mut ids: Vec<TechId> = self.completions.keys().copied().collect();
+        ids.sort_unstable_by_key(|id| id.0);
+        let mut speed = Fixed64::ONE;
+        for id in ids {
+            let Some(tech) = self.technologies.get(&id) else {
+                continue;
+            };
+            let count = self.completion_count(id);
+            for unlock in &tech.unlocks {
+                if let Unlock::ResearchSpeed(bonus) = unlock {
+                    let factor = (Fixed64::ONE.saturating_add(*bonus)).max(Fixed64::ZERO);
+                    speed = speed.saturating_mul(saturating_powi_64(factor, count));
+                }
+            }
+        }
+        self.research_speed = speed;
+    }
+
     /// Complete research for a technology. Updates state and emits event.
     fn complete_research(&mut self, id: TechId, tech: &Technology, tick: Ticks) {
         let prev = self.completion_count(id);
@@ -740,6 +1146,16 @@ impl TechTree {
                 times_completed: level,
             },
         );
+        self.progress_crossed.remove(&id);
+
+        self.point_carry.remove(&id);
+        if tech
+            .unlocks
+            .iter()
+            .any(|u| matches!(u, Unlock::ResearchSpeed(_)))
+        {
+            self.recompute_research_speed();
+        }
 
         self.events.push(TechEvent::ResearchCompleted {
             tech_id: id,
@@ -747,6 +1163,22 @@ impl TechTree {
             level,
             tick,
         });
+
+        for i in 0..self.milestones.len() {
+            let milestone = &self.milestones[i];
+            if milestone.reached
+                || !milestone.required.contains(&id)
+                || !milestone.required.iter().all(|&r| self.is_completed(r))
+            {
+                continue;
+            }
+            let milestone = &mut self.milestones[i];
+            milestone.reached = true;
+            self.events.push(TechEvent::MilestoneReached {
+                name: milestone.name.clone(),
+                tick,
+            });
+        }
     }
 }
 
@@ -756,10 +1188,507 @@ impl Default for TechTree {
     }
 }
 
+/// How far `progress` is toward `cost`, clamped to 0..=1. `None` for
+/// Custom research and mismatched cost models. A zero cost counts as done.
+fn fraction_of(progress: &ResearchProgress, cost: &ResearchCost) -> Option<Fixed64> {
+    let items = |have: &[(ItemTypeId, u32)], need: &[(ItemTypeId, u32)]| {
+        let need_total: u64 = need.iter().map(|&(_, qty)| u64::from(qty)).sum();
+        let have_total: u64 = have
+            .iter()
+            .zip(need)
+            .map(|(&(_, have), &(_, need))| u64::from(have.min(need)))
+            .sum();
+        count_fraction(have_total, need_total)
+    };
+    match (progress, cost) {
+        (ResearchProgress::Items(have), ResearchCost::Items(need))
+        | (ResearchProgress::Delivery(have), ResearchCost::Delivery(need)) => {
+            Some(items(have, need))
+        }
+        (ResearchProgress::Points(have), ResearchCost::Points(need)) => {
+            Some(count_fraction(u64::from(*have), u64::from(*need)))
+        }
+        (ResearchProgress::Rate(have), ResearchCost::Rate { total, .. }) => {
+            if *total <= Fixed64::ZERO {
+                return Some(Fixed64::ONE);
+            }
+            Some(
+                checked_div_64(*have, *total)
+                    .unwrap_or(Fixed64::ONE)
+                    .clamp(Fixed64::ZERO, Fixed64::ONE),
+            )
+        }
+        (ResearchProgress::ItemRate(elapsed), ResearchCost::ItemRate { duration, .. }) => {
+            Some(count_fraction(*elapsed, *duration))
+        }
+        (ResearchProgress::Threshold(produced), ResearchCost::Threshold { amount, .. }) => {
+            Some(count_fraction(*produced, u64::from(*amount)))
+        }
+        _ => None,
+    }
+}
+
+/// `have / need` as a fraction clamped to 0..=1, computed exactly in
+/// integers so large counts do not overflow `Fixed64`.
+fn count_fraction(have: u64, need: u64) -> Fixed64 {
+    if have >= need {
+        return Fixed64::ONE;
+    }
+    let bits = (u128::from(have) << Fixed64::FRAC_NBITS) / u128::from(need);
+    Fixed64::from_bits(bits as i64)
+}
+
+/// Scale `points` by `speed` on top of a `carry` fraction, crediting at
+/// most `remaining`. Returns the contribution and the fraction left over.
+/// Computed exactly on the fixed-point bits.
+fn scale_points(
+    points: u32,
+    remaining: u32,
+    speed: Fixed64,
+    carry: Fixed64,
+) -> (PointsContribution, Fixed64) {
+    let nothing = PointsContribution {
+        consumed: 0,
+        credited: 0,
+    };
+    if speed <= Fixed64::ZERO || remaining == 0 {
+        return (nothing, carry);
+    }
+    let frac = Fixed64::FRAC_NBITS;
+    let speed = speed.to_bits() as u128;
+    let carry = carry.to_bits().max(0) as u128;
+    // Fewest points whose scaled credit reaches `remaining`.
+    let target = (u128::from(remaining) << frac).saturating_sub(carry);
+    let needed = target.div_ceil(speed);
+    if needed <= u128::from(points) {
+        let contribution = PointsContribution {
+            consumed: needed as u32,
+            credited: remaining,
+        };
+        return (contribution, Fixed64::ZERO);
+    }
+    let scaled = u128::from(points) * speed + carry;
+    let contribution = PointsContribution {
+        consumed: points,
+        credited: (scaled >> frac) as u32,
+    };
+    let left = Fixed64::from_bits((scaled & ((1 << frac) - 1)) as i64);
+    (contribution, left)
+}
+
+/// Zero progress for a research with the given cost model.
+fn initial_progress(cost: &ResearchCost) -> ResearchProgress {
+    match cost {
+        ResearchCost::Items(items) => {
+            ResearchProgress::Items(items.iter().map(|(item, _)| (*item, 0)).collect())
+        }
+        ResearchCost::Points(_) => ResearchProgress::Points(0),
+        ResearchCost::Delivery(items) => {
+            ResearchProgress::Delivery(items.iter().map(|(item, _)| (*item, 0)).collect())
+        }
+        ResearchCost::Rate { .. } => ResearchProgress::Rate(Fixed64::ZERO),
+        ResearchCost::ItemRate { .. } => ResearchProgress::ItemRate(0),
+        ResearchCost::Threshold { .. } => ResearchProgress::Threshold(0),
+        ResearchCost::Custom(_) => ResearchProgress::Custom,
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Compact completion export
+// ---------------------------------------------------------------------------
+
+/// Version byte at the start of a completion bitset.
+const COMPLETION_BITSET_VERSION: u8 = 1;
+
+const BITS_NOT_STARTED: u8 = 0;
+const BITS_IN_PROGRESS: u8 = 1;
+const BITS_COMPLETED: u8 = 2;
+
+impl TechTree {
+    /// Encode which technologies are completed or in progress, for saves
+    /// where the full serde form of a large tree is too bulky.
+    ///
+    /// The layout, with integers as LEB128 varints:
+    ///
+    /// 1. a version byte (`1`);
+    /// 2. `n`, one past the highest `TechId` with a state;
+    /// 3. `ceil(n / 4)` bytes holding two bits per `TechId` from 0, lowest
+    ///    bits first: `0` not started, `1` in progress, `2` completed;
+    /// 4. a side channel of completion counts: an entry count, then
+    ///    `(tech id, count)` pairs for every technology whose count is not
+    ///    what its bits imply (1 if completed, else 0). In practice these
+    ///    are repeatable technologies.
+    ///
+    /// A tree of 1000 technologies fits in about 250 bytes. Research
+    /// progress of in-progress technologies is not included; see
+    /// [`import_completion_bitset`](Self::import_completion_bitset).
+    pub fn export_completion_bitset(&self) -> Vec<u8> {
+        let n = self
+            .states
+            .keys()
+            .map(|id| id.0 as usize + 1)
+            .max()
+            .unwrap_or(0);
+        let mut out = vec![COMPLETION_BITSET_VERSION];
+        write_varint(&mut out, n as u64);
+        let start = out.len();
+        out.resize(start + n.div_ceil(4), 0);
+        for (id, state) in &self.states {
+            let bits = match state {
+                ResearchState::NotStarted => BITS_NOT_STARTED,
+                ResearchState::InProgress(_) => BITS_IN_PROGRESS,
+                ResearchState::Completed { .. } => BITS_COMPLETED,
+            };
+            let i = id.0 as usize;
+            out[start + i / 4] |= bits << (2 * (i % 4));
+        }
+
+        let mut counts: Vec<(TechId, u32)> = self
+            .completions
+            .iter()
+            .filter(|&(id, &count)| {
+                let implied = u32::from(matches!(
+                    self.states.get(id),
+                    Some(ResearchState::Completed { .. })
+                ));
+                count != implied
+            })
+            .map(|(&id, &count)| (id, count))
+            .collect();
+        counts.sort_unstable_by_key(|(id, _)| id.0);
+        write_varint(&mut out, counts.len() as u64);
+        for (id, count) in counts {
+            write_varint(&mut out, u64::from(id.0));
+            write_varint(&mut out, u64::from(count));
+        }
+        out
+    }
+
+    /// Restore completion state written by
+    /// [`export_completion_bitset`](Self::export_completion_bitset) into a
+    /// tree with the same technologies registered.
+    ///
+    /// Every registered technology's state and completion count is
+    /// replaced. A technology marked in progress keeps its current progress
+    /// if this tree already has it in progress (for example, restored from
+    /// a separate serde save of in-progress details) and otherwise starts
+    /// from zero progress. Milestones whose technologies are all completed
+    /// are marked reached and the research speed multiplier is recomputed.
+    /// No events are emitted.
+    ///
+    /// Nothing changes if the data is malformed or marks a technology that
+    /// is not registered.
+    pub fn import_completion_bitset(&mut self, bytes: &[u8]) -> Result<(), TechTreeError> {
+        let CompletionBitset { bits, counts } = decode_completion_bitset(bytes)?;
+        for (i, &state) in bits.iter().enumerate() {
+            let id = TechId(i as u32);
+            if state != BITS_NOT_STARTED && !self.technologies.contains_key(&id) {
+                return Err(TechTreeError::TechNotFound(id));
+            }
+        }
+        if let Some(&(id, _)) = counts
+            .iter()
+            .find(|(id, _)| !self.technologies.contains_key(id))
+        {
+            return Err(TechTreeError::TechNotFound(id));
+        }
+
+        self.completions.clear();
+        for (&id, tech) in &self.technologies {
+            let bits = bits.get(id.0 as usize).copied().unwrap_or(BITS_NOT_STARTED);
+            let state = match bits {
+                BITS_COMPLETED => {
+                    self.completions.insert(id, 1);
+                    ResearchState::Completed { times_completed: 1 }
+                }
+                BITS_IN_PROGRESS => match self.states.remove(&id) {
+                    Some(state @ ResearchState::InProgress(_)) => state,
+                    _ => ResearchState::InProgress(initial_progress(&tech.cost)),
+                },
+                _ => ResearchState::NotStarted,
+            };
+            self.states.insert(id, state);
+        }
+        let states = &self.states;
+        self.progress_crossed
+            .retain(|id, _| matches!(states.get(id), Some(ResearchState::InProgress(_))));
+        self.point_carry
+            .retain(|id, _| matches!(states.get(id), Some(ResearchState::InProgress(_))));
+        for (id, count) in counts {
+            if count == 0 {
+                self.completions.remove(&id);
+            } else {
+                self.completions.insert(id, count);
+            }
+            if let Some(ResearchState::Completed { times_completed }) = self.states.get_mut(&id) {
+                *times_completed = count;
+            }
+        }
+        for i in 0..self.milestones.len() {
+            let reached = self.milestones[i]
+                .required
+                .iter()
+                .all(|&r| self.is_completed(r));
+            self.milestones[i].reached = reached;
+        }
+        self.recompute_research_speed();
+        Ok(())
+    }
+}
+
+/// Append `value` as an unsigned LEB128 varint.
+fn write_varint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+/// Read an unsigned LEB128 varint from the front of `bytes`.
+fn read_varint(bytes: &mut &[u8]) -> Result<u64, TechTreeError> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let (&byte, rest) = bytes
+            .split_first()
+            .ok_or(TechTreeError::InvalidBitset("truncated varint"))?;
+        *bytes = rest;
+        value |= u64::from(byte & 0x7F) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err(TechTreeError::InvalidBitset("varint too long"))
+}
+
+/// A decoded completion bitset.
+struct CompletionBitset {
+    /// State bits, indexed by `TechId`.
+    bits: Vec<u8>,
+    /// Side-channel completion counts.
+    counts: Vec<(TechId, u32)>,
+}
+
+/// Split a completion bitset into per-id state bits and side-channel counts.
+fn decode_completion_bitset(mut bytes: &[u8]) -> Result<CompletionBitset, TechTreeError> {
+    let (&version, rest) = bytes
+        .split_first()
+        .ok_or(TechTreeError::InvalidBitset("empty"))?;
+    if version != COMPLETION_BITSET_VERSION {
+        return Err(TechTreeError::InvalidBitset("unknown version"));
+    }
+    bytes = rest;
+    let tech_id = |value: u64| {
+        u32::try_from(value)
+            .map(TechId)
+            .map_err(|_| TechTreeError::InvalidBitset("tech id out of range"))
+    };
+
+    let n = read_varint(&mut bytes)?;
+    let len = tech_id(n)?.0 as usize;
+    if bytes.len() < len.div_ceil(4) {
+        return Err(TechTreeError::InvalidBitset("truncated states"));
+    }
+    let (packed, rest) = bytes.split_at(len.div_ceil(4));
+    bytes = rest;
+    let mut bits = Vec::with_capacity(len);
+    for i in 0..len {
+        let state = (packed[i / 4] >> (2 * (i % 4))) & 0b11;
+        if state > BITS_COMPLETED {
+            return Err(TechTreeError::InvalidBitset("unknown state"));
+        }
+        bits.push(state);
+    }
+
+    let entries = read_varint(&mut bytes)?;
+    let mut counts = Vec::new();
+    for _ in 0..entries {
+        let id = tech_id(read_varint(&mut bytes)?)?;
+        let count = u32::try_from(read_varint(&mut bytes)?)
+            .map_err(|_| TechTreeError::InvalidBitset("count out of range"))?;
+        counts.push((id, count));
+    }
+    if !bytes.is_empty() {
+        return Err(TechTreeError::InvalidBitset("trailing bytes"));
+    }
+    Ok(CompletionBitset { bits, counts })
+}
+
+// ---------------------------------------------------------------------------
+// Labs
+// ---------------------------------------------------------------------------
+
+/// The research throughput of a set of lab nodes, for
+/// [`TechTree::tick_rate_scaled`]: the sum of the speed multipliers of the
+/// labs whose processors are working. Stalled, idle and missing labs add
+/// nothing.
+pub fn lab_throughput(engine: &Engine, labs: &[NodeId]) -> Fixed64 {
+    labs.iter()
+        .filter(|&&lab| {
+            matches!(
+                engine.get_processor_state(lab),
+                Some(ProcessorState::Working { .. })
+            )
+        })
+        .map(|&lab| speed_multiplier(engine.get_modifiers(lab).map_or(&[], Vec::as_slice)))
+        .fold(Fixed64::ZERO, Fixed64::saturating_add)
+}
+
+// ---------------------------------------------------------------------------
+// Engine module
+// ---------------------------------------------------------------------------
+
+/// Registering the tree with [`Engine::register_module`] lets a strict-mode
+/// engine refuse gated buildings (see [`TechTree::gate_building`]). Reach the
+/// registered tree through `engine.find_module_mut::<TechTree>()` to drive
+/// research; completions take effect on the next `apply_mutations`.
+///
+/// [`Engine::register_module`]: factorial_core::engine::Engine::register_module
+impl Module for TechTree {
+    fn name(&self) -> &str {
+        "tech_tree"
+    }
+
+    fn extension(&self) -> Option<ExtensionId> {
+        Some(ExtensionId::TechTree)
+    }
+
+    fn allows_building(&self, building_type: BuildingTypeId) -> bool {
+        self.is_building_unlocked(building_type)
+    }
+
+    /// Hashes research states and completion counts in `TechId` order.
+    fn state_hash(&self) -> u64 {
+        let mut h = StateHash::new();
+        let mut states: Vec<_> = self.states.iter().collect();
+        states.sort_unstable_by_key(|(id, _)| id.0);
+        for (id, state) in states {
+            h.write_u32(id.0);
+            hash_research_state(&mut h, state);
+        }
+        let mut completions: Vec<_> = self.completions.iter().collect();
+        completions.sort_unstable_by_key(|(id, _)| id.0);
+        h.write_u32(u32::MAX);
+        for (id, count) in completions {
+            h.write_u32(id.0);
+            h.write_u32(*count);
+        }
+        if !self.milestones.is_empty() {
+            h.write_u32(u32::MAX);
+            for milestone in &self.milestones {
+                h.write_u32(u32::from(milestone.reached));
+            }
+        }
+        if !self.progress_crossed.is_empty() {
+            let mut crossed: Vec<_> = self.progress_crossed.iter().collect();
+            crossed.sort_unstable_by_key(|(id, _)| id.0);
+            h.write_u32(u32::MAX);
+            for (id, count) in crossed {
+                h.write_u32(id.0);
+                h.write_u32(*count);
+            }
+        }
+        if !self.point_carry.is_empty() {
+            let mut carry: Vec<_> = self.point_carry.iter().collect();
+            carry.sort_unstable_by_key(|(id, _)| id.0);
+            h.write_u32(u32::MAX);
+            for (id, fraction) in carry {
+                h.write_u32(id.0);
+                h.write_fixed64(*fraction);
+            }
+        }
+        if !self.produced.is_empty() {
+            let mut produced: Vec<_> = self.produced.iter().collect();
+            produced.sort_unstable_by_key(|(item, _)| item.0);
+            h.write_u32(u32::MAX);
+            for (item, total) in produced {
+                h.write_u32(item.0);
+                h.write_u64(*total);
+            }
+        }
+        h.finish()
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+}
+
+/// Feed one technology's research state into `h`.
+fn hash_research_state(h: &mut StateHash, state: &ResearchState) {
+    match state {
+        ResearchState::NotStarted => h.write_u32(0),
+        ResearchState::InProgress(progress) => {
+            h.write_u32(1);
+            match progress {
+                ResearchProgress::Items(items) => {
+                    h.write_u32(0);
+                    hash_item_counts(h, items);
+                }
+                ResearchProgress::Delivery(items) => {
+                    h.write_u32(1);
+                    hash_item_counts(h, items);
+                }
+                ResearchProgress::Points(points) => {
+                    h.write_u32(2);
+                    h.write_u32(*points);
+                }
+                ResearchProgress::Rate(points) => {
+                    h.write_u32(3);
+                    h.write_fixed64(*points);
+                }
+                ResearchProgress::ItemRate(ticks) => {
+                    h.write_u32(4);
+                    h.write_u64(*ticks);
+                }
+                ResearchProgress::Custom => h.write_u32(5),
+                ResearchProgress::Threshold(produced) => {
+                    h.write_u32(6);
+                    h.write_u64(*produced);
+                }
+            }
+        }
+        ResearchState::Completed { times_completed } => {
+            h.write_u32(2);
+            h.write_u32(*times_completed);
+        }
+    }
+}
+
+fn hash_item_counts(h: &mut StateHash, items: &[(ItemTypeId, u32)]) {
+    h.write_u32(items.len() as u32);
+    for (item, qty) in items {
+        h.write_u32(item.0);
+        h.write_u32(*qty);
+    }
+}
+
 // ---------------------------------------------------------------------------
 // Cost scaling helper
 // ---------------------------------------------------------------------------
 
+/// `base * multiplier^level`, saturating at the Fixed64 range.
+fn exponential_cost(base: u32, multiplier: Fixed64, level: u32) -> Fixed64 {
+    Fixed64::from_num(base).saturating_mul(saturating_powi_64(multiplier, level))
+}
+
+/// Clamp a Fixed64 cost to the u32 range. Fractional costs round down, so a
+/// level never costs more than its exact scaled value.
+fn clamp_to_u32(cost: Fixed64) -> u32 {
+    let result: i64 = cost.round_dp(0, RoundingMode::Floor).to_num();
+    if result < 0 {
+        0
+    } else if result > u32::MAX as i64 {
+        u32::MAX
+    } else {
+        result as u32
+    }
+}
+
 /// Apply cost scaling to a research cost at a given completion level.
 fn scale_cost(base_cost: &ResearchCost, scaling: &CostScaling, level: u32) -> ResearchCost {
     match base_cost {
@@ -776,18 +1705,7 @@ fn scale_cost(base_cost: &ResearchCost, scaling: &CostScaling, level: u32) -> Re
                             base_qty.saturating_mul(factor.max(1))
                         }
                         CostScaling::Exponential { multiplier, .. } => {
-                            let mut cost = Fixed64::from_num(*base_qty);
-                            for _ in 0..level {
-                                cost = cost.saturating_mul(*multiplier);
-                            }
-                            let result: i64 = cost.to_num();
-                            if result < 0 {
-                                0
-                            } else if result > u32::MAX as i64 {
-                                u32::MAX
-                            } else {
-                                result as u32
-                            }
+                            clamp_to_u32(exponential_cost(*base_qty, *multiplier, level))
                         }
                     };
                     (*item, base_scaling)
@@ -813,18 +1731,7 @@ fn scale_cost(base_cost: &ResearchCost, scaling: &CostScaling, level: u32) -> Re
                             base_qty.saturating_mul(factor.max(1))
                         }
                         CostScaling::Exponential { multiplier, .. } => {
-                            let mut cost = Fixed64::from_num(*base_qty);
-                            for _ in 0..level {
-                                cost = cost.saturating_mul(*multiplier);
-                            }
-                            let result: i64 = cost.to_num();
-                            if result < 0 {
-                                0
-                            } else if result > u32::MAX as i64 {
-                                u32::MAX
-                            } else {
-                                result as u32
-                            }
+                            clamp_to_u32(exponential_cost(*base_qty, *multiplier, level))
                         }
                     };
                     (*item, scaled_qty)
@@ -862,11 +1769,9 @@ fn scale_cost(base_cost: &ResearchCost, scaling: &CostScaling, level: u32) -> Re
                     scaled as Ticks
                 }
                 CostScaling::Exponential { base, multiplier } => {
-                    let mut cost = Fixed64::from_num(*base);
-                    for _ in 0..level {
-                        cost = cost.saturating_mul(*multiplier);
-                    }
-                    let result: i64 = cost.to_num();
+                    let result: i64 = exponential_cost(*base, *multiplier, level)
+                        .round_dp(0, RoundingMode::Floor)
+                        .to_num();
                     if result < 0 { 0 } else { result as Ticks }
                 }
             };
@@ -877,6 +1782,23 @@ fn scale_cost(base_cost: &ResearchCost, scaling: &CostScaling, level: u32) -> Re
                 duration: scaled_duration,
             }
         }
+        ResearchCost::Threshold { item, amount } => {
+            // Scale the amount like a single Items entry.
+            let scaled = match scaling {
+                CostScaling::Linear { base, increment } => {
+                    let scaled_total = base.saturating_add(increment.saturating_mul(level));
+                    let factor = if *base == 0 { 1 } else { scaled_total / base };
+                    amount.saturating_mul(factor.max(1))
+                }
+                CostScaling::Exponential { multiplier, .. } => {
+                    clamp_to_u32(exponential_cost(*amount, *multiplier, level))
+                }
+            };
+            ResearchCost::Threshold {
+                item: *item,
+                amount: scaled,
+            }
+        }
         ResearchCost::Custom(fn_id) => {
             // Custom costs are not scalable by the engine; game code handles it.
             ResearchCost::Custom(*fn_id)
@@ -892,6 +1814,7 @@ fn scale_cost(base_cost: &ResearchCost, scaling: &CostScaling, level: u32) -> Re
 mod tests {
     use super::*;
     use factorial_core::fixed::Fixed64;
+    use factorial_core::graph::MutationRejectReason;
 
     // -----------------------------------------------------------------------
     // Helpers
@@ -956,6 +1879,36 @@ mod tests {
         tree
     }
 
+    #[test]
+    fn state_hash_tracks_research_progress() {
+        let mut a = setup_linear_tree();
+        let mut b = setup_linear_tree();
+        assert_eq!(a.state_hash(), b.state_hash());
+
+        a.start_research(TechId(0), 0).unwrap();
+        b.start_research(TechId(0), 0).unwrap();
+        a.contribute_points(TechId(0), 10, 1).unwrap();
+        assert_ne!(a.state_hash(), b.state_hash());
+        b.contribute_points(TechId(0), 10, 1).unwrap();
+        assert_eq!(a.state_hash(), b.state_hash());
+
+        let mut engine =
+            factorial_core::engine::Engine::new(factorial_core::sim::SimulationStrategy::Tick);
+        engine.register_module(Box::new(a));
+        engine.step();
+        // The modules part also covers the enabled-extension set.
+        let mut bare =
+            factorial_core::engine::Engine::new(factorial_core::sim::SimulationStrategy::Tick);
+        bare.enable_extension(ExtensionId::TechTree);
+        bare.step();
+        assert_eq!(
+            engine.state_hash_breakdown().modules,
+            bare.state_hash_breakdown()
+                .modules
+                .wrapping_add(b.state_hash())
+        );
+    }
+
     // -----------------------------------------------------------------------
     // Test 1: Prerequisites block research until met
     // -----------------------------------------------------------------------
@@ -1065,11 +2018,11 @@ mod tests {
 
         tree.start_research(TechId(0), 0).unwrap();
 
-        let consumed = tree.contribute_points(TechId(0), 60, 1).unwrap();
+        let consumed = tree.contribute_points(TechId(0), 60, 1).unwrap().consumed;
         assert_eq!(consumed, 60);
         assert!(tree.is_in_progress(TechId(0)));
 
-        let consumed = tree.contribute_points(TechId(0), 60, 2).unwrap();
+        let consumed = tree.contribute_points(TechId(0), 60, 2).unwrap().consumed;
         assert_eq!(consumed, 40); // Only 40 remaining.
         assert!(tree.is_completed(TechId(0)));
     }
@@ -1112,28 +2065,234 @@ mod tests {
             name: "Test".to_string(),
             prerequisites: vec![],
             cost: ResearchCost::Rate {
-                points_per_tick: Fixed64::from_num(10),
-                total: Fixed64::from_num(50),
+                points_per_tick: Fixed64::from_num(10),
+                total: Fixed64::from_num(50),
+            },
+            unlocks: vec![],
+            repeatable: false,
+            cost_scaling: None,
+        })
+        .unwrap();
+
+        tree.start_research(TechId(0), 0).unwrap();
+
+        // Tick 4 times (40 points), should not complete.
+        for tick in 1..=4 {
+            let complete = tree.tick_rate(TechId(0), tick).unwrap();
+            assert!(!complete);
+        }
+        assert!(tree.is_in_progress(TechId(0)));
+
+        // Tick once more (50 points), should complete.
+        let complete = tree.tick_rate(TechId(0), 5).unwrap();
+        assert!(complete);
+        assert!(tree.is_completed(TechId(0)));
+    }
+
+    /// An engine with `fed` labs stocked with red science and `starved`
+    /// labs with nothing to consume.
+    fn threshold_tree(amount: u32) -> TechTree {
+        let mut tree = TechTree::new();
+        tree.register(Technology {
+            id: TechId(0),
+            name: "Mass Production".to_string(),
+            prerequisites: vec![],
+            cost: ResearchCost::Threshold {
+                item: factorial_core::test_utils::iron(),
+                amount,
+            },
+            unlocks: vec![Unlock::Building(steel_furnace())],
+            repeatable: false,
+            cost_scaling: None,
+        })
+        .unwrap();
+        tree
+    }
+
+    #[test]
+    fn threshold_research_completes_from_production_events() {
+        use factorial_core::sim::SimulationStrategy;
+        use factorial_core::test_utils::{add_node, iron, make_source};
+
+        let mut engine = Engine::new(SimulationStrategy::Tick);
+        add_node(&mut engine, make_source(iron(), 1.0), 0, 1000);
+        engine.enable_event_log();
+        let mut tree = threshold_tree(10);
+        tree.start_research(TechId(0), 0).unwrap();
+        tree.drain_events();
+
+        let mut run = |tree: &mut TechTree, steps| {
+            for _ in 0..steps {
+                engine.step();
+            }
+            for event in engine.take_event_log().unwrap().events() {
+                tree.process_event(event);
+            }
+        };
+        run(&mut tree, 4);
+        assert!(tree.is_in_progress(TechId(0)));
+        assert_eq!(tree.produced_total(iron()), 4);
+        assert_eq!(
+            tree.progress_fraction(TechId(0)),
+            Some(Fixed64::from_num(4) / Fixed64::from_num(10))
+        );
+        assert!(tree.drain_events().is_empty());
+
+        run(&mut tree, 6);
+        assert!(tree.is_completed(TechId(0)));
+        assert_eq!(tree.progress_fraction(TechId(0)), None);
+        assert_eq!(
+            tree.drain_events(),
+            vec![TechEvent::ResearchCompleted {
+                tech_id: TechId(0),
+                unlocks: vec![Unlock::Building(steel_furnace())],
+                level: 1,
+                tick: 9,
+            }]
+        );
+    }
+
+    #[test]
+    fn threshold_research_counts_earlier_production_of_its_item_only() {
+        use factorial_core::test_utils::{copper, iron};
+
+        let mut tree = threshold_tree(100);
+        tree.record_production(iron(), 30, 1);
+        tree.record_production(copper(), 500, 2);
+        tree.start_research(TechId(0), 3).unwrap();
+        assert_eq!(
+            tree.progress_fraction(TechId(0)),
+            Some(Fixed64::from_num(3) / Fixed64::from_num(10))
+        );
+
+        // Progress survives a save, and the totals feed the state hash.
+        let hash = tree.state_hash();
+        let json = serde_json::to_string(&tree).unwrap();
+        let mut tree: TechTree = serde_json::from_str(&json).unwrap();
+        assert_eq!(tree.state_hash(), hash);
+        tree.record_production(copper(), 1000, 4);
+        assert_ne!(tree.state_hash(), hash);
+        assert!(tree.is_in_progress(TechId(0)));
+
+        tree.record_production(iron(), 70, 5);
+        assert!(tree.is_completed(TechId(0)));
+
+        // Production already past the amount completes it on start.
+        let mut tree = threshold_tree(10);
+        tree.record_production(iron(), 12, 1);
+        tree.start_research(TechId(0), 2).unwrap();
+        assert!(tree.is_completed(TechId(0)));
+        assert!(matches!(
+            tree.drain_events().as_slice(),
+            [
+                TechEvent::ResearchStarted { .. },
+                TechEvent::ResearchCompleted { tick: 2, .. }
+            ]
+        ));
+    }
+
+    fn lab_engine(fed: usize, starved: usize) -> (Engine, Vec<NodeId>) {
+        use factorial_core::processor::{DemandProcessor, Processor};
+        use factorial_core::sim::SimulationStrategy;
+        use factorial_core::test_utils;
+
+        let mut engine = Engine::new(SimulationStrategy::Tick);
+        let mut labs = Vec::new();
+        for i in 0..fed + starved {
+            let lab = test_utils::add_node(
+                &mut engine,
+                Processor::Demand(DemandProcessor {
+                    input_type: red_science(),
+                    base_rate: Fixed64::ONE,
+                    accumulated: Fixed64::ZERO,
+                    consumed_total: 0,
+                    accepted_types: None,
+                }),
+                1000,
+                10,
+            );
+            if i < fed {
+                let _ = engine.get_input_inventory_mut(lab).unwrap().input_slots[0]
+                    .add(red_science(), 1000);
+            }
+            labs.push(lab);
+        }
+        (engine, labs)
+    }
+
+    fn rate_tech_tree() -> TechTree {
+        let mut tree = TechTree::new();
+        tree.register(Technology {
+            id: TechId(0),
+            name: "Test".to_string(),
+            prerequisites: vec![],
+            cost: ResearchCost::Rate {
+                points_per_tick: Fixed64::from_num(3),
+                total: Fixed64::from_num(60),
             },
             unlocks: vec![],
             repeatable: false,
             cost_scaling: None,
         })
         .unwrap();
-
         tree.start_research(TechId(0), 0).unwrap();
+        tree
+    }
 
-        // Tick 4 times (40 points), should not complete.
-        for tick in 1..=4 {
-            let complete = tree.tick_rate(TechId(0), tick).unwrap();
-            assert!(!complete);
+    /// Step the engine and research until the tech completes; returns the
+    /// number of ticks taken.
+    fn ticks_to_research(engine: &mut Engine, labs: &[NodeId]) -> u64 {
+        let mut tree = rate_tech_tree();
+        for tick in 1..=1000 {
+            engine.step();
+            let throughput = lab_throughput(engine, labs);
+            if tree.tick_rate_scaled(TechId(0), throughput, tick).unwrap() {
+                return tick;
+            }
         }
-        assert!(tree.is_in_progress(TechId(0)));
+        panic!("research never completed");
+    }
 
-        // Tick once more (50 points), should complete.
-        let complete = tree.tick_rate(TechId(0), 5).unwrap();
-        assert!(complete);
-        assert!(tree.is_completed(TechId(0)));
+    #[test]
+    fn two_labs_research_twice_as_fast() {
+        let (mut one, one_labs) = lab_engine(1, 0);
+        let (mut two, two_labs) = lab_engine(2, 0);
+        assert_eq!(ticks_to_research(&mut one, &one_labs), 20);
+        assert_eq!(ticks_to_research(&mut two, &two_labs), 10);
+    }
+
+    #[test]
+    fn stalled_labs_contribute_nothing() {
+        let (mut engine, labs) = lab_engine(0, 2);
+        let mut tree = rate_tech_tree();
+        engine.step();
+        assert_eq!(lab_throughput(&engine, &labs), Fixed64::ZERO);
+        assert!(!tree.tick_rate_scaled(TechId(0), Fixed64::ZERO, 1).unwrap());
+        assert!(
+            !tree
+                .tick_rate_scaled(TechId(0), Fixed64::from_num(-4), 2)
+                .unwrap()
+        );
+        assert!(matches!(
+            tree.get_state(TechId(0)),
+            Some(ResearchState::InProgress(ResearchProgress::Rate(p))) if *p == Fixed64::ZERO
+        ));
+    }
+
+    #[test]
+    fn lab_speed_modifiers_scale_throughput() {
+        use factorial_core::processor::{Modifier, ModifierKind};
+        let (mut engine, labs) = lab_engine(1, 0);
+        engine.set_modifiers(
+            labs[0],
+            vec![Modifier {
+                id: factorial_core::id::ModifierId(0),
+                kind: ModifierKind::Speed(Fixed64::from_num(1.5)),
+                stacking: Default::default(),
+            }],
+        );
+        engine.step();
+        assert_eq!(lab_throughput(&engine, &labs), Fixed64::from_num(1.5));
     }
 
     // -----------------------------------------------------------------------
@@ -1553,6 +2712,33 @@ mod tests {
         assert_eq!(scaling.cost_at_level(3), 800);
     }
 
+    #[test]
+    fn cost_scaling_exponential_matches_iteration_and_saturates() {
+        let scaling = CostScaling::Exponential {
+            base: 100,
+            multiplier: Fixed64::from_num(1.5),
+        };
+        for level in 0..20 {
+            let mut cost = Fixed64::from_num(100);
+            for _ in 0..level {
+                cost = cost.saturating_mul(Fixed64::from_num(1.5));
+            }
+            assert_eq!(scaling.cost_at_level(level), cost.to_num::<u32>());
+        }
+
+        // Huge levels saturate instead of looping or overflowing.
+        assert_eq!(scaling.cost_at_level(u32::MAX), i32::MAX as u32);
+        let cost = scale_cost(
+            &ResearchCost::Items(vec![(red_science(), 10)]),
+            &scaling,
+            1_000_000,
+        );
+        assert_eq!(
+            cost,
+            ResearchCost::Items(vec![(red_science(), i32::MAX as u32)])
+        );
+    }
+
     // -----------------------------------------------------------------------
     // Test 21: prerequisites_met query
     // -----------------------------------------------------------------------
@@ -1878,4 +3064,587 @@ mod tests {
         let cost = restored.effective_cost(TechId(0)).unwrap();
         assert_eq!(cost, ResearchCost::Points(250));
     }
+
+    // -----------------------------------------------------------------------
+    // Strict-mode building gates
+    // -----------------------------------------------------------------------
+
+    fn strict_engine_with_gate() -> factorial_core::engine::Engine {
+        let mut tree = setup_linear_tree();
+        tree.gate_building(steel_furnace(), TechId(1)).unwrap();
+        let mut engine =
+            factorial_core::engine::Engine::new(factorial_core::sim::SimulationStrategy::Tick);
+        engine.register_module(Box::new(tree));
+        engine.set_strict_mode(true);
+        engine
+    }
+
+    #[test]
+    fn strict_mode_rejects_locked_building() {
+        let mut engine = strict_engine_with_gate();
+        let pending = engine.graph.queue_add_node(steel_furnace());
+        let result = engine.apply_mutations();
+
+        assert!(result.added_nodes.is_empty());
+        assert_eq!(
+            result.rejected,
+            vec![(pending, MutationRejectReason::BuildingLocked)]
+        );
+        assert_eq!(engine.graph.node_count(), 0);
+    }
+
+    #[test]
+    fn strict_mode_allows_ungated_and_unlocked_buildings() {
+        let mut engine = strict_engine_with_gate();
+        let pending = engine.graph.queue_add_node(BuildingTypeId(99));
+        let result = engine.apply_mutations();
+        assert!(result.resolve_node(pending).is_some());
+        assert!(result.rejected.is_empty());
+
+        // Completing the gating tech permits the very next apply.
+        let tree = engine.find_module_mut::<TechTree>().unwrap();
+        tree.start_research(TechId(0), 0).unwrap();
+        tree.contribute_points(TechId(0), 100, 1).unwrap();
+        tree.start_research(TechId(1), 2).unwrap();
+        tree.contribute_items(TechId(1), &[(red_science(), 50), (green_science(), 50)], 3)
+            .unwrap();
+
+        let pending = engine.graph.queue_add_node(steel_furnace());
+        let result = engine.apply_mutations();
+        assert!(result.resolve_node(pending).is_some());
+        assert!(result.rejected.is_empty());
+    }
+
+    #[test]
+    fn strict_mode_applies_allowed_subset_of_mixed_batch() {
+        let mut engine = strict_engine_with_gate();
+        let a = engine.graph.queue_add_node(BuildingTypeId(1));
+        let locked_1 = engine.graph.queue_add_node(steel_furnace());
+        let b = engine.graph.queue_add_node(BuildingTypeId(2));
+        let locked_2 = engine.graph.queue_add_node(steel_furnace());
+        let result = engine.apply_mutations();
+
+        assert_eq!(
+            result
+                .added_nodes
+                .iter()
+                .map(|&(p, _)| p)
+                .collect::<Vec<_>>(),
+            vec![a, b]
+        );
+        assert_eq!(
+            result.rejected,
+            vec![
+                (locked_1, MutationRejectReason::BuildingLocked),
+                (locked_2, MutationRejectReason::BuildingLocked),
+            ]
+        );
+        assert_eq!(engine.graph.node_count(), 2);
+
+        // Without strict mode the gate is not enforced.
+        engine.set_strict_mode(false);
+        engine.graph.queue_add_node(steel_furnace());
+        assert!(engine.apply_mutations().rejected.is_empty());
+    }
+
+    // -----------------------------------------------------------------------
+    // Milestones
+    // -----------------------------------------------------------------------
+
+    /// Three independent Points techs 10, 11, 12 behind a "tier 1" milestone.
+    fn tier_one_tree() -> TechTree {
+        let mut tree = TechTree::new();
+        for id in 10..13 {
+            tree.register(Technology {
+                id: TechId(id),
+                name: format!("Tier 1 #{id}"),
+                prerequisites: vec![],
+                cost: ResearchCost::Points(10),
+                unlocks: vec![],
+                repeatable: true,
+                cost_scaling: None,
+            })
+            .unwrap();
+        }
+        tree.define_milestone("tier 1", vec![TechId(10), TechId(11), TechId(12)])
+            .unwrap();
+        tree
+    }
+
+    fn research(tree: &mut TechTree, id: u32, tick: Ticks) {
+        tree.start_research(TechId(id), tick).unwrap();
+        tree.contribute_points(TechId(id), 10, tick).unwrap();
+    }
+
+    fn milestone_events(events: &[TechEvent]) -> Vec<(String, Ticks)> {
+        events
+            .iter()
+            .filter_map(|e| match e {
+                TechEvent::MilestoneReached { name, tick } => Some((name.clone(), *tick)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn milestone_fires_once_when_the_last_tech_completes() {
+        let mut tree = tier_one_tree();
+        research(&mut tree, 12, 1);
+        research(&mut tree, 10, 2);
+        assert!(milestone_events(&tree.drain_events()).is_empty());
+        assert!(!tree.milestone("tier 1").unwrap().reached);
+
+        research(&mut tree, 11, 3);
+        let events = tree.drain_events();
+        assert_eq!(milestone_events(&events), [("tier 1".to_string(), 3)]);
+        // It follows the completion that triggered it.
+        assert!(matches!(
+            events[events.len() - 2],
+            TechEvent::ResearchCompleted {
+                tech_id: TechId(11),
+                ..
+            }
+        ));
+        assert!(tree.milestone("tier 1").unwrap().reached);
+
+        // Researching a repeatable tech again does not fire it twice.
+        research(&mut tree, 11, 4);
+        assert!(milestone_events(&tree.drain_events()).is_empty());
+    }
+
+    #[test]
+    fn milestone_progress_survives_serialization() {
+        let mut tree = tier_one_tree();
+        research(&mut tree, 10, 1);
+        research(&mut tree, 11, 2);
+        tree.drain_events();
+
+        let json = serde_json::to_string(&tree).unwrap();
+        let mut restored: TechTree = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.milestones(), tree.milestones());
+        assert_eq!(restored.state_hash(), tree.state_hash());
+
+        research(&mut restored, 12, 3);
+        assert_eq!(
+            milestone_events(&restored.drain_events()),
+            [("tier 1".to_string(), 3)]
+        );
+        let restored: TechTree =
+            serde_json::from_str(&serde_json::to_string(&restored).unwrap()).unwrap();
+        assert!(restored.milestone("tier 1").unwrap().reached);
+    }
+
+    #[test]
+    fn milestone_definition_is_validated() {
+        let mut tree = tier_one_tree();
+        assert!(matches!(
+            tree.define_milestone("tier 1", vec![TechId(10)]),
+            Err(TechTreeError::DuplicateMilestone(_))
+        ));
+        assert!(matches!(
+            tree.define_milestone("missing", vec![TechId(10), TechId(99)]),
+            Err(TechTreeError::TechNotFound(TechId(99)))
+        ));
+
+        // Already satisfied: reached at once, silently.
+        research(&mut tree, 10, 1);
+        tree.drain_events();
+        tree.define_milestone("first", vec![TechId(10)]).unwrap();
+        assert!(tree.milestone("first").unwrap().reached);
+        assert!(tree.pending_events().is_empty());
+    }
+
+    #[test]
+    fn milestones_are_in_the_state_hash() {
+        let mut with = setup_linear_tree();
+        let without = setup_linear_tree();
+        with.define_milestone("none", vec![]).unwrap();
+        assert_ne!(with.state_hash(), without.state_hash());
+    }
+
+    fn quarters() -> Vec<Fixed64> {
+        vec![
+            Fixed64::from_num(0.75),
+            Fixed64::from_num(0.25),
+            Fixed64::from_num(0.5),
+        ]
+    }
+
+    fn progress_events(events: &[TechEvent]) -> Vec<(u32, f64, Ticks)> {
+        events
+            .iter()
+            .filter_map(|e| match e {
+                TechEvent::ResearchProgress {
+                    tech_id,
+                    fraction,
+                    tick,
+                } => Some((tech_id.0, fraction.to_num::<f64>(), *tick)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn progress_thresholds_fire_once_each_in_order() {
+        let mut tree = tier_one_tree();
+        tree.set_progress_thresholds(quarters()).unwrap();
+        tree.start_research(TechId(10), 0).unwrap();
+        tree.drain_events();
+
+        // 0.2: below the first threshold.
+        tree.contribute_points(TechId(10), 2, 1).unwrap();
+        assert!(progress_events(&tree.drain_events()).is_empty());
+
+        // 0.6: crosses 25% and 50% in one contribution.
+        tree.contribute_points(TechId(10), 4, 2).unwrap();
+        assert_eq!(
+            progress_events(&tree.drain_events()),
+            [(10, 0.25, 2), (10, 0.5, 2)]
+        );
+
+        // 0.7: still in the same band, so nothing fires again.
+        tree.contribute_points(TechId(10), 1, 3).unwrap();
+        assert!(progress_events(&tree.drain_events()).is_empty());
+        assert_eq!(
+            tree.progress_fraction(TechId(10)),
+            Some(Fixed64::from_num(7) / Fixed64::from_num(10))
+        );
+
+        // Landing exactly on a threshold counts as crossing it.
+        let mut tree = tier_one_tree();
+        tree.set_progress_thresholds(vec![Fixed64::from_num(0.5)])
+            .unwrap();
+        tree.start_research(TechId(11), 0).unwrap();
+        tree.contribute_points(TechId(11), 5, 1).unwrap();
+        assert_eq!(progress_events(&tree.drain_events()), [(11, 0.5, 1)]);
+    }
+
+    #[test]
+    fn completing_contribution_fires_remaining_thresholds_first() {
+        let mut tree = tier_one_tree();
+        tree.set_progress_thresholds(quarters()).unwrap();
+        tree.start_research(TechId(10), 0).unwrap();
+        tree.contribute_points(TechId(10), 3, 1).unwrap();
+        tree.drain_events();
+
+        tree.contribute_points(TechId(10), 7, 2).unwrap();
+        let events = tree.drain_events();
+        assert_eq!(progress_events(&events), [(10, 0.5, 2), (10, 0.75, 2)]);
+        assert!(matches!(events[2], TechEvent::ResearchCompleted { .. }));
+        assert_eq!(tree.progress_fraction(TechId(10)), None);
+    }
+
+    #[test]
+    fn progress_thresholds_reset_on_start_and_cancel() {
+        let mut tree = tier_one_tree();
+        tree.set_progress_thresholds(quarters()).unwrap();
+        tree.start_research(TechId(10), 0).unwrap();
+        tree.contribute_points(TechId(10), 6, 1).unwrap();
+        assert_eq!(progress_events(&tree.drain_events()).len(), 2);
+
+        tree.cancel_research(TechId(10)).unwrap();
+        assert_eq!(tree.get_state(TechId(10)), Some(&ResearchState::NotStarted));
+        assert!(matches!(
+            tree.cancel_research(TechId(10)),
+            Err(TechTreeError::NotInProgress(TechId(10)))
+        ));
+
+        tree.start_research(TechId(10), 2).unwrap();
+        tree.contribute_points(TechId(10), 6, 3).unwrap();
+        assert_eq!(
+            progress_events(&tree.drain_events()),
+            [(10, 0.25, 3), (10, 0.5, 3)]
+        );
+
+        // A repeatable tech cancelled after a completion goes back to
+        // completed, and its next round fires every threshold again.
+        tree.contribute_points(TechId(10), 4, 4).unwrap();
+        tree.start_research(TechId(10), 5).unwrap();
+        tree.contribute_points(TechId(10), 8, 5).unwrap();
+        tree.cancel_research(TechId(10)).unwrap();
+        assert_eq!(
+            tree.get_state(TechId(10)),
+            Some(&ResearchState::Completed { times_completed: 1 })
+        );
+        tree.drain_events();
+        tree.start_research(TechId(10), 6).unwrap();
+        tree.contribute_points(TechId(10), 9, 7).unwrap();
+        assert_eq!(progress_events(&tree.drain_events()).len(), 3);
+    }
+
+    #[test]
+    fn progress_thresholds_are_validated_and_survive_serialization() {
+        let mut tree = tier_one_tree();
+        for bad in [0.0, 1.0, -0.5] {
+            assert!(matches!(
+                tree.set_progress_thresholds(vec![Fixed64::from_num(bad)]),
+                Err(TechTreeError::InvalidProgressThreshold(_))
+            ));
+        }
+        tree.set_progress_thresholds(vec![
+            Fixed64::from_num(0.5),
+            Fixed64::from_num(0.25),
+            Fixed64::from_num(0.5),
+        ])
+        .unwrap();
+        assert_eq!(
+            tree.progress_thresholds(),
+            [Fixed64::from_num(0.25), Fixed64::from_num(0.5)]
+        );
+
+        tree.start_research(TechId(10), 0).unwrap();
+        tree.contribute_points(TechId(10), 3, 1).unwrap();
+        let json = serde_json::to_string(&tree).unwrap();
+        let mut restored: TechTree = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.state_hash(), tree.state_hash());
+        restored.drain_events();
+        restored.contribute_points(TechId(10), 3, 2).unwrap();
+        assert_eq!(progress_events(&restored.drain_events()), [(10, 0.5, 2)]);
+    }
+
+    #[test]
+    fn progress_fraction_covers_each_cost_model() {
+        let mut tree = setup_linear_tree();
+        assert_eq!(tree.progress_fraction(TechId(0)), None);
+        tree.start_research(TechId(0), 0).unwrap();
+        assert_eq!(tree.progress_fraction(TechId(0)), Some(Fixed64::ZERO));
+        tree.contribute_points(TechId(0), 100, 1).unwrap();
+
+        // Items count every required item equally; surplus is not consumed.
+        tree.start_research(TechId(1), 2).unwrap();
+        tree.contribute_items(TechId(1), &[(red_science(), 80)], 3)
+            .unwrap();
+        assert_eq!(
+            tree.progress_fraction(TechId(1)),
+            Some(Fixed64::from_num(0.5))
+        );
+
+        let mut tree = rate_tech_tree();
+        tree.tick_rate(TechId(0), 1).unwrap();
+        assert_eq!(
+            tree.progress_fraction(TechId(0)),
+            Some(Fixed64::from_num(3) / Fixed64::from_num(60))
+        );
+    }
+
+    fn points_tech(id: u32, repeatable: bool) -> Technology {
+        Technology {
+            id: TechId(id),
+            name: format!("tech {id}"),
+            prerequisites: vec![],
+            cost: ResearchCost::Points(10),
+            unlocks: vec![],
+            repeatable,
+            cost_scaling: None,
+        }
+    }
+
+    /// 1000 independent technologies; the repeatable ones are every 100th.
+    fn thousand_techs() -> TechTree {
+        let mut tree = TechTree::new();
+        for id in 0..1000 {
+            tree.register(points_tech(id, id % 100 == 0)).unwrap();
+        }
+        tree
+    }
+
+    #[test]
+    fn completion_bitset_restores_a_large_tree() {
+        let mut tree = thousand_techs();
+        for id in (0..1000).step_by(2) {
+            tree.start_research(TechId(id), 0).unwrap();
+            tree.contribute_points(TechId(id), 10, 1).unwrap();
+        }
+        tree.start_research(TechId(1), 2).unwrap();
+        tree.contribute_points(TechId(1), 4, 3).unwrap();
+
+        let bitset = tree.export_completion_bitset();
+        // Version, length, 250 bytes of states, empty side channel.
+        assert_eq!(bitset.len(), 1 + 2 + 250 + 1);
+        assert!(bitset.len() * 20 < serde_json::to_vec(&tree).unwrap().len());
+
+        let mut restored = thousand_techs();
+        restored.import_completion_bitset(&bitset).unwrap();
+        for id in 0..1000 {
+            assert_eq!(restored.is_completed(TechId(id)), id % 2 == 0);
+        }
+        // Progress is not in the bitset: the in-progress tech starts over.
+        assert_eq!(
+            restored.get_state(TechId(1)),
+            Some(&ResearchState::InProgress(ResearchProgress::Points(0)))
+        );
+        assert!(restored.pending_events().is_empty());
+
+        // With its progress restored separately, the trees are identical.
+        restored.contribute_points(TechId(1), 4, 3).unwrap();
+        assert_eq!(restored.state_hash(), tree.state_hash());
+        assert_eq!(restored.export_completion_bitset(), bitset);
+    }
+
+    #[test]
+    fn completion_bitset_keeps_repeatable_counts_in_the_side_channel() {
+        let mut tree = thousand_techs();
+        for _ in 0..3 {
+            tree.start_research(TechId(100), 0).unwrap();
+            tree.contribute_points(TechId(100), 1000, 1).unwrap();
+        }
+        // Level 4 in progress, with 3 completions.
+        tree.start_research(TechId(200), 0).unwrap();
+        tree.contribute_points(TechId(200), 10, 1).unwrap();
+        tree.start_research(TechId(200), 2).unwrap();
+        tree.contribute_points(TechId(200), 1, 3).unwrap();
+        tree.define_milestone("hundreds", vec![TechId(100), TechId(200)])
+            .unwrap();
+
+        let bitset = tree.export_completion_bitset();
+        let mut restored = thousand_techs();
+        restored
+            .define_milestone("hundreds", vec![TechId(100), TechId(200)])
+            .unwrap();
+        // Progress restored first is kept.
+        restored.start_research(TechId(200), 2).unwrap();
+        restored.contribute_points(TechId(200), 1, 3).unwrap();
+        restored.import_completion_bitset(&bitset).unwrap();
+
+        assert_eq!(restored.completion_count(TechId(100)), 3);
+        assert_eq!(
+            restored.get_state(TechId(100)),
+            Some(&ResearchState::Completed { times_completed: 3 })
+        );
+        assert_eq!(restored.completion_count(TechId(200)), 1);
+        assert!(restored.is_in_progress(TechId(200)));
+        assert!(restored.milestone("hundreds").unwrap().reached);
+        assert_eq!(restored.state_hash(), tree.state_hash());
+    }
+
+    #[test]
+    fn malformed_completion_bitsets_change_nothing() {
+        let mut tree = setup_linear_tree();
+        tree.start_research(TechId(0), 0).unwrap();
+        tree.contribute_points(TechId(0), 100, 1).unwrap();
+        let bitset = tree.export_completion_bitset();
+
+        let mut target = setup_linear_tree();
+        let before = target.state_hash();
+        for bad in [
+            &[][..],
+            &[9, 0, 0][..],
+            &bitset[..bitset.len() - 1],
+            &[bitset.as_slice(), &[0]].concat()[..],
+        ] {
+            assert!(matches!(
+                target.import_completion_bitset(bad),
+                Err(TechTreeError::InvalidBitset(_))
+            ));
+        }
+        // Tech 7 marked completed, but only 0..3 are registered.
+        assert!(matches!(
+            target.import_completion_bitset(&[1, 8, 0, 0x80, 0]),
+            Err(TechTreeError::TechNotFound(TechId(7)))
+        ));
+        assert_eq!(target.state_hash(), before);
+
+        target.import_completion_bitset(&bitset).unwrap();
+        assert_eq!(target.state_hash(), tree.state_hash());
+    }
+
+    /// Two +50% research speed techs (ids 0 and 1), a 300-point tech (2)
+    /// and a rate tech (3).
+    fn research_speed_tree() -> TechTree {
+        let mut tree = TechTree::new();
+        for id in 0..2 {
+            tree.register(Technology {
+                unlocks: vec![Unlock::ResearchSpeed(Fixed64::from_num(0.5))],
+                ..points_tech(id, false)
+            })
+            .unwrap();
+        }
+        tree.register(Technology {
+            cost: ResearchCost::Points(300),
+            ..points_tech(2, false)
+        })
+        .unwrap();
+        tree.register(Technology {
+            cost: ResearchCost::Rate {
+                points_per_tick: Fixed64::from_num(2),
+                total: Fixed64::from_num(9),
+            },
+            ..points_tech(3, false)
+        })
+        .unwrap();
+        for id in 0..2 {
+            tree.start_research(TechId(id), 0).unwrap();
+            tree.contribute_points(TechId(id), 10, 1).unwrap();
+        }
+        tree
+    }
+
+    #[test]
+    fn research_speed_bonuses_stack_multiplicatively() {
+        let mut tree = research_speed_tree();
+        // 1.5 * 1.5
+        assert_eq!(tree.research_speed_multiplier(), Fixed64::from_num(2.25));
+
+        // 133 points credit floor(299.25) = 299; 134 reach 300.
+        tree.start_research(TechId(2), 2).unwrap();
+        let contribution = tree.contribute_points(TechId(2), 300, 3).unwrap();
+        assert_eq!(
+            contribution,
+            PointsContribution {
+                consumed: 134,
+                credited: 300,
+            }
+        );
+        assert!(tree.is_completed(TechId(2)));
+
+        // Rate research gains 2 * 2.25 = 4.5 a tick: done on the second.
+        tree.start_research(TechId(3), 4).unwrap();
+        assert!(!tree.tick_rate(TechId(3), 5).unwrap());
+        assert!(tree.tick_rate(TechId(3), 6).unwrap());
+    }
+
+    #[test]
+    fn scaled_points_carry_fractions_between_contributions() {
+        let mut tree = research_speed_tree();
+        tree.start_research(TechId(2), 2).unwrap();
+        let mut consumed = 0;
+        let mut credited = 0;
+        while !tree.is_completed(TechId(2)) {
+            let contribution = tree.contribute_points(TechId(2), 1, 3).unwrap();
+            consumed += contribution.consumed;
+            credited += contribution.credited;
+            // Carried fractions are part of the saved state.
+            if consumed == 3 {
+                let json = serde_json::to_string(&tree).unwrap();
+                let restored: TechTree = serde_json::from_str(&json).unwrap();
+                assert_eq!(restored.state_hash(), tree.state_hash());
+                tree = restored;
+            }
+        }
+        assert_eq!((consumed, credited), (134, 300));
+    }
+
+    #[test]
+    fn research_speed_survives_saves_and_bitset_imports() {
+        let tree = research_speed_tree();
+        let json = serde_json::to_string(&tree).unwrap();
+        let restored: TechTree = serde_json::from_str(&json).unwrap();
+        assert_eq!(
+            restored.research_speed_multiplier(),
+            Fixed64::from_num(2.25)
+        );
+
+        let mut imported = research_speed_tree();
+        imported
+            .import_completion_bitset(&TechTree::new().export_completion_bitset())
+            .unwrap();
+        assert_eq!(imported.research_speed_multiplier(), Fixed64::ONE);
+        imported
+            .import_completion_bitset(&tree.export_completion_bitset())
+            .unwrap();
+        assert_eq!(
+            imported.research_speed_multiplier(),
+            Fixed64::from_num(2.25)
+        );
+    }
 }
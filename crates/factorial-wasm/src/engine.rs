@@ -4,8 +4,7 @@ use factorial_core::engine::Engine;
 use factorial_core::sim::SimulationStrategy;
 
 use crate::{
-    EVENT_CACHE, EngineSlot, HANDLE_TABLE, RESULT_INVALID_HANDLE, RESULT_OK,
-    register_event_listeners, with_engine,
+    EVENT_CACHE, EngineSlot, HANDLE_TABLE, RESULT_INVALID_HANDLE, RESULT_OK, with_engine_mut,
 };
 
 /// Create a new engine with `Tick` simulation strategy.
@@ -17,12 +16,7 @@ pub extern "C" fn factorial_create() -> i32 {
         let mut table = table.borrow_mut();
         for (i, slot) in table.iter_mut().enumerate() {
             if slot.is_none() {
-                let mut engine = Engine::new(SimulationStrategy::Tick);
-                register_event_listeners(&mut engine);
-                *slot = Some(EngineSlot {
-                    engine,
-                    event_cache: Vec::new(),
-                });
+                *slot = Some(EngineSlot::new(Engine::new(SimulationStrategy::Tick)));
                 return i as i32;
             }
         }
@@ -40,12 +34,9 @@ pub extern "C" fn factorial_create_delta(fixed_timestep: u64) -> i32 {
         let mut table = table.borrow_mut();
         for (i, slot) in table.iter_mut().enumerate() {
             if slot.is_none() {
-                let mut engine = Engine::new(SimulationStrategy::Delta { fixed_timestep });
-                register_event_listeners(&mut engine);
-                *slot = Some(EngineSlot {
-                    engine,
-                    event_cache: Vec::new(),
-                });
+                *slot = Some(EngineSlot::new(Engine::new(SimulationStrategy::Delta {
+                    fixed_timestep,
+                })));
                 return i as i32;
             }
         }
@@ -80,7 +71,8 @@ pub extern "C" fn factorial_destroy(handle: i32) -> i32 {
 #[unsafe(no_mangle)]
 pub extern "C" fn factorial_step(handle: i32) -> i32 {
     EVENT_CACHE.with(|c| c.borrow_mut().clear());
-    with_engine(handle, |slot| {
+    with_engine_mut(handle, |slot| {
+        slot.reset_dropped_events();
         slot.engine.step();
         RESULT_OK
     })
@@ -95,7 +87,8 @@ pub extern "C" fn factorial_step(handle: i32) -> i32 {
 #[unsafe(no_mangle)]
 pub extern "C" fn factorial_advance(handle: i32, dt: u64) -> i32 {
     EVENT_CACHE.with(|c| c.borrow_mut().clear());
-    with_engine(handle, |slot| {
+    with_engine_mut(handle, |slot| {
+        slot.reset_dropped_events();
         slot.engine.advance(dt);
         RESULT_OK
     })
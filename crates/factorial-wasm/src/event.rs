@@ -30,11 +30,7 @@ pub unsafe extern "C" fn factorial_poll_events(
         EVENT_CACHE.with(|c| {
             let cache = c.borrow();
             let event_size = std::mem::size_of::<FlatEvent>();
-            let max_events = if event_size > 0 {
-                out_len as usize / event_size
-            } else {
-                0
-            };
+            let max_events = (out_len as usize).checked_div(event_size).unwrap_or(0);
             let count = cache.len().min(max_events);
 
             if count > 0 {
@@ -51,6 +47,41 @@ pub unsafe extern "C" fn factorial_poll_events(
     })
 }
 
+/// Set the maximum number of events cached for [`factorial_poll_events`]
+/// per step. Events past the limit are counted by
+/// [`factorial_dropped_event_count`] instead of being cached. The cap only
+/// affects observation, never the simulation.
+///
+/// Returns [`RESULT_OK`] or [`crate::RESULT_INVALID_HANDLE`].
+#[unsafe(no_mangle)]
+pub extern "C" fn factorial_set_event_cache_limit(handle: i32, max_events: u32) -> i32 {
+    with_engine(handle, |slot| {
+        slot.event_cap.limit.set(max_events as usize);
+        RESULT_OK
+    })
+}
+
+/// Write the number of events dropped by the event cache limit since the
+/// last step to `*out_count_ptr`. A non-zero count means the events from
+/// [`factorial_poll_events`] are incomplete.
+///
+/// # Safety
+///
+/// `out_count_ptr` must be a valid, aligned pointer to a `u64`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_dropped_event_count(
+    handle: i32,
+    out_count_ptr: *mut u64,
+) -> i32 {
+    if out_count_ptr.is_null() {
+        return crate::RESULT_INTERNAL_ERROR;
+    }
+    with_engine(handle, |slot| {
+        unsafe { *out_count_ptr = slot.event_cap.dropped.get() };
+        RESULT_OK
+    })
+}
+
 // ---------------------------------------------------------------------------
 // Tests
 // ---------------------------------------------------------------------------
@@ -59,12 +90,16 @@ pub unsafe extern "C" fn factorial_poll_events(
 mod tests {
     use super::*;
     use crate::engine::{factorial_create, factorial_destroy, factorial_step};
-    use crate::graph::{factorial_add_node, factorial_apply_mutations};
+    use crate::graph::{factorial_add_node, factorial_apply_mutations, factorial_connect};
     use crate::processor::factorial_set_source;
     use crate::transport::factorial_set_output_capacity;
     use crate::{EVENT_CACHE, HANDLE_TABLE};
 
+    use factorial_core::builder::FactoryBuilder;
     use factorial_core::fixed::Fixed64;
+    use factorial_core::id::NodeId;
+    use factorial_core::test_utils::iron;
+    use std::collections::BTreeMap;
 
     fn cleanup() {
         HANDLE_TABLE.with(|t| {
@@ -130,6 +165,139 @@ mod tests {
         cleanup();
     }
 
+    fn poll_all(h: i32) -> Vec<FlatEvent> {
+        let event_size = std::mem::size_of::<FlatEvent>();
+        let mut buf = vec![0u8; event_size * 64];
+        let mut count: u32 = 0;
+        let rc =
+            unsafe { factorial_poll_events(h, buf.as_mut_ptr(), buf.len() as i32, &mut count) };
+        assert_eq!(rc, RESULT_OK);
+        (0..count as usize)
+            .map(|i| unsafe {
+                std::ptr::read_unaligned(buf.as_ptr().add(i * event_size) as *const FlatEvent)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn graph_events_stamped_with_upcoming_tick() {
+        cleanup();
+        let h = factorial_create();
+        factorial_step(h);
+        factorial_step(h);
+
+        // Two apply calls between step 2 and step 3.
+        let mut p1: u64 = 0;
+        let mut p2: u64 = 0;
+        unsafe { factorial_add_node(h, 0, &mut p1) };
+        unsafe { factorial_add_node(h, 0, &mut p2) };
+        let mut buf = [0u8; 256];
+        let mut written: i32 = 0;
+        unsafe { factorial_apply_mutations(h, buf.as_mut_ptr(), 256, &mut written) };
+        let n1 = u64::from_le_bytes(buf[16..24].try_into().unwrap());
+        let n2 = u64::from_le_bytes(buf[32..40].try_into().unwrap());
+        let mut pe: u64 = 0;
+        unsafe { factorial_connect(h, n1, n2, &mut pe) };
+        unsafe { factorial_apply_mutations(h, buf.as_mut_ptr(), 256, &mut written) };
+        let edge = u64::from_le_bytes(buf[16..24].try_into().unwrap());
+
+        // Not visible until the next step has run.
+        assert!(poll_all(h).is_empty());
+
+        factorial_step(h);
+        let events = poll_all(h);
+        assert_eq!(events.len(), 3);
+        assert!(events.iter().all(|e| e.tick == 2));
+        assert_eq!(events.iter().filter(|e| e.kind == 8).count(), 2);
+        let edge_event = events.iter().find(|e| e.kind == 10).unwrap();
+        assert_eq!(edge_event.edge, edge);
+        assert_eq!(edge_event.from_node, n1);
+        assert_eq!(edge_event.to_node, n2);
+
+        factorial_step(h);
+        assert!(poll_all(h).is_empty());
+
+        factorial_destroy(h);
+        cleanup();
+    }
+
+    /// Materialize a `FactoryBuilder` into the first free handle slot.
+    fn create_from_builder(builder: &FactoryBuilder) -> (i32, BTreeMap<String, NodeId>) {
+        let (engine, nodes) = builder.build().unwrap().into_parts();
+        (crate::install_engine(engine), nodes)
+    }
+
+    #[test]
+    fn builder_factory_events_through_wasm() {
+        cleanup();
+        let mut builder = FactoryBuilder::new();
+        builder.node("miner").source(iron(), 3.0);
+        builder.node("chest");
+        builder.belt("miner", "chest", 1.0);
+        let (h, nodes) = create_from_builder(&builder);
+
+        factorial_step(h);
+        let events = poll_all(h);
+        let added: Vec<u64> = events
+            .iter()
+            .filter(|e| e.kind == 8)
+            .map(|e| e.node)
+            .collect();
+        assert_eq!(
+            added,
+            vec![
+                crate::node_id_to_ffi(nodes["miner"]),
+                crate::node_id_to_ffi(nodes["chest"])
+            ]
+        );
+        assert_eq!(events.iter().filter(|e| e.kind == 10).count(), 1);
+
+        factorial_destroy(h);
+        cleanup();
+    }
+
+    /// Step 10k miners (one `ItemProduced` each per step) with the given
+    /// cache limit. Returns (cached, dropped, state hash).
+    fn step_ten_thousand_miners(limit: Option<u32>) -> (usize, u64, u64) {
+        cleanup();
+        let mut builder = FactoryBuilder::new();
+        for i in 0..10_000 {
+            builder.node(format!("miner{i}")).source(iron(), 1.0);
+        }
+        let (mut engine, _) = builder.build().unwrap().into_parts();
+        engine.event_bus = factorial_core::event::EventBus::new(20_000);
+        let h = crate::install_engine(engine);
+        // The first step also delivers the 10k `NodeAdded` events.
+        factorial_step(h);
+        if let Some(limit) = limit {
+            assert_eq!(factorial_set_event_cache_limit(h, limit), RESULT_OK);
+        }
+
+        factorial_step(h);
+        let cached = EVENT_CACHE.with(|c| c.borrow().len());
+        let mut dropped = 0u64;
+        let mut hash = 0u64;
+        unsafe {
+            assert_eq!(factorial_dropped_event_count(h, &mut dropped), RESULT_OK);
+            crate::query::factorial_get_state_hash(h, &mut hash);
+        }
+        factorial_destroy(h);
+        cleanup();
+        (cached, dropped, hash)
+    }
+
+    #[test]
+    fn event_cache_limit_drops_and_reports() {
+        let (cached, dropped, capped_hash) = step_ten_thousand_miners(Some(1_000));
+        assert_eq!(cached, 1_000);
+        assert_eq!(dropped, 9_000);
+
+        let (cached, dropped, hash) = step_ten_thousand_miners(None);
+        assert_eq!(cached, 10_000);
+        assert_eq!(dropped, 0);
+        assert_eq!(hash, capped_hash, "the cap must not affect simulation");
+    }
+
     #[test]
     fn poll_events_invalid_handle() {
         cleanup();
@@ -1,9 +1,11 @@
 //! Graph mutation WASM exports.
 
+use factorial_core::graph::MutationResult;
 use factorial_core::id::BuildingTypeId;
 
 use crate::{
-    RESULT_OK, edge_id_to_ffi, ffi_to_edge_id, ffi_to_node_id, node_id_to_ffi, with_engine,
+    RESULT_INDEX_OUT_OF_RANGE, RESULT_OK, edge_id_to_ffi, ffi_to_edge_id, ffi_to_node_id,
+    node_id_to_ffi, with_engine, with_engine_mut,
 };
 
 /// Queue a new node with the given building type. Writes the pending ID to
@@ -22,7 +24,7 @@ pub unsafe extern "C" fn factorial_add_node(
     if out_pending_ptr.is_null() {
         return crate::RESULT_INTERNAL_ERROR;
     }
-    with_engine(handle, |slot| {
+    with_engine_mut(handle, |slot| {
         let pending = slot
             .engine
             .graph
@@ -35,7 +37,7 @@ pub unsafe extern "C" fn factorial_add_node(
 /// Queue a node for removal.
 #[unsafe(no_mangle)]
 pub extern "C" fn factorial_remove_node(handle: i32, node_id: u64) -> i32 {
-    with_engine(handle, |slot| {
+    with_engine_mut(handle, |slot| {
         slot.engine.graph.queue_remove_node(ffi_to_node_id(node_id));
         RESULT_OK
     })
@@ -57,7 +59,7 @@ pub unsafe extern "C" fn factorial_connect(
     if out_pending_ptr.is_null() {
         return crate::RESULT_INTERNAL_ERROR;
     }
-    with_engine(handle, |slot| {
+    with_engine_mut(handle, |slot| {
         let pending = slot
             .engine
             .graph
@@ -70,7 +72,7 @@ pub unsafe extern "C" fn factorial_connect(
 /// Queue an edge for removal.
 #[unsafe(no_mangle)]
 pub extern "C" fn factorial_disconnect(handle: i32, edge_id: u64) -> i32 {
-    with_engine(handle, |slot| {
+    with_engine_mut(handle, |slot| {
         slot.engine.graph.queue_disconnect(ffi_to_edge_id(edge_id));
         RESULT_OK
     })
@@ -87,8 +89,18 @@ pub extern "C" fn factorial_disconnect(handle: i32, edge_id: u64) -> i32 {
 /// | 4      | 4    | `edge_count` (u32 LE) |
 /// | 8      | 16 * node_count | (pending_id: u64 LE, real_id: u64 LE) pairs |
 /// | ...    | 16 * edge_count | (pending_id: u64 LE, real_id: u64 LE) pairs |
+/// | ...    | 4    | `rejected_count` (u32 LE) |
+/// | ...    | 12 * rejected_count | (pending_id: u64 LE, reason: u32 LE) |
+///
+/// Rejected entries are node additions refused in strict mode; reason `1`
+/// means the building is locked behind incomplete research. The actual
+/// number of bytes written is stored in `*out_written_ptr`.
+///
+/// The resulting graph events are stamped with the upcoming tick and are
+/// returned by `factorial_poll_events` after the next step, not immediately.
 ///
-/// The actual number of bytes written is stored in `*out_written_ptr`.
+/// The id pairs are also kept for the `factorial_mutation_*` accessors, even
+/// when the buffer turns out to be too small.
 ///
 /// # Safety
 ///
@@ -104,12 +116,15 @@ pub unsafe extern "C" fn factorial_apply_mutations(
     if out_ptr.is_null() || out_written_ptr.is_null() {
         return crate::RESULT_INTERNAL_ERROR;
     }
-    with_engine(handle, |slot| {
-        let result = slot.engine.graph.apply_mutations();
+    with_engine_mut(handle, |slot| {
+        let result = slot.engine.apply_mutations();
+        slot.last_mutation = MutationRecord::from_result(&result);
 
         let node_count = result.added_nodes.len() as u32;
         let edge_count = result.added_edges.len() as u32;
-        let needed = 8 + (node_count as usize + edge_count as usize) * 16;
+        let rejected_count = result.rejected.len() as u32;
+        let needed =
+            8 + (node_count as usize + edge_count as usize) * 16 + 4 + rejected_count as usize * 12;
 
         if (out_len as usize) < needed {
             return crate::RESULT_INTERNAL_ERROR;
@@ -140,11 +155,268 @@ pub unsafe extern "C" fn factorial_apply_mutations(
             offset += 8;
         }
 
+        // Rejected node additions
+        buf[offset..offset + 4].copy_from_slice(&rejected_count.to_le_bytes());
+        offset += 4;
+        for &(pending, reason) in &result.rejected {
+            buf[offset..offset + 8].copy_from_slice(&pending.0.to_le_bytes());
+            offset += 8;
+            buf[offset..offset + 4].copy_from_slice(&(reason as u32).to_le_bytes());
+            offset += 4;
+        }
+
         unsafe { *out_written_ptr = needed as i32 };
         RESULT_OK
     })
 }
 
+// ---------------------------------------------------------------------------
+// Index-based mutation result accessors
+// ---------------------------------------------------------------------------
+
+/// The `(pending, real)` id pairs of the last applied mutation batch, kept in
+/// the engine slot until the next apply or destroy.
+#[derive(Debug, Default)]
+pub(crate) struct MutationRecord {
+    nodes: Vec<(u64, u64)>,
+    edges: Vec<(u64, u64)>,
+}
+
+impl MutationRecord {
+    fn from_result(result: &MutationResult) -> Self {
+        Self {
+            nodes: result
+                .added_nodes
+                .iter()
+                .map(|(pending, real)| (pending.0, node_id_to_ffi(*real)))
+                .collect(),
+            edges: result
+                .added_edges
+                .iter()
+                .map(|(pending, real)| (pending.0, edge_id_to_ffi(*real)))
+                .collect(),
+        }
+    }
+}
+
+/// Which id of a recorded pair an accessor reads.
+#[derive(Clone, Copy)]
+enum PairSide {
+    Pending,
+    Real,
+}
+
+/// Apply all queued graph mutations and keep the results in the engine slot
+/// for the `factorial_mutation_*` accessors, which stay valid until the next
+/// apply or [`crate::engine::factorial_destroy`].
+///
+/// This is the buffer-free counterpart of [`factorial_apply_mutations`] for
+/// hosts, such as JavaScript, that would rather not decode packed 64-bit
+/// fields out of linear memory.
+#[unsafe(no_mangle)]
+pub extern "C" fn factorial_apply_mutations_indexed(handle: i32) -> i32 {
+    with_engine_mut(handle, |slot| {
+        let result = slot.engine.apply_mutations();
+        slot.last_mutation = MutationRecord::from_result(&result);
+        RESULT_OK
+    })
+}
+
+/// Number of nodes added by the last applied batch, or `-1` if the handle is
+/// invalid.
+#[unsafe(no_mangle)]
+pub extern "C" fn factorial_mutation_added_node_count(handle: i32) -> i32 {
+    let mut count = -1;
+    with_engine(handle, |slot| {
+        count = slot.last_mutation.nodes.len() as i32;
+        RESULT_OK
+    });
+    count
+}
+
+/// Number of edges added by the last applied batch, or `-1` if the handle is
+/// invalid.
+#[unsafe(no_mangle)]
+pub extern "C" fn factorial_mutation_added_edge_count(handle: i32) -> i32 {
+    let mut count = -1;
+    with_engine(handle, |slot| {
+        count = slot.last_mutation.edges.len() as i32;
+        RESULT_OK
+    });
+    count
+}
+
+/// Write one id of the `index`th recorded pair as two 32-bit halves.
+///
+/// # Safety
+///
+/// `out_lo` and `out_hi` must be valid, aligned pointers to `u32`s.
+unsafe fn write_pair_id(
+    handle: i32,
+    edges: bool,
+    index: u32,
+    side: PairSide,
+    out_lo: *mut u32,
+    out_hi: *mut u32,
+) -> i32 {
+    if out_lo.is_null() || out_hi.is_null() {
+        return crate::RESULT_INTERNAL_ERROR;
+    }
+    with_engine(handle, |slot| {
+        let Some(id) = pair_id(&slot.last_mutation, edges, index, side) else {
+            return RESULT_INDEX_OUT_OF_RANGE;
+        };
+        unsafe {
+            *out_lo = id as u32;
+            *out_hi = (id >> 32) as u32;
+        }
+        RESULT_OK
+    })
+}
+
+/// Write one id of the `index`th recorded pair as decimal ASCII.
+///
+/// # Safety
+///
+/// `out_ptr` must be valid for `out_len` bytes and `out_written_ptr` must be
+/// a valid, aligned pointer to an `i32`.
+unsafe fn write_pair_id_str(
+    handle: i32,
+    edges: bool,
+    index: u32,
+    out_ptr: *mut u8,
+    out_len: i32,
+    out_written_ptr: *mut i32,
+) -> i32 {
+    if out_ptr.is_null() || out_written_ptr.is_null() {
+        return crate::RESULT_INTERNAL_ERROR;
+    }
+    with_engine(handle, |slot| {
+        let Some(id) = pair_id(&slot.last_mutation, edges, index, PairSide::Real) else {
+            return RESULT_INDEX_OUT_OF_RANGE;
+        };
+        let text = id.to_string();
+        unsafe { *out_written_ptr = text.len() as i32 };
+        if (out_len as usize) < text.len() {
+            return crate::RESULT_INTERNAL_ERROR;
+        }
+        let buf = unsafe { std::slice::from_raw_parts_mut(out_ptr, text.len()) };
+        buf.copy_from_slice(text.as_bytes());
+        RESULT_OK
+    })
+}
+
+fn pair_id(record: &MutationRecord, edges: bool, index: u32, side: PairSide) -> Option<u64> {
+    let pairs = if edges { &record.edges } else { &record.nodes };
+    let &(pending, real) = pairs.get(index as usize)?;
+    Some(match side {
+        PairSide::Pending => pending,
+        PairSide::Real => real,
+    })
+}
+
+/// Write the pending id of the `index`th added node as low and high 32-bit
+/// halves. Returns [`RESULT_INDEX_OUT_OF_RANGE`] past the end of the list.
+///
+/// # Safety
+///
+/// `out_lo` and `out_hi` must be valid, aligned pointers to `u32`s.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_mutation_added_node_pending(
+    handle: i32,
+    index: u32,
+    out_lo: *mut u32,
+    out_hi: *mut u32,
+) -> i32 {
+    unsafe { write_pair_id(handle, false, index, PairSide::Pending, out_lo, out_hi) }
+}
+
+/// Write the real id of the `index`th added node as low and high 32-bit
+/// halves. Returns [`RESULT_INDEX_OUT_OF_RANGE`] past the end of the list.
+///
+/// # Safety
+///
+/// `out_lo` and `out_hi` must be valid, aligned pointers to `u32`s.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_mutation_added_node_real(
+    handle: i32,
+    index: u32,
+    out_lo: *mut u32,
+    out_hi: *mut u32,
+) -> i32 {
+    unsafe { write_pair_id(handle, false, index, PairSide::Real, out_lo, out_hi) }
+}
+
+/// Write the real id of the `index`th added node as a decimal string (no
+/// terminator). `*out_written_ptr` receives the length needed; if `out_len`
+/// is too small nothing else is written and [`crate::RESULT_INTERNAL_ERROR`]
+/// is returned.
+///
+/// # Safety
+///
+/// `out_ptr` must be valid for `out_len` bytes and `out_written_ptr` must be
+/// a valid, aligned pointer to an `i32`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_mutation_added_node_real_str(
+    handle: i32,
+    index: u32,
+    out_ptr: *mut u8,
+    out_len: i32,
+    out_written_ptr: *mut i32,
+) -> i32 {
+    unsafe { write_pair_id_str(handle, false, index, out_ptr, out_len, out_written_ptr) }
+}
+
+/// Write the pending id of the `index`th added edge as low and high 32-bit
+/// halves. Returns [`RESULT_INDEX_OUT_OF_RANGE`] past the end of the list.
+///
+/// # Safety
+///
+/// `out_lo` and `out_hi` must be valid, aligned pointers to `u32`s.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_mutation_added_edge_pending(
+    handle: i32,
+    index: u32,
+    out_lo: *mut u32,
+    out_hi: *mut u32,
+) -> i32 {
+    unsafe { write_pair_id(handle, true, index, PairSide::Pending, out_lo, out_hi) }
+}
+
+/// Write the real id of the `index`th added edge as low and high 32-bit
+/// halves. Returns [`RESULT_INDEX_OUT_OF_RANGE`] past the end of the list.
+///
+/// # Safety
+///
+/// `out_lo` and `out_hi` must be valid, aligned pointers to `u32`s.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_mutation_added_edge_real(
+    handle: i32,
+    index: u32,
+    out_lo: *mut u32,
+    out_hi: *mut u32,
+) -> i32 {
+    unsafe { write_pair_id(handle, true, index, PairSide::Real, out_lo, out_hi) }
+}
+
+/// Write the real id of the `index`th added edge as a decimal string; see
+/// [`factorial_mutation_added_node_real_str`].
+///
+/// # Safety
+///
+/// `out_ptr` must be valid for `out_len` bytes and `out_written_ptr` must be
+/// a valid, aligned pointer to an `i32`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_mutation_added_edge_real_str(
+    handle: i32,
+    index: u32,
+    out_ptr: *mut u8,
+    out_len: i32,
+    out_written_ptr: *mut i32,
+) -> i32 {
+    unsafe { write_pair_id_str(handle, true, index, out_ptr, out_len, out_written_ptr) }
+}
+
 // ---------------------------------------------------------------------------
 // Tests
 // ---------------------------------------------------------------------------
@@ -258,7 +530,7 @@ mod tests {
         let mut buf3 = [0u8; 512];
         let mut written3: i32 = 0;
         unsafe { factorial_apply_mutations(h, buf3.as_mut_ptr(), 512, &mut written3) };
-        assert_eq!(written3, 8); // header only, no new nodes/edges
+        assert_eq!(written3, 12); // header and empty rejected list only
 
         // Step to verify engine is still healthy
         assert_eq!(factorial_step(h), RESULT_OK);
@@ -267,6 +539,155 @@ mod tests {
         cleanup();
     }
 
+    /// Refuses building type 7.
+    #[derive(Debug)]
+    struct LockSeven;
+
+    impl factorial_core::module::Module for LockSeven {
+        fn name(&self) -> &str {
+            "lock_seven"
+        }
+        fn allows_building(&self, building_type: BuildingTypeId) -> bool {
+            building_type.0 != 7
+        }
+        fn as_any(&self) -> &dyn std::any::Any {
+            self
+        }
+        fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+            self
+        }
+    }
+
+    #[test]
+    fn apply_reports_rejected_additions() {
+        cleanup();
+        let h = factorial_create();
+        with_engine(h, |slot| {
+            slot.engine.register_module(Box::new(LockSeven));
+            slot.engine.set_strict_mode(true);
+            RESULT_OK
+        });
+        let mut locked: u64 = 0;
+        let mut allowed: u64 = 0;
+        unsafe { factorial_add_node(h, 7, &mut locked) };
+        unsafe { factorial_add_node(h, 1, &mut allowed) };
+
+        let mut buf = [0u8; 256];
+        let mut written: i32 = 0;
+        unsafe { factorial_apply_mutations(h, buf.as_mut_ptr(), 256, &mut written) };
+        assert_eq!(written, 8 + 16 + 4 + 12);
+        assert_eq!(u32::from_le_bytes(buf[0..4].try_into().unwrap()), 1);
+        assert_eq!(u64::from_le_bytes(buf[8..16].try_into().unwrap()), allowed);
+        assert_eq!(u32::from_le_bytes(buf[24..28].try_into().unwrap()), 1);
+        assert_eq!(u64::from_le_bytes(buf[28..36].try_into().unwrap()), locked);
+        assert_eq!(u32::from_le_bytes(buf[36..40].try_into().unwrap()), 1);
+
+        factorial_destroy(h);
+        cleanup();
+    }
+
+    /// Read one side of a recorded pair through the split-id accessors.
+    fn read_split(
+        f: unsafe extern "C" fn(i32, u32, *mut u32, *mut u32) -> i32,
+        h: i32,
+        index: u32,
+    ) -> u64 {
+        let (mut lo, mut hi) = (0u32, 0u32);
+        assert_eq!(unsafe { f(h, index, &mut lo, &mut hi) }, RESULT_OK);
+        (hi as u64) << 32 | lo as u64
+    }
+
+    fn read_str(
+        f: unsafe extern "C" fn(i32, u32, *mut u8, i32, *mut i32) -> i32,
+        h: i32,
+        index: u32,
+    ) -> String {
+        let mut buf = [0u8; 32];
+        let mut written = 0;
+        assert_eq!(
+            unsafe { f(h, index, buf.as_mut_ptr(), 32, &mut written) },
+            RESULT_OK
+        );
+        String::from_utf8(buf[..written as usize].to_vec()).unwrap()
+    }
+
+    #[test]
+    fn indexed_accessors_match_core_results() {
+        cleanup();
+        let h = factorial_create();
+        let pending_nodes: Vec<u64> = (0..3)
+            .map(|_| {
+                let mut pending = 0;
+                unsafe { factorial_add_node(h, 0, &mut pending) };
+                pending
+            })
+            .collect();
+        assert_eq!(factorial_apply_mutations_indexed(h), RESULT_OK);
+        assert_eq!(factorial_mutation_added_node_count(h), 3);
+        assert_eq!(factorial_mutation_added_edge_count(h), 0);
+
+        let nodes: Vec<u64> = (0..3)
+            .map(|i| {
+                assert_eq!(
+                    read_split(factorial_mutation_added_node_pending, h, i),
+                    pending_nodes[i as usize]
+                );
+                let real = read_split(factorial_mutation_added_node_real, h, i);
+                assert_eq!(
+                    read_str(factorial_mutation_added_node_real_str, h, i),
+                    real.to_string()
+                );
+                real
+            })
+            .collect();
+
+        let endpoints = [(0, 1), (1, 2), (0, 2)];
+        let mut pending_edges = Vec::new();
+        for (from, to) in endpoints {
+            let mut pending = 0;
+            unsafe { factorial_connect(h, nodes[from], nodes[to], &mut pending) };
+            pending_edges.push(pending);
+        }
+        assert_eq!(factorial_apply_mutations_indexed(h), RESULT_OK);
+        assert_eq!(factorial_mutation_added_node_count(h), 0);
+        assert_eq!(factorial_mutation_added_edge_count(h), 3);
+
+        let edges: Vec<u64> = (0..3)
+            .map(|i| {
+                assert_eq!(
+                    read_split(factorial_mutation_added_edge_pending, h, i),
+                    pending_edges[i as usize]
+                );
+                let real = read_split(factorial_mutation_added_edge_real, h, i);
+                assert_eq!(
+                    read_str(factorial_mutation_added_edge_real_str, h, i),
+                    real.to_string()
+                );
+                real
+            })
+            .collect();
+        let (mut lo, mut hi) = (0, 0);
+        let rc = unsafe { factorial_mutation_added_edge_real(h, 3, &mut lo, &mut hi) };
+        assert_eq!(rc, RESULT_INDEX_OUT_OF_RANGE);
+
+        // Compare against the engine's own view of the graph.
+        with_engine(h, |slot| {
+            let graph = &slot.engine.graph;
+            for &node in &nodes {
+                assert!(graph.contains_node(ffi_to_node_id(node)));
+            }
+            for (edge, (from, to)) in edges.iter().zip(endpoints) {
+                let data = graph.get_edge(ffi_to_edge_id(*edge)).unwrap();
+                assert_eq!(node_id_to_ffi(data.from), nodes[from]);
+                assert_eq!(node_id_to_ffi(data.to), nodes[to]);
+            }
+            RESULT_OK
+        });
+
+        factorial_destroy(h);
+        cleanup();
+    }
+
     #[test]
     fn remove_node() {
         cleanup();
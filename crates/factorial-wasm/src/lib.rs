@@ -15,6 +15,10 @@
 //! After each `factorial_step` / `factorial_advance` call the host polls
 //! events via the event module. Events are cached in a thread-local buffer
 //! that is valid until the next step or destroy call.
+//!
+//! Graph events produced by `factorial_apply_mutations` between steps are
+//! stamped with the upcoming tick and are polled after the *next* step,
+//! together with that step's other events.
 
 pub mod engine;
 pub mod event;
@@ -25,12 +29,16 @@ pub mod query;
 pub mod serialize;
 pub mod transport;
 
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
+use std::collections::BTreeMap;
+use std::rc::Rc;
 
+use factorial_core::capacity::InventorySide;
 use factorial_core::engine::Engine;
 use factorial_core::event::{Event, EventKind};
 use factorial_core::fixed::Fixed64;
 use factorial_core::id::{EdgeId, ItemTypeId, NodeId};
+use factorial_core::sim::StepPhase;
 
 use factorial_logic::WireColor;
 use factorial_logic::combinator::{ArithmeticOp, SignalSelector};
@@ -58,6 +66,10 @@ pub const RESULT_EDGE_NOT_FOUND: i32 = 5;
 pub const RESULT_INTERNAL_ERROR: i32 = 6;
 /// Memory allocation failed.
 pub const RESULT_ALLOC_ERROR: i32 = 7;
+/// An index passed to an accessor is past the end of its list.
+pub const RESULT_INDEX_OUT_OF_RANGE: i32 = 8;
+/// An argument is outside the values the export accepts.
+pub const RESULT_INVALID_ARGUMENT: i32 = 9;
 
 // ---------------------------------------------------------------------------
 // Handle table
@@ -66,10 +78,61 @@ pub const RESULT_ALLOC_ERROR: i32 = 7;
 /// Maximum number of simultaneous engine instances.
 pub const MAX_ENGINES: usize = 16;
 
+/// Default maximum number of events held in the poll cache per step.
+pub const DEFAULT_EVENT_CACHE_LIMIT: u32 = 262_144;
+
 /// Per-engine state held in the handle table.
 pub struct EngineSlot {
     pub engine: Engine,
     pub event_cache: Vec<FlatEvent>,
+    event_cap: Rc<EventCacheCap>,
+    /// Id pairs from the most recent `factorial_apply_mutations*` call, read
+    /// through the `factorial_mutation_*` accessors.
+    last_mutation: graph::MutationRecord,
+    /// Per-item inventory listings read since the last engine change, keyed
+    /// by node and side, so paginated reads don't rebuild the list per page.
+    inventory_listings: BTreeMap<(u64, u32), Vec<(ItemTypeId, u32)>>,
+}
+
+impl EngineSlot {
+    /// Wrap `engine`, registering the event-cache listeners.
+    fn new(mut engine: Engine) -> Self {
+        let event_cap = Rc::new(EventCacheCap::default());
+        register_event_listeners(&mut engine, &event_cap);
+        Self {
+            engine,
+            event_cache: Vec::new(),
+            event_cap,
+            last_mutation: graph::MutationRecord::default(),
+            inventory_listings: BTreeMap::new(),
+        }
+    }
+
+    /// Reset the dropped-event counter when the event cache is cleared.
+    fn reset_dropped_events(&self) {
+        self.event_cap.dropped.set(0);
+    }
+
+    /// Forget the cached inventory listings after the engine changes.
+    fn reset_inventory_listings(&mut self) {
+        self.inventory_listings.clear();
+    }
+}
+
+/// Per-engine cap on the event cache, shared with the passive listeners.
+/// Events past `limit` are counted in `dropped` instead of being cached.
+struct EventCacheCap {
+    limit: Cell<usize>,
+    dropped: Cell<u64>,
+}
+
+impl Default for EventCacheCap {
+    fn default() -> Self {
+        Self {
+            limit: Cell::new(DEFAULT_EVENT_CACHE_LIMIT as usize),
+            dropped: Cell::new(0),
+        }
+    }
 }
 
 /// Flat, `repr(C)` event representation for WASM consumers.
@@ -123,6 +186,32 @@ where
     })
 }
 
+/// Like [`with_engine`], for exports that change the engine: drops the
+/// cached inventory listings first so later reads see the change.
+fn with_engine_mut<F>(handle: i32, f: F) -> i32
+where
+    F: FnOnce(&mut EngineSlot) -> i32,
+{
+    with_engine(handle, |slot| {
+        slot.reset_inventory_listings();
+        f(slot)
+    })
+}
+
+/// Install a pre-built engine into the first free slot (tests build their
+/// factories with `factorial_core::builder`). Returns the handle.
+#[cfg(test)]
+fn install_engine(engine: Engine) -> i32 {
+    with_table(|table| {
+        let idx = table
+            .iter()
+            .position(|s| s.is_none())
+            .expect("free engine slot");
+        table[idx] = Some(EngineSlot::new(engine));
+        idx as i32
+    })
+}
+
 // ---------------------------------------------------------------------------
 // ID conversion helpers
 // ---------------------------------------------------------------------------
@@ -175,10 +264,12 @@ fn convert_event(event: &Event) -> FlatEvent {
             quantity: *quantity,
             ..Default::default()
         },
-        Event::RecipeStarted { node, tick } => FlatEvent {
+        Event::RecipeStarted { node, recipe, tick } => FlatEvent {
             kind: 2,
             tick: *tick,
             node: node_id_to_ffi(*node),
+            item_type: recipe.map_or(0, |r| r.0),
+            quantity: u32::from(recipe.is_some()),
             ..Default::default()
         },
         Event::RecipeCompleted { node, tick } => FlatEvent {
@@ -269,6 +360,85 @@ fn convert_event(event: &Event) -> FlatEvent {
             quantity: *new_recipe_index as u32,
             ..Default::default()
         },
+        Event::CraftStarted { node, recipe, tick } => FlatEvent {
+            kind: 13,
+            tick: *tick,
+            node: node_id_to_ffi(*node),
+            item_type: recipe.0,
+            ..Default::default()
+        },
+        Event::CraftCompleted {
+            node,
+            recipe,
+            remaining,
+            tick,
+        } => FlatEvent {
+            kind: 14,
+            tick: *tick,
+            node: node_id_to_ffi(*node),
+            item_type: recipe.0,
+            quantity: *remaining,
+            ..Default::default()
+        },
+        Event::ItemSpoiled {
+            node,
+            item_type,
+            quantity,
+            tick,
+            ..
+        } => FlatEvent {
+            kind: 15,
+            tick: *tick,
+            node: node_id_to_ffi(*node),
+            item_type: item_type.0,
+            quantity: *quantity,
+            ..Default::default()
+        },
+        Event::InventoryFull {
+            node,
+            side,
+            slot,
+            item_type,
+            tick,
+        }
+        | Event::InventorySpaceAvailable {
+            node,
+            side,
+            slot,
+            item_type,
+            tick,
+        } => FlatEvent {
+            kind: if matches!(event, Event::InventoryFull { .. }) {
+                16
+            } else {
+                17
+            },
+            tick: *tick,
+            node: node_id_to_ffi(*node),
+            item_type: item_type.0,
+            quantity: *slot,
+            building_type: match side {
+                InventorySide::Input => 0,
+                InventorySide::Output => 1,
+            },
+            ..Default::default()
+        },
+        Event::StepTruncated {
+            phase,
+            work_done,
+            tick,
+        } => FlatEvent {
+            kind: 18,
+            tick: *tick,
+            quantity: u32::try_from(*work_done).unwrap_or(u32::MAX),
+            building_type: match phase {
+                StepPhase::PreTick => 0,
+                StepPhase::Transport => 1,
+                StepPhase::Process => 2,
+                StepPhase::Component => 3,
+            },
+            ..Default::default()
+        },
     }
 }
 
@@ -276,6 +446,13 @@ fn convert_event(event: &Event) -> FlatEvent {
 // Logic helpers
 // ---------------------------------------------------------------------------
 
+fn wire_color_to_ffi(color: WireColor) -> u32 {
+    match color {
+        WireColor::Red => 0,
+        WireColor::Green => 1,
+    }
+}
+
 fn ffi_to_wire_color(color: u32) -> Option<WireColor> {
     match color {
         0 => Some(WireColor::Red),
@@ -321,8 +498,8 @@ fn ffi_to_comparison_op(op: u32) -> Option<ComparisonOp> {
 // ---------------------------------------------------------------------------
 
 /// Register passive listeners on all event kinds that capture events into
-/// the thread-local [`EVENT_CACHE`].
-fn register_event_listeners(engine: &mut Engine) {
+/// the thread-local [`EVENT_CACHE`], up to the limit in `cap`.
+fn register_event_listeners(engine: &mut Engine, cap: &Rc<EventCacheCap>) {
     let all_kinds = [
         EventKind::ItemProduced,
         EventKind::ItemConsumed,
@@ -337,14 +514,26 @@ fn register_event_listeners(engine: &mut Engine) {
         EventKind::EdgeAdded,
         EventKind::EdgeRemoved,
         EventKind::RecipeSwitched,
+        EventKind::CraftStarted,
+        EventKind::CraftCompleted,
+        EventKind::ItemSpoiled,
+        EventKind::InventoryFull,
+        EventKind::InventorySpaceAvailable,
+        EventKind::StepTruncated,
     ];
 
     for kind in all_kinds {
+        let cap = Rc::clone(cap);
         engine.on_passive(
             kind,
             Box::new(move |event: &Event| {
                 EVENT_CACHE.with(|c| {
-                    c.borrow_mut().push(convert_event(event));
+                    let mut cache = c.borrow_mut();
+                    if cache.len() < cap.limit.get() {
+                        cache.push(convert_event(event));
+                    } else {
+                        cap.dropped.set(cap.dropped.get() + 1);
+                    }
                 });
             }),
         );
@@ -9,7 +9,8 @@ use factorial_logic::{LogicModuleBridge, SignalSet, WireNetworkId};
 
 use crate::{
     RESULT_INTERNAL_ERROR, RESULT_NODE_NOT_FOUND, RESULT_OK, ffi_to_arithmetic_op,
-    ffi_to_comparison_op, ffi_to_node_id, ffi_to_selector, ffi_to_wire_color, with_engine,
+    ffi_to_comparison_op, ffi_to_node_id, ffi_to_selector, ffi_to_wire_color, node_id_to_ffi,
+    wire_color_to_ffi, with_engine, with_engine_mut,
 };
 
 /// Register the logic module on the engine at `handle`.
@@ -18,7 +19,7 @@ use crate::{
 /// Returns [`RESULT_OK`] on success.
 #[unsafe(no_mangle)]
 pub extern "C" fn factorial_logic_register(handle: i32) -> i32 {
-    with_engine(handle, |slot| {
+    with_engine_mut(handle, |slot| {
         slot.engine
             .register_module(Box::new(LogicModuleBridge::new()));
         RESULT_OK
@@ -45,7 +46,7 @@ pub unsafe extern "C" fn factorial_logic_create_network(
         Some(c) => c,
         None => return RESULT_INTERNAL_ERROR,
     };
-    with_engine(handle, |slot| {
+    with_engine_mut(handle, |slot| {
         let bridge = match slot.engine.find_module_mut::<LogicModuleBridge>() {
             Some(b) => b,
             None => return RESULT_INTERNAL_ERROR,
@@ -59,7 +60,7 @@ pub unsafe extern "C" fn factorial_logic_create_network(
 /// Remove a wire network by ID.
 #[unsafe(no_mangle)]
 pub extern "C" fn factorial_logic_remove_network(handle: i32, network_id: u32) -> i32 {
-    with_engine(handle, |slot| {
+    with_engine_mut(handle, |slot| {
         let bridge = match slot.engine.find_module_mut::<LogicModuleBridge>() {
             Some(b) => b,
             None => return RESULT_INTERNAL_ERROR,
@@ -76,7 +77,7 @@ pub extern "C" fn factorial_logic_add_to_network(
     network_id: u32,
     node_id: u64,
 ) -> i32 {
-    with_engine(handle, |slot| {
+    with_engine_mut(handle, |slot| {
         let bridge = match slot.engine.find_module_mut::<LogicModuleBridge>() {
             Some(b) => b,
             None => return RESULT_INTERNAL_ERROR,
@@ -95,7 +96,7 @@ pub extern "C" fn factorial_logic_remove_from_network(
     network_id: u32,
     node_id: u64,
 ) -> i32 {
-    with_engine(handle, |slot| {
+    with_engine_mut(handle, |slot| {
         let bridge = match slot.engine.find_module_mut::<LogicModuleBridge>() {
             Some(b) => b,
             None => return RESULT_INTERNAL_ERROR,
@@ -134,7 +135,7 @@ pub unsafe extern "C" fn factorial_logic_set_constant(
         let value = unsafe { *values_ptr.add(i) };
         signals.insert(ItemTypeId(item_id), Fixed64::from_bits(value));
     }
-    with_engine(handle, |slot| {
+    with_engine_mut(handle, |slot| {
         let bridge = match slot.engine.find_module_mut::<LogicModuleBridge>() {
             Some(b) => b,
             None => return RESULT_INTERNAL_ERROR,
@@ -170,7 +171,7 @@ pub extern "C" fn factorial_logic_set_arithmetic(
         Some(o) => o,
         None => return RESULT_INTERNAL_ERROR,
     };
-    with_engine(handle, |slot| {
+    with_engine_mut(handle, |slot| {
         let bridge = match slot.engine.find_module_mut::<LogicModuleBridge>() {
             Some(b) => b,
             None => return RESULT_INTERNAL_ERROR,
@@ -222,7 +223,7 @@ pub extern "C" fn factorial_logic_set_decider(
         2 => DeciderOutput::Everything,
         _ => return RESULT_INTERNAL_ERROR,
     };
-    with_engine(handle, |slot| {
+    with_engine_mut(handle, |slot| {
         let bridge = match slot.engine.find_module_mut::<LogicModuleBridge>() {
             Some(b) => b,
             None => return RESULT_INTERNAL_ERROR,
@@ -230,7 +231,12 @@ pub extern "C" fn factorial_logic_set_decider(
         bridge.logic_mut().set_decider(
             ffi_to_node_id(node_id),
             DeciderCombinator {
-                condition: Condition { left, op, right },
+                condition: Condition {
+                    left,
+                    op,
+                    right,
+                    epsilon: Fixed64::ZERO,
+                },
                 output,
             },
         );
@@ -269,14 +275,19 @@ pub extern "C" fn factorial_logic_set_circuit_control(
         Some(c) => c,
         None => return RESULT_INTERNAL_ERROR,
     };
-    with_engine(handle, |slot| {
+    with_engine_mut(handle, |slot| {
         let bridge = match slot.engine.find_module_mut::<LogicModuleBridge>() {
             Some(b) => b,
             None => return RESULT_INTERNAL_ERROR,
         };
         bridge.logic_mut().set_circuit_control(
             ffi_to_node_id(node_id),
-            Condition { left, op, right },
+            Condition {
+                left,
+                op,
+                right,
+                epsilon: Fixed64::ZERO,
+            },
             color,
         );
         RESULT_OK
@@ -355,10 +366,74 @@ pub unsafe extern "C" fn factorial_logic_get_network_signal(
     })
 }
 
+/// Size in bytes of one entry written by
+/// [`factorial_logic_get_changed_signals`].
+pub const SIGNAL_CHANGE_SIZE: usize = 36;
+
+/// Write the signals whose value on some node's wire changed during the most
+/// recent step into a flat byte buffer at `out_ptr` (capacity `out_len`
+/// bytes), ordered by (node, wire, signal, network).
+///
+/// # Buffer layout
+///
+/// Each entry is [`SIGNAL_CHANGE_SIZE`] bytes:
+///
+/// | Offset | Size | Description |
+/// |--------|------|-------------|
+/// | 0      | 8    | `node_id` (u64 LE) |
+/// | 8      | 4    | `wire` (u32 LE, 0 = red, 1 = green) |
+/// | 12     | 4    | `signal` item id (u32 LE) |
+/// | 16     | 4    | `network` id (u32 LE) |
+/// | 20     | 8    | `old` Fixed64 bits (i64 LE) |
+/// | 28     | 8    | `new` Fixed64 bits (i64 LE) |
+///
+/// The number of entries is stored in `*out_count_ptr`. Fails with
+/// [`RESULT_INTERNAL_ERROR`] if the buffer is too small.
+///
+/// # Safety
+///
+/// `out_ptr` must point to a valid byte buffer of at least `out_len` bytes.
+/// `out_count_ptr` must be a valid, aligned pointer to a `u32`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_logic_get_changed_signals(
+    handle: i32,
+    out_ptr: *mut u8,
+    out_len: i32,
+    out_count_ptr: *mut u32,
+) -> i32 {
+    if out_ptr.is_null() || out_count_ptr.is_null() {
+        return RESULT_INTERNAL_ERROR;
+    }
+    with_engine(handle, |slot| {
+        let bridge = match slot.engine.find_module::<LogicModuleBridge>() {
+            Some(b) => b,
+            None => return RESULT_INTERNAL_ERROR,
+        };
+        let changes = bridge.changed_signals();
+        let needed = changes.len() * SIGNAL_CHANGE_SIZE;
+        if (out_len as usize) < needed {
+            return RESULT_INTERNAL_ERROR;
+        }
+
+        let buf = unsafe { std::slice::from_raw_parts_mut(out_ptr, out_len as usize) };
+        for (entry, change) in buf.chunks_exact_mut(SIGNAL_CHANGE_SIZE).zip(changes) {
+            entry[0..8].copy_from_slice(&node_id_to_ffi(change.node).to_le_bytes());
+            entry[8..12].copy_from_slice(&wire_color_to_ffi(change.wire).to_le_bytes());
+            entry[12..16].copy_from_slice(&change.signal.0.to_le_bytes());
+            entry[16..20].copy_from_slice(&change.network.0.to_le_bytes());
+            entry[20..28].copy_from_slice(&change.old.to_bits().to_le_bytes());
+            entry[28..36].copy_from_slice(&change.new.to_bits().to_le_bytes());
+        }
+
+        unsafe { *out_count_ptr = changes.len() as u32 };
+        RESULT_OK
+    })
+}
+
 /// Remove all logic state associated with a node.
 #[unsafe(no_mangle)]
 pub extern "C" fn factorial_logic_remove_node(handle: i32, node_id: u64) -> i32 {
-    with_engine(handle, |slot| {
+    with_engine_mut(handle, |slot| {
         let bridge = match slot.engine.find_module_mut::<LogicModuleBridge>() {
             Some(b) => b,
             None => return RESULT_INTERNAL_ERROR,
@@ -383,7 +458,7 @@ pub extern "C" fn factorial_logic_set_inventory_reader(
     } else {
         InventorySource::Output
     };
-    with_engine(handle, |slot| {
+    with_engine_mut(handle, |slot| {
         let bridge = match slot.engine.find_module_mut::<LogicModuleBridge>() {
             Some(b) => b,
             None => return RESULT_INTERNAL_ERROR,
@@ -502,6 +577,55 @@ mod tests {
         cleanup();
     }
 
+    #[test]
+    fn changed_signals_packed_buffer() {
+        cleanup();
+        let (h, node_id) = create_engine_with_node();
+        factorial_logic_register(h);
+        let mut net_id: u32 = 0;
+        unsafe { factorial_logic_create_network(h, 1, &mut net_id) };
+        factorial_logic_add_to_network(h, net_id, node_id);
+
+        let item_ids: [u32; 1] = [4];
+        let values: [i64; 1] = [Fixed64::from_num(9).to_bits()];
+        unsafe {
+            factorial_logic_set_constant(h, node_id, item_ids.as_ptr(), values.as_ptr(), 1, 1)
+        };
+        factorial_step(h);
+
+        let mut buf = [0u8; SIGNAL_CHANGE_SIZE * 2];
+        let mut count: u32 = 0;
+        let rc = unsafe {
+            factorial_logic_get_changed_signals(h, buf.as_mut_ptr(), buf.len() as i32, &mut count)
+        };
+        assert_eq!(rc, RESULT_OK);
+        assert_eq!(count, 1);
+        assert_eq!(u64::from_le_bytes(buf[0..8].try_into().unwrap()), node_id);
+        assert_eq!(u32::from_le_bytes(buf[8..12].try_into().unwrap()), 1);
+        assert_eq!(u32::from_le_bytes(buf[12..16].try_into().unwrap()), 4);
+        assert_eq!(u32::from_le_bytes(buf[16..20].try_into().unwrap()), net_id);
+        assert_eq!(i64::from_le_bytes(buf[20..28].try_into().unwrap()), 0);
+        assert_eq!(
+            i64::from_le_bytes(buf[28..36].try_into().unwrap()),
+            Fixed64::from_num(9).to_bits()
+        );
+
+        // A buffer too small for the report is rejected.
+        let rc = unsafe { factorial_logic_get_changed_signals(h, buf.as_mut_ptr(), 8, &mut count) };
+        assert_eq!(rc, RESULT_INTERNAL_ERROR);
+
+        // The report is cleared by the next step.
+        factorial_step(h);
+        let rc = unsafe {
+            factorial_logic_get_changed_signals(h, buf.as_mut_ptr(), buf.len() as i32, &mut count)
+        };
+        assert_eq!(rc, RESULT_OK);
+        assert_eq!(count, 0);
+
+        factorial_destroy(h);
+        cleanup();
+    }
+
     #[test]
     fn logic_register_invalid_handle() {
         cleanup();
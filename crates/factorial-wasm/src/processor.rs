@@ -6,7 +6,7 @@ use factorial_core::processor::{
     Depletion, FixedRecipe, Processor, RecipeInput, RecipeOutput, SourceProcessor,
 };
 
-use crate::{RESULT_OK, ffi_to_node_id, with_engine};
+use crate::{RESULT_OK, ffi_to_node_id, with_engine_mut};
 
 /// Configure a node as a source processor (mine, extractor, well).
 ///
@@ -19,7 +19,7 @@ pub extern "C" fn factorial_set_source(
     item_type: u32,
     rate: i64,
 ) -> i32 {
-    with_engine(handle, |slot| {
+    with_engine_mut(handle, |slot| {
         let nid = ffi_to_node_id(node_id);
         slot.engine.set_processor(
             nid,
@@ -94,11 +94,12 @@ pub unsafe extern "C" fn factorial_set_fixed_processor(
             item_type: ItemTypeId(item_type),
             quantity,
             bonus: None,
+            probability: None,
         });
         offset += 8;
     }
 
-    with_engine(handle, |slot| {
+    with_engine_mut(handle, |slot| {
         let nid = ffi_to_node_id(node_id);
         slot.engine.set_processor(
             nid,
@@ -106,6 +107,9 @@ pub unsafe extern "C" fn factorial_set_fixed_processor(
                 inputs,
                 outputs,
                 duration,
+                fluid_inputs: Vec::new(),
+                fluid_outputs: Vec::new(),
+                duration_per_unit: None,
             }),
         );
         RESULT_OK
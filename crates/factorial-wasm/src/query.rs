@@ -1,8 +1,14 @@
 //! Query WASM exports.
 
+use std::collections::btree_map::Entry;
+
+use factorial_core::id::ItemTypeId;
 use factorial_core::processor::{ProcessorState, StallReason};
 
-use crate::{RESULT_NODE_NOT_FOUND, RESULT_OK, ffi_to_node_id, with_engine};
+use crate::{
+    EngineSlot, RESULT_EDGE_NOT_FOUND, RESULT_INVALID_ARGUMENT, RESULT_NODE_NOT_FOUND, RESULT_OK,
+    ffi_to_edge_id, ffi_to_node_id, with_engine,
+};
 
 /// Write the current node count to `*out_count`.
 ///
@@ -72,7 +78,7 @@ pub unsafe extern "C" fn factorial_get_state_hash(handle: i32, out_hash: *mut u6
 ///
 /// Writes the state discriminant to `*out_state`:
 ///   0 = Idle, 1 = Working, 2 = StalledMissingInputs, 3 = StalledOutputFull,
-///   4 = StalledNoPower, 5 = StalledDepleted.
+///   4 = StalledNoPower, 5 = StalledDepleted, 6 = Storing.
 ///
 /// For `Working`, `*out_progress` receives the progress counter.
 ///
@@ -105,6 +111,7 @@ pub unsafe extern "C" fn factorial_get_processor_state(
                         };
                         (code, 0)
                     }
+                    ProcessorState::Storing => (6, 0),
                 };
                 unsafe {
                     *out_state = s;
@@ -135,8 +142,7 @@ pub unsafe extern "C" fn factorial_get_input_inventory_count(
         let nid = ffi_to_node_id(node_id);
         match slot.engine.get_input_inventory(nid) {
             Some(inv) => {
-                let total: u32 = inv.input_slots.iter().map(|s| s.total()).sum();
-                unsafe { *out_count = total };
+                unsafe { *out_count = inv.total() };
                 RESULT_OK
             }
             None => RESULT_NODE_NOT_FOUND,
@@ -162,8 +168,7 @@ pub unsafe extern "C" fn factorial_get_output_inventory_count(
         let nid = ffi_to_node_id(node_id);
         match slot.engine.get_output_inventory(nid) {
             Some(inv) => {
-                let total: u32 = inv.output_slots.iter().map(|s| s.total()).sum();
-                unsafe { *out_count = total };
+                unsafe { *out_count = inv.total() };
                 RESULT_OK
             }
             None => RESULT_NODE_NOT_FOUND,
@@ -171,6 +176,167 @@ pub unsafe extern "C" fn factorial_get_output_inventory_count(
     })
 }
 
+/// Size in bytes of one packed record written by
+/// [`factorial_get_inventory_items_page`].
+pub const INVENTORY_ITEM_RECORD_SIZE: usize = 8;
+
+/// The per-item listing of one side of a node's inventory (`which`: 0 =
+/// input, 1 = output), in ascending item-type order.
+///
+/// The listing is built on the first read and cached until the next export
+/// that changes the engine (see [`crate::with_engine_mut`]), so paging
+/// through an unchanged engine doesn't rebuild it per page and a change made
+/// before or between reads shows up on the next one.
+fn inventory_listing(
+    slot: &mut EngineSlot,
+    node_id: u64,
+    which: u32,
+) -> Result<&[(ItemTypeId, u32)], i32> {
+    if which > 1 {
+        return Err(RESULT_INVALID_ARGUMENT);
+    }
+    let listing = match slot.inventory_listings.entry((node_id, which)) {
+        Entry::Occupied(entry) => entry.into_mut(),
+        Entry::Vacant(entry) => {
+            let nid = ffi_to_node_id(node_id);
+            let inventory = if which == 0 {
+                slot.engine.get_input_inventory(nid)
+            } else {
+                slot.engine.get_output_inventory(nid)
+            };
+            let inventory = inventory.ok_or(RESULT_NODE_NOT_FOUND)?;
+            entry.insert(inventory.iter_items().collect())
+        }
+    };
+    Ok(listing)
+}
+
+/// Number of distinct item types held in one side of a node's inventory
+/// (`which`: 0 = input, 1 = output).
+///
+/// Returns the count, or a negated result code:
+/// [`RESULT_NODE_NOT_FOUND`] if the node has no such inventory,
+/// [`RESULT_INVALID_ARGUMENT`] for any other `which`.
+#[unsafe(no_mangle)]
+pub extern "C" fn factorial_get_inventory_item_count(handle: i32, node_id: u64, which: u32) -> i32 {
+    let mut count = 0;
+    let rc = with_engine(handle, |slot| {
+        match inventory_listing(slot, node_id, which) {
+            Ok(listing) => {
+                count = listing.len() as i32;
+                RESULT_OK
+            }
+            Err(rc) => rc,
+        }
+    });
+    if rc == RESULT_OK { count } else { -rc }
+}
+
+/// Write up to `max` per-item records from one side of a node's inventory
+/// into `out_ptr`, starting at the `start_index`-th distinct item type.
+///
+/// Records are packed little-endian `item_type: u32, quantity: u32` pairs
+/// in ascending item-type order. Calls with no engine change in between read
+/// the same listing, so consecutive pages have no duplicates or gaps. A
+/// `start_index` past the end writes nothing.
+///
+/// Returns the number of records written, or a negated result code as for
+/// [`factorial_get_inventory_item_count`] ([`RESULT_INVALID_ARGUMENT`] also
+/// for a null `out_ptr`).
+///
+/// # Safety
+///
+/// `out_ptr` must be valid for `max * INVENTORY_ITEM_RECORD_SIZE` bytes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_get_inventory_items_page(
+    handle: i32,
+    node_id: u64,
+    which: u32,
+    start_index: u32,
+    out_ptr: *mut u8,
+    max: u32,
+) -> i32 {
+    if out_ptr.is_null() {
+        return -RESULT_INVALID_ARGUMENT;
+    }
+    let mut written = 0;
+    let rc = with_engine(handle, |slot| {
+        let listing = match inventory_listing(slot, node_id, which) {
+            Ok(listing) => listing,
+            Err(rc) => return rc,
+        };
+        let page = listing.iter().skip(start_index as usize).take(max as usize);
+        let buf = unsafe {
+            std::slice::from_raw_parts_mut(out_ptr, max as usize * INVENTORY_ITEM_RECORD_SIZE)
+        };
+        for (record, &(item_type, quantity)) in
+            buf.chunks_exact_mut(INVENTORY_ITEM_RECORD_SIZE).zip(page)
+        {
+            record[0..4].copy_from_slice(&item_type.0.to_le_bytes());
+            record[4..8].copy_from_slice(&quantity.to_le_bytes());
+            written += 1;
+        }
+        RESULT_OK
+    });
+    if rc == RESULT_OK { written } else { -rc }
+}
+
+/// Size in bytes of one packed record written by [`factorial_get_transport_items`].
+pub const TRANSPORT_ITEM_RECORD_SIZE: usize = 24;
+
+/// Write the items currently on a belt edge into `out_ptr` as a packed
+/// little-endian buffer, for animating items with stable identities.
+///
+/// Layout: `count: u32`, then `count` records of
+/// `instance_id: u64, item_type: u32, lane: u32, position: i64` where
+/// `position` is raw Fixed64 bits (0 at the source end, `1 << 32` at the
+/// destination end). Non-belt transports report zero items.
+///
+/// `*out_written_ptr` receives the number of bytes needed. If `out_len` is
+/// too small nothing else is written and [`crate::RESULT_INTERNAL_ERROR`] is
+/// returned, so callers can grow the buffer and retry.
+///
+/// # Safety
+///
+/// `out_ptr` must be valid for `out_len` bytes and `out_written_ptr` must be
+/// a valid, aligned pointer to an `i32`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_get_transport_items(
+    handle: i32,
+    edge_id: u64,
+    out_ptr: *mut u8,
+    out_len: i32,
+    out_written_ptr: *mut i32,
+) -> i32 {
+    if out_ptr.is_null() || out_written_ptr.is_null() {
+        return crate::RESULT_INTERNAL_ERROR;
+    }
+    with_engine(handle, |slot| {
+        let eid = ffi_to_edge_id(edge_id);
+        let Some(views) = slot.engine.transport_items(eid) else {
+            return RESULT_EDGE_NOT_FOUND;
+        };
+
+        let needed = 4 + views.len() * TRANSPORT_ITEM_RECORD_SIZE;
+        unsafe { *out_written_ptr = needed as i32 };
+        if (out_len as usize) < needed {
+            return crate::RESULT_INTERNAL_ERROR;
+        }
+
+        let buf = unsafe { std::slice::from_raw_parts_mut(out_ptr, out_len as usize) };
+        buf[0..4].copy_from_slice(&(views.len() as u32).to_le_bytes());
+        let mut offset = 4;
+        for view in &views {
+            buf[offset..offset + 8].copy_from_slice(&view.instance_id.to_le_bytes());
+            buf[offset + 8..offset + 12].copy_from_slice(&view.item_type.0.to_le_bytes());
+            buf[offset + 12..offset + 16].copy_from_slice(&(view.lane as u32).to_le_bytes());
+            buf[offset + 16..offset + 24].copy_from_slice(&view.position.to_bits().to_le_bytes());
+            offset += TRANSPORT_ITEM_RECORD_SIZE;
+        }
+        RESULT_OK
+    })
+}
+
 // ---------------------------------------------------------------------------
 // Tests
 // ---------------------------------------------------------------------------
@@ -313,4 +479,175 @@ mod tests {
         factorial_destroy(h);
         cleanup();
     }
+
+    fn read_inventory_page(h: i32, node: u64, start: u32, max: u32) -> Vec<(u32, u32)> {
+        let mut buf = vec![0u8; max as usize * INVENTORY_ITEM_RECORD_SIZE];
+        let written =
+            unsafe { factorial_get_inventory_items_page(h, node, 0, start, buf.as_mut_ptr(), max) };
+        assert!(written >= 0, "page read failed: {written}");
+        buf.chunks_exact(INVENTORY_ITEM_RECORD_SIZE)
+            .take(written as usize)
+            .map(|r| {
+                (
+                    u32::from_le_bytes(r[0..4].try_into().unwrap()),
+                    u32::from_le_bytes(r[4..8].try_into().unwrap()),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn inventory_items_paginate_stably_within_a_tick() {
+        use factorial_core::item::Inventory;
+
+        cleanup();
+        let (h, node_id) = create_engine_with_node();
+        let nid = crate::ffi_to_node_id(node_id);
+        crate::with_engine_mut(h, |slot| {
+            let mut inventory = Inventory::new(10, 1, 100);
+            // Insert out of id order; listings come back sorted.
+            for item in (0..10u32).rev() {
+                let overflow =
+                    inventory.input_slots[item as usize].add(ItemTypeId(item * 3), item + 1);
+                assert_eq!(overflow, 0);
+            }
+            slot.engine.set_input_inventory(nid, inventory);
+            RESULT_OK
+        });
+        factorial_step(h);
+
+        assert_eq!(factorial_get_inventory_item_count(h, node_id, 0), 10);
+        let pages: Vec<_> = (0..4)
+            .map(|i| read_inventory_page(h, node_id, i * 3, 3))
+            .collect();
+        assert_eq!(
+            pages.iter().map(Vec::len).collect::<Vec<_>>(),
+            vec![3, 3, 3, 1]
+        );
+        let all: Vec<_> = pages.concat();
+        let expected: Vec<_> = (0..10u32).map(|i| (i * 3, i + 1)).collect();
+        assert_eq!(all, expected);
+        assert!(read_inventory_page(h, node_id, 10, 3).is_empty());
+
+        // A change between pages shows up on the next read.
+        crate::with_engine_mut(h, |slot| {
+            let inventory = slot.engine.get_input_inventory_mut(nid).unwrap();
+            assert_eq!(inventory.input_slots[0].remove(ItemTypeId(0), 1), 1);
+            assert_eq!(inventory.input_slots[0].add(ItemTypeId(1), 5), 0);
+            RESULT_OK
+        });
+        assert_eq!(factorial_get_inventory_item_count(h, node_id, 0), 10);
+        assert_eq!(read_inventory_page(h, node_id, 0, 2), vec![(1, 5), (3, 2)]);
+
+        let mut buf = [0u8; 8];
+        assert_eq!(
+            factorial_get_inventory_item_count(h, 9999, 0),
+            -RESULT_NODE_NOT_FOUND
+        );
+        assert_eq!(
+            unsafe { factorial_get_inventory_items_page(h, 9999, 0, 0, buf.as_mut_ptr(), 1) },
+            -RESULT_NODE_NOT_FOUND
+        );
+        assert_eq!(
+            factorial_get_inventory_item_count(h, node_id, 2),
+            -RESULT_INVALID_ARGUMENT
+        );
+        assert_eq!(
+            unsafe { factorial_get_inventory_items_page(h, node_id, 2, 0, buf.as_mut_ptr(), 1) },
+            -RESULT_INVALID_ARGUMENT
+        );
+        assert_eq!(
+            unsafe {
+                factorial_get_inventory_items_page(h, node_id, 0, 0, std::ptr::null_mut(), 1)
+            },
+            -RESULT_INVALID_ARGUMENT
+        );
+
+        factorial_destroy(h);
+        cleanup();
+    }
+
+    #[test]
+    fn inventory_listing_sees_changes_made_after_a_step() {
+        cleanup();
+        let (h, node_id) = create_engine_with_node();
+        crate::transport::factorial_set_input_capacity(h, node_id, 100);
+        crate::with_engine_mut(h, |slot| {
+            let nid = crate::ffi_to_node_id(node_id);
+            let inventory = slot.engine.get_input_inventory_mut(nid).unwrap();
+            assert_eq!(inventory.input_slots[0].add(ItemTypeId(7), 4), 0);
+            RESULT_OK
+        });
+        factorial_step(h);
+
+        // Replace the inventory before anything reads the listing.
+        crate::transport::factorial_set_input_capacity(h, node_id, 100);
+        assert_eq!(factorial_get_inventory_item_count(h, node_id, 0), 0);
+        assert!(read_inventory_page(h, node_id, 0, 4).is_empty());
+
+        factorial_step(h);
+        assert_eq!(factorial_get_inventory_item_count(h, node_id, 0), 0);
+        crate::with_engine_mut(h, |slot| {
+            let nid = crate::ffi_to_node_id(node_id);
+            let inventory = slot.engine.get_input_inventory_mut(nid).unwrap();
+            assert_eq!(inventory.input_slots[0].add(ItemTypeId(2), 9), 0);
+            RESULT_OK
+        });
+        assert_eq!(read_inventory_page(h, node_id, 0, 4), vec![(2, 9)]);
+
+        factorial_destroy(h);
+        cleanup();
+    }
+
+    #[test]
+    fn transport_items_packed_with_stable_ids() {
+        use factorial_core::builder::FactoryBuilder;
+        use factorial_core::id::ItemTypeId;
+
+        cleanup();
+        let mut builder = FactoryBuilder::new();
+        builder.node("miner").source(ItemTypeId(4), 1.0);
+        builder.node("chest");
+        builder.belt("miner", "chest", 1.0);
+        let factory = builder.build().unwrap();
+        let edge = crate::edge_id_to_ffi(factory.edges[0]);
+        let h = crate::install_engine(factory.engine);
+
+        for _ in 0..3 {
+            factorial_step(h);
+        }
+        let mut buf = [0u8; 256];
+        let mut written: i32 = 0;
+        let rc =
+            unsafe { factorial_get_transport_items(h, edge, buf.as_mut_ptr(), 256, &mut written) };
+        assert_eq!(rc, RESULT_OK);
+        assert_eq!(written, 4 + 2 * TRANSPORT_ITEM_RECORD_SIZE as i32);
+        assert_eq!(u32::from_le_bytes(buf[0..4].try_into().unwrap()), 2);
+        let id = u64::from_le_bytes(buf[4..12].try_into().unwrap());
+        assert_eq!(u32::from_le_bytes(buf[12..16].try_into().unwrap()), 4);
+        let position = i64::from_le_bytes(buf[20..28].try_into().unwrap());
+
+        factorial_step(h);
+        unsafe { factorial_get_transport_items(h, edge, buf.as_mut_ptr(), 256, &mut written) };
+        let count = u32::from_le_bytes(buf[0..4].try_into().unwrap()) as usize;
+        let moved = (0..count)
+            .map(|i| 4 + i * TRANSPORT_ITEM_RECORD_SIZE)
+            .find(|&o| u64::from_le_bytes(buf[o..o + 8].try_into().unwrap()) == id)
+            .map(|o| i64::from_le_bytes(buf[o + 16..o + 24].try_into().unwrap()))
+            .unwrap();
+        assert!(moved > position);
+
+        // Too-small buffer reports the needed size.
+        let rc =
+            unsafe { factorial_get_transport_items(h, edge, buf.as_mut_ptr(), 8, &mut written) };
+        assert_eq!(rc, crate::RESULT_INTERNAL_ERROR);
+        assert!(written > 8);
+
+        let rc =
+            unsafe { factorial_get_transport_items(h, 9999, buf.as_mut_ptr(), 256, &mut written) };
+        assert_eq!(rc, RESULT_EDGE_NOT_FOUND);
+
+        factorial_destroy(h);
+        cleanup();
+    }
 }
@@ -4,7 +4,7 @@ use factorial_core::engine::Engine;
 
 use crate::{
     EVENT_CACHE, EngineSlot, RESULT_DESERIALIZE_ERROR, RESULT_OK, RESULT_SERIALIZE_ERROR,
-    register_event_listeners, with_engine, with_table,
+    with_engine, with_table,
 };
 
 /// Serialize engine state into the caller-provided buffer at `out_ptr`
@@ -60,16 +60,12 @@ pub unsafe extern "C" fn factorial_deserialize(data_ptr: *const u8, data_len: i3
     }
     let slice = unsafe { std::slice::from_raw_parts(data_ptr, data_len as usize) };
     match Engine::deserialize(slice) {
-        Ok(mut engine) => {
-            register_event_listeners(&mut engine);
+        Ok(engine) => {
             EVENT_CACHE.with(|c| c.borrow_mut().clear());
             with_table(|table| {
                 for (i, slot) in table.iter_mut().enumerate() {
                     if slot.is_none() {
-                        *slot = Some(EngineSlot {
-                            engine,
-                            event_cache: Vec::new(),
-                        });
+                        *slot = Some(EngineSlot::new(engine));
                         return i as i32;
                     }
                 }
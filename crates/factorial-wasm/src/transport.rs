@@ -6,14 +6,14 @@ use factorial_core::transport::{
     BatchTransport, FlowTransport, ItemTransport, Transport, VehicleTransport,
 };
 
-use crate::{RESULT_OK, ffi_to_edge_id, ffi_to_node_id, with_engine};
+use crate::{RESULT_OK, ffi_to_edge_id, ffi_to_node_id, with_engine_mut};
 
 /// Set the transport on `edge_id` to a continuous flow transport.
 ///
 /// `rate` is the fixed-point bits representation of items per tick.
 #[unsafe(no_mangle)]
 pub extern "C" fn factorial_set_flow_transport(handle: i32, edge_id: u64, rate: i64) -> i32 {
-    with_engine(handle, |slot| {
+    with_engine_mut(handle, |slot| {
         let eid = ffi_to_edge_id(edge_id);
         slot.engine.set_transport(
             eid,
@@ -38,7 +38,7 @@ pub extern "C" fn factorial_set_item_transport(
     slot_count: u32,
     lanes: u8,
 ) -> i32 {
-    with_engine(handle, |slot| {
+    with_engine_mut(handle, |slot| {
         let eid = ffi_to_edge_id(edge_id);
         slot.engine.set_transport(
             eid,
@@ -46,6 +46,7 @@ pub extern "C" fn factorial_set_item_transport(
                 speed: Fixed64::from_bits(speed),
                 slot_count,
                 lanes,
+                min_spacing: Fixed64::ONE,
             }),
         );
         RESULT_OK
@@ -60,7 +61,7 @@ pub extern "C" fn factorial_set_batch_transport(
     batch_size: u32,
     cycle_time: u32,
 ) -> i32 {
-    with_engine(handle, |slot| {
+    with_engine_mut(handle, |slot| {
         let eid = ffi_to_edge_id(edge_id);
         slot.engine.set_transport(
             eid,
@@ -81,7 +82,7 @@ pub extern "C" fn factorial_set_vehicle_transport(
     capacity: u32,
     travel_time: u32,
 ) -> i32 {
-    with_engine(handle, |slot| {
+    with_engine_mut(handle, |slot| {
         let eid = ffi_to_edge_id(edge_id);
         slot.engine.set_transport(
             eid,
@@ -100,7 +101,7 @@ pub extern "C" fn factorial_set_vehicle_transport(
 /// given capacity.
 #[unsafe(no_mangle)]
 pub extern "C" fn factorial_set_input_capacity(handle: i32, node_id: u64, capacity: u32) -> i32 {
-    with_engine(handle, |slot| {
+    with_engine_mut(handle, |slot| {
         let nid = ffi_to_node_id(node_id);
         slot.engine
             .set_input_inventory(nid, Inventory::new(1, 1, capacity));
@@ -114,7 +115,7 @@ pub extern "C" fn factorial_set_input_capacity(handle: i32, node_id: u64, capaci
 /// given capacity.
 #[unsafe(no_mangle)]
 pub extern "C" fn factorial_set_output_capacity(handle: i32, node_id: u64, capacity: u32) -> i32 {
-    with_engine(handle, |slot| {
+    with_engine_mut(handle, |slot| {
         let nid = ffi_to_node_id(node_id);
         slot.engine
             .set_output_inventory(nid, Inventory::new(1, 1, capacity));